@@ -0,0 +1,97 @@
+//! Incremental terminal mesh benchmarks
+//!
+//! Simulates a typing workload: each frame one character lands on the cursor
+//! row and the daemon reports only that row as damaged. The incremental path
+//! should stay well under the 1ms frame CPU budget; the full rebuild is the
+//! baseline it replaces.
+
+use bevy::prelude::{Assets, Image};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use scarab_client::rendering::config::FontConfig;
+use scarab_client::rendering::{DirtyRegion, MeshCache, TextRenderer};
+use scarab_client::safe_state::MockTerminalState;
+use scarab_protocol::{Cell, TerminalStateReader};
+use std::time::Duration;
+
+/// Fill the grid with shell-like output so every row has glyphs to draw
+fn populated_state(cols: usize, rows: usize) -> MockTerminalState {
+    let mut state = MockTerminalState::new(cols, rows);
+    let text = b"drwxr-xr-x  5 user staff  160 Oct 16 09:41 src/ ";
+    for (i, cell) in state.cells_mut().iter_mut().enumerate() {
+        cell.char_codepoint = text[i % text.len()] as u32;
+    }
+    state
+}
+
+/// Type one character at the cursor and report the cursor row as damaged
+fn type_char(state: &mut MockTerminalState, keystroke: usize) {
+    let (cols, rows) = state.dimensions();
+    let row = rows - 1;
+    let col = keystroke % cols;
+
+    let cell = Cell {
+        char_codepoint: b'a' as u32 + (keystroke % 26) as u32,
+        ..Cell::default()
+    };
+    state.set_cell(row, col, cell);
+    state.increment_sequence();
+    state.set_row_damage(&[row]);
+}
+
+fn bench_typing_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("typing_frame");
+    group.measurement_time(Duration::from_secs(5));
+
+    let mut images = Assets::<Image>::default();
+    let mut renderer = TextRenderer::new(FontConfig::default(), &mut images);
+
+    for (cols, rows) in [(80, 24), (200, 100)] {
+        let mut state = populated_state(cols, rows);
+        let mut cache = MeshCache::default();
+
+        // Warm the cache and the glyph atlas with a full build
+        cache.update(&state, &mut renderer, &DirtyRegion::new());
+        renderer.atlas.update_texture(&mut images);
+
+        let mut keystroke = 0;
+        group.bench_with_input(
+            BenchmarkId::new("incremental", format!("{}x{}", cols, rows)),
+            &(cols, rows),
+            |b, _| {
+                b.iter(|| {
+                    let last_sequence = state.sequence();
+                    type_char(&mut state, keystroke);
+                    keystroke += 1;
+
+                    let mut dirty = DirtyRegion::new();
+                    dirty.clear();
+                    dirty.apply_damage(&state, last_sequence);
+
+                    black_box(cache.update(&state, &mut renderer, &dirty));
+                    renderer.atlas.update_texture(&mut images);
+                    black_box(cache.build_mesh());
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("full_rebuild", format!("{}x{}", cols, rows)),
+            &(cols, rows),
+            |b, _| {
+                b.iter(|| {
+                    type_char(&mut state, keystroke);
+                    keystroke += 1;
+
+                    black_box(cache.update(&state, &mut renderer, &DirtyRegion::new()));
+                    renderer.atlas.update_texture(&mut images);
+                    black_box(cache.build_mesh());
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_typing_frame);
+criterion_main!(benches);
@@ -1,9 +1,210 @@
+use scarab_protocol::terminal_state::TerminalStateReader;
 use scarab_protocol::{Cell, SharedState, GRID_HEIGHT, GRID_WIDTH};
+use std::fmt::Write as _;
 use std::fs::File;
 use std::io::{self, Write};
 use std::path::Path;
 
 use super::settings::ExportFormat;
+use crate::rendering::config::TextAttributes;
+use crate::terminal::scrollback::ScrollbackBuffer;
+
+/// Theme colors the window shows for cells without colors of their own
+/// (Slime dark, as in the snapshot renderer)
+const THEME_FG: u32 = 0xFFA8DF5A;
+const THEME_BG: u32 = 0xFF0D1208;
+
+/// SVG cell size and font size in pixels
+const SVG_CELL_WIDTH: f32 = 8.4;
+const SVG_CELL_HEIGHT: f32 = 17.0;
+const SVG_FONT_SIZE: f32 = 14.0;
+/// Distance from the top of a cell to the text baseline
+const SVG_BASELINE: f32 = 13.0;
+
+/// Lines to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportRange {
+    /// The visible screen
+    #[default]
+    Screen,
+    /// Lines `first..=last`, numbered like copy mode: screen rows count
+    /// from 0 and scrollback lines upward from -1, the newest
+    Lines { first: i32, last: i32 },
+}
+
+impl ExportRange {
+    /// Cells of the range as a grid `cols` wide, and its height
+    ///
+    /// Short lines are padded with blank cells. Lines past the top of the
+    /// scrollback or the bottom of the screen are left out.
+    pub fn cells(
+        &self,
+        state: &impl TerminalStateReader,
+        scrollback: Option<&ScrollbackBuffer>,
+        cols: usize,
+        rows: usize,
+    ) -> (Vec<Cell>, usize) {
+        let (first, last) = match *self {
+            ExportRange::Screen => (0, rows as i32 - 1),
+            ExportRange::Lines { first, last } => (first.min(last), first.max(last)),
+        };
+        let history = scrollback.map_or(0, |s| s.line_count() as i32);
+
+        let mut grid = Vec::new();
+        let mut height = 0;
+        for y in first.max(-history)..=last.min(rows as i32 - 1) {
+            let line: Vec<Cell> = if y < 0 {
+                scrollback
+                    .and_then(|s| s.get_line((history + y) as usize))
+                    .map_or_else(Vec::new, |line| line.cells.clone())
+            } else {
+                (0..cols)
+                    .map(|col| state.cell(y as usize, col).copied().unwrap_or_default())
+                    .collect()
+            };
+            grid.extend(line.into_iter().take(cols));
+            grid.resize((height + 1) * cols, Cell::default());
+            height += 1;
+        }
+        (grid, height)
+    }
+}
+
+/// How a run of cells looks, with reverse video already applied
+///
+/// `None` colors are the theme's and are left to the viewer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Style {
+    fg: Option<u32>,
+    bg: Option<u32>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+    dim: bool,
+}
+
+impl Style {
+    fn of(cell: &Cell) -> Self {
+        let attrs = TextAttributes::from_flags(cell.flags);
+        let fg = (cell.fg != THEME_FG).then_some(cell.fg);
+        let bg = match cell.bg {
+            0 | 0xFF000000 | THEME_BG => None,
+            bg => Some(bg),
+        };
+        let (fg, bg) = if attrs.reverse {
+            (Some(bg.unwrap_or(THEME_BG)), Some(fg.unwrap_or(THEME_FG)))
+        } else {
+            (fg, bg)
+        };
+        Self {
+            fg,
+            bg,
+            bold: attrs.bold,
+            italic: attrs.italic,
+            underline: attrs.underline,
+            strikethrough: attrs.strikethrough,
+            dim: attrs.dim,
+        }
+    }
+
+    /// CSS `text-decoration` value, if any
+    fn decoration(&self) -> Option<&'static str> {
+        match (self.underline, self.strikethrough) {
+            (true, true) => Some("underline line-through"),
+            (true, false) => Some("underline"),
+            (false, true) => Some("line-through"),
+            (false, false) => None,
+        }
+    }
+
+    /// SGR parameters selecting this style from a reset
+    fn sgr(&self) -> String {
+        let mut sgr = String::from("0");
+        for (on, code) in [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline, "4"),
+            (self.strikethrough, "9"),
+        ] {
+            if on {
+                sgr.push(';');
+                sgr.push_str(code);
+            }
+        }
+        for (color, code) in [(self.fg, 38), (self.bg, 48)] {
+            if let Some(color) = color {
+                let [_, r, g, b] = color.to_be_bytes();
+                let _ = write!(sgr, ";{};2;{};{};{}", code, r, g, b);
+            }
+        }
+        sgr
+    }
+}
+
+/// Character shown for a cell; empty cells show as spaces
+fn cell_char(cell: &Cell) -> char {
+    match cell.char_codepoint {
+        0 => ' ',
+        codepoint => char::from_u32(codepoint).unwrap_or(' '),
+    }
+}
+
+/// Runs of equally styled text in a row, as (first column, style, text)
+///
+/// Trailing blanks without a background or line of their own are left out.
+fn runs(row: &[Cell]) -> Vec<(usize, Style, String)> {
+    let end = row
+        .iter()
+        .rposition(|cell| {
+            let style = Style::of(cell);
+            cell_char(cell) != ' ' || style.bg.is_some() || style.decoration().is_some()
+        })
+        .map_or(0, |last| last + 1);
+
+    let mut runs: Vec<(usize, Style, String)> = Vec::new();
+    for (col, cell) in row[..end].iter().enumerate() {
+        let style = Style::of(cell);
+        match runs.last_mut() {
+            Some((_, last, text)) if *last == style => text.push(cell_char(cell)),
+            _ => runs.push((col, style, cell_char(cell).to_string())),
+        }
+    }
+    runs
+}
+
+/// Rows of `grid` as runs, without trailing blank rows
+fn styled_rows(grid: &[Cell], width: usize, height: usize) -> Vec<Vec<(usize, Style, String)>> {
+    if width == 0 {
+        return Vec::new();
+    }
+    let mut rows: Vec<_> = grid.chunks(width).take(height).map(runs).collect();
+    while rows.last().is_some_and(Vec::is_empty) {
+        rows.pop();
+    }
+    rows
+}
+
+/// Escape text for HTML and SVG
+fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// CSS hex form of an ARGB color
+fn css_color(argb: u32) -> String {
+    format!("#{:06x}", argb & 0x00FF_FFFF)
+}
 
 /// Export terminal grid to various formats for accessibility
 pub struct TerminalExporter;
@@ -17,17 +218,27 @@ impl TerminalExporter {
         format: ExportFormat,
         path: &Path,
     ) -> io::Result<()> {
-        let content = match format {
-            ExportFormat::PlainText => Self::export_to_text(grid, width, height),
-            ExportFormat::Html => Self::export_to_html(grid, width, height),
-            ExportFormat::Markdown => Self::export_to_markdown(grid, width, height),
-        };
+        let content = Self::render(grid, width, height, format);
 
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
         let mut file = File::create(path)?;
         file.write_all(content.as_bytes())?;
         Ok(())
     }
 
+    /// Render grid in the specified format
+    pub fn render(grid: &[Cell], width: usize, height: usize, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::PlainText => Self::export_to_text(grid, width, height),
+            ExportFormat::Html => Self::export_to_html(grid, width, height),
+            ExportFormat::Markdown => Self::export_to_markdown(grid, width, height),
+            ExportFormat::Svg => Self::export_to_svg(grid, width, height),
+            ExportFormat::Ansi => Self::export_to_ansi(grid, width, height),
+        }
+    }
+
     /// Export from SharedState directly
     pub fn export_from_shared_state(
         state: &SharedState,
@@ -76,115 +287,165 @@ impl TerminalExporter {
         output.trim_end().to_string() + "\n"
     }
 
-    /// Export to HTML with CSS color preservation
+    /// Export to HTML with colors and text styles as inline CSS
+    ///
+    /// Cells in the theme's colors take the page's, so only text styled by
+    /// the program gets a `<span>`.
     pub fn export_to_html(grid: &[Cell], width: usize, height: usize) -> String {
         let mut output = String::new();
 
-        // HTML header with styling
         output.push_str("<!DOCTYPE html>\n");
         output.push_str("<html>\n<head>\n");
         output.push_str("  <meta charset=\"UTF-8\">\n");
         output.push_str("  <title>Terminal Export</title>\n");
         output.push_str("  <style>\n");
         output.push_str("    body {\n");
-        output.push_str("      background-color: #000;\n");
-        output.push_str("      color: #fff;\n");
-        output.push_str("      font-family: 'Courier New', monospace;\n");
-        output.push_str("      font-size: 14px;\n");
-        output.push_str("      line-height: 1.2;\n");
+        let _ = writeln!(output, "      background-color: {};", css_color(THEME_BG));
+        let _ = writeln!(output, "      color: {};", css_color(THEME_FG));
         output.push_str("      padding: 20px;\n");
         output.push_str("    }\n");
         output.push_str("    .terminal {\n");
-        output.push_str("      white-space: pre;\n");
-        output.push_str("      display: inline-block;\n");
-        output.push_str("    }\n");
-        output.push_str("    .terminal-line {\n");
-        output.push_str("      display: block;\n");
-        output.push_str("    }\n");
-        output.push_str("    .cell {\n");
-        output.push_str("      display: inline;\n");
+        output.push_str("      font-family: 'Courier New', monospace;\n");
+        output.push_str("      font-size: 14px;\n");
+        output.push_str("      line-height: 1.2;\n");
+        output.push_str("      margin: 0;\n");
         output.push_str("    }\n");
-        output.push_str("    .bold { font-weight: bold; }\n");
-        output.push_str("    .italic { font-style: italic; }\n");
         output.push_str("  </style>\n");
         output.push_str("</head>\n<body>\n");
-        output.push_str("<div class=\"terminal\">\n");
-
-        // Export grid with color information
-        for row in 0..height {
-            output.push_str("<span class=\"terminal-line\">");
-
-            for col in 0..width {
-                let idx = row * width + col;
-                if idx >= grid.len() {
-                    break;
+        output.push_str("<pre class=\"terminal\">");
+
+        for row in styled_rows(grid, width, height) {
+            for (_, style, text) in row {
+                let text = escape_markup(&text);
+                if style == Style::default() {
+                    output.push_str(&text);
+                    continue;
                 }
 
-                let cell = &grid[idx];
-                let ch = char::from_u32(cell.char_codepoint).unwrap_or(' ');
+                let mut css = String::new();
+                if let Some(fg) = style.fg {
+                    let _ = write!(css, "color: {};", css_color(fg));
+                }
+                if let Some(bg) = style.bg {
+                    let _ = write!(css, "background-color: {};", css_color(bg));
+                }
+                if style.bold {
+                    css.push_str("font-weight: bold;");
+                }
+                if style.italic {
+                    css.push_str("font-style: italic;");
+                }
+                if let Some(decoration) = style.decoration() {
+                    let _ = write!(css, "text-decoration: {};", decoration);
+                }
+                if style.dim {
+                    css.push_str("opacity: 0.5;");
+                }
+                let _ = write!(output, "<span style=\"{}\">{}</span>", css, text);
+            }
+            output.push('\n');
+        }
 
-                // Extract RGBA components
-                let fg = cell.fg;
-                let bg = cell.bg;
+        output.push_str("</pre>\n");
+        output.push_str("</body>\n</html>\n");
 
-                let fg_r = ((fg >> 24) & 0xFF) as u8;
-                let fg_g = ((fg >> 16) & 0xFF) as u8;
-                let fg_b = ((fg >> 8) & 0xFF) as u8;
+        output
+    }
 
-                let bg_r = ((bg >> 24) & 0xFF) as u8;
-                let bg_g = ((bg >> 16) & 0xFF) as u8;
-                let bg_b = ((bg >> 8) & 0xFF) as u8;
+    /// Export to an SVG image of the grid in the theme's colors
+    ///
+    /// Text stays text, so the image can be searched, copied from and
+    /// read aloud.
+    pub fn export_to_svg(grid: &[Cell], width: usize, height: usize) -> String {
+        let rows = styled_rows(grid, width, height);
+        let image_width = width as f32 * SVG_CELL_WIDTH;
+        let image_height = rows.len().max(1) as f32 * SVG_CELL_HEIGHT;
 
-                // Build style classes
-                let mut classes = vec!["cell"];
-                if cell.flags & 0x01 != 0 {
-                    classes.push("bold");
+        let mut output = String::new();
+        let _ = writeln!(
+            output,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.1}\" height=\"{h:.1}\" \
+             viewBox=\"0 0 {w:.1} {h:.1}\" font-family=\"monospace\" font-size=\"{size:.1}\">",
+            w = image_width,
+            h = image_height,
+            size = SVG_FONT_SIZE,
+        );
+        let _ = writeln!(
+            output,
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            css_color(THEME_BG)
+        );
+
+        for (row, runs) in rows.iter().enumerate() {
+            let y = row as f32 * SVG_CELL_HEIGHT;
+            for (col, style, text) in runs {
+                let x = *col as f32 * SVG_CELL_WIDTH;
+                let length = text.chars().count() as f32 * SVG_CELL_WIDTH;
+                if let Some(bg) = style.bg {
+                    let _ = writeln!(
+                        output,
+                        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>",
+                        x,
+                        y,
+                        length,
+                        SVG_CELL_HEIGHT,
+                        css_color(bg)
+                    );
                 }
-                if cell.flags & 0x02 != 0 {
-                    classes.push("italic");
+                if text.trim().is_empty() && style.decoration().is_none() {
+                    continue;
                 }
 
-                // Only add span if we have non-default colors or styles
-                // Default fg is white (0xFFFFFFFF), default bg is black (0xFF000000) in ARGB format
-                let has_custom_fg = fg != 0xFFFFFFFF;
-                let has_custom_bg = bg != 0xFF000000;
-                let has_styles = classes.len() > 1;
-
-                if has_custom_fg || has_custom_bg || has_styles {
-                    output.push_str(&format!("<span class=\"{}\" style=\"", classes.join(" ")));
-
-                    if has_custom_fg {
-                        output.push_str(&format!("color: rgb({}, {}, {});", fg_r, fg_g, fg_b));
-                    }
-                    if has_custom_bg {
-                        output.push_str(&format!(
-                            "background-color: rgb({}, {}, {});",
-                            bg_r, bg_g, bg_b
-                        ));
-                    }
-
-                    output.push_str("\">");
+                let mut attributes = format!(
+                    "x=\"{:.1}\" y=\"{:.1}\" textLength=\"{:.1}\" fill=\"{}\"",
+                    x,
+                    y + SVG_BASELINE,
+                    length,
+                    css_color(style.fg.unwrap_or(THEME_FG))
+                );
+                if style.bold {
+                    attributes.push_str(" font-weight=\"bold\"");
                 }
-
-                // Escape HTML entities
-                match ch {
-                    '<' => output.push_str("&lt;"),
-                    '>' => output.push_str("&gt;"),
-                    '&' => output.push_str("&amp;"),
-                    ' ' => output.push_str("&nbsp;"),
-                    _ => output.push(ch),
+                if style.italic {
+                    attributes.push_str(" font-style=\"italic\"");
                 }
-
-                if has_custom_fg || has_custom_bg || has_styles {
-                    output.push_str("</span>");
+                if let Some(decoration) = style.decoration() {
+                    let _ = write!(attributes, " text-decoration=\"{}\"", decoration);
+                }
+                if style.dim {
+                    attributes.push_str(" opacity=\"0.5\"");
                 }
+                let _ = writeln!(
+                    output,
+                    "<text {} xml:space=\"preserve\">{}</text>",
+                    attributes,
+                    escape_markup(text)
+                );
             }
-
-            output.push_str("</span>\n");
         }
 
-        output.push_str("</div>\n");
-        output.push_str("</body>\n</html>\n");
+        output.push_str("</svg>\n");
+        output
+    }
+
+    /// Export to text with SGR escape sequences, e.g. for `less -R` or `cat`
+    ///
+    /// Colors are 24-bit; theme colors are left to the terminal showing
+    /// the dump. Every line ends with a reset.
+    pub fn export_to_ansi(grid: &[Cell], width: usize, height: usize) -> String {
+        let mut output = String::new();
+
+        for row in styled_rows(grid, width, height) {
+            for (_, style, text) in row {
+                if style == Style::default() {
+                    output.push_str("\x1b[0m");
+                } else {
+                    let _ = write!(output, "\x1b[{}m", style.sgr());
+                }
+                output.push_str(&text);
+            }
+            output.push_str("\x1b[0m\n");
+        }
 
         output
     }
@@ -270,13 +531,14 @@ impl TerminalExporter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::safe_state::MockTerminalState;
+    use crate::terminal::scrollback::ScrollbackLine;
     use scarab_protocol::Cell;
 
     fn create_test_grid() -> Vec<Cell> {
         let mut grid = vec![Cell::default(); 80 * 24];
 
-        // Set all cells to default white-on-black colors for HTML export test
-        // (the export function considers fg=0xFFFFFFFF, bg=0xFF000000 as "default")
+        // White on black; an opaque black background counts as the theme's
         for cell in grid.iter_mut() {
             cell.fg = 0xFFFFFFFF; // White foreground (ARGB)
             cell.bg = 0xFF000000; // Black background (ARGB)
@@ -303,8 +565,65 @@ mod tests {
         let grid = create_test_grid();
         let html = TerminalExporter::export_to_html(&grid, 80, 24);
         assert!(html.contains("<!DOCTYPE html>"));
-        // Spaces are encoded as &nbsp; in HTML output
-        assert!(html.contains("Hello,&nbsp;World!"));
+        // Runs of one style share a span; the black background is the theme's
+        assert!(html.contains("<span style=\"color: #ffffff;\">Hello, World!</span>\n</pre>"));
+    }
+
+    #[test]
+    fn test_export_styles() {
+        let mut grid = create_test_grid();
+        grid[0].flags = 0x01; // Bold
+        grid[7].bg = 0xFFFF0000;
+        grid[12].char_codepoint = '<' as u32;
+
+        let html = TerminalExporter::export_to_html(&grid, 80, 24);
+        assert!(html.contains("<span style=\"color: #ffffff;font-weight: bold;\">H</span>"));
+        assert!(html.contains("background-color: #ff0000;\">W</span>"));
+        assert!(html.contains("&lt;"));
+
+        let svg = TerminalExporter::export_to_svg(&grid, 80, 24);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"672.0\""));
+        // One row of text is left after trailing blank rows
+        assert!(svg.contains("height=\"17.0\""));
+        assert!(svg.contains("<rect x=\"58.8\" y=\"0.0\" width=\"8.4\""));
+        assert!(svg.contains("font-weight=\"bold\" xml:space=\"preserve\">H</text>"));
+
+        let ansi = TerminalExporter::export_to_ansi(&grid, 80, 24);
+        assert_eq!(
+            ansi,
+            "\x1b[0;1;38;2;255;255;255mH\x1b[0;38;2;255;255;255mello, \
+             \x1b[0;38;2;255;255;255;48;2;255;0;0mW\x1b[0;38;2;255;255;255morld<\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_export_range_cells() {
+        let mut state = MockTerminalState::new(4, 2);
+        state.set_cell(
+            0,
+            0,
+            Cell {
+                char_codepoint: 'a' as u32,
+                ..Cell::default()
+            },
+        );
+        let mut scrollback = ScrollbackBuffer::new(100);
+        scrollback.push_line(ScrollbackLine::from_text("old"));
+        scrollback.push_line(ScrollbackLine::from_text("newer line"));
+
+        let (grid, height) = ExportRange::Screen.cells(&state, Some(&scrollback), 4, 2);
+        assert_eq!(height, 2);
+        assert_eq!(TerminalExporter::export_to_text(&grid, 4, height), "a\n");
+
+        // From the newest scrollback line into the screen; lines are cut
+        // to the width, and lines above the scrollback are left out
+        let range = ExportRange::Lines { first: 0, last: -5 };
+        let (grid, height) = range.cells(&state, Some(&scrollback), 4, 2);
+        assert_eq!(height, 3);
+        assert_eq!(
+            TerminalExporter::export_to_text(&grid, 4, height),
+            "old\nnewe\na\n"
+        );
     }
 
     #[test]
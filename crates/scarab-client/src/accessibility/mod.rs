@@ -1,8 +1,8 @@
 /// Accessibility module for Scarab terminal emulator
 ///
 /// Provides accessibility features including:
-/// - Screen reader integration (AT-SPI stubs for future implementation)
-/// - Export capabilities (plain text, HTML, Markdown)
+/// - Screen reader integration (AccessKit live region, new output, review)
+/// - Export capabilities (plain text, HTML, Markdown, SVG, ANSI)
 /// - High contrast mode
 /// - Text scaling support
 /// - Keyboard-only navigation enhancements
@@ -10,16 +10,27 @@
 /// This module implements accessibility best practices for terminal emulators,
 /// making Scarab usable with assistive technologies.
 pub mod export;
+pub mod review;
 pub mod screen_reader;
 pub mod settings;
 
+use crate::copy_mode::{selection_spans, CopyModeStateResource};
+use crate::integration::SharedMemoryReader;
+use crate::ratatui_bridge::CommandSelected;
+use crate::terminal::scrollback::ScrollbackBuffer;
+use crate::ui::keybindings::KeyBindingTriggeredEvent;
+use crate::ui::ScrollbackSelectionState;
 use bevy::prelude::*;
+use scarab_protocol::terminal_state::TerminalStateReader;
+use scarab_protocol::TerminalMetrics;
+use std::path::{Path, PathBuf};
 
 // Re-export main types
-pub use export::TerminalExporter;
+pub use export::{ExportRange, TerminalExporter};
+pub use review::{ReviewCursor, ReviewMove};
 pub use screen_reader::{
     announce_content_changes, announce_cursor_movement, Announcement, AnnouncementPriority,
-    AtSpiIntegration, ScreenReaderAnnounceEvent, ScreenReaderState,
+    AtSpiIntegration, LiveRegion, ScreenReaderAnnounceEvent, ScreenReaderState,
 };
 pub use settings::{AccessibilityConfig, AccessibilityEvent, ExportFormat};
 
@@ -27,7 +38,7 @@ pub use settings::{AccessibilityConfig, AccessibilityEvent, ExportFormat};
 ///
 /// This plugin integrates all accessibility features into the Bevy ECS:
 /// - Registers accessibility resources and events
-/// - Sets up screen reader announcement systems
+/// - Sets up screen reader announcement systems, new output and review
 /// - Handles high contrast mode toggles
 /// - Manages export commands
 pub struct AccessibilityPlugin;
@@ -37,21 +48,29 @@ impl Plugin for AccessibilityPlugin {
         // Initialize resources
         app.insert_resource(AccessibilityConfig::default())
             .insert_resource(ScreenReaderState::default())
-            .insert_resource(AtSpiIntegration::default());
+            .insert_resource(AtSpiIntegration::default())
+            .init_resource::<ReviewCursor>();
 
         // Register events
         app.add_event::<AccessibilityEvent>()
             .add_event::<ScreenReaderAnnounceEvent>()
             .add_event::<ExportGridEvent>()
             .add_event::<ToggleHighContrastEvent>()
-            .add_event::<ChangeTextScaleEvent>();
+            .add_event::<ChangeTextScaleEvent>()
+            .add_event::<CommandSelected>()
+            .add_event::<KeyBindingTriggeredEvent>();
 
         // Add systems
+        app.add_systems(Startup, screen_reader::spawn_live_region);
         app.add_systems(
             Update,
             (
-                screen_reader::handle_screen_reader_announcements,
+                screen_reader::enable_when_requested,
+                review::handle_review_commands,
+                screen_reader::announce_content_changes,
+                handle_export_commands,
                 handle_export_requests,
+                screen_reader::handle_screen_reader_announcements,
                 handle_high_contrast_toggle,
                 handle_text_scale_changes,
                 apply_high_contrast_mode,
@@ -70,6 +89,65 @@ pub struct ExportGridEvent {
     pub format: ExportFormat,
     /// Output file path
     pub path: String,
+    /// Lines to export
+    pub range: ExportRange,
+}
+
+/// Palette commands exporting the screen or selection, as (id, name, format)
+pub const EXPORT_COMMANDS: [(&str, &str, ExportFormat); 4] = [
+    ("a11y.export.html", "Export as HTML", ExportFormat::Html),
+    ("a11y.export.svg", "Export as SVG", ExportFormat::Svg),
+    (
+        "a11y.export.ansi",
+        "Export as ANSI Text",
+        ExportFormat::Ansi,
+    ),
+    (
+        "a11y.export.text",
+        "Export as Plain Text",
+        ExportFormat::PlainText,
+    ),
+];
+
+/// Default path for an export: the documents directory, named by time
+pub fn default_export_path(format: ExportFormat) -> PathBuf {
+    let dir = dirs::document_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let name = format!(
+        "scarab-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+    dir.join(name)
+}
+
+/// Lines covered by the current selection, if any
+///
+/// A copy mode selection wins over one made in the scrollback view. Both
+/// export whole lines.
+pub fn selection_range(
+    copy_mode: Option<&CopyModeStateResource>,
+    scrollback_selection: Option<&ScrollbackSelectionState>,
+    scrollback_lines: usize,
+    cols: u16,
+) -> Option<ExportRange> {
+    if let Some(copy_mode) = copy_mode.filter(|c| c.is_active()) {
+        let spans = selection_spans(&copy_mode.state, cols);
+        if let (Some(first), Some(last)) = (spans.first(), spans.last()) {
+            return Some(ExportRange::Lines {
+                first: first.y,
+                last: last.y,
+            });
+        }
+    }
+
+    let selection = scrollback_selection.filter(|s| s.active && s.in_scrollback)?;
+    let line = |index: usize| index as i32 - scrollback_lines as i32;
+    Some(ExportRange::Lines {
+        first: line(selection.scrollback_start_line),
+        last: line(selection.scrollback_end_line),
+    })
 }
 
 /// Event to toggle high contrast mode
@@ -85,27 +163,69 @@ pub struct ChangeTextScaleEvent {
     pub delta: bool,
 }
 
+/// System to turn palette commands into export requests
+fn handle_export_commands(
+    mut commands_selected: EventReader<CommandSelected>,
+    copy_mode: Option<Res<CopyModeStateResource>>,
+    scrollback_selection: Option<Res<ScrollbackSelectionState>>,
+    scrollback: Option<Res<ScrollbackBuffer>>,
+    metrics: Option<Res<TerminalMetrics>>,
+    mut export_events: EventWriter<ExportGridEvent>,
+) {
+    for event in commands_selected.read() {
+        let Some(&(_, _, format)) = EXPORT_COMMANDS
+            .iter()
+            .find(|(id, _, _)| *id == event.command_id)
+        else {
+            continue;
+        };
+        let range = selection_range(
+            copy_mode.as_deref(),
+            scrollback_selection.as_deref(),
+            scrollback.as_ref().map_or(0, |s| s.line_count()),
+            metrics.as_ref().map_or(80, |m| m.columns),
+        )
+        .unwrap_or_default();
+        export_events.send(ExportGridEvent {
+            format,
+            path: default_export_path(format).display().to_string(),
+            range,
+        });
+    }
+}
+
 /// System to handle export requests
 fn handle_export_requests(
     mut export_events: EventReader<ExportGridEvent>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    scrollback: Option<Res<ScrollbackBuffer>>,
+    metrics: Option<Res<TerminalMetrics>>,
     mut accessibility_events: EventWriter<AccessibilityEvent>,
-    // TODO: Add terminal grid resource access
-    // grid: Res<SharedMemoryReader>,
+    mut announcements: EventWriter<ScreenReaderAnnounceEvent>,
 ) {
+    if export_events.is_empty() {
+        return;
+    }
+    let Some(state_reader) = state_reader else {
+        export_events.clear();
+        warn!("Export requested before the terminal was ready");
+        return;
+    };
+
+    let state = state_reader.get_safe_state();
+    let (cols, rows) = metrics.map_or_else(
+        || state.dimensions(),
+        |m| (m.columns as usize, m.rows as usize),
+    );
     for event in export_events.read() {
         info!(
-            "Export requested: format={:?}, path={}",
-            event.format, event.path
+            "Export requested: format={:?}, path={}, range={:?}",
+            event.format, event.path, event.range
         );
 
-        // TODO: Get actual grid data from SharedMemoryReader
-        // For now, we'll just simulate the export
-        /*
-        let result = TerminalExporter::export_from_shared_state(
-            &grid.state,
-            event.format,
-            Path::new(&event.path),
-        );
+        let (grid, height) = event.range.cells(&state, scrollback.as_deref(), cols, rows);
+        let result =
+            TerminalExporter::export(&grid, cols, height, event.format, Path::new(&event.path));
 
         match result {
             Ok(_) => {
@@ -113,6 +233,10 @@ fn handle_export_requests(
                     format: event.format,
                     path: event.path.clone(),
                 });
+                announcements.send(ScreenReaderAnnounceEvent::new(Announcement::new(format!(
+                    "Exported to {}",
+                    event.path
+                ))));
                 info!("Export completed: {}", event.path);
             }
             Err(e) => {
@@ -120,16 +244,13 @@ fn handle_export_requests(
                     format: event.format,
                     error: e.to_string(),
                 });
+                announcements.send(ScreenReaderAnnounceEvent::new(Announcement::new(format!(
+                    "Export failed: {}",
+                    e
+                ))));
                 error!("Export failed: {}", e);
             }
         }
-        */
-
-        // Placeholder success event
-        accessibility_events.send(AccessibilityEvent::ExportCompleted {
-            format: event.format,
-            path: event.path.clone(),
-        });
     }
 }
 
@@ -230,7 +351,11 @@ pub fn parse_export_command(command: &str) -> Option<ExportGridEvent> {
     let format = ExportFormat::from_str(parts[2])?;
     let path = parts[3..].join(" ");
 
-    Some(ExportGridEvent { format, path })
+    Some(ExportGridEvent {
+        format,
+        path,
+        range: ExportRange::Screen,
+    })
 }
 
 /// Parse accessibility command
@@ -293,6 +418,8 @@ impl AccessibilityCommand {
   :a11y export text <path>       - Export terminal to plain text
   :a11y export html <path>       - Export terminal to HTML with colors
   :a11y export markdown <path>   - Export terminal to Markdown
+  :a11y export svg <path>        - Export terminal to an SVG image
+  :a11y export ansi <path>       - Export terminal with ANSI colors
   :a11y contrast toggle          - Toggle high contrast mode
   :a11y scale <factor>           - Set text scale (0.5 - 3.0)
   :a11y scale increase [delta]   - Increase text scale (default: 0.1)
@@ -343,6 +470,27 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_selection_range() {
+        assert_eq!(selection_range(None, None, 100, 80), None);
+
+        let mut selection = ScrollbackSelectionState::default();
+        selection.start_scrollback_selection(
+            90,
+            0,
+            crate::ui::visual_selection::SelectionMode::Line,
+        );
+        selection.update_scrollback_selection(95, 10);
+        // Scrollback lines count up to -1 for the newest of the 100
+        assert_eq!(
+            selection_range(None, Some(&selection), 100, 80),
+            Some(ExportRange::Lines {
+                first: -10,
+                last: -5
+            })
+        );
+    }
+
     #[test]
     fn test_parse_accessibility_command_help() {
         let cmd = ":a11y help";
@@ -0,0 +1,259 @@
+//! Screen review
+//!
+//! Moves a review cursor over the visible grid by line, word or character
+//! and reads what it lands on, without moving the terminal's own cursor.
+//! Review starts at the terminal cursor, and starts there again once new
+//! output has been announced.
+
+use bevy::prelude::*;
+
+use super::screen_reader::{grid_rows, Announcement, ScreenReaderAnnounceEvent, ScreenReaderState};
+use crate::integration::SharedMemoryReader;
+use crate::ratatui_bridge::CommandSelected;
+use crate::ui::keybindings::KeyBindingTriggeredEvent;
+use scarab_protocol::terminal_state::TerminalStateReader;
+
+/// A step of the review cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewMove {
+    PreviousLine,
+    NextLine,
+    PreviousWord,
+    NextWord,
+    PreviousChar,
+    NextChar,
+}
+
+impl ReviewMove {
+    pub const ALL: [ReviewMove; 6] = [
+        ReviewMove::PreviousLine,
+        ReviewMove::NextLine,
+        ReviewMove::PreviousWord,
+        ReviewMove::NextWord,
+        ReviewMove::PreviousChar,
+        ReviewMove::NextChar,
+    ];
+
+    /// Palette command and key binding action for this step
+    pub fn action(self) -> &'static str {
+        match self {
+            ReviewMove::PreviousLine => "a11y.review.previous_line",
+            ReviewMove::NextLine => "a11y.review.next_line",
+            ReviewMove::PreviousWord => "a11y.review.previous_word",
+            ReviewMove::NextWord => "a11y.review.next_word",
+            ReviewMove::PreviousChar => "a11y.review.previous_char",
+            ReviewMove::NextChar => "a11y.review.next_char",
+        }
+    }
+
+    /// Name in the command palette
+    pub fn name(self) -> &'static str {
+        match self {
+            ReviewMove::PreviousLine => "Review: Previous Line",
+            ReviewMove::NextLine => "Review: Next Line",
+            ReviewMove::PreviousWord => "Review: Previous Word",
+            ReviewMove::NextWord => "Review: Next Word",
+            ReviewMove::PreviousChar => "Review: Previous Character",
+            ReviewMove::NextChar => "Review: Next Character",
+        }
+    }
+
+    pub fn from_action(action: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|step| step.action() == action)
+    }
+}
+
+/// Where screen review is, as (row, column) of the visible grid
+#[derive(Resource, Debug, Default)]
+pub struct ReviewCursor {
+    /// None until review starts, or after new output was announced
+    pub position: Option<(usize, usize)>,
+}
+
+/// Non-blank runs of `line` as (start, end) character columns
+fn words(line: &[char]) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (col, ch) in line.iter().enumerate() {
+        match (ch.is_whitespace(), start) {
+            (false, None) => start = Some(col),
+            (true, Some(begin)) => {
+                words.push((begin, col));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        words.push((begin, line.len()));
+    }
+    words
+}
+
+/// Move from `position` over `rows` and return where review lands and
+/// what to say
+///
+/// Lines read whole, words and characters read alone. Review stops at the
+/// edges of the screen and, for characters, of the line, saying so.
+pub fn review(
+    rows: &[String],
+    position: (usize, usize),
+    step: ReviewMove,
+) -> ((usize, usize), String) {
+    let lines: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+    if lines.is_empty() {
+        return (position, "blank".to_string());
+    }
+    let last_row = lines.len().saturating_sub(1);
+    let (row, col) = (position.0.min(last_row), position.1);
+    let line_text = |row: usize| {
+        let text: String = lines
+            .get(row)
+            .map_or_else(String::new, |l| l.iter().collect());
+        if text.trim().is_empty() {
+            "blank".to_string()
+        } else {
+            text.trim_end().to_string()
+        }
+    };
+    let word_text = |row: usize, (start, end): (usize, usize)| -> String {
+        lines[row][start..end].iter().collect()
+    };
+
+    match step {
+        ReviewMove::PreviousLine if row == 0 => (position, "top".to_string()),
+        ReviewMove::NextLine if row >= last_row => (position, "bottom".to_string()),
+        ReviewMove::PreviousLine => ((row - 1, 0), line_text(row - 1)),
+        ReviewMove::NextLine => ((row + 1, 0), line_text(row + 1)),
+        ReviewMove::PreviousChar | ReviewMove::NextChar => {
+            let len = lines.get(row).map_or(0, Vec::len);
+            if len == 0 {
+                return ((row, 0), "blank".to_string());
+            }
+            let col = col.min(len - 1);
+            let target = match step {
+                ReviewMove::PreviousChar if col == 0 => return ((row, 0), "line start".into()),
+                ReviewMove::PreviousChar => col - 1,
+                _ if col + 1 >= len => return ((row, col), "line end".into()),
+                _ => col + 1,
+            };
+            let text = match lines[row][target] {
+                ' ' => "space".to_string(),
+                ch => ch.to_string(),
+            };
+            ((row, target), text)
+        }
+        ReviewMove::NextWord => {
+            if let Some(&word) = words(&lines[row]).iter().find(|(start, _)| *start > col) {
+                return ((row, word.0), word_text(row, word));
+            }
+            for next in row + 1..lines.len() {
+                if let Some(&word) = words(&lines[next]).first() {
+                    return ((next, word.0), word_text(next, word));
+                }
+            }
+            (position, "bottom".to_string())
+        }
+        ReviewMove::PreviousWord => {
+            if let Some(&word) = words(&lines[row])
+                .iter()
+                .rev()
+                .find(|(start, _)| *start < col)
+            {
+                return ((row, word.0), word_text(row, word));
+            }
+            for previous in (0..row).rev() {
+                if let Some(&word) = words(&lines[previous]).last() {
+                    return ((previous, word.0), word_text(previous, word));
+                }
+            }
+            (position, "top".to_string())
+        }
+    }
+}
+
+/// System to move screen review from the palette or key bindings
+pub fn handle_review_commands(
+    mut commands_selected: EventReader<CommandSelected>,
+    mut key_bindings: EventReader<KeyBindingTriggeredEvent>,
+    mut cursor: ResMut<ReviewCursor>,
+    state: Res<ScreenReaderState>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    mut announcements: EventWriter<ScreenReaderAnnounceEvent>,
+) {
+    let steps: Vec<ReviewMove> = commands_selected
+        .read()
+        .filter_map(|event| ReviewMove::from_action(&event.command_id))
+        .chain(
+            key_bindings
+                .read()
+                .filter_map(|event| ReviewMove::from_action(&event.action)),
+        )
+        .collect();
+    if steps.is_empty() || !state.enabled {
+        return;
+    }
+    let Some(state_reader) = state_reader else {
+        return;
+    };
+
+    let grid = state_reader.get_safe_state();
+    let rows = grid_rows(&grid);
+    let mut position = cursor.position.unwrap_or_else(|| {
+        let (x, y) = grid.cursor_pos();
+        (y as usize, x as usize)
+    });
+    for step in steps {
+        let (next, text) = review(&rows, position, step);
+        position = next;
+        announcements.send(ScreenReaderAnnounceEvent::new(Announcement::urgent(text)));
+    }
+    cursor.position = Some(position);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review() {
+        let rows: Vec<String> = ["$ cargo  build", "", "   Compiling scarab"]
+            .iter()
+            .map(|row| row.to_string())
+            .collect();
+
+        assert_eq!(
+            review(&rows, (0, 0), ReviewMove::NextWord),
+            ((0, 2), "cargo".to_string())
+        );
+        // Words continue on the next line with text
+        assert_eq!(
+            review(&rows, (0, 9), ReviewMove::NextWord),
+            ((2, 3), "Compiling".to_string())
+        );
+        assert_eq!(
+            review(&rows, (2, 3), ReviewMove::PreviousWord),
+            ((0, 9), "build".to_string())
+        );
+        assert_eq!(
+            review(&rows, (0, 0), ReviewMove::NextLine),
+            ((1, 0), "blank".to_string())
+        );
+        assert_eq!(
+            review(&rows, (2, 0), ReviewMove::NextLine),
+            ((2, 0), "bottom".to_string())
+        );
+        assert_eq!(
+            review(&rows, (0, 0), ReviewMove::NextChar),
+            ((0, 1), "space".to_string())
+        );
+        assert_eq!(
+            review(&rows, (0, 13), ReviewMove::NextChar),
+            ((0, 13), "line end".to_string())
+        );
+        assert_eq!(
+            ReviewMove::from_action("a11y.review.next_word"),
+            Some(ReviewMove::NextWord)
+        );
+    }
+}
@@ -1,12 +1,23 @@
+use bevy::a11y::accesskit::{Live, Node as AccessNode, Role};
+use bevy::a11y::{AccessibilityNode, AccessibilityRequested};
 use bevy::prelude::*;
+use scarab_protocol::terminal_state::TerminalStateReader;
+use std::time::{Duration, Instant};
+
+use super::review::ReviewCursor;
+use crate::integration::SharedMemoryReader;
 
 /// Screen reader integration module
 ///
-/// This module provides stubs for future AT-SPI (Assistive Technology Service Provider Interface)
-/// integration on Linux. AT-SPI is the accessibility framework used by screen readers like Orca.
+/// This module connects Scarab to screen readers such as Orca, which on Linux
+/// listen on AT-SPI (Assistive Technology Service Provider Interface).
+///
+/// Announcements reach screen readers through an AccessKit live region, which
+/// bevy_winit exposes over AT-SPI, UI Automation or NSAccessibility. New
+/// lines of terminal output are announced there too, batched so a flood of
+/// output does not queue minutes of speech.
 ///
-/// Current implementation provides event placeholders and announcement infrastructure.
-/// Full AT-SPI integration would require:
+/// A native AT-SPI text interface for the whole grid would further require:
 /// - D-Bus bindings for AT-SPI protocol
 /// - Application accessibility object tree
 /// - Text interface implementation (org.a11y.atspi.Text)
@@ -64,8 +75,24 @@ impl Announcement {
             interrupt: true,
         }
     }
+
+    /// Create a low priority announcement of terminal output
+    pub fn output(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            priority: AnnouncementPriority::Low,
+            interrupt: false,
+        }
+    }
 }
 
+/// Shortest time between announcements of new output
+pub const LIVE_OUTPUT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Most lines of output read in one announcement; older ones are counted
+/// instead of read
+pub const MAX_ANNOUNCED_LINES: usize = 10;
+
 /// Screen reader state and configuration
 #[derive(Debug, Clone, Resource)]
 pub struct ScreenReaderState {
@@ -199,19 +226,69 @@ impl AtSpiIntegration {
     }
 }
 
+/// Marker for the AccessKit live region announcements are spoken from
+#[derive(Component)]
+pub struct LiveRegion;
+
+/// Bevy system to create the live region
+pub fn spawn_live_region(mut commands: Commands) {
+    let mut node = AccessNode::new(Role::Log);
+    node.set_live(Live::Polite);
+    commands.spawn((LiveRegion, AccessibilityNode::from(node)));
+}
+
+/// Bevy system to turn screen reader support on once one connects
+pub fn enable_when_requested(
+    requested: Option<Res<AccessibilityRequested>>,
+    mut state: ResMut<ScreenReaderState>,
+) {
+    let requested = requested.is_some_and(|requested| requested.get());
+    if requested && !state.enabled {
+        info!("Assistive technology connected; enabling screen reader support");
+        state.enabled = true;
+    }
+}
+
 /// Bevy system to handle screen reader announcements
 pub fn handle_screen_reader_announcements(
     mut events: EventReader<ScreenReaderAnnounceEvent>,
     state: Res<ScreenReaderState>,
     atspi: Res<AtSpiIntegration>,
+    mut live_region: Query<&mut AccessibilityNode, With<LiveRegion>>,
+    mut alternate: Local<bool>,
 ) {
     if !state.enabled {
+        events.clear();
         return;
     }
 
+    let mut texts = Vec::new();
+    let mut interrupt = false;
     for event in events.read() {
         atspi.announce(&event.announcement);
+        texts.push(event.announcement.text.as_str());
+        interrupt |= event.announcement.interrupt;
     }
+    if texts.is_empty() {
+        return;
+    }
+
+    let Ok(mut node) = live_region.get_single_mut() else {
+        return;
+    };
+    // Screen readers only speak a live region when its text changes, so
+    // saying the same thing twice needs the text to differ invisibly
+    *alternate = !*alternate;
+    let mut text = texts.join("\n");
+    if *alternate {
+        text.push('\u{a0}');
+    }
+    node.set_live(if interrupt {
+        Live::Assertive
+    } else {
+        Live::Polite
+    });
+    node.set_label(text);
 }
 
 /// Bevy system to announce cursor movements
@@ -231,21 +308,148 @@ pub fn announce_cursor_movement(
     // Announce new position or line content
 }
 
+/// Visible rows of the grid as text, without trailing blanks
+pub fn grid_rows(state: &impl TerminalStateReader) -> Vec<String> {
+    let (width, height) = state.dimensions();
+    (0..height)
+        .map(|row| {
+            let line: String = (0..width)
+                .map(|col| {
+                    state
+                        .cell(row, col)
+                        .and_then(|cell| match cell.char_codepoint {
+                            0 => Some(' '),
+                            codepoint => char::from_u32(codepoint),
+                        })
+                        .unwrap_or(' ')
+                })
+                .collect();
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// How many rows the screen scrolled up between `previous` and `current`
+///
+/// Picks the shift lining up the most non-blank rows; ties go to the
+/// smaller shift.
+fn scroll_shift(previous: &[String], current: &[String]) -> usize {
+    (0..previous.len())
+        .map(|shift| {
+            let matching = current
+                .iter()
+                .zip(&previous[shift..])
+                .filter(|(now, before)| now == before && !now.is_empty())
+                .count();
+            (shift, matching)
+        })
+        .fold((0, 0), |best, (shift, matching)| {
+            if matching > best.1 {
+                (shift, matching)
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+/// Rows of `current` that are new since `previous`, top to bottom
+///
+/// Rows that only scrolled are not new. The row at `cursor_row` is left
+/// out while it is being written, as typed input is echoed by the screen
+/// reader itself; once the cursor moves on, the finished row is new if it
+/// changed.
+pub fn new_lines(previous: &[String], current: &[String], cursor_row: usize) -> Vec<String> {
+    let shift = scroll_shift(previous, current);
+    current
+        .iter()
+        .enumerate()
+        .filter(|(row, line)| {
+            *row != cursor_row && !line.is_empty() && previous.get(row + shift) != Some(*line)
+        })
+        .map(|(_, line)| line.clone())
+        .collect()
+}
+
+/// Text announcing `lines` of new output, after `skipped` lines that came
+/// too fast to read
+pub fn batch_announcement(skipped: usize, lines: &[String]) -> Option<String> {
+    if lines.is_empty() {
+        return None;
+    }
+    let text = lines.join("\n");
+    Some(match skipped {
+        0 => text,
+        1 => format!("1 more line.\n{}", text),
+        _ => format!("{} more lines.\n{}", skipped, text),
+    })
+}
+
+/// Output seen on screen and waiting to be announced
+#[derive(Default)]
+pub struct LiveOutput {
+    /// Rows as last seen
+    rows: Vec<String>,
+    /// Shared state sequence the rows were read at
+    sequence: u64,
+    /// New lines not announced yet, at most [`MAX_ANNOUNCED_LINES`]
+    pending: Vec<String>,
+    /// New lines dropped from `pending` to keep it short
+    skipped: usize,
+    last_announced: Option<Instant>,
+}
+
 /// Bevy system to announce content changes
+///
+/// New lines are collected every frame the grid changes and announced at
+/// most every [`LIVE_OUTPUT_INTERVAL`].
 pub fn announce_content_changes(
     state: Res<ScreenReaderState>,
-    // TODO: Add terminal content tracking
-    // grid: Res<TerminalGrid>,
-    // mut last_content: Local<Option<String>>,
-    _events: EventWriter<ScreenReaderAnnounceEvent>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    mut live: Local<LiveOutput>,
+    mut review: ResMut<ReviewCursor>,
+    mut events: EventWriter<ScreenReaderAnnounceEvent>,
 ) {
+    let Some(state_reader) = state_reader else {
+        return;
+    };
     if !state.enabled || !state.announce_content {
+        // Turning announcements on later should not read the whole screen
+        *live = LiveOutput::default();
         return;
     }
 
-    // TODO: Implement content change detection
-    // Compare current grid with last known state
-    // Announce new content that appears
+    let grid = state_reader.get_safe_state();
+    let sequence = grid.sequence();
+    if sequence != live.sequence {
+        let rows = grid_rows(&grid);
+        if !live.rows.is_empty() {
+            let (_, cursor_row) = grid.cursor_pos();
+            let lines = new_lines(&live.rows, &rows, cursor_row as usize);
+            live.pending.extend(lines);
+            let excess = live.pending.len().saturating_sub(MAX_ANNOUNCED_LINES);
+            live.pending.drain(..excess);
+            live.skipped += excess;
+        }
+        live.rows = rows;
+        live.sequence = sequence;
+    }
+
+    let now = Instant::now();
+    let due = live
+        .last_announced
+        .map_or(true, |at| now.duration_since(at) >= LIVE_OUTPUT_INTERVAL);
+    if !due {
+        return;
+    }
+    if let Some(text) = batch_announcement(live.skipped, &live.pending) {
+        events.send(ScreenReaderAnnounceEvent::new(Announcement::output(text)));
+        live.pending.clear();
+        live.skipped = 0;
+        live.last_announced = Some(now);
+        // Review picks up again from the cursor, next to the new output
+        review.position = None;
+    }
 }
 
 #[cfg(test)]
@@ -274,6 +478,28 @@ mod tests {
         assert!(ann.interrupt);
     }
 
+    #[test]
+    fn test_new_lines() {
+        let rows =
+            |lines: &[&str]| -> Vec<String> { lines.iter().map(|line| line.to_string()).collect() };
+        let previous = rows(&["$ ls", "a.txt", "$ make", ""]);
+
+        // Two lines of output scrolled the screen up by one
+        let current = rows(&["a.txt", "$ make", "cc main.c", "ld main"]);
+        assert_eq!(new_lines(&previous, &current, 3), ["cc main.c"]);
+        assert_eq!(new_lines(&previous, &current, 0), ["cc main.c", "ld main"]);
+
+        // Nothing changed but the line being typed
+        let typing = rows(&["$ ls", "a.txt", "$ make", "$ gi"]);
+        assert!(new_lines(&previous, &typing, 3).is_empty());
+
+        assert_eq!(batch_announcement(0, &[]), None);
+        assert_eq!(
+            batch_announcement(2, &current[2..]).unwrap(),
+            "2 more lines.\ncc main.c\nld main"
+        );
+    }
+
     #[test]
     fn test_screen_reader_state_default() {
         let state = ScreenReaderState::default();
@@ -60,6 +60,10 @@ pub enum ExportFormat {
     Html,
     /// Markdown code block format
     Markdown,
+    /// SVG image with colors and text styles
+    Svg,
+    /// Text with SGR escape sequences for colors and text styles
+    Ansi,
 }
 
 impl ExportFormat {
@@ -69,6 +73,8 @@ impl ExportFormat {
             Self::PlainText => "txt",
             Self::Html => "html",
             Self::Markdown => "md",
+            Self::Svg => "svg",
+            Self::Ansi => "ansi",
         }
     }
 
@@ -78,6 +84,8 @@ impl ExportFormat {
             Self::PlainText => "text/plain",
             Self::Html => "text/html",
             Self::Markdown => "text/markdown",
+            Self::Svg => "image/svg+xml",
+            Self::Ansi => "text/plain",
         }
     }
 
@@ -87,6 +95,8 @@ impl ExportFormat {
             "text" | "txt" | "plain" => Some(Self::PlainText),
             "html" | "htm" => Some(Self::Html),
             "markdown" | "md" => Some(Self::Markdown),
+            "svg" => Some(Self::Svg),
+            "ansi" => Some(Self::Ansi),
             _ => None,
         }
     }
@@ -7,11 +7,18 @@
 //! - Open file in editor
 //! - Split pane operations
 //! - Search activation
+//! - Re-running or exporting the command of the block clicked
 //! - Custom plugin actions
 
 use bevy::prelude::*;
+use scarab_protocol::ControlMessage;
 
 use super::ContextMenuItemSelected;
+use crate::ipc::IpcChannel;
+use crate::ui::block_rerun::{RerunCommand, RerunTarget};
+
+/// Daemon command that exports the block last clicked in
+const EXPORT_COMMAND: &str = "zones.export";
 
 /// Context menu action types
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -40,6 +47,12 @@ pub enum ContextMenuAction {
     OpenFile(String),
     /// Copy file path to clipboard
     CopyPath(String),
+    /// Run the command of the block on this row again
+    RerunCommand(u16),
+    /// Edit the command of the block on this row, then run it
+    EditRerunCommand(u16),
+    /// Write the block clicked and its output to a file
+    ExportCommand,
     /// Custom plugin action
     PluginAction(String),
 }
@@ -60,6 +73,11 @@ impl ContextMenuAction {
             "copy_url" => data.map(|d| Self::CopyUrl(d.to_string())),
             "open_file" => data.map(|d| Self::OpenFile(d.to_string())),
             "copy_path" => data.map(|d| Self::CopyPath(d.to_string())),
+            "rerun_command" => data.and_then(|d| d.parse().ok()).map(Self::RerunCommand),
+            "edit_rerun_command" => data
+                .and_then(|d| d.parse().ok())
+                .map(Self::EditRerunCommand),
+            "export_command" => Some(Self::ExportCommand),
             _ => {
                 // Check if it's a plugin action
                 if id.starts_with("plugin.") {
@@ -96,6 +114,8 @@ pub fn handle_context_menu_actions(
 /// System to execute context menu actions
 pub fn dispatch_action(
     mut action_events: EventReader<DispatchContextMenuAction>,
+    mut reruns: EventWriter<RerunCommand>,
+    ipc: Option<Res<IpcChannel>>,
     // TODO: Add resources for clipboard, IPC, etc.
 ) {
     for event in action_events.read() {
@@ -197,6 +217,23 @@ pub fn dispatch_action(
                 }
             }
 
+            ContextMenuAction::RerunCommand(row) | ContextMenuAction::EditRerunCommand(row) => {
+                reruns.send(RerunCommand {
+                    target: RerunTarget::Row(*row),
+                    edit: matches!(event.action, ContextMenuAction::EditRerunCommand(_)),
+                });
+            }
+
+            ContextMenuAction::ExportCommand => {
+                // The right-click that opened the menu already told the
+                // daemon which block it landed in
+                if let Some(ipc) = ipc.as_ref() {
+                    ipc.send(ControlMessage::CommandSelected {
+                        id: EXPORT_COMMAND.to_string(),
+                    });
+                }
+            }
+
             ContextMenuAction::PluginAction(action_id) => {
                 info!("Executing plugin action: {}", action_id);
                 // TODO: Route to plugin system
@@ -244,6 +281,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rerun_actions() {
+        assert_eq!(
+            ContextMenuAction::from_id("rerun_command", Some("12")),
+            Some(ContextMenuAction::RerunCommand(12))
+        );
+        assert_eq!(
+            ContextMenuAction::from_id("edit_rerun_command", Some("3")),
+            Some(ContextMenuAction::EditRerunCommand(3))
+        );
+        // The row is needed to find the block
+        assert_eq!(ContextMenuAction::from_id("rerun_command", None), None);
+        assert_eq!(
+            ContextMenuAction::from_id("export_command", None),
+            Some(ContextMenuAction::ExportCommand)
+        );
+    }
+
     #[test]
     fn test_action_from_id_plugin() {
         let plugin_action = ContextMenuAction::from_id("plugin.custom_action", None);
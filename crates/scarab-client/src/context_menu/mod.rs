@@ -39,10 +39,12 @@ pub use overlay::{render_context_menu, ContextMenuOverlay};
 pub use plugin_items::get_plugin_menu_items;
 
 use bevy::prelude::*;
-use scarab_mouse::context_menu::ContextMenu;
+use bevy::window::PrimaryWindow;
+use scarab_mouse::context_menu::{ContextMenu, MenuItem};
 use scarab_mouse::types::Position;
 
 use crate::ratatui_bridge::{RatatuiSurface, SurfaceFocus, SurfaceInputEvent};
+use crate::ui::block_rerun::RerunCommand;
 
 /// Marker component for the context menu surface
 #[derive(Component)]
@@ -55,6 +57,9 @@ pub struct ContextMenuState {
     pub menu: Option<ContextMenu>,
     /// Whether the menu surface entity has been spawned
     pub surface_spawned: bool,
+    /// What the items act on: the URL or file path clicked, or else the
+    /// row, for the command block items
+    pub data: Option<String>,
 }
 
 impl ContextMenuState {
@@ -109,7 +114,7 @@ pub struct ContextMenuItemSelected {
 /// System to spawn context menu on right-click
 pub fn detect_context_menu_request(
     mouse_button: Res<ButtonInput<bevy::input::mouse::MouseButton>>,
-    windows: Query<&Window>,
+    windows: Query<&Window, With<PrimaryWindow>>,
     metrics: Res<scarab_protocol::TerminalMetrics>,
     mut events: EventWriter<ShowContextMenuEvent>,
 ) {
@@ -156,8 +161,21 @@ pub fn handle_show_context_menu(
         } else if let Some(path) = &event.file_path {
             ContextMenu::file_menu(event.position, path.clone())
         } else {
-            ContextMenu::standard(event.position, event.has_selection)
+            let mut menu = ContextMenu::standard(event.position, event.has_selection);
+            menu.add_item(MenuItem::separator());
+            menu.add_item(MenuItem::new("rerun_command", "Re-run Command"));
+            menu.add_item(MenuItem::new(
+                "edit_rerun_command",
+                "Edit and Re-run Command",
+            ));
+            menu.add_item(MenuItem::new("export_command", "Export Command..."));
+            menu
         };
+        state.data = event
+            .url
+            .clone()
+            .or_else(|| event.file_path.clone())
+            .or_else(|| Some(event.position.y.to_string()));
 
         // Adjust position if menu would go off-screen
         let mut adjusted_menu = menu;
@@ -229,7 +247,7 @@ pub fn handle_context_menu_input(
                             if item.enabled {
                                 selection_events.send(ContextMenuItemSelected {
                                     item_id: item.id.clone(),
-                                    data: None, // TODO: Pass URL/path data
+                                    data: state.data.clone(),
                                 });
 
                                 state.hide();
@@ -256,7 +274,7 @@ pub fn handle_context_menu_input(
                             if item.enabled && !item.separator {
                                 selection_events.send(ContextMenuItemSelected {
                                     item_id: item.id.clone(),
-                                    data: None,
+                                    data: state.data.clone(),
                                 });
 
                                 state.hide();
@@ -333,6 +351,7 @@ impl Plugin for ContextMenuPlugin {
             .add_event::<ShowContextMenuEvent>()
             .add_event::<ContextMenuItemSelected>()
             .add_event::<DispatchContextMenuAction>()
+            .add_event::<RerunCommand>()
             .add_systems(Startup, spawn_context_menu_surface)
             .add_systems(
                 Update,
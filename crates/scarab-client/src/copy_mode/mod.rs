@@ -2,14 +2,35 @@
 //!
 //! This module integrates copy mode functionality with the Bevy game engine,
 //! providing systems and resources for vim-like keyboard navigation and selection.
+//!
+//! The copy mode cursor, the selection (including block rectangles) and search
+//! matches are drawn as translucent sprites parented to the terminal grid, so
+//! they follow its position, smooth scrolling and zoom. Rows are mapped to the
+//! viewport using the state's `viewport_offset`; anything scrolled out of view
+//! is skipped.
 
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
 use scarab_plugin_api::copy_mode::{
     copy_mode_indicator, copy_mode_position_indicator, find_matches, get_selection_bounds,
-    search_match_indicator, CopyModeState, SearchDirection, SearchState, SelectionMode,
+    normalize_selection, search_match_indicator, CopyModeState, SearchDirection, SearchMatch,
+    SearchState, SelectionMode,
 };
 use scarab_plugin_api::key_tables::CopyModeAction;
 use scarab_plugin_api::status_bar::RenderItem;
+use scarab_protocol::TerminalMetrics;
+
+use crate::integration::TerminalGridEntity;
+use crate::rendering::layers::{LAYER_CURSOR, LAYER_TEXT_DECORATIONS};
+
+/// Copy mode cursor (slime green, translucent so the glyph stays readable)
+const COPY_CURSOR_COLOR: Color = Color::srgba(0.66, 0.87, 0.35, 0.6);
+/// Selected cells
+const SELECTION_COLOR: Color = Color::srgba(0.66, 0.87, 0.35, 0.3);
+/// Search matches other than the current one
+const MATCH_COLOR: Color = Color::srgba(1.0, 1.0, 0.0, 0.3);
+/// The match the cursor jumped to
+const CURRENT_MATCH_COLOR: Color = Color::srgba(1.0, 0.5, 0.0, 0.5);
 
 /// Bevy plugin for copy mode functionality
 pub struct CopyModePlugin;
@@ -21,19 +42,19 @@ impl Plugin for CopyModePlugin {
             .init_resource::<TerminalDimensions>()
             .add_event::<CopyModeActionEvent>()
             .add_event::<CopyModeIndicatorEvent>()
-            .add_systems(Startup, spawn_copy_mode_cursor)
             .add_systems(
                 Update,
                 (
-                    handle_copy_mode_actions,
-                    update_cursor_visibility,
-                    update_cursor_position,
-                    render_selection_highlights,
+                    handle_copy_mode_actions.run_if(copy_mode_active),
+                    sync_terminal_dimensions,
+                    // Rendering also runs while inactive so leaving copy mode
+                    // clears whatever was drawn
                     render_search_highlights,
-                    update_mode_indicator,
+                    render_selection_highlights,
+                    render_copy_mode_cursor,
+                    update_mode_indicator.run_if(copy_mode_active),
                 )
-                    .chain()
-                    .run_if(copy_mode_active),
+                    .chain(),
             );
     }
 }
@@ -121,6 +142,87 @@ impl TerminalDimensions {
     pub fn max_y(&self) -> i32 {
         self.rows as i32 - 1
     }
+
+    /// Screen row showing logical line `y`, if it is in view
+    pub fn viewport_row(&self, y: i32, viewport_offset: i32) -> Option<u16> {
+        let row = y + viewport_offset;
+        (0..self.rows as i32).contains(&row).then_some(row as u16)
+    }
+
+    /// Top-left corner of a cell relative to the grid origin
+    pub fn cell_offset(&self, x: u16, row: u16) -> Vec2 {
+        Vec2::new(x as f32 * self.cell_width, -(row as f32 * self.cell_height))
+    }
+
+    /// Pair each span that is in view with the screen row it lands on
+    pub fn visible_spans(
+        &self,
+        spans: &[HighlightSpan],
+        viewport_offset: i32,
+    ) -> Vec<(u16, HighlightSpan)> {
+        spans
+            .iter()
+            .filter_map(|span| Some((self.viewport_row(span.y, viewport_offset)?, *span)))
+            .collect()
+    }
+}
+
+/// A run of highlighted cells on one logical line (columns inclusive)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HighlightSpan {
+    /// Logical line, negative for scrollback
+    pub y: i32,
+    /// First highlighted column
+    pub start_x: u16,
+    /// Last highlighted column
+    pub end_x: u16,
+}
+
+/// Spans covering a stream of cells from `start` to `end`, wrapping at the
+/// right edge the way a text selection does
+fn stream_spans(start: (u16, i32), end: (u16, i32), cols: u16) -> Vec<HighlightSpan> {
+    let last_col = cols.saturating_sub(1);
+    (start.1..=end.1)
+        .map(|y| HighlightSpan {
+            y,
+            start_x: if y == start.1 { start.0 } else { 0 },
+            end_x: if y == end.1 { end.0 } else { last_col },
+        })
+        .collect()
+}
+
+/// Spans covered by the current selection
+///
+/// Cell and word selections flow from the earlier end to the later one,
+/// line selections cover whole lines, and block selections are a rectangle
+/// between the two corners.
+pub fn selection_spans(state: &CopyModeState, cols: u16) -> Vec<HighlightSpan> {
+    let Some(selection) = state.selection.as_ref() else {
+        return Vec::new();
+    };
+    let (min_x, min_y, max_x, max_y) = get_selection_bounds(selection);
+
+    match state.selection_mode {
+        SelectionMode::None => Vec::new(),
+        SelectionMode::Cell | SelectionMode::Word => {
+            let (start, end) = normalize_selection(selection);
+            stream_spans((start.x, start.y), (end.x, end.y), cols)
+        }
+        SelectionMode::Line => stream_spans((0, min_y), (cols.saturating_sub(1), max_y), cols),
+        SelectionMode::Block => (min_y..=max_y)
+            .map(|y| HighlightSpan {
+                y,
+                start_x: min_x,
+                end_x: max_x,
+            })
+            .collect(),
+    }
+}
+
+/// Spans covered by a search match
+pub fn match_spans(search_match: &SearchMatch, cols: u16) -> Vec<HighlightSpan> {
+    let (start, end) = (search_match.start, search_match.end);
+    stream_spans((start.x, start.y), (end.x, end.y), cols)
 }
 
 /// Marker component for the copy mode cursor entity
@@ -169,212 +271,173 @@ pub fn copy_mode_active(state: Res<CopyModeStateResource>) -> bool {
     state.is_active()
 }
 
-/// System that spawns the copy mode cursor entity at startup
-pub fn spawn_copy_mode_cursor(mut commands: Commands) {
-    commands.spawn((
-        CopyModeCursorMarker,
-        Sprite {
-            color: Color::srgba(0.0, 1.0, 0.0, 0.5), // Semi-transparent green
-            custom_size: Some(Vec2::new(10.0, 20.0)),
-            ..default()
-        },
-        Transform::from_translation(Vec3::new(0.0, 0.0, 10.0)),
-        Visibility::Hidden, // Hidden by default until copy mode activates
-    ));
+/// System that keeps the coordinate mapping in step with the grid metrics
+pub fn sync_terminal_dimensions(
+    metrics: Option<Res<TerminalMetrics>>,
+    mut terminal_dims: ResMut<TerminalDimensions>,
+) {
+    let Some(metrics) = metrics else {
+        return;
+    };
+    if !metrics.is_changed() {
+        return;
+    }
+
+    terminal_dims.cols = metrics.columns;
+    terminal_dims.rows = metrics.rows;
+    terminal_dims.cell_width = metrics.cell_width;
+    terminal_dims.cell_height = metrics.cell_height;
 }
 
-/// System that updates cursor visibility based on copy mode state
-pub fn update_cursor_visibility(
-    copy_mode_state: Res<CopyModeStateResource>,
-    mut cursor_query: Query<&mut Visibility, With<CopyModeCursorMarker>>,
+/// Spawn one translucent sprite per visible span as children of the grid
+fn spawn_spans<C: Component>(
+    commands: &mut Commands,
+    grids: &Query<Entity, With<TerminalGridEntity>>,
+    terminal_dims: &TerminalDimensions,
+    spans: &[(u16, HighlightSpan)],
+    color: Color,
+    z: f32,
+    marker: impl Fn(&HighlightSpan) -> C,
 ) {
-    if let Ok(mut visibility) = cursor_query.get_single_mut() {
-        *visibility = if copy_mode_state.is_active() {
-            Visibility::Visible
-        } else {
-            Visibility::Hidden
-        };
+    for grid in grids.iter() {
+        commands.entity(grid).with_children(|parent| {
+            for (row, span) in spans {
+                let width = (span.end_x - span.start_x + 1) as f32 * terminal_dims.cell_width;
+                let offset = terminal_dims.cell_offset(span.start_x, *row);
+
+                parent.spawn((
+                    marker(span),
+                    Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(width, terminal_dims.cell_height)),
+                        anchor: Anchor::TopLeft,
+                        ..default()
+                    },
+                    Transform::from_xyz(offset.x, offset.y, z),
+                ));
+            }
+        });
     }
 }
 
-/// System that updates the visual cursor position
-pub fn update_cursor_position(
+/// System that draws the copy mode cursor over the grid
+pub fn render_copy_mode_cursor(
+    mut commands: Commands,
     copy_mode_state: Res<CopyModeStateResource>,
     terminal_dims: Res<TerminalDimensions>,
-    mut cursor_query: Query<(&mut Transform, &mut Sprite), With<CopyModeCursorMarker>>,
+    grids: Query<Entity, With<TerminalGridEntity>>,
+    cursors: Query<Entity, With<CopyModeCursorMarker>>,
 ) {
-    if let Ok((mut transform, mut sprite)) = cursor_query.get_single_mut() {
-        let cursor = copy_mode_state.state.cursor;
-
-        // Convert logical coordinates to screen space
-        transform.translation = terminal_dims.logical_to_screen(cursor.x, cursor.y);
+    if !copy_mode_state.is_changed() && !terminal_dims.is_changed() {
+        return;
+    }
 
-        // Update sprite size based on terminal dimensions
-        if let Some(ref mut size) = sprite.custom_size {
-            size.x = terminal_dims.cell_width;
-            size.y = terminal_dims.cell_height;
-        }
+    for entity in cursors.iter() {
+        commands.entity(entity).despawn_recursive();
     }
+    if !copy_mode_state.is_active() {
+        return;
+    }
+
+    let state = &copy_mode_state.state;
+    let cursor = HighlightSpan {
+        y: state.cursor.y,
+        start_x: state.cursor.x,
+        end_x: state.cursor.x,
+    };
+    spawn_spans(
+        &mut commands,
+        &grids,
+        &terminal_dims,
+        &terminal_dims.visible_spans(&[cursor], state.viewport_offset),
+        COPY_CURSOR_COLOR,
+        LAYER_CURSOR + 0.01,
+        |_| CopyModeCursorMarker,
+    );
 }
 
 /// System that renders selection highlights
 pub fn render_selection_highlights(
+    mut commands: Commands,
     copy_mode_state: Res<CopyModeStateResource>,
     terminal_dims: Res<TerminalDimensions>,
-    mut commands: Commands,
+    grids: Query<Entity, With<TerminalGridEntity>>,
     existing_highlights: Query<Entity, With<SelectionHighlight>>,
 ) {
-    // Despawn all existing highlights
-    for entity in existing_highlights.iter() {
-        commands.entity(entity).despawn();
-    }
-
-    // Spawn new highlights if there's an active selection
-    if let Some(ref selection) = copy_mode_state.state.selection {
-        let (min_x, min_y, max_x, max_y) = get_selection_bounds(selection);
-
-        match copy_mode_state.state.selection_mode {
-            SelectionMode::None => {}
-            SelectionMode::Cell => {
-                // Character-by-character selection
-                if min_y == max_y {
-                    // Single line
-                    let width = (max_x - min_x + 1) as f32 * terminal_dims.cell_width;
-                    let pos = terminal_dims.logical_to_screen(min_x, min_y);
-
-                    commands.spawn((
-                        SelectionHighlight { y: min_y },
-                        Sprite {
-                            color: Color::srgba(0.0, 0.5, 1.0, 0.3), // Semi-transparent blue
-                            custom_size: Some(Vec2::new(width, terminal_dims.cell_height)),
-                            ..default()
-                        },
-                        Transform::from_translation(pos + Vec3::new(width / 2.0, 0.0, 9.0)),
-                    ));
-                } else {
-                    // Multi-line selection
-                    for y in min_y..=max_y {
-                        let (start_x, end_x) = if y == min_y {
-                            (min_x, terminal_dims.cols - 1)
-                        } else if y == max_y {
-                            (0, max_x)
-                        } else {
-                            (0, terminal_dims.cols - 1)
-                        };
-
-                        let width = (end_x - start_x + 1) as f32 * terminal_dims.cell_width;
-                        let pos = terminal_dims.logical_to_screen(start_x, y);
-
-                        commands.spawn((
-                            SelectionHighlight { y },
-                            Sprite {
-                                color: Color::srgba(0.0, 0.5, 1.0, 0.3),
-                                custom_size: Some(Vec2::new(width, terminal_dims.cell_height)),
-                                ..default()
-                            },
-                            Transform::from_translation(pos + Vec3::new(width / 2.0, 0.0, 9.0)),
-                        ));
-                    }
-                }
-            }
-            SelectionMode::Line => {
-                // Whole line selection
-                for y in min_y..=max_y {
-                    let width = terminal_dims.cols as f32 * terminal_dims.cell_width;
-                    let pos = terminal_dims.logical_to_screen(0, y);
-
-                    commands.spawn((
-                        SelectionHighlight { y },
-                        Sprite {
-                            color: Color::srgba(0.0, 0.5, 1.0, 0.3),
-                            custom_size: Some(Vec2::new(width, terminal_dims.cell_height)),
-                            ..default()
-                        },
-                        Transform::from_translation(pos + Vec3::new(width / 2.0, 0.0, 9.0)),
-                    ));
-                }
-            }
-            SelectionMode::Block => {
-                // Rectangular block selection
-                let width = (max_x - min_x + 1) as f32 * terminal_dims.cell_width;
-                for y in min_y..=max_y {
-                    let pos = terminal_dims.logical_to_screen(min_x, y);
-
-                    commands.spawn((
-                        SelectionHighlight { y },
-                        Sprite {
-                            color: Color::srgba(0.0, 0.5, 1.0, 0.3),
-                            custom_size: Some(Vec2::new(width, terminal_dims.cell_height)),
-                            ..default()
-                        },
-                        Transform::from_translation(pos + Vec3::new(width / 2.0, 0.0, 9.0)),
-                    ));
-                }
-            }
-            SelectionMode::Word => {
-                // Word selection (similar to cell for now)
-                let width = (max_x - min_x + 1) as f32 * terminal_dims.cell_width;
-                let pos = terminal_dims.logical_to_screen(min_x, min_y);
+    if !copy_mode_state.is_changed() && !terminal_dims.is_changed() {
+        return;
+    }
 
-                commands.spawn((
-                    SelectionHighlight { y: min_y },
-                    Sprite {
-                        color: Color::srgba(0.0, 0.5, 1.0, 0.3),
-                        custom_size: Some(Vec2::new(width, terminal_dims.cell_height)),
-                        ..default()
-                    },
-                    Transform::from_translation(pos + Vec3::new(width / 2.0, 0.0, 9.0)),
-                ));
-            }
-        }
+    for entity in existing_highlights.iter() {
+        commands.entity(entity).despawn_recursive();
     }
+    if !copy_mode_state.is_active() {
+        return;
+    }
+
+    let state = &copy_mode_state.state;
+    spawn_spans(
+        &mut commands,
+        &grids,
+        &terminal_dims,
+        &terminal_dims.visible_spans(
+            &selection_spans(state, terminal_dims.cols),
+            state.viewport_offset,
+        ),
+        SELECTION_COLOR,
+        LAYER_TEXT_DECORATIONS + 0.01,
+        |span| SelectionHighlight { y: span.y },
+    );
 }
 
 /// System that renders search match highlights
+///
+/// Matches sit just under the selection so a selected match still reads as
+/// selected; the current match is drawn in a stronger color than the rest.
 pub fn render_search_highlights(
+    mut commands: Commands,
+    copy_mode_state: Res<CopyModeStateResource>,
     search_state: Res<CopyModeSearchResource>,
     terminal_dims: Res<TerminalDimensions>,
-    mut commands: Commands,
+    grids: Query<Entity, With<TerminalGridEntity>>,
     existing_highlights: Query<Entity, With<SearchHighlight>>,
 ) {
-    // Despawn all existing search highlights
-    for entity in existing_highlights.iter() {
-        commands.entity(entity).despawn();
+    if !search_state.is_changed() && !copy_mode_state.is_changed() && !terminal_dims.is_changed() {
+        return;
     }
 
-    // Render search matches if search is active and there are matches
-    if search_state.is_active() && !search_state.state.matches.is_empty() {
-        let current_match_idx = search_state.state.current_match;
-
-        for (idx, search_match) in search_state.state.matches.iter().enumerate() {
-            let is_current = Some(idx) == current_match_idx;
-            let start = search_match.start;
-            let end = search_match.end;
-
-            // Calculate width and position
-            let width = (end.x - start.x + 1) as f32 * terminal_dims.cell_width;
-            let pos = terminal_dims.logical_to_screen(start.x, start.y);
+    for entity in existing_highlights.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !copy_mode_state.is_active() || !search_state.is_active() {
+        return;
+    }
 
-            // Different colors for current match vs other matches
-            let color = if is_current {
-                Color::srgba(1.0, 0.5, 0.0, 0.5) // Orange for current match
-            } else {
-                Color::srgba(1.0, 1.0, 0.0, 0.3) // Yellow for other matches
-            };
+    let viewport_offset = copy_mode_state.state.viewport_offset;
+    let current_match_idx = search_state.state.current_match;
+    for (idx, search_match) in search_state.state.matches.iter().enumerate() {
+        let is_current = Some(idx) == current_match_idx;
+        let color = if is_current {
+            CURRENT_MATCH_COLOR
+        } else {
+            MATCH_COLOR
+        };
 
-            commands.spawn((
-                SearchHighlight {
-                    y: start.y,
-                    is_current,
-                },
-                Sprite {
-                    color,
-                    custom_size: Some(Vec2::new(width, terminal_dims.cell_height)),
-                    ..default()
-                },
-                Transform::from_translation(pos + Vec3::new(width / 2.0, 0.0, 8.0)), // Z=8 to render under selection
-            ));
-        }
+        spawn_spans(
+            &mut commands,
+            &grids,
+            &terminal_dims,
+            &terminal_dims.visible_spans(
+                &match_spans(search_match, terminal_dims.cols),
+                viewport_offset,
+            ),
+            color,
+            LAYER_TEXT_DECORATIONS,
+            |span| SearchHighlight {
+                y: span.y,
+                is_current,
+            },
+        );
     }
 }
 
@@ -607,6 +670,99 @@ mod tests {
         assert_eq!(dims.max_y(), 23);
     }
 
+    #[test]
+    fn test_terminal_dimensions_viewport_row() {
+        let dims = TerminalDimensions::default();
+
+        assert_eq!(dims.viewport_row(0, 0), Some(0));
+        assert_eq!(dims.viewport_row(23, 0), Some(23));
+        assert_eq!(dims.viewport_row(24, 0), None);
+        assert_eq!(dims.viewport_row(-1, 0), None);
+        // Scrolled up five lines, so history line -5 is at the top
+        assert_eq!(dims.viewport_row(-5, 5), Some(0));
+        assert_eq!(dims.cell_offset(3, 2), Vec2::new(30.0, -40.0));
+    }
+
+    #[test]
+    fn test_selection_spans() {
+        let mut state = CopyModeState::new();
+        state.activate(CopyModeCursor::new(6, 1));
+        state.toggle_cell_selection();
+        state.cursor = CopyModeCursor::new(2, 3);
+        state.update_selection();
+
+        // A cell selection flows from the anchor to the end of each line
+        assert_eq!(
+            selection_spans(&state, 10),
+            vec![
+                HighlightSpan {
+                    y: 1,
+                    start_x: 6,
+                    end_x: 9
+                },
+                HighlightSpan {
+                    y: 2,
+                    start_x: 0,
+                    end_x: 9
+                },
+                HighlightSpan {
+                    y: 3,
+                    start_x: 0,
+                    end_x: 2
+                },
+            ]
+        );
+
+        // The same corners as a block cover only the rectangle between them
+        state.selection_mode = SelectionMode::Block;
+        assert_eq!(
+            selection_spans(&state, 10),
+            vec![
+                HighlightSpan {
+                    y: 1,
+                    start_x: 2,
+                    end_x: 6
+                },
+                HighlightSpan {
+                    y: 2,
+                    start_x: 2,
+                    end_x: 6
+                },
+                HighlightSpan {
+                    y: 3,
+                    start_x: 2,
+                    end_x: 6
+                },
+            ]
+        );
+
+        state.selection_mode = SelectionMode::Line;
+        assert!(selection_spans(&state, 10)
+            .iter()
+            .all(|span| span.start_x == 0 && span.end_x == 9));
+
+        state.clear_selection();
+        assert!(selection_spans(&state, 10).is_empty());
+    }
+
+    #[test]
+    fn test_visible_match_spans() {
+        let dims = TerminalDimensions::default();
+        let search_match = SearchMatch::new(CopyModeCursor::new(4, -1), CopyModeCursor::new(8, -1));
+        let spans = match_spans(&search_match, dims.cols);
+
+        assert_eq!(
+            spans,
+            vec![HighlightSpan {
+                y: -1,
+                start_x: 4,
+                end_x: 8
+            }]
+        );
+        assert!(dims.visible_spans(&spans, 0).is_empty());
+        assert_eq!(dims.visible_spans(&spans, 1), vec![(0, spans[0])]);
+    }
+
     #[test]
     fn test_copy_mode_action_event() {
         let event = CopyModeActionEvent {
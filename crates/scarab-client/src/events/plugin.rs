@@ -5,7 +5,7 @@
 
 use super::bevy_events::*;
 use bevy::prelude::*;
-use bevy::window::WindowFocused;
+use bevy::window::{PrimaryWindow, WindowFocused};
 use scarab_plugin_api::object_model::{ObjectHandle, ObjectType};
 use scarab_protocol::{DaemonMessage, EventMessage};
 use std::sync::{Arc, Mutex};
@@ -110,8 +110,13 @@ fn handle_bevy_window_resize(
     mut bevy_resize_events: EventReader<bevy::window::WindowResized>,
     mut resize_events: EventWriter<WindowResizedEvent>,
     metrics: Option<Res<scarab_protocol::TerminalMetrics>>,
+    primary: Query<(), With<PrimaryWindow>>,
 ) {
-    for event in bevy_resize_events.read() {
+    // Secondary windows size their own sessions
+    for event in bevy_resize_events
+        .read()
+        .filter(|e| primary.contains(e.window))
+    {
         // Get cell dimensions from TerminalMetrics resource (set by rendering system)
         let (cell_width, cell_height) = if let Some(ref metrics) = metrics {
             (metrics.cell_width, metrics.cell_height)
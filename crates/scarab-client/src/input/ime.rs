@@ -0,0 +1,323 @@
+//! IME composition for CJK input
+//!
+//! Enables the platform input method on the primary window and routes its
+//! events into the input pipeline:
+//! - Preedit text is drawn at the terminal cursor through the glyph atlas, so
+//!   it uses the same fonts and fallbacks as the grid
+//! - Committed text is sent to the daemon as UTF-8 input
+//! - The candidate window is anchored under the cursor cell using
+//!   `TerminalMetrics`
+//!
+//! While a composition is in progress, key presses belong to the IME and are
+//! not forwarded to the PTY.
+
+use bevy::input::keyboard::Ime;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use scarab_protocol::{Cell, ControlMessage, TerminalMetrics, TerminalStateReader};
+
+use crate::integration::{SharedMemoryReader, TerminalGridEntity};
+use crate::ipc::IpcChannel;
+use crate::rendering::layers::LAYER_CURSOR;
+use crate::rendering::text::{DirtyRegion, MeshCache, TextRenderer};
+use crate::safe_state::GridView;
+use crate::terminal::scrollback::ScrollbackState;
+use crate::ui::TerminalInsets;
+use crate::InputSystemSet;
+
+/// Preedit foreground (slime green) and a background just lighter than the
+/// theme so the composition stands out from the grid beneath it
+const PREEDIT_FG: u32 = 0xFFA8DF5A;
+const PREEDIT_BG: u32 = 0xFF1F2A14;
+
+/// Underline flag, matching `TextAttributes`
+const FLAG_UNDERLINE: u8 = 0x04;
+
+/// Current IME composition
+#[derive(Resource, Debug, Default)]
+pub struct ImeState {
+    /// Whether the platform IME is active
+    pub enabled: bool,
+    /// Text being composed, not yet committed
+    pub preedit: String,
+    /// Byte range of the IME cursor within `preedit`
+    pub cursor: Option<(usize, usize)>,
+    /// Set when an IME event arrived this frame, so the key that produced
+    /// it is not also sent to the PTY
+    pub handled_this_frame: bool,
+}
+
+impl ImeState {
+    /// True while text is being composed
+    pub fn is_composing(&self) -> bool {
+        !self.preedit.is_empty()
+    }
+
+    /// True if key presses this frame belong to the IME
+    pub fn captures_keys(&self) -> bool {
+        self.is_composing() || self.handled_this_frame
+    }
+
+    /// Apply an IME event, returning committed text to send
+    pub fn apply(&mut self, event: &Ime) -> Option<String> {
+        match event {
+            Ime::Preedit { value, cursor, .. } => {
+                self.preedit = value.clone();
+                self.cursor = *cursor;
+                self.handled_this_frame = true;
+                None
+            }
+            Ime::Commit { value, .. } => {
+                self.preedit.clear();
+                self.cursor = None;
+                self.handled_this_frame = true;
+                (!value.is_empty()).then(|| value.clone())
+            }
+            Ime::Enabled { .. } => {
+                self.enabled = true;
+                None
+            }
+            Ime::Disabled { .. } => {
+                self.enabled = false;
+                self.preedit.clear();
+                self.cursor = None;
+                None
+            }
+        }
+    }
+}
+
+/// Cells for the preedit text, underlined as IMEs conventionally show it
+pub fn preedit_cells(text: &str) -> Vec<Cell> {
+    text.chars()
+        .map(|ch| Cell {
+            char_codepoint: ch as u32,
+            fg: PREEDIT_FG,
+            bg: PREEDIT_BG,
+            flags: FLAG_UNDERLINE,
+            _padding: [0; 3],
+        })
+        .collect()
+}
+
+/// Candidate window position in window coordinates (top-left origin)
+///
+/// Places the window at the bottom-left of the cell under the IME cursor,
+/// `preedit_col` cells past the terminal cursor.
+pub fn candidate_position(
+    cursor: (u16, u16),
+    preedit_col: usize,
+    metrics: &TerminalMetrics,
+    insets: TerminalInsets,
+) -> Vec2 {
+    let col = cursor.0 as usize + preedit_col;
+    Vec2::new(
+        col as f32 * metrics.cell_width,
+        insets.top + (cursor.1 as f32 + 1.0) * metrics.cell_height,
+    )
+}
+
+/// Marker for the preedit mesh, parented to the terminal grid
+#[derive(Component)]
+pub struct ImePreedit;
+
+/// System to turn on IME for the primary window
+fn enable_ime(mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+    for mut window in windows.iter_mut() {
+        if !window.ime_enabled {
+            window.ime_enabled = true;
+        }
+    }
+}
+
+/// System to track composition and send committed text to the daemon
+fn handle_ime_events(
+    mut events: EventReader<Ime>,
+    mut ime: ResMut<ImeState>,
+    ipc: Option<Res<IpcChannel>>,
+    scrollback_state: Option<Res<ScrollbackState>>,
+) {
+    if ime.handled_this_frame {
+        ime.handled_this_frame = false;
+    }
+
+    // The search bar owns the keyboard while it is open
+    let search_active = scrollback_state.map_or(false, |s| s.search_visible);
+
+    for event in events.read() {
+        let Some(text) = ime.apply(event) else {
+            continue;
+        };
+        if search_active {
+            continue;
+        }
+        if let Some(ipc) = ipc.as_ref() {
+            ipc.send(ControlMessage::Input {
+                data: text.into_bytes(),
+            });
+        }
+    }
+}
+
+/// System to place the candidate window under the cursor
+fn update_ime_position(
+    ime: Res<ImeState>,
+    metrics: Option<Res<TerminalMetrics>>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    insets: Option<Res<TerminalInsets>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let (Some(metrics), Some(state_reader)) = (metrics, state_reader) else {
+        return;
+    };
+
+    let cursor = state_reader.get_safe_state().cursor_pos();
+    let preedit_col = ime
+        .cursor
+        .and_then(|(start, _)| ime.preedit.get(..start))
+        .map_or(0, |before| before.chars().count());
+    let insets = insets.map(|i| *i).unwrap_or_default();
+    let position = candidate_position(cursor, preedit_col, &metrics, insets);
+
+    for mut window in windows.iter_mut() {
+        if window.ime_position != position {
+            window.ime_position = position;
+        }
+    }
+}
+
+/// System to draw the preedit text over the cursor cell
+fn render_preedit(
+    mut commands: Commands,
+    ime: Res<ImeState>,
+    renderer: Option<ResMut<TextRenderer>>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    grids: Query<(Entity, &MeshMaterial2d<ColorMaterial>), With<TerminalGridEntity>>,
+    preedits: Query<Entity, With<ImePreedit>>,
+    mut last: Local<Option<(String, (u16, u16), Vec2)>>,
+) {
+    let (Some(mut renderer), Some(state_reader)) = (renderer, state_reader) else {
+        return;
+    };
+
+    let cursor = state_reader.get_safe_state().cursor_pos();
+    let cell_size = Vec2::new(renderer.cell_width, renderer.cell_height);
+    let key = (ime.preedit.clone(), cursor, cell_size);
+    if last.as_ref() == Some(&key) {
+        return;
+    }
+    *last = Some(key);
+
+    for entity in preedits.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !ime.is_composing() {
+        return;
+    }
+
+    let state = GridView::from_row(preedit_cells(&ime.preedit));
+
+    let mut cache = MeshCache::default();
+    cache.update(&state, &mut renderer, &DirtyRegion::new());
+    renderer.atlas.update_texture(&mut images);
+    let mesh = meshes.add(cache.build_mesh());
+
+    let offset = Vec3::new(
+        cursor.0 as f32 * cell_size.x,
+        -(cursor.1 as f32 * cell_size.y),
+        LAYER_CURSOR + 0.01,
+    );
+    for (grid, material) in grids.iter() {
+        commands.entity(grid).with_children(|parent| {
+            parent.spawn((
+                ImePreedit,
+                Mesh2d(mesh.clone()),
+                MeshMaterial2d(material.0.clone()),
+                Transform::from_translation(offset),
+            ));
+        });
+    }
+}
+
+/// Plugin for IME composition support
+pub struct ImePlugin;
+
+impl Plugin for ImePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ImeState>()
+            .add_systems(Startup, enable_ime)
+            .add_systems(Update, handle_ime_events.in_set(InputSystemSet::Surface))
+            .add_systems(Update, (update_ime_position, render_preedit));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_composition_lifecycle() {
+        let window = Entity::PLACEHOLDER;
+        let mut ime = ImeState::default();
+
+        ime.apply(&Ime::Enabled { window });
+        assert!(ime.enabled);
+
+        let commit = ime.apply(&Ime::Preedit {
+            window,
+            value: "にほ".into(),
+            cursor: Some((6, 6)),
+        });
+        assert_eq!(commit, None);
+        assert!(ime.is_composing());
+        assert!(ime.captures_keys());
+
+        let commit = ime.apply(&Ime::Commit {
+            window,
+            value: "日本".into(),
+        });
+        assert_eq!(commit.as_deref(), Some("日本"));
+        assert!(!ime.is_composing());
+        // The committing key press is still swallowed this frame
+        assert!(ime.captures_keys());
+
+        ime.handled_this_frame = false;
+        assert!(!ime.captures_keys());
+    }
+
+    #[test]
+    fn test_preedit_cells() {
+        let cells = preedit_cells("한국");
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[0].char_codepoint, '한' as u32);
+        assert_eq!(cells[1].flags & FLAG_UNDERLINE, FLAG_UNDERLINE);
+    }
+
+    #[test]
+    fn test_candidate_position() {
+        let metrics = TerminalMetrics {
+            cell_width: 10.0,
+            cell_height: 20.0,
+            columns: 80,
+            rows: 24,
+        };
+        let insets = TerminalInsets {
+            top: 30.0,
+            bottom: 0.0,
+            right: 0.0,
+        };
+
+        // Bottom-left of the cursor cell, below the tab bar
+        assert_eq!(
+            candidate_position((5, 2), 0, &metrics, insets),
+            Vec2::new(50.0, 90.0)
+        );
+        // Follows the IME cursor within the preedit
+        assert_eq!(
+            candidate_position((5, 2), 3, &metrics, insets),
+            Vec2::new(80.0, 90.0)
+        );
+    }
+}
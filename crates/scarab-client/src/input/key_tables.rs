@@ -2,24 +2,119 @@
 //!
 //! This module provides Bevy resources and systems for managing key table stacks
 //! and leader key state in the Scarab client.
+//!
+//! Every key press is resolved against the stack before it can reach the
+//! PTY: the top table wins, falling through to the default table built from
+//! `[keybindings]`. A resolved key, an armed leader, or any table on the
+//! stack swallows the key. Chorded bindings such as `"Ctrl+X Ctrl+S"` are
+//! one-shot tables pushed by their prefix keys.
+
+use std::time::Instant;
 
 use bevy::input::keyboard::KeyCode as BevyKeyCode;
 use bevy::prelude::*;
+use scarab_config::{ConfigSection, ConfigSectionsChanged, KeyBindings, ScarabConfig};
+use scarab_plugin_api::copy_mode::CopyModeCursor;
 use scarab_plugin_api::key_tables::{
-    KeyCode as ApiKeyCode, KeyCombo, KeyModifiers as ApiKeyModifiers, KeyTableStack, LeaderKeyState,
+    ActivateKeyTableMode, CopyModeAction, Direction, KeyAction, KeyCode as ApiKeyCode, KeyCombo,
+    KeyModifiers as ApiKeyModifiers, KeyTable, KeyTableActivation, KeyTableRegistry, KeyTableStack,
+    LeaderKeyState, SearchAction, SplitDirection, DIGIT_KEYS, LETTER_KEYS,
 };
+use scarab_protocol::terminal_state::TerminalStateReader;
+use scarab_protocol::{ControlMessage, PaneInfo};
+
+use crate::copy_mode::{CopyModeActionEvent, CopyModeStateResource};
+use crate::input::ImeState;
+use crate::integration::SharedMemoryReader;
+use crate::ipc::IpcChannel;
+use crate::ratatui_bridge::CommandSelected;
+use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackState};
+use crate::ui::{IdleLockState, PaneLayout};
+use crate::InputSystemSet;
+
+pub use scarab_plugin_api::key_tables::{combo_label, parse_key_combo, parse_key_sequence};
 
 /// Bevy resource wrapping KeyTableStack
-#[derive(Resource, Debug)]
+#[derive(Resource)]
 pub struct KeyTableStackResource {
     /// The underlying key table stack
     stack: KeyTableStack,
+    /// Tables that can be pushed by name
+    registry: KeyTableRegistry,
+    /// Whether a key was swallowed this frame
+    captured: bool,
+}
+
+impl std::fmt::Debug for KeyTableStackResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyTableStackResource")
+            .field("stack", &self.stack)
+            .field("captured", &self.captured)
+            .finish_non_exhaustive()
+    }
 }
 
 impl KeyTableStackResource {
     /// Create a new key table stack resource
     pub fn new(stack: KeyTableStack) -> Self {
-        Self { stack }
+        Self {
+            stack,
+            registry: KeyTableRegistry::new(),
+            captured: false,
+        }
+    }
+
+    /// Build the default table and registry from `[keybindings]`
+    pub fn from_config(config: &KeyBindings) -> Self {
+        let mut registry = KeyTableRegistry::new();
+        registry.register("nav", default_nav_table());
+
+        for (name, bindings) in &config.key_tables {
+            if !registry.contains(name) {
+                registry.register(name.clone(), KeyTable::new(name.clone()));
+            }
+            let table = registry.get_mut(name).expect("table registered above");
+            for (key, action) in bindings {
+                match parse_key_combo(key) {
+                    Some(combo) => table.bind(combo, parse_key_action(action)),
+                    None => warn!("Ignoring unknown key '{}' in key table '{}'", key, name),
+                }
+            }
+        }
+
+        let mut default_table = KeyTable::new("default");
+        match parse_key_combo(&config.copy_mode) {
+            Some(combo) => default_table.bind(combo, KeyAction::ActivateCopyMode),
+            None => warn!("Ignoring unknown copy_mode key '{}'", config.copy_mode),
+        }
+        // Leader+w and Leader+r open the navigation and resize tables
+        for (key, name) in [(ApiKeyCode::KeyW, "nav"), (ApiKeyCode::KeyR, "resize_pane")] {
+            default_table.bind(
+                KeyCombo::new(key, ApiKeyModifiers::LEADER),
+                KeyAction::ActivateKeyTable {
+                    name: name.to_string(),
+                    mode: ActivateKeyTableMode::Persistent,
+                    replace_current: false,
+                },
+            );
+        }
+        for (action, keys) in &config.custom {
+            match parse_key_sequence(keys) {
+                Some(sequence) => bind_sequence(
+                    &mut default_table,
+                    &mut registry,
+                    &sequence,
+                    parse_key_action(action),
+                ),
+                None => warn!("Ignoring unknown key '{}' for '{}'", keys, action),
+            }
+        }
+
+        Self {
+            stack: KeyTableStack::new(default_table),
+            registry,
+            captured: false,
+        }
     }
 
     /// Get a reference to the underlying stack
@@ -31,13 +126,84 @@ impl KeyTableStackResource {
     pub fn stack_mut(&mut self) -> &mut KeyTableStack {
         &mut self.stack
     }
+
+    /// Push the named table, returning false if no such table exists
+    pub fn activate(
+        &mut self,
+        name: &str,
+        mode: &ActivateKeyTableMode,
+        replace_current: bool,
+        now: Instant,
+    ) -> bool {
+        let Some(table) = self.registry.get(name).cloned() else {
+            warn!("No key table named '{}'", name);
+            return false;
+        };
+        let name = name.to_string();
+        let activation = match mode {
+            ActivateKeyTableMode::OneShot => KeyTableActivation::one_shot(name, table),
+            ActivateKeyTableMode::Timeout(duration) => {
+                KeyTableActivation::timed(name, table, *duration, now)
+            }
+            ActivateKeyTableMode::Persistent | ActivateKeyTableMode::UntilAction(_) => {
+                KeyTableActivation::persistent(name, table)
+            }
+        };
+
+        if replace_current {
+            self.stack.pop();
+        }
+        self.stack.push(activation.with_replace(replace_current));
+        true
+    }
+
+    /// Pop the top table if it is `name`
+    pub fn pop_table(&mut self, name: &str) {
+        if self.stack.current_name() == name {
+            self.stack.pop();
+        }
+    }
+
+    /// Resolve a key press against the stack
+    ///
+    /// Table management actions are applied here and still returned, so the
+    /// caller knows the key was bound.
+    pub fn handle_combo(&mut self, combo: KeyCombo, now: Instant) -> Option<KeyAction> {
+        let until_action = match self.stack.current().map(|a| &a.mode) {
+            Some(ActivateKeyTableMode::UntilAction(action)) => Some((**action).clone()),
+            _ => None,
+        };
+
+        let action = self.stack.handle_key(combo, now)?;
+        match &action {
+            KeyAction::ActivateKeyTable {
+                name,
+                mode,
+                replace_current,
+            } => {
+                self.activate(name, mode, *replace_current, now);
+            }
+            KeyAction::PopKeyTable => {
+                self.stack.pop();
+            }
+            KeyAction::ClearKeyTableStack => self.stack.clear(),
+            other if until_action.as_ref() == Some(other) => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+        Some(action)
+    }
+
+    /// Whether key presses should be kept from the terminal
+    pub fn captures_keys(&self) -> bool {
+        self.captured || !self.stack.is_empty()
+    }
 }
 
 impl Default for KeyTableStackResource {
     fn default() -> Self {
-        Self {
-            stack: KeyTableStack::default(),
-        }
+        Self::new(KeyTableStack::default())
     }
 }
 
@@ -46,12 +212,39 @@ impl Default for KeyTableStackResource {
 pub struct LeaderKeyResource {
     /// The underlying leader key state
     state: LeaderKeyState,
+    /// Whether a leader key is configured
+    enabled: bool,
 }
 
 impl LeaderKeyResource {
     /// Create a new leader key resource
     pub fn new(state: LeaderKeyState) -> Self {
-        Self { state }
+        Self {
+            state,
+            enabled: true,
+        }
+    }
+
+    /// Build the leader from `[keybindings]`; an empty key disables it
+    pub fn from_config(config: &KeyBindings) -> Self {
+        let key = config.leader_key.trim();
+        match parse_key_combo(key) {
+            Some(combo) => Self::new(LeaderKeyState::new(combo, config.leader_timeout_ms)),
+            None => {
+                if !key.is_empty() {
+                    warn!("Ignoring unknown leader key '{}'", key);
+                }
+                Self {
+                    enabled: false,
+                    ..Self::default()
+                }
+            }
+        }
+    }
+
+    /// Whether a leader key is configured
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
     }
 
     /// Get a reference to the underlying state
@@ -73,12 +266,10 @@ impl LeaderKeyResource {
 impl Default for LeaderKeyResource {
     fn default() -> Self {
         // Default: Ctrl+A with 1000ms timeout
-        Self {
-            state: LeaderKeyState::new(
-                KeyCombo::new(ApiKeyCode::KeyA, ApiKeyModifiers::CTRL),
-                1000,
-            ),
-        }
+        Self::new(LeaderKeyState::new(
+            KeyCombo::new(ApiKeyCode::KeyA, ApiKeyModifiers::CTRL),
+            1000,
+        ))
     }
 }
 
@@ -145,6 +336,7 @@ pub fn bevy_to_api_keycode(bevy_key: BevyKeyCode) -> Option<ApiKeyCode> {
         BevyKeyCode::Tab => Some(ApiKeyCode::Tab),
         BevyKeyCode::Backspace => Some(ApiKeyCode::Backspace),
         BevyKeyCode::Space => Some(ApiKeyCode::Space),
+        BevyKeyCode::Slash => Some(ApiKeyCode::Slash),
 
         // Arrow keys
         BevyKeyCode::ArrowLeft => Some(ApiKeyCode::Left),
@@ -205,6 +397,607 @@ pub fn check_leader_timeout_system(mut leader: ResMut<LeaderKeyResource>) {
     leader.state_mut().check_timeout();
 }
 
+/// Parse an action name from `[keybindings]`
+///
+/// Table, mode, pane, tab, scroll and text actions map onto `KeyAction`;
+/// any other name is treated as a command palette id.
+pub fn parse_key_action(s: &str) -> KeyAction {
+    let (name, arg) = match s.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (s, None),
+    };
+    let amount = || arg.and_then(|a| a.parse().ok()).unwrap_or(2);
+
+    match (name, arg) {
+        ("activate_key_table", Some(table)) => KeyAction::ActivateKeyTable {
+            name: table.to_string(),
+            mode: ActivateKeyTableMode::Persistent,
+            replace_current: false,
+        },
+        ("activate_key_table_once", Some(table)) => KeyAction::ActivateKeyTable {
+            name: table.to_string(),
+            mode: ActivateKeyTableMode::OneShot,
+            replace_current: false,
+        },
+        ("pop_key_table", None) => KeyAction::PopKeyTable,
+        ("clear_key_table_stack", None) => KeyAction::ClearKeyTableStack,
+        ("noop", None) => KeyAction::Noop,
+
+        ("copy_mode" | "copy_mode_enter", None) => KeyAction::ActivateCopyMode,
+        ("search_mode" | "search_open", None) => KeyAction::ActivateSearchMode,
+        ("search_next", None) => KeyAction::Search(SearchAction::NextMatch),
+        ("search_previous", None) => KeyAction::Search(SearchAction::PrevMatch),
+        ("search_close", None) => KeyAction::Search(SearchAction::Cancel),
+        (name, None) if name.starts_with("copy_mode.") => {
+            match parse_copy_mode_action(&name["copy_mode.".len()..]) {
+                Some(action) => KeyAction::CopyMode(action),
+                None => command_action(s),
+            }
+        }
+
+        ("pane_left", None) => KeyAction::ActivatePaneDirection(Direction::Left),
+        ("pane_right", None) => KeyAction::ActivatePaneDirection(Direction::Right),
+        ("pane_up", None) => KeyAction::ActivatePaneDirection(Direction::Up),
+        ("pane_down", None) => KeyAction::ActivatePaneDirection(Direction::Down),
+        ("resize_pane_left", _) => KeyAction::AdjustPaneSize {
+            direction: Direction::Left,
+            amount: amount(),
+        },
+        ("resize_pane_right", _) => KeyAction::AdjustPaneSize {
+            direction: Direction::Right,
+            amount: amount(),
+        },
+        ("resize_pane_up", _) => KeyAction::AdjustPaneSize {
+            direction: Direction::Up,
+            amount: amount(),
+        },
+        ("resize_pane_down", _) => KeyAction::AdjustPaneSize {
+            direction: Direction::Down,
+            amount: amount(),
+        },
+        ("split_horizontal", None) => KeyAction::SplitPane {
+            direction: SplitDirection::Horizontal,
+        },
+        ("split_vertical", None) => KeyAction::SplitPane {
+            direction: SplitDirection::Vertical,
+        },
+        ("close_pane", None) => KeyAction::ClosePane,
+
+        ("next_tab", None) => KeyAction::ActivateTabRelative(1),
+        ("prev_tab", None) => KeyAction::ActivateTabRelative(-1),
+        ("new_tab", None) => KeyAction::SpawnTab,
+
+        ("scroll_page_up", None) => KeyAction::ScrollByPage(-1),
+        ("scroll_page_down", None) => KeyAction::ScrollByPage(1),
+        ("scroll_line_up", None) => KeyAction::ScrollByLine(-1),
+        ("scroll_line_down", None) => KeyAction::ScrollByLine(1),
+        ("scroll_to_top", None) => KeyAction::ScrollToTop,
+        ("scroll_to_bottom", None) => KeyAction::ScrollToBottom,
+
+        ("send_string", Some(text)) => KeyAction::SendString(text.to_string()),
+        ("run", Some(command)) => KeyAction::RunCommand(command.to_string()),
+
+        _ => command_action(s),
+    }
+}
+
+fn command_action(id: &str) -> KeyAction {
+    KeyAction::EmitEvent {
+        event: id.to_string(),
+        args: Vec::new(),
+    }
+}
+
+fn parse_copy_mode_action(name: &str) -> Option<CopyModeAction> {
+    Some(match name {
+        "move_left" => CopyModeAction::MoveLeft,
+        "move_right" => CopyModeAction::MoveRight,
+        "move_up" => CopyModeAction::MoveUp,
+        "move_down" => CopyModeAction::MoveDown,
+        "word_forward" => CopyModeAction::MoveWordForward,
+        "word_backward" => CopyModeAction::MoveWordBackward,
+        "line_start" => CopyModeAction::MoveToLineStart,
+        "line_end" => CopyModeAction::MoveToLineEnd,
+        "top" => CopyModeAction::MoveToTop,
+        "bottom" => CopyModeAction::MoveToBottom,
+        "toggle_selection" => CopyModeAction::ToggleSelection,
+        "toggle_line_selection" => CopyModeAction::ToggleLineSelection,
+        "toggle_block_selection" => CopyModeAction::ToggleBlockSelection,
+        "search_forward" => CopyModeAction::SearchForward,
+        "search_backward" => CopyModeAction::SearchBackward,
+        "next_match" => CopyModeAction::NextMatch,
+        "prev_match" => CopyModeAction::PrevMatch,
+        "copy_and_exit" => CopyModeAction::CopyAndExit,
+        "exit" => CopyModeAction::Exit,
+        _ => return None,
+    })
+}
+
+/// Bind `sequence` to `action`, routing chord prefixes through one-shot tables
+fn bind_sequence(
+    default_table: &mut KeyTable,
+    registry: &mut KeyTableRegistry,
+    sequence: &[KeyCombo],
+    action: KeyAction,
+) {
+    let Some((last, prefix)) = sequence.split_last() else {
+        return;
+    };
+
+    let mut table_name: Option<String> = None;
+    for (i, combo) in prefix.iter().enumerate() {
+        let chord = format!(
+            "chord:{}",
+            sequence[..=i]
+                .iter()
+                .map(combo_label)
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        let table = match &table_name {
+            Some(name) => registry.get_mut(name).expect("chord table registered"),
+            None => &mut *default_table,
+        };
+        table.bind(
+            combo.clone(),
+            KeyAction::ActivateKeyTable {
+                name: chord.clone(),
+                mode: ActivateKeyTableMode::OneShot,
+                replace_current: false,
+            },
+        );
+        if !registry.contains(&chord) {
+            registry.register(chord.clone(), KeyTable::new(chord.clone()));
+        }
+        table_name = Some(chord);
+    }
+
+    let table = match &table_name {
+        Some(name) => registry.get_mut(name).expect("chord table registered"),
+        None => default_table,
+    };
+    table.bind(last.clone(), action);
+}
+
+/// Pane and tab navigation: hjkl/arrows focus, HJKL resize, s/v split
+pub fn default_nav_table() -> KeyTable {
+    let mut table = KeyTable::new("nav");
+
+    for (keys, direction) in [
+        ([ApiKeyCode::KeyH, ApiKeyCode::Left], Direction::Left),
+        ([ApiKeyCode::KeyJ, ApiKeyCode::Down], Direction::Down),
+        ([ApiKeyCode::KeyK, ApiKeyCode::Up], Direction::Up),
+        ([ApiKeyCode::KeyL, ApiKeyCode::Right], Direction::Right),
+    ] {
+        for key in keys {
+            table.bind(
+                KeyCombo::key(key),
+                KeyAction::ActivatePaneDirection(direction),
+            );
+            table.bind(
+                KeyCombo::shift(key),
+                KeyAction::AdjustPaneSize {
+                    direction,
+                    amount: 2,
+                },
+            );
+        }
+    }
+
+    table.bind(
+        KeyCombo::key(ApiKeyCode::KeyS),
+        KeyAction::SplitPane {
+            direction: SplitDirection::Horizontal,
+        },
+    );
+    table.bind(
+        KeyCombo::key(ApiKeyCode::KeyV),
+        KeyAction::SplitPane {
+            direction: SplitDirection::Vertical,
+        },
+    );
+    table.bind(KeyCombo::key(ApiKeyCode::KeyX), KeyAction::ClosePane);
+    table.bind(
+        KeyCombo::key(ApiKeyCode::KeyN),
+        KeyAction::ActivateTabRelative(1),
+    );
+    table.bind(
+        KeyCombo::key(ApiKeyCode::KeyP),
+        KeyAction::ActivateTabRelative(-1),
+    );
+    table.bind(KeyCombo::key(ApiKeyCode::KeyT), KeyAction::SpawnTab);
+
+    table.bind(KeyCombo::key(ApiKeyCode::Escape), KeyAction::PopKeyTable);
+    table.bind(KeyCombo::key(ApiKeyCode::Enter), KeyAction::PopKeyTable);
+    table.bind(KeyCombo::key(ApiKeyCode::KeyQ), KeyAction::PopKeyTable);
+
+    table
+}
+
+/// Pane adjacent to the focused one in `direction`
+///
+/// Picks the closest pane whose edge touches the focused pane's edge on
+/// that side, preferring the one that overlaps it the most.
+pub fn pane_in_direction(panes: &[PaneInfo], direction: Direction) -> Option<u64> {
+    let focused = panes.iter().find(|p| p.is_focused)?;
+    let overlap = |a0: u16, a1: u16, b0: u16, b1: u16| a1.min(b1) as i32 - a0.max(b0) as i32;
+    let (fx0, fy0) = (focused.x, focused.y);
+    let (fx1, fy1) = (focused.x + focused.width, focused.y + focused.height);
+
+    panes
+        .iter()
+        .filter(|p| p.id != focused.id)
+        .filter_map(|p| {
+            let (px0, py0) = (p.x, p.y);
+            let (px1, py1) = (p.x + p.width, p.y + p.height);
+            let (distance, shared) = match direction {
+                Direction::Left if px1 <= fx0 => (fx0 - px1, overlap(py0, py1, fy0, fy1)),
+                Direction::Right if px0 >= fx1 => (px0 - fx1, overlap(py0, py1, fy0, fy1)),
+                Direction::Up if py1 <= fy0 => (fy0 - py1, overlap(px0, px1, fx0, fx1)),
+                Direction::Down if py0 >= fy1 => (py0 - fy1, overlap(px0, px1, fx0, fx1)),
+                _ => return None,
+            };
+            (shared > 0).then_some((distance, -shared, p.id))
+        })
+        .min()
+        .map(|(_, _, id)| id)
+}
+
+/// Keys that type text, which the search bar keeps while it is open
+fn is_text_combo(combo: &KeyCombo) -> bool {
+    let chorded = combo
+        .mods
+        .intersects(ApiKeyModifiers::CTRL | ApiKeyModifiers::ALT | ApiKeyModifiers::SUPER);
+    !chorded
+        && (LETTER_KEYS.contains(&combo.key)
+            || DIGIT_KEYS.contains(&combo.key)
+            || matches!(combo.key, ApiKeyCode::Space | ApiKeyCode::Slash))
+}
+
+/// A bound action for the dispatcher
+#[derive(Event, Debug, Clone)]
+pub struct KeyActionEvent {
+    pub action: KeyAction,
+}
+
+/// System to rebuild the tables and leader when the keybindings change
+///
+/// Other config changes, like a theme switch, leave the tables and any
+/// pending leader alone.
+fn sync_key_tables_with_config(
+    config: Option<Res<ScarabConfig>>,
+    mut changes: EventReader<ConfigSectionsChanged>,
+    mut stack: ResMut<KeyTableStackResource>,
+    mut leader: ResMut<LeaderKeyResource>,
+) {
+    let rebound = changes
+        .read()
+        .any(|event| event.contains(ConfigSection::KeyBindings));
+    let Some(config) = config.filter(|c| c.is_added() || rebound) else {
+        return;
+    };
+    *stack = KeyTableStackResource::from_config(&config.keybindings);
+    *leader = LeaderKeyResource::from_config(&config.keybindings);
+}
+
+/// System to resolve key presses against the leader and key table stack
+fn handle_key_tables_system(
+    keyboard: Res<ButtonInput<BevyKeyCode>>,
+    mut stack: ResMut<KeyTableStackResource>,
+    mut leader: ResMut<LeaderKeyResource>,
+    scrollback_state: Option<Res<ScrollbackState>>,
+    ime_state: Option<Res<ImeState>>,
+    idle_lock: Option<Res<IdleLockState>>,
+    mut actions: EventWriter<KeyActionEvent>,
+) {
+    stack.captured = false;
+    if ime_state.map_or(false, |s| s.captures_keys()) {
+        return;
+    }
+    // Bindings could act on the session without unlocking the screen
+    if idle_lock.map_or(false, |s| s.captures_keys()) {
+        return;
+    }
+    let search_open = scrollback_state.map_or(false, |s| s.search_visible);
+    let now = Instant::now();
+
+    for key in keyboard.get_just_pressed() {
+        let Some(api_key) = bevy_to_api_keycode(*key) else {
+            continue;
+        };
+        if is_modifier(api_key) {
+            continue;
+        }
+        let mut combo = KeyCombo::new(api_key, build_modifiers(&keyboard, false));
+
+        if leader.is_enabled() && !leader.is_active() && leader.state_mut().feed_key(&combo) {
+            stack.captured = true;
+            continue;
+        }
+        let leader_armed = leader.is_active();
+        if leader_armed {
+            combo.mods |= ApiKeyModifiers::LEADER;
+            leader.state_mut().deactivate();
+        } else if search_open && is_text_combo(&combo) {
+            continue;
+        }
+
+        let modal = !stack.stack().is_empty();
+        let action = stack.handle_combo(combo, now);
+        // A leader sequence or modal table never leaks keys to the shell
+        stack.captured |= action.is_some() || leader_armed || modal;
+
+        match action {
+            Some(
+                KeyAction::ActivateKeyTable { .. }
+                | KeyAction::PopKeyTable
+                | KeyAction::ClearKeyTableStack
+                | KeyAction::Noop,
+            )
+            | None => {}
+            Some(action) => {
+                actions.send(KeyActionEvent { action });
+            }
+        }
+    }
+}
+
+fn is_modifier(key: ApiKeyCode) -> bool {
+    matches!(
+        key,
+        ApiKeyCode::ControlLeft
+            | ApiKeyCode::ControlRight
+            | ApiKeyCode::AltLeft
+            | ApiKeyCode::AltRight
+            | ApiKeyCode::ShiftLeft
+            | ApiKeyCode::ShiftRight
+            | ApiKeyCode::SuperLeft
+            | ApiKeyCode::SuperRight
+    )
+}
+
+/// System to keep the `search_mode` table on the stack while search is open
+fn sync_search_mode_table(
+    scrollback_state: Option<Res<ScrollbackState>>,
+    mut stack: ResMut<KeyTableStackResource>,
+    mut was_open: Local<bool>,
+) {
+    let open = scrollback_state.map_or(false, |s| s.search_visible);
+    if open && !*was_open {
+        stack.activate(
+            "search_mode",
+            &ActivateKeyTableMode::Persistent,
+            false,
+            Instant::now(),
+        );
+    } else if !open && *was_open {
+        stack.pop_table("search_mode");
+    }
+    *was_open = open;
+}
+
+/// System to run palette commands named after an action, e.g. `split_vertical`
+fn route_palette_commands(
+    mut commands_selected: EventReader<CommandSelected>,
+    mut actions: EventWriter<KeyActionEvent>,
+) {
+    for event in commands_selected.read() {
+        // Unknown names are palette commands, which other systems handle
+        let action = parse_key_action(&event.command_id);
+        if !matches!(action, KeyAction::EmitEvent { .. }) {
+            actions.send(KeyActionEvent { action });
+        }
+    }
+}
+
+/// System to carry out bound actions
+#[allow(clippy::too_many_arguments)]
+fn dispatch_key_actions(
+    mut events: EventReader<KeyActionEvent>,
+    ipc: Option<Res<IpcChannel>>,
+    mut stack: ResMut<KeyTableStackResource>,
+    mut copy_mode: ResMut<CopyModeStateResource>,
+    mut copy_mode_actions: EventWriter<CopyModeActionEvent>,
+    mut scrollback: Option<ResMut<ScrollbackBuffer>>,
+    mut scrollback_state: Option<ResMut<ScrollbackState>>,
+    layout: Option<Res<PaneLayout>>,
+    reader: Option<Res<SharedMemoryReader>>,
+    mut commands_selected: EventWriter<CommandSelected>,
+) {
+    let send = |msg: ControlMessage| {
+        if let Some(ipc) = &ipc {
+            ipc.send(msg);
+        }
+    };
+    let focused = layout.as_ref().and_then(|l| l.focused());
+
+    for event in events.read() {
+        match &event.action {
+            KeyAction::ActivateCopyMode => {
+                let (x, y) = reader
+                    .as_ref()
+                    .map_or((0, 0), |r| r.get_safe_state().cursor_pos());
+                copy_mode.state.activate(CopyModeCursor::new(x, y as i32));
+                stack.activate(
+                    "copy_mode",
+                    &ActivateKeyTableMode::Persistent,
+                    false,
+                    Instant::now(),
+                );
+            }
+            KeyAction::CopyMode(action) => {
+                copy_mode_actions.send(CopyModeActionEvent { action: *action });
+                if matches!(action, CopyModeAction::Exit | CopyModeAction::CopyAndExit) {
+                    stack.pop_table("copy_mode");
+                }
+            }
+            KeyAction::ActivateSearchMode => {
+                if let Some(state) = scrollback_state.as_mut() {
+                    state.search_visible = true;
+                }
+            }
+            KeyAction::Search(action) => {
+                let Some(buffer) = scrollback.as_mut() else {
+                    continue;
+                };
+                match action {
+                    SearchAction::Confirm | SearchAction::NextMatch => buffer.next_search_result(),
+                    SearchAction::PrevMatch => buffer.prev_search_result(),
+                    SearchAction::Cancel => {
+                        buffer.clear_search();
+                        if let Some(state) = scrollback_state.as_mut() {
+                            state.search_visible = false;
+                            state.search_input.clear();
+                        }
+                    }
+                }
+            }
+
+            KeyAction::ActivatePaneDirection(direction) => {
+                let target = layout
+                    .as_ref()
+                    .and_then(|l| pane_in_direction(&l.panes, *direction));
+                if let Some(pane_id) = target {
+                    send(ControlMessage::PaneFocus { pane_id });
+                }
+            }
+            KeyAction::AdjustPaneSize { direction, amount } => {
+                if let Some(pane) = focused {
+                    let grow = |size: u16, delta: i32| (size as i32 + delta).max(1) as u16;
+                    let (width, height) = match direction {
+                        Direction::Left => (grow(pane.width, -amount), pane.height),
+                        Direction::Right => (grow(pane.width, *amount), pane.height),
+                        Direction::Up => (pane.width, grow(pane.height, -amount)),
+                        Direction::Down => (pane.width, grow(pane.height, *amount)),
+                    };
+                    send(ControlMessage::PaneResize {
+                        pane_id: pane.id,
+                        width,
+                        height,
+                    });
+                }
+            }
+            KeyAction::SplitPane { direction } => {
+                if let Some(pane) = focused {
+                    let direction = match direction {
+                        SplitDirection::Horizontal => scarab_protocol::SplitDirection::Horizontal,
+                        SplitDirection::Vertical => scarab_protocol::SplitDirection::Vertical,
+                    };
+                    send(ControlMessage::PaneSplit {
+                        pane_id: pane.id,
+                        direction,
+                        domain: None,
+                    });
+                }
+            }
+            KeyAction::ClosePane => {
+                if let Some(pane) = focused {
+                    send(ControlMessage::PaneClose { pane_id: pane.id });
+                }
+            }
+
+            KeyAction::ActivateTabRelative(delta) => {
+                for _ in 0..delta.unsigned_abs() {
+                    send(if *delta > 0 {
+                        ControlMessage::TabNext
+                    } else {
+                        ControlMessage::TabPrev
+                    });
+                }
+            }
+            KeyAction::SpawnTab => send(ControlMessage::TabCreate {
+                title: None,
+                domain: None,
+            }),
+
+            KeyAction::ScrollByPage(pages) => {
+                if let (Some(buffer), Some(state)) =
+                    (scrollback.as_mut(), scrollback_state.as_mut())
+                {
+                    let lines = pages.unsigned_abs() as usize * state.lines_per_page;
+                    scroll_lines(buffer, state, *pages, lines);
+                }
+            }
+            KeyAction::ScrollByLine(lines) => {
+                if let (Some(buffer), Some(state)) =
+                    (scrollback.as_mut(), scrollback_state.as_mut())
+                {
+                    scroll_lines(buffer, state, *lines, lines.unsigned_abs() as usize);
+                }
+            }
+            KeyAction::ScrollToTop | KeyAction::ScrollToBottom => {
+                if let (Some(buffer), Some(state)) =
+                    (scrollback.as_mut(), scrollback_state.as_mut())
+                {
+                    if event.action == KeyAction::ScrollToTop {
+                        buffer.scroll_to_top();
+                    } else {
+                        buffer.scroll_to_bottom();
+                    }
+                    state.is_scrolled = !buffer.is_at_bottom();
+                }
+            }
+
+            KeyAction::SendString(text) => send(ControlMessage::Input {
+                data: text.clone().into_bytes(),
+            }),
+            KeyAction::RunCommand(command) => send(ControlMessage::Input {
+                data: format!("{}\r", command).into_bytes(),
+            }),
+            KeyAction::EmitEvent { event, .. } => {
+                commands_selected.send(CommandSelected {
+                    command_id: event.clone(),
+                });
+            }
+
+            other => debug!("No handler for key action {:?}", other),
+        }
+    }
+}
+
+/// Scroll history; negative `direction` scrolls up into the past
+fn scroll_lines(
+    buffer: &mut ScrollbackBuffer,
+    state: &mut ScrollbackState,
+    direction: i32,
+    lines: usize,
+) {
+    if direction < 0 {
+        buffer.scroll_up(lines);
+    } else {
+        buffer.scroll_down(lines);
+    }
+    state.is_scrolled = !buffer.is_at_bottom();
+}
+
+/// Plugin driving keyboard input through the leader and key table stack
+pub struct KeyTablesPlugin;
+
+impl Plugin for KeyTablesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyTableStackResource>()
+            .init_resource::<LeaderKeyResource>()
+            .init_resource::<CopyModeStateResource>()
+            .add_event::<KeyActionEvent>()
+            .add_event::<CopyModeActionEvent>()
+            .add_event::<CommandSelected>()
+            .add_event::<ConfigSectionsChanged>()
+            .add_systems(
+                Update,
+                (
+                    sync_key_tables_with_config,
+                    check_leader_timeout_system,
+                    sync_search_mode_table,
+                    handle_key_tables_system,
+                    route_palette_commands,
+                    dispatch_key_actions,
+                )
+                    .chain()
+                    .in_set(InputSystemSet::Navigation),
+            );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,4 +1035,160 @@ mod tests {
         leader.state_mut().deactivate();
         assert!(!leader.is_active());
     }
+
+    #[test]
+    fn test_parse_key_combo() {
+        assert_eq!(
+            parse_key_combo("Ctrl+Shift+C"),
+            Some(KeyCombo::new(
+                ApiKeyCode::KeyC,
+                ApiKeyModifiers::CTRL | ApiKeyModifiers::SHIFT
+            ))
+        );
+        assert_eq!(
+            parse_key_combo("G"),
+            Some(KeyCombo::shift(ApiKeyCode::KeyG))
+        );
+        assert_eq!(
+            parse_key_combo("Leader+KeyW"),
+            Some(KeyCombo::new(ApiKeyCode::KeyW, ApiKeyModifiers::LEADER))
+        );
+        assert_eq!(
+            parse_key_combo("alt+F12"),
+            Some(KeyCombo::alt(ApiKeyCode::F12))
+        );
+        assert_eq!(parse_key_combo("Ctrl+Hyper"), None);
+        assert_eq!(parse_key_combo("Ctrl+A+B"), None);
+        assert_eq!(parse_key_combo(""), None);
+
+        assert_eq!(
+            parse_key_sequence("Ctrl+X  Ctrl+S"),
+            Some(vec![
+                KeyCombo::ctrl(ApiKeyCode::KeyX),
+                KeyCombo::ctrl(ApiKeyCode::KeyS)
+            ])
+        );
+        assert_eq!(parse_key_sequence("   "), None);
+    }
+
+    #[test]
+    fn test_parse_key_action() {
+        assert_eq!(
+            parse_key_action("copy_mode.move_left"),
+            KeyAction::CopyMode(CopyModeAction::MoveLeft)
+        );
+        assert_eq!(
+            parse_key_action("resize_pane_up:5"),
+            KeyAction::AdjustPaneSize {
+                direction: Direction::Up,
+                amount: 5
+            }
+        );
+        assert_eq!(
+            parse_key_action("send_string:ls -la"),
+            KeyAction::SendString("ls -la".into())
+        );
+        // Anything else is a palette command
+        assert_eq!(
+            parse_key_action("zoom_in"),
+            KeyAction::EmitEvent {
+                event: "zoom_in".into(),
+                args: Vec::new()
+            }
+        );
+    }
+
+    #[test]
+    fn test_stack_from_config() {
+        let mut config = KeyBindings::default();
+        config
+            .custom
+            .insert("screenshot".into(), "Ctrl+X Ctrl+S".into());
+        config
+            .key_tables
+            .entry("copy_mode".into())
+            .or_default()
+            .insert("x".into(), "copy_mode.exit".into());
+        let mut stack = KeyTableStackResource::from_config(&config);
+        let now = Instant::now();
+
+        // The configured copy mode key enters copy mode
+        assert_eq!(
+            stack.handle_combo(parse_key_combo("Ctrl+Shift+C").unwrap(), now),
+            Some(KeyAction::ActivateCopyMode)
+        );
+
+        // A chord prefix pushes a one-shot table that resolves the rest
+        assert!(stack
+            .handle_combo(KeyCombo::ctrl(ApiKeyCode::KeyX), now)
+            .is_some());
+        assert!(stack.captures_keys());
+        assert_eq!(
+            stack.handle_combo(KeyCombo::ctrl(ApiKeyCode::KeyS), now),
+            Some(KeyAction::EmitEvent {
+                event: "screenshot".into(),
+                args: Vec::new()
+            })
+        );
+        assert!(stack.stack().is_empty());
+        assert_eq!(
+            stack.handle_combo(KeyCombo::ctrl(ApiKeyCode::KeyS), now),
+            None
+        );
+
+        // Config entries extend the built-in tables
+        assert!(stack.activate("copy_mode", &ActivateKeyTableMode::Persistent, false, now));
+        assert_eq!(
+            stack.handle_combo(KeyCombo::key(ApiKeyCode::KeyX), now),
+            Some(KeyAction::CopyMode(CopyModeAction::Exit))
+        );
+        assert_eq!(
+            stack.handle_combo(KeyCombo::key(ApiKeyCode::KeyH), now),
+            Some(KeyAction::CopyMode(CopyModeAction::MoveLeft))
+        );
+        stack.pop_table("copy_mode");
+
+        // Leader+w opens the nav table until Escape pops it
+        let leader_w = KeyCombo::new(ApiKeyCode::KeyW, ApiKeyModifiers::LEADER);
+        assert!(stack.handle_combo(leader_w, now).is_some());
+        assert_eq!(stack.stack().current_name(), "nav");
+        stack.handle_combo(KeyCombo::key(ApiKeyCode::Escape), now);
+        assert!(stack.stack().is_empty());
+        assert!(!stack.activate("missing", &ActivateKeyTableMode::Persistent, false, now));
+    }
+
+    #[test]
+    fn test_leader_from_config() {
+        let mut config = KeyBindings::default();
+        config.leader_key = "Ctrl+B".into();
+        config.leader_timeout_ms = 500;
+        let leader = LeaderKeyResource::from_config(&config);
+        assert!(leader.is_enabled());
+        assert_eq!(leader.state().key, KeyCombo::ctrl(ApiKeyCode::KeyB));
+        assert_eq!(leader.state().timeout().as_millis(), 500);
+
+        config.leader_key = String::new();
+        assert!(!LeaderKeyResource::from_config(&config).is_enabled());
+    }
+
+    #[test]
+    fn test_pane_in_direction() {
+        let pane = |id, x, y, width, height, is_focused| PaneInfo {
+            id,
+            x,
+            y,
+            width,
+            height,
+            is_focused,
+        };
+        // Left half focused; right side split into top and bottom
+        let panes = [
+            pane(1, 0, 0, 40, 24, true),
+            pane(2, 40, 0, 40, 10, false),
+            pane(3, 40, 10, 40, 14, false),
+        ];
+        assert_eq!(pane_in_direction(&panes, Direction::Right), Some(3));
+        assert_eq!(pane_in_direction(&panes, Direction::Left), None);
+        assert_eq!(pane_in_direction(&panes, Direction::Up), None);
+    }
 }
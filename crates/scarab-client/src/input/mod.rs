@@ -1,7 +1,12 @@
 //! Input handling for Scarab client
 //!
-//! This module provides key table and modal input handling for the Bevy client.
+//! This module provides key table and modal input handling for the Bevy client,
+//! and reports mouse events to daemon plugins.
 
+pub mod ime;
 pub mod key_tables;
+pub mod mouse;
 
-pub use key_tables::{KeyTableStackResource, LeaderKeyResource};
+pub use ime::{ImePlugin, ImeState};
+pub use key_tables::{KeyActionEvent, KeyTableStackResource, KeyTablesPlugin, LeaderKeyResource};
+pub use mouse::{PluginMousePlugin, PluginMouseState};
@@ -0,0 +1,190 @@
+//! Mouse events for daemon plugins
+//!
+//! Presses, releases, drags and wheel scrolls over the terminal grid are
+//! decoded into grid cells, modifiers and click counts and sent to the
+//! daemon as `ControlMessage::MouseEvent`, where plugins receive them in
+//! `on_mouse`. This runs alongside the client's own mouse handling
+//! (selection, links, scrolling) rather than replacing it.
+
+use bevy::input::mouse::{MouseButton as BevyMouseButton, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use scarab_protocol::{ControlMessage, MouseButton, MouseEvent, MouseEventKind, TerminalMetrics};
+
+use crate::input::key_tables::build_modifiers;
+use crate::ipc::IpcChannel;
+use crate::ui::link_hover::hover_cell;
+use crate::ui::TerminalInsets;
+
+/// Longest gap between presses that still counts as a multi-click
+const MULTI_CLICK_SECS: f64 = 0.4;
+
+/// Buttons reported to plugins, with their protocol equivalents
+const BUTTONS: [(BevyMouseButton, MouseButton); 3] = [
+    (BevyMouseButton::Left, MouseButton::Left),
+    (BevyMouseButton::Middle, MouseButton::Middle),
+    (BevyMouseButton::Right, MouseButton::Right),
+];
+
+/// Click counting and drag tracking for plugin mouse events
+#[derive(Resource, Debug, Default)]
+pub struct PluginMouseState {
+    /// Button, cell and time of the last press
+    last_press: Option<(MouseButton, (u16, u16), f64)>,
+    click_count: u8,
+    /// Cell of the last press or drag report while a button is held
+    drag_cell: Option<(u16, u16)>,
+}
+
+impl PluginMouseState {
+    /// Record a press, returning its click count
+    ///
+    /// Quick presses of the same button on the same cell count up to a
+    /// triple click, then start over.
+    pub fn press(&mut self, button: MouseButton, cell: (u16, u16), now: f64) -> u8 {
+        let repeated = self.last_press.is_some_and(|(last_button, last_cell, at)| {
+            last_button == button && last_cell == cell && now - at <= MULTI_CLICK_SECS
+        });
+        self.click_count = if repeated && self.click_count < 3 {
+            self.click_count + 1
+        } else {
+            1
+        };
+        self.last_press = Some((button, cell, now));
+        self.drag_cell = Some(cell);
+        self.click_count
+    }
+
+    /// Check whether a held button moved to a new cell, recording it if so
+    pub fn drag_to(&mut self, cell: (u16, u16)) -> bool {
+        match self.drag_cell {
+            Some(last) if last != cell => {
+                self.drag_cell = Some(cell);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forget the held button's cell
+    pub fn release(&mut self) {
+        self.drag_cell = None;
+    }
+}
+
+/// System to send mouse events over the grid to the daemon
+#[allow(clippy::too_many_arguments)]
+fn send_mouse_events(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    metrics: Option<Res<TerminalMetrics>>,
+    insets: Option<Res<TerminalInsets>>,
+    buttons: Res<ButtonInput<BevyMouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    time: Res<Time>,
+    ipc: Res<IpcChannel>,
+    mut state: ResMut<PluginMouseState>,
+) {
+    let cell = match (windows.get_single(), metrics) {
+        (Ok(window), Some(metrics)) => window.cursor_position().and_then(|pos| {
+            hover_cell(
+                pos,
+                &metrics,
+                insets.as_deref().copied().unwrap_or_default(),
+            )
+        }),
+        _ => None,
+    };
+    let Some((col, row)) = cell else {
+        wheel.clear();
+        if buttons.get_just_released().next().is_some() {
+            state.release();
+        }
+        return;
+    };
+
+    let modifiers = build_modifiers(&keys, false).bits();
+    let send = |kind, button, click_count| {
+        ipc.send(ControlMessage::MouseEvent {
+            event: MouseEvent {
+                kind,
+                button,
+                col,
+                row,
+                modifiers,
+                click_count,
+            },
+        });
+    };
+
+    let now = time.elapsed_secs_f64();
+    for (bevy_button, button) in BUTTONS {
+        if buttons.just_pressed(bevy_button) {
+            let clicks = state.press(button, (col, row), now);
+            send(MouseEventKind::Press, button, clicks);
+        } else if buttons.just_released(bevy_button) {
+            state.release();
+            send(MouseEventKind::Release, button, 0);
+        } else if buttons.pressed(bevy_button) && state.drag_to((col, row)) {
+            send(MouseEventKind::Drag, button, 0);
+        }
+    }
+
+    for event in wheel.read() {
+        let button = if event.y > 0.0 {
+            MouseButton::WheelUp
+        } else if event.y < 0.0 {
+            MouseButton::WheelDown
+        } else {
+            continue;
+        };
+        send(MouseEventKind::Scroll, button, 0);
+    }
+}
+
+/// Plugin that reports mouse events to daemon plugins
+pub struct PluginMousePlugin;
+
+impl Plugin for PluginMousePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PluginMouseState>().add_systems(
+            Update,
+            send_mouse_events.run_if(resource_exists::<IpcChannel>),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_count() {
+        let mut state = PluginMouseState::default();
+
+        assert_eq!(state.press(MouseButton::Left, (3, 4), 0.0), 1);
+        assert_eq!(state.press(MouseButton::Left, (3, 4), 0.2), 2);
+        assert_eq!(state.press(MouseButton::Left, (3, 4), 0.4), 3);
+        // Counting starts over after a triple click
+        assert_eq!(state.press(MouseButton::Left, (3, 4), 0.5), 1);
+
+        // Too slow, another cell, or another button
+        assert_eq!(state.press(MouseButton::Left, (3, 4), 2.0), 1);
+        assert_eq!(state.press(MouseButton::Left, (5, 4), 2.1), 1);
+        assert_eq!(state.press(MouseButton::Right, (5, 4), 2.2), 1);
+    }
+
+    #[test]
+    fn test_drag_reports_each_cell_once() {
+        let mut state = PluginMouseState::default();
+        assert!(!state.drag_to((1, 1)));
+
+        state.press(MouseButton::Left, (1, 1), 0.0);
+        assert!(!state.drag_to((1, 1)));
+        assert!(state.drag_to((2, 1)));
+        assert!(!state.drag_to((2, 1)));
+
+        state.release();
+        assert!(!state.drag_to((3, 1)));
+    }
+}
@@ -2,14 +2,19 @@
 // This demonstrates the complete VTE → SharedState → Rendering pipeline
 
 use crate::events::WindowResizedEvent;
+use crate::rendering::background::PanePalettes;
 use crate::rendering::config::{color, FontConfig};
+use crate::rendering::flash_guard::{grid_luminance, FlashGuard};
 use crate::rendering::layers::LAYER_TERMINAL_BG;
-use crate::rendering::text::{generate_terminal_mesh, TerminalMesh, TextRenderer};
+use crate::rendering::smooth_scroll::SmoothScroll;
+use crate::rendering::text::{TerminalMesh, TextRenderer};
 use crate::safe_state::SafeSharedState;
-use crate::ui::BOTTOM_UI_HEIGHT;
+use crate::ui::{PaneLayout, TerminalInsets, BOTTOM_UI_HEIGHT};
 use bevy::prelude::*;
 use bevy::render::mesh::Mesh2d;
 use bevy::sprite::{MeshMaterial2d, Sprite};
+use bevy::window::RequestRedraw;
+use scarab_config::{ConfigSection, ConfigSectionsChanged};
 use scarab_protocol::{
     terminal_state::TerminalStateReader, TerminalMetrics, GRID_HEIGHT, GRID_WIDTH,
 };
@@ -55,10 +60,13 @@ struct ColorDumpOnce(bool);
 impl Plugin for IntegrationPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ColorDumpOnce::default())
+            .add_event::<ConfigSectionsChanged>()
             .add_systems(Startup, setup_terminal_rendering)
             .add_systems(
                 Update,
                 (
+                    apply_font_shaping_config_system,
+                    apply_minimum_contrast_system,
                     handle_terminal_resize_system,
                     sync_terminal_state_system,
                     update_terminal_rendering_system,
@@ -121,6 +129,7 @@ fn update_grid_position_system(
     mut query: Query<&mut Transform, With<TerminalGridEntity>>,
     window_query: Query<&Window, With<bevy::window::PrimaryWindow>>,
     renderer: Option<Res<TextRenderer>>, // Use Option to avoid panic if not ready
+    insets: Option<Res<TerminalInsets>>,
 ) {
     let Ok(window) = window_query.get_single() else {
         return;
@@ -138,9 +147,11 @@ fn update_grid_position_system(
         // 1. Move left edge to -width/2 (left side of window)
         // 2. Move top edge to +height/2 (top of window)
         // The grid rows are already calculated to fit above the status bar,
-        // so we don't need to offset Y for the status bar here.
+        // so we don't need to offset Y for the status bar here. A top tab bar
+        // pushes the grid down.
+        let top_inset = insets.as_ref().map_or(0.0, |i| i.top);
         let x = -window.width() * 0.5;
-        let y = window.height() * 0.5;
+        let y = window.height() * 0.5 - top_inset;
 
         // Only update if changed to avoid unnecessary dirty flags
         if transform.translation.x != x || transform.translation.y != y {
@@ -150,6 +161,93 @@ fn update_grid_position_system(
     }
 }
 
+/// Apply ligature, font feature, and fallback changes from a reloaded config
+fn apply_font_shaping_config_system(
+    scarab_config: Option<Res<scarab_config::ScarabConfig>>,
+    mut changes: EventReader<ConfigSectionsChanged>,
+    renderer: Option<ResMut<TextRenderer>>,
+    mut meshes: Query<&mut TerminalMesh>,
+) {
+    let font_changed = changes
+        .read()
+        .any(|event| event.contains(ConfigSection::Font));
+    let (Some(config), Some(mut renderer)) = (scarab_config, renderer) else {
+        return;
+    };
+    if !font_changed && !config.is_added() {
+        return;
+    }
+
+    let font = &config.font;
+    if renderer.config.ligatures == font.ligatures
+        && renderer.config.features == font.features
+        && renderer.config.family == font.family
+        && renderer.config.fallback == font.fallback
+    {
+        return;
+    }
+
+    renderer.config.ligatures = font.ligatures;
+    renderer.config.features = font.features.clone();
+    renderer.config.family = font.family.clone();
+    renderer.config.fallback = font.fallback.clone();
+    renderer.shaper.clear();
+
+    let families = renderer
+        .config
+        .all_families()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    renderer.fallback.set_families(families);
+
+    for mut mesh in meshes.iter_mut() {
+        mesh.dirty_region.mark_full_redraw();
+    }
+
+    info!(
+        "Font shaping updated: ligatures={}, feature sets={}, fallbacks={}",
+        font.ligatures,
+        font.features.len(),
+        font.fallback.len()
+    );
+}
+
+/// Apply `colors.minimum_contrast` and keep the renderer's idea of the
+/// default background in step with the focused pane's palette
+fn apply_minimum_contrast_system(
+    scarab_config: Option<Res<scarab_config::ScarabConfig>>,
+    palettes: Option<Res<PanePalettes>>,
+    layout: Option<Res<PaneLayout>>,
+    renderer: Option<ResMut<TextRenderer>>,
+    mut meshes: Query<&mut TerminalMesh>,
+) {
+    let Some(mut renderer) = renderer else {
+        return;
+    };
+    let minimum = scarab_config.map_or(1.0, |config| config.colors.minimum_contrast);
+    let focused = layout
+        .as_ref()
+        .and_then(|layout| layout.focused())
+        .map(|pane| pane.id);
+    let background = palettes.map_or(renderer.default_background, |palettes| {
+        palettes.background(focused)
+    });
+    if renderer.minimum_contrast == minimum && renderer.default_background == background {
+        return;
+    }
+
+    // Without adjustment the background doesn't change what is drawn
+    let redraw = minimum > 1.0 || renderer.minimum_contrast > 1.0;
+    renderer.minimum_contrast = minimum;
+    renderer.default_background = background;
+    if redraw {
+        for mut mesh in meshes.iter_mut() {
+            mesh.dirty_region.mark_full_redraw();
+        }
+    }
+}
+
 /// Setup the terminal rendering pipeline
 fn setup_terminal_rendering(
     mut commands: Commands,
@@ -158,9 +256,18 @@ fn setup_terminal_rendering(
     mut materials: ResMut<Assets<ColorMaterial>>,
     ipc: Option<Res<crate::ipc::IpcChannel>>,
     window_query: Query<&Window, With<bevy::window::PrimaryWindow>>,
+    scarab_config: Option<Res<scarab_config::ScarabConfig>>,
 ) {
     // Create text renderer
-    let font_config = FontConfig::default();
+    // Shaping and fallback settings come from the user config; size keeps the
+    // renderer default that the grid metrics are tuned for.
+    let mut font_config = FontConfig::default();
+    if let Some(config) = scarab_config.as_ref() {
+        font_config.family = config.font.family.clone();
+        font_config.fallback = config.font.fallback.clone();
+        font_config.ligatures = config.font.ligatures;
+        font_config.features = config.font.features.clone();
+    }
 
     // Extract values needed for metrics before moving font_config
     let _font_size = font_config.size;
@@ -361,15 +468,27 @@ fn update_background_size_system(
 }
 
 /// Update terminal rendering from shared state
+#[allow(clippy::too_many_arguments)]
 fn update_terminal_rendering_system(
     mut renderer: ResMut<TextRenderer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut images: ResMut<Assets<Image>>,
     mut query: Query<&mut TerminalMesh, With<TerminalGridEntity>>,
     state_reader: Res<SharedMemoryReader>,
+    smooth_scroll: Option<Res<SmoothScroll>>,
+    scarab_config: Option<Res<scarab_config::ScarabConfig>>,
+    time: Res<Time>,
+    mut flash_guard: Local<FlashGuard>,
+    mut redraw: EventWriter<RequestRedraw>,
 ) {
+    // The composed view owns the grid mesh while history or folds are shown
+    if smooth_scroll.is_some_and(|s| s.owns_grid()) {
+        return;
+    }
+
     // Use safe wrapper to access shared state
     let safe_state = state_reader.get_safe_state();
+    let reduce_flashing = scarab_config.is_some_and(|config| config.ui.reduce_flashing);
 
     for mut terminal_mesh in query.iter_mut() {
         // Check if state changed OR if this is the first render (last_sequence == 0 but we haven't rendered yet)
@@ -377,8 +496,22 @@ fn update_terminal_rendering_system(
         let is_first_render =
             terminal_mesh.last_sequence == 0 && terminal_mesh.dirty_region.is_full_redraw();
 
+        if current_seq != terminal_mesh.last_sequence && reduce_flashing {
+            let luminance = grid_luminance(&safe_state, renderer.default_background);
+            if !flash_guard.allow(luminance, time.elapsed_secs_f64()) {
+                // Leave the update pending and keep frames coming until the
+                // flash limit lets it through
+                redraw.send(RequestRedraw);
+                continue;
+            }
+        }
+
         if current_seq != terminal_mesh.last_sequence {
-            terminal_mesh.dirty_region.mark_full_redraw();
+            // Only the rows the daemon reported as damaged need rebuilding
+            let last_sequence = terminal_mesh.last_sequence;
+            terminal_mesh
+                .dirty_region
+                .apply_damage(&safe_state, last_sequence);
             terminal_mesh.last_sequence = current_seq;
         }
 
@@ -387,16 +520,15 @@ fn update_terminal_rendering_system(
             continue;
         }
 
-        // Generate new mesh from terminal state using safe wrapper
-        let new_mesh = generate_terminal_mesh(
-            &safe_state,
-            &mut renderer,
-            &terminal_mesh.dirty_region,
-            &mut images,
-        );
+        // Rebuild dirty rows from terminal state using safe wrapper
+        let terminal_mesh = &mut *terminal_mesh;
+        terminal_mesh
+            .cache
+            .update(&safe_state, &mut renderer, &terminal_mesh.dirty_region);
+        renderer.atlas.update_texture(&mut images);
 
         // Update mesh asset using insert (proper way for Bevy 0.15+)
-        meshes.insert(&terminal_mesh.mesh_handle, new_mesh);
+        meshes.insert(&terminal_mesh.mesh_handle, terminal_mesh.cache.build_mesh());
 
         // Clear dirty region
         terminal_mesh.dirty_region.clear();
@@ -1,14 +1,28 @@
+use crate::input::{ImeState, KeyTableStackResource};
 use crate::rendering::text::TextRenderer;
+use crate::rendering::zoom::is_zoom_key;
+use crate::scripting::ReplState;
+use crate::terminal::scrollback::ScrollbackState;
+use crate::ui::command_palette::CommandPaletteState;
+use crate::ui::idle_lock::IdleLockState;
 use crate::ui::link_hints::LinkHintsState;
 use crate::ui::plugin_menu::MenuState;
-use crate::ui::BOTTOM_UI_HEIGHT;
+use crate::ui::plugin_permissions::PermissionPromptState;
+use crate::ui::plugin_prompt::PluginPromptState;
+use crate::ui::session_picker::SessionPickerState;
+use crate::ui::theme_gallery::ThemeGalleryState;
+use crate::ui::{TerminalInsets, BOTTOM_UI_HEIGHT};
 use crate::InputSystemSet;
 use anyhow::{Context, Result};
 use bevy::prelude::*;
+use scarab_config::{settings, ConfigSection, ConfigSectionsChanged, ScarabConfig};
+use scarab_platform::Paths;
 use scarab_protocol::{
-    ControlMessage, DaemonMessage, MAX_MESSAGE_SIZE, MAX_RECONNECT_ATTEMPTS, RECONNECT_DELAY_MS,
-    SOCKET_PATH,
+    ConfigEntry, ControlMessage, DaemonMessage, MAX_MESSAGE_SIZE, MAX_RECONNECT_ATTEMPTS,
+    RECONNECT_DELAY_MS,
 };
+use scarab_themes::Theme;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
@@ -24,13 +38,67 @@ pub struct RemoteMessageEvent(pub DaemonMessage);
 #[derive(Resource)]
 pub struct StartupCommand(pub String);
 
+/// Where the daemon's socket and shared memory are, after `[paths]`
+///
+/// Insert it before [`IpcPlugin`]; without it the environment and defaults
+/// are used.
+#[derive(Resource, Debug, Clone)]
+pub struct DaemonPaths(pub Paths);
+
+impl Default for DaemonPaths {
+    fn default() -> Self {
+        Self(Paths::from_env())
+    }
+}
+
 /// Bevy resource for IPC communication
 #[derive(Resource)]
 pub struct IpcChannel {
     inner: Arc<RwLock<Option<IpcConnection>>>,
+    socket: PathBuf,
     // Receiver for messages from the read loop to the Bevy system
     rx: Arc<std::sync::Mutex<std::sync::mpsc::Receiver<DaemonMessage>>>,
     runtime: tokio::runtime::Runtime,
+    // Connection of the focused secondary window, which receives typed input
+    input_route: Arc<std::sync::Mutex<Option<IpcSink>>>,
+}
+
+/// An additional daemon connection running on the channel's runtime
+///
+/// Each extra client window talks to the daemon over its own connection, so
+/// the daemon can route that window's input and resizes to its session.
+#[derive(Clone)]
+pub struct IpcSink {
+    inner: Arc<RwLock<Option<IpcConnection>>>,
+    rx: Arc<std::sync::Mutex<std::sync::mpsc::Receiver<DaemonMessage>>>,
+    runtime: tokio::runtime::Handle,
+}
+
+impl IpcSink {
+    /// Send a control message over this connection
+    pub fn send(&self, msg: ControlMessage) {
+        let inner = self.inner.clone();
+        self.runtime.spawn(async move {
+            if let Err(e) = send_message(inner, msg).await {
+                log::warn!("Failed to send message: {}", e);
+            }
+        });
+    }
+
+    /// Whether the connection has been established
+    pub fn is_connected(&self) -> bool {
+        self.inner
+            .try_read()
+            .map_or(false, |conn| conn.as_ref().map_or(false, |c| c.connected))
+    }
+
+    /// Drain messages received on this connection
+    pub fn drain(&self) -> Vec<DaemonMessage> {
+        self.rx
+            .lock()
+            .map(|rx| rx.try_iter().collect())
+            .unwrap_or_default()
+    }
 }
 
 struct IpcConnection {
@@ -39,8 +107,8 @@ struct IpcConnection {
 }
 
 impl IpcChannel {
-    /// Create new IPC channel with automatic connection
-    pub fn new() -> Result<Self> {
+    /// Create new IPC channel with automatic connection to the daemon at `socket`
+    pub fn new(socket: PathBuf) -> Result<Self> {
         let runtime = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .worker_threads(2)
@@ -53,21 +121,65 @@ impl IpcChannel {
 
         // Spawn connection task with exponential backoff
         let inner_clone = inner.clone();
+        let socket_clone = socket.clone();
         runtime.spawn(async move {
-            if let Err(e) = establish_connection(inner_clone, tx).await {
+            if let Err(e) = establish_connection(socket_clone, inner_clone, tx).await {
                 log::error!("Failed to establish initial connection: {}", e);
             }
         });
 
         Ok(Self {
             inner,
+            socket,
             rx: Arc::new(std::sync::Mutex::new(rx)),
             runtime,
+            input_route: Arc::new(std::sync::Mutex::new(None)),
         })
     }
 
+    /// Open another connection to the daemon for a secondary window
+    pub fn open_connection(&self) -> IpcSink {
+        let inner = Arc::new(RwLock::new(None));
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let inner_clone = inner.clone();
+        let socket = self.socket.clone();
+        self.runtime.spawn(async move {
+            if let Err(e) = establish_connection(socket, inner_clone, tx).await {
+                log::error!("Failed to open window connection: {}", e);
+            }
+        });
+
+        IpcSink {
+            inner,
+            rx: Arc::new(std::sync::Mutex::new(rx)),
+            runtime: self.runtime.handle().clone(),
+        }
+    }
+
+    /// Send typed input over `sink` instead of the primary connection
+    ///
+    /// Used while a secondary window has focus; `None` restores the primary
+    /// window's session as the input target.
+    pub fn route_input_to(&self, sink: Option<IpcSink>) {
+        if let Ok(mut route) = self.input_route.lock() {
+            *route = sink;
+        }
+    }
+
     /// Send a control message to the daemon
     pub fn send(&self, msg: ControlMessage) {
+        if matches!(
+            msg,
+            ControlMessage::Input { .. } | ControlMessage::MouseClick { .. }
+        ) {
+            let route = self.input_route.lock().ok().and_then(|r| r.clone());
+            if let Some(sink) = route {
+                sink.send(msg);
+                return;
+            }
+        }
+
         let inner = self.inner.clone();
         self.runtime.spawn(async move {
             if let Err(e) = send_message(inner, msg).await {
@@ -95,6 +207,7 @@ impl IpcChannel {
 
 /// Establish connection with exponential backoff (implements automatic reconnection)
 async fn establish_connection(
+    socket: PathBuf,
     inner: Arc<RwLock<Option<IpcConnection>>>,
     tx: std::sync::mpsc::Sender<DaemonMessage>,
 ) -> Result<()> {
@@ -102,9 +215,9 @@ async fn establish_connection(
     let mut delay_ms = RECONNECT_DELAY_MS;
 
     loop {
-        match UnixStream::connect(SOCKET_PATH).await {
+        match UnixStream::connect(&socket).await {
             Ok(stream) => {
-                println!("Connected to daemon at {}", SOCKET_PATH);
+                println!("Connected to daemon at {}", socket.display());
                 let (stream_read, stream_write) = stream.into_split();
 
                 let mut conn = inner.write().await;
@@ -257,17 +370,60 @@ pub fn handle_keyboard_input(
     ipc: Res<IpcChannel>,
     link_hints_state: Option<Res<LinkHintsState>>,
     menu_state: Option<Res<MenuState>>,
+    scrollback_state: Option<Res<ScrollbackState>>,
+    ime_state: Option<Res<ImeState>>,
+    session_picker: Option<Res<SessionPickerState>>,
+    key_tables: Option<Res<KeyTableStackResource>>,
+    command_palette: Option<Res<CommandPaletteState>>,
+    plugin_prompt: Option<Res<PluginPromptState>>,
+    permission_prompt: Option<Res<PermissionPromptState>>,
+    repl: Option<Res<ReplState>>,
+    theme_gallery: Option<Res<ThemeGalleryState>>,
+    idle_lock: Option<Res<IdleLockState>>,
 ) {
     // Don't send input to terminal when hint mode is active
     let hints_active = link_hints_state.map_or(false, |s| s.active);
     let menu_hint_active = menu_state.map_or(false, |s| s.active && s.hint_mode);
-
-    if hints_active || menu_hint_active {
+    // The search bar owns the keyboard while it is open
+    let search_active = scrollback_state.map_or(false, |s| s.search_visible);
+    // Keys that drive an IME composition never reach the PTY
+    let ime_active = ime_state.map_or(false, |s| s.captures_keys());
+    // The startup session picker is modal
+    let picker_active = session_picker.map_or(false, |s| s.captures_keys());
+    // Bound keys and modal key tables never reach the PTY
+    let table_active = key_tables.map_or(false, |s| s.captures_keys());
+    // Typing goes to the command palette while it is open
+    let palette_active = command_palette.map_or(false, |s| s.active);
+    // Plugin prompts and forms are modal
+    let prompt_active = plugin_prompt.map_or(false, |s| s.captures_keys())
+        || permission_prompt.map_or(false, |s| s.captures_keys());
+    // So are the Fusabi REPL and the theme gallery
+    let repl_active = repl.map_or(false, |s| s.captures_keys())
+        || theme_gallery.map_or(false, |s| s.captures_keys());
+    // Nothing reaches the shell while the screen is locked
+    let locked = idle_lock.map_or(false, |s| s.captures_keys());
+
+    if hints_active
+        || menu_hint_active
+        || search_active
+        || ime_active
+        || picker_active
+        || table_active
+        || palette_active
+        || prompt_active
+        || repl_active
+        || locked
+    {
         return;
     }
 
+    let mods = modifier_param(&keys);
     for key in keys.get_just_pressed() {
-        let bytes = key_to_bytes(*key);
+        // Ctrl+0 resets the zoom
+        if is_zoom_key(*key) && mods != 1 {
+            continue;
+        }
+        let bytes = modified_key_to_bytes(*key, mods).or_else(|| key_to_bytes(*key));
         if let Some(bytes) = bytes {
             ipc.send(ControlMessage::Input { data: bytes });
         }
@@ -310,18 +466,149 @@ fn key_to_bytes(key: KeyCode) -> Option<Vec<u8>> {
     }
 }
 
+/// xterm modifier parameter for the held modifiers: 1 plus Shift 1, Alt 2,
+/// Ctrl 4
+fn modifier_param(keys: &ButtonInput<KeyCode>) -> u8 {
+    let mut param = 1;
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        param += 1;
+    }
+    if keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+        param += 2;
+    }
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        param += 4;
+    }
+    param
+}
+
+/// Terminal bytes for a key whose modifiers change what is sent
+///
+/// Arrows, Home and End take xterm's `CSI 1;<mods> X` form. Ctrl+digit,
+/// and Ctrl with Shift or Alt on a letter, digit or `/`, have no control
+/// byte that tells them apart from plain Ctrl+letter, so they go out in the
+/// kitty keyboard protocol's `CSI <code>;<mods> u` form. The daemon decodes
+/// those for plugin key bindings and drops the ones no plugin takes.
+fn modified_key_to_bytes(key: KeyCode, mods: u8) -> Option<Vec<u8>> {
+    if mods <= 1 {
+        return None;
+    }
+    let cursor = match key {
+        KeyCode::ArrowUp => Some('A'),
+        KeyCode::ArrowDown => Some('B'),
+        KeyCode::ArrowRight => Some('C'),
+        KeyCode::ArrowLeft => Some('D'),
+        KeyCode::Home => Some('H'),
+        KeyCode::End => Some('F'),
+        _ => None,
+    };
+    if let Some(final_byte) = cursor {
+        return Some(format!("\x1b[1;{}{}", mods, final_byte).into_bytes());
+    }
+
+    let bits = mods - 1;
+    let ctrl = bits & 4 != 0;
+    let shift_or_alt = bits & 3 != 0;
+    let c = key_char(key)?;
+    (ctrl && (shift_or_alt || c.is_ascii_digit()))
+        .then(|| format!("\x1b[{};{}u", c as u32, mods).into_bytes())
+}
+
+/// Unshifted character of a letter, digit or `/` key
+fn key_char(key: KeyCode) -> Option<char> {
+    Some(match key {
+        KeyCode::KeyA => 'a',
+        KeyCode::KeyB => 'b',
+        KeyCode::KeyC => 'c',
+        KeyCode::KeyD => 'd',
+        KeyCode::KeyE => 'e',
+        KeyCode::KeyF => 'f',
+        KeyCode::KeyG => 'g',
+        KeyCode::KeyH => 'h',
+        KeyCode::KeyI => 'i',
+        KeyCode::KeyJ => 'j',
+        KeyCode::KeyK => 'k',
+        KeyCode::KeyL => 'l',
+        KeyCode::KeyM => 'm',
+        KeyCode::KeyN => 'n',
+        KeyCode::KeyO => 'o',
+        KeyCode::KeyP => 'p',
+        KeyCode::KeyQ => 'q',
+        KeyCode::KeyR => 'r',
+        KeyCode::KeyS => 's',
+        KeyCode::KeyT => 't',
+        KeyCode::KeyU => 'u',
+        KeyCode::KeyV => 'v',
+        KeyCode::KeyW => 'w',
+        KeyCode::KeyX => 'x',
+        KeyCode::KeyY => 'y',
+        KeyCode::KeyZ => 'z',
+        KeyCode::Digit0 => '0',
+        KeyCode::Digit1 => '1',
+        KeyCode::Digit2 => '2',
+        KeyCode::Digit3 => '3',
+        KeyCode::Digit4 => '4',
+        KeyCode::Digit5 => '5',
+        KeyCode::Digit6 => '6',
+        KeyCode::Digit7 => '7',
+        KeyCode::Digit8 => '8',
+        KeyCode::Digit9 => '9',
+        KeyCode::Slash => '/',
+        _ => return None,
+    })
+}
+
 /// Bevy system to handle character input (for printable characters)
 pub fn handle_character_input(
     mut char_events: EventReader<bevy::input::keyboard::KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
     ipc: Res<IpcChannel>,
     link_hints_state: Option<Res<LinkHintsState>>,
     menu_state: Option<Res<MenuState>>,
+    scrollback_state: Option<Res<ScrollbackState>>,
+    ime_state: Option<Res<ImeState>>,
+    session_picker: Option<Res<SessionPickerState>>,
+    key_tables: Option<Res<KeyTableStackResource>>,
+    command_palette: Option<Res<CommandPaletteState>>,
+    plugin_prompt: Option<Res<PluginPromptState>>,
+    permission_prompt: Option<Res<PermissionPromptState>>,
+    repl: Option<Res<ReplState>>,
+    theme_gallery: Option<Res<ThemeGalleryState>>,
+    idle_lock: Option<Res<IdleLockState>>,
 ) {
     // Don't send input to terminal when hint mode is active
     let hints_active = link_hints_state.map_or(false, |s| s.active);
     let menu_hint_active = menu_state.map_or(false, |s| s.active && s.hint_mode);
-
-    if hints_active || menu_hint_active {
+    // The search bar owns the keyboard while it is open
+    let search_active = scrollback_state.map_or(false, |s| s.search_visible);
+    // Keys that drive an IME composition never reach the PTY
+    let ime_active = ime_state.map_or(false, |s| s.captures_keys());
+    // The startup session picker is modal
+    let picker_active = session_picker.map_or(false, |s| s.captures_keys());
+    // Bound keys and modal key tables never reach the PTY
+    let table_active = key_tables.map_or(false, |s| s.captures_keys());
+    // Typing goes to the command palette while it is open
+    let palette_active = command_palette.map_or(false, |s| s.active);
+    // Plugin prompts and forms are modal
+    let prompt_active = plugin_prompt.map_or(false, |s| s.captures_keys())
+        || permission_prompt.map_or(false, |s| s.captures_keys());
+    // So are the Fusabi REPL and the theme gallery
+    let repl_active = repl.map_or(false, |s| s.captures_keys())
+        || theme_gallery.map_or(false, |s| s.captures_keys());
+    // Nothing reaches the shell while the screen is locked
+    let locked = idle_lock.map_or(false, |s| s.captures_keys());
+
+    if hints_active
+        || menu_hint_active
+        || search_active
+        || ime_active
+        || picker_active
+        || table_active
+        || palette_active
+        || prompt_active
+        || repl_active
+        || locked
+    {
         // Consume all events but don't send them
         for _ in char_events.read() {}
         return;
@@ -335,7 +622,16 @@ pub fn handle_character_input(
 
         // Skip keys already handled by handle_keyboard_input
         // This prevents double-sending for Space, Tab, etc.
-        if key_to_bytes(event.key_code).is_some() {
+        if key_to_bytes(event.key_code).is_some()
+            || modified_key_to_bytes(event.key_code, modifier_param(&keys)).is_some()
+        {
+            continue;
+        }
+
+        // Ctrl+= / Ctrl+- / Ctrl+0 zoom the font instead of typing
+        if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && is_zoom_key(event.key_code)
+        {
             continue;
         }
 
@@ -363,12 +659,34 @@ pub fn handle_character_input(
 }
 
 /// Bevy system to handle window resize
+///
+/// Also re-sends the grid size when window chrome (e.g. the tab bar) changes
+/// the space available to the terminal.
 pub fn handle_window_resize(
     mut resize_events: EventReader<bevy::window::WindowResized>,
     ipc: Res<IpcChannel>,
     renderer: Option<Res<TextRenderer>>,
+    insets: Option<Res<TerminalInsets>>,
+    windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
 ) {
-    for event in resize_events.read() {
+    let insets_changed = insets
+        .as_ref()
+        .is_some_and(|i| i.is_changed() && !i.is_added());
+    let insets = insets.map(|i| *i).unwrap_or_default();
+
+    // Secondary windows resize their own sessions
+    let mut sizes: Vec<(f32, f32)> = resize_events
+        .read()
+        .filter(|e| windows.contains(e.window))
+        .map(|e| (e.width, e.height))
+        .collect();
+    if sizes.is_empty() && insets_changed {
+        if let Ok(window) = windows.get_single() {
+            sizes.push((window.width(), window.height()));
+        }
+    }
+
+    for (width, height) in sizes {
         // Calculate terminal rows/cols from window size
         let (cell_width, cell_height) = if let Some(renderer) = &renderer {
             (renderer.cell_width, renderer.cell_height)
@@ -382,8 +700,8 @@ pub fn handle_window_resize(
             continue;
         }
 
-        let cols: u16 = (event.width / cell_width).floor() as u16;
-        let available_height = event.height - BOTTOM_UI_HEIGHT;
+        let cols: u16 = ((width - insets.right) / cell_width).floor() as u16;
+        let available_height = height - BOTTOM_UI_HEIGHT - insets.top - insets.bottom;
         let rows: u16 = (available_height / cell_height).floor() as u16;
 
         // Clamp to protocol limits
@@ -392,7 +710,7 @@ pub fn handle_window_resize(
 
         println!(
             "Window resized: {}x{} -> {}x{} chars (cell: {}x{})",
-            event.width, event.height, cols, rows, cell_width, cell_height
+            width, height, cols, rows, cell_width, cell_height
         );
 
         ipc.send(ControlMessage::Resize { cols, rows });
@@ -409,17 +727,190 @@ pub fn receive_ipc_messages(ipc: Res<IpcChannel>, mut events: EventWriter<Remote
     }
 }
 
+/// Apply settings changed at runtime through the daemon
+///
+/// Changed sections are announced like a config reload.
+pub fn apply_config_changes(
+    mut events: EventReader<RemoteMessageEvent>,
+    config: Option<ResMut<ScarabConfig>>,
+    mut changed_events: EventWriter<ConfigSectionsChanged>,
+) {
+    let Some(mut config) = config else {
+        return;
+    };
+    for event in events.read() {
+        let DaemonMessage::ConfigChanged { key, value } = &event.0 else {
+            continue;
+        };
+        let value = settings::parse_setting_value(value);
+        match settings::set_setting(&config, key, value) {
+            Ok(updated) => {
+                *config = updated;
+                log::info!("Setting {} changed at runtime", key);
+                if let Some(section) = settings::setting_section(key) {
+                    changed_events.send(ConfigSectionsChanged {
+                        sections: vec![section],
+                    });
+                }
+            }
+            Err(e) => log::warn!("Failed to apply setting {}: {}", key, e),
+        }
+    }
+}
+
+/// Apply theme and color changes from the daemon
+///
+/// Themes come from following the desktop's light/dark mode. Single colors
+/// come from programs setting them (OSC 4/10/11/12) or from plugins, named
+/// by their key in `[colors]`, such as `background` or `palette.red`.
+pub fn apply_theme_updates(
+    mut events: EventReader<RemoteMessageEvent>,
+    config: Option<ResMut<ScarabConfig>>,
+    mut changed_events: EventWriter<ConfigSectionsChanged>,
+) {
+    let Some(mut config) = config else {
+        return;
+    };
+    let mut changed = false;
+    for event in events.read() {
+        match &event.0 {
+            DaemonMessage::ThemeUpdate { theme_json } => {
+                match serde_json::from_str::<Theme>(theme_json) {
+                    Ok(theme) => {
+                        theme.apply_to(&mut config.colors);
+                        log::info!("Switched to theme {}", theme.id());
+                        changed = true;
+                    }
+                    Err(e) => log::warn!("Failed to parse theme update: {}", e),
+                }
+            }
+            DaemonMessage::PaletteColorSet { color_name, value } => {
+                let key = format!("colors.{}", color_name);
+                let value = settings::parse_setting_value(value);
+                match settings::set_setting(&config, &key, value) {
+                    Ok(updated) => {
+                        *config = updated;
+                        changed = true;
+                    }
+                    Err(e) => log::warn!("Failed to set color {}: {}", color_name, e),
+                }
+            }
+            _ => {}
+        }
+    }
+    if changed {
+        changed_events.send(ConfigSectionsChanged {
+            sections: vec![ConfigSection::Colors],
+        });
+    }
+}
+
+/// Settings overridden for the focused tab and pane
+#[derive(Resource, Debug, Default)]
+pub struct ConfigOverrideState {
+    /// Overrides in effect, as sent by the daemon
+    pub active: Vec<ConfigEntry>,
+    /// The config's own value of each overridden key, restored when the
+    /// override goes away
+    base: Vec<ConfigEntry>,
+}
+
+/// Lay the focused pane's config overrides over [`ScarabConfig`]
+///
+/// The daemon sends the overrides whenever the focus moves or they change.
+/// When a reload or runtime change replaces an overridden value, the new
+/// value is kept aside in its place and the override applied again.
+pub fn apply_config_overrides(
+    mut events: EventReader<RemoteMessageEvent>,
+    config: Option<ResMut<ScarabConfig>>,
+    mut state: ResMut<ConfigOverrideState>,
+    mut changed_events: EventWriter<ConfigSectionsChanged>,
+) {
+    let Some(mut config) = config else {
+        return;
+    };
+    let mut keys = Vec::new();
+
+    if config.is_changed() {
+        let state = &mut *state;
+        for entry in &state.active {
+            let Ok(current) = settings::get_setting(&config, &entry.key) else {
+                continue;
+            };
+            let current = current.to_string();
+            if current == entry.value {
+                continue;
+            }
+            if let Some(base) = state.base.iter_mut().find(|base| base.key == entry.key) {
+                base.value = current;
+            }
+            set_config_entry(&mut config, entry);
+            keys.push(entry.key.clone());
+        }
+    }
+
+    for event in events.read() {
+        let DaemonMessage::ConfigOverrides {
+            settings: overrides,
+            ..
+        } = &event.0
+        else {
+            continue;
+        };
+        for base in std::mem::take(&mut state.base).into_iter().rev() {
+            set_config_entry(&mut config, &base);
+            keys.push(base.key);
+        }
+        for entry in overrides {
+            if let Ok(current) = settings::get_setting(&config, &entry.key) {
+                state.base.push(ConfigEntry {
+                    key: entry.key.clone(),
+                    value: current.to_string(),
+                });
+            }
+            set_config_entry(&mut config, entry);
+            keys.push(entry.key.clone());
+        }
+        state.active = overrides.clone();
+    }
+
+    let mut sections = Vec::new();
+    for section in keys.iter().filter_map(|key| settings::setting_section(key)) {
+        if !sections.contains(&section) {
+            sections.push(section);
+        }
+    }
+    if !sections.is_empty() {
+        changed_events.send(ConfigSectionsChanged { sections });
+    }
+}
+
+fn set_config_entry(config: &mut ScarabConfig, entry: &ConfigEntry) {
+    let value = settings::parse_setting_value(&entry.value);
+    match settings::set_setting(config, &entry.key, value) {
+        Ok(updated) => *config = updated,
+        Err(e) => log::warn!("Failed to apply override of {}: {}", entry.key, e),
+    }
+}
+
 /// Bevy plugin for IPC functionality
 pub struct IpcPlugin;
 
 impl Plugin for IpcPlugin {
     fn build(&self, app: &mut App) {
         // Initialize IPC channel
-        match IpcChannel::new() {
+        let paths = app
+            .world()
+            .get_resource::<DaemonPaths>()
+            .cloned()
+            .unwrap_or_default();
+        match IpcChannel::new(paths.0.socket) {
             Ok(channel) => {
                 println!("IPC channel initialized");
                 app.insert_resource(channel);
                 app.add_event::<RemoteMessageEvent>();
+                app.add_event::<ConfigSectionsChanged>();
+                app.init_resource::<ConfigOverrideState>();
 
                 // Register input handling systems
                 app.add_systems(
@@ -433,6 +924,14 @@ impl Plugin for IpcPlugin {
                     )
                         .in_set(InputSystemSet::Daemon),
                 );
+                app.add_systems(
+                    Update,
+                    (
+                        apply_config_changes.after(receive_ipc_messages),
+                        apply_theme_updates.after(apply_config_changes),
+                        apply_config_overrides.after(apply_theme_updates),
+                    ),
+                );
             }
             Err(e) => {
                 log::error!("Failed to initialize IPC: {}", e);
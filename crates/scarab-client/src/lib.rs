@@ -27,6 +27,7 @@ pub mod input;
 pub mod integration;
 pub mod ipc;
 pub mod marketplace;
+pub mod multi_window;
 pub mod navigation;
 pub mod plugin_host;
 pub mod prompt_markers;
@@ -34,12 +35,15 @@ pub mod ratatui_bridge;
 pub mod rendering;
 pub mod scripting;
 pub mod shaders;
+pub mod shell_integration;
 pub mod telemetry_integration;
 pub mod tutorial;
 pub mod zones;
 
 #[cfg(feature = "plugin-inspector")]
 pub mod plugin_inspector;
+#[cfg(feature = "plugin-inspector")]
+pub mod plugin_log_viewer;
 
 pub mod graphics_inspector;
 
@@ -53,7 +57,7 @@ pub use rendering::*;
 pub use integration::{extract_grid_text, get_cell_at, IntegrationPlugin, SharedMemoryReader};
 
 // Re-export safe state abstractions
-pub use safe_state::{MockTerminalState, SafeSharedState};
+pub use safe_state::{GridView, MockTerminalState, SafeSharedState};
 
 // Re-export terminal types
 pub use terminal::scrollback::{
@@ -128,6 +132,8 @@ pub use marketplace::{
 // Re-export plugin inspector (feature-gated)
 #[cfg(feature = "plugin-inspector")]
 pub use plugin_inspector::{PluginInspectorPlugin, PluginInspectorState};
+#[cfg(feature = "plugin-inspector")]
+pub use plugin_log_viewer::{PluginLogBuffer, PluginLogViewerState};
 
 // Re-export graphics inspector
 pub use graphics_inspector::{GraphicsInspectorPlugin, GraphicsInspectorState};
@@ -155,8 +161,8 @@ pub use dev::{BevyInspectorPlugin, BevyInspectorState};
 pub use accessibility::{
     parse_accessibility_command, parse_export_command, AccessibilityCommand, AccessibilityConfig,
     AccessibilityEvent, AccessibilityPlugin, Announcement, AnnouncementPriority, AtSpiIntegration,
-    ChangeTextScaleEvent, ExportFormat, ExportGridEvent, ScreenReaderAnnounceEvent,
-    ScreenReaderState, TerminalExporter, ToggleHighContrastEvent,
+    ChangeTextScaleEvent, ExportFormat, ExportGridEvent, ExportRange, ReviewCursor, ReviewMove,
+    ScreenReaderAnnounceEvent, ScreenReaderState, TerminalExporter, ToggleHighContrastEvent,
 };
 
 // // Re-export zones system
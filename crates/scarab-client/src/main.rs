@@ -1,25 +1,37 @@
 use bevy::prelude::*;
 use bevy::render::camera::OrthographicProjection;
+use bevy::render::settings::{Backends, PowerPreference, RenderCreation, WgpuSettings};
+use bevy::render::RenderPlugin;
 use bevy::winit::{UpdateMode, WinitSettings};
 use scarab_client::integration::{IntegrationPlugin, SharedMemWrapper, SharedMemoryReader};
 use scarab_client::rendering::config::color;
+use scarab_client::input::{ImePlugin, KeyTablesPlugin, PluginMousePlugin};
+use scarab_client::multi_window::MultiWindowPlugin;
 use scarab_client::navigation::{FocusablePlugin, NavigationPlugin};
-use scarab_client::rendering::HintOverlayPlugin;
+use scarab_client::rendering::{
+    window_needs_transparency, BackgroundPlugin, CursorPlugin, FontConfig, FontZoomPlugin,
+    HintOverlayPlugin, SmoothScrollPlugin, SnapshotRenderer,
+};
 use scarab_client::{
-    AccessibilityPlugin, AdvancedUIPlugin, CopyModePlugin, EventsPlugin, GraphicsInspectorPlugin,
-    ImagesPlugin, InputSystemSet, ScarabEffectsPlugin, ScarabTelemetryPlugin, ScriptingPlugin,
-    ScrollbackPlugin, TutorialPlugin,
+    AccessibilityPlugin, AdvancedUIPlugin, CopyModePlugin, EventsPlugin, ExportFormat, ExportRange,
+    GraphicsInspectorPlugin, ImagesPlugin, InputSystemSet, ScarabEffectsPlugin,
+    ScarabTelemetryPlugin, ScriptingPlugin, ScrollbackPlugin, TerminalExporter, TutorialPlugin,
 };
-use scarab_config::{ConfigLoader, FusabiConfigLoader};
+use scarab_config::{ConfigLoader, FusabiConfigReloadPlugin, FusabiConfigSession};
+use scarab_platform::Paths;
 // Uncomment to enable hot-reloading config via bevy-fusabi:
 // use scarab_config::ScarabConfigPlugin;
 use scarab_protocol::terminal_state::TerminalStateReader;
-use scarab_protocol::{SharedState, SHMEM_PATH, SHMEM_PATH_ENV};
+use scarab_protocol::{SharedState, SHMEM_PATH_ENV};
 use shared_memory::ShmemConf;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use clap::Parser;
-use scarab_client::ipc::{IpcPlugin, StartupCommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use scarab_client::ipc::{DaemonPaths, IpcPlugin, StartupCommand};
+use scarab_client::shell_integration::{
+    self, CheckStatus, InstallOutcome, IntegrationPaths, Shell,
+};
 
 #[cfg(feature = "plugin-inspector")]
 use scarab_client::PluginInspectorPlugin;
@@ -37,29 +49,114 @@ struct Args {
     /// Run in headless mode (no window, dump terminal grid and exit)
     #[arg(long)]
     headless: bool,
+
+    /// Renderer to use: `gpu`, or `soft` for a CPU (software adapter) fallback
+    #[arg(long, value_enum, default_value_t = RenderMode::Gpu)]
+    render: RenderMode,
+
+    /// In headless mode, also write a PNG snapshot of the grid to this path
+    #[arg(long, value_name = "PATH")]
+    snapshot: Option<PathBuf>,
+
+    /// In headless mode, also export the screen to this path, as text, HTML,
+    /// Markdown, SVG or ANSI by its extension (txt, html, md, svg, ansi)
+    #[arg(long, value_name = "PATH")]
+    export: Option<PathBuf>,
+
+    #[command(subcommand)]
+    action: Option<Action>,
+}
+
+/// Commands run instead of opening a window
+#[derive(Subcommand, Debug)]
+enum Action {
+    /// Set up the shell to mark prompts and commands (OSC 133) for Scarab
+    #[command(subcommand)]
+    ShellIntegration(ShellIntegrationCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum ShellIntegrationCommand {
+    /// Add the integration to the shell's startup file
+    Install {
+        /// Shell to set up (default: from $SHELL)
+        #[arg(long, value_enum)]
+        shell: Option<Shell>,
+        /// Install even if the startup file already emits OSC 133
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check the install and that marks from this shell reach the daemon
+    Doctor {
+        /// Shell to check (default: from $SHELL)
+        #[arg(long, value_enum)]
+        shell: Option<Shell>,
+    },
+}
+
+/// How the windowed client renders
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum RenderMode {
+    /// Hardware GPU adapter (default)
+    Gpu,
+    /// wgpu's fallback adapter (llvmpipe/lavapipe/WARP) for VMs and CI
+    Soft,
+}
+
+impl RenderMode {
+    /// wgpu adapter selection for this mode
+    fn render_creation(self) -> RenderCreation {
+        match self {
+            RenderMode::Gpu => RenderCreation::Automatic(WgpuSettings::default()),
+            RenderMode::Soft => RenderCreation::Automatic(WgpuSettings {
+                // Software adapters are often only exposed through GL
+                backends: Some(Backends::all()),
+                power_preference: PowerPreference::LowPower,
+                force_fallback_adapter: true,
+                ..default()
+            }),
+        }
+    }
 }
 
 fn main() {
     let args = Args::parse();
 
+    if let Some(Action::ShellIntegration(command)) = args.action {
+        std::process::exit(match run_shell_integration(command) {
+            Ok(true) => 0,
+            Ok(false) => 1,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                1
+            }
+        });
+    }
+
     // Load Configuration (Fusabi-based)
-    let home_dir = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let fusabi_config_path = std::path::PathBuf::from(&home_dir).join(".config/scarab/config.fsx");
-    let toml_config_path = std::path::PathBuf::from(&home_dir).join(".config/scarab/config.toml");
+    let config_dir = Paths::from_env().config_dir;
+    let fusabi_config_path = config_dir.join("config.fsx");
+    let toml_config_path = config_dir.join("config.toml");
 
+    // Kept alive so edits to config.fsx only re-evaluate what changed
+    let mut fusabi_session = None;
     let config = if fusabi_config_path.exists() {
         println!(
             "Loading Fusabi config from: {}",
             fusabi_config_path.display()
         );
-        FusabiConfigLoader::from_file(&fusabi_config_path).expect("Failed to load Fusabi config")
+        let session =
+            FusabiConfigSession::load(&fusabi_config_path).expect("Failed to load Fusabi config");
+        let config = session.config().clone();
+        fusabi_session = Some(session);
+        config
     } else if toml_config_path.exists() {
         println!(
             "⚠️  Loading legacy TOML config from: {}",
             toml_config_path.display()
         );
         println!(
-            "💡 Consider migrating to Fusabi config: {}",
+            "💡 Consider migrating to Fusabi config: {} (run `scarab-config migrate`)",
             fusabi_config_path.display()
         );
         ConfigLoader::from_file(&toml_config_path).expect("Failed to load TOML config")
@@ -72,9 +169,18 @@ fn main() {
         scarab_config::ScarabConfig::default()
     };
 
+    // Profiles for this host and environment, then the project config;
+    // directory profiles follow the focused pane and come from the daemon
+    let config = ConfigLoader::with_path(toml_config_path.clone())
+        .without_directory_profiles()
+        .apply_overrides_or_base(config);
+
+    // Socket and shared memory locations: environment, then `[paths]`
+    let paths = DaemonPaths(config.paths.resolve());
+
     // Initialize shared memory before Bevy app starts
     // Support environment variable override for sandboxed environments
-    let shmem_path = std::env::var(SHMEM_PATH_ENV).unwrap_or_else(|_| SHMEM_PATH.to_string());
+    let shmem_path = paths.0.shmem.clone();
 
     let shmem = match ShmemConf::new()
         .size(std::mem::size_of::<SharedState>())
@@ -90,7 +196,7 @@ fn main() {
             eprintln!("Is the daemon running?");
             eprintln!("");
             eprintln!("If using a custom shared memory path, ensure both daemon and client");
-            eprintln!("use the same {} setting.", SHMEM_PATH_ENV);
+            eprintln!("use the same `paths.shmem` or {} setting.", SHMEM_PATH_ENV);
             std::process::exit(1);
         }
     };
@@ -110,14 +216,113 @@ fn main() {
 
     // Branch: Headless mode vs Normal windowed mode
     if args.headless {
-        run_headless(reader, args.command);
+        let export = args.export.map(|path| {
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            let Some(format) = ExportFormat::from_str(extension) else {
+                eprintln!(
+                    "Can't export to {}: use a .txt, .html, .md, .svg or .ansi file",
+                    path.display()
+                );
+                std::process::exit(1);
+            };
+            HeadlessExport {
+                path,
+                format,
+                columns: config.terminal.columns as usize,
+                rows: config.terminal.rows as usize,
+            }
+        });
+        run_headless(reader, paths, &config, args.command, args.snapshot, export);
     } else {
-        run_windowed(reader, config, window_width, window_height, args.command);
+        if args.snapshot.is_some() {
+            eprintln!("--snapshot is only used with --headless; ignoring it");
+        }
+        if args.export.is_some() {
+            eprintln!("--export is only used with --headless; ignoring it");
+        }
+        run_windowed(
+            reader,
+            paths,
+            config,
+            window_width,
+            window_height,
+            args.command,
+            args.render,
+        );
+    }
+}
+
+/// Run a `shell-integration` command, returning whether it succeeded
+fn run_shell_integration(command: ShellIntegrationCommand) -> anyhow::Result<bool> {
+    let shell = match &command {
+        ShellIntegrationCommand::Install { shell, .. }
+        | ShellIntegrationCommand::Doctor { shell } => *shell,
+    };
+    let Some(shell) = shell.or_else(Shell::detect) else {
+        eprintln!("Could not tell the shell from $SHELL; pass --shell bash, zsh or fish");
+        return Ok(false);
+    };
+    let paths = IntegrationPaths::from_env(shell)?;
+
+    match command {
+        ShellIntegrationCommand::Install { force, .. } => {
+            match shell_integration::install(shell, &paths, force)? {
+                InstallOutcome::Added => {
+                    println!(
+                        "Installed {} integration in {}",
+                        shell.name(),
+                        paths.rc_file.display()
+                    );
+                    println!(
+                        "Open a new Scarab tab, or run `exec {}`, to load it",
+                        shell.name()
+                    );
+                }
+                InstallOutcome::Updated => println!(
+                    "{} integration is already in {}; updated {}",
+                    shell.name(),
+                    paths.rc_file.display(),
+                    paths.script_file.display()
+                ),
+                InstallOutcome::Conflict => {
+                    eprintln!(
+                        "{} already emits OSC 133 marks, likely from another terminal's integration.",
+                        paths.rc_file.display()
+                    );
+                    eprintln!("Remove that, or pass --force to install alongside it.");
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        ShellIntegrationCommand::Doctor { .. } => {
+            let socket = scarab_config::ConfigLoader::new().paths().socket;
+            let checks = shell_integration::doctor(shell, &paths, &socket);
+            for check in &checks {
+                let status = match check.status {
+                    CheckStatus::Pass => "ok",
+                    CheckStatus::Warn => "warn",
+                    CheckStatus::Fail => "FAIL",
+                };
+                println!("[{:>4}] {}", status, check.message);
+            }
+            Ok(checks.iter().all(|check| check.status != CheckStatus::Fail))
+        }
     }
 }
 
 /// Run in headless mode (no window, dump terminal grid and exit)
-fn run_headless(reader: SharedMemoryReader, command: Option<String>) {
+///
+/// With a snapshot path the grid is also rasterized on the CPU and written
+/// as a PNG, for golden-image tests on machines without a GPU.
+fn run_headless(
+    reader: SharedMemoryReader,
+    paths: DaemonPaths,
+    config: &scarab_config::ScarabConfig,
+    command: Option<String>,
+    snapshot: Option<PathBuf>,
+    export: Option<HeadlessExport>,
+) {
     println!("Running in headless mode");
 
     let mut app = App::new();
@@ -126,6 +331,7 @@ fn run_headless(reader: SharedMemoryReader, command: Option<String>) {
     app.add_plugins(MinimalPlugins);
 
     // Add IPC plugin for command injection
+    app.insert_resource(paths);
     app.add_plugins(IpcPlugin);
 
     // Insert shared memory reader
@@ -145,6 +351,22 @@ fn run_headless(reader: SharedMemoryReader, command: Option<String>) {
         initial_sequence: 0,
     });
 
+    if let Some(path) = snapshot {
+        println!("PNG snapshot will be written to: {}", path.display());
+        // Same font settings as the windowed renderer (see integration.rs)
+        let font_config = FontConfig {
+            family: config.font.family.clone(),
+            fallback: config.font.fallback.clone(),
+            ..FontConfig::default()
+        };
+        app.insert_resource(HeadlessSnapshot { path, font_config });
+    }
+
+    if let Some(export) = export {
+        println!("Export will be written to: {}", export.path.display());
+        app.insert_resource(export);
+    }
+
     // Add headless system to dump grid and exit
     app.add_systems(Update, headless_dump_and_exit);
 
@@ -155,10 +377,12 @@ fn run_headless(reader: SharedMemoryReader, command: Option<String>) {
 /// Run in normal windowed mode
 fn run_windowed(
     reader: SharedMemoryReader,
+    paths: DaemonPaths,
     config: scarab_config::ScarabConfig,
     _window_width: f32,
     _window_height: f32,
     command: Option<String>,
+    render: RenderMode,
 ) {
     // Window icon loading note: Bevy 0.15 window icon support requires platform-specific handling
     // and may not be available in all backends. For now, we log if an icon path is configured.
@@ -174,12 +398,31 @@ fn run_windowed(
     if let Some(cmd) = command {
         app.insert_resource(StartupCommand(cmd));
     }
+    // Read by IpcPlugin and ImagesPlugin when they are built
+    app.insert_resource(paths);
 
     // Default to left-half of a 1920x1080 screen
     // Position at (0, 0) with half screen width
     let default_width = 960.0; // Half of 1920
     let default_height = 1040.0; // 1080 minus typical panel height
 
+    // A translucent background needs an alpha-capable window surface
+    let transparent = window_needs_transparency(&config);
+    #[cfg(target_os = "macos")]
+    let composite_alpha_mode = if transparent {
+        bevy::window::CompositeAlphaMode::PostMultiplied
+    } else {
+        bevy::window::CompositeAlphaMode::Auto
+    };
+    #[cfg(target_os = "linux")]
+    let composite_alpha_mode = if transparent {
+        bevy::window::CompositeAlphaMode::PreMultiplied
+    } else {
+        bevy::window::CompositeAlphaMode::Auto
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    let composite_alpha_mode = bevy::window::CompositeAlphaMode::Auto;
+
     app.add_plugins(
         DefaultPlugins
             .set(WindowPlugin {
@@ -188,10 +431,16 @@ fn run_windowed(
                     resolution: (default_width, default_height).into(),
                     position: bevy::window::WindowPosition::At(IVec2::new(0, 0)),
                     window_theme: Some(bevy::window::WindowTheme::Dark),
+                    transparent,
+                    composite_alpha_mode,
                     ..default()
                 }),
                 ..default()
             })
+            .set(RenderPlugin {
+                render_creation: render.render_creation(),
+                ..default()
+            })
             .set(bevy::log::LogPlugin {
                 level: bevy::log::Level::INFO,
                 filter: "wgpu=error,bevy_render::view::window=error,bevy_ecs=info".into(),
@@ -209,8 +458,15 @@ fn run_windowed(
     .add_plugins(AdvancedUIPlugin) // Add advanced UI features (includes search, indicators)
     .add_plugins(ScriptingPlugin) // Add client-side scripting
     .add_plugins(IntegrationPlugin) // Add text rendering
+    .add_plugins(CursorPlugin) // Add terminal cursor (DECSCUSR shapes, blink, smooth movement)
+    .add_plugins(BackgroundPlugin) // Add window opacity, blur-behind, and background image
+    .add_plugins(FontZoomPlugin) // Add runtime font zoom (Ctrl+= / Ctrl+- / Ctrl+0)
+    .add_plugins(ImePlugin) // Add IME composition (preedit overlay, candidate window placement)
+    .add_plugins(KeyTablesPlugin) // Add leader key, chords and modal key tables
+    .add_plugins(PluginMousePlugin) // Add mouse events for daemon plugins (on_mouse)
+    .add_plugins(SmoothScrollPlugin) // Add sub-line wheel/touchpad scrolling of the grid
+    .add_plugins(MultiWindowPlugin) // Add extra windows attached to their own sessions (Ctrl+Shift+N)
     .add_plugins(TutorialPlugin) // Add interactive tutorial system
-    .add_plugins(ScarabEffectsPlugin) // Add post-processing effects (blur, glow)
     .add_plugins(ScarabTelemetryPlugin) // Add telemetry HUD overlay (Ctrl+Shift+T to toggle)
     .add_plugins(AccessibilityPlugin) // Add accessibility features (screen reader, export, high contrast)
     .configure_sets(
@@ -234,6 +490,18 @@ fn run_windowed(
     // .add_plugins(ScarabConfigPlugin::new("config.fsx"))
     .add_systems(Startup, setup);
 
+    if let Some(session) = fusabi_session {
+        app.insert_non_send_resource(session)
+            .add_plugins(FusabiConfigReloadPlugin);
+    }
+
+    // Post-processing (blur, glow) is too slow on a software adapter
+    if render == RenderMode::Soft {
+        println!("Software rendering: post-processing effects disabled");
+    } else {
+        app.add_plugins(ScarabEffectsPlugin);
+    }
+
     // Conditionally add plugin inspector
     #[cfg(feature = "plugin-inspector")]
     {
@@ -264,10 +532,28 @@ struct HeadlessMode {
     initial_sequence: u64,
 }
 
+/// Where headless mode writes its PNG snapshot
+#[derive(Resource)]
+struct HeadlessSnapshot {
+    path: PathBuf,
+    font_config: FontConfig,
+}
+
+/// Where and how headless mode exports the screen
+#[derive(Resource)]
+struct HeadlessExport {
+    path: PathBuf,
+    format: ExportFormat,
+    columns: usize,
+    rows: usize,
+}
+
 /// System that waits for terminal updates, dumps grid, and exits
 fn headless_dump_and_exit(
     mut headless: ResMut<HeadlessMode>,
     reader: Res<SharedMemoryReader>,
+    snapshot: Option<Res<HeadlessSnapshot>>,
+    export: Option<Res<HeadlessExport>>,
     mut app_exit: EventWriter<bevy::app::AppExit>,
 ) {
     // Get safe state wrapper
@@ -319,6 +605,39 @@ fn headless_dump_and_exit(
         // Dump terminal grid to stdout
         dump_terminal_grid(&safe_state);
 
+        if let Some(snapshot) = snapshot {
+            let mut renderer = SnapshotRenderer::new(&snapshot.font_config);
+            match renderer.save_png(&safe_state, &snapshot.path) {
+                Ok(()) => println!("Wrote PNG snapshot to {}", snapshot.path.display()),
+                Err(e) => {
+                    eprintln!(
+                        "Failed to write PNG snapshot to {}: {}",
+                        snapshot.path.display(),
+                        e
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if let Some(export) = export {
+            let (grid, height) =
+                ExportRange::Screen.cells(&safe_state, None, export.columns, export.rows);
+            match TerminalExporter::export(
+                &grid,
+                export.columns,
+                height,
+                export.format,
+                &export.path,
+            ) {
+                Ok(()) => println!("Wrote export to {}", export.path.display()),
+                Err(e) => {
+                    eprintln!("Failed to export to {}: {}", export.path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
         // Exit the app
         println!("Headless mode complete, exiting.");
         app_exit.send(bevy::app::AppExit::Success);
@@ -0,0 +1,422 @@
+//! Additional terminal windows, each attached to its own daemon session
+//!
+//! The `window.new` key binding (Ctrl+Shift+N) opens another OS window. Each
+//! window:
+//! - Talks to the daemon over its own connection, creates a session and
+//!   attaches to it, so the daemon routes that window's input and resizes to
+//!   the session's active pane
+//! - Maps the session's shared memory region with its own
+//!   `SharedMemoryReader`
+//! - Draws its grid with a dedicated camera on its own render layer, sharing
+//!   the primary window's glyph atlas
+//!
+//! Typed input follows focus: while a secondary window is focused, the
+//! primary `IpcChannel` forwards input over that window's connection.
+//! Closing a window detaches from its session, which keeps running.
+
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::view::RenderLayers;
+use bevy::window::{WindowClosed, WindowFocused, WindowRef, WindowResized};
+use scarab_protocol::{
+    ControlMessage, DaemonMessage, SessionResponse, TerminalStateReader, GRID_HEIGHT, GRID_WIDTH,
+};
+use shared_memory::ShmemConf;
+use std::sync::Arc;
+
+use crate::integration::{SharedMemoryReader, TerminalGridEntity};
+use crate::ipc::{IpcChannel, IpcSink};
+use crate::rendering::config::color;
+use crate::rendering::text::{TerminalMesh, TextRenderer};
+use crate::ui::keybindings::KeyBindingTriggeredEvent;
+
+/// Key binding action that opens a window
+pub const NEW_WINDOW_ACTION: &str = "window.new";
+
+/// Initial size of a new window, matching the primary window
+const DEFAULT_WINDOW_SIZE: Vec2 = Vec2::new(960.0, 1040.0);
+
+/// Progress of a window's session setup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionLink {
+    /// Waiting for the window's daemon connection
+    Connecting { name: String },
+    /// Waiting for the daemon to create the session
+    Creating,
+    /// Waiting for the attach response
+    Attaching { id: String },
+    /// Attached; the grid is drawn from `shmem_path`
+    Attached { id: String, shmem_path: String },
+}
+
+impl SessionLink {
+    /// Next state after a daemon response, with the message to send for it
+    pub fn advance(&self, msg: &DaemonMessage) -> Option<(SessionLink, Option<ControlMessage>)> {
+        let DaemonMessage::Session(response) = msg else {
+            return None;
+        };
+        match (self, response) {
+            (SessionLink::Creating, SessionResponse::Created { id, .. }) => Some((
+                SessionLink::Attaching { id: id.clone() },
+                Some(ControlMessage::SessionAttach {
+                    id: id.clone(),
+                    read_only: false,
+                }),
+            )),
+            (
+                SessionLink::Attaching { id },
+                SessionResponse::Attached {
+                    id: attached,
+                    shmem_path,
+                    ..
+                },
+            ) if id == attached => Some((
+                SessionLink::Attached {
+                    id: id.clone(),
+                    shmem_path: shmem_path.clone(),
+                },
+                None,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// A secondary terminal window, stored on its window entity
+#[derive(Component)]
+pub struct SessionWindow {
+    pub link: SessionLink,
+    /// Render layer shared by the window's camera and grid
+    pub layer: usize,
+    sink: IpcSink,
+    reader: Option<SharedMemoryReader>,
+}
+
+/// Grid drawn into a secondary window
+#[derive(Component)]
+pub struct SessionWindowView {
+    pub window: Entity,
+}
+
+/// Grid size in cells for a secondary window, which has no tab or status bar
+pub fn window_grid_size(size: Vec2, cell: Vec2) -> (u16, u16) {
+    let cols = (size.x / cell.x.max(1.0)).floor() as u16;
+    let rows = (size.y / cell.y.max(1.0)).floor() as u16;
+    (
+        cols.clamp(1, GRID_WIDTH as u16),
+        rows.clamp(1, GRID_HEIGHT as u16),
+    )
+}
+
+/// System to open a window when the key binding fires
+fn open_session_window(
+    mut commands: Commands,
+    mut events: EventReader<KeyBindingTriggeredEvent>,
+    ipc: Option<Res<IpcChannel>>,
+    mut opened: Local<usize>,
+) {
+    let Some(ipc) = ipc else {
+        events.clear();
+        return;
+    };
+
+    for event in events.read() {
+        if event.action != NEW_WINDOW_ACTION {
+            continue;
+        }
+        *opened += 1;
+        let name = format!("window-{}", *opened + 1);
+        info!("Opening window for new session '{}'", name);
+
+        commands.spawn((
+            Window {
+                title: format!("Scarab Terminal — {}", name),
+                resolution: DEFAULT_WINDOW_SIZE.into(),
+                window_theme: Some(bevy::window::WindowTheme::Dark),
+                ..default()
+            },
+            SessionWindow {
+                link: SessionLink::Connecting { name },
+                layer: *opened,
+                sink: ipc.open_connection(),
+                reader: None,
+            },
+        ));
+    }
+}
+
+/// System to drive session setup and spawn each window's camera and grid
+fn link_session_windows(
+    mut commands: Commands,
+    mut windows: Query<(Entity, &Window, &mut SessionWindow)>,
+    grid_material: Query<&MeshMaterial2d<ColorMaterial>, With<TerminalGridEntity>>,
+    renderer: Option<Res<TextRenderer>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (entity, window, mut session_window) in windows.iter_mut() {
+        if let SessionLink::Connecting { name } = &session_window.link {
+            if session_window.sink.is_connected() {
+                session_window
+                    .sink
+                    .send(ControlMessage::SessionCreate { name: name.clone() });
+                session_window.link = SessionLink::Creating;
+            }
+            continue;
+        }
+
+        for msg in session_window.sink.drain() {
+            if let DaemonMessage::Session(SessionResponse::Error { message }) = &msg {
+                warn!("Window session setup failed: {}", message);
+                continue;
+            }
+            if let Some((link, reply)) = session_window.link.advance(&msg) {
+                if let Some(reply) = reply {
+                    session_window.sink.send(reply);
+                }
+                session_window.link = link;
+            }
+        }
+
+        let SessionLink::Attached { shmem_path, .. } = &session_window.link else {
+            continue;
+        };
+        if session_window.reader.is_some() {
+            continue;
+        }
+
+        let (Some(renderer), Ok(material)) = (renderer.as_ref(), grid_material.get_single()) else {
+            continue;
+        };
+
+        // The daemon maps the region on its next compositor tick; keep
+        // trying until it exists
+        let Ok(shmem) = ShmemConf::new().os_id(shmem_path).open() else {
+            continue;
+        };
+        session_window.reader = Some(SharedMemoryReader::new(Arc::new(shmem)));
+        let cell = Vec2::new(renderer.cell_width, renderer.cell_height);
+        let (cols, rows) = window_grid_size(window.size(), cell);
+        session_window
+            .sink
+            .send(ControlMessage::Resize { cols, rows });
+
+        let layers = RenderLayers::layer(session_window.layer);
+        commands.spawn((
+            Camera2d,
+            Camera {
+                target: RenderTarget::Window(WindowRef::Entity(entity)),
+                clear_color: ClearColorConfig::Custom(color::from_rgba(0xFF0D1208)),
+                order: session_window.layer as isize,
+                ..default()
+            },
+            layers.clone(),
+            SessionWindowView { window: entity },
+        ));
+
+        let mesh_handle = meshes.add(Mesh::new(
+            bevy::render::mesh::PrimitiveTopology::TriangleList,
+            bevy::render::render_asset::RenderAssetUsages::MAIN_WORLD
+                | bevy::render::render_asset::RenderAssetUsages::RENDER_WORLD,
+        ));
+        commands.spawn((
+            TerminalMesh::new(mesh_handle.clone()),
+            Mesh2d(mesh_handle),
+            material.clone(),
+            Transform::from_xyz(-window.width() * 0.5, window.height() * 0.5, 0.0),
+            layers,
+            SessionWindowView { window: entity },
+        ));
+    }
+}
+
+/// System to redraw each window's grid from its own shared memory
+fn render_session_windows(
+    windows: Query<&SessionWindow>,
+    mut grids: Query<(&SessionWindowView, &mut TerminalMesh)>,
+    renderer: Option<ResMut<TextRenderer>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(mut renderer) = renderer else {
+        return;
+    };
+
+    for (view, mut terminal_mesh) in grids.iter_mut() {
+        let Some(reader) = windows
+            .get(view.window)
+            .ok()
+            .and_then(|w| w.reader.as_ref())
+        else {
+            continue;
+        };
+
+        let state = reader.get_safe_state();
+        let sequence = state.sequence();
+        let first_render =
+            terminal_mesh.last_sequence == 0 && terminal_mesh.dirty_region.is_full_redraw();
+        if sequence != terminal_mesh.last_sequence {
+            let last_sequence = terminal_mesh.last_sequence;
+            terminal_mesh
+                .dirty_region
+                .apply_damage(&state, last_sequence);
+            terminal_mesh.last_sequence = sequence;
+        }
+        if !first_render && terminal_mesh.dirty_region.is_empty() {
+            continue;
+        }
+
+        let terminal_mesh = &mut *terminal_mesh;
+        terminal_mesh
+            .cache
+            .update(&state, &mut renderer, &terminal_mesh.dirty_region);
+        renderer.atlas.update_texture(&mut images);
+        meshes.insert(&terminal_mesh.mesh_handle, terminal_mesh.cache.build_mesh());
+        terminal_mesh.dirty_region.clear();
+    }
+}
+
+/// System to resize a window's session and keep its grid at the top-left
+fn resize_session_windows(
+    mut events: EventReader<WindowResized>,
+    windows: Query<&SessionWindow>,
+    renderer: Option<Res<TextRenderer>>,
+    mut grids: Query<(&SessionWindowView, &mut Transform, &mut TerminalMesh)>,
+) {
+    let cell = renderer.map_or(Vec2::new(8.0, 16.0), |r| {
+        Vec2::new(r.cell_width, r.cell_height)
+    });
+
+    for event in events.read() {
+        let Ok(session_window) = windows.get(event.window) else {
+            continue;
+        };
+        let size = Vec2::new(event.width, event.height);
+        let (cols, rows) = window_grid_size(size, cell);
+        session_window
+            .sink
+            .send(ControlMessage::Resize { cols, rows });
+
+        for (view, mut transform, mut terminal_mesh) in grids.iter_mut() {
+            if view.window == event.window {
+                transform.translation.x = -size.x * 0.5;
+                transform.translation.y = size.y * 0.5;
+                terminal_mesh.dirty_region.mark_full_redraw();
+            }
+        }
+    }
+}
+
+/// System to send typed input to the focused window's session
+fn route_input_to_focused_window(
+    mut focus_events: EventReader<WindowFocused>,
+    mut closed_events: EventReader<WindowClosed>,
+    windows: Query<&SessionWindow>,
+    ipc: Option<Res<IpcChannel>>,
+) {
+    let Some(ipc) = ipc else {
+        return;
+    };
+
+    for event in focus_events.read().filter(|e| e.focused) {
+        let sink = windows
+            .get(event.window)
+            .ok()
+            .filter(|w| matches!(w.link, SessionLink::Attached { .. }))
+            .map(|w| w.sink.clone());
+        ipc.route_input_to(sink);
+    }
+
+    // Input goes back to the primary window until another gains focus
+    if closed_events.read().count() > 0 {
+        ipc.route_input_to(None);
+    }
+}
+
+/// System to remove a closed window's camera and grid
+fn despawn_closed_window_views(
+    mut commands: Commands,
+    mut closed_events: EventReader<WindowClosed>,
+    views: Query<(Entity, &SessionWindowView)>,
+) {
+    for event in closed_events.read() {
+        for (entity, view) in views.iter() {
+            if view.window == event.window {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Plugin for additional session windows
+pub struct MultiWindowPlugin;
+
+impl Plugin for MultiWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                open_session_window,
+                link_session_windows,
+                resize_session_windows,
+                render_session_windows,
+                route_input_to_focused_window,
+                despawn_closed_window_views,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_link_handshake() {
+        let created = DaemonMessage::Session(SessionResponse::Created {
+            id: "abc".into(),
+            name: "window-2".into(),
+        });
+        let (link, reply) = SessionLink::Creating.advance(&created).unwrap();
+        assert_eq!(link, SessionLink::Attaching { id: "abc".into() });
+        assert!(matches!(
+            reply,
+            Some(ControlMessage::SessionAttach { ref id, .. }) if id == "abc"
+        ));
+
+        // Attach responses for other sessions are ignored
+        let other = DaemonMessage::Session(SessionResponse::Attached {
+            id: "xyz".into(),
+            shmem_path: "/scarab_shm_v1_xyz".into(),
+            read_only: false,
+        });
+        assert!(link.advance(&other).is_none());
+
+        let attached = DaemonMessage::Session(SessionResponse::Attached {
+            id: "abc".into(),
+            shmem_path: "/scarab_shm_v1_abc".into(),
+            read_only: false,
+        });
+        let (link, reply) = link.advance(&attached).unwrap();
+        assert_eq!(
+            link,
+            SessionLink::Attached {
+                id: "abc".into(),
+                shmem_path: "/scarab_shm_v1_abc".into(),
+            }
+        );
+        assert!(reply.is_none());
+    }
+
+    #[test]
+    fn test_window_grid_size() {
+        let cell = Vec2::new(10.0, 20.0);
+        assert_eq!(window_grid_size(Vec2::new(805.0, 490.0), cell), (80, 24));
+        // Never zero, never past the shared grid
+        assert_eq!(window_grid_size(Vec2::new(0.0, 0.0), cell), (1, 1));
+        assert_eq!(
+            window_grid_size(Vec2::new(100_000.0, 100_000.0), cell),
+            (GRID_WIDTH as u16, GRID_HEIGHT as u16)
+        );
+    }
+}
@@ -57,6 +57,9 @@ use bevy::prelude::*;
 ///   their expiration time
 /// - `cleanup_removed_overlays`: Removes overlay entities when plugins
 ///   are unloaded or overlays are despawned
+/// - `cleanup_removed_status_items`: Removes status item entities when
+///   plugins are unloaded or items are removed
+/// - `render_plugin_status_items`: Hands status items to the status bar
 ///
 /// # Resources
 ///
@@ -76,7 +79,7 @@ impl Plugin for ScarabPluginHostPlugin {
                 process_plugin_actions,
                 cleanup_expired_notifications,
                 cleanup_removed_overlays,
-                render_plugin_status_items,
+                (cleanup_removed_status_items, render_plugin_status_items).chain(),
             ),
         );
 
@@ -247,9 +250,7 @@ fn process_plugin_actions(
                 // Track in registry
                 registry.add_status_item(plugin_id, item_id);
 
-                // TODO: Integrate with status bar system
-                // This will require updating the status bar to query for
-                // PluginStatusItem components
+                // Drawn by render_plugin_status_items
             }
 
             PluginAction::RemoveStatusItem { plugin_id, item_id } => {
@@ -261,8 +262,7 @@ fn process_plugin_actions(
 
                 registry.remove_status_item(plugin_id, *item_id);
 
-                // The actual entity cleanup will be handled by a separate system
-                // that queries for despawned PluginStatusItem components
+                // The actual entity cleanup will happen via cleanup_removed_status_items system
             }
 
             PluginAction::RegisterKeybinding {
@@ -581,59 +581,55 @@ fn cleanup_removed_overlays(
     }
 }
 
+/// Clean up status item entities that have been removed from the registry
+///
+/// Mirrors `cleanup_removed_overlays` for RemoveStatusItem and plugin
+/// unloading.
+fn cleanup_removed_status_items(
+    mut commands: Commands,
+    registry: Res<PluginRegistry>,
+    status_items: Query<(Entity, &PluginStatusItem)>,
+) {
+    for (entity, item) in status_items.iter() {
+        let tracked = registry
+            .get(&item.plugin_id)
+            .is_some_and(|plugin| plugin.status_item_ids.contains(&item.item_id));
+        if !tracked {
+            debug!(
+                plugin_id = %item.plugin_id,
+                item_id = item.item_id,
+                "Cleaning up removed status item"
+            );
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
 /// Render plugin status items to the status bar
 ///
-/// Queries for PluginStatusItem entities and updates the StatusBarState
-/// resource with their content, sorted by priority.
+/// Hands each PluginStatusItem to the StatusBarState as a segment, which
+/// orders them by priority and fits them to the bar's width.
 fn render_plugin_status_items(
     status_items: Query<&PluginStatusItem>,
     mut status_bar: ResMut<crate::ui::status_bar::StatusBarState>,
 ) {
-    use crate::events::StatusSide;
+    use crate::ui::status_bar::StatusSegment;
     use scarab_plugin_api::status_bar::RenderItem;
 
-    // Collect items by side
-    let mut left_items: Vec<_> = status_items
+    let mut segments: Vec<StatusSegment> = status_items
         .iter()
-        .filter(|item| item.side == StatusSide::Left)
-        .collect();
-    let mut right_items: Vec<_> = status_items
-        .iter()
-        .filter(|item| item.side == StatusSide::Right)
-        .collect();
-
-    // Sort by priority (higher first)
-    left_items.sort_by(|a, b| b.priority.cmp(&a.priority));
-    right_items.sort_by(|a, b| b.priority.cmp(&a.priority));
-
-    // Convert to RenderItems
-    let left_render: Vec<RenderItem> = left_items
-        .iter()
-        .flat_map(|item| {
-            vec![
-                RenderItem::Text(item.content.clone()),
-                RenderItem::Padding(1),
-            ]
+        .map(|item| StatusSegment {
+            plugin: item.plugin_id.clone(),
+            item_id: item.item_id,
+            side: item.side,
+            priority: item.priority,
+            items: vec![RenderItem::Text(item.content.clone())],
         })
         .collect();
+    // Keep a stable order so unchanged items don't trigger a redraw
+    segments.sort_by_key(|segment| segment.item_id);
 
-    let right_render: Vec<RenderItem> = right_items
-        .iter()
-        .flat_map(|item| {
-            vec![
-                RenderItem::Text(item.content.clone()),
-                RenderItem::Padding(1),
-            ]
-        })
-        .collect();
-
-    // Update status bar if we have items
-    if !left_render.is_empty() {
-        status_bar.set_left(left_render);
-    }
-    if !right_render.is_empty() {
-        status_bar.set_right(right_render);
-    }
+    status_bar.set_local_segments(segments);
 }
 
 #[cfg(test)]
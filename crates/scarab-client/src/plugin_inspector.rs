@@ -17,6 +17,9 @@ use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
 use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::plugin_log_viewer::{
+    record_plugin_logs, render_plugin_log_viewer, PluginLogBuffer, PluginLogViewerState,
+};
 
 /// Maximum number of log entries to retain
 const MAX_LOG_ENTRIES: usize = 1000;
@@ -273,6 +276,7 @@ pub fn toggle_inspector_input(
 pub fn render_inspector_ui(
     mut contexts: EguiContexts,
     mut state: ResMut<PluginInspectorState>,
+    mut log_viewer: ResMut<PluginLogViewerState>,
     ipc: Option<Res<IpcChannel>>,
 ) {
     if !state.visible {
@@ -290,7 +294,7 @@ pub fn render_inspector_ui(
             egui::TopBottomPanel::top("toolbar")
                 .exact_height(40.0)
                 .show_inside(ui, |ui| {
-                    render_toolbar(ui, &mut state, ipc.as_ref());
+                    render_toolbar(ui, &mut state, &mut log_viewer, ipc.as_ref());
                 });
 
             egui::SidePanel::left("plugin_list")
@@ -301,12 +305,17 @@ pub fn render_inspector_ui(
                 });
 
             egui::CentralPanel::default().show_inside(ui, |ui| {
-                render_plugin_details(ui, &mut state, ipc.as_ref());
+                render_plugin_details(ui, &mut state, &mut log_viewer, ipc.as_ref());
             });
         });
 }
 
-fn render_toolbar(ui: &mut egui::Ui, state: &mut PluginInspectorState, ipc: Option<&IpcChannel>) {
+fn render_toolbar(
+    ui: &mut egui::Ui,
+    state: &mut PluginInspectorState,
+    log_viewer: &mut PluginLogViewerState,
+    ipc: Option<&IpcChannel>,
+) {
     ui.horizontal(|ui| {
         ui.heading("Plugin Inspector");
 
@@ -342,6 +351,10 @@ fn render_toolbar(ui: &mut egui::Ui, state: &mut PluginInspectorState, ipc: Opti
             state.add_log(LogLevel::Info, None, "Logs cleared".to_string());
         }
 
+        if ui.button("Plugin Logs").clicked() {
+            log_viewer.visible = !log_viewer.visible;
+        }
+
         if ui.button("Export Debug Info").clicked() {
             export_debug_info(state);
         }
@@ -433,6 +446,7 @@ fn render_plugin_list(ui: &mut egui::Ui, state: &mut PluginInspectorState) {
 fn render_plugin_details(
     ui: &mut egui::Ui,
     state: &mut PluginInspectorState,
+    log_viewer: &mut PluginLogViewerState,
     ipc: Option<&IpcChannel>,
 ) {
     let Some(plugin) = state.selected_plugin() else {
@@ -460,7 +474,7 @@ fn render_plugin_details(
         egui::ScrollArea::vertical()
             .auto_shrink([false; 2])
             .show(ui, |ui| match state.selected_tab {
-                InspectorTab::Overview => render_overview_tab(ui, &plugin, state, ipc),
+                InspectorTab::Overview => render_overview_tab(ui, &plugin, state, log_viewer, ipc),
                 InspectorTab::Metadata => render_metadata_tab(ui, &plugin),
                 InspectorTab::Hooks => render_hooks_tab(ui, &plugin, state),
                 InspectorTab::Logs => render_logs_tab(ui, state),
@@ -473,6 +487,7 @@ fn render_overview_tab(
     ui: &mut egui::Ui,
     plugin: &InspectedPlugin,
     state: &mut PluginInspectorState,
+    log_viewer: &mut PluginLogViewerState,
     ipc: Option<&IpcChannel>,
 ) {
     ui.heading(&plugin.name);
@@ -585,6 +600,10 @@ fn render_overview_tab(
                 );
             }
         }
+
+        if ui.button("View Logs").clicked() {
+            log_viewer.open_for(&plugin.name);
+        }
     });
 }
 
@@ -904,12 +923,16 @@ impl Plugin for PluginInspectorPlugin {
         }
 
         app.insert_resource(PluginInspectorState::new())
+            .init_resource::<PluginLogBuffer>()
+            .init_resource::<PluginLogViewerState>()
             .add_systems(
                 Update,
                 (
                     toggle_inspector_input,
                     render_inspector_ui,
                     handle_plugin_messages,
+                    record_plugin_logs,
+                    render_plugin_log_viewer,
                 ),
             );
 
@@ -0,0 +1,361 @@
+//! Plugin log viewer
+//!
+//! Keeps the most recent `PluginLog` messages from the daemon in a bounded
+//! ring buffer and shows them in a scrollable panel that can be filtered by
+//! plugin, level, and text. The panel is opened from the plugin inspector.
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use scarab_protocol::{DaemonMessage, LogLevel};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::ipc::RemoteMessageEvent;
+
+/// Number of plugin log lines kept by default
+pub const DEFAULT_LOG_CAPACITY: usize = 5000;
+
+/// One line logged by a daemon plugin
+#[derive(Clone, Debug)]
+pub struct PluginLogLine {
+    pub timestamp: Instant,
+    pub plugin: String,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+impl PluginLogLine {
+    /// Line as copied to the clipboard
+    pub fn to_plain_text(&self) -> String {
+        format!(
+            "[{}] {}: {}",
+            level_name(self.level),
+            self.plugin,
+            self.message
+        )
+    }
+}
+
+/// Ring buffer of recent plugin log lines
+///
+/// Once full, each new line drops the oldest one.
+#[derive(Resource, Debug)]
+pub struct PluginLogBuffer {
+    lines: VecDeque<PluginLogLine>,
+    capacity: usize,
+}
+
+impl Default for PluginLogBuffer {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_LOG_CAPACITY)
+    }
+}
+
+impl PluginLogBuffer {
+    /// Create a buffer holding at most `capacity` lines
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity.min(DEFAULT_LOG_CAPACITY)),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Append a line, dropping the oldest if the buffer is full
+    pub fn push(&mut self, plugin: impl Into<String>, level: LogLevel, message: impl Into<String>) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(PluginLogLine {
+            timestamp: Instant::now(),
+            plugin: plugin.into(),
+            level,
+            message: message.into(),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Lines oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &PluginLogLine> {
+        self.lines.iter()
+    }
+
+    /// Names of plugins with lines in the buffer, sorted
+    pub fn plugins(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.lines.iter().map(|l| l.plugin.clone()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Lines that pass `filter`, oldest first
+    pub fn filtered<'a>(
+        &'a self,
+        filter: &'a PluginLogFilter,
+    ) -> impl Iterator<Item = &'a PluginLogLine> {
+        self.lines.iter().filter(move |line| filter.matches(line))
+    }
+}
+
+/// Which lines the viewer shows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginLogFilter {
+    /// Only show this plugin's lines
+    pub plugin: Option<String>,
+    /// Least severe level shown
+    pub min_level: LogLevel,
+    /// Case-insensitive text the message must contain
+    pub text: String,
+}
+
+impl Default for PluginLogFilter {
+    fn default() -> Self {
+        Self {
+            plugin: None,
+            min_level: LogLevel::Debug,
+            text: String::new(),
+        }
+    }
+}
+
+impl PluginLogFilter {
+    pub fn matches(&self, line: &PluginLogLine) -> bool {
+        if self.plugin.as_ref().is_some_and(|p| *p != line.plugin) {
+            return false;
+        }
+        if severity(line.level) < severity(self.min_level) {
+            return false;
+        }
+        self.text.is_empty()
+            || line
+                .message
+                .to_lowercase()
+                .contains(&self.text.to_lowercase())
+    }
+}
+
+/// State of the log viewer window
+#[derive(Resource, Debug)]
+pub struct PluginLogViewerState {
+    pub visible: bool,
+    pub filter: PluginLogFilter,
+    /// Keep the newest line in view as lines arrive
+    pub follow: bool,
+}
+
+impl Default for PluginLogViewerState {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            filter: PluginLogFilter::default(),
+            follow: true,
+        }
+    }
+}
+
+impl PluginLogViewerState {
+    /// Show the viewer, limited to one plugin's lines
+    pub fn open_for(&mut self, plugin: &str) {
+        self.visible = true;
+        self.filter.plugin = Some(plugin.to_string());
+    }
+}
+
+fn severity(level: LogLevel) -> u8 {
+    match level {
+        LogLevel::Debug => 0,
+        LogLevel::Info => 1,
+        LogLevel::Warn => 2,
+        LogLevel::Error => 3,
+    }
+}
+
+fn level_name(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Debug => "DEBUG",
+        LogLevel::Info => "INFO",
+        LogLevel::Warn => "WARN",
+        LogLevel::Error => "ERROR",
+    }
+}
+
+fn level_color(level: LogLevel) -> egui::Color32 {
+    match level {
+        LogLevel::Debug => egui::Color32::from_rgb(150, 150, 255),
+        LogLevel::Info => egui::Color32::WHITE,
+        LogLevel::Warn => egui::Color32::from_rgb(255, 200, 0),
+        LogLevel::Error => egui::Color32::from_rgb(255, 80, 80),
+    }
+}
+
+fn copy_to_clipboard(text: String) {
+    match arboard::Clipboard::new() {
+        Ok(mut clipboard) => {
+            if let Err(e) = clipboard.set_text(text) {
+                log::warn!("Failed to copy plugin logs: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Clipboard unavailable: {}", e),
+    }
+}
+
+/// System to store plugin logs sent by the daemon
+pub fn record_plugin_logs(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut buffer: ResMut<PluginLogBuffer>,
+) {
+    for event in events.read() {
+        if let DaemonMessage::PluginLog {
+            plugin_name,
+            level,
+            message,
+        } = &event.0
+        {
+            buffer.push(plugin_name.as_str(), *level, message.as_str());
+        }
+    }
+}
+
+/// System to render the log viewer window
+pub fn render_plugin_log_viewer(
+    mut contexts: EguiContexts,
+    mut state: ResMut<PluginLogViewerState>,
+    mut buffer: ResMut<PluginLogBuffer>,
+) {
+    if !state.visible {
+        return;
+    }
+
+    let state = &mut *state;
+    let mut open = true;
+
+    egui::Window::new("Plugin Logs")
+        .open(&mut open)
+        .resizable(true)
+        .default_width(800.0)
+        .default_height(400.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let selected = state.filter.plugin.as_deref().unwrap_or("All plugins");
+                egui::ComboBox::from_id_salt("plugin_log_plugin")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut state.filter.plugin, None, "All plugins");
+                        for name in buffer.plugins() {
+                            let label = name.clone();
+                            ui.selectable_value(&mut state.filter.plugin, Some(name), label);
+                        }
+                    });
+
+                egui::ComboBox::from_id_salt("plugin_log_level")
+                    .selected_text(level_name(state.filter.min_level))
+                    .show_ui(ui, |ui| {
+                        for level in [
+                            LogLevel::Debug,
+                            LogLevel::Info,
+                            LogLevel::Warn,
+                            LogLevel::Error,
+                        ] {
+                            ui.selectable_value(
+                                &mut state.filter.min_level,
+                                level,
+                                level_name(level),
+                            );
+                        }
+                    });
+
+                ui.label("Filter:");
+                ui.text_edit_singleline(&mut state.filter.text);
+                ui.checkbox(&mut state.follow, "Follow");
+
+                if ui.button("Copy").clicked() {
+                    let text: Vec<String> = buffer
+                        .filtered(&state.filter)
+                        .map(PluginLogLine::to_plain_text)
+                        .collect();
+                    copy_to_clipboard(text.join("\n"));
+                }
+                if ui.button("Clear").clicked() {
+                    buffer.clear();
+                }
+            });
+
+            ui.separator();
+
+            let lines: Vec<&PluginLogLine> = buffer.filtered(&state.filter).collect();
+            if lines.is_empty() {
+                ui.colored_label(egui::Color32::GRAY, "No plugin logs");
+                return;
+            }
+
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+            egui::ScrollArea::vertical()
+                .auto_shrink([false; 2])
+                .stick_to_bottom(state.follow)
+                .show_rows(ui, row_height, lines.len(), |ui, rows| {
+                    for line in &lines[rows] {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(level_color(line.level), level_name(line.level));
+                            ui.label(format!("{:.1}s", line.timestamp.elapsed().as_secs_f32()));
+                            ui.colored_label(egui::Color32::from_rgb(100, 200, 255), &line.plugin);
+                            ui.label(&line.message).context_menu(|ui| {
+                                if ui.button("Copy line").clicked() {
+                                    copy_to_clipboard(line.to_plain_text());
+                                    ui.close_menu();
+                                }
+                            });
+                        });
+                    }
+                });
+        });
+
+    if !open {
+        state.visible = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_drops_oldest_when_full() {
+        let mut buffer = PluginLogBuffer::with_capacity(2);
+        buffer.push("git", LogLevel::Info, "one");
+        buffer.push("git", LogLevel::Info, "two");
+        buffer.push("git", LogLevel::Info, "three");
+
+        let messages: Vec<_> = buffer.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(messages, vec!["two", "three"]);
+    }
+
+    #[test]
+    fn test_filter_by_plugin_level_and_text() {
+        let mut buffer = PluginLogBuffer::default();
+        buffer.push("git", LogLevel::Debug, "polling repo");
+        buffer.push("git", LogLevel::Error, "Repo not found");
+        buffer.push("weather", LogLevel::Error, "timeout");
+
+        let filter = PluginLogFilter {
+            plugin: Some("git".to_string()),
+            min_level: LogLevel::Warn,
+            text: "repo".to_string(),
+        };
+        let lines: Vec<_> = buffer.filtered(&filter).collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].to_plain_text(), "[ERROR] git: Repo not found");
+
+        assert_eq!(buffer.filtered(&PluginLogFilter::default()).count(), 3);
+        assert_eq!(buffer.plugins(), vec!["git", "weather"]);
+    }
+}
@@ -164,7 +164,7 @@ pub struct PromptZoneFocusedEvent {
 /// - PromptStart (0): Blue - indicates a new prompt
 /// - CommandFinished (3): Green (success) or Red (failure) - based on exit code
 /// - Other types: Gray - less important markers
-fn marker_color(marker_type: u8, exit_code: Option<i32>) -> Color {
+pub(crate) fn marker_color(marker_type: u8, exit_code: Option<i32>) -> Color {
     match marker_type {
         0 => Color::srgb(0.3, 0.7, 1.0), // PromptStart - blue
         3 => {
@@ -485,7 +485,7 @@ pub fn receive_prompt_markers(
             markers: new_markers,
         } = &event.0
         {
-            println!("Received {} prompt markers from daemon", new_markers.len());
+            debug!("Received {} prompt markers from daemon", new_markers.len());
             markers.update_markers(new_markers.clone());
         }
     }
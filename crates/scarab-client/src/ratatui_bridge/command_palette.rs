@@ -186,6 +186,24 @@ fn default_commands() -> Vec<PaletteCommand> {
             description: Some("Decrease font size".into()),
             shortcut: Some("Ctrl+-".into()),
         },
+        PaletteCommand {
+            id: "zoom_reset".into(),
+            label: "Reset Zoom".into(),
+            description: Some("Restore the configured font size".into()),
+            shortcut: Some("Ctrl+0".into()),
+        },
+        PaletteCommand {
+            id: "screenshot".into(),
+            label: "Save Screenshot".into(),
+            description: Some("Save the screen or selection as a PNG".into()),
+            shortcut: None,
+        },
+        PaletteCommand {
+            id: "copy_as_image".into(),
+            label: "Copy as Image".into(),
+            description: Some("Copy the screen or selection to the clipboard as an image".into()),
+            shortcut: None,
+        },
     ]
 }
 
@@ -6,6 +6,7 @@
 use bevy::input::keyboard::KeyboardInput;
 use bevy::input::mouse::{MouseButton, MouseButtonInput};
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use crossterm::event::{
     Event as RatEvent, KeyCode as RatKeyCode, KeyEvent, KeyModifiers,
     MouseButton as RatMouseButton, MouseEvent, MouseEventKind,
@@ -258,7 +259,7 @@ pub fn handle_keyboard_input(
 /// Surfaces with higher z-index take priority for mouse events.
 pub fn handle_mouse_input(
     mut mouse_button: EventReader<MouseButtonInput>,
-    windows: Query<&Window>,
+    windows: Query<&Window, With<PrimaryWindow>>,
     metrics: Res<TerminalMetrics>,
     mut focus: ResMut<SurfaceFocus>,
     surfaces: Query<(Entity, &RatatuiSurface)>,
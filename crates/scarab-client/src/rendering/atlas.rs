@@ -63,24 +63,176 @@ impl AtlasRect {
     }
 }
 
+/// Granularity of shelf heights, so glyphs of similar height share shelves
+const SHELF_HEIGHT_STEP: u32 = 8;
+
+/// A horizontal strip of the atlas holding glyphs of similar height
+#[derive(Debug, Clone)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    next_x: u32,
+    /// Frame tick in which a glyph on this shelf was last used
+    last_used: u64,
+}
+
+/// Where a glyph was placed by the `ShelfAllocator`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShelfAllocation {
+    pub x: u32,
+    pub y: u32,
+    pub shelf: usize,
+    /// Whether the shelf's previous glyphs were evicted to make room
+    pub evicted: bool,
+}
+
+/// Shelf packer with least-recently-used eviction
+///
+/// Glyphs are packed left to right on shelves. Once the atlas is full, the
+/// shelf that has gone unused the longest is emptied and reused. Shelves used
+/// in the current tick are never evicted, so everything drawn in one frame
+/// stays valid for that frame.
+#[derive(Debug, Clone)]
+pub struct ShelfAllocator {
+    size: u32,
+    shelves: Vec<Shelf>,
+    next_y: u32,
+}
+
+impl ShelfAllocator {
+    pub fn new(size: u32) -> Self {
+        Self {
+            size,
+            shelves: Vec::new(),
+            next_y: GLYPH_PADDING,
+        }
+    }
+
+    /// Allocate space for a glyph, evicting the least recently used shelf if needed
+    pub fn allocate(&mut self, width: u32, height: u32, tick: u64) -> Option<ShelfAllocation> {
+        let padded_width = width + GLYPH_PADDING * 2;
+        let padded_height = height + GLYPH_PADDING * 2;
+        if padded_width > self.size {
+            return None;
+        }
+
+        // Best fit: the shortest shelf with room left
+        let existing = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.height >= padded_height && s.next_x + padded_width <= self.size)
+            .min_by_key(|(_, s)| s.height)
+            .map(|(i, _)| i);
+        if let Some(shelf) = existing {
+            return Some(self.place(shelf, padded_width, tick, false));
+        }
+
+        // Open a new shelf below the last one
+        let shelf_height = padded_height.div_ceil(SHELF_HEIGHT_STEP) * SHELF_HEIGHT_STEP;
+        if self.next_y + shelf_height <= self.size {
+            self.shelves.push(Shelf {
+                y: self.next_y,
+                height: shelf_height,
+                next_x: GLYPH_PADDING,
+                last_used: tick,
+            });
+            self.next_y += shelf_height;
+            return Some(self.place(self.shelves.len() - 1, padded_width, tick, false));
+        }
+
+        // Atlas full: recycle the least recently used shelf that is tall enough
+        let victim = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.height >= padded_height && s.last_used < tick)
+            .min_by_key(|(_, s)| s.last_used)
+            .map(|(i, _)| i)?;
+        self.shelves[victim].next_x = GLYPH_PADDING;
+        Some(self.place(victim, padded_width, tick, true))
+    }
+
+    fn place(
+        &mut self,
+        shelf: usize,
+        padded_width: u32,
+        tick: u64,
+        evicted: bool,
+    ) -> ShelfAllocation {
+        let s = &mut self.shelves[shelf];
+        let allocation = ShelfAllocation {
+            x: s.next_x,
+            y: s.y,
+            shelf,
+            evicted,
+        };
+        s.next_x += padded_width;
+        s.last_used = tick;
+        allocation
+    }
+
+    /// Record that a glyph on a shelf was used this tick
+    pub fn touch(&mut self, shelf: usize, tick: u64) {
+        if let Some(s) = self.shelves.get_mut(shelf) {
+            s.last_used = tick;
+        }
+    }
+
+    /// Vertical extent of the atlas in use
+    pub fn used_height(&self) -> u32 {
+        self.next_y
+    }
+
+    /// Y range covered by a shelf
+    fn shelf_rows(&self, shelf: usize) -> (u32, u32) {
+        let s = &self.shelves[shelf];
+        (s.y, s.y + s.height)
+    }
+
+    /// Drop every shelf
+    pub fn reset(&mut self) {
+        self.shelves.clear();
+        self.next_y = GLYPH_PADDING;
+    }
+}
+
+/// Cached glyph placement and the shelf holding it
+#[derive(Debug, Clone, Copy)]
+struct AtlasEntry {
+    rect: AtlasRect,
+    shelf: usize,
+}
+
 /// Glyph atlas for caching rasterized glyphs
+///
+/// The atlas lives for the whole session. When it fills up, glyphs on the
+/// least recently used shelf are evicted and `generation()` advances, which
+/// tells cached meshes that their UVs may be stale.
 pub struct GlyphAtlas {
     /// Texture handle for the atlas
     pub texture: Handle<Image>,
 
     /// Map from glyph key to atlas position
-    glyph_positions: HashMap<GlyphKey, AtlasRect>,
+    glyph_positions: HashMap<GlyphKey, AtlasEntry>,
 
-    /// Current packing position
-    current_x: u32,
-    current_y: u32,
-    current_row_height: u32,
+    /// Shelf packer
+    allocator: ShelfAllocator,
+
+    /// Current frame tick for LRU bookkeeping
+    tick: u64,
+
+    /// Bumped whenever glyphs are evicted
+    generation: u64,
+
+    /// Total number of glyphs evicted
+    evictions: u64,
 
     /// Raw texture data (RGBA8)
     texture_data: Vec<u8>,
 
-    /// Whether the texture needs updating
-    dirty: bool,
+    /// Rows of the texture (start, end) that changed since the last upload
+    dirty_rows: Option<(u32, u32)>,
 }
 
 impl GlyphAtlas {
@@ -114,11 +266,12 @@ impl GlyphAtlas {
         let mut atlas = Self {
             texture,
             glyph_positions: HashMap::new(),
-            current_x: GLYPH_PADDING,
-            current_y: GLYPH_PADDING,
-            current_row_height: 0,
+            allocator: ShelfAllocator::new(ATLAS_SIZE),
+            tick: 1,
+            generation: 0,
+            evictions: 0,
             texture_data: vec![0; (ATLAS_SIZE * ATLAS_SIZE * 4) as usize],
-            dirty: false,
+            dirty_rows: None,
         };
 
         // Reserve white pixel for solid colors
@@ -135,7 +288,7 @@ impl GlyphAtlas {
         self.texture_data[idx + 1] = 255;
         self.texture_data[idx + 2] = 255;
         self.texture_data[idx + 3] = 255;
-        self.dirty = true;
+        self.mark_rows_dirty(0, 1);
     }
 
     /// Get UV coordinates for the white pixel
@@ -147,6 +300,19 @@ impl GlyphAtlas {
         [half_pixel, half_pixel, half_pixel, half_pixel]
     }
 
+    /// Start a new frame for LRU bookkeeping
+    ///
+    /// Glyphs looked up after this call are protected from eviction until
+    /// the next call.
+    pub fn begin_frame(&mut self) {
+        self.tick += 1;
+    }
+
+    /// Counter that advances whenever cached glyphs are evicted
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
     /// Get or cache a glyph in the atlas
     pub fn get_or_cache(
         &mut self,
@@ -155,8 +321,9 @@ impl GlyphAtlas {
         swash_cache: &mut SwashCache,
     ) -> Option<AtlasRect> {
         // Check if already cached
-        if let Some(rect) = self.glyph_positions.get(&glyph_key) {
-            return Some(*rect);
+        if let Some(entry) = self.glyph_positions.get(&glyph_key) {
+            self.allocator.touch(entry.shelf, self.tick);
+            return Some(entry.rect);
         }
 
         // Rasterize the glyph using cosmic-text
@@ -182,79 +349,68 @@ impl GlyphAtlas {
 
         let image = image.unwrap();
 
-        // Check if we have space in the atlas
         let glyph_width = image.placement.width as u32;
         let glyph_height = image.placement.height as u32;
 
-        if !self.can_fit(glyph_width, glyph_height) {
-            warn!("Atlas full! Consider implementing dynamic atlas expansion");
+        let Some(allocation) = self
+            .allocator
+            .allocate(glyph_width, glyph_height, self.tick)
+        else {
+            warn!("Atlas full and every shelf is in use this frame");
             return None;
+        };
+
+        if allocation.evicted {
+            self.evict_shelf(allocation.shelf);
         }
 
-        // Pack the glyph with placement offsets for proper positioning
-        let rect = self.pack_glyph(
-            glyph_width,
-            glyph_height,
-            image.placement.left,
-            image.placement.top,
-        );
+        let rect = AtlasRect {
+            x: allocation.x,
+            y: allocation.y,
+            width: glyph_width,
+            height: glyph_height,
+            placement_left: image.placement.left,
+            placement_top: image.placement.top,
+        };
 
         // Copy glyph data to atlas
         self.copy_glyph_data(image, &rect);
+        self.mark_rows_dirty(rect.y, rect.y + rect.height);
 
         // Cache the position
-        self.glyph_positions.insert(glyph_key, rect);
-        self.dirty = true;
+        self.glyph_positions.insert(
+            glyph_key,
+            AtlasEntry {
+                rect,
+                shelf: allocation.shelf,
+            },
+        );
 
         Some(rect)
     }
 
-    /// Check if a glyph can fit in the atlas
-    fn can_fit(&self, width: u32, height: u32) -> bool {
-        let padded_width = width + GLYPH_PADDING * 2;
-        let padded_height = height + GLYPH_PADDING * 2;
+    /// Forget every glyph on a recycled shelf and clear its pixels
+    fn evict_shelf(&mut self, shelf: usize) {
+        let before = self.glyph_positions.len();
+        self.glyph_positions.retain(|_, entry| entry.shelf != shelf);
+        let evicted = (before - self.glyph_positions.len()) as u64;
 
-        // Try current row
-        if self.current_x + padded_width <= ATLAS_SIZE {
-            return true;
-        }
+        let (start, end) = self.allocator.shelf_rows(shelf);
+        let row_bytes = (ATLAS_SIZE * 4) as usize;
+        self.texture_data[start as usize * row_bytes..end as usize * row_bytes].fill(0);
+        self.mark_rows_dirty(start, end);
 
-        // Try next row
-        let next_y = self.current_y + self.current_row_height + GLYPH_PADDING;
-        next_y + padded_height <= ATLAS_SIZE
+        self.evictions += evicted;
+        self.generation += 1;
+        debug!("Atlas evicted {} glyphs from shelf {}", evicted, shelf);
     }
 
-    /// Pack a glyph into the atlas and return its rect with placement info
-    fn pack_glyph(
-        &mut self,
-        width: u32,
-        height: u32,
-        placement_left: i32,
-        placement_top: i32,
-    ) -> AtlasRect {
-        let padded_width = width + GLYPH_PADDING * 2;
-        let padded_height = height + GLYPH_PADDING * 2;
-
-        // Check if we need to move to next row
-        if self.current_x + padded_width > ATLAS_SIZE {
-            self.current_x = GLYPH_PADDING;
-            self.current_y += self.current_row_height + GLYPH_PADDING;
-            self.current_row_height = 0;
-        }
-
-        let rect = AtlasRect {
-            x: self.current_x,
-            y: self.current_y,
-            width,
-            height,
-            placement_left,
-            placement_top,
-        };
-
-        self.current_x += padded_width;
-        self.current_row_height = self.current_row_height.max(padded_height);
-
-        rect
+    /// Extend the range of texture rows awaiting upload
+    fn mark_rows_dirty(&mut self, start: u32, end: u32) {
+        self.dirty_rows = Some(match self.dirty_rows {
+            Some((s, e)) => (s.min(start), e.max(end)),
+            None => (start, end),
+        });
     }
 
     /// Copy glyph image data to the atlas texture
@@ -309,32 +465,33 @@ impl GlyphAtlas {
     }
 
     /// Update the GPU texture if dirty
+    ///
+    /// Only the rows touched since the last upload are copied.
     pub fn update_texture(&mut self, images: &mut Assets<Image>) {
-        if !self.dirty {
+        let Some((start, end)) = self.dirty_rows.take() else {
             return;
-        }
+        };
 
         if let Some(image) = images.get_mut(&self.texture) {
-            image.data.copy_from_slice(&self.texture_data);
+            let row_bytes = (ATLAS_SIZE * 4) as usize;
+            let range = start as usize * row_bytes..(end.min(ATLAS_SIZE) as usize) * row_bytes;
+            image.data[range.clone()].copy_from_slice(&self.texture_data[range]);
         }
-
-        self.dirty = false;
     }
 
     /// Clear the atlas (for debugging/testing)
     pub fn clear(&mut self) {
         self.glyph_positions.clear();
-        self.current_x = GLYPH_PADDING;
-        self.current_y = GLYPH_PADDING;
-        self.current_row_height = 0;
+        self.allocator.reset();
         self.texture_data.fill(0);
+        self.generation += 1;
         self.reserve_white_pixel();
-        self.dirty = true;
+        self.mark_rows_dirty(0, ATLAS_SIZE);
     }
 
     /// Get atlas statistics
     pub fn stats(&self) -> AtlasStats {
-        let used_height = self.current_y + self.current_row_height;
+        let used_height = self.allocator.used_height();
         let total_pixels = ATLAS_SIZE * ATLAS_SIZE;
         let used_pixels = used_height * ATLAS_SIZE;
 
@@ -344,6 +501,7 @@ impl GlyphAtlas {
             total_height: ATLAS_SIZE,
             occupancy: used_pixels as f32 / total_pixels as f32,
             memory_mb: (self.texture_data.len() as f32) / (1024.0 * 1024.0),
+            evictions: self.evictions,
         }
     }
 }
@@ -356,4 +514,54 @@ pub struct AtlasStats {
     pub total_height: u32,
     pub occupancy: f32,
     pub memory_mb: f32,
+    pub evictions: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shelf_allocator_packs_rows() {
+        let mut allocator = ShelfAllocator::new(64);
+
+        let a = allocator.allocate(10, 10, 1).unwrap();
+        let b = allocator.allocate(10, 10, 1).unwrap();
+        assert_eq!(a.shelf, b.shelf);
+        assert_eq!(b.x, a.x + 10 + GLYPH_PADDING * 2);
+        assert!(!a.evicted && !b.evicted);
+
+        // A taller glyph opens a new shelf below
+        let c = allocator.allocate(10, 20, 1).unwrap();
+        assert_ne!(c.shelf, a.shelf);
+        assert!(c.y > a.y);
+    }
+
+    #[test]
+    fn test_shelf_allocator_evicts_least_recently_used() {
+        let mut allocator = ShelfAllocator::new(64);
+
+        // Fill the atlas with three shelves of 16px, one glyph each
+        let shelves: Vec<_> = (0..3)
+            .map(|tick| allocator.allocate(40, 10, tick + 1))
+            .map(|a| a.filter(|a| !a.evicted).unwrap().shelf)
+            .collect();
+
+        // Shelf 0 is used again, so shelf 1 is now the oldest
+        allocator.touch(shelves[0], 4);
+        let recycled = allocator.allocate(40, 10, 5).unwrap();
+        assert!(recycled.evicted);
+        assert_eq!(recycled.shelf, shelves[1]);
+    }
+
+    #[test]
+    fn test_shelf_allocator_protects_current_tick() {
+        let mut allocator = ShelfAllocator::new(40);
+        allocator.allocate(20, 10, 1).unwrap();
+        allocator.allocate(20, 10, 1).unwrap();
+
+        // Every shelf was used this tick, so nothing can be evicted
+        assert!(allocator.allocate(20, 10, 1).is_none());
+        assert!(allocator.allocate(20, 10, 2).is_some_and(|a| a.evicted));
+    }
 }
@@ -0,0 +1,466 @@
+// Window translucency, blur-behind, and background images
+//
+// Layers under the cell grid, back to front:
+// 1. Theme background (TerminalBackgroundEntity), alpha from `colors.opacity`,
+//    in the focused pane's own theme when the daemon gave it one
+// 2. Optional background image, scaled per `ui.background_image_fit`
+// 3. Dim layer in the theme background color, so text over a busy image
+//    stays legible
+//
+// Translucency needs a transparent window surface, which main.rs requests when
+// the config asks for it. Blur-behind is left to the compositor and is skipped
+// in low-power mode, matching the post-process effects.
+
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::window::{PrimaryWindow, RequestRedraw, WindowResized};
+use bevy::winit::WinitWindows;
+use scarab_config::{BackgroundImageFit, ScarabConfig};
+use scarab_protocol::{DaemonMessage, PanePalette};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::config::color;
+use super::layers::LAYER_TERMINAL_BG;
+use super::theme_transition::ColorFade;
+use crate::integration::TerminalBackgroundEntity;
+use crate::ipc::RemoteMessageEvent;
+use crate::ui::PaneLayout;
+
+/// Z offset of the background image (above the theme background sprite)
+const Z_IMAGE: f32 = LAYER_TERMINAL_BG - 0.008;
+
+/// Z offset of the dim layer (above the image, below cell backgrounds)
+const Z_DIM: f32 = LAYER_TERMINAL_BG - 0.006;
+
+/// Background of panes without a theme of their own (Slime dark #0d1208)
+const WINDOW_BG: u32 = 0xFF0D1208;
+
+/// Plugin that applies window opacity, blur-behind, and background images
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackgroundState>()
+            .init_resource::<PanePalettes>()
+            .init_resource::<PaneLayout>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(
+                Update,
+                (
+                    apply_background_config_system,
+                    load_background_image_system,
+                    layout_background_image_system,
+                    apply_pane_palette_system,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Marker for the background image sprite
+#[derive(Component)]
+pub struct BackgroundImageEntity;
+
+/// Marker for the dim layer drawn over the background image
+#[derive(Component)]
+pub struct BackgroundDimEntity;
+
+/// Background image currently on screen
+#[derive(Resource, Default)]
+pub struct BackgroundState {
+    /// Configured path the current image was loaded from
+    path: Option<String>,
+    /// Native image size in pixels
+    image_size: Vec2,
+    /// Whether blur-behind was last requested from the window
+    blur: Option<bool>,
+}
+
+/// Themes the daemon gave single panes
+#[derive(Resource, Debug, Default)]
+pub struct PanePalettes {
+    palettes: HashMap<u64, PanePalette>,
+}
+
+impl PanePalettes {
+    /// Apply a daemon message, returning true if any palette changed
+    pub fn apply(&mut self, msg: &DaemonMessage) -> bool {
+        match msg {
+            DaemonMessage::PanePaletteUpdate {
+                pane_id,
+                palette: Some(palette),
+            } => {
+                self.palettes.insert(*pane_id, palette.clone());
+                true
+            }
+            DaemonMessage::PanePaletteUpdate { pane_id, .. }
+            | DaemonMessage::PaneClosed { pane_id } => self.palettes.remove(pane_id).is_some(),
+            _ => false,
+        }
+    }
+
+    /// A pane's own palette, if it has one
+    pub fn get(&self, pane_id: u64) -> Option<&PanePalette> {
+        self.palettes.get(&pane_id)
+    }
+
+    /// Background to draw for a pane, as `0xAARRGGBB`
+    pub fn background(&self, pane_id: Option<u64>) -> u32 {
+        pane_id
+            .and_then(|id| self.get(id))
+            .map_or(WINDOW_BG, |palette| palette.background)
+    }
+}
+
+/// Whether the window surface must support alpha for this config
+pub fn window_needs_transparency(config: &ScarabConfig) -> bool {
+    config.colors.opacity < 1.0
+}
+
+/// Size of the image sprite for a window, per the configured fit
+///
+/// `Cover` may overflow the window; the overflow lands off-screen since the
+/// sprite is centered.
+pub fn fit_image_size(image: Vec2, window: Vec2, fit: BackgroundImageFit) -> Vec2 {
+    if image.x <= 0.0 || image.y <= 0.0 {
+        return window;
+    }
+
+    let scale_x = window.x / image.x;
+    let scale_y = window.y / image.y;
+    match fit {
+        BackgroundImageFit::Cover => image * scale_x.max(scale_y),
+        BackgroundImageFit::Contain => image * scale_x.min(scale_y),
+        BackgroundImageFit::Stretch => window,
+        BackgroundImageFit::Center => image,
+    }
+}
+
+/// Apply opacity and blur-behind when the config changes
+fn apply_background_config_system(
+    config: Option<Res<ScarabConfig>>,
+    mut state: ResMut<BackgroundState>,
+    mut backgrounds: Query<&mut Sprite, With<TerminalBackgroundEntity>>,
+    mut cameras: Query<&mut Camera, With<Camera2d>>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    winit_windows: Option<NonSend<WinitWindows>>,
+    added: Query<(), Added<TerminalBackgroundEntity>>,
+) {
+    let Some(config) = config else {
+        return;
+    };
+    if !config.is_changed() && added.is_empty() {
+        return;
+    }
+
+    let opacity = config.colors.opacity.clamp(0.0, 1.0);
+    for mut sprite in backgrounds.iter_mut() {
+        sprite.color.set_alpha(opacity);
+    }
+
+    // An opaque clear color would show through a translucent background sprite
+    if opacity < 1.0 {
+        for mut camera in cameras.iter_mut() {
+            camera.clear_color = ClearColorConfig::Custom(Color::NONE);
+        }
+    }
+
+    let blur = config.ui.background_blur && opacity < 1.0 && !config.effects.low_power_mode;
+    if state.blur != Some(blur) {
+        let window = primary_window
+            .get_single()
+            .ok()
+            .zip(winit_windows.as_ref())
+            .and_then(|(entity, windows)| windows.get_window(entity));
+        if let Some(window) = window {
+            // Only some platforms (macOS, KDE Wayland) honor this; others ignore it
+            window.set_blur(blur);
+            state.blur = Some(blur);
+            debug!(
+                "Window blur-behind {}",
+                if blur { "enabled" } else { "disabled" }
+            );
+        }
+    }
+}
+
+/// Load, replace, or remove the background image and dim layer
+fn load_background_image_system(
+    mut commands: Commands,
+    config: Option<Res<ScarabConfig>>,
+    mut state: ResMut<BackgroundState>,
+    mut images: ResMut<Assets<Image>>,
+    backgrounds: Query<&Sprite, With<TerminalBackgroundEntity>>,
+    mut image_sprites: Query<
+        (Entity, &mut Sprite),
+        (
+            With<BackgroundImageEntity>,
+            Without<TerminalBackgroundEntity>,
+            Without<BackgroundDimEntity>,
+        ),
+    >,
+    mut dim_sprites: Query<
+        (Entity, &mut Sprite),
+        (
+            With<BackgroundDimEntity>,
+            Without<TerminalBackgroundEntity>,
+            Without<BackgroundImageEntity>,
+        ),
+    >,
+) {
+    let Some(config) = config else {
+        return;
+    };
+    if !config.is_changed() {
+        return;
+    }
+
+    let ui = &config.ui;
+    let theme_bg = backgrounds
+        .iter()
+        .next()
+        .map(|sprite| sprite.color.with_alpha(1.0))
+        .unwrap_or(Color::BLACK);
+
+    if state.path != ui.background_image {
+        for (entity, _) in image_sprites.iter().chain(dim_sprites.iter()) {
+            commands.entity(entity).despawn_recursive();
+        }
+        state.path = ui.background_image.clone();
+
+        let Some(path) = ui.background_image.as_deref() else {
+            return;
+        };
+
+        let Some(image) = load_image(path) else {
+            return;
+        };
+        state.image_size = image.size().as_vec2();
+        let handle = images.add(image);
+
+        commands.spawn((
+            BackgroundImageEntity,
+            Sprite {
+                image: handle,
+                color: Color::WHITE.with_alpha(ui.background_image_opacity),
+                custom_size: Some(state.image_size),
+                ..default()
+            },
+            Transform::from_xyz(0.0, 0.0, Z_IMAGE),
+        ));
+        commands.spawn((
+            BackgroundDimEntity,
+            Sprite {
+                color: theme_bg.with_alpha(ui.background_dim),
+                ..default()
+            },
+            Transform::from_xyz(0.0, 0.0, Z_DIM),
+        ));
+        return;
+    }
+
+    for (_, mut sprite) in image_sprites.iter_mut() {
+        sprite.color = Color::WHITE.with_alpha(ui.background_image_opacity);
+    }
+    for (_, mut sprite) in dim_sprites.iter_mut() {
+        sprite.color = theme_bg.with_alpha(ui.background_dim);
+    }
+}
+
+/// Resize the image and dim layers to the window and configured fit
+fn layout_background_image_system(
+    config: Option<Res<ScarabConfig>>,
+    state: Res<BackgroundState>,
+    mut resize_events: EventReader<WindowResized>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut image_sprites: Query<
+        &mut Sprite,
+        (With<BackgroundImageEntity>, Without<BackgroundDimEntity>),
+    >,
+    mut dim_sprites: Query<
+        &mut Sprite,
+        (With<BackgroundDimEntity>, Without<BackgroundImageEntity>),
+    >,
+    added: Query<(), Added<BackgroundImageEntity>>,
+) {
+    let Some(config) = config else {
+        return;
+    };
+    let resized = resize_events.read().count() > 0;
+    if !resized && !config.is_changed() && added.is_empty() {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    let window_size = Vec2::new(window.width(), window.height());
+    let image_size = fit_image_size(
+        state.image_size,
+        window_size,
+        config.ui.background_image_fit,
+    );
+
+    for mut sprite in image_sprites.iter_mut() {
+        sprite.custom_size = Some(image_size);
+    }
+    for mut sprite in dim_sprites.iter_mut() {
+        sprite.custom_size = Some(window_size);
+    }
+}
+
+/// Paint the background in the focused pane's theme
+///
+/// The grid only ever shows the focused pane, so its theme colors the whole
+/// window; panes without one get the window's background back. The color
+/// fades over unless motion is turned off. Opacity and the dim layer's
+/// alpha are kept.
+#[allow(clippy::too_many_arguments)]
+fn apply_pane_palette_system(
+    time: Res<Time>,
+    config: Option<Res<ScarabConfig>>,
+    mut events: EventReader<RemoteMessageEvent>,
+    mut palettes: ResMut<PanePalettes>,
+    layout: Res<PaneLayout>,
+    mut redraw: EventWriter<RequestRedraw>,
+    mut backgrounds: Query<
+        &mut Sprite,
+        (With<TerminalBackgroundEntity>, Without<BackgroundDimEntity>),
+    >,
+    mut dim_sprites: Query<
+        &mut Sprite,
+        (With<BackgroundDimEntity>, Without<TerminalBackgroundEntity>),
+    >,
+    added: Query<(), Or<(Added<TerminalBackgroundEntity>, Added<BackgroundDimEntity>)>>,
+    mut fade: Local<Option<ColorFade>>,
+) {
+    for event in events.read() {
+        palettes.apply(&event.0);
+    }
+
+    let background = palettes.background(layout.focused().map(|pane| pane.id));
+    let target = color::from_rgba(background);
+    let animate = config
+        .as_ref()
+        .map_or(true, |config| config.ui.motion_enabled());
+    let fade = fade.get_or_insert_with(|| ColorFade::new(target));
+
+    let was_fading = fade.is_fading();
+    fade.tick(time.delta_secs());
+    let retargeted = fade.set_target(target, animate);
+    if !was_fading && !retargeted && added.is_empty() {
+        return;
+    }
+    if fade.is_fading() {
+        // The app updates reactively; keep frames coming until the fade ends
+        redraw.send(RequestRedraw);
+    }
+
+    let color = fade.current();
+    for mut sprite in backgrounds.iter_mut().chain(dim_sprites.iter_mut()) {
+        let alpha = sprite.color.alpha();
+        sprite.color = color.with_alpha(alpha);
+    }
+}
+
+/// Decode an image file into a texture, expanding a leading `~`
+fn load_image(path: &str) -> Option<Image> {
+    let resolved = match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    };
+
+    match image::open(&resolved) {
+        Ok(dynamic) => {
+            info!(
+                "Loaded background image {} ({}x{})",
+                resolved.display(),
+                dynamic.width(),
+                dynamic.height()
+            );
+            Some(Image::from_dynamic(
+                dynamic,
+                true,
+                RenderAssetUsages::RENDER_WORLD,
+            ))
+        }
+        Err(e) => {
+            warn!(
+                "Failed to load background image {}: {}",
+                resolved.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_image_size() {
+        let image = Vec2::new(1000.0, 500.0);
+        let window = Vec2::new(800.0, 800.0);
+
+        assert_eq!(
+            fit_image_size(image, window, BackgroundImageFit::Cover),
+            Vec2::new(1600.0, 800.0)
+        );
+        assert_eq!(
+            fit_image_size(image, window, BackgroundImageFit::Contain),
+            Vec2::new(800.0, 400.0)
+        );
+        assert_eq!(
+            fit_image_size(image, window, BackgroundImageFit::Stretch),
+            window
+        );
+        assert_eq!(
+            fit_image_size(image, window, BackgroundImageFit::Center),
+            image
+        );
+        assert_eq!(
+            fit_image_size(Vec2::ZERO, window, BackgroundImageFit::Cover),
+            window
+        );
+    }
+
+    #[test]
+    fn test_pane_palette_background() {
+        let palette = PanePalette {
+            theme: "red-alert".to_string(),
+            foreground: 0xFFFFFFFF,
+            background: 0xFF3A0000,
+            cursor: 0xFFFFFFFF,
+            ansi: [0xFF000000; 16],
+        };
+        let mut palettes = PanePalettes::default();
+        assert!(palettes.apply(&DaemonMessage::PanePaletteUpdate {
+            pane_id: 2,
+            palette: Some(palette),
+        }));
+        assert_eq!(palettes.background(Some(2)), 0xFF3A0000);
+        assert_eq!(palettes.background(Some(1)), WINDOW_BG);
+        assert_eq!(palettes.background(None), WINDOW_BG);
+
+        assert!(palettes.apply(&DaemonMessage::PaneClosed { pane_id: 2 }));
+        assert_eq!(palettes.background(Some(2)), WINDOW_BG);
+        assert!(!palettes.apply(&DaemonMessage::PanePaletteUpdate {
+            pane_id: 2,
+            palette: None,
+        }));
+    }
+
+    #[test]
+    fn test_window_transparency() {
+        let mut config = ScarabConfig::default();
+        assert!(!window_needs_transparency(&config));
+
+        config.colors.opacity = 0.85;
+        assert!(window_needs_transparency(&config));
+    }
+}
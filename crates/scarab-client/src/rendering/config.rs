@@ -1,6 +1,7 @@
 // Font configuration and management
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Font configuration for the text renderer
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +26,12 @@ pub struct FontConfig {
 
     /// Enable subpixel positioning
     pub subpixel: bool,
+
+    /// Shape runs of same-style cells so fonts can form ligatures
+    pub ligatures: bool,
+
+    /// OpenType feature toggles per font family (`"*"` applies to all)
+    pub features: HashMap<String, Vec<String>>,
 }
 
 impl Default for FontConfig {
@@ -42,6 +49,8 @@ impl Default for FontConfig {
             letter_spacing: 0.0,
             hinting: true,
             subpixel: true,
+            ligatures: true,
+            features: HashMap::new(),
         }
     }
 }
@@ -183,4 +192,83 @@ pub mod color {
         let b = (b * 255.0) as u32;
         (a << 24) | (r << 16) | (g << 8) | b
     }
+
+    /// WCAG relative luminance of an ARGB color, ignoring alpha
+    pub fn relative_luminance(argb: u32) -> f32 {
+        let [r, g, b, _] = from_rgba(argb).to_linear().to_f32_array();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// WCAG contrast ratio between two ARGB colors, from 1 to 21
+    pub fn contrast_ratio(a: u32, b: u32) -> f32 {
+        let (a, b) = (relative_luminance(a), relative_luminance(b));
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// `fg`, lightened or darkened as little as needed to reach a contrast
+    /// ratio of `minimum` against `bg`
+    ///
+    /// Moves toward white or black, whichever contrasts more with `bg`; if
+    /// even that falls short, returns it.
+    pub fn with_minimum_contrast(fg: u32, bg: u32, minimum: f32) -> u32 {
+        if contrast_ratio(fg, bg) >= minimum {
+            return fg;
+        }
+        let alpha = fg & 0xFF00_0000;
+        let target = if contrast_ratio(0xFFFF_FFFF, bg) >= contrast_ratio(0xFF00_0000, bg) {
+            0xFF
+        } else {
+            0x00
+        };
+        let mix = |t: f32| {
+            let channel = |shift: u32| {
+                let from = ((fg >> shift) & 0xFF) as f32;
+                let to = target as f32;
+                ((from + (to - from) * t).round() as u32) << shift
+            };
+            alpha | channel(16) | channel(8) | channel(0)
+        };
+
+        // Contrast grows steadily toward the target; find the least mix
+        let (mut low, mut high) = (0.0, 1.0);
+        if contrast_ratio(mix(high), bg) < minimum {
+            return mix(high);
+        }
+        for _ in 0..12 {
+            let mid = (low + high) / 2.0;
+            if contrast_ratio(mix(mid), bg) >= minimum {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        mix(high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::color::*;
+
+    #[test]
+    fn test_with_minimum_contrast() {
+        assert!((contrast_ratio(0xFFFFFFFF, 0xFF000000) - 21.0).abs() < 0.01);
+
+        // Dark blue on the default background is hard to read
+        let bg = 0xFF0D1208;
+        let fg = 0xFF1E3A8A;
+        assert!(contrast_ratio(fg, bg) < 2.0);
+        let adjusted = with_minimum_contrast(fg, bg, 4.5);
+        assert!(contrast_ratio(adjusted, bg) >= 4.5);
+        // Lightened, not swapped for white
+        assert_ne!(adjusted, 0xFFFFFFFF);
+        assert!(relative_luminance(adjusted) > relative_luminance(fg));
+
+        // Light text on a light background is darkened
+        let adjusted = with_minimum_contrast(0xFFE0E0E0, 0xFFFFFFF0, 4.5);
+        assert!(contrast_ratio(adjusted, 0xFFFFFFF0) >= 4.5);
+
+        // Colors that already contrast enough are untouched
+        assert_eq!(with_minimum_contrast(0xFFE0E0E0, bg, 4.5), 0xFFE0E0E0);
+    }
 }
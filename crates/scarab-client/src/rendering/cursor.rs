@@ -0,0 +1,326 @@
+// Terminal cursor rendering
+//
+// Draws the grid cursor as a block, underline, or bar. Applications can pick
+// the shape and blink with DECSCUSR (`CSI Ps SP q`); otherwise the style from
+// `UiConfig` applies. With `cursor_smooth` enabled the cursor glides between
+// cells instead of jumping, and a new theme's cursor color fades in; both
+// are off with `reduce_motion`.
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::window::RequestRedraw;
+use scarab_config::{CursorStyle, ScarabConfig};
+use scarab_protocol::{
+    terminal_state::TerminalStateReader, CURSOR_STYLE_BLINKING_BAR, CURSOR_STYLE_BLINKING_BLOCK,
+    CURSOR_STYLE_BLINKING_UNDERLINE, CURSOR_STYLE_STEADY_BAR, CURSOR_STYLE_STEADY_BLOCK,
+    CURSOR_STYLE_STEADY_UNDERLINE,
+};
+use std::time::Duration;
+
+use super::layers::LAYER_CURSOR;
+use super::smooth_scroll::SmoothScroll;
+use super::text::TextRenderer;
+use super::theme_transition::ColorFade;
+use crate::integration::{SharedMemoryReader, TerminalGridEntity};
+
+/// Width of the bar cursor in pixels
+const BAR_WIDTH: f32 = 2.0;
+
+/// Height of the underline cursor in pixels
+const UNDERLINE_HEIGHT: f32 = 2.0;
+
+/// Opacity of the block cursor so the glyph underneath stays visible
+const BLOCK_ALPHA: f32 = 0.6;
+
+/// Exponential approach rate for smooth movement (higher is snappier)
+const SMOOTH_SPEED: f32 = 30.0;
+
+/// Plugin that renders and animates the terminal cursor
+pub struct CursorPlugin;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CursorSettings>().add_systems(
+            Update,
+            (
+                sync_cursor_settings_system,
+                spawn_cursor_system,
+                update_cursor_system,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Cursor shape as drawn on the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    Block,
+    Underline,
+    Bar,
+}
+
+impl From<CursorStyle> for CursorShape {
+    fn from(style: CursorStyle) -> Self {
+        match style {
+            CursorStyle::Block => CursorShape::Block,
+            CursorStyle::Underline => CursorShape::Underline,
+            CursorStyle::Beam => CursorShape::Bar,
+        }
+    }
+}
+
+impl CursorShape {
+    /// Resolve shape and blink from a DECSCUSR value
+    ///
+    /// Unknown values and `CURSOR_STYLE_DEFAULT` fall back to the configured
+    /// shape and blink setting.
+    pub fn from_decscusr(
+        style: u8,
+        default_shape: CursorShape,
+        default_blink: bool,
+    ) -> (Self, bool) {
+        match style {
+            CURSOR_STYLE_BLINKING_BLOCK => (CursorShape::Block, true),
+            CURSOR_STYLE_STEADY_BLOCK => (CursorShape::Block, false),
+            CURSOR_STYLE_BLINKING_UNDERLINE => (CursorShape::Underline, true),
+            CURSOR_STYLE_STEADY_UNDERLINE => (CursorShape::Underline, false),
+            CURSOR_STYLE_BLINKING_BAR => (CursorShape::Bar, true),
+            CURSOR_STYLE_STEADY_BAR => (CursorShape::Bar, false),
+            _ => (default_shape, default_blink),
+        }
+    }
+}
+
+/// Cursor settings derived from `UiConfig` and `ColorConfig`
+#[derive(Resource, Debug, Clone)]
+pub struct CursorSettings {
+    pub shape: CursorShape,
+    pub blink: bool,
+    pub blink_interval: Duration,
+    pub smooth: bool,
+    pub color: Color,
+    /// Fade to a new color rather than switch at once
+    pub fade: bool,
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self::from_config(&ScarabConfig::default())
+    }
+}
+
+impl CursorSettings {
+    pub fn from_config(config: &ScarabConfig) -> Self {
+        let color = config
+            .colors
+            .cursor
+            .as_deref()
+            .and_then(|hex| Srgba::hex(hex).ok())
+            .map(Color::from)
+            .unwrap_or(Color::srgb(0.97, 0.97, 0.95));
+
+        Self {
+            shape: config.ui.cursor_style.into(),
+            blink: config.ui.cursor_blink,
+            blink_interval: Duration::from_millis(config.ui.cursor_blink_interval.max(1) as u64),
+            smooth: config.ui.cursor_smooth && config.ui.motion_enabled(),
+            color,
+            fade: config.ui.motion_enabled(),
+        }
+    }
+}
+
+/// Terminal cursor entity, parented to the terminal grid
+#[derive(Component)]
+pub struct TerminalCursor {
+    /// Cell the cursor was last seen in
+    cell: (u16, u16),
+    /// Current drawn position in grid-local pixels
+    position: Vec2,
+    blink_timer: Timer,
+    blink_on: bool,
+    /// Color as drawn, fading when the theme changes
+    color: ColorFade,
+}
+
+impl TerminalCursor {
+    fn new(blink_interval: Duration, color: Color) -> Self {
+        Self {
+            cell: (0, 0),
+            position: Vec2::ZERO,
+            blink_timer: Timer::new(blink_interval, TimerMode::Repeating),
+            blink_on: true,
+            color: ColorFade::new(color),
+        }
+    }
+}
+
+/// Keep cursor settings in sync with the loaded config
+fn sync_cursor_settings_system(
+    config: Option<Res<ScarabConfig>>,
+    mut settings: ResMut<CursorSettings>,
+) {
+    if let Some(config) = config {
+        if config.is_changed() {
+            *settings = CursorSettings::from_config(&config);
+        }
+    }
+}
+
+/// Attach a cursor to the terminal grid once it exists
+fn spawn_cursor_system(
+    mut commands: Commands,
+    settings: Res<CursorSettings>,
+    grids: Query<Entity, Added<TerminalGridEntity>>,
+) {
+    for grid in grids.iter() {
+        commands.entity(grid).with_children(|parent| {
+            parent.spawn((
+                TerminalCursor::new(settings.blink_interval, settings.color),
+                Sprite {
+                    color: settings.color,
+                    custom_size: Some(Vec2::ZERO),
+                    anchor: Anchor::TopLeft,
+                    ..default()
+                },
+                Transform::from_xyz(0.0, 0.0, LAYER_CURSOR),
+            ));
+        });
+    }
+}
+
+/// Move, reshape, and blink the cursor from the shared terminal state
+fn update_cursor_system(
+    time: Res<Time>,
+    settings: Res<CursorSettings>,
+    renderer: Option<Res<TextRenderer>>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    smooth_scroll: Option<Res<SmoothScroll>>,
+    mut redraw: EventWriter<RequestRedraw>,
+    mut cursors: Query<(
+        &mut TerminalCursor,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+) {
+    let (Some(renderer), Some(state_reader)) = (renderer, state_reader) else {
+        return;
+    };
+
+    let safe_state = state_reader.get_safe_state();
+    let (col, row) = safe_state.cursor_pos();
+    let (shape, blink) =
+        CursorShape::from_decscusr(safe_state.cursor_style(), settings.shape, settings.blink);
+
+    let cell_width = renderer.cell_width;
+    let cell_height = renderer.cell_height;
+    let target = Vec2::new(col as f32 * cell_width, -(row as f32 * cell_height));
+
+    for (mut cursor, mut transform, mut sprite, mut visibility) in cursors.iter_mut() {
+        // Keep the cursor solid while it moves, like most terminals do
+        if cursor.cell != (col, row) {
+            cursor.cell = (col, row);
+            cursor.blink_on = true;
+            cursor.blink_timer.reset();
+        }
+
+        if cursor.blink_timer.duration() != settings.blink_interval {
+            cursor.blink_timer.set_duration(settings.blink_interval);
+        }
+
+        if blink {
+            if cursor.blink_timer.tick(time.delta()).just_finished() {
+                cursor.blink_on = !cursor.blink_on;
+            }
+        } else {
+            cursor.blink_on = true;
+        }
+
+        if settings.smooth {
+            let t = 1.0 - (-SMOOTH_SPEED * time.delta_secs()).exp();
+            cursor.position = cursor.position.lerp(target, t);
+            if cursor.position.distance(target) < 0.5 {
+                cursor.position = target;
+            } else {
+                // The app updates reactively; keep frames coming until the glide ends
+                redraw.send(RequestRedraw);
+            }
+        } else {
+            cursor.position = target;
+        }
+
+        cursor.color.tick(time.delta_secs());
+        cursor.color.set_target(settings.color, settings.fade);
+        if cursor.color.is_fading() {
+            redraw.send(RequestRedraw);
+        }
+
+        let (offset, size, alpha) = match shape {
+            CursorShape::Block => (Vec2::ZERO, Vec2::new(cell_width, cell_height), BLOCK_ALPHA),
+            CursorShape::Underline => (
+                Vec2::new(0.0, -(cell_height - UNDERLINE_HEIGHT)),
+                Vec2::new(cell_width, UNDERLINE_HEIGHT),
+                1.0,
+            ),
+            CursorShape::Bar => (Vec2::ZERO, Vec2::new(BAR_WIDTH, cell_height), 1.0),
+        };
+
+        let position = cursor.position + offset;
+        transform.translation = Vec3::new(position.x, position.y, LAYER_CURSOR);
+        sprite.custom_size = Some(size);
+        sprite.color = cursor.color.current().with_alpha(alpha);
+
+        // The cursor belongs to the live screen, not the history view
+        let scrolled = smooth_scroll.as_ref().is_some_and(|s| s.is_scrolled());
+        let desired = if cursor.blink_on && !scrolled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != desired {
+            *visibility = desired;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decscusr_shapes() {
+        let default = (CursorShape::Bar, false);
+        let resolve = |style| CursorShape::from_decscusr(style, default.0, default.1);
+
+        assert_eq!(resolve(0), default);
+        assert_eq!(resolve(1), (CursorShape::Block, true));
+        assert_eq!(resolve(2), (CursorShape::Block, false));
+        assert_eq!(resolve(3), (CursorShape::Underline, true));
+        assert_eq!(resolve(4), (CursorShape::Underline, false));
+        assert_eq!(resolve(5), (CursorShape::Bar, true));
+        assert_eq!(resolve(6), (CursorShape::Bar, false));
+        assert_eq!(resolve(42), default);
+    }
+
+    #[test]
+    fn test_settings_from_config() {
+        let mut config = ScarabConfig::default();
+        config.ui.cursor_style = CursorStyle::Beam;
+        config.ui.cursor_smooth = true;
+        config.ui.animations = false;
+
+        let settings = CursorSettings::from_config(&config);
+        assert_eq!(settings.shape, CursorShape::Bar);
+        // Smooth movement is an animation and follows the global switch
+        assert!(!settings.smooth);
+        assert!(!settings.fade);
+
+        config.ui.animations = true;
+        config.ui.reduce_motion = true;
+        let settings = CursorSettings::from_config(&config);
+        assert!(!settings.smooth);
+        assert!(!settings.fade);
+    }
+}
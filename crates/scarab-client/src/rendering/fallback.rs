@@ -0,0 +1,236 @@
+// Font fallback chain for characters missing from the primary face
+//
+// Resolution order for a codepoint:
+// 1. The monospace face used for the grid
+// 2. User-configured families (font.family, then font.fallback)
+// 3. Well-known CJK, emoji, and Nerd Font symbol families
+// 4. Every other face in the system font database
+//
+// Results (including misses) are cached per codepoint and style, so each
+// character pays the lookup cost once.
+
+use bevy::prelude::*;
+use cosmic_text::{fontdb, FontSystem};
+use std::collections::HashMap;
+
+/// Upper bound on cached codepoint resolutions before the cache is flushed
+const MAX_CACHED_CODEPOINTS: usize = 65_536;
+
+/// Families tried after the user's fallbacks and before scanning every face
+const BUILTIN_FALLBACKS: &[&str] = &[
+    // Nerd Font symbols (powerline, devicons, codicons)
+    "Symbols Nerd Font Mono",
+    "Symbols Nerd Font",
+    // Color emoji
+    "Noto Color Emoji",
+    "Apple Color Emoji",
+    "Segoe UI Emoji",
+    "Twemoji",
+    // CJK
+    "Noto Sans Mono CJK SC",
+    "Noto Sans CJK SC",
+    "Source Han Sans SC",
+    "PingFang SC",
+    "Hiragino Sans",
+    "Microsoft YaHei",
+    "Malgun Gothic",
+    // Miscellaneous symbols
+    "Noto Sans Symbols",
+    "Noto Sans Symbols 2",
+    "Segoe UI Symbol",
+    "DejaVu Sans",
+];
+
+/// A glyph located in a specific face
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedGlyph {
+    pub font_id: fontdb::ID,
+    pub glyph_id: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FallbackKey {
+    ch: char,
+    bold: bool,
+    italic: bool,
+}
+
+/// Per-codepoint font resolution with caching
+pub struct FontFallback {
+    /// User-configured families, in order of preference
+    families: Vec<String>,
+    cache: HashMap<FallbackKey, Option<ResolvedGlyph>>,
+}
+
+impl FontFallback {
+    pub fn new(families: Vec<String>) -> Self {
+        Self {
+            families,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Replace the user-configured families and drop cached resolutions
+    pub fn set_families(&mut self, families: Vec<String>) {
+        if self.families != families {
+            self.families = families;
+            self.cache.clear();
+        }
+    }
+
+    /// Drop all cached resolutions (e.g. after fonts were installed)
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Find a face that can render `ch` in the given style
+    ///
+    /// Returns the primary face's `.notdef` glyph if nothing covers the
+    /// character, so the cell still shows a visible placeholder.
+    pub fn resolve(
+        &mut self,
+        font_system: &mut FontSystem,
+        ch: char,
+        bold: bool,
+        italic: bool,
+    ) -> Option<ResolvedGlyph> {
+        let key = FallbackKey { ch, bold, italic };
+        if let Some(resolved) = self.cache.get(&key) {
+            return *resolved;
+        }
+
+        let resolved = self.resolve_uncached(font_system, ch, bold, italic);
+
+        if self.cache.len() >= MAX_CACHED_CODEPOINTS {
+            self.cache.clear();
+        }
+        self.cache.insert(key, resolved);
+
+        resolved
+    }
+
+    fn resolve_uncached(
+        &self,
+        font_system: &mut FontSystem,
+        ch: char,
+        bold: bool,
+        italic: bool,
+    ) -> Option<ResolvedGlyph> {
+        let primary = query_face(font_system, fontdb::Family::Monospace, bold, italic);
+
+        let named = self
+            .families
+            .iter()
+            .map(String::as_str)
+            .chain(BUILTIN_FALLBACKS.iter().copied())
+            .filter_map(|name| query_face(font_system, fontdb::Family::Name(name), bold, italic));
+
+        let candidates: Vec<fontdb::ID> = primary.into_iter().chain(named).collect();
+        for font_id in &candidates {
+            if let Some(glyph) = glyph_in_face(font_system, *font_id, ch) {
+                return Some(glyph);
+            }
+        }
+
+        for font_id in system_faces(font_system, bold, italic) {
+            if candidates.contains(&font_id) {
+                continue;
+            }
+            if let Some(glyph) = glyph_in_face(font_system, font_id, ch) {
+                debug!(
+                    "Resolved U+{:04X} via system font scan ({:?})",
+                    ch as u32, font_id
+                );
+                return Some(glyph);
+            }
+        }
+
+        warn!("No installed font covers '{}' (U+{:04X})", ch, ch as u32);
+        primary.map(|font_id| ResolvedGlyph {
+            font_id,
+            glyph_id: 0,
+        })
+    }
+}
+
+/// Query the font database for a family in the given style
+fn query_face(
+    font_system: &FontSystem,
+    family: fontdb::Family<'_>,
+    bold: bool,
+    italic: bool,
+) -> Option<fontdb::ID> {
+    font_system.db().query(&fontdb::Query {
+        families: &[family],
+        weight: if bold {
+            fontdb::Weight::BOLD
+        } else {
+            fontdb::Weight::NORMAL
+        },
+        stretch: fontdb::Stretch::Normal,
+        style: if italic {
+            fontdb::Style::Italic
+        } else {
+            fontdb::Style::Normal
+        },
+    })
+}
+
+/// Every face in the database, best style matches and monospaced faces first
+fn system_faces(font_system: &FontSystem, bold: bool, italic: bool) -> Vec<fontdb::ID> {
+    let mut faces: Vec<_> = font_system
+        .db()
+        .faces()
+        .map(|face| {
+            let style_miss =
+                (face.weight.0 >= 600) != bold || (face.style != fontdb::Style::Normal) != italic;
+            ((style_miss, !face.monospaced), face.id)
+        })
+        .collect();
+    faces.sort_by_key(|(rank, _)| *rank);
+    faces.into_iter().map(|(_, id)| id).collect()
+}
+
+/// Look up the glyph for `ch` in a face, if the face covers it
+fn glyph_in_face(
+    font_system: &mut FontSystem,
+    font_id: fontdb::ID,
+    ch: char,
+) -> Option<ResolvedGlyph> {
+    let font = font_system.get_font(font_id)?;
+    let glyph_id = font.rustybuzz().glyph_index(ch)?;
+    (glyph_id.0 != 0).then_some(ResolvedGlyph {
+        font_id,
+        glyph_id: glyph_id.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_database_resolves_nothing() {
+        let mut font_system =
+            FontSystem::new_with_locale_and_db("en-US".to_string(), fontdb::Database::new());
+        let mut fallback = FontFallback::new(vec!["Fira Code".to_string()]);
+
+        assert_eq!(fallback.resolve(&mut font_system, '漢', false, false), None);
+        // Misses are cached too
+        assert_eq!(fallback.cache.len(), 1);
+    }
+
+    #[test]
+    fn test_set_families_invalidates_cache() {
+        let mut font_system =
+            FontSystem::new_with_locale_and_db("en-US".to_string(), fontdb::Database::new());
+        let mut fallback = FontFallback::new(vec!["Fira Code".to_string()]);
+        fallback.resolve(&mut font_system, '\u{e0a0}', false, false);
+
+        fallback.set_families(vec!["Fira Code".to_string()]);
+        assert_eq!(fallback.cache.len(), 1);
+
+        fallback.set_families(vec!["Symbols Nerd Font".to_string()]);
+        assert!(fallback.cache.is_empty());
+    }
+}
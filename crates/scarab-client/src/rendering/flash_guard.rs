@@ -0,0 +1,125 @@
+// Flash limiting for photosensitive users
+//
+// With `ui.reduce_flashing`, output that makes the screen flash, such as a
+// program alternating dark and light backgrounds, is slowed down. A grid
+// update that moves the average luminance of the cell backgrounds by
+// FLASH_LUMINANCE_STEP or more from the last such change counts as a flash
+// transition, and is held back until MIN_TRANSITION_GAP after the previous
+// one was shown. Other updates show as usual. Held-back updates are shown
+// late rather than dropped, so the screen always catches up with the
+// terminal. Three transitions a second stay well under WCAG's limit of
+// three flashes, six transitions, a second.
+
+use scarab_protocol::TerminalStateReader;
+use std::collections::HashMap;
+
+use super::config::color;
+use super::text::is_default_background;
+
+/// Change in average relative luminance that counts as a flash transition
+pub const FLASH_LUMINANCE_STEP: f32 = 0.1;
+
+/// Least time between two flash transitions on screen, in seconds
+pub const MIN_TRANSITION_GAP: f64 = 1.0 / 3.0;
+
+/// Decides which grid updates are shown now and which wait
+#[derive(Debug, Default)]
+pub struct FlashGuard {
+    /// Luminance after the last transition shown
+    anchor: Option<f32>,
+    /// When the last transition was shown
+    last_transition: Option<f64>,
+}
+
+impl FlashGuard {
+    /// Whether a grid with average `luminance` may be shown at `now`, in
+    /// seconds
+    ///
+    /// Small changes add up: drifting far enough from the luminance at the
+    /// last transition is a transition too, so a quick fade is slowed down
+    /// the same way as a flash.
+    pub fn allow(&mut self, luminance: f32, now: f64) -> bool {
+        let Some(anchor) = self.anchor else {
+            self.anchor = Some(luminance);
+            return true;
+        };
+        if (luminance - anchor).abs() < FLASH_LUMINANCE_STEP {
+            return true;
+        }
+        if self
+            .last_transition
+            .is_some_and(|at| now - at < MIN_TRANSITION_GAP)
+        {
+            return false;
+        }
+        self.anchor = Some(luminance);
+        self.last_transition = Some(now);
+        true
+    }
+}
+
+/// Average relative luminance of the backgrounds drawn for `state`, with
+/// `default_background` behind cells without a color of their own
+pub fn grid_luminance(state: &impl TerminalStateReader, default_background: u32) -> f32 {
+    let (width, height) = state.dimensions();
+    if width == 0 || height == 0 {
+        return 0.0;
+    }
+
+    // Screens use few colors; convert each once
+    let mut luminance: HashMap<u32, f32> = HashMap::new();
+    let mut total = 0.0;
+    for row in 0..height {
+        for col in 0..width {
+            let bg = match state.cell(row, col) {
+                Some(cell) if !is_default_background(cell.bg) => cell.bg,
+                _ => default_background,
+            };
+            total += *luminance
+                .entry(bg)
+                .or_insert_with(|| color::relative_luminance(bg));
+        }
+    }
+    total / (width * height) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_state::MockTerminalState;
+    use scarab_protocol::Cell;
+
+    #[test]
+    fn test_flashes_are_slowed_down() {
+        let mut guard = FlashGuard::default();
+        assert!(guard.allow(0.0, 0.0));
+        // Output that doesn't change the overall brightness shows at once
+        assert!(guard.allow(0.05, 0.01));
+        // A full-screen flash to white shows, but flashing back right
+        // away waits for the gap
+        assert!(guard.allow(1.0, 0.1));
+        assert!(!guard.allow(0.0, 0.2));
+        assert!(guard.allow(0.0, 0.5));
+        // Creeping toward white in small steps is a transition as well
+        assert!(guard.allow(0.08, 0.6));
+        assert!(!guard.allow(0.16, 0.61));
+    }
+
+    #[test]
+    fn test_grid_luminance() {
+        let mut state = MockTerminalState::new(2, 1);
+        assert!(grid_luminance(&state, 0xFF000000) < 0.01);
+
+        state.set_cell(
+            0,
+            1,
+            Cell {
+                bg: 0xFFFFFFFF,
+                ..Cell::default()
+            },
+        );
+        assert!((grid_luminance(&state, 0xFF000000) - 0.5).abs() < 0.01);
+        // Default cells take the pane's background
+        assert!((grid_luminance(&state, 0xFFFFFFFF) - 1.0).abs() < 0.01);
+    }
+}
@@ -12,11 +12,12 @@
 //! - `ImagePlacementComponent` marks sprite entities for lifecycle management
 //! - Three main systems: load, render, and cleanup
 
+use crate::ipc::DaemonPaths;
 use bevy::prelude::*;
 use bevy::render::render_asset::RenderAssetUsages;
 use scarab_protocol::{
     ImageFormat as ProtocolImageFormat, ImagePlacement, SharedImageBuffer, SharedImagePlacement,
-    TerminalMetrics, IMAGE_BUFFER_SIZE, IMAGE_SHMEM_PATH,
+    TerminalMetrics, IMAGE_BUFFER_SIZE,
 };
 use shared_memory::Shmem;
 use std::collections::HashMap;
@@ -43,15 +44,15 @@ pub struct SharedImageReader {
 }
 
 impl SharedImageReader {
-    /// Try to open the shared image buffer
-    pub fn try_new() -> Option<Self> {
+    /// Try to open the shared image buffer at `path`
+    pub fn try_new(path: &str) -> Option<Self> {
         match shared_memory::ShmemConf::new()
             .size(std::mem::size_of::<SharedImageBuffer>())
-            .os_id(IMAGE_SHMEM_PATH)
+            .os_id(path)
             .open()
         {
             Ok(shmem) => {
-                info!("Connected to shared image buffer at: {}", IMAGE_SHMEM_PATH);
+                info!("Connected to shared image buffer at: {}", path);
                 Some(Self {
                     shmem: SharedMemWrapper(Arc::new(shmem)),
                     last_sequence: 0,
@@ -185,7 +186,12 @@ pub struct ImagesPlugin;
 impl Plugin for ImagesPlugin {
     fn build(&self, app: &mut App) {
         // Try to connect to shared image buffer
-        if let Some(reader) = SharedImageReader::try_new() {
+        let paths = app
+            .world()
+            .get_resource::<DaemonPaths>()
+            .cloned()
+            .unwrap_or_default();
+        if let Some(reader) = SharedImageReader::try_new(&paths.0.image_shmem) {
             app.insert_resource(reader);
         }
 
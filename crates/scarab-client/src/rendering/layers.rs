@@ -13,6 +13,7 @@
 //! LAYER_TERMINAL_BG        0.0        Terminal background (solid color)
 //! LAYER_TERMINAL_TEXT      0.1        Terminal text glyphs and cell backgrounds
 //! LAYER_TEXT_DECORATIONS   0.15       Underlines, strikethroughs (on text)
+//! LAYER_CURSOR             0.2        Terminal cursor (block, underline, bar)
 //! LAYER_IMAGES             50.0       Inline images (iTerm2/Kitty protocol)
 //! LAYER_HINTS              200.0      Navigation hints (Vimium-style overlays)
 //! LAYER_FOCUS              210.0      Focus indicators and visual feedback
@@ -71,6 +72,14 @@ pub const LAYER_TERMINAL_TEXT: f32 = 0.1;
 /// Must be above text to be visible, but below images.
 pub const LAYER_TEXT_DECORATIONS: f32 = 0.15;
 
+/// Terminal cursor layer
+///
+/// The grid cursor sits directly on top of text and decorations. Block
+/// cursors are drawn translucent so the glyph underneath stays readable.
+///
+/// Must be below images so inline images are not obscured by the cursor.
+pub const LAYER_CURSOR: f32 = 0.2;
+
 /// Inline images layer (iTerm2/Kitty image protocol)
 ///
 /// Images embedded in the terminal via escape sequences. These render above
@@ -143,7 +152,8 @@ pub const LAYER_NOTIFICATIONS: f32 = 400.0;
 pub const fn validate_layer_ordering() -> bool {
     LAYER_TERMINAL_BG < LAYER_TERMINAL_TEXT
         && LAYER_TERMINAL_TEXT < LAYER_TEXT_DECORATIONS
-        && LAYER_TEXT_DECORATIONS < LAYER_IMAGES
+        && LAYER_TEXT_DECORATIONS < LAYER_CURSOR
+        && LAYER_CURSOR < LAYER_IMAGES
         && LAYER_IMAGES < LAYER_HINTS
         && LAYER_HINTS < LAYER_FOCUS
         && LAYER_FOCUS < LAYER_MODALS
@@ -159,6 +169,8 @@ mod tests {
         assert!(LAYER_TERMINAL_BG < LAYER_TERMINAL_TEXT);
         assert!(LAYER_TERMINAL_TEXT < LAYER_TEXT_DECORATIONS);
         assert!(LAYER_TEXT_DECORATIONS < LAYER_IMAGES);
+        assert!(LAYER_TEXT_DECORATIONS < LAYER_CURSOR);
+        assert!(LAYER_CURSOR < LAYER_IMAGES);
         assert!(LAYER_IMAGES < LAYER_HINTS);
         assert!(LAYER_HINTS < LAYER_FOCUS);
         assert!(LAYER_FOCUS < LAYER_MODALS);
@@ -177,6 +189,7 @@ mod tests {
         assert_eq!(LAYER_TERMINAL_BG, 0.0);
         assert_eq!(LAYER_TERMINAL_TEXT, 0.1);
         assert_eq!(LAYER_TEXT_DECORATIONS, 0.15);
+        assert_eq!(LAYER_CURSOR, 0.2);
         assert_eq!(LAYER_IMAGES, 50.0);
         assert_eq!(LAYER_HINTS, 200.0);
         assert_eq!(LAYER_FOCUS, 210.0);
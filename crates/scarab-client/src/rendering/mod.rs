@@ -2,27 +2,49 @@
 // GPU-accelerated text rendering with cosmic-text and glyph atlas caching
 
 pub mod atlas;
+pub mod background;
 pub mod config;
+pub mod cursor;
+pub mod fallback;
+pub mod flash_guard;
 pub mod hint_overlay;
 pub mod images;
 pub mod layers;
 pub mod scrollback_render;
+pub mod shaping;
+pub mod smooth_scroll;
+pub mod snapshot;
 pub mod text;
+pub mod theme_transition;
+pub mod zoom;
 
 #[cfg(test)]
 mod z_order_tests;
 
-pub use atlas::{AtlasRect, GlyphAtlas, GlyphKey};
+pub use atlas::{AtlasRect, GlyphAtlas, GlyphKey, ShelfAllocation, ShelfAllocator};
+pub use background::{
+    fit_image_size, window_needs_transparency, BackgroundDimEntity, BackgroundImageEntity,
+    BackgroundPlugin, PanePalettes,
+};
 pub use config::{color, FontConfig, TextAttributes};
+pub use cursor::{CursorPlugin, CursorSettings, CursorShape, TerminalCursor};
+pub use fallback::{FontFallback, ResolvedGlyph};
+pub use flash_guard::{grid_luminance, FlashGuard};
 pub use hint_overlay::{
     HintFade, HintOverlay, HintOverlayBundle, HintOverlayConfig, HintOverlayPlugin,
 };
 pub use images::{ImageCache, ImagePlacementComponent, ImagesPlugin, SharedImageReader};
 pub use layers::*;
 pub use scrollback_render::generate_scrollback_mesh;
+pub use shaping::{RunShaper, ShapedGlyph, ShapedRun};
+pub use smooth_scroll::{GridFolds, LineFold, SmoothScroll, SmoothScrollPlugin};
+pub use snapshot::SnapshotRenderer;
 pub use text::{
-    generate_terminal_mesh, update_terminal_mesh_system, DirtyRegion, TerminalMesh, TextRenderer,
+    generate_terminal_mesh, update_terminal_mesh_system, DirtyRegion, MeshBuffers, MeshCache,
+    TerminalMesh, TextRenderer,
 };
+pub use theme_transition::{ColorFade, THEME_TRANSITION_SECS};
+pub use zoom::{FontZoom, FontZoomPlugin, ZoomAction, ZoomStore};
 
 // Re-export shader effects from parent shaders module
 pub use crate::shaders::{BlurSettings, GlowSettings, ScarabEffectsPlugin};
@@ -9,7 +9,7 @@ use scarab_protocol::{Cell, GRID_HEIGHT, GRID_WIDTH};
 use super::atlas::GlyphKey;
 use super::config::{color, TextAttributes};
 use super::layers::{LAYER_TERMINAL_BG, LAYER_TERMINAL_TEXT, LAYER_TEXT_DECORATIONS};
-use super::text::TextRenderer;
+use super::text::{contrasting, TextRenderer};
 use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackState};
 
 const DEFAULT_BG: u32 = 0xFF0D1208; // Slime dark
@@ -253,6 +253,9 @@ fn render_scrollback_glyph(
     if attrs.reverse {
         fg = color::from_rgba(cell.bg);
     }
+    if renderer.minimum_contrast > 1.0 {
+        fg = color::from_rgba(contrasting(color::to_rgba(fg), cell, renderer));
+    }
 
     let fg_array = fg.to_srgba().to_f32_array();
 
@@ -0,0 +1,283 @@
+// Run shaping for programming ligatures and OpenType features
+//
+// Cells that share a style are shaped together with rustybuzz against the face
+// cosmic-text resolves for that style. Glyph clusters are mapped back onto the
+// grid, so a ligature always starts at the cell of its first character and the
+// grid stays strictly monospace.
+
+use bevy::prelude::*;
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+use rustybuzz::{Feature, UnicodeBuffer};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Upper bound on cached shaped runs before the cache is flushed
+const MAX_CACHED_RUNS: usize = 4096;
+
+/// A glyph positioned relative to the cell where its cluster starts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    /// Glyph index in the run's font (0 means the font has no glyph)
+    pub glyph_id: u16,
+    /// Index of the first cell in the run covered by this glyph's cluster
+    pub cell: usize,
+    /// Number of cells covered by the cluster
+    pub cells: usize,
+    /// Horizontal offset from the cluster's first cell, in pixels
+    pub x_offset: f32,
+    /// Vertical offset from the baseline, in pixels (positive = up)
+    pub y_offset: f32,
+}
+
+/// Result of shaping one run of cells
+#[derive(Debug, Clone)]
+pub struct ShapedRun {
+    pub font_id: fontdb::ID,
+    pub glyphs: Vec<ShapedGlyph>,
+}
+
+/// Cache key for a shaped run
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RunKey {
+    text: String,
+    bold: bool,
+    italic: bool,
+    font_size_bits: u32,
+}
+
+/// Shapes runs of cells and caches the results
+#[derive(Default)]
+pub struct RunShaper {
+    /// Face resolved by cosmic-text for each (bold, italic) style
+    faces: HashMap<(bool, bool), Option<fontdb::ID>>,
+    /// Parsed feature settings per face
+    features: HashMap<fontdb::ID, Vec<Feature>>,
+    runs: HashMap<RunKey, Option<ShapedRun>>,
+}
+
+impl RunShaper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop all cached faces, features, and runs
+    ///
+    /// Call after the font configuration changes.
+    pub fn clear(&mut self) {
+        self.faces.clear();
+        self.features.clear();
+        self.runs.clear();
+    }
+
+    /// Shape a run of single-cell characters
+    ///
+    /// Returns `None` if no face could be resolved for the style, in which
+    /// case the caller should fall back to per-cell rendering.
+    pub fn shape(
+        &mut self,
+        font_system: &mut FontSystem,
+        text: &str,
+        bold: bool,
+        italic: bool,
+        font_size: f32,
+        feature_config: &HashMap<String, Vec<String>>,
+    ) -> Option<ShapedRun> {
+        let key = RunKey {
+            text: text.to_string(),
+            bold,
+            italic,
+            font_size_bits: font_size.to_bits(),
+        };
+
+        if let Some(run) = self.runs.get(&key) {
+            return run.clone();
+        }
+
+        let run = self.shape_uncached(font_system, text, bold, italic, font_size, feature_config);
+
+        if self.runs.len() >= MAX_CACHED_RUNS {
+            self.runs.clear();
+        }
+        self.runs.insert(key, run.clone());
+
+        run
+    }
+
+    fn shape_uncached(
+        &mut self,
+        font_system: &mut FontSystem,
+        text: &str,
+        bold: bool,
+        italic: bool,
+        font_size: f32,
+        feature_config: &HashMap<String, Vec<String>>,
+    ) -> Option<ShapedRun> {
+        let font_id = *self
+            .faces
+            .entry((bold, italic))
+            .or_insert_with(|| resolve_face(font_system, bold, italic, font_size))
+            .as_ref()?;
+
+        let features = self
+            .features
+            .entry(font_id)
+            .or_insert_with(|| {
+                let family = font_system
+                    .db()
+                    .face(font_id)
+                    .and_then(|face| face.families.first())
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_default();
+                parse_features(&features_for(feature_config, &family))
+            })
+            .clone();
+
+        let font = font_system.get_font(font_id)?;
+        let face = font.rustybuzz();
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        let output = rustybuzz::shape(face, &features, buffer);
+
+        let scale = font_size / face.units_per_em() as f32;
+        let cluster_cells = byte_to_cell_map(text);
+        let cell_count = text.chars().count();
+
+        let infos = output.glyph_infos();
+        let positions = output.glyph_positions();
+
+        let mut glyphs = Vec::with_capacity(infos.len());
+        let mut current_cluster = u32::MAX;
+        let mut pen_in_cluster = 0.0;
+
+        for (i, (info, pos)) in infos.iter().zip(positions).enumerate() {
+            if info.cluster != current_cluster {
+                current_cluster = info.cluster;
+                pen_in_cluster = 0.0;
+            }
+
+            let cell = cluster_cells
+                .get(info.cluster as usize)
+                .copied()
+                .unwrap_or(0);
+
+            // The cluster ends where the next different cluster begins
+            let next_cell = infos[i + 1..]
+                .iter()
+                .find(|next| next.cluster != info.cluster)
+                .and_then(|next| cluster_cells.get(next.cluster as usize).copied())
+                .unwrap_or(cell_count);
+
+            glyphs.push(ShapedGlyph {
+                glyph_id: info.glyph_id as u16,
+                cell,
+                cells: next_cell.saturating_sub(cell).max(1),
+                x_offset: pen_in_cluster + pos.x_offset as f32 * scale,
+                y_offset: pos.y_offset as f32 * scale,
+            });
+
+            pen_in_cluster += pos.x_advance as f32 * scale;
+        }
+
+        Some(ShapedRun { font_id, glyphs })
+    }
+}
+
+/// Resolve the monospace face cosmic-text would use for a style
+fn resolve_face(
+    font_system: &mut FontSystem,
+    bold: bool,
+    italic: bool,
+    font_size: f32,
+) -> Option<fontdb::ID> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(font_size, font_size));
+    buffer.set_size(font_system, 100.0, 100.0);
+
+    let mut attrs = Attrs::new().family(Family::Monospace);
+    if bold {
+        attrs = attrs.weight(cosmic_text::Weight::BOLD);
+    }
+    if italic {
+        attrs = attrs.style(cosmic_text::Style::Italic);
+    }
+
+    buffer.set_text(font_system, "M", attrs, Shaping::Advanced);
+    buffer.shape_until_scroll(font_system, false);
+
+    let font_id = buffer
+        .layout_runs()
+        .next()
+        .and_then(|run| run.glyphs.first())
+        .map(|glyph| glyph.font_id);
+
+    if font_id.is_none() {
+        warn!(
+            "Could not resolve a monospace face (bold: {}, italic: {}); ligatures disabled",
+            bold, italic
+        );
+    }
+    font_id
+}
+
+/// Collect feature settings for a family, with `"*"` entries applied first
+fn features_for(config: &HashMap<String, Vec<String>>, family: &str) -> Vec<String> {
+    let mut features = config.get("*").cloned().unwrap_or_default();
+    if let Some(specific) = config.get(family) {
+        features.extend(specific.iter().cloned());
+    }
+    features
+}
+
+/// Parse OpenType feature settings, skipping invalid entries
+fn parse_features(settings: &[String]) -> Vec<Feature> {
+    settings
+        .iter()
+        .filter_map(|setting| match Feature::from_str(setting) {
+            Ok(feature) => Some(feature),
+            Err(_) => {
+                warn!("Ignoring invalid font feature '{}'", setting);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Map each byte offset of `text` to the index of the cell holding that char
+fn byte_to_cell_map(text: &str) -> Vec<usize> {
+    let mut map = vec![0; text.len()];
+    for (cell, (offset, ch)) in text.char_indices().enumerate() {
+        for slot in &mut map[offset..offset + ch.len_utf8()] {
+            *slot = cell;
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_to_cell_map() {
+        assert_eq!(byte_to_cell_map("a=>"), vec![0, 1, 2]);
+        assert_eq!(byte_to_cell_map("λ->"), vec![0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_features_for_family() {
+        let mut config = HashMap::new();
+        config.insert("*".to_string(), vec!["-calt".to_string()]);
+        config.insert("Fira Code".to_string(), vec!["+ss01".to_string()]);
+
+        assert_eq!(features_for(&config, "Fira Code"), vec!["-calt", "+ss01"]);
+        assert_eq!(features_for(&config, "Hack"), vec!["-calt"]);
+    }
+
+    #[test]
+    fn test_parse_features_skips_invalid() {
+        let features =
+            parse_features(&["+liga".to_string(), "cv01=2".to_string(), "+".to_string()]);
+        assert_eq!(features.len(), 2);
+    }
+}
@@ -0,0 +1,466 @@
+// Smooth pixel scrolling of the terminal grid
+//
+// Wheel and touchpad deltas move a fractional scroll position instead of
+// jumping whole lines. The displayed position glides toward the target; its
+// whole lines go to the `ScrollbackBuffer` and the remainder offsets the grid
+// mesh by a fraction of a cell, so the line being revealed slides in at the
+// top edge. With `ui.smooth_scroll` disabled, or motion reduced, scrolling
+// snaps to whole lines.
+//
+// While scrolled, the grid shows a view composed of scrollback lines followed
+// by the live screen, rendered through the same mesh cache as the live grid.
+// The same view shows folded lines, such as collapsed command blocks, as one
+// summary row each, even when not scrolled.
+
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::RequestRedraw;
+use scarab_config::ScarabConfig;
+use scarab_protocol::{Cell, TerminalMetrics, TerminalStateReader};
+
+use super::text::{DirtyRegion, MeshCache, TerminalMesh, TextRenderer};
+use crate::integration::{SharedMemoryReader, TerminalGridEntity};
+use crate::safe_state::GridView;
+use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackState};
+
+/// Lines moved per wheel notch
+const LINES_PER_NOTCH: f32 = 3.0;
+
+/// Exponential approach rate for the glide (higher is snappier)
+const GLIDE_SPEED: f32 = 20.0;
+
+/// Distance in lines below which the glide snaps to its target
+const SNAP_EPSILON: f32 = 0.01;
+
+/// Fractional scroll position of the grid
+#[derive(Resource, Debug, Default)]
+pub struct SmoothScroll {
+    /// Displayed position in lines above the live view
+    pub position: f32,
+    /// Position the display is gliding toward
+    pub target: f32,
+    /// Whole-line offset last written to the scrollback buffer
+    applied_offset: usize,
+    /// Whether the grid mesh currently shows the composed view
+    showing_history: bool,
+    /// Mesh cache for the scrolled view, separate from the live grid's
+    cache: MeshCache,
+}
+
+impl SmoothScroll {
+    /// Move the target by `lines` (positive = up), within the scrollback
+    pub fn scroll_by(&mut self, lines: f32, max_lines: usize) {
+        self.target = (self.target + lines).clamp(0.0, max_lines as f32);
+    }
+
+    /// Jump straight to a whole-line offset set elsewhere
+    pub fn snap_to(&mut self, offset: usize) {
+        self.position = offset as f32;
+        self.target = offset as f32;
+        self.applied_offset = offset;
+    }
+
+    /// Advance the glide by `dt` seconds, returning true while still moving
+    pub fn step(&mut self, dt: f32) -> bool {
+        let t = 1.0 - (-GLIDE_SPEED * dt).exp();
+        self.position += (self.target - self.position) * t;
+        if (self.target - self.position).abs() < SNAP_EPSILON {
+            self.position = self.target;
+            return false;
+        }
+        true
+    }
+
+    /// Whole lines scrolled above the live view
+    pub fn whole_lines(&self) -> usize {
+        self.position.floor() as usize
+    }
+
+    /// Fraction of the next line revealed at the top edge (0.0..1.0)
+    pub fn fraction(&self) -> f32 {
+        self.position - self.position.floor()
+    }
+
+    /// True while the grid shows history instead of the live screen
+    pub fn is_scrolled(&self) -> bool {
+        self.position > 0.0
+    }
+
+    /// True while the grid mesh is drawn from the composed view, scrolled or
+    /// with folded lines on screen
+    pub fn owns_grid(&self) -> bool {
+        self.is_scrolled() || self.showing_history
+    }
+}
+
+/// Lines `start..=end` of the scrolled view shown as a single row
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineFold {
+    /// ID of what was folded, such as a command block
+    pub id: u64,
+    pub start: usize,
+    pub end: usize,
+    /// Row shown in place of the lines
+    pub summary: Vec<Cell>,
+}
+
+/// Folds applied to the grid
+///
+/// Lines are numbered as in the scrolled view: scrollback first, then the
+/// live screen.
+#[derive(Resource, Debug, Default)]
+pub struct GridFolds {
+    pub folds: Vec<LineFold>,
+    /// Screen rows showing a fold's summary when last drawn, with its ID
+    pub summary_rows: Vec<(u16, u64)>,
+}
+
+/// Lines (positive = up) for a wheel or touchpad event
+pub fn wheel_lines(event: &MouseWheel, cell_height: f32) -> f32 {
+    match event.unit {
+        MouseScrollUnit::Line => event.y * LINES_PER_NOTCH,
+        MouseScrollUnit::Pixel => event.y / cell_height.max(1.0),
+    }
+}
+
+/// Grid contents when scrolled `offset` lines into history
+///
+/// Returns `rows + 1` rows: row 0 is the line sliding in above the top edge,
+/// followed by the `rows` lines a whole-line scroll would show. Lines come
+/// from the scrollback first, then from the live screen.
+pub fn scrolled_view(
+    live: &impl TerminalStateReader,
+    scrollback: &ScrollbackBuffer,
+    offset: usize,
+    rows: usize,
+) -> GridView {
+    folded_view(live, scrollback, offset, rows, &[]).0
+}
+
+/// [`scrolled_view`] with each of `folds` shown as its summary row
+///
+/// The bottom row stays where a whole-line scroll puts it, so folds pull
+/// older lines down into view. Also returns the rows showing a summary,
+/// with the fold's ID.
+pub fn folded_view(
+    live: &impl TerminalStateReader,
+    scrollback: &ScrollbackBuffer,
+    offset: usize,
+    rows: usize,
+    folds: &[LineFold],
+) -> (GridView, Vec<(usize, u64)>) {
+    let (width, live_rows) = live.dimensions();
+    let history = scrollback.line_count();
+    let live_cells = live.cells();
+
+    let mut view = GridView::new(width, rows + 1);
+    let mut summary_rows = Vec::new();
+    let cells = view.cells_mut();
+    let mut next = (history + rows) as isize - offset as isize - 1;
+
+    for (row, dest) in cells.chunks_exact_mut(width).enumerate().rev() {
+        let Ok(line) = usize::try_from(next) else {
+            break;
+        };
+        if let Some(fold) = folds.iter().find(|f| f.start <= line && line <= f.end) {
+            for (d, s) in dest.iter_mut().zip(&fold.summary) {
+                *d = *s;
+            }
+            summary_rows.push((row, fold.id));
+            next = fold.start as isize - 1;
+            continue;
+        }
+        next -= 1;
+
+        if line < history {
+            if let Some(source) = scrollback.get_line(line) {
+                for (d, s) in dest.iter_mut().zip(&source.cells) {
+                    *d = *s;
+                }
+            }
+        } else if line - history < live_rows {
+            let start = (line - history) * width;
+            if let Some(source) = live_cells.get(start..start + width) {
+                dest.copy_from_slice(source);
+            }
+        }
+    }
+    summary_rows.reverse();
+    (view, summary_rows)
+}
+
+/// Whether wheel scrolling glides rather than jumping whole lines
+pub fn smooth_scroll_enabled(config: Option<&ScarabConfig>) -> bool {
+    config.map_or(true, |c| c.ui.smooth_scroll && c.ui.motion_enabled())
+}
+
+/// System to turn wheel and touchpad input into a scroll target
+fn handle_smooth_scroll_input(
+    config: Option<Res<ScarabConfig>>,
+    mut wheel: EventReader<MouseWheel>,
+    renderer: Option<Res<TextRenderer>>,
+    scrollback: Res<ScrollbackBuffer>,
+    mut smooth: ResMut<SmoothScroll>,
+) {
+    // Line jumps are handled by the scrollback plugin
+    if !smooth_scroll_enabled(config.as_deref()) {
+        wheel.clear();
+        return;
+    }
+
+    let cell_height = renderer.map_or(16.0, |r| r.cell_height);
+    for event in wheel.read() {
+        smooth.scroll_by(wheel_lines(event, cell_height), scrollback.line_count());
+    }
+}
+
+/// System to glide toward the target and keep the scrollback offset in step
+fn animate_smooth_scroll(
+    time: Res<Time>,
+    config: Option<Res<ScarabConfig>>,
+    mut smooth: ResMut<SmoothScroll>,
+    mut scrollback: ResMut<ScrollbackBuffer>,
+    mut state: ResMut<ScrollbackState>,
+    mut redraw: EventWriter<RequestRedraw>,
+) {
+    // Keys, the scrollbar, and search move the buffer directly; jump there
+    if scrollback.scroll_offset() != smooth.applied_offset {
+        smooth.snap_to(scrollback.scroll_offset());
+    }
+
+    if !smooth_scroll_enabled(config.as_deref()) {
+        let target = smooth.target;
+        smooth.position = target;
+    } else if smooth.step(time.delta_secs()) {
+        // The app updates reactively; keep frames coming until the glide ends
+        redraw.send(RequestRedraw);
+    }
+
+    let whole = smooth.whole_lines();
+    if whole != scrollback.scroll_offset() {
+        scrollback.scroll_to_bottom();
+        scrollback.scroll_up(whole);
+        state.is_scrolled = !scrollback.is_at_bottom();
+    }
+    smooth.applied_offset = scrollback.scroll_offset();
+}
+
+/// Hand the grid back to the live renderer
+fn release_grid(
+    smooth: &mut SmoothScroll,
+    grids: &mut Query<&mut TerminalMesh, With<TerminalGridEntity>>,
+) {
+    if smooth.showing_history {
+        smooth.showing_history = false;
+        for mut terminal_mesh in grids.iter_mut() {
+            terminal_mesh.dirty_region.mark_full_redraw();
+        }
+    }
+}
+
+/// System to draw the scrolled or folded view into the grid mesh
+fn render_scrolled_grid(
+    mut smooth: ResMut<SmoothScroll>,
+    mut folds: ResMut<GridFolds>,
+    renderer: Option<ResMut<TextRenderer>>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    scrollback: Res<ScrollbackBuffer>,
+    metrics: Option<Res<TerminalMetrics>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut grids: Query<&mut TerminalMesh, With<TerminalGridEntity>>,
+    mut last: Local<Option<(f32, u64, usize, Vec2)>>,
+) {
+    let (Some(mut renderer), Some(state_reader)) = (renderer, state_reader) else {
+        return;
+    };
+
+    if !smooth.is_scrolled() && folds.folds.is_empty() {
+        release_grid(&mut smooth, &mut grids);
+        *last = None;
+        if !folds.summary_rows.is_empty() {
+            folds.summary_rows.clear();
+        }
+        return;
+    }
+
+    let live = state_reader.get_safe_state();
+    let cell_size = Vec2::new(renderer.cell_width, renderer.cell_height);
+    let key = (
+        smooth.position,
+        live.sequence(),
+        scrollback.line_count(),
+        cell_size,
+    );
+    // Reflow can rewrite history without moving the view or changing its size
+    if *last == Some(key) && !scrollback.is_changed() && !folds.is_changed() {
+        return;
+    }
+    *last = Some(key);
+
+    let rows = metrics.map_or(live.dimensions().1, |m| m.rows as usize);
+    let (view, summary_rows) =
+        folded_view(&live, &scrollback, smooth.whole_lines(), rows, &folds.folds);
+    // Row 0 sits above the top edge
+    folds.bypass_change_detection().summary_rows = summary_rows
+        .into_iter()
+        .filter_map(|(row, id)| Some((u16::try_from(row.checked_sub(1)?).ok()?, id)))
+        .collect();
+
+    // Folds off screen leave the live screen as it is
+    if !smooth.is_scrolled() && folds.summary_rows.is_empty() {
+        release_grid(&mut smooth, &mut grids);
+        return;
+    }
+    smooth.showing_history = true;
+
+    let smooth = &mut *smooth;
+    smooth
+        .cache
+        .update(&view, &mut renderer, &DirtyRegion::new());
+    renderer.atlas.update_texture(&mut images);
+
+    // Row 0 starts one cell above the top edge and slides down as the
+    // fraction grows
+    let mut mesh = smooth.cache.build_mesh();
+    mesh.translate_by(Vec3::new(0.0, (1.0 - smooth.fraction()) * cell_size.y, 0.0));
+    for terminal_mesh in grids.iter() {
+        meshes.insert(&terminal_mesh.mesh_handle, mesh.clone());
+    }
+}
+
+/// Plugin for smooth pixel scrolling
+pub struct SmoothScrollPlugin;
+
+impl Plugin for SmoothScrollPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SmoothScroll>()
+            .init_resource::<GridFolds>()
+            .add_systems(
+                Update,
+                (
+                    handle_smooth_scroll_input,
+                    animate_smooth_scroll,
+                    render_scrolled_grid,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_state::MockTerminalState;
+    use crate::terminal::scrollback::ScrollbackLine;
+    use scarab_protocol::Cell;
+
+    #[test]
+    fn test_wheel_lines() {
+        let notch = MouseWheel {
+            unit: MouseScrollUnit::Line,
+            x: 0.0,
+            y: 1.0,
+            window: Entity::PLACEHOLDER,
+        };
+        assert_eq!(wheel_lines(&notch, 20.0), 3.0);
+
+        let swipe = MouseWheel {
+            unit: MouseScrollUnit::Pixel,
+            x: 0.0,
+            y: -10.0,
+            window: Entity::PLACEHOLDER,
+        };
+        assert_eq!(wheel_lines(&swipe, 20.0), -0.5);
+    }
+
+    #[test]
+    fn test_glide_reaches_target() {
+        let mut smooth = SmoothScroll::default();
+        smooth.scroll_by(2.5, 10);
+        assert!(smooth.step(1.0 / 60.0));
+        assert!(smooth.position > 0.0 && smooth.position < 2.5);
+
+        while smooth.step(1.0 / 60.0) {}
+        assert_eq!(smooth.position, 2.5);
+        assert_eq!(smooth.whole_lines(), 2);
+        assert_eq!(smooth.fraction(), 0.5);
+
+        // The target stays within the scrollback
+        smooth.scroll_by(100.0, 10);
+        assert_eq!(smooth.target, 10.0);
+        smooth.scroll_by(-100.0, 10);
+        assert_eq!(smooth.target, 0.0);
+    }
+
+    #[test]
+    fn test_scrolled_view_joins_history_and_screen() {
+        let mut scrollback = ScrollbackBuffer::new(100);
+        for text in ["h0", "h1", "h2"] {
+            scrollback.push_line(ScrollbackLine::from_text(text));
+        }
+
+        let mut live = MockTerminalState::new(4, 3);
+        live.set_cell(
+            0,
+            0,
+            Cell {
+                char_codepoint: 'L' as u32,
+                ..Cell::default()
+            },
+        );
+
+        let first_char =
+            |view: &GridView, row: usize| char::from_u32(view.cell(row, 0).unwrap().char_codepoint);
+        let second_char =
+            |view: &GridView, row: usize| char::from_u32(view.cell(row, 1).unwrap().char_codepoint);
+
+        // One line up: "h1" slides in, "h2" is the top row, then the screen
+        let view = scrolled_view(&live, &scrollback, 1, 3);
+        assert_eq!(view.dimensions(), (4, 4));
+        assert_eq!(second_char(&view, 0), Some('1'));
+        assert_eq!(second_char(&view, 1), Some('2'));
+        assert_eq!(first_char(&view, 2), Some('L'));
+
+        // Scrolled to the top: nothing above the oldest line
+        let view = scrolled_view(&live, &scrollback, 3, 3);
+        assert_eq!(first_char(&view, 0), Some(' '));
+        assert_eq!(second_char(&view, 1), Some('0'));
+    }
+
+    #[test]
+    fn test_folded_view_keeps_bottom_row() {
+        let mut scrollback = ScrollbackBuffer::new(100);
+        for text in ["h0", "h1", "h2"] {
+            scrollback.push_line(ScrollbackLine::from_text(text));
+        }
+        let mut live = MockTerminalState::new(4, 3);
+        live.set_cell(
+            2,
+            0,
+            Cell {
+                char_codepoint: '$' as u32,
+                ..Cell::default()
+            },
+        );
+
+        // The first two rows of the screen folded into one
+        let fold = LineFold {
+            id: 7,
+            start: 3,
+            end: 4,
+            summary: ScrollbackLine::from_text("SUM").cells,
+        };
+        let (view, summary_rows) = folded_view(&live, &scrollback, 0, 3, &[fold]);
+        let line = |row: usize| -> String {
+            (0..4)
+                .filter_map(|col| char::from_u32(view.cell(row, col).unwrap().char_codepoint))
+                .collect()
+        };
+        assert_eq!(line(3), "$   ");
+        assert_eq!(line(2), "SUM ");
+        assert_eq!(line(1), "h2  ");
+        assert_eq!(line(0), "h1  ");
+        assert_eq!(summary_rows, vec![(2, 7)]);
+    }
+}
@@ -0,0 +1,360 @@
+// CPU rasterizer for PNG snapshots of the terminal grid
+//
+// Used by headless mode to write golden images without a GPU, and by
+// screenshots in the windowed client. Cells are drawn the same way as the
+// GPU path in text.rs: backgrounds fill whole cells (unset backgrounds get
+// the theme's), glyphs come from the font fallback chain and sit centered
+// on a common baseline, and dim/reverse/underline/strikethrough are honored.
+
+use cosmic_text::{CacheKey, CacheKeyFlags, FontSystem, SubpixelBin, SwashCache, SwashContent};
+use image::{Rgba, RgbaImage};
+use scarab_protocol::terminal_state::TerminalStateReader;
+use scarab_protocol::Cell;
+use std::path::Path;
+
+use super::config::{FontConfig, TextAttributes};
+use super::fallback::FontFallback;
+
+/// Fraction of the cell height from the top of the cell to the baseline
+const BASELINE_RATIO: f32 = 0.8;
+
+/// Theme background the GPU path draws behind cells (Slime dark #0d1208)
+const THEME_BG: u32 = 0xFF0D1208;
+
+/// A rectangle of grid cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRect {
+    pub col: u16,
+    pub row: u16,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl CellRect {
+    /// Smallest rectangle covering two corner cells, in either order
+    pub fn spanning(a: (u16, u16), b: (u16, u16)) -> Self {
+        let (col, row) = (a.0.min(b.0), a.1.min(b.1));
+        Self {
+            col,
+            row,
+            cols: a.0.max(b.0) - col + 1,
+            rows: a.1.max(b.1) - row + 1,
+        }
+    }
+}
+
+/// Software renderer that draws a terminal grid into an RGBA image
+pub struct SnapshotRenderer {
+    font_system: FontSystem,
+    swash_cache: SwashCache,
+    fallback: FontFallback,
+    font_size: f32,
+    cell_width: u32,
+    cell_height: u32,
+}
+
+impl SnapshotRenderer {
+    /// Create a renderer using the system fonts
+    pub fn new(config: &FontConfig) -> Self {
+        let mut font_system = FontSystem::new();
+        font_system.db_mut().load_system_fonts();
+        Self::with_font_system(config, font_system)
+    }
+
+    /// Create a renderer with a specific font database
+    pub fn with_font_system(config: &FontConfig, font_system: FontSystem) -> Self {
+        let (cell_width, cell_height) = config.cell_dimensions();
+        Self {
+            font_system,
+            swash_cache: SwashCache::new(),
+            fallback: FontFallback::new(
+                config
+                    .all_families()
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            ),
+            font_size: config.size,
+            cell_width: (cell_width.round() as u32).max(1),
+            cell_height: (cell_height.round() as u32).max(1),
+        }
+    }
+
+    /// Size of one cell in pixels
+    pub fn cell_size(&self) -> (u32, u32) {
+        (self.cell_width, self.cell_height)
+    }
+
+    /// Draw every cell of the grid
+    pub fn render(&mut self, state: &impl TerminalStateReader) -> RgbaImage {
+        let (cols, rows) = state.dimensions();
+        self.render_region(
+            state,
+            CellRect {
+                col: 0,
+                row: 0,
+                cols: cols as u16,
+                rows: rows as u16,
+            },
+        )
+    }
+
+    /// Draw the cells inside `region`, with its top-left cell at the origin
+    ///
+    /// Cells past the edge of the grid are left transparent.
+    pub fn render_region(
+        &mut self,
+        state: &impl TerminalStateReader,
+        region: CellRect,
+    ) -> RgbaImage {
+        let mut image = RgbaImage::new(
+            region.cols as u32 * self.cell_width,
+            region.rows as u32 * self.cell_height,
+        );
+
+        for row in 0..region.rows {
+            for col in 0..region.cols {
+                let cell = state.cell((region.row + row) as usize, (region.col + col) as usize);
+                if let Some(cell) = cell {
+                    self.draw_cell(
+                        &mut image,
+                        cell,
+                        col as u32 * self.cell_width,
+                        row as u32 * self.cell_height,
+                    );
+                }
+            }
+        }
+
+        image
+    }
+
+    /// Render the grid and write it to a PNG file
+    pub fn save_png(
+        &mut self,
+        state: &impl TerminalStateReader,
+        path: &Path,
+    ) -> image::ImageResult<()> {
+        self.render(state).save(path)
+    }
+
+    fn draw_cell(&mut self, image: &mut RgbaImage, cell: &Cell, x: u32, y: u32) {
+        let attrs = TextAttributes::from_flags(cell.flags);
+        let (fg, bg) = cell_colors(cell, attrs);
+
+        fill_rect(image, x, y, self.cell_width, self.cell_height, bg);
+
+        if let Some(ch) =
+            char::from_u32(cell.char_codepoint).filter(|c| *c != '\0' && !c.is_whitespace())
+        {
+            self.draw_glyph(image, ch, attrs, fg, x, y);
+        }
+
+        let thickness = (self.cell_height / 16).max(1);
+        if attrs.underline {
+            let underline_y = y + self.cell_height - thickness;
+            fill_rect(image, x, underline_y, self.cell_width, thickness, fg);
+        }
+        if attrs.strikethrough {
+            let strike_y = y + self.cell_height / 2;
+            fill_rect(image, x, strike_y, self.cell_width, thickness, fg);
+        }
+    }
+
+    fn draw_glyph(
+        &mut self,
+        image: &mut RgbaImage,
+        ch: char,
+        attrs: TextAttributes,
+        fg: [u8; 4],
+        x: u32,
+        y: u32,
+    ) {
+        let Some(resolved) =
+            self.fallback
+                .resolve(&mut self.font_system, ch, attrs.bold, attrs.italic)
+        else {
+            return;
+        };
+
+        let cache_key = CacheKey {
+            font_id: resolved.font_id,
+            glyph_id: resolved.glyph_id,
+            font_size_bits: self.font_size.to_bits(),
+            x_bin: SubpixelBin::Zero,
+            y_bin: SubpixelBin::Zero,
+            flags: CacheKeyFlags::empty(),
+        };
+        let Some(glyph) = self
+            .swash_cache
+            .get_image(&mut self.font_system, cache_key)
+            .as_ref()
+        else {
+            return;
+        };
+
+        let width = glyph.placement.width as i64;
+        let height = glyph.placement.height as i64;
+        let left = x as i64 + (self.cell_width as i64 - width).max(0) / 2;
+        let baseline = y as i64 + (self.cell_height as f32 * BASELINE_RATIO) as i64;
+        let top = baseline - glyph.placement.top as i64;
+
+        for gy in 0..height {
+            for gx in 0..width {
+                let (px, py) = (left + gx, top + gy);
+                if px < 0 || py < 0 || px >= image.width() as i64 || py >= image.height() as i64 {
+                    continue;
+                }
+
+                let src = (gy * width + gx) as usize;
+                let (color, alpha) = match glyph.content {
+                    SwashContent::Mask => (fg, glyph.data.get(src).copied().unwrap_or(0)),
+                    SwashContent::SubpixelMask => {
+                        (fg, glyph.data.get(src * 3).copied().unwrap_or(0))
+                    }
+                    SwashContent::Color => match glyph.data.get(src * 4..src * 4 + 4) {
+                        Some(&[r, g, b, a]) => ([r, g, b, 255], a),
+                        _ => (fg, 0),
+                    },
+                };
+
+                let pixel = image.get_pixel_mut(px as u32, py as u32);
+                *pixel = blend(*pixel, color, alpha);
+            }
+        }
+    }
+}
+
+/// Foreground and background bytes for a cell, honoring dim and reverse video
+///
+/// Like the GPU path, a background of 0 or opaque black shows the theme's.
+fn cell_colors(cell: &Cell, attrs: TextAttributes) -> ([u8; 4], [u8; 4]) {
+    let mut fg = argb_to_rgba(cell.fg);
+    let bg = match cell.bg {
+        0 | 0xFF000000 => THEME_BG,
+        bg => bg,
+    };
+    let mut bg = argb_to_rgba(bg);
+    if attrs.dim {
+        for channel in &mut fg[..3] {
+            *channel /= 2;
+        }
+    }
+    if attrs.reverse {
+        std::mem::swap(&mut fg, &mut bg);
+    }
+    (fg, bg)
+}
+
+/// Convert a packed ARGB cell color to RGBA bytes
+pub fn argb_to_rgba(argb: u32) -> [u8; 4] {
+    let [a, r, g, b] = argb.to_be_bytes();
+    [r, g, b, a]
+}
+
+fn fill_rect(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: [u8; 4]) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, Rgba(color));
+        }
+    }
+}
+
+/// Draw `color` over `dst` with the given coverage
+fn blend(dst: Rgba<u8>, color: [u8; 4], alpha: u8) -> Rgba<u8> {
+    let a = alpha as u32;
+    let mix = |src: u8, dst: u8| ((src as u32 * a + dst as u32 * (255 - a)) / 255) as u8;
+    Rgba([
+        mix(color[0], dst[0]),
+        mix(color[1], dst[1]),
+        mix(color[2], dst[2]),
+        dst[3].max(alpha),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_state::MockTerminalState;
+
+    /// Renderer without any fonts, so output does not depend on the host
+    fn renderer() -> SnapshotRenderer {
+        let font_system = FontSystem::new_with_locale_and_db(
+            "en-US".into(),
+            cosmic_text::fontdb::Database::new(),
+        );
+        SnapshotRenderer::with_font_system(&FontConfig::default(), font_system)
+    }
+
+    #[test]
+    fn test_snapshot_size_and_backgrounds() {
+        let mut renderer = renderer();
+        let (cw, ch) = renderer.cell_size();
+
+        let mut state = MockTerminalState::new(4, 2);
+        state.cells_mut()[5].bg = 0xFFFF0000;
+        state.cells_mut()[6].bg = 0xFF0000FF;
+        state.cells_mut()[6].flags = TextAttributes {
+            reverse: true,
+            ..Default::default()
+        }
+        .to_flags();
+
+        let image = renderer.render(&state);
+        assert_eq!(image.dimensions(), (4 * cw, 2 * ch));
+        assert_eq!(image.get_pixel(0, 0).0, argb_to_rgba(0xFF0D1208));
+        assert_eq!(image.get_pixel(cw + 1, ch + 1).0, [255, 0, 0, 255]);
+        // Reverse video paints the foreground color as the background
+        assert_eq!(
+            image.get_pixel(2 * cw + 1, ch + 1).0,
+            argb_to_rgba(0xFFA8DF5A)
+        );
+    }
+
+    #[test]
+    fn test_render_region() {
+        let mut renderer = renderer();
+        let (cw, ch) = renderer.cell_size();
+
+        let mut state = MockTerminalState::new(4, 3);
+        state.cells_mut()[4 + 2].bg = 0xFFFF0000;
+        state.cells_mut()[8 + 3].bg = 0;
+
+        let region = CellRect::spanning((3, 2), (2, 1));
+        assert_eq!(
+            region,
+            CellRect {
+                col: 2,
+                row: 1,
+                cols: 2,
+                rows: 2
+            }
+        );
+
+        let image = renderer.render_region(&state, region);
+        assert_eq!(image.dimensions(), (2 * cw, 2 * ch));
+        assert_eq!(image.get_pixel(1, 1).0, [255, 0, 0, 255]);
+        // An unset background shows the theme's
+        assert_eq!(image.get_pixel(cw + 1, ch + 1).0, argb_to_rgba(THEME_BG));
+
+        // Past the edge of the grid stays transparent
+        let image = renderer.render_region(
+            &state,
+            CellRect {
+                col: 3,
+                row: 0,
+                cols: 2,
+                rows: 1,
+            },
+        );
+        assert_eq!(image.get_pixel(cw + 1, 1).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_blend() {
+        let dst = Rgba([0, 0, 0, 255]);
+        assert_eq!(blend(dst, [200, 100, 50, 255], 255).0, [200, 100, 50, 255]);
+        assert_eq!(blend(dst, [200, 100, 50, 255], 0).0, [0, 0, 0, 255]);
+        assert_eq!(blend(dst, [200, 100, 50, 255], 128).0, [100, 50, 25, 255]);
+    }
+}
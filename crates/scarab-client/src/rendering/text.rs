@@ -4,12 +4,23 @@ use bevy::prelude::*;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use bevy::render::render_asset::RenderAssetUsages;
 use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, SwashCache};
-use scarab_protocol::{terminal_state::TerminalStateReader, Cell};
+use scarab_protocol::{row_damaged, terminal_state::TerminalStateReader, Cell};
 use std::collections::HashSet;
 
 use super::atlas::{AtlasRect, GlyphAtlas, GlyphKey};
 use super::config::{color, FontConfig, TextAttributes};
+use super::fallback::FontFallback;
 use super::layers::{LAYER_TERMINAL_BG, LAYER_TERMINAL_TEXT, LAYER_TEXT_DECORATIONS};
+use super::shaping::RunShaper;
+
+/// Cell background of the theme (Slime dark #0d1208), drawn by the
+/// TerminalBackgroundEntity sprite rather than per cell
+const THEME_BG: u32 = 0xFF0D1208;
+
+/// Whether a cell background is the theme's, so needs no quad of its own
+pub(super) fn is_default_background(bg: u32) -> bool {
+    bg == 0 || bg == 0xFF000000 || bg == THEME_BG
+}
 
 /// Text renderer resource managing fonts and glyph caching
 #[derive(Resource)]
@@ -17,9 +28,16 @@ pub struct TextRenderer {
     pub font_system: FontSystem,
     pub swash_cache: SwashCache,
     pub atlas: GlyphAtlas,
+    pub shaper: RunShaper,
+    pub fallback: FontFallback,
     pub config: FontConfig,
     pub cell_width: f32,
     pub cell_height: f32,
+    /// Contrast ratio text is adjusted to reach against its background;
+    /// 1.0 draws theme colors unchanged
+    pub minimum_contrast: f32,
+    /// Background behind cells without a color of their own, as ARGB
+    pub default_background: u32,
 }
 
 impl TextRenderer {
@@ -51,9 +69,19 @@ impl TextRenderer {
             font_system,
             swash_cache,
             atlas,
+            shaper: RunShaper::new(),
+            fallback: FontFallback::new(
+                config
+                    .all_families()
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            ),
             config,
             cell_width,
             cell_height,
+            minimum_contrast: 1.0,
+            default_background: THEME_BG,
         }
     }
 
@@ -107,6 +135,8 @@ impl TextRenderer {
 pub struct DirtyRegion {
     /// Set of dirty cell indices
     dirty_cells: HashSet<usize>,
+    /// Set of dirty rows
+    dirty_rows: HashSet<usize>,
     /// Whether the entire grid is dirty
     full_redraw: bool,
 }
@@ -115,6 +145,7 @@ impl DirtyRegion {
     pub fn new() -> Self {
         Self {
             dirty_cells: HashSet::new(),
+            dirty_rows: HashSet::new(),
             full_redraw: true, // Start with full redraw
         }
     }
@@ -123,9 +154,39 @@ impl DirtyRegion {
         self.dirty_cells.insert(index);
     }
 
+    pub fn mark_row_dirty(&mut self, row: usize) {
+        self.dirty_rows.insert(row);
+    }
+
+    /// Mark the rows changed since `last_sequence`
+    ///
+    /// Uses the daemon's damage bitmask when it covers exactly the one update
+    /// since `last_sequence`; after a skipped update, or without damage
+    /// information, the whole grid is redrawn.
+    pub fn apply_damage(&mut self, state: &impl TerminalStateReader, last_sequence: u64) {
+        let sequence = state.sequence();
+        match state.row_damage() {
+            Some(damage) if last_sequence != 0 && sequence == last_sequence + 1 => {
+                let (_width, height) = state.dimensions();
+                for row in (0..height).filter(|row| row_damaged(&damage, *row)) {
+                    self.mark_row_dirty(row);
+                }
+            }
+            _ => self.mark_full_redraw(),
+        }
+    }
+
+    /// Rows containing a dirty cell or marked dirty directly
+    pub fn dirty_rows(&self, width: usize) -> HashSet<usize> {
+        let mut rows = self.dirty_rows.clone();
+        rows.extend(self.dirty_cells.iter().map(|idx| idx / width.max(1)));
+        rows
+    }
+
     pub fn mark_full_redraw(&mut self) {
         self.full_redraw = true;
         self.dirty_cells.clear();
+        self.dirty_rows.clear();
     }
 
     pub fn is_dirty(&self, index: usize) -> bool {
@@ -134,11 +195,12 @@ impl DirtyRegion {
 
     pub fn clear(&mut self) {
         self.dirty_cells.clear();
+        self.dirty_rows.clear();
         self.full_redraw = false;
     }
 
     pub fn is_empty(&self) -> bool {
-        !self.full_redraw && self.dirty_cells.is_empty()
+        !self.full_redraw && self.dirty_cells.is_empty() && self.dirty_rows.is_empty()
     }
 
     pub fn is_full_redraw(&self) -> bool {
@@ -152,6 +214,7 @@ pub struct TerminalMesh {
     pub dirty_region: DirtyRegion,
     pub last_sequence: u64,
     pub mesh_handle: Handle<Mesh>, // Store handle in component
+    pub cache: MeshCache,
 }
 
 impl TerminalMesh {
@@ -160,6 +223,7 @@ impl TerminalMesh {
             dirty_region: DirtyRegion::new(),
             last_sequence: 0,
             mesh_handle,
+            cache: MeshCache::default(),
         }
     }
 }
@@ -170,10 +234,161 @@ impl Default for TerminalMesh {
             dirty_region: DirtyRegion::new(),
             last_sequence: 0,
             mesh_handle: Handle::default(),
+            cache: MeshCache::default(),
         }
     }
 }
 
+/// Vertex and index data for part of the terminal mesh
+///
+/// Indices start at zero for each buffer and are offset when buffers are
+/// combined into the final mesh.
+#[derive(Debug, Clone, Default)]
+pub struct MeshBuffers {
+    pub positions: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub colors: Vec<[f32; 4]>,
+    pub indices: Vec<u32>,
+    pub vertex_count: u32,
+}
+
+impl MeshBuffers {
+    /// Append another buffer, offsetting its indices past our vertices
+    fn append(&mut self, other: &MeshBuffers) {
+        let base = self.vertex_count;
+        self.positions.extend_from_slice(&other.positions);
+        self.uvs.extend_from_slice(&other.uvs);
+        self.colors.extend_from_slice(&other.colors);
+        self.indices.extend(other.indices.iter().map(|i| i + base));
+        self.vertex_count += other.vertex_count;
+    }
+
+    /// Number of quads in the buffer
+    pub fn quad_count(&self) -> usize {
+        self.vertex_count as usize / 4
+    }
+}
+
+/// Cached geometry of one grid row
+#[derive(Debug, Clone, Default)]
+struct RowGeometry {
+    backgrounds: MeshBuffers,
+    glyphs: MeshBuffers,
+}
+
+/// Per-row terminal geometry reused between frames
+///
+/// Only rows marked dirty are rebuilt; the rest keep their quads from earlier
+/// frames. Everything is rebuilt after a full redraw, a cell size change, or
+/// a glyph atlas eviction, since cached UVs may then point at other glyphs.
+#[derive(Debug, Default)]
+pub struct MeshCache {
+    rows: Vec<RowGeometry>,
+    cell_size: Vec2,
+    atlas_generation: u64,
+}
+
+impl MeshCache {
+    /// Rebuild the dirty rows, returning how many rows were rebuilt
+    pub fn update(
+        &mut self,
+        state: &impl TerminalStateReader,
+        renderer: &mut TextRenderer,
+        dirty_region: &DirtyRegion,
+    ) -> usize {
+        let (width, height) = state.dimensions();
+        let cells = state.cells();
+        let cell_size = Vec2::new(renderer.cell_width, renderer.cell_height);
+
+        renderer.atlas.begin_frame();
+        let generation = renderer.atlas.generation();
+        let full = dirty_region.is_full_redraw()
+            || self.rows.len() != height
+            || self.cell_size != cell_size
+            || self.atlas_generation != generation;
+
+        self.rows.resize_with(height, RowGeometry::default);
+        self.cell_size = cell_size;
+
+        let rows: Vec<usize> = if full {
+            (0..height).collect()
+        } else {
+            let mut rows: Vec<usize> = dirty_region
+                .dirty_rows(width)
+                .into_iter()
+                .filter(|row| *row < height)
+                .collect();
+            rows.sort_unstable();
+            rows
+        };
+
+        let mut stats = GlyphStats::default();
+        for &row in &rows {
+            self.rows[row] = build_row(row_cells(cells, width, row), row, renderer, &mut stats);
+        }
+
+        // An eviction while rebuilding can invalidate rows we kept
+        let mut rebuilt = rows.len();
+        if !full && renderer.atlas.generation() != generation {
+            for row in 0..height {
+                self.rows[row] = build_row(row_cells(cells, width, row), row, renderer, &mut stats);
+            }
+            rebuilt = height;
+        }
+        self.atlas_generation = renderer.atlas.generation();
+
+        if stats.attempts > 0 {
+            debug!(
+                "Mesh update: {} rows rebuilt, {}/{} glyphs rendered successfully",
+                rebuilt, stats.success, stats.attempts
+            );
+        }
+
+        rebuilt
+    }
+
+    /// Combine the cached rows into a mesh
+    ///
+    /// Backgrounds of every row come before any glyphs, so glyphs are never
+    /// covered by a later row's background when depth testing is disabled.
+    pub fn build_mesh(&self) -> Mesh {
+        let mut combined = MeshBuffers::default();
+        for row in &self.rows {
+            combined.append(&row.backgrounds);
+        }
+        for row in &self.rows {
+            combined.append(&row.glyphs);
+        }
+
+        // Use MAIN_WORLD | RENDER_WORLD so the mesh can be accessed from update systems
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+        );
+
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, combined.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, combined.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, combined.colors);
+        mesh.insert_indices(Indices::U32(combined.indices));
+
+        mesh
+    }
+}
+
+/// Glyph counters for debug logging
+#[derive(Default)]
+struct GlyphStats {
+    attempts: usize,
+    success: usize,
+}
+
+/// Cells of one row, empty if the row is out of range
+fn row_cells(cells: &[Cell], width: usize, row: usize) -> &[Cell] {
+    let start = (row * width).min(cells.len());
+    let end = (start + width).min(cells.len());
+    &cells[start..end]
+}
+
 /// Generate mesh from terminal grid state
 ///
 /// Now accepts any type implementing TerminalStateReader for safe access.
@@ -188,47 +403,46 @@ pub fn generate_terminal_mesh(
     dirty_region: &DirtyRegion,
     images: &mut ResMut<Assets<Image>>,
 ) -> Mesh {
-    let mut positions = Vec::new();
-    let mut uvs = Vec::new();
-    let mut colors = Vec::new();
-    let mut indices = Vec::new();
+    // Without a cache to patch, every row is built regardless of the dirty
+    // region; `MeshCache` handles incremental updates.
+    let _ = dirty_region;
+    let mut cache = MeshCache::default();
+    cache.update(state, renderer, &DirtyRegion::new());
 
-    let mut vertex_index = 0u32;
-
-    // Get UVs for white pixel (for solid backgrounds)
-    let white_uv_rect = renderer.atlas.get_white_pixel_uv();
+    // Update atlas texture if dirty
+    renderer.atlas.update_texture(images);
 
-    let (width, _height) = state.dimensions();
-    let cells = state.cells();
+    cache.build_mesh()
+}
 
-    // Note: We always regenerate the full mesh because partial updates would require
-    // incremental mesh modification which is complex. The dirty_region parameter is
-    // kept for future optimization but currently ignored.
-    let _ = dirty_region; // Silence unused warning
+/// Build the background and glyph quads of one row
+fn build_row(
+    row_cells: &[Cell],
+    row: usize,
+    renderer: &mut TextRenderer,
+    stats: &mut GlyphStats,
+) -> RowGeometry {
+    let mut geometry = RowGeometry::default();
 
-    // PASS 1: Render ALL backgrounds first
-    // This ensures backgrounds are drawn before any glyphs, preventing
-    // backgrounds from covering glyphs when depth testing is disabled.
-    for (idx, cell) in cells.iter().enumerate() {
-        let row = idx / width;
-        let col = idx % width;
+    // Get UVs for white pixel (for solid backgrounds)
+    let white_uv_rect = renderer.atlas.get_white_pixel_uv();
+    let y = -(row as f32 * renderer.cell_height);
 
+    // Backgrounds
+    for (col, cell) in row_cells.iter().enumerate() {
         let x = col as f32 * renderer.cell_width;
-        let y = -(row as f32 * renderer.cell_height);
 
         // Background quad - only render when cell bg differs from theme default
         // The TerminalBackgroundEntity sprite provides the uniform theme background,
         // so we only need to render background quads for cells with custom colors.
-        // Theme default: 0xFF0D1208 (Slime dark #0d1208)
-        // Also treat 0 and 0xFF000000 as theme default to avoid rendering unnecessary quads
-        let needs_custom_bg = cell.bg != 0 && cell.bg != 0xFF000000 && cell.bg != 0xFF0D1208;
-        if needs_custom_bg {
+        if !is_default_background(cell.bg) {
+            let bg = &mut geometry.backgrounds;
             add_background_quad(
-                &mut positions,
-                &mut uvs,
-                &mut colors,
-                &mut indices,
-                &mut vertex_index,
+                &mut bg.positions,
+                &mut bg.uvs,
+                &mut bg.colors,
+                &mut bg.indices,
+                &mut bg.vertex_count,
                 x,
                 y,
                 renderer.cell_width,
@@ -239,61 +453,73 @@ pub fn generate_terminal_mesh(
         }
     }
 
-    // PASS 2: Render ALL glyphs after backgrounds
-    let mut glyph_attempts = 0;
-    let mut glyph_success = 0;
+    // Glyphs
+    // Consecutive cells with the same style form a run. With ligatures
+    // enabled, each run is shaped as a whole so the font can substitute
+    // multi-character glyphs; otherwise every cell is rendered on its own.
+    let out = &mut geometry.glyphs;
+    let mut col = 0;
+    while col < row_cells.len() {
+        let cell = &row_cells[col];
+        if !has_glyph(cell) {
+            col += 1;
+            continue;
+        }
 
-    for (idx, cell) in cells.iter().enumerate() {
-        let row = idx / width;
-        let col = idx % width;
+        let mut end = col + 1;
+        while end < row_cells.len()
+            && has_glyph(&row_cells[end])
+            && same_style(cell, &row_cells[end])
+        {
+            end += 1;
+        }
 
+        let run = &row_cells[col..end];
         let x = col as f32 * renderer.cell_width;
-        let y = -(row as f32 * renderer.cell_height);
+        stats.attempts += run.len();
 
-        // Foreground glyph
-        if cell.char_codepoint != 0 && cell.char_codepoint != 32 {
-            glyph_attempts += 1;
-            if render_glyph(
-                cell,
+        let rendered = if renderer.config.ligatures && run.len() > 1 {
+            render_run(
+                run,
                 renderer,
-                &mut positions,
-                &mut uvs,
-                &mut colors,
-                &mut indices,
-                &mut vertex_index,
+                &mut out.positions,
+                &mut out.uvs,
+                &mut out.colors,
+                &mut out.indices,
+                &mut out.vertex_count,
                 x,
                 y,
             )
-            .is_some()
-            {
-                glyph_success += 1;
+        } else {
+            None
+        };
+
+        if let Some(count) = rendered {
+            stats.success += count;
+        } else {
+            for (i, cell) in run.iter().enumerate() {
+                if render_glyph(
+                    cell,
+                    renderer,
+                    &mut out.positions,
+                    &mut out.uvs,
+                    &mut out.colors,
+                    &mut out.indices,
+                    &mut out.vertex_count,
+                    x + i as f32 * renderer.cell_width,
+                    y,
+                )
+                .is_some()
+                {
+                    stats.success += 1;
+                }
             }
         }
-    }
 
-    if glyph_attempts > 0 {
-        debug!(
-            "Mesh generation: {}/{} glyphs rendered successfully",
-            glyph_success, glyph_attempts
-        );
+        col = end;
     }
 
-    // Update atlas texture if dirty
-    renderer.atlas.update_texture(images);
-
-    // Build mesh
-    // Use MAIN_WORLD | RENDER_WORLD so the mesh can be accessed from update systems
-    let mut mesh = Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
-    );
-
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
-    mesh.insert_indices(Indices::U32(indices));
-
-    mesh
+    geometry
 }
 
 /// Add a background quad for a cell
@@ -352,9 +578,22 @@ fn add_background_quad(
     *vertex_index += 4;
 }
 
-/// Render a glyph quad
-fn render_glyph(
-    cell: &Cell,
+/// Whether a cell has a visible character to draw
+fn has_glyph(cell: &Cell) -> bool {
+    cell.char_codepoint != 0 && cell.char_codepoint != 32
+}
+
+/// Whether two cells can be shaped as part of the same run
+fn same_style(a: &Cell, b: &Cell) -> bool {
+    a.flags == b.flags && a.fg == b.fg && a.bg == b.bg
+}
+
+/// Render a run of same-style cells through the shaping stage
+///
+/// Returns the number of cells covered by rendered glyphs, or `None` if the
+/// run could not be shaped and should be rendered cell by cell instead.
+fn render_run(
+    run: &[Cell],
     renderer: &mut TextRenderer,
     positions: &mut Vec<[f32; 3]>,
     uvs: &mut Vec<[f32; 2]>,
@@ -363,71 +602,136 @@ fn render_glyph(
     vertex_index: &mut u32,
     x: f32,
     y: f32,
-) -> Option<AtlasRect> {
-    // Get character from codepoint
-    let ch = char::from_u32(cell.char_codepoint)?;
-
-    // Parse text attributes
-    let attrs = TextAttributes::from_flags(cell.flags);
-
-    // Get the glyph cache key
-    // Use a block to limit the scope of the buffer borrow on font_system
-    let glyph_key = {
-        // Create cosmic-text buffer to get glyph info
-        let metrics = Metrics::new(
-            renderer.config.size,
-            renderer.config.size * renderer.config.line_height,
-        );
-        let mut buffer = Buffer::new(&mut renderer.font_system, metrics);
+) -> Option<usize> {
+    let text: String = run
+        .iter()
+        .filter_map(|cell| char::from_u32(cell.char_codepoint))
+        .collect();
+    if text.chars().count() != run.len() {
+        return None;
+    }
 
-        // CRITICAL: Set buffer size or shaping won't work!
-        buffer.set_size(&mut renderer.font_system, 100.0, 100.0);
+    let attrs = TextAttributes::from_flags(run[0].flags);
+    let shaped = renderer.shaper.shape(
+        &mut renderer.font_system,
+        &text,
+        attrs.bold,
+        attrs.italic,
+        renderer.config.size,
+        &renderer.config.features,
+    )?;
 
-        // Build attrs with font family and bold/italic
-        // CRITICAL: Specify monospace font family to prevent cosmic-text from
-        // picking different fonts for different characters
-        let mut cosmic_attrs = Attrs::new().family(cosmic_text::Family::Monospace);
+    let fg_array = glyph_color(&run[0], attrs, renderer);
+    let baseline_y = y - renderer.cell_height * 0.8;
 
-        if attrs.bold {
-            cosmic_attrs = cosmic_attrs.weight(cosmic_text::Weight::BOLD);
-        }
-        if attrs.italic {
-            cosmic_attrs = cosmic_attrs.style(cosmic_text::Style::Italic);
+    // Cells drawn via the per-cell fallback already carry their decorations
+    let mut fallback = vec![false; run.len()];
+    let mut covered = vec![false; run.len()];
+
+    for glyph in &shaped.glyphs {
+        let cluster = glyph.cell.min(run.len())..(glyph.cell + glyph.cells).min(run.len());
+
+        if glyph.glyph_id == 0 {
+            // The run's face lacks this character; let cosmic-text pick a fallback font
+            for i in cluster {
+                if fallback[i] {
+                    continue;
+                }
+                fallback[i] = true;
+                covered[i] = render_glyph(
+                    &run[i],
+                    renderer,
+                    positions,
+                    uvs,
+                    colors,
+                    indices,
+                    vertex_index,
+                    x + i as f32 * renderer.cell_width,
+                    y,
+                )
+                .is_some();
+            }
+            continue;
         }
 
-        buffer.set_text(
+        let glyph_key = GlyphKey {
+            font_id: shaped.font_id,
+            glyph_id: glyph.glyph_id,
+            font_size_bits: renderer.config.size.to_bits(),
+        };
+
+        let Some(atlas_rect) = renderer.atlas.get_or_cache(
             &mut renderer.font_system,
-            &ch.to_string(),
-            cosmic_attrs,
-            Shaping::Advanced,
+            glyph_key,
+            &mut renderer.swash_cache,
+        ) else {
+            continue;
+        };
+
+        // Ligature glyphs keep their shaped offsets so their pieces join up
+        // across cells instead of being centered in each one
+        let glyph_x = x
+            + glyph.cell as f32 * renderer.cell_width
+            + glyph.x_offset
+            + atlas_rect.placement_left as f32;
+        let glyph_top_y = baseline_y + glyph.y_offset + atlas_rect.placement_top as f32;
+
+        add_glyph_quad(
+            positions,
+            uvs,
+            colors,
+            indices,
+            vertex_index,
+            glyph_x,
+            glyph_top_y,
+            &atlas_rect,
+            fg_array,
         );
 
-        // CRITICAL: Must shape the buffer before layout_runs() will work!
-        buffer.shape_until_scroll(&mut renderer.font_system, false);
-
-        // Get the first glyph from the first run
-        let layout_runs: Vec<_> = buffer.layout_runs().collect();
-        let run_count = layout_runs.len();
-
-        let key = layout_runs
-            .first()
-            .and_then(|run| run.glyphs.first())
-            .map(|glyph| GlyphKey {
-                font_id: glyph.font_id,
-                glyph_id: glyph.glyph_id,
-                font_size_bits: glyph.font_size.to_bits(),
-            });
-
-        if key.is_none() {
-            warn!(
-                "No glyph found for char '{}' (U+{:04X}), runs: {}",
-                ch, ch as u32, run_count
+        for flag in &mut covered[cluster] {
+            *flag = true;
+        }
+    }
+
+    for (i, cell) in run.iter().enumerate() {
+        if !fallback[i] {
+            add_cell_decorations(
+                cell,
+                attrs,
+                renderer,
+                positions,
+                uvs,
+                colors,
+                indices,
+                vertex_index,
+                x + i as f32 * renderer.cell_width,
+                y,
             );
         }
-        key
-    };
+    }
+
+    Some(covered.iter().filter(|c| **c).count())
+}
 
-    let glyph_key = glyph_key?;
+/// Render a glyph quad
+fn render_glyph(
+    cell: &Cell,
+    renderer: &mut TextRenderer,
+    positions: &mut Vec<[f32; 3]>,
+    uvs: &mut Vec<[f32; 2]>,
+    colors: &mut Vec<[f32; 4]>,
+    indices: &mut Vec<u32>,
+    vertex_index: &mut u32,
+    x: f32,
+    y: f32,
+) -> Option<AtlasRect> {
+    // Get character from codepoint
+    let ch = char::from_u32(cell.char_codepoint)?;
+
+    // Parse text attributes
+    let attrs = TextAttributes::from_flags(cell.flags);
+
+    let glyph_key = cell_glyph_key(ch, attrs, renderer)?;
 
     // Get or cache the glyph in atlas
     let atlas_rect = renderer.atlas.get_or_cache(
@@ -436,29 +740,11 @@ fn render_glyph(
         &mut renderer.swash_cache,
     )?;
 
-    // Get UV coordinates
-    let uv_rect = atlas_rect.uv_rect();
-
-    // Get foreground color (with dim attribute)
-    // from_rgba returns linear color for vertex colors
-    let mut fg = color::from_rgba(cell.fg);
-    if attrs.dim {
-        let [r, g, b, a] = fg.to_linear().to_f32_array();
-        fg = Color::linear_rgba(r * 0.5, g * 0.5, b * 0.5, a);
-    }
-
-    // Handle reverse video
-    if attrs.reverse {
-        fg = color::from_rgba(cell.bg);
-    }
-
-    // Use linear color directly for vertex colors
-    let fg_array = fg.to_linear().to_f32_array();
+    let fg_array = glyph_color(cell, attrs, renderer);
 
     // Use the ACTUAL glyph dimensions from the atlas to preserve aspect ratio
     // This prevents stretching/distortion of characters
     let glyph_width = atlas_rect.width as f32;
-    let glyph_height = atlas_rect.height as f32;
 
     // For terminal rendering, we need FIXED cell positioning:
     // - All characters occupy exactly one cell width horizontally
@@ -476,15 +762,108 @@ fn render_glyph(
     let baseline_y = y - renderer.cell_height * 0.8;
     let glyph_top_y = baseline_y + atlas_rect.placement_top as f32;
 
+    add_glyph_quad(
+        positions,
+        uvs,
+        colors,
+        indices,
+        vertex_index,
+        glyph_x,
+        glyph_top_y,
+        &atlas_rect,
+        fg_array,
+    );
+
+    add_cell_decorations(
+        cell,
+        attrs,
+        renderer,
+        positions,
+        uvs,
+        colors,
+        indices,
+        vertex_index,
+        x,
+        y,
+    );
+
+    Some(atlas_rect)
+}
+
+/// Resolve the glyph for a single character through the fallback chain
+fn cell_glyph_key(
+    ch: char,
+    attrs: TextAttributes,
+    renderer: &mut TextRenderer,
+) -> Option<GlyphKey> {
+    let resolved =
+        renderer
+            .fallback
+            .resolve(&mut renderer.font_system, ch, attrs.bold, attrs.italic)?;
+
+    Some(GlyphKey {
+        font_id: resolved.font_id,
+        glyph_id: resolved.glyph_id,
+        font_size_bits: renderer.config.size.to_bits(),
+    })
+}
+
+/// Foreground vertex color for a cell, honoring dim and reverse video
+fn glyph_color(cell: &Cell, attrs: TextAttributes, renderer: &TextRenderer) -> [f32; 4] {
+    // from_rgba returns linear color for vertex colors
+    let mut fg = color::from_rgba(cell.fg);
+    if attrs.dim {
+        let [r, g, b, a] = fg.to_linear().to_f32_array();
+        fg = Color::linear_rgba(r * 0.5, g * 0.5, b * 0.5, a);
+    }
+
+    // Handle reverse video
+    if attrs.reverse {
+        fg = color::from_rgba(cell.bg);
+    }
+
+    if renderer.minimum_contrast > 1.0 {
+        fg = color::from_rgba(contrasting(color::to_rgba(fg), cell, renderer));
+    }
+
+    fg.to_linear().to_f32_array()
+}
+
+/// `fg` adjusted to the renderer's minimum contrast against the background
+/// drawn behind `cell`
+pub(super) fn contrasting(fg: u32, cell: &Cell, renderer: &TextRenderer) -> u32 {
+    if renderer.minimum_contrast <= 1.0 {
+        return fg;
+    }
+    let bg = if is_default_background(cell.bg) {
+        renderer.default_background
+    } else {
+        cell.bg
+    };
+    color::with_minimum_contrast(fg, bg, renderer.minimum_contrast)
+}
+
+/// Add a textured quad for a glyph whose top-left corner is at (x, top_y)
+fn add_glyph_quad(
+    positions: &mut Vec<[f32; 3]>,
+    uvs: &mut Vec<[f32; 2]>,
+    colors: &mut Vec<[f32; 4]>,
+    indices: &mut Vec<u32>,
+    vertex_index: &mut u32,
+    x: f32,
+    top_y: f32,
+    atlas_rect: &AtlasRect,
+    color: [f32; 4],
+) {
+    let uv_rect = atlas_rect.uv_rect();
+    let width = atlas_rect.width as f32;
+    let height = atlas_rect.height as f32;
+
     positions.extend_from_slice(&[
-        [glyph_x, glyph_top_y, LAYER_TERMINAL_TEXT],
-        [glyph_x + glyph_width, glyph_top_y, LAYER_TERMINAL_TEXT],
-        [
-            glyph_x + glyph_width,
-            glyph_top_y - glyph_height,
-            LAYER_TERMINAL_TEXT,
-        ],
-        [glyph_x, glyph_top_y - glyph_height, LAYER_TERMINAL_TEXT],
+        [x, top_y, LAYER_TERMINAL_TEXT],
+        [x + width, top_y, LAYER_TERMINAL_TEXT],
+        [x + width, top_y - height, LAYER_TERMINAL_TEXT],
+        [x, top_y - height, LAYER_TERMINAL_TEXT],
     ]);
 
     // Use normal UVs (no flip)
@@ -496,7 +875,7 @@ fn render_glyph(
     ]);
 
     for _ in 0..4 {
-        colors.push(fg_array);
+        colors.push(color);
     }
 
     indices.extend_from_slice(&[
@@ -509,9 +888,24 @@ fn render_glyph(
     ]);
 
     *vertex_index += 4;
+}
 
+/// Add underline and strikethrough lines for a cell
+fn add_cell_decorations(
+    cell: &Cell,
+    attrs: TextAttributes,
+    renderer: &TextRenderer,
+    positions: &mut Vec<[f32; 3]>,
+    uvs: &mut Vec<[f32; 2]>,
+    colors: &mut Vec<[f32; 4]>,
+    indices: &mut Vec<u32>,
+    vertex_index: &mut u32,
+    x: f32,
+    y: f32,
+) {
     // Get UVs for white pixel (for lines)
     let white_uv_rect = renderer.atlas.get_white_pixel_uv();
+    let line_color = contrasting(cell.fg, cell, renderer);
 
     // Handle underline
     if attrs.underline {
@@ -525,7 +919,7 @@ fn render_glyph(
             y - renderer.cell_height + 2.0,
             renderer.cell_width,
             1.0,
-            cell.fg,
+            line_color,
             white_uv_rect,
         );
     }
@@ -542,12 +936,10 @@ fn render_glyph(
             y - renderer.cell_height / 2.0,
             renderer.cell_width,
             1.0,
-            cell.fg,
+            line_color,
             white_uv_rect,
         );
     }
-
-    Some(atlas_rect)
 }
 
 /// Add underline/strikethrough line
@@ -613,7 +1005,10 @@ pub fn update_terminal_mesh_system(
         // Check if state changed
         let current_seq = safe_state.sequence();
         if current_seq != terminal_mesh.last_sequence {
-            terminal_mesh.dirty_region.mark_full_redraw();
+            let last_sequence = terminal_mesh.last_sequence;
+            terminal_mesh
+                .dirty_region
+                .apply_damage(&safe_state, last_sequence);
             terminal_mesh.last_sequence = current_seq;
         }
 
@@ -622,20 +1017,80 @@ pub fn update_terminal_mesh_system(
             continue;
         }
 
-        // Generate new mesh using safe wrapper
-        let new_mesh = generate_terminal_mesh(
-            &safe_state,
-            &mut renderer,
-            &terminal_mesh.dirty_region,
-            &mut images,
-        );
+        // Rebuild only the dirty rows using safe wrapper
+        let terminal_mesh = &mut *terminal_mesh;
+        terminal_mesh
+            .cache
+            .update(&safe_state, &mut renderer, &terminal_mesh.dirty_region);
+        renderer.atlas.update_texture(&mut images);
 
         // Update mesh asset using the handle stored in the component
         if let Some(mesh) = meshes.get_mut(&terminal_mesh.mesh_handle) {
-            *mesh = new_mesh;
+            *mesh = terminal_mesh.cache.build_mesh();
         }
 
         // Clear dirty region
         terminal_mesh.dirty_region.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_state::MockTerminalState;
+
+    #[test]
+    fn test_apply_damage_marks_rows() {
+        let mut state = MockTerminalState::new(80, 24);
+        state.increment_sequence();
+        state.increment_sequence();
+        state.set_row_damage(&[3, 7]);
+
+        let mut region = DirtyRegion::new();
+        region.clear();
+        region.apply_damage(&state, 1);
+
+        assert!(!region.is_full_redraw());
+        assert_eq!(region.dirty_rows(80), HashSet::from([3, 7]));
+    }
+
+    #[test]
+    fn test_apply_damage_falls_back_to_full_redraw() {
+        let mut state = MockTerminalState::new(80, 24);
+        for _ in 0..3 {
+            state.increment_sequence();
+        }
+        state.set_row_damage(&[3]);
+
+        // An update was skipped, so the damage does not cover everything
+        let mut region = DirtyRegion::new();
+        region.clear();
+        region.apply_damage(&state, 1);
+        assert!(region.is_full_redraw());
+
+        // No damage information at all
+        let state = MockTerminalState::new(80, 24);
+        let mut region = DirtyRegion::new();
+        region.clear();
+        region.apply_damage(&state, 0);
+        assert!(region.is_full_redraw());
+    }
+
+    #[test]
+    fn test_mesh_buffers_append_offsets_indices() {
+        let quad = MeshBuffers {
+            positions: vec![[0.0; 3]; 4],
+            uvs: vec![[0.0; 2]; 4],
+            colors: vec![[1.0; 4]; 4],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            vertex_count: 4,
+        };
+
+        let mut combined = MeshBuffers::default();
+        combined.append(&quad);
+        combined.append(&quad);
+
+        assert_eq!(combined.quad_count(), 2);
+        assert_eq!(&combined.indices[6..], &[4, 5, 6, 4, 6, 7]);
+    }
+}
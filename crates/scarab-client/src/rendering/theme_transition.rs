@@ -0,0 +1,118 @@
+// Theme transitions
+//
+// Colors the client draws itself, the window background and the cursor,
+// fade to a new theme's over THEME_TRANSITION_SECS instead of snapping.
+// Fading is in linear space with an ease-in-out curve, and restarts from the
+// color on screen if the theme changes again mid-fade. `ui.reduce_motion`
+// (or `ui.animations = false`) makes every change immediate.
+
+use bevy::prelude::*;
+
+use crate::ui::animations::easing;
+
+/// How long a theme change takes to fade in
+pub const THEME_TRANSITION_SECS: f32 = 0.2;
+
+/// A color fading towards a target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorFade {
+    from: LinearRgba,
+    to: LinearRgba,
+    /// 0.0 at the start of the fade, 1.0 once the target is reached
+    progress: f32,
+}
+
+impl ColorFade {
+    /// A fade that rests at `color`
+    pub fn new(color: Color) -> Self {
+        let color = color.to_linear();
+        Self {
+            from: color,
+            to: color,
+            progress: 1.0,
+        }
+    }
+
+    /// Head for `color` from the color shown now, or jump to it without
+    /// `animate`
+    ///
+    /// Returns false, leaving the fade alone, if `color` is already the
+    /// target.
+    pub fn set_target(&mut self, color: Color, animate: bool) -> bool {
+        let color = color.to_linear();
+        if color == self.to {
+            return false;
+        }
+        self.from = self.current().to_linear();
+        self.to = color;
+        self.progress = if animate { 0.0 } else { 1.0 };
+        true
+    }
+
+    /// Move the fade on by `secs`
+    pub fn tick(&mut self, secs: f32) {
+        self.progress = (self.progress + secs / THEME_TRANSITION_SECS).min(1.0);
+    }
+
+    /// Whether the target hasn't been reached yet
+    pub fn is_fading(&self) -> bool {
+        self.progress < 1.0
+    }
+
+    /// Color to draw now
+    pub fn current(&self) -> Color {
+        let t = easing::ease_in_out_cubic(self.progress);
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Color::LinearRgba(LinearRgba::new(
+            mix(self.from.red, self.to.red),
+            mix(self.from.green, self.to.green),
+            mix(self.from.blue, self.to.blue),
+            mix(self.from.alpha, self.to.alpha),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_reaches_target() {
+        let mut fade = ColorFade::new(Color::BLACK);
+        assert!(fade.set_target(Color::WHITE, true));
+        assert!(fade.is_fading());
+        assert_eq!(fade.current(), Color::BLACK.to_linear().into());
+
+        fade.tick(THEME_TRANSITION_SECS / 2.0);
+        let halfway = fade.current().to_linear();
+        assert!(halfway.red > 0.0 && halfway.red < 1.0);
+
+        fade.tick(THEME_TRANSITION_SECS);
+        assert!(!fade.is_fading());
+        assert_eq!(fade.current(), Color::WHITE.to_linear().into());
+    }
+
+    #[test]
+    fn test_fade_without_animation_jumps() {
+        let mut fade = ColorFade::new(Color::BLACK);
+        fade.set_target(Color::WHITE, false);
+        assert!(!fade.is_fading());
+        assert_eq!(fade.current(), Color::WHITE.to_linear().into());
+    }
+
+    #[test]
+    fn test_retarget_starts_from_shown_color() {
+        let mut fade = ColorFade::new(Color::BLACK);
+        fade.set_target(Color::WHITE, true);
+        fade.tick(THEME_TRANSITION_SECS / 2.0);
+        let shown = fade.current();
+
+        fade.set_target(Color::BLACK, true);
+        assert_eq!(fade.current(), shown);
+        // The same target again doesn't restart the fade
+        fade.tick(THEME_TRANSITION_SECS / 4.0);
+        let later = fade.current();
+        assert!(!fade.set_target(Color::BLACK, true));
+        assert_eq!(fade.current(), later);
+    }
+}
@@ -0,0 +1,377 @@
+// Runtime font zoom (Ctrl+= / Ctrl+- / Ctrl+0)
+//
+// Changing the scale resizes the font, recomputes TerminalMetrics for the
+// current window size, and resizes the PTY so the grid keeps filling the
+// window. The chosen scale is remembered per window (keyed by title) in
+// Scarab's data directory and restored at startup.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use scarab_protocol::{ControlMessage, TerminalMetrics, GRID_HEIGHT, GRID_WIDTH};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::text::{TerminalMesh, TextRenderer};
+use crate::integration::TerminalGridEntity;
+use crate::ipc::{DaemonPaths, IpcChannel};
+use crate::ratatui_bridge::CommandSelected;
+use crate::ui::{TerminalInsets, BOTTOM_UI_HEIGHT};
+
+/// Smallest allowed font scale
+pub const MIN_FONT_SCALE: f32 = 0.5;
+
+/// Largest allowed font scale
+pub const MAX_FONT_SCALE: f32 = 3.0;
+
+/// Scale change per zoom step
+pub const FONT_SCALE_STEP: f32 = 0.1;
+
+/// A zoom request from the keyboard or command palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoomAction {
+    In,
+    Out,
+    Reset,
+}
+
+impl ZoomAction {
+    /// Map a command palette id to a zoom action
+    pub fn from_command(id: &str) -> Option<Self> {
+        match id {
+            "zoom_in" => Some(Self::In),
+            "zoom_out" => Some(Self::Out),
+            "zoom_reset" => Some(Self::Reset),
+            _ => None,
+        }
+    }
+}
+
+/// Current font scale for the window
+#[derive(Resource, Debug, Clone)]
+pub struct FontZoom {
+    /// Font size from the config, before scaling
+    pub base_size: f32,
+    /// Multiplier applied to `base_size`
+    pub scale: f32,
+    /// Key the scale is persisted under
+    pub window_key: String,
+}
+
+impl FontZoom {
+    pub fn new(base_size: f32, window_key: impl Into<String>) -> Self {
+        Self {
+            base_size,
+            scale: 1.0,
+            window_key: window_key.into(),
+        }
+    }
+
+    /// Scaled font size in points
+    pub fn font_size(&self) -> f32 {
+        self.base_size * self.scale
+    }
+
+    /// Apply a zoom action, returning true if the scale changed
+    pub fn apply(&mut self, action: ZoomAction) -> bool {
+        let scale = match action {
+            ZoomAction::In => self.scale + FONT_SCALE_STEP,
+            ZoomAction::Out => self.scale - FONT_SCALE_STEP,
+            ZoomAction::Reset => 1.0,
+        };
+        self.set_scale(scale)
+    }
+
+    /// Set the scale, clamped and rounded to whole steps
+    pub fn set_scale(&mut self, scale: f32) -> bool {
+        let scale = ((scale / FONT_SCALE_STEP).round() * FONT_SCALE_STEP)
+            .clamp(MIN_FONT_SCALE, MAX_FONT_SCALE);
+        if (scale - self.scale).abs() < f32::EPSILON {
+            return false;
+        }
+        self.scale = scale;
+        true
+    }
+}
+
+/// Font scales saved across runs, keyed by window
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ZoomStore {
+    pub scales: HashMap<String, f32>,
+}
+
+impl ZoomStore {
+    /// Location under Scarab's data directory
+    pub fn path_in(data_dir: &Path) -> PathBuf {
+        data_dir.join("font_zoom.json")
+    }
+
+    /// Load the store, treating a missing or unreadable file as empty
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// True for the keys that zoom when Ctrl is held
+pub fn is_zoom_key(key: KeyCode) -> bool {
+    matches!(
+        key,
+        KeyCode::Equal
+            | KeyCode::NumpadAdd
+            | KeyCode::Minus
+            | KeyCode::NumpadSubtract
+            | KeyCode::Digit0
+            | KeyCode::Numpad0
+    )
+}
+
+/// Zoom action for this frame's keyboard input, if any
+pub fn zoom_action_from_keys(keys: &ButtonInput<KeyCode>) -> Option<ZoomAction> {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return None;
+    }
+
+    if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        Some(ZoomAction::In)
+    } else if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        Some(ZoomAction::Out)
+    } else if keys.any_just_pressed([KeyCode::Digit0, KeyCode::Numpad0]) {
+        Some(ZoomAction::Reset)
+    } else {
+        None
+    }
+}
+
+/// Grid size that fits the window, matching the window resize handler
+pub fn grid_size_for_window(
+    window: Vec2,
+    cell_size: Vec2,
+    insets: TerminalInsets,
+) -> Option<(u16, u16)> {
+    if cell_size.x <= 0.0 || cell_size.y <= 0.0 {
+        return None;
+    }
+
+    let available_width = window.x - insets.right;
+    let available_height = window.y - BOTTOM_UI_HEIGHT - insets.top - insets.bottom;
+    let cols = ((available_width / cell_size.x).floor().max(1.0) as u16).min(GRID_WIDTH as u16);
+    let rows = ((available_height / cell_size.y).floor().max(1.0) as u16).min(GRID_HEIGHT as u16);
+    Some((cols, rows))
+}
+
+/// System to create the zoom state once the renderer exists, restoring
+/// the saved scale for this window
+fn init_font_zoom(
+    mut commands: Commands,
+    renderer: Option<Res<TextRenderer>>,
+    zoom: Option<Res<FontZoom>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    paths: Res<DaemonPaths>,
+) {
+    let (Some(renderer), None) = (renderer, zoom) else {
+        return;
+    };
+
+    let window_key = windows
+        .get_single()
+        .map(|w| w.title.clone())
+        .unwrap_or_else(|_| "default".to_string());
+    let mut zoom = FontZoom::new(renderer.config.size, window_key);
+
+    let path = ZoomStore::path_in(&paths.0.data_dir);
+    if let Some(&scale) = ZoomStore::load(&path).scales.get(&zoom.window_key) {
+        zoom.set_scale(scale);
+    }
+    if zoom.scale != 1.0 {
+        info!(
+            "Restoring font scale {:.1} for {:?}",
+            zoom.scale, zoom.window_key
+        );
+    }
+
+    commands.insert_resource(zoom);
+}
+
+/// System to handle zoom shortcuts and palette commands
+fn handle_zoom_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut commands_selected: EventReader<CommandSelected>,
+    zoom: Option<ResMut<FontZoom>>,
+    paths: Res<DaemonPaths>,
+) {
+    let Some(mut zoom) = zoom else {
+        return;
+    };
+
+    let actions = zoom_action_from_keys(&keys).into_iter().chain(
+        commands_selected
+            .read()
+            .filter_map(|event| ZoomAction::from_command(&event.command_id)),
+    );
+
+    // Only flag the resource as changed when the scale actually moves
+    let mut changed = false;
+    for action in actions {
+        changed |= zoom.bypass_change_detection().apply(action);
+    }
+    if !changed {
+        return;
+    }
+    zoom.set_changed();
+
+    let path = ZoomStore::path_in(&paths.0.data_dir);
+    let mut store = ZoomStore::load(&path);
+    store.scales.insert(zoom.window_key.clone(), zoom.scale);
+    if let Err(e) = store.save(&path) {
+        warn!("Failed to save font scale: {}", e);
+    }
+}
+
+/// System to resize the font, metrics, and PTY when the scale changes
+fn apply_font_zoom(
+    zoom: Option<Res<FontZoom>>,
+    mut renderer: Option<ResMut<TextRenderer>>,
+    mut metrics: Option<ResMut<TerminalMetrics>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    insets: Option<Res<TerminalInsets>>,
+    mut meshes: Query<&mut TerminalMesh, With<TerminalGridEntity>>,
+    ipc: Option<Res<IpcChannel>>,
+) {
+    let (Some(zoom), Some(renderer)) = (zoom, renderer.as_mut()) else {
+        return;
+    };
+    if !zoom.is_changed() || renderer.config.size == zoom.font_size() {
+        return;
+    }
+
+    renderer.set_font_size(zoom.font_size());
+    renderer.update_metrics();
+    let cell_size = Vec2::new(renderer.cell_width, renderer.cell_height);
+    info!(
+        "Font zoom {:.0}%: {:.1}pt, cell {:.2}x{:.2}",
+        zoom.scale * 100.0,
+        zoom.font_size(),
+        cell_size.x,
+        cell_size.y
+    );
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let insets = insets.map(|i| *i).unwrap_or_default();
+    let Some((cols, rows)) = grid_size_for_window(
+        Vec2::new(window.width(), window.height()),
+        cell_size,
+        insets,
+    ) else {
+        return;
+    };
+
+    if let Some(metrics) = metrics.as_mut() {
+        metrics.cell_width = cell_size.x;
+        metrics.cell_height = cell_size.y;
+        metrics.columns = cols;
+        metrics.rows = rows;
+    }
+
+    if let Some(ipc) = ipc {
+        ipc.send(ControlMessage::Resize { cols, rows });
+    }
+
+    for mut mesh in meshes.iter_mut() {
+        mesh.dirty_region.mark_full_redraw();
+    }
+}
+
+/// Plugin for runtime font zoom
+pub struct FontZoomPlugin;
+
+impl Plugin for FontZoomPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DaemonPaths>()
+            .add_event::<CommandSelected>()
+            .add_systems(
+                Update,
+                (init_font_zoom, handle_zoom_input, apply_font_zoom).chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zoom_steps_and_clamps() {
+        let mut zoom = FontZoom::new(14.0, "main");
+        assert!(zoom.apply(ZoomAction::In));
+        assert!((zoom.font_size() - 15.4).abs() < 0.01);
+
+        for _ in 0..50 {
+            zoom.apply(ZoomAction::In);
+        }
+        assert_eq!(zoom.scale, MAX_FONT_SCALE);
+        assert!(!zoom.apply(ZoomAction::In));
+
+        assert!(zoom.apply(ZoomAction::Reset));
+        assert_eq!(zoom.scale, 1.0);
+        for _ in 0..50 {
+            zoom.apply(ZoomAction::Out);
+        }
+        assert_eq!(zoom.scale, MIN_FONT_SCALE);
+    }
+
+    #[test]
+    fn test_grid_size_for_window() {
+        let insets = TerminalInsets::default();
+        let window = Vec2::new(800.0, 600.0 + BOTTOM_UI_HEIGHT);
+
+        assert_eq!(
+            grid_size_for_window(window, Vec2::new(10.0, 20.0), insets),
+            Some((80, 30))
+        );
+        // Doubling the cell size halves the grid for the same window
+        assert_eq!(
+            grid_size_for_window(window, Vec2::new(20.0, 40.0), insets),
+            Some((40, 15))
+        );
+        assert_eq!(grid_size_for_window(window, Vec2::ZERO, insets), None);
+    }
+
+    #[test]
+    fn test_zoom_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("font_zoom.json");
+
+        let mut store = ZoomStore::default();
+        store.scales.insert("Scarab Terminal".into(), 1.3);
+        store.save(&path).unwrap();
+
+        let loaded = ZoomStore::load(&path);
+        assert_eq!(loaded.scales.get("Scarab Terminal"), Some(&1.3));
+        assert!(ZoomStore::load(&dir.path().join("missing.json"))
+            .scales
+            .is_empty());
+    }
+
+    #[test]
+    fn test_zoom_commands() {
+        assert_eq!(ZoomAction::from_command("zoom_in"), Some(ZoomAction::In));
+        assert_eq!(
+            ZoomAction::from_command("zoom_reset"),
+            Some(ZoomAction::Reset)
+        );
+        assert_eq!(ZoomAction::from_command("toggle_theme"), None);
+    }
+}
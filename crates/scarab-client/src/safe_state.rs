@@ -15,7 +15,7 @@
 //! are sufficient since individual Cell writes are atomic.
 
 use scarab_protocol::{
-    terminal_state::TerminalStateReader, Cell, SharedState, GRID_HEIGHT, GRID_WIDTH,
+    terminal_state::TerminalStateReader, Cell, SharedState, DAMAGE_WORDS, GRID_HEIGHT, GRID_WIDTH,
 };
 use std::marker::PhantomData;
 use std::sync::atomic::Ordering;
@@ -102,9 +102,7 @@ impl<'a> SafeSharedState<'a> {
     /// Read the sequence number using volatile/atomic semantics
     #[inline]
     fn read_sequence_atomic(&self) -> u64 {
-        let state = self.state_ref();
-        // Use volatile read to prevent compiler reordering
-        unsafe { std::ptr::read_volatile(&state.sequence_number) }
+        self.state_ref().sequence()
     }
 
     /// Read cells with consistency guarantee
@@ -121,66 +119,17 @@ impl<'a> SafeSharedState<'a> {
     /// * `Some((sequence, cells))` - Consistent read succeeded
     /// * `None` - Failed after max_retries (only if max_retries > 0)
     pub fn read_consistent(&self, max_retries: u32) -> Option<(u64, Vec<Cell>)> {
-        let mut attempts = 0u32;
-
-        loop {
-            // Step 1: Read sequence before
-            let seq_before = self.read_sequence_atomic();
-
-            // Step 2: Memory barrier to ensure we see current data
-            std::sync::atomic::fence(Ordering::Acquire);
-
-            // Step 3: Read data
-            let state = self.state_ref();
-            let cells = state.cells.to_vec();
-
-            // Step 4: Memory barrier before re-reading sequence
-            std::sync::atomic::fence(Ordering::Acquire);
-
-            // Step 5: Verify sequence unchanged
-            let seq_after = self.read_sequence_atomic();
-            if seq_before == seq_after {
-                // Success - consistent read!
-                return Some((seq_before, cells));
-            }
-
-            // Sequence changed during read - retry
-            std::hint::spin_loop();
-            attempts += 1;
-            if max_retries > 0 && attempts >= max_retries {
-                return None;
-            }
-        }
+        self.state_ref()
+            .read_consistent(max_retries, |state| state.cells.to_vec())
     }
 
     /// Read cursor position with consistency guarantee
     ///
     /// Lighter weight than `read_consistent` since cursor is only 4 bytes.
     pub fn read_cursor_consistent(&self, max_retries: u32) -> Option<(u64, u16, u16)> {
-        let mut attempts = 0u32;
-
-        loop {
-            let seq_before = self.read_sequence_atomic();
-
-            std::sync::atomic::fence(Ordering::Acquire);
-
-            let state = self.state_ref();
-            let cursor_x = state.cursor_x;
-            let cursor_y = state.cursor_y;
-
-            std::sync::atomic::fence(Ordering::Acquire);
-
-            let seq_after = self.read_sequence_atomic();
-            if seq_before == seq_after {
-                return Some((seq_before, cursor_x, cursor_y));
-            }
-
-            std::hint::spin_loop();
-            attempts += 1;
-            if max_retries > 0 && attempts >= max_retries {
-                return None;
-            }
-        }
+        self.state_ref()
+            .read_consistent(max_retries, |state| (state.cursor_x, state.cursor_y))
+            .map(|(seq, (x, y))| (seq, x, y))
     }
 
     /// Try to read cells without blocking, returning None if sequence changed during read
@@ -227,6 +176,25 @@ impl<'a> TerminalStateReader for SafeSharedState<'a> {
         (state.cursor_x, state.cursor_y)
     }
 
+    fn cursor_style(&self) -> u8 {
+        let state = self.state_ref();
+        state.cursor_style
+    }
+
+    fn row_damage(&self) -> Option<[u64; DAMAGE_WORDS]> {
+        let state = self.state_ref();
+
+        // The daemon zeroes damage_sequence while rewriting the bitmask, so a
+        // matching sequence on both sides of the copy means it was not torn
+        let before = unsafe { std::ptr::read_volatile(&state.damage_sequence) };
+        std::sync::atomic::fence(Ordering::Acquire);
+        let damage = unsafe { std::ptr::read_volatile(&state.damage_rows) };
+        std::sync::atomic::fence(Ordering::Acquire);
+        let after = unsafe { std::ptr::read_volatile(&state.damage_sequence) };
+
+        (before != 0 && before == after && before == self.read_sequence_atomic()).then_some(damage)
+    }
+
     fn sequence(&self) -> u64 {
         let state = self.state_ref();
         state.sequence_number
@@ -251,6 +219,78 @@ impl<'a> TerminalStateReader for SafeSharedState<'a> {
     }
 }
 
+/// Grid of cells composed by the client rather than read from the daemon
+///
+/// Used for views the shared grid can't provide, such as scrollback joined
+/// to the live screen or IME preedit text. It owns its cells and has no
+/// cursor, damage or sequence of its own.
+#[derive(Clone)]
+pub struct GridView {
+    cells: Vec<Cell>,
+    width: usize,
+    height: usize,
+}
+
+impl GridView {
+    /// Blank grid of `width` x `height` default cells
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![Cell::default(); width * height],
+            width,
+            height,
+        }
+    }
+
+    /// Grid of a single row of cells
+    pub fn from_row(cells: Vec<Cell>) -> Self {
+        Self {
+            width: cells.len(),
+            height: 1,
+            cells,
+        }
+    }
+
+    /// Cells in row-major order, for filling the view
+    pub fn cells_mut(&mut self) -> &mut [Cell] {
+        &mut self.cells
+    }
+}
+
+impl TerminalStateReader for GridView {
+    fn cell(&self, row: usize, col: usize) -> Option<&Cell> {
+        self.cell_index(row, col)
+            .and_then(|idx| self.cells.get(idx))
+    }
+
+    fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    fn cursor_pos(&self) -> (u16, u16) {
+        (0, 0)
+    }
+
+    fn sequence(&self) -> u64 {
+        0
+    }
+
+    fn is_valid(&self) -> bool {
+        self.cells.len() == self.width * self.height
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn is_dirty(&self) -> bool {
+        false
+    }
+
+    fn is_error_mode(&self) -> bool {
+        false
+    }
+}
+
 /// Mock terminal state for testing
 ///
 /// Provides a TerminalStateReader implementation that doesn't require
@@ -278,6 +318,7 @@ pub struct MockTerminalState {
     cursor_y: u16,
     sequence: u64,
     dirty: bool,
+    damage: Option<[u64; DAMAGE_WORDS]>,
 }
 
 impl MockTerminalState {
@@ -291,6 +332,7 @@ impl MockTerminalState {
             cursor_y: 0,
             sequence: 0,
             dirty: false,
+            damage: None,
         }
     }
 
@@ -323,6 +365,15 @@ impl MockTerminalState {
         self.sequence += 1;
     }
 
+    /// Report the given rows as damaged by the current sequence
+    pub fn set_row_damage(&mut self, rows: &[usize]) {
+        let mut damage = [0u64; DAMAGE_WORDS];
+        for &row in rows.iter().filter(|row| **row < GRID_HEIGHT) {
+            damage[row / 64] |= 1 << (row % 64);
+        }
+        self.damage = Some(damage);
+    }
+
     /// Clear dirty flag
     pub fn clear_dirty(&mut self) {
         self.dirty = false;
@@ -379,6 +430,10 @@ impl TerminalStateReader for MockTerminalState {
         (self.cursor_x, self.cursor_y)
     }
 
+    fn row_damage(&self) -> Option<[u64; DAMAGE_WORDS]> {
+        self.damage
+    }
+
     fn sequence(&self) -> u64 {
         self.sequence
     }
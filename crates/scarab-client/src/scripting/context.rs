@@ -4,6 +4,7 @@
 
 use super::api::{ColorContext, FontContext, ScriptContext, TerminalContext, WindowContext};
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use scarab_config::ScarabConfig;
 
 /// Bevy resource that provides context to scripts
@@ -178,7 +179,7 @@ fn parse_color(hex: &str) -> Color {
 pub fn initialize_context(
     mut commands: Commands,
     config: Res<ScarabConfig>,
-    window: Query<&Window>,
+    window: Query<&Window, With<PrimaryWindow>>,
 ) {
     if let Ok(window) = window.get_single() {
         let context = RuntimeContext::from_resources(&config, window);
@@ -191,7 +192,7 @@ pub fn initialize_context(
 pub fn update_context(
     mut context: ResMut<RuntimeContext>,
     config: Res<ScarabConfig>,
-    window: Query<&Window>,
+    window: Query<&Window, With<PrimaryWindow>>,
 ) {
     // Only update if config changed
     if !config.is_changed() {
@@ -7,6 +7,7 @@ use std::path::Path;
 use std::sync::Arc;
 
 use bevy::prelude::*;
+use scarab_config::AstCache;
 
 use super::api::ScriptEvent;
 use super::context::RuntimeContext;
@@ -23,6 +24,8 @@ pub struct ScriptManager {
     runtime: ScriptRuntime,
     watcher: ScriptWatcher,
     scripts: HashMap<String, LoadedScript>,
+    /// Files pulled in with `#load`, shared by every script
+    modules: AstCache,
     initialized: bool,
 }
 
@@ -40,6 +43,7 @@ impl ScriptManager {
             runtime,
             watcher: ScriptWatcher::new(),
             scripts: HashMap::new(),
+            modules: AstCache::new(),
             initialized: false,
         }
     }
@@ -64,9 +68,12 @@ impl ScriptManager {
         let loaded_scripts = self.loader.load_all_scripts()?;
         let count = loaded_scripts.len();
 
-        // Register scripts for watching
+        // Register scripts and the files they load for watching
         for script in loaded_scripts {
             self.watcher.watch(script.path.clone())?;
+            for dep in &script.dependencies {
+                self.watcher.watch(dep.clone())?;
+            }
             self.scripts.insert(script.name.clone(), script);
         }
 
@@ -85,17 +92,28 @@ impl ScriptManager {
         let changed_paths = self.watcher.check_changes()?;
 
         for path in changed_paths {
-            // Find the script by path
-            let script_name = path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown");
-
-            if let Some(script) = self.scripts.get_mut(script_name) {
+            // The script itself, or scripts that `#load` the changed file
+            let dependents: Vec<String> = self
+                .scripts
+                .values()
+                .filter(|script| script.is_affected_by(&path))
+                .map(|script| script.name.clone())
+                .collect();
+
+            for script_name in dependents {
+                let Some(script) = self.scripts.get_mut(&script_name) else {
+                    continue;
+                };
                 info!("Reloading script: {}", script_name);
-                match script.reload() {
+                match script.reload(&mut self.modules) {
                     Ok(_) => {
                         info!("Successfully reloaded: {}", script_name);
+                        // Files it started loading are followed from now on
+                        for dep in &script.dependencies {
+                            if let Err(e) = self.watcher.watch(dep.clone()) {
+                                warn!("Failed to watch '{}': {}", dep.display(), e);
+                            }
+                        }
                     }
                     Err(e) => {
                         error!("Failed to reload '{}': {}", script_name, e);
@@ -290,4 +308,28 @@ mod tests {
         assert!(manager.has_script("test"));
         assert!(!manager.has_script("nonexistent"));
     }
+
+    #[test]
+    fn test_module_change_reloads_dependents() {
+        let temp_dir = TempDir::new().unwrap();
+        let lib_dir = temp_dir.path().join("lib");
+        fs::create_dir(&lib_dir).unwrap();
+        fs::write(lib_dir.join("palette.fsx"), "let accent = \"#ff79c6\"").unwrap();
+        fs::write(
+            temp_dir.path().join("theme.fsx"),
+            "#load \"lib/palette.fsx\"\nScarab.setColor \"accent\" accent",
+        )
+        .unwrap();
+
+        let mut manager = ScriptManager::new(temp_dir.path().to_path_buf());
+        manager.initialize(temp_dir.path()).unwrap();
+        assert!(manager.scripts["theme"].source.contains("#ff79c6"));
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        fs::write(lib_dir.join("palette.fsx"), "let accent = \"#8be9fd\"").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(600)); // Wait for check interval
+
+        manager.check_reloads().unwrap();
+        assert!(manager.scripts["theme"].source.contains("#8be9fd"));
+    }
 }
@@ -10,6 +10,7 @@
 //! - Custom overlay/widget registration
 //! - Event handling for daemon messages
 //! - ECS bridge for plugin actions and responses
+//! - An interactive REPL overlay (`fusabi.repl`)
 
 pub mod api;
 pub mod context;
@@ -17,6 +18,7 @@ pub mod ecs_bridge;
 pub mod error;
 pub mod loader;
 pub mod manager;
+pub mod repl;
 pub mod runtime;
 pub mod watcher;
 
@@ -26,10 +28,12 @@ pub use ecs_bridge::{FusabiActionChannel, FusabiEcsBridgePlugin, FusabiNatives};
 pub use error::{ScriptError, ScriptResult};
 pub use loader::ScriptLoader;
 pub use manager::ScriptManager;
+pub use repl::{ReplPlugin, ReplState};
 pub use runtime::ScriptRuntime;
 pub use watcher::ScriptWatcher;
 
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 
 /// Main plugin that integrates scripting into the client
 pub struct ScriptingPlugin;
@@ -38,7 +42,7 @@ impl Plugin for ScriptingPlugin {
     fn build(&self, app: &mut App) {
         // Add plugin host first to ensure PluginRegistry is available
         app.add_plugins(crate::plugin_host::ScarabPluginHostPlugin)
-            .add_plugins((ScriptingSystemPlugin, FusabiEcsBridgePlugin))
+            .add_plugins((ScriptingSystemPlugin, FusabiEcsBridgePlugin, ReplPlugin))
             .add_event::<ScriptEvent>()
             .add_systems(
                 Startup,
@@ -141,7 +145,10 @@ fn handle_script_events(mut events: EventReader<ScriptEvent>, mut manager: ResMu
 }
 
 /// Display script errors in the UI
-fn display_script_errors(errors: Res<ScriptErrorDisplay>, window: Query<&Window>) {
+fn display_script_errors(
+    errors: Res<ScriptErrorDisplay>,
+    window: Query<&Window, With<PrimaryWindow>>,
+) {
     if !errors.visible {
         return;
     }
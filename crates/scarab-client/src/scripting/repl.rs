@@ -0,0 +1,508 @@
+//! Interactive Fusabi REPL, opened with the `fusabi.repl` palette command
+//!
+//! Input is compiled and run in a VM of the REPL's own, with the same
+//! stdlib and `Scarab.*` host functions as scripts but none of their
+//! bindings: client scripts each run in a fresh VM, so there is no shared
+//! environment to join. `let` bindings carry over from one line to the next
+//! and host calls act on the running terminal, so config tweaks can be
+//! tried before they go in a file. Up and Down recall earlier input;
+//! Escape closes the overlay.
+//!
+//! The VM runs on a thread of its own so a runaway expression cannot
+//! freeze the client. fusabi-vm has no step limit, so an evaluation still
+//! running after [`EVAL_TIMEOUT_SECS`] is given up on: the REPL starts over
+//! with a fresh VM, and the old thread exits once the expression returns.
+
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use fusabi_frontend::compile_program_from_source;
+use fusabi_vm::Vm;
+
+use super::ecs_bridge::FusabiActionChannel;
+use super::runtime::new_vm;
+use crate::ratatui_bridge::CommandSelected;
+use crate::InputSystemSet;
+
+/// Palette command that opens the REPL
+pub const REPL_COMMAND: &str = "fusabi.repl";
+
+/// Output lines kept in the overlay
+const MAX_OUTPUT_LINES: usize = 200;
+
+/// Output lines drawn at once
+const VISIBLE_OUTPUT_LINES: usize = 16;
+
+/// Seconds an evaluation may run before the REPL gives up on its VM
+pub const EVAL_TIMEOUT_SECS: f64 = 5.0;
+
+/// A VM on its own thread whose globals outlive each evaluation
+pub struct ReplSession {
+    inputs: mpsc::Sender<String>,
+    results: mpsc::Receiver<Result<String, String>>,
+}
+
+impl ReplSession {
+    pub fn new(channel: Arc<FusabiActionChannel>) -> Self {
+        let (inputs, queue) = mpsc::channel::<String>();
+        let (reply, results) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("fusabi-repl".into())
+            .spawn(move || {
+                // The VM uses `Rc` internally, so it is made on this thread
+                let mut vm = new_vm(channel);
+                for source in queue {
+                    if reply.send(eval(&mut vm, &source)).is_err() {
+                        break;
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            error!("Failed to start the Fusabi REPL: {}", e);
+        }
+        Self { inputs, results }
+    }
+
+    /// Start evaluating `source`; false if the VM thread has stopped
+    pub fn submit(&self, source: String) -> bool {
+        self.inputs.send(source).is_ok()
+    }
+
+    /// The outcome of the last submitted input, if it has finished
+    pub fn try_result(&self) -> Option<Result<String, String>> {
+        match self.results.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Some(Err("The REPL VM has stopped".to_string()))
+            }
+        }
+    }
+
+    /// Evaluate `source`, waiting up to `limit` for the value
+    pub fn eval(&self, source: &str, limit: Duration) -> Result<String, String> {
+        if !self.submit(source.to_string()) {
+            return Err("The REPL VM has stopped".to_string());
+        }
+        self.results
+            .recv_timeout(limit)
+            .map_err(|_| format!("Still running after {:?}", limit))?
+    }
+}
+
+/// Compile and run `source`, returning the value it evaluates to
+fn eval(vm: &mut Vm, source: &str) -> Result<String, String> {
+    let chunk = compile_program_from_source(source).map_err(|e| e.to_string())?;
+    vm.execute(chunk)
+        .map(|value| value.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// The REPL VM, started the first time something is evaluated
+///
+/// Non-send because the result receiver is not `Sync`.
+#[derive(Default)]
+struct ReplVm(Option<ReplSession>);
+
+/// What a line of REPL output is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplLineKind {
+    Input,
+    Value,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplLine {
+    pub kind: ReplLineKind,
+    pub text: String,
+}
+
+/// Input, output and history of the REPL overlay
+#[derive(Resource, Debug, Default)]
+pub struct ReplState {
+    pub active: bool,
+    pub input: String,
+    pub output: Vec<ReplLine>,
+    history: Vec<String>,
+    /// Index into `history` while recalling with Up and Down
+    recalled: Option<usize>,
+    /// When the input being evaluated was submitted
+    evaluating_since: Option<f64>,
+}
+
+impl ReplState {
+    /// Whether the REPL owns the keyboard
+    pub fn captures_keys(&self) -> bool {
+        self.active
+    }
+
+    pub fn open(&mut self) {
+        self.active = true;
+    }
+
+    /// Close the overlay, keeping the output and history for next time
+    pub fn close(&mut self) {
+        self.active = false;
+        self.input.clear();
+        self.recalled = None;
+    }
+
+    /// Take the input to evaluate, echoing it to the output
+    pub fn submit(&mut self) -> Option<String> {
+        let source = std::mem::take(&mut self.input);
+        self.recalled = None;
+        if source.trim().is_empty() {
+            return None;
+        }
+        if self.history.last() != Some(&source) {
+            self.history.push(source.clone());
+        }
+        self.push_line(ReplLineKind::Input, format!("> {}", source));
+        Some(source)
+    }
+
+    /// Whether an input is still being evaluated
+    pub fn is_evaluating(&self) -> bool {
+        self.evaluating_since.is_some()
+    }
+
+    /// Note that the submitted input started evaluating at `now`
+    pub fn start_evaluating(&mut self, now: f64) {
+        self.evaluating_since = Some(now);
+    }
+
+    /// Whether the evaluation has run for longer than [`EVAL_TIMEOUT_SECS`]
+    pub fn timed_out(&self, now: f64) -> bool {
+        self.evaluating_since
+            .is_some_and(|since| now - since > EVAL_TIMEOUT_SECS)
+    }
+
+    /// Show the outcome of evaluating the last input
+    pub fn record(&mut self, result: Result<String, String>) {
+        self.evaluating_since = None;
+        match result {
+            Ok(value) => self.push_line(ReplLineKind::Value, value),
+            Err(error) => self.push_line(ReplLineKind::Error, error),
+        }
+    }
+
+    /// Replace the input with the previous history entry
+    pub fn recall_previous(&mut self) {
+        let index = match self.recalled {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.recalled = Some(index);
+        self.input = self.history[index].clone();
+    }
+
+    /// Replace the input with the next history entry, or clear it past the end
+    pub fn recall_next(&mut self) {
+        let Some(index) = self.recalled else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.recalled = Some(index + 1);
+            self.input = self.history[index + 1].clone();
+        } else {
+            self.recalled = None;
+            self.input.clear();
+        }
+    }
+
+    fn push_line(&mut self, kind: ReplLineKind, text: String) {
+        for line in text.lines() {
+            self.output.push(ReplLine {
+                kind,
+                text: line.to_string(),
+            });
+        }
+        if self.output.len() > MAX_OUTPUT_LINES {
+            let excess = self.output.len() - MAX_OUTPUT_LINES;
+            self.output.drain(..excess);
+        }
+    }
+}
+
+/// Marker for the REPL overlay
+#[derive(Component)]
+struct ReplUI;
+
+/// System to open the REPL from the command palette
+fn open_repl(mut commands_selected: EventReader<CommandSelected>, mut state: ResMut<ReplState>) {
+    for event in commands_selected.read() {
+        if event.command_id == REPL_COMMAND {
+            state.open();
+        }
+    }
+}
+
+/// System for typing into the REPL and evaluating input
+fn handle_repl_keys(
+    mut key_events: EventReader<KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    channel: Res<FusabiActionChannel>,
+    mut state: ResMut<ReplState>,
+    mut session: NonSendMut<ReplVm>,
+) {
+    if !state.captures_keys() {
+        key_events.clear();
+        return;
+    }
+
+    let chorded = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+
+    for event in key_events.read() {
+        if !event.state.is_pressed() || !state.captures_keys() {
+            continue;
+        }
+
+        match (&event.key_code, &event.logical_key) {
+            // One evaluation at a time
+            (KeyCode::Enter | KeyCode::NumpadEnter, _) if !state.is_evaluating() => {
+                if let Some(source) = state.submit() {
+                    let started = session
+                        .0
+                        .get_or_insert_with(|| ReplSession::new(Arc::new(channel.clone())))
+                        .submit(source);
+                    if started {
+                        state.start_evaluating(time.elapsed_secs_f64());
+                    } else {
+                        session.0 = None;
+                        state.record(Err("The REPL VM has stopped; try again".to_string()));
+                    }
+                }
+            }
+            (KeyCode::Escape, _) => state.close(),
+            (KeyCode::Backspace, _) => {
+                state.input.pop();
+            }
+            (KeyCode::ArrowUp, _) => state.recall_previous(),
+            (KeyCode::ArrowDown, _) => state.recall_next(),
+            (_, Key::Space) if !chorded => state.input.push(' '),
+            (_, Key::Character(s)) if !chorded && !s.chars().any(char::is_control) => {
+                state.input.push_str(s);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// System to show finished evaluations and give up on stuck ones
+fn poll_repl(time: Res<Time>, mut state: ResMut<ReplState>, mut session: NonSendMut<ReplVm>) {
+    if !state.is_evaluating() {
+        return;
+    }
+    if let Some(result) = session.0.as_ref().and_then(ReplSession::try_result) {
+        state.record(result);
+    } else if state.timed_out(time.elapsed_secs_f64()) {
+        // The stuck VM cannot be interrupted; leave it and start over
+        session.0 = None;
+        state.record(Err(format!(
+            "Still running after {}s; gave up and started a fresh VM",
+            EVAL_TIMEOUT_SECS
+        )));
+    }
+}
+
+/// System to draw the REPL overlay
+fn render_repl(
+    mut commands: Commands,
+    state: Res<ReplState>,
+    existing_ui: Query<Entity, With<ReplUI>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in existing_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !state.active {
+        return;
+    }
+
+    commands
+        .spawn((
+            ReplUI,
+            Node {
+                width: Val::Px(640.0),
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                bottom: Val::Px(40.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                margin: UiRect {
+                    left: Val::Px(-320.0), // Center with width/2
+                    ..default()
+                },
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            ZIndex(2000),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Fusabi REPL"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+
+            let skip = state.output.len().saturating_sub(VISIBLE_OUTPUT_LINES);
+            for line in &state.output[skip..] {
+                let color = match line.kind {
+                    ReplLineKind::Input => Color::srgba(0.6, 0.7, 0.9, 1.0),
+                    ReplLineKind::Value => Color::srgba(0.85, 0.85, 0.85, 1.0),
+                    ReplLineKind::Error => Color::srgba(0.95, 0.45, 0.45, 1.0),
+                };
+                parent.spawn((
+                    Text::new(line.text.clone()),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
+
+            let prompt = if state.is_evaluating() {
+                "Evaluating…".to_string()
+            } else {
+                format!("> {}_", state.input)
+            };
+            parent.spawn((
+                Text::new(prompt),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::vertical(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                Text::new("Enter: Evaluate  ↑/↓: History  Esc: Close"),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.5, 0.5, 0.5, 1.0)),
+            ));
+        });
+}
+
+/// Plugin for the Fusabi REPL overlay
+pub struct ReplPlugin;
+
+impl Plugin for ReplPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplState>()
+            .init_non_send_resource::<ReplVm>()
+            .add_event::<CommandSelected>()
+            .add_systems(
+                Update,
+                (
+                    open_repl,
+                    handle_repl_keys.run_if(resource_exists::<FusabiActionChannel>),
+                    poll_repl,
+                    render_repl,
+                )
+                    .chain()
+                    // After the terminal input systems, so the key that
+                    // closes the REPL never reaches the shell
+                    .after(InputSystemSet::Daemon),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_keeps_bindings() {
+        let session = ReplSession::new(Arc::new(FusabiActionChannel::new()));
+        let limit = Duration::from_secs(5);
+        assert!(session.eval("let answer = 40", limit).is_ok());
+        assert_eq!(session.eval("answer + 2", limit), Ok("42".to_string()));
+        assert!(session.eval("let = ", limit).is_err());
+        assert_eq!(session.eval("answer", limit), Ok("40".to_string()));
+        assert!(session.try_result().is_none());
+    }
+
+    #[test]
+    fn test_evaluation_timeout() {
+        let mut state = ReplState::default();
+        state.open();
+        state.input = "slow ()".into();
+        state.submit();
+        state.start_evaluating(10.0);
+        assert!(state.is_evaluating());
+        assert!(!state.timed_out(10.0 + EVAL_TIMEOUT_SECS));
+        assert!(state.timed_out(10.5 + EVAL_TIMEOUT_SECS));
+
+        state.record(Err("gave up".into()));
+        assert!(!state.is_evaluating());
+        assert!(!state.timed_out(100.0));
+    }
+
+    #[test]
+    fn test_submit_and_history() {
+        let mut state = ReplState::default();
+        state.open();
+        assert!(state.submit().is_none());
+
+        state.input = "1 + 1".into();
+        assert_eq!(state.submit().as_deref(), Some("1 + 1"));
+        state.record(Ok("2".into()));
+        state.input = "oops".into();
+        state.submit();
+        state.record(Err("Undefined variable: oops".into()));
+        assert_eq!(
+            state.output.last(),
+            Some(&ReplLine {
+                kind: ReplLineKind::Error,
+                text: "Undefined variable: oops".into()
+            })
+        );
+        assert_eq!(state.output.len(), 4);
+
+        state.recall_previous();
+        assert_eq!(state.input, "oops");
+        state.recall_previous();
+        state.recall_previous();
+        assert_eq!(state.input, "1 + 1");
+        state.recall_next();
+        assert_eq!(state.input, "oops");
+        state.recall_next();
+        assert_eq!(state.input, "");
+
+        state.close();
+        assert!(!state.captures_keys());
+        assert_eq!(state.output.len(), 4);
+    }
+}
@@ -10,6 +10,8 @@ use std::sync::{Arc, Mutex};
 use bevy::prelude::*;
 use fusabi_frontend::compile_program_from_source;
 use fusabi_vm::{Value, Vm};
+use scarab_config::AstCache;
+use scarab_plugin_api::fusabi_stdlib::register_stdlib_ext;
 
 use super::api::{ScriptContext, ScriptEvent};
 use super::ecs_bridge::FusabiActionChannel;
@@ -76,11 +78,7 @@ impl ScriptRuntime {
         };
 
         // Create VM and register host functions
-        let mut vm = Vm::new();
-        fusabi_vm::stdlib::register_stdlib(&mut vm);
-
-        // Register Scarab functions and create Scarab module
-        register_scarab_module(&mut vm, self.channel.clone());
+        let mut vm = new_vm(self.channel.clone());
 
         // Execute the bytecode
         match vm.execute(chunk) {
@@ -111,11 +109,7 @@ impl ScriptRuntime {
             message: format!("Failed to deserialize bytecode: {}", e),
         })?;
 
-        let mut vm = Vm::new();
-        fusabi_vm::stdlib::register_stdlib(&mut vm);
-
-        // Register Scarab functions and create Scarab module
-        register_scarab_module(&mut vm, self.channel.clone());
+        let mut vm = new_vm(self.channel.clone());
 
         match vm.execute(chunk) {
             Ok(result) => {
@@ -142,6 +136,18 @@ impl ScriptRuntime {
     }
 }
 
+/// A VM with the stdlib and Scarab host functions registered
+pub(crate) fn new_vm(channel: Arc<FusabiActionChannel>) -> Vm {
+    let mut vm = Vm::new();
+    fusabi_vm::stdlib::register_stdlib(&mut vm);
+    // Client scripts are the user's own config, so they get regex too
+    register_stdlib_ext(&mut vm, true);
+
+    // Register Scarab functions and create Scarab module
+    register_scarab_module(&mut vm, channel);
+    vm
+}
+
 /// Register Scarab functions in VM's host registry and as direct globals
 fn register_scarab_module(vm: &mut Vm, channel: Arc<FusabiActionChannel>) {
     use crate::events::{NotificationLevel, PluginAction, StatusSide};
@@ -424,14 +430,22 @@ fn register_scarab_module(vm: &mut Vm, channel: Arc<FusabiActionChannel>) {
 pub struct LoadedScript {
     pub name: String,
     pub path: std::path::PathBuf,
+    /// Source with the files it `#load`s inlined
     pub source: String,
     pub last_modified: std::time::SystemTime,
+    /// Files pulled in with `#load`, directly or not
+    pub dependencies: Vec<std::path::PathBuf>,
 }
 
 impl LoadedScript {
     /// Load a script from a file
     pub fn from_file(path: &Path) -> ScriptResult<Self> {
-        let source = std::fs::read_to_string(path).map_err(|e| ScriptError::LoadError {
+        Self::load(path, &mut AstCache::new())
+    }
+
+    /// Load a script, reusing modules already parsed into `modules`
+    pub fn load(path: &Path, modules: &mut AstCache) -> ScriptResult<Self> {
+        let resolved = modules.resolve(path).map_err(|e| ScriptError::LoadError {
             path: path.display().to_string(),
             reason: e.to_string(),
         })?;
@@ -455,8 +469,9 @@ impl LoadedScript {
         Ok(Self {
             name,
             path: path.to_path_buf(),
-            source,
+            source: resolved.source,
             last_modified,
+            dependencies: resolved.dependencies,
         })
     }
 
@@ -470,19 +485,57 @@ impl LoadedScript {
         false
     }
 
-    /// Reload the script from disk
-    pub fn reload(&mut self) -> ScriptResult<()> {
-        let script = Self::from_file(&self.path)?;
+    /// Whether the script `#load`s `path`, directly or not
+    ///
+    /// `path` is canonicalized first, like the recorded dependencies, so
+    /// `lib/../lib/colors.fsx` matches `lib/colors.fsx`.
+    pub fn depends_on(&self, path: &Path) -> bool {
+        self.dependencies.contains(&canonical(path))
+    }
+
+    /// Whether a change to `path` calls for reloading the script: it is
+    /// the script itself or a file the script loads
+    pub fn is_affected_by(&self, path: &Path) -> bool {
+        canonical(&self.path) == canonical(path) || self.depends_on(path)
+    }
+
+    /// Reload the script and the files it loads from disk
+    pub fn reload(&mut self, modules: &mut AstCache) -> ScriptResult<()> {
+        let script = Self::load(&self.path, modules)?;
         self.source = script.source;
         self.last_modified = script.last_modified;
+        self.dependencies = script.dependencies;
         Ok(())
     }
 }
 
+/// `path` with symlinks and `..` resolved, or as given if it is missing
+fn canonical(path: &Path) -> std::path::PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_dependencies_match_any_spelling() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("lib")).unwrap();
+        std::fs::write(dir.path().join("lib/colors.fsx"), "let accent = 1\n").unwrap();
+        std::fs::write(
+            dir.path().join("theme.fsx"),
+            "#load \"lib/colors.fsx\"\nlet x = accent\n",
+        )
+        .unwrap();
+
+        let script = LoadedScript::from_file(&dir.path().join("lib/../theme.fsx")).unwrap();
+        assert!(script.depends_on(&dir.path().join("lib/./colors.fsx")));
+        assert!(script.is_affected_by(&dir.path().join("lib/../lib/colors.fsx")));
+        assert!(script.is_affected_by(&dir.path().join("theme.fsx")));
+        assert!(!script.is_affected_by(&dir.path().join("other.fsx")));
+    }
+
     #[test]
     fn test_runtime_creation() {
         let channel = Arc::new(FusabiActionChannel::new());
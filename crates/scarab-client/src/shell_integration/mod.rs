@@ -0,0 +1,458 @@
+//! Shell integration setup
+//!
+//! `scarab-client shell-integration install` sets the user's shell up to emit the
+//! OSC 133 prompt and command marks that semantic zones, folding, re-running
+//! and the exit-code gutter are built on, and OSC 7 for the working
+//! directory. The scripts only do anything when `TERM_PROGRAM` is `scarab`,
+//! which the daemon sets for every pane, so other terminals are unaffected.
+//!
+//! For bash and zsh the script is written to the Scarab config directory and
+//! a block between [`BLOCK_BEGIN`] and [`BLOCK_END`] sourcing it is appended
+//! to the rc file; finding that block is how an existing install is
+//! recognized. Fish loads the script straight from `conf.d`.
+//!
+//! `scarab-client shell-integration doctor` checks the install and asks the daemon
+//! for the active pane's prompt markers: the doctor command itself was
+//! started from a prompt, so if the marks work the daemon has just seen its
+//! command start.
+
+use anyhow::Context;
+use scarab_protocol::{ControlMessage, DaemonMessage, PromptMarkerInfo, MAX_MESSAGE_SIZE};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// First line of the block added to rc files
+pub const BLOCK_BEGIN: &str = "# >>> scarab shell integration >>>";
+/// Last line of the block added to rc files
+pub const BLOCK_END: &str = "# <<< scarab shell integration <<<";
+
+/// How long to wait for the daemon to answer
+const DAEMON_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How recently the doctor's own command must have started for the marks to
+/// count as arriving
+const RECENT_COMMAND: Duration = Duration::from_secs(30);
+
+/// Shells with an integration script
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// Shell of a path such as `/usr/bin/zsh`, or of a login shell's `-zsh`
+    pub fn from_path(path: &str) -> Option<Self> {
+        let name = Path::new(path).file_name()?.to_str()?;
+        match name.trim_start_matches('-') {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+
+    /// The user's shell, from `$SHELL`
+    pub fn detect() -> Option<Self> {
+        Self::from_path(&std::env::var("SHELL").ok()?)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    /// The integration script
+    pub fn script(self) -> &'static str {
+        match self {
+            Shell::Bash => include_str!("scarab.bash"),
+            Shell::Zsh => include_str!("scarab.zsh"),
+            Shell::Fish => include_str!("scarab.fish"),
+        }
+    }
+}
+
+/// Where the integration for a shell is installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrationPaths {
+    /// The shell's startup file loading the script
+    pub rc_file: PathBuf,
+    /// Where the script is written; the same as `rc_file` for fish
+    pub script_file: PathBuf,
+}
+
+impl IntegrationPaths {
+    /// Paths for `shell` under `home`, with `config_home` the XDG config
+    /// directory and `scarab_config_dir` Scarab's own
+    pub fn new(shell: Shell, home: &Path, config_home: &Path, scarab_config_dir: &Path) -> Self {
+        let script_dir = scarab_config_dir.join("shell-integration");
+        match shell {
+            Shell::Bash => Self {
+                rc_file: home.join(".bashrc"),
+                script_file: script_dir.join("scarab.bash"),
+            },
+            Shell::Zsh => {
+                let zdotdir = std::env::var_os("ZDOTDIR").map(PathBuf::from);
+                Self {
+                    rc_file: zdotdir.unwrap_or_else(|| home.to_path_buf()).join(".zshrc"),
+                    script_file: script_dir.join("scarab.zsh"),
+                }
+            }
+            Shell::Fish => {
+                let file = config_home.join("fish/conf.d/scarab.fish");
+                Self {
+                    rc_file: file.clone(),
+                    script_file: file,
+                }
+            }
+        }
+    }
+
+    /// Paths for `shell` in the user's home and config directories
+    pub fn from_env(shell: Shell) -> anyhow::Result<Self> {
+        let home = dirs::home_dir().context("Could not find the home directory")?;
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"));
+        let scarab_config_dir = scarab_platform::Paths::from_env().config_dir;
+        Ok(Self::new(shell, &home, &config_home, &scarab_config_dir))
+    }
+}
+
+/// Lines to add to an rc file to load `script`
+pub fn source_block(script: &Path) -> String {
+    format!(
+        "{}\n[ -f \"{path}\" ] && . \"{path}\"\n{}\n",
+        BLOCK_BEGIN,
+        BLOCK_END,
+        path = script.display()
+    )
+}
+
+/// How a shell's startup file stands with regard to the integration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallState {
+    /// Nothing emits prompt marks yet
+    Missing,
+    /// Scarab's integration is loaded
+    Installed,
+    /// Something else already emits OSC 133, such as another terminal's
+    /// integration; installing as well would mark every prompt twice
+    Foreign,
+}
+
+/// Install state of a startup file with `contents`
+pub fn install_state(shell: Shell, contents: &str) -> InstallState {
+    let ours = match shell {
+        Shell::Fish => contents.contains("__scarab_integration"),
+        Shell::Bash | Shell::Zsh => contents.contains(BLOCK_BEGIN),
+    };
+    if ours {
+        InstallState::Installed
+    } else if contents.contains("133;") {
+        InstallState::Foreign
+    } else {
+        InstallState::Missing
+    }
+}
+
+/// Install state of the integration at `paths`
+pub fn installed(shell: Shell, paths: &IntegrationPaths) -> InstallState {
+    let contents = std::fs::read_to_string(&paths.rc_file).unwrap_or_default();
+    match install_state(shell, &contents) {
+        // The rc file may be fine while the script it loads was removed
+        InstallState::Installed if !paths.script_file.exists() => InstallState::Missing,
+        state => state,
+    }
+}
+
+/// What [`install`] did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallOutcome {
+    /// The integration was added to the startup file
+    Added,
+    /// It was already there; the script was refreshed
+    Updated,
+    /// Something else emits OSC 133 and `force` was not given; nothing
+    /// was changed
+    Conflict,
+}
+
+/// Write the integration script for `shell` and load it from the startup
+/// file, unless it is loaded already
+pub fn install(
+    shell: Shell,
+    paths: &IntegrationPaths,
+    force: bool,
+) -> anyhow::Result<InstallOutcome> {
+    let state = installed(shell, paths);
+    let rc_contents = std::fs::read_to_string(&paths.rc_file).unwrap_or_default();
+    if state == InstallState::Foreign && !force {
+        return Ok(InstallOutcome::Conflict);
+    }
+
+    if let Some(dir) = paths.script_file.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&paths.script_file, shell.script())
+        .with_context(|| format!("Failed to write {}", paths.script_file.display()))?;
+
+    if shell != Shell::Fish && !rc_contents.contains(BLOCK_BEGIN) {
+        let mut rc = rc_contents;
+        if !rc.is_empty() && !rc.ends_with('\n') {
+            rc.push('\n');
+        }
+        if !rc.is_empty() {
+            rc.push('\n');
+        }
+        rc.push_str(&source_block(&paths.script_file));
+        std::fs::write(&paths.rc_file, rc)
+            .with_context(|| format!("Failed to update {}", paths.rc_file.display()))?;
+    }
+
+    Ok(match state {
+        InstallState::Installed => InstallOutcome::Updated,
+        InstallState::Missing | InstallState::Foreign => InstallOutcome::Added,
+    })
+}
+
+/// Result of one doctor check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One line of the doctor's report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorCheck {
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl DoctorCheck {
+    fn new(status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+/// Check the prompt markers the daemon has for the doctor's pane
+///
+/// `now_micros` is the current time; the last command start (OSC 133;C)
+/// must be at most [`RECENT_COMMAND`] old, as the doctor was just run.
+pub fn check_markers(markers: &[PromptMarkerInfo], now_micros: u64) -> Vec<DoctorCheck> {
+    let kinds = [
+        (0, "prompt starts (OSC 133;A)"),
+        (1, "command input (OSC 133;B)"),
+        (2, "command output (OSC 133;C)"),
+        (3, "exit codes (OSC 133;D)"),
+    ];
+    let mut checks: Vec<DoctorCheck> = kinds
+        .iter()
+        .map(|(kind, name)| {
+            let count = markers.iter().filter(|m| m.marker_type == *kind).count();
+            if count > 0 {
+                DoctorCheck::new(
+                    CheckStatus::Pass,
+                    format!("Daemon received {} {}", count, name),
+                )
+            } else if *kind == 3 {
+                // A new shell's first command is the doctor, still running
+                DoctorCheck::new(
+                    CheckStatus::Warn,
+                    format!("Daemon received no {}; no command has finished yet", name),
+                )
+            } else {
+                DoctorCheck::new(CheckStatus::Fail, format!("Daemon received no {}", name))
+            }
+        })
+        .collect();
+
+    let last_start = markers
+        .iter()
+        .filter(|m| m.marker_type == 2)
+        .map(|m| m.timestamp_micros)
+        .max();
+    checks.push(match last_start {
+        Some(at) if now_micros.saturating_sub(at) <= RECENT_COMMAND.as_micros() as u64 => {
+            DoctorCheck::new(CheckStatus::Pass, "This command's start reached the daemon")
+        }
+        Some(_) => DoctorCheck::new(
+            CheckStatus::Fail,
+            "This command's start did not reach the daemon; the marks seen are old",
+        ),
+        None => DoctorCheck::new(CheckStatus::Fail, "No command start has reached the daemon"),
+    });
+    checks
+}
+
+/// Ask the daemon at `socket` for the active pane's prompt markers
+pub fn fetch_prompt_markers(socket: &Path) -> anyhow::Result<Vec<PromptMarkerInfo>> {
+    let mut stream = UnixStream::connect(socket)
+        .map_err(|e| anyhow::anyhow!("Is the daemon running? ({}: {})", socket.display(), e))?;
+    stream.set_read_timeout(Some(DAEMON_TIMEOUT))?;
+
+    let body = rkyv::to_bytes::<_, MAX_MESSAGE_SIZE>(&ControlMessage::ZonesRequest)
+        .map_err(|e| anyhow::anyhow!("Failed to serialize message: {:?}", e))?;
+    stream.write_all(&(body.len() as u32).to_be_bytes())?;
+    stream.write_all(&body)?;
+    stream.flush()?;
+
+    // Other clients' updates are broadcast here too; skip them
+    let mut buffer = vec![0u8; MAX_MESSAGE_SIZE];
+    loop {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 || len > MAX_MESSAGE_SIZE {
+            anyhow::bail!("Invalid message length from daemon: {}", len);
+        }
+        stream.read_exact(&mut buffer[..len])?;
+
+        let message = rkyv::from_bytes::<DaemonMessage>(&buffer[..len])
+            .map_err(|e| anyhow::anyhow!("Failed to read daemon message: {:?}", e))?;
+        if let DaemonMessage::PromptMarkersUpdate { markers } = message {
+            return Ok(markers);
+        }
+    }
+}
+
+/// Check the integration for `shell` end to end
+///
+/// Covers the install, whether this runs inside Scarab, and whether the
+/// daemon at `socket` received marks from this shell.
+pub fn doctor(shell: Shell, paths: &IntegrationPaths, socket: &Path) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    match installed(shell, paths) {
+        InstallState::Installed => checks.push(DoctorCheck::new(
+            CheckStatus::Pass,
+            format!(
+                "Installed for {} in {}",
+                shell.name(),
+                paths.rc_file.display()
+            ),
+        )),
+        InstallState::Foreign => checks.push(DoctorCheck::new(
+            CheckStatus::Warn,
+            format!(
+                "{} emits OSC 133 without Scarab's integration; marks may be incomplete",
+                paths.rc_file.display()
+            ),
+        )),
+        InstallState::Missing => checks.push(DoctorCheck::new(
+            CheckStatus::Fail,
+            format!(
+                "Not installed for {}; run `scarab-client shell-integration install`",
+                shell.name()
+            ),
+        )),
+    }
+
+    if std::env::var("TERM_PROGRAM").as_deref() != Ok("scarab") {
+        checks.push(DoctorCheck::new(
+            CheckStatus::Warn,
+            "Not running inside Scarab; run the doctor in a Scarab pane to check the marks",
+        ));
+        return checks;
+    }
+
+    match fetch_prompt_markers(socket) {
+        Ok(markers) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_micros() as u64)
+                .unwrap_or(0);
+            checks.extend(check_markers(&markers, now));
+        }
+        Err(e) => checks.push(DoctorCheck::new(
+            CheckStatus::Fail,
+            format!("Could not ask the daemon for markers: {}", e),
+        )),
+    }
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_detection() {
+        assert_eq!(Shell::from_path("/usr/bin/zsh"), Some(Shell::Zsh));
+        assert_eq!(Shell::from_path("-bash"), Some(Shell::Bash));
+        assert_eq!(Shell::from_path("/bin/nu"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let paths = IntegrationPaths::new(
+            Shell::Bash,
+            dir.path(),
+            &dir.path().join(".config"),
+            &dir.path().join(".config/scarab"),
+        );
+        std::fs::write(&paths.rc_file, "alias ll='ls -l'").unwrap();
+
+        assert_eq!(installed(Shell::Bash, &paths), InstallState::Missing);
+        assert_eq!(
+            install(Shell::Bash, &paths, false).unwrap(),
+            InstallOutcome::Added
+        );
+        assert_eq!(installed(Shell::Bash, &paths), InstallState::Installed);
+        // Installing again refreshes the script without a second block
+        assert_eq!(
+            install(Shell::Bash, &paths, false).unwrap(),
+            InstallOutcome::Updated
+        );
+        let rc = std::fs::read_to_string(&paths.rc_file).unwrap();
+        assert!(rc.starts_with("alias ll='ls -l'\n\n"));
+        assert_eq!(rc.matches(BLOCK_BEGIN).count(), 1);
+
+        // Another terminal's integration already marks prompts
+        std::fs::write(&paths.rc_file, "PS1=\"\\[\\e]133;A\\a\\]$PS1\"\n").unwrap();
+        assert_eq!(
+            install(Shell::Bash, &paths, false).unwrap(),
+            InstallOutcome::Conflict
+        );
+        assert_eq!(
+            install(Shell::Bash, &paths, true).unwrap(),
+            InstallOutcome::Added
+        );
+    }
+
+    #[test]
+    fn test_check_markers() {
+        let now = 100_000_000;
+        let markers = [
+            PromptMarkerInfo::prompt_start(0, now - 2_000_000),
+            PromptMarkerInfo::command_start(0, now - 2_000_000),
+            PromptMarkerInfo::command_executed(1, now - 1_000_000),
+        ];
+        let checks = check_markers(&markers, now);
+        let statuses: Vec<CheckStatus> = checks.iter().map(|c| c.status).collect();
+        // The doctor's own command has not finished, so no exit code yet
+        assert_eq!(
+            statuses,
+            [
+                CheckStatus::Pass,
+                CheckStatus::Pass,
+                CheckStatus::Pass,
+                CheckStatus::Warn,
+                CheckStatus::Pass
+            ]
+        );
+
+        // Marks from a shell that has since lost its integration are stale
+        let checks = check_markers(&markers, now + 60_000_000);
+        assert_eq!(checks.last().unwrap().status, CheckStatus::Fail);
+    }
+}
@@ -0,0 +1,54 @@
+# Scarab shell integration for bash
+#
+# Marks prompts and commands with OSC 133 so Scarab can fold, re-run, search
+# and export them, and reports the working directory with OSC 7.
+
+if [[ "$TERM_PROGRAM" == scarab && -z "$__scarab_integration" ]]; then
+    __scarab_integration=1
+    __scarab_status=0
+    __scarab_at_prompt=""
+    __scarab_ran=""
+
+    # First in PROMPT_COMMAND: end the command with its exit status, and
+    # leave that in $? for the hooks after
+    __scarab_command_done() {
+        __scarab_status=$?
+        __scarab_at_prompt=""
+        if [[ -n "$__scarab_ran" ]]; then
+            printf '\e]133;D;%s\e\\' "$__scarab_status"
+            __scarab_ran=""
+        fi
+        return $__scarab_status
+    }
+
+    # Last in PROMPT_COMMAND, so whatever the hooks before it print comes
+    # before the prompt mark rather than inside the prompt
+    __scarab_prompt_command() {
+        printf '\e]7;file://%s%s\e\\' "$HOSTNAME" "$PWD"
+        printf '\e]133;A\e\\'
+        # Input starts where the prompt ends
+        [[ "$PS1" == *'133;B'* ]] || PS1="$PS1"'\[\e]133;B\e\\\]'
+        __scarab_at_prompt=1
+        return $__scarab_status
+    }
+
+    # Without PS0 (bash before 4.4), the DEBUG trap runs before every simple
+    # command; only the first one after a prompt starts the command's output
+    __scarab_preexec() {
+        [[ -n "$__scarab_at_prompt" && -z "$COMP_LINE" ]] || return
+        __scarab_at_prompt=""
+        [[ "$BASH_COMMAND" == __scarab_* ]] && return
+        __scarab_ran=1
+        printf '\e]133;C\e\\'
+    }
+
+    PROMPT_COMMAND="__scarab_command_done${PROMPT_COMMAND:+; $PROMPT_COMMAND}; __scarab_prompt_command"
+    if (( BASH_VERSINFO[0] > 4 || (BASH_VERSINFO[0] == 4 && BASH_VERSINFO[1] >= 4) )); then
+        # PS0 is printed once a command line has been read, and not for an
+        # empty one; the subscript only notes that a command ran
+        PS0="$PS0"'${__scarab_none[__scarab_ran=1]}\e]133;C\e\\'
+    else
+        # Another DEBUG trap (e.g. bash-preexec) is left alone; prompts are still marked
+        [[ -z "$(trap -p DEBUG)" ]] && trap '__scarab_preexec' DEBUG
+    fi
+fi
@@ -0,0 +1,30 @@
+# Scarab shell integration for fish
+#
+# Marks prompts and commands with OSC 133 so Scarab can fold, re-run, search
+# and export them, and reports the working directory with OSC 7.
+
+if test "$TERM_PROGRAM" = scarab; and not set -q __scarab_integration
+    set -g __scarab_integration 1
+
+    function __scarab_prompt_start --on-event fish_prompt
+        printf '\e]7;file://%s%s\e\\' (hostname) $PWD
+        printf '\e]133;A\e\\'
+    end
+
+    function __scarab_preexec --on-event fish_preexec
+        printf '\e]133;C\e\\'
+    end
+
+    function __scarab_postexec --on-event fish_postexec
+        printf '\e]133;D;%s\e\\' $status
+    end
+
+    # Input starts where the prompt ends
+    if functions -q fish_prompt
+        functions --copy fish_prompt __scarab_fish_prompt
+        function fish_prompt
+            __scarab_fish_prompt
+            printf '\e]133;B\e\\'
+        end
+    end
+end
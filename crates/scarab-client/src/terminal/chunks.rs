@@ -6,7 +6,8 @@ use bevy::render::mesh::{Indices, Mesh, Mesh2d, PrimitiveTopology};
 use bevy::render::render_asset::RenderAssetUsages;
 use bevy::sprite::{ColorMaterial, MeshMaterial2d};
 use scarab_protocol::{
-    terminal_state::TerminalStateReader, Cell, BUFFER_SIZE, GRID_HEIGHT, GRID_WIDTH,
+    row_damaged, terminal_state::TerminalStateReader, Cell, BUFFER_SIZE, DAMAGE_WORDS, GRID_HEIGHT,
+    GRID_WIDTH,
 };
 
 use crate::integration::SharedMemoryReader;
@@ -193,6 +194,32 @@ impl PreviousGridState {
         dirty
     }
 
+    /// Like `find_dirty_cells`, but only compares rows set in a damage bitmask
+    ///
+    /// Rows outside the damage are known to be unchanged, so a keystroke
+    /// costs one row comparison instead of a full grid scan.
+    pub fn find_dirty_cells_in_rows(
+        &self,
+        current: &[Cell],
+        damage: &[u64; DAMAGE_WORDS],
+    ) -> Vec<(u16, u16)> {
+        let mut dirty = Vec::new();
+
+        for y in (0..GRID_HEIGHT).filter(|y| row_damaged(damage, *y)) {
+            let start = y * GRID_WIDTH;
+            let end = (start + GRID_WIDTH)
+                .min(current.len())
+                .min(self.cells.len());
+            for idx in start..end {
+                if !cells_equal(&self.cells[idx], &current[idx]) {
+                    dirty.push(((idx - start) as u16, y as u16));
+                }
+            }
+        }
+
+        dirty
+    }
+
     /// Update stored state from current
     ///
     /// Call this after processing changes to update the baseline
@@ -201,6 +228,18 @@ impl PreviousGridState {
         self.cells.copy_from_slice(current);
         self.last_sequence = sequence;
     }
+
+    /// Update only the rows set in a damage bitmask
+    pub fn update_rows(&mut self, current: &[Cell], damage: &[u64; DAMAGE_WORDS], sequence: u64) {
+        for y in (0..GRID_HEIGHT).filter(|y| row_damaged(damage, *y)) {
+            let start = y * GRID_WIDTH;
+            let end = (start + GRID_WIDTH)
+                .min(current.len())
+                .min(self.cells.len());
+            self.cells[start..end].copy_from_slice(&current[start..end]);
+        }
+        self.last_sequence = sequence;
+    }
 }
 
 /// Fast cell equality check (compare relevant fields)
@@ -443,8 +482,15 @@ fn mark_dirty_chunks(
     // Get current cells from shared memory
     let current_cells = safe_state.cells();
 
-    // Find dirty cells by comparing with previous state
-    let dirty_cells = prev_state.find_dirty_cells(current_cells);
+    // Find dirty cells by comparing with previous state. When the daemon's
+    // damage covers exactly this update, only damaged rows are compared.
+    let damage = safe_state
+        .row_damage()
+        .filter(|_| prev_state.last_sequence != 0 && current_seq == prev_state.last_sequence + 1);
+    let dirty_cells = match &damage {
+        Some(damage) => prev_state.find_dirty_cells_in_rows(current_cells, damage),
+        None => prev_state.find_dirty_cells(current_cells),
+    };
 
     // Track unique dirty chunks using a HashSet for deduplication
     let mut dirty_chunks = std::collections::HashSet::new();
@@ -468,7 +514,10 @@ fn mark_dirty_chunks(
     }
 
     // Update previous state for next frame
-    prev_state.update(current_cells, current_seq);
+    match &damage {
+        Some(damage) => prev_state.update_rows(current_cells, damage, current_seq),
+        None => prev_state.update(current_cells, current_seq),
+    }
 
     // Debug telemetry (only log when changes detected)
     if !dirty_cells.is_empty() {
@@ -664,6 +713,21 @@ mod tests {
         assert_eq!(dirty.len(), 0);
     }
 
+    #[test]
+    fn test_find_dirty_cells_in_damaged_rows() {
+        let prev = PreviousGridState::default();
+
+        let mut current = vec![Cell::default(); BUFFER_SIZE];
+        current[GRID_WIDTH + 5].char_codepoint = 'A' as u32;
+        current[3 * GRID_WIDTH].char_codepoint = 'B' as u32;
+
+        // Only row 1 is reported damaged, so row 3 is not compared
+        let mut damage = [0u64; DAMAGE_WORDS];
+        damage[0] = 1 << 1;
+        let dirty = prev.find_dirty_cells_in_rows(&current, &damage);
+        assert_eq!(dirty, vec![(5, 1)]);
+    }
+
     #[test]
     fn test_dirty_cell_to_chunk_mapping() {
         // Cell (0, 0) maps to chunk (0, 0)
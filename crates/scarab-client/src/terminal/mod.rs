@@ -7,4 +7,6 @@ pub mod scrollback;
 pub use chunks::{
     ChunkGrid, ChunkMesh, ChunkPlugin, TerminalChunk, CHUNKS_X, CHUNKS_Y, CHUNK_HEIGHT, CHUNK_WIDTH,
 };
-pub use scrollback::{ScrollbackBuffer, ScrollbackLine, ScrollbackPlugin, ScrollbackState};
+pub use scrollback::{
+    ScrollbackAnchor, ScrollbackBuffer, ScrollbackLine, ScrollbackPlugin, ScrollbackState,
+};
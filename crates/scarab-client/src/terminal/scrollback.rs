@@ -2,10 +2,13 @@
 // Provides efficient storage and retrieval of historical terminal lines
 
 use bevy::prelude::*;
-use scarab_protocol::Cell;
+use scarab_config::ScarabConfig;
+use scarab_protocol::{Cell, TerminalMetrics};
 use std::collections::VecDeque;
 use std::time::SystemTime;
 
+use crate::rendering::smooth_scroll::{smooth_scroll_enabled, SmoothScroll};
+
 // Re-export the event from scarab_mouse for convenience
 pub use scarab_mouse::ScrollbackScrollEvent;
 
@@ -42,6 +45,20 @@ impl ScrollbackLine {
         }
     }
 
+    /// Create a line from plain text fetched from the daemon
+    ///
+    /// Colors are not carried over; cells use the default theme colors.
+    pub fn from_text(text: &str) -> Self {
+        let cells = text
+            .chars()
+            .map(|ch| Cell {
+                char_codepoint: ch as u32,
+                ..Cell::default()
+            })
+            .collect();
+        Self::new(cells)
+    }
+
     /// Get text content of this line
     pub fn to_string(&self) -> String {
         let mut text = String::with_capacity(self.cells.len());
@@ -302,6 +319,122 @@ impl ScrollbackBuffer {
         self.scroll_offset = 0;
         self.clear_search();
     }
+
+    /// Position of the top visible row within its logical line
+    ///
+    /// Returns `None` at the live view, which needs no anchor.
+    pub fn viewport_anchor(&self) -> Option<ScrollbackAnchor> {
+        if self.scroll_offset == 0 || self.lines.is_empty() {
+            return None;
+        }
+
+        let top = self.lines.len().saturating_sub(self.scroll_offset);
+        let mut anchor = ScrollbackAnchor { line: 0, column: 0 };
+        for (idx, row) in self.lines.iter().enumerate().take(top + 1) {
+            if idx > 0 && !row.wrapped {
+                anchor.line += 1;
+                anchor.column = 0;
+            }
+            if idx < top {
+                anchor.column += row.cells.len();
+            }
+        }
+        Some(anchor)
+    }
+
+    /// Scroll so the row holding `anchor` is the top visible row
+    pub fn restore_anchor(&mut self, anchor: ScrollbackAnchor) {
+        let mut line = 0;
+        let mut column = 0;
+        let mut top = 0;
+        for (idx, row) in self.lines.iter().enumerate() {
+            if idx > 0 && !row.wrapped {
+                line += 1;
+                column = 0;
+            }
+            if line > anchor.line || (line == anchor.line && column > anchor.column) {
+                break;
+            }
+            top = idx;
+            column += row.cells.len();
+        }
+        self.scroll_offset = self.lines.len() - top;
+    }
+
+    /// Rewrap history to `width` columns, keeping the same content at the
+    /// top of the viewport
+    ///
+    /// Rows marked `wrapped` are joined back into their logical line, trailing
+    /// blanks are dropped, and each logical line is split again at the new
+    /// width.
+    pub fn reflow(&mut self, width: usize) {
+        if width == 0 || self.lines.is_empty() {
+            return;
+        }
+        let anchor = self.viewport_anchor();
+
+        let mut logical: Vec<(Vec<Cell>, SystemTime)> = Vec::new();
+        for row in self.lines.drain(..) {
+            match logical.last_mut() {
+                Some((cells, _)) if row.wrapped => cells.extend(row.cells),
+                _ => logical.push((row.cells, row.timestamp)),
+            }
+        }
+
+        for (mut cells, timestamp) in logical {
+            let end = cells
+                .iter()
+                .rposition(|c| !is_blank(c))
+                .map_or(0, |i| i + 1);
+            cells.truncate(end);
+            if cells.is_empty() {
+                self.lines.push_back(ScrollbackLine {
+                    cells,
+                    timestamp,
+                    wrapped: false,
+                });
+                continue;
+            }
+            for (idx, chunk) in cells.chunks(width).enumerate() {
+                self.lines.push_back(ScrollbackLine {
+                    cells: chunk.to_vec(),
+                    timestamp,
+                    wrapped: idx > 0,
+                });
+            }
+        }
+
+        if let Some(anchor) = anchor {
+            self.restore_anchor(anchor);
+        }
+        // The offset counts from the newest line, so evicting old rows keeps
+        // the viewport in place
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+        }
+        self.scroll_offset = self.scroll_offset.min(self.lines.len());
+
+        if self.search_query.is_some() {
+            let offset = self.scroll_offset;
+            self.invalidate_search();
+            self.scroll_offset = offset;
+        }
+    }
+}
+
+/// Whitespace on the default background, dropped from the end of a line when
+/// it is rewrapped
+fn is_blank(cell: &Cell) -> bool {
+    (cell.char_codepoint == 0 || cell.char_codepoint == 32) && cell.bg == Cell::default().bg
+}
+
+/// Content position of the top visible row, independent of wrapping
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScrollbackAnchor {
+    /// Index of the logical (unwrapped) line
+    pub line: usize,
+    /// Cells of that line above the top row
+    pub column: usize,
 }
 
 /// Search state information
@@ -343,9 +476,17 @@ fn handle_mouse_scroll(
     mut scroll_events: EventReader<bevy::input::mouse::MouseWheel>,
     mut scrollback: ResMut<ScrollbackBuffer>,
     mut state: ResMut<ScrollbackState>,
+    config: Option<Res<ScarabConfig>>,
+    smooth_scroll: Option<Res<SmoothScroll>>,
 ) {
     use bevy::input::mouse::MouseScrollUnit;
 
+    // Smooth scrolling takes over the wheel when enabled
+    if smooth_scroll.is_some() && smooth_scroll_enabled(config.as_deref()) {
+        scroll_events.clear();
+        return;
+    }
+
     for event in scroll_events.read() {
         let lines = match event.unit {
             MouseScrollUnit::Line => (event.y * 3.0) as i32, // 3 lines per scroll notch
@@ -418,8 +559,9 @@ fn handle_keyboard_scrolling(
         }
     }
 
-    // Ctrl+F: Open search
+    // Ctrl+Shift+F: Toggle search (plain Ctrl+F belongs to the shell)
     if (keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight))
+        && (keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight))
         && keys.just_pressed(KeyCode::KeyF)
     {
         state.search_visible = !state.search_visible;
@@ -437,26 +579,27 @@ fn handle_keyboard_scrolling(
     }
 }
 
-/// System to handle search navigation
-fn handle_search_navigation(
-    keys: Res<ButtonInput<KeyCode>>,
+/// System to rewrap history when the grid width changes
+///
+/// The viewport stays on the same content rather than the same row index.
+fn reflow_on_resize(
+    metrics: Option<Res<TerminalMetrics>>,
     mut scrollback: ResMut<ScrollbackBuffer>,
-    state: Res<ScrollbackState>,
+    mut state: ResMut<ScrollbackState>,
+    mut last_columns: Local<Option<u16>>,
 ) {
-    if !state.search_visible {
+    let Some(metrics) = metrics else {
         return;
-    }
-
-    // Enter: Next result
-    if keys.just_pressed(KeyCode::Enter) {
-        if keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight) {
-            // Shift+Enter: Previous result
-            scrollback.prev_search_result();
-        } else {
-            // Enter: Next result
-            scrollback.next_search_result();
+    };
+    let columns = metrics.columns;
+    match *last_columns {
+        Some(last) if last != columns => {
+            scrollback.reflow(columns as usize);
+            state.is_scrolled = !scrollback.is_at_bottom();
         }
+        _ => {}
     }
+    *last_columns = Some(columns);
 }
 
 /// Bevy plugin for scrollback functionality
@@ -472,10 +615,10 @@ impl Plugin for ScrollbackPlugin {
             .add_systems(
                 Update,
                 (
+                    reflow_on_resize,
                     handle_mouse_scroll,
                     handle_scrollback_scroll_events,
                     handle_keyboard_scrolling,
-                    handle_search_navigation,
                 )
                     .chain(),
             );
@@ -574,6 +717,55 @@ mod tests {
         assert_eq!(state.current_index, 1);
     }
 
+    #[test]
+    fn test_scrollback_reflow_keeps_anchor() {
+        let mut buffer = ScrollbackBuffer::new(100);
+        // "abcdefgh" soft-wrapped at 4 columns, then "xy" padded with blanks
+        buffer.push_line(ScrollbackLine::from_text("abcd"));
+        buffer.push_line(ScrollbackLine::new_wrapped(
+            ScrollbackLine::from_text("efgh").cells,
+        ));
+        buffer.push_line(ScrollbackLine::from_text("xy  "));
+        buffer.push_line(ScrollbackLine::from_text("zzzz"));
+
+        // "efgh" at the top of the viewport
+        buffer.scroll_up(3);
+        assert_eq!(
+            buffer.viewport_anchor(),
+            Some(ScrollbackAnchor { line: 0, column: 4 })
+        );
+
+        // Wider: the logical line fits on one row, which becomes the top
+        buffer.reflow(8);
+        assert_eq!(buffer.line_count(), 3);
+        assert_eq!(buffer.get_line(0).unwrap().to_string(), "abcdefgh");
+        assert_eq!(buffer.get_line(1).unwrap().to_string(), "xy");
+        assert_eq!(buffer.scroll_offset(), 3);
+
+        // Narrower: "xy" stays on top after the rows above it split
+        buffer.scroll_down(1);
+        buffer.reflow(3);
+        let rows: Vec<String> = (0..buffer.line_count())
+            .map(|i| buffer.get_line(i).unwrap().to_string())
+            .collect();
+        assert_eq!(rows, ["abc", "def", "gh", "xy", "zzz", "z"]);
+        assert_eq!(buffer.get_visible_lines(1)[0].to_string(), "xy");
+
+        // A top row in the middle of a line keeps the row holding its first cell
+        buffer.scroll_up(1);
+        assert_eq!(
+            buffer.viewport_anchor(),
+            Some(ScrollbackAnchor { line: 0, column: 6 })
+        );
+        buffer.reflow(4);
+        assert_eq!(buffer.get_visible_lines(1)[0].to_string(), "efgh");
+
+        // The live view is not anchored
+        buffer.scroll_to_bottom();
+        buffer.reflow(4);
+        assert!(buffer.is_at_bottom());
+    }
+
     #[test]
     fn test_scrollback_line_to_string() {
         let text = "Test line";
@@ -0,0 +1,406 @@
+//! Folding the output of finished commands
+//!
+//! With shell integration, each command the shell runs forms a command
+//! block. "Fold: Toggle Last Command" collapses the last finished block to
+//! one row giving its command, duration and exit code, or expands it again.
+//! "Fold: Collapse Passed Commands" collapses every block that exited 0 and
+//! leaves failures open, so a long CI-style log comes down to what broke.
+//! Clicking a folded row expands it.
+//!
+//! Blocks come from the daemon's `CommandBlocksUpdate`, asked for when a
+//! fold command runs and again while output arrives. Folded rows are drawn
+//! by the composed view of the smooth scroll renderer; history above the
+//! screen is fetched so that folds can pull older lines into view.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use scarab_protocol::{
+    Cell, CommandBlock, ControlMessage, DaemonMessage, TerminalMetrics, TerminalStateReader,
+};
+
+use crate::integration::SharedMemoryReader;
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::ratatui_bridge::CommandSelected;
+use crate::rendering::smooth_scroll::{GridFolds, LineFold};
+use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackLine};
+use crate::zones::format_duration;
+
+/// Palette command that folds or unfolds the last finished command
+pub const FOLD_TOGGLE_COMMAND: &str = "zones.fold_toggle";
+/// Palette command that folds commands that passed and unfolds failures
+pub const FOLD_PASSED_COMMAND: &str = "zones.fold_passed";
+/// Palette command that unfolds every command
+pub const UNFOLD_ALL_COMMAND: &str = "zones.unfold_all";
+
+/// Least time between block requests while output arrives
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Colors of a folded row (ARGB)
+const SUMMARY_BG: u32 = 0xFF1A2414;
+const SUMMARY_FG_PASSED: u32 = 0xFF7FBF6A;
+const SUMMARY_FG_FAILED: u32 = 0xFFE06C6C;
+
+/// A change to which blocks are folded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldAction {
+    /// Fold the last finished block, or unfold it if folded
+    ToggleLast,
+    /// Fold every block that exited 0 and unfold the rest
+    CollapsePassed,
+    ExpandAll,
+}
+
+/// Command blocks and which of them are folded
+#[derive(Resource, Debug, Default)]
+pub struct BlockFolds {
+    /// Blocks from the last update
+    pub blocks: Vec<CommandBlock>,
+    /// Lines of the daemon's scrollback when `blocks` were sent
+    pub scrollback_lines: usize,
+    /// IDs of folded blocks
+    pub collapsed: HashSet<u64>,
+    /// Action waiting for up-to-date blocks
+    pending: Option<FoldAction>,
+    /// Scrollback length being fetched to fill in history
+    fetch_target: Option<usize>,
+}
+
+impl BlockFolds {
+    pub fn apply(&mut self, action: FoldAction) {
+        match action {
+            FoldAction::ToggleLast => {
+                let last = self.blocks.iter().rev().find(|b| b.is_complete());
+                if let Some(id) = last.map(|b| b.id) {
+                    if !self.collapsed.remove(&id) {
+                        self.collapsed.insert(id);
+                    }
+                }
+            }
+            FoldAction::CollapsePassed => {
+                self.collapsed = self
+                    .blocks
+                    .iter()
+                    .filter(|b| b.is_complete() && b.is_success())
+                    .map(|b| b.id)
+                    .collect();
+            }
+            FoldAction::ExpandAll => self.collapsed.clear(),
+        }
+    }
+
+    /// Take blocks from the daemon, dropping folds of blocks it no longer
+    /// has
+    pub fn update_blocks(&mut self, blocks: Vec<CommandBlock>, scrollback_lines: usize) {
+        self.collapsed
+            .retain(|id| blocks.iter().any(|block| block.id == *id));
+        self.blocks = blocks;
+        self.scrollback_lines = scrollback_lines;
+    }
+
+    /// Folds of the scrolled view for the folded blocks, `width` cells wide
+    ///
+    /// `history` is the number of scrollback lines held by the client,
+    /// the newest of the daemon's.
+    pub fn line_folds(&self, history: usize, width: usize) -> Vec<LineFold> {
+        let shift = history as i64 - self.scrollback_lines as i64;
+        self.blocks
+            .iter()
+            .filter(|block| self.collapsed.contains(&block.id))
+            .filter_map(|block| {
+                // The row with the end marker is where the next prompt starts
+                let end = block.end_row.saturating_sub(1).max(block.start_row);
+                let end = usize::try_from(end as i64 + shift).ok()?;
+                let start = (block.start_row as i64 + shift).max(0) as usize;
+                Some(LineFold {
+                    id: block.id,
+                    start,
+                    end,
+                    summary: summary_cells(block, width),
+                })
+            })
+            .collect()
+    }
+}
+
+/// One-line summary of a folded block, e.g. `▸ cargo test · 1m 5s · exit 1`
+pub fn fold_summary(block: &CommandBlock) -> String {
+    let mut text = format!("▸ {}", block.command_text().unwrap_or("command"));
+    if let Some(secs) = block.duration_secs() {
+        text.push_str(&format!(" · {}", format_duration(secs)));
+    }
+    if let Some(code) = block.exit_code() {
+        text.push_str(&format!(" · exit {}", code));
+    }
+    let lines = block.end_row.saturating_sub(block.start_row).max(1);
+    text.push_str(&format!(
+        " · {} line{}",
+        lines,
+        if lines == 1 { "" } else { "s" }
+    ));
+    text
+}
+
+fn summary_cells(block: &CommandBlock, width: usize) -> Vec<Cell> {
+    let fg = if block.is_failure() {
+        SUMMARY_FG_FAILED
+    } else {
+        SUMMARY_FG_PASSED
+    };
+    let mut cells: Vec<Cell> = ScrollbackLine::from_text(&fold_summary(block))
+        .cells
+        .into_iter()
+        .take(width)
+        .map(|cell| Cell {
+            fg,
+            bg: SUMMARY_BG,
+            ..cell
+        })
+        .collect();
+    // Fill the row so it reads as a bar
+    cells.resize(
+        width,
+        Cell {
+            bg: SUMMARY_BG,
+            ..Cell::default()
+        },
+    );
+    cells
+}
+
+/// System to run the fold commands picked in the palette
+fn handle_fold_commands(
+    mut commands_selected: EventReader<CommandSelected>,
+    mut folds: ResMut<BlockFolds>,
+    ipc: Option<Res<IpcChannel>>,
+) {
+    for event in commands_selected.read() {
+        let action = match event.command_id.as_str() {
+            FOLD_TOGGLE_COMMAND => FoldAction::ToggleLast,
+            FOLD_PASSED_COMMAND => FoldAction::CollapsePassed,
+            UNFOLD_ALL_COMMAND => FoldAction::ExpandAll,
+            _ => continue,
+        };
+        match ipc.as_ref() {
+            // Apply once the daemon says which blocks there are now
+            Some(ipc) => {
+                folds.pending = Some(action);
+                ipc.send(ControlMessage::ZonesRequest);
+            }
+            None => folds.apply(action),
+        }
+    }
+}
+
+/// System to take in blocks and the history fetched for folds
+fn receive_command_blocks(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut folds: ResMut<BlockFolds>,
+    mut scrollback: ResMut<ScrollbackBuffer>,
+    ipc: Option<Res<IpcChannel>>,
+) {
+    for event in events.read() {
+        match &event.0 {
+            DaemonMessage::CommandBlocksUpdate {
+                blocks,
+                scrollback_lines,
+                ..
+            } => {
+                let scrollback_lines = *scrollback_lines as usize;
+                folds.update_blocks(blocks.clone(), scrollback_lines);
+                if let Some(action) = folds.pending.take() {
+                    folds.apply(action);
+                }
+
+                let held = scrollback.line_count();
+                if folds.collapsed.is_empty()
+                    || folds.fetch_target.is_some()
+                    || held >= scrollback_lines
+                {
+                    continue;
+                }
+                if let Some(ipc) = ipc.as_ref() {
+                    folds.fetch_target = Some(scrollback_lines);
+                    ipc.send(ControlMessage::ScrollbackFetch {
+                        start: held as u32,
+                        count: (scrollback_lines - held) as u32,
+                    });
+                }
+            }
+            DaemonMessage::ScrollbackLines { start, lines } => {
+                let Some(target) = folds.fetch_target else {
+                    continue;
+                };
+                // Lines asked for by search, or stale
+                if *start as usize != scrollback.line_count() {
+                    continue;
+                }
+                scrollback.push_lines(
+                    lines
+                        .iter()
+                        .map(|text| ScrollbackLine::from_text(text))
+                        .collect(),
+                );
+
+                let held = scrollback.line_count();
+                match ipc.as_ref() {
+                    Some(ipc) if held < target && !lines.is_empty() => {
+                        ipc.send(ControlMessage::ScrollbackFetch {
+                            start: held as u32,
+                            count: (target - held) as u32,
+                        });
+                    }
+                    _ => folds.fetch_target = None,
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// System to keep block lines current while output scrolls them
+fn refresh_command_blocks(
+    folds: Res<BlockFolds>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    ipc: Option<Res<IpcChannel>>,
+    mut last: Local<Option<(Instant, u64)>>,
+) {
+    let (Some(state_reader), Some(ipc)) = (state_reader, ipc) else {
+        return;
+    };
+    if folds.collapsed.is_empty() {
+        return;
+    }
+    let sequence = state_reader.get_safe_state().sequence();
+    let now = Instant::now();
+    let due = last.map_or(true, |(at, seen)| {
+        seen != sequence && now.duration_since(at) >= REFRESH_INTERVAL
+    });
+    if due {
+        *last = Some((now, sequence));
+        ipc.send(ControlMessage::ZonesRequest);
+    }
+}
+
+/// System to hand the folded blocks to the renderer
+fn sync_grid_folds(
+    folds: Res<BlockFolds>,
+    scrollback: Res<ScrollbackBuffer>,
+    metrics: Option<Res<TerminalMetrics>>,
+    mut grid_folds: ResMut<GridFolds>,
+) {
+    if !folds.is_changed() && !scrollback.is_changed() {
+        return;
+    }
+    let width = metrics.map_or(scarab_protocol::GRID_WIDTH, |m| m.columns as usize);
+    let line_folds = folds.line_folds(scrollback.line_count(), width);
+    if grid_folds.folds != line_folds {
+        grid_folds.folds = line_folds;
+    }
+}
+
+/// System to unfold a block when its folded row is clicked
+fn expand_clicked_fold(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    metrics: Option<Res<TerminalMetrics>>,
+    grid_folds: Res<GridFolds>,
+    mut folds: ResMut<BlockFolds>,
+) {
+    if !mouse_buttons.just_pressed(MouseButton::Left) || grid_folds.summary_rows.is_empty() {
+        return;
+    }
+    let (Ok(window), Some(metrics)) = (windows.get_single(), metrics) else {
+        return;
+    };
+    let Some(cursor_pos) = window.cursor_position() else {
+        return;
+    };
+    let (_, row) = metrics.screen_to_grid(cursor_pos.x, cursor_pos.y);
+    if let Some(&(_, id)) = grid_folds.summary_rows.iter().find(|(r, _)| *r == row) {
+        folds.collapsed.remove(&id);
+    }
+}
+
+/// Plugin for folding command blocks
+pub struct BlockFoldingPlugin;
+
+impl Plugin for BlockFoldingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BlockFolds>()
+            .init_resource::<GridFolds>()
+            .add_event::<CommandSelected>()
+            .add_systems(
+                Update,
+                (
+                    handle_fold_commands,
+                    receive_command_blocks,
+                    refresh_command_blocks,
+                    expand_clicked_fold,
+                    sync_grid_folds,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_protocol::SemanticZone;
+
+    fn block(id: u64, start_row: u32, end_row: u32, exit_code: i32) -> CommandBlock {
+        let mut block = CommandBlock::new(id, SemanticZone::new_prompt(id * 10, start_row, 0));
+        let mut output = SemanticZone::new_output(id * 10 + 1, start_row + 1, 0);
+        output.set_exit_code(exit_code);
+        output.complete(end_row, 2_500_000);
+        block.add_output_zone(output);
+        block
+    }
+
+    #[test]
+    fn test_fold_actions() {
+        let mut folds = BlockFolds::default();
+        folds.update_blocks(
+            vec![block(1, 0, 5, 0), block(2, 5, 9, 1), block(3, 9, 20, 0)],
+            0,
+        );
+
+        folds.apply(FoldAction::CollapsePassed);
+        assert_eq!(folds.collapsed, HashSet::from([1, 3]));
+
+        // The last command unfolds and folds again
+        folds.apply(FoldAction::ToggleLast);
+        assert_eq!(folds.collapsed, HashSet::from([1]));
+        folds.apply(FoldAction::ToggleLast);
+        assert_eq!(folds.collapsed, HashSet::from([1, 3]));
+
+        // Blocks the daemon dropped lose their folds
+        folds.update_blocks(vec![block(3, 9, 20, 0)], 0);
+        assert_eq!(folds.collapsed, HashSet::from([3]));
+
+        folds.apply(FoldAction::ExpandAll);
+        assert!(folds.collapsed.is_empty());
+    }
+
+    #[test]
+    fn test_line_folds() {
+        let mut folds = BlockFolds::default();
+        folds.update_blocks(vec![block(1, 100, 140, 1)], 120);
+        folds.collapsed.insert(1);
+
+        // 20 lines of the daemon's history are held; its line 100 is ours 0
+        let line_folds = folds.line_folds(20, 40);
+        assert_eq!(line_folds.len(), 1);
+        assert_eq!((line_folds[0].start, line_folds[0].end), (0, 39));
+        assert_eq!(line_folds[0].summary.len(), 40);
+        assert_eq!(line_folds[0].summary[0].fg, SUMMARY_FG_FAILED);
+
+        assert_eq!(
+            fold_summary(&folds.blocks[0]),
+            "▸ command · 2.5s · exit 1 · 40 lines"
+        );
+    }
+}
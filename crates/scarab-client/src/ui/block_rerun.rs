@@ -0,0 +1,245 @@
+//! Running a previous command again
+//!
+//! "Re-run Last Command" (Ctrl+Shift+E) pastes the command line of the
+//! last command block into the shell again and presses Enter. "Edit and
+//! Re-run Last Command" puts it at the palette's "Run Command" prompt to
+//! change first. Right-clicking a block's rows offers both for that block.
+//!
+//! The command line is what the shell echoed between its OSC 133;B and
+//! 133;C markers, so only commands run with shell integration can be run
+//! again, and of a multi-line command only its first line. Blocks are asked
+//! of the daemon each time, so the row clicked is matched against where
+//! they are now. While a command is still running nothing is sent, since it
+//! would be typed into that program rather than the shell.
+
+use bevy::prelude::*;
+use scarab_protocol::{CommandBlock, ControlMessage, DaemonMessage};
+
+use scarab_protocol::NotifyLevel;
+
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::ratatui_bridge::CommandSelected;
+use crate::ui::command_palette::{CommandPaletteState, CommandRegistry};
+use crate::ui::keybindings::KeyBindingTriggeredEvent;
+use crate::ui::overlays::spawn_notification;
+
+/// Palette command and key binding action that re-runs the last command
+pub const RERUN_COMMAND: &str = "zones.rerun";
+/// Palette command that opens the last command for editing before it runs
+pub const EDIT_RERUN_COMMAND: &str = "zones.edit_rerun";
+
+/// Palette command whose prompt takes the command to edit
+const RUN_COMMAND: &str = "run_command";
+
+/// Which block's command to run again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RerunTarget {
+    /// The last block with a command line
+    Last,
+    /// The block on this row of the screen
+    Row(u16),
+}
+
+/// Request to run a block's command again
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RerunCommand {
+    pub target: RerunTarget,
+    /// Open the command at the palette's prompt instead of running it
+    pub edit: bool,
+}
+
+/// Requests waiting for the daemon's blocks
+#[derive(Resource, Debug, Default)]
+struct PendingReruns(Vec<RerunCommand>);
+
+/// Command line of the block `target` picks out of `blocks`
+///
+/// `scrollback_lines` is the daemon's scrollback when the blocks were sent,
+/// so screen row 0 is that line of the pane.
+pub fn rerun_command_text(
+    blocks: &[CommandBlock],
+    scrollback_lines: usize,
+    target: RerunTarget,
+) -> Option<&str> {
+    match target {
+        RerunTarget::Last => blocks.iter().rev().find_map(|block| block.command_text()),
+        RerunTarget::Row(row) => {
+            let line = u32::try_from(scrollback_lines + row as usize).ok()?;
+            blocks
+                .iter()
+                .rev()
+                .find(|block| block.contains_line(line))?
+                .command_text()
+        }
+    }
+}
+
+/// Input that runs `command` at the shell's prompt
+///
+/// The command goes in as a bracketed paste, so the shell takes it as text
+/// rather than keys, then Enter runs it. Control characters are dropped so
+/// nothing in it can end the paste early.
+pub fn rerun_input(command: &str) -> Vec<u8> {
+    let command: String = command.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b[200~{}\x1b[201~\r", command).into_bytes()
+}
+
+/// System to turn the palette commands and key binding into requests
+fn request_reruns(
+    mut commands_selected: EventReader<CommandSelected>,
+    mut key_bindings: EventReader<KeyBindingTriggeredEvent>,
+    mut requests: EventWriter<RerunCommand>,
+) {
+    for event in commands_selected.read() {
+        let edit = match event.command_id.as_str() {
+            RERUN_COMMAND => false,
+            EDIT_RERUN_COMMAND => true,
+            _ => continue,
+        };
+        requests.send(RerunCommand {
+            target: RerunTarget::Last,
+            edit,
+        });
+    }
+    for event in key_bindings.read() {
+        if event.action == RERUN_COMMAND {
+            requests.send(RerunCommand {
+                target: RerunTarget::Last,
+                edit: false,
+            });
+        }
+    }
+}
+
+/// System to ask the daemon for its blocks when a re-run is requested
+fn queue_reruns(
+    mut requests: EventReader<RerunCommand>,
+    mut pending: ResMut<PendingReruns>,
+    ipc: Option<Res<IpcChannel>>,
+) {
+    let Some(ipc) = ipc else {
+        requests.clear();
+        return;
+    };
+    let before = pending.0.len();
+    pending.0.extend(requests.read().copied());
+    if pending.0.len() > before {
+        ipc.send(ControlMessage::ZonesRequest);
+    }
+}
+
+/// System to run or open the commands once the blocks arrive
+fn run_reruns(
+    mut commands: Commands,
+    mut events: EventReader<RemoteMessageEvent>,
+    mut pending: ResMut<PendingReruns>,
+    ipc: Option<Res<IpcChannel>>,
+    registry: Res<CommandRegistry>,
+    mut palette: ResMut<CommandPaletteState>,
+    time: Res<Time>,
+) {
+    for event in events.read() {
+        let DaemonMessage::CommandBlocksUpdate {
+            blocks,
+            scrollback_lines,
+            command_running,
+        } = &event.0
+        else {
+            continue;
+        };
+        for request in std::mem::take(&mut pending.0) {
+            let Some(command) =
+                rerun_command_text(blocks, *scrollback_lines as usize, request.target)
+            else {
+                warn!("No command to re-run for {:?}", request.target);
+                continue;
+            };
+            if request.edit {
+                if let Some(run) = registry.get(RUN_COMMAND) {
+                    palette.ask(run.clone(), command);
+                }
+            } else if *command_running {
+                warn!("Not re-running {:?} while a command is running", command);
+                spawn_notification(
+                    &mut commands,
+                    "Re-run",
+                    "A command is still running; wait for the prompt to re-run",
+                    NotifyLevel::Warning,
+                    time.elapsed_secs_f64(),
+                );
+            } else if let Some(ipc) = ipc.as_ref() {
+                info!("Re-running: {}", command);
+                ipc.send(ControlMessage::Input {
+                    data: rerun_input(command),
+                });
+            }
+        }
+    }
+}
+
+/// Plugin for running previous commands again
+pub struct BlockRerunPlugin;
+
+impl Plugin for BlockRerunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingReruns>()
+            .add_event::<RerunCommand>()
+            .add_event::<CommandSelected>()
+            .add_systems(Update, (request_reruns, queue_reruns, run_reruns).chain());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_protocol::SemanticZone;
+
+    fn block(id: u64, start_row: u32, end_row: u32, command: Option<&str>) -> CommandBlock {
+        let mut block = CommandBlock::new(id, SemanticZone::new_prompt(id * 10, start_row, 0));
+        let mut input = SemanticZone::new_input(id * 10 + 1, start_row, 0);
+        if let Some(command) = command {
+            input.set_command(command.to_string());
+        }
+        block.add_input_zone(input);
+        let mut output = SemanticZone::new_output(id * 10 + 2, start_row + 1, 0);
+        output.complete(end_row, 1_000_000);
+        block.add_output_zone(output);
+        block
+    }
+
+    #[test]
+    fn test_rerun_command_text() {
+        let blocks = vec![
+            block(1, 100, 104, Some("cargo build")),
+            block(2, 104, 110, Some("cargo test")),
+            block(3, 110, 112, None),
+        ];
+
+        // The last block has no command line, so the one before is used
+        assert_eq!(
+            rerun_command_text(&blocks, 0, RerunTarget::Last),
+            Some("cargo test")
+        );
+
+        // Row 2 of the screen is line 102 with 100 lines of scrollback
+        assert_eq!(
+            rerun_command_text(&blocks, 100, RerunTarget::Row(2)),
+            Some("cargo build")
+        );
+        assert_eq!(rerun_command_text(&blocks, 100, RerunTarget::Row(11)), None);
+        assert_eq!(rerun_command_text(&blocks, 0, RerunTarget::Row(2)), None);
+    }
+
+    #[test]
+    fn test_rerun_input() {
+        assert_eq!(
+            rerun_input("cargo test"),
+            b"\x1b[200~cargo test\x1b[201~\r".to_vec()
+        );
+        // An escape in the command can't end the paste
+        assert_eq!(
+            rerun_input("echo \x1b[201~rm -rf ~\n"),
+            b"\x1b[200~echo [201~rm -rf ~\x1b[201~\r".to_vec()
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Exit-code gutter beside the terminal grid
+//!
+//! With shell integration, a slim column to the right of the grid marks the
+//! row each command's prompt starts on: green if the command exited 0, red
+//! if it failed, blue while it is still running. Hovering a mark shows the
+//! exit code and how long the command ran.
+//!
+//! Marks come from the OSC 133 markers the daemon pushes as they change,
+//! and are placed with the same line numbering as the scrollbar's ticks, so
+//! they follow the view while scrolling back. The gutter's room is reserved
+//! together with the minimap's in [`TerminalInsets::right`].
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use scarab_config::ScarabConfig;
+use scarab_protocol::{ControlMessage, PromptMarkerInfo, TerminalMetrics};
+
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::prompt_markers::{marker_color, receive_prompt_markers, PromptMarkers};
+use crate::terminal::scrollback::ScrollbackBuffer;
+use crate::ui::status_bar::STATUS_BAR_HEIGHT;
+use crate::ui::tab_bar::TerminalInsets;
+use crate::zones::format_duration;
+
+/// Width of the gutter in pixels
+pub const GUTTER_WIDTH: f32 = 6.0;
+
+/// OSC 133 marker types a command is pieced together from
+const MARKER_PROMPT_START: u8 = 0;
+const MARKER_COMMAND_EXECUTED: u8 = 2;
+const MARKER_COMMAND_FINISHED: u8 = 3;
+
+/// Gap between the tooltip and the gutter
+const TOOLTIP_GAP: f32 = 4.0;
+
+/// A command as the gutter shows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GutterMark {
+    /// Line the command's prompt starts on
+    pub line: u32,
+    /// Exit code, once the command finished
+    pub exit_code: Option<i32>,
+    /// Time from the command starting to it finishing, in microseconds
+    pub duration_micros: Option<u64>,
+}
+
+impl GutterMark {
+    /// Tooltip text, e.g. `exit 1 · 2.5s`
+    pub fn label(&self) -> String {
+        match (self.exit_code, self.duration_micros) {
+            (Some(code), Some(micros)) => {
+                format!("exit {} · {}", code, format_duration(micros as f64 / 1e6))
+            }
+            (Some(code), None) => format!("exit {}", code),
+            (None, _) => "running".to_string(),
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self.exit_code {
+            Some(code) => marker_color(MARKER_COMMAND_FINISHED, Some(code)),
+            None => marker_color(MARKER_PROMPT_START, None),
+        }
+    }
+}
+
+/// Commands run in `markers`, oldest first
+///
+/// A command starts at OSC 133;C and finishes at the next 133;D, which
+/// carries its exit code. Prompts where nothing was run get no mark.
+pub fn gutter_marks(markers: &[PromptMarkerInfo]) -> Vec<GutterMark> {
+    let mut marks: Vec<GutterMark> = Vec::new();
+    let mut prompt_line = None;
+    let mut running = None;
+    for marker in markers {
+        match marker.marker_type {
+            MARKER_PROMPT_START => prompt_line = Some(marker.line),
+            MARKER_COMMAND_EXECUTED => {
+                running = Some((marks.len(), marker.timestamp_micros));
+                marks.push(GutterMark {
+                    line: prompt_line.take().unwrap_or(marker.line),
+                    exit_code: None,
+                    duration_micros: None,
+                });
+            }
+            MARKER_COMMAND_FINISHED => {
+                if let Some((index, started)) = running.take() {
+                    marks[index].exit_code = marker.exit_code;
+                    marks[index].duration_micros =
+                        Some(marker.timestamp_micros.saturating_sub(started));
+                }
+            }
+            _ => {}
+        }
+    }
+    marks
+}
+
+/// Marks on screen with their rows, for a view whose top row shows line
+/// `view_top` and that is `rows` rows tall
+pub fn visible_marks(marks: &[GutterMark], view_top: usize, rows: usize) -> Vec<(u16, GutterMark)> {
+    marks
+        .iter()
+        .filter_map(|mark| {
+            let row = (mark.line as usize).checked_sub(view_top)?;
+            (row < rows).then_some((row as u16, *mark))
+        })
+        .collect()
+}
+
+/// Whether the gutter is enabled in the config
+pub fn gutter_enabled(config: Option<&ScarabConfig>) -> bool {
+    config.map_or(true, |c| c.ui.show_command_gutter)
+}
+
+/// Distance of the gutter from the right edge of the window
+fn gutter_right(insets: &TerminalInsets) -> f32 {
+    (insets.right - GUTTER_WIDTH).max(0.0)
+}
+
+/// Marks currently drawn, by screen row
+#[derive(Resource, Debug, Default)]
+pub struct CommandGutterState {
+    pub rows: Vec<(u16, GutterMark)>,
+}
+
+/// Marker component for the gutter column
+#[derive(Component)]
+pub struct CommandGutter;
+
+/// Marker component for one command's mark
+#[derive(Component)]
+struct GutterMarkNode;
+
+/// Marker component for the hover tooltip
+#[derive(Component)]
+struct GutterTooltip;
+
+/// System to spawn the gutter column and its tooltip
+fn spawn_command_gutter(mut commands: Commands) {
+    commands.spawn((
+        CommandGutter,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(0.0),
+            right: Val::Px(0.0),
+            bottom: Val::Px(STATUS_BAR_HEIGHT),
+            width: Val::Px(GUTTER_WIDTH),
+            display: Display::None,
+            ..default()
+        },
+        ZIndex(950), // Same level as the scrollbar
+    ));
+
+    commands.spawn((
+        GutterTooltip,
+        Node {
+            position_type: PositionType::Absolute,
+            padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.05, 0.07, 0.03, 0.95)),
+        BorderColor(Color::srgba(0.66, 0.87, 0.35, 0.6)),
+        BorderRadius::all(Val::Px(3.0)),
+        Text::default(),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.85, 0.92, 0.78)),
+        ZIndex(2000), // Above status bar (ZIndex 1000)
+    ));
+}
+
+/// System to ask for the markers once connected, since they are otherwise
+/// only sent as they change
+fn request_prompt_markers(ipc: Option<Res<IpcChannel>>, mut requested: Local<bool>) {
+    if let (Some(ipc), false) = (ipc, *requested) {
+        ipc.send(ControlMessage::ZonesRequest);
+        *requested = true;
+    }
+}
+
+/// System to place the gutter and redraw its marks when markers, the view
+/// or the layout change
+#[allow(clippy::too_many_arguments)]
+fn update_gutter_marks(
+    mut commands: Commands,
+    config: Option<Res<ScarabConfig>>,
+    markers: Res<PromptMarkers>,
+    scrollback: Res<ScrollbackBuffer>,
+    metrics: Option<Res<TerminalMetrics>>,
+    insets: Option<Res<TerminalInsets>>,
+    mut state: ResMut<CommandGutterState>,
+    mut gutters: Query<(Entity, &mut Node), With<CommandGutter>>,
+    mark_nodes: Query<Entity, With<GutterMarkNode>>,
+) {
+    let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+    let metrics_changed = metrics.as_ref().is_some_and(|m| m.is_changed());
+    let insets_changed = insets.as_ref().is_some_and(|i| i.is_changed());
+    if !markers.is_changed()
+        && !scrollback.is_changed()
+        && !config_changed
+        && !metrics_changed
+        && !insets_changed
+    {
+        return;
+    }
+    let Ok((gutter, mut node)) = gutters.get_single_mut() else {
+        return;
+    };
+    let insets = insets.map(|i| *i).unwrap_or_default();
+
+    let enabled = gutter_enabled(config.as_deref());
+    let display = if enabled {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    if node.display != display {
+        node.display = display;
+    }
+    node.top = Val::Px(insets.top);
+    node.bottom = Val::Px(STATUS_BAR_HEIGHT + insets.bottom);
+    node.right = Val::Px(gutter_right(&insets));
+
+    for entity in mark_nodes.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(metrics) = metrics.filter(|_| enabled) else {
+        state.rows.clear();
+        return;
+    };
+
+    let view_top = scrollback
+        .line_count()
+        .saturating_sub(scrollback.scroll_offset());
+    state.rows = visible_marks(
+        &gutter_marks(&markers.markers),
+        view_top,
+        metrics.rows as usize,
+    );
+
+    commands.entity(gutter).with_children(|gutter| {
+        for (row, mark) in &state.rows {
+            gutter.spawn((
+                GutterMarkNode,
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(*row as f32 * metrics.cell_height + 1.0),
+                    left: Val::Px(1.0),
+                    right: Val::Px(1.0),
+                    height: Val::Px((metrics.cell_height - 2.0).max(1.0)),
+                    ..default()
+                },
+                BackgroundColor(mark.color()),
+                BorderRadius::all(Val::Px(2.0)),
+            ));
+        }
+    });
+}
+
+/// System to show the exit code and duration of the mark under the pointer
+fn update_gutter_tooltip(
+    config: Option<Res<ScarabConfig>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    metrics: Option<Res<TerminalMetrics>>,
+    insets: Option<Res<TerminalInsets>>,
+    state: Res<CommandGutterState>,
+    mut tooltips: Query<(&mut Node, &mut Text), With<GutterTooltip>>,
+) {
+    let Ok((mut node, mut text)) = tooltips.get_single_mut() else {
+        return;
+    };
+    let insets = insets.map(|i| *i).unwrap_or_default();
+    let hovered = match (windows.get_single(), metrics) {
+        (Ok(window), Some(metrics)) if gutter_enabled(config.as_deref()) => {
+            window.cursor_position().and_then(|cursor| {
+                let right = gutter_right(&insets);
+                let x = window.width() - cursor.x;
+                let y = cursor.y - insets.top;
+                if x <= right || x > right + GUTTER_WIDTH || y < 0.0 {
+                    return None;
+                }
+                let row = (y / metrics.cell_height) as u16;
+                let (_, mark) = state.rows.iter().find(|(r, _)| *r == row)?;
+                Some((
+                    mark.label(),
+                    right + GUTTER_WIDTH + TOOLTIP_GAP,
+                    insets.top + row as f32 * metrics.cell_height,
+                ))
+            })
+        }
+        _ => None,
+    };
+
+    let Some((label, right, top)) = hovered else {
+        if node.display != Display::None {
+            node.display = Display::None;
+        }
+        return;
+    };
+    if **text != label {
+        **text = label;
+    }
+    if node.display != Display::Flex || node.right != Val::Px(right) || node.top != Val::Px(top) {
+        node.display = Display::Flex;
+        node.right = Val::Px(right);
+        node.top = Val::Px(top);
+    }
+}
+
+/// Plugin for the exit-code gutter
+pub struct CommandGutterPlugin;
+
+impl Plugin for CommandGutterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PromptMarkers>()
+            .init_resource::<CommandGutterState>()
+            .init_resource::<TerminalInsets>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(Startup, spawn_command_gutter)
+            .add_systems(
+                Update,
+                (
+                    request_prompt_markers,
+                    receive_prompt_markers,
+                    update_gutter_marks,
+                    update_gutter_tooltip,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gutter_marks() {
+        let markers = vec![
+            PromptMarkerInfo::prompt_start(10, 0),
+            PromptMarkerInfo::command_start(10, 100),
+            PromptMarkerInfo::command_executed(11, 1_000_000),
+            PromptMarkerInfo::command_finished(14, 0, 3_500_000),
+            // Enter on an empty prompt runs nothing
+            PromptMarkerInfo::prompt_start(14, 4_000_000),
+            PromptMarkerInfo::command_finished(15, 0, 4_000_100),
+            PromptMarkerInfo::prompt_start(15, 5_000_000),
+            PromptMarkerInfo::command_executed(16, 5_000_000),
+            PromptMarkerInfo::command_finished(20, 2, 5_250_000),
+            PromptMarkerInfo::prompt_start(20, 6_000_000),
+            PromptMarkerInfo::command_executed(21, 6_000_000),
+        ];
+        let marks = gutter_marks(&markers);
+        assert_eq!(
+            marks,
+            vec![
+                GutterMark {
+                    line: 10,
+                    exit_code: Some(0),
+                    duration_micros: Some(2_500_000),
+                },
+                GutterMark {
+                    line: 15,
+                    exit_code: Some(2),
+                    duration_micros: Some(250_000),
+                },
+                GutterMark {
+                    line: 20,
+                    exit_code: None,
+                    duration_micros: None,
+                },
+            ]
+        );
+        assert_eq!(marks[0].label(), "exit 0 · 2.5s");
+        assert_eq!(marks[2].label(), "running");
+
+        // Scrolled so line 12 is at the top of a 5-row view
+        let rows: Vec<(u16, u32)> = visible_marks(&marks, 12, 5)
+            .iter()
+            .map(|(row, mark)| (*row, mark.line))
+            .collect();
+        assert_eq!(rows, vec![(3, 15)]);
+    }
+}
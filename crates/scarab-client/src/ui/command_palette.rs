@@ -1,14 +1,36 @@
 // Command palette with fuzzy search
 // Provides quick access to all terminal commands
+//
+// Typing filters commands with a fuzzy matcher; results are ranked by match
+// quality plus frecency (how often and how recently a command was run),
+// which is saved across runs. Each command shows the key bound to it in
+// `[keybindings]`. Commands with a prompt ask for an argument before they
+// run, e.g. the new title for "Rename Tab".
 
-use crate::ipc::IpcChannel;
-use bevy::input::keyboard::KeyCode;
+use crate::input::key_tables::{bevy_to_api_keycode, build_modifiers, parse_key_combo};
+use crate::ipc::{DaemonPaths, IpcChannel};
+use crate::ratatui_bridge::CommandSelected;
+use crate::ui::overlays::{HideModalEvent, ShowRemoteModalEvent};
+use crate::ui::tab_bar::TabBarState;
+use bevy::input::keyboard::{Key, KeyCode, KeyboardInput};
 use bevy::prelude::*;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
-use scarab_protocol::{ControlMessage, ModalItem};
+use scarab_config::{KeyBindings, ScarabConfig};
+use scarab_plugin_api::key_tables::KeyCombo;
+use scarab_protocol::ControlMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// Score added per point of frecency when ranking
+const FRECENCY_WEIGHT: f64 = 10.0;
+
+const HOUR_SECS: i64 = 60 * 60;
+const DAY_SECS: i64 = 24 * HOUR_SECS;
+const WEEK_SECS: i64 = 7 * DAY_SECS;
+
 /// Plugin for command palette functionality
 pub struct CommandPalettePlugin;
 
@@ -16,30 +38,31 @@ impl Plugin for CommandPalettePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<CommandRegistry>()
             .init_resource::<CommandPaletteState>()
+            .init_resource::<CommandHistory>()
+            .init_resource::<DaemonPaths>()
             .add_event::<CommandExecutedEvent>()
+            .add_event::<CommandSelected>()
             .add_event::<ShowRemoteModalEvent>()
+            .add_event::<HideModalEvent>()
             .add_systems(
                 Update,
                 (
+                    sync_keybinds_with_config,
+                    handle_remote_modal_system,
                     toggle_palette_system,
                     handle_palette_input_system,
                     render_palette_system,
                     execute_command_system,
-                    handle_remote_modal_system,
                 )
                     .chain(),
             )
-            .add_systems(Startup, register_default_commands_system);
+            .add_systems(
+                Startup,
+                (register_default_commands_system, load_command_history),
+            );
     }
 }
 
-/// Event to trigger a remote modal (populating palette from daemon)
-#[derive(Event)]
-pub struct ShowRemoteModalEvent {
-    pub title: String,
-    pub items: Vec<ModalItem>,
-}
-
 /// A command that can be executed
 #[derive(Clone)]
 pub struct Command {
@@ -48,6 +71,8 @@ pub struct Command {
     pub description: String,
     pub category: String,
     pub keybind: Option<String>,
+    /// Label of the argument asked for before the command runs
+    pub prompt: Option<String>,
     pub action: Arc<dyn Fn(&IpcChannel) + Send + Sync>,
 }
 
@@ -62,14 +87,26 @@ impl Command {
             description: description.to_string(),
             category: category.to_string(),
             keybind: None,
+            prompt: None,
             action: Arc::new(action),
         }
     }
 
+    /// A command carried out by client systems reading `CommandSelected`
+    pub fn client(id: &str, name: &str, description: &str, category: &str) -> Self {
+        Self::new(id, name, description, category, |_| {})
+    }
+
     pub fn with_keybind(mut self, keybind: &str) -> Self {
         self.keybind = Some(keybind.to_string());
         self
     }
+
+    /// Ask for an argument first; it arrives in `CommandExecutedEvent::argument`
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
 }
 
 /// Registry of all available commands
@@ -117,6 +154,169 @@ impl CommandRegistry {
         results.sort_by(|a, b| b.1.cmp(&a.1));
         results
     }
+
+    /// Fuzzy search boosted by how often and how recently commands ran
+    pub fn ranked_search(
+        &self,
+        query: &str,
+        history: &CommandHistory,
+        now: i64,
+    ) -> Vec<(Command, i64)> {
+        rank_commands(&self.commands, query, Some(history), now)
+    }
+
+    /// Show the keys bound in config next to matching commands
+    pub fn apply_keybinds(&mut self, keys: &HashMap<String, String>) {
+        for command in &mut self.commands {
+            if let Some(key) = keys.get(&command.id) {
+                command.keybind = Some(key.clone());
+            }
+        }
+    }
+}
+
+/// Filter and rank commands for a query
+///
+/// Without a query every command is listed, most frecent first; ties keep
+/// registration order.
+fn rank_commands(
+    commands: &[Command],
+    query: &str,
+    history: Option<&CommandHistory>,
+    now: i64,
+) -> Vec<(Command, i64)> {
+    let matcher = SkimMatcherV2::default();
+    let boost = |id: &str| {
+        history.map_or(0, |h| {
+            (h.frecency(id, now) * FRECENCY_WEIGHT).round() as i64
+        })
+    };
+
+    let mut results: Vec<(Command, i64)> = commands
+        .iter()
+        .filter_map(|cmd| {
+            if query.is_empty() {
+                return Some((cmd.clone(), boost(&cmd.id)));
+            }
+            let score = [&cmd.name, &cmd.description, &cmd.id]
+                .into_iter()
+                .filter_map(|text| matcher.fuzzy_match(text, query))
+                .max()
+                .filter(|&score| score > 0)?;
+            Some((cmd.clone(), score + boost(&cmd.id)))
+        })
+        .collect();
+
+    results.sort_by(|a, b| b.1.cmp(&a.1));
+    results
+}
+
+/// Keys bound in `[keybindings]`, by command id
+pub fn bound_keys(config: &KeyBindings) -> HashMap<String, String> {
+    let named = [
+        ("copy_mode", &config.copy_mode),
+        ("paste", &config.paste),
+        ("search_mode", &config.search),
+        ("command_palette", &config.command_palette),
+        ("new_window", &config.new_window),
+        ("close_window", &config.close_window),
+        ("next_tab", &config.next_tab),
+        ("prev_tab", &config.prev_tab),
+    ];
+
+    let mut keys: HashMap<String, String> = named
+        .into_iter()
+        .filter(|(_, key)| !key.is_empty())
+        .map(|(id, key)| (id.to_string(), key.clone()))
+        .collect();
+    keys.extend(config.custom.clone());
+    keys
+}
+
+/// How often a command ran, and when it last did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommandUse {
+    pub count: u32,
+    /// Unix timestamp in seconds
+    pub last_used: i64,
+}
+
+/// Command usage saved across runs, for frecency ranking
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct CommandHistory {
+    pub commands: HashMap<String, CommandUse>,
+}
+
+impl CommandHistory {
+    /// Location under Scarab's data directory
+    pub fn path_in(data_dir: &Path) -> PathBuf {
+        data_dir.join("command_history.json")
+    }
+
+    /// Load the history, treating a missing or unreadable file as empty
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Note that a command ran at `now`
+    pub fn record(&mut self, id: &str, now: i64) {
+        let usage = self.commands.entry(id.to_string()).or_default();
+        usage.count = usage.count.saturating_add(1);
+        usage.last_used = now;
+    }
+
+    /// Use count weighted by how recently the command last ran
+    pub fn frecency(&self, id: &str, now: i64) -> f64 {
+        let Some(usage) = self.commands.get(id) else {
+            return 0.0;
+        };
+        let recency = match (now - usage.last_used).max(0) {
+            age if age < HOUR_SECS => 4.0,
+            age if age < DAY_SECS => 2.0,
+            age if age < WEEK_SECS => 1.0,
+            _ => 0.5,
+        };
+        usage.count as f64 * recency
+    }
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// What the palette is listing
+#[derive(Clone, Default)]
+pub enum PaletteMode {
+    /// Registered commands
+    #[default]
+    Commands,
+    /// Items sent by the daemon in a `ShowModal` message
+    Remote { title: String, items: Vec<Command> },
+    /// Asking for the argument of a command
+    Argument(Command),
+}
+
+/// What to run after the palette is confirmed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteChoice {
+    /// Run a registered command
+    Run {
+        command_id: String,
+        argument: Option<String>,
+    },
+    /// Report a remote item back to the daemon
+    Remote(String),
 }
 
 /// State of command palette
@@ -126,12 +326,124 @@ pub struct CommandPaletteState {
     pub query: String,
     pub selected_index: usize,
     pub filtered_commands: Vec<(Command, i64)>,
+    pub mode: PaletteMode,
+}
+
+impl CommandPaletteState {
+    /// Open the palette listing registered commands
+    pub fn open(&mut self, registry: &CommandRegistry, history: &CommandHistory, now: i64) {
+        self.active = true;
+        self.mode = PaletteMode::Commands;
+        self.query.clear();
+        self.selected_index = 0;
+        self.refresh(registry, history, now);
+    }
+
+    /// Open the palette listing items sent by the daemon
+    pub fn open_remote(&mut self, title: String, items: Vec<Command>) {
+        self.active = true;
+        self.mode = PaletteMode::Remote { title, items };
+        self.query.clear();
+        self.selected_index = 0;
+        self.filtered_commands = rank_commands(self.items(), "", None, 0);
+    }
+
+    /// Open the palette at the prompt of `command`, with `initial` already
+    /// typed
+    pub fn ask(&mut self, command: Command, initial: &str) {
+        self.active = true;
+        self.mode = PaletteMode::Argument(command);
+        self.query = initial.to_string();
+        self.selected_index = 0;
+        self.filtered_commands.clear();
+    }
+
+    pub fn close(&mut self) {
+        self.active = false;
+        self.mode = PaletteMode::Commands;
+        self.query.clear();
+    }
+
+    /// Re-filter the list after the query changed
+    pub fn refresh(&mut self, registry: &CommandRegistry, history: &CommandHistory, now: i64) {
+        self.filtered_commands = match &self.mode {
+            PaletteMode::Commands => registry.ranked_search(&self.query, history, now),
+            PaletteMode::Remote { .. } => rank_commands(self.items(), &self.query, None, now),
+            PaletteMode::Argument(_) => Vec::new(),
+        };
+        self.selected_index = self
+            .selected_index
+            .min(self.filtered_commands.len().saturating_sub(1));
+    }
+
+    fn items(&self) -> &[Command] {
+        match &self.mode {
+            PaletteMode::Remote { items, .. } => items,
+            _ => &[],
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected_index < self.filtered_commands.len().saturating_sub(1) {
+            self.selected_index += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    /// Act on Enter
+    ///
+    /// Picking a command with a prompt switches to asking for its argument
+    /// and returns `None`; the palette closes once there is something to run.
+    pub fn confirm(&mut self) -> Option<PaletteChoice> {
+        match std::mem::take(&mut self.mode) {
+            PaletteMode::Argument(command) => {
+                let argument = self.query.trim().to_string();
+                if argument.is_empty() {
+                    self.mode = PaletteMode::Argument(command);
+                    return None;
+                }
+                self.close();
+                Some(PaletteChoice::Run {
+                    command_id: command.id,
+                    argument: Some(argument),
+                })
+            }
+            mode => {
+                let Some((command, _)) = self.filtered_commands.get(self.selected_index).cloned()
+                else {
+                    self.mode = mode;
+                    return None;
+                };
+                if matches!(mode, PaletteMode::Remote { .. }) {
+                    self.close();
+                    return Some(PaletteChoice::Remote(command.id));
+                }
+                if command.prompt.is_some() {
+                    self.mode = PaletteMode::Argument(command);
+                    self.query.clear();
+                    self.selected_index = 0;
+                    self.filtered_commands.clear();
+                    return None;
+                }
+                self.close();
+                Some(PaletteChoice::Run {
+                    command_id: command.id,
+                    argument: None,
+                })
+            }
+        }
+    }
 }
 
 /// Event fired when command is executed
 #[derive(Event)]
 pub struct CommandExecutedEvent {
     pub command_id: String,
+    /// Text entered at the command's prompt
+    pub argument: Option<String>,
 }
 
 /// Component for palette UI elements
@@ -145,110 +457,153 @@ struct CommandItem {
     index: usize,
 }
 
+/// System to show configured keys next to commands
+fn sync_keybinds_with_config(
+    config: Option<Res<ScarabConfig>>,
+    mut registry: ResMut<CommandRegistry>,
+) {
+    let Some(config) = config.filter(|c| c.is_changed()) else {
+        return;
+    };
+    registry.apply_keybinds(&bound_keys(&config.keybindings));
+}
+
 /// Toggle command palette visibility
 fn toggle_palette_system(
     keyboard: Res<ButtonInput<KeyCode>>,
+    config: Option<Res<ScarabConfig>>,
     mut state: ResMut<CommandPaletteState>,
     registry: Res<CommandRegistry>,
+    history: Res<CommandHistory>,
+    mut hide_events: EventReader<HideModalEvent>,
 ) {
-    // Toggle palette with Ctrl+P
-    if keyboard.just_pressed(KeyCode::ControlLeft) && keyboard.pressed(KeyCode::KeyP) {
-        state.active = !state.active;
-
+    // Toggle palette with the `command_palette` key
+    let binding = match config {
+        Some(config) => config.keybindings.command_palette.clone(),
+        None => KeyBindings::default().command_palette,
+    };
+    if binding_just_pressed(&keyboard, &binding) {
         if state.active {
-            state.query.clear();
-            state.selected_index = 0;
-            state.filtered_commands = registry.fuzzy_search("");
+            state.close();
+        } else {
+            state.open(&registry, &history, now_secs());
         }
     }
 
-    // Close with Escape
-    if state.active && keyboard.just_pressed(KeyCode::Escape) {
-        state.active = false;
+    // Close with Escape, or when the daemon hides its modal
+    let hidden = hide_events.read().count() > 0;
+    if state.active && (keyboard.just_pressed(KeyCode::Escape) || hidden) {
+        state.close();
     }
 }
 
+/// Whether the keys in `binding` (e.g. `"Ctrl+Shift+P"`) were just pressed
+fn binding_just_pressed(keyboard: &ButtonInput<KeyCode>, binding: &str) -> bool {
+    let Some(combo) = parse_key_combo(binding) else {
+        return false;
+    };
+    let mods = build_modifiers(keyboard, false);
+    keyboard
+        .get_just_pressed()
+        .filter_map(|key| bevy_to_api_keycode(*key))
+        .any(|key| KeyCombo::new(key, mods) == combo)
+}
+
 /// Handle input in command palette
 fn handle_palette_input_system(
     keyboard: Res<ButtonInput<KeyCode>>,
+    mut char_events: EventReader<KeyboardInput>,
     mut state: ResMut<CommandPaletteState>,
     registry: Res<CommandRegistry>,
+    history: Res<CommandHistory>,
+    ipc: Res<IpcChannel>,
     mut command_events: EventWriter<CommandExecutedEvent>,
 ) {
     if !state.active {
+        char_events.clear();
         return;
     }
 
-    // Note: Character input handling would need keyboard text input events
-    // For now, we'll handle basic commands with keycodes
+    // Typed text filters the list, or fills in the argument
+    let chorded = keyboard.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    let mut query_changed = false;
+    for event in char_events.read() {
+        if !event.state.is_pressed() || chorded {
+            continue;
+        }
+        if let Key::Character(ref s) = event.logical_key {
+            // Skip control characters
+            if s.chars().any(char::is_control) {
+                continue;
+            }
+            state.query.push_str(s);
+            query_changed = true;
+        }
+    }
 
-    // Handle backspace
     if keyboard.just_pressed(KeyCode::Backspace) {
-        state.query.pop();
-        // If we are in remote mode (empty registry or special flag?), we might need to re-filter remote items.
-        // For now, we assume remote mode uses the filtered_commands directly and local mode uses registry.
-        // But wait, toggle_palette_system resets filtered_commands from registry.
-        // If we received a remote modal, we should NOT query the registry.
-        // We need a flag in State.
-
-        // Simple hack: if active and filtered_commands is not empty but registry search returns different count?
-        // No. We need `mode` in state.
-
-        // For now, let's assume if we have a query we filter from registry.
-        // If we are in remote mode, we probably shouldn't type to search yet (needs implementation).
-        // Let's just re-run search on registry.
-        state.filtered_commands = registry.fuzzy_search(&state.query);
+        query_changed |= state.query.pop().is_some();
+    }
+
+    if query_changed {
         state.selected_index = 0;
+        state.refresh(&registry, &history, now_secs());
     }
 
     // Handle navigation
     if keyboard.just_pressed(KeyCode::ArrowDown) {
-        if state.selected_index < state.filtered_commands.len().saturating_sub(1) {
-            state.selected_index += 1;
-        }
+        state.select_next();
     }
-
     if keyboard.just_pressed(KeyCode::ArrowUp) {
-        state.selected_index = state.selected_index.saturating_sub(1);
+        state.select_previous();
     }
 
     // Handle selection with Enter
     if keyboard.just_pressed(KeyCode::Enter) {
-        if let Some((command, _)) = state.filtered_commands.get(state.selected_index) {
-            command_events.send(CommandExecutedEvent {
-                command_id: command.id.clone(),
-            });
-            state.active = false;
+        match state.confirm() {
+            Some(PaletteChoice::Run {
+                command_id,
+                argument,
+            }) => {
+                command_events.send(CommandExecutedEvent {
+                    command_id,
+                    argument,
+                });
+            }
+            Some(PaletteChoice::Remote(id)) => {
+                ipc.send(ControlMessage::CommandSelected { id });
+            }
+            None => {}
         }
     }
 }
 
+/// Show a modal sent by the daemon as a filterable list
 fn handle_remote_modal_system(
     mut events: EventReader<ShowRemoteModalEvent>,
     mut state: ResMut<CommandPaletteState>,
 ) {
     for event in events.read() {
-        state.active = true;
-        state.query.clear();
-        state.selected_index = 0;
-        state.filtered_commands.clear();
-
-        for item in &event.items {
-            let id_for_closure = item.id.clone();
-            // Create a command that sends CommandSelected
-            let command = Command::new(
-                &item.id,
-                &item.label,
-                item.description.as_deref().unwrap_or(""),
-                "Remote",
-                move |ipc| {
-                    ipc.send(ControlMessage::CommandSelected {
-                        id: id_for_closure.clone(),
-                    });
-                },
-            );
-            state.filtered_commands.push((command, 0));
-        }
+        let items = event
+            .items
+            .iter()
+            .map(|item| {
+                Command::client(
+                    &item.id,
+                    &item.label,
+                    item.description.as_deref().unwrap_or(""),
+                    "Remote",
+                )
+            })
+            .collect();
+        state.open_remote(event.title.clone(), items);
     }
 }
 
@@ -258,6 +613,10 @@ fn render_palette_system(
     state: Res<CommandPaletteState>,
     existing_ui: Query<Entity, With<PaletteUI>>,
 ) {
+    if !state.is_changed() {
+        return;
+    }
+
     // Remove existing UI
     for entity in existing_ui.iter() {
         commands.entity(entity).despawn_recursive();
@@ -267,6 +626,16 @@ fn render_palette_system(
         return;
     }
 
+    let header = match &state.mode {
+        PaletteMode::Commands => format!("> {}", state.query),
+        PaletteMode::Remote { title, .. } => format!("{} > {}", title, state.query),
+        PaletteMode::Argument(command) => format!(
+            "{}: {}",
+            command.prompt.as_deref().unwrap_or(&command.name),
+            state.query
+        ),
+    };
+
     // Create palette container
     commands
         .spawn((
@@ -286,7 +655,7 @@ fn render_palette_system(
         .with_children(|parent| {
             // Search input display
             parent.spawn((
-                Text::new(format!("> {}", state.query)),
+                Text::new(header),
                 TextFont {
                     font_size: 20.0,
                     ..default()
@@ -298,8 +667,20 @@ fn render_palette_system(
                 },
             ));
 
+            if let PaletteMode::Argument(command) = &state.mode {
+                parent.spawn((
+                    Text::new(format!("Enter: {}  Esc: Cancel", command.name)),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.7, 0.7, 0.7, 1.0)),
+                ));
+                return;
+            }
+
             // Command list (show first 10 results)
-            for (index, (command, score)) in state.filtered_commands.iter().take(10).enumerate() {
+            for (index, (command, _)) in state.filtered_commands.iter().take(10).enumerate() {
                 let is_selected = index == state.selected_index;
                 let bg_color = if is_selected {
                     Color::srgba(0.3, 0.3, 0.5, 0.8)
@@ -314,30 +695,42 @@ fn render_palette_system(
                             width: Val::Percent(100.0),
                             padding: UiRect::all(Val::Px(8.0)),
                             margin: UiRect::bottom(Val::Px(2.0)),
+                            flex_direction: FlexDirection::Column,
                             ..default()
                         },
                         BackgroundColor(bg_color),
                     ))
                     .with_children(|item| {
-                        // Command name and keybind
-                        let keybind_text = command
-                            .keybind
-                            .as_ref()
-                            .map(|k| format!(" [{}]", k))
-                            .unwrap_or_default();
-
-                        item.spawn((
-                            Text::new(format!("{}{}", command.name, keybind_text)),
-                            TextFont {
-                                font_size: 16.0,
-                                ..default()
-                            },
-                            TextColor(Color::WHITE),
-                        ));
+                        // Command name, with its bound key on the right
+                        item.spawn(Node {
+                            width: Val::Percent(100.0),
+                            justify_content: JustifyContent::SpaceBetween,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new(command.name.clone()),
+                                TextFont {
+                                    font_size: 16.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                            if let Some(keybind) = &command.keybind {
+                                row.spawn((
+                                    Text::new(keybind.clone()),
+                                    TextFont {
+                                        font_size: 14.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::srgba(0.6, 0.7, 0.9, 1.0)),
+                                ));
+                            }
+                        });
 
                         // Command description
                         item.spawn((
-                            Text::new(format!("{} (score: {})", command.description, score)),
+                            Text::new(format!("{} - {}", command.description, command.category)),
                             TextFont {
                                 font_size: 12.0,
                                 ..default()
@@ -357,21 +750,69 @@ fn render_palette_system(
 fn execute_command_system(
     mut events: EventReader<CommandExecutedEvent>,
     registry: Res<CommandRegistry>,
+    mut history: ResMut<CommandHistory>,
     ipc: Res<IpcChannel>,
+    tabs: Option<Res<TabBarState>>,
+    mut commands_selected: EventWriter<CommandSelected>,
+    paths: Res<DaemonPaths>,
 ) {
     for event in events.read() {
-        if let Some(command) = registry.get(&event.command_id) {
-            info!("Executing command: {}", command.name);
-            (command.action)(&ipc);
+        let Some(command) = registry.get(&event.command_id) else {
+            continue;
+        };
+        info!("Executing command: {}", command.name);
+        match &event.argument {
+            Some(argument) => run_with_argument(&command.id, argument, &ipc, tabs.as_deref()),
+            None => (command.action)(&ipc),
+        }
+        // Zoom, screenshots and key table actions are handled by their own
+        // systems
+        commands_selected.send(CommandSelected {
+            command_id: command.id.clone(),
+        });
+
+        history.record(&command.id, now_secs());
+        if let Err(e) = history.save(&CommandHistory::path_in(&paths.0.data_dir)) {
+            warn!("Failed to save command history: {}", e);
         }
     }
 }
 
+/// Run a built-in command that took an argument
+fn run_with_argument(id: &str, argument: &str, ipc: &IpcChannel, tabs: Option<&TabBarState>) {
+    match id {
+        "rename_tab" => {
+            let Some(tab) = tabs.and_then(|t| t.tabs.iter().find(|t| t.is_active)) else {
+                warn!("No active tab to rename");
+                return;
+            };
+            ipc.send(ControlMessage::TabRename {
+                tab_id: tab.id,
+                new_title: argument.to_string(),
+            });
+        }
+        "new_named_tab" => ipc.send(ControlMessage::TabCreate {
+            title: Some(argument.to_string()),
+            domain: None,
+        }),
+        "run_command" => ipc.send(ControlMessage::Input {
+            data: format!("{}\r", argument).into_bytes(),
+        }),
+        // Commands registered elsewhere read the argument from the event
+        _ => {}
+    }
+}
+
 /// Initialize default commands at startup
 fn register_default_commands_system(mut registry: ResMut<CommandRegistry>) {
     register_default_commands(&mut registry);
 }
 
+/// Load saved command usage at startup
+fn load_command_history(mut history: ResMut<CommandHistory>, paths: Res<DaemonPaths>) {
+    *history = CommandHistory::load(&CommandHistory::path_in(&paths.0.data_dir));
+}
+
 /// Register default commands with IPC actions
 pub fn register_default_commands(registry: &mut CommandRegistry) {
     // Clear terminal (Ctrl+L sends clear command)
@@ -482,6 +923,195 @@ pub fn register_default_commands(registry: &mut CommandRegistry) {
         )
         .with_keybind("F1"),
     );
+
+    // Handled by client systems listening for the command id
+    for (id, name, description, category) in [
+        (
+            "copy_mode",
+            "Enter Copy Mode",
+            "Select and copy text with the keyboard",
+            "Edit",
+        ),
+        ("search_mode", "Search", "Search terminal output", "Search"),
+        ("new_tab", "New Tab", "Open a new tab", "Tabs"),
+        ("next_tab", "Next Tab", "Switch to the next tab", "Tabs"),
+        (
+            "prev_tab",
+            "Previous Tab",
+            "Switch to the previous tab",
+            "Tabs",
+        ),
+        (
+            "split_horizontal",
+            "Split Horizontal",
+            "Split the pane side by side",
+            "Panes",
+        ),
+        (
+            "split_vertical",
+            "Split Vertical",
+            "Split the pane top and bottom",
+            "Panes",
+        ),
+        (
+            "close_pane",
+            "Close Pane",
+            "Close the focused pane",
+            "Panes",
+        ),
+        (
+            "screenshot",
+            "Save Screenshot",
+            "Save the screen or selection as a PNG",
+            "View",
+        ),
+        (
+            "copy_as_image",
+            "Copy as Image",
+            "Copy the screen or selection as an image",
+            "View",
+        ),
+    ] {
+        registry.register(Command::client(id, name, description, category));
+    }
+    for (id, name, description, keybind) in [
+        ("zoom_in", "Zoom In", "Increase font size", "Ctrl+="),
+        ("zoom_out", "Zoom Out", "Decrease font size", "Ctrl+-"),
+        (
+            "zoom_reset",
+            "Reset Zoom",
+            "Restore the configured font size",
+            "Ctrl+0",
+        ),
+    ] {
+        registry.register(Command::client(id, name, description, "View").with_keybind(keybind));
+    }
+
+    // Commands that ask for an argument
+    registry.register(
+        Command::client(
+            "rename_tab",
+            "Rename Tab",
+            "Change the title of the current tab",
+            "Tabs",
+        )
+        .with_prompt("New tab name"),
+    );
+    registry.register(
+        Command::client(
+            "new_named_tab",
+            "New Named Tab",
+            "Open a new tab with a title",
+            "Tabs",
+        )
+        .with_prompt("Tab name"),
+    );
+    registry.register(
+        Command::client(
+            "run_command",
+            "Run Command",
+            "Type a command into the shell and run it",
+            "Terminal",
+        )
+        .with_prompt("Command"),
+    );
+
+    registry.register(Command::client(
+        crate::ui::theme_gallery::THEME_BROWSE_COMMAND,
+        "Theme: Browse",
+        "Preview installed themes and pick one",
+        "Themes",
+    ));
+    registry.register(Command::client(
+        crate::ui::idle_lock::LOCK_COMMAND,
+        "Lock Screen",
+        "Hide the terminal until a key or the lock password is entered",
+        "Session",
+    ));
+    registry.register(Command::new(
+        "session_share_read_only",
+        "Session: Share Read-Only",
+        "Clients that attach from now on can watch this session but not type",
+        "Session",
+        |ipc| {
+            ipc.send(ControlMessage::SessionSetAccess {
+                read_only_by_default: true,
+            });
+        },
+    ));
+    registry.register(Command::new(
+        "session_share_writable",
+        "Session: Share Writable",
+        "Let clients that attach from now on type into this session",
+        "Session",
+        |ipc| {
+            ipc.send(ControlMessage::SessionSetAccess {
+                read_only_by_default: false,
+            });
+        },
+    ));
+    for (id, name, description) in [
+        (
+            crate::ui::block_folding::FOLD_TOGGLE_COMMAND,
+            "Fold: Toggle Last Command",
+            "Collapse the last command's output to one line, or expand it",
+        ),
+        (
+            crate::ui::block_folding::FOLD_PASSED_COMMAND,
+            "Fold: Collapse Passed Commands",
+            "Collapse commands that succeeded and keep failures expanded",
+        ),
+        (
+            crate::ui::block_folding::UNFOLD_ALL_COMMAND,
+            "Fold: Expand All Commands",
+            "Show the output of every command again",
+        ),
+    ] {
+        registry.register(Command::client(id, name, description, "Terminal"));
+    }
+    registry.register(
+        Command::client(
+            crate::ui::block_rerun::RERUN_COMMAND,
+            "Re-run Last Command",
+            "Run the last command again in this pane",
+            "Terminal",
+        )
+        .with_keybind("Ctrl+Shift+E"),
+    );
+    registry.register(Command::client(
+        crate::ui::block_rerun::EDIT_RERUN_COMMAND,
+        "Edit and Re-run Last Command",
+        "Change the last command before running it again",
+        "Terminal",
+    ));
+    registry.register(Command::client(
+        crate::ui::search_overlay::SEARCH_LAST_COMMAND,
+        "Search Last Command Output",
+        "Find text in the last command's output only",
+        "Terminal",
+    ));
+    for step in crate::accessibility::ReviewMove::ALL {
+        registry.register(Command::client(
+            step.action(),
+            step.name(),
+            "Read the screen without moving the cursor (screen reader)",
+            "Accessibility",
+        ));
+    }
+    for (id, name, _) in crate::accessibility::EXPORT_COMMANDS {
+        registry.register(Command::client(
+            id,
+            name,
+            "Save the screen, or the selected lines, to the documents folder",
+            "Accessibility",
+        ));
+    }
+    registry.register(Command::client(
+        crate::scripting::repl::REPL_COMMAND,
+        "Fusabi REPL",
+        "Evaluate Fusabi expressions against the live config",
+        "Scripting",
+    ));
 }
 
 #[cfg(test)]
@@ -538,4 +1168,128 @@ mod tests {
         // Should complete in <50ms
         assert!(duration.as_millis() < 50);
     }
+
+    #[test]
+    fn test_frecency_ranking() {
+        let mut registry = CommandRegistry::default();
+        registry.register(Command::client("zoom_in", "Zoom In", "Bigger", "View"));
+        registry.register(Command::client("zoom_out", "Zoom Out", "Smaller", "View"));
+
+        let mut history = CommandHistory::default();
+        let now = 1_000_000;
+        history.record("zoom_out", now - 10);
+        history.record("zoom_out", now - 5);
+
+        // Frecency breaks the tie between equally good matches
+        let results = registry.ranked_search("zoom", &history, now);
+        assert_eq!(results[0].0.id, "zoom_out");
+        let results = registry.ranked_search("", &history, now);
+        assert_eq!(results[0].0.id, "zoom_out");
+
+        // Old use counts for less
+        assert_eq!(history.frecency("zoom_out", now), 8.0);
+        assert_eq!(history.frecency("zoom_out", now + WEEK_SECS), 1.0);
+        assert_eq!(history.frecency("zoom_in", now), 0.0);
+    }
+
+    #[test]
+    fn test_history_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scarab").join("command_history.json");
+        assert!(CommandHistory::load(&path).commands.is_empty());
+
+        let mut history = CommandHistory::default();
+        history.record("clear", 42);
+        history.save(&path).unwrap();
+
+        let loaded = CommandHistory::load(&path);
+        assert_eq!(
+            loaded.commands["clear"],
+            CommandUse {
+                count: 1,
+                last_used: 42
+            }
+        );
+    }
+
+    #[test]
+    fn test_bound_keys() {
+        let mut config = KeyBindings::default();
+        config
+            .custom
+            .insert("zoom_in".into(), "Ctrl+Shift+Equal".into());
+        config.paste = String::new();
+
+        let mut registry = CommandRegistry::default();
+        register_default_commands(&mut registry);
+        registry.apply_keybinds(&bound_keys(&config));
+
+        let keybind = |id: &str| registry.get(id).unwrap().keybind.clone();
+        assert_eq!(keybind("zoom_in").as_deref(), Some("Ctrl+Shift+Equal"));
+        assert_eq!(keybind("copy_mode").as_deref(), Some("Ctrl+Shift+C"));
+        // An unbound key leaves the built-in hint alone
+        assert_eq!(keybind("paste").as_deref(), Some("Ctrl+Shift+V"));
+    }
+
+    #[test]
+    fn test_binding_just_pressed() {
+        let mut keyboard = ButtonInput::<KeyCode>::default();
+        keyboard.press(KeyCode::ControlLeft);
+        keyboard.press(KeyCode::AltLeft);
+        keyboard.press(KeyCode::KeyK);
+
+        assert!(binding_just_pressed(&keyboard, "Ctrl+Alt+K"));
+        assert!(!binding_just_pressed(&keyboard, "Ctrl+Shift+P"));
+        assert!(!binding_just_pressed(&keyboard, ""));
+    }
+
+    #[test]
+    fn test_argument_prompt() {
+        let mut registry = CommandRegistry::default();
+        register_default_commands(&mut registry);
+        let history = CommandHistory::default();
+        let mut state = CommandPaletteState::default();
+        state.open(&registry, &history, 0);
+
+        state.query = "rename tab".into();
+        state.refresh(&registry, &history, 0);
+        assert_eq!(state.filtered_commands[0].0.id, "rename_tab");
+
+        // Picking it asks for the new name instead of running
+        assert_eq!(state.confirm(), None);
+        assert!(state.active);
+        assert!(matches!(state.mode, PaletteMode::Argument(_)));
+        assert!(state.query.is_empty());
+
+        // An empty argument is not accepted
+        assert_eq!(state.confirm(), None);
+
+        state.query = "build".into();
+        assert_eq!(
+            state.confirm(),
+            Some(PaletteChoice::Run {
+                command_id: "rename_tab".into(),
+                argument: Some("build".into())
+            })
+        );
+        assert!(!state.active);
+    }
+
+    #[test]
+    fn test_remote_items() {
+        let mut state = CommandPaletteState::default();
+        state.open_remote(
+            "Pick".into(),
+            vec![
+                Command::client("a", "Alpha", "", "Remote"),
+                Command::client("b", "Beta", "", "Remote"),
+            ],
+        );
+        assert_eq!(state.filtered_commands.len(), 2);
+
+        state.query = "bet".into();
+        state.refresh(&CommandRegistry::default(), &CommandHistory::default(), 0);
+        assert_eq!(state.filtered_commands.len(), 1);
+        assert_eq!(state.confirm(), Some(PaletteChoice::Remote("b".into())));
+    }
 }
@@ -10,6 +10,7 @@
 
 use bevy::input::keyboard::KeyCode;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use prost::Message as ProstMessage;
 use scarab_nav_protocol::{ElementType, InteractiveElement, UpdateLayout};
 use scarab_protocol::{ControlMessage, DaemonMessage, PluginInspectorInfo};
@@ -372,7 +373,7 @@ fn update_dock_items(
 /// System to compute and cache dock item bounds after layout
 fn compute_dock_item_bounds(
     mut query: Query<(&mut DockItemBounds, &GlobalTransform), With<DockItem>>,
-    windows: Query<&Window>,
+    windows: Query<&Window, With<PrimaryWindow>>,
 ) {
     if let Ok(window) = windows.get_single() {
         let window_height = window.height();
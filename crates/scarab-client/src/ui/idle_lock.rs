@@ -0,0 +1,399 @@
+//! Lock screen for a client left alone
+//!
+//! With `sessions.lock_after_idle` set, the terminal is hidden behind a
+//! lock screen once nobody has typed, clicked, scrolled or moved the mouse
+//! in the window for that many seconds, and keys stop reaching the shell.
+//! Any key unlocks it, unless `sessions.lock_password_hash` is set; then
+//! it takes the password that hash was made from, with a wait that doubles
+//! after each wrong one. "Lock Screen" in the command palette locks right
+//! away.
+//!
+//! The lock belongs to this window only: the daemon knows nothing of it, so
+//! the TUI or another client attached to the same session stays usable.
+//! How long each session has gone without input is tracked by the daemon
+//! and listed as `SessionInfo::idle_secs`.
+
+use std::time::{Duration, Instant};
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::mouse::{MouseButtonInput, MouseWheel};
+use bevy::prelude::*;
+use scarab_config::{lock_password_matches, ScarabConfig};
+
+use crate::ratatui_bridge::CommandSelected;
+use crate::InputSystemSet;
+
+/// Palette command that locks the screen
+pub const LOCK_COMMAND: &str = "session.lock";
+
+/// Character shown in place of each typed character of the password
+const MASK_CHAR: char = '•';
+
+/// Wait after the first wrong password, doubled after each further one
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between two tries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Whether the screen is locked, and the password typed so far
+#[derive(Resource, Debug)]
+pub struct IdleLockState {
+    last_input: Instant,
+    pub locked: bool,
+    pub entry: String,
+    /// The last password entered was wrong
+    pub failed: bool,
+    /// Wrong passwords entered since the last unlock
+    failures: u32,
+    /// No password is tried before this
+    retry_at: Option<Instant>,
+}
+
+impl Default for IdleLockState {
+    fn default() -> Self {
+        Self {
+            last_input: Instant::now(),
+            locked: false,
+            entry: String::new(),
+            failed: false,
+            failures: 0,
+            retry_at: None,
+        }
+    }
+}
+
+impl IdleLockState {
+    /// Whether the lock screen owns the keyboard
+    pub fn captures_keys(&self) -> bool {
+        self.locked
+    }
+
+    /// Note input from the user at `now`
+    pub fn note_input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    /// Whether the screen should lock at `now` after `lock_after` without
+    /// input; zero never locks
+    pub fn should_lock(&self, now: Instant, lock_after: Duration) -> bool {
+        !self.locked
+            && !lock_after.is_zero()
+            && now.saturating_duration_since(self.last_input) >= lock_after
+    }
+
+    pub fn lock(&mut self) {
+        self.locked = true;
+        self.entry.clear();
+        self.failed = false;
+    }
+
+    /// How long after the last wrong password the next one may be tried
+    pub fn retry_delay(&self) -> Duration {
+        match self.failures {
+            0 => Duration::ZERO,
+            n => RETRY_DELAY
+                .saturating_mul(1 << (n - 1).min(16))
+                .min(MAX_RETRY_DELAY),
+        }
+    }
+
+    /// Unlock with the password typed so far, if it is the one
+    /// `password_hash` was made from; without one any attempt unlocks
+    ///
+    /// Until the wait after a wrong password is over nothing is tried and
+    /// the password typed so far is kept.
+    pub fn unlock(&mut self, password_hash: Option<&str>, now: Instant) -> bool {
+        if let Some(password_hash) = password_hash {
+            if self.retry_at.is_some_and(|retry_at| now < retry_at) {
+                return false;
+            }
+            let entry = std::mem::take(&mut self.entry);
+            if !lock_password_matches(&entry, password_hash) {
+                self.failed = true;
+                self.failures = self.failures.saturating_add(1);
+                self.retry_at = Some(now + self.retry_delay());
+                return false;
+            }
+        }
+        self.entry.clear();
+        self.locked = false;
+        self.failed = false;
+        self.failures = 0;
+        self.retry_at = None;
+        self.last_input = now;
+        true
+    }
+}
+
+/// Marker for the lock screen
+#[derive(Component)]
+struct LockScreenUI;
+
+/// System for unlocking with the keyboard
+fn handle_lock_keys(
+    mut key_events: EventReader<KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Option<Res<ScarabConfig>>,
+    mut state: ResMut<IdleLockState>,
+) {
+    if !state.captures_keys() {
+        key_events.clear();
+        return;
+    }
+
+    let password = config.and_then(|c| c.sessions.lock_password_hash.clone());
+    let chorded = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+
+    for event in key_events.read() {
+        if !event.state.is_pressed() || !state.captures_keys() {
+            continue;
+        }
+        let Some(password) = password.as_deref() else {
+            state.unlock(None, Instant::now());
+            continue;
+        };
+
+        match (&event.key_code, &event.logical_key) {
+            (KeyCode::Enter | KeyCode::NumpadEnter, _) => {
+                if !state.unlock(Some(password), Instant::now()) {
+                    warn!("Wrong password entered at the lock screen");
+                }
+            }
+            (KeyCode::Escape, _) => state.entry.clear(),
+            (KeyCode::Backspace, _) => {
+                state.entry.pop();
+            }
+            (_, Key::Character(s)) if !chorded && !s.chars().any(char::is_control) => {
+                state.entry.push_str(s);
+            }
+            (KeyCode::Space, _) if !chorded => state.entry.push(' '),
+            _ => {}
+        }
+    }
+}
+
+/// System to lock when "Lock Screen" is picked in the palette
+fn lock_on_command(
+    mut commands_selected: EventReader<CommandSelected>,
+    mut state: ResMut<IdleLockState>,
+) {
+    if commands_selected
+        .read()
+        .any(|event| event.command_id == LOCK_COMMAND)
+    {
+        state.lock();
+    }
+}
+
+/// System to lock after `sessions.lock_after_idle` seconds without input
+fn track_idle_time(
+    mut keys: EventReader<KeyboardInput>,
+    mut buttons: EventReader<MouseButtonInput>,
+    mut wheel: EventReader<MouseWheel>,
+    mut cursor: EventReader<CursorMoved>,
+    config: Option<Res<ScarabConfig>>,
+    mut state: ResMut<IdleLockState>,
+) {
+    let events =
+        keys.read().count() + buttons.read().count() + wheel.read().count() + cursor.read().count();
+    if state.locked {
+        return;
+    }
+
+    let now = Instant::now();
+    if events > 0 {
+        // Only locking redraws the lock screen
+        state.bypass_change_detection().note_input(now);
+        return;
+    }
+    let lock_after = config.map_or(0, |c| c.sessions.lock_after_idle);
+    if state.should_lock(now, Duration::from_secs(lock_after as u64)) {
+        info!("Locking after {}s without input", lock_after);
+        state.lock();
+    }
+}
+
+/// System to draw the lock screen over the terminal
+fn render_lock_screen(
+    mut commands: Commands,
+    state: Res<IdleLockState>,
+    config: Option<Res<ScarabConfig>>,
+    existing_ui: Query<Entity, With<LockScreenUI>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in existing_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !state.locked {
+        return;
+    }
+
+    let has_password = config.map_or(false, |c| c.sessions.lock_password_hash.is_some());
+    let (prompt, hint) = if has_password {
+        let masked: String = std::iter::repeat(MASK_CHAR)
+            .take(state.entry.chars().count())
+            .collect();
+        (
+            format!("Password: {}_", masked),
+            "Enter: Unlock  Esc: Clear",
+        )
+    } else {
+        ("Press any key to unlock".to_string(), "")
+    };
+
+    commands
+        .spawn((
+            LockScreenUI,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            // Opaque enough that nothing of the terminal can be read
+            BackgroundColor(Color::srgba(0.02, 0.02, 0.03, 0.98)),
+            ZIndex(5000), // Above every modal (ZIndex 2000)
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Locked"),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            if state.failed {
+                parent.spawn((
+                    Text::new(format!(
+                        "Wrong password; wait {}s before trying again",
+                        state.retry_delay().as_secs()
+                    )),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.9, 0.4, 0.4, 1.0)),
+                ));
+            }
+            parent.spawn((
+                Text::new(prompt),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.8, 0.8, 0.8, 1.0)),
+            ));
+            if !hint.is_empty() {
+                parent.spawn((
+                    Text::new(hint),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.5, 0.5, 0.5, 1.0)),
+                ));
+            }
+        });
+}
+
+/// Plugin for the idle lock screen
+pub struct IdleLockPlugin;
+
+impl Plugin for IdleLockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IdleLockState>()
+            .add_event::<CommandSelected>()
+            .add_systems(
+                Update,
+                (
+                    // Keys first, so the Enter that picked "Lock Screen"
+                    // doesn't also unlock it
+                    handle_lock_keys,
+                    lock_on_command,
+                    track_idle_time,
+                    render_lock_screen,
+                )
+                    .chain()
+                    // After the terminal input systems, so the key that
+                    // unlocks the screen never reaches the shell
+                    .after(InputSystemSet::Daemon),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use scarab_config::hash_lock_password;
+
+    #[test]
+    fn test_idle_lock() {
+        let start = Instant::now();
+        let mut state = IdleLockState::default();
+        state.note_input(start);
+        let lock_after = Duration::from_secs(300);
+
+        assert!(!state.should_lock(start + Duration::from_secs(299), lock_after));
+        assert!(state.should_lock(start + Duration::from_secs(300), lock_after));
+        // Zero turns the lock off
+        assert!(!state.should_lock(start + Duration::from_secs(3600), Duration::ZERO));
+
+        state.lock();
+        assert!(state.captures_keys());
+        assert!(state.unlock(None, start + Duration::from_secs(400)));
+        assert!(!state.captures_keys());
+        // Unlocking counts as input
+        assert!(!state.should_lock(start + Duration::from_secs(600), lock_after));
+    }
+
+    #[test]
+    fn test_password_unlock() {
+        let hash = hash_lock_password("hunter2").unwrap();
+        let start = Instant::now();
+        let mut state = IdleLockState::default();
+        state.lock();
+
+        state.entry.push_str("hunter1");
+        assert!(!state.unlock(Some(&hash), start));
+        assert!(state.locked && state.failed);
+        assert!(state.entry.is_empty());
+        assert_eq!(state.retry_delay(), Duration::from_secs(1));
+
+        // Nothing is tried before the wait is over, not even the right one
+        state.entry.push_str("hunter2");
+        assert!(!state.unlock(Some(&hash), start + Duration::from_millis(500)));
+        assert_eq!(state.entry, "hunter2");
+        assert!(state.unlock(Some(&hash), start + Duration::from_secs(1)));
+        assert!(!state.locked && !state.failed);
+        assert_eq!(state.retry_delay(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_password_backoff() {
+        let hash = hash_lock_password("hunter2").unwrap();
+        let mut now = Instant::now();
+        let mut state = IdleLockState::default();
+        state.lock();
+
+        for expected in [1, 2, 4, 8, 16, 32, 60, 60] {
+            assert!(!state.unlock(Some(&hash), now));
+            assert_eq!(state.retry_delay(), Duration::from_secs(expected));
+            now += state.retry_delay();
+        }
+    }
+}
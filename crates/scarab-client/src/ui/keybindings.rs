@@ -1,6 +1,7 @@
 // Configurable key bindings system
 // Allows users to customize keyboard shortcuts
 
+use crate::accessibility::ReviewMove;
 use crate::InputSystemSet;
 use bevy::input::keyboard::KeyCode;
 use bevy::prelude::*;
@@ -198,6 +199,10 @@ impl KeyBindingConfig {
         );
 
         // Window management
+        self.bind(
+            KeyBinding::new(KeyCode::KeyN).with_ctrl().with_shift(),
+            "window.new",
+        );
         self.bind(
             KeyBinding::new(KeyCode::Backslash).with_ctrl(),
             "window.split_vertical",
@@ -220,6 +225,25 @@ impl KeyBindingConfig {
         // Command palette
         self.bind(KeyBinding::new(KeyCode::KeyP).with_ctrl(), "palette.open");
 
+        // Command blocks
+        self.bind(
+            KeyBinding::new(KeyCode::KeyE).with_ctrl().with_shift(),
+            crate::ui::block_rerun::RERUN_COMMAND,
+        );
+
+        // Screen review: lines, words and characters
+        let review = [
+            (KeyCode::ArrowUp, ReviewMove::PreviousLine),
+            (KeyCode::ArrowDown, ReviewMove::NextLine),
+            (KeyCode::ArrowLeft, ReviewMove::PreviousWord),
+            (KeyCode::ArrowRight, ReviewMove::NextWord),
+            (KeyCode::Comma, ReviewMove::PreviousChar),
+            (KeyCode::Period, ReviewMove::NextChar),
+        ];
+        for (key, step) in review {
+            self.bind(KeyBinding::new(key).with_alt().with_shift(), step.action());
+        }
+
         // Link hints - NOTE: Primary trigger is Esc+Esc (double-tap Escape)
         // Ctrl+K kept as alternative for users who prefer single keypress
         self.bind(
@@ -320,6 +344,12 @@ fn parse_keycode(s: &str) -> Option<KeyCode> {
         "Tab" => Some(KeyCode::Tab),
         "Minus" => Some(KeyCode::Minus),
         "Backslash" => Some(KeyCode::Backslash),
+        "Comma" => Some(KeyCode::Comma),
+        "Period" => Some(KeyCode::Period),
+        "ArrowUp" => Some(KeyCode::ArrowUp),
+        "ArrowDown" => Some(KeyCode::ArrowDown),
+        "ArrowLeft" => Some(KeyCode::ArrowLeft),
+        "ArrowRight" => Some(KeyCode::ArrowRight),
         _ => None,
     }
 }
@@ -0,0 +1,355 @@
+//! Hover underlining and preview for links
+//!
+//! When the mouse rests on a hyperlink set with OSC 8 (sent by the daemon as
+//! `HyperlinksUpdate`) or on a URL matched by the focusable scanner's regex,
+//! the link is underlined and a tooltip next to the pointer shows its full
+//! target. Ctrl+Click opens the hovered link; Ctrl+Click anywhere else does
+//! nothing.
+//!
+//! Hit-testing uses `TerminalMetrics` and the grid's top inset. Hover is off
+//! while the scrollback view is scrolled, since the daemon's links and the
+//! shared grid describe the live screen.
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::window::PrimaryWindow;
+use regex::Regex;
+use scarab_protocol::{DaemonMessage, HyperlinkInfo, TerminalMetrics, TerminalStateReader};
+
+use crate::integration::{SharedMemoryReader, TerminalGridEntity};
+use crate::ipc::RemoteMessageEvent;
+use crate::navigation::focusable::FocusableDetector;
+use crate::navigation::{NavAction, NavActionEvent};
+use crate::rendering::layers::LAYER_TEXT_DECORATIONS;
+use crate::terminal::scrollback::ScrollbackState;
+use crate::ui::TerminalInsets;
+
+/// Underline color (slime green accent)
+const UNDERLINE_COLOR: Color = Color::srgb(0.66, 0.87, 0.35);
+
+/// Offset of the tooltip from the pointer
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(12.0, 18.0);
+
+/// Longest target shown in the tooltip before it is shortened
+const TOOLTIP_MAX_CHARS: usize = 96;
+
+/// OSC 8 hyperlinks on the live screen, as last sent by the daemon
+#[derive(Resource, Default, Debug)]
+pub struct ScreenHyperlinks {
+    pub links: Vec<HyperlinkInfo>,
+}
+
+/// The link under the mouse, if any
+#[derive(Resource, Default, Debug)]
+pub struct HoveredLink {
+    pub link: Option<HyperlinkInfo>,
+}
+
+/// Marker for the hover underline sprite
+#[derive(Component)]
+struct LinkUnderline;
+
+/// Marker for the hover tooltip
+#[derive(Component)]
+struct LinkTooltip;
+
+/// Grid cell under a window position, if the position lies on the grid
+pub fn hover_cell(
+    cursor: Vec2,
+    metrics: &TerminalMetrics,
+    insets: TerminalInsets,
+) -> Option<(u16, u16)> {
+    let y = cursor.y - insets.top;
+    let (width, height) = metrics.screen_size();
+    if cursor.x < 0.0 || y < 0.0 || cursor.x >= width || y >= height {
+        return None;
+    }
+    Some(metrics.screen_to_grid(cursor.x, y))
+}
+
+/// URL matched in a row's text that covers `col`
+///
+/// Each character of `line` is one grid column.
+pub fn url_at(regex: &Regex, line: &str, row: u16, col: u16) -> Option<HyperlinkInfo> {
+    regex.find_iter(line).find_map(|m| {
+        let start_col = line[..m.start()].chars().count() as u16;
+        let end_col = start_col + m.as_str().chars().count().saturating_sub(1) as u16;
+        (start_col..=end_col).contains(&col).then(|| HyperlinkInfo {
+            row,
+            start_col,
+            end_col,
+            uri: m.as_str().to_string(),
+        })
+    })
+}
+
+/// Shorten a target for display, keeping its start
+pub fn tooltip_text(uri: &str) -> String {
+    if uri.chars().count() <= TOOLTIP_MAX_CHARS {
+        uri.to_string()
+    } else {
+        let head: String = uri.chars().take(TOOLTIP_MAX_CHARS - 1).collect();
+        format!("{}…", head)
+    }
+}
+
+/// Text of one grid row, one character per column
+fn row_text(state: &impl TerminalStateReader, row: u16, cols: u16) -> String {
+    (0..cols as usize)
+        .map(|col| match state.cell(row as usize, col) {
+            Some(cell) if cell.char_codepoint != 0 => {
+                char::from_u32(cell.char_codepoint).unwrap_or('?')
+            }
+            _ => ' ',
+        })
+        .collect()
+}
+
+/// System to track OSC 8 links sent by the daemon
+fn receive_hyperlinks(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut hyperlinks: ResMut<ScreenHyperlinks>,
+) {
+    for event in events.read() {
+        if let DaemonMessage::HyperlinksUpdate { links } = &event.0 {
+            hyperlinks.links = links.clone();
+        }
+    }
+}
+
+/// System to find the link under the mouse
+///
+/// OSC 8 links take precedence over URLs matched in the text.
+fn update_hovered_link(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    metrics: Option<Res<TerminalMetrics>>,
+    insets: Option<Res<TerminalInsets>>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    detector: Option<Res<FocusableDetector>>,
+    scrollback: Option<Res<ScrollbackState>>,
+    hyperlinks: Res<ScreenHyperlinks>,
+    mut hovered: ResMut<HoveredLink>,
+) {
+    let scrolled = scrollback.is_some_and(|s| s.is_scrolled);
+    let cell = match (windows.get_single(), metrics) {
+        (Ok(window), Some(metrics)) if !scrolled => window.cursor_position().and_then(|pos| {
+            hover_cell(
+                pos,
+                &metrics,
+                insets.as_deref().copied().unwrap_or_default(),
+            )
+            .map(|cell| (cell, metrics.columns))
+        }),
+        _ => None,
+    };
+
+    let link = cell.and_then(|((col, row), cols)| {
+        if let Some(link) = hyperlinks.links.iter().find(|l| l.contains(col, row)) {
+            return Some(link.clone());
+        }
+        let (detector, state_reader) = (detector.as_ref()?, state_reader.as_ref()?);
+        let line = row_text(&state_reader.get_safe_state(), row, cols);
+        url_at(&detector.url_regex, &line, row, col)
+    });
+
+    if hovered.link != link {
+        hovered.link = link;
+    }
+}
+
+/// System to underline the hovered link
+fn render_link_underline(
+    mut commands: Commands,
+    hovered: Res<HoveredLink>,
+    metrics: Option<Res<TerminalMetrics>>,
+    grids: Query<Entity, With<TerminalGridEntity>>,
+    underlines: Query<Entity, With<LinkUnderline>>,
+) {
+    let Some(metrics) = metrics else {
+        return;
+    };
+    if !hovered.is_changed() && !metrics.is_changed() {
+        return;
+    }
+
+    for entity in underlines.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(link) = &hovered.link else {
+        return;
+    };
+
+    let thickness = (metrics.cell_height / 16.0).max(1.0);
+    let width = (link.end_col - link.start_col + 1) as f32 * metrics.cell_width;
+    let (x, y) = metrics.grid_to_screen(link.start_col, link.row + 1);
+    for grid in grids.iter() {
+        commands.entity(grid).with_children(|parent| {
+            parent.spawn((
+                LinkUnderline,
+                Sprite {
+                    color: UNDERLINE_COLOR,
+                    custom_size: Some(Vec2::new(width, thickness)),
+                    anchor: Anchor::TopLeft,
+                    ..default()
+                },
+                // Sits on the bottom edge of the row, over any text decoration
+                Transform::from_xyz(x, -y + thickness, LAYER_TEXT_DECORATIONS + 0.02),
+            ));
+        });
+    }
+}
+
+/// System to show the hovered link's target next to the pointer
+fn update_link_tooltip(
+    mut commands: Commands,
+    hovered: Res<HoveredLink>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut tooltips: Query<(Entity, &mut Node, &mut Text), With<LinkTooltip>>,
+) {
+    let cursor = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    let (Some(link), Some(cursor)) = (&hovered.link, cursor) else {
+        for (entity, _, _) in tooltips.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+
+    let position = cursor + TOOLTIP_OFFSET;
+    if let Ok((_, mut node, mut text)) = tooltips.get_single_mut() {
+        if node.left != Val::Px(position.x) || node.top != Val::Px(position.y) {
+            node.left = Val::Px(position.x);
+            node.top = Val::Px(position.y);
+        }
+        if hovered.is_changed() {
+            **text = tooltip_text(&link.uri);
+        }
+        return;
+    }
+
+    commands.spawn((
+        LinkTooltip,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(position.x),
+            top: Val::Px(position.y),
+            padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.05, 0.07, 0.03, 0.95)),
+        BorderColor(UNDERLINE_COLOR.with_alpha(0.6)),
+        BorderRadius::all(Val::Px(3.0)),
+        Text::new(tooltip_text(&link.uri)),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.85, 0.92, 0.78)),
+        ZIndex(2000), // Above status bar (ZIndex 1000)
+    ));
+}
+
+/// System to open the hovered link on Ctrl+Click
+fn handle_link_click(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    hovered: Res<HoveredLink>,
+    mut nav_actions: EventWriter<NavActionEvent>,
+) {
+    if !mouse_buttons.just_pressed(MouseButton::Left)
+        || !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        return;
+    }
+    if let Some(link) = &hovered.link {
+        info!("Opening link: {}", link.uri);
+        nav_actions.send(NavActionEvent::new(NavAction::Open(link.uri.clone())));
+    }
+}
+
+/// Plugin for link hover underlining, tooltips and Ctrl+Click
+pub struct LinkHoverPlugin;
+
+impl Plugin for LinkHoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenHyperlinks>()
+            .init_resource::<HoveredLink>()
+            .init_resource::<TerminalInsets>()
+            .add_event::<RemoteMessageEvent>()
+            .add_event::<NavActionEvent>()
+            .add_systems(
+                Update,
+                (
+                    receive_hyperlinks,
+                    update_hovered_link,
+                    render_link_underline,
+                    update_link_tooltip,
+                    handle_link_click,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> TerminalMetrics {
+        TerminalMetrics {
+            cell_width: 10.0,
+            cell_height: 20.0,
+            columns: 80,
+            rows: 24,
+        }
+    }
+
+    #[test]
+    fn test_hover_cell() {
+        let insets = TerminalInsets {
+            top: 24.0,
+            ..default()
+        };
+
+        assert_eq!(
+            hover_cell(Vec2::new(35.0, 24.0 + 45.0), &metrics(), insets),
+            Some((3, 2))
+        );
+        // Over the tab bar or past the last row/column
+        assert_eq!(hover_cell(Vec2::new(35.0, 10.0), &metrics(), insets), None);
+        assert_eq!(
+            hover_cell(Vec2::new(800.0, 100.0), &metrics(), insets),
+            None
+        );
+        assert_eq!(
+            hover_cell(Vec2::new(10.0, 24.0 + 480.0), &metrics(), insets),
+            None
+        );
+    }
+
+    #[test]
+    fn test_url_at() {
+        let regex = Regex::new(r"https?://[^\s<>{}|\^~\[\]`]+").unwrap();
+        let line = "→ see https://example.com/docs for more";
+
+        let link = url_at(&regex, line, 3, 10).unwrap();
+        assert_eq!(link.uri, "https://example.com/docs");
+        // Columns count characters, not bytes
+        assert_eq!((link.row, link.start_col, link.end_col), (3, 6, 29));
+        assert!(url_at(&regex, line, 3, 5).is_none());
+        assert!(url_at(&regex, line, 3, 30).is_none());
+    }
+
+    #[test]
+    fn test_tooltip_text() {
+        assert_eq!(tooltip_text("https://a.b"), "https://a.b");
+
+        let long = format!("https://example.com/{}", "x".repeat(200));
+        let text = tooltip_text(&long);
+        assert_eq!(text.chars().count(), TOOLTIP_MAX_CHARS);
+        assert!(text.ends_with('…'));
+    }
+}
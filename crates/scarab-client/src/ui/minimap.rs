@@ -0,0 +1,464 @@
+// Minimap column beside the scrollbar
+//
+// Draws a miniature density view of the scrollback, one texture row per line
+// (or per group of lines once the history outgrows the texture), so long
+// build logs can be skimmed at a glance. Output of commands that exited with
+// a non-zero code is tinted red, and clicking or dragging on the strip jumps
+// the viewport there.
+
+use crate::prompt_markers::PromptMarkers;
+use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackState};
+use crate::ui::command_gutter::{gutter_enabled, GUTTER_WIDTH};
+use crate::ui::scrollbar::{offset_for_track_fraction, thumb_geometry, SCROLLBAR_WIDTH};
+use crate::ui::status_bar::STATUS_BAR_HEIGHT;
+use crate::ui::tab_bar::TerminalInsets;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+use scarab_config::ScarabConfig;
+use scarab_protocol::PromptMarkerInfo;
+
+/// Width of the minimap column in pixels
+pub const MINIMAP_WIDTH: f32 = 64.0;
+
+/// Terminal cells folded into one minimap pixel column
+const CELLS_PER_PIXEL: usize = 2;
+
+/// Texture width; long lines are cut off like in editor minimaps
+const TEXTURE_WIDTH: usize = MINIMAP_WIDTH as usize;
+
+/// Upper bound on texture rows; longer histories share rows
+const MAX_TEXTURE_ROWS: usize = 2048;
+
+/// Minimum time between texture rebuilds while output is streaming
+const REBUILD_INTERVAL_SECS: f32 = 0.25;
+
+/// OSC 133 marker types used to find failed command zones
+const MARKER_PROMPT_START: u8 = 0;
+const MARKER_COMMAND_FINISHED: u8 = 3;
+
+/// Text density color (slime green #a8df5a) and failed-zone color
+const TEXT_RGB: [u8; 3] = [168, 223, 90];
+const ERROR_RGB: [u8; 3] = [255, 85, 85];
+
+/// Alpha of the tint behind every line of a failed zone
+const ERROR_BACKGROUND_ALPHA: f32 = 0.2;
+
+/// Marker component for the minimap column
+#[derive(Component)]
+pub struct MinimapStrip;
+
+/// Marker component for the viewport box drawn over the minimap
+#[derive(Component)]
+pub struct MinimapViewport;
+
+/// Minimap texture and interaction state
+#[derive(Resource, Default)]
+pub struct MinimapState {
+    /// Density texture shown by the strip
+    pub image: Handle<Image>,
+    /// True while the left button is held after pressing on the minimap
+    pub dragging: bool,
+    /// Set when the texture needs rebuilding
+    dirty: bool,
+}
+
+/// Line ranges (inclusive) of commands that finished with a non-zero exit code
+///
+/// Each zone runs from the prompt that started the command to the line where
+/// it finished.
+pub fn failed_zones(markers: &[PromptMarkerInfo]) -> Vec<(u32, u32)> {
+    let mut zones = Vec::new();
+    let mut zone_start = None;
+    for marker in markers {
+        match marker.marker_type {
+            MARKER_PROMPT_START => zone_start = Some(marker.line),
+            MARKER_COMMAND_FINISHED => {
+                if marker.exit_code.is_some_and(|code| code != 0) {
+                    zones.push((zone_start.unwrap_or(marker.line), marker.line));
+                }
+                zone_start = None;
+            }
+            _ => {}
+        }
+    }
+    zones
+}
+
+/// Fraction of non-blank cells in each pixel column of a line
+pub fn line_density(line: &[scarab_protocol::Cell]) -> [f32; TEXTURE_WIDTH] {
+    let mut density = [0.0; TEXTURE_WIDTH];
+    for (pixel, cells) in line.chunks(CELLS_PER_PIXEL).take(TEXTURE_WIDTH).enumerate() {
+        let filled = cells
+            .iter()
+            .filter(|c| c.char_codepoint != 0 && c.char_codepoint != ' ' as u32)
+            .count();
+        density[pixel] = filled as f32 / CELLS_PER_PIXEL as f32;
+    }
+    density
+}
+
+/// Number of texture rows for the given content height in lines
+pub fn texture_rows(content_lines: usize) -> usize {
+    content_lines.clamp(1, MAX_TEXTURE_ROWS)
+}
+
+/// RGBA pixels of the minimap texture
+///
+/// The content matches the scrollbar's: the scrollback followed by one
+/// screen of live lines, which stay blank here since they are on screen
+/// anyway. Rows covering several lines show their densest pixels.
+pub fn render_minimap(
+    scrollback: &ScrollbackBuffer,
+    viewport_lines: usize,
+    zones: &[(u32, u32)],
+) -> (usize, Vec<u8>) {
+    let line_count = scrollback.line_count();
+    let content = line_count + viewport_lines;
+    let rows = texture_rows(content);
+    let mut pixels = vec![0u8; rows * TEXTURE_WIDTH * 4];
+
+    for line in 0..line_count {
+        let row = line * rows / content.max(1);
+        let failed = zones
+            .iter()
+            .any(|&(start, end)| (start as usize..=end as usize).contains(&line));
+        let density = scrollback
+            .get_line(line)
+            .map(|l| line_density(&l.cells))
+            .unwrap_or([0.0; TEXTURE_WIDTH]);
+
+        let rgb = if failed { ERROR_RGB } else { TEXT_RGB };
+        let floor = if failed { ERROR_BACKGROUND_ALPHA } else { 0.0 };
+        let row_pixels = &mut pixels[row * TEXTURE_WIDTH * 4..(row + 1) * TEXTURE_WIDTH * 4];
+        for (pixel, d) in row_pixels.chunks_exact_mut(4).zip(density) {
+            let alpha = (floor.max(d * 0.8) * 255.0) as u8;
+            if alpha >= pixel[3] {
+                pixel.copy_from_slice(&[rgb[0], rgb[1], rgb[2], alpha]);
+            }
+        }
+    }
+
+    (rows, pixels)
+}
+
+/// Whether the minimap is enabled in the config
+fn minimap_enabled(config: Option<&ScarabConfig>) -> bool {
+    config.is_some_and(|c| c.ui.show_minimap)
+}
+
+/// Distance of the minimap from the right edge of the window
+fn minimap_right(config: Option<&ScarabConfig>) -> f32 {
+    if config.map_or(true, |c| c.ui.show_scrollbar) {
+        SCROLLBAR_WIDTH
+    } else {
+        0.0
+    }
+}
+
+/// System to spawn the minimap column and its viewport box
+fn spawn_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut state: ResMut<MinimapState>,
+) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: TEXTURE_WIDTH as u32,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    state.image = images.add(image);
+    state.dirty = true;
+
+    commands
+        .spawn((
+            MinimapStrip,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                right: Val::Px(SCROLLBAR_WIDTH),
+                bottom: Val::Px(STATUS_BAR_HEIGHT),
+                width: Val::Px(MINIMAP_WIDTH),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.12, 0.14, 0.14, 0.9)),
+            ImageNode::new(state.image.clone()),
+            ZIndex(950), // Same level as the scrollbar
+        ))
+        .with_children(|strip| {
+            strip.spawn((
+                MinimapViewport,
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.66, 0.87, 0.35, 0.08)),
+                BorderColor(Color::srgba(0.66, 0.87, 0.35, 0.5)),
+            ));
+        });
+}
+
+/// System to reserve room for the minimap and the command gutter beside the
+/// terminal grid
+///
+/// The gutter sits left of the minimap, and both stay clear of the
+/// scrollbar.
+fn update_minimap_insets(config: Option<Res<ScarabConfig>>, mut insets: ResMut<TerminalInsets>) {
+    let config = config.as_deref();
+    let mut right = 0.0;
+    if minimap_enabled(config) {
+        right += MINIMAP_WIDTH;
+    }
+    if gutter_enabled(config) {
+        right += GUTTER_WIDTH;
+    }
+    if right > 0.0 {
+        right += minimap_right(config);
+    }
+    if insets.right != right {
+        insets.right = right;
+    }
+}
+
+/// System to show/hide the minimap and place the viewport box
+fn update_minimap_layout(
+    config: Option<Res<ScarabConfig>>,
+    scrollback: Res<ScrollbackBuffer>,
+    state: Res<ScrollbackState>,
+    insets: Option<Res<TerminalInsets>>,
+    mut strips: Query<&mut Node, (With<MinimapStrip>, Without<MinimapViewport>)>,
+    mut viewports: Query<&mut Node, (With<MinimapViewport>, Without<MinimapStrip>)>,
+) {
+    let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+    let insets_changed = insets.as_ref().is_some_and(|i| i.is_changed());
+    if !scrollback.is_changed() && !state.is_changed() && !config_changed && !insets_changed {
+        return;
+    }
+    let insets = insets.map(|i| *i).unwrap_or_default();
+    let config = config.as_deref();
+
+    let display = if minimap_enabled(config) {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    for mut node in strips.iter_mut() {
+        if node.display != display {
+            node.display = display;
+        }
+        node.top = Val::Px(insets.top);
+        node.bottom = Val::Px(STATUS_BAR_HEIGHT + insets.bottom);
+        node.right = Val::Px(minimap_right(config));
+    }
+
+    let (top, height) = thumb_geometry(
+        scrollback.line_count(),
+        scrollback.scroll_offset(),
+        state.lines_per_page,
+    );
+    for mut node in viewports.iter_mut() {
+        node.top = Val::Percent(top * 100.0);
+        node.height = Val::Percent(height * 100.0);
+    }
+}
+
+/// System to redraw the density texture, throttled while output streams in
+fn update_minimap_texture(
+    config: Option<Res<ScarabConfig>>,
+    time: Res<Time>,
+    markers: Option<Res<PromptMarkers>>,
+    scrollback: Res<ScrollbackBuffer>,
+    scroll_state: Res<ScrollbackState>,
+    mut state: ResMut<MinimapState>,
+    mut images: ResMut<Assets<Image>>,
+    mut since_rebuild: Local<f32>,
+    mut last_content: Local<(usize, usize)>,
+) {
+    if !minimap_enabled(config.as_deref()) {
+        return;
+    }
+
+    let content = (scrollback.line_count(), scroll_state.lines_per_page);
+    let markers_changed = markers.as_ref().is_some_and(|m| m.is_changed());
+    if content != *last_content || markers_changed {
+        state.dirty = true;
+    }
+
+    *since_rebuild += time.delta_secs();
+    if !state.dirty || *since_rebuild < REBUILD_INTERVAL_SECS {
+        return;
+    }
+    *since_rebuild = 0.0;
+    *last_content = content;
+    state.dirty = false;
+
+    let zones = markers
+        .as_ref()
+        .map(|m| failed_zones(&m.markers))
+        .unwrap_or_default();
+    let (rows, pixels) = render_minimap(&scrollback, scroll_state.lines_per_page, &zones);
+
+    let Some(image) = images.get_mut(&state.image) else {
+        return;
+    };
+    image.resize(Extent3d {
+        width: TEXTURE_WIDTH as u32,
+        height: rows as u32,
+        depth_or_array_layers: 1,
+    });
+    image.data = pixels;
+}
+
+/// System to jump by clicking or dragging on the minimap
+fn handle_minimap_click(
+    config: Option<Res<ScarabConfig>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut minimap: ResMut<MinimapState>,
+    mut scrollback: ResMut<ScrollbackBuffer>,
+    mut state: ResMut<ScrollbackState>,
+    insets: Option<Res<TerminalInsets>>,
+) {
+    if mouse_buttons.just_released(MouseButton::Left) {
+        minimap.dragging = false;
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(cursor_pos) = window.cursor_position() else {
+        return;
+    };
+    let config = config.as_deref();
+    let insets = insets.map(|i| *i).unwrap_or_default();
+    let strip_right = window.width() - minimap_right(config);
+    let strip_top = insets.top;
+    let strip_height = window.height() - STATUS_BAR_HEIGHT - insets.top - insets.bottom;
+
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        let on_strip = cursor_pos.x >= strip_right - MINIMAP_WIDTH
+            && cursor_pos.x < strip_right
+            && cursor_pos.y >= strip_top
+            && cursor_pos.y < strip_top + strip_height;
+        minimap.dragging = minimap_enabled(config) && on_strip;
+    }
+
+    if !minimap.dragging || !mouse_buttons.pressed(MouseButton::Left) || strip_height <= 0.0 {
+        return;
+    }
+
+    let offset = offset_for_track_fraction(
+        (cursor_pos.y - strip_top) / strip_height,
+        scrollback.line_count(),
+        state.lines_per_page,
+    );
+    if offset != scrollback.scroll_offset() {
+        scrollback.scroll_to_bottom();
+        scrollback.scroll_up(offset);
+        state.is_scrolled = !scrollback.is_at_bottom();
+    }
+}
+
+/// Plugin for the scrollback minimap
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MinimapState>()
+            .init_resource::<TerminalInsets>()
+            .add_systems(Startup, spawn_minimap)
+            .add_systems(
+                Update,
+                (
+                    handle_minimap_click,
+                    update_minimap_insets,
+                    update_minimap_layout,
+                    update_minimap_texture,
+                )
+                    .chain(),
+            );
+
+        info!("Minimap plugin initialized");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::scrollback::ScrollbackLine;
+
+    fn marker(marker_type: u8, line: u32, exit_code: Option<i32>) -> PromptMarkerInfo {
+        PromptMarkerInfo {
+            marker_type,
+            line,
+            exit_code,
+            timestamp_micros: 0,
+        }
+    }
+
+    #[test]
+    fn test_failed_zones() {
+        let markers = vec![
+            marker(MARKER_PROMPT_START, 0, None),
+            marker(MARKER_COMMAND_FINISHED, 4, Some(0)),
+            marker(MARKER_PROMPT_START, 5, None),
+            marker(2, 6, None),
+            marker(MARKER_COMMAND_FINISHED, 20, Some(101)),
+            // Finished without a prompt start in view
+            marker(MARKER_COMMAND_FINISHED, 30, Some(1)),
+        ];
+        assert_eq!(failed_zones(&markers), vec![(5, 20), (30, 30)]);
+    }
+
+    #[test]
+    fn test_line_density() {
+        let line = ScrollbackLine::from_text("ab c    ");
+        let density = line_density(&line.cells);
+        assert_eq!(density[0], 1.0);
+        assert_eq!(density[1], 0.5);
+        assert_eq!(density[2], 0.0);
+        assert_eq!(density[TEXTURE_WIDTH - 1], 0.0);
+    }
+
+    #[test]
+    fn test_render_minimap_marks_failures() {
+        let mut scrollback = ScrollbackBuffer::new(100);
+        scrollback.push_line(ScrollbackLine::from_text("ok"));
+        scrollback.push_line(ScrollbackLine::from_text("error"));
+        scrollback.push_line(ScrollbackLine::from_text(""));
+
+        let (rows, pixels) = render_minimap(&scrollback, 2, &[(1, 2)]);
+        assert_eq!(rows, 5);
+        assert_eq!(pixels.len(), rows * TEXTURE_WIDTH * 4);
+
+        let row = |r: usize| &pixels[r * TEXTURE_WIDTH * 4..][..4];
+        assert_eq!(&row(0)[..3], &TEXT_RGB);
+        assert_eq!(&row(1)[..3], &ERROR_RGB);
+        // Blank lines in a failed zone still get the red tint
+        assert_eq!(&row(2)[..3], &ERROR_RGB);
+        assert!(row(2)[3] > 0);
+        // Live lines stay blank
+        assert_eq!(row(3)[3], 0);
+    }
+
+    #[test]
+    fn test_texture_rows_are_capped() {
+        assert_eq!(texture_rows(0), 1);
+        assert_eq!(texture_rows(500), 500);
+        assert_eq!(texture_rows(1_000_000), MAX_TEXTURE_ROWS);
+    }
+}
@@ -2,32 +2,53 @@
 // Provides power-user features: link hints, command palette, leader keys, etc.
 
 pub mod animations;
+pub mod block_folding;
+pub mod block_rerun;
 pub mod breadcrumb;
+pub mod command_gutter;
 pub mod command_palette;
 pub mod dashboard;
 pub mod dock;
 pub mod fusabi_widgets;
 pub mod grid_utils;
+pub mod idle_lock;
 pub mod keybindings;
 pub mod leader_key;
 pub mod link_hints;
+pub mod link_hover;
+pub mod minimap;
 pub mod modes;
 pub mod omnibar;
+pub mod overlay_panels;
 pub mod overlays;
+pub mod pane_borders;
 pub mod plugin_menu;
+pub mod plugin_permissions;
+pub mod plugin_prompt;
+pub mod screenshot;
 pub mod scroll_indicator;
 pub mod scrollback_selection;
+pub mod scrollbar;
 pub mod search_overlay;
+pub mod session_picker;
 pub mod status_bar;
 pub mod tab_animations;
+pub mod tab_bar;
+pub mod task_progress;
+pub mod theme_gallery;
 pub mod visual_selection;
 
 pub use animations::{AnimationState, AnimationsPlugin, FadeAnimation};
+pub use block_folding::{BlockFoldingPlugin, BlockFolds};
+pub use block_rerun::{BlockRerunPlugin, RerunCommand, RerunTarget};
 pub use breadcrumb::{
     BreadcrumbContainer, BreadcrumbPlugin, BreadcrumbSegmentSelectedEvent, BreadcrumbState,
     BreadcrumbText, OpenDirectoryPickerEvent, PathSegment, BREADCRUMB_BAR_HEIGHT,
 };
-pub use command_palette::{Command, CommandPalettePlugin, CommandRegistry};
+pub use command_gutter::{CommandGutterPlugin, CommandGutterState, GUTTER_WIDTH};
+pub use command_palette::{
+    Command, CommandExecutedEvent, CommandHistory, CommandPalettePlugin, CommandRegistry,
+};
 pub use dashboard::{
     create_system_monitor_dashboard, DashboardLayout, DashboardPane, DashboardPlugin,
     DashboardState, DashboardUpdateEvent, DashboardWidget, TextDisplayStyle,
@@ -38,19 +59,29 @@ pub use grid_utils::{
     grid_cell_bounds, grid_cell_center, grid_region_bounds, grid_to_pixel,
     grid_to_pixel_with_renderer, pixel_to_grid,
 };
+pub use idle_lock::{IdleLockPlugin, IdleLockState};
 pub use keybindings::{KeyBinding, KeyBindingConfig, KeybindingsPlugin};
 pub use leader_key::{LeaderKeyPlugin, LeaderKeyState};
 pub use link_hints::{LinkDetector, LinkHint, LinkHintsPlugin};
-pub use modes::{ModeActionEvent, ModeChangeEvent, ModesPlugin, ModeState, ScarabMode};
+pub use link_hover::{HoveredLink, LinkHoverPlugin, ScreenHyperlinks};
+pub use minimap::{MinimapPlugin, MinimapState, MINIMAP_WIDTH};
+pub use modes::{ModeActionEvent, ModeChangeEvent, ModeState, ModesPlugin, ScarabMode};
 pub use omnibar::{
     OmnibarContext, OmnibarExecuteEvent, OmnibarPlugin, OmnibarProvider, OmnibarResult,
     OmnibarState, OmnibarUI, ProviderRegistry,
 };
+pub use overlay_panels::{OverlayPanelState, OverlayPanelsPlugin};
 pub use overlays::RemoteUiPlugin;
+pub use pane_borders::{PaneBorder, PaneBordersPlugin, PaneLayout};
 pub use plugin_menu::{MenuPosition, MenuState, PluginMenuPlugin, ShowPluginMenuEvent};
+pub use plugin_permissions::{PermissionPromptState, PluginPermissionsPlugin};
+pub use plugin_prompt::{PluginPromptPlugin, PluginPromptState};
+pub use screenshot::{ScreenshotPlugin, ScreenshotRequest, ScreenshotTakenEvent, ScreenshotTarget};
 pub use scroll_indicator::{ScrollIndicatorConfig, ScrollIndicatorPlugin};
 pub use scrollback_selection::{ScrollbackSelectionPlugin, ScrollbackSelectionState};
-pub use search_overlay::{SearchOverlayConfig, SearchOverlayPlugin};
+pub use scrollbar::{ScrollbarDrag, ScrollbarPlugin, SCROLLBAR_WIDTH};
+pub use search_overlay::{SearchMatches, SearchOverlayConfig, SearchOverlayPlugin};
+pub use session_picker::{SessionPickerPlugin, SessionPickerState};
 pub use status_bar::{
     StatusBarContainer, StatusBarLeft, StatusBarPlugin, StatusBarRight, StatusBarState,
     TabContainer, TabLabel, TabState, TabSwitchEvent, BOTTOM_UI_HEIGHT, DOCK_HEIGHT,
@@ -59,6 +90,9 @@ pub use status_bar::{
 pub use tab_animations::{
     TabAnimationConfig, TabAnimationsPlugin, TabEasingFunction, TabFade, TabHover, TabTransition,
 };
+pub use tab_bar::{TabBarItem, TabBarPlugin, TabBarState, TerminalInsets, TAB_BAR_HEIGHT};
+pub use task_progress::{TaskProgressPlugin, TaskProgressState};
+pub use theme_gallery::{ThemeGalleryPlugin, ThemeGalleryState};
 pub use visual_selection::{SelectionMode, SelectionRegion, VisualSelectionPlugin};
 
 use bevy::prelude::*;
@@ -72,12 +106,15 @@ impl Plugin for AdvancedUIPlugin {
         app.add_plugins((
             BreadcrumbPlugin,
             LinkHintsPlugin,
+            LinkHoverPlugin,
             OmnibarPlugin,
+            CommandPalettePlugin,
             LeaderKeyPlugin,
             KeybindingsPlugin,
             AnimationsPlugin,
             TabAnimationsPlugin,
             DashboardPlugin,
+            TaskProgressPlugin,
         ));
 
         app.add_plugins((
@@ -86,9 +123,26 @@ impl Plugin for AdvancedUIPlugin {
             RemoteUiPlugin,
             PluginMenuPlugin,
             ScrollIndicatorPlugin,
+            ScrollbarPlugin,
+            MinimapPlugin,
             ScrollbackSelectionPlugin,
             SearchOverlayPlugin,
             StatusBarPlugin,
+            TabBarPlugin,
+            PaneBordersPlugin,
+            ScreenshotPlugin,
+            SessionPickerPlugin,
+            PluginPromptPlugin,
+        ));
+
+        app.add_plugins((
+            OverlayPanelsPlugin,
+            PluginPermissionsPlugin,
+            ThemeGalleryPlugin,
+            IdleLockPlugin,
+            BlockFoldingPlugin,
+            BlockRerunPlugin,
+            CommandGutterPlugin,
         ));
 
         app.insert_resource(UIConfig::default())
@@ -196,7 +196,7 @@ fn toggle_omnibar_system(
     let ctrl = keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight);
     let shift = keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
 
-    // Ctrl+P - Open omnibar (all providers)
+    // Ctrl+P - Open omnibar (all providers); Ctrl+Shift+P is the command palette
     if ctrl && !shift && keyboard.just_pressed(KeyCode::KeyP) {
         state.active = !state.active;
 
@@ -208,18 +208,6 @@ fn toggle_omnibar_system(
         }
     }
 
-    // Ctrl+Shift+P - Open omnibar with ">" prefix (commands only)
-    if ctrl && shift && keyboard.just_pressed(KeyCode::KeyP) {
-        state.active = !state.active;
-
-        if state.active {
-            state.query.clear();
-            state.active_prefix = Some(">".to_string());
-            state.selected_index = 0;
-            state.results = registry.query("", 10, Some(">"));
-        }
-    }
-
     // Ctrl+R - Open omnibar with "#" prefix (history)
     if ctrl && keyboard.just_pressed(KeyCode::KeyR) {
         state.active = !state.active;
@@ -0,0 +1,294 @@
+//! Floating panels drawn by daemon plugins
+//!
+//! Plugins spawn panels with `SpawnPanel` and remove them with the same
+//! `RemoveOverlay` message as plain overlays. A panel sits at a grid
+//! position and stacks an optional title, text lines, and selectable items
+//! inside an optional border. Clicking an item sends `PanelItemSelected`
+//! back to the daemon for the plugin that owns the panel.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use scarab_protocol::{
+    ControlMessage, DaemonMessage, OverlayPanel, PanelAlign, PanelBorder, TerminalMetrics,
+};
+
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::ui::TerminalInsets;
+
+/// Panels on screen, keyed by owning plugin and overlay ID
+#[derive(Resource, Debug, Default)]
+pub struct OverlayPanelState {
+    pub panels: BTreeMap<(String, u64), OverlayPanel>,
+}
+
+impl OverlayPanelState {
+    /// Apply a daemon message, returning whether it changed the panels
+    pub fn apply(&mut self, message: &DaemonMessage) -> bool {
+        match message {
+            DaemonMessage::SpawnPanel {
+                plugin_name,
+                overlay_id,
+                panel,
+            } => {
+                self.panels
+                    .insert((plugin_name.to_string(), *overlay_id), panel.clone());
+                true
+            }
+            DaemonMessage::RemoveOverlay {
+                plugin_name,
+                overlay_id,
+            } => self
+                .panels
+                .remove(&(plugin_name.to_string(), *overlay_id))
+                .is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Border width and corner radius in pixels, plus whether a second line is
+/// drawn outside the border
+fn border_metrics(border: PanelBorder) -> (f32, f32, bool) {
+    match border {
+        PanelBorder::None => (0.0, 0.0, false),
+        PanelBorder::Single => (1.0, 0.0, false),
+        PanelBorder::Rounded => (1.0, 6.0, false),
+        PanelBorder::Double => (1.0, 0.0, true),
+    }
+}
+
+fn justify(align: PanelAlign) -> JustifyText {
+    match align {
+        PanelAlign::Left => JustifyText::Left,
+        PanelAlign::Center => JustifyText::Center,
+        PanelAlign::Right => JustifyText::Right,
+    }
+}
+
+/// Convert an `OverlayStyle` RGBA value
+fn rgba(value: u32) -> Color {
+    Color::srgba_u8(
+        (value >> 24) as u8,
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    )
+}
+
+/// Marker for panel UI roots
+#[derive(Component)]
+struct OverlayPanelUI;
+
+/// Clickable item of a panel
+#[derive(Component, Debug, Clone)]
+struct PanelItemButton {
+    plugin_name: String,
+    overlay_id: u64,
+    item_id: String,
+}
+
+/// System to track panels spawned and removed by the daemon
+fn receive_panels(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut state: ResMut<OverlayPanelState>,
+) {
+    for event in events.read() {
+        // Only mark the state changed for panel messages
+        if matches!(
+            event.0,
+            DaemonMessage::SpawnPanel { .. } | DaemonMessage::RemoveOverlay { .. }
+        ) {
+            state.apply(&event.0);
+        }
+    }
+}
+
+/// System to rebuild panel UI when panels or the grid change
+fn render_panels(
+    mut commands: Commands,
+    state: Res<OverlayPanelState>,
+    metrics: Option<Res<TerminalMetrics>>,
+    insets: Option<Res<TerminalInsets>>,
+    existing_ui: Query<Entity, With<OverlayPanelUI>>,
+) {
+    let Some(metrics) = metrics else {
+        return;
+    };
+    let insets_changed = insets.as_ref().is_some_and(|i| i.is_changed());
+    if !state.is_changed() && !metrics.is_changed() && !insets_changed {
+        return;
+    }
+
+    for entity in existing_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let top_inset = insets.map_or(0.0, |i| i.top);
+    for ((plugin_name, overlay_id), panel) in &state.panels {
+        let (left, top) = metrics.grid_to_screen(panel.x, panel.y);
+        let (border_width, radius, double) = border_metrics(panel.border);
+        let fg = rgba(panel.style.fg);
+        let font_size = metrics.cell_height * 0.8;
+        let text_justify = justify(panel.align);
+
+        let mut root = commands.spawn((
+            OverlayPanelUI,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(left),
+                top: Val::Px(top + top_inset),
+                max_width: panel
+                    .max_width
+                    .map_or(Val::Auto, |cols| Val::Px(cols as f32 * metrics.cell_width)),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::axes(Val::Px(metrics.cell_width), Val::Px(4.0)),
+                border: UiRect::all(Val::Px(border_width)),
+                ..default()
+            },
+            BackgroundColor(rgba(panel.style.bg)),
+            BorderColor(fg),
+            BorderRadius::all(Val::Px(radius)),
+            // Plugin z-index orders panels above the status bar (ZIndex 1000)
+            ZIndex(1100 + panel.style.z_index as i32),
+        ));
+        if double {
+            root.insert(Outline::new(Val::Px(1.0), Val::Px(2.0), fg));
+        }
+
+        root.with_children(|parent| {
+            if let Some(title) = &panel.title {
+                parent.spawn((
+                    Text::new(title.clone()),
+                    TextFont {
+                        font_size,
+                        ..default()
+                    },
+                    TextColor(fg),
+                    TextLayout::new_with_justify(text_justify),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(4.0)),
+                        ..default()
+                    },
+                ));
+            }
+
+            for line in &panel.lines {
+                parent.spawn((
+                    Text::new(line.clone()),
+                    TextFont {
+                        font_size,
+                        ..default()
+                    },
+                    TextColor(fg),
+                    TextLayout::new_with_justify(text_justify),
+                ));
+            }
+
+            for item in &panel.items {
+                parent
+                    .spawn((
+                        Button,
+                        PanelItemButton {
+                            plugin_name: plugin_name.clone(),
+                            overlay_id: *overlay_id,
+                            item_id: item.id.clone(),
+                        },
+                        Node {
+                            padding: UiRect::horizontal(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                    ))
+                    .with_children(|button| {
+                        button.spawn((
+                            Text::new(item.label.clone()),
+                            TextFont {
+                                font_size,
+                                ..default()
+                            },
+                            TextColor(fg),
+                            TextLayout::new_with_justify(text_justify),
+                        ));
+                    });
+            }
+        });
+    }
+}
+
+/// System to send clicked panel items to the daemon
+fn handle_item_clicks(
+    mut items: Query<(&Interaction, &PanelItemButton, &mut BackgroundColor), Changed<Interaction>>,
+    ipc: Res<IpcChannel>,
+) {
+    for (interaction, item, mut bg) in items.iter_mut() {
+        match interaction {
+            Interaction::Pressed => ipc.send(ControlMessage::PanelItemSelected {
+                plugin_name: item.plugin_name.clone(),
+                overlay_id: item.overlay_id,
+                item_id: item.item_id.clone(),
+            }),
+            Interaction::Hovered => *bg = BackgroundColor(Color::WHITE.with_alpha(0.15)),
+            Interaction::None => *bg = BackgroundColor(Color::NONE),
+        }
+    }
+}
+
+/// Plugin for floating panels spawned by daemon plugins
+pub struct OverlayPanelsPlugin;
+
+impl Plugin for OverlayPanelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OverlayPanelState>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(
+                Update,
+                (
+                    receive_panels,
+                    render_panels,
+                    handle_item_clicks.run_if(resource_exists::<IpcChannel>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(plugin_name: &str, overlay_id: u64, title: &str) -> DaemonMessage {
+        DaemonMessage::SpawnPanel {
+            plugin_name: plugin_name.into(),
+            overlay_id,
+            panel: OverlayPanel::new(1, 2)
+                .with_title(title)
+                .with_item("main", "main"),
+        }
+    }
+
+    #[test]
+    fn test_spawn_replace_and_remove() {
+        let mut state = OverlayPanelState::default();
+        assert!(state.apply(&spawn("git", 1, "Branches")));
+        assert!(state.apply(&spawn("weather", 1, "Forecast")));
+        assert!(state.apply(&spawn("git", 1, "Remotes")));
+        assert_eq!(state.panels.len(), 2);
+        assert_eq!(
+            state.panels[&("git".to_string(), 1)].title.as_deref(),
+            Some("Remotes")
+        );
+
+        // Overlay IDs are per plugin
+        assert!(state.apply(&DaemonMessage::RemoveOverlay {
+            plugin_name: "git".into(),
+            overlay_id: 1,
+        }));
+        assert!(!state.apply(&DaemonMessage::RemoveOverlay {
+            plugin_name: "git".into(),
+            overlay_id: 1,
+        }));
+        assert!(state.panels.contains_key(&("weather".to_string(), 1)));
+        assert!(!state.apply(&DaemonMessage::HideModal));
+    }
+}
@@ -18,11 +18,6 @@ struct NotificationUI {
     lifetime: f32,
 }
 
-/// Component for plugin log display
-#[allow(dead_code)]
-#[derive(Component)]
-struct PluginLogDisplay;
-
 /// Event to hide modal (sent when ESC is pressed or modal is dismissed)
 #[derive(Event)]
 pub struct HideModalEvent;
@@ -131,7 +126,7 @@ fn handle_remote_messages(
                     LogLevel::Debug => debug!("[{}] {}", plugin_name, message),
                 }
 
-                // TODO: Could also display in an on-screen log panel
+                // The plugin log viewer keeps its own copy of these
             }
             DaemonMessage::PluginNotification { title, body, level } => {
                 spawn_notification(
@@ -148,7 +143,7 @@ fn handle_remote_messages(
 }
 
 /// Spawn a notification UI element
-fn spawn_notification(
+pub(crate) fn spawn_notification(
     commands: &mut Commands,
     title: &str,
     body: &str,
@@ -267,10 +262,9 @@ fn handle_hide_modal(
     }
 }
 
-/// Handle show modal events - for now just log, could be routed to omnibar in the future
+/// Log show modal events; the command palette lists the items
 fn handle_show_modal(mut events: EventReader<ShowRemoteModalEvent>) {
     for event in events.read() {
-        // TODO: Route to omnibar or create a dedicated modal UI
         info!(
             "Remote modal requested: '{}' with {} items",
             event.title,
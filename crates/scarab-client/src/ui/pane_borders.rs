@@ -0,0 +1,331 @@
+//! Split pane border rendering
+//!
+//! Tracks pane geometry from daemon messages (`PaneLayoutUpdate`,
+//! `PaneCreated`, `PaneClosed`, `PaneFocused`) and outlines each pane once a
+//! tab is split. The focused pane is drawn in the accent color on top of its
+//! neighbours' borders.
+//!
+//! With `ui.inactive_pane_brightness` below 1.0, unfocused panes are covered
+//! by a black layer whose alpha scales their cells' brightness by that factor.
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use scarab_config::ScarabConfig;
+use scarab_protocol::{DaemonMessage, PaneInfo};
+
+use crate::integration::TerminalGridEntity;
+use crate::ipc::RemoteMessageEvent;
+use crate::rendering::layers::{LAYER_FOCUS, LAYER_IMAGES};
+use crate::rendering::text::TextRenderer;
+
+/// Client-side copy of the active tab's pane layout
+#[derive(Resource, Debug, Default)]
+pub struct PaneLayout {
+    pub panes: Vec<PaneInfo>,
+}
+
+impl PaneLayout {
+    /// Apply a daemon message, returning true if the layout changed
+    pub fn apply(&mut self, msg: &DaemonMessage) -> bool {
+        match msg {
+            DaemonMessage::PaneLayoutUpdate { panes } => {
+                self.panes = panes.clone();
+            }
+            DaemonMessage::PaneCreated { pane } => {
+                if pane.is_focused {
+                    self.set_focused(pane.id);
+                }
+                match self.panes.iter_mut().find(|p| p.id == pane.id) {
+                    Some(existing) => *existing = pane.clone(),
+                    None => self.panes.push(pane.clone()),
+                }
+            }
+            DaemonMessage::PaneClosed { pane_id } => {
+                self.panes.retain(|p| p.id != *pane_id);
+            }
+            DaemonMessage::PaneFocused { pane_id } => {
+                self.set_focused(*pane_id);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// The focused pane, if known
+    pub fn focused(&self) -> Option<&PaneInfo> {
+        self.panes.iter().find(|p| p.is_focused)
+    }
+
+    fn set_focused(&mut self, pane_id: u64) {
+        for pane in &mut self.panes {
+            pane.is_focused = pane.id == pane_id;
+        }
+    }
+}
+
+/// One edge of a pane outline, parented to the terminal grid
+#[derive(Component)]
+pub struct PaneBorder {
+    pub pane_id: u64,
+}
+
+/// Dimming layer over an unfocused pane, parented to the terminal grid
+#[derive(Component)]
+pub struct PaneDim {
+    pub pane_id: u64,
+}
+
+/// Color of the layer that scales a pane's brightness by `brightness`
+///
+/// Returns `None` when no dimming is needed.
+pub fn inactive_pane_dim(brightness: f32) -> Option<Color> {
+    let brightness = brightness.clamp(0.0, 1.0);
+    (brightness < 1.0).then(|| Color::BLACK.with_alpha(1.0 - brightness))
+}
+
+/// Top, bottom, left, and right edges of a pane outline
+///
+/// Returned as `(top_left, size)` in grid-local pixels with y growing
+/// downward, so callers negate y when placing sprites.
+pub fn pane_border_rects(pane: &PaneInfo, cell_size: Vec2, thickness: f32) -> [(Vec2, Vec2); 4] {
+    let origin = Vec2::new(pane.x as f32, pane.y as f32) * cell_size;
+    let size = Vec2::new(pane.width as f32, pane.height as f32) * cell_size;
+    let thickness = thickness.min(size.x).min(size.y);
+
+    [
+        (origin, Vec2::new(size.x, thickness)),
+        (
+            Vec2::new(origin.x, origin.y + size.y - thickness),
+            Vec2::new(size.x, thickness),
+        ),
+        (origin, Vec2::new(thickness, size.y)),
+        (
+            Vec2::new(origin.x + size.x - thickness, origin.y),
+            Vec2::new(thickness, size.y),
+        ),
+    ]
+}
+
+/// Border colors from the config: (unfocused, focused)
+fn border_colors(config: Option<&ScarabConfig>) -> (Color, Color) {
+    let accent = Color::srgb(0.66, 0.87, 0.35); // #a8df5a - slime green
+    let Some(config) = config else {
+        return (accent.with_alpha(0.25), accent);
+    };
+
+    let parse = |hex: Option<&String>| hex.and_then(|h| Srgba::hex(h).ok()).map(Color::from);
+    let focused = parse(config.ui.pane_border_focused_color.as_ref())
+        .or_else(|| parse(config.colors.cursor.as_ref()))
+        .unwrap_or(accent);
+    let unfocused =
+        parse(config.ui.pane_border_color.as_ref()).unwrap_or_else(|| focused.with_alpha(0.25));
+
+    (unfocused, focused)
+}
+
+/// System to keep the pane layout in sync with the daemon
+fn receive_pane_updates(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut layout: ResMut<PaneLayout>,
+) {
+    for event in events.read() {
+        if matches!(
+            event.0,
+            DaemonMessage::PaneLayoutUpdate { .. }
+                | DaemonMessage::PaneCreated { .. }
+                | DaemonMessage::PaneClosed { .. }
+                | DaemonMessage::PaneFocused { .. }
+        ) {
+            layout.apply(&event.0);
+        }
+    }
+}
+
+/// System to rebuild pane borders when the layout, config, or cell size changes
+fn render_pane_borders(
+    mut commands: Commands,
+    config: Option<Res<ScarabConfig>>,
+    layout: Res<PaneLayout>,
+    renderer: Option<Res<TextRenderer>>,
+    grids: Query<Entity, With<TerminalGridEntity>>,
+    borders: Query<Entity, With<PaneBorder>>,
+    mut last_cell_size: Local<Vec2>,
+) {
+    let Some(renderer) = renderer else {
+        return;
+    };
+    let cell_size = Vec2::new(renderer.cell_width, renderer.cell_height);
+    let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+    if !layout.is_changed() && !config_changed && *last_cell_size == cell_size {
+        return;
+    }
+    *last_cell_size = cell_size;
+
+    for entity in borders.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let thickness = config.as_ref().map_or(1.0, |c| c.ui.pane_border_width);
+    // A single pane fills the window; there is nothing to separate
+    if layout.panes.len() < 2 || thickness <= 0.0 {
+        return;
+    }
+
+    let (unfocused, focused) = border_colors(config.as_deref());
+    for grid in grids.iter() {
+        commands.entity(grid).with_children(|parent| {
+            for pane in &layout.panes {
+                let (color, z) = if pane.is_focused {
+                    (focused, LAYER_FOCUS + 0.01)
+                } else {
+                    (unfocused, LAYER_FOCUS)
+                };
+
+                for (top_left, size) in pane_border_rects(pane, cell_size, thickness) {
+                    parent.spawn((
+                        PaneBorder { pane_id: pane.id },
+                        Sprite {
+                            color,
+                            custom_size: Some(size),
+                            anchor: Anchor::TopLeft,
+                            ..default()
+                        },
+                        Transform::from_xyz(top_left.x, -top_left.y, z),
+                    ));
+                }
+            }
+        });
+    }
+}
+
+/// System to dim unfocused panes when the layout, config, or cell size changes
+fn render_inactive_pane_dim(
+    mut commands: Commands,
+    config: Option<Res<ScarabConfig>>,
+    layout: Res<PaneLayout>,
+    renderer: Option<Res<TextRenderer>>,
+    grids: Query<Entity, With<TerminalGridEntity>>,
+    dims: Query<Entity, With<PaneDim>>,
+    mut last_cell_size: Local<Vec2>,
+) {
+    let Some(renderer) = renderer else {
+        return;
+    };
+    let cell_size = Vec2::new(renderer.cell_width, renderer.cell_height);
+    let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+    if !layout.is_changed() && !config_changed && *last_cell_size == cell_size {
+        return;
+    }
+    *last_cell_size = cell_size;
+
+    for entity in dims.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let brightness = config
+        .as_ref()
+        .map_or(1.0, |c| c.ui.inactive_pane_brightness);
+    let Some(color) = inactive_pane_dim(brightness) else {
+        return;
+    };
+    if layout.panes.len() < 2 {
+        return;
+    }
+
+    for grid in grids.iter() {
+        commands.entity(grid).with_children(|parent| {
+            for pane in layout.panes.iter().filter(|p| !p.is_focused) {
+                let origin = Vec2::new(pane.x as f32, pane.y as f32) * cell_size;
+                let size = Vec2::new(pane.width as f32, pane.height as f32) * cell_size;
+                parent.spawn((
+                    PaneDim { pane_id: pane.id },
+                    Sprite {
+                        color,
+                        custom_size: Some(size),
+                        anchor: Anchor::TopLeft,
+                        ..default()
+                    },
+                    // Over the pane's text and images, under hints and borders
+                    Transform::from_xyz(origin.x, -origin.y, LAYER_IMAGES + 1.0),
+                ));
+            }
+        });
+    }
+}
+
+/// Plugin for split pane border rendering
+pub struct PaneBordersPlugin;
+
+impl Plugin for PaneBordersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PaneLayout>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(
+                Update,
+                (
+                    receive_pane_updates,
+                    (render_pane_borders, render_inactive_pane_dim),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(id: u64, x: u16, y: u16, width: u16, height: u16, is_focused: bool) -> PaneInfo {
+        PaneInfo {
+            id,
+            x,
+            y,
+            width,
+            height,
+            is_focused,
+        }
+    }
+
+    #[test]
+    fn test_pane_layout_updates() {
+        let mut layout = PaneLayout::default();
+        layout.apply(&DaemonMessage::PaneLayoutUpdate {
+            panes: vec![pane(1, 0, 0, 40, 24, true)],
+        });
+        layout.apply(&DaemonMessage::PaneCreated {
+            pane: pane(2, 40, 0, 40, 24, true),
+        });
+        assert_eq!(layout.panes.len(), 2);
+        assert_eq!(layout.focused().map(|p| p.id), Some(2));
+
+        layout.apply(&DaemonMessage::PaneFocused { pane_id: 1 });
+        assert_eq!(layout.focused().map(|p| p.id), Some(1));
+
+        layout.apply(&DaemonMessage::PaneClosed { pane_id: 2 });
+        assert_eq!(layout.panes.len(), 1);
+    }
+
+    #[test]
+    fn test_inactive_pane_dim() {
+        assert_eq!(inactive_pane_dim(1.0), None);
+        assert_eq!(inactive_pane_dim(1.5), None);
+
+        // Black at 30% alpha leaves 70% of each cell's color
+        let color = inactive_pane_dim(0.7).unwrap();
+        assert!((color.alpha() - 0.3).abs() < 1e-6);
+        assert_eq!(color.with_alpha(1.0), Color::BLACK);
+    }
+
+    #[test]
+    fn test_pane_border_rects() {
+        let rects = pane_border_rects(&pane(1, 40, 0, 40, 24, false), Vec2::new(10.0, 20.0), 2.0);
+
+        // Top edge spans the pane width at its origin
+        assert_eq!(rects[0], (Vec2::new(400.0, 0.0), Vec2::new(400.0, 2.0)));
+        // Bottom edge sits inside the last row
+        assert_eq!(rects[1], (Vec2::new(400.0, 478.0), Vec2::new(400.0, 2.0)));
+        // Left and right edges span the pane height
+        assert_eq!(rects[2], (Vec2::new(400.0, 0.0), Vec2::new(2.0, 480.0)));
+        assert_eq!(rects[3], (Vec2::new(798.0, 0.0), Vec2::new(2.0, 480.0)));
+    }
+}
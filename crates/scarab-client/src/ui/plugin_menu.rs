@@ -8,6 +8,7 @@ use crate::ui::dock::NavConnection;
 use crate::ui::link_hints::PluginMenuRequestEvent;
 use bevy::input::keyboard::KeyCode;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use scarab_nav_protocol::{ElementType, InteractiveElement, UpdateLayout};
 use scarab_plugin_api::menu::{MenuAction, MenuItem};
 use scarab_protocol::{ControlMessage, DaemonMessage, MenuActionType};
@@ -418,7 +419,7 @@ fn render_menu_system(
     mut commands: Commands,
     menu_state: Res<MenuState>,
     existing_ui: Query<Entity, With<MenuUI>>,
-    windows: Query<&Window>,
+    windows: Query<&Window, With<PrimaryWindow>>,
 ) {
     // Remove existing menu UI
     for entity in existing_ui.iter() {
@@ -719,7 +720,11 @@ fn render_menu_system(
 }
 
 /// Render loading state while waiting for menu from daemon
-fn render_loading_state(commands: &mut Commands, menu_state: &MenuState, windows: &Query<&Window>) {
+fn render_loading_state(
+    commands: &mut Commands,
+    menu_state: &MenuState,
+    windows: &Query<&Window, With<PrimaryWindow>>,
+) {
     let menu_width = 400.0;
     let menu_height = 150.0;
 
@@ -773,7 +778,12 @@ fn render_loading_state(commands: &mut Commands, menu_state: &MenuState, windows
 }
 
 /// Render error state when menu loading fails
-fn render_error_state(commands: &mut Commands, menu_state: &MenuState, error: &str, windows: &Query<&Window>) {
+fn render_error_state(
+    commands: &mut Commands,
+    menu_state: &MenuState,
+    error: &str,
+    windows: &Query<&Window, With<PrimaryWindow>>,
+) {
     let menu_width = 400.0;
     let menu_height = 200.0;
 
@@ -839,7 +849,7 @@ fn render_error_state(commands: &mut Commands, menu_state: &MenuState, error: &s
 /// System to compute and cache menu item bounds after layout
 fn compute_menu_item_bounds(
     mut query: Query<(&mut MenuItemBounds, &GlobalTransform), With<MenuItemComponent>>,
-    windows: Query<&Window>,
+    windows: Query<&Window, With<PrimaryWindow>>,
 ) {
     if let Ok(window) = windows.get_single() {
         let window_height = window.height();
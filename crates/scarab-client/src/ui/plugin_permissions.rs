@@ -0,0 +1,427 @@
+//! Permission prompts and clipboard writes from daemon plugins
+//!
+//! The first time a plugin uses network, exec, clipboard or terminal
+//! control access, the daemon sends `ShowPermissionPrompt` and the user
+//! picks "Allow once", "Allow always" or "Deny". "Deny" is highlighted, and
+//! keys are ignored for [`INPUT_DELAY_SECS`] after a prompt appears so that
+//! typing in the shell cannot answer it. The choice goes back as a
+//! `PermissionResponse`; the daemon saves it and the plugin's next attempt
+//! succeeds or fails accordingly. `PermissionPromptClosed` takes down a
+//! prompt answered in another client or timed out. Text that allowed
+//! plugins copy arrives as `SetClipboard`.
+
+use std::collections::VecDeque;
+
+use arboard::Clipboard;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use scarab_protocol::{ControlMessage, DaemonMessage, PermissionDecision};
+
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::InputSystemSet;
+
+/// Choices in the order they are drawn, with their shortcut keys
+pub const CHOICES: [(PermissionDecision, &str, &str); 3] = [
+    (PermissionDecision::AllowOnce, "Allow once", "o"),
+    (PermissionDecision::AllowAlways, "Allow always", "a"),
+    (PermissionDecision::Deny, "Deny", "d"),
+];
+
+/// Index into [`CHOICES`] highlighted when a prompt opens
+const DENY_CHOICE: usize = 2;
+
+/// Seconds a new prompt ignores keys for
+pub const INPUT_DELAY_SECS: f64 = 0.5;
+
+/// A plugin waiting to hear whether it may use a capability
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionPrompt {
+    pub prompt_id: u64,
+    pub plugin_name: String,
+    pub capability: String,
+    pub detail: String,
+}
+
+impl PermissionPrompt {
+    /// Build a prompt from a daemon message, if it is one
+    pub fn from_message(message: &DaemonMessage) -> Option<Self> {
+        match message {
+            DaemonMessage::ShowPermissionPrompt {
+                prompt_id,
+                plugin_name,
+                capability,
+                detail,
+            } => Some(Self {
+                prompt_id: *prompt_id,
+                plugin_name: plugin_name.to_string(),
+                capability: capability.to_string(),
+                detail: detail.to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Question shown to the user
+    pub fn question(&self) -> String {
+        format!(
+            "Plugin '{}' wants {} access to {}",
+            self.plugin_name, self.capability, self.detail
+        )
+    }
+}
+
+/// Open and queued permission prompts
+#[derive(Resource, Debug, Default)]
+pub struct PermissionPromptState {
+    pub active: Option<PermissionPrompt>,
+    /// Index into [`CHOICES`] of the highlighted choice
+    pub selected: usize,
+    /// When the open prompt starts taking keys, set on its first frame
+    ready_at: Option<f64>,
+    queued: VecDeque<PermissionPrompt>,
+}
+
+impl PermissionPromptState {
+    /// Whether a prompt owns the keyboard
+    pub fn captures_keys(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Whether the open prompt has been up long enough to take keys
+    ///
+    /// The first call after a prompt opens starts the delay.
+    pub fn accepts_keys(&mut self, now: f64) -> bool {
+        match self.ready_at {
+            Some(ready_at) => now >= ready_at,
+            None => {
+                self.ready_at = Some(now + INPUT_DELAY_SECS);
+                false
+            }
+        }
+    }
+
+    /// Show a prompt now, or after the ones already open
+    pub fn open(&mut self, prompt: PermissionPrompt) {
+        if self.active.is_none() {
+            self.show(Some(prompt));
+        } else {
+            self.queued.push_back(prompt);
+        }
+    }
+
+    /// Drop a prompt that no longer needs an answer
+    pub fn close(&mut self, prompt_id: u64) {
+        if self.active.as_ref().map(|p| p.prompt_id) == Some(prompt_id) {
+            let next = self.queued.pop_front();
+            self.show(next);
+        } else {
+            self.queued.retain(|p| p.prompt_id != prompt_id);
+        }
+    }
+
+    fn show(&mut self, prompt: Option<PermissionPrompt>) {
+        self.active = prompt;
+        self.selected = DENY_CHOICE;
+        self.ready_at = None;
+    }
+
+    /// Move the highlight by `delta` choices, wrapping around
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = CHOICES.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+    }
+
+    /// Close the open prompt with the highlighted choice
+    pub fn confirm(&mut self) -> Option<ControlMessage> {
+        self.answer(CHOICES[self.selected].0)
+    }
+
+    /// Close the open prompt with `decision`, returning the message to send
+    pub fn answer(&mut self, decision: PermissionDecision) -> Option<ControlMessage> {
+        let prompt = self.active.take()?;
+        let next = self.queued.pop_front();
+        self.show(next);
+        Some(ControlMessage::PermissionResponse {
+            prompt_id: prompt.prompt_id,
+            decision,
+        })
+    }
+}
+
+/// Marker for the permission modal
+#[derive(Component)]
+struct PermissionPromptUI;
+
+/// System to open permission prompts and apply clipboard writes
+fn receive_plugin_requests(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut state: ResMut<PermissionPromptState>,
+) {
+    for event in events.read() {
+        if let Some(prompt) = PermissionPrompt::from_message(&event.0) {
+            state.open(prompt);
+        } else if let DaemonMessage::PermissionPromptClosed { prompt_id } = &event.0 {
+            state.close(*prompt_id);
+        } else if let DaemonMessage::SetClipboard { text } = &event.0 {
+            match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text.as_str())) {
+                Ok(()) => info!("Plugin copied {} characters", text.chars().count()),
+                Err(e) => error!("Failed to copy plugin text to clipboard: {}", e),
+            }
+        }
+    }
+}
+
+/// System for answering the open prompt
+fn handle_permission_keys(
+    mut key_events: EventReader<KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    ipc: Res<IpcChannel>,
+    mut state: ResMut<PermissionPromptState>,
+) {
+    if !state.captures_keys() || !state.accepts_keys(time.elapsed_secs_f64()) {
+        key_events.clear();
+        return;
+    }
+
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    for event in key_events.read() {
+        if !event.state.is_pressed() || !state.captures_keys() {
+            continue;
+        }
+
+        let message = match (&event.key_code, &event.logical_key) {
+            (KeyCode::Enter | KeyCode::NumpadEnter, _) => state.confirm(),
+            // Closing the prompt is a refusal
+            (KeyCode::Escape, _) => state.answer(PermissionDecision::Deny),
+            (KeyCode::ArrowLeft, _) => {
+                state.move_selection(-1);
+                None
+            }
+            (KeyCode::ArrowRight, _) => {
+                state.move_selection(1);
+                None
+            }
+            (KeyCode::Tab, _) => {
+                state.move_selection(if shift { -1 } else { 1 });
+                None
+            }
+            (_, Key::Character(s)) => CHOICES
+                .iter()
+                .find(|(_, _, shortcut)| s.as_str() == *shortcut)
+                .and_then(|(decision, _, _)| state.answer(*decision)),
+            _ => None,
+        };
+        if let Some(msg) = message {
+            ipc.send(msg);
+        }
+    }
+}
+
+/// System to draw the open prompt
+fn render_permission_prompt(
+    mut commands: Commands,
+    state: Res<PermissionPromptState>,
+    existing_ui: Query<Entity, With<PermissionPromptUI>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in existing_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(prompt) = &state.active else {
+        return;
+    };
+
+    commands
+        .spawn((
+            PermissionPromptUI,
+            Node {
+                width: Val::Px(480.0),
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                top: Val::Px(100.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                margin: UiRect {
+                    left: Val::Px(-240.0), // Center with width/2
+                    ..default()
+                },
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            ZIndex(2100), // Above plugin prompts, which may be waiting on it
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Plugin permission"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                Text::new(prompt.question()),
+                TextFont {
+                    font_size: 15.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.85, 0.85, 0.85, 1.0)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(12.0)),
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(8.0),
+                    margin: UiRect::bottom(Val::Px(8.0)),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (index, (_, label, shortcut)) in CHOICES.iter().enumerate() {
+                        let bg_color = if index == state.selected {
+                            Color::srgba(0.3, 0.4, 0.6, 0.9)
+                        } else {
+                            Color::srgba(0.2, 0.2, 0.2, 0.5)
+                        };
+                        row.spawn((
+                            Node {
+                                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                                ..default()
+                            },
+                            BackgroundColor(bg_color),
+                            BorderRadius::all(Val::Px(4.0)),
+                        ))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new(format!("{} ({})", label, shortcut)),
+                                TextFont {
+                                    font_size: 15.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                    }
+                });
+
+            parent.spawn((
+                Text::new("←/→: Choose  Enter: Confirm  Esc: Deny"),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.5, 0.5, 0.5, 1.0)),
+            ));
+        });
+}
+
+/// Plugin for permission prompts and clipboard writes from daemon plugins
+pub struct PluginPermissionsPlugin;
+
+impl Plugin for PluginPermissionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PermissionPromptState>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(
+                Update,
+                (
+                    receive_plugin_requests,
+                    handle_permission_keys.run_if(resource_exists::<IpcChannel>),
+                    render_permission_prompt,
+                )
+                    .chain()
+                    // After the terminal input systems, so the key that
+                    // answers the prompt never reaches the shell
+                    .after(InputSystemSet::Daemon),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(prompt_id: u64, capability: &str) -> PermissionPrompt {
+        PermissionPrompt::from_message(&DaemonMessage::ShowPermissionPrompt {
+            prompt_id,
+            plugin_name: "git-status".into(),
+            capability: capability.into(),
+            detail: "run git".into(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_choose_and_confirm() {
+        let mut state = PermissionPromptState::default();
+        state.open(prompt(3, "exec"));
+        assert!(state.captures_keys());
+        assert_eq!(
+            state.active.as_ref().unwrap().question(),
+            "Plugin 'git-status' wants exec access to run git"
+        );
+
+        // Deny is highlighted, and keys wait out the delay
+        assert_eq!(CHOICES[state.selected].0, PermissionDecision::Deny);
+        assert!(!state.accepts_keys(10.0));
+        assert!(!state.accepts_keys(10.0 + INPUT_DELAY_SECS / 2.0));
+        assert!(state.accepts_keys(10.0 + INPUT_DELAY_SECS));
+
+        state.move_selection(-1);
+        let msg = state.confirm().unwrap();
+        assert!(matches!(
+            msg,
+            ControlMessage::PermissionResponse {
+                prompt_id: 3,
+                decision: PermissionDecision::AllowAlways
+            }
+        ));
+        assert!(!state.captures_keys());
+        assert!(state.confirm().is_none());
+    }
+
+    #[test]
+    fn test_queue_and_wrap() {
+        let mut state = PermissionPromptState::default();
+        state.open(prompt(1, "exec"));
+        state.open(prompt(2, "network"));
+
+        state.move_selection(1);
+        assert_eq!(CHOICES[state.selected].0, PermissionDecision::AllowOnce);
+        state.answer(PermissionDecision::AllowOnce);
+
+        // The queued prompt opens with Deny highlighted and its own delay
+        let active = state.active.as_ref().unwrap();
+        assert_eq!((active.prompt_id, state.selected), (2, DENY_CHOICE));
+        assert!(!state.accepts_keys(1.0));
+        assert!(PermissionPrompt::from_message(&DaemonMessage::HideModal).is_none());
+    }
+
+    #[test]
+    fn test_closed_elsewhere() {
+        let mut state = PermissionPromptState::default();
+        state.open(prompt(1, "exec"));
+        state.open(prompt(2, "network"));
+        state.open(prompt(3, "clipboard"));
+
+        state.close(2);
+        state.close(1);
+        assert_eq!(state.active.as_ref().unwrap().prompt_id, 3);
+        state.close(3);
+        assert!(!state.captures_keys());
+    }
+}
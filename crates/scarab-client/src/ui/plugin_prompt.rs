@@ -0,0 +1,472 @@
+//! Text prompts and forms opened by daemon plugins
+//!
+//! Plugins ask for free text with `ShowInputPrompt` (one line) or
+//! `ShowForm` (several labelled fields). The modal owns the keyboard until
+//! the user submits with Enter or cancels with Esc; either way the answer
+//! goes back to the daemon as a `PromptResponse` for the plugin that asked.
+//! Prompts arriving while one is open wait their turn.
+
+use std::collections::VecDeque;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use scarab_protocol::{ControlMessage, DaemonMessage};
+
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::InputSystemSet;
+
+/// Character shown in place of each typed character of a masked field
+const MASK_CHAR: char = '•';
+
+/// One editable line of a prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptInput {
+    /// Label shown above the field; empty for single-line prompts
+    pub label: String,
+    pub placeholder: String,
+    pub masked: bool,
+    pub value: String,
+}
+
+impl PromptInput {
+    /// Text to draw for the field, hiding masked input
+    pub fn display_value(&self) -> String {
+        if self.masked {
+            std::iter::repeat(MASK_CHAR)
+                .take(self.value.chars().count())
+                .collect()
+        } else {
+            self.value.clone()
+        }
+    }
+}
+
+/// A prompt waiting for the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivePrompt {
+    pub prompt_id: u64,
+    pub title: String,
+    pub fields: Vec<PromptInput>,
+    /// Index of the field receiving typed text
+    pub focused: usize,
+}
+
+impl ActivePrompt {
+    /// Build a prompt from a daemon message, if it is one
+    pub fn from_message(message: &DaemonMessage) -> Option<Self> {
+        let (prompt_id, title, fields) = match message {
+            DaemonMessage::ShowInputPrompt {
+                prompt_id,
+                title,
+                placeholder,
+                masked,
+            } => (
+                *prompt_id,
+                title,
+                vec![PromptInput {
+                    label: String::new(),
+                    placeholder: placeholder.to_string(),
+                    masked: *masked,
+                    value: String::new(),
+                }],
+            ),
+            DaemonMessage::ShowForm {
+                prompt_id,
+                title,
+                fields,
+            } => (
+                *prompt_id,
+                title,
+                fields
+                    .iter()
+                    .map(|field| PromptInput {
+                        label: field.label.to_string(),
+                        placeholder: field.placeholder.to_string(),
+                        masked: field.masked,
+                        value: String::new(),
+                    })
+                    .collect(),
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            prompt_id,
+            title: title.to_string(),
+            fields,
+            focused: 0,
+        })
+    }
+}
+
+/// Open and queued plugin prompts
+#[derive(Resource, Debug, Default)]
+pub struct PluginPromptState {
+    pub active: Option<ActivePrompt>,
+    queued: VecDeque<ActivePrompt>,
+}
+
+impl PluginPromptState {
+    /// Whether a prompt owns the keyboard
+    pub fn captures_keys(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Show a prompt now, or after the ones already open
+    pub fn open(&mut self, prompt: ActivePrompt) {
+        if self.active.is_none() {
+            self.active = Some(prompt);
+        } else {
+            self.queued.push_back(prompt);
+        }
+    }
+
+    /// Append typed text to the focused field
+    pub fn insert(&mut self, text: &str) {
+        if let Some(field) = self.focused_field() {
+            field.value.push_str(text);
+        }
+    }
+
+    /// Delete the last character of the focused field
+    pub fn backspace(&mut self) {
+        if let Some(field) = self.focused_field() {
+            field.value.pop();
+        }
+    }
+
+    /// Move focus by `delta` fields, wrapping around
+    pub fn move_focus(&mut self, delta: isize) {
+        if let Some(prompt) = &mut self.active {
+            let len = prompt.fields.len() as isize;
+            if len > 0 {
+                prompt.focused = (prompt.focused as isize + delta).rem_euclid(len) as usize;
+            }
+        }
+    }
+
+    /// Close the open prompt with its values, returning the message to send
+    pub fn submit(&mut self) -> Option<ControlMessage> {
+        let prompt = self.close()?;
+        Some(ControlMessage::PromptResponse {
+            prompt_id: prompt.prompt_id,
+            values: Some(prompt.fields.into_iter().map(|f| f.value).collect()),
+        })
+    }
+
+    /// Close the open prompt without values, returning the message to send
+    pub fn cancel(&mut self) -> Option<ControlMessage> {
+        let prompt = self.close()?;
+        Some(ControlMessage::PromptResponse {
+            prompt_id: prompt.prompt_id,
+            values: None,
+        })
+    }
+
+    fn close(&mut self) -> Option<ActivePrompt> {
+        let prompt = self.active.take()?;
+        self.active = self.queued.pop_front();
+        Some(prompt)
+    }
+
+    fn focused_field(&mut self) -> Option<&mut PromptInput> {
+        let prompt = self.active.as_mut()?;
+        prompt.fields.get_mut(prompt.focused)
+    }
+}
+
+/// Marker for the prompt modal
+#[derive(Component)]
+struct PluginPromptUI;
+
+/// System to open prompts sent by the daemon
+fn receive_prompts(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut state: ResMut<PluginPromptState>,
+) {
+    for event in events.read() {
+        if let Some(prompt) = ActivePrompt::from_message(&event.0) {
+            state.open(prompt);
+        }
+    }
+}
+
+/// System for typing into the open prompt
+fn handle_prompt_keys(
+    mut key_events: EventReader<KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    ipc: Res<IpcChannel>,
+    mut state: ResMut<PluginPromptState>,
+) {
+    if !state.captures_keys() {
+        key_events.clear();
+        return;
+    }
+
+    let chorded = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    for event in key_events.read() {
+        if !event.state.is_pressed() || !state.captures_keys() {
+            continue;
+        }
+
+        match (&event.key_code, &event.logical_key) {
+            (KeyCode::Escape, _) => {
+                if let Some(msg) = state.cancel() {
+                    ipc.send(msg);
+                }
+            }
+            (KeyCode::Enter | KeyCode::NumpadEnter, _) => {
+                if let Some(msg) = state.submit() {
+                    ipc.send(msg);
+                }
+            }
+            (KeyCode::Tab, _) => state.move_focus(if shift { -1 } else { 1 }),
+            (KeyCode::ArrowDown, _) => state.move_focus(1),
+            (KeyCode::ArrowUp, _) => state.move_focus(-1),
+            (KeyCode::Backspace, _) => state.backspace(),
+            (_, Key::Character(s)) if !chorded && !s.chars().any(char::is_control) => {
+                state.insert(s);
+            }
+            (KeyCode::Space, _) if !chorded => state.insert(" "),
+            _ => {}
+        }
+    }
+}
+
+/// System to draw the open prompt
+fn render_prompt(
+    mut commands: Commands,
+    state: Res<PluginPromptState>,
+    existing_ui: Query<Entity, With<PluginPromptUI>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in existing_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(prompt) = &state.active else {
+        return;
+    };
+    let hint = if prompt.fields.len() > 1 {
+        "Tab: Next field  Enter: Submit  Esc: Cancel"
+    } else {
+        "Enter: Submit  Esc: Cancel"
+    };
+
+    commands
+        .spawn((
+            PluginPromptUI,
+            Node {
+                width: Val::Px(480.0),
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                top: Val::Px(100.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                margin: UiRect {
+                    left: Val::Px(-240.0), // Center with width/2
+                    ..default()
+                },
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            ZIndex(2000), // Above status bar (ZIndex 1000)
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(prompt.title.clone()),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                },
+            ));
+
+            for (index, field) in prompt.fields.iter().enumerate() {
+                let focused = index == prompt.focused;
+
+                if !field.label.is_empty() {
+                    parent.spawn((
+                        Text::new(field.label.clone()),
+                        TextFont {
+                            font_size: 13.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgba(0.7, 0.7, 0.7, 1.0)),
+                        Node {
+                            margin: UiRect::bottom(Val::Px(3.0)),
+                            ..default()
+                        },
+                    ));
+                }
+
+                let (text, color) = if field.value.is_empty() && !focused {
+                    (field.placeholder.clone(), Color::srgba(0.5, 0.5, 0.5, 1.0))
+                } else if focused {
+                    (format!("{}_", field.display_value()), Color::WHITE)
+                } else {
+                    (field.display_value(), Color::WHITE)
+                };
+                let bg_color = if focused {
+                    Color::srgba(0.3, 0.4, 0.6, 0.9)
+                } else {
+                    Color::srgba(0.2, 0.2, 0.2, 0.5)
+                };
+
+                parent
+                    .spawn((
+                        Node {
+                            width: Val::Percent(100.0),
+                            padding: UiRect::all(Val::Px(8.0)),
+                            margin: UiRect::bottom(Val::Px(8.0)),
+                            ..default()
+                        },
+                        BackgroundColor(bg_color),
+                        BorderRadius::all(Val::Px(4.0)),
+                    ))
+                    .with_children(|row| {
+                        row.spawn((
+                            Text::new(text),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(color),
+                        ));
+                    });
+            }
+
+            parent.spawn((
+                Text::new(hint),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.5, 0.5, 0.5, 1.0)),
+            ));
+        });
+}
+
+/// Plugin for prompts and forms requested by daemon plugins
+pub struct PluginPromptPlugin;
+
+impl Plugin for PluginPromptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PluginPromptState>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(
+                Update,
+                (
+                    receive_prompts,
+                    handle_prompt_keys.run_if(resource_exists::<IpcChannel>),
+                    render_prompt,
+                )
+                    .chain()
+                    // After the terminal input systems, so the key that
+                    // closes the prompt never reaches the shell
+                    .after(InputSystemSet::Daemon),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_protocol::PromptField;
+
+    fn form(prompt_id: u64) -> ActivePrompt {
+        ActivePrompt::from_message(&DaemonMessage::ShowForm {
+            prompt_id,
+            title: "SSH login".into(),
+            fields: vec![
+                PromptField {
+                    id: "user".into(),
+                    label: "User".into(),
+                    placeholder: "root".into(),
+                    masked: false,
+                },
+                PromptField {
+                    id: "password".into(),
+                    label: "Password".into(),
+                    placeholder: String::new(),
+                    masked: true,
+                },
+            ],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_form_submit() {
+        let mut state = PluginPromptState::default();
+        state.open(form(7));
+        assert!(state.captures_keys());
+
+        state.insert("admim");
+        state.backspace();
+        state.insert("n");
+        state.move_focus(1);
+        state.insert("hunter2");
+
+        let prompt = state.active.as_ref().unwrap();
+        assert_eq!(prompt.fields[1].display_value(), "•••••••");
+        assert_eq!(prompt.fields[0].display_value(), "admin");
+
+        let msg = state.submit().unwrap();
+        assert!(matches!(
+            msg,
+            ControlMessage::PromptResponse { prompt_id: 7, values: Some(ref v) }
+                if v == &["admin".to_string(), "hunter2".to_string()]
+        ));
+        assert!(!state.captures_keys());
+    }
+
+    #[test]
+    fn test_cancel_and_queue() {
+        let mut state = PluginPromptState::default();
+        let single = ActivePrompt::from_message(&DaemonMessage::ShowInputPrompt {
+            prompt_id: 1,
+            title: "Rename tab".into(),
+            placeholder: "name".into(),
+            masked: false,
+        })
+        .unwrap();
+        state.open(single);
+        state.open(form(2));
+
+        // Focus wraps within the single field
+        state.move_focus(-1);
+        assert_eq!(state.active.as_ref().unwrap().focused, 0);
+
+        let msg = state.cancel().unwrap();
+        assert!(matches!(
+            msg,
+            ControlMessage::PromptResponse {
+                prompt_id: 1,
+                values: None
+            }
+        ));
+
+        // The queued form opens next
+        assert_eq!(state.active.as_ref().unwrap().prompt_id, 2);
+        state.cancel();
+        assert!(state.cancel().is_none());
+        assert!(ActivePrompt::from_message(&DaemonMessage::HideModal).is_none());
+    }
+}
@@ -0,0 +1,314 @@
+//! Screenshots and "copy as image" of the terminal grid
+//!
+//! The `screenshot` and `copy_as_image` palette commands rasterize the live
+//! screen into an offscreen image with the same font and theme colors as the
+//! window, then save it as a PNG or put it on the clipboard. When copy mode
+//! or visual selection has a selection, only the rectangle around it is
+//! captured.
+//!
+//! Drawing uses the CPU `SnapshotRenderer`, so the image is the grid alone,
+//! without overlays, and does not depend on the window being visible.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use arboard::{Clipboard, ImageData};
+use bevy::prelude::*;
+use cosmic_text::FontSystem;
+use image::RgbaImage;
+use scarab_protocol::TerminalMetrics;
+
+use crate::copy_mode::{selection_spans, CopyModeStateResource};
+use crate::integration::SharedMemoryReader;
+use crate::ratatui_bridge::CommandSelected;
+use crate::rendering::snapshot::{CellRect, SnapshotRenderer};
+use crate::rendering::text::TextRenderer;
+use crate::ui::visual_selection::SelectionState;
+
+/// Where a screenshot goes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScreenshotTarget {
+    /// Save a PNG, to the pictures directory when no path is given
+    File(Option<PathBuf>),
+    /// Put the image on the clipboard
+    Clipboard,
+}
+
+impl ScreenshotTarget {
+    /// Map a command palette id to a screenshot target
+    pub fn from_command(id: &str) -> Option<Self> {
+        match id {
+            "screenshot" => Some(Self::File(None)),
+            "copy_as_image" => Some(Self::Clipboard),
+            _ => None,
+        }
+    }
+}
+
+/// Request a screenshot of the grid
+#[derive(Event, Debug, Clone)]
+pub struct ScreenshotRequest {
+    pub target: ScreenshotTarget,
+}
+
+/// Fired after a screenshot was saved or copied
+#[derive(Event, Debug, Clone)]
+pub struct ScreenshotTakenEvent {
+    /// Path of the saved PNG, or `None` when copied to the clipboard
+    pub path: Option<PathBuf>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Cells to capture: the selection's bounding rectangle, or the whole screen
+///
+/// A copy mode selection wins over a visual one. Copy mode lines scrolled
+/// out of the viewport are left out, since only the live screen is drawn.
+pub fn capture_region(
+    copy_mode: Option<&CopyModeStateResource>,
+    visual: Option<&SelectionState>,
+    cols: u16,
+    rows: u16,
+) -> CellRect {
+    let screen = CellRect {
+        col: 0,
+        row: 0,
+        cols,
+        rows,
+    };
+
+    if let Some(copy_mode) = copy_mode.filter(|c| c.is_active()) {
+        let offset = copy_mode.state.viewport_offset;
+        let corners = selection_spans(&copy_mode.state, cols)
+            .into_iter()
+            .filter_map(|span| {
+                let row = span.y + offset;
+                (0..rows as i32)
+                    .contains(&row)
+                    .then_some((span.start_x, span.end_x, row as u16))
+            })
+            .fold(
+                None,
+                |acc: Option<((u16, u16), (u16, u16))>, (start, end, row)| {
+                    Some(match acc {
+                        None => ((start, row), (end, row)),
+                        Some((min, max)) => ((min.0.min(start), min.1), (max.0.max(end), row)),
+                    })
+                },
+            );
+        if let Some((min, max)) = corners {
+            return clamp(CellRect::spanning(min, max), cols, rows);
+        }
+    }
+
+    if let Some(visual) = visual.filter(|v| v.active && !v.region.is_empty()) {
+        let region = &visual.region;
+        let rect = CellRect::spanning(
+            (region.start_x, region.start_y),
+            (region.end_x, region.end_y),
+        );
+        return clamp(rect, cols, rows);
+    }
+
+    screen
+}
+
+/// Trim a rectangle to the screen
+fn clamp(rect: CellRect, cols: u16, rows: u16) -> CellRect {
+    let col = rect.col.min(cols.saturating_sub(1));
+    let row = rect.row.min(rows.saturating_sub(1));
+    CellRect {
+        col,
+        row,
+        cols: rect.cols.min(cols - col).max(1),
+        rows: rect.rows.min(rows - row).max(1),
+    }
+}
+
+/// Default path for a saved screenshot
+pub fn default_screenshot_path() -> PathBuf {
+    let dir = dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let name = format!(
+        "scarab-{}.png",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    dir.join(name)
+}
+
+fn copy_image_to_clipboard(image: RgbaImage) -> Result<(), arboard::Error> {
+    let (width, height) = image.dimensions();
+    Clipboard::new()?.set_image(ImageData {
+        width: width as usize,
+        height: height as usize,
+        bytes: Cow::Owned(image.into_raw()),
+    })
+}
+
+fn save_image(image: &RgbaImage, path: &Path) -> image::ImageResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    image.save(path)
+}
+
+/// System to turn palette commands into screenshot requests
+fn handle_screenshot_commands(
+    mut commands_selected: EventReader<CommandSelected>,
+    mut requests: EventWriter<ScreenshotRequest>,
+) {
+    for event in commands_selected.read() {
+        if let Some(target) = ScreenshotTarget::from_command(&event.command_id) {
+            requests.send(ScreenshotRequest { target });
+        }
+    }
+}
+
+/// System to render and deliver requested screenshots
+fn take_screenshots(
+    mut requests: EventReader<ScreenshotRequest>,
+    state_reader: Option<Res<SharedMemoryReader>>,
+    renderer: Option<Res<TextRenderer>>,
+    metrics: Option<Res<TerminalMetrics>>,
+    copy_mode: Option<Res<CopyModeStateResource>>,
+    visual: Option<Res<SelectionState>>,
+    mut taken: EventWriter<ScreenshotTakenEvent>,
+) {
+    if requests.is_empty() {
+        return;
+    }
+    let (Some(state_reader), Some(renderer), Some(metrics)) = (state_reader, renderer, metrics)
+    else {
+        requests.clear();
+        warn!("Screenshot requested before the terminal was ready");
+        return;
+    };
+
+    let region = capture_region(
+        copy_mode.as_deref(),
+        visual.as_deref(),
+        metrics.columns,
+        metrics.rows,
+    );
+    // Reuse the window's font database and current (zoomed) font settings
+    let font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), renderer.font_system.db().clone());
+    let mut snapshot = SnapshotRenderer::with_font_system(&renderer.config, font_system);
+    let image = snapshot.render_region(&state_reader.get_safe_state(), region);
+    let (width, height) = image.dimensions();
+
+    for request in requests.read() {
+        match &request.target {
+            ScreenshotTarget::File(path) => {
+                let path = path.clone().unwrap_or_else(default_screenshot_path);
+                match save_image(&image, &path) {
+                    Ok(()) => {
+                        info!("Saved screenshot to {}", path.display());
+                        taken.send(ScreenshotTakenEvent {
+                            path: Some(path),
+                            width,
+                            height,
+                        });
+                    }
+                    Err(e) => error!("Failed to save screenshot to {}: {}", path.display(), e),
+                }
+            }
+            ScreenshotTarget::Clipboard => match copy_image_to_clipboard(image.clone()) {
+                Ok(()) => {
+                    info!("Copied {}x{} screenshot to clipboard", width, height);
+                    taken.send(ScreenshotTakenEvent {
+                        path: None,
+                        width,
+                        height,
+                    });
+                }
+                Err(e) => error!("Failed to copy screenshot to clipboard: {}", e),
+            },
+        }
+    }
+}
+
+/// Plugin for grid screenshots and copy as image
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CommandSelected>()
+            .add_event::<ScreenshotRequest>()
+            .add_event::<ScreenshotTakenEvent>()
+            .add_systems(
+                Update,
+                (handle_screenshot_commands, take_screenshots).chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_plugin_api::copy_mode::{CopyModeCursor, Selection, SelectionMode};
+
+    #[test]
+    fn test_screenshot_commands() {
+        assert_eq!(
+            ScreenshotTarget::from_command("screenshot"),
+            Some(ScreenshotTarget::File(None))
+        );
+        assert_eq!(
+            ScreenshotTarget::from_command("copy_as_image"),
+            Some(ScreenshotTarget::Clipboard)
+        );
+        assert_eq!(ScreenshotTarget::from_command("zoom_in"), None);
+    }
+
+    #[test]
+    fn test_capture_region() {
+        let screen = CellRect {
+            col: 0,
+            row: 0,
+            cols: 80,
+            rows: 24,
+        };
+        assert_eq!(capture_region(None, None, 80, 24), screen);
+
+        // A visual selection captures its bounding rectangle
+        let mut visual = SelectionState::default();
+        visual.start_selection(10, 5, crate::ui::visual_selection::SelectionMode::Block);
+        visual.update_selection(4, 7);
+        assert_eq!(
+            capture_region(None, Some(&visual), 80, 24),
+            CellRect {
+                col: 4,
+                row: 5,
+                cols: 7,
+                rows: 3
+            }
+        );
+
+        // Copy mode wins, and lines scrolled out of view are dropped
+        let mut copy_mode = CopyModeStateResource::new();
+        copy_mode.state.active = true;
+        copy_mode.state.selection_mode = SelectionMode::Block;
+        copy_mode.state.selection = Some(Selection::new(
+            CopyModeCursor::new(2, -3),
+            CopyModeCursor::new(6, 1),
+        ));
+        assert_eq!(
+            capture_region(Some(&copy_mode), Some(&visual), 80, 24),
+            CellRect {
+                col: 2,
+                row: 0,
+                cols: 5,
+                rows: 2
+            }
+        );
+
+        // Nothing selected in copy mode falls back to the visual selection
+        copy_mode.state.selection = None;
+        assert_eq!(
+            capture_region(Some(&copy_mode), Some(&visual), 80, 24).rows,
+            3
+        );
+    }
+}
@@ -5,6 +5,7 @@ use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackState};
 use crate::ui::visual_selection::{SelectionMode, SelectionRegion};
 use arboard::Clipboard;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 
 /// Extended selection state that works with scrollback
 #[derive(Resource)]
@@ -74,7 +75,7 @@ pub struct ScrollbackMouseSelection {
 /// System to handle mouse selection in scrollback
 fn handle_mouse_selection(
     mouse_buttons: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
+    windows: Query<&Window, With<PrimaryWindow>>,
     mut selection: ResMut<ScrollbackSelectionState>,
     scrollback: Res<ScrollbackBuffer>,
     scrollback_state: Res<ScrollbackState>,
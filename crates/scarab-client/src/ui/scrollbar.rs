@@ -0,0 +1,320 @@
+// Scrollbar along the right edge of the terminal
+//
+// Shows where the viewport sits within the scrollback and lets the user drag
+// the thumb (or click the track) to jump. Finished commands from shell
+// integration prompt markers appear as ticks colored by exit code.
+
+use crate::prompt_markers::{marker_color, PromptMarkers};
+use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackState};
+use crate::ui::status_bar::STATUS_BAR_HEIGHT;
+use crate::ui::tab_bar::TerminalInsets;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use scarab_config::ScarabConfig;
+
+/// Width of the scrollbar track in pixels
+pub const SCROLLBAR_WIDTH: f32 = 8.0;
+
+/// Minimum thumb height so it stays grabbable in long scrollback
+const MIN_THUMB_HEIGHT: f32 = 16.0;
+
+/// Height of a command-block tick in pixels
+const TICK_HEIGHT: f32 = 2.0;
+
+/// OSC 133;D marker type (command finished, carries the exit code)
+const MARKER_COMMAND_FINISHED: u8 = 3;
+
+/// Marker component for the scrollbar track
+#[derive(Component)]
+pub struct ScrollbarTrack;
+
+/// Marker component for the scrollbar thumb
+#[derive(Component)]
+pub struct ScrollbarThumb;
+
+/// Command-block tick on the scrollbar track
+#[derive(Component)]
+pub struct ScrollbarTick {
+    pub line: u32,
+}
+
+/// Drag state of the scrollbar
+#[derive(Resource, Default)]
+pub struct ScrollbarDrag {
+    /// True while the left button is held after pressing on the scrollbar
+    pub dragging: bool,
+}
+
+/// Thumb top and height as fractions of the track height
+///
+/// The content is the scrollback plus one screen of live lines, so the thumb
+/// reaches the bottom of the track in live view.
+pub fn thumb_geometry(
+    total_lines: usize,
+    scroll_offset: usize,
+    viewport_lines: usize,
+) -> (f32, f32) {
+    let content = total_lines + viewport_lines;
+    if content == 0 {
+        return (0.0, 1.0);
+    }
+
+    let top_line = total_lines.saturating_sub(scroll_offset.min(total_lines));
+    let top = top_line as f32 / content as f32;
+    let height = viewport_lines as f32 / content as f32;
+    (top, height)
+}
+
+/// Scroll offset that centers the viewport on a track position (0.0 = top)
+pub fn offset_for_track_fraction(
+    fraction: f32,
+    total_lines: usize,
+    viewport_lines: usize,
+) -> usize {
+    let content = (total_lines + viewport_lines) as f32;
+    let top_line = (fraction.clamp(0.0, 1.0) * content - viewport_lines as f32 / 2.0)
+        .round()
+        .clamp(0.0, total_lines as f32) as usize;
+    total_lines - top_line
+}
+
+/// Position of a scrollback line on the track (0.0 = top)
+pub fn line_track_fraction(line: u32, total_lines: usize, viewport_lines: usize) -> f32 {
+    let content = total_lines + viewport_lines;
+    if content == 0 {
+        return 0.0;
+    }
+    (line as f32 / content as f32).clamp(0.0, 1.0)
+}
+
+/// System to spawn the scrollbar track and thumb
+fn spawn_scrollbar(mut commands: Commands) {
+    commands
+        .spawn((
+            ScrollbarTrack,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                right: Val::Px(0.0),
+                bottom: Val::Px(STATUS_BAR_HEIGHT),
+                width: Val::Px(SCROLLBAR_WIDTH),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.66, 0.87, 0.35, 0.06)),
+            ZIndex(950), // Above terminal content, below breadcrumb and overlays
+        ))
+        .with_children(|track| {
+            track.spawn((
+                ScrollbarThumb,
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(1.0),
+                    right: Val::Px(1.0),
+                    min_height: Val::Px(MIN_THUMB_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.66, 0.87, 0.35, 0.45)), // Slime green #a8df5a
+                BorderRadius::all(Val::Px(SCROLLBAR_WIDTH / 2.0)),
+                ZIndex(1),
+            ));
+        });
+}
+
+/// System to show/hide the scrollbar and place the thumb
+fn update_scrollbar_thumb(
+    config: Option<Res<ScarabConfig>>,
+    scrollback: Res<ScrollbackBuffer>,
+    state: Res<ScrollbackState>,
+    insets: Option<Res<TerminalInsets>>,
+    mut tracks: Query<&mut Node, (With<ScrollbarTrack>, Without<ScrollbarThumb>)>,
+    mut thumbs: Query<&mut Node, (With<ScrollbarThumb>, Without<ScrollbarTrack>)>,
+) {
+    let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+    let insets_changed = insets.as_ref().is_some_and(|i| i.is_changed());
+    if !scrollback.is_changed() && !state.is_changed() && !config_changed && !insets_changed {
+        return;
+    }
+    let insets = insets.map(|i| *i).unwrap_or_default();
+
+    let enabled = config.as_ref().map_or(true, |c| c.ui.show_scrollbar);
+    let display = if enabled && scrollback.line_count() > 0 {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    for mut node in tracks.iter_mut() {
+        if node.display != display {
+            node.display = display;
+        }
+        // Stay clear of the tab bar
+        node.top = Val::Px(insets.top);
+        node.bottom = Val::Px(STATUS_BAR_HEIGHT + insets.bottom);
+    }
+
+    let (top, height) = thumb_geometry(
+        scrollback.line_count(),
+        scrollback.scroll_offset(),
+        state.lines_per_page,
+    );
+    for mut node in thumbs.iter_mut() {
+        node.top = Val::Percent(top * 100.0);
+        node.height = Val::Percent(height * 100.0);
+    }
+}
+
+/// System to rebuild command-block ticks when markers or scrollback change
+fn update_scrollbar_ticks(
+    mut commands: Commands,
+    markers: Option<Res<PromptMarkers>>,
+    scrollback: Res<ScrollbackBuffer>,
+    state: Res<ScrollbackState>,
+    tracks: Query<Entity, With<ScrollbarTrack>>,
+    ticks: Query<Entity, With<ScrollbarTick>>,
+    mut last_line_count: Local<usize>,
+) {
+    let Some(markers) = markers else {
+        return;
+    };
+    let line_count = scrollback.line_count();
+    if !markers.is_changed() && *last_line_count == line_count {
+        return;
+    }
+    *last_line_count = line_count;
+
+    for entity in ticks.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Ok(track) = tracks.get_single() else {
+        return;
+    };
+
+    commands.entity(track).with_children(|track| {
+        for marker in markers
+            .markers
+            .iter()
+            .filter(|m| m.marker_type == MARKER_COMMAND_FINISHED)
+        {
+            let top = line_track_fraction(marker.line, line_count, state.lines_per_page);
+            track.spawn((
+                ScrollbarTick { line: marker.line },
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(top * 100.0),
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    height: Val::Px(TICK_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(marker_color(marker.marker_type, marker.exit_code)),
+                ZIndex(2), // Above the thumb so failures stay visible while scrolled
+            ));
+        }
+    });
+}
+
+/// System to scroll by dragging the thumb or clicking the track
+fn handle_scrollbar_drag(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    tracks: Query<&Node, With<ScrollbarTrack>>,
+    mut drag: ResMut<ScrollbarDrag>,
+    mut scrollback: ResMut<ScrollbackBuffer>,
+    mut state: ResMut<ScrollbackState>,
+    insets: Option<Res<TerminalInsets>>,
+) {
+    if mouse_buttons.just_released(MouseButton::Left) {
+        drag.dragging = false;
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(cursor_pos) = window.cursor_position() else {
+        return;
+    };
+    let insets = insets.map(|i| *i).unwrap_or_default();
+    let track_top = insets.top;
+    let track_height = window.height() - STATUS_BAR_HEIGHT - insets.top - insets.bottom;
+
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        let visible = tracks.iter().any(|node| node.display != Display::None);
+        let on_track = cursor_pos.x >= window.width() - SCROLLBAR_WIDTH
+            && cursor_pos.y >= track_top
+            && cursor_pos.y < track_top + track_height;
+        drag.dragging = visible && on_track;
+    }
+
+    if !drag.dragging || !mouse_buttons.pressed(MouseButton::Left) || track_height <= 0.0 {
+        return;
+    }
+
+    let offset = offset_for_track_fraction(
+        (cursor_pos.y - track_top) / track_height,
+        scrollback.line_count(),
+        state.lines_per_page,
+    );
+    if offset != scrollback.scroll_offset() {
+        scrollback.scroll_to_bottom();
+        scrollback.scroll_up(offset);
+        state.is_scrolled = !scrollback.is_at_bottom();
+    }
+}
+
+/// Plugin for the scrollback scrollbar
+pub struct ScrollbarPlugin;
+
+impl Plugin for ScrollbarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScrollbarDrag>()
+            .add_systems(Startup, spawn_scrollbar)
+            .add_systems(
+                Update,
+                (
+                    handle_scrollbar_drag,
+                    update_scrollbar_thumb,
+                    update_scrollbar_ticks,
+                )
+                    .chain(),
+            );
+
+        info!("Scrollbar plugin initialized");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumb_geometry() {
+        // Live view: thumb sits at the bottom of the track
+        let (top, height) = thumb_geometry(75, 0, 25);
+        assert_eq!(top, 0.75);
+        assert_eq!(height, 0.25);
+
+        // Scrolled to the top
+        let (top, _) = thumb_geometry(75, 75, 25);
+        assert_eq!(top, 0.0);
+
+        // No content
+        assert_eq!(thumb_geometry(0, 0, 0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_offset_for_track_fraction() {
+        assert_eq!(offset_for_track_fraction(0.0, 75, 25), 75);
+        assert_eq!(offset_for_track_fraction(1.0, 75, 25), 0);
+        // Centering the viewport on the middle of the track
+        assert_eq!(offset_for_track_fraction(0.5, 75, 25), 37);
+    }
+
+    #[test]
+    fn test_line_track_fraction() {
+        assert_eq!(line_track_fraction(50, 75, 25), 0.5);
+        assert_eq!(line_track_fraction(500, 75, 25), 1.0);
+        assert_eq!(line_track_fraction(10, 0, 0), 0.0);
+    }
+}
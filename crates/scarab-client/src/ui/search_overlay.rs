@@ -1,9 +1,29 @@
 // Search overlay UI for scrollback buffer
-// Provides Ctrl+F search interface with match highlighting and navigation
+// Provides Ctrl+Shift+F search with incremental match highlighting, n/N
+// navigation, and a regex toggle. Queries run in the daemon over the active
+// pane's full scrollback and grid; matches outside the viewport are scrolled
+// into view after pulling the scrollback text into the client buffer.
+// Alt+S narrows the search to the last command's output or the screen, and
+// "Search Last Command Output" in the palette opens it narrowed that way.
 
-use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackState};
+use crate::integration::TerminalGridEntity;
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::ratatui_bridge::CommandSelected;
+use crate::rendering::layers::LAYER_TEXT_DECORATIONS;
+use crate::rendering::text::TextRenderer;
+use crate::terminal::scrollback::{ScrollbackBuffer, ScrollbackLine, ScrollbackState};
+use crate::ui::modes::{ModeState, ScarabMode};
+use bevy::input::keyboard::{Key, KeyboardInput};
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use regex::{Regex, RegexBuilder};
 use scarab_config::ScarabConfig;
+use scarab_protocol::{
+    ControlMessage, DaemonMessage, SearchMatch, SearchScope, MAX_SEARCH_RESULTS,
+};
+
+/// Palette command that opens search over the last command's output
+pub const SEARCH_LAST_COMMAND: &str = "search.last_command";
 
 /// Marker component for search overlay UI
 #[derive(Component)]
@@ -17,6 +37,10 @@ pub struct SearchInputBox;
 #[derive(Component)]
 pub struct SearchResultsText;
 
+/// Highlight drawn over one match, parented to the terminal grid
+#[derive(Component)]
+pub struct SearchHighlight;
+
 /// Search overlay configuration
 #[derive(Resource, Clone)]
 pub struct SearchOverlayConfig {
@@ -30,6 +54,10 @@ pub struct SearchOverlayConfig {
     pub border_color: Color,
     /// Font size
     pub font_size: f32,
+    /// Highlight for every match in view
+    pub match_color: Color,
+    /// Highlight for the selected match
+    pub current_match_color: Color,
 }
 
 impl Default for SearchOverlayConfig {
@@ -40,22 +68,189 @@ impl Default for SearchOverlayConfig {
             text_color: Color::srgb(0.9, 0.9, 0.9),
             border_color: Color::srgb(0.3, 0.5, 0.8),
             font_size: 16.0,
+            match_color: Color::srgba(1.0, 0.8, 0.2, 0.3),
+            current_match_color: Color::srgba(0.66, 0.87, 0.35, 0.55), // Slime green #a8df5a
         }
     }
 }
 
+/// Results of the current search
+///
+/// Match lines are absolute: scrollback lines first, then grid rows starting
+/// at `scrollback_lines`.
+#[derive(Resource, Debug, Default)]
+pub struct SearchMatches {
+    /// Query the results belong to
+    pub query: String,
+    /// Matches, oldest first
+    pub matches: Vec<SearchMatch>,
+    /// Matches found, which may exceed `matches.len()`
+    pub total: usize,
+    /// Index of the selected match
+    pub current: usize,
+    /// Scrollback length when the search ran
+    pub scrollback_lines: usize,
+    /// Treat the query as a regular expression
+    pub use_regex: bool,
+    /// Match case exactly
+    pub case_sensitive: bool,
+    /// Lines searched; back to all of them each time search closes
+    pub scope: SearchScope,
+    /// True while typing; false once Enter commits the query so n/N navigate
+    pub editing: bool,
+    /// Invalid regex message
+    pub error: Option<String>,
+    /// Selected match needs to be scrolled into view
+    reveal: bool,
+    /// Scrollback text received so far while syncing from the daemon
+    fetch: Option<ScrollbackFetch>,
+}
+
+/// Progress of a chunked scrollback fetch
+#[derive(Debug)]
+struct ScrollbackFetch {
+    target: usize,
+    lines: Vec<String>,
+}
+
+impl SearchMatches {
+    /// The selected match
+    pub fn current_match(&self) -> Option<&SearchMatch> {
+        self.matches.get(self.current)
+    }
+
+    /// Replace the results, selecting the newest match
+    pub fn set_results(
+        &mut self,
+        matches: Vec<SearchMatch>,
+        total: usize,
+        scrollback_lines: usize,
+    ) {
+        self.current = matches.len().saturating_sub(1);
+        self.matches = matches;
+        self.total = total;
+        self.scrollback_lines = scrollback_lines;
+        self.error = None;
+        self.reveal = !self.matches.is_empty();
+    }
+
+    /// Select the next match, wrapping to the oldest
+    pub fn next(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+            self.reveal = true;
+        }
+    }
+
+    /// Select the previous match, wrapping to the newest
+    pub fn prev(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = self
+                .current
+                .checked_sub(1)
+                .unwrap_or(self.matches.len() - 1);
+            self.reveal = true;
+        }
+    }
+
+    /// Drop results, keeping the toggles
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.matches.clear();
+        self.total = 0;
+        self.current = 0;
+        self.error = None;
+        self.reveal = false;
+        self.fetch = None;
+    }
+
+    /// Text for the results counter
+    pub fn status_text(&self) -> String {
+        let scope = match self.scope {
+            SearchScope::All => String::new(),
+            scope => format!(" [{}]", scope.label()),
+        };
+        let flags = format!(
+            "{}{}{}",
+            scope,
+            if self.case_sensitive { " [Aa]" } else { "" },
+            if self.use_regex { " [.*]" } else { "" }
+        );
+        if let Some(error) = &self.error {
+            format!("Invalid regex: {}{}", error, flags)
+        } else if self.query.is_empty() {
+            format!("Type to search{}", flags)
+        } else if self.matches.is_empty() {
+            format!("No matches{}", flags)
+        } else {
+            // Results are the newest matches; number them from the end
+            let skipped = self.total - self.matches.len();
+            format!(
+                "{} of {} matches{}",
+                skipped + self.current + 1,
+                self.total,
+                flags
+            )
+        }
+    }
+}
+
+/// Compile a query the same way the daemon does
+pub fn build_search_regex(
+    query: &str,
+    case_sensitive: bool,
+    use_regex: bool,
+) -> Result<Regex, regex::Error> {
+    let pattern = if use_regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+}
+
+/// Find non-empty matches in lines of text, with columns counted in chars
+pub fn find_matches<'a>(regex: &Regex, lines: impl Iterator<Item = &'a str>) -> Vec<SearchMatch> {
+    let mut matches = Vec::new();
+    for (line, text) in lines.enumerate() {
+        for m in regex.find_iter(text).filter(|m| !m.is_empty()) {
+            matches.push(SearchMatch {
+                line: line as u32,
+                col: text[..m.start()].chars().count() as u16,
+                len: m.as_str().chars().count() as u16,
+            });
+        }
+    }
+    matches
+}
+
+/// Absolute line shown in the top viewport row
+///
+/// In live view the grid is on screen, so the top row is the first grid
+/// line. Scrolled views show the client scrollback buffer.
+pub fn viewport_top(scrollback_lines: usize, local_lines: usize, scroll_offset: usize) -> usize {
+    if scroll_offset == 0 {
+        scrollback_lines
+    } else {
+        local_lines.saturating_sub(scroll_offset)
+    }
+}
+
+/// Scroll offset that brings a scrollback line to the middle of the viewport
+pub fn scroll_offset_for_line(line: usize, local_lines: usize, viewport_rows: usize) -> usize {
+    let top = line.saturating_sub(viewport_rows / 2);
+    local_lines.saturating_sub(top).max(1).min(local_lines)
+}
+
 /// System to spawn search overlay when activated
 fn spawn_search_overlay(
     mut commands: Commands,
     state: Res<ScrollbackState>,
     config: Res<SearchOverlayConfig>,
     overlay_query: Query<Entity, With<SearchOverlay>>,
-    window_query: Query<&Window>,
 ) {
-    // Get window dimensions
-    let window = window_query.single();
-    let _window_height = window.height();
-
     if state.search_visible && overlay_query.is_empty() {
         // Spawn search overlay container
         commands
@@ -135,10 +330,72 @@ fn despawn_search_overlay(
     }
 }
 
+/// System to keep the search bar and Search mode in step
+///
+/// Either side can open or close search: Ctrl+Shift+F toggles the bar, and
+/// the mode system enters Search mode or escapes back to Normal.
+fn sync_search_mode(
+    mut state: ResMut<ScrollbackState>,
+    mut matches: ResMut<SearchMatches>,
+    mode_state: Option<ResMut<ModeState>>,
+    config: Res<ScarabConfig>,
+    mut was_visible: Local<bool>,
+) {
+    if let Some(mut mode_state) = mode_state {
+        let in_search_mode = mode_state.current == ScarabMode::Search;
+        if state.search_visible != *was_visible {
+            if state.search_visible && !in_search_mode {
+                mode_state.enter_mode(ScarabMode::Search);
+            } else if !state.search_visible && in_search_mode {
+                mode_state.exit_to_normal();
+            }
+        } else if mode_state.is_changed() && in_search_mode != state.search_visible {
+            state.search_visible = in_search_mode;
+            if !in_search_mode {
+                state.search_input.clear();
+            }
+        }
+    }
+
+    if state.search_visible != *was_visible {
+        *was_visible = state.search_visible;
+        matches.clear();
+        if state.search_visible {
+            matches.editing = true;
+            matches.case_sensitive = config.ui.search_case_sensitive;
+            matches.use_regex = config.ui.search_use_regex;
+        } else {
+            matches.scope = SearchScope::All;
+        }
+    }
+}
+
+/// System to open search over the last command's output from the palette
+fn handle_search_commands(
+    mut commands_selected: EventReader<CommandSelected>,
+    mut state: ResMut<ScrollbackState>,
+    mut matches: ResMut<SearchMatches>,
+    scrollback: Res<ScrollbackBuffer>,
+    ipc: Option<Res<IpcChannel>>,
+) {
+    for event in commands_selected.read() {
+        if event.command_id != SEARCH_LAST_COMMAND {
+            continue;
+        }
+        matches.scope = SearchScope::LastCommand;
+        if state.search_visible {
+            let query = state.search_input.clone();
+            run_search(&query, &mut matches, &scrollback, ipc.as_deref());
+        } else {
+            state.search_visible = true;
+        }
+    }
+}
+
 /// System to update search overlay content
 fn update_search_overlay(
     state: Res<ScrollbackState>,
-    scrollback: Res<ScrollbackBuffer>,
+    matches: Res<SearchMatches>,
     mut input_query: Query<&mut Text, (With<SearchInputBox>, Without<SearchResultsText>)>,
     mut results_query: Query<&mut Text, (With<SearchResultsText>, Without<SearchInputBox>)>,
 ) {
@@ -148,92 +405,184 @@ fn update_search_overlay(
 
     // Update input text
     for mut text in input_query.iter_mut() {
-        **text = format!("{}_", state.search_input); // Add cursor
+        // Show a cursor only while typing
+        let cursor = if matches.editing { "_" } else { "" };
+        **text = format!("{}{}", state.search_input, cursor);
     }
 
     // Update results text
-    if let Some(search_state) = scrollback.search_state() {
-        for mut text in results_query.iter_mut() {
-            if search_state.total_results > 0 {
-                **text = format!(
-                    "{} of {} matches{}{}",
-                    search_state.current_index + 1,
-                    search_state.total_results,
-                    if search_state.case_sensitive {
-                        " [Aa]"
-                    } else {
-                        ""
-                    },
-                    if search_state.use_regex { " [.*]" } else { "" }
-                );
-            } else {
-                **text = format!(
-                    "No matches{}{}",
-                    if search_state.case_sensitive {
-                        " [Aa]"
-                    } else {
-                        ""
-                    },
-                    if search_state.use_regex { " [.*]" } else { "" }
-                );
-            }
-        }
-    } else {
-        for mut text in results_query.iter_mut() {
-            **text = "Type to search".to_string();
+    for mut text in results_query.iter_mut() {
+        **text = matches.status_text();
+    }
+}
+
+/// Run the query in the daemon, or over the local scrollback when offline
+fn run_search(
+    query: &str,
+    matches: &mut SearchMatches,
+    scrollback: &ScrollbackBuffer,
+    ipc: Option<&IpcChannel>,
+) {
+    matches.clear();
+    if query.is_empty() {
+        return;
+    }
+    matches.query = query.to_string();
+
+    if let Some(ipc) = ipc {
+        ipc.send(ControlMessage::ScrollbackSearch {
+            query: query.to_string(),
+            case_sensitive: matches.case_sensitive,
+            use_regex: matches.use_regex,
+            max_results: MAX_SEARCH_RESULTS,
+            scope: matches.scope,
+        });
+        return;
+    }
+
+    // Offline there are no zones or grid to narrow the search to
+
+    match build_search_regex(query, matches.case_sensitive, matches.use_regex) {
+        Ok(regex) => {
+            let lines: Vec<String> = (0..scrollback.line_count())
+                .filter_map(|i| scrollback.get_line(i))
+                .map(|line| line.to_string())
+                .collect();
+            let found = find_matches(&regex, lines.iter().map(String::as_str));
+            let total = found.len();
+            matches.set_results(found, total, scrollback.line_count());
         }
+        Err(e) => matches.error = Some(e.to_string()),
     }
 }
 
-/// System to handle character input in search box
+/// System to handle typing, toggles, and navigation in the search bar
 fn handle_search_input(
-    mut char_events: EventReader<bevy::input::keyboard::KeyboardInput>,
+    mut key_events: EventReader<KeyboardInput>,
     mut state: ResMut<ScrollbackState>,
-    mut scrollback: ResMut<ScrollbackBuffer>,
+    mut matches: ResMut<SearchMatches>,
+    scrollback: Res<ScrollbackBuffer>,
     keys: Res<ButtonInput<KeyCode>>,
-    config: Res<ScarabConfig>,
+    ipc: Option<Res<IpcChannel>>,
 ) {
     if !state.search_visible {
         return;
     }
 
-    for event in char_events.read() {
-        if !event.state.is_pressed() {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    let mut rerun = false;
+    for event in key_events.read() {
+        if !event.state.is_pressed() || ctrl {
             continue;
         }
 
-        match &event.logical_key {
-            bevy::input::keyboard::Key::Character(ref s) => {
-                // Don't add control characters
-                if keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight) {
-                    continue;
+        match (&event.key_code, &event.logical_key) {
+            // Alt+R / Alt+C: toggle regex and case sensitivity
+            (KeyCode::KeyR, _) if alt => {
+                matches.use_regex = !matches.use_regex;
+                rerun = true;
+            }
+            (KeyCode::KeyC, _) if alt => {
+                matches.case_sensitive = !matches.case_sensitive;
+                rerun = true;
+            }
+            // Alt+S: all scrollback, then the last command, then the screen
+            (KeyCode::KeyS, _) if alt => {
+                matches.scope = matches.scope.next();
+                rerun = true;
+            }
+            (KeyCode::Enter | KeyCode::NumpadEnter, _) => {
+                // Enter commits the query, then walks matches
+                matches.editing = false;
+                if shift {
+                    matches.prev();
+                } else {
+                    matches.next();
                 }
-
-                // Add character to search input
+            }
+            (KeyCode::Backspace, _) => {
+                matches.editing = true;
+                state.search_input.pop();
+                rerun = true;
+            }
+            (_, Key::Character(_)) if alt => {}
+            (_, Key::Character(s)) if !matches.editing => match s.as_str() {
+                "n" => matches.next(),
+                "N" => matches.prev(),
+                "/" => matches.editing = true,
+                _ => {}
+            },
+            (_, Key::Character(s)) => {
                 state.search_input.push_str(s);
+                rerun = true;
+            }
+            _ => {}
+        }
+    }
 
-                // Trigger search with user-configured settings
-                if !state.search_input.is_empty() {
-                    scrollback.search(
-                        state.search_input.clone(),
-                        config.ui.search_case_sensitive,
-                        config.ui.search_use_regex,
-                    );
+    if rerun {
+        let query = state.search_input.clone();
+        run_search(&query, &mut matches, &scrollback, ipc.as_deref());
+    }
+}
+
+/// System to apply daemon search results and scrollback text
+fn receive_search_results(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut matches: ResMut<SearchMatches>,
+    mut scrollback: ResMut<ScrollbackBuffer>,
+    ipc: Option<Res<IpcChannel>>,
+) {
+    for event in events.read() {
+        match &event.0 {
+            DaemonMessage::ScrollbackSearchResults {
+                query,
+                scrollback_lines,
+                total,
+                matches: found,
+                error,
+            } => {
+                // Ignore replies to queries the user has already typed past
+                if *query != matches.query {
+                    continue;
                 }
+                matches.set_results(found.clone(), *total as usize, *scrollback_lines as usize);
+                matches.error = error.clone();
             }
-            bevy::input::keyboard::Key::Backspace => {
-                // Remove last character
-                state.search_input.pop();
+            DaemonMessage::ScrollbackLines { start, lines } => {
+                let Some(fetch) = matches.fetch.as_mut() else {
+                    continue;
+                };
+                if *start as usize != fetch.lines.len() {
+                    continue;
+                }
+                fetch.lines.extend(lines.iter().cloned());
+
+                let received = fetch.lines.len();
+                if received < fetch.target && !lines.is_empty() {
+                    if let Some(ipc) = ipc.as_ref() {
+                        ipc.send(ControlMessage::ScrollbackFetch {
+                            start: received as u32,
+                            count: (fetch.target - received) as u32,
+                        });
+                        continue;
+                    }
+                }
 
-                // Re-trigger search or clear if empty
-                if !state.search_input.is_empty() {
-                    scrollback.search(
-                        state.search_input.clone(),
-                        config.ui.search_case_sensitive,
-                        config.ui.search_use_regex,
+                // Mirror the daemon's scrollback so matches line up by index
+                if let Some(fetch) = matches.fetch.take() {
+                    scrollback.clear();
+                    scrollback.push_lines(
+                        fetch
+                            .lines
+                            .iter()
+                            .map(|text| ScrollbackLine::from_text(text))
+                            .collect(),
                     );
-                } else {
-                    scrollback.clear_search();
+                    matches.reveal = true;
                 }
             }
             _ => {}
@@ -241,19 +590,155 @@ fn handle_search_input(
     }
 }
 
+/// System to scroll the selected match into view
+fn reveal_current_match(
+    mut matches: ResMut<SearchMatches>,
+    mut scrollback: ResMut<ScrollbackBuffer>,
+    mut state: ResMut<ScrollbackState>,
+    ipc: Option<Res<IpcChannel>>,
+) {
+    if !matches.reveal || matches.fetch.is_some() {
+        return;
+    }
+    let Some(line) = matches.current_match().map(|m| m.line as usize) else {
+        matches.reveal = false;
+        return;
+    };
+
+    // Matches on the live grid only need the live view
+    if line >= matches.scrollback_lines {
+        matches.reveal = false;
+        scrollback.scroll_to_bottom();
+        state.is_scrolled = false;
+        return;
+    }
+
+    // Pull the daemon's scrollback text before scrolling into it
+    if scrollback.line_count() != matches.scrollback_lines {
+        if let Some(ipc) = ipc {
+            let target = matches.scrollback_lines;
+            matches.fetch = Some(ScrollbackFetch {
+                target,
+                lines: Vec::with_capacity(target),
+            });
+            ipc.send(ControlMessage::ScrollbackFetch {
+                start: 0,
+                count: target as u32,
+            });
+            return;
+        }
+    }
+
+    matches.reveal = false;
+    let top = viewport_top(
+        matches.scrollback_lines,
+        scrollback.line_count(),
+        scrollback.scroll_offset(),
+    );
+    if (top..top + state.lines_per_page).contains(&line) {
+        return;
+    }
+
+    let offset = scroll_offset_for_line(line, scrollback.line_count(), state.lines_per_page);
+    scrollback.scroll_to_bottom();
+    scrollback.scroll_up(offset);
+    state.is_scrolled = !scrollback.is_at_bottom();
+}
+
+/// System to redraw match highlights over the visible rows
+fn render_search_highlights(
+    mut commands: Commands,
+    state: Res<ScrollbackState>,
+    matches: Res<SearchMatches>,
+    scrollback: Res<ScrollbackBuffer>,
+    config: Res<SearchOverlayConfig>,
+    renderer: Option<Res<TextRenderer>>,
+    grids: Query<Entity, With<TerminalGridEntity>>,
+    highlights: Query<Entity, With<SearchHighlight>>,
+    mut last_cell_size: Local<Vec2>,
+) {
+    let Some(renderer) = renderer else {
+        return;
+    };
+    let cell_size = Vec2::new(renderer.cell_width, renderer.cell_height);
+    if !matches.is_changed()
+        && !scrollback.is_changed()
+        && !state.is_changed()
+        && *last_cell_size == cell_size
+    {
+        return;
+    }
+    *last_cell_size = cell_size;
+
+    for entity in highlights.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !state.search_visible || matches.matches.is_empty() {
+        return;
+    }
+
+    let top = viewport_top(
+        matches.scrollback_lines,
+        scrollback.line_count(),
+        scrollback.scroll_offset(),
+    );
+    let bottom = top + state.lines_per_page;
+
+    for grid in grids.iter() {
+        commands.entity(grid).with_children(|parent| {
+            for (i, m) in matches.matches.iter().enumerate() {
+                let line = m.line as usize;
+                if line < top || line >= bottom {
+                    continue;
+                }
+
+                let color = if i == matches.current {
+                    config.current_match_color
+                } else {
+                    config.match_color
+                };
+                let row = (line - top) as f32;
+                parent.spawn((
+                    SearchHighlight,
+                    Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(m.len as f32 * cell_size.x, cell_size.y)),
+                        anchor: Anchor::TopLeft,
+                        ..default()
+                    },
+                    // Translucent, so the matched text stays readable underneath
+                    Transform::from_xyz(
+                        m.col as f32 * cell_size.x,
+                        -row * cell_size.y,
+                        LAYER_TEXT_DECORATIONS,
+                    ),
+                ));
+            }
+        });
+    }
+}
+
 /// Plugin for search overlay functionality
 pub struct SearchOverlayPlugin;
 
 impl Plugin for SearchOverlayPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(SearchOverlayConfig::default())
+            .init_resource::<SearchMatches>()
+            .add_event::<RemoteMessageEvent>()
+            .add_event::<CommandSelected>()
             .add_systems(
                 Update,
                 (
+                    handle_search_commands,
+                    sync_search_mode,
                     spawn_search_overlay,
                     despawn_search_overlay,
-                    update_search_overlay,
                     handle_search_input,
+                    receive_search_results,
+                    reveal_current_match,
+                    update_search_overlay,
+                    render_search_highlights,
                 )
                     .chain(),
             );
@@ -261,3 +746,66 @@ impl Plugin for SearchOverlayPlugin {
         info!("Search overlay plugin initialized");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(line: u32, col: u16) -> SearchMatch {
+        SearchMatch { line, col, len: 3 }
+    }
+
+    #[test]
+    fn test_find_matches_columns() {
+        let regex = build_search_regex("foo", false, false).unwrap();
+        let lines = ["a foo", "日本FOO", "bar"];
+        let found = find_matches(&regex, lines.iter().copied());
+        assert_eq!(found, vec![m(0, 2), m(1, 2)]);
+
+        assert!(build_search_regex("(", true, true).is_err());
+    }
+
+    #[test]
+    fn test_navigation_wraps() {
+        let mut matches = SearchMatches::default();
+        matches.query = "foo".into();
+        matches.set_results(vec![m(0, 0), m(5, 0), m(9, 0)], 3, 10);
+        // Newest match is selected first
+        assert_eq!(matches.current, 2);
+
+        matches.next();
+        assert_eq!(matches.current, 0);
+        matches.prev();
+        assert_eq!(matches.current, 2);
+        assert_eq!(matches.status_text(), "3 of 3 matches");
+    }
+
+    #[test]
+    fn test_status_counts_truncated_results() {
+        let mut matches = SearchMatches::default();
+        matches.query = "foo".into();
+        matches.use_regex = true;
+        matches.set_results(vec![m(7, 0), m(8, 0)], 600, 10);
+        assert_eq!(matches.status_text(), "600 of 600 matches [.*]");
+
+        matches.scope = SearchScope::LastCommand;
+        assert_eq!(
+            matches.status_text(),
+            "600 of 600 matches [last command] [.*]"
+        );
+    }
+
+    #[test]
+    fn test_viewport_mapping() {
+        // Live view: the first grid row follows the daemon's scrollback
+        assert_eq!(viewport_top(100, 0, 0), 100);
+        // Scrolled view uses the synced client buffer
+        assert_eq!(viewport_top(100, 100, 30), 70);
+
+        // Centering a line in a 24-row viewport
+        let offset = scroll_offset_for_line(50, 100, 24);
+        assert_eq!(viewport_top(100, 100, offset), 38);
+        // Lines near the top clamp to the oldest line
+        assert_eq!(scroll_offset_for_line(3, 100, 24), 100);
+    }
+}
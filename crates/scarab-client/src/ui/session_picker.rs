@@ -0,0 +1,526 @@
+//! Session picker shown when the client starts
+//!
+//! Once connected, the client asks the daemon for its sessions. With more
+//! than one session running, a modal lists them with their last attach time
+//! and attached client count instead of silently joining the default one.
+//! Choosing a session attaches this client to it and maps that session's
+//! shared memory as the window's grid; Esc keeps the default session.
+//! Shift+Enter attaches read-only, to watch a session without typing in it.
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use scarab_protocol::{ControlMessage, DaemonMessage, SessionInfo, SessionResponse};
+use shared_memory::ShmemConf;
+
+use crate::integration::SharedMemoryReader;
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::InputSystemSet;
+
+/// Where the startup session choice stands
+#[derive(Debug, Clone, Default)]
+pub enum PickerPhase {
+    /// Waiting for the daemon connection
+    #[default]
+    Connecting,
+    /// `SessionList` sent, waiting for the reply
+    Listing,
+    /// Modal open with the sessions to choose from
+    Choosing {
+        sessions: Vec<SessionInfo>,
+        selected: usize,
+    },
+    /// `SessionAttach` sent for the chosen session
+    Attaching { id: String },
+    /// Attached to the region at `shmem_path`, which the daemon may not
+    /// have mapped yet
+    Remapping { shmem_path: String },
+    /// Nothing left to do
+    Done,
+}
+
+/// State of the startup session picker
+#[derive(Resource, Debug, Default)]
+pub struct SessionPickerState {
+    pub phase: PickerPhase,
+}
+
+impl SessionPickerState {
+    /// Whether the picker owns the keyboard
+    pub fn captures_keys(&self) -> bool {
+        matches!(self.phase, PickerPhase::Choosing { .. })
+    }
+
+    /// Handle the daemon's reply to the startup `SessionList` or `SessionAttach`
+    pub fn handle_response(&mut self, response: &SessionResponse) {
+        match (&self.phase, response) {
+            (PickerPhase::Listing, SessionResponse::List { sessions }) => {
+                self.phase = if sessions.len() > 1 {
+                    PickerPhase::Choosing {
+                        sessions: sort_sessions(sessions.clone()),
+                        selected: 0,
+                    }
+                } else {
+                    PickerPhase::Done
+                };
+            }
+            (
+                PickerPhase::Attaching { id },
+                SessionResponse::Attached {
+                    id: got,
+                    shmem_path,
+                    read_only,
+                },
+            ) if id == got => {
+                if *read_only {
+                    info!("Watching session {} read-only", got);
+                }
+                self.phase = PickerPhase::Remapping {
+                    shmem_path: shmem_path.clone(),
+                };
+            }
+            (
+                PickerPhase::Listing | PickerPhase::Attaching { .. },
+                SessionResponse::Error { message },
+            ) => {
+                warn!("Session selection failed: {}", message);
+                self.phase = PickerPhase::Done;
+            }
+            _ => {}
+        }
+    }
+
+    /// Move the highlighted row by `delta`, wrapping around
+    pub fn move_selection(&mut self, delta: isize) {
+        if let PickerPhase::Choosing { sessions, selected } = &mut self.phase {
+            let len = sessions.len() as isize;
+            *selected = (*selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    /// Attach to the session at `index`, returning the message to send
+    pub fn choose(&mut self, index: usize, read_only: bool) -> Option<ControlMessage> {
+        let PickerPhase::Choosing { sessions, .. } = &self.phase else {
+            return None;
+        };
+        let id = sessions.get(index)?.id.clone();
+        self.phase = PickerPhase::Attaching { id: id.clone() };
+        Some(ControlMessage::SessionAttach { id, read_only })
+    }
+
+    /// Close the picker and stay on the default session
+    pub fn dismiss(&mut self) {
+        if self.captures_keys() {
+            self.phase = PickerPhase::Done;
+        }
+    }
+}
+
+/// Most recently attached sessions first, then by name
+pub fn sort_sessions(mut sessions: Vec<SessionInfo>) -> Vec<SessionInfo> {
+    sessions.sort_by(|a, b| {
+        b.last_attached
+            .cmp(&a.last_attached)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    sessions
+}
+
+/// Human-readable age of a Unix timestamp, e.g. "5m ago"
+pub fn format_last_attached(timestamp: u64, now: u64) -> String {
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Marker for the picker modal
+#[derive(Component)]
+struct SessionPickerUI;
+
+/// A clickable session row
+#[derive(Component)]
+struct SessionRow {
+    index: usize,
+}
+
+/// System to request the session list once the daemon is reachable
+fn request_session_list(ipc: Res<IpcChannel>, mut state: ResMut<SessionPickerState>) {
+    if matches!(state.phase, PickerPhase::Connecting) && ipc.is_connected() {
+        ipc.send(ControlMessage::SessionList);
+        state.phase = PickerPhase::Listing;
+    }
+}
+
+/// System to advance the picker on session replies
+fn receive_session_responses(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut state: ResMut<SessionPickerState>,
+) {
+    for event in events.read() {
+        if let DaemonMessage::Session(response) = &event.0 {
+            state.handle_response(response);
+        }
+    }
+}
+
+/// System for keyboard navigation in the picker
+fn handle_picker_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    ipc: Res<IpcChannel>,
+    mut state: ResMut<SessionPickerState>,
+) {
+    if !state.captures_keys() {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::ArrowUp) || keys.just_pressed(KeyCode::KeyK) {
+        state.move_selection(-1);
+    } else if keys.just_pressed(KeyCode::ArrowDown) || keys.just_pressed(KeyCode::KeyJ) {
+        state.move_selection(1);
+    } else if keys.just_pressed(KeyCode::Escape) {
+        state.dismiss();
+    } else if keys.just_pressed(KeyCode::Enter) {
+        let read_only = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        if let PickerPhase::Choosing { selected, .. } = state.phase {
+            if let Some(msg) = state.choose(selected, read_only) {
+                ipc.send(msg);
+            }
+        }
+    }
+}
+
+/// System to attach to a clicked session row
+fn handle_row_clicks(
+    rows: Query<(&Interaction, &SessionRow), Changed<Interaction>>,
+    ipc: Res<IpcChannel>,
+    mut state: ResMut<SessionPickerState>,
+) {
+    for (interaction, row) in rows.iter() {
+        if *interaction == Interaction::Pressed {
+            if let Some(msg) = state.choose(row.index, false) {
+                ipc.send(msg);
+            }
+        }
+    }
+}
+
+/// First wait between attempts to open a session's region
+const REMAP_INITIAL_DELAY_SECS: f64 = 0.05;
+/// Longest wait between attempts to open a session's region
+const REMAP_MAX_DELAY_SECS: f64 = 2.0;
+/// Attempts before giving up and keeping the current grid
+const REMAP_MAX_ATTEMPTS: u32 = 12;
+
+/// Backoff between attempts to open a session's region
+#[derive(Debug, Default)]
+struct RemapRetry {
+    attempts: u32,
+    next_at: f64,
+}
+
+impl RemapRetry {
+    /// Schedule the next attempt; `false` once attempts run out
+    fn failed(&mut self, now: f64) -> bool {
+        self.attempts += 1;
+        let delay = REMAP_INITIAL_DELAY_SECS * 2f64.powi(self.attempts as i32 - 1);
+        self.next_at = now + delay.min(REMAP_MAX_DELAY_SECS);
+        self.attempts < REMAP_MAX_ATTEMPTS
+    }
+}
+
+/// System to map the chosen session's shared memory as the window's grid
+fn remap_shared_memory(
+    mut state: ResMut<SessionPickerState>,
+    reader: Option<ResMut<SharedMemoryReader>>,
+    time: Res<Time>,
+    mut retry: Local<RemapRetry>,
+) {
+    let shmem_path = match &state.phase {
+        PickerPhase::Remapping { shmem_path } => shmem_path.clone(),
+        _ => return,
+    };
+    let Some(mut reader) = reader else {
+        state.phase = PickerPhase::Done;
+        return;
+    };
+
+    // The default session lives in the region already mapped
+    if reader.shmem.0.get_os_id() != shmem_path.as_str() {
+        let now = time.elapsed_secs_f64();
+        if now < retry.next_at {
+            return;
+        }
+        // The daemon maps the region on its next compositor tick; retry
+        // with backoff until it exists
+        let Ok(shmem) = ShmemConf::new().os_id(&shmem_path).open() else {
+            if !retry.failed(now) {
+                warn!("Session grid at {} never appeared", shmem_path);
+                *retry = RemapRetry::default();
+                state.phase = PickerPhase::Done;
+            }
+            return;
+        };
+        info!("Attached to session grid at {}", shmem_path);
+        *reader = SharedMemoryReader::new(Arc::new(shmem));
+    }
+    *retry = RemapRetry::default();
+    state.phase = PickerPhase::Done;
+}
+
+/// System to draw the picker modal
+fn render_session_picker(
+    mut commands: Commands,
+    state: Res<SessionPickerState>,
+    existing_ui: Query<Entity, With<SessionPickerUI>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in existing_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let PickerPhase::Choosing { sessions, selected } = &state.phase else {
+        return;
+    };
+    let now = unix_now();
+
+    commands
+        .spawn((
+            SessionPickerUI,
+            Node {
+                width: Val::Px(520.0),
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                top: Val::Px(100.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                margin: UiRect {
+                    left: Val::Px(-260.0), // Center with width/2
+                    ..default()
+                },
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            ZIndex(2000), // Above status bar (ZIndex 1000)
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Attach to session"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                },
+            ));
+
+            for (index, session) in sessions.iter().enumerate() {
+                let bg_color = if index == *selected {
+                    Color::srgba(0.3, 0.4, 0.6, 0.9)
+                } else {
+                    Color::srgba(0.2, 0.2, 0.2, 0.5)
+                };
+                let mut clients = match session.attached_clients {
+                    1 => "1 client".to_string(),
+                    n => format!("{} clients", n),
+                };
+                if session.read_only_clients > 0 {
+                    clients.push_str(&format!(" ({} watching)", session.read_only_clients));
+                }
+                if session.read_only_by_default {
+                    clients.push_str(", read-only");
+                }
+
+                parent
+                    .spawn((
+                        SessionRow { index },
+                        Button,
+                        Node {
+                            width: Val::Percent(100.0),
+                            padding: UiRect::all(Val::Px(10.0)),
+                            margin: UiRect::bottom(Val::Px(3.0)),
+                            justify_content: JustifyContent::SpaceBetween,
+                            ..default()
+                        },
+                        BackgroundColor(bg_color),
+                        BorderRadius::all(Val::Px(4.0)),
+                    ))
+                    .with_children(|row| {
+                        row.spawn((
+                            Text::new(session.name.to_string()),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+                        row.spawn((
+                            Text::new(format!(
+                                "{}  ·  {}",
+                                format_last_attached(session.last_attached, now),
+                                clients
+                            )),
+                            TextFont {
+                                font_size: 13.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgba(0.7, 0.7, 0.7, 1.0)),
+                        ));
+                    });
+            }
+
+            parent.spawn((
+                Text::new(
+                    "Up/Down: Navigate  Enter: Attach  Shift+Enter: Watch  Esc: Default session",
+                ),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.5, 0.5, 0.5, 1.0)),
+                Node {
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// Plugin for the startup session picker
+pub struct SessionPickerPlugin;
+
+impl Plugin for SessionPickerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SessionPickerState>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(
+                Update,
+                (
+                    request_session_list.run_if(resource_exists::<IpcChannel>),
+                    receive_session_responses,
+                    handle_picker_keys.run_if(resource_exists::<IpcChannel>),
+                    handle_row_clicks.run_if(resource_exists::<IpcChannel>),
+                    remap_shared_memory,
+                    render_session_picker,
+                )
+                    .chain()
+                    // After the terminal input systems, so the key that
+                    // closes the picker never reaches the shell
+                    .after(InputSystemSet::Daemon),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, last_attached: u64, attached_clients: u32) -> SessionInfo {
+        SessionInfo {
+            id: id.into(),
+            name: id.into(),
+            created_at: 0,
+            last_attached,
+            attached_clients,
+            read_only_clients: 0,
+            read_only_by_default: false,
+            idle_secs: 0,
+        }
+    }
+
+    #[test]
+    fn test_sort_and_format() {
+        let sorted = sort_sessions(vec![info("b", 10, 0), info("c", 50, 1), info("a", 10, 2)]);
+        let ids: Vec<&str> = sorted.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["c", "a", "b"]);
+
+        assert_eq!(format_last_attached(1000, 1030), "just now");
+        assert_eq!(format_last_attached(1000, 1000 + 5 * 60), "5m ago");
+        assert_eq!(format_last_attached(1000, 1000 + 3 * 3600), "3h ago");
+        assert_eq!(format_last_attached(1000, 1000 + 2 * 86_400), "2d ago");
+        // Clock skew never underflows
+        assert_eq!(format_last_attached(2000, 1000), "just now");
+    }
+
+    #[test]
+    fn test_picker_flow() {
+        // A single session is attached silently
+        let mut state = SessionPickerState {
+            phase: PickerPhase::Listing,
+        };
+        state.handle_response(&SessionResponse::List {
+            sessions: vec![info("main", 0, 1)],
+        });
+        assert!(matches!(state.phase, PickerPhase::Done));
+
+        let mut state = SessionPickerState {
+            phase: PickerPhase::Listing,
+        };
+        state.handle_response(&SessionResponse::List {
+            sessions: vec![info("main", 10, 1), info("work", 20, 0)],
+        });
+        assert!(state.captures_keys());
+
+        state.move_selection(-1);
+        let PickerPhase::Choosing { selected, .. } = state.phase else {
+            panic!("picker closed");
+        };
+        assert_eq!(selected, 1);
+
+        let msg = state.choose(selected, true).unwrap();
+        assert!(matches!(
+            msg,
+            ControlMessage::SessionAttach { ref id, read_only: true } if id == "main"
+        ));
+        assert!(!state.captures_keys());
+
+        // Replies for other sessions are ignored
+        state.handle_response(&SessionResponse::Attached {
+            id: "work".into(),
+            shmem_path: "/scarab_shm_v1_work".into(),
+            read_only: false,
+        });
+        assert!(matches!(state.phase, PickerPhase::Attaching { ref id } if id == "main"));
+
+        state.handle_response(&SessionResponse::Attached {
+            id: "main".into(),
+            shmem_path: "/scarab_shm_v1".into(),
+            read_only: true,
+        });
+        assert!(matches!(
+            state.phase,
+            PickerPhase::Remapping { ref shmem_path } if shmem_path == "/scarab_shm_v1"
+        ));
+    }
+
+    #[test]
+    fn test_remap_backoff() {
+        let mut retry = RemapRetry::default();
+        assert!(retry.failed(0.0));
+        assert_eq!(retry.next_at, REMAP_INITIAL_DELAY_SECS);
+        assert!(retry.failed(1.0));
+        assert_eq!(retry.next_at, 1.0 + 2.0 * REMAP_INITIAL_DELAY_SECS);
+
+        // The delay is capped, and attempts eventually run out
+        while retry.failed(10.0) {}
+        assert_eq!(retry.next_at, 10.0 + REMAP_MAX_DELAY_SECS);
+        assert_eq!(retry.attempts, REMAP_MAX_ATTEMPTS);
+    }
+}
@@ -2,6 +2,14 @@
 //!
 //! Provides Bevy resources, components, and systems for rendering
 //! programmable status bars with rich styling and dynamic content.
+//!
+//! Content comes from two places: whole-side `StatusBarUpdate` messages, and
+//! per-plugin segments (`StatusSegment` and `AddStatusItem` from daemon
+//! plugins, or status items from client-side plugins). Segments are ordered
+//! by priority, with higher priorities closer to the bar's edge. When the
+//! bar is too narrow, the lowest-priority segments are hidden first and the
+//! last one that partly fits is cut short. Clicking a plugin's segment opens
+//! that plugin's menu.
 
 use bevy::prelude::*;
 
@@ -21,11 +29,30 @@ pub const DOCK_HEIGHT: f32 = 40.0;
 /// Total height of bottom UI elements
 /// Note: Dock is currently disabled, so this only includes the status bar
 pub const BOTTOM_UI_HEIGHT: f32 = STATUS_BAR_HEIGHT;
+use bevy::window::PrimaryWindow;
+use scarab_config::ScarabConfig;
 use scarab_plugin_api::status_bar::Color as StatusColor;
 use scarab_plugin_api::status_bar::{AnsiColor, RenderItem};
 use scarab_protocol::{DaemonMessage, StatusBarSide as ProtocolStatusBarSide, StatusRenderItem};
-use std::sync::{Arc, Mutex};
-use tokio::sync::broadcast;
+
+use crate::events::StatusSide;
+use crate::ipc::RemoteMessageEvent;
+use crate::ui::link_hints::PluginMenuRequestEvent;
+
+/// Font size of status bar text
+const STATUS_FONT_SIZE: f32 = 14.0;
+
+/// Approximate advance of one status bar character, as a fraction of the font size
+const STATUS_CHAR_WIDTH_RATIO: f32 = 0.6;
+
+/// Columns between adjacent segments
+const SEGMENT_GAP: usize = 1;
+
+/// Narrowest a cut-short segment may be, ellipsis included
+const MIN_SEGMENT_COLUMNS: usize = 4;
+
+/// Default status bar text color (slime green) when no theme is configured
+const DEFAULT_STATUS_FG: Color = Color::srgb(0.66, 0.87, 0.35);
 
 /// Plugin for status bar functionality
 pub struct StatusBarPlugin;
@@ -38,6 +65,8 @@ impl Plugin for StatusBarPlugin {
             .init_resource::<StatusBarFonts>()
             .add_event::<StatusUpdateEvent>()
             .add_event::<TabSwitchEvent>()
+            .add_event::<RemoteMessageEvent>()
+            .add_event::<PluginMenuRequestEvent>()
             .add_systems(Startup, (load_status_bar_fonts, setup_status_bar).chain())
             .add_systems(
                 Update,
@@ -45,6 +74,7 @@ impl Plugin for StatusBarPlugin {
                     receive_status_updates,
                     trigger_status_update,
                     update_status_bar_system,
+                    handle_segment_clicks,
                     handle_tab_switch,
                     update_tab_display,
                 )
@@ -87,10 +117,6 @@ fn load_status_bar_fonts(
     }
 }
 
-/// Resource for daemon message receiver (for status bar IPC)
-#[derive(Resource)]
-pub struct DaemonMessageReceiver(pub Arc<Mutex<broadcast::Receiver<DaemonMessage>>>);
-
 /// Timer resource for triggering status updates
 #[derive(Resource)]
 pub struct StatusUpdateTimer {
@@ -115,6 +141,34 @@ pub struct TabSwitchEvent {
     pub tab_index: usize,
 }
 
+/// A plugin-provided entry in the status bar
+#[derive(Debug, Clone)]
+pub struct StatusSegment {
+    /// Plugin that owns the segment; clicking it opens that plugin's menu
+    pub plugin: String,
+    /// Identifier of the segment within its plugin
+    pub item_id: u64,
+    /// Which side of the bar the segment sits on
+    pub side: StatusSide,
+    /// Higher priorities sit closer to the bar's edge and are hidden last
+    pub priority: i32,
+    /// Styled content
+    pub items: Vec<RenderItem>,
+}
+
+impl StatusSegment {
+    /// Identity and rendered text, for detecting changes
+    fn key(&self) -> (&str, u64, StatusSide, i32, String) {
+        (
+            &self.plugin,
+            self.item_id,
+            self.side,
+            self.priority,
+            render_items_to_text(&self.items),
+        )
+    }
+}
+
 /// Resource holding current status bar state
 ///
 /// Tracks the render items for left and right sections of the status bar
@@ -129,6 +183,10 @@ pub struct StatusBarState {
     pub left_dirty: bool,
     /// Whether right side needs re-rendering
     pub right_dirty: bool,
+    /// Segments added by daemon plugins
+    segments: Vec<StatusSegment>,
+    /// Segments owned by client-side plugins
+    local_segments: Vec<StatusSegment>,
 }
 
 impl StatusBarState {
@@ -157,6 +215,151 @@ impl StatusBarState {
         self.left_dirty = false;
         self.right_dirty = false;
     }
+
+    /// Add a daemon plugin's segment, replacing one with the same id
+    pub fn upsert_segment(&mut self, segment: StatusSegment) {
+        self.mark_dirty(segment.side);
+        match self
+            .segments
+            .iter_mut()
+            .find(|s| s.plugin == segment.plugin && s.item_id == segment.item_id)
+        {
+            Some(existing) => {
+                self.mark_dirty(existing.side);
+                *existing = segment;
+            }
+            None => self.segments.push(segment),
+        }
+    }
+
+    /// Remove a daemon plugin's segment, returning true if it existed
+    pub fn remove_segment(&mut self, plugin: &str, item_id: u64) -> bool {
+        let Some(index) = self
+            .segments
+            .iter()
+            .position(|s| s.plugin == plugin && s.item_id == item_id)
+        else {
+            return false;
+        };
+        let removed = self.segments.remove(index);
+        self.mark_dirty(removed.side);
+        true
+    }
+
+    /// Replace the client-side plugins' segments if they changed
+    pub fn set_local_segments(&mut self, segments: Vec<StatusSegment>) {
+        let unchanged = segments.len() == self.local_segments.len()
+            && segments
+                .iter()
+                .zip(&self.local_segments)
+                .all(|(a, b)| a.key() == b.key());
+        if !unchanged {
+            self.left_dirty = true;
+            self.right_dirty = true;
+            self.local_segments = segments;
+        }
+    }
+
+    /// Segments on one side, in display order from left to right
+    ///
+    /// Higher priorities come first on the left and last on the right, so
+    /// they sit closest to the bar's edge.
+    pub fn side_segments(&self, side: StatusSide) -> Vec<&StatusSegment> {
+        let mut segments: Vec<&StatusSegment> = self
+            .local_segments
+            .iter()
+            .chain(&self.segments)
+            .filter(|s| s.side == side)
+            .collect();
+        match side {
+            StatusSide::Left => segments.sort_by_key(|s| std::cmp::Reverse(s.priority)),
+            StatusSide::Right => segments.sort_by_key(|s| s.priority),
+        }
+        segments
+    }
+
+    fn mark_dirty(&mut self, side: StatusSide) {
+        match side {
+            StatusSide::Left => self.left_dirty = true,
+            StatusSide::Right => self.right_dirty = true,
+        }
+    }
+}
+
+/// Columns given to each segment when `available` columns are free
+///
+/// `segments` holds each segment's width and priority in display order.
+/// Lowest-priority segments are hidden (`None`) first, later ones losing
+/// ties. The last hidden segment comes back cut short if enough room is
+/// left, and a lone segment that still overflows is cut to fit.
+pub fn fit_segments(segments: &[(usize, i32)], available: usize) -> Vec<Option<usize>> {
+    let mut widths: Vec<Option<usize>> = segments.iter().map(|(width, _)| Some(*width)).collect();
+    let total = |widths: &[Option<usize>]| {
+        let shown = widths.iter().flatten().count();
+        widths.iter().flatten().sum::<usize>() + shown.saturating_sub(1) * SEGMENT_GAP
+    };
+
+    let mut last_hidden = None;
+    while total(&widths) > available && widths.iter().flatten().count() > 1 {
+        let lowest = (0..segments.len())
+            .filter(|&i| widths[i].is_some())
+            .min_by_key(|&i| (segments[i].1, std::cmp::Reverse(i)));
+        if let Some(i) = lowest {
+            widths[i] = None;
+            last_hidden = Some(i);
+        }
+    }
+
+    let used = total(&widths);
+    if used > available {
+        for width in widths.iter_mut().flatten() {
+            *width = available;
+        }
+    } else if let Some(i) = last_hidden {
+        let room = available - used;
+        let room = if used > 0 {
+            room.saturating_sub(SEGMENT_GAP)
+        } else {
+            room
+        };
+        if room >= MIN_SEGMENT_COLUMNS {
+            widths[i] = Some(room);
+        }
+    }
+    widths
+}
+
+/// Cut styled text to `columns` characters, ending with an ellipsis
+pub fn truncate_styled(segments: Vec<StyledTextSegment>, columns: usize) -> Vec<StyledTextSegment> {
+    let total: usize = segments.iter().map(|s| s.text.chars().count()).sum();
+    if total <= columns {
+        return segments;
+    }
+
+    let mut remaining = columns.saturating_sub(1);
+    let mut truncated: Vec<StyledTextSegment> = Vec::new();
+    for mut segment in segments {
+        if remaining == 0 {
+            break;
+        }
+        let len = segment.text.chars().count();
+        if len > remaining {
+            segment.text = segment.text.chars().take(remaining).collect();
+        }
+        remaining -= segment.text.chars().count();
+        truncated.push(segment);
+    }
+
+    if columns > 0 {
+        match truncated.last_mut() {
+            Some(last) => last.text.push('…'),
+            None => truncated.push(StyledTextSegment {
+                text: "…".to_string(),
+                ..default()
+            }),
+        }
+    }
+    truncated
 }
 
 /// Resource holding tab state (for terminal sessions/panes)
@@ -179,14 +382,20 @@ impl Default for TabState {
 #[derive(Component)]
 pub struct StatusBarLeft;
 
-/// Marker component for plugin status items text
+/// Marker component for the container of left-side segments
 #[derive(Component)]
 pub struct PluginStatusText;
 
-/// Marker component for the right section of the status bar
+/// Marker component for the container of right-side segments
 #[derive(Component)]
 pub struct StatusBarRight;
 
+/// A rendered status segment; plugin segments open their plugin's menu on click
+#[derive(Component)]
+pub struct StatusSegmentButton {
+    pub plugin: Option<String>,
+}
+
 /// Marker component for the status bar container
 #[derive(Component)]
 pub struct StatusBarContainer;
@@ -206,13 +415,8 @@ pub struct TabLabel {
 /// Creates a horizontal container with left and right text sections.
 /// The status bar is positioned at the bottom of the window.
 fn setup_status_bar(mut commands: Commands, tab_state: Res<TabState>, _fonts: Res<StatusBarFonts>) {
-    // Slime theme colors
+    // Slime theme colors; the themed background is applied on first update
     let status_bar_bg = Color::srgba(0.15, 0.15, 0.18, 0.95); // Dark gray status bar
-    let text_color = Color::srgb(0.66, 0.87, 0.35); // #a8df5a - slime green
-
-    // Use default font - the emoji font is loaded separately and should be
-    // available in the font database for fallback when needed
-    let text_font = TextFont::from_font_size(14.0);
 
     commands
         .spawn((
@@ -245,14 +449,8 @@ fn setup_status_bar(mut commands: Commands, tab_state: Res<TabState>, _fonts: Re
                     StatusBarLeft,
                 ))
                 .with_children(|left_parent| {
-                    // Plugin status items (rendered dynamically)
-                    // Uses emoji font for proper emoji rendering
-                    left_parent.spawn((
-                        Text::new(""),
-                        text_font.clone(),
-                        TextColor(text_color),
-                        PluginStatusText,
-                    ));
+                    // Plugin status segments (rendered dynamically)
+                    left_parent.spawn((segment_row(), PluginStatusText));
 
                     // Tab container
                     left_parent
@@ -301,42 +499,199 @@ fn setup_status_bar(mut commands: Commands, tab_state: Res<TabState>, _fonts: Re
                         });
                 });
 
-            // Right section - show mode indicator
-            parent.spawn((
-                Text::new("NORMAL"),
-                TextFont::from_font_size(14.0),
-                TextColor(text_color),
-                StatusBarRight,
-            ));
+            // Right section - plugin status segments
+            parent.spawn((segment_row(), StatusBarRight));
         });
 }
 
-/// Update status bar content when dirty flags are set
+/// Row layout shared by both segment containers
+fn segment_row() -> Node {
+    Node {
+        display: Display::Flex,
+        flex_direction: FlexDirection::Row,
+        column_gap: Val::Px(STATUS_FONT_SIZE * STATUS_CHAR_WIDTH_RATIO * SEGMENT_GAP as f32),
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+/// Status bar background and default text color from the theme
+fn status_theme(config: Option<&ScarabConfig>) -> (Color, Color) {
+    let default_bg = Color::srgba(0.15, 0.15, 0.18, 0.95); // Dark gray status bar
+    let Some(config) = config else {
+        return (default_bg, DEFAULT_STATUS_FG);
+    };
+
+    let parse = |hex: Option<&String>| hex.and_then(|h| Srgba::hex(h).ok()).map(Color::from);
+    // A step lighter than the terminal so the bar stands apart from the grid
+    let background = parse(config.colors.background.as_ref())
+        .map(|bg| bg.lighter(0.04).with_alpha(0.95))
+        .unwrap_or(default_bg);
+    let foreground = parse(config.colors.foreground.as_ref()).unwrap_or(DEFAULT_STATUS_FG);
+    (background, foreground)
+}
+
+/// Rebuild the status segments when content, theme, tabs, or width change
 ///
-/// Converts RenderItem sequences to Bevy Text with appropriate styling.
+/// Converts RenderItem sequences to styled Bevy text, fitted to the width
+/// left over by the tab labels.
 fn update_status_bar_system(
+    mut commands: Commands,
     mut status: ResMut<StatusBarState>,
-    mut left_query: Query<&mut Text, (With<PluginStatusText>, Without<StatusBarRight>)>,
-    mut right_query: Query<&mut Text, With<StatusBarRight>>,
+    config: Option<Res<ScarabConfig>>,
+    tab_state: Res<TabState>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    left_query: Query<Entity, With<PluginStatusText>>,
+    right_query: Query<Entity, With<StatusBarRight>>,
+    mut containers: Query<&mut BackgroundColor, With<StatusBarContainer>>,
+    mut last_width: Local<f32>,
 ) {
-    // Update left side (plugin status items)
-    if status.left_dirty {
-        if let Ok(mut text) = left_query.get_single_mut() {
-            let content = render_items_to_text(&status.left_items);
-            **text = content;
-        }
+    let width = windows.get_single().map_or(0.0, |w| w.width());
+    let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+    if !status.left_dirty
+        && !status.right_dirty
+        && !config_changed
+        && !tab_state.is_changed()
+        && *last_width == width
+    {
+        return;
     }
+    *last_width = width;
 
-    // Update right side
-    if status.right_dirty {
-        if let Ok(mut text) = right_query.get_single_mut() {
-            **text = render_items_to_text(&status.right_items);
-        }
+    let (background, foreground) = status_theme(config.as_deref());
+    for mut bg in containers.iter_mut() {
+        *bg = BackgroundColor(background);
     }
 
-    // Clear dirty flags after rendering
-    if status.left_dirty || status.right_dirty {
-        status.clear_dirty();
+    // The left-side base content leads, the right-side base content trails,
+    // and both outrank every plugin segment
+    let base = |items: &[RenderItem]| {
+        (!render_items_to_text(items).is_empty()).then(|| (None, items.to_vec(), i32::MAX))
+    };
+    let segments = |side: StatusSide| {
+        status
+            .side_segments(side)
+            .into_iter()
+            .map(|s| (Some(s.plugin.clone()), s.items.clone(), s.priority))
+            .collect::<Vec<_>>()
+    };
+    let left: Vec<_> = base(&status.left_items)
+        .into_iter()
+        .chain(segments(StatusSide::Left))
+        .collect();
+    let right: Vec<_> = segments(StatusSide::Right)
+        .into_iter()
+        .chain(base(&status.right_items))
+        .collect();
+
+    let char_width = STATUS_FONT_SIZE * STATUS_CHAR_WIDTH_RATIO;
+    let tab_columns: usize = tab_state
+        .tabs
+        .iter()
+        .map(|name| name.chars().count() + 3)
+        .sum();
+    // Horizontal padding, plus a gap that keeps the two sides apart
+    let available = ((width - 16.0) / char_width).max(0.0) as usize;
+    let available = available.saturating_sub(tab_columns + 2 * SEGMENT_GAP);
+
+    let styled: Vec<(Option<String>, Vec<StyledTextSegment>, i32)> = left
+        .iter()
+        .chain(&right)
+        .map(|(plugin, items, priority)| {
+            (
+                plugin.clone(),
+                styled_segments(items, foreground),
+                *priority,
+            )
+        })
+        .collect();
+    let sizes: Vec<(usize, i32)> = styled
+        .iter()
+        .map(|(_, text, priority)| (text.iter().map(|t| t.text.chars().count()).sum(), *priority))
+        .collect();
+    let widths = fit_segments(&sizes, available);
+
+    let mut fitted = styled
+        .into_iter()
+        .zip(widths)
+        .map(|((plugin, text, _), width)| width.map(|w| (plugin, truncate_styled(text, w))));
+    let left_fitted: Vec<_> = fitted.by_ref().take(left.len()).flatten().collect();
+    let right_fitted: Vec<_> = fitted.flatten().collect();
+
+    for (container, segments) in [
+        (left_query.get_single(), left_fitted),
+        (right_query.get_single(), right_fitted),
+    ] {
+        let Ok(container) = container else {
+            continue;
+        };
+        commands.entity(container).despawn_descendants();
+        commands.entity(container).with_children(|parent| {
+            for (plugin, text) in segments {
+                spawn_segment(parent, plugin, text);
+            }
+        });
+    }
+
+    status.clear_dirty();
+}
+
+/// Spawn one clickable segment with a text span per style run
+fn spawn_segment(parent: &mut ChildBuilder, plugin: Option<String>, text: Vec<StyledTextSegment>) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::horizontal(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(Color::NONE),
+            BorderRadius::all(Val::Px(3.0)),
+            StatusSegmentButton { plugin },
+        ))
+        .with_children(|button| {
+            button
+                .spawn((Text::default(), TextFont::from_font_size(STATUS_FONT_SIZE)))
+                .with_children(|spans| {
+                    for run in text {
+                        spans.spawn((
+                            TextSpan::new(run.text),
+                            TextFont::from_font_size(STATUS_FONT_SIZE),
+                            TextColor(run.color),
+                        ));
+                    }
+                });
+        });
+}
+
+/// System to open a plugin's menu when its segment is clicked
+fn handle_segment_clicks(
+    mut segments: Query<
+        (&Interaction, &StatusSegmentButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut menu_requests: EventWriter<PluginMenuRequestEvent>,
+) {
+    for (interaction, segment, mut bg) in segments.iter_mut() {
+        let Some(plugin) = &segment.plugin else {
+            continue;
+        };
+        match interaction {
+            Interaction::Pressed => {
+                let position = windows
+                    .get_single()
+                    .ok()
+                    .and_then(|w| w.cursor_position())
+                    .unwrap_or_default();
+                menu_requests.send(PluginMenuRequestEvent {
+                    plugin_id: plugin.clone(),
+                    position,
+                });
+            }
+            Interaction::Hovered => *bg = BackgroundColor(DEFAULT_STATUS_FG.with_alpha(0.15)),
+            Interaction::None => *bg = BackgroundColor(Color::NONE),
+        }
     }
 }
 
@@ -385,29 +740,24 @@ fn update_tab_display(
 
 /// System to receive status bar updates from daemon via IPC
 ///
-/// Processes StatusBarUpdate messages from the daemon and updates the
-/// StatusBarState resource accordingly.
+/// Processes StatusBarUpdate, StatusSegment, AddStatusItem and
+/// RemoveStatusItem messages from the daemon and updates the StatusBarState
+/// resource accordingly. Plain status items carry no side and go on the right.
 fn receive_status_updates(
-    receiver: Option<Res<DaemonMessageReceiver>>,
+    mut events: EventReader<RemoteMessageEvent>,
     mut status: ResMut<StatusBarState>,
 ) {
-    let Some(receiver) = receiver else {
-        return;
-    };
-
-    let mut receiver = receiver.0.lock().unwrap();
-
-    // Process all available messages without blocking
-    loop {
-        match receiver.try_recv() {
-            Ok(DaemonMessage::StatusBarUpdate {
+    for event in events.read() {
+        match &event.0 {
+            DaemonMessage::StatusBarUpdate {
                 window_id: _,
                 side,
                 items,
-            }) => {
+            } => {
                 // Convert protocol items to RenderItems
                 let render_items: Vec<RenderItem> = items
-                    .into_iter()
+                    .iter()
+                    .cloned()
                     .filter_map(convert_protocol_item_to_render_item)
                     .collect();
 
@@ -421,22 +771,50 @@ fn receive_status_updates(
                     }
                 }
             }
-            Ok(_other_message) => {
-                // Other daemon messages - not status bar updates
-                // These are handled elsewhere
-            }
-            Err(broadcast::error::TryRecvError::Empty) => {
-                // No more messages available
-                break;
+            DaemonMessage::AddStatusItem {
+                plugin_name,
+                item_id,
+                label: _,
+                content,
+                priority,
+            } => {
+                status.upsert_segment(StatusSegment {
+                    plugin: plugin_name.clone(),
+                    item_id: *item_id,
+                    side: StatusSide::Right,
+                    priority: *priority,
+                    items: vec![RenderItem::Text(content.clone())],
+                });
             }
-            Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
-                warn!("Status bar receiver lagged, skipped {} messages", skipped);
-                // Continue processing available messages
+            DaemonMessage::StatusSegment {
+                plugin_name,
+                item_id,
+                side,
+                priority,
+                items,
+            } => {
+                status.upsert_segment(StatusSegment {
+                    plugin: plugin_name.clone(),
+                    item_id: *item_id,
+                    side: match side {
+                        ProtocolStatusBarSide::Left => StatusSide::Left,
+                        ProtocolStatusBarSide::Right => StatusSide::Right,
+                    },
+                    priority: *priority,
+                    items: items
+                        .iter()
+                        .cloned()
+                        .filter_map(convert_protocol_item_to_render_item)
+                        .collect(),
+                });
             }
-            Err(broadcast::error::TryRecvError::Closed) => {
-                warn!("Daemon message channel closed");
-                break;
+            DaemonMessage::RemoveStatusItem {
+                plugin_name,
+                item_id,
+            } => {
+                status.remove_segment(plugin_name, *item_id);
             }
+            _ => {}
         }
     }
 }
@@ -512,9 +890,19 @@ impl Default for StyledTextSegment {
 ///
 /// A vector of styled text segments for rendering with Bevy Text
 pub fn render_items_to_styled_text(items: &[RenderItem]) -> Vec<StyledTextSegment> {
+    styled_segments(items, StyledTextSegment::default().color)
+}
+
+/// Convert RenderItems to styled text segments, using `default_fg` for text
+/// with no foreground color and after a reset
+pub fn styled_segments(items: &[RenderItem], default_fg: Color) -> Vec<StyledTextSegment> {
+    let fresh = || StyledTextSegment {
+        color: default_fg,
+        ..default()
+    };
     let mut segments = Vec::new();
-    let mut current_segment = StyledTextSegment::default();
-    let mut current_fg = Color::srgb(0.9, 0.9, 0.9); // Default foreground
+    let mut current_segment = fresh();
+    let mut current_fg = default_fg;
     let mut is_bold = false;
     let mut is_italic = false;
 
@@ -548,14 +936,14 @@ pub fn render_items_to_styled_text(items: &[RenderItem]) -> Vec<StyledTextSegmen
                 // Push current segment if it has content, then change color
                 if !current_segment.text.is_empty() {
                     segments.push(current_segment);
-                    current_segment = StyledTextSegment::default();
+                    current_segment = fresh();
                 }
                 current_fg = color_to_bevy(color);
             }
             RenderItem::ForegroundAnsi(ansi) => {
                 if !current_segment.text.is_empty() {
                     segments.push(current_segment);
-                    current_segment = StyledTextSegment::default();
+                    current_segment = fresh();
                 }
                 current_fg = ansi_color_to_bevy(ansi);
             }
@@ -569,14 +957,14 @@ pub fn render_items_to_styled_text(items: &[RenderItem]) -> Vec<StyledTextSegmen
             RenderItem::Bold => {
                 if !current_segment.text.is_empty() && !current_segment.is_bold {
                     segments.push(current_segment);
-                    current_segment = StyledTextSegment::default();
+                    current_segment = fresh();
                 }
                 is_bold = true;
             }
             RenderItem::Italic => {
                 if !current_segment.text.is_empty() && !current_segment.is_italic {
                     segments.push(current_segment);
-                    current_segment = StyledTextSegment::default();
+                    current_segment = fresh();
                 }
                 is_italic = true;
             }
@@ -589,18 +977,18 @@ pub fn render_items_to_styled_text(items: &[RenderItem]) -> Vec<StyledTextSegmen
             RenderItem::ResetAttributes => {
                 if !current_segment.text.is_empty() {
                     segments.push(current_segment);
-                    current_segment = StyledTextSegment::default();
+                    current_segment = fresh();
                 }
-                current_fg = Color::srgb(0.9, 0.9, 0.9);
+                current_fg = default_fg;
                 is_bold = false;
                 is_italic = false;
             }
             RenderItem::ResetForeground => {
                 if !current_segment.text.is_empty() {
                     segments.push(current_segment);
-                    current_segment = StyledTextSegment::default();
+                    current_segment = fresh();
                 }
-                current_fg = Color::srgb(0.9, 0.9, 0.9);
+                current_fg = default_fg;
             }
             RenderItem::ResetBackground => {
                 // Background reset - no-op since we don't render backgrounds
@@ -858,6 +1246,101 @@ mod tests {
         assert_eq!(state.right_items.len(), 0);
     }
 
+    fn ids(state: &StatusBarState, side: StatusSide) -> Vec<u64> {
+        state
+            .side_segments(side)
+            .iter()
+            .map(|s| s.item_id)
+            .collect()
+    }
+
+    fn segment(plugin: &str, item_id: u64, side: StatusSide, priority: i32) -> StatusSegment {
+        StatusSegment {
+            plugin: plugin.to_string(),
+            item_id,
+            side,
+            priority,
+            items: vec![RenderItem::Text(format!("{}:{}", plugin, item_id))],
+        }
+    }
+
+    #[test]
+    fn test_status_segments() {
+        let mut state = StatusBarState::default();
+        state.upsert_segment(segment("git", 1, StatusSide::Right, 10));
+        state.upsert_segment(segment("clock", 2, StatusSide::Right, 20));
+        state.upsert_segment(segment("mode", 3, StatusSide::Left, 5));
+        state.upsert_segment(segment("host", 4, StatusSide::Left, 50));
+        assert!(state.left_dirty && state.right_dirty);
+
+        // Higher priorities sit at the edges
+        assert_eq!(ids(&state, StatusSide::Left), vec![4, 3]);
+        assert_eq!(ids(&state, StatusSide::Right), vec![1, 2]);
+
+        // Upserting replaces in place, and moving sides dirties both
+        state.clear_dirty();
+        state.upsert_segment(segment("git", 1, StatusSide::Left, 10));
+        assert!(state.left_dirty && state.right_dirty);
+        assert_eq!(ids(&state, StatusSide::Left), vec![4, 1, 3]);
+        assert_eq!(ids(&state, StatusSide::Right), vec![2]);
+
+        state.clear_dirty();
+        assert!(state.remove_segment("clock", 2));
+        assert!(!state.remove_segment("clock", 2));
+        assert!(state.right_dirty && !state.left_dirty);
+
+        // Unchanged local segments don't trigger a redraw
+        state.set_local_segments(vec![segment("local", 9, StatusSide::Right, 0)]);
+        state.clear_dirty();
+        state.set_local_segments(vec![segment("local", 9, StatusSide::Right, 0)]);
+        assert!(!state.left_dirty && !state.right_dirty);
+        assert_eq!(ids(&state, StatusSide::Right), vec![9]);
+    }
+
+    #[test]
+    fn test_fit_segments() {
+        let segments = [(5, 1), (5, 2), (5, 3)];
+        assert_eq!(fit_segments(&segments, 17), vec![Some(5), Some(5), Some(5)]);
+        // The lowest priority goes first, and comes back cut short if it can
+        assert_eq!(fit_segments(&segments, 16), vec![Some(4), Some(5), Some(5)]);
+        assert_eq!(fit_segments(&segments, 12), vec![None, Some(5), Some(5)]);
+        assert_eq!(fit_segments(&segments, 6), vec![None, None, Some(5)]);
+
+        // Ties drop the later segment; a lone segment is cut to fit
+        assert_eq!(fit_segments(&[(5, 1), (5, 1)], 8), vec![Some(5), None]);
+        assert_eq!(fit_segments(&[(10, 0)], 6), vec![Some(6)]);
+        assert_eq!(fit_segments(&[], 6), Vec::<Option<usize>>::new());
+    }
+
+    #[test]
+    fn test_truncate_styled() {
+        let red = Color::srgb(1.0, 0.0, 0.0);
+        let text = styled_segments(
+            &[
+                RenderItem::Foreground(StatusColor::Rgb(255, 0, 0)),
+                RenderItem::Text("hello".to_string()),
+                RenderItem::ResetForeground,
+                RenderItem::Text(" world".to_string()),
+            ],
+            DEFAULT_STATUS_FG,
+        );
+        assert_eq!(text.len(), 2);
+        assert_eq!(text[1].color, DEFAULT_STATUS_FG);
+
+        let cut = truncate_styled(text.clone(), 8);
+        assert_eq!(cut.len(), 2);
+        assert_eq!(cut[0].text, "hello");
+        assert_eq!(cut[0].color, red);
+        assert_eq!(cut[1].text, " w…");
+
+        let cut = truncate_styled(text.clone(), 3);
+        assert_eq!(cut.len(), 1);
+        assert_eq!(cut[0].text, "he…");
+
+        assert_eq!(truncate_styled(text.clone(), 11).len(), 2);
+        assert!(truncate_styled(text, 0).is_empty());
+    }
+
     #[test]
     fn test_convert_protocol_item_to_render_item() {
         // Test Text conversion
@@ -0,0 +1,529 @@
+//! On-screen tab bar
+//!
+//! Mirrors the daemon's tab list (`TabListResponse`, `TabCreated`,
+//! `TabClosed`, `TabSwitched`) as a row of clickable tabs at the top or bottom
+//! of the window. Tabs can show their index, title, and an activity marker
+//! for background tabs that produced output since they were last viewed.
+//!
+//! Progress reported by programs (OSC 9;4, via `ProgressUpdate`) is drawn as
+//! a thin strip along the bottom of the tab. winit has no taskbar progress
+//! API, so the active tab's progress is also prefixed to the window title,
+//! which taskbars and docks display.
+//!
+//! The terminal grid is shifted and shrunk by [`TerminalInsets`] so the bar
+//! never covers terminal rows.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use scarab_config::{ScarabConfig, TabPosition, UiConfig};
+use scarab_protocol::{ControlMessage, DaemonMessage, ProgressState, TabInfo};
+use std::collections::HashMap;
+
+use crate::ipc::{IpcChannel, RemoteMessageEvent};
+use crate::ui::status_bar::STATUS_BAR_HEIGHT;
+
+/// Height of the tab bar in pixels
+pub const TAB_BAR_HEIGHT: f32 = 24.0;
+
+/// How often the tab list is refreshed to pick up background activity
+const TAB_LIST_REFRESH_SECS: f32 = 2.0;
+
+/// Marker shown on background tabs with new output
+const ACTIVITY_MARKER: &str = "●";
+
+/// Height of the progress strip at the bottom of a tab
+const PROGRESS_STRIP_HEIGHT: f32 = 2.0;
+
+/// Space reserved around the terminal grid by window chrome
+///
+/// These are in addition to the status bar (`BOTTOM_UI_HEIGHT`).
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct TerminalInsets {
+    pub top: f32,
+    pub bottom: f32,
+    /// Columns reserved on the right, e.g. by the minimap
+    pub right: f32,
+}
+
+/// Client-side copy of the daemon's tab list
+#[derive(Resource, Debug, Default)]
+pub struct TabBarState {
+    pub tabs: Vec<TabInfo>,
+    /// Progress reported by each tab's panes
+    pub progress: HashMap<u64, ProgressState>,
+}
+
+impl TabBarState {
+    /// Apply a daemon message, returning true if the tab list changed
+    pub fn apply(&mut self, msg: &DaemonMessage) -> bool {
+        match msg {
+            DaemonMessage::TabListResponse { tabs } => {
+                self.tabs = tabs.clone();
+            }
+            DaemonMessage::TabCreated { tab } => {
+                if tab.is_active {
+                    self.set_active(tab.id);
+                }
+                match self.tabs.iter_mut().find(|t| t.id == tab.id) {
+                    Some(existing) => *existing = tab.clone(),
+                    None => self.tabs.push(tab.clone()),
+                }
+            }
+            DaemonMessage::TabClosed { tab_id } => {
+                self.tabs.retain(|t| t.id != *tab_id);
+                self.progress.remove(tab_id);
+            }
+            DaemonMessage::TabSwitched { tab_id } => {
+                self.set_active(*tab_id);
+            }
+            DaemonMessage::ProgressUpdate { tab_id, state } => {
+                if state.is_visible() {
+                    self.progress.insert(*tab_id, *state);
+                } else {
+                    self.progress.remove(tab_id);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Index of the active tab
+    pub fn active_index(&self) -> Option<usize> {
+        self.tabs.iter().position(|t| t.is_active)
+    }
+
+    /// Progress shown for a tab
+    pub fn progress(&self, tab_id: u64) -> ProgressState {
+        self.progress.get(&tab_id).copied().unwrap_or_default()
+    }
+
+    fn set_active(&mut self, tab_id: u64) {
+        for tab in &mut self.tabs {
+            tab.is_active = tab.id == tab_id;
+            if tab.is_active {
+                tab.has_activity = false;
+            }
+        }
+    }
+}
+
+/// Marker component for the tab bar container
+#[derive(Component)]
+pub struct TabBarContainer;
+
+/// A clickable tab in the tab bar
+#[derive(Component)]
+pub struct TabBarItem {
+    pub tab_id: u64,
+}
+
+/// Text shown for a tab, per the tab bar settings
+pub fn tab_label(index: usize, tab: &TabInfo, ui: &UiConfig) -> String {
+    let mut label = match (ui.tab_show_index, ui.tab_show_title) {
+        (true, true) => format!("{}: {}", index + 1, tab.title),
+        (false, true) => tab.title.clone(),
+        // Fall back to the index so a tab is never blank
+        _ => (index + 1).to_string(),
+    };
+
+    if ui.tab_show_activity && tab.has_activity && !tab.is_active {
+        label = format!("{} {}", ACTIVITY_MARKER, label);
+    }
+    label
+}
+
+/// Window title with the progress prefix for `progress`
+///
+/// `previous_prefix` is the prefix added last time, removed before the new
+/// one is applied so titles set elsewhere are kept.
+pub fn progress_title(title: &str, previous_prefix: &str, progress: ProgressState) -> String {
+    let base = title.strip_prefix(previous_prefix).unwrap_or(title);
+    format!("{}{}", progress_prefix(progress), base)
+}
+
+/// Title prefix for a progress state
+pub fn progress_prefix(progress: ProgressState) -> String {
+    match progress {
+        ProgressState::Hidden => String::new(),
+        ProgressState::Normal(p) => format!("[{}%] ", p),
+        ProgressState::Error(p) => format!("[✗ {}%] ", p),
+        ProgressState::Paused(p) => format!("[‖ {}%] ", p),
+        ProgressState::Indeterminate => "[…] ".to_string(),
+    }
+}
+
+/// Insets needed for the tab bar with the given settings
+///
+/// Vertical tab bars are not supported yet; `left` and `right` fall back to
+/// the top.
+pub fn tab_bar_insets(ui: &UiConfig, tab_count: usize) -> TerminalInsets {
+    if !ui.show_tabs || tab_count == 0 {
+        return TerminalInsets::default();
+    }
+
+    match ui.tab_position {
+        TabPosition::Bottom => TerminalInsets {
+            top: 0.0,
+            bottom: TAB_BAR_HEIGHT,
+            ..default()
+        },
+        TabPosition::Top | TabPosition::Left | TabPosition::Right => TerminalInsets {
+            top: TAB_BAR_HEIGHT,
+            bottom: 0.0,
+            ..default()
+        },
+    }
+}
+
+/// System to keep the tab list in sync with the daemon
+fn receive_tab_updates(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut state: ResMut<TabBarState>,
+) {
+    for event in events.read() {
+        if matches!(
+            event.0,
+            DaemonMessage::TabListResponse { .. }
+                | DaemonMessage::TabCreated { .. }
+                | DaemonMessage::TabClosed { .. }
+                | DaemonMessage::TabSwitched { .. }
+                | DaemonMessage::ProgressUpdate { .. }
+        ) {
+            state.apply(&event.0);
+        }
+    }
+}
+
+/// System to request the tab list at startup and periodically afterwards
+fn request_tab_list(
+    time: Res<Time>,
+    config: Option<Res<ScarabConfig>>,
+    ipc: Option<Res<IpcChannel>>,
+    mut timer: Local<Option<Timer>>,
+) {
+    let Some(ipc) = ipc else {
+        return;
+    };
+    let show_tabs = config.as_ref().map_or(true, |c| c.ui.show_tabs);
+
+    let timer = match timer.as_mut() {
+        Some(timer) => timer,
+        None => {
+            // First run: ask right away
+            ipc.send(ControlMessage::TabList);
+            *timer = Some(Timer::from_seconds(
+                TAB_LIST_REFRESH_SECS,
+                TimerMode::Repeating,
+            ));
+            return;
+        }
+    };
+
+    if show_tabs && timer.tick(time.delta()).just_finished() {
+        ipc.send(ControlMessage::TabList);
+    }
+}
+
+/// System to reserve room for the tab bar around the terminal grid
+fn update_terminal_insets(
+    config: Option<Res<ScarabConfig>>,
+    state: Res<TabBarState>,
+    mut insets: ResMut<TerminalInsets>,
+) {
+    let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+    if !state.is_changed() && !config_changed {
+        return;
+    }
+
+    let ui = config.map(|c| c.ui.clone()).unwrap_or_default();
+    // The right inset belongs to the minimap
+    let new_insets = TerminalInsets {
+        right: insets.right,
+        ..tab_bar_insets(&ui, state.tabs.len())
+    };
+    if *insets != new_insets {
+        *insets = new_insets;
+    }
+}
+
+/// System to rebuild the tab bar when tabs or settings change
+fn render_tab_bar(
+    mut commands: Commands,
+    config: Option<Res<ScarabConfig>>,
+    state: Res<TabBarState>,
+    containers: Query<Entity, With<TabBarContainer>>,
+) {
+    let config_changed = config.as_ref().is_some_and(|c| c.is_changed());
+    if !state.is_changed() && !config_changed {
+        return;
+    }
+
+    for entity in containers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let ui = config.map(|c| c.ui.clone()).unwrap_or_default();
+    let insets = tab_bar_insets(&ui, state.tabs.len());
+    if insets == TerminalInsets::default() {
+        return;
+    }
+
+    // Slime theme colors
+    let bar_bg = Color::srgba(0.15, 0.15, 0.18, 0.95);
+    let active_bg = Color::srgb(0.66, 0.87, 0.35); // #a8df5a - slime green
+    let active_fg = Color::srgb(0.12, 0.14, 0.14); // #1e2324 - dark background
+    let inactive_fg = Color::srgb(0.78, 0.76, 0.62); // #c8dba8 - muted green
+    let activity_fg = Color::srgb(0.95, 0.98, 0.55); // #f1fa8c - bright yellow
+    let error_fg = Color::srgb(1.0, 0.33, 0.33); // #ff5555 - red
+
+    let mut bar = Node {
+        position_type: PositionType::Absolute,
+        left: Val::Px(0.0),
+        width: Val::Percent(100.0),
+        height: Val::Px(TAB_BAR_HEIGHT),
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        column_gap: Val::Px(2.0),
+        padding: UiRect::horizontal(Val::Px(4.0)),
+        ..default()
+    };
+    if insets.top > 0.0 {
+        bar.top = Val::Px(0.0);
+    } else {
+        bar.bottom = Val::Px(STATUS_BAR_HEIGHT);
+    }
+
+    commands
+        .spawn((bar, BackgroundColor(bar_bg), ZIndex(1000), TabBarContainer))
+        .with_children(|parent| {
+            for (index, tab) in state.tabs.iter().enumerate() {
+                let text_color = if tab.is_active {
+                    active_fg
+                } else if ui.tab_show_activity && tab.has_activity {
+                    activity_fg
+                } else {
+                    inactive_fg
+                };
+
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(10.0), Val::Px(3.0)),
+                            ..default()
+                        },
+                        BackgroundColor(if tab.is_active {
+                            active_bg
+                        } else {
+                            Color::NONE
+                        }),
+                        BorderRadius::top(Val::Px(3.0)),
+                        TabBarItem { tab_id: tab.id },
+                    ))
+                    .with_children(|item| {
+                        item.spawn((
+                            Text::new(tab_label(index, tab, &ui)),
+                            TextFont::from_font_size(13.0),
+                            TextColor(text_color),
+                        ));
+
+                        let progress = state.progress(tab.id);
+                        if !progress.is_visible() {
+                            return;
+                        }
+                        let strip_color = match progress {
+                            ProgressState::Error(_) => error_fg,
+                            ProgressState::Paused(_) => activity_fg,
+                            // Green would vanish on the active tab's background
+                            _ if tab.is_active => active_fg,
+                            _ => active_bg,
+                        };
+                        // Without a percentage, a dimmer full-width strip
+                        let (width, alpha) = match progress.percent() {
+                            Some(percent) => (percent as f32, 1.0),
+                            None => (100.0, 0.5),
+                        };
+                        item.spawn((
+                            Node {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(0.0),
+                                bottom: Val::Px(0.0),
+                                width: Val::Percent(width),
+                                height: Val::Px(PROGRESS_STRIP_HEIGHT),
+                                ..default()
+                            },
+                            BackgroundColor(strip_color.with_alpha(alpha)),
+                        ));
+                    });
+            }
+        });
+}
+
+/// System to switch tabs when a tab is clicked
+fn handle_tab_clicks(
+    ipc: Option<Res<IpcChannel>>,
+    state: Res<TabBarState>,
+    items: Query<(&Interaction, &TabBarItem), Changed<Interaction>>,
+) {
+    let Some(ipc) = ipc else {
+        return;
+    };
+
+    for (interaction, item) in items.iter() {
+        let already_active = state
+            .tabs
+            .iter()
+            .any(|t| t.id == item.tab_id && t.is_active);
+        if *interaction == Interaction::Pressed && !already_active {
+            ipc.send(ControlMessage::TabSwitch {
+                tab_id: item.tab_id,
+            });
+        }
+    }
+}
+
+/// System to show the active tab's progress in the window title
+fn update_window_progress(
+    state: Res<TabBarState>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut prefix: Local<String>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+
+    let progress = state
+        .tabs
+        .iter()
+        .find(|t| t.is_active)
+        .map(|t| state.progress(t.id))
+        .unwrap_or_default();
+    for mut window in windows.iter_mut() {
+        let title = progress_title(&window.title, &prefix, progress);
+        if window.title != title {
+            window.title = title;
+        }
+    }
+    *prefix = progress_prefix(progress);
+}
+
+/// Plugin for the on-screen tab bar
+pub struct TabBarPlugin;
+
+impl Plugin for TabBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TabBarState>()
+            .init_resource::<TerminalInsets>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(
+                Update,
+                (
+                    request_tab_list,
+                    receive_tab_updates,
+                    update_terminal_insets,
+                    render_tab_bar,
+                    update_window_progress,
+                    handle_tab_clicks,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab(id: u64, title: &str, is_active: bool) -> TabInfo {
+        TabInfo {
+            id,
+            title: title.to_string(),
+            session_id: None,
+            is_active,
+            pane_count: 1,
+            has_activity: false,
+        }
+    }
+
+    #[test]
+    fn test_tab_state_updates() {
+        let mut state = TabBarState::default();
+        state.apply(&DaemonMessage::TabListResponse {
+            tabs: vec![tab(1, "zsh", true), tab(2, "vim", false)],
+        });
+        assert_eq!(state.active_index(), Some(0));
+
+        state.apply(&DaemonMessage::TabSwitched { tab_id: 2 });
+        assert_eq!(state.active_index(), Some(1));
+
+        state.apply(&DaemonMessage::TabCreated {
+            tab: tab(3, "htop", true),
+        });
+        assert_eq!(state.tabs.len(), 3);
+        assert_eq!(state.active_index(), Some(2));
+
+        state.apply(&DaemonMessage::TabClosed { tab_id: 1 });
+        assert_eq!(state.tabs.len(), 2);
+
+        assert!(!state.apply(&DaemonMessage::PaneFocused { pane_id: 1 }));
+    }
+
+    #[test]
+    fn test_tab_progress() {
+        let mut state = TabBarState::default();
+        state.apply(&DaemonMessage::TabListResponse {
+            tabs: vec![tab(1, "cargo", true)],
+        });
+        assert!(state.apply(&DaemonMessage::ProgressUpdate {
+            tab_id: 1,
+            state: ProgressState::Normal(40),
+        }));
+        assert_eq!(state.progress(1), ProgressState::Normal(40));
+
+        state.apply(&DaemonMessage::ProgressUpdate {
+            tab_id: 1,
+            state: ProgressState::Hidden,
+        });
+        assert!(state.progress.is_empty());
+
+        // The title keeps its own text as the prefix changes
+        let title = progress_title("Scarab Terminal", "", ProgressState::Normal(40));
+        assert_eq!(title, "[40%] Scarab Terminal");
+        let title = progress_title(&title, "[40%] ", ProgressState::Error(70));
+        assert_eq!(title, "[✗ 70%] Scarab Terminal");
+        let title = progress_title(&title, "[✗ 70%] ", ProgressState::Hidden);
+        assert_eq!(title, "Scarab Terminal");
+    }
+
+    #[test]
+    fn test_tab_label() {
+        let mut ui = UiConfig::default();
+        let mut background = tab(2, "build", false);
+        background.has_activity = true;
+
+        assert_eq!(tab_label(1, &background, &ui), "● 2: build");
+
+        ui.tab_show_activity = false;
+        assert_eq!(tab_label(1, &background, &ui), "2: build");
+
+        ui.tab_show_index = false;
+        assert_eq!(tab_label(1, &background, &ui), "build");
+
+        ui.tab_show_title = false;
+        assert_eq!(tab_label(1, &background, &ui), "2");
+    }
+
+    #[test]
+    fn test_tab_bar_insets() {
+        let mut ui = UiConfig::default();
+        assert_eq!(tab_bar_insets(&ui, 0), TerminalInsets::default());
+        assert_eq!(tab_bar_insets(&ui, 2).top, TAB_BAR_HEIGHT);
+
+        ui.tab_position = TabPosition::Bottom;
+        assert_eq!(tab_bar_insets(&ui, 2).bottom, TAB_BAR_HEIGHT);
+
+        ui.show_tabs = false;
+        assert_eq!(tab_bar_insets(&ui, 2), TerminalInsets::default());
+    }
+}
@@ -0,0 +1,224 @@
+//! Progress toasts for background tasks of daemon plugins
+//!
+//! Plugins report progress of long-running tasks with `TaskProgress`
+//! messages. Each running task gets a toast in the bottom-right corner with
+//! its title, latest status message and a progress bar; the toast closes
+//! when the daemon reports the task as done, failed or cancelled.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use scarab_protocol::DaemonMessage;
+
+use crate::ipc::RemoteMessageEvent;
+
+/// Latest report of a running task
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskProgress {
+    pub plugin_name: String,
+    pub title: String,
+    pub percent: u8,
+    pub message: String,
+}
+
+/// Running plugin tasks, keyed by task ID so toasts keep their order
+#[derive(Resource, Default)]
+pub struct TaskProgressState {
+    pub tasks: BTreeMap<u64, TaskProgress>,
+}
+
+impl TaskProgressState {
+    /// Apply a daemon message, returning whether it was a progress report
+    pub fn apply(&mut self, message: &DaemonMessage) -> bool {
+        let DaemonMessage::TaskProgress {
+            plugin_name,
+            task_id,
+            title,
+            percent,
+            message,
+            done,
+        } = message
+        else {
+            return false;
+        };
+
+        if *done {
+            if let Some(task) = self.tasks.remove(task_id) {
+                log::debug!(
+                    "Task '{}' of plugin '{}' ended: {}",
+                    task.title,
+                    plugin_name,
+                    message
+                );
+            }
+        } else {
+            self.tasks.insert(
+                *task_id,
+                TaskProgress {
+                    plugin_name: plugin_name.to_string(),
+                    title: title.to_string(),
+                    percent: (*percent).min(100),
+                    message: message.to_string(),
+                },
+            );
+        }
+        true
+    }
+}
+
+/// Marker component for the progress toast container
+#[derive(Component)]
+struct TaskProgressUI;
+
+/// System to receive progress reports from the daemon
+fn receive_progress(
+    mut events: EventReader<RemoteMessageEvent>,
+    mut state: ResMut<TaskProgressState>,
+) {
+    for event in events.read() {
+        state.apply(&event.0);
+    }
+}
+
+/// System to redraw the toasts when progress changes
+fn render_progress(
+    mut commands: Commands,
+    state: Res<TaskProgressState>,
+    existing_ui: Query<Entity, With<TaskProgressUI>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in existing_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if state.tasks.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            TaskProgressUI,
+            Node {
+                width: Val::Px(320.0),
+                position_type: PositionType::Absolute,
+                right: Val::Px(20.0),
+                bottom: Val::Px(40.0), // Clear of the status bar
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ZIndex(1500), // Above status bar (ZIndex 1000), below prompts
+        ))
+        .with_children(|parent| {
+            for task in state.tasks.values() {
+                parent
+                    .spawn((
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            padding: UiRect::all(Val::Px(10.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.95)),
+                        BorderRadius::all(Val::Px(6.0)),
+                    ))
+                    .with_children(|toast| {
+                        toast.spawn((
+                            Text::new(format!("{} ({}%)", task.title, task.percent)),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+
+                        if !task.message.is_empty() {
+                            toast.spawn((
+                                Text::new(task.message.clone()),
+                                TextFont {
+                                    font_size: 12.0,
+                                    ..default()
+                                },
+                                TextColor(Color::srgba(0.7, 0.7, 0.7, 1.0)),
+                                Node {
+                                    margin: UiRect::top(Val::Px(3.0)),
+                                    ..default()
+                                },
+                            ));
+                        }
+
+                        // Progress bar track and fill
+                        toast
+                            .spawn((
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Px(4.0),
+                                    margin: UiRect::top(Val::Px(6.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgba(0.3, 0.3, 0.3, 1.0)),
+                                BorderRadius::all(Val::Px(2.0)),
+                            ))
+                            .with_children(|track| {
+                                track.spawn((
+                                    Node {
+                                        width: Val::Percent(task.percent as f32),
+                                        height: Val::Percent(100.0),
+                                        ..default()
+                                    },
+                                    BackgroundColor(Color::srgba(0.66, 0.87, 0.35, 1.0)),
+                                    BorderRadius::all(Val::Px(2.0)),
+                                ));
+                            });
+                    });
+            }
+        });
+}
+
+/// Plugin for progress toasts of plugin background tasks
+pub struct TaskProgressPlugin;
+
+impl Plugin for TaskProgressPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TaskProgressState>()
+            .add_event::<RemoteMessageEvent>()
+            .add_systems(Update, (receive_progress, render_progress).chain());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(task_id: u64, percent: u8, done: bool) -> DaemonMessage {
+        DaemonMessage::TaskProgress {
+            plugin_name: "indexer".into(),
+            task_id,
+            title: "Index scrollback".into(),
+            percent,
+            message: format!("{}%", percent),
+            done,
+        }
+    }
+
+    #[test]
+    fn test_progress_lifecycle() {
+        let mut state = TaskProgressState::default();
+
+        assert!(state.apply(&progress(2, 10, false)));
+        assert!(state.apply(&progress(1, 40, false)));
+        assert!(state.apply(&progress(2, 60, false)));
+        assert_eq!(state.tasks.keys().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(state.tasks[&2].percent, 60);
+        assert_eq!(state.tasks[&2].message, "60%");
+
+        assert!(state.apply(&progress(2, 100, true)));
+        assert_eq!(state.tasks.len(), 1);
+
+        // A task that ends before reporting never shows a toast
+        assert!(state.apply(&progress(3, 100, true)));
+        assert_eq!(state.tasks.len(), 1);
+
+        assert!(!state.apply(&DaemonMessage::HideModal));
+    }
+}
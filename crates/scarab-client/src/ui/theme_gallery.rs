@@ -0,0 +1,515 @@
+//! Theme gallery, opened with the "Theme: Browse" palette command
+//!
+//! Every installed theme, built-in or imported, is drawn as a card in its
+//! own colors: its name and a sample prompt on its background, with its
+//! sixteen ANSI colors as swatches underneath. Themes are shown a page at a
+//! time. Moving the highlight applies that theme at once, Enter keeps it and
+//! Escape puts back the colors the gallery was opened with.
+
+use bevy::prelude::*;
+use scarab_config::{ColorConfig, ConfigSection, ConfigSectionsChanged, ScarabConfig};
+use scarab_protocol::ControlMessage;
+use scarab_themes::{Theme, ThemeManager};
+
+use crate::ipc::IpcChannel;
+use crate::ratatui_bridge::CommandSelected;
+use crate::InputSystemSet;
+
+/// Palette command that opens the gallery
+pub const THEME_BROWSE_COMMAND: &str = "theme.browse";
+
+/// Cards in a row
+const COLUMNS: usize = 4;
+
+/// Cards on a page
+const PAGE_SIZE: usize = 2 * COLUMNS;
+
+/// Width of a theme card
+const CARD_WIDTH: f32 = 168.0;
+
+/// Side of an ANSI color swatch
+const SWATCH_SIZE: f32 = 16.0;
+
+/// State of the theme gallery
+#[derive(Resource, Debug, Default)]
+pub struct ThemeGalleryState {
+    pub active: bool,
+    /// Installed themes, by name
+    themes: Vec<Theme>,
+    /// Index of the highlighted theme
+    selected: usize,
+    /// Colors from before the gallery opened, put back on Escape
+    original: Option<ColorConfig>,
+}
+
+impl ThemeGalleryState {
+    /// Whether the gallery owns the keyboard
+    pub fn captures_keys(&self) -> bool {
+        self.active
+    }
+
+    /// Show `themes`, highlighting the one `colors` uses
+    pub fn open(&mut self, mut themes: Vec<Theme>, colors: &ColorConfig) {
+        themes.sort_by(|a, b| a.name().cmp(b.name()));
+        self.selected = colors
+            .theme
+            .as_deref()
+            .and_then(|id| themes.iter().position(|theme| theme.id() == id))
+            .unwrap_or(0);
+        self.themes = themes;
+        self.original = Some(colors.clone());
+        self.active = !self.themes.is_empty();
+    }
+
+    /// Move the highlight by `delta` themes, stopping at either end
+    ///
+    /// Returns the newly highlighted theme, or `None` if it didn't move.
+    pub fn move_selection(&mut self, delta: isize) -> Option<&Theme> {
+        if !self.active {
+            return None;
+        }
+        let last = self.themes.len().saturating_sub(1) as isize;
+        let selected = (self.selected as isize + delta).clamp(0, last) as usize;
+        if selected == self.selected {
+            return None;
+        }
+        self.selected = selected;
+        self.themes.get(selected)
+    }
+
+    /// The highlighted theme
+    pub fn highlighted(&self) -> Option<&Theme> {
+        self.themes.get(self.selected)
+    }
+
+    /// Page the highlighted theme is on, from 0
+    pub fn page(&self) -> usize {
+        self.selected / PAGE_SIZE
+    }
+
+    /// Number of pages of themes
+    pub fn page_count(&self) -> usize {
+        self.themes.len().div_ceil(PAGE_SIZE).max(1)
+    }
+
+    /// Close the gallery keeping the highlighted theme, returning its ID
+    pub fn confirm(&mut self) -> Option<String> {
+        if !self.active {
+            return None;
+        }
+        self.active = false;
+        self.original = None;
+        self.highlighted().map(|theme| theme.id().to_string())
+    }
+
+    /// Close the gallery, returning the colors to put back
+    pub fn cancel(&mut self) -> Option<ColorConfig> {
+        if !self.active {
+            return None;
+        }
+        self.active = false;
+        self.original.take()
+    }
+}
+
+/// A theme color as a Bevy color, white if it can't be parsed
+fn theme_color(hex: &str) -> Color {
+    Srgba::hex(hex).map(Color::from).unwrap_or(Color::WHITE)
+}
+
+/// Marker for the gallery modal
+#[derive(Component)]
+struct ThemeGalleryUI;
+
+/// System to open the gallery from the command palette
+fn open_gallery(
+    mut commands_selected: EventReader<CommandSelected>,
+    config: Option<Res<ScarabConfig>>,
+    mut state: ResMut<ThemeGalleryState>,
+) {
+    for event in commands_selected.read() {
+        if event.command_id != THEME_BROWSE_COMMAND {
+            continue;
+        }
+        let Some(config) = config.as_deref() else {
+            continue;
+        };
+        let mut manager = ThemeManager::new();
+        if let Err(e) = manager.initialize() {
+            warn!("Failed to load user themes: {}", e);
+        }
+        let themes = manager.all_themes().into_iter().cloned().collect();
+        state.open(themes, &config.colors);
+    }
+}
+
+/// System for moving through the gallery, applying each theme highlighted
+fn handle_gallery_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    ipc: Res<IpcChannel>,
+    config: Option<ResMut<ScarabConfig>>,
+    mut state: ResMut<ThemeGalleryState>,
+    mut changed_events: EventWriter<ConfigSectionsChanged>,
+) {
+    if !state.captures_keys() {
+        return;
+    }
+    let Some(mut config) = config else {
+        return;
+    };
+
+    let delta = if keys.just_pressed(KeyCode::ArrowLeft) {
+        -1
+    } else if keys.just_pressed(KeyCode::ArrowRight) {
+        1
+    } else if keys.just_pressed(KeyCode::ArrowUp) {
+        -(COLUMNS as isize)
+    } else if keys.just_pressed(KeyCode::ArrowDown) {
+        COLUMNS as isize
+    } else if keys.just_pressed(KeyCode::PageUp) {
+        -(PAGE_SIZE as isize)
+    } else if keys.just_pressed(KeyCode::PageDown) {
+        PAGE_SIZE as isize
+    } else {
+        0
+    };
+
+    if delta != 0 {
+        if let Some(theme) = state.move_selection(delta) {
+            theme.apply_to(&mut config.colors);
+            changed_events.send(ConfigSectionsChanged {
+                sections: vec![ConfigSection::Colors],
+            });
+        }
+    } else if keys.just_pressed(KeyCode::Escape) {
+        if let Some(original) = state.cancel() {
+            config.colors = original;
+            changed_events.send(ConfigSectionsChanged {
+                sections: vec![ConfigSection::Colors],
+            });
+        }
+    } else if keys.just_pressed(KeyCode::Enter) {
+        if let Some(id) = state.confirm() {
+            // The theme plugin makes it the active theme and sends it to
+            // every client
+            ipc.send(ControlMessage::CommandSelected {
+                id: format!("theme:apply:{}", id),
+            });
+        }
+    }
+}
+
+/// System to draw the gallery's current page
+fn render_theme_gallery(
+    mut commands: Commands,
+    state: Res<ThemeGalleryState>,
+    existing_ui: Query<Entity, With<ThemeGalleryUI>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in existing_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !state.active {
+        return;
+    }
+
+    let page = state.page();
+    let start = page * PAGE_SIZE;
+    let end = (start + PAGE_SIZE).min(state.themes.len());
+    let width = COLUMNS as f32 * (CARD_WIDTH + 8.0) + 16.0;
+
+    commands
+        .spawn((
+            ThemeGalleryUI,
+            Node {
+                width: Val::Px(width),
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                top: Val::Px(80.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                margin: UiRect {
+                    left: Val::Px(-width / 2.0),
+                    ..default()
+                },
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            ZIndex(2000),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                })
+                .with_children(|header| {
+                    header.spawn((
+                        Text::new("Themes"),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                    header.spawn((
+                        Text::new(format!("Page {} of {}", page + 1, state.page_count())),
+                        TextFont {
+                            font_size: 13.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgba(0.7, 0.7, 0.7, 1.0)),
+                    ));
+                });
+
+            parent
+                .spawn(Node {
+                    width: Val::Percent(100.0),
+                    flex_wrap: FlexWrap::Wrap,
+                    ..default()
+                })
+                .with_children(|grid| {
+                    for (index, theme) in state.themes[start..end].iter().enumerate() {
+                        spawn_theme_card(grid, theme, start + index == state.selected);
+                    }
+                });
+
+            let description = state
+                .highlighted()
+                .map(|theme| theme.metadata.description.clone())
+                .unwrap_or_default();
+            parent.spawn((
+                Text::new(description),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.8, 0.8, 0.8, 1.0)),
+                Node {
+                    margin: UiRect::top(Val::Px(6.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                Text::new("Arrows: Navigate  PgUp/PgDn: Page  Enter: Keep  Esc: Revert"),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.5, 0.5, 0.5, 1.0)),
+                Node {
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// A card showing `theme` in its own colors
+fn spawn_theme_card(parent: &mut ChildBuilder, theme: &Theme, highlighted: bool) {
+    let colors = &theme.colors;
+    let foreground = theme_color(&colors.foreground);
+    let palette = &colors.palette;
+    let border = if highlighted {
+        theme_color(&colors.cursor)
+    } else {
+        Color::srgba(0.3, 0.3, 0.3, 1.0)
+    };
+
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(CARD_WIDTH),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                margin: UiRect::all(Val::Px(4.0)),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(theme_color(&colors.background)),
+            BorderColor(border),
+            BorderRadius::all(Val::Px(4.0)),
+        ))
+        .with_children(|card| {
+            card.spawn((
+                Text::new(theme.name().to_string()),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(foreground),
+            ));
+
+            card.spawn((
+                Text::default(),
+                TextFont::from_font_size(12.0),
+                Node {
+                    margin: UiRect::vertical(Val::Px(4.0)),
+                    ..default()
+                },
+            ))
+            .with_children(|spans| {
+                for (text, color) in [
+                    ("~/src ", &palette.blue),
+                    ("main ", &palette.magenta),
+                    ("$ ", &colors.foreground),
+                    ("ls ", &palette.green),
+                    ("-la", &palette.yellow),
+                ] {
+                    spans.spawn((
+                        TextSpan::new(text),
+                        TextFont::from_font_size(12.0),
+                        TextColor(theme_color(color)),
+                    ));
+                }
+            });
+
+            let rows = [
+                [
+                    &palette.black,
+                    &palette.red,
+                    &palette.green,
+                    &palette.yellow,
+                    &palette.blue,
+                    &palette.magenta,
+                    &palette.cyan,
+                    &palette.white,
+                ],
+                [
+                    &palette.bright_black,
+                    &palette.bright_red,
+                    &palette.bright_green,
+                    &palette.bright_yellow,
+                    &palette.bright_blue,
+                    &palette.bright_magenta,
+                    &palette.bright_cyan,
+                    &palette.bright_white,
+                ],
+            ];
+            for row in rows {
+                card.spawn(Node::default()).with_children(|swatches| {
+                    for color in row {
+                        swatches.spawn((
+                            Node {
+                                width: Val::Px(SWATCH_SIZE),
+                                height: Val::Px(SWATCH_SIZE),
+                                ..default()
+                            },
+                            BackgroundColor(theme_color(color)),
+                        ));
+                    }
+                });
+            }
+        });
+}
+
+/// Plugin for the theme gallery
+pub struct ThemeGalleryPlugin;
+
+impl Plugin for ThemeGalleryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ThemeGalleryState>()
+            .add_event::<CommandSelected>()
+            .add_event::<ConfigSectionsChanged>()
+            .add_systems(
+                Update,
+                (
+                    // Keys first, so the Enter that picked the palette
+                    // command doesn't also close the gallery
+                    handle_gallery_keys.run_if(resource_exists::<IpcChannel>),
+                    open_gallery,
+                    render_theme_gallery,
+                )
+                    .chain()
+                    // After the terminal input systems, so the key that
+                    // closes the gallery never reaches the shell
+                    .after(InputSystemSet::Daemon),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gallery(count: usize) -> ThemeGalleryState {
+        let mut manager = ThemeManager::new();
+        manager.initialize().ok();
+        let themes: Vec<Theme> = manager
+            .all_themes()
+            .into_iter()
+            .take(count)
+            .cloned()
+            .collect();
+        assert_eq!(themes.len(), count);
+
+        let colors = ColorConfig {
+            theme: None,
+            ..Default::default()
+        };
+        let mut state = ThemeGalleryState::default();
+        state.open(themes, &colors);
+        state
+    }
+
+    #[test]
+    fn test_gallery_paging() {
+        let mut state = gallery(10);
+        assert!(state.captures_keys());
+        assert_eq!(state.page_count(), 2);
+        assert_eq!(state.page(), 0);
+
+        assert!(state.move_selection(-1).is_none());
+        state.move_selection(PAGE_SIZE as isize);
+        assert_eq!(state.page(), 1);
+        // The highlight stops at the last theme
+        state.move_selection(PAGE_SIZE as isize);
+        assert_eq!(state.selected, 9);
+        assert!(state.move_selection(1).is_none());
+    }
+
+    #[test]
+    fn test_gallery_highlights_current_theme() {
+        let mut manager = ThemeManager::new();
+        manager.initialize().ok();
+        let themes: Vec<Theme> = manager.all_themes().into_iter().cloned().collect();
+        let colors = ColorConfig {
+            theme: Some("nord".to_string()),
+            ..Default::default()
+        };
+
+        let mut state = ThemeGalleryState::default();
+        state.open(themes, &colors);
+        assert_eq!(state.highlighted().map(Theme::id), Some("nord"));
+    }
+
+    #[test]
+    fn test_gallery_confirm_and_cancel() {
+        let colors = ColorConfig {
+            theme: None,
+            foreground: Some("#123456".to_string()),
+            ..Default::default()
+        };
+
+        let mut state = gallery(3);
+        state.open(state.themes.clone(), &colors);
+        state.move_selection(1);
+        let expected = state.highlighted().map(|theme| theme.id().to_string());
+        assert_eq!(state.confirm(), expected);
+        assert!(!state.captures_keys());
+        assert!(state.cancel().is_none());
+
+        state.open(state.themes.clone(), &colors);
+        state.move_selection(2);
+        let original = state.cancel().unwrap();
+        assert_eq!(original.foreground.as_deref(), Some("#123456"));
+        assert!(!state.captures_keys());
+    }
+}
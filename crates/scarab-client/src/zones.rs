@@ -12,6 +12,7 @@ use bevy::input::keyboard::KeyCode;
 use bevy::input::mouse::MouseButton;
 use bevy::input::ButtonInput;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use scarab_protocol::{CommandBlock, DaemonMessage, SemanticZone, ZoneType};
 
 /// Resource storing semantic zones from the daemon
@@ -102,7 +103,7 @@ pub fn receive_zone_updates(
                 println!("Received {} semantic zones from daemon", new_zones.len());
                 zones.zones = new_zones.clone();
             }
-            DaemonMessage::CommandBlocksUpdate { blocks } => {
+            DaemonMessage::CommandBlocksUpdate { blocks, .. } => {
                 println!("Received {} command blocks from daemon", blocks.len());
                 zones.command_blocks = blocks.clone();
             }
@@ -159,7 +160,7 @@ pub fn render_zone_indicators(
 }
 
 /// Format duration for display
-fn format_duration(seconds: f64) -> String {
+pub(crate) fn format_duration(seconds: f64) -> String {
     if seconds < 60.0 {
         format!("{:.1}s", seconds)
     } else if seconds < 3600.0 {
@@ -229,7 +230,7 @@ fn extract_zone_text(zone: &SemanticZone, scrollback: &ScrollbackBuffer) -> Stri
 /// When clicking in a zone, select that entire zone for copying
 pub fn handle_zone_selection(
     mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
+    windows: Query<&Window, With<PrimaryWindow>>,
     zones: Res<SemanticZones>,
     mut select_events: EventWriter<SelectZoneEvent>,
     metrics: Res<scarab_protocol::TerminalMetrics>,
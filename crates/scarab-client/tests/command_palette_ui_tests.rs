@@ -18,7 +18,7 @@ mod harness;
 use bevy::input::keyboard::KeyCode;
 use bevy::prelude::*;
 use harness::HeadlessTestHarness;
-use scarab_client::ipc::IpcChannel;
+use scarab_client::ipc::{DaemonPaths, IpcChannel};
 use scarab_client::ui::command_palette::{
     Command, CommandExecutedEvent, CommandPalettePlugin, CommandPaletteState, CommandRegistry,
     ShowRemoteModalEvent,
@@ -37,7 +37,7 @@ fn create_mock_ipc_resource() -> IpcChannel {
     // For headless tests, we create a minimal IPC channel that won't actually connect
     // This is safe because tests don't actually execute the command actions
     // Note: This will fail to connect, but that's fine for UI state testing
-    IpcChannel::new().unwrap_or_else(|_| {
+    IpcChannel::new(DaemonPaths::default().0.socket).unwrap_or_else(|_| {
         // If connection fails (expected in headless tests), we still need the resource
         // The tests focus on state management, not actual IPC communication
         panic!("IPC not available in headless test environment - this is expected")
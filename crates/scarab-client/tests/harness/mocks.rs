@@ -270,7 +270,10 @@ impl MockSharedState {
             error_mode: 0,
             cursor_x: self.cursor_x,
             cursor_y: self.cursor_y,
-            _padding2: [0; 2],
+            cursor_style: 0,
+            _padding2: [0; 1],
+            damage_sequence: 0,
+            damage_rows: [0; scarab_protocol::DAMAGE_WORDS],
             cells,
         }
     }
@@ -74,6 +74,10 @@ impl ColorPalette {
         let resolver = ThemeResolver::new();
         let mut config = ColorConfig {
             theme: Some(theme_name.to_string()),
+            light_theme: None,
+            dark_theme: None,
+            pane_themes: Vec::new(),
+            custom_themes: Default::default(),
             foreground: None,
             background: None,
             cursor: None,
@@ -82,6 +86,7 @@ impl ColorPalette {
             palette: scarab_config::ColorPalette::default(),
             opacity: 1.0,
             dim_opacity: 0.7,
+            minimum_contrast: 1.0,
         };
         resolver.resolve(&mut config)?;
         Self::from_config(&config)
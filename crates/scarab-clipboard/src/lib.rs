@@ -9,13 +9,18 @@
 //!   - Separate PRIMARY and CLIPBOARD selections maintained
 //! - Paste confirmation for large/multiline content
 //! - Bracket paste mode for shell safety
+//!
+//! Copy, paste and the visual modes are commands. Ctrl+Shift+L copies the
+//! current line by default; while a selection is active, `y` yanks it and
+//! Escape cancels it.
 
 use async_trait::async_trait;
 use parking_lot::Mutex;
 use regex::Regex;
 use scarab_plugin_api::{
+    key_tables::{KeyCode, KeyCombo, KeyModifiers},
     types::{ModalItem, OverlayStyle, RemoteCommand},
-    Action, Plugin, PluginContext, PluginMetadata, Result,
+    Action, Plugin, PluginContext, PluginKeyBinding, PluginMetadata, Result,
 };
 
 mod clipboard;
@@ -382,74 +387,33 @@ impl ClipboardPlugin {
         // Primary selection is Linux-specific
     }
 
-    /// Handle keybindings
-    fn handle_keybinding(&self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
+    /// Handle a key while a selection is active
+    fn handle_selection_key(&self, key: KeyCombo, ctx: &PluginContext) -> Result<Action> {
         let mut state = self.state.lock();
-
-        // Ctrl+Shift+C (Copy) - 0x03 with modifiers
-        if input == [0x03] && state.selection.active {
-            return self.handle_copy(ctx, &mut state, ClipboardType::Standard);
-        }
-
-        // Ctrl+Shift+V (Paste) - 0x16 with modifiers
-        if input == [0x16] {
-            return self.handle_paste(ctx, &mut state, ClipboardType::Standard);
-        }
-
-        // Ctrl+Shift+L (Copy entire line) - 0x0C with modifiers
-        if input == [0x0C] {
-            let (_cursor_x, cursor_y) = ctx.get_cursor();
-            let (cols, _) = ctx.get_size();
-
-            state.selection.start(0, cursor_y, SelectionMode::Line);
-            state.selection.update(cols - 1, cursor_y);
-
-            return self.handle_copy(ctx, &mut state, ClipboardType::Standard);
-        }
-
-        // Visual mode activation
-        // 'v' - Character mode
-        if input == [b'v'] && !state.selection.active {
-            self.start_selection(ctx, &mut state, SelectionMode::Character);
-            return Ok(Action::Modify(Vec::new()));
-        }
-
-        // 'V' - Line mode (Shift+v)
-        if input == [b'V'] && !state.selection.active {
-            self.start_selection(ctx, &mut state, SelectionMode::Line);
-            return Ok(Action::Modify(Vec::new()));
+        if !state.selection.active {
+            return Ok(Action::Continue);
         }
 
-        // Ctrl+V - Block mode
-        if input == [0x16] && !state.selection.active {
-            self.start_selection(ctx, &mut state, SelectionMode::Block);
+        // Escape - Cancel selection
+        if key == KeyCombo::key(KeyCode::Escape) {
+            state.selection.clear();
+            ctx.queue_command(RemoteCommand::ClearOverlays { id: Some(1000) });
+            log::info!("Cancelled selection");
             return Ok(Action::Modify(Vec::new()));
         }
 
-        // Handle selection movement (when active)
-        if state.selection.active {
-            // Escape - Cancel selection
-            if input == [0x1b] {
-                state.selection.clear();
-                ctx.queue_command(RemoteCommand::ClearOverlays { id: Some(1000) });
-                log::info!("Cancelled selection");
-                return Ok(Action::Modify(Vec::new()));
-            }
+        // 'y' - Yank (copy) and exit selection
+        if key == KeyCombo::key(KeyCode::KeyY) {
+            // Auto-copy to primary selection on Linux before copying to standard clipboard
+            self.auto_copy_to_primary(ctx, &state);
 
-            // 'y' - Yank (copy) and exit selection
-            if input == [b'y'] {
-                // Auto-copy to primary selection on Linux before copying to standard clipboard
-                self.auto_copy_to_primary(ctx, &state);
-
-                let result = self.handle_copy(ctx, &mut state, ClipboardType::Standard);
-                ctx.queue_command(RemoteCommand::ClearOverlays { id: Some(1000) });
-                return result;
-            }
-
-            // Arrow keys would be handled here in a full implementation
-            // For now, we'll let them pass through
+            let result = self.handle_copy(ctx, &mut state, ClipboardType::Standard);
+            ctx.queue_command(RemoteCommand::ClearOverlays { id: Some(1000) });
+            return result;
         }
 
+        // Arrow keys would be handled here in a full implementation
+        // For now, we'll let them pass through
         Ok(Action::Continue)
     }
 }
@@ -491,17 +455,17 @@ impl Plugin for ClipboardPlugin {
             ModalItem {
                 id: "clipboard.visual_character".to_string(),
                 label: "Visual Character Mode".to_string(),
-                description: Some("Start character-wise selection (v)".to_string()),
+                description: Some("Start character-wise selection".to_string()),
             },
             ModalItem {
                 id: "clipboard.visual_line".to_string(),
                 label: "Visual Line Mode".to_string(),
-                description: Some("Start line-wise selection (V)".to_string()),
+                description: Some("Start line-wise selection".to_string()),
             },
             ModalItem {
                 id: "clipboard.visual_block".to_string(),
                 label: "Visual Block Mode".to_string(),
-                description: Some("Start block selection (Ctrl+V)".to_string()),
+                description: Some("Start block selection".to_string()),
             },
             ModalItem {
                 id: "clipboard.toggle_bracket_mode".to_string(),
@@ -511,8 +475,19 @@ impl Plugin for ClipboardPlugin {
         ]
     }
 
-    async fn on_input(&mut self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
-        self.handle_keybinding(input, ctx)
+    /// Ctrl+Shift+L copies the current line
+    ///
+    /// Copy and paste have no default key, since Ctrl+Shift+C and
+    /// Ctrl+Shift+V are the client's.
+    fn get_keybindings(&self) -> Vec<PluginKeyBinding> {
+        vec![PluginKeyBinding::new(
+            KeyCombo::new(KeyCode::KeyL, KeyModifiers::CTRL | KeyModifiers::SHIFT),
+            "clipboard.copy_line",
+        )]
+    }
+
+    async fn on_key(&mut self, key: KeyCombo, ctx: &PluginContext) -> Result<Action> {
+        self.handle_selection_key(key, ctx)
     }
 
     async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
@@ -0,0 +1,370 @@
+//! Scarab Config CLI
+//!
+//! Checks config files, prints the configuration Scarab will run with, and
+//! reads or changes settings of the running daemon
+
+use scarab_config::prelude::*;
+use scarab_config::profiles;
+use scarab_protocol::{ControlMessage, DaemonMessage, MAX_MESSAGE_SIZE};
+use std::io::{BufRead, IsTerminal, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+/// How long to wait for the daemon to answer
+const DAEMON_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn main() {
+    match run() {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Run the command, returning whether the config is free of errors
+fn run() -> anyhow::Result<bool> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(command) = args.first() else {
+        print_usage();
+        return Ok(true);
+    };
+
+    let json = args.iter().any(|arg| arg == "--json");
+    let file = args[1..]
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from);
+
+    match command.as_str() {
+        "check" => cmd_check(file),
+        "show" => cmd_show(file, json),
+        "migrate" => cmd_migrate(file, args.iter().any(|arg| arg == "--force")),
+        "upgrade" => cmd_upgrade(file),
+        "get" => match args.get(1) {
+            Some(key) => cmd_get(key),
+            None => {
+                eprintln!("Usage: scarab-config get <KEY>");
+                Ok(false)
+            }
+        },
+        "set" => {
+            let persist = args.iter().any(|arg| arg == "--persist");
+            let operands: Vec<&String> = args[1..]
+                .iter()
+                .filter(|arg| !arg.starts_with("--"))
+                .collect();
+            match operands.as_slice() {
+                [key, value] => cmd_set(key, value, persist),
+                _ => {
+                    eprintln!("Usage: scarab-config set [--persist] <KEY> <VALUE>");
+                    Ok(false)
+                }
+            }
+        }
+        "hash-password" => cmd_hash_password(),
+        "help" | "--help" | "-h" => {
+            print_usage();
+            Ok(true)
+        }
+        _ => {
+            eprintln!("Unknown command: {}", command);
+            print_usage();
+            Ok(false)
+        }
+    }
+}
+
+fn print_usage() {
+    println!(
+        r#"Scarab Config
+
+USAGE:
+    scarab-config <COMMAND> [FILE]
+
+COMMANDS:
+    check [FILE]          Check a config for unknown keys, type errors and
+                          invalid values. Without FILE, checks the global
+                          config, profiles.toml and the project config.
+    show [--json] [FILE]  Print the effective configuration: FILE (or the
+                          global config) with matching profiles and the
+                          project config applied
+    migrate [--force] [FILE]
+                          Convert a config.toml (default
+                          ~/.config/scarab/config.toml) to a config.fsx
+                          beside it. Settings config.fsx does not read are
+                          kept as comments. --force overwrites an existing
+                          config.fsx.
+    upgrade [FILE]        Move settings in a config.toml (default
+                          ~/.config/scarab/config.toml) from an older
+                          layout to their current keys, keeping the old
+                          file as config.toml.v<N>.bak
+    get <KEY>             Print a setting of the running daemon, such as
+                          font.size, as TOML
+    set [--persist] <KEY> <VALUE>
+                          Change a setting of the running daemon. VALUE is
+                          TOML, or a bare string. --persist also writes it
+                          to config.toml.
+    hash-password         Read a password, from the terminal without
+                          echoing it or else from the first line of stdin,
+                          and print its hash for
+                          sessions.lock_password_hash
+    help                  Show this help message
+
+For check and show, FILE defaults to ~/.config/scarab/config.fsx, or
+config.toml if there is no config.fsx."#
+    );
+}
+
+fn config_dir() -> PathBuf {
+    scarab_platform::Paths::from_env().config_dir
+}
+
+/// The config the daemon and client load
+fn global_config_path() -> PathBuf {
+    let dir = config_dir();
+    let fsx = dir.join("config.fsx");
+    if fsx.exists() {
+        fsx
+    } else {
+        dir.join("config.toml")
+    }
+}
+
+fn cmd_check(file: Option<PathBuf>) -> anyhow::Result<bool> {
+    let paths = match file {
+        Some(path) => vec![path],
+        None => {
+            let global = global_config_path();
+            let mut paths = vec![global.clone()];
+            if let Some(dir) = global.parent() {
+                paths.push(profiles::profiles_file(dir));
+            }
+            if let Some(project) = ConfigLoader::find_local_path(&std::env::current_dir()?) {
+                paths.push(project);
+            }
+            paths.retain(|path| path.exists());
+            if paths.is_empty() {
+                println!("No config files found; Scarab uses its defaults");
+                return Ok(true);
+            }
+            paths
+        }
+    };
+
+    let mut ok = true;
+    for path in paths {
+        let report = check_file(&path)?;
+        print_report(&report);
+        ok &= report.errors() == 0;
+    }
+    Ok(ok)
+}
+
+fn print_report(report: &CheckReport) {
+    let path = report.path.display();
+    for diagnostic in &report.diagnostics {
+        match diagnostic.line {
+            Some(line) => println!(
+                "{}:{}: {}: {}",
+                path, line, diagnostic.severity, diagnostic.message
+            ),
+            None => println!("{}: {}: {}", path, diagnostic.severity, diagnostic.message),
+        }
+    }
+
+    match (report.errors(), report.warnings()) {
+        (0, 0) => println!("{}: ok", path),
+        (errors, warnings) => println!("{}: {} error(s), {} warning(s)", path, errors, warnings),
+    }
+}
+
+fn cmd_show(file: Option<PathBuf>, json: bool) -> anyhow::Result<bool> {
+    let path = file.unwrap_or_else(global_config_path);
+
+    let base = if path.exists() {
+        let report = check_file(&path)?;
+        match report.config {
+            Some(config) => Some(config),
+            None => {
+                print_report(&report);
+                return Ok(false);
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut sources = vec!["defaults".to_string()];
+    if base.is_some() {
+        sources.push(path.display().to_string());
+    }
+
+    // Profiles are read from next to the config, as at startup
+    let layered = ConfigLoader::with_path(path.clone()).layer(base.unwrap_or_default())?;
+    sources.extend(
+        layered
+            .profiles
+            .iter()
+            .map(|name| format!("profile {}", name)),
+    );
+    if let Some(project) = &layered.project {
+        sources.push(project.display().to_string());
+    }
+
+    // Profiles have been applied; listing them again is noise
+    let mut config = layered.config;
+    config.profiles.clear();
+
+    if json {
+        let output = serde_json::json!({
+            "sources": sources,
+            "config": config,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        println!("# Effective Scarab configuration");
+        println!("#");
+        println!("# Layers, later ones winning:");
+        for (index, source) in sources.iter().enumerate() {
+            println!("#   {}. {}", index + 1, source);
+        }
+        println!();
+        print!("{}", toml::to_string_pretty(&config)?);
+    }
+    Ok(true)
+}
+
+fn cmd_migrate(file: Option<PathBuf>, force: bool) -> anyhow::Result<bool> {
+    let path = file.unwrap_or_else(|| config_dir().join("config.toml"));
+    let output = path.with_file_name("config.fsx");
+    if output.exists() && !force {
+        eprintln!(
+            "{} already exists; pass --force to overwrite it",
+            output.display()
+        );
+        return Ok(false);
+    }
+
+    let mut source = std::fs::read_to_string(&path)?;
+    if let Some((upgraded, _)) = upgrade_source(&source)? {
+        source = upgraded;
+    }
+    std::fs::write(&output, toml_to_fsx(&source)?)?;
+    println!("Wrote {} from {}", output.display(), path.display());
+
+    // config.fsx takes over from config.toml as soon as it exists
+    let report = check_file(&output)?;
+    print_report(&report);
+    Ok(report.errors() == 0)
+}
+
+fn cmd_upgrade(file: Option<PathBuf>) -> anyhow::Result<bool> {
+    let path = file.unwrap_or_else(|| config_dir().join("config.toml"));
+    match upgrade_file(&path)? {
+        Some(upgrade) => println!("Upgraded {}: {}", path.display(), upgrade),
+        None => println!("{} is up to date", path.display()),
+    }
+    Ok(true)
+}
+
+fn cmd_get(key: &str) -> anyhow::Result<bool> {
+    let reply = config_request(ControlMessage::ConfigGet {
+        key: key.to_string(),
+    })?;
+    print_config_reply(reply)
+}
+
+fn cmd_set(key: &str, value: &str, persist: bool) -> anyhow::Result<bool> {
+    let reply = config_request(ControlMessage::ConfigSet {
+        key: key.to_string(),
+        value: value.to_string(),
+        persist,
+    })?;
+    print_config_reply(reply)
+}
+
+fn cmd_hash_password() -> anyhow::Result<bool> {
+    let password = if std::io::stdin().is_terminal() {
+        let password = rpassword::prompt_password("Password: ")?;
+        if rpassword::prompt_password("Again: ")? != password {
+            eprintln!("The passwords don't match");
+            return Ok(false);
+        }
+        password
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        line.trim_end_matches(['\r', '\n']).to_string()
+    };
+    if password.is_empty() {
+        eprintln!("The password is empty");
+        return Ok(false);
+    }
+
+    let hash = hash_lock_password(&password)?;
+    println!("[sessions]\nlock_password_hash = \"{}\"", hash);
+    Ok(true)
+}
+
+fn print_config_reply((value, error): (Option<String>, Option<String>)) -> anyhow::Result<bool> {
+    match (value, error) {
+        (_, Some(error)) => {
+            eprintln!("{}", error);
+            Ok(false)
+        }
+        (Some(value), None) => {
+            println!("{}", value);
+            Ok(true)
+        }
+        (None, None) => Ok(false),
+    }
+}
+
+/// Send a config message to the daemon and wait for its `ConfigValue`
+fn config_request(msg: ControlMessage) -> anyhow::Result<(Option<String>, Option<String>)> {
+    let key = match &msg {
+        ControlMessage::ConfigGet { key } | ControlMessage::ConfigSet { key, .. } => key.clone(),
+        _ => unreachable!("only config messages are sent"),
+    };
+
+    let socket = ConfigLoader::new().paths().socket;
+    let mut stream = UnixStream::connect(&socket)
+        .map_err(|e| anyhow::anyhow!("Is the daemon running? ({}: {})", socket.display(), e))?;
+    stream.set_read_timeout(Some(DAEMON_TIMEOUT))?;
+
+    let body = rkyv::to_bytes::<_, MAX_MESSAGE_SIZE>(&msg)
+        .map_err(|e| anyhow::anyhow!("Failed to serialize message: {:?}", e))?;
+    stream.write_all(&(body.len() as u32).to_be_bytes())?;
+    stream.write_all(&body)?;
+    stream.flush()?;
+
+    // Other clients' updates are broadcast here too; skip them
+    let mut buffer = vec![0u8; MAX_MESSAGE_SIZE];
+    loop {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 || len > MAX_MESSAGE_SIZE {
+            anyhow::bail!("Invalid message length from daemon: {}", len);
+        }
+        stream.read_exact(&mut buffer[..len])?;
+
+        let reply = rkyv::from_bytes::<DaemonMessage>(&buffer[..len])
+            .map_err(|e| anyhow::anyhow!("Failed to read daemon message: {:?}", e))?;
+        if let DaemonMessage::ConfigValue {
+            key: reply_key,
+            value,
+            error,
+        } = reply
+        {
+            if reply_key == key {
+                return Ok((value, error));
+            }
+        }
+    }
+}
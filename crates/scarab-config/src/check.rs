@@ -0,0 +1,490 @@
+//! Config checking: what `scarab-config check` reports about a file
+//!
+//! TOML files are read against the same schema the daemon uses. Keys the
+//! schema does not know, which loading drops without a word, are reported
+//! as warnings, and type errors as errors on the line of the bad value.
+//! Profile tables are checked the same way. Files named in `include` are
+//! loaded too, and a missing file, a cycle or a bad value in one of them
+//! is an error on the `include` line. Keys from an older layout are
+//! checked where the loader moves them, with a warning on the old key.
+//!
+//! `.fsx` files are evaluated, then each section record is compared with
+//! the fields the loader reads. A misspelt field or one holding the wrong
+//! kind of value would otherwise be left at its default.
+
+use crate::fusabi_loader::{section_fields, FieldKind, FusabiConfigLoader};
+use crate::fusabi_modules::resolve_source;
+use crate::fusabi_reload::ConfigSection;
+use crate::upgrade::{self, VERSION_KEY};
+use crate::{ConfigLoader, ConfigValidator, ScarabConfig};
+use fusabi_vm::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How bad a [`Diagnostic`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The config fails to load, or loads with a value it should not have
+    Error,
+    /// Part of the config is ignored
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// One problem found in a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 1-based line, when the problem can be pinned to one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            line,
+            message: message.into(),
+        }
+    }
+
+    fn warning(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// The result of checking one file
+#[derive(Debug)]
+pub struct CheckReport {
+    pub path: PathBuf,
+    /// The config as loaded, unless an error stopped it loading
+    pub config: Option<ScarabConfig>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CheckReport {
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    }
+}
+
+/// Check a `.toml` or `.fsx` config, picked by extension
+pub fn check_file(path: &Path) -> crate::Result<CheckReport> {
+    let text = std::fs::read_to_string(path)?;
+    let is_fsx = path.extension().is_some_and(|ext| ext == "fsx");
+    let (mut config, mut diagnostics) = if is_fsx {
+        check_fsx(path, &text)
+    } else {
+        check_toml(&text)
+    };
+
+    // Included files only come in through the loader
+    let include_line = toml_key_line(&text, &["include".to_string()]);
+    if !is_fsx && config.is_some() && include_line.is_some() {
+        match ConfigLoader::from_file(path) {
+            Ok(full) => config = Some(full),
+            Err(e) => {
+                diagnostics.push(Diagnostic::error(include_line, e.to_string()));
+                config = None;
+            }
+        }
+    }
+
+    if let Some(config) = &config {
+        if let Err(e) = ConfigValidator::validate(config) {
+            diagnostics.push(Diagnostic::error(None, e.to_string()));
+        }
+    }
+
+    Ok(CheckReport {
+        path: path.to_path_buf(),
+        config,
+        diagnostics,
+    })
+}
+
+/// Load TOML config text, reporting unknown keys and type errors
+pub fn check_toml(source: &str) -> (Option<ScarabConfig>, Vec<Diagnostic>) {
+    let upgraded = match upgrade::upgrade_source(source) {
+        Ok(upgraded) => upgraded,
+        Err(e) => {
+            let line = toml_key_line(source, &[VERSION_KEY.to_string()]);
+            return (None, vec![Diagnostic::error(line, e.to_string())]);
+        }
+    };
+    let text = upgraded.as_ref().map_or(source, |(text, _)| text.as_str());
+
+    let mut unknown = Vec::new();
+    let result: Result<ScarabConfig, toml::de::Error> =
+        serde_ignored::deserialize(toml::Deserializer::new(text), |path| {
+            let mut segments = Vec::new();
+            path_segments(&path, &mut segments);
+            unknown.push(segments);
+        });
+
+    let config = match result {
+        Ok(config) => config,
+        Err(e) => {
+            // Spans in upgraded text don't match the file's lines
+            let line = e
+                .span()
+                .filter(|_| upgraded.is_none())
+                .map(|span| line_at(source, span.start));
+            return (None, vec![Diagnostic::error(line, e.message())]);
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    for moved in upgraded.iter().flat_map(|(_, upgrade)| &upgrade.changes) {
+        let path: Vec<String> = moved.from.split('.').map(str::to_string).collect();
+        let message = if moved.dropped {
+            format!("`{}` is ignored: `{}` is already set", moved.from, moved.to)
+        } else {
+            format!(
+                "`{}` is now `{}`; Scarab moves it when it starts",
+                moved.from, moved.to
+            )
+        };
+        diagnostics.push(Diagnostic::warning(toml_key_line(source, &path), message));
+    }
+    for (index, profile) in config.profiles.iter().enumerate() {
+        let prefix = vec!["profiles".to_string(), index.to_string()];
+        let settings = toml::Value::Table(profile.settings.clone());
+        let result: Result<ScarabConfig, toml::de::Error> =
+            serde_ignored::deserialize(settings, |path| {
+                let mut segments = prefix.clone();
+                path_segments(&path, &mut segments);
+                unknown.push(segments);
+            });
+        if let Err(e) = result {
+            diagnostics.push(Diagnostic::error(
+                toml_key_line(source, &prefix),
+                format!("profile `{}`: {}", profile.name, e.message()),
+            ));
+        }
+    }
+
+    // `include` is read by the loader rather than the schema
+    unknown.retain(|path| *path != ["include"]);
+    for path in unknown {
+        diagnostics.push(Diagnostic::warning(
+            toml_key_line(source, &path),
+            format!("unknown key `{}`", path.join(".")),
+        ));
+    }
+    diagnostics.sort_by_key(|d| d.line);
+    (Some(config), diagnostics)
+}
+
+/// Evaluate an `.fsx` config, reporting unknown and mistyped section fields
+fn check_fsx(path: &Path, text: &str) -> (Option<ScarabConfig>, Vec<Diagnostic>) {
+    let resolved = match resolve_source(path) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let message = e.to_string();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let line = number_after(&message, &format!("{}:", name));
+            return (None, vec![Diagnostic::error(line, message)]);
+        }
+    };
+    // Lines of `#load`ed files ahead of the file's own
+    let prelude = resolved
+        .source
+        .matches('\n')
+        .count()
+        .saturating_sub(text.matches('\n').count());
+
+    let module = match FusabiConfigLoader::evaluate(&resolved.source) {
+        Ok(module) => module,
+        Err(e) => {
+            let message = e.to_string();
+            let line = number_after(&message, "line: ")
+                .and_then(|line| line.checked_sub(prelude))
+                .filter(|line| *line > 0);
+            return (None, vec![Diagnostic::error(line, message)]);
+        }
+    };
+
+    let mut config = ScarabConfig::default();
+    let mut diagnostics = Vec::new();
+    for section in ConfigSection::ALL {
+        FusabiConfigLoader::extract_section(&module, section, &mut config);
+        if let Some(Value::Record(fields)) = module.get_global(section.binding()) {
+            check_fields(
+                section.binding(),
+                &fields.lock().unwrap(),
+                section_fields(section),
+                text,
+                &mut diagnostics,
+            );
+        }
+    }
+    diagnostics.sort_by_key(|d| d.line);
+    (Some(config), diagnostics)
+}
+
+fn check_fields(
+    prefix: &str,
+    fields: &HashMap<String, Value>,
+    known: &[(&str, FieldKind)],
+    text: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut names: Vec<_> = fields.keys().collect();
+    names.sort();
+    for name in names {
+        let value = &fields[name];
+        let line = fsx_field_line(text, name);
+        let Some((_, kind)) = known.iter().find(|(field, _)| field == name) else {
+            diagnostics.push(Diagnostic::warning(
+                line,
+                format!("unknown field `{}.{}`", prefix, name),
+            ));
+            continue;
+        };
+        if !kind.accepts(value) {
+            diagnostics.push(Diagnostic::error(
+                line,
+                format!("`{}.{}` should be {}", prefix, name, kind.name()),
+            ));
+        } else if let (FieldKind::Record(nested), Value::Record(map)) = (kind, value) {
+            let prefix = format!("{}.{}", prefix, name);
+            check_fields(&prefix, &map.lock().unwrap(), nested, text, diagnostics);
+        }
+    }
+}
+
+fn path_segments(path: &serde_ignored::Path, out: &mut Vec<String>) {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => {}
+        Path::Seq { parent, index } => {
+            path_segments(parent, out);
+            out.push(index.to_string());
+        }
+        Path::Map { parent, key } => {
+            path_segments(parent, out);
+            out.push(key.clone());
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => path_segments(parent, out),
+    }
+}
+
+/// 1-based line of byte `offset`
+fn line_at(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// 1-based line where the TOML key at `path` is set
+///
+/// Array-of-tables entries are numbered in `path`, as in `profiles.1.font`.
+fn toml_key_line(source: &str, path: &[String]) -> Option<usize> {
+    let mut table: Vec<String> = Vec::new();
+    let mut arrays: HashMap<String, usize> = HashMap::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix("[[").and_then(|l| l.split("]]").next()) {
+            let name = header.trim().to_string();
+            let entry = arrays
+                .entry(name.clone())
+                .and_modify(|n| *n += 1)
+                .or_insert(0);
+            table = key_segments(&name);
+            table.push(entry.to_string());
+        } else if let Some(header) = line.strip_prefix('[').and_then(|l| l.split(']').next()) {
+            table = key_segments(header);
+            if let Some(entry) = table.first().and_then(|first| arrays.get(first)) {
+                table.insert(1, entry.to_string());
+            }
+        } else if let Some((key, _)) = line.split_once('=') {
+            let mut full = table.clone();
+            full.extend(key_segments(key));
+            // The key itself, or an inline table holding it
+            let last = path.last().map(String::as_str).unwrap_or_default();
+            if full == path || (path.starts_with(&full) && line.contains(last)) {
+                return Some(index + 1);
+            }
+            continue;
+        } else {
+            continue;
+        }
+
+        if table == path {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+fn key_segments(key: &str) -> Vec<String> {
+    key.split('.')
+        .map(|part| part.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+/// 1-based line where `Name = ...` is first written
+fn fsx_field_line(text: &str, name: &str) -> Option<usize> {
+    text.lines()
+        .position(|line| {
+            line.match_indices(name).any(|(at, _)| {
+                let before = line[..at].chars().next_back();
+                let after = line[at + name.len()..].trim_start();
+                !before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+                    && after.starts_with('=')
+                    && !after.starts_with("==")
+            })
+        })
+        .map(|index| index + 1)
+}
+
+fn number_after(text: &str, label: &str) -> Option<usize> {
+    let start = text.find(label)? + label.len();
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_toml_unknown_keys_have_lines() {
+        let source = r#"
+[font]
+family = "Iosevka"
+sise = 13.0
+
+[apperance]
+theme = "dark"
+
+[[profiles]]
+name = "big"
+env = "BIG"
+
+[[profiles]]
+name = "work"
+env = "WORK"
+
+[profiles.font]
+sizee = 12.0
+"#;
+        let (config, diagnostics) = check_toml(source);
+        assert!(config.is_some());
+
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Severity::Warning, Some(4), "unknown key `font.sise`"),
+                (Severity::Warning, Some(6), "unknown key `apperance`"),
+                (
+                    Severity::Warning,
+                    Some(18),
+                    "unknown key `profiles.1.font.sizee`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toml_type_errors() {
+        let (config, diagnostics) = check_toml("[font]\nfamily = \"Iosevka\"\nsize = \"big\"\n");
+        assert!(config.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, Some(3));
+
+        let source = "[[profiles]]\nname = \"p\"\nfont = { size = \"big\" }\n";
+        let (config, diagnostics) = check_toml(source);
+        assert!(config.is_some());
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, Some(1));
+        assert!(diagnostics[0].message.starts_with("profile `p`"));
+    }
+
+    #[test]
+    fn test_toml_old_layout() {
+        let source =
+            "[font]\nsize = 15.0\n\n[appearance]\nfont_family = \"Hack\"\nfont_size = 13.0\n";
+        let (config, diagnostics) = check_toml(source);
+        assert_eq!(config.unwrap().font.family, "Hack");
+
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    Some(5),
+                    "`appearance.font_family` is now `font.family`; Scarab moves it when it starts"
+                ),
+                (
+                    Some(6),
+                    "`appearance.font_size` is ignored: `font.size` is already set"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fsx_fields() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.fsx");
+        std::fs::write(
+            &path,
+            "let font = {\n    Family = \"Iosevka\";\n    Sise = 13.0;\n    Ligatures = 1\n}\n\n{ font = font }\n",
+        )
+        .unwrap();
+
+        let report = check_file(&path).unwrap();
+        assert_eq!(report.config.unwrap().font.family, "Iosevka");
+        assert_eq!(
+            report.diagnostics,
+            vec![
+                Diagnostic::warning(Some(3), "unknown field `font.Sise`"),
+                Diagnostic::error(Some(4), "`font.Ligatures` should be a bool"),
+            ]
+        );
+    }
+}
@@ -1,13 +1,17 @@
 //! Core configuration structures
 
+use crate::profiles::{wildcard_match, Profile};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 /// Root configuration structure
 #[derive(Debug, Clone, Deserialize, Serialize, Resource)]
 #[serde(default)]
 pub struct ScarabConfig {
+    /// Schema version the config was written for (see [`crate::upgrade`])
+    pub config_version: u32,
     pub terminal: TerminalConfig,
     pub font: FontConfig,
     pub colors: ColorConfig,
@@ -19,11 +23,19 @@ pub struct ScarabConfig {
     pub navigation: NavConfig,
     pub effects: EffectsConfig,
     pub ssh_domains: Vec<SshDomainConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub serial_domains: Vec<SerialDomainConfig>,
+    pub paths: PathsConfig,
+
+    /// Overrides applied by host, environment or directory (see [`crate::profiles`])
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
 }
 
 impl Default for ScarabConfig {
     fn default() -> Self {
         Self {
+            config_version: crate::upgrade::CONFIG_VERSION,
             terminal: TerminalConfig::default(),
             font: FontConfig::default(),
             colors: ColorConfig::default(),
@@ -35,6 +47,9 @@ impl Default for ScarabConfig {
             telemetry: TelemetryConfig::default(),
             effects: EffectsConfig::default(),
             ssh_domains: Vec::new(),
+            serial_domains: Vec::new(),
+            paths: PathsConfig::default(),
+            profiles: Vec::new(),
         }
     }
 }
@@ -59,6 +74,14 @@ impl ScarabConfig {
 
         // Keybindings
         self.keybindings.custom.extend(other.keybindings.custom);
+        self.keybindings.plugins.extend(other.keybindings.plugins);
+        for (name, table) in other.keybindings.key_tables {
+            self.keybindings
+                .key_tables
+                .entry(name)
+                .or_default()
+                .extend(table);
+        }
 
         // UI settings
         if other.ui != UiConfig::default() {
@@ -68,6 +91,8 @@ impl ScarabConfig {
         // Plugins
         self.plugins.enabled.extend(other.plugins.enabled);
         self.plugins.config.extend(other.plugins.config);
+        self.plugins.settings.extend(other.plugins.settings);
+        self.plugins.dev_mode |= other.plugins.dev_mode;
 
         // Sessions
         if other.sessions != SessionConfig::default() {
@@ -128,6 +153,14 @@ pub struct FontConfig {
     pub fallback: Vec<String>,
     pub bold_is_bright: bool,
     pub use_thin_strokes: bool,
+
+    /// Shape runs of cells so fonts can form programming ligatures
+    pub ligatures: bool,
+
+    /// OpenType feature toggles per font family (e.g. `"+ss01"`, `"-calt"`)
+    ///
+    /// The `"*"` key applies to every family.
+    pub features: HashMap<String, Vec<String>>,
 }
 
 impl Default for FontConfig {
@@ -143,6 +176,8 @@ impl Default for FontConfig {
             ],
             bold_is_bright: true,
             use_thin_strokes: false,
+            ligatures: true,
+            features: HashMap::new(),
         }
     }
 }
@@ -154,6 +189,24 @@ pub struct ColorConfig {
     /// Theme name (e.g., "dracula", "nord", "monokai")
     pub theme: Option<String>,
 
+    /// Theme used while the desktop is in light mode
+    ///
+    /// With `dark_theme`, Scarab follows the desktop's appearance, switching
+    /// themes as it changes; either may be left out to keep `theme` then.
+    pub light_theme: Option<String>,
+    /// Theme used while the desktop is in dark mode
+    pub dark_theme: Option<String>,
+
+    /// Themes for panes running particular programs, such as root shells
+    /// or ssh sessions to production hosts
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pane_themes: Vec<PaneThemeRule>,
+
+    /// Themes defined here, each built on another theme with a few colors
+    /// changed, usable as `theme` like any other
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_themes: BTreeMap<String, CustomTheme>,
+
     /// Custom colors (override theme)
     pub foreground: Option<String>,
     pub background: Option<String>,
@@ -167,12 +220,24 @@ pub struct ColorConfig {
     /// Transparency settings
     pub opacity: f32,
     pub dim_opacity: f32,
+
+    /// Minimum WCAG contrast ratio of text against its background, from
+    /// 1.0 to 21.0
+    ///
+    /// Foreground colors that fall short, whatever the theme or program
+    /// chose, are lightened or darkened per cell until they reach it. 4.5
+    /// is the WCAG AA level for text; 1.0 leaves colors as they are.
+    pub minimum_contrast: f32,
 }
 
 impl Default for ColorConfig {
     fn default() -> Self {
         Self {
             theme: Some("slime".to_string()),
+            light_theme: None,
+            dark_theme: None,
+            pane_themes: Vec::new(),
+            custom_themes: BTreeMap::new(),
             foreground: Some("#e0e0e0".to_string()),
             background: Some("#1e2324".to_string()),
             cursor: Some("#a8df5a".to_string()),
@@ -181,6 +246,112 @@ impl Default for ColorConfig {
             palette: ColorPalette::default(),
             opacity: 1.0,
             dim_opacity: 0.7,
+            minimum_contrast: 1.0,
+        }
+    }
+}
+
+/// Theme for panes whose foreground process matches
+///
+/// Written as `[[colors.pane_themes]]`. The first matching rule wins; a
+/// rule with no conditions matches every pane.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PaneThemeRule {
+    /// Theme ID, such as "dracula"
+    pub theme: String,
+    /// User the process runs as, such as "root"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Command line, where `*` matches any run of characters, such as
+    /// "ssh *prod*"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl PaneThemeRule {
+    /// Whether a process run by `user` with command line `command` matches
+    pub fn matches(&self, user: &str, command: &str) -> bool {
+        let user_ok = self.user.as_deref().map_or(true, |u| u == user);
+        let command_ok = self
+            .command
+            .as_deref()
+            .map_or(true, |pattern| wildcard_match(pattern, command));
+        user_ok && command_ok
+    }
+}
+
+/// Theme defined in the config on top of another
+///
+/// Written as `[colors.custom_themes.<name>]`. The theme named by `extends`,
+/// built-in or custom, supplies every color not given here; without it the
+/// theme has only its own colors.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CustomTheme {
+    /// Theme this one starts from, such as "gruvbox-dark"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_foreground: Option<String>,
+    /// ANSI colors to change
+    pub palette: PaletteOverrides,
+}
+
+/// Some of the 16 ANSI colors, the rest left as they are
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaletteOverrides {
+    pub black: Option<String>,
+    pub red: Option<String>,
+    pub green: Option<String>,
+    pub yellow: Option<String>,
+    pub blue: Option<String>,
+    pub magenta: Option<String>,
+    pub cyan: Option<String>,
+    pub white: Option<String>,
+    pub bright_black: Option<String>,
+    pub bright_red: Option<String>,
+    pub bright_green: Option<String>,
+    pub bright_yellow: Option<String>,
+    pub bright_blue: Option<String>,
+    pub bright_magenta: Option<String>,
+    pub bright_cyan: Option<String>,
+    pub bright_white: Option<String>,
+}
+
+impl PaletteOverrides {
+    /// Replace the colors of `palette` given here
+    pub fn apply_to(&self, palette: &mut ColorPalette) {
+        let slots = [
+            (&self.black, &mut palette.black),
+            (&self.red, &mut palette.red),
+            (&self.green, &mut palette.green),
+            (&self.yellow, &mut palette.yellow),
+            (&self.blue, &mut palette.blue),
+            (&self.magenta, &mut palette.magenta),
+            (&self.cyan, &mut palette.cyan),
+            (&self.white, &mut palette.white),
+            (&self.bright_black, &mut palette.bright_black),
+            (&self.bright_red, &mut palette.bright_red),
+            (&self.bright_green, &mut palette.bright_green),
+            (&self.bright_yellow, &mut palette.bright_yellow),
+            (&self.bright_blue, &mut palette.bright_blue),
+            (&self.bright_magenta, &mut palette.bright_magenta),
+            (&self.bright_cyan, &mut palette.bright_cyan),
+            (&self.bright_white, &mut palette.bright_white),
+        ];
+        for (color, slot) in slots {
+            if let Some(color) = color {
+                slot.clone_from(color);
+            }
         }
     }
 }
@@ -238,7 +409,10 @@ impl Default for ColorPalette {
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(default)]
 pub struct KeyBindings {
+    /// Key that arms `Leader+<key>` bindings (empty to disable)
     pub leader_key: String,
+    /// How long the leader stays armed waiting for the next key
+    pub leader_timeout_ms: u64,
     pub copy_mode: String,
     pub paste: String,
     pub search: String,
@@ -249,13 +423,29 @@ pub struct KeyBindings {
     pub prev_tab: String,
 
     /// Custom keybindings (action -> key)
+    ///
+    /// Keys separated by spaces form a chord, e.g. `"Ctrl+X Ctrl+S"`.
     pub custom: HashMap<String, String>,
+
+    /// Key tables (table name -> key -> action)
+    ///
+    /// Entries are merged over the built-in `copy_mode`, `search_mode`,
+    /// `resize_pane` and `nav` tables; other names define new tables that
+    /// `activate_key_table:<name>` can push.
+    pub key_tables: HashMap<String, HashMap<String, String>>,
+
+    /// Keys for plugin commands (command id -> key)
+    ///
+    /// Replaces the key a plugin binds to the command; an empty string
+    /// unbinds it.
+    pub plugins: HashMap<String, String>,
 }
 
 impl Default for KeyBindings {
     fn default() -> Self {
         Self {
-            leader_key: "Space".to_string(),
+            leader_key: "Ctrl+Space".to_string(),
+            leader_timeout_ms: 1000,
             copy_mode: "Ctrl+Shift+C".to_string(),
             paste: "Ctrl+Shift+V".to_string(),
             search: "Ctrl+Shift+F".to_string(),
@@ -265,6 +455,8 @@ impl Default for KeyBindings {
             next_tab: "Ctrl+Tab".to_string(),
             prev_tab: "Ctrl+Shift+Tab".to_string(),
             custom: HashMap::new(),
+            key_tables: HashMap::new(),
+            plugins: HashMap::new(),
         }
     }
 }
@@ -276,15 +468,33 @@ pub struct UiConfig {
     pub link_hints: bool,
     pub command_palette: bool,
     pub animations: bool,
+    pub reduce_motion: bool, // Snap instead of animating, e.g. theme fades, cursor glides and smooth scroll
+    pub reduce_flashing: bool, // Reduce motion and hold back output that flashes (photosensitivity)
     pub smooth_scroll: bool,
     pub show_tabs: bool,
+    pub show_scrollbar: bool, // Scrollbar with command-block markers on the right edge
+    pub show_minimap: bool,   // Scrollback density minimap beside the scrollbar
+    pub show_command_gutter: bool, // Exit-code markers beside the grid (shell integration)
     pub tab_position: TabPosition,
+    pub tab_show_index: bool,
+    pub tab_show_title: bool,
+    pub tab_show_activity: bool, // Mark background tabs that produced output
+    pub pane_border_width: f32,  // Split border thickness in pixels (0 hides borders)
+    pub pane_border_color: Option<String>, // Defaults to a muted theme color
+    pub pane_border_focused_color: Option<String>, // Defaults to the cursor color
+    pub inactive_pane_brightness: f32, // Brightness of unfocused split panes (1.0 = no dimming)
     pub cursor_style: CursorStyle,
     pub cursor_blink: bool,
     pub cursor_blink_interval: u32,
+    pub cursor_smooth: bool,         // Animate cursor movement between cells
     pub window_icon: Option<String>, // Path to custom icon (PNG format, optional)
     pub search_case_sensitive: bool, // Case-sensitive search by default
     pub search_use_regex: bool,      // Use regex search by default
+    pub background_image: Option<String>, // Image drawn under the cell grid (PNG/JPEG)
+    pub background_image_fit: BackgroundImageFit,
+    pub background_image_opacity: f32,
+    pub background_dim: f32, // Theme-colored layer over the image to keep text legible
+    pub background_blur: bool, // Blur behind a translucent window (needs compositor support)
 }
 
 impl Default for UiConfig {
@@ -293,19 +503,44 @@ impl Default for UiConfig {
             link_hints: true,
             command_palette: true,
             animations: true,
+            reduce_motion: false,
+            reduce_flashing: false,
             smooth_scroll: true,
             show_tabs: true,
+            show_scrollbar: true,
+            show_minimap: false,
+            show_command_gutter: true,
             tab_position: TabPosition::Top,
+            tab_show_index: true,
+            tab_show_title: true,
+            tab_show_activity: true,
+            pane_border_width: 1.0,
+            pane_border_color: None,
+            pane_border_focused_color: None,
+            inactive_pane_brightness: 1.0,
             cursor_style: CursorStyle::Block,
             cursor_blink: true,
             cursor_blink_interval: 750,
+            cursor_smooth: false,
             window_icon: None, // No custom icon by default
             search_case_sensitive: false,
             search_use_regex: false,
+            background_image: None,
+            background_image_fit: BackgroundImageFit::Cover,
+            background_image_opacity: 1.0,
+            background_dim: 0.5,
+            background_blur: false,
         }
     }
 }
 
+impl UiConfig {
+    /// Whether anything may move or fade rather than change at once
+    pub fn motion_enabled(&self) -> bool {
+        self.animations && !self.reduce_motion && !self.reduce_flashing
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TabPosition {
@@ -315,6 +550,20 @@ pub enum TabPosition {
     Right,
 }
 
+/// How a background image is scaled to the window
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundImageFit {
+    /// Fill the window, cropping the image if aspect ratios differ
+    Cover,
+    /// Fit the whole image inside the window
+    Contain,
+    /// Stretch to the window size, ignoring aspect ratio
+    Stretch,
+    /// Draw at native size, centered
+    Center,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum CursorStyle {
@@ -329,6 +578,16 @@ pub enum CursorStyle {
 pub struct PluginConfig {
     pub enabled: Vec<String>,
     pub config: HashMap<String, serde_json::Value>,
+
+    /// Watch native plugin libraries and reload them when rebuilt, keeping
+    /// their state across failed builds
+    pub dev_mode: bool,
+
+    /// Settings for individual plugins, from `[plugins.settings.<name>]`
+    /// tables
+    ///
+    /// Each plugin checks its table against the schema it declares.
+    pub settings: HashMap<String, toml::Value>,
 }
 
 impl Default for PluginConfig {
@@ -336,18 +595,42 @@ impl Default for PluginConfig {
         Self {
             enabled: vec![],
             config: HashMap::new(),
+            dev_mode: false,
+            settings: HashMap::new(),
         }
     }
 }
 
+impl PluginConfig {
+    /// Settings table for the plugin called `name`, if the user wrote one
+    pub fn settings_for(&self, name: &str) -> Option<&toml::Table> {
+        self.settings.get(name)?.as_table()
+    }
+}
+
 /// Session management configuration
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(default)]
 pub struct SessionConfig {
     pub restore_on_startup: bool,
+    /// Seconds between crash recovery checkpoints (0 turns them off)
     pub auto_save_interval: u32,
+    /// Whether checkpoints keep the last lines of each pane's output
     pub save_scrollback: bool,
     pub working_directory: Option<String>,
+
+    /// Titles of the tabs a new session opens with (empty for one untitled tab)
+    pub startup_tabs: Vec<String>,
+
+    /// Open the windows of a `tmux -CC` running in a pane as tabs
+    pub tmux_control_mode: bool,
+
+    /// Seconds without input before the client hides the terminal behind a
+    /// lock screen (0 never locks)
+    pub lock_after_idle: u32,
+    /// Argon2 hash of the password that unlocks it, from
+    /// `scarab-config hash-password`; any key unlocks when unset
+    pub lock_password_hash: Option<String>,
 }
 
 impl Default for SessionConfig {
@@ -357,6 +640,53 @@ impl Default for SessionConfig {
             auto_save_interval: 300, // 5 minutes
             save_scrollback: true,
             working_directory: None,
+            startup_tabs: Vec::new(),
+            tmux_control_mode: true,
+            lock_after_idle: 0,
+            lock_password_hash: None,
+        }
+    }
+}
+
+/// Where the socket, shared memory and data files live
+///
+/// Each field left unset uses its `SCARAB_*` environment variable if that
+/// is set, and the default location otherwise (see [`scarab_platform::paths`]).
+/// The environment wins over the config so a sandbox can move everything
+/// without editing it.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PathsConfig {
+    /// Directory for state such as `sessions.db`
+    pub data_dir: Option<String>,
+    /// Socket the daemon listens on and clients connect to
+    pub socket: Option<String>,
+    /// Name of the main shared memory region
+    pub shmem: Option<String>,
+    /// Name of the shared image buffer
+    pub image_shmem: Option<String>,
+    /// Session database (defaults to `sessions.db` in `data_dir`)
+    pub sessions_db: Option<String>,
+    /// Directory plugins are installed to and loaded from
+    pub plugin_dir: Option<String>,
+}
+
+impl PathsConfig {
+    /// The locations to use, after the environment and defaults
+    pub fn resolve(&self) -> scarab_platform::Paths {
+        scarab_platform::Paths::resolve(&self.settings())
+    }
+
+    /// These settings in the form [`scarab_platform::Paths`] takes
+    pub fn settings(&self) -> scarab_platform::PathSettings {
+        let path = |value: &Option<String>| value.as_ref().map(PathBuf::from);
+        scarab_platform::PathSettings {
+            data_dir: path(&self.data_dir),
+            socket: path(&self.socket),
+            shmem: self.shmem.clone(),
+            image_shmem: self.image_shmem.clone(),
+            sessions_db: path(&self.sessions_db),
+            plugin_dir: path(&self.plugin_dir),
         }
     }
 }
@@ -739,6 +1069,91 @@ mod effects_tests {
         assert_eq!(config.effects.overlay_glow_color, "#ff00ff");
         assert!(config.effects.low_power_mode);
     }
+
+    #[test]
+    fn test_key_tables_merge() {
+        let toml = r#"
+            [keybindings]
+            leader_timeout_ms = 750
+
+            [keybindings.key_tables.copy_mode]
+            x = "copy_mode.exit"
+
+            [keybindings.plugins]
+            "git.status" = "Ctrl+Alt+S"
+        "#;
+        let mut base: ScarabConfig = toml::from_str(toml).unwrap();
+        assert_eq!(base.keybindings.leader_key, "Ctrl+Space");
+        assert_eq!(base.keybindings.leader_timeout_ms, 750);
+
+        let mut override_config = ScarabConfig::default();
+        override_config
+            .keybindings
+            .key_tables
+            .entry("copy_mode".into())
+            .or_default()
+            .insert("q".into(), "copy_mode.exit".into());
+        base.merge(override_config);
+
+        let copy_mode = &base.keybindings.key_tables["copy_mode"];
+        assert_eq!(copy_mode.len(), 2);
+        assert_eq!(copy_mode["x"], "copy_mode.exit");
+        assert_eq!(base.keybindings.plugins["git.status"], "Ctrl+Alt+S");
+    }
+
+    #[test]
+    fn test_plugin_settings_tables() {
+        let toml = r#"
+            [plugins]
+            enabled = ["git-status"]
+
+            [plugins.settings.git-status]
+            show_branch = true
+            ignore = ["target"]
+        "#;
+        let config: ScarabConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.plugins.enabled, vec!["git-status".to_string()]);
+
+        let settings = config.plugins.settings_for("git-status").unwrap();
+        assert_eq!(settings["show_branch"].as_bool(), Some(true));
+        assert_eq!(settings["ignore"].as_array().unwrap().len(), 1);
+        assert!(config.plugins.settings_for("other").is_none());
+        assert!(!config.plugins.dev_mode);
+
+        let config: ScarabConfig = toml::from_str("[plugins]\ndev_mode = true").unwrap();
+        assert!(config.plugins.dev_mode);
+        assert!(config.plugins.settings.is_empty());
+
+        // Plugins named like the built-in keys no longer collide with them
+        let toml = r#"
+            [plugins.settings.enabled]
+            verbose = true
+        "#;
+        let config: ScarabConfig = toml::from_str(toml).unwrap();
+        assert!(config.plugins.enabled.is_empty());
+        assert!(config.plugins.settings_for("enabled").is_some());
+    }
+
+    #[test]
+    fn test_pane_theme_rules() {
+        let toml = r#"
+            [[colors.pane_themes]]
+            theme = "red-alert"
+            user = "root"
+
+            [[colors.pane_themes]]
+            theme = "gruvbox"
+            command = "ssh *prod*"
+        "#;
+        let config: ScarabConfig = toml::from_str(toml).unwrap();
+        let rules = &config.colors.pane_themes;
+        assert_eq!(rules.len(), 2);
+        assert!(rules[0].matches("root", "-bash"));
+        assert!(!rules[0].matches("alice", "-bash"));
+        assert!(rules[1].matches("alice", "ssh db.prod.example.com"));
+        assert!(!rules[1].matches("alice", "ssh staging"));
+        assert!(ScarabConfig::default().colors.pane_themes.is_empty());
+    }
 }
 
 /// Navigation style defining the keymap philosophy
@@ -902,6 +1317,11 @@ pub struct SshDomainConfig {
 
     /// Default remote working directory
     pub remote_cwd: Option<String>,
+
+    /// Hosts to connect through, in order, as `[user@]host[:port]` like
+    /// `ProxyJump`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub jump_hosts: Vec<String>,
 }
 
 impl Default for SshDomainConfig {
@@ -916,6 +1336,7 @@ impl Default for SshDomainConfig {
             connect_timeout: 10,
             forward_agent: false,
             remote_cwd: None,
+            jump_hosts: Vec::new(),
         }
     }
 }
@@ -940,6 +1361,9 @@ pub enum SshAuthConfig {
         /// Password (consider using environment variable)
         password: String,
     },
+
+    /// Ask for a password or keyboard-interactive answers when connecting
+    Interactive,
 }
 
 impl Default for SshAuthConfig {
@@ -948,6 +1372,84 @@ impl Default for SshAuthConfig {
     }
 }
 
+/// Serial port domain configuration
+///
+/// Opens a serial device as a pane, for talking to boards and other
+/// embedded targets. Written as `[[serial_domains]]`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SerialDomainConfig {
+    /// Unique identifier for this serial domain
+    pub id: String,
+
+    /// Human-readable name
+    pub name: String,
+
+    /// Device path, e.g. "/dev/ttyUSB0" or "COM3"
+    pub port: String,
+
+    pub baud_rate: u32,
+
+    /// Data bits per character, 5 to 8
+    pub data_bits: u8,
+
+    pub parity: SerialParity,
+
+    /// Stop bits, 1 or 2
+    pub stop_bits: u8,
+
+    pub flow_control: SerialFlowControl,
+
+    /// DTR level set when the port is opened; left alone if unset
+    pub dtr: Option<bool>,
+
+    /// RTS level set when the port is opened; left alone if unset
+    pub rts: Option<bool>,
+
+    /// File everything received from the device is appended to
+    pub log_file: Option<String>,
+}
+
+impl Default for SerialDomainConfig {
+    fn default() -> Self {
+        Self {
+            id: "serial".to_string(),
+            name: "Serial Port".to_string(),
+            port: "/dev/ttyUSB0".to_string(),
+            baud_rate: 115_200,
+            data_bits: 8,
+            parity: SerialParity::None,
+            stop_bits: 1,
+            flow_control: SerialFlowControl::None,
+            dtr: None,
+            rts: None,
+            log_file: None,
+        }
+    }
+}
+
+/// Parity bit of a serial line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerialParity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+/// Flow control of a serial line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerialFlowControl {
+    #[default]
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
 #[cfg(test)]
 mod ssh_config_tests {
     use super::*;
@@ -974,6 +1476,7 @@ mod ssh_config_tests {
             connect_timeout = 30
             forward_agent = true
             remote_cwd = "/home/alice/projects"
+            jump_hosts = ["ops@bastion.example.com:2222"]
         "#;
 
         let config: SshDomainConfig = toml::from_str(toml).unwrap();
@@ -985,6 +1488,7 @@ mod ssh_config_tests {
         assert_eq!(config.connect_timeout, 30);
         assert!(config.forward_agent);
         assert_eq!(config.remote_cwd, Some("/home/alice/projects".to_string()));
+        assert_eq!(config.jump_hosts, vec!["ops@bastion.example.com:2222"]);
 
         match config.auth {
             SshAuthConfig::PublicKey { key_path, .. } => {
@@ -1011,11 +1515,45 @@ mod ssh_config_tests {
             user = "admin"
             auth_type = "publickey"
             key_path = "/root/.ssh/prod_key"
+
+            [[ssh_domains]]
+            id = "bastion"
+            host = "bastion.example.com"
+            auth_type = "interactive"
         "#;
 
         let config: ScarabConfig = toml::from_str(toml).unwrap();
-        assert_eq!(config.ssh_domains.len(), 2);
+        assert_eq!(config.ssh_domains.len(), 3);
         assert_eq!(config.ssh_domains[0].id, "dev");
         assert_eq!(config.ssh_domains[1].id, "prod");
+        assert_eq!(config.ssh_domains[2].auth, SshAuthConfig::Interactive);
+    }
+}
+
+#[cfg(test)]
+mod serial_config_tests {
+    use super::*;
+
+    #[test]
+    fn test_serial_domain_config_deserialize() {
+        let toml = r#"
+            [[serial_domains]]
+            id = "esp32"
+            port = "/dev/ttyUSB1"
+            parity = "even"
+            flow_control = "hardware"
+            dtr = false
+            log_file = "~/logs/esp32.log"
+        "#;
+
+        let config: ScarabConfig = toml::from_str(toml).unwrap();
+        let serial = &config.serial_domains[0];
+        assert_eq!(serial.id, "esp32");
+        assert_eq!(serial.baud_rate, 115_200);
+        assert_eq!(serial.data_bits, 8);
+        assert_eq!(serial.parity, SerialParity::Even);
+        assert_eq!(serial.flow_control, SerialFlowControl::Hardware);
+        assert_eq!(serial.dtr, Some(false));
+        assert_eq!(serial.rts, None);
     }
 }
@@ -1,5 +1,6 @@
 //! Error types for the configuration system
 
+use scarab_plugin_api::fusabi_modules::ModuleError;
 use std::io;
 use thiserror::Error;
 
@@ -95,5 +96,14 @@ pub enum ConfigError {
     InvalidTheme(String),
 }
 
+impl From<ModuleError> for ConfigError {
+    fn from(err: ModuleError) -> Self {
+        match err {
+            ModuleError::Io(e) => ConfigError::IoError(e),
+            ModuleError::Load(message) => ConfigError::FusabiCompileError(message),
+        }
+    }
+}
+
 /// Result type alias
 pub type Result<T> = std::result::Result<T, ConfigError>;
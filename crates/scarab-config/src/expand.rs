@@ -0,0 +1,106 @@
+//! `~` and `${VAR}` expansion in path-valued config fields
+//!
+//! A leading `~` or `~/` is the home directory and `${NAME}` is the
+//! variable's value, so `"${SCARAB_THEMES}/bg.png"` and `"~/bin/fish"`
+//! work wherever the config names a file. A `$` not followed by `{` is
+//! kept as it is. A variable that is not set is an error naming the field,
+//! rather than a path that quietly points nowhere.
+
+use crate::config::{ScarabConfig, SshAuthConfig};
+use crate::error::{ConfigError, Result};
+
+pub use scarab_plugin_api::config::expand;
+
+/// Expand the path-valued fields of `config` from the process environment
+pub fn expand_paths(config: &mut ScarabConfig) -> Result<()> {
+    expand_paths_with(config, &|name| std::env::var(name).ok())
+}
+
+/// Expand the path-valued fields of `config`, reading variables from `lookup`
+pub fn expand_paths_with(
+    config: &mut ScarabConfig,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    expand_field(
+        "terminal.default_shell",
+        &mut config.terminal.default_shell,
+        lookup,
+    )?;
+
+    let optional = [
+        ("ui.window_icon", &mut config.ui.window_icon),
+        ("ui.background_image", &mut config.ui.background_image),
+        (
+            "sessions.working_directory",
+            &mut config.sessions.working_directory,
+        ),
+        ("paths.data_dir", &mut config.paths.data_dir),
+        ("paths.socket", &mut config.paths.socket),
+        ("paths.sessions_db", &mut config.paths.sessions_db),
+        ("paths.plugin_dir", &mut config.paths.plugin_dir),
+    ];
+    for (field, value) in optional {
+        if let Some(value) = value {
+            expand_field(field, value, lookup)?;
+        }
+    }
+
+    for domain in &mut config.ssh_domains {
+        if let SshAuthConfig::PublicKey { key_path, .. } = &mut domain.auth {
+            let field = format!("ssh_domains.{}.key_path", domain.id);
+            expand_field(&field, key_path, lookup)?;
+        }
+    }
+    for domain in &mut config.serial_domains {
+        if let Some(log_file) = &mut domain.log_file {
+            let field = format!("serial_domains.{}.log_file", domain.id);
+            expand_field(&field, log_file, lookup)?;
+        }
+    }
+    Ok(())
+}
+
+fn expand_field(
+    field: &str,
+    value: &mut String,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    *value = expand(value, lookup).map_err(|message| ConfigError::InvalidValue {
+        field: field.to_string(),
+        message,
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/me".to_string()),
+            "SCARAB_THEMES" => Some("/opt/themes".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_paths_names_the_field() {
+        let mut config = ScarabConfig::default();
+        config.terminal.default_shell = "~/bin/fish".to_string();
+        config.ui.background_image = Some("${SCARAB_THEMES}/bg.png".to_string());
+        expand_paths_with(&mut config, &lookup).unwrap();
+        assert_eq!(config.terminal.default_shell, "/home/me/bin/fish");
+        assert_eq!(
+            config.ui.background_image.as_deref(),
+            Some("/opt/themes/bg.png")
+        );
+
+        config.sessions.working_directory = Some("${PROJECTS}/scarab".to_string());
+        let err = expand_paths_with(&mut config, &lookup).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::InvalidValue { ref field, .. } if field == "sessions.working_directory"
+        ));
+    }
+}
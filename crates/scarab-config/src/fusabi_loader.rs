@@ -4,9 +4,11 @@
 //! This replaces the TOML-based configuration system with a programmable
 //! F# DSL that allows dynamic configuration, hooks, and validation.
 
+use crate::fusabi_modules::resolve_source;
+use crate::fusabi_reload::ConfigSection;
 use crate::{config::*, error::*};
 use fusabi_frontend::{Compiler, Lexer, Parser};
-use fusabi_vm::{Value, Vm};
+use fusabi_vm::{Chunk, Value, Vm};
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -15,13 +17,32 @@ pub struct FusabiConfigLoader;
 
 impl FusabiConfigLoader {
     /// Load configuration from a Fusabi script file
+    ///
+    /// Files it pulls in with `#load` are inlined first; see
+    /// [`fusabi_modules`](crate::fusabi_modules).
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ScarabConfig> {
-        let source = std::fs::read_to_string(path.as_ref())?;
-        Self::from_source(&source)
+        let resolved = resolve_source(path.as_ref())?;
+        Self::from_source(&resolved.source)
     }
 
     /// Load configuration from Fusabi source code
     pub fn from_source(source: &str) -> Result<ScarabConfig> {
+        let module = Self::evaluate(source)?;
+        let mut config = ScarabConfig::default();
+
+        // Extract configuration sections
+        // We use a best-effort approach: if a section is defined, we use it;
+        // otherwise we keep the default.
+        for section in ConfigSection::ALL {
+            Self::extract_section(&module, section, &mut config);
+        }
+
+        tracing::debug!("Fusabi config loaded");
+        Ok(config)
+    }
+
+    /// Compile Fusabi source as a program
+    pub(crate) fn compile(source: &str) -> Result<Chunk> {
         // Compile the Fusabi source manually to ensure proper program structure
         let mut lexer = Lexer::new(source);
         let tokens = lexer
@@ -33,8 +54,13 @@ impl FusabiConfigLoader {
             .parse_program()
             .map_err(|e| ConfigError::FusabiCompileError(format!("Parser error: {:?}", e)))?;
 
-        let chunk = Compiler::compile_program(&program)
-            .map_err(|e| ConfigError::FusabiCompileError(format!("Compiler error: {:?}", e)))?;
+        Compiler::compile_program(&program)
+            .map_err(|e| ConfigError::FusabiCompileError(format!("Compiler error: {:?}", e)))
+    }
+
+    /// Compile and run Fusabi source in a fresh VM
+    pub(crate) fn evaluate(source: &str) -> Result<FusabiModule> {
+        let chunk = Self::compile(source)?;
 
         // Execute the compiled config
         let mut vm = Vm::new();
@@ -42,44 +68,62 @@ impl FusabiConfigLoader {
             ConfigError::FusabiRuntimeError(format!("Failed to execute config: {:?}", e))
         })?;
 
-        let module = FusabiModule { vm, result };
-        let mut config = ScarabConfig::default();
+        Ok(FusabiModule { vm, result })
+    }
 
-        // Extract configuration sections
-        // We use a best-effort approach: if a section is defined, we use it;
-        // otherwise we keep the default.
-        if let Ok(c) = Self::extract_terminal_config(&module) {
-            config.terminal = c;
-        }
-        if let Ok(c) = Self::extract_font_config(&module) {
-            config.font = c;
-        }
-        if let Ok(c) = Self::extract_color_config(&module) {
-            config.colors = c;
-        }
-        if let Ok(c) = Self::extract_keybindings(&module) {
-            config.keybindings = c;
-        }
-        if let Ok(c) = Self::extract_ui_config(&module) {
-            config.ui = c;
-        }
-        if let Ok(c) = Self::extract_plugin_config(&module) {
-            config.plugins = c;
-        }
-        if let Ok(c) = Self::extract_session_config(&module) {
-            config.sessions = c;
+    /// Read one section from an evaluated config, keeping `config`'s value
+    /// if the section is missing or malformed
+    pub(crate) fn extract_section(
+        module: &FusabiModule,
+        section: ConfigSection,
+        config: &mut ScarabConfig,
+    ) {
+        match section {
+            ConfigSection::Terminal => {
+                if let Ok(c) = Self::extract_terminal_config(module) {
+                    config.terminal = c;
+                }
+            }
+            ConfigSection::Font => {
+                if let Ok(c) = Self::extract_font_config(module) {
+                    config.font = c;
+                }
+            }
+            ConfigSection::Colors => {
+                if let Ok(c) = Self::extract_color_config(module) {
+                    config.colors = c;
+                }
+            }
+            ConfigSection::KeyBindings => {
+                if let Ok(c) = Self::extract_keybindings(module) {
+                    config.keybindings = c;
+                }
+            }
+            ConfigSection::Ui => {
+                if let Ok(c) = Self::extract_ui_config(module) {
+                    config.ui = c;
+                }
+            }
+            ConfigSection::Plugins => {
+                if let Ok(c) = Self::extract_plugin_config(module) {
+                    config.plugins = c;
+                }
+            }
+            ConfigSection::Sessions => {
+                if let Ok(c) = Self::extract_session_config(module) {
+                    config.sessions = c;
+                }
+            }
         }
-
-        println!("✅ Fusabi config loaded successfully");
-        Ok(config)
     }
 
     /// Load configuration with fallback chain:
     /// 1. Try ~/.config/scarab/config.fsx
     /// 2. Fall back to default config
     pub fn load_with_fallback() -> Result<ScarabConfig> {
-        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        let config_path = std::path::PathBuf::from(home).join(".config/scarab/config.fsx");
+        let config_path = scarab_platform::Paths::from_env()
+            .config_dir
+            .join("config.fsx");
 
         if config_path.exists() {
             println!("Loading Fusabi config from: {}", config_path.display());
@@ -164,6 +208,26 @@ impl FusabiConfigLoader {
             if let Some(b) = get_bool(&map, "UseThinStrokes") {
                 config.use_thin_strokes = b;
             }
+            if let Some(b) = get_bool(&map, "Ligatures") {
+                config.ligatures = b;
+            }
+
+            // Features (nested record of family -> tuple of feature strings)
+            if let Some(Value::Record(features_map)) = map.get("Features") {
+                let f_map = features_map.lock().unwrap();
+                for (family, v) in f_map.iter() {
+                    if let Value::Tuple(vec) = v {
+                        let features: Vec<String> = vec
+                            .iter()
+                            .filter_map(|v| match v {
+                                Value::Str(s) => Some(s.to_string()),
+                                _ => None,
+                            })
+                            .collect();
+                        config.features.insert(family.to_string(), features);
+                    }
+                }
+            }
 
             if let Some(Value::Tuple(vec)) = map.get("Fallback") {
                 let mut fallback = Vec::new();
@@ -195,12 +259,21 @@ impl FusabiConfigLoader {
             if let Some(s) = get_string(&map, "Theme") {
                 config.theme = Some(s);
             }
+            if let Some(s) = get_string(&map, "LightTheme") {
+                config.light_theme = Some(s);
+            }
+            if let Some(s) = get_string(&map, "DarkTheme") {
+                config.dark_theme = Some(s);
+            }
             if let Some(f) = get_float(&map, "Opacity") {
                 config.opacity = f as f32;
             }
             if let Some(f) = get_float(&map, "DimOpacity") {
                 config.dim_opacity = f as f32;
             }
+            if let Some(f) = get_float(&map, "MinimumContrast") {
+                config.minimum_contrast = f as f32;
+            }
 
             // Optional overrides
             if let Some(s) = get_string(&map, "Foreground") {
@@ -293,6 +366,9 @@ impl FusabiConfigLoader {
             if let Some(s) = get_string(&map, "LeaderKey") {
                 config.leader_key = s;
             }
+            if let Some(ms) = get_int(&map, "LeaderTimeoutMs") {
+                config.leader_timeout_ms = ms.max(0) as u64;
+            }
             // ... other bindings ...
 
             // Custom bindings
@@ -304,6 +380,21 @@ impl FusabiConfigLoader {
                     }
                 }
             }
+
+            // Key tables: name -> (key -> action)
+            if let Some(Value::Map(tables)) = map.get("KeyTables") {
+                for (name, table) in tables.lock().unwrap().iter() {
+                    let Value::Map(bindings) = table else {
+                        continue;
+                    };
+                    let entry = config.key_tables.entry(name.clone()).or_default();
+                    for (key, action) in bindings.lock().unwrap().iter() {
+                        if let Value::Str(action) = action {
+                            entry.insert(key.clone(), action.to_string());
+                        }
+                    }
+                }
+            }
         }
 
         Ok(config)
@@ -329,18 +420,69 @@ impl FusabiConfigLoader {
             if let Some(b) = get_bool(&map, "Animations") {
                 config.animations = b;
             }
+            if let Some(b) = get_bool(&map, "ReduceMotion") {
+                config.reduce_motion = b;
+            }
+            if let Some(b) = get_bool(&map, "ReduceFlashing") {
+                config.reduce_flashing = b;
+            }
             if let Some(b) = get_bool(&map, "SmoothScroll") {
                 config.smooth_scroll = b;
             }
             if let Some(b) = get_bool(&map, "ShowTabs") {
                 config.show_tabs = b;
             }
+            if let Some(b) = get_bool(&map, "ShowScrollbar") {
+                config.show_scrollbar = b;
+            }
+            if let Some(b) = get_bool(&map, "ShowMinimap") {
+                config.show_minimap = b;
+            }
+            if let Some(b) = get_bool(&map, "ShowCommandGutter") {
+                config.show_command_gutter = b;
+            }
+            if let Some(b) = get_bool(&map, "TabShowIndex") {
+                config.tab_show_index = b;
+            }
+            if let Some(b) = get_bool(&map, "TabShowTitle") {
+                config.tab_show_title = b;
+            }
+            if let Some(b) = get_bool(&map, "TabShowActivity") {
+                config.tab_show_activity = b;
+            }
+            if let Some(f) = get_float(&map, "PaneBorderWidth") {
+                config.pane_border_width = f as f32;
+            }
+            if let Some(s) = get_string(&map, "PaneBorderColor") {
+                config.pane_border_color = Some(s);
+            }
+            if let Some(s) = get_string(&map, "PaneBorderFocusedColor") {
+                config.pane_border_focused_color = Some(s);
+            }
+            if let Some(f) = get_float(&map, "InactivePaneBrightness") {
+                config.inactive_pane_brightness = f as f32;
+            }
             if let Some(b) = get_bool(&map, "CursorBlink") {
                 config.cursor_blink = b;
             }
             if let Some(i) = get_int(&map, "CursorBlinkInterval") {
                 config.cursor_blink_interval = i as u32;
             }
+            if let Some(b) = get_bool(&map, "CursorSmooth") {
+                config.cursor_smooth = b;
+            }
+            if let Some(s) = get_string(&map, "BackgroundImage") {
+                config.background_image = Some(s);
+            }
+            if let Some(f) = get_float(&map, "BackgroundImageOpacity") {
+                config.background_image_opacity = f as f32;
+            }
+            if let Some(f) = get_float(&map, "BackgroundDim") {
+                config.background_dim = f as f32;
+            }
+            if let Some(b) = get_bool(&map, "BackgroundBlur") {
+                config.background_blur = b;
+            }
             if let Some(s) = get_string(&map, "WindowIcon") {
                 config.window_icon = Some(s);
             }
@@ -403,20 +545,182 @@ impl FusabiConfigLoader {
             if let Some(s) = get_string(&map, "WorkingDirectory") {
                 config.working_directory = Some(s);
             }
+            if let Some(i) = get_int(&map, "LockAfterIdle") {
+                config.lock_after_idle = i as u32;
+            }
+            if let Some(s) = get_string(&map, "LockPasswordHash") {
+                config.lock_password_hash = Some(s);
+            }
+            if let Some(Value::Tuple(vec)) = map.get("StartupTabs") {
+                config.startup_tabs = vec
+                    .iter()
+                    .filter_map(|v| match v {
+                        Value::Str(s) => Some(s.to_string()),
+                        _ => None,
+                    })
+                    .collect();
+            }
         }
 
         Ok(config)
     }
 }
 
+/// What a section record field must hold to be read
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum FieldKind {
+    Str,
+    Int,
+    /// A float, or an int standing in for one
+    Float,
+    Bool,
+    Tuple,
+    Map,
+    /// A record with these fields
+    Record(&'static [(&'static str, FieldKind)]),
+    /// A record with fields of any name
+    AnyRecord,
+}
+
+impl FieldKind {
+    pub(crate) fn accepts(self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (FieldKind::Str, Value::Str(_))
+                | (FieldKind::Int, Value::Int(_))
+                | (FieldKind::Float, Value::Float(_) | Value::Int(_))
+                | (FieldKind::Bool, Value::Bool(_))
+                | (FieldKind::Tuple, Value::Tuple(_))
+                | (FieldKind::Map, Value::Map(_))
+                | (
+                    FieldKind::Record(_) | FieldKind::AnyRecord,
+                    Value::Record(_)
+                )
+        )
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            FieldKind::Str => "a string",
+            FieldKind::Int => "an int",
+            FieldKind::Float => "a float",
+            FieldKind::Bool => "a bool",
+            FieldKind::Tuple => "a tuple",
+            FieldKind::Map => "a map",
+            FieldKind::Record(_) | FieldKind::AnyRecord => "a record",
+        }
+    }
+}
+
+const PALETTE_FIELDS: &[(&str, FieldKind)] = &[
+    ("Black", FieldKind::Str),
+    ("Red", FieldKind::Str),
+    ("Green", FieldKind::Str),
+    ("Yellow", FieldKind::Str),
+    ("Blue", FieldKind::Str),
+    ("Magenta", FieldKind::Str),
+    ("Cyan", FieldKind::Str),
+    ("White", FieldKind::Str),
+    ("BrightBlack", FieldKind::Str),
+    ("BrightRed", FieldKind::Str),
+    ("BrightGreen", FieldKind::Str),
+    ("BrightYellow", FieldKind::Str),
+    ("BrightBlue", FieldKind::Str),
+    ("BrightMagenta", FieldKind::Str),
+    ("BrightCyan", FieldKind::Str),
+    ("BrightWhite", FieldKind::Str),
+];
+
+/// Fields the `extract_*` functions read from each section's record
+///
+/// Keep in step with those functions: `scarab-config check` reports any
+/// other field as unknown.
+pub(crate) fn section_fields(section: ConfigSection) -> &'static [(&'static str, FieldKind)] {
+    match section {
+        ConfigSection::Terminal => &[
+            ("DefaultShell", FieldKind::Str),
+            ("ScrollbackLines", FieldKind::Int),
+            ("AltScreen", FieldKind::Bool),
+            ("ScrollMultiplier", FieldKind::Float),
+            ("AutoScroll", FieldKind::Bool),
+            ("Columns", FieldKind::Int),
+            ("Rows", FieldKind::Int),
+        ],
+        ConfigSection::Font => &[
+            ("Family", FieldKind::Str),
+            ("Size", FieldKind::Float),
+            ("LineHeight", FieldKind::Float),
+            ("BoldIsBright", FieldKind::Bool),
+            ("UseThinStrokes", FieldKind::Bool),
+            ("Ligatures", FieldKind::Bool),
+            ("Features", FieldKind::AnyRecord),
+            ("Fallback", FieldKind::Tuple),
+        ],
+        ConfigSection::Colors => &[
+            ("Theme", FieldKind::Str),
+            ("LightTheme", FieldKind::Str),
+            ("DarkTheme", FieldKind::Str),
+            ("Opacity", FieldKind::Float),
+            ("DimOpacity", FieldKind::Float),
+            ("MinimumContrast", FieldKind::Float),
+            ("Foreground", FieldKind::Str),
+            ("Background", FieldKind::Str),
+            ("Cursor", FieldKind::Str),
+            ("SelectionBackground", FieldKind::Str),
+            ("SelectionForeground", FieldKind::Str),
+            ("Palette", FieldKind::Record(PALETTE_FIELDS)),
+        ],
+        ConfigSection::KeyBindings => &[
+            ("LeaderKey", FieldKind::Str),
+            ("LeaderTimeoutMs", FieldKind::Int),
+            ("Custom", FieldKind::Map),
+            ("KeyTables", FieldKind::Map),
+        ],
+        ConfigSection::Ui => &[
+            ("LinkHints", FieldKind::Bool),
+            ("CommandPalette", FieldKind::Bool),
+            ("Animations", FieldKind::Bool),
+            ("ReduceMotion", FieldKind::Bool),
+            ("ReduceFlashing", FieldKind::Bool),
+            ("SmoothScroll", FieldKind::Bool),
+            ("ShowTabs", FieldKind::Bool),
+            ("ShowScrollbar", FieldKind::Bool),
+            ("ShowMinimap", FieldKind::Bool),
+            ("TabShowIndex", FieldKind::Bool),
+            ("TabShowTitle", FieldKind::Bool),
+            ("TabShowActivity", FieldKind::Bool),
+            ("PaneBorderWidth", FieldKind::Float),
+            ("PaneBorderColor", FieldKind::Str),
+            ("PaneBorderFocusedColor", FieldKind::Str),
+            ("InactivePaneBrightness", FieldKind::Float),
+            ("CursorBlink", FieldKind::Bool),
+            ("CursorBlinkInterval", FieldKind::Int),
+            ("CursorSmooth", FieldKind::Bool),
+            ("BackgroundImage", FieldKind::Str),
+            ("BackgroundImageOpacity", FieldKind::Float),
+            ("BackgroundDim", FieldKind::Float),
+            ("BackgroundBlur", FieldKind::Bool),
+            ("WindowIcon", FieldKind::Str),
+        ],
+        ConfigSection::Plugins => &[("Enabled", FieldKind::Tuple)],
+        ConfigSection::Sessions => &[
+            ("RestoreOnStartup", FieldKind::Bool),
+            ("AutoSaveInterval", FieldKind::Int),
+            ("SaveScrollback", FieldKind::Bool),
+            ("WorkingDirectory", FieldKind::Str),
+            ("StartupTabs", FieldKind::Tuple),
+        ],
+    }
+}
+
 /// Wrapper for Fusabi VM
-struct FusabiModule {
-    vm: Vm,
-    result: Value,
+pub(crate) struct FusabiModule {
+    pub(crate) vm: Vm,
+    pub(crate) result: Value,
 }
 
 impl FusabiModule {
-    fn get_global(&self, name: &str) -> Option<Value> {
+    pub(crate) fn get_global(&self, name: &str) -> Option<Value> {
         // First check VM globals
         if let Some(v) = self.vm.globals.get(name) {
             return Some(v.clone());
@@ -0,0 +1,477 @@
+//! Incremental re-evaluation of `config.fsx` on hot reload
+//!
+//! A [`FusabiConfigSession`] keeps the VM that evaluated the config and
+//! splits the source into its top-level bindings. When the file changes,
+//! only bindings whose text changed, and the bindings that use them, are
+//! run again against the existing globals; everything else keeps its value.
+//! Only the sections bound to recomputed names are read back, and only
+//! those that came out different are reported, so a theme tweak does not
+//! rebuild the keymap.
+//!
+//! Anything the split cannot reason about (bindings added, removed or
+//! reordered, or a name bound twice) falls back to evaluating the whole
+//! config.
+
+use crate::config::ScarabConfig;
+use crate::error::{ConfigError, Result};
+use crate::fusabi_loader::{FusabiConfigLoader, FusabiModule};
+use crate::fusabi_modules::{top_level_binding, AstCache};
+use bevy::prelude::Event;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+use tracing::{debug, info};
+
+/// A part of [`ScarabConfig`] read from its own top-level binding
+///
+/// `[paths]` is not one: it only comes from `config.toml` and is resolved
+/// once at startup, since the daemon and clients have to agree on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigSection {
+    Terminal,
+    Font,
+    Colors,
+    KeyBindings,
+    Ui,
+    Plugins,
+    Sessions,
+}
+
+impl ConfigSection {
+    pub const ALL: [ConfigSection; 7] = [
+        ConfigSection::Terminal,
+        ConfigSection::Font,
+        ConfigSection::Colors,
+        ConfigSection::KeyBindings,
+        ConfigSection::Ui,
+        ConfigSection::Plugins,
+        ConfigSection::Sessions,
+    ];
+
+    /// Name of the binding the section is read from
+    pub fn binding(self) -> &'static str {
+        match self {
+            ConfigSection::Terminal => "terminal",
+            ConfigSection::Font => "font",
+            ConfigSection::Colors => "colors",
+            ConfigSection::KeyBindings => "keybindings",
+            ConfigSection::Ui => "ui",
+            ConfigSection::Plugins => "plugins",
+            ConfigSection::Sessions => "sessions",
+        }
+    }
+
+    /// Copy this section from one config to another
+    pub fn copy(self, from: &ScarabConfig, to: &mut ScarabConfig) {
+        match self {
+            ConfigSection::Terminal => to.terminal = from.terminal.clone(),
+            ConfigSection::Font => to.font = from.font.clone(),
+            ConfigSection::Colors => to.colors = from.colors.clone(),
+            ConfigSection::KeyBindings => to.keybindings = from.keybindings.clone(),
+            ConfigSection::Ui => to.ui = from.ui.clone(),
+            ConfigSection::Plugins => to.plugins = from.plugins.clone(),
+            ConfigSection::Sessions => to.sessions = from.sessions.clone(),
+        }
+    }
+
+    fn differs(self, a: &ScarabConfig, b: &ScarabConfig) -> bool {
+        match self {
+            ConfigSection::Terminal => a.terminal != b.terminal,
+            ConfigSection::Font => a.font != b.font,
+            ConfigSection::Colors => a.colors != b.colors,
+            ConfigSection::KeyBindings => a.keybindings != b.keybindings,
+            ConfigSection::Ui => a.ui != b.ui,
+            ConfigSection::Plugins => a.plugins != b.plugins,
+            ConfigSection::Sessions => a.sessions != b.sessions,
+        }
+    }
+}
+
+/// Sections of the config that a hot reload changed
+///
+/// Systems that rebuild state from one section, such as the key tables,
+/// read this instead of watching the whole [`ScarabConfig`] for changes.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ConfigSectionsChanged {
+    pub sections: Vec<ConfigSection>,
+}
+
+impl ConfigSectionsChanged {
+    /// Every section, for a config replaced as a whole
+    pub fn all() -> Self {
+        Self {
+            sections: ConfigSection::ALL.to_vec(),
+        }
+    }
+
+    pub fn contains(&self, section: ConfigSection) -> bool {
+        self.sections.contains(&section)
+    }
+}
+
+/// Outcome of [`FusabiConfigSession::reload`]
+#[derive(Debug, Clone)]
+pub struct ConfigReload {
+    /// Sections whose value changed
+    pub changed: Vec<ConfigSection>,
+    /// Top-level bindings that were evaluated again
+    pub recomputed: Vec<String>,
+    /// Whether the whole config had to be evaluated again
+    pub full: bool,
+}
+
+/// A top-level binding, or an expression between bindings
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Segment {
+    /// Name bound, `None` for an expression
+    name: Option<String>,
+    text: String,
+    /// Identifiers the text mentions
+    refs: HashSet<String>,
+}
+
+/// An evaluated `config.fsx` that can be reloaded incrementally
+///
+/// The VM is not `Send`, so the session stays on the thread that made it.
+pub struct FusabiConfigSession {
+    path: PathBuf,
+    modules: AstCache,
+    module: FusabiModule,
+    segments: Vec<Segment>,
+    config: ScarabConfig,
+    /// The config and the files it loads, with their modification times
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl FusabiConfigSession {
+    /// Evaluate the config at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut modules = AstCache::new();
+        let resolved = modules.resolve(&path)?;
+        let module = FusabiConfigLoader::evaluate(&resolved.source)?;
+
+        let mut config = ScarabConfig::default();
+        for section in ConfigSection::ALL {
+            FusabiConfigLoader::extract_section(&module, section, &mut config);
+        }
+
+        let mut session = Self {
+            segments: split_segments(&resolved.source),
+            files: Vec::new(),
+            path,
+            modules,
+            module,
+            config,
+        };
+        session.record_files(resolved.dependencies);
+        Ok(session)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The config as of the last successful (re)load
+    pub fn config(&self) -> &ScarabConfig {
+        &self.config
+    }
+
+    /// Whether the config or a file it loads changed since it was evaluated
+    pub fn is_stale(&self) -> bool {
+        self.files
+            .iter()
+            .any(|(path, modified)| modified_time(path) != *modified)
+    }
+
+    /// Evaluate what changed on disk and update the config
+    ///
+    /// On error the previous config stays in place, and the next reload
+    /// evaluates everything.
+    pub fn reload(&mut self) -> Result<ConfigReload> {
+        let start = Instant::now();
+        let resolved = self.modules.resolve(&self.path)?;
+        let segments = split_segments(&resolved.source);
+        self.record_files(resolved.dependencies);
+
+        let (recomputed, full, read_all) = match plan_rerun(&self.segments, &segments) {
+            Some(rerun) => {
+                if !rerun.is_empty() {
+                    let source: String = rerun.iter().map(|&i| segments[i].text.as_str()).collect();
+                    let result = FusabiConfigLoader::compile(&source).and_then(|chunk| {
+                        self.module.vm.execute(chunk).map_err(|e| {
+                            ConfigError::FusabiRuntimeError(format!(
+                                "Failed to execute config: {:?}",
+                                e
+                            ))
+                        })
+                    });
+                    let value = match result {
+                        Ok(value) => value,
+                        Err(e) => {
+                            // Globals may be half updated; start over next time
+                            self.segments.clear();
+                            return Err(e);
+                        }
+                    };
+                    // A trailing expression is the config's result value
+                    let last = segments.len() - 1;
+                    if rerun.last() == Some(&last) && segments[last].name.is_none() {
+                        self.module.result = value;
+                    }
+                }
+                let names = rerun
+                    .iter()
+                    .filter_map(|&i| segments[i].name.clone())
+                    .collect();
+                let read_all = rerun.iter().any(|&i| segments[i].name.is_none());
+                (names, false, read_all)
+            }
+            None => {
+                self.module = FusabiConfigLoader::evaluate(&resolved.source)?;
+                let names = segments.iter().filter_map(|s| s.name.clone()).collect();
+                (names, true, true)
+            }
+        };
+        self.segments = segments;
+
+        let mut config = self.config.clone();
+        for section in ConfigSection::ALL {
+            if read_all || recomputed.iter().any(|name| name == section.binding()) {
+                // Start from the default, as a fresh load would
+                section.copy(&ScarabConfig::default(), &mut config);
+                FusabiConfigLoader::extract_section(&self.module, section, &mut config);
+            }
+        }
+        let changed: Vec<ConfigSection> = ConfigSection::ALL
+            .into_iter()
+            .filter(|section| section.differs(&self.config, &config))
+            .collect();
+        self.config = config;
+
+        info!(
+            "Reloaded {} in {}ms ({} of {} bindings evaluated{})",
+            self.path.display(),
+            start.elapsed().as_millis(),
+            recomputed.len(),
+            self.segments.iter().filter(|s| s.name.is_some()).count(),
+            if full { ", full" } else { "" }
+        );
+        debug!("Recomputed {:?}, changed {:?}", recomputed, changed);
+
+        Ok(ConfigReload {
+            changed,
+            recomputed,
+            full,
+        })
+    }
+
+    fn record_files(&mut self, dependencies: Vec<PathBuf>) {
+        self.files = std::iter::once(self.path.clone())
+            .chain(dependencies)
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect();
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Split flattened source into top-level segments
+///
+/// A segment starts at an unindented line other than a comment or a
+/// closing bracket, and takes the comments and blank lines just above it.
+fn split_segments(source: &str) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut pending = String::new();
+
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            pending.push_str(line);
+            continue;
+        }
+
+        let unindented = !line.starts_with(char::is_whitespace);
+        let closes = trimmed.starts_with(['}', ')', ']', '|']);
+        if (unindented && !closes) || segments.is_empty() {
+            segments.push(Segment {
+                name: top_level_binding(line),
+                text: std::mem::take(&mut pending),
+                refs: HashSet::new(),
+            });
+        }
+        let segment = segments.last_mut().expect("segment was just pushed");
+        segment.text.push_str(&pending);
+        pending.clear();
+        segment.text.push_str(line);
+    }
+
+    match segments.last_mut() {
+        Some(segment) => segment.text.push_str(&pending),
+        None if !pending.is_empty() => segments.push(Segment {
+            name: None,
+            text: pending,
+            refs: HashSet::new(),
+        }),
+        None => {}
+    }
+
+    for segment in &mut segments {
+        segment.refs = identifiers(&segment.text);
+    }
+    segments
+}
+
+/// Indices of the segments of `new` to run again, in order, or `None` if
+/// the whole config has to be evaluated
+fn plan_rerun(old: &[Segment], new: &[Segment]) -> Option<Vec<usize>> {
+    if old.len() != new.len() || old.iter().zip(new).any(|(a, b)| a.name != b.name) {
+        return None;
+    }
+    // Re-running one of two bindings of a name would see the other's value
+    let mut bound = HashSet::new();
+    if new
+        .iter()
+        .filter_map(|s| s.name.as_deref())
+        .any(|name| !bound.insert(name))
+    {
+        return None;
+    }
+
+    let mut dirty: HashSet<&str> = HashSet::new();
+    let mut rerun = Vec::new();
+    for (index, (before, after)) in old.iter().zip(new).enumerate() {
+        let uses_dirty = after.refs.iter().any(|r| dirty.contains(r.as_str()));
+        if before.text != after.text || uses_dirty {
+            rerun.push(index);
+            if let Some(name) = &after.name {
+                dirty.insert(name);
+            }
+        }
+    }
+    Some(rerun)
+}
+
+/// Identifiers in `text`, skipping strings and comments
+fn identifiers(text: &str) -> HashSet<String> {
+    let mut found = HashSet::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' || next == '\'' {
+                        word.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                found.insert(word);
+            }
+            // Skip the rest of a number so `1e5` is not read as `e5`
+            c if c.is_ascii_digit() => {
+                while chars.peek().is_some_and(|c| c.is_alphanumeric()) {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    const CONFIG: &str = r#"// Shared size
+let size = 14.0
+
+let font = {
+    Family = "Hack";
+    Size = size
+}
+
+let terminal = {
+    ScrollbackLines = 5000
+}
+"#;
+
+    #[test]
+    fn test_split_and_plan() {
+        let old = split_segments(CONFIG);
+        let names: Vec<_> = old.iter().map(|s| s.name.as_deref()).collect();
+        assert_eq!(names, [Some("size"), Some("font"), Some("terminal")]);
+        assert!(old[0].text.starts_with("// Shared size"));
+        assert!(old[1].refs.contains("size"));
+        assert!(!old[1].refs.contains("Hack"));
+        assert_eq!(
+            old.iter().map(|s| s.text.as_str()).collect::<String>(),
+            CONFIG
+        );
+
+        // Changing `size` reruns `font`, which uses it, but not `terminal`
+        let new = split_segments(&CONFIG.replace("14.0", "16.0"));
+        assert_eq!(plan_rerun(&old, &new), Some(vec![0, 1]));
+        assert_eq!(plan_rerun(&old, &old), Some(vec![]));
+
+        let added = split_segments(&format!("{}let ui = {{ Minimap = true }}\n", CONFIG));
+        assert_eq!(plan_rerun(&old, &added), None);
+        let rebound = split_segments("let x = 1\nlet x = x + 1\n");
+        assert_eq!(plan_rerun(&rebound, &rebound), None);
+    }
+
+    #[test]
+    fn test_reload_reports_changed_sections() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.fsx");
+        fs::write(&path, CONFIG).unwrap();
+
+        let mut session = FusabiConfigSession::load(&path).unwrap();
+        assert_eq!(session.config().font.size, 14.0);
+        assert_eq!(session.config().terminal.scrollback_lines, 5000);
+        assert!(!session.is_stale());
+
+        fs::write(&path, CONFIG.replace("14.0", "16.0")).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+        assert!(session.is_stale());
+
+        let reload = session.reload().unwrap();
+        assert!(!reload.full);
+        assert_eq!(reload.recomputed, ["size", "font"]);
+        assert_eq!(reload.changed, [ConfigSection::Font]);
+        assert_eq!(session.config().font.size, 16.0);
+        assert_eq!(session.config().terminal.scrollback_lines, 5000);
+        assert!(!session.is_stale());
+    }
+}
@@ -4,38 +4,65 @@
 //! - Type-safe configuration structs
 //! - Bevy plugin for asset-based hot-reloading
 
+pub mod check;
 pub mod config;
 pub mod error;
+pub mod expand;
 pub mod fusabi_loader;
+pub use scarab_plugin_api::fusabi_modules;
+pub mod fusabi_reload;
 pub mod loader;
+pub mod lock;
+pub mod migrate;
 pub mod plugin;
+pub mod profiles;
 pub mod registry;
+pub mod settings;
 pub mod theme_resolver;
+pub mod upgrade;
 pub mod validation;
 pub mod watcher;
 
+pub use check::{check_file, CheckReport, Diagnostic, Severity};
 pub use config::{
-    ColorConfig, ColorPalette, CursorStyle, EffectsConfig, FontConfig, KeyBindings, NavConfig,
-    NavStyle, PluginConfig, ScarabConfig, SessionConfig, SshAuthConfig, SshDomainConfig,
-    TabPosition, TerminalConfig, UiConfig,
+    BackgroundImageFit, ColorConfig, ColorPalette, CursorStyle, CustomTheme, EffectsConfig,
+    FontConfig, KeyBindings, NavConfig, NavStyle, PaletteOverrides, PaneThemeRule, PathsConfig,
+    PluginConfig, ScarabConfig, SerialDomainConfig, SerialFlowControl, SerialParity, SessionConfig,
+    SshAuthConfig, SshDomainConfig, TabPosition, TerminalConfig, UiConfig,
 };
 pub use error::{ConfigError, Result};
 pub use fusabi_loader::FusabiConfigLoader;
-pub use loader::ConfigLoader;
-pub use plugin::{ConfigHandle, ScarabConfigPlugin};
+pub use fusabi_modules::{AstCache, ResolvedSource};
+pub use fusabi_reload::{ConfigReload, ConfigSection, ConfigSectionsChanged, FusabiConfigSession};
+pub use loader::{ConfigLoader, LayeredConfig};
+pub use lock::{hash_lock_password, lock_password_matches};
+pub use migrate::toml_to_fsx;
+pub use plugin::{ConfigHandle, FusabiConfigReloadPlugin, ScarabConfigPlugin};
+pub use profiles::{apply_profiles, directory_settings, Profile, ProfileEnv};
 pub use registry::{PluginFilter, RegistryManager};
+pub use settings::{get_setting, parse_setting_value, persist_setting, set_setting};
 pub use theme_resolver::ThemeResolver;
+pub use upgrade::{upgrade_file, Upgrade, CONFIG_VERSION};
 pub use validation::ConfigValidator;
 pub use watcher::ConfigWatcher;
 
 pub mod prelude {
+    pub use crate::check::*;
     pub use crate::config::*;
     pub use crate::error::*;
+    pub use crate::expand::*;
     pub use crate::fusabi_loader::*;
+    pub use crate::fusabi_modules::*;
+    pub use crate::fusabi_reload::*;
     pub use crate::loader::*;
+    pub use crate::lock::*;
+    pub use crate::migrate::*;
     pub use crate::plugin::*;
+    pub use crate::profiles::*;
     pub use crate::registry::*;
+    pub use crate::settings::*;
     pub use crate::theme_resolver::*;
+    pub use crate::upgrade::*;
     pub use crate::validation::*;
     pub use crate::watcher::*;
 }
@@ -1,18 +1,43 @@
 //! Configuration file loading and discovery
 
 use crate::{
-    error::Result, theme_resolver::ThemeResolver, ConfigError, ConfigValidator, ScarabConfig,
+    error::Result,
+    expand,
+    profiles::{self, Profile, ProfileEnv},
+    theme_resolver::ThemeResolver,
+    upgrade::{self, Upgrade},
+    ConfigError, ConfigValidator, FusabiConfigLoader, ScarabConfig,
 };
+use scarab_platform::Paths;
 use std::{
     env, fs,
     path::{Path, PathBuf},
 };
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Project config file names, in the order they are looked for in each directory
+const LOCAL_CONFIG_NAMES: [&str; 2] = [".scarab.fsx", ".scarab.toml"];
+
+/// Key listing other TOML files to read before the file's own settings
+const INCLUDE_KEY: &str = "include";
+
+/// A config with profiles and the project config laid over it
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: ScarabConfig,
+    /// Profiles applied, in order
+    pub profiles: Vec<String>,
+    /// Project config merged last, if one was found
+    pub project: Option<PathBuf>,
+}
 
 /// Configuration loader with discovery
 pub struct ConfigLoader {
     global_path: PathBuf,
     theme_resolver: ThemeResolver,
+    /// Whether profiles with a `directory` are matched against the working
+    /// directory when layering
+    directory_profiles: bool,
 }
 
 impl ConfigLoader {
@@ -21,6 +46,7 @@ impl ConfigLoader {
         Self {
             global_path: Self::default_config_path(),
             theme_resolver: ThemeResolver::new(),
+            directory_profiles: true,
         }
     }
 
@@ -29,25 +55,45 @@ impl ConfigLoader {
         Self {
             global_path: path,
             theme_resolver: ThemeResolver::new(),
+            directory_profiles: true,
         }
     }
 
+    /// Leave profiles with a `directory` out of [`layer`](Self::layer)
+    ///
+    /// For the terminal, whose own working directory says nothing about the
+    /// panes': the daemon applies those profiles per pane instead.
+    pub fn without_directory_profiles(mut self) -> Self {
+        self.directory_profiles = false;
+        self
+    }
+
     /// Get default global config path (~/.config/scarab/config.toml)
+    ///
+    /// The directory can be moved with `SCARAB_CONFIG_DIR`.
     pub fn default_config_path() -> PathBuf {
-        dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("scarab")
-            .join("config.toml")
+        Paths::from_env().config_dir.join("config.toml")
+    }
+
+    /// Where the socket, shared memory and data files are, after `[paths]`
+    /// in the global config and the environment
+    ///
+    /// For programs that talk to the daemon without loading the whole
+    /// config; a global config that can't be read counts as having no
+    /// `[paths]`.
+    pub fn paths(&self) -> Paths {
+        let settings = Self::from_file(&self.global_path)
+            .and_then(|mut config| {
+                expand::expand_paths(&mut config)?;
+                Ok(config.paths)
+            })
+            .unwrap_or_default();
+        settings.resolve()
     }
 
     /// Load configuration with global + local merging
     pub fn load(&self) -> Result<ScarabConfig> {
-        let mut config = self.load_global()?;
-
-        if let Some(local) = self.load_local()? {
-            debug!("Found local config, merging with global");
-            config.merge(local);
-        }
+        let mut config = self.apply_overrides(self.load_global()?)?;
 
         // Resolve theme if specified
         self.theme_resolver.resolve(&mut config.colors)?;
@@ -57,9 +103,81 @@ impl ConfigLoader {
         Ok(config)
     }
 
+    /// Lay matching profiles and the project config over a loaded global config
+    ///
+    /// Precedence, lowest first: `config` itself, the profiles in it and in
+    /// `profiles.toml` next to the global config that match this host,
+    /// environment and working directory (in file order), then the nearest
+    /// `.scarab.fsx` or `.scarab.toml` walking up from the working directory.
+    /// Path-valued fields then have `~` and `${VAR}` expanded.
+    ///
+    /// See [`without_directory_profiles`](Self::without_directory_profiles)
+    /// to leave profiles with a `directory` out.
+    pub fn apply_overrides(&self, config: ScarabConfig) -> Result<ScarabConfig> {
+        Ok(self.layer(config)?.config)
+    }
+
+    /// [`apply_overrides`](Self::apply_overrides), or `config` with only its
+    /// paths expanded if a profile or the project config can't be read
+    ///
+    /// For the terminal, which should still start with a broken
+    /// `profiles.toml` or `.scarab.toml`.
+    pub fn apply_overrides_or_base(&self, config: ScarabConfig) -> ScarabConfig {
+        match self.apply_overrides(config.clone()) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Ignoring config profiles and project config: {}", e);
+                let mut config = config;
+                if let Err(e) = expand::expand_paths(&mut config) {
+                    warn!("Failed to expand config paths: {}", e);
+                }
+                config
+            }
+        }
+    }
+
+    /// Profiles in `config` and in `profiles.toml` next to the global config
+    pub fn profiles(&self, config: &ScarabConfig) -> Result<Vec<Profile>> {
+        let mut profile_list = config.profiles.clone();
+        if let Some(dir) = self.global_path.parent() {
+            profile_list.extend(profiles::load_profiles_file(&profiles::profiles_file(dir))?);
+        }
+        Ok(profile_list)
+    }
+
+    /// [`apply_overrides`](Self::apply_overrides), also saying what was applied
+    pub fn layer(&self, mut config: ScarabConfig) -> Result<LayeredConfig> {
+        let mut profile_list = self.profiles(&config)?;
+        if !self.directory_profiles {
+            profile_list.retain(|profile| profile.directory.is_none());
+        }
+
+        let applied = profiles::apply_profiles(&mut config, &profile_list, &ProfileEnv::current())?;
+        if !applied.is_empty() {
+            info!("Applied config profiles: {}", applied.join(", "));
+        }
+
+        let project = Self::find_local_path(&env::current_dir()?);
+        if let Some(path) = &project {
+            info!("Found local config at: {}", path.display());
+            config.merge(Self::from_any_file(path)?);
+        }
+
+        expand::expand_paths(&mut config)?;
+
+        Ok(LayeredConfig {
+            config,
+            profiles: applied,
+            project,
+        })
+    }
+
     /// Load global configuration
     fn load_global(&self) -> Result<ScarabConfig> {
         if self.global_path.exists() {
+            if let Some(upgrade) = Self::upgrade_in_place(&self.global_path) {
+                info!("Upgraded {}: {}", self.global_path.display(), upgrade);
+            }
             info!("Loading global config from: {}", self.global_path.display());
             Self::from_file(&self.global_path)
         } else {
@@ -68,16 +186,16 @@ impl ConfigLoader {
         }
     }
 
-    /// Load local configuration by walking up from cwd
-    fn load_local(&self) -> Result<Option<ScarabConfig>> {
-        let mut current = env::current_dir()?;
+    /// Nearest project config in `dir` or its ancestors
+    pub fn find_local_path(dir: &Path) -> Option<PathBuf> {
+        let mut current = dir.to_path_buf();
 
         loop {
-            let config_path = current.join(".scarab.toml");
-
-            if config_path.exists() {
-                info!("Found local config at: {}", config_path.display());
-                return Ok(Some(Self::from_file(&config_path)?));
+            for name in LOCAL_CONFIG_NAMES {
+                let config_path = current.join(name);
+                if config_path.exists() {
+                    return Some(config_path);
+                }
             }
 
             // Try to go up one directory
@@ -87,19 +205,161 @@ impl ConfigLoader {
         }
 
         debug!("No local config found in directory tree");
-        Ok(None)
+        None
     }
 
     /// Load config from a specific file
+    ///
+    /// Files listed in its `include` are read first, relative to the file,
+    /// and the file's own settings are laid over them key by key. Included
+    /// files can include others; a file including itself, directly or not,
+    /// is an error naming the chain. Files in an older layout are upgraded
+    /// as they are read, without changing them on disk.
     pub fn from_file(path: &Path) -> Result<ScarabConfig> {
         let content = fs::read_to_string(path)
             .map_err(|_| ConfigError::FileNotFound(path.display().to_string()))?;
+        let content = Self::upgraded(path, content)?;
 
-        let config: ScarabConfig = toml::from_str(&content)?;
+        // Without includes, keep the line numbers in type errors
+        let table: toml::Table = toml::from_str(&content)?;
+        let config: ScarabConfig = if table.contains_key(INCLUDE_KEY) {
+            let merged = Self::read_with_includes(path, table, &mut Vec::new(), &mut Vec::new())?;
+            toml::Value::Table(merged).try_into()?
+        } else {
+            toml::from_str(&content)?
+        };
         debug!("Loaded config from: {}", path.display());
         Ok(config)
     }
 
+    /// Upgrade the TOML config at `path` to the current layout on disk
+    ///
+    /// The old file is kept beside it (see [`upgrade::upgrade_file`]).
+    /// Returns what changed, or `None` if nothing did. A file that can't be
+    /// rewritten is only logged, since [`from_file`](Self::from_file)
+    /// upgrades it in memory anyway.
+    pub fn upgrade_in_place(path: &Path) -> Option<Upgrade> {
+        match upgrade::upgrade_file(path) {
+            Ok(upgrade) => upgrade,
+            Err(e) => {
+                warn!("Could not upgrade {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// `content` of `path` in the current layout
+    fn upgraded(path: &Path, content: String) -> Result<String> {
+        match upgrade::upgrade_source(&content)? {
+            Some((upgraded, upgrade)) => {
+                warn!(
+                    "{} uses an older config layout; read it as {}",
+                    path.display(),
+                    upgrade
+                );
+                Ok(upgraded)
+            }
+            None => Ok(content),
+        }
+    }
+
+    /// Files included by the TOML config at `path`, directly or not
+    ///
+    /// Files that can't be read are left out.
+    pub fn included_files(path: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let table = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str::<toml::Table>(&content).ok());
+        if let Some(table) = table {
+            let _ = Self::read_with_includes(path, table, &mut Vec::new(), &mut files);
+        }
+        files
+    }
+
+    /// `table`, read from `path`, laid over the files it includes
+    ///
+    /// `stack` holds the files being included, to catch cycles; every file
+    /// read is added to `files`.
+    fn read_with_includes(
+        path: &Path,
+        mut table: toml::Table,
+        stack: &mut Vec<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<toml::Table> {
+        let Some(includes) = table.remove(INCLUDE_KEY) else {
+            return Ok(table);
+        };
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        stack.push(canonical);
+
+        let mut merged = toml::Table::new();
+        for include in Self::include_paths(path, includes)? {
+            let canonical = fs::canonicalize(&include).unwrap_or_else(|_| include.clone());
+            if let Some(start) = stack.iter().position(|p| *p == canonical) {
+                let chain: Vec<String> = stack[start..]
+                    .iter()
+                    .chain([&canonical])
+                    .map(|p| p.display().to_string())
+                    .collect();
+                return Err(ConfigError::Validation(format!(
+                    "include cycle: {}",
+                    chain.join(" -> ")
+                )));
+            }
+
+            let content = fs::read_to_string(&include).map_err(|_| {
+                ConfigError::FileNotFound(format!(
+                    "{} (included from {})",
+                    include.display(),
+                    path.display()
+                ))
+            })?;
+            let content = Self::upgraded(&include, content)?;
+            let included: toml::Table = toml::from_str(&content)
+                .map_err(|e| ConfigError::Validation(format!("{}: {}", include.display(), e)))?;
+            files.push(include.clone());
+            let included = Self::read_with_includes(&include, included, stack, files)?;
+            profiles::merge_tables(&mut merged, included);
+        }
+
+        stack.pop();
+        profiles::merge_tables(&mut merged, table);
+        Ok(merged)
+    }
+
+    /// Paths listed in an `include` value, resolved against `path`'s directory
+    fn include_paths(path: &Path, includes: toml::Value) -> Result<Vec<PathBuf>> {
+        let invalid = |message: String| ConfigError::InvalidValue {
+            field: format!("{} in {}", INCLUDE_KEY, path.display()),
+            message,
+        };
+        let toml::Value::Array(entries) = includes else {
+            return Err(invalid("expected a list of file names".to_string()));
+        };
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        entries
+            .into_iter()
+            .map(|entry| {
+                let name = entry
+                    .as_str()
+                    .ok_or_else(|| invalid(format!("expected a file name, found {}", entry)))?;
+                let name = expand::expand(name, &|var| env::var(var).ok()).map_err(invalid)?;
+                Ok(dir.join(name))
+            })
+            .collect()
+    }
+
+    /// Load a `.fsx` or `.toml` config, picked by extension
+    pub fn from_any_file(path: &Path) -> Result<ScarabConfig> {
+        if path.extension().is_some_and(|ext| ext == "fsx") {
+            FusabiConfigLoader::from_file(path)
+        } else {
+            Self::from_file(path)
+        }
+    }
+
     /// Save config to global config file
     pub fn save_global(&self, config: &ScarabConfig) -> Result<()> {
         self.save_to(&self.global_path, config)
@@ -149,9 +409,15 @@ impl ConfigLoader {
         // Walk up from cwd
         if let Ok(mut current) = env::current_dir() {
             loop {
-                let local_path = current.join(".scarab.toml");
-                if local_path.exists() {
-                    locations.push((format!("Local ({})", current.display()), local_path, true));
+                for name in LOCAL_CONFIG_NAMES {
+                    let local_path = current.join(name);
+                    if local_path.exists() {
+                        locations.push((
+                            format!("Local ({})", current.display()),
+                            local_path,
+                            true,
+                        ));
+                    }
                 }
 
                 if !current.pop() {
@@ -208,6 +474,135 @@ mod tests {
         assert_eq!(loaded.font.size, 18.0);
     }
 
+    #[test]
+    fn test_project_fsx_preferred_and_found_from_subdirectory() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        let nested = project.join("src/module");
+        fs::create_dir_all(&nested).unwrap();
+
+        fs::write(project.join(".scarab.toml"), "[font]\nsize = 11.0\n").unwrap();
+        fs::write(
+            project.join(".scarab.fsx"),
+            "let font = { Family = \"Iosevka\"; Size = 16.0 }\n\n{ font = font }\n",
+        )
+        .unwrap();
+
+        let local = ConfigLoader::find_local_path(&nested).unwrap();
+        assert_eq!(local, project.join(".scarab.fsx"));
+        assert_eq!(ConfigLoader::from_any_file(&local).unwrap().font.size, 16.0);
+
+        fs::remove_file(&local).unwrap();
+        let local = ConfigLoader::find_local_path(&nested).unwrap();
+        assert_eq!(ConfigLoader::from_any_file(&local).unwrap().font.size, 11.0);
+    }
+
+    #[test]
+    fn test_includes_are_merged_under_the_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::create_dir_all(dir.join("parts")).unwrap();
+        fs::write(
+            dir.join("parts/keys.toml"),
+            "include = [\"theme.toml\"]\n[keybindings.custom]\nsplit_horizontal = \"Ctrl+Alt+H\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("parts/theme.toml"),
+            "[colors]\ntheme = \"nord\"\n[font]\nsize = 12.0\nfamily = \"Hack\"\n",
+        )
+        .unwrap();
+        let config_path = dir.join("config.toml");
+        fs::write(
+            &config_path,
+            "include = [\"parts/keys.toml\"]\n[font]\nsize = 15.0\n",
+        )
+        .unwrap();
+
+        let config = ConfigLoader::from_file(&config_path).unwrap();
+        assert_eq!(config.font.size, 15.0);
+        assert_eq!(config.font.family, "Hack");
+        assert_eq!(config.colors.theme.as_deref(), Some("nord"));
+        assert_eq!(
+            config
+                .keybindings
+                .custom
+                .get("split_horizontal")
+                .map(String::as_str),
+            Some("Ctrl+Alt+H")
+        );
+        assert_eq!(
+            ConfigLoader::included_files(&config_path),
+            vec![dir.join("parts/keys.toml"), dir.join("parts/theme.toml")]
+        );
+    }
+
+    #[test]
+    fn test_old_layout_is_upgraded_on_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let old = "[appearance]\nfont_size = 13.0\n";
+        fs::write(&config_path, old).unwrap();
+
+        // Reading leaves the file alone
+        let config = ConfigLoader::from_file(&config_path).unwrap();
+        assert_eq!(config.font.size, 13.0);
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), old);
+
+        let config = ConfigLoader::with_path(config_path.clone())
+            .load_global()
+            .unwrap();
+        assert_eq!(config.font.size, 13.0);
+        assert!(temp_dir.path().join("config.toml.v0.bak").exists());
+        assert!(!fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("appearance"));
+    }
+
+    #[test]
+    fn test_include_cycle_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("a.toml"), "include = [\"b.toml\"]\n").unwrap();
+        fs::write(dir.join("b.toml"), "include = [\"a.toml\"]\n").unwrap();
+
+        let err = ConfigLoader::from_file(&dir.join("a.toml")).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("include cycle"), "{}", message);
+        assert!(message.contains("a.toml -> "), "{}", message);
+
+        fs::write(dir.join("b.toml"), "include = [\"missing.toml\"]\n").unwrap();
+        assert!(matches!(
+            ConfigLoader::from_file(&dir.join("a.toml")),
+            Err(ConfigError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_paths_from_global_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let config_path = dir.join("config.toml");
+        fs::write(
+            &config_path,
+            format!(
+                "[paths]\ndata_dir = \"{}/state\"\nsocket = \"{}/scarab.sock\"\n",
+                dir.display(),
+                dir.display()
+            ),
+        )
+        .unwrap();
+
+        let paths = ConfigLoader::with_path(config_path.clone()).paths();
+        assert_eq!(paths.socket, dir.join("scarab.sock"));
+        assert_eq!(paths.sessions_db, dir.join("state/sessions.db"));
+
+        // An unreadable config falls back to the defaults
+        fs::write(&config_path, "[paths\n").unwrap();
+        let paths = ConfigLoader::with_path(config_path).paths();
+        assert_eq!(paths, Paths::from_env());
+    }
+
     #[test]
     fn test_ensure_default_config() {
         let temp_dir = TempDir::new().unwrap();
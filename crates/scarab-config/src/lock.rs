@@ -0,0 +1,58 @@
+//! Password of the idle lock screen
+//!
+//! `sessions.lock_password_hash` holds a salted Argon2id hash in PHC
+//! format, as printed by `scarab-config hash-password`, so a config file
+//! that leaks doesn't give the password away to a quick dictionary lookup.
+
+use crate::error::{ConfigError, Result};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+/// Hash `password` with a fresh salt, for `sessions.lock_password_hash`
+pub fn hash_lock_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| ConfigError::InvalidValue {
+            field: "sessions.lock_password_hash".to_string(),
+            message: e.to_string(),
+        })
+}
+
+/// Whether `password` is the one `hash` was made from
+///
+/// A hash that can't be read matches no password.
+pub fn lock_password_matches(password: &str, hash: &str) -> bool {
+    match PasswordHash::new(hash.trim()) {
+        Ok(hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok(),
+        Err(e) => {
+            tracing::warn!("sessions.lock_password_hash is not a password hash: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_password() {
+        let hash = hash_lock_password("hunter2").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(lock_password_matches("hunter2", &hash));
+        assert!(!lock_password_matches("hunter1", &hash));
+        assert!(!lock_password_matches("", &hash));
+
+        // Salted: the same password never hashes the same way twice
+        assert_ne!(hash_lock_password("hunter2").unwrap(), hash);
+
+        // Neither an old SHA-256 hex digest nor garbage unlocks anything
+        let sha256 = "f52fbd32b2b3b86ff88ef6c490628285f482af15ddcb29541f94bcf526a3f6c7";
+        assert!(!lock_password_matches("hunter2", sha256));
+    }
+}
@@ -0,0 +1,305 @@
+//! Converting a legacy `config.toml` to `config.fsx`
+//!
+//! Each section the Fusabi loader reads becomes a top-level record, with
+//! the TOML keys renamed to the loader's PascalCase fields. Anything the
+//! loader would not pick up is kept as commented-out TOML for review: keys
+//! it does not read, tables it has no section for, and values it has no
+//! way to express, such as a one-item list where it expects a tuple.
+
+use crate::error::Result;
+use crate::fusabi_loader::{section_fields, FieldKind};
+use crate::fusabi_reload::ConfigSection;
+use std::fmt::Write;
+
+/// `config.fsx` source with the settings in `source`, a `config.toml`
+pub fn toml_to_fsx(source: &str) -> Result<String> {
+    let mut table: toml::Table = toml::from_str(source)?;
+    let mut out = String::from(
+        "// Scarab configuration, converted from config.toml\n\
+         //\n\
+         // Commented-out TOML holds settings config.fsx does not read; move\n\
+         // them by hand or drop them.\n",
+    );
+
+    let mut bound = Vec::new();
+    for section in ConfigSection::ALL {
+        let name = section.binding();
+        let Some(value) = table.remove(name) else {
+            continue;
+        };
+        let toml::Value::Table(values) = value else {
+            let mut kept = toml::Table::new();
+            kept.insert(name.to_string(), value);
+            out.push_str("\n// Not read from config.fsx:\n");
+            push_commented(&mut out, &kept);
+            continue;
+        };
+
+        let (fields, kept) = convert_record(&values, section_fields(section));
+        push_kept(&mut out, name, &kept);
+        if !fields.is_empty() {
+            out.push('\n');
+            let _ = writeln!(out, "let {} = {}", name, record(&fields, 0));
+            bound.push(name);
+        }
+    }
+
+    if let Some(profiles) = table.remove("profiles") {
+        let mut moved = toml::Table::new();
+        moved.insert("profiles".to_string(), profiles);
+        out.push_str("\n// Move these to profiles.toml next to config.fsx:\n");
+        push_commented(&mut out, &moved);
+    }
+    if !table.is_empty() {
+        out.push_str("\n// Not read from config.fsx:\n");
+        push_commented(&mut out, &table);
+    }
+
+    out.push('\n');
+    if bound.is_empty() {
+        out.push_str("()\n");
+    } else {
+        let fields: Vec<_> = bound
+            .iter()
+            .map(|name| format!("{} = {}", name, name))
+            .collect();
+        let _ = writeln!(out, "{{\n    {}\n}}", fields.join(";\n    "));
+    }
+    Ok(out)
+}
+
+/// Record fields for the keys in `values` the loader reads, and the rest
+fn convert_record(
+    values: &toml::Table,
+    known: &[(&str, FieldKind)],
+) -> (Vec<(String, String)>, Vec<(String, toml::Value)>) {
+    let mut fields = Vec::new();
+    let mut kept = Vec::new();
+
+    // In the loader's order, so sections read the same as the examples
+    for (field, kind) in known {
+        let Some((key, value)) = values.iter().find(|(key, _)| pascal_case(key) == *field) else {
+            continue;
+        };
+        match convert_value(value, *kind) {
+            Some(expr) => fields.push((field.to_string(), expr)),
+            None => kept.push((key.clone(), value.clone())),
+        }
+    }
+    for (key, value) in values {
+        let field = pascal_case(key);
+        if !known.iter().any(|(name, _)| *name == field) {
+            kept.push((key.clone(), value.clone()));
+        }
+    }
+    (fields, kept)
+}
+
+/// `value` as an expression the loader reads as `kind`
+fn convert_value(value: &toml::Value, kind: FieldKind) -> Option<String> {
+    use toml::Value as V;
+    match (kind, value) {
+        (FieldKind::Str, V::String(s)) => Some(string(s)),
+        (FieldKind::Int, V::Integer(i)) => Some(i.to_string()),
+        (FieldKind::Float, V::Float(f)) if f.is_finite() => Some(float(*f)),
+        (FieldKind::Float, V::Integer(i)) => Some(format!("{}.0", i)),
+        (FieldKind::Bool, V::Boolean(b)) => Some(b.to_string()),
+        (FieldKind::Tuple, V::Array(items)) => tuple(items),
+        (FieldKind::Map, V::Table(entries)) => map(entries),
+        (FieldKind::Record(known), V::Table(values)) => {
+            let (fields, kept) = convert_record(values, known);
+            // Partly converting a nested record would drop the rest unseen
+            (kept.is_empty() && !fields.is_empty()).then(|| record(&fields, 1))
+        }
+        (FieldKind::AnyRecord, V::Table(values)) => {
+            let fields = values
+                .iter()
+                .map(|(key, value)| {
+                    // Family names become field names
+                    let is_ident = key.chars().all(|c| c.is_alphanumeric() || c == '_')
+                        && key.chars().next().is_some_and(char::is_alphabetic);
+                    match value {
+                        V::Array(items) if is_ident => Some((key.clone(), tuple(items)?)),
+                        _ => None,
+                    }
+                })
+                .collect::<Option<Vec<_>>>()?;
+            (!fields.is_empty()).then(|| record(&fields, 1))
+        }
+        _ => None,
+    }
+}
+
+/// A tuple of strings; the loader has no one-item tuple to read
+fn tuple(items: &[toml::Value]) -> Option<String> {
+    if items.len() < 2 {
+        return None;
+    }
+    let items = items
+        .iter()
+        .map(|item| item.as_str().map(string))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", items.join(", ")))
+}
+
+/// `Map.ofList` of strings, or of maps of strings
+fn map(entries: &toml::Table) -> Option<String> {
+    let entries = entries
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(s) => string(s),
+                toml::Value::Table(nested) => map(nested)?,
+                _ => return None,
+            };
+            Some(format!("({}, {})", string(key), value))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("Map.ofList [{}]", entries.join("; ")))
+}
+
+fn record(fields: &[(String, String)], depth: usize) -> String {
+    let indent = "    ".repeat(depth + 1);
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|(name, expr)| format!("{}{} = {}", indent, name, expr))
+        .collect();
+    format!("{{\n{}\n{}}}", fields.join(";\n"), "    ".repeat(depth))
+}
+
+fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn float(f: f64) -> String {
+    if f.fract() == 0.0 {
+        format!("{:.1}", f)
+    } else {
+        f.to_string()
+    }
+}
+
+fn pascal_case(key: &str) -> String {
+    key.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Comment out `kept` keys of section `name`, if there are any
+fn push_kept(out: &mut String, name: &str, kept: &[(String, toml::Value)]) {
+    if kept.is_empty() {
+        return;
+    }
+    let mut section = toml::Table::new();
+    section.insert(
+        name.to_string(),
+        toml::Value::Table(kept.iter().cloned().collect()),
+    );
+    out.push_str("\n// Not read from config.fsx:\n");
+    push_commented(out, &section);
+}
+
+/// `table` as TOML, one comment line per line
+fn push_commented(out: &mut String, table: &toml::Table) {
+    let text = toml::to_string(table).unwrap_or_else(|_| format!("{:?}", table));
+    for line in text.lines() {
+        if line.is_empty() {
+            out.push_str("//\n");
+        } else {
+            let _ = writeln!(out, "// {}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FusabiConfigLoader;
+
+    const LEGACY: &str = r##"
+[terminal]
+default_shell = "/bin/fish"
+columns = 100
+
+[font]
+family = "Iosevka \"Term\""
+size = 13
+fallback = ["Hack", "Noto Color Emoji"]
+ligatures = false
+
+[colors]
+theme = "dracula"
+opacity = 0.9
+
+[colors.palette]
+red = "#ff5555"
+
+[ui]
+cursor_style = "beam"
+show_tabs = false
+
+[plugins]
+enabled = ["git-status"]
+
+[navigation]
+style = "vimium"
+"##;
+
+    #[test]
+    fn test_sections_become_records() {
+        let fsx = toml_to_fsx(LEGACY).unwrap();
+
+        assert!(fsx
+            .contains("let terminal = {\n    DefaultShell = \"/bin/fish\";\n    Columns = 100\n}"));
+        assert!(fsx.contains("    Family = \"Iosevka \\\"Term\\\"\";\n    Size = 13.0;"));
+        assert!(fsx.contains("Fallback = (\"Hack\", \"Noto Color Emoji\")"));
+        assert!(fsx.contains("Palette = {\n        Red = \"#ff5555\"\n    }"));
+        assert!(fsx.ends_with(
+            "{\n    terminal = terminal;\n    font = font;\n    colors = colors;\n    ui = ui\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_unread_settings_are_commented() {
+        let fsx = toml_to_fsx(LEGACY).unwrap();
+
+        assert!(fsx.contains("// [ui]\n// cursor_style = \"beam\"\n"));
+        // One plugin can't be written as a tuple
+        assert!(fsx.contains("// [plugins]\n// enabled = [\"git-status\"]\n"));
+        assert!(fsx.contains("// [navigation]\n// style = \"vimium\"\n"));
+        assert!(!fsx.contains("let plugins"));
+    }
+
+    #[test]
+    fn test_converted_config_loads() {
+        let fsx = toml_to_fsx(LEGACY).unwrap();
+        let config = FusabiConfigLoader::from_source(&fsx).unwrap();
+
+        assert_eq!(config.terminal.default_shell, "/bin/fish");
+        assert_eq!(config.terminal.columns, 100);
+        assert_eq!(config.font.family, "Iosevka \"Term\"");
+        assert_eq!(config.font.size, 13.0);
+        assert_eq!(config.font.fallback, vec!["Hack", "Noto Color Emoji"]);
+        assert!(!config.font.ligatures);
+        assert_eq!(config.colors.palette.red, "#ff5555");
+        assert!(!config.ui.show_tabs);
+    }
+}
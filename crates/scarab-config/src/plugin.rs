@@ -6,11 +6,14 @@
 use crate::{
     config::*,
     error::{ConfigError, Result},
+    fusabi_reload::{ConfigSectionsChanged, FusabiConfigSession},
+    loader::ConfigLoader,
 };
 use bevy::prelude::*;
 use bevy_fusabi::prelude::*;
 use fusabi_vm::{Value, Vm};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Resource to hold the handle to the config script asset
 #[derive(Resource, Clone)]
@@ -44,6 +47,7 @@ impl Plugin for ScarabConfigPlugin {
 
         app.add_plugins(FusabiPlugin)
             .init_resource::<ScarabConfig>()
+            .add_event::<ConfigSectionsChanged>()
             .add_systems(
                 Startup,
                 move |commands: Commands, asset_server: Res<AssetServer>| {
@@ -67,6 +71,7 @@ fn apply_config_updates(
     config_handle: Option<Res<ConfigHandle>>,
     scripts: Res<Assets<FusabiScript>>,
     mut config_store: ResMut<ScarabConfig>,
+    mut changed_events: EventWriter<ConfigSectionsChanged>,
 ) {
     let Some(config_handle) = config_handle else {
         return;
@@ -86,6 +91,7 @@ fn apply_config_updates(
                         error!("Failed to apply config: {:?}", e);
                     } else {
                         info!("Configuration reloaded successfully");
+                        changed_events.send(ConfigSectionsChanged::all());
                     }
                 }
             }
@@ -106,6 +112,71 @@ fn apply_config_updates(
     }
 }
 
+/// Bevy plugin reloading a `config.fsx` evaluated by a [`FusabiConfigSession`]
+///
+/// Insert the session as a non-send resource. When the config or a file it
+/// loads changes, only the affected bindings are evaluated again; changed
+/// sections are copied into [`ScarabConfig`] and announced with
+/// [`ConfigSectionsChanged`].
+pub struct FusabiConfigReloadPlugin;
+
+impl Plugin for FusabiConfigReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScarabConfig>()
+            .add_event::<ConfigSectionsChanged>()
+            .add_systems(Update, reload_fusabi_config);
+    }
+}
+
+/// How often the config files are checked for changes
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Re-evaluate the config when its files change
+fn reload_fusabi_config(
+    session: Option<NonSendMut<FusabiConfigSession>>,
+    mut config: ResMut<ScarabConfig>,
+    mut changed_events: EventWriter<ConfigSectionsChanged>,
+    time: Res<Time>,
+    mut since_check: Local<Duration>,
+) {
+    let Some(mut session) = session else {
+        return;
+    };
+    *since_check += time.delta();
+    if *since_check < RELOAD_POLL_INTERVAL {
+        return;
+    }
+    *since_check = Duration::ZERO;
+
+    if !session.is_stale() {
+        return;
+    }
+    match session.reload() {
+        Ok(reload) if reload.changed.is_empty() => {}
+        Ok(reload) => {
+            // Profiles and the project config still win over the reloaded file
+            let effective = match ConfigLoader::new()
+                .without_directory_profiles()
+                .apply_overrides(session.config().clone())
+            {
+                Ok(effective) => effective,
+                Err(e) => {
+                    error!("Failed to apply config overrides: {}", e);
+                    session.config().clone()
+                }
+            };
+            for section in &reload.changed {
+                section.copy(&effective, &mut config);
+            }
+            info!("Configuration sections changed: {:?}", reload.changed);
+            changed_events.send(ConfigSectionsChanged {
+                sections: reload.changed,
+            });
+        }
+        Err(e) => error!("Failed to reload {}: {}", session.path().display(), e),
+    }
+}
+
 /// Execute the script and extract configuration values
 fn apply_script(script: &FusabiScript, config: &mut ScarabConfig) -> Result<()> {
     // 1. Deserialize bytecode
@@ -243,6 +314,26 @@ fn extract_font_config(val: &Value) -> Result<FontConfig> {
         if let Some(b) = get_bool(&map, "UseThinStrokes") {
             config.use_thin_strokes = b;
         }
+        if let Some(b) = get_bool(&map, "Ligatures") {
+            config.ligatures = b;
+        }
+
+        // Features (nested record of family -> tuple of feature strings)
+        if let Some(Value::Record(features_map)) = map.get("Features") {
+            let f_map = features_map.lock().unwrap();
+            for (family, v) in f_map.iter() {
+                if let Value::Tuple(vec) = v {
+                    let features: Vec<String> = vec
+                        .iter()
+                        .filter_map(|v| match v {
+                            Value::Str(s) => Some(s.to_string()),
+                            _ => None,
+                        })
+                        .collect();
+                    config.features.insert(family.to_string(), features);
+                }
+            }
+        }
 
         if let Some(Value::Tuple(vec)) = map.get("Fallback") {
             let mut fallback = Vec::new();
@@ -268,12 +359,21 @@ fn extract_color_config(val: &Value) -> Result<ColorConfig> {
         if let Some(s) = get_string(&map, "Theme") {
             config.theme = Some(s);
         }
+        if let Some(s) = get_string(&map, "LightTheme") {
+            config.light_theme = Some(s);
+        }
+        if let Some(s) = get_string(&map, "DarkTheme") {
+            config.dark_theme = Some(s);
+        }
         if let Some(f) = get_float(&map, "Opacity") {
             config.opacity = f as f32;
         }
         if let Some(f) = get_float(&map, "DimOpacity") {
             config.dim_opacity = f as f32;
         }
+        if let Some(f) = get_float(&map, "MinimumContrast") {
+            config.minimum_contrast = f as f32;
+        }
 
         // Optional overrides
         if let Some(s) = get_string(&map, "Foreground") {
@@ -360,6 +460,9 @@ fn extract_keybindings(val: &Value) -> Result<KeyBindings> {
         if let Some(s) = get_string(&map, "LeaderKey") {
             config.leader_key = s;
         }
+        if let Some(ms) = get_int(&map, "LeaderTimeoutMs") {
+            config.leader_timeout_ms = ms.max(0) as u64;
+        }
 
         // Custom bindings
         if let Some(Value::Map(custom_map)) = map.get("Custom") {
@@ -370,6 +473,21 @@ fn extract_keybindings(val: &Value) -> Result<KeyBindings> {
                 }
             }
         }
+
+        // Key tables: name -> (key -> action)
+        if let Some(Value::Map(tables)) = map.get("KeyTables") {
+            for (name, table) in tables.lock().unwrap().iter() {
+                let Value::Map(bindings) = table else {
+                    continue;
+                };
+                let entry = config.key_tables.entry(name.clone()).or_default();
+                for (key, action) in bindings.lock().unwrap().iter() {
+                    if let Value::Str(action) = action {
+                        entry.insert(key.clone(), action.to_string());
+                    }
+                }
+            }
+        }
     }
 
     Ok(config)
@@ -389,18 +507,69 @@ fn extract_ui_config(val: &Value) -> Result<UiConfig> {
         if let Some(b) = get_bool(&map, "Animations") {
             config.animations = b;
         }
+        if let Some(b) = get_bool(&map, "ReduceMotion") {
+            config.reduce_motion = b;
+        }
+        if let Some(b) = get_bool(&map, "ReduceFlashing") {
+            config.reduce_flashing = b;
+        }
         if let Some(b) = get_bool(&map, "SmoothScroll") {
             config.smooth_scroll = b;
         }
         if let Some(b) = get_bool(&map, "ShowTabs") {
             config.show_tabs = b;
         }
+        if let Some(b) = get_bool(&map, "ShowScrollbar") {
+            config.show_scrollbar = b;
+        }
+        if let Some(b) = get_bool(&map, "ShowMinimap") {
+            config.show_minimap = b;
+        }
+        if let Some(b) = get_bool(&map, "ShowCommandGutter") {
+            config.show_command_gutter = b;
+        }
+        if let Some(b) = get_bool(&map, "TabShowIndex") {
+            config.tab_show_index = b;
+        }
+        if let Some(b) = get_bool(&map, "TabShowTitle") {
+            config.tab_show_title = b;
+        }
+        if let Some(b) = get_bool(&map, "TabShowActivity") {
+            config.tab_show_activity = b;
+        }
+        if let Some(f) = get_float(&map, "PaneBorderWidth") {
+            config.pane_border_width = f as f32;
+        }
+        if let Some(s) = get_string(&map, "PaneBorderColor") {
+            config.pane_border_color = Some(s);
+        }
+        if let Some(s) = get_string(&map, "PaneBorderFocusedColor") {
+            config.pane_border_focused_color = Some(s);
+        }
+        if let Some(f) = get_float(&map, "InactivePaneBrightness") {
+            config.inactive_pane_brightness = f as f32;
+        }
         if let Some(b) = get_bool(&map, "CursorBlink") {
             config.cursor_blink = b;
         }
         if let Some(i) = get_int(&map, "CursorBlinkInterval") {
             config.cursor_blink_interval = i as u32;
         }
+        if let Some(b) = get_bool(&map, "CursorSmooth") {
+            config.cursor_smooth = b;
+        }
+        if let Some(s) = get_string(&map, "BackgroundImage") {
+            config.background_image = Some(s);
+        }
+        if let Some(f) = get_float(&map, "BackgroundImageOpacity") {
+            config.background_image_opacity = f as f32;
+        }
+        if let Some(f) = get_float(&map, "BackgroundDim") {
+            config.background_dim = f as f32;
+        }
+        if let Some(b) = get_bool(&map, "BackgroundBlur") {
+            config.background_blur = b;
+        }
         if let Some(s) = get_string(&map, "WindowIcon") {
             config.window_icon = Some(s);
         }
@@ -444,6 +613,12 @@ fn extract_session_config(val: &Value) -> Result<SessionConfig> {
         if let Some(s) = get_string(&map, "WorkingDirectory") {
             config.working_directory = Some(s);
         }
+        if let Some(i) = get_int(&map, "LockAfterIdle") {
+            config.lock_after_idle = i as u32;
+        }
+        if let Some(s) = get_string(&map, "LockPasswordHash") {
+            config.lock_password_hash = Some(s);
+        }
     }
 
     Ok(config)
@@ -503,5 +678,20 @@ mod tests {
         assert_eq!(config.default_shell, "/bin/zsh");
         assert_eq!(config.scrollback_lines, 10000);
         assert_eq!(config.alt_screen, true);
+
+        let mut features_map = HashMap::new();
+        features_map.insert(
+            "*".to_string(),
+            Value::Tuple(vec![Value::Str("-calt".into()), Value::Str("+ss01".into())]),
+        );
+        let mut font_map = HashMap::new();
+        font_map.insert(
+            "Features".to_string(),
+            Value::Record(Arc::new(Mutex::new(features_map))),
+        );
+
+        let val = Value::Record(Arc::new(Mutex::new(font_map)));
+        let config = extract_font_config(&val).unwrap();
+        assert_eq!(config.features["*"], vec!["-calt", "+ss01"]);
     }
 }
@@ -0,0 +1,376 @@
+//! Config profiles: overrides picked by host, environment or directory
+//!
+//! A profile is a `[[profiles]]` table holding any config keys, plus the
+//! conditions under which it applies:
+//!
+//! ```toml
+//! [[profiles]]
+//! name = "work"
+//! hostname = "work-*"
+//! directory = "~/work"
+//!
+//! [profiles.font]
+//! size = 12.0
+//! ```
+//!
+//! Every condition a profile gives must hold; a profile with none only
+//! applies when named in `SCARAB_PROFILE` (comma-separated). Matching
+//! profiles are laid over the config key by key, in file order, so a
+//! later profile wins over an earlier one and keys a profile leaves out
+//! keep their value.
+//!
+//! The terminal itself applies profiles with a `directory` per pane, against
+//! the pane's working directory, through [`directory_settings`].
+
+use crate::error::Result;
+use crate::expand::expand;
+use crate::ScarabConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming profiles to apply regardless of conditions
+pub const PROFILE_ENV: &str = "SCARAB_PROFILE";
+
+/// A set of config overrides and when to apply them
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct Profile {
+    pub name: String,
+    /// Host name, with `*` matching any run of characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// `VAR` to require a non-empty variable, or `VAR=value`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// Directory the working directory must be in, with `~` and `${VAR}` expanded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// Config keys to override, laid out as in the config file
+    #[serde(flatten)]
+    pub settings: toml::Table,
+}
+
+/// What profiles are matched against
+#[derive(Debug, Clone, Default)]
+pub struct ProfileEnv {
+    pub hostname: Option<String>,
+    pub cwd: PathBuf,
+    pub vars: HashMap<String, String>,
+}
+
+impl ProfileEnv {
+    /// The running process's host, working directory and environment
+    pub fn current() -> Self {
+        Self {
+            hostname: current_hostname(),
+            cwd: std::env::current_dir().unwrap_or_default(),
+            vars: std::env::vars().collect(),
+        }
+    }
+
+    /// The same host and environment, with `cwd` as the working directory
+    pub fn in_dir(&self, cwd: impl Into<PathBuf>) -> Self {
+        Self {
+            cwd: cwd.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Profile names listed in `SCARAB_PROFILE`
+    fn selected(&self) -> Vec<&str> {
+        self.vars
+            .get(PROFILE_ENV)
+            .map(|names| {
+                names
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Profile {
+    /// Whether the profile applies in `env`
+    pub fn matches(&self, env: &ProfileEnv) -> bool {
+        if env.selected().contains(&self.name.as_str()) {
+            return true;
+        }
+        if self.hostname.is_none() && self.env.is_none() && self.directory.is_none() {
+            return false;
+        }
+
+        let host_ok = self.hostname.as_deref().map_or(true, |pattern| {
+            env.hostname
+                .as_deref()
+                .is_some_and(|host| wildcard_match(pattern, host))
+        });
+        let env_ok = self
+            .env
+            .as_deref()
+            .map_or(true, |condition| env_matches(condition, &env.vars));
+        let dir_ok = self.directory.as_deref().map_or(true, |dir| {
+            let lookup = |name: &str| env.vars.get(name).cloned();
+            // A directory naming an unset variable matches nothing
+            expand(dir, &lookup).is_ok_and(|dir| env.cwd.starts_with(dir))
+        });
+        host_ok && env_ok && dir_ok
+    }
+}
+
+/// Lay every profile in `profiles` that matches `env` over `config`
+///
+/// Returns the names of the profiles applied, in order.
+pub fn apply_profiles(
+    config: &mut ScarabConfig,
+    profiles: &[Profile],
+    env: &ProfileEnv,
+) -> Result<Vec<String>> {
+    let mut applied = Vec::new();
+    for profile in profiles.iter().filter(|p| p.matches(env)) {
+        *config = overlay(config, &profile.settings)?;
+        applied.push(profile.name.clone());
+    }
+    Ok(applied)
+}
+
+/// Settings of the profiles with a `directory` that match `env`, as TOML by
+/// dotted key
+///
+/// Later profiles win over earlier ones, as with [`apply_profiles`].
+pub fn directory_settings(profiles: &[Profile], env: &ProfileEnv) -> BTreeMap<String, String> {
+    let mut settings = BTreeMap::new();
+    for profile in profiles
+        .iter()
+        .filter(|p| p.directory.is_some() && p.matches(env))
+    {
+        flatten_settings("", &profile.settings, &mut settings);
+    }
+    settings
+}
+
+/// Add the leaves of `table` to `out` under dotted keys starting with `prefix`
+fn flatten_settings(prefix: &str, table: &toml::Table, out: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        if prefix.is_empty() && key == "profiles" {
+            continue;
+        }
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::Table(table) => flatten_settings(&key, table, out),
+            value => {
+                out.insert(key, value.to_string());
+            }
+        }
+    }
+}
+
+/// `config` with the keys in `settings` replaced
+fn overlay(config: &ScarabConfig, settings: &toml::Table) -> Result<ScarabConfig> {
+    let toml::Value::Table(mut base) = toml::Value::try_from(config)? else {
+        unreachable!("config serializes to a table");
+    };
+    let mut settings = settings.clone();
+    // Profiles do not nest
+    settings.remove("profiles");
+    merge_tables(&mut base, settings);
+    Ok(toml::Value::Table(base).try_into()?)
+}
+
+/// Merge `over` into `base`, recursing into tables present in both
+pub(crate) fn merge_tables(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(over_table)) => {
+                merge_tables(base_table, over_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Whether `vars` satisfy a `VAR` or `VAR=value` condition
+fn env_matches(condition: &str, vars: &HashMap<String, String>) -> bool {
+    match condition.split_once('=') {
+        Some((name, value)) => vars.get(name.trim()).is_some_and(|v| v == value),
+        None => vars.get(condition.trim()).is_some_and(|v| !v.is_empty()),
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any run of characters
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+fn current_hostname() -> Option<String> {
+    if let Ok(name) = std::env::var("HOSTNAME") {
+        if !name.is_empty() {
+            return Some(name);
+        }
+    }
+    let output = std::process::Command::new("hostname").output().ok()?;
+    let name = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Profiles file read next to a `config.fsx`, which has no TOML tables
+pub fn profiles_file(config_dir: &Path) -> PathBuf {
+    config_dir.join("profiles.toml")
+}
+
+/// Profiles listed in a `profiles.toml`, or none if there is no such file
+pub fn load_profiles_file(path: &Path) -> Result<Vec<Profile>> {
+    #[derive(Deserialize)]
+    struct ProfilesFile {
+        #[serde(default)]
+        profiles: Vec<Profile>,
+    }
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    Ok(toml::from_str::<ProfilesFile>(&content)?.profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(hostname: &str, cwd: &str, vars: &[(&str, &str)]) -> ProfileEnv {
+        ProfileEnv {
+            hostname: Some(hostname.to_string()),
+            cwd: PathBuf::from(cwd),
+            vars: vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("work-*", "work-laptop"));
+        assert!(wildcard_match("*-laptop", "work-laptop"));
+        assert!(wildcard_match("w*k-*p", "work-laptop"));
+        assert!(wildcard_match("desk", "desk"));
+        assert!(!wildcard_match("desk", "desktop"));
+        assert!(!wildcard_match("a*a", "a"));
+    }
+
+    #[test]
+    fn test_conditions() {
+        let profile: Profile = toml::from_str(
+            r#"
+            name = "work"
+            hostname = "work-*"
+            env = "TERM_PROGRAM=scarab"
+            directory = "/srv/work"
+            "#,
+        )
+        .unwrap();
+
+        let vars = [("TERM_PROGRAM", "scarab")];
+        assert!(profile.matches(&env("work-laptop", "/srv/work/api", &vars)));
+        assert!(!profile.matches(&env("home", "/srv/work/api", &vars)));
+        assert!(!profile.matches(&env("work-laptop", "/srv/other", &vars)));
+        assert!(!profile.matches(&env("work-laptop", "/srv/work", &[])));
+
+        // No conditions: only when selected by name
+        let manual = Profile {
+            name: "demo".to_string(),
+            ..Profile::default()
+        };
+        assert!(!manual.matches(&env("home", "/", &[])));
+        assert!(manual.matches(&env("home", "/", &[(PROFILE_ENV, "work, demo")])));
+    }
+
+    #[test]
+    fn test_profiles_override_key_by_key_in_order() {
+        let config: ScarabConfig = toml::from_str(
+            r#"
+            [font]
+            family = "Iosevka"
+            size = 14.0
+
+            [[profiles]]
+            name = "big"
+            env = "BIG"
+            font = { size = 18.0 }
+
+            [[profiles]]
+            name = "bigger"
+            env = "BIG"
+            font = { size = 20.0 }
+            keybindings = { custom = { split = "Ctrl+Alt+S" } }
+
+            [[profiles]]
+            name = "never"
+            hostname = "nowhere"
+            font = { size = 8.0 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.profiles.len(), 3);
+
+        let mut merged = config.clone();
+        let profiles = merged.profiles.clone();
+        let applied =
+            apply_profiles(&mut merged, &profiles, &env("home", "/", &[("BIG", "1")])).unwrap();
+
+        assert_eq!(applied, vec!["big", "bigger"]);
+        assert_eq!(merged.font.size, 20.0);
+        assert_eq!(merged.font.family, "Iosevka");
+        assert_eq!(merged.keybindings.custom["split"], "Ctrl+Alt+S");
+        assert_eq!(merged.keybindings.copy_mode, config.keybindings.copy_mode);
+    }
+
+    #[test]
+    fn test_directory_settings() {
+        let config: ScarabConfig = toml::from_str(
+            r#"
+            [[profiles]]
+            name = "work"
+            directory = "/srv/work"
+            font = { size = 12.0 }
+            colors = { theme = "nord" }
+
+            [[profiles]]
+            name = "big"
+            env = "BIG"
+            font = { size = 18.0 }
+            "#,
+        )
+        .unwrap();
+
+        let base = env("home", "/", &[("BIG", "1")]);
+        assert!(directory_settings(&config.profiles, &base).is_empty());
+
+        let settings = directory_settings(&config.profiles, &base.in_dir("/srv/work/api"));
+        assert_eq!(settings.len(), 2);
+        assert_eq!(settings["font.size"], "12.0");
+        assert_eq!(settings["colors.theme"], "\"nord\"");
+    }
+}
@@ -45,6 +45,8 @@ impl PluginInstaller {
         // Determine file extension based on content
         let extension = if content.starts_with(b"FZB\x00") {
             "fzb"
+        } else if content.starts_with(b"\0asm") {
+            "wasm"
         } else {
             "fsx"
         };
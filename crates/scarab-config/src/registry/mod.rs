@@ -158,18 +158,12 @@ impl RegistryManager {
 
     /// Get default cache directory
     pub fn default_cache_dir() -> PathBuf {
-        dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from(".config"))
-            .join("scarab")
-            .join("registry")
+        RegistryConfig::default().cache_dir
     }
 
     /// Get default plugin directory
     pub fn default_plugin_dir() -> PathBuf {
-        dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from(".config"))
-            .join("scarab")
-            .join("plugins")
+        RegistryConfig::default().plugin_dir
     }
 }
 
@@ -19,16 +19,12 @@ pub struct RegistryConfig {
 
 impl Default for RegistryConfig {
     fn default() -> Self {
+        // Install where the daemon loads plugins from, after `[paths]`
+        let paths = crate::ConfigLoader::new().paths();
         Self {
             registry_url: "https://registry.scarab.dev".to_string(),
-            cache_dir: dirs::config_dir()
-                .unwrap_or_else(|| PathBuf::from(".config"))
-                .join("scarab")
-                .join("registry"),
-            plugin_dir: dirs::config_dir()
-                .unwrap_or_else(|| PathBuf::from(".config"))
-                .join("scarab")
-                .join("plugins"),
+            cache_dir: paths.config_dir.join("registry"),
+            plugin_dir: paths.plugin_dir,
             security: SecurityConfig::default(),
         }
     }
@@ -0,0 +1,210 @@
+//! Reading and changing single settings by dotted key
+//!
+//! Keys name a setting the way `config.toml` nests it, such as `font.size`
+//! or `terminal.scrollback_lines`. Values are written as TOML (`14.0`,
+//! `true`, `"dracula"`, `["Hack", "Noto Color Emoji"]`); text that is not
+//! valid TOML is taken as a string, so `dracula` works too. A changed
+//! config is checked with [`ConfigValidator`] before it is returned.
+
+use crate::config::ScarabConfig;
+use crate::error::{ConfigError, Result};
+use crate::fusabi_reload::ConfigSection;
+use crate::validation::ConfigValidator;
+use std::path::Path;
+
+/// Current value of the setting at `key`
+pub fn get_setting(config: &ScarabConfig, key: &str) -> Result<toml::Value> {
+    let parts = split_key(key)?;
+    let mut value = &toml::Value::try_from(config)?;
+    for part in &parts {
+        value = value
+            .get(*part)
+            .ok_or_else(|| ConfigError::NotFound(format!("setting `{}`", key)))?;
+    }
+    Ok(value.clone())
+}
+
+/// `value` as TOML, or as a string if it is not valid TOML
+pub fn parse_setting_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+/// `config` with the setting at `key` changed to `value`
+///
+/// Fails if `key` is not a setting, `value` has the wrong type, or the
+/// result does not pass validation.
+pub fn set_setting(config: &ScarabConfig, key: &str, value: toml::Value) -> Result<ScarabConfig> {
+    let parts = split_key(key)?;
+    let toml::Value::Table(mut table) = toml::Value::try_from(config)? else {
+        unreachable!("ScarabConfig serializes to a table");
+    };
+    insert(&mut table, &parts, value);
+
+    let updated = toml::Value::Table(table)
+        .try_into::<ScarabConfig>()
+        .map_err(|e| ConfigError::InvalidValue {
+            field: key.to_string(),
+            message: e.message().to_string(),
+        })?;
+
+    // Unknown keys are dropped on the way through, so they don't come back
+    if get_setting(&updated, key).is_err() {
+        return Err(ConfigError::NotFound(format!("setting `{}`", key)));
+    }
+    ConfigValidator::validate(&updated)?;
+    Ok(updated)
+}
+
+/// Write the setting at `key` into the `config.toml` at `path`
+///
+/// Other settings, comments and formatting in the file are kept; the file
+/// is created if it does not exist. A `config.fsx` is a script and can't be
+/// rewritten this way.
+pub fn persist_setting(path: &Path, key: &str, value: &toml::Value) -> Result<()> {
+    if path.extension().is_some_and(|ext| ext == "fsx") {
+        return Err(ConfigError::Validation(format!(
+            "{} is a script; set `{}` there by hand",
+            path.display(),
+            key
+        )));
+    }
+    let parts = split_key(key)?;
+
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut document: toml_edit::DocumentMut = source.parse().map_err(|e| {
+        ConfigError::Validation(format!("Failed to parse {}: {}", path.display(), e))
+    })?;
+    let text = value.to_string();
+    let item: toml_edit::Value = text.parse().map_err(|e| ConfigError::InvalidValue {
+        field: key.to_string(),
+        message: format!("{}", e),
+    })?;
+
+    let (leaf, parents) = parts.split_last().expect("keys have at least one part");
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for part in parents {
+        table = table
+            .entry(part)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| ConfigError::InvalidValue {
+                field: key.to_string(),
+                message: format!("`{}` in {} is not a table", part, path.display()),
+            })?;
+    }
+    table.insert(leaf, toml_edit::value(item));
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, document.to_string())?;
+    Ok(())
+}
+
+/// The section a key belongs to, if it is one reloaded on its own
+pub fn setting_section(key: &str) -> Option<ConfigSection> {
+    let name = key.split('.').next()?;
+    ConfigSection::ALL
+        .into_iter()
+        .find(|section| section.binding() == name)
+}
+
+fn split_key(key: &str) -> Result<Vec<&str>> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        return Err(ConfigError::InvalidValue {
+            field: key.to_string(),
+            message: "expected a dotted key such as `font.size`".to_string(),
+        });
+    }
+    Ok(parts)
+}
+
+/// Set `parts` in `table`, creating tables on the way
+fn insert(table: &mut toml::Table, parts: &[&str], value: toml::Value) {
+    let (leaf, parents) = parts.split_last().expect("keys have at least one part");
+    let mut table = table;
+    for part in parents {
+        let entry = table
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        let toml::Value::Table(next) = entry else {
+            unreachable!()
+        };
+        table = next;
+    }
+    table.insert(leaf.to_string(), value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_and_set_setting() {
+        let config = ScarabConfig::default();
+        let updated = set_setting(&config, "font.size", parse_setting_value("16")).unwrap();
+        assert_eq!(updated.font.size, 16.0);
+        assert_eq!(
+            get_setting(&updated, "font.size").unwrap(),
+            toml::Value::Float(16.0)
+        );
+
+        let updated = set_setting(&config, "colors.theme", parse_setting_value("dracula")).unwrap();
+        assert_eq!(updated.colors.theme.as_deref(), Some("dracula"));
+        assert_eq!(setting_section("colors.theme"), Some(ConfigSection::Colors));
+    }
+
+    #[test]
+    fn test_set_setting_rejects_bad_values() {
+        let config = ScarabConfig::default();
+        assert!(matches!(
+            set_setting(&config, "font.sise", parse_setting_value("16")),
+            Err(ConfigError::NotFound(_))
+        ));
+        assert!(matches!(
+            set_setting(&config, "font.size", parse_setting_value("big")),
+            Err(ConfigError::InvalidValue { ref field, .. }) if field == "font.size"
+        ));
+        // Parses, but ConfigValidator rejects it
+        assert!(set_setting(&config, "font.size", parse_setting_value("500")).is_err());
+        assert!(set_setting(&config, "font..size", parse_setting_value("16")).is_err());
+    }
+
+    #[test]
+    fn test_persist_setting_keeps_the_rest_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "# My settings\n[font]\nfamily = \"Hack\" # favourite\n",
+        )
+        .unwrap();
+
+        persist_setting(&path, "font.size", &toml::Value::Float(15.0)).unwrap();
+        persist_setting(
+            &path,
+            "terminal.scrollback_lines",
+            &toml::Value::Integer(5000),
+        )
+        .unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with("# My settings\n[font]\nfamily = \"Hack\" # favourite\n"));
+        assert!(written.contains("size = 15.0"));
+        assert!(written.contains("[terminal]\nscrollback_lines = 5000"));
+
+        let fsx = dir.path().join("config.fsx");
+        assert!(persist_setting(&fsx, "font.size", &toml::Value::Float(15.0)).is_err());
+    }
+}
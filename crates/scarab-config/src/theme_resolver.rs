@@ -3,11 +3,12 @@
 //! This module provides the `ThemeResolver` which:
 //! - Maps theme names to actual color palettes
 //! - Applies theme overrides to ColorConfig
+//! - Resolves custom themes through the themes they extend
 //! - Validates theme names
 //! - Provides a list of available themes
 
-use crate::{ColorConfig, ColorPalette, ConfigError, Result};
-use std::collections::HashMap;
+use crate::{ColorConfig, ColorPalette, ConfigError, CustomTheme, Result};
+use std::collections::{BTreeMap, HashMap};
 use tracing::debug;
 
 /// Built-in theme definition
@@ -191,9 +192,12 @@ impl ThemeResolver {
     ///
     /// If the theme name is found, the ColorConfig will be updated with the theme's colors.
     /// Any existing custom color overrides in the ColorConfig will be preserved.
+    ///
+    /// The name may be one of `config.custom_themes`, in which case its colors
+    /// are laid over those of the theme it extends, and so on down the chain.
     pub fn resolve(&self, config: &mut ColorConfig) -> Result<()> {
         let theme_name = match &config.theme {
-            Some(name) => name.as_str(),
+            Some(name) => name.clone(),
             None => {
                 debug!("No theme specified in config");
                 return Ok(());
@@ -202,59 +206,132 @@ impl ThemeResolver {
 
         debug!("Resolving theme: {}", theme_name);
 
-        let theme = self.themes.get(theme_name).ok_or_else(|| {
-            ConfigError::InvalidTheme(format!(
-                "Theme '{}' not found. Available themes: {}",
-                theme_name,
-                self.available_themes().join(", ")
-            ))
-        })?;
+        let (chain, base) = self.inheritance_chain(&theme_name, &config.custom_themes)?;
+        let theme = base.map(|name| &self.themes[name]);
 
         debug!("Found theme: {}", theme_name);
 
-        // Only apply theme colors if not already overridden
+        // Only apply theme colors if not already overridden, nearest
+        // definition first
+        let mut foreground = None;
+        let mut background = None;
+        let mut cursor = None;
+        let mut selection_bg = None;
+        let mut selection_fg = None;
+        for custom in &chain {
+            foreground = foreground.or_else(|| custom.foreground.clone());
+            background = background.or_else(|| custom.background.clone());
+            cursor = cursor.or_else(|| custom.cursor.clone());
+            selection_bg = selection_bg.or_else(|| custom.selection_background.clone());
+            selection_fg = selection_fg.or_else(|| custom.selection_foreground.clone());
+        }
+        if let Some(theme) = theme {
+            foreground = foreground.or_else(|| Some(theme.foreground.to_string()));
+            background = background.or_else(|| Some(theme.background.to_string()));
+            cursor = cursor.or_else(|| Some(theme.cursor.to_string()));
+            selection_bg = selection_bg.or_else(|| Some(theme.selection_bg.to_string()));
+            selection_fg = selection_fg.or_else(|| Some(theme.selection_fg.to_string()));
+        }
+
         if config.foreground.is_none() {
-            config.foreground = Some(theme.foreground.to_string());
+            config.foreground = foreground;
         }
         if config.background.is_none() {
-            config.background = Some(theme.background.to_string());
+            config.background = background;
         }
         if config.cursor.is_none() {
-            config.cursor = Some(theme.cursor.to_string());
+            config.cursor = cursor;
         }
         if config.selection_background.is_none() {
-            config.selection_background = Some(theme.selection_bg.to_string());
+            config.selection_background = selection_bg;
         }
         if config.selection_foreground.is_none() {
-            config.selection_foreground = Some(theme.selection_fg.to_string());
+            config.selection_foreground = selection_fg;
         }
 
         // Apply palette (check if it's still the default palette)
         if config.palette == ColorPalette::default() {
-            config.palette = ColorPalette {
-                black: theme.ansi[0].to_string(),
-                red: theme.ansi[1].to_string(),
-                green: theme.ansi[2].to_string(),
-                yellow: theme.ansi[3].to_string(),
-                blue: theme.ansi[4].to_string(),
-                magenta: theme.ansi[5].to_string(),
-                cyan: theme.ansi[6].to_string(),
-                white: theme.ansi[7].to_string(),
-                bright_black: theme.ansi[8].to_string(),
-                bright_red: theme.ansi[9].to_string(),
-                bright_green: theme.ansi[10].to_string(),
-                bright_yellow: theme.ansi[11].to_string(),
-                bright_blue: theme.ansi[12].to_string(),
-                bright_magenta: theme.ansi[13].to_string(),
-                bright_cyan: theme.ansi[14].to_string(),
-                bright_white: theme.ansi[15].to_string(),
-            };
+            let mut palette = theme.map(Self::palette).unwrap_or_default();
+            for custom in chain.iter().rev() {
+                custom.palette.apply_to(&mut palette);
+            }
+            config.palette = palette;
         }
 
         debug!("Applied theme: {}", theme_name);
         Ok(())
     }
 
+    /// Custom themes from `name` down through what each extends, and the
+    /// built-in theme at the bottom, if any
+    fn inheritance_chain<'a>(
+        &self,
+        name: &str,
+        custom_themes: &'a BTreeMap<String, CustomTheme>,
+    ) -> Result<(Vec<&'a CustomTheme>, Option<&'static str>)> {
+        let mut chain = Vec::new();
+        let mut seen = vec![name];
+        let mut current = name;
+        let mut extender = name;
+        loop {
+            if let Some(custom) = custom_themes.get(current) {
+                chain.push(custom);
+                let Some(parent) = custom.extends.as_deref() else {
+                    return Ok((chain, None));
+                };
+                if seen.contains(&parent) {
+                    return Err(ConfigError::InvalidTheme(format!(
+                        "Theme '{}' extends '{}', which leads back to it",
+                        current, parent
+                    )));
+                }
+                seen.push(parent);
+                extender = current;
+                current = parent;
+            } else if let Some((&builtin, _)) = self.themes.get_key_value(current) {
+                return Ok((chain, Some(builtin)));
+            } else {
+                let mut available = self.available_themes();
+                available.extend(custom_themes.keys().cloned());
+                let missing = if current == name {
+                    format!("Theme '{}' not found", name)
+                } else {
+                    format!(
+                        "Theme '{}' extends '{}', which was not found",
+                        extender, current
+                    )
+                };
+                return Err(ConfigError::InvalidTheme(format!(
+                    "{}. Available themes: {}",
+                    missing,
+                    available.join(", ")
+                )));
+            }
+        }
+    }
+
+    /// The 16 ANSI colors of a built-in theme
+    fn palette(theme: &ThemeDefinition) -> ColorPalette {
+        ColorPalette {
+            black: theme.ansi[0].to_string(),
+            red: theme.ansi[1].to_string(),
+            green: theme.ansi[2].to_string(),
+            yellow: theme.ansi[3].to_string(),
+            blue: theme.ansi[4].to_string(),
+            magenta: theme.ansi[5].to_string(),
+            cyan: theme.ansi[6].to_string(),
+            white: theme.ansi[7].to_string(),
+            bright_black: theme.ansi[8].to_string(),
+            bright_red: theme.ansi[9].to_string(),
+            bright_green: theme.ansi[10].to_string(),
+            bright_yellow: theme.ansi[11].to_string(),
+            bright_blue: theme.ansi[12].to_string(),
+            bright_magenta: theme.ansi[13].to_string(),
+            bright_cyan: theme.ansi[14].to_string(),
+            bright_white: theme.ansi[15].to_string(),
+        }
+    }
+
     /// Get a list of all available theme names
     pub fn available_themes(&self) -> Vec<String> {
         self.themes.keys().map(|s| s.to_string()).collect()
@@ -307,6 +384,10 @@ mod tests {
         let resolver = ThemeResolver::new();
         let mut config = ColorConfig {
             theme: Some("dracula".to_string()),
+            light_theme: None,
+            dark_theme: None,
+            pane_themes: Vec::new(),
+            custom_themes: BTreeMap::new(),
             foreground: None,
             background: None,
             cursor: None,
@@ -315,6 +396,7 @@ mod tests {
             palette: ColorPalette::default(),
             opacity: 1.0,
             dim_opacity: 0.7,
+            minimum_contrast: 1.0,
         };
 
         resolver.resolve(&mut config).unwrap();
@@ -369,6 +451,92 @@ mod tests {
         resolver.resolve(&mut config).unwrap();
     }
 
+    #[test]
+    fn test_custom_theme_extends_builtin() {
+        let resolver = ThemeResolver::new();
+        let mut custom = CustomTheme {
+            extends: Some("gruvbox-dark".to_string()),
+            cursor: Some("#fe8019".to_string()),
+            ..Default::default()
+        };
+        custom.palette.red = Some("#ff0000".to_string());
+        let mut config = ColorConfig {
+            theme: Some("my-gruvbox".to_string()),
+            foreground: None,
+            background: None,
+            cursor: None,
+            custom_themes: BTreeMap::from([("my-gruvbox".to_string(), custom)]),
+            ..Default::default()
+        };
+
+        resolver.resolve(&mut config).unwrap();
+
+        assert_eq!(config.cursor.as_deref(), Some("#fe8019"));
+        assert_eq!(config.background.as_deref(), Some("#282828"));
+        assert_eq!(config.palette.red, "#ff0000");
+        assert_eq!(config.palette.green, "#98971a");
+    }
+
+    #[test]
+    fn test_custom_theme_chain() {
+        let resolver = ThemeResolver::new();
+        let mut base = CustomTheme {
+            extends: Some("nord".to_string()),
+            background: Some("#000000".to_string()),
+            ..Default::default()
+        };
+        base.palette.blue = Some("#0000ff".to_string());
+        let mut tweak = CustomTheme {
+            extends: Some("dark-nord".to_string()),
+            ..Default::default()
+        };
+        tweak.palette.blue = Some("#3333ff".to_string());
+        let mut config = ColorConfig {
+            theme: Some("tweak".to_string()),
+            background: None,
+            custom_themes: BTreeMap::from([
+                ("dark-nord".to_string(), base),
+                ("tweak".to_string(), tweak),
+            ]),
+            ..Default::default()
+        };
+
+        resolver.resolve(&mut config).unwrap();
+
+        // The nearest definition of a color wins
+        assert_eq!(config.background.as_deref(), Some("#000000"));
+        assert_eq!(config.palette.blue, "#3333ff");
+        assert_eq!(config.palette.red, "#bf616a");
+    }
+
+    #[test]
+    fn test_custom_theme_errors() {
+        let resolver = ThemeResolver::new();
+        let extending = |parent: &str| CustomTheme {
+            extends: Some(parent.to_string()),
+            ..Default::default()
+        };
+
+        let mut config = ColorConfig {
+            theme: Some("a".to_string()),
+            custom_themes: BTreeMap::from([
+                ("a".to_string(), extending("b")),
+                ("b".to_string(), extending("a")),
+            ]),
+            ..Default::default()
+        };
+        let err = resolver.resolve(&mut config).unwrap_err();
+        assert!(err.to_string().contains("leads back"), "{}", err);
+
+        config.custom_themes = BTreeMap::from([("a".to_string(), extending("nonexistent"))]);
+        let err = resolver.resolve(&mut config).unwrap_err();
+        assert!(
+            err.to_string().contains("'a' extends 'nonexistent'"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn test_theme_exists() {
         let resolver = ThemeResolver::new();
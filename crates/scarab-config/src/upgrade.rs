@@ -0,0 +1,371 @@
+//! Upgrading older `config.toml` layouts to the current schema
+//!
+//! Each TOML config records the schema it was written for in
+//! `config_version`; a file without it is version 0. When the schema
+//! changes, a migration here moves the old keys to their new places, so
+//! an old config keeps working instead of having its settings dropped as
+//! unknown keys. Migrations edit the document in place, keeping comments
+//! and the layout of everything they don't move.
+
+use crate::error::{ConfigError, Result};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, TableLike};
+use tracing::warn;
+
+/// Schema version of configs written by this build
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Key holding a file's schema version
+pub const VERSION_KEY: &str = "config_version";
+
+/// Palette colors, as named in `[colors.palette]` without the `bright_` prefix
+const PALETTE: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// Colors set directly in `[colors]`
+const COLORS: [&str; 5] = [
+    "foreground",
+    "background",
+    "cursor",
+    "selection_background",
+    "selection_foreground",
+];
+
+/// A step from the previous schema version to `version`
+struct Migration {
+    version: u32,
+    apply: fn(&mut DocumentMut, &mut Vec<Moved>),
+}
+
+const MIGRATIONS: [Migration; 1] = [Migration {
+    version: 1,
+    apply: split_sections,
+}];
+
+/// A key an upgrade moved to its new place
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moved {
+    /// Dotted key in the old layout
+    pub from: String,
+    /// Dotted key in the current layout
+    pub to: String,
+    /// `to` was already set, so the old value was dropped
+    pub dropped: bool,
+}
+
+impl fmt::Display for Moved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dropped {
+            write!(f, "dropped `{}`: `{}` is already set", self.from, self.to)
+        } else {
+            write!(f, "moved `{}` to `{}`", self.from, self.to)
+        }
+    }
+}
+
+/// What upgrading a config changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upgrade {
+    pub from: u32,
+    pub to: u32,
+    pub changes: Vec<Moved>,
+    /// Copy of the file as it was, if the file was rewritten
+    pub backup: Option<PathBuf>,
+}
+
+impl fmt::Display for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "config_version {} -> {}", self.from, self.to)?;
+        if let Some(backup) = &self.backup {
+            write!(f, " (backup at {})", backup.display())?;
+        }
+        for change in &self.changes {
+            write!(f, "\n  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Bring `document` up to [`CONFIG_VERSION`]
+///
+/// Returns `None` if nothing had to change. A file from a newer Scarab is
+/// left as it is, with a warning.
+pub fn upgrade_document(document: &mut DocumentMut) -> Result<Option<Upgrade>> {
+    let from = match document.get(VERSION_KEY) {
+        None => 0,
+        Some(item) => item
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| ConfigError::InvalidValue {
+                field: VERSION_KEY.to_string(),
+                message: "expected a whole number".to_string(),
+            })?,
+    };
+    if from > CONFIG_VERSION {
+        warn!(
+            "Config is config_version {}, newer than this build's {}; reading it as is",
+            from, CONFIG_VERSION
+        );
+        return Ok(None);
+    }
+
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > from) {
+        (migration.apply)(document, &mut changes);
+    }
+    if changes.is_empty() {
+        return Ok(None);
+    }
+
+    document.insert(VERSION_KEY, toml_edit::value(i64::from(CONFIG_VERSION)));
+    Ok(Some(Upgrade {
+        from,
+        to: CONFIG_VERSION,
+        changes,
+        backup: None,
+    }))
+}
+
+/// `source` in the current layout, if it had to change
+///
+/// Text that is not valid TOML is left for the TOML parser to report.
+pub fn upgrade_source(source: &str) -> Result<Option<(String, Upgrade)>> {
+    let Ok(mut document) = source.parse::<DocumentMut>() else {
+        return Ok(None);
+    };
+    let upgrade = upgrade_document(&mut document)?;
+    Ok(upgrade.map(|upgrade| (document.to_string(), upgrade)))
+}
+
+/// Upgrade the `config.toml` at `path`, keeping a copy of the old file
+///
+/// The copy is written next to it as `<name>.v<from>.bak`. Returns `None`,
+/// leaving the file alone, if it is already current.
+pub fn upgrade_file(path: &Path) -> Result<Option<Upgrade>> {
+    let source = fs::read_to_string(path)
+        .map_err(|_| ConfigError::FileNotFound(path.display().to_string()))?;
+    let mut document: DocumentMut = source.parse().map_err(|e| {
+        ConfigError::Validation(format!("Failed to parse {}: {}", path.display(), e))
+    })?;
+    let Some(mut upgrade) = upgrade_document(&mut document)? else {
+        return Ok(None);
+    };
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", upgrade.from));
+    let backup = path.with_file_name(name);
+    fs::write(&backup, &source)?;
+    fs::write(path, document.to_string())?;
+
+    upgrade.backup = Some(backup);
+    Ok(Some(upgrade))
+}
+
+/// Version 1: the `[appearance]`, `[theme]` and `[behavior]` sections were
+/// split into `[font]`, `[colors]`, `[ui]` and `[terminal]`
+fn split_sections(document: &mut DocumentMut, changes: &mut Vec<Moved>) {
+    let mut moves = vec![
+        ("appearance.theme".to_string(), "colors.theme".to_string()),
+        ("appearance.font_family".into(), "font.family".into()),
+        ("appearance.font_size".into(), "font.size".into()),
+        ("appearance.line_spacing".into(), "font.line_height".into()),
+        ("appearance.cursor_style".into(), "ui.cursor_style".into()),
+        ("appearance.cursor_blink".into(), "ui.cursor_blink".into()),
+        ("terminal.shell".into(), "terminal.default_shell".into()),
+        ("behavior.shell".into(), "terminal.default_shell".into()),
+        (
+            "behavior.scrollback_lines".into(),
+            "terminal.scrollback_lines".into(),
+        ),
+    ];
+    for color in COLORS {
+        for section in ["appearance.colors", "theme.colors"] {
+            moves.push((
+                format!("{}.{}", section, color),
+                format!("colors.{}", color),
+            ));
+        }
+    }
+    for color in PALETTE {
+        moves.push((
+            format!("theme.colors.normal.{}", color),
+            format!("colors.palette.{}", color),
+        ));
+        moves.push((
+            format!("theme.colors.bright.{}", color),
+            format!("colors.palette.bright_{}", color),
+        ));
+    }
+
+    let root = document.as_table_mut();
+    for (from, to) in &moves {
+        let from_parts: Vec<&str> = from.split('.').collect();
+        let Some(item) = take(root, &from_parts) else {
+            continue;
+        };
+        let to_parts: Vec<&str> = to.split('.').collect();
+        changes.push(Moved {
+            from: from.clone(),
+            to: to.clone(),
+            dropped: !put(root, &to_parts, item),
+        });
+    }
+
+    for table in [
+        "appearance.colors",
+        "appearance",
+        "theme.colors.normal",
+        "theme.colors.bright",
+        "theme.colors",
+        "theme",
+        "behavior",
+    ] {
+        let parts: Vec<&str> = table.split('.').collect();
+        remove_if_empty(root, &parts);
+    }
+}
+
+/// Remove and return the item at `path`
+fn take(table: &mut dyn TableLike, path: &[&str]) -> Option<Item> {
+    let (leaf, parents) = path.split_last()?;
+    let mut table = table;
+    for part in parents {
+        table = table.get_mut(part)?.as_table_like_mut()?;
+    }
+    table.remove(leaf)
+}
+
+/// Set `path` to `item`, creating tables on the way, unless it is set
+fn put(table: &mut dyn TableLike, path: &[&str], item: Item) -> bool {
+    let (leaf, parents) = path.split_last().expect("keys have at least one part");
+    let mut table = table;
+    for part in parents {
+        let Some(next) = table
+            .entry(part)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+        else {
+            return false;
+        };
+        table = next;
+    }
+    if table.contains_key(leaf) {
+        return false;
+    }
+    table.insert(leaf, item);
+    true
+}
+
+/// Remove the table at `path` if nothing is left in it
+fn remove_if_empty(table: &mut dyn TableLike, path: &[&str]) {
+    let Some((leaf, parents)) = path.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for part in parents {
+        match table.get_mut(part).and_then(Item::as_table_like_mut) {
+            Some(next) => table = next,
+            None => return,
+        }
+    }
+    let empty = table
+        .get(leaf)
+        .and_then(Item::as_table_like)
+        .is_some_and(|t| t.is_empty());
+    if empty {
+        table.remove(leaf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScarabConfig;
+
+    const OLD: &str = r##"# My terminal
+[appearance]
+theme = "nord"
+font_family = "Hack" # the good one
+font_size = 13.0
+
+[behavior]
+shell = "/bin/fish"
+
+[theme.colors]
+background = "#1e1e1e"
+
+[theme.colors.bright]
+red = "#ff5555"
+
+[keybindings]
+copy = "Ctrl+Shift+C"
+"##;
+
+    #[test]
+    fn test_upgrade_moves_old_sections() {
+        let mut document: DocumentMut = OLD.parse().unwrap();
+        let upgrade = upgrade_document(&mut document).unwrap().unwrap();
+        assert_eq!((upgrade.from, upgrade.to), (0, CONFIG_VERSION));
+        assert!(upgrade.changes.contains(&Moved {
+            from: "appearance.font_size".to_string(),
+            to: "font.size".to_string(),
+            dropped: false,
+        }));
+
+        let text = document.to_string();
+        assert!(text.contains("\"Hack\" # the good one"));
+        assert!(!text.contains("[appearance]") && !text.contains("[behavior]"));
+        assert!(!text.contains("[theme"));
+
+        let config: ScarabConfig = toml::from_str(&text).unwrap();
+        assert_eq!(config.config_version, CONFIG_VERSION);
+        assert_eq!(config.font.family, "Hack");
+        assert_eq!(config.font.size, 13.0);
+        assert_eq!(config.colors.theme.as_deref(), Some("nord"));
+        assert_eq!(config.colors.background.as_deref(), Some("#1e1e1e"));
+        assert_eq!(config.colors.palette.bright_red, "#ff5555");
+        assert_eq!(config.terminal.default_shell, "/bin/fish");
+
+        // Already current: nothing to do
+        assert_eq!(upgrade_document(&mut document).unwrap(), None);
+    }
+
+    #[test]
+    fn test_upgrade_keeps_the_new_key() {
+        let mut document: DocumentMut = "[font]\nsize = 15.0\n[appearance]\nfont_size = 13.0\n"
+            .parse()
+            .unwrap();
+        let upgrade = upgrade_document(&mut document).unwrap().unwrap();
+        assert_eq!(upgrade.changes.len(), 1);
+        assert_eq!(
+            upgrade.changes[0].to_string(),
+            "dropped `appearance.font_size`: `font.size` is already set"
+        );
+        let config: ScarabConfig = toml::from_str(&document.to_string()).unwrap();
+        assert_eq!(config.font.size, 15.0);
+
+        // Current layouts without a version are left alone
+        let mut document: DocumentMut = "[font]\nsize = 15.0\n".parse().unwrap();
+        assert_eq!(upgrade_document(&mut document).unwrap(), None);
+    }
+
+    #[test]
+    fn test_upgrade_file_writes_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, OLD).unwrap();
+
+        let upgrade = upgrade_file(&path).unwrap().unwrap();
+        let backup = dir.path().join("config.toml.v0.bak");
+        assert_eq!(upgrade.backup.as_deref(), Some(backup.as_path()));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), OLD);
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("config_version = 1"));
+
+        assert_eq!(upgrade_file(&path).unwrap(), None);
+    }
+}
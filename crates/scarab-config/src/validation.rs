@@ -32,6 +32,17 @@ impl ConfigValidator {
             ));
         }
 
+        for (family, features) in &font.features {
+            for feature in features {
+                if !is_valid_font_feature(feature) {
+                    return Err(ConfigError::Validation(format!(
+                        "Invalid font feature '{}' for '{}' (expected e.g. \"+liga\", \"-calt\", \"cv01=2\")",
+                        feature, family
+                    )));
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -96,6 +107,13 @@ impl ConfigValidator {
             )));
         }
 
+        if !(1.0..=21.0).contains(&colors.minimum_contrast) {
+            return Err(ConfigError::Validation(format!(
+                "Minimum contrast {} must be between 1.0 and 21.0",
+                colors.minimum_contrast
+            )));
+        }
+
         Ok(())
     }
 
@@ -108,6 +126,39 @@ impl ConfigValidator {
             );
         }
 
+        if ui.background_image_opacity < 0.0 || ui.background_image_opacity > 1.0 {
+            return Err(ConfigError::Validation(format!(
+                "Background image opacity {} must be between 0.0 and 1.0",
+                ui.background_image_opacity
+            )));
+        }
+
+        if ui.pane_border_width < 0.0 || ui.pane_border_width > 8.0 {
+            return Err(ConfigError::Validation(format!(
+                "Pane border width {} must be between 0.0 and 8.0",
+                ui.pane_border_width
+            )));
+        }
+        if let Some(ref color) = ui.pane_border_color {
+            Self::validate_color(color)?;
+        }
+        if let Some(ref color) = ui.pane_border_focused_color {
+            Self::validate_color(color)?;
+        }
+        if ui.inactive_pane_brightness < 0.0 || ui.inactive_pane_brightness > 1.0 {
+            return Err(ConfigError::Validation(format!(
+                "Inactive pane brightness {} must be between 0.0 and 1.0",
+                ui.inactive_pane_brightness
+            )));
+        }
+
+        if ui.background_dim < 0.0 || ui.background_dim > 1.0 {
+            return Err(ConfigError::Validation(format!(
+                "Background dim {} must be between 0.0 and 1.0",
+                ui.background_dim
+            )));
+        }
+
         Ok(())
     }
 
@@ -201,11 +252,29 @@ impl ConfigValidator {
         // Clamp opacity
         config.colors.opacity = config.colors.opacity.clamp(0.0, 1.0);
         config.colors.dim_opacity = config.colors.dim_opacity.clamp(0.0, 1.0);
+        config.colors.minimum_contrast = config.colors.minimum_contrast.clamp(1.0, 21.0);
 
         config
     }
 }
 
+/// Check a font feature setting of the form `[+|-]tag[=value]`
+fn is_valid_font_feature(feature: &str) -> bool {
+    let body = feature
+        .strip_prefix('+')
+        .or_else(|| feature.strip_prefix('-'))
+        .unwrap_or(feature);
+    let (tag, value) = match body.split_once('=') {
+        Some((tag, value)) => (tag, Some(value)),
+        None => (body, None),
+    };
+
+    tag.len() == 4
+        && tag.chars().all(|c| c.is_ascii_alphanumeric())
+        && value.map_or(true, |v| v.parse::<u32>().is_ok())
+        && !(feature.starts_with('-') && value.is_some())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -223,6 +292,26 @@ mod tests {
         assert!(ConfigValidator::validate(&config).is_err());
     }
 
+    #[test]
+    fn test_font_features() {
+        let mut config = ScarabConfig::default();
+        config.font.features.insert(
+            "Fira Code".to_string(),
+            vec![
+                "+ss01".to_string(),
+                "-calt".to_string(),
+                "cv02=3".to_string(),
+            ],
+        );
+        assert!(ConfigValidator::validate(&config).is_ok());
+
+        config
+            .font
+            .features
+            .insert("*".to_string(), vec!["ligatures".to_string()]);
+        assert!(ConfigValidator::validate(&config).is_err());
+    }
+
     #[test]
     fn test_invalid_color() {
         let result = ConfigValidator::validate_color("FF5555");
@@ -143,7 +143,9 @@ impl ConfigWatcher {
 
     /// Get paths to watch
     fn get_watch_paths(_loader: &ConfigLoader) -> Vec<PathBuf> {
-        let mut paths = vec![ConfigLoader::default_config_path()];
+        let global = ConfigLoader::default_config_path();
+        let mut paths = ConfigLoader::included_files(&global);
+        paths.insert(0, global);
 
         // Add local config if it exists
         if let Ok(Some(local_path)) = Self::find_local_config() {
@@ -831,6 +831,58 @@ fn bench_realistic_terminal_session(c: &mut Criterion) {
     group.finish();
 }
 
+// ============================================================================
+// Benchmark Group 8: Fusabi Hook Call Overhead
+// ============================================================================
+
+/// A script plugin with `bindings` top-level helpers and an `on_output` hook
+fn generate_plugin_script(bindings: usize) -> String {
+    let mut script = String::from("// @name: bench-plugin\n");
+    for i in 0..bindings {
+        script.push_str(&format!("let limit_{} = {}\n", i, i * 10));
+        script.push_str(&format!("let helper_{} = fun x -> x + limit_{}\n", i, i));
+    }
+    script.push_str("let on_output = fun line -> true\n");
+    script
+}
+
+/// Per-call cost of a Fusabi hook as the plugin grows
+///
+/// The plugin's top level runs once per VM, so a hook call should cost
+/// the same at every size and stay well under the 1ms hook budget.
+fn bench_fusabi_hook_call(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fusabi_hook_call");
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    for bindings in [10, 100, 500].iter() {
+        let script = generate_plugin_script(*bindings);
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(script.as_bytes()).unwrap();
+        temp_file.flush().unwrap();
+
+        let mut plugin = FusabiScriptPlugin::load(temp_file.path()).unwrap();
+        let state = Arc::new(parking_lot::Mutex::new(PluginSharedState::new(80, 24)));
+        let ctx = PluginContext::new(Default::default(), state, "test");
+
+        // Run the top level before measuring
+        runtime.block_on(plugin.on_output("warmup", &ctx)).unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::new("script_on_output", bindings),
+            bindings,
+            |b, _| {
+                b.to_async(&runtime).iter(|| async {
+                    let result = plugin.on_output("test", &ctx).await;
+                    black_box(result)
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 // ============================================================================
 // Benchmark Configuration
 // ============================================================================
@@ -865,11 +917,14 @@ criterion_group!(
 
 criterion_group!(workload_benches, bench_realistic_terminal_session);
 
+criterion_group!(fusabi_benches, bench_fusabi_hook_call);
+
 criterion_main!(
     loading_benches,
     dispatch_benches,
     chaining_benches,
     vm_benches,
     throughput_benches,
-    workload_benches
+    workload_benches,
+    fusabi_benches
 );
@@ -0,0 +1,193 @@
+//! Following the desktop's light/dark mode
+//!
+//! With `colors.light_theme` or `colors.dark_theme` set, the desktop's
+//! appearance is polled and `colors.theme` switched to match. Clients are
+//! sent the theme as a `ThemeUpdate`, and clients that connect later get it
+//! too. A mode without a theme of its own goes back to the configured
+//! `theme`.
+//!
+//! Whatever the colors end up as, panes report them to the programs that
+//! ask, so editors can pick a matching scheme.
+
+use crate::ipc::ClientRegistry;
+use crate::session::SessionManager;
+use crate::settings::RuntimeConfig;
+use crate::vte::{set_default_reported_colors, ReportedColors};
+use scarab_config::ColorConfig;
+use scarab_platform::appearance::{system_appearance, Appearance};
+use scarab_protocol::DaemonMessage;
+use scarab_themes::{Theme, ThemeManager};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the desktop's appearance and the colors are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Switches themes with the desktop's appearance
+pub struct AppearanceWatcher {
+    session_manager: Arc<SessionManager>,
+    runtime_config: Arc<RuntimeConfig>,
+    client_registry: ClientRegistry,
+    /// Theme configured before any switching, used by modes without one
+    base_theme: Option<String>,
+    /// Theme last switched to
+    applied: Option<String>,
+    /// Theme that couldn't be found, so it's only warned about once
+    missing: Option<String>,
+    /// Colors the panes were last told to report
+    reported: ReportedColors,
+}
+
+impl AppearanceWatcher {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        runtime_config: Arc<RuntimeConfig>,
+        client_registry: ClientRegistry,
+    ) -> Self {
+        let colors = runtime_config.snapshot().colors;
+        Self {
+            session_manager,
+            runtime_config,
+            client_registry,
+            base_theme: colors.theme.clone(),
+            applied: None,
+            missing: None,
+            reported: ReportedColors::from_config(&colors),
+        }
+    }
+
+    /// Poll until the daemon exits
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            self.follow_appearance().await;
+            self.update_reported_colors();
+        }
+    }
+
+    async fn follow_appearance(&mut self) {
+        let colors = self.runtime_config.snapshot().colors;
+        // Anything else changing the theme, such as a config reload,
+        // becomes the theme to fall back on
+        if colors.theme != self.applied {
+            self.base_theme = colors.theme.clone();
+        }
+
+        if colors.light_theme.is_none() && colors.dark_theme.is_none() {
+            if self.applied.take().is_some() {
+                self.runtime_config.set_system_theme(None);
+            }
+            return;
+        }
+
+        let appearance = match tokio::task::spawn_blocking(system_appearance).await {
+            Ok(Some(appearance)) => appearance,
+            _ => return,
+        };
+        let Some(wanted) = theme_for(&colors, appearance, self.base_theme.as_deref()) else {
+            return;
+        };
+        let wanted = wanted.to_string();
+        if self.applied.as_ref() == Some(&wanted) && colors.theme.as_ref() == Some(&wanted) {
+            return;
+        }
+
+        let Some(theme) = load_theme(&wanted) else {
+            if self.missing.as_ref() != Some(&wanted) {
+                log::warn!("Theme '{}' for {:?} mode not found", wanted, appearance);
+                self.missing = Some(wanted);
+            }
+            return;
+        };
+        let theme_json = match serde_json::to_string(&theme) {
+            Ok(json) => json,
+            Err(e) => {
+                log::warn!("Failed to serialize theme '{}': {}", wanted, e);
+                return;
+            }
+        };
+
+        log::info!(
+            "Desktop is in {:?} mode, switching to theme '{}'",
+            appearance,
+            wanted
+        );
+        let mut config = self.runtime_config.snapshot();
+        theme.apply_to(&mut config.colors);
+        self.runtime_config.replace(config);
+        self.runtime_config
+            .set_system_theme(Some(theme_json.clone()));
+        self.applied = Some(wanted);
+        self.missing = None;
+        self.client_registry
+            .broadcast(DaemonMessage::ThemeUpdate { theme_json })
+            .await;
+    }
+
+    /// Tell every pane about new colors, whether from a theme switch or
+    /// an edited config
+    fn update_reported_colors(&mut self) {
+        let colors = ReportedColors::from_config(&self.runtime_config.snapshot().colors);
+        if colors == self.reported {
+            return;
+        }
+        self.reported = colors;
+        set_default_reported_colors(colors);
+
+        for (session_id, _, _, _, _) in self.session_manager.list_sessions() {
+            if let Some(session) = self.session_manager.get_session(&session_id) {
+                for pane in session.all_panes() {
+                    pane.set_reported_colors(colors);
+                }
+            }
+        }
+    }
+}
+
+/// Theme to use in `appearance`, falling back to `base_theme` if none is
+/// configured for it
+fn theme_for<'a>(
+    colors: &'a ColorConfig,
+    appearance: Appearance,
+    base_theme: Option<&'a str>,
+) -> Option<&'a str> {
+    let theme = match appearance {
+        Appearance::Light => &colors.light_theme,
+        Appearance::Dark => &colors.dark_theme,
+    };
+    theme.as_deref().or(base_theme)
+}
+
+/// A built-in or user theme by ID
+///
+/// Themes are looked up afresh each time, so newly imported ones are found.
+pub(crate) fn load_theme(id: &str) -> Option<Theme> {
+    let mut themes = ThemeManager::new();
+    if let Err(e) = themes.initialize() {
+        log::warn!("Failed to load user themes: {}", e);
+    }
+    themes.get_theme(id).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_for_appearance() {
+        let colors = ColorConfig {
+            dark_theme: Some("dracula".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            theme_for(&colors, Appearance::Dark, Some("slime")),
+            Some("dracula")
+        );
+        assert_eq!(
+            theme_for(&colors, Appearance::Light, Some("slime")),
+            Some("slime")
+        );
+        assert_eq!(theme_for(&colors, Appearance::Light, None), None);
+    }
+}
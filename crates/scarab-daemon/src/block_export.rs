@@ -0,0 +1,353 @@
+//! Exporting command blocks
+//!
+//! "Zones: Export Command" asks for a file and writes the selected command
+//! block of the active pane there: the command line after `$ `, then its
+//! output. A block is selected by clicking in it; until then, or after a
+//! click outside every block, the last finished one is. Files named
+//! `*.ansi` keep the output's colors as SGR escapes; anything else gets
+//! plain text. An existing file is only replaced once the user confirms.
+//! "Zones: Share Command" uploads the plain text to paste.rs through the
+//! plugin HTTP API, which lets this plugin reach that host and no other,
+//! and shows the paste's URL in a notification.
+
+use crate::plugin_manager::history::SessionHistory;
+use async_trait::async_trait;
+use scarab_plugin_api::history::{CommandBlock, TerminalHistory};
+use scarab_plugin_api::types::{Action, ModalItem, MouseEvent, MouseEventKind, PromptResponse};
+use scarab_plugin_api::{Capability, Plugin, PluginContext, PluginError, PluginMetadata, Result};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Command that writes the selected command block to a file
+pub const EXPORT_COMMAND: &str = "zones.export";
+/// Command that uploads the selected command block to paste.rs
+pub const SHARE_COMMAND: &str = "zones.export.paste";
+
+/// Hosts the plugin may send requests to
+pub const PASTE_HOSTS: [&str; 1] = ["paste.rs"];
+
+/// Where shared blocks are posted; the response body is the paste's URL
+const PASTE_URL: &str = "https://paste.rs/";
+
+/// Extension of files that keep the output's colors
+const ANSI_EXTENSION: &str = "ansi";
+
+/// Block to export out of `blocks`: the one with ID `selected`, or else
+/// the last
+pub fn selected_block(blocks: &[CommandBlock], selected: Option<u64>) -> Option<&CommandBlock> {
+    selected
+        .and_then(|id| blocks.iter().find(|block| block.id == id))
+        .or_else(|| blocks.last())
+}
+
+/// Whether the answer to "Replace this file?" is yes
+fn confirms(answer: &str) -> bool {
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+/// Text of `block` to export: `$ ` and the command, then the output
+///
+/// `lines` reads absolute lines `start..end`. The row the block ends on is
+/// where the next prompt starts, so it is left out, as are blank lines at
+/// the end of the output.
+pub fn block_export_text(
+    block: &CommandBlock,
+    lines: impl Fn(usize, usize) -> Vec<String>,
+) -> String {
+    let mut text = String::new();
+    if let Some(command) = block.command_text() {
+        text.push_str(&format!("$ {}\n", command));
+    }
+    if let Some((start, end)) = block.output_bounds() {
+        let mut output = lines(start as usize, end.max(start + 1) as usize);
+        while output.last().is_some_and(|line| line.is_empty()) {
+            output.pop();
+        }
+        for line in output {
+            text.push_str(&line);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Plugin exporting command blocks to files and paste.rs
+pub struct BlockExportPlugin {
+    metadata: PluginMetadata,
+    history: Arc<SessionHistory>,
+    /// ID of the block last clicked in
+    selected: Option<u64>,
+    /// Prompt asking for the file to export to, with the block to write
+    export_prompt: Option<(u64, CommandBlock)>,
+    /// Prompt asking whether to replace an existing file, with the file
+    /// and the block to write
+    overwrite_prompt: Option<(u64, String, CommandBlock)>,
+}
+
+impl BlockExportPlugin {
+    pub fn new(history: Arc<SessionHistory>) -> Self {
+        Self {
+            metadata: PluginMetadata::new(
+                "scarab-block-export",
+                env!("CARGO_PKG_VERSION"),
+                "Export a command and its output to a file or paste.rs",
+                "Scarab Team",
+            )
+            .with_homepage("https://github.com/raibid-labs/scarab"),
+            history,
+            selected: None,
+            export_prompt: None,
+            overwrite_prompt: None,
+        }
+    }
+
+    /// The selected command block, or the last finished one
+    fn block(&self) -> Option<CommandBlock> {
+        selected_block(&self.history.command_blocks(), self.selected).cloned()
+    }
+
+    /// Export text of `block`
+    fn block_text(&self, block: &CommandBlock, ansi: bool) -> String {
+        block_export_text(block, |start, end| {
+            if ansi {
+                self.history.ansi_lines(start, end)
+            } else {
+                self.history.lines(start, end)
+            }
+        })
+    }
+
+    /// Write `block` to `path`, asking first if the file exists unless
+    /// `replace` is set
+    fn export_to(&mut self, path: &str, block: CommandBlock, replace: bool, ctx: &PluginContext) {
+        let path =
+            match scarab_config::expand::expand(path.trim(), &|name| std::env::var(name).ok()) {
+                Ok(path) => path,
+                Err(e) => {
+                    ctx.notify_error("Export Failed", &e);
+                    return;
+                }
+            };
+        let ansi = Path::new(&path)
+            .extension()
+            .is_some_and(|extension| extension == ANSI_EXTENSION);
+        let text = self.block_text(&block, ansi);
+
+        let written = if replace {
+            std::fs::write(&path, text)
+        } else {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+        };
+        match written {
+            Ok(()) => {
+                log::info!("Exported a command to {}", path);
+                ctx.notify_success("Command Exported", &format!("Wrote {}", path));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let prompt_id = ctx.show_input_prompt(
+                    format!("{} exists. Replace it?", path),
+                    "yes to replace, anything else to keep it",
+                    false,
+                );
+                self.overwrite_prompt = Some((prompt_id, path, block));
+            }
+            Err(e) => {
+                log::error!("Failed to export a command to {}: {}", path, e);
+                ctx.notify_error("Export Failed", &e.to_string());
+            }
+        }
+    }
+
+    /// Upload the selected command block to paste.rs in the background
+    fn share(&self, ctx: &PluginContext) {
+        if let Err(e) = ctx.check_permission(
+            &Capability::Network,
+            "upload a command and its output to paste.rs",
+        ) {
+            ctx.notify_warning("Share", &format!("Not shared: {}", e));
+            return;
+        }
+        let Some(block) = self.block() else {
+            ctx.notify_warning("Share", "No command has finished yet");
+            return;
+        };
+        let text = self.block_text(&block, false);
+
+        let task_ctx = ctx.clone();
+        ctx.spawn_task("Share command output", move |_| async move {
+            let response = task_ctx
+                .http_post(PASTE_URL, "text/plain; charset=utf-8", text)
+                .await?;
+            if !response.is_success() {
+                return Err(PluginError::Other(anyhow::anyhow!(
+                    "paste.rs answered with status {}",
+                    response.status
+                )));
+            }
+            let url = response.text().trim().to_string();
+            log::info!("Shared a command at {}", url);
+            task_ctx.notify_success("Command Shared", &url);
+            Ok(())
+        });
+    }
+}
+
+#[async_trait]
+impl Plugin for BlockExportPlugin {
+    fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
+    fn get_commands(&self) -> Vec<ModalItem> {
+        vec![
+            ModalItem {
+                id: EXPORT_COMMAND.to_string(),
+                label: "Zones: Export Command".to_string(),
+                description: Some(
+                    "Write the clicked or last command and output to a file; *.ansi keeps colors"
+                        .to_string(),
+                ),
+            },
+            ModalItem {
+                id: SHARE_COMMAND.to_string(),
+                label: "Zones: Share Command".to_string(),
+                description: Some(
+                    "Upload the clicked or last command and its output to paste.rs (needs network)"
+                        .to_string(),
+                ),
+            },
+        ]
+    }
+
+    async fn on_mouse(&mut self, event: &MouseEvent, _ctx: &PluginContext) -> Result<Action> {
+        if matches!(event.kind, MouseEventKind::Press) {
+            // Rows of the screen follow the scrollback
+            let line = (self.history.scrollback_len() + event.row as usize) as u32;
+            self.selected = self
+                .history
+                .command_blocks()
+                .iter()
+                .rev()
+                .find(|block| block.contains_line(line))
+                .map(|block| block.id);
+        }
+        Ok(Action::Continue)
+    }
+
+    async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
+        match id {
+            EXPORT_COMMAND => {
+                let Some(block) = self.block() else {
+                    ctx.notify_warning("Export", "No command has finished yet");
+                    return Ok(());
+                };
+                let title = match block.command_text() {
+                    Some(command) => format!("Export `{}`", command),
+                    None => "Export Command".to_string(),
+                };
+                let prompt_id = ctx.show_input_prompt(
+                    title,
+                    "File to write, e.g. ~/build.log or ~/build.ansi",
+                    false,
+                );
+                self.export_prompt = Some((prompt_id, block));
+            }
+            SHARE_COMMAND => self.share(ctx),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn on_prompt_response(
+        &mut self,
+        response: &PromptResponse,
+        ctx: &PluginContext,
+    ) -> Result<()> {
+        if self
+            .export_prompt
+            .as_ref()
+            .is_some_and(|(id, _)| *id == response.prompt_id)
+        {
+            let Some((_, block)) = self.export_prompt.take() else {
+                return Ok(());
+            };
+            match response.value() {
+                Some(path) if !path.trim().is_empty() => self.export_to(path, block, false, ctx),
+                _ => {}
+            }
+        } else if self
+            .overwrite_prompt
+            .as_ref()
+            .is_some_and(|(id, _, _)| *id == response.prompt_id)
+        {
+            let Some((_, path, block)) = self.overwrite_prompt.take() else {
+                return Ok(());
+            };
+            if response.value().is_some_and(confirms) {
+                self.export_to(&path, block, true, ctx);
+            } else {
+                ctx.notify_info("Export", &format!("Kept {}", path));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_plugin_api::history::SemanticZone;
+
+    #[test]
+    fn test_block_export_text() {
+        let mut block = CommandBlock::new(1, SemanticZone::new_prompt(10, 4, 0));
+        let mut input = SemanticZone::new_input(11, 4, 0);
+        input.set_command("cargo test".to_string());
+        block.add_input_zone(input);
+        let mut output = SemanticZone::new_output(12, 5, 0);
+        output.complete(9, 1_000);
+        block.add_output_zone(output);
+
+        let screen = [
+            "$ cargo test",
+            "running 2 tests",
+            "test a ... ok",
+            "",
+            "",
+            "$ ",
+        ];
+        let lines = |start: usize, end: usize| -> Vec<String> {
+            screen[start - 4..end - 4]
+                .iter()
+                .map(|line| line.to_string())
+                .collect()
+        };
+
+        // Output runs up to the next prompt, without the blank lines before it
+        assert_eq!(
+            block_export_text(&block, lines),
+            "$ cargo test\nrunning 2 tests\ntest a ... ok\n"
+        );
+    }
+
+    #[test]
+    fn test_selected_block() {
+        let blocks: Vec<CommandBlock> = (1..=3)
+            .map(|id| CommandBlock::new(id, SemanticZone::new_prompt(id * 10, id as u32, 0)))
+            .collect();
+
+        assert_eq!(selected_block(&blocks, Some(2)).map(|b| b.id), Some(2));
+        // Without a click, or once the clicked block has gone, the last
+        assert_eq!(selected_block(&blocks, None).map(|b| b.id), Some(3));
+        assert_eq!(selected_block(&blocks, Some(9)).map(|b| b.id), Some(3));
+        assert!(selected_block(&[], None).is_none());
+
+        assert!(confirms(" Yes\n") && confirms("y"));
+        assert!(!confirms("") && !confirms("no"));
+    }
+}
@@ -0,0 +1,297 @@
+//! Crash recovery checkpoints
+//!
+//! Every `sessions.auto_save_interval` seconds the daemon writes its
+//! sessions, tabs and panes to `checkpoint.json` in the data directory,
+//! with the last lines of each pane's output unless
+//! `sessions.save_scrollback` is off. A `daemon.running` marker sits next
+//! to it while the daemon runs; both are removed on a clean shutdown. When
+//! the marker is still there at startup, the last daemon crashed, and
+//! clients are asked which of its sessions to bring back.
+
+use crate::session::SessionManager;
+use anyhow::{Context, Result};
+use scarab_config::SessionConfig;
+use scarab_protocol::{DaemonMessage, ModalItem};
+use scarab_session::workspace::{WorkspaceSnapshot, SNAPSHOT_VERSION};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Lines of output checkpointed with each pane
+pub const SCROLLBACK_LINES: usize = 200;
+
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+const RUNNING_MARKER: &str = "daemon.running";
+
+/// The checkpoint and running marker in a data directory
+#[derive(Debug, Clone)]
+pub struct Checkpoints {
+    dir: PathBuf,
+}
+
+impl Checkpoints {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn checkpoint_path(&self) -> PathBuf {
+        self.dir.join(CHECKPOINT_FILE)
+    }
+
+    fn marker_path(&self) -> PathBuf {
+        self.dir.join(RUNNING_MARKER)
+    }
+
+    /// Mark the daemon as running
+    ///
+    /// Returns the last checkpoint if the daemon before this one crashed.
+    pub fn start(&self) -> Result<Option<WorkspaceSnapshot>> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let crashed = if self.marker_path().exists() {
+            self.read()
+        } else {
+            None
+        };
+        let marker = self.marker_path();
+        std::fs::write(&marker, std::process::id().to_string())
+            .with_context(|| format!("Failed to write {}", marker.display()))?;
+        Ok(crashed)
+    }
+
+    fn read(&self) -> Option<WorkspaceSnapshot> {
+        let json = std::fs::read_to_string(self.checkpoint_path()).ok()?;
+        match serde_json::from_str::<WorkspaceSnapshot>(&json) {
+            Ok(snapshot) if snapshot.version <= SNAPSHOT_VERSION => {
+                Some(snapshot).filter(|snapshot| !snapshot.sessions.is_empty())
+            }
+            Ok(snapshot) => {
+                log::warn!(
+                    "Ignoring checkpoint from a newer Scarab (format {})",
+                    snapshot.version
+                );
+                None
+            }
+            Err(e) => {
+                log::warn!("Ignoring unreadable checkpoint: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Replace the checkpoint with `snapshot`
+    ///
+    /// The new checkpoint is renamed over the old one, so a crash while
+    /// writing leaves the previous one intact.
+    pub fn save(&self, snapshot: &WorkspaceSnapshot) -> Result<()> {
+        let path = self.checkpoint_path();
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_string(snapshot)?)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        std::fs::rename(&partial, &path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Remove the checkpoint and marker on a clean shutdown
+    pub fn finish(&self) {
+        let _ = std::fs::remove_file(self.checkpoint_path());
+        let _ = std::fs::remove_file(self.marker_path());
+    }
+}
+
+/// Writes a checkpoint of every session on an interval
+pub struct CheckpointWriter {
+    session_manager: Arc<SessionManager>,
+    checkpoints: Checkpoints,
+    interval: Duration,
+    scrollback_lines: usize,
+}
+
+impl CheckpointWriter {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        checkpoints: Checkpoints,
+        config: &SessionConfig,
+    ) -> Self {
+        Self {
+            session_manager,
+            checkpoints,
+            interval: Duration::from_secs(config.auto_save_interval as u64),
+            scrollback_lines: if config.save_scrollback {
+                SCROLLBACK_LINES
+            } else {
+                0
+            },
+        }
+    }
+
+    /// Checkpoint until the daemon exits; an interval of 0 turns it off
+    pub async fn run(self) {
+        if self.interval.is_zero() {
+            log::info!("Session checkpoints are turned off");
+            return;
+        }
+
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            // The crashed daemon's checkpoint is kept until the user has
+            // decided about it, in case this one goes down too
+            if self.session_manager.pending_recovery().is_some() {
+                continue;
+            }
+
+            // Reading panes' working directories and programs blocks
+            let session_manager = self.session_manager.clone();
+            let scrollback_lines = self.scrollback_lines;
+            let snapshot = match tokio::task::spawn_blocking(move || {
+                session_manager.snapshot_workspace(scrollback_lines)
+            })
+            .await
+            {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    log::warn!("Failed to checkpoint sessions: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = self.checkpoints.save(&snapshot) {
+                log::warn!("Failed to checkpoint sessions: {:#}", e);
+            }
+        }
+    }
+}
+
+/// What was picked in the recovery prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryChoice {
+    All,
+    Session(String),
+    Discard,
+}
+
+impl RecoveryChoice {
+    /// The choice a prompt item stands for, if it is one of the prompt's
+    pub fn parse(id: &str) -> Option<Self> {
+        match id.strip_prefix("recovery:")? {
+            "all" => Some(Self::All),
+            "discard" => Some(Self::Discard),
+            other => other
+                .strip_prefix("session:")
+                .map(|name| Self::Session(name.to_string())),
+        }
+    }
+
+    /// ID of the prompt item for this choice
+    pub fn id(&self) -> String {
+        match self {
+            Self::All => "recovery:all".to_string(),
+            Self::Session(name) => format!("recovery:session:{}", name),
+            Self::Discard => "recovery:discard".to_string(),
+        }
+    }
+}
+
+/// Prompt asking which sessions of a crashed daemon to restore
+pub fn recovery_prompt(snapshot: &WorkspaceSnapshot) -> DaemonMessage {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let minutes = now.saturating_sub(snapshot.saved_at) / 60;
+    let age = match minutes {
+        0 => "less than a minute ago".to_string(),
+        1 => "1 minute ago".to_string(),
+        minutes => format!("{} minutes ago", minutes),
+    };
+
+    let mut items = vec![ModalItem {
+        id: RecoveryChoice::All.id(),
+        label: "Restore all sessions".into(),
+        description: Some(format!(
+            "{} session(s), checkpointed {}",
+            snapshot.sessions.len(),
+            age
+        )),
+    }];
+    items.extend(snapshot.sessions.iter().map(|session| {
+        let panes: usize = session.tabs.iter().map(|tab| tab.panes.len()).sum();
+        ModalItem {
+            id: RecoveryChoice::Session(session.name.clone()).id(),
+            label: format!("Restore '{}'", session.name),
+            description: Some(format!("{} tab(s), {} pane(s)", session.tabs.len(), panes)),
+        }
+    }));
+    items.push(ModalItem {
+        id: RecoveryChoice::Discard.id(),
+        label: "Start fresh".into(),
+        description: Some("Forget the previous workspace".into()),
+    });
+
+    DaemonMessage::ShowModal {
+        title: "Restore previous workspace?".into(),
+        items,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_session::workspace::{PaneSnapshot, SessionSnapshot, TabSnapshot};
+    use tempfile::TempDir;
+
+    fn snapshot() -> WorkspaceSnapshot {
+        WorkspaceSnapshot {
+            version: SNAPSHOT_VERSION,
+            saved_at: 1_700_000_000,
+            sessions: vec![SessionSnapshot {
+                name: "dev".to_string(),
+                active_tab: 0,
+                tabs: vec![TabSnapshot {
+                    title: "Tab 1".to_string(),
+                    active_pane: 0,
+                    panes: vec![PaneSnapshot {
+                        shell: "bash".to_string(),
+                        cwd: Some("/tmp".to_string()),
+                        command: None,
+                        scrollback: vec!["$ make".to_string()],
+                    }],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_crash_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let checkpoints = Checkpoints::new(temp_dir.path());
+
+        // First start, then a crash with a checkpoint written
+        assert_eq!(checkpoints.start().unwrap(), None);
+        checkpoints.save(&snapshot()).unwrap();
+        assert_eq!(checkpoints.start().unwrap(), Some(snapshot()));
+
+        // A clean shutdown leaves nothing to recover
+        checkpoints.finish();
+        assert_eq!(checkpoints.start().unwrap(), None);
+    }
+
+    #[test]
+    fn test_recovery_prompt() {
+        let DaemonMessage::ShowModal { title, items } = recovery_prompt(&snapshot()) else {
+            panic!("expected a modal");
+        };
+        assert_eq!(title, "Restore previous workspace?");
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["recovery:all", "recovery:session:dev", "recovery:discard"]
+        );
+        assert_eq!(
+            RecoveryChoice::parse(ids[1]),
+            Some(RecoveryChoice::Session("dev".to_string()))
+        );
+        assert_eq!(RecoveryChoice::parse("workspace.save"), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Panes in SSH and serial domains
+//!
+//! Tabs and panes open on this machine unless `TabCreate` or `PaneSplit`
+//! names one of the `[[ssh_domains]]` or `[[serial_domains]]` from the
+//! config. A tab remembers the domain its first pane was opened in, and
+//! splits of its panes open there too. Such panes have no PTY here: a
+//! relay task feeds what the domain sends into the pane's terminal state,
+//! and what is typed into the pane is written to the domain.
+
+use crate::session::{Pane, Rect};
+use anyhow::{anyhow, Result};
+use scarab_config::{ScarabConfig, SerialFlowControl, SerialParity, SshAuthConfig};
+use scarab_protocol::DomainInfo;
+use scarab_session::serial_domain::{DataBits, FlowControl, Parity, StopBits};
+use scarab_session::{
+    AuthPrompter, Domain, DomainPaneHandle, DomainRegistry, LocalDomain, PaneConfig, SerialDomain,
+    SerialDomainConfig, SshAuth, SshDomain, SshDomainConfig,
+};
+use std::io::Write;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// ID of the domain of this machine, where panes open by default
+pub const LOCAL_DOMAIN: &str = "local";
+
+/// Wait between reads when a domain has nothing to show
+const IDLE_POLL: Duration = Duration::from_millis(10);
+
+/// Registry of this machine and the domains in `config`
+///
+/// SSH domains ask for passwords and passphrases through `prompter`.
+pub fn registry_from_config(
+    config: &ScarabConfig,
+    prompter: Arc<dyn AuthPrompter>,
+) -> DomainRegistry {
+    let registry = DomainRegistry::new();
+    registry.register(Arc::new(LocalDomain::new()));
+    for domain in ssh_domain_configs(config) {
+        registry.register(Arc::new(
+            SshDomain::new(domain).with_prompter(prompter.clone()),
+        ));
+    }
+    for domain in &config.serial_domains {
+        registry.register(Arc::new(SerialDomain::new(serial_domain_config(domain))));
+    }
+    registry
+}
+
+/// The `[[ssh_domains]]` of `config`
+pub fn ssh_domain_configs(config: &ScarabConfig) -> Vec<SshDomainConfig> {
+    config.ssh_domains.iter().map(ssh_domain_config).collect()
+}
+
+fn ssh_domain_config(config: &scarab_config::SshDomainConfig) -> SshDomainConfig {
+    SshDomainConfig {
+        id: config.id.clone(),
+        name: config.name.clone(),
+        host: config.host.clone(),
+        port: config.port,
+        user: config.user.clone(),
+        auth: match &config.auth {
+            SshAuthConfig::Agent => SshAuth::Agent,
+            SshAuthConfig::PublicKey {
+                key_path,
+                passphrase,
+            } => SshAuth::PublicKey {
+                path: key_path.clone(),
+                passphrase: passphrase.clone(),
+            },
+            SshAuthConfig::Password { password } => SshAuth::Password(password.clone()),
+            SshAuthConfig::Interactive => SshAuth::Interactive,
+        },
+        connect_timeout: config.connect_timeout,
+        forward_agent: config.forward_agent,
+        remote_cwd: config.remote_cwd.clone(),
+        jump_hosts: config.jump_hosts.clone(),
+    }
+}
+
+fn serial_domain_config(config: &scarab_config::SerialDomainConfig) -> SerialDomainConfig {
+    SerialDomainConfig {
+        id: config.id.clone(),
+        name: config.name.clone(),
+        port: config.port.clone(),
+        baud_rate: config.baud_rate,
+        data_bits: match config.data_bits {
+            5 => DataBits::Five,
+            6 => DataBits::Six,
+            7 => DataBits::Seven,
+            8 => DataBits::Eight,
+            other => {
+                log::warn!(
+                    "serial_domains.{}: {} data bits isn't supported, using 8",
+                    config.id,
+                    other
+                );
+                DataBits::Eight
+            }
+        },
+        parity: match config.parity {
+            SerialParity::None => Parity::None,
+            SerialParity::Odd => Parity::Odd,
+            SerialParity::Even => Parity::Even,
+        },
+        stop_bits: if config.stop_bits == 2 {
+            StopBits::Two
+        } else {
+            StopBits::One
+        },
+        flow_control: match config.flow_control {
+            SerialFlowControl::None => FlowControl::None,
+            SerialFlowControl::Software => FlowControl::Software,
+            SerialFlowControl::Hardware => FlowControl::Hardware,
+        },
+        dtr: config.dtr,
+        rts: config.rts,
+        log_file: config.log_file.as_ref().map(Into::into),
+    }
+}
+
+/// The domains of `registry` as `DomainList` answers
+pub fn domain_infos(registry: &DomainRegistry) -> Vec<DomainInfo> {
+    let default_id = registry.default_id();
+    registry
+        .list()
+        .into_iter()
+        .map(|(id, name, kind, connected)| DomainInfo {
+            is_default: default_id.as_ref() == Some(&id),
+            id,
+            name,
+            kind: kind.to_string(),
+            connected,
+        })
+        .collect()
+}
+
+/// The domain `id`, unless it is this machine's
+///
+/// Local panes get a PTY of their own rather than going through a domain.
+pub fn remote_domain(registry: &DomainRegistry, id: &str) -> Result<Option<Arc<dyn Domain>>> {
+    if id == LOCAL_DOMAIN {
+        return Ok(None);
+    }
+    registry
+        .get(&id.to_string())
+        .map(Some)
+        .ok_or_else(|| anyhow!("No domain named '{}'", id))
+}
+
+/// A pane started in a domain, not yet relayed to a [`Pane`]
+pub struct DomainPane {
+    domain: Arc<dyn Domain>,
+    handle: DomainPaneHandle,
+    input: mpsc::UnboundedReceiver<Vec<u8>>,
+}
+
+impl DomainPane {
+    /// Start a pane of `cols` x `rows` in `domain`, connecting first if
+    /// needed
+    ///
+    /// Returns the pane to put in a tab and the relay to start once it is
+    /// there.
+    pub async fn spawn(domain: Arc<dyn Domain>, cols: u16, rows: u16) -> Result<(Pane, Self)> {
+        let config = PaneConfig {
+            // The remote user's login shell, as `ssh host` starts
+            shell: "$SHELL -l".to_string(),
+            cols,
+            rows,
+            ..Default::default()
+        };
+        let handle = domain.spawn_pane(config).await?;
+        let (input_tx, input) = mpsc::unbounded_channel();
+        let pane = Pane::remote(
+            1,
+            domain.name().to_string(),
+            Rect::full(cols, rows),
+            Box::new(DomainInput(input_tx)),
+        );
+        Ok((
+            pane,
+            Self {
+                domain,
+                handle,
+                input,
+            },
+        ))
+    }
+
+    /// Relay output and typing between the domain and `pane` until the
+    /// pane is closed
+    ///
+    /// When the domain disconnects, the pane says so and stops.
+    pub fn relay(self, pane: &Arc<Pane>) {
+        let Self {
+            domain,
+            handle,
+            mut input,
+        } = self;
+
+        tokio::spawn({
+            let domain = domain.clone();
+            let handle = handle.clone();
+            async move {
+                // Ends when the pane, which holds the sender, is dropped
+                while let Some(data) = input.recv().await {
+                    if let Err(e) = domain.write_to_pane(&handle, &data).await {
+                        log::debug!("Dropped input to {}: {:#}", domain.id(), e);
+                    }
+                }
+            }
+        });
+
+        tokio::spawn(relay_output(domain, handle, Arc::downgrade(pane)));
+    }
+}
+
+async fn relay_output(domain: Arc<dyn Domain>, handle: DomainPaneHandle, target: Weak<Pane>) {
+    let mut buf = vec![0u8; 4096];
+    let mut size = None;
+    loop {
+        let Some(pane) = target.upgrade() else {
+            let _ = domain.close_pane(&handle).await;
+            break;
+        };
+
+        // Domain panes have no PTY for resizes to reach
+        let dimensions = pane.dimensions();
+        if size.replace(dimensions) != Some(dimensions) {
+            let (cols, rows) = dimensions;
+            if let Err(e) = domain.resize_pane(&handle, cols, rows).await {
+                log::debug!("Failed to resize pane in {}: {:#}", domain.id(), e);
+            }
+        }
+
+        match domain.read_from_pane(&handle, &mut buf).await {
+            Ok(0) => {
+                drop(pane);
+                tokio::time::sleep(IDLE_POLL).await;
+            }
+            Ok(n) => pane.feed_output(&buf[..n]),
+            Err(e) => {
+                log::info!("Pane in {} stopped: {:#}", domain.id(), e);
+                pane.feed_output(
+                    format!("\r\n[Disconnected from {}]\r\n", domain.name()).as_bytes(),
+                );
+                break;
+            }
+        }
+    }
+}
+
+/// Typing into a domain pane, written to the domain by its relay
+struct DomainInput(mpsc::UnboundedSender<Vec<u8>>);
+
+impl Write for DomainInput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.send(buf.to_vec()).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "domain pane closed")
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_from_config() {
+        let mut config = ScarabConfig::default();
+        config.ssh_domains.push(scarab_config::SshDomainConfig {
+            id: "prod".to_string(),
+            name: "Production".to_string(),
+            host: "prod.example.com".to_string(),
+            ..Default::default()
+        });
+        config
+            .serial_domains
+            .push(scarab_config::SerialDomainConfig {
+                id: "board".to_string(),
+                port: "/dev/scarab-no-such-port".to_string(),
+                data_bits: 7,
+                ..Default::default()
+            });
+        let registry =
+            registry_from_config(&config, Arc::new(scarab_session::ModalPrompter::new()));
+
+        let domains = domain_infos(&registry);
+        let summary: Vec<(&str, &str, bool, bool)> = domains
+            .iter()
+            .map(|d| (d.id.as_str(), d.kind.as_str(), d.connected, d.is_default))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("local", "local", true, true),
+                ("board", "serial", false, false),
+                ("prod", "ssh", false, false),
+            ]
+        );
+
+        assert!(remote_domain(&registry, LOCAL_DOMAIN).unwrap().is_none());
+        assert_eq!(
+            remote_domain(&registry, "prod").unwrap().unwrap().name(),
+            "Production"
+        );
+        assert!(remote_domain(&registry, "staging").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_domain_pane() {
+        let domain = Arc::new(SerialDomain::new(SerialDomainConfig {
+            id: "board".to_string(),
+            port: "/dev/scarab-no-such-port".to_string(),
+            ..Default::default()
+        }));
+        let err = DomainPane::spawn(domain, 80, 24).await.err().unwrap();
+        assert!(err.to_string().contains("/dev/scarab-no-such-port"));
+    }
+}
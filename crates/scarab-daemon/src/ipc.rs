@@ -1,22 +1,32 @@
+use crate::checkpoint::{recovery_prompt, RecoveryChoice};
 use crate::orchestrator::OrchestratorMessage;
 use crate::plugin_manager::PluginManager;
+use crate::search::{fetch_lines, search_terminal, SearchQuery};
 use crate::session::{
-    handle_pane_command, handle_session_command, handle_tab_command, SessionManager,
+    handle_domain_command, handle_pane_command, handle_session_command, handle_tab_command,
+    may_connect, SessionId, SessionManager,
 };
+use crate::settings::RuntimeConfig;
 use anyhow::{Context, Result};
 use portable_pty::PtySize;
 use scarab_protocol::{
-    ControlMessage, DaemonMessage, MenuActionType, PluginInspectorInfo, SemanticZone, MAX_CLIENTS,
-    MAX_MESSAGE_SIZE, SOCKET_PATH,
+    ConfigScope, ControlMessage, DaemonMessage, MenuActionType, NotifyLevel, PluginInspectorInfo,
+    SemanticZone, SessionResponse, MAX_CLIENTS, MAX_MESSAGE_SIZE, MAX_SEARCH_RESULTS,
 };
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::unix::OwnedWriteHalf;
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::{mpsc, Mutex, RwLock};
 
+/// Client ID under which control messages from plugins' workspace handles
+/// are handled; replies to it go to every client
+///
+/// Real clients are numbered from 1.
+pub const PLUGIN_CLIENT_ID: u64 = 0;
+
 /// Helper for defer logic (since we don't have a crate for it)
 macro_rules! defer {
     ( $($code:tt)* ) => {
@@ -32,16 +42,33 @@ macro_rules! defer {
     }
 }
 
+/// Input bytes for a session's active pane
+///
+/// `session` is `None` for the default session, which is the one drawn to the
+/// main shared memory region.
+#[derive(Debug)]
+pub struct PtyInput {
+    pub session: Option<SessionId>,
+    pub data: Vec<u8>,
+}
+
+/// Resize for a session's active pane (`None` targets the default session)
+#[derive(Debug)]
+pub struct PtyResize {
+    pub session: Option<SessionId>,
+    pub size: PtySize,
+}
+
 /// Handle to send commands to PTY
 /// Using channels for thread-safe communication
 #[derive(Clone)]
 pub struct PtyHandle {
-    input_tx: mpsc::Sender<Vec<u8>>,
-    resize_tx: mpsc::Sender<PtySize>,
+    input_tx: mpsc::Sender<PtyInput>,
+    resize_tx: mpsc::Sender<PtyResize>,
 }
 
 impl PtyHandle {
-    pub fn new(input_tx: mpsc::Sender<Vec<u8>>, resize_tx: mpsc::Sender<PtySize>) -> Self {
+    pub fn new(input_tx: mpsc::Sender<PtyInput>, resize_tx: mpsc::Sender<PtyResize>) -> Self {
         Self {
             input_tx,
             resize_tx,
@@ -49,20 +76,41 @@ impl PtyHandle {
     }
 
     pub async fn write_input(&self, data: &[u8]) -> Result<()> {
+        self.write_input_to(None, data).await
+    }
+
+    /// Send input to a specific session's active pane
+    pub async fn write_input_to(&self, session: Option<SessionId>, data: &[u8]) -> Result<()> {
         self.input_tx
-            .send(data.to_vec())
+            .send(PtyInput {
+                session,
+                data: data.to_vec(),
+            })
             .await
             .context("Failed to send input to PTY channel")?;
         Ok(())
     }
 
     pub async fn resize(&self, cols: u16, rows: u16) -> Result<()> {
+        self.resize_session(None, cols, rows).await
+    }
+
+    /// Resize a specific session's active pane
+    pub async fn resize_session(
+        &self,
+        session: Option<SessionId>,
+        cols: u16,
+        rows: u16,
+    ) -> Result<()> {
         self.resize_tx
-            .send(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
+            .send(PtyResize {
+                session,
+                size: PtySize {
+                    rows,
+                    cols,
+                    pixel_width: 0,
+                    pixel_height: 0,
+                },
             })
             .await
             .context("Failed to send resize event to PTY")?;
@@ -100,15 +148,25 @@ impl ClientSender {
 #[derive(Clone)]
 pub struct ClientRegistry {
     clients: Arc<RwLock<HashMap<u64, ClientSender>>>,
+    /// Sessions the clients are attached to, to tell read-only clients apart
+    sessions: Option<Arc<SessionManager>>,
 }
 
 impl ClientRegistry {
     pub fn new() -> Self {
         Self {
             clients: Arc::new(RwLock::new(HashMap::new())),
+            sessions: None,
         }
     }
 
+    /// Keep prompts and clipboard writes from clients attached read-only to
+    /// sessions of `session_manager`
+    pub fn with_sessions(mut self, session_manager: Arc<SessionManager>) -> Self {
+        self.sessions = Some(session_manager);
+        self
+    }
+
     pub async fn register(&self, id: u64, sender: ClientSender) {
         let mut map = self.clients.write().await;
         map.insert(id, sender);
@@ -120,6 +178,10 @@ impl ClientRegistry {
     }
 
     pub async fn send(&self, id: u64, msg: DaemonMessage) -> Result<()> {
+        if id == PLUGIN_CLIENT_ID {
+            self.broadcast(msg).await;
+            return Ok(());
+        }
         let map = self.clients.read().await;
         if let Some(sender) = map.get(&id) {
             sender.send(msg).await?;
@@ -137,56 +199,117 @@ impl ClientRegistry {
             }
         }
     }
+
+    /// Send a message to every client that may type into its session
+    ///
+    /// For prompts and clipboard writes, which clients attached read-only
+    /// must neither see nor answer.
+    pub async fn broadcast_to_writers(&self, msg: DaemonMessage) {
+        let map = self.clients.read().await;
+        for (id, sender) in map.iter() {
+            if let Some(sessions) = &self.sessions {
+                if !sessions.can_write(*id) {
+                    continue;
+                }
+            }
+            if let Err(e) = sender.send(msg.clone()).await {
+                log::warn!("Failed to broadcast to client {}: {}", id, e);
+            }
+        }
+    }
 }
 
 /// IPC server managing multiple client connections
 pub struct IpcServer {
     listener: UnixListener,
+    socket_path: PathBuf,
     pty_handle: PtyHandle,
     session_manager: Arc<SessionManager>,
     plugin_manager: Arc<Mutex<PluginManager>>,
     client_registry: ClientRegistry,
     client_counter: Arc<RwLock<u64>>,
     orchestrator_tx: mpsc::UnboundedSender<OrchestratorMessage>,
+    runtime_config: Arc<RuntimeConfig>,
 }
 
 impl IpcServer {
-    /// Create new IPC server, removing stale socket if exists
+    /// Create new IPC server listening at `socket_path`, removing stale socket if exists
     pub async fn new(
+        socket_path: PathBuf,
         pty_handle: PtyHandle,
         session_manager: Arc<SessionManager>,
         client_registry: ClientRegistry,
         plugin_manager: Arc<Mutex<PluginManager>>,
         orchestrator_tx: mpsc::UnboundedSender<OrchestratorMessage>,
+        runtime_config: Arc<RuntimeConfig>,
     ) -> Result<Self> {
+        if let Some(parent) = socket_path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create socket directory")?;
+        }
+
         // Remove existing socket if present
-        if Path::new(SOCKET_PATH).exists() {
-            std::fs::remove_file(SOCKET_PATH).context("Failed to remove stale socket")?;
+        if socket_path.exists() {
+            std::fs::remove_file(&socket_path).context("Failed to remove stale socket")?;
         }
 
-        let listener = UnixListener::bind(SOCKET_PATH).context("Failed to bind Unix socket")?;
+        let listener = UnixListener::bind(&socket_path)
+            .with_context(|| format!("Failed to bind Unix socket at {}", socket_path.display()))?;
 
         // Set socket permissions to 700 (owner only)
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            std::fs::set_permissions(SOCKET_PATH, std::fs::Permissions::from_mode(0o700))
+            std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o700))
                 .context("Failed to set socket permissions")?;
         }
 
-        println!("IPC server listening on: {}", SOCKET_PATH);
+        println!("IPC server listening on: {}", socket_path.display());
 
         Ok(Self {
             listener,
+            socket_path,
             pty_handle,
             session_manager,
             plugin_manager,
             client_registry,
             client_counter: Arc::new(RwLock::new(0)),
             orchestrator_tx,
+            runtime_config,
         })
     }
 
+    /// Apply workspace changes requested by plugins as they arrive
+    ///
+    /// They are handled like control messages from a client, and the
+    /// resulting tab and pane updates are sent to every client.
+    pub fn spawn_workspace_control(&self, mut rx: mpsc::UnboundedReceiver<ControlMessage>) {
+        let pty_handle = self.pty_handle.clone();
+        let session_manager = self.session_manager.clone();
+        let client_registry = self.client_registry.clone();
+        let plugin_manager = self.plugin_manager.clone();
+        let orchestrator_tx = self.orchestrator_tx.clone();
+        let runtime_config = self.runtime_config.clone();
+
+        tokio::spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                if let Err(e) = handle_message(
+                    msg,
+                    &pty_handle,
+                    &session_manager,
+                    &plugin_manager,
+                    &client_registry,
+                    PLUGIN_CLIENT_ID,
+                    &orchestrator_tx,
+                    &runtime_config,
+                )
+                .await
+                {
+                    log::warn!("Plugin workspace change failed: {}", e);
+                }
+            }
+        });
+    }
+
     /// Accept client connections in a loop
     pub async fn accept_loop(self) -> Result<()> {
         let active_clients = Arc::new(RwLock::new(0usize));
@@ -223,6 +346,7 @@ impl IpcServer {
                     let client_registry = self.client_registry.clone();
                     let plugin_manager = self.plugin_manager.clone();
                     let orchestrator_tx = self.orchestrator_tx.clone();
+                    let runtime_config = self.runtime_config.clone();
                     let active_clients = active_clients.clone();
 
                     tokio::spawn(async move {
@@ -234,6 +358,7 @@ impl IpcServer {
                             client_registry,
                             plugin_manager,
                             orchestrator_tx,
+                            runtime_config,
                         )
                         .await
                         {
@@ -262,6 +387,7 @@ async fn handle_client(
     client_registry: ClientRegistry,
     plugin_manager: Arc<Mutex<PluginManager>>,
     orchestrator_tx: mpsc::UnboundedSender<OrchestratorMessage>,
+    runtime_config: Arc<RuntimeConfig>,
 ) -> Result<()> {
     let (mut stream_read, stream_write) = stream.into_split();
     let mut buffer = vec![0u8; MAX_MESSAGE_SIZE];
@@ -270,9 +396,46 @@ async fn handle_client(
     let sender = ClientSender::new(stream_write);
     client_registry.register(client_id, sender).await;
 
+    // Theme picked for the desktop's light/dark mode
+    if let Some(theme_json) = runtime_config.system_theme() {
+        let msg = DaemonMessage::ThemeUpdate { theme_json };
+        let _ = client_registry.send(client_id, msg).await;
+    }
+
+    // Panes given a theme of their own
+    for (pane_id, palette) in runtime_config.pane_palettes() {
+        let msg = DaemonMessage::PanePaletteUpdate {
+            pane_id,
+            palette: Some(palette),
+        };
+        let _ = client_registry.send(client_id, msg).await;
+    }
+
+    // Overrides set before this client connected
+    if let Some((tab_id, pane_id)) = focused_pane(&session_manager) {
+        let settings = runtime_config.overrides_for(tab_id, pane_id);
+        if !settings.is_empty() {
+            let msg = DaemonMessage::ConfigOverrides {
+                tab_id,
+                pane_id,
+                settings,
+            };
+            let _ = client_registry.send(client_id, msg).await;
+        }
+    }
+
+    // Sessions of a crashed daemon, offered until someone decides
+    if let Some(pending) = session_manager.pending_recovery() {
+        let _ = client_registry
+            .send(client_id, recovery_prompt(&pending))
+            .await;
+    }
+
     // Ensure cleanup on exit
     let registry_clone = client_registry.clone();
+    let sessions_clone = session_manager.clone();
     defer! {
+        sessions_clone.detach_client_everywhere(client_id);
         tokio::spawn(async move {
             registry_clone.unregister(client_id).await;
         });
@@ -321,6 +484,7 @@ async fn handle_client(
             &client_registry,
             client_id,
             &orchestrator_tx,
+            &runtime_config,
         )
         .await
         {
@@ -332,28 +496,61 @@ async fn handle_client(
     Ok(())
 }
 
-/// Process a control message
-async fn handle_message(
+/// Session a client's input and resizes go to (`None` = default session)
+fn client_target(session_manager: &SessionManager, client_id: u64) -> Option<SessionId> {
+    session_manager
+        .client_session(client_id)
+        .map(|session| session.id.clone())
+}
+
+/// Whether a client attached read-only may send a message
+///
+/// Only messages that look at sessions or move the client between them are
+/// let through. Anything else could type, rearrange panes, run plugin code
+/// or change settings.
+fn viewer_may_send(msg: &ControlMessage) -> bool {
+    matches!(
+        msg,
+        ControlMessage::Ping { .. }
+            | ControlMessage::Disconnect { .. }
+            | ControlMessage::SessionList
+            | ControlMessage::SessionAttach { .. }
+            | ControlMessage::SessionDetach { .. }
+            | ControlMessage::TabList
+            | ControlMessage::ZonesRequest
+            | ControlMessage::CopyLastOutput
+            | ControlMessage::SelectZone { .. }
+            | ControlMessage::ExtractZoneText { .. }
+            | ControlMessage::ScrollbackSearch { .. }
+            | ControlMessage::ScrollbackFetch { .. }
+    )
+}
+
+/// Whether a message reads the tabs, screen or scrollback of the default
+/// session
+fn reads_default_session(msg: &ControlMessage) -> bool {
+    matches!(
+        msg,
+        ControlMessage::TabList
+            | ControlMessage::ZonesRequest
+            | ControlMessage::CopyLastOutput
+            | ControlMessage::SelectZone { .. }
+            | ControlMessage::ExtractZoneText { .. }
+            | ControlMessage::ScrollbackSearch { .. }
+            | ControlMessage::ScrollbackFetch { .. }
+    )
+}
+
+/// Handle tab, pane and domain commands
+///
+/// Returns whether `msg` was one.
+async fn handle_layout_command(
     msg: ControlMessage,
-    pty_handle: &PtyHandle,
     session_manager: &Arc<SessionManager>,
-    plugin_manager: &Arc<Mutex<PluginManager>>,
     client_registry: &ClientRegistry,
     client_id: u64,
     orchestrator_tx: &mpsc::UnboundedSender<OrchestratorMessage>,
-) -> Result<()> {
-    // Try to handle as session command first
-    if let Ok(Some(response)) =
-        handle_session_command(msg.clone(), session_manager, client_id).await
-    {
-        log::info!("Session command response: {:?}", response);
-        // Send response back to client
-        client_registry
-            .send(client_id, DaemonMessage::Session(response))
-            .await?;
-        return Ok(());
-    }
-
+) -> Result<bool> {
     // Try to handle as tab command
     if let Ok(Some(result)) = handle_tab_command(msg.clone(), session_manager, client_id).await {
         log::info!(
@@ -383,7 +580,7 @@ async fn handle_message(
         if let Some(response) = result.message {
             client_registry.send(client_id, response).await?;
         }
-        return Ok(());
+        return Ok(true);
     }
 
     // Try to handle as pane command
@@ -404,6 +601,262 @@ async fn handle_message(
             _ => {}
         }
         client_registry.send(client_id, response).await?;
+        return Ok(true);
+    }
+
+    // Try to handle as domain command
+    if let Ok(Some(response)) = handle_domain_command(msg, session_manager, client_id).await {
+        client_registry.send(client_id, response).await?;
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Notification telling the user how saving or restoring a workspace went
+fn workspace_notification(response: &SessionResponse) -> Option<DaemonMessage> {
+    let (body, level) = match response {
+        SessionResponse::WorkspaceSaved { name, sessions } => (
+            format!("Saved {} session(s) as '{}'", sessions, name),
+            NotifyLevel::Success,
+        ),
+        SessionResponse::WorkspaceRestored { name, session_ids } if session_ids.is_empty() => (
+            format!("Every session of '{}' is already running", name),
+            NotifyLevel::Info,
+        ),
+        SessionResponse::WorkspaceRestored { name, session_ids } => (
+            format!("Restored {} session(s) from '{}'", session_ids.len(), name),
+            NotifyLevel::Success,
+        ),
+        SessionResponse::Error { message } => (message.clone(), NotifyLevel::Error),
+        _ => return None,
+    };
+    Some(DaemonMessage::PluginNotification {
+        title: "Workspace".into(),
+        body,
+        level,
+    })
+}
+
+/// Bring back the sessions of a crashed daemon picked in the recovery
+/// prompt, then ask about the rest again
+async fn recover_sessions(
+    choice: RecoveryChoice,
+    session_manager: &SessionManager,
+    client_registry: &ClientRegistry,
+    client_id: u64,
+    orchestrator_tx: &mpsc::UnboundedSender<OrchestratorMessage>,
+) -> Result<()> {
+    let name = match &choice {
+        RecoveryChoice::All => None,
+        RecoveryChoice::Session(name) => Some(name.as_str()),
+        RecoveryChoice::Discard => {
+            session_manager.take_pending_recovery(None);
+            log::info!("Client {} discarded the previous workspace", client_id);
+            return Ok(());
+        }
+    };
+
+    for saved in session_manager.take_pending_recovery(name) {
+        let (body, level) = match session_manager.recover_session(&saved, 80, 24) {
+            Ok((id, replaced_panes)) => {
+                for pane_id in replaced_panes {
+                    let _ = orchestrator_tx.send(OrchestratorMessage::PaneDestroyed(pane_id));
+                }
+                if let Some(session) = session_manager.get_session(&id) {
+                    for pane in session.all_panes() {
+                        let _ = orchestrator_tx.send(OrchestratorMessage::PaneCreated(pane.id));
+                    }
+                }
+                (format!("Restored '{}'", saved.name), NotifyLevel::Success)
+            }
+            Err(e) => (
+                format!("Failed to restore '{}': {:#}", saved.name, e),
+                NotifyLevel::Error,
+            ),
+        };
+        let notification = DaemonMessage::PluginNotification {
+            title: "Workspace".into(),
+            body,
+            level,
+        };
+        client_registry.send(client_id, notification).await?;
+    }
+
+    if let Some(pending) = session_manager.pending_recovery() {
+        client_registry
+            .send(client_id, recovery_prompt(&pending))
+            .await?;
+    }
+    Ok(())
+}
+
+/// The default session's active tab and focused pane
+fn focused_pane(session_manager: &SessionManager) -> Option<(u64, u64)> {
+    let session = session_manager.get_default_session()?;
+    let pane = session.get_active_pane()?;
+    Some((session.active_tab_id(), pane.id))
+}
+
+/// Config overrides in effect for the focused pane
+pub(crate) fn focused_overrides(
+    session_manager: &SessionManager,
+    runtime_config: &RuntimeConfig,
+) -> Option<DaemonMessage> {
+    let (tab_id, pane_id) = focused_pane(session_manager)?;
+    Some(DaemonMessage::ConfigOverrides {
+        tab_id,
+        pane_id,
+        settings: runtime_config.overrides_for(tab_id, pane_id),
+    })
+}
+
+/// Process a control message
+///
+/// Clients are sent the focused pane's config overrides whenever the focus
+/// moves or the overrides change. Overrides of closed tabs and panes are
+/// dropped.
+async fn handle_message(
+    msg: ControlMessage,
+    pty_handle: &PtyHandle,
+    session_manager: &Arc<SessionManager>,
+    plugin_manager: &Arc<Mutex<PluginManager>>,
+    client_registry: &ClientRegistry,
+    client_id: u64,
+    orchestrator_tx: &mpsc::UnboundedSender<OrchestratorMessage>,
+    runtime_config: &Arc<RuntimeConfig>,
+) -> Result<()> {
+    let overrides_changed = matches!(
+        msg,
+        ControlMessage::ConfigOverride { .. } | ControlMessage::ConfigOverrideClear { .. }
+    );
+    let closes = matches!(
+        msg,
+        ControlMessage::TabClose { .. } | ControlMessage::PaneClose { .. }
+    );
+    let focus_before = focused_pane(session_manager);
+
+    let result = dispatch_message(
+        msg,
+        pty_handle,
+        session_manager,
+        plugin_manager,
+        client_registry,
+        client_id,
+        orchestrator_tx,
+        runtime_config,
+    )
+    .await;
+
+    if closes {
+        if let Some(session) = session_manager.get_default_session() {
+            let tabs: Vec<u64> = session.list_tabs().into_iter().map(|tab| tab.0).collect();
+            let panes: Vec<u64> = session.all_panes().iter().map(|pane| pane.id).collect();
+            runtime_config.retain_overrides(|scope| match scope {
+                ConfigScope::Tab(id) => tabs.contains(&id),
+                ConfigScope::Pane(id) => panes.contains(&id),
+            });
+        }
+    }
+    if overrides_changed || focused_pane(session_manager) != focus_before {
+        if let Some(msg) = focused_overrides(session_manager, runtime_config) {
+            client_registry.broadcast(msg).await;
+        }
+    }
+    result
+}
+
+/// Process a control message that is not about the focused pane's overrides
+async fn dispatch_message(
+    msg: ControlMessage,
+    pty_handle: &PtyHandle,
+    session_manager: &Arc<SessionManager>,
+    plugin_manager: &Arc<Mutex<PluginManager>>,
+    client_registry: &ClientRegistry,
+    client_id: u64,
+    orchestrator_tx: &mpsc::UnboundedSender<OrchestratorMessage>,
+    runtime_config: &Arc<RuntimeConfig>,
+) -> Result<()> {
+    // Clients attached read-only only get to watch the session they're
+    // attached to
+    if !session_manager.can_write(client_id) {
+        let other_session =
+            reads_default_session(&msg) && client_target(session_manager, client_id).is_some();
+        if !viewer_may_send(&msg) || other_session {
+            log::debug!("Ignoring a message from read-only client {}", client_id);
+            return Ok(());
+        }
+    }
+
+    let workspace_command = matches!(
+        msg,
+        ControlMessage::WorkspaceSave { .. } | ControlMessage::WorkspaceRestore { .. }
+    );
+
+    // Try to handle as session command first
+    if let Ok(Some(response)) =
+        handle_session_command(msg.clone(), session_manager, client_id).await
+    {
+        log::info!("Session command response: {:?}", response);
+        // A new session starts with a shell; read its output like any pane
+        let new_sessions = match &response {
+            SessionResponse::Created { id, .. } => vec![id.clone()],
+            SessionResponse::WorkspaceRestored { session_ids, .. } => session_ids.clone(),
+            _ => Vec::new(),
+        };
+        for id in &new_sessions {
+            if let Some(session) = session_manager.get_session(id) {
+                for pane in session.all_panes() {
+                    let _ = orchestrator_tx.send(OrchestratorMessage::PaneCreated(pane.id));
+                }
+            }
+        }
+        // Workspaces are saved and restored from the command palette, so
+        // say how it went
+        if let Some(notification) = workspace_command
+            .then(|| workspace_notification(&response))
+            .flatten()
+        {
+            client_registry.send(client_id, notification).await?;
+        }
+        // Send response back to client
+        client_registry
+            .send(client_id, DaemonMessage::Session(response))
+            .await?;
+        return Ok(());
+    }
+
+    // Connecting to a domain may ask for a password, whose answer comes
+    // back through this client's messages, so it mustn't hold them up
+    if may_connect(&msg, session_manager) {
+        let session_manager = session_manager.clone();
+        let client_registry = client_registry.clone();
+        let orchestrator_tx = orchestrator_tx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_layout_command(
+                msg,
+                &session_manager,
+                &client_registry,
+                client_id,
+                &orchestrator_tx,
+            )
+            .await
+            {
+                log::warn!("Client {} domain command failed: {}", client_id, e);
+            }
+        });
+        return Ok(());
+    }
+
+    if handle_layout_command(
+        msg.clone(),
+        session_manager,
+        client_registry,
+        client_id,
+        orchestrator_tx,
+    )
+    .await?
+    {
         return Ok(());
     }
 
@@ -411,14 +864,46 @@ async fn handle_message(
     match msg {
         ControlMessage::Resize { cols, rows } => {
             log::info!("Client {} resize: {}x{}", client_id, cols, rows);
-            pty_handle.resize(cols, rows).await?;
+            pty_handle
+                .resize_session(client_target(session_manager, client_id), cols, rows)
+                .await?;
         }
         ControlMessage::Input { data } => {
             // Validate input size to prevent abuse
             if data.len() > MAX_MESSAGE_SIZE {
                 anyhow::bail!("Input data too large: {} bytes", data.len());
             }
-            pty_handle.write_input(&data).await?;
+            session_manager.record_input(client_id);
+            pty_handle
+                .write_input_to(client_target(session_manager, client_id), &data)
+                .await?;
+        }
+        ControlMessage::PaneInput {
+            tab_id,
+            pane_id,
+            data,
+        } => {
+            if data.len() > MAX_MESSAGE_SIZE {
+                anyhow::bail!("Input data too large: {} bytes", data.len());
+            }
+            session_manager.record_input(client_id);
+            let pane = session_manager
+                .get_default_session()
+                .and_then(|session| session.get_pane(tab_id, pane_id))
+                .with_context(|| format!("No pane {} in tab {}", pane_id, tab_id))?;
+            let writer = pane.pty_writer();
+            let mut writer = match writer.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => {
+                    log::warn!("PTY writer lock poisoned, recovering");
+                    poisoned.into_inner()
+                }
+            };
+            if let Some(writer) = writer.as_mut() {
+                use std::io::Write;
+                writer.write_all(&data)?;
+                writer.flush()?;
+            }
         }
         ControlMessage::LoadPlugin { path } => {
             log::info!("Client {} loading plugin: {}", client_id, path);
@@ -441,6 +926,8 @@ async fn handle_message(
                         path: abs_path.clone(),
                         enabled: true,
                         config: Default::default(),
+                        capabilities: Default::default(),
+                        allowed_hosts: Default::default(),
                     };
 
                     let plugin_name = config.name.clone(); // Clone name before moving config
@@ -517,9 +1004,61 @@ async fn handle_message(
         }
         ControlMessage::CommandSelected { id } => {
             log::info!("Client {} selected command: {}", client_id, id);
+            if let Some(choice) = RecoveryChoice::parse(&id) {
+                recover_sessions(
+                    choice,
+                    session_manager,
+                    client_registry,
+                    client_id,
+                    orchestrator_tx,
+                )
+                .await?;
+            } else {
+                let mut pm = plugin_manager.lock().await;
+                if let Err(e) = pm.dispatch_remote_command(&id).await {
+                    log::error!("Failed to dispatch remote command: {}", e);
+                }
+            }
+        }
+        ControlMessage::PromptResponse { prompt_id, values } => {
+            log::debug!("Client {} answered prompt {}", client_id, prompt_id);
+            let mut pm = plugin_manager.lock().await;
+            if let Err(e) = pm.dispatch_prompt_response(prompt_id, values).await {
+                log::error!("Failed to dispatch prompt response: {}", e);
+            }
+        }
+        ControlMessage::PermissionResponse {
+            prompt_id,
+            decision,
+        } => {
+            log::debug!(
+                "Client {} answered permission prompt {}",
+                client_id,
+                prompt_id
+            );
+            let pm = plugin_manager.lock().await;
+            if let Err(e) = pm.resolve_permission(prompt_id, decision).await {
+                log::error!("Failed to save permission decision: {}", e);
+            }
+        }
+        ControlMessage::PanelItemSelected {
+            plugin_name,
+            overlay_id,
+            item_id,
+        } => {
+            log::debug!(
+                "Client {} picked '{}' in panel {} of plugin '{}'",
+                client_id,
+                item_id,
+                overlay_id,
+                plugin_name
+            );
             let mut pm = plugin_manager.lock().await;
-            if let Err(e) = pm.dispatch_remote_command(&id).await {
-                log::error!("Failed to dispatch remote command: {}", e);
+            if let Err(e) = pm
+                .dispatch_panel_selection(&plugin_name, overlay_id, &item_id)
+                .await
+            {
+                log::error!("Failed to dispatch panel selection: {}", e);
             }
         }
         ControlMessage::PluginListRequest => {
@@ -643,7 +1182,9 @@ async fn handle_message(
                     // Send command to PTY
                     let mut cmd_bytes = command.into_bytes();
                     cmd_bytes.push(b'\r'); // Add carriage return
-                    pty_handle.write_input(&cmd_bytes).await?;
+                    pty_handle
+                        .write_input_to(client_target(session_manager, client_id), &cmd_bytes)
+                        .await?;
                 }
                 MenuActionType::Remote { id } => {
                     log::info!(
@@ -654,8 +1195,7 @@ async fn handle_message(
 
                     let mut pm = plugin_manager.lock().await;
 
-                    // Extract context and timeout before mutable borrow
-                    let ctx = pm.context.clone();
+                    // Extract timeout before mutable borrow
                     let timeout_duration = pm.hook_timeout;
 
                     // Find the plugin by name
@@ -680,6 +1220,7 @@ async fn handle_message(
                                 .await?;
                         } else {
                             // Call the plugin's on_remote_command hook with timeout
+                            let ctx = managed.context.clone();
                             let result = tokio::time::timeout(
                                 timeout_duration,
                                 managed.plugin.on_remote_command(&id, &ctx),
@@ -736,7 +1277,7 @@ async fn handle_message(
                             }
 
                             // Process any pending commands from the plugin
-                            pm.process_pending_commands().await;
+                            pm.flush_commands().await;
                         }
                     } else {
                         log::error!("Plugin '{}' not found", plugin_name);
@@ -839,107 +1380,39 @@ async fn handle_message(
         ControlMessage::PluginReload { name } => {
             log::info!("Client {} reloading plugin: {}", client_id, name);
 
-            let plugin_manager = plugin_manager.clone();
             let mut pm = plugin_manager.lock().await;
 
-            // Find the plugin and get its config path
-            let plugin_config = pm
-                .plugins
-                .iter()
-                .find(|p| p.plugin.metadata().name == name.as_str())
-                .map(|p| p.config.clone());
-
-            if let Some(config) = plugin_config {
-                // Unload the plugin
-                let plugin_idx = pm
-                    .plugins
-                    .iter()
-                    .position(|p| p.plugin.metadata().name == name.as_str());
-
-                if let Some(idx) = plugin_idx {
-                    let mut managed = pm.plugins.remove(idx); // Make it mutable
-
-                    log::debug!("Unloading plugin '{}'", name);
-
-                    // Call on_unload with timeout
-                    if let Err(e) =
-                        tokio::time::timeout(pm.hook_timeout, managed.plugin.on_unload()).await
-                    {
-                        log::warn!("Plugin '{}' unload timed out: {:?}", name, e);
-                    }
-
-                    // Reload the plugin
-                    log::debug!("Reloading plugin '{}' from {:?}", name, config.path);
-
-                    match pm.load_plugin_from_config(config.clone()).await {
-                        Ok(_) => {
-                            log::info!("Successfully reloaded plugin '{}'", name);
-
-                            // Refresh commands
-                            pm.refresh_commands();
+            match pm.reload_plugin(&name).await {
+                Ok(()) => {
+                    log::info!("Successfully reloaded plugin '{}'", name);
 
-                            // Send updated plugin list to all clients
-                            let plugins = pm.list_plugins();
-                            let plugin_infos: Vec<PluginInspectorInfo> = plugins
-                                .into_iter()
-                                .map(|p| PluginInspectorInfo {
-                                    name: p.name.clone().into(),
-                                    version: p.version.clone().into(),
-                                    description: p.description.clone().into(),
-                                    author: p.author.clone().into(),
-                                    homepage: p.homepage.clone().map(|s| s.into()),
-                                    api_version: p.api_version.clone().into(),
-                                    min_scarab_version: p.min_scarab_version.clone().into(),
-                                    enabled: p.enabled,
-                                    failure_count: p.failure_count,
-                                    emoji: p.emoji.clone().map(|s| s.into()),
-                                    color: p.color.clone().map(|s| s.into()),
-                                    verification:
-                                        scarab_protocol::PluginVerificationStatus::Unverified {
-                                            warning: "Verification not yet implemented".into(),
-                                        },
-                                })
-                                .collect();
-
-                            client_registry
-                                .broadcast(DaemonMessage::PluginList {
-                                    plugins: plugin_infos,
-                                })
-                                .await;
+                    // Send updated plugin list to all clients
+                    client_registry
+                        .broadcast(DaemonMessage::PluginList {
+                            plugins: pm.inspector_infos(),
+                        })
+                        .await;
 
-                            // Notify status change
-                            client_registry
-                                .broadcast(DaemonMessage::PluginStatusChanged {
-                                    name: name.clone(),
-                                    enabled: true,
-                                })
-                                .await;
-                        }
-                        Err(e) => {
-                            log::error!("Failed to reload plugin '{}': {}", name, e);
-                            client_registry
-                                .send(
-                                    client_id,
-                                    DaemonMessage::PluginError {
-                                        name: name.clone(),
-                                        error: format!("Reload failed: {}", e).into(),
-                                    },
-                                )
-                                .await?;
-                        }
-                    }
-                }
-            } else {
-                log::error!("Plugin '{}' not found", name);
-                client_registry
-                    .send(
-                        client_id,
-                        DaemonMessage::PluginError {
+                    // Notify status change
+                    client_registry
+                        .broadcast(DaemonMessage::PluginStatusChanged {
                             name: name.clone(),
-                            error: "Plugin not found".to_string().into(),
-                        },
-                    )
-                    .await?;
+                            enabled: true,
+                        })
+                        .await;
+                }
+                Err(e) => {
+                    log::error!("Failed to reload plugin '{}': {}", name, e);
+                    client_registry
+                        .send(
+                            client_id,
+                            DaemonMessage::PluginError {
+                                name: name.clone(),
+                                error: format!("Reload failed: {}", e).into(),
+                            },
+                        )
+                        .await?;
+                }
             }
         }
         // Session commands and internal messages - already handled elsewhere
@@ -948,7 +1421,10 @@ async fn handle_message(
         | ControlMessage::SessionList
         | ControlMessage::SessionAttach { .. }
         | ControlMessage::SessionDetach { .. }
-        | ControlMessage::SessionRename { .. } => {
+        | ControlMessage::SessionRename { .. }
+        | ControlMessage::SessionSetAccess { .. }
+        | ControlMessage::WorkspaceSave { .. }
+        | ControlMessage::WorkspaceRestore { .. } => {
             // Already handled by handle_session_command
         }
         // Tab management - handled by handle_tab_command above
@@ -966,6 +1442,12 @@ async fn handle_message(
         | ControlMessage::PaneResize { .. } => {
             // Already handled by handle_pane_command
         }
+        // Domains - handled by handle_domain_command above
+        ControlMessage::DomainList
+        | ControlMessage::DomainConnect { .. }
+        | ControlMessage::DomainDisconnect { .. } => {
+            // Already handled by handle_domain_command
+        }
         // Navigation pane/tab commands
         ControlMessage::PaneFocusNext => {
             log::debug!("Client {} requested focus next pane", client_id);
@@ -1042,7 +1524,18 @@ async fn handle_message(
             // Forward mouse click as escape sequence to PTY
             // Format: CSI < button ; col ; row M (for press)
             let mouse_seq = format!("\x1b[<{};{};{}M", button, col + 1, row + 1);
-            pty_handle.write_input(mouse_seq.as_bytes()).await?;
+            pty_handle
+                .write_input_to(
+                    client_target(session_manager, client_id),
+                    mouse_seq.as_bytes(),
+                )
+                .await?;
+        }
+        ControlMessage::MouseEvent { event } => {
+            let mut pm = plugin_manager.lock().await;
+            if let Err(e) = pm.dispatch_mouse(&event.into()).await {
+                log::error!("Failed to dispatch mouse event: {}", e);
+            }
         }
         ControlMessage::PluginLog { .. } | ControlMessage::PluginNotify { .. } => {
             // These are internal messages sent BY plugins, not received FROM clients
@@ -1070,6 +1563,10 @@ async fn handle_message(
                     // Get all zones and command blocks
                     let zones = zone_tracker.zones().to_vec();
                     let blocks = zone_tracker.command_blocks().to_vec();
+                    let scrollback_lines = terminal_state.scrollback_len() as u32;
+                    let command_running = zone_tracker
+                        .current_block()
+                        .is_some_and(|block| block.output_zone.is_some());
 
                     // Send zones update
                     client_registry
@@ -1078,7 +1575,25 @@ async fn handle_message(
 
                     // Send command blocks update
                     client_registry
-                        .send(client_id, DaemonMessage::CommandBlocksUpdate { blocks })
+                        .send(
+                            client_id,
+                            DaemonMessage::CommandBlocksUpdate {
+                                blocks,
+                                scrollback_lines,
+                                command_running,
+                            },
+                        )
+                        .await?;
+
+                    // Send the markers too, which are otherwise only pushed
+                    // as they change
+                    client_registry
+                        .send(
+                            client_id,
+                            DaemonMessage::PromptMarkersUpdate {
+                                markers: terminal_state.prompt_marker_infos(),
+                            },
+                        )
                         .await?;
 
                     log::debug!(
@@ -1189,6 +1704,158 @@ async fn handle_message(
                 }
             }
         }
+        ControlMessage::ScrollbackSearch {
+            query,
+            case_sensitive,
+            use_regex,
+            max_results,
+            scope,
+        } => {
+            log::debug!("Client {} searching {:?} for {:?}", client_id, scope, query);
+            if let Some(session) = session_manager.get_default_session() {
+                if let Some(pane) = session.get_active_pane() {
+                    let response = {
+                        let terminal_state = pane.terminal_state.read();
+                        let scrollback_lines = terminal_state.scrollback_len() as u32;
+                        match SearchQuery::new(&query, case_sensitive, use_regex) {
+                            Ok(compiled) => {
+                                let (matches, total) = search_terminal(
+                                    &terminal_state,
+                                    &compiled,
+                                    scope,
+                                    max_results.min(MAX_SEARCH_RESULTS) as usize,
+                                );
+                                DaemonMessage::ScrollbackSearchResults {
+                                    query,
+                                    scrollback_lines,
+                                    total: total as u32,
+                                    matches,
+                                    error: None,
+                                }
+                            }
+                            Err(e) => DaemonMessage::ScrollbackSearchResults {
+                                query,
+                                scrollback_lines,
+                                total: 0,
+                                matches: Vec::new(),
+                                error: Some(e.to_string()),
+                            },
+                        }
+                    };
+
+                    client_registry.send(client_id, response).await?;
+                }
+            }
+        }
+        ControlMessage::ScrollbackFetch { start, count } => {
+            if let Some(session) = session_manager.get_default_session() {
+                if let Some(pane) = session.get_active_pane() {
+                    let lines = {
+                        let terminal_state = pane.terminal_state.read();
+                        fetch_lines(&terminal_state, start as usize, count as usize)
+                    };
+
+                    client_registry
+                        .send(client_id, DaemonMessage::ScrollbackLines { start, lines })
+                        .await?;
+                }
+            }
+        }
+        ControlMessage::ConfigGet { key } => {
+            let response = match runtime_config.get(&key) {
+                Ok(value) => DaemonMessage::ConfigValue {
+                    key,
+                    value: Some(value),
+                    error: None,
+                },
+                Err(e) => DaemonMessage::ConfigValue {
+                    key,
+                    value: None,
+                    error: Some(e.to_string()),
+                },
+            };
+            client_registry.send(client_id, response).await?;
+        }
+        ControlMessage::ConfigSet {
+            key,
+            value,
+            persist,
+        } => {
+            log::info!(
+                "Client {} setting {} = {} (persist: {})",
+                client_id,
+                key,
+                value,
+                persist
+            );
+            let result = runtime_config.set(&key, &value, persist);
+
+            // Plugins' changes are only logged; every client hears of them below
+            if client_id != PLUGIN_CLIENT_ID {
+                let response = match &result {
+                    Ok(value) => DaemonMessage::ConfigValue {
+                        key: key.clone(),
+                        value: Some(value.clone()),
+                        error: None,
+                    },
+                    Err(e) => DaemonMessage::ConfigValue {
+                        key: key.clone(),
+                        value: None,
+                        error: Some(e.to_string()),
+                    },
+                };
+                client_registry.send(client_id, response).await?;
+            }
+
+            match result {
+                Ok(value) => {
+                    if key == "plugins" || key.starts_with("plugins.") {
+                        let plugins = runtime_config.snapshot().plugins;
+                        plugin_manager
+                            .lock()
+                            .await
+                            .apply_plugin_settings(&plugins)
+                            .await;
+                    }
+                    client_registry
+                        .broadcast(DaemonMessage::ConfigChanged { key, value })
+                        .await;
+                }
+                Err(e) => log::warn!("Failed to set {}: {}", key, e),
+            }
+        }
+        ControlMessage::ConfigOverride { scope, key, value } => {
+            log::info!(
+                "Client {} overriding {} for {:?}: {:?}",
+                client_id,
+                key,
+                scope,
+                value
+            );
+            let result = runtime_config.set_override(scope, &key, value.as_deref());
+            if client_id != PLUGIN_CLIENT_ID {
+                let response = match &result {
+                    Ok(value) => DaemonMessage::ConfigValue {
+                        key: key.clone(),
+                        value: value.clone(),
+                        error: None,
+                    },
+                    Err(e) => DaemonMessage::ConfigValue {
+                        key: key.clone(),
+                        value: None,
+                        error: Some(e.to_string()),
+                    },
+                };
+                client_registry.send(client_id, response).await?;
+            }
+            if let Err(e) = result {
+                log::warn!("Failed to override {} for {:?}: {}", key, scope, e);
+            }
+        }
+        ControlMessage::ConfigOverrideClear { scope } => {
+            log::info!("Client {} clearing overrides for {:?}", client_id, scope);
+            runtime_config.clear_overrides(scope);
+        }
     }
 
     Ok(())
@@ -1228,9 +1895,48 @@ fn extract_zone_text(terminal_state: &crate::vte::TerminalState, zone: &Semantic
 /// Cleanup socket on server shutdown
 impl Drop for IpcServer {
     fn drop(&mut self) {
-        if Path::new(SOCKET_PATH).exists() {
-            let _ = std::fs::remove_file(SOCKET_PATH);
-            println!("Cleaned up socket: {}", SOCKET_PATH);
+        if self.socket_path.exists() {
+            let _ = std::fs::remove_file(&self.socket_path);
+            println!("Cleaned up socket: {}", self.socket_path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_clients_only_watch() {
+        assert!(viewer_may_send(&ControlMessage::SessionList));
+        assert!(viewer_may_send(&ControlMessage::ScrollbackFetch {
+            start: 0,
+            count: 10,
+        }));
+
+        for msg in [
+            ControlMessage::Input {
+                data: b"ls\r".to_vec(),
+            },
+            ControlMessage::LoadPlugin {
+                path: "evil.fzb".into(),
+            },
+            ControlMessage::SessionSetAccess {
+                read_only_by_default: false,
+            },
+            ControlMessage::ConfigSet {
+                key: "font.size".into(),
+                value: "20".into(),
+                persist: true,
+            },
+            ControlMessage::PluginMenuExecute {
+                plugin_name: "git".into(),
+                action: scarab_protocol::MenuActionType::Command {
+                    command: "push".into(),
+                },
+            },
+        ] {
+            assert!(!viewer_may_send(&msg), "{:?}", msg);
         }
     }
 }
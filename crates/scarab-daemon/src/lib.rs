@@ -1,11 +1,20 @@
 // Public modules
+pub mod appearance;
+pub mod block_export;
+pub mod checkpoint;
+pub mod domains;
 pub mod events;
 pub mod images;
 pub mod ipc;
 pub mod orchestrator;
+pub mod pane_profiles;
+pub mod pane_theme;
 pub mod plugin_manager;
 pub mod profiling;
+pub mod search;
 pub mod session;
+pub mod settings;
+pub mod tmux;
 pub mod vte;
 pub mod vte_optimized;
 
@@ -1,24 +1,34 @@
 use anyhow::Result;
-use portable_pty::PtySize;
-use scarab_config::ConfigLoader;
+use scarab_config::{ConfigLoader, ConfigWatcher};
 use scarab_protocol::{
-    SharedImageBuffer, SharedImagePlacement, SharedState, IMAGE_SHMEM_PATH, IMAGE_SHMEM_PATH_ENV,
-    MAX_IMAGES, SHMEM_PATH, SHMEM_PATH_ENV,
+    DaemonMessage, HyperlinkInfo, SharedImageBuffer, SharedImagePlacement, SharedState,
+    IMAGE_SHMEM_PATH_ENV, MAX_IMAGES, SHMEM_PATH_ENV,
 };
 use shared_memory::{ShmemConf, ShmemError};
+use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use tokio::sync::mpsc;
 
-use scarab_daemon::ipc::{ClientRegistry, IpcServer, PtyHandle};
+use scarab_daemon::appearance::AppearanceWatcher;
+use scarab_daemon::block_export::{BlockExportPlugin, PASTE_HOSTS};
+use scarab_daemon::checkpoint::{CheckpointWriter, Checkpoints};
+use scarab_daemon::domains::{registry_from_config, ssh_domain_configs};
+use scarab_daemon::ipc::{ClientRegistry, IpcServer, PtyHandle, PtyInput, PtyResize};
 use scarab_daemon::orchestrator::PaneOrchestrator;
-use scarab_daemon::plugin_manager::PluginManager;
-use scarab_daemon::session::SessionManager;
-use scarab_daemon::vte::TerminalState;
+use scarab_daemon::pane_profiles::PaneProfileWatcher;
+use scarab_daemon::pane_theme::PaneThemeWatcher;
+use scarab_daemon::plugin_manager::{
+    history::SessionHistory, workspace::SessionWorkspace, PluginDirWatcher, PluginManager,
+    PERMISSION_EXPIRY_INTERVAL, STATUS_SEGMENT_INTERVAL,
+};
+use scarab_daemon::session::{SessionManager, SessionRegions};
+use scarab_daemon::settings::RuntimeConfig;
+use scarab_daemon::vte::{set_default_reported_colors, ReportedColors, TerminalState};
 use scarab_protocol::{GRID_HEIGHT, GRID_WIDTH};
 
 use scarab_plugin_api::context::PluginSharedState;
-use scarab_plugin_api::PluginContext;
+use scarab_plugin_api::{Capability, PermissionStore, PluginContext, PluginStorage};
 
 #[cfg(test)]
 mod tests;
@@ -29,9 +39,9 @@ async fn main() -> Result<()> {
     println!("Starting Scarab Daemon...");
 
     // 0. Load Configuration (Fusabi-based)
-    let home_dir = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let fusabi_config_path = std::path::PathBuf::from(&home_dir).join(".config/scarab/config.fsx");
-    let toml_config_path = std::path::PathBuf::from(&home_dir).join(".config/scarab/config.toml");
+    let config_dir = scarab_platform::Paths::from_env().config_dir;
+    let fusabi_config_path = config_dir.join("config.fsx");
+    let toml_config_path = config_dir.join("config.toml");
 
     let config = if fusabi_config_path.exists() {
         println!(
@@ -45,9 +55,12 @@ async fn main() -> Result<()> {
             toml_config_path.display()
         );
         println!(
-            "💡 Consider migrating to Fusabi config: {}",
+            "💡 Consider migrating to Fusabi config: {} (run `scarab-config migrate`)",
             fusabi_config_path.display()
         );
+        if let Some(upgrade) = ConfigLoader::upgrade_in_place(&toml_config_path) {
+            println!("Upgraded {}: {}", toml_config_path.display(), upgrade);
+        }
         ConfigLoader::from_file(&toml_config_path)?
     } else {
         println!("No config found (tried .fsx and .toml), using defaults");
@@ -58,6 +71,21 @@ async fn main() -> Result<()> {
         scarab_config::ScarabConfig::default()
     };
 
+    // Profiles for this host and environment, then the project config;
+    // directory profiles are applied per pane below
+    let loader = ConfigLoader::with_path(toml_config_path.clone()).without_directory_profiles();
+    let config = loader.apply_overrides_or_base(config);
+    let profiles = loader.profiles(&config).unwrap_or_else(|e| {
+        log::warn!("Failed to read config profiles: {}", e);
+        config.profiles.clone()
+    });
+
+    // Socket, shared memory and data locations: environment, then `[paths]`
+    let paths = config.paths.resolve();
+
+    // Colors panes report to programs asking for them
+    set_default_reported_colors(ReportedColors::from_config(&config.colors));
+
     // Apply environment variable overrides to telemetry config
     let telemetry = config.telemetry.from_env();
 
@@ -73,7 +101,7 @@ async fn main() -> Result<()> {
 
     // 1. Initialize Shared Memory early so we can render errors even if PTY fails
     // Support environment variable override for sandboxed environments
-    let shmem_path = std::env::var(SHMEM_PATH_ENV).unwrap_or_else(|_| SHMEM_PATH.to_string());
+    let shmem_path = paths.shmem.clone();
 
     // Try to create new shared memory, or open existing if it already exists
     // Only fall back to open() for MappingIdExists; other errors are fatal
@@ -117,7 +145,7 @@ async fn main() -> Result<()> {
             eprintln!("  - /dev/shm not mounted or not writable");
             eprintln!("");
             eprintln!(
-                "To use a custom path, set the {} environment variable:",
+                "To use a custom path, set `paths.shmem` in config.toml or the {} environment variable:",
                 SHMEM_PATH_ENV
             );
             eprintln!("  export {}=/my_custom_shm_path", SHMEM_PATH_ENV);
@@ -127,7 +155,7 @@ async fn main() -> Result<()> {
             eprintln!("Failed to create shared memory at {}: {}", shmem_path, e);
             eprintln!("");
             eprintln!(
-                "To use a custom path, set the {} environment variable:",
+                "To use a custom path, set `paths.shmem` in config.toml or the {} environment variable:",
                 SHMEM_PATH_ENV
             );
             eprintln!("  export {}=/my_custom_shm_path", SHMEM_PATH_ENV);
@@ -155,8 +183,7 @@ async fn main() -> Result<()> {
 
     // Initialize SharedImageBuffer for iTerm2 image protocol
     // Support environment variable override for sandboxed environments
-    let image_shmem_path =
-        std::env::var(IMAGE_SHMEM_PATH_ENV).unwrap_or_else(|_| IMAGE_SHMEM_PATH.to_string());
+    let image_shmem_path = paths.image_shmem.clone();
 
     let image_shmem = match ShmemConf::new()
         .size(std::mem::size_of::<SharedImageBuffer>())
@@ -204,7 +231,7 @@ async fn main() -> Result<()> {
             eprintln!("  - /dev/shm not mounted or not writable");
             eprintln!("");
             eprintln!(
-                "To use a custom path, set the {} environment variable:",
+                "To use a custom path, set `paths.image_shmem` in config.toml or the {} environment variable:",
                 IMAGE_SHMEM_PATH_ENV
             );
             eprintln!("  export {}=/my_custom_img_shm_path", IMAGE_SHMEM_PATH_ENV);
@@ -217,7 +244,7 @@ async fn main() -> Result<()> {
             );
             eprintln!("");
             eprintln!(
-                "To use a custom path, set the {} environment variable:",
+                "To use a custom path, set `paths.image_shmem` in config.toml or the {} environment variable:",
                 IMAGE_SHMEM_PATH_ENV
             );
             eprintln!("  export {}=/my_custom_img_shm_path", IMAGE_SHMEM_PATH_ENV);
@@ -231,10 +258,22 @@ async fn main() -> Result<()> {
         std::ptr::write_bytes(image_ptr, 0, 1);
     }
 
-    // 2. Initialize Session Manager (after shared memory is ready)
-    let db_path = std::path::PathBuf::from(&home_dir).join(".local/share/scarab/sessions.db");
+    // Domains tabs and panes can open in; SSH domains ask for passwords
+    // through the session plugin
+    let session_plugin = scarab_session::SessionPlugin::new().with_data_dir(&paths.data_dir);
+    let domains = Arc::new(registry_from_config(
+        &config,
+        session_plugin.auth_prompter(),
+    ));
+    println!("Domains: {} configured", domains.count());
 
-    let session_manager = std::sync::Arc::new(SessionManager::new(db_path)?);
+    // 2. Initialize Session Manager (after shared memory is ready)
+    let session_manager = std::sync::Arc::new(
+        SessionManager::new(paths.sessions_db.clone())?
+            .with_shmem_path(&shmem_path)
+            .with_domains(domains.clone())
+            .with_data_dir(&paths.data_dir),
+    );
 
     // Restore sessions from previous daemon runs
     if let Err(e) = session_manager.restore_sessions(
@@ -265,6 +304,11 @@ async fn main() -> Result<()> {
                     "Created default session: {} ({}x{})",
                     session_id, cols, rows
                 );
+                if let Some(session) = session_manager.get_session(&session_id) {
+                    if let Err(e) = session.open_startup_tabs(&config.sessions.startup_tabs) {
+                        log::warn!("Failed to open startup tabs: {}", e);
+                    }
+                }
             }
             Err(e) => {
                 emit_error_grid(
@@ -278,6 +322,27 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Checkpoint sessions for crash recovery, and offer the last daemon's
+    // if it didn't shut down cleanly
+    let checkpoints = Checkpoints::new(&paths.data_dir);
+    match checkpoints.start() {
+        Ok(Some(crashed)) => {
+            println!(
+                "Previous daemon crashed; offering {} session(s) to restore",
+                crashed.sessions.len()
+            );
+            session_manager.set_pending_recovery(crashed);
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Crash recovery is unavailable: {:#}", e),
+    }
+    let checkpoint_writer = CheckpointWriter::new(
+        session_manager.clone(),
+        checkpoints.clone(),
+        &config.sessions,
+    );
+    tokio::spawn(checkpoint_writer.run());
+
     // PTY is now managed per-pane by SessionManager
     // The active pane's PTY master is accessed via session_manager.get_default_session()
     println!(
@@ -292,23 +357,55 @@ async fn main() -> Result<()> {
     println!("Image support: iTerm2 protocol (max {} images)", MAX_IMAGES);
 
     // 3. Setup IPC Control Channel with channels for thread safety
-    let (resize_tx, mut resize_rx) = mpsc::channel::<PtySize>(32);
-    let (input_tx, mut input_rx) = mpsc::channel::<Vec<u8>>(1024);
+    let (resize_tx, mut resize_rx) = mpsc::channel::<PtyResize>(32);
+    let (input_tx, mut input_rx) = mpsc::channel::<PtyInput>(1024);
     let pty_handle = PtyHandle::new(input_tx, resize_tx);
 
-    let client_registry = ClientRegistry::new();
+    let client_registry = ClientRegistry::new().with_sessions(session_manager.clone());
 
     // Initialize Plugin Manager
     let plugin_state = Arc::new(parking_lot::Mutex::new(PluginSharedState::new(
         config.terminal.columns,
         config.terminal.rows,
     )));
-    let plugin_ctx = Arc::new(PluginContext::new(
-        Default::default(),
-        plugin_state.clone(),
-        "daemon",
-    ));
-    let mut plugin_manager = PluginManager::new(plugin_ctx, client_registry.clone());
+    // Settings changed at runtime with ConfigSet; persisted to the config in use
+    let settings_path = if fusabi_config_path.exists() {
+        fusabi_config_path.clone()
+    } else {
+        toml_config_path.clone()
+    };
+    let runtime_config = Arc::new(RuntimeConfig::new(config.clone(), settings_path));
+
+    let history = Arc::new(SessionHistory::new(session_manager.clone()));
+    let plugin_ctx = Arc::new(
+        PluginContext::new(Default::default(), plugin_state.clone(), "daemon")
+            .with_history(history.clone())
+            .with_workspace(Arc::new(SessionWorkspace::new(session_manager.clone())))
+            .with_settings(runtime_config.clone())
+            .with_storage_root(PluginStorage::root_in(&paths.data_dir))
+            .with_permissions(Arc::new(open_permission_store(&paths.data_dir))),
+    );
+    // Tab and pane changes made through plugins' object handles
+    let (workspace_tx, workspace_rx) = mpsc::unbounded_channel();
+    // --force-load skips the plugin API and min_scarab_version checks
+    let force_load = std::env::args().any(|arg| arg == "--force-load");
+    // --plugin-dev (or `[plugins] dev_mode`) reloads native plugins on every
+    // build; `--plugin-dev <path>` also loads the library at <path>
+    let args: Vec<String> = std::env::args().collect();
+    let dev_plugins: Vec<std::path::PathBuf> = args
+        .windows(2)
+        .filter(|pair| pair[0] == "--plugin-dev" && !pair[1].starts_with("--"))
+        .map(|pair| std::path::PathBuf::from(&pair[1]))
+        .collect();
+    let plugin_dev = config.plugins.dev_mode || args.iter().any(|arg| arg == "--plugin-dev");
+    let mut plugin_manager = PluginManager::new(plugin_ctx, client_registry.clone())
+        .with_plugin_dir(&paths.plugin_dir)
+        .with_data_dir(&paths.data_dir)
+        .with_force_load(force_load)
+        .with_dev_mode(plugin_dev)
+        .with_workspace_control(workspace_tx)
+        .with_keybindings(&config.keybindings)
+        .with_plugin_settings(&config.plugins);
 
     // Register Palette Plugin
     if let Err(e) = plugin_manager
@@ -320,31 +417,172 @@ async fn main() -> Result<()> {
 
     // Register Session Plugin
     if let Err(e) = plugin_manager
-        .register_plugin(Box::new(scarab_session::SessionPlugin::new()))
+        .register_plugin(Box::new(
+            session_plugin
+                .with_domains(domains)
+                .with_ssh_overrides(ssh_domain_configs(&config)),
+        ))
         .await
     {
         eprintln!("Failed to register SessionPlugin: {}", e);
     }
 
+    // Register Theme Plugin, which applies themes picked in clients and
+    // may download the base16/base24 scheme catalog
+    if let Err(e) = plugin_manager
+        .register_plugin_with_capabilities(
+            Box::new(scarab_themes::ThemePlugin::new()),
+            &[Capability::Network],
+            &scarab_themes::catalog::CATALOG_HOSTS,
+        )
+        .await
+    {
+        eprintln!("Failed to register ThemePlugin: {}", e);
+    }
+
+    // Register Block Export Plugin, which may upload a command's output to
+    // paste.rs
+    if let Err(e) = plugin_manager
+        .register_plugin_with_capabilities(
+            Box::new(BlockExportPlugin::new(history)),
+            &[Capability::Network],
+            &PASTE_HOSTS,
+        )
+        .await
+    {
+        eprintln!("Failed to register BlockExportPlugin: {}", e);
+    }
+
     // Discover and load plugins
     if let Err(e) = plugin_manager.discover_and_load().await {
         eprintln!("Failed to load plugins: {}", e);
     }
+    for path in dev_plugins {
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
+        if let Err(e) = plugin_manager.load_plugin_from_path(path.clone()).await {
+            eprintln!("Failed to load plugin {}: {}", path.display(), e);
+        }
+    }
 
+    let plugin_dirs = plugin_manager.search_paths();
+    let plugin_tasks = plugin_manager.context.tasks.clone();
     let plugin_manager = Arc::new(tokio::sync::Mutex::new(plugin_manager));
 
+    // Watch plugin directories so plugins can be added, rebuilt, or removed live
+    match PluginDirWatcher::new(&plugin_dirs) {
+        Ok(watcher) => {
+            let pm_watch = plugin_manager.clone();
+            let registry_watch = client_registry.clone();
+            tokio::spawn(watcher.run(pm_watch, registry_watch));
+        }
+        Err(e) => log::warn!("Plugin hot reload unavailable: {}", e),
+    }
+
+    // Deliver edited `[plugins.settings.<name>]` tables to running plugins
+    let (settings_tx, mut settings_rx) = mpsc::unbounded_channel();
+    let runtime_watch = runtime_config.clone();
+    let _config_watcher = match ConfigWatcher::new(config.clone()) {
+        Ok(mut watcher) => {
+            watcher.on_change(Box::new(move |new_config| {
+                runtime_watch.replace(new_config.clone());
+                let _ = settings_tx.send(new_config.plugins.clone());
+            }));
+            if let Err(e) = watcher.start() {
+                log::warn!("Plugin settings hot reload unavailable: {}", e);
+            }
+            Some(watcher)
+        }
+        Err(e) => {
+            log::warn!("Plugin settings hot reload unavailable: {}", e);
+            None
+        }
+    };
+    let pm_settings = plugin_manager.clone();
+    tokio::spawn(async move {
+        while let Some(settings) = settings_rx.recv().await {
+            pm_settings
+                .lock()
+                .await
+                .apply_plugin_settings(&settings)
+                .await;
+        }
+    });
+
+    // Background tasks queue progress outside of hooks, so flush it as it comes
+    let pm_tasks = plugin_manager.clone();
+    tokio::spawn(async move {
+        loop {
+            plugin_tasks.changed().await;
+            pm_tasks.lock().await.flush_commands().await;
+        }
+    });
+
+    // Status segment updates held back by rate limiting go out on this tick
+    let pm_status = plugin_manager.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(STATUS_SEGMENT_INTERVAL);
+        loop {
+            interval.tick().await;
+            pm_status.lock().await.flush_status_segments().await;
+        }
+    });
+
+    // Permission prompts nobody answers are denied after a while
+    let pm_permissions = plugin_manager.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PERMISSION_EXPIRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            pm_permissions
+                .lock()
+                .await
+                .expire_permission_prompts()
+                .await;
+        }
+    });
+
+    // Switch themes with the desktop's light/dark mode
+    let appearance_watcher = AppearanceWatcher::new(
+        session_manager.clone(),
+        runtime_config.clone(),
+        client_registry.clone(),
+    );
+    tokio::spawn(appearance_watcher.run());
+
+    // Give panes their own themes by override or rule
+    let pane_theme_watcher = PaneThemeWatcher::new(
+        session_manager.clone(),
+        runtime_config.clone(),
+        client_registry.clone(),
+    );
+    tokio::spawn(pane_theme_watcher.run());
+
+    // Give panes the settings of the directory profiles they are in
+    let pane_profile_watcher = PaneProfileWatcher::new(
+        session_manager.clone(),
+        runtime_config.clone(),
+        client_registry.clone(),
+        profiles,
+    );
+    tokio::spawn(pane_profile_watcher.run());
+
     // Create Pane Orchestrator early so we can pass its command sender to IPC
-    let orchestrator = PaneOrchestrator::new(session_manager.clone(), telemetry.log_pane_events);
+    let orchestrator = PaneOrchestrator::new(session_manager.clone(), telemetry.log_pane_events)
+        .with_client_registry(client_registry.clone())
+        .with_tmux_control_mode(config.sessions.tmux_control_mode);
     let orchestrator_tx = orchestrator.command_sender();
 
     let ipc_server = IpcServer::new(
+        paths.socket.clone(),
         pty_handle.clone(),
         session_manager.clone(),
         client_registry.clone(),
         plugin_manager.clone(),
         orchestrator_tx,
+        runtime_config,
     )
     .await?;
+    ipc_server.spawn_workspace_control(workspace_rx);
 
     // Spawn IPC server task
     tokio::spawn(async move {
@@ -359,7 +597,7 @@ async fn main() -> Result<()> {
     let pm_input = plugin_manager.clone();
     tokio::spawn(async move {
         use std::io::Write;
-        while let Some(data) = input_rx.recv().await {
+        while let Some(PtyInput { session, data }) = input_rx.recv().await {
             // Dispatch input to plugins
             let processed_data = {
                 let mut pm = pm_input.lock().await;
@@ -376,8 +614,12 @@ async fn main() -> Result<()> {
                 continue; // Input consumed by plugin
             }
 
-            // Route input to the active pane's PTY writer
-            if let Some(session) = sm_writer.get_default_session() {
+            // Route input to the target session's active pane, falling back
+            // to the default session
+            let target = session
+                .and_then(|id| sm_writer.get_session(&id))
+                .or_else(|| sm_writer.get_default_session());
+            if let Some(session) = target {
                 if let Some(writer_arc) = session.get_active_pty_writer() {
                     let mut writer_lock = match writer_arc.lock() {
                         Ok(guard) => guard,
@@ -417,6 +659,15 @@ async fn main() -> Result<()> {
     let mut last_sequence = 0u64;
     let compositor_interval = tokio::time::Duration::from_millis(16); // ~60fps
 
+    // Regions for sessions shown in additional client windows
+    let mut session_regions = SessionRegions::new();
+
+    // Hyperlinks last sent to clients
+    let mut last_hyperlinks: Vec<HyperlinkInfo> = Vec::new();
+
+    // Pane whose prompt markers clients were last sent
+    let mut markers_pane = Weak::new();
+
     // FPS tracking
     let mut fps_tracker = if telemetry.fps_log_interval_secs > 0 {
         Some(FpsTracker::new(telemetry.fps_log_interval_secs))
@@ -424,8 +675,14 @@ async fn main() -> Result<()> {
         None
     };
 
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
     loop {
         tokio::select! {
+            // Stop on Ctrl+C or SIGTERM
+            _ = &mut shutdown => break,
+
             // Compositor tick - blit active pane to shared memory
             _ = tokio::time::sleep(compositor_interval) => {
                 // Update FPS tracker
@@ -435,9 +692,20 @@ async fn main() -> Result<()> {
 
                 // Get the active pane from session manager
                 if let Some(session) = session_manager.get_default_session() {
+                    let mut hyperlinks_changed = false;
+                    let mut color_changes = Vec::new();
+                    let mut prompt_markers = None;
                     if let Some(active_pane) = session.get_active_pane() {
                         let terminal_state_arc = active_pane.terminal_state();
                         let mut terminal_state = terminal_state_arc.write();
+                        color_changes = terminal_state.take_color_changes();
+
+                        // A newly focused pane's markers replace the last pane's
+                        prompt_markers = terminal_state.take_prompt_markers_change();
+                        if !Weak::ptr_eq(&markers_pane, &Arc::downgrade(&active_pane)) {
+                            markers_pane = Arc::downgrade(&active_pane);
+                            prompt_markers = Some(terminal_state.prompt_marker_infos());
+                        }
 
                         // Only blit to shared memory if content has changed
                         // This makes rendering reactive - sequence only increments on actual changes
@@ -448,6 +716,11 @@ async fn main() -> Result<()> {
                             // Blit images to SharedImageBuffer
                             blit_images_to_shm(&terminal_state, image_ptr);
 
+                            if terminal_state.hyperlinks() != last_hyperlinks.as_slice() {
+                                last_hyperlinks = terminal_state.hyperlinks().to_vec();
+                                hyperlinks_changed = true;
+                            }
+
                             let new_seq = sequence_counter.load(Ordering::SeqCst);
                             if telemetry.log_sequence_changes && new_seq != last_sequence {
                                 log::debug!("Sequence: {} -> {}", last_sequence, new_seq);
@@ -455,30 +728,75 @@ async fn main() -> Result<()> {
                             last_sequence = new_seq;
                         }
                     }
+
+                    // Push OSC 8 hyperlinks for hover underlining when they move
+                    if hyperlinks_changed {
+                        client_registry
+                            .broadcast(DaemonMessage::HyperlinksUpdate {
+                                links: last_hyperlinks.clone(),
+                            })
+                            .await;
+                    }
+
+                    // Push OSC 133 markers for the command gutter and scrollbar ticks
+                    if let Some(markers) = prompt_markers {
+                        client_registry
+                            .broadcast(DaemonMessage::PromptMarkersUpdate { markers })
+                            .await;
+                    }
+
+                    // Push colors the focused pane's programs changed (OSC 4/10/11/12)
+                    for (color_name, value) in color_changes {
+                        client_registry
+                            .broadcast(DaemonMessage::PaletteColorSet { color_name, value })
+                            .await;
+                    }
+
+                    // Push progress changes (OSC 9;4) for the tab bar and window title
+                    for (tab_id, state) in session.take_progress_updates() {
+                        client_registry
+                            .broadcast(DaemonMessage::ProgressUpdate { tab_id, state })
+                            .await;
+                    }
                 }
+
+                session_regions.sync(&session_manager);
             }
 
             // Handle resize events from IPC
-            Some(pty_size) = resize_rx.recv() => {
-                println!("Resizing active pane to {}x{}", pty_size.cols, pty_size.rows);
+            Some(PtyResize { session, size: pty_size }) = resize_rx.recv() => {
+                let secondary = session
+                    .filter(|id| !session_manager.is_default_session(id))
+                    .and_then(|id| session_manager.get_session(&id));
 
-                // Resize the active pane (both PTY and terminal state)
-                if let Some(session) = session_manager.get_default_session() {
+                if let Some(session) = secondary {
+                    // Secondary sessions are blitted to their own regions on the next tick
                     if let Some(active_pane) = session.get_active_pane() {
                         if let Err(e) = active_pane.resize(pty_size.cols, pty_size.rows) {
                             eprintln!("Failed to resize pane: {}", e);
                         }
+                    }
+                } else {
+                    println!("Resizing active pane to {}x{}", pty_size.cols, pty_size.rows);
+
+                    // Resize the active pane (both PTY and terminal state)
+                    if let Some(session) = session_manager.get_default_session() {
+                        if let Some(active_pane) = session.get_active_pane() {
+                            if let Err(e) = active_pane.resize(pty_size.cols, pty_size.rows) {
+                                eprintln!("Failed to resize pane: {}", e);
+                            }
 
-                        // Force blit after resize (resize marks content as changed)
-                        let terminal_state_arc = active_pane.terminal_state();
-                        let mut terminal_state = terminal_state_arc.write();
-                        // SAFETY: shared_ptr points to valid SharedState in shared memory
-                        unsafe { terminal_state.blit_to_shm(shared_ptr, &sequence_counter) };
+                            // Force blit after resize (resize marks content as changed)
+                            let terminal_state_arc = active_pane.terminal_state();
+                            let mut terminal_state = terminal_state_arc.write();
+                            // SAFETY: shared_ptr points to valid SharedState in shared memory
+                            unsafe { terminal_state.blit_to_shm(shared_ptr, &sequence_counter) };
 
-                        // Blit images after resize
-                        blit_images_to_shm(&terminal_state, image_ptr);
+                            // Blit images after resize
+                            blit_images_to_shm(&terminal_state, image_ptr);
 
-                        last_sequence = sequence_counter.load(Ordering::SeqCst);
+                            last_sequence = sequence_counter.load(Ordering::SeqCst);
+                        }
                     }
                 }
             }
@@ -486,13 +804,37 @@ async fn main() -> Result<()> {
     }
 
     // Cleanup shared memory
-    #[allow(unreachable_code)]
+    println!("Daemon shutting down...");
+    checkpoints.finish();
+    drop(shmem);
+    drop(image_shmem);
+    Ok(())
+}
+
+/// Resolves when the daemon is asked to stop
+async fn shutdown_signal() {
+    #[cfg(unix)]
     {
-        drop(shmem);
-        drop(image_shmem);
-        println!("Daemon shutting down...");
-        Ok(())
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
     }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Load the user's saved plugin permission decisions from `data_dir`
+///
+/// With an unreadable file, decisions only last until the daemon exits.
+fn open_permission_store(data_dir: &Path) -> PermissionStore {
+    PermissionStore::open(&PermissionStore::path_in(data_dir)).unwrap_or_else(|e| {
+        eprintln!("Failed to load plugin permissions: {}", e);
+        PermissionStore::in_memory()
+    })
 }
 
 /// Blit images from TerminalState to SharedImageBuffer
@@ -583,6 +925,9 @@ fn emit_error_grid(shared_ptr: *mut SharedState, sequence_counter: &Arc<AtomicU6
     unsafe {
         let state = &mut *shared_ptr;
 
+        // The error screen replaces everything; clients must redraw in full
+        state.damage_sequence = 0;
+
         // Clear
         for cell in state.cells.iter_mut() {
             cell.char_codepoint = b' ' as u32;
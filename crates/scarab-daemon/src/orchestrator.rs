@@ -7,8 +7,13 @@
 //! - Runs independently of whether the pane is active
 //!
 //! The compositor (in main.rs) only needs to blit the active pane to SharedState.
+//!
+//! Readers also watch for tmux switching a pane to control mode, and hand
+//! that pane's output to the tmux adapter instead (see [`crate::tmux`]).
 
+use crate::ipc::ClientRegistry;
 use crate::session::{Pane, PaneId, SessionManager};
+use crate::tmux::ControlModeGateway;
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::io::{Read, Write};
@@ -39,6 +44,10 @@ pub struct PaneOrchestrator {
     command_rx: Option<mpsc::UnboundedReceiver<OrchestratorMessage>>,
     /// Enable pane lifecycle event logging
     log_events: bool,
+    /// Clients told about tabs and panes of tmux in control mode
+    client_registry: Option<ClientRegistry>,
+    /// Whether panes watch their output for tmux entering control mode
+    tmux_control_mode: bool,
 }
 
 impl PaneOrchestrator {
@@ -52,9 +61,23 @@ impl PaneOrchestrator {
             command_tx,
             command_rx: Some(command_rx),
             log_events,
+            client_registry: None,
+            tmux_control_mode: true,
         }
     }
 
+    /// Send tab and pane updates from tmux control mode to these clients
+    pub fn with_client_registry(mut self, client_registry: ClientRegistry) -> Self {
+        self.client_registry = Some(client_registry);
+        self
+    }
+
+    /// Whether `tmux -CC` in a pane opens tmux windows as tabs
+    pub fn with_tmux_control_mode(mut self, enabled: bool) -> Self {
+        self.tmux_control_mode = enabled;
+        self
+    }
+
     /// Get the command sender for external use
     pub fn command_sender(&self) -> mpsc::UnboundedSender<OrchestratorMessage> {
         self.command_tx.clone()
@@ -162,7 +185,13 @@ impl PaneOrchestrator {
 
         // Spawn the reader task
         let log_events = self.log_events;
-        let handle = tokio::spawn(Self::pane_reader_task(pane, log_events));
+        let handle = tokio::spawn(Self::pane_reader_task(
+            pane,
+            log_events,
+            self.session_manager.clone(),
+            self.client_registry.clone(),
+            self.tmux_control_mode,
+        ));
 
         self.reader_tasks.write().insert(pane_id, handle);
 
@@ -175,8 +204,15 @@ impl PaneOrchestrator {
 
     /// The reader task for a single pane
     /// Reads from PTY and updates TerminalState continuously
-    async fn pane_reader_task(pane: Arc<Pane>, log_events: bool) {
+    async fn pane_reader_task(
+        pane: Arc<Pane>,
+        log_events: bool,
+        session_manager: Arc<SessionManager>,
+        client_registry: Option<ClientRegistry>,
+        tmux_control_mode: bool,
+    ) {
         let pane_id = pane.id;
+        let mut control_mode = ControlModeGateway::new();
 
         if log_events {
             log::info!("PaneOrchestrator: Reader task started for pane {}", pane_id);
@@ -218,13 +254,27 @@ impl PaneOrchestrator {
 
             match read_result {
                 Ok(Ok((n, buf))) if n > 0 => {
-                    let data = &buf[..n];
+                    let (shown, updates) = if tmux_control_mode {
+                        control_mode.feed(&pane, &session_manager, &buf[..n])
+                    } else {
+                        (buf[..n].to_vec(), Vec::new())
+                    };
+                    if let Some(registry) = &client_registry {
+                        for update in updates {
+                            registry.broadcast(update).await;
+                        }
+                    }
+                    if shown.is_empty() {
+                        continue;
+                    }
+                    let data = &shown[..];
 
                     // Process output through the pane's VTE parser
                     // This updates the pane's local grid
                     let terminal_state_arc = pane.terminal_state();
                     let mut terminal_state = terminal_state_arc.write();
                     terminal_state.process_output(data);
+                    pane.mark_activity();
 
                     // Send any pending responses (e.g., DSR cursor position) back to PTY
                     let responses: Vec<Vec<u8>> =
@@ -254,7 +304,12 @@ impl PaneOrchestrator {
                     // for the active pane only
                 }
                 Ok(Ok(_)) => {
-                    // EOF - shell exited
+                    // EOF - shell exited, and any tmux it was running
+                    if let Some(registry) = &client_registry {
+                        for update in control_mode.end() {
+                            registry.broadcast(update).await;
+                        }
+                    }
                     if log_events {
                         log::info!(
                             "PaneOrchestrator: PTY EOF for pane {}, shell exited",
@@ -0,0 +1,130 @@
+//! Directory profiles for single panes
+//!
+//! A `[[profiles]]` entry with a `directory` applies to the panes whose
+//! foreground program is working in that directory, not to the directory
+//! the daemon was started from. Its settings are given to each such pane as
+//! overrides, taken away again once the pane leaves the directory, and
+//! clients are sent the focused pane's overrides whenever they change.
+
+use crate::ipc::{focused_overrides, ClientRegistry};
+use crate::session::{PaneId, SessionManager};
+use crate::settings::RuntimeConfig;
+use scarab_config::{directory_settings, Profile, ProfileEnv};
+use scarab_protocol::ConfigScope;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often panes' working directories are checked against the profiles
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Gives panes the settings of the directory profiles they are in
+pub struct PaneProfileWatcher {
+    session_manager: Arc<SessionManager>,
+    runtime_config: Arc<RuntimeConfig>,
+    client_registry: ClientRegistry,
+    /// Profiles from `profiles.toml`, read at startup
+    file_profiles: Vec<Profile>,
+    /// Host and environment the profiles are matched against
+    env: ProfileEnv,
+    /// Settings each pane was last given, as TOML by dotted key
+    applied: HashMap<PaneId, BTreeMap<String, String>>,
+}
+
+impl PaneProfileWatcher {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        runtime_config: Arc<RuntimeConfig>,
+        client_registry: ClientRegistry,
+        file_profiles: Vec<Profile>,
+    ) -> Self {
+        Self {
+            session_manager,
+            runtime_config,
+            client_registry,
+            file_profiles,
+            env: ProfileEnv::current(),
+            applied: HashMap::new(),
+        }
+    }
+
+    /// Poll until the daemon exits
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if self.update() {
+                if let Some(msg) = focused_overrides(&self.session_manager, &self.runtime_config) {
+                    self.client_registry.broadcast(msg).await;
+                }
+            }
+        }
+    }
+
+    /// Bring every pane's overrides in line with its directory, returning
+    /// whether any changed
+    fn update(&mut self) -> bool {
+        let mut profiles = self.runtime_config.snapshot().profiles;
+        profiles.extend(self.file_profiles.iter().cloned());
+        profiles.retain(|profile| profile.directory.is_some());
+        if profiles.is_empty() && self.applied.is_empty() {
+            return false;
+        }
+
+        let mut changed = false;
+        let mut seen = HashSet::new();
+        for (session_id, _, _, _, _) in self.session_manager.list_sessions() {
+            let Some(session) = self.session_manager.get_session(&session_id) else {
+                continue;
+            };
+            for pane in session.all_panes() {
+                seen.insert(pane.id);
+                let wanted = match pane.current_dir() {
+                    Some(cwd) => directory_settings(&profiles, &self.env.in_dir(cwd)),
+                    None => BTreeMap::new(),
+                };
+                changed |= self.apply(pane.id, wanted);
+            }
+        }
+
+        // The overrides of closed panes go with them
+        self.applied.retain(|pane_id, _| seen.contains(pane_id));
+        changed
+    }
+
+    /// Give pane `pane_id` the settings in `wanted`, dropping those it was
+    /// given before and no longer wants
+    fn apply(&mut self, pane_id: PaneId, wanted: BTreeMap<String, String>) -> bool {
+        let previous = self.applied.remove(&pane_id).unwrap_or_default();
+        if previous == wanted {
+            if !wanted.is_empty() {
+                self.applied.insert(pane_id, wanted);
+            }
+            return false;
+        }
+
+        let scope = ConfigScope::Pane(pane_id);
+        for key in previous.keys().filter(|key| !wanted.contains_key(*key)) {
+            let _ = self.runtime_config.set_override(scope, key, None);
+        }
+        for (key, value) in &wanted {
+            if let Err(e) = self.runtime_config.set_override(scope, key, Some(value)) {
+                log::warn!(
+                    "Invalid profile setting {} for pane {}: {}",
+                    key,
+                    pane_id,
+                    e
+                );
+            }
+        }
+        log::info!(
+            "Pane {} now has directory profile settings for {:?}",
+            pane_id,
+            wanted.keys().collect::<Vec<_>>()
+        );
+        if !wanted.is_empty() {
+            self.applied.insert(pane_id, wanted);
+        }
+        true
+    }
+}
@@ -0,0 +1,212 @@
+//! Themes for single panes
+//!
+//! A pane gets a theme of its own from a `colors.theme` override for it or
+//! its tab, or else from the first `[[colors.pane_themes]]` rule matching
+//! the program in its foreground, such as a root shell or an ssh session to
+//! a production host. The pane's programs then get the theme's ANSI colors
+//! and see its colors when they ask, and clients are sent its palette as a
+//! `PanePaletteUpdate` to draw the pane with.
+
+use crate::appearance::load_theme;
+use crate::ipc::ClientRegistry;
+use crate::session::{Pane, PaneId, SessionManager, TabId};
+use crate::settings::RuntimeConfig;
+use crate::vte::{rgb_to_rgba, ReportedColors};
+use scarab_config::{settings, PaneThemeRule};
+use scarab_protocol::{DaemonMessage, PanePalette};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often panes' foreground programs are checked against the rules
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Gives panes the themes their overrides and rules ask for
+pub struct PaneThemeWatcher {
+    session_manager: Arc<SessionManager>,
+    runtime_config: Arc<RuntimeConfig>,
+    client_registry: ClientRegistry,
+    /// Theme each pane was last given
+    assigned: HashMap<PaneId, String>,
+    /// Themes that couldn't be found, so each is only warned about once
+    missing: HashSet<String>,
+}
+
+impl PaneThemeWatcher {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        runtime_config: Arc<RuntimeConfig>,
+        client_registry: ClientRegistry,
+    ) -> Self {
+        Self {
+            session_manager,
+            runtime_config,
+            client_registry,
+            assigned: HashMap::new(),
+            missing: HashSet::new(),
+        }
+    }
+
+    /// Poll until the daemon exits
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            self.update().await;
+        }
+    }
+
+    async fn update(&mut self) {
+        let rules = self.runtime_config.snapshot().colors.pane_themes;
+        let mut seen = HashSet::new();
+
+        for (session_id, _, _, _, _) in self.session_manager.list_sessions() {
+            let Some(session) = self.session_manager.get_session(&session_id) else {
+                continue;
+            };
+            for (tab_id, panes) in session.panes_by_tab() {
+                for pane in panes {
+                    seen.insert(pane.id);
+                    let wanted = self.wanted_theme(&rules, tab_id, &pane).await;
+                    if self.assigned.get(&pane.id) != wanted.as_ref() {
+                        self.assign(&pane, wanted).await;
+                    }
+                }
+            }
+        }
+
+        // Closed panes need no colors, but clients are left to forget them
+        // as the panes close
+        let closed: Vec<PaneId> = self
+            .assigned
+            .keys()
+            .filter(|pane_id| !seen.contains(pane_id))
+            .copied()
+            .collect();
+        for pane_id in closed {
+            self.assigned.remove(&pane_id);
+            self.runtime_config.set_pane_palette(pane_id, None);
+        }
+    }
+
+    /// Theme a pane should have: a `colors.theme` override for it or its
+    /// tab, or else the first rule its foreground program matches
+    async fn wanted_theme(
+        &self,
+        rules: &[PaneThemeRule],
+        tab_id: TabId,
+        pane: &Arc<Pane>,
+    ) -> Option<String> {
+        let overridden = self
+            .runtime_config
+            .overrides_for(tab_id, pane.id)
+            .into_iter()
+            .find(|entry| entry.key == "colors.theme")
+            .and_then(|entry| {
+                let value = settings::parse_setting_value(&entry.value);
+                value.as_str().map(str::to_string)
+            });
+        if overridden.is_some() || rules.is_empty() {
+            return overridden;
+        }
+
+        let pane = Arc::clone(pane);
+        let (user, command) = tokio::task::spawn_blocking(move || pane.foreground_process())
+            .await
+            .ok()??;
+        rule_for(rules, &user, &command).map(|rule| rule.theme.clone())
+    }
+
+    /// Give `pane` the theme `theme_id`, or take its theme away with `None`,
+    /// and tell clients
+    async fn assign(&mut self, pane: &Pane, theme_id: Option<String>) {
+        let palette = match theme_id.as_deref() {
+            Some(id) => {
+                let Some(theme) = load_theme(id) else {
+                    if self.missing.insert(id.to_string()) {
+                        log::warn!("Theme '{}' for pane {} not found", id, pane.id);
+                    }
+                    return;
+                };
+                let colors = ReportedColors::from_config(&theme.to_color_config());
+                pane.set_theme(Some(colors));
+                Some(palette_for(id, &colors))
+            }
+            None => {
+                pane.set_theme(None);
+                None
+            }
+        };
+
+        match theme_id {
+            Some(id) => {
+                log::info!("Pane {} now uses theme '{}'", pane.id, id);
+                self.assigned.insert(pane.id, id);
+            }
+            None => {
+                log::info!("Pane {} went back to the window's theme", pane.id);
+                self.assigned.remove(&pane.id);
+            }
+        }
+        self.runtime_config
+            .set_pane_palette(pane.id, palette.clone());
+        self.client_registry
+            .broadcast(DaemonMessage::PanePaletteUpdate {
+                pane_id: pane.id,
+                palette,
+            })
+            .await;
+    }
+}
+
+/// The first rule a program run by `user` as `command` matches
+fn rule_for<'a>(
+    rules: &'a [PaneThemeRule],
+    user: &str,
+    command: &str,
+) -> Option<&'a PaneThemeRule> {
+    rules.iter().find(|rule| rule.matches(user, command))
+}
+
+/// Palette sent to clients for a pane with theme `id`
+fn palette_for(id: &str, colors: &ReportedColors) -> PanePalette {
+    PanePalette {
+        theme: id.to_string(),
+        foreground: rgb_to_rgba(colors.foreground),
+        background: rgb_to_rgba(colors.background),
+        cursor: rgb_to_rgba(colors.cursor),
+        ansi: colors.palette.map(rgb_to_rgba),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rule = |theme: &str, user: Option<&str>, command: Option<&str>| PaneThemeRule {
+            theme: theme.to_string(),
+            user: user.map(str::to_string),
+            command: command.map(str::to_string),
+        };
+        let rules = [
+            rule("red-alert", Some("root"), None),
+            rule("gruvbox", None, Some("ssh *prod*")),
+            rule("nord", Some("root"), Some("ssh *")),
+        ];
+        let theme = |user, command| rule_for(&rules, user, command).map(|r| r.theme.as_str());
+        assert_eq!(theme("root", "ssh web.prod"), Some("red-alert"));
+        assert_eq!(theme("alice", "ssh web.prod"), Some("gruvbox"));
+        assert_eq!(theme("alice", "-zsh"), None);
+    }
+
+    #[test]
+    fn test_palette_for() {
+        let palette = palette_for("slime", &ReportedColors::DEFAULT);
+        assert_eq!(palette.theme, "slime");
+        assert_eq!(palette.foreground, 0xFFA8DF5A);
+        assert_eq!(palette.background, 0xFF0D1208);
+        assert_eq!(palette.ansi[1], 0xFFFF5555);
+    }
+}
@@ -2,11 +2,54 @@
 //!
 //! This module provides adapters for both .fzb (compiled bytecode) and .fsx (scripts)
 //! to implement the scarab-plugin-api Plugin trait.
+//!
+//! Hooks are top-level functions named after the trait methods:
+//!
+//! | Function | Called with | Return value |
+//! |----------|-------------|--------------|
+//! | `on_load` | `()` | ignored |
+//! | `on_output` | the line | `false` stops, a string replaces the line |
+//! | `on_input` | the input as text | `false` stops, a string replaces the input |
+//! | `on_resize` | columns, rows | ignored |
+//! | `on_remote_command` | the command ID | ignored |
+//!
+//! Scripts declare palette commands with `// @command: id | Label` comments,
+//! and can pull in shared code with `#load "lib/util.fsx"`.
+//!
+//! Each plugin runs its VM on a thread of its own. fusabi-vm 0.17 has no
+//! instruction budget or yield points, so a hook stuck in a loop cannot be
+//! interrupted; off the async runtime it only stalls its own plugin. Its
+//! calls stop answering, the plugin manager's hook timeout fires, and
+//! after repeated timeouts the plugin is disabled. Calls that time out
+//! while still queued behind the stuck hook are dropped rather than run
+//! late. Reloading or unloading the plugin cancels its VM thread: the
+//! thread runs nothing more and exits once the current hook returns, and is
+//! joined if it already has. At most [`MAX_STUCK_VM_THREADS`] cancelled
+//! threads may still be running before new VMs are refused.
+//!
+//! A plugin's top level runs once per VM, before its first hook, so values
+//! it binds persist from one hook to the next. On a hot reload the plain
+//! data among them (counters, caches, settings) is carried over to the new
+//! VM; see [`super::fusabi_state`].
+//!
+//! VMs get the JSON and time functions of
+//! [`scarab_plugin_api::fusabi_stdlib`]; the regex ones need the
+//! `output-filtering` or `input-filtering` capability.
 
 use async_trait::async_trait;
-use scarab_plugin_api::{Action, Plugin, PluginContext, PluginError, PluginMetadata, Result};
-use std::cell::RefCell;
+use scarab_plugin_api::fusabi_modules::resolve_source;
+use scarab_plugin_api::fusabi_stdlib::{allows_regex, register_stdlib_ext};
+use scarab_plugin_api::{
+    load_state, save_state, types::ModalItem, Action, DefinitionMap, Package, Plugin,
+    PluginContext, PluginError, PluginManifest, PluginMetadata, Result,
+};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::fusabi_state::{self, VmSnapshot};
 
 // Import Fusabi VM (official runtime from crates.io)
 use fusabi_vm::{Value, Vm};
@@ -14,18 +57,300 @@ use fusabi_vm::{Value, Vm};
 // Import Fusabi Frontend (F# script parser/compiler)
 use fusabi_frontend::{Compiler, Lexer, Parser};
 
-// Thread-local storage for VM instances to work around !Send constraints
-// The Fusabi VM uses Rc which is !Send, so we use thread-local storage
-thread_local! {
-    static VM_CACHE: RefCell<Option<Vm>> = RefCell::new(None);
+/// Work sent to a plugin's VM thread
+type VmJob = Box<dyn FnOnce(&mut Vm) + Send>;
+
+/// Cancelled VM threads allowed to still be running a hook
+pub const MAX_STUCK_VM_THREADS: usize = 8;
+
+/// VM threads that have not exited
+static VM_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Workers that have not been dropped; the threads beyond these are stuck
+static VM_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a VM thread until the thread exits, even by panicking
+struct VmThreadCount;
+
+impl VmThreadCount {
+    fn new() -> Self {
+        VM_THREADS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for VmThreadCount {
+    fn drop(&mut self) {
+        VM_THREADS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Cancelled VM threads still running a hook
+fn stuck_vm_threads() -> usize {
+    VM_THREADS
+        .load(Ordering::Relaxed)
+        .saturating_sub(VM_WORKERS.load(Ordering::Relaxed))
+}
+
+/// A Fusabi VM owned by a thread of its own
+///
+/// The VM uses `Rc` internally, so it never leaves the thread; hooks are
+/// sent to it as closures and their results come back over a channel.
+/// Dropping the worker cancels the thread.
+#[derive(Debug)]
+struct VmWorker {
+    jobs: mpsc::Sender<VmJob>,
+    cancelled: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl VmWorker {
+    /// Start a VM thread for the plugin `name`
+    fn spawn(name: &str) -> Result<Self> {
+        let stuck = stuck_vm_threads();
+        if stuck >= MAX_STUCK_VM_THREADS {
+            return Err(PluginError::LoadError(format!(
+                "{} Fusabi hooks are still stuck; not starting another VM",
+                stuck
+            )));
+        }
+
+        let (jobs, queue) = mpsc::channel::<VmJob>();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let thread_cancelled = cancelled.clone();
+        let count = VmThreadCount::new();
+        let thread = std::thread::Builder::new()
+            .name(format!("fusabi-{}", name))
+            .spawn(move || {
+                let _count = count;
+                let mut vm = Vm::new();
+                for job in queue {
+                    if thread_cancelled.load(Ordering::Acquire) {
+                        break;
+                    }
+                    job(&mut vm);
+                }
+            })
+            .map_err(|e| PluginError::LoadError(format!("Failed to start Fusabi VM: {}", e)))?;
+        VM_WORKERS.fetch_add(1, Ordering::Relaxed);
+        Ok(Self {
+            jobs,
+            cancelled,
+            thread: Some(thread),
+        })
+    }
+
+    /// Run `job` on the VM thread, waiting without blocking the runtime
+    ///
+    /// Never returns while a hook is stuck; callers bound the wait with the
+    /// hook timeout. If the caller stops waiting before the job starts, the
+    /// job is skipped.
+    async fn run<T: Send + 'static>(
+        &self,
+        job: impl FnOnce(&mut Vm) -> T + Send + 'static,
+    ) -> Result<T> {
+        let (reply, result) = tokio::sync::oneshot::channel();
+        self.jobs
+            .send(Box::new(move |vm| {
+                if !reply.is_closed() {
+                    let _ = reply.send(job(vm));
+                }
+            }))
+            .map_err(|_| vm_stopped())?;
+        result.await.map_err(|_| vm_stopped())
+    }
+
+    /// Run `job` on the VM thread from synchronous code
+    ///
+    /// Gives up after `limit`, so a stuck hook cannot hang the caller; a
+    /// job that has not started by then is skipped.
+    fn run_blocking<T: Send + 'static>(
+        &self,
+        job: impl FnOnce(&mut Vm) -> T + Send + 'static,
+        limit: Duration,
+    ) -> Option<T> {
+        let deadline = Instant::now() + limit;
+        let (reply, result) = mpsc::channel();
+        self.jobs
+            .send(Box::new(move |vm| {
+                if Instant::now() < deadline {
+                    let _ = reply.send(job(vm));
+                }
+            }))
+            .ok()?;
+        result.recv_timeout(limit).ok()
+    }
+
+    /// Drop the VM's globals before the next hook
+    fn reset(&self) {
+        let _ = self.jobs.send(Box::new(|vm| *vm = Vm::new()));
+    }
+}
+
+impl Drop for VmWorker {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Release);
+        VM_WORKERS.fetch_sub(1, Ordering::Relaxed);
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        if thread.is_finished() {
+            let _ = thread.join();
+        } else {
+            // Idle and about to see the closed queue, or stuck in a hook
+            log::debug!(
+                "Cancelled Fusabi VM thread {:?}; it exits when its hook returns",
+                thread.thread().name()
+            );
+        }
+    }
+}
+
+/// Give a fresh VM the JSON, time and (if allowed) regex functions
+///
+/// Registration is skipped once done, so a plugin's VM keeps the functions
+/// it started with until it is reset.
+fn register_stdlib_ext_once(vm: &mut Vm, allow_regex: bool) {
+    if !vm.globals.contains_key("json_parse") {
+        register_stdlib_ext(vm, allow_regex);
+    }
+}
+
+/// Global marking a VM that has run its plugin's top level
+const LOADED_MARKER: &str = "__scarab_loaded";
+
+/// How long a reload waits for a plugin's VM to hand over its state
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Get a VM ready for a hook: stdlib extensions, then the plugin's top level
+///
+/// The top level runs only on a VM that has not run it yet, so the values
+/// it binds live as long as the VM.
+fn ensure_loaded(vm: &mut Vm, bytecode: &[u8], allow_regex: bool, kind: &str) -> Result<()> {
+    register_stdlib_ext_once(vm, allow_regex);
+    if vm.globals.contains_key(LOADED_MARKER) {
+        return Ok(());
+    }
+
+    let chunk = fusabi_vm::deserialize_chunk(bytecode)
+        .map_err(|e| PluginError::Other(anyhow::anyhow!("Deserialization failed: {}", e)))?;
+    vm.execute(chunk)
+        .map_err(|e| PluginError::Other(anyhow::anyhow!("{} execution failed: {}", kind, e)))?;
+    vm.globals
+        .insert(LOADED_MARKER.to_string(), Value::Bool(true));
+    Ok(())
+}
+
+/// Snapshot of the plugin data on `vm`, or `None` if there is none
+fn snapshot_vm(vm: &VmWorker, plugin_name: &str) -> Option<Vec<u8>> {
+    let Some(snapshot) = vm.run_blocking(|vm| fusabi_state::snapshot(vm), SNAPSHOT_TIMEOUT) else {
+        log::warn!(
+            "Fusabi plugin '{}' did not hand over its state in time",
+            plugin_name
+        );
+        return None;
+    };
+    if snapshot.is_empty() {
+        return None;
+    }
+    save_state(&snapshot)
+}
+
+/// Load the plugin on `vm` and put the values from `state` back
+async fn restore_vm(
+    vm: &VmWorker,
+    plugin_name: &str,
+    bytecode: Arc<Vec<u8>>,
+    kind: &'static str,
+    state: &[u8],
+    ctx: &PluginContext,
+) -> Result<()> {
+    let snapshot: VmSnapshot = load_state(state)?;
+    let total = snapshot.len();
+    let allow_regex = allows_regex(&ctx.capabilities);
+    let restored = vm
+        .run(move |vm| {
+            ensure_loaded(vm, &bytecode, allow_regex, kind)?;
+            Ok::<_, PluginError>(fusabi_state::restore(vm, &snapshot))
+        })
+        .await??;
+    log::debug!(
+        "Restored {} of {} values for Fusabi plugin '{}'",
+        restored,
+        total,
+        plugin_name
+    );
+    Ok(())
+}
+
+fn vm_stopped() -> PluginError {
+    PluginError::Other(anyhow::anyhow!("Fusabi VM thread has stopped"))
+}
+
+/// What a hook function returned, as it leaves the VM thread
+#[derive(Debug)]
+struct HookReturn {
+    action: Action,
+    /// The returned value, formatted for logs
+    value: String,
+}
+
+impl HookReturn {
+    fn new(value: &Value) -> Self {
+        Self {
+            action: hook_action(value),
+            value: format!("{:?}", value),
+        }
+    }
+}
+
+/// Turn what an `on_output` or `on_input` function returned into an [`Action`]
+///
+/// `false` stops later plugins and a string replaces the data; anything
+/// else, such as `true` or `()`, continues.
+fn hook_action(value: &Value) -> Action {
+    match value {
+        Value::Bool(false) => Action::Stop,
+        Value::Str(text) => Action::Modify(text.as_bytes().to_vec()),
+        _ => Action::Continue,
+    }
+}
+
+/// Error for a failed hook call, naming where the hook is defined if known
+fn hook_error(
+    definitions: Option<&DefinitionMap>,
+    function_name: &str,
+    error: impl std::fmt::Display,
+) -> PluginError {
+    let message = format!("Hook execution failed: {}", error);
+    let message = match definitions {
+        Some(map) => map.annotate(function_name, &message),
+        None => message,
+    };
+    PluginError::Other(anyhow::anyhow!(message))
+}
+
+/// Definition map of a script, taken from the script file itself
+fn script_definitions(path: &Path) -> DefinitionMap {
+    let file = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("script.fsx");
+    DefinitionMap::from_source(file, &std::fs::read_to_string(path).unwrap_or_default())
+}
+
+/// Quote text as a string literal for a generated hook call
+fn string_literal(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
 }
 
 /// Adapter for compiled Fusabi bytecode (.fzb files)
 pub struct FusabiBytecodePlugin {
     metadata: PluginMetadata,
-    bytecode: Vec<u8>,
-    // Note: Vm contains Rc which is !Send, so we can't store it directly
-    // We use thread-local VM_CACHE for VM instances
+    bytecode: Arc<Vec<u8>>,
+    /// Written by scarab-plugin-compiler next to the `.fzb`
+    definitions: Option<Arc<DefinitionMap>>,
+    vm: VmWorker,
 }
 
 impl std::fmt::Debug for FusabiBytecodePlugin {
@@ -44,10 +369,6 @@ impl FusabiBytecodePlugin {
         let bytecode = std::fs::read(path)
             .map_err(|e| PluginError::LoadError(format!("Failed to read bytecode file: {}", e)))?;
 
-        // Validate we can deserialize the bytecode
-        let _ = fusabi_vm::deserialize_chunk(&bytecode)
-            .map_err(|e| PluginError::LoadError(format!("Invalid Fusabi bytecode: {}", e)))?;
-
         // Extract metadata from bytecode
         let plugin_name = path
             .file_stem()
@@ -58,71 +379,97 @@ impl FusabiBytecodePlugin {
         let metadata =
             PluginMetadata::new(plugin_name, "0.1.0", "Fusabi bytecode plugin", "Fusabi VM");
 
-        Ok(Self { metadata, bytecode })
+        Self::from_bytecode(metadata, bytecode, DefinitionMap::load_for(path))
+    }
+
+    /// Load the plugin compiled into a `.scarabpkg`, along with its manifest
+    pub fn load_package(path: &Path) -> Result<(Self, PluginManifest)> {
+        let package = Package::read(path)?;
+        let manifest = package.manifest()?;
+        manifest
+            .validate(scarab_plugin_api::API_VERSION)
+            .map_err(|e| PluginError::InvalidMetadata(e.to_string()))?;
+
+        let bytecode_name = format!("{}.fzb", manifest.name);
+        let bytecode = package
+            .get(&bytecode_name)
+            .ok_or_else(|| PluginError::LoadError(format!("Package has no {}", bytecode_name)))?;
+        let map_name = DefinitionMap::path_for(Path::new(&bytecode_name));
+        let definitions = package
+            .get(&map_name.to_string_lossy())
+            .and_then(|map| serde_json::from_slice(map).ok());
+
+        let mut metadata = PluginMetadata::new(
+            &manifest.name,
+            &manifest.version,
+            &manifest.description,
+            &manifest.author,
+        );
+        metadata.capabilities = manifest.capabilities.clone();
+        let plugin = Self::from_bytecode(metadata, bytecode.to_vec(), definitions)?;
+        Ok((plugin, manifest))
+    }
+
+    fn from_bytecode(
+        metadata: PluginMetadata,
+        bytecode: Vec<u8>,
+        definitions: Option<DefinitionMap>,
+    ) -> Result<Self> {
+        // Validate we can deserialize the bytecode
+        let _ = fusabi_vm::deserialize_chunk(&bytecode)
+            .map_err(|e| PluginError::LoadError(format!("Invalid Fusabi bytecode: {}", e)))?;
+
+        Ok(Self {
+            vm: VmWorker::spawn(&metadata.name)?,
+            metadata,
+            bytecode: Arc::new(bytecode),
+            definitions: definitions.map(Arc::new),
+        })
     }
 
     /// Call a hook function in the bytecode VM
     ///
     /// This function mirrors the implementation of FusabiScriptPlugin::call_hook_function()
     /// but works with pre-compiled bytecode instead of parsing/compiling F# source.
+    /// `args` builds the arguments on the VM thread.
     ///
     /// Returns Ok(None) if the function doesn't exist (not an error)
     /// Returns Ok(Some(value)) if the function exists and was called successfully
-    fn call_hook_function(
+    async fn call_hook_function(
         &self,
         function_name: &str,
-        args: &[Value],
-        _ctx: &PluginContext,
-    ) -> Result<Option<Value>> {
-        // Use thread-local VM to avoid Send issues
-        VM_CACHE.with(|cache| {
-            let mut cache = cache.borrow_mut();
-
-            // Create a new VM if not cached
-            if cache.is_none() {
-                *cache = Some(Vm::new());
-            }
-
-            let vm = cache.as_mut().unwrap();
-
-            // Deserialize and execute the main bytecode chunk to populate globals
-            let chunk = fusabi_vm::deserialize_chunk(&self.bytecode).map_err(|e| {
-                PluginError::Other(anyhow::anyhow!("Deserialization failed: {}", e))
-            })?;
-
-            vm.execute(chunk).map_err(|e| {
-                PluginError::Other(anyhow::anyhow!("Bytecode execution failed: {}", e))
-            })?;
-
-            // Check if the hook function exists in globals
-            if !vm.globals.contains_key(function_name) {
-                log::trace!(
-                    "Hook function '{}' not defined in bytecode plugin '{}'",
-                    function_name,
-                    self.metadata.name
-                );
-                return Ok(None);
-            }
-
-            // Get the function value from globals
-            let func_value = vm
-                .globals
-                .get(function_name)
-                .ok_or_else(|| {
-                    PluginError::Other(anyhow::anyhow!(
-                        "Function '{}' not found in globals",
-                        function_name
-                    ))
-                })?
-                .clone();
-
-            // Call the function with provided arguments
-            let result = vm
-                .call_value(func_value, args)
-                .map_err(|e| PluginError::Other(anyhow::anyhow!("Hook execution failed: {}", e)))?;
-
-            Ok(Some(result))
-        })
+        args: impl FnOnce() -> Vec<Value> + Send + 'static,
+        ctx: &PluginContext,
+    ) -> Result<Option<HookReturn>> {
+        let bytecode = self.bytecode.clone();
+        let definitions = self.definitions.clone();
+        let plugin_name = self.metadata.name.clone();
+        let function_name = function_name.to_string();
+        let allow_regex = allows_regex(&ctx.capabilities);
+
+        self.vm
+            .run(move |vm| {
+                // Run the main bytecode chunk to populate globals
+                ensure_loaded(vm, &bytecode, allow_regex, "Bytecode")?;
+
+                // Get the function value from globals
+                let Some(func_value) = vm.globals.get(&function_name).cloned() else {
+                    log::trace!(
+                        "Hook function '{}' not defined in bytecode plugin '{}'",
+                        function_name,
+                        plugin_name
+                    );
+                    return Ok(None);
+                };
+
+                // Call the function with provided arguments
+                let result = vm
+                    .call_value(func_value, &args())
+                    .map_err(|e| hook_error(definitions.as_deref(), &function_name, e))?;
+
+                Ok(Some(HookReturn::new(&result)))
+            })
+            .await?
     }
 }
 
@@ -140,12 +487,15 @@ impl Plugin for FusabiBytecodePlugin {
 
         // Execute the plugin bytecode and call on_load hook if defined
         // Expected signature: let on_load = fun () -> ()
-        match self.call_hook_function("on_load", &[Value::Unit], ctx) {
+        match self
+            .call_hook_function("on_load", || vec![Value::Unit], ctx)
+            .await
+        {
             Ok(Some(result)) => {
                 log::debug!(
-                    "Bytecode plugin '{}' on_load returned: {:?}",
+                    "Bytecode plugin '{}' on_load returned: {}",
                     self.metadata.name,
-                    result
+                    result.value
                 );
             }
             Ok(None) => {
@@ -170,22 +520,20 @@ impl Plugin for FusabiBytecodePlugin {
 
     async fn on_output(&mut self, line: &str, ctx: &PluginContext) -> Result<Action> {
         // Call the on_output hook if defined
-        // Expected signature: let on_output = fun line -> bool (true = continue, false = suppress)
-        let args = vec![Value::Str(line.to_string())];
+        // Expected signature: let on_output = fun line -> bool | string
+        let line = line.to_string();
 
-        match self.call_hook_function("on_output", &args, ctx) {
+        match self
+            .call_hook_function("on_output", move || vec![Value::Str(line)], ctx)
+            .await
+        {
             Ok(Some(result)) => {
                 log::trace!(
-                    "Bytecode plugin '{}' on_output returned: {:?}",
+                    "Bytecode plugin '{}' on_output returned: {}",
                     self.metadata.name,
-                    result
+                    result.value
                 );
-
-                // Check if result indicates we should suppress output
-                match result {
-                    Value::Bool(false) => Ok(Action::Stop),
-                    _ => Ok(Action::Continue),
-                }
+                Ok(result.action)
             }
             Ok(None) => {
                 log::trace!("Bytecode plugin '{}' processing output", self.metadata.name);
@@ -204,22 +552,20 @@ impl Plugin for FusabiBytecodePlugin {
 
     async fn on_input(&mut self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
         // Call the on_input hook if defined
-        // Expected signature: let on_input = fun bytes_str -> bool
-        let input_str = String::from_utf8_lossy(input);
-        let args = vec![Value::Str(input_str.to_string())];
+        // Expected signature: let on_input = fun bytes_str -> bool | string
+        let input_str = String::from_utf8_lossy(input).into_owned();
 
-        match self.call_hook_function("on_input", &args, ctx) {
+        match self
+            .call_hook_function("on_input", move || vec![Value::Str(input_str)], ctx)
+            .await
+        {
             Ok(Some(result)) => {
                 log::trace!(
-                    "Bytecode plugin '{}' on_input returned: {:?}",
+                    "Bytecode plugin '{}' on_input returned: {}",
                     self.metadata.name,
-                    result
+                    result.value
                 );
-
-                match result {
-                    Value::Bool(false) => Ok(Action::Stop),
-                    _ => Ok(Action::Continue),
-                }
+                Ok(result.action)
             }
             Ok(None) => {
                 log::trace!(
@@ -243,14 +589,14 @@ impl Plugin for FusabiBytecodePlugin {
     async fn on_resize(&mut self, cols: u16, rows: u16, ctx: &PluginContext) -> Result<()> {
         // Call the on_resize hook if defined
         // Expected signature: let on_resize = fun cols rows -> ()
-        let args = vec![Value::Int(cols as i64), Value::Int(rows as i64)];
+        let args = move || vec![Value::Int(cols as i64), Value::Int(rows as i64)];
 
-        match self.call_hook_function("on_resize", &args, ctx) {
+        match self.call_hook_function("on_resize", args, ctx).await {
             Ok(Some(result)) => {
                 log::trace!(
-                    "Bytecode plugin '{}' on_resize returned: {:?}",
+                    "Bytecode plugin '{}' on_resize returned: {}",
                     self.metadata.name,
-                    result
+                    result.value
                 );
             }
             Ok(None) => {
@@ -273,13 +619,40 @@ impl Plugin for FusabiBytecodePlugin {
         Ok(())
     }
 
+    async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
+        // Call the on_remote_command hook if defined
+        // Expected signature: let on_remote_command = fun id -> ()
+        let id = id.to_string();
+
+        match self
+            .call_hook_function("on_remote_command", move || vec![Value::Str(id)], ctx)
+            .await
+        {
+            Ok(Some(result)) => {
+                log::trace!(
+                    "Bytecode plugin '{}' on_remote_command returned: {}",
+                    self.metadata.name,
+                    result.value
+                );
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(e) => {
+                log::warn!(
+                    "Bytecode plugin '{}' on_remote_command hook failed: {}",
+                    self.metadata.name,
+                    e
+                );
+                Err(e)
+            }
+        }
+    }
+
     async fn on_unload(&mut self) -> Result<()> {
         log::info!("Unloading Fusabi bytecode plugin: {}", self.metadata.name);
 
-        // Clear VM cache to release resources
-        VM_CACHE.with(|cache| {
-            *cache.borrow_mut() = None;
-        });
+        // Release the VM's globals
+        self.vm.reset();
 
         // Note: We can't call the on_unload hook here because we don't have access to ctx
         // The Plugin trait's on_unload doesn't provide a context parameter
@@ -287,6 +660,23 @@ impl Plugin for FusabiBytecodePlugin {
 
         Ok(())
     }
+
+    fn snapshot_state(&self) -> Option<Vec<u8>> {
+        snapshot_vm(&self.vm, &self.metadata.name)
+    }
+
+    async fn restore_state(&mut self, state: &[u8], ctx: &PluginContext) -> Result<()> {
+        let bytecode = self.bytecode.clone();
+        restore_vm(
+            &self.vm,
+            &self.metadata.name,
+            bytecode,
+            "Bytecode",
+            state,
+            ctx,
+        )
+        .await
+    }
 }
 
 /// Adapter for Fusabi script files (.fsx files)
@@ -301,27 +691,36 @@ pub struct FusabiScriptPlugin {
     #[allow(dead_code)]
     script_path: std::path::PathBuf,
     /// Compiled bytecode (serialized, Send-safe)
-    bytecode: Option<Vec<u8>>,
+    bytecode: Option<Arc<Vec<u8>>>,
+    /// Palette commands declared with `// @command:` comments
+    commands: Vec<ModalItem>,
+    definitions: Arc<DefinitionMap>,
+    vm: VmWorker,
 }
 
 impl FusabiScriptPlugin {
     /// Load a .fsx script file
     pub fn load(path: &Path) -> Result<Self> {
-        // Read script file
-        let script_source = std::fs::read_to_string(path)
-            .map_err(|e| PluginError::LoadError(format!("Failed to read script file: {}", e)))?;
+        // Read the script and the files it `#load`s
+        let script_source = resolve_source(path)
+            .map_err(|e| PluginError::LoadError(format!("Failed to read script file: {}", e)))?
+            .source;
 
         // Parse and compile the script
         let bytecode = Self::compile_script(&script_source)?;
 
         // Extract metadata from script comments
         let metadata = Self::extract_metadata(path, &script_source);
+        let commands = Self::extract_commands(&script_source);
 
         Ok(Self {
+            vm: VmWorker::spawn(&metadata.name)?,
             metadata,
             script_source,
             script_path: path.to_path_buf(),
-            bytecode: Some(bytecode),
+            bytecode: Some(Arc::new(bytecode)),
+            commands,
+            definitions: Arc::new(script_definitions(path)),
         })
     }
 
@@ -384,97 +783,116 @@ impl FusabiScriptPlugin {
         PluginMetadata::new(name, version, description, author)
     }
 
+    /// Extract palette commands from script comments
+    ///
+    /// Each `// @command: id | Label | Optional description` comment adds a
+    /// command; choosing it calls `on_remote_command` with the ID.
+    fn extract_commands(source: &str) -> Vec<ModalItem> {
+        source
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                let rest = line
+                    .strip_prefix("//")
+                    .or_else(|| line.strip_prefix("(*"))?;
+                let value = rest.trim().strip_prefix("@command:")?;
+                let mut parts = value.split('|').map(str::trim);
+                let id = parts.next().filter(|id| !id.is_empty())?;
+                let label = parts.next().filter(|label| !label.is_empty()).unwrap_or(id);
+                let description = parts.next().map(str::to_string);
+                Some(ModalItem {
+                    id: id.to_string(),
+                    label: label.to_string(),
+                    description,
+                })
+            })
+            .collect()
+    }
+
     /// Create a VM with the compiled script and call a hook function
     ///
     /// Returns Ok(None) if the function doesn't exist (not an error)
     /// Returns Ok(Some(value)) if the function exists and was called successfully
-    fn call_hook_function(
+    async fn call_hook_function(
         &self,
         function_name: &str,
         args_source: &str,
-        _ctx: &PluginContext,
-    ) -> Result<Option<Value>> {
-        // Use thread-local VM to avoid Send issues
-        VM_CACHE.with(|cache| {
-            let mut cache = cache.borrow_mut();
-
-            // Create a new VM if not cached
-            if cache.is_none() {
-                *cache = Some(Vm::new());
-            }
-
-            let vm = cache.as_mut().unwrap();
-
-            // Deserialize and execute the main script chunk to populate globals
-            let bytecode = self
-                .bytecode
-                .as_ref()
-                .ok_or_else(|| PluginError::LoadError("Bytecode not compiled".to_string()))?;
-
-            let chunk = fusabi_vm::deserialize_chunk(bytecode).map_err(|e| {
-                PluginError::Other(anyhow::anyhow!("Deserialization failed: {}", e))
-            })?;
-
-            vm.execute(chunk).map_err(|e| {
-                PluginError::Other(anyhow::anyhow!("Script execution failed: {}", e))
-            })?;
-
-            // Check if the hook function exists in globals
-            if !vm.globals.contains_key(function_name) {
-                log::trace!(
-                    "Hook function '{}' not defined in plugin '{}'",
-                    function_name,
-                    self.metadata.name
-                );
-                return Ok(None);
-            }
-
-            // Build and compile the function call expression
-            let call_source = format!("{} {}", function_name, args_source);
-
-            let mut lexer = Lexer::new(&call_source);
-            let tokens = lexer.tokenize().map_err(|e| {
-                PluginError::Other(anyhow::anyhow!("Failed to tokenize call: {}", e))
-            })?;
-
-            let mut parser = Parser::new(tokens);
-            let expr = parser
-                .parse()
-                .map_err(|e| PluginError::Other(anyhow::anyhow!("Failed to parse call: {}", e)))?;
-
-            let call_chunk = Compiler::compile(&expr).map_err(|e| {
-                PluginError::Other(anyhow::anyhow!("Failed to compile call: {}", e))
-            })?;
-
-            // Execute the call
-            let result = vm
-                .execute(call_chunk)
-                .map_err(|e| PluginError::Other(anyhow::anyhow!("Hook execution failed: {}", e)))?;
+        ctx: &PluginContext,
+    ) -> Result<Option<HookReturn>> {
+        let bytecode = self
+            .bytecode
+            .clone()
+            .ok_or_else(|| PluginError::LoadError("Bytecode not compiled".to_string()))?;
+        let definitions = self.definitions.clone();
+        let plugin_name = self.metadata.name.clone();
+        let function_name = function_name.to_string();
+        // Build the function call expression
+        let call_source = format!("{} {}", function_name, args_source);
+        let allow_regex = allows_regex(&ctx.capabilities);
+
+        self.vm
+            .run(move |vm| {
+                // Run the main script chunk to populate globals
+                ensure_loaded(vm, &bytecode, allow_regex, "Script")?;
+
+                // Check if the hook function exists in globals
+                if !vm.globals.contains_key(&function_name) {
+                    log::trace!(
+                        "Hook function '{}' not defined in plugin '{}'",
+                        function_name,
+                        plugin_name
+                    );
+                    return Ok(None);
+                }
 
-            Ok(Some(result))
-        })
+                // Compile the function call expression
+                let mut lexer = Lexer::new(&call_source);
+                let tokens = lexer.tokenize().map_err(|e| {
+                    PluginError::Other(anyhow::anyhow!("Failed to tokenize call: {}", e))
+                })?;
+
+                let mut parser = Parser::new(tokens);
+                let expr = parser.parse().map_err(|e| {
+                    PluginError::Other(anyhow::anyhow!("Failed to parse call: {}", e))
+                })?;
+
+                let call_chunk = Compiler::compile(&expr).map_err(|e| {
+                    PluginError::Other(anyhow::anyhow!("Failed to compile call: {}", e))
+                })?;
+
+                // Execute the call
+                let result = vm
+                    .execute(call_chunk)
+                    .map_err(|e| hook_error(Some(&*definitions), &function_name, e))?;
+
+                Ok(Some(HookReturn::new(&result)))
+            })
+            .await?
     }
 
     /// Hot-reload the script from disk
     pub fn reload(&mut self, path: &Path) -> Result<()> {
-        let script_source = std::fs::read_to_string(path)
-            .map_err(|e| PluginError::LoadError(format!("Failed to reload script: {}", e)))?;
+        let script_source = resolve_source(path)
+            .map_err(|e| PluginError::LoadError(format!("Failed to reload script: {}", e)))?
+            .source;
 
         // Compile the new script
         let bytecode = Self::compile_script(&script_source)?;
 
         // Extract updated metadata
         let metadata = Self::extract_metadata(path, &script_source);
+        let commands = Self::extract_commands(&script_source);
 
         // Update state
         self.script_source = script_source;
-        self.bytecode = Some(bytecode);
+        self.bytecode = Some(Arc::new(bytecode));
         self.metadata = metadata;
+        self.commands = commands;
+        self.definitions = Arc::new(script_definitions(path));
 
-        // Clear VM cache to force reload on next call
-        VM_CACHE.with(|cache| {
-            *cache.borrow_mut() = None;
-        });
+        // Start over on a fresh VM, cancelling the old one and any hook
+        // stuck on it
+        self.vm = VmWorker::spawn(&self.metadata.name)?;
 
         log::info!("Hot-reloaded script plugin: {}", self.metadata.name);
 
@@ -487,6 +905,10 @@ impl Plugin for FusabiScriptPlugin {
         &self.metadata
     }
 
+    fn get_commands(&self) -> Vec<ModalItem> {
+        self.commands.clone()
+    }
+
     async fn on_load(&mut self, ctx: &mut PluginContext) -> Result<()> {
         ctx.log(
             scarab_plugin_api::context::LogLevel::Info,
@@ -495,12 +917,12 @@ impl Plugin for FusabiScriptPlugin {
 
         // Call the on_load hook if defined
         // Expected signature: let on_load = fun _u -> ()
-        match self.call_hook_function("on_load", "()", ctx) {
+        match self.call_hook_function("on_load", "()", ctx).await {
             Ok(Some(result)) => {
                 log::debug!(
-                    "Plugin '{}' on_load returned: {:?}",
+                    "Plugin '{}' on_load returned: {}",
                     self.metadata.name,
-                    result
+                    result.value
                 );
             }
             Ok(None) => {
@@ -517,23 +939,17 @@ impl Plugin for FusabiScriptPlugin {
 
     async fn on_output(&mut self, line: &str, ctx: &PluginContext) -> Result<Action> {
         // Call the on_output hook if defined
-        // Expected signature: let on_output = fun line -> bool (true = continue, false = suppress)
-        let escaped_line = line.replace('\\', "\\\\").replace('"', "\\\"");
-        let args = format!("\"{}\"", escaped_line);
+        // Expected signature: let on_output = fun line -> bool | string
+        let args = string_literal(line);
 
-        match self.call_hook_function("on_output", &args, ctx) {
+        match self.call_hook_function("on_output", &args, ctx).await {
             Ok(Some(result)) => {
                 log::trace!(
-                    "Plugin '{}' on_output returned: {:?}",
+                    "Plugin '{}' on_output returned: {}",
                     self.metadata.name,
-                    result
+                    result.value
                 );
-
-                // Check if result indicates we should suppress output
-                match result {
-                    Value::Bool(false) => Ok(Action::Stop),
-                    _ => Ok(Action::Continue),
-                }
+                Ok(result.action)
             }
             Ok(None) => Ok(Action::Continue),
             Err(e) => {
@@ -549,23 +965,17 @@ impl Plugin for FusabiScriptPlugin {
 
     async fn on_input(&mut self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
         // Call the on_input hook if defined
-        // Expected signature: let on_input = fun bytes -> bool
-        let input_str = String::from_utf8_lossy(input);
-        let escaped_input = input_str.replace('\\', "\\\\").replace('"', "\\\"");
-        let args = format!("\"{}\"", escaped_input);
+        // Expected signature: let on_input = fun bytes -> bool | string
+        let args = string_literal(&String::from_utf8_lossy(input));
 
-        match self.call_hook_function("on_input", &args, ctx) {
+        match self.call_hook_function("on_input", &args, ctx).await {
             Ok(Some(result)) => {
                 log::trace!(
-                    "Plugin '{}' on_input returned: {:?}",
+                    "Plugin '{}' on_input returned: {}",
                     self.metadata.name,
-                    result
+                    result.value
                 );
-
-                match result {
-                    Value::Bool(false) => Ok(Action::Stop),
-                    _ => Ok(Action::Continue),
-                }
+                Ok(result.action)
             }
             Ok(None) => Ok(Action::Continue),
             Err(e) => {
@@ -584,12 +994,12 @@ impl Plugin for FusabiScriptPlugin {
         // Expected signature: let on_resize = fun cols rows -> ()
         let args = format!("{} {}", cols, rows);
 
-        match self.call_hook_function("on_resize", &args, ctx) {
+        match self.call_hook_function("on_resize", &args, ctx).await {
             Ok(Some(result)) => {
                 log::trace!(
-                    "Plugin '{}' on_resize returned: {:?}",
+                    "Plugin '{}' on_resize returned: {}",
                     self.metadata.name,
-                    result
+                    result.value
                 );
             }
             Ok(None) => {}
@@ -605,24 +1015,70 @@ impl Plugin for FusabiScriptPlugin {
         Ok(())
     }
 
+    async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
+        // Call the on_remote_command hook if defined
+        // Expected signature: let on_remote_command = fun id -> ()
+        match self
+            .call_hook_function("on_remote_command", &string_literal(id), ctx)
+            .await
+        {
+            Ok(Some(result)) => {
+                log::trace!(
+                    "Plugin '{}' on_remote_command returned: {}",
+                    self.metadata.name,
+                    result.value
+                );
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(e) => {
+                log::warn!(
+                    "Plugin '{}' on_remote_command hook failed: {}",
+                    self.metadata.name,
+                    e
+                );
+                Err(e)
+            }
+        }
+    }
+
     async fn on_unload(&mut self) -> Result<()> {
         log::info!("Unloading Fusabi script plugin: {}", self.metadata.name);
 
-        // Clear VM cache
-        VM_CACHE.with(|cache| {
-            *cache.borrow_mut() = None;
-        });
+        // Release the VM's globals
+        self.vm.reset();
 
         // Note: We can't call the on_unload hook here because we don't have access to ctx
         // The Plugin trait's on_unload doesn't provide a context parameter
 
         Ok(())
     }
+
+    fn snapshot_state(&self) -> Option<Vec<u8>> {
+        snapshot_vm(&self.vm, &self.metadata.name)
+    }
+
+    async fn restore_state(&mut self, state: &[u8], ctx: &PluginContext) -> Result<()> {
+        let bytecode = self
+            .bytecode
+            .clone()
+            .ok_or_else(|| PluginError::LoadError("Bytecode not compiled".to_string()))?;
+        restore_vm(
+            &self.vm,
+            &self.metadata.name,
+            bytecode,
+            "Script",
+            state,
+            ctx,
+        )
+        .await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use scarab_plugin_api::Capability;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -658,6 +1114,46 @@ mod tests {
         assert!(plugin.metadata.name.len() > 0);
     }
 
+    #[test]
+    fn test_load_package() {
+        use fusabi_vm::ChunkBuilder;
+
+        let manifest = r#"
+name = "weather"
+version = "0.2.0"
+description = "Forecasts"
+author = "Someone"
+api-version = "0.1.0"
+min-scarab-version = "0.1.0"
+capabilities = ["network"]
+allowed-hosts = ["api.weather.example"]
+
+[build]
+entry = "src/main.fsx"
+"#;
+        let mut package = Package::new();
+        package
+            .add("plugin.toml", manifest.as_bytes().to_vec())
+            .unwrap();
+        let mut temp_file = NamedTempFile::new().unwrap();
+        package.write(temp_file.path()).unwrap();
+        // No bytecode for the manifest's name
+        assert!(FusabiBytecodePlugin::load_package(temp_file.path()).is_err());
+
+        let chunk = ChunkBuilder::new().build();
+        package
+            .add("weather.fzb", fusabi_vm::serialize_chunk(&chunk).unwrap())
+            .unwrap();
+        package.write(temp_file.path()).unwrap();
+        temp_file.flush().unwrap();
+
+        let (plugin, manifest) = FusabiBytecodePlugin::load_package(temp_file.path()).unwrap();
+        assert_eq!(plugin.metadata.name, "weather");
+        assert_eq!(plugin.metadata.version, "0.2.0");
+        assert!(plugin.metadata.capabilities.contains(&Capability::Network));
+        assert_eq!(manifest.allowed_hosts, ["api.weather.example"]);
+    }
+
     #[test]
     fn test_load_script() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -767,6 +1263,95 @@ mod tests {
         assert_eq!(action, Action::Continue);
     }
 
+    #[tokio::test]
+    async fn test_script_output_replacement_and_commands() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file
+            .write_all(
+                b"// @command: fusabi.greet | Say hello | Prints a greeting\n// @command: fusabi.bare\nlet on_output = fun line -> \"[build] ok\"\nlet on_input = fun data -> false\nlet on_remote_command = fun id -> ()",
+            )
+            .unwrap();
+        temp_file.flush().unwrap();
+
+        let mut plugin = FusabiScriptPlugin::load(temp_file.path()).unwrap();
+        let state = std::sync::Arc::new(parking_lot::Mutex::new(
+            scarab_plugin_api::context::PluginSharedState::new(80, 24),
+        ));
+        let ctx = PluginContext::new(Default::default(), state, "test");
+
+        let commands = plugin.get_commands();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].id, "fusabi.greet");
+        assert_eq!(commands[0].label, "Say hello");
+        assert_eq!(
+            commands[0].description.as_deref(),
+            Some("Prints a greeting")
+        );
+        assert_eq!(commands[1].label, "fusabi.bare");
+
+        assert_eq!(
+            plugin.on_output("ok", &ctx).await.unwrap(),
+            Action::Modify(b"[build] ok".to_vec())
+        );
+        assert_eq!(plugin.on_input(b"q", &ctx).await.unwrap(), Action::Stop);
+        assert!(plugin.on_remote_command("fusabi.greet", &ctx).await.is_ok());
+    }
+
+    #[test]
+    fn test_hook_action_and_literals() {
+        assert_eq!(hook_action(&Value::Bool(true)), Action::Continue);
+        assert_eq!(hook_action(&Value::Unit), Action::Continue);
+        assert_eq!(hook_action(&Value::Bool(false)), Action::Stop);
+        assert_eq!(string_literal(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_hook_does_not_block_runtime() {
+        let worker = VmWorker::spawn("stuck").unwrap();
+        let (release, stuck) = mpsc::channel::<()>();
+
+        // A hook that never returns only times out its own call
+        let call = worker.run(move |_vm| {
+            let _ = stuck.recv();
+        });
+        let waited = tokio::time::timeout(std::time::Duration::from_millis(50), call).await;
+        assert!(waited.is_err());
+
+        release.send(()).unwrap();
+        assert_eq!(worker.run(|_vm| 7).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_and_cancelled_jobs_are_skipped() {
+        let short = std::time::Duration::from_millis(20);
+        let mut worker = VmWorker::spawn("cancel").unwrap();
+        let (release, stuck) = mpsc::channel::<()>();
+        let ran = Arc::new(AtomicBool::new(false));
+
+        let call = worker.run(move |_vm| {
+            let _ = stuck.recv();
+        });
+        assert!(tokio::time::timeout(short, call).await.is_err());
+
+        // Timed out while queued behind the stuck hook: never runs
+        let flag = ran.clone();
+        let queued = worker.run(move |_vm| flag.store(true, Ordering::SeqCst));
+        assert!(tokio::time::timeout(short, queued).await.is_err());
+
+        // Queued when the worker is dropped: never runs either
+        let flag = ran.clone();
+        worker
+            .jobs
+            .send(Box::new(move |_vm| flag.store(true, Ordering::SeqCst)))
+            .unwrap();
+        let thread = worker.thread.take().unwrap();
+        drop(worker);
+
+        release.send(()).unwrap();
+        thread.join().unwrap();
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn test_script_hot_reload() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -783,6 +1368,57 @@ mod tests {
         assert!(plugin.reload(temp_file.path()).is_ok());
     }
 
+    #[tokio::test]
+    async fn test_script_state_survives_reload() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file
+            .write_all(b"let count = 0\nlet greeting = \"hi\"\nlet on_output = fun line -> true")
+            .unwrap();
+        temp_file.flush().unwrap();
+
+        let plugin = FusabiScriptPlugin::load(temp_file.path()).unwrap();
+        let state = std::sync::Arc::new(parking_lot::Mutex::new(
+            scarab_plugin_api::context::PluginSharedState::new(80, 24),
+        ));
+        let ctx = PluginContext::new(Default::default(), state, "test");
+
+        // Nothing to keep before the script has run
+        assert!(plugin.snapshot_state().is_none());
+        plugin.on_output("x", &ctx).await.unwrap();
+
+        // Stand in for values the plugin changed while running
+        plugin
+            .vm
+            .run(|vm| {
+                vm.globals.insert("count".to_string(), Value::Int(5));
+            })
+            .await
+            .unwrap();
+        let snapshot = plugin.snapshot_state().unwrap();
+        assert_eq!(plugin.snapshot_state().unwrap(), snapshot);
+
+        // `greeting` is now a number, so it keeps its new value
+        std::fs::write(
+            temp_file.path(),
+            "let count = 0\nlet greeting = 1\nlet on_output = fun line -> true",
+        )
+        .unwrap();
+        let mut reloaded = FusabiScriptPlugin::load(temp_file.path()).unwrap();
+        reloaded.restore_state(&snapshot, &ctx).await.unwrap();
+        let (count, greeting) = reloaded
+            .vm
+            .run(|vm| {
+                (
+                    format!("{:?}", vm.globals.get("count")),
+                    format!("{:?}", vm.globals.get("greeting")),
+                )
+            })
+            .await
+            .unwrap();
+        assert_eq!(count, format!("{:?}", Some(Value::Int(5))));
+        assert_eq!(greeting, format!("{:?}", Some(Value::Int(1))));
+    }
+
     #[test]
     fn test_parse_error_handling() {
         let mut temp_file = NamedTempFile::new().unwrap();
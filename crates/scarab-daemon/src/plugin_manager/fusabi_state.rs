@@ -0,0 +1,187 @@
+//! Snapshots of a Fusabi plugin's top-level values across hot reloads
+//!
+//! A snapshot holds the plugin's own globals that are plain data: `()`,
+//! booleans, numbers, strings, and tuples, records and maps of those.
+//! Functions are left out, since the reloaded code defines them again, as
+//! are the VM's own globals and names starting with `__`. Maps are kept
+//! sorted, so the same state always encodes to the same bytes.
+//!
+//! On restore a value only replaces the freshly loaded one when the new
+//! code still binds that name to the same kind of value. A binding that
+//! was renamed, or changed from a number to a string, starts over from its
+//! new definition.
+
+use fusabi_vm::{Value, Vm};
+use scarab_plugin_api::fusabi_stdlib::register_stdlib_ext;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// A plugin's data globals by name
+pub type VmSnapshot = BTreeMap<String, StateValue>;
+
+/// A Fusabi value that can outlive its VM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum StateValue {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Tuple(Vec<StateValue>),
+    Record(BTreeMap<String, StateValue>),
+    Map(BTreeMap<String, StateValue>),
+}
+
+impl StateValue {
+    /// `value` as state, or `None` if it is or holds a function
+    pub fn from_value(value: &Value) -> Option<Self> {
+        Some(match value {
+            Value::Unit => Self::Unit,
+            Value::Bool(b) => Self::Bool(*b),
+            Value::Int(i) => Self::Int(*i),
+            // JSON has no NaN or infinity
+            Value::Float(f) if f.is_finite() => Self::Float(*f),
+            Value::Str(s) => Self::Str(s.clone()),
+            Value::Tuple(items) => {
+                Self::Tuple(items.iter().map(Self::from_value).collect::<Option<_>>()?)
+            }
+            Value::Record(fields) => Self::Record(fields_state(&fields.lock().unwrap())?),
+            Value::Map(fields) => Self::Map(fields_state(&fields.lock().unwrap())?),
+            _ => return None,
+        })
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Unit => Value::Unit,
+            Self::Bool(b) => Value::Bool(*b),
+            Self::Int(i) => Value::Int(*i),
+            Self::Float(f) => Value::Float(*f),
+            Self::Str(s) => Value::Str(s.clone()),
+            Self::Tuple(items) => Value::Tuple(items.iter().map(Self::to_value).collect()),
+            Self::Record(fields) => Value::Record(Arc::new(Mutex::new(fields_value(fields)))),
+            Self::Map(fields) => Value::Map(Arc::new(Mutex::new(fields_value(fields)))),
+        }
+    }
+
+    /// Whether `value` is the same kind of value as this one
+    fn same_kind(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (Self::Unit, Value::Unit)
+                | (Self::Bool(_), Value::Bool(_))
+                | (Self::Int(_), Value::Int(_))
+                | (Self::Float(_), Value::Float(_))
+                | (Self::Str(_), Value::Str(_))
+                | (Self::Tuple(_), Value::Tuple(_))
+                | (Self::Record(_), Value::Record(_))
+                | (Self::Map(_), Value::Map(_))
+        )
+    }
+}
+
+fn fields_state(fields: &HashMap<String, Value>) -> Option<BTreeMap<String, StateValue>> {
+    fields
+        .iter()
+        .map(|(name, value)| Some((name.clone(), StateValue::from_value(value)?)))
+        .collect()
+}
+
+fn fields_value(fields: &BTreeMap<String, StateValue>) -> HashMap<String, Value> {
+    fields
+        .iter()
+        .map(|(name, value)| (name.clone(), value.to_value()))
+        .collect()
+}
+
+/// Globals every plugin VM starts with
+fn builtin_globals() -> HashSet<String> {
+    let mut vm = Vm::new();
+    register_stdlib_ext(&mut vm, true);
+    vm.globals.keys().cloned().collect()
+}
+
+/// The data globals `vm`'s plugin has bound
+pub fn snapshot(vm: &Vm) -> VmSnapshot {
+    let builtins = builtin_globals();
+    vm.globals
+        .iter()
+        .filter(|(name, _)| !name.starts_with("__") && !builtins.contains(*name))
+        .filter_map(|(name, value)| Some((name.clone(), StateValue::from_value(value)?)))
+        .collect()
+}
+
+/// Put `snapshot`'s values back where `vm` binds the same names to the
+/// same kinds of value, returning how many were restored
+pub fn restore(vm: &mut Vm, snapshot: &VmSnapshot) -> usize {
+    let mut restored = 0;
+    for (name, state) in snapshot {
+        let Some(current) = vm.globals.get_mut(name) else {
+            continue;
+        };
+        if state.same_kind(current) {
+            *current = state.to_value();
+            restored += 1;
+        }
+    }
+    restored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fields: &[(&str, Value)]) -> Value {
+        let fields = fields
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        Value::Record(Arc::new(Mutex::new(fields)))
+    }
+
+    #[test]
+    fn test_snapshot_keeps_plugin_data() {
+        let mut vm = Vm::new();
+        register_stdlib_ext(&mut vm, false);
+        vm.globals.insert("count".into(), Value::Int(3));
+        vm.globals.insert(
+            "cache".into(),
+            record(&[
+                ("last", Value::Str("ls".into())),
+                ("hits", Value::Tuple(vec![Value::Int(1), Value::Float(0.5)])),
+            ]),
+        );
+        vm.globals.insert("nan".into(), Value::Float(f64::NAN));
+        vm.globals
+            .insert("__scarab_loaded".into(), Value::Bool(true));
+
+        let snapshot = snapshot(&vm);
+        assert_eq!(snapshot.keys().collect::<Vec<_>>(), vec!["cache", "count"]);
+
+        // Same state, same bytes
+        let encoded = serde_json::to_vec(&snapshot).unwrap();
+        assert_eq!(encoded, serde_json::to_vec(&self::snapshot(&vm)).unwrap());
+        let decoded: VmSnapshot = serde_json::from_slice(&encoded).unwrap();
+        assert_eq!(decoded, snapshot);
+    }
+
+    #[test]
+    fn test_restore_needs_same_name_and_kind() {
+        let mut snapshot = VmSnapshot::new();
+        snapshot.insert("count".into(), StateValue::Int(41));
+        snapshot.insert("label".into(), StateValue::Int(1));
+        snapshot.insert("gone".into(), StateValue::Bool(true));
+
+        let mut vm = Vm::new();
+        vm.globals.insert("count".into(), Value::Int(0));
+        vm.globals
+            .insert("label".into(), Value::Str("fresh".into()));
+
+        assert_eq!(restore(&mut vm, &snapshot), 1);
+        assert!(matches!(vm.globals.get("count"), Some(Value::Int(41))));
+        assert!(matches!(vm.globals.get("label"), Some(Value::Str(s)) if s == "fresh"));
+        assert!(!vm.globals.contains_key("gone"));
+    }
+}
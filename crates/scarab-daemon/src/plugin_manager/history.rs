@@ -0,0 +1,106 @@
+//! Terminal history exposed to plugins
+//!
+//! Bridges [`PluginContext::get_scrollback_range`] and
+//! [`PluginContext::get_command_blocks`] to the scrollback and zone tracker
+//! of the default session's active pane.
+//!
+//! [`PluginContext::get_scrollback_range`]: scarab_plugin_api::PluginContext::get_scrollback_range
+//! [`PluginContext::get_command_blocks`]: scarab_plugin_api::PluginContext::get_command_blocks
+
+use crate::session::{SessionManager, TerminalState};
+use scarab_plugin_api::history::{CommandBlock, TerminalHistory};
+use std::sync::Arc;
+
+/// History of whichever pane is active in the default session
+pub struct SessionHistory {
+    sessions: Arc<SessionManager>,
+}
+
+impl SessionHistory {
+    /// Create a history view over the session manager
+    pub fn new(sessions: Arc<SessionManager>) -> Self {
+        Self { sessions }
+    }
+
+    /// Lines `start..end` with their colors as SGR escapes, stopping at the
+    /// last line
+    pub fn ansi_lines(&self, start: usize, end: usize) -> Vec<String> {
+        self.with_state(|state| {
+            (start..end)
+                .map_while(|line| state.line_ansi(line))
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&TerminalState) -> T) -> Option<T> {
+        let session = self.sessions.get_default_session()?;
+        let state = session.get_active_terminal_state()?;
+        let state = state.read();
+        Some(f(&state))
+    }
+}
+
+impl TerminalHistory for SessionHistory {
+    fn scrollback_len(&self) -> usize {
+        self.with_state(|state| state.scrollback_len()).unwrap_or(0)
+    }
+
+    fn lines(&self, start: usize, end: usize) -> Vec<String> {
+        self.with_state(|state| lines(state, start, end))
+            .unwrap_or_default()
+    }
+
+    fn command_blocks(&self) -> Vec<CommandBlock> {
+        self.with_state(|state| state.zone_tracker.command_blocks().to_vec())
+            .unwrap_or_default()
+    }
+}
+
+/// Text of absolute lines `start..end`, stopping at the last line
+fn lines(state: &TerminalState, start: usize, end: usize) -> Vec<String> {
+    (start..end)
+        .map_while(|line| state.line_text(line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_span_scrollback_and_grid() {
+        let mut state = TerminalState::new(20, 3);
+        for i in 0..5 {
+            state.process_output(format!("line {}\r\n", i).as_bytes());
+        }
+        let scrollback = state.scrollback_len();
+        assert!(scrollback > 0);
+
+        let all = lines(&state, 0, 100);
+        assert_eq!(all.len(), scrollback + 3);
+        assert_eq!(all[0], "line 0");
+        assert_eq!(lines(&state, 4, 5), vec!["line 4".to_string()]);
+        assert!(lines(&state, 100, 200).is_empty());
+    }
+
+    #[test]
+    fn test_block_output_after_scrolling() {
+        let mut state = TerminalState::new(20, 3);
+        state.process_output(
+            b"\x1b]133;A\x07$ \x1b]133;B\x07echo hi\r\n\x1b]133;C\x07hi\r\n\x1b]133;D;0\x07",
+        );
+        // Push the block into scrollback
+        for i in 0..5 {
+            state.process_output(format!("\r\nlater {}", i).as_bytes());
+        }
+        assert!(state.scrollback_len() > 0);
+
+        let block = state.zone_tracker.command_blocks().last().cloned().unwrap();
+        let (start, _) = block.output_bounds().unwrap();
+        assert_eq!(
+            lines(&state, start as usize, start as usize + 1),
+            vec!["hi".to_string()]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Decode PTY-bound input into key presses for `Plugin::on_key`
+//!
+//! Clients send keys as the bytes a terminal would: printable characters,
+//! C0 control bytes for Ctrl+letter, an ESC prefix for Alt, and CSI or SS3
+//! sequences for special keys, including the kitty keyboard protocol's
+//! `CSI <code>;<mods> u` form. Input is decoded here once so every plugin
+//! sees the same `KeyCombo`s instead of parsing escape sequences itself.
+//!
+//! Bytes with no `KeyCode` (most punctuation, non-ASCII text, key releases,
+//! bracketed paste) are kept as undecoded spans so they still reach the PTY
+//! and `on_input`.
+//!
+//! The client only sends `CSI u` for keys a C0 byte can't tell apart, such
+//! as Ctrl+Shift+T or Ctrl+1, and no program in a pane has asked for the
+//! kitty protocol, so [`is_csi_u`] keys no plugin takes are dropped.
+
+use scarab_plugin_api::key_tables::{KeyCode, KeyCombo, KeyModifiers};
+use std::ops::Range;
+
+const ESC: u8 = 0x1B;
+
+/// Bracketed paste start and end markers
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// A span of input, decoded to a key press when it is one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedKey {
+    /// The key, or `None` for bytes that are not a key press
+    pub combo: Option<KeyCombo>,
+    /// Bytes of the input this span covers
+    pub range: Range<usize>,
+}
+
+/// Split input into key presses and undecoded spans, covering every byte
+pub fn decode_keys(input: &[u8]) -> Vec<DecodedKey> {
+    let mut keys = Vec::new();
+    let mut pos = 0;
+    while pos < input.len() {
+        let (combo, len) = decode_one(&input[pos..]);
+        keys.push(DecodedKey {
+            combo,
+            range: pos..pos + len,
+        });
+        pos += len;
+    }
+    keys
+}
+
+/// Whether `bytes` is one kitty keyboard `CSI <code>;<mods> u` key
+pub fn is_csi_u(bytes: &[u8]) -> bool {
+    bytes.len() > 3
+        && bytes.starts_with(b"\x1b[")
+        && bytes.ends_with(b"u")
+        && bytes[2..bytes.len() - 1]
+            .iter()
+            .all(|b| b.is_ascii_digit() || *b == b';' || *b == b':')
+}
+
+/// Decode the key at the start of `input`, returning it and its length
+fn decode_one(input: &[u8]) -> (Option<KeyCombo>, usize) {
+    match input[0] {
+        ESC => decode_escape(input),
+        byte if byte.is_ascii() => (decode_byte(byte), 1),
+        byte => (None, utf8_len(byte).min(input.len())),
+    }
+}
+
+/// A single-byte key
+fn decode_byte(byte: u8) -> Option<KeyCombo> {
+    match byte {
+        b'\r' | b'\n' => Some(KeyCombo::key(KeyCode::Enter)),
+        b'\t' => Some(KeyCombo::key(KeyCode::Tab)),
+        0x7F | 0x08 => Some(KeyCombo::key(KeyCode::Backspace)),
+        ESC => Some(KeyCombo::key(KeyCode::Escape)),
+        0x00 => Some(KeyCombo::ctrl(KeyCode::Space)),
+        0x1F => Some(KeyCombo::ctrl(KeyCode::Slash)),
+        0x01..=0x1A => {
+            char_combo((byte - 1 + b'a') as char).map(|c| with_mods(c, KeyModifiers::CTRL))
+        }
+        _ => char_combo(byte as char),
+    }
+}
+
+/// Key for a printable character, with Shift for capitals
+fn char_combo(c: char) -> Option<KeyCombo> {
+    let key = match c.to_ascii_lowercase() {
+        'a' => KeyCode::KeyA,
+        'b' => KeyCode::KeyB,
+        'c' => KeyCode::KeyC,
+        'd' => KeyCode::KeyD,
+        'e' => KeyCode::KeyE,
+        'f' => KeyCode::KeyF,
+        'g' => KeyCode::KeyG,
+        'h' => KeyCode::KeyH,
+        'i' => KeyCode::KeyI,
+        'j' => KeyCode::KeyJ,
+        'k' => KeyCode::KeyK,
+        'l' => KeyCode::KeyL,
+        'm' => KeyCode::KeyM,
+        'n' => KeyCode::KeyN,
+        'o' => KeyCode::KeyO,
+        'p' => KeyCode::KeyP,
+        'q' => KeyCode::KeyQ,
+        'r' => KeyCode::KeyR,
+        's' => KeyCode::KeyS,
+        't' => KeyCode::KeyT,
+        'u' => KeyCode::KeyU,
+        'v' => KeyCode::KeyV,
+        'w' => KeyCode::KeyW,
+        'x' => KeyCode::KeyX,
+        'y' => KeyCode::KeyY,
+        'z' => KeyCode::KeyZ,
+        '0' => KeyCode::Digit0,
+        '1' => KeyCode::Digit1,
+        '2' => KeyCode::Digit2,
+        '3' => KeyCode::Digit3,
+        '4' => KeyCode::Digit4,
+        '5' => KeyCode::Digit5,
+        '6' => KeyCode::Digit6,
+        '7' => KeyCode::Digit7,
+        '8' => KeyCode::Digit8,
+        '9' => KeyCode::Digit9,
+        ' ' => KeyCode::Space,
+        '/' => KeyCode::Slash,
+        _ => return None,
+    };
+    if c.is_ascii_uppercase() {
+        Some(KeyCombo::shift(key))
+    } else {
+        Some(KeyCombo::key(key))
+    }
+}
+
+fn with_mods(mut combo: KeyCombo, mods: KeyModifiers) -> KeyCombo {
+    combo.mods |= mods;
+    combo
+}
+
+/// Decode input starting with ESC
+fn decode_escape(input: &[u8]) -> (Option<KeyCombo>, usize) {
+    match input.get(1) {
+        None => (Some(KeyCombo::key(KeyCode::Escape)), 1),
+        Some(b'[') if input.starts_with(PASTE_START) => {
+            // Pasted text is not typed keys
+            let len = find(&input[PASTE_START.len()..], PASTE_END)
+                .map_or(input.len(), |end| PASTE_START.len() + end + PASTE_END.len());
+            (None, len)
+        }
+        Some(b'[') => decode_csi(input),
+        Some(b'O') if input.len() > 2 => (ss3_key(input[2]).map(KeyCombo::key), 3),
+        Some(&ESC) => (Some(KeyCombo::key(KeyCode::Escape)), 1),
+        // ESC before a key is Alt+key
+        Some(_) => match decode_one(&input[1..]) {
+            (Some(combo), len) => (Some(with_mods(combo, KeyModifiers::ALT)), len + 1),
+            (None, len) => (None, len + 1),
+        },
+    }
+}
+
+/// Key for the final byte of an SS3 or unparameterized CSI sequence
+fn ss3_key(byte: u8) -> Option<KeyCode> {
+    Some(match byte {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F1,
+        b'Q' => KeyCode::F2,
+        b'R' => KeyCode::F3,
+        b'S' => KeyCode::F4,
+        _ => return None,
+    })
+}
+
+/// Decode `ESC [ params final`
+fn decode_csi(input: &[u8]) -> (Option<KeyCombo>, usize) {
+    // Parameter and intermediate bytes, then a final byte in 0x40..=0x7E
+    let Some(final_pos) = input[2..]
+        .iter()
+        .position(|b| !(0x20..=0x3F).contains(b))
+        .map(|i| i + 2)
+    else {
+        return (None, input.len());
+    };
+    let len = final_pos + 1;
+    let final_byte = input[final_pos];
+    if !(0x40..=0x7E).contains(&final_byte) {
+        return (None, final_pos);
+    }
+
+    let params = std::str::from_utf8(&input[2..final_pos]).unwrap_or("");
+    let mut fields = params.split(';');
+    // Sub-parameters after ':' (kitty alternate keys and event types)
+    let mut first = fields.next().unwrap_or("").split(':');
+    let code: Option<u32> = first.next().and_then(|s| s.parse().ok());
+    let mut mod_field = fields.next().unwrap_or("").split(':');
+    let mods = mod_field
+        .next()
+        .and_then(|s| s.parse().ok())
+        .map_or(KeyModifiers::NONE, csi_modifiers);
+    // Kitty event type 3 is a release
+    if mod_field.next() == Some("3") {
+        return (None, len);
+    }
+
+    let combo = match (final_byte, code) {
+        (b'u', Some(code)) => csi_u_key(code),
+        (b'~', Some(code)) => tilde_key(code).map(KeyCombo::key),
+        (b'Z', _) => Some(KeyCombo::shift(KeyCode::Tab)),
+        (byte, None | Some(1)) => ss3_key(byte).map(KeyCombo::key),
+        _ => None,
+    };
+    (combo.map(|c| with_mods(c, mods)), len)
+}
+
+/// Modifiers from an xterm/kitty modifier parameter (1 + bitmask)
+fn csi_modifiers(param: u8) -> KeyModifiers {
+    let bits = param.saturating_sub(1);
+    let mut mods = KeyModifiers::NONE;
+    if bits & 1 != 0 {
+        mods |= KeyModifiers::SHIFT;
+    }
+    if bits & 2 != 0 {
+        mods |= KeyModifiers::ALT;
+    }
+    if bits & 4 != 0 {
+        mods |= KeyModifiers::CTRL;
+    }
+    if bits & 8 != 0 {
+        mods |= KeyModifiers::SUPER;
+    }
+    mods
+}
+
+/// Key for a kitty `CSI <code> u` Unicode key code
+fn csi_u_key(code: u32) -> Option<KeyCombo> {
+    match code {
+        9 => Some(KeyCombo::key(KeyCode::Tab)),
+        13 => Some(KeyCombo::key(KeyCode::Enter)),
+        27 => Some(KeyCombo::key(KeyCode::Escape)),
+        127 => Some(KeyCombo::key(KeyCode::Backspace)),
+        _ => char::from_u32(code).and_then(char_combo),
+    }
+}
+
+/// Key for a `CSI <code> ~` sequence
+fn tilde_key(code: u32) -> Option<KeyCode> {
+    Some(match code {
+        1 | 7 => KeyCode::Home,
+        2 => KeyCode::Insert,
+        3 => KeyCode::Delete,
+        4 | 8 => KeyCode::End,
+        5 => KeyCode::PageUp,
+        6 => KeyCode::PageDown,
+        11 => KeyCode::F1,
+        12 => KeyCode::F2,
+        13 => KeyCode::F3,
+        14 => KeyCode::F4,
+        15 => KeyCode::F5,
+        17 => KeyCode::F6,
+        18 => KeyCode::F7,
+        19 => KeyCode::F8,
+        20 => KeyCode::F9,
+        21 => KeyCode::F10,
+        23 => KeyCode::F11,
+        24 => KeyCode::F12,
+        _ => return None,
+    })
+}
+
+/// Length of a UTF-8 sequence from its lead byte
+fn utf8_len(lead: u8) -> usize {
+    match lead {
+        0xF0..=0xF7 => 4,
+        0xE0..=0xEF => 3,
+        0xC0..=0xDF => 2,
+        _ => 1,
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combos(input: &[u8]) -> Vec<Option<KeyCombo>> {
+        decode_keys(input).into_iter().map(|k| k.combo).collect()
+    }
+
+    fn one(input: &[u8]) -> Option<KeyCombo> {
+        let keys = decode_keys(input);
+        assert_eq!(keys.len(), 1, "{:?} decoded to {:?}", input, keys);
+        keys[0].combo.clone()
+    }
+
+    #[test]
+    fn test_plain_and_control_bytes() {
+        assert_eq!(
+            combos(b"aB/"),
+            vec![
+                Some(KeyCombo::key(KeyCode::KeyA)),
+                Some(KeyCombo::shift(KeyCode::KeyB)),
+                Some(KeyCombo::key(KeyCode::Slash)),
+            ]
+        );
+        assert_eq!(one(b"\x03"), Some(KeyCombo::ctrl(KeyCode::KeyC)));
+        assert_eq!(one(b"\r"), Some(KeyCombo::key(KeyCode::Enter)));
+        assert_eq!(one(b"\t"), Some(KeyCombo::key(KeyCode::Tab)));
+        assert_eq!(one(b"\x7f"), Some(KeyCombo::key(KeyCode::Backspace)));
+        assert_eq!(one(b"\x1b"), Some(KeyCombo::key(KeyCode::Escape)));
+        assert_eq!(one(b"."), None);
+        assert_eq!(one("é".as_bytes()), None);
+    }
+
+    #[test]
+    fn test_alt_prefix() {
+        assert_eq!(one(b"\x1bx"), Some(KeyCombo::alt(KeyCode::KeyX)));
+        assert_eq!(
+            one(b"\x1b\x01"),
+            Some(KeyCombo::new(
+                KeyCode::KeyA,
+                KeyModifiers::CTRL | KeyModifiers::ALT
+            ))
+        );
+    }
+
+    #[test]
+    fn test_csi_and_ss3() {
+        assert_eq!(one(b"\x1b[A"), Some(KeyCombo::key(KeyCode::Up)));
+        assert_eq!(one(b"\x1bOP"), Some(KeyCombo::key(KeyCode::F1)));
+        assert_eq!(one(b"\x1b[3~"), Some(KeyCombo::key(KeyCode::Delete)));
+        assert_eq!(one(b"\x1b[24~"), Some(KeyCombo::key(KeyCode::F12)));
+        assert_eq!(one(b"\x1b[Z"), Some(KeyCombo::shift(KeyCode::Tab)));
+        assert_eq!(one(b"\x1b[1;5D"), Some(KeyCombo::ctrl(KeyCode::Left)));
+        assert_eq!(one(b"\x1b[5;3~"), Some(KeyCombo::alt(KeyCode::PageUp)));
+        // Unknown sequences are kept whole
+        assert_eq!(one(b"\x1b[?1049h"), None);
+    }
+
+    #[test]
+    fn test_csi_u() {
+        assert_eq!(one(b"\x1b[97;5u"), Some(KeyCombo::ctrl(KeyCode::KeyA)));
+        assert_eq!(one(b"\x1b[13;2u"), Some(KeyCombo::shift(KeyCode::Enter)));
+        assert_eq!(
+            one(b"\x1b[105;9u"),
+            Some(KeyCombo::super_key(KeyCode::KeyI))
+        );
+        // Releases are not key presses
+        assert_eq!(one(b"\x1b[97;1:3u"), None);
+
+        assert!(is_csi_u(b"\x1b[116;6u"));
+        assert!(!is_csi_u(b"\x1b[1;6A"));
+        assert!(!is_csi_u(b"u"));
+    }
+
+    #[test]
+    fn test_spans_cover_input() {
+        let input = b"ls\x1b[200~a.b\x1b[201~\x1b[B";
+        let keys = decode_keys(input);
+        assert_eq!(keys.len(), 4);
+        // The paste is one undecoded span
+        assert_eq!(keys[2].combo, None);
+        assert_eq!(&input[keys[2].range.clone()], b"\x1b[200~a.b\x1b[201~");
+        assert_eq!(keys[3].combo, Some(KeyCombo::key(KeyCode::Down)));
+        assert_eq!(keys.last().unwrap().range.end, input.len());
+    }
+}
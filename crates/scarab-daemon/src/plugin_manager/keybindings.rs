@@ -0,0 +1,197 @@
+//! Resolve plugin-declared key bindings against the user's configuration
+//!
+//! Plugins suggest default keys for their commands with
+//! `Plugin::get_keybindings`. The user's `[keybindings.plugins]` table
+//! replaces or removes those defaults, and keys the client already binds
+//! (the named shortcuts, the leader key and custom chords) are never handed
+//! to a plugin, since the client captures them before they reach the daemon.
+//! When two plugins want the same key, the one loaded first keeps it.
+
+use scarab_config::KeyBindings;
+use scarab_plugin_api::key_tables::{combo_label, parse_key_combo, parse_key_sequence, KeyCombo};
+use scarab_plugin_api::PluginKeyBinding;
+use std::collections::{HashMap, HashSet};
+
+/// A plugin command bound to a key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundCommand {
+    /// Name of the plugin that owns the command
+    pub plugin: String,
+    /// Command id passed to `on_remote_command`
+    pub command: String,
+}
+
+/// The parts of the user's key configuration that affect plugin bindings
+#[derive(Debug, Clone, Default)]
+pub struct UserKeyBindings {
+    /// Command id mapped to its replacement key, or `None` to unbind it
+    overrides: HashMap<String, Option<KeyCombo>>,
+    /// Keys bound by the client
+    reserved: HashSet<KeyCombo>,
+}
+
+impl UserKeyBindings {
+    /// Read overrides and client-bound keys from the `[keybindings]` config
+    pub fn from_config(config: &KeyBindings) -> Self {
+        let mut overrides = HashMap::new();
+        for (command, key) in &config.plugins {
+            if key.trim().is_empty() {
+                overrides.insert(command.clone(), None);
+            } else if let Some(combo) = parse_key_combo(key.trim()) {
+                overrides.insert(command.clone(), Some(combo));
+            } else {
+                log::warn!(
+                    "Ignoring invalid key '{}' for plugin command '{}'",
+                    key,
+                    command
+                );
+            }
+        }
+
+        let named = [
+            &config.leader_key,
+            &config.copy_mode,
+            &config.paste,
+            &config.search,
+            &config.command_palette,
+            &config.new_window,
+            &config.close_window,
+            &config.next_tab,
+            &config.prev_tab,
+        ];
+        let mut reserved: HashSet<KeyCombo> = named
+            .into_iter()
+            .filter_map(|key| parse_key_combo(key))
+            .collect();
+        // Only the first key of a chord is captured before the chord is known
+        reserved.extend(
+            config
+                .custom
+                .values()
+                .filter_map(|keys| parse_key_sequence(keys))
+                .map(|sequence| sequence[0].clone()),
+        );
+
+        Self {
+            overrides,
+            reserved,
+        }
+    }
+
+    /// Build the key map from each plugin's declared bindings, in load order
+    pub fn resolve(
+        &self,
+        plugins: &[(String, Vec<PluginKeyBinding>)],
+    ) -> HashMap<KeyCombo, BoundCommand> {
+        let mut bound: HashMap<KeyCombo, BoundCommand> = HashMap::new();
+        let mut bind = |key: KeyCombo, plugin: &str, command: &str| {
+            if self.reserved.contains(&key) {
+                log::warn!(
+                    "Key {} for plugin command '{}' is already bound by Scarab",
+                    combo_label(&key),
+                    command
+                );
+            } else if let Some(existing) = bound.get(&key) {
+                log::warn!(
+                    "Key {} for plugin command '{}' ({}) is already bound to '{}' ({})",
+                    combo_label(&key),
+                    command,
+                    plugin,
+                    existing.command,
+                    existing.plugin
+                );
+            } else {
+                bound.insert(
+                    key,
+                    BoundCommand {
+                        plugin: plugin.to_string(),
+                        command: command.to_string(),
+                    },
+                );
+            }
+        };
+
+        // User overrides first so they win over every plugin default
+        for (plugin, bindings) in plugins {
+            for binding in bindings {
+                if let Some(Some(key)) = self.overrides.get(&binding.command) {
+                    bind(key.clone(), plugin, &binding.command);
+                }
+            }
+        }
+        for (plugin, bindings) in plugins {
+            for binding in bindings {
+                if !self.overrides.contains_key(&binding.command) {
+                    bind(binding.key.clone(), plugin, &binding.command);
+                }
+            }
+        }
+
+        bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_plugin_api::key_tables::{KeyCode, KeyModifiers};
+
+    fn ctrl_alt(key: KeyCode) -> KeyCombo {
+        KeyCombo::new(key, KeyModifiers::CTRL | KeyModifiers::ALT)
+    }
+
+    fn plugin(name: &str, bindings: &[(KeyCode, &str)]) -> (String, Vec<PluginKeyBinding>) {
+        let bindings = bindings
+            .iter()
+            .map(|(key, command)| PluginKeyBinding::new(ctrl_alt(*key), *command))
+            .collect();
+        (name.to_string(), bindings)
+    }
+
+    #[test]
+    fn test_first_plugin_keeps_conflicting_key() {
+        let user = UserKeyBindings::from_config(&KeyBindings::default());
+        let bound = user.resolve(&[
+            plugin("git", &[(KeyCode::KeyG, "git.status")]),
+            plugin(
+                "grep",
+                &[(KeyCode::KeyG, "grep.open"), (KeyCode::KeyR, "grep.rerun")],
+            ),
+        ]);
+
+        assert_eq!(bound.len(), 2);
+        assert_eq!(bound[&ctrl_alt(KeyCode::KeyG)].command, "git.status");
+        assert_eq!(bound[&ctrl_alt(KeyCode::KeyR)].plugin, "grep");
+    }
+
+    #[test]
+    fn test_user_overrides_and_reserved_keys() {
+        let mut config = KeyBindings::default();
+        config
+            .plugins
+            .insert("grep.open".to_string(), "Ctrl+Alt+o".to_string());
+        config
+            .plugins
+            .insert("grep.rerun".to_string(), String::new());
+        config
+            .custom
+            .insert("split".to_string(), "Ctrl+Alt+s Ctrl+Alt+v".to_string());
+        let user = UserKeyBindings::from_config(&config);
+
+        let bound = user.resolve(&[
+            plugin(
+                "git",
+                &[(KeyCode::KeyO, "git.log"), (KeyCode::KeyS, "git.stash")],
+            ),
+            plugin(
+                "grep",
+                &[(KeyCode::KeyG, "grep.open"), (KeyCode::KeyR, "grep.rerun")],
+            ),
+        ]);
+
+        // The override beats the earlier plugin's default, the unbound command
+        // has no key, and the chord's first key stays with the client
+        assert_eq!(bound.len(), 1);
+        assert_eq!(bound[&ctrl_alt(KeyCode::KeyO)].command, "grep.open");
+    }
+}
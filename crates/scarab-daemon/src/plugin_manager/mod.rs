@@ -1,30 +1,68 @@
 //! Plugin lifecycle management and hook dispatch
 
 use crate::ipc::ClientRegistry;
+use parking_lot::Mutex;
 use scarab_plugin_api::{
-    context::{LogLevel, NotifyLevel},
+    context::{LogLevel, NotifyLevel, PluginConfigData},
     delight,
-    types::RemoteCommand,
-    Achievement, Action, Plugin, PluginConfig, PluginContext, PluginDiscovery, PluginError,
-    PluginInfo, PluginMood, Result,
+    key_tables::KeyCombo,
+    permissions::{capability_key, PermissionDecision},
+    types::{MouseEvent, PluginMessage, PromptResponse, RemoteCommand},
+    Achievement, Action, Capability, Plugin, PluginConfig, PluginContext, PluginDiscovery,
+    PluginError, PluginInfo, PluginMetadata, PluginMood, Result,
 };
-use scarab_protocol::DaemonMessage;
+use scarab_protocol::{ControlMessage, DaemonMessage, PluginInspectorInfo};
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tokio::time::timeout;
+use tokio::{sync::mpsc, time::timeout};
 
 pub mod fusabi_adapter;
+pub mod fusabi_state;
+pub mod history;
+pub mod key_decoder;
+pub mod keybindings;
+pub mod native;
+pub mod output_filter;
+pub mod status_segments;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watcher;
+pub mod workspace;
 use fusabi_adapter::{FusabiBytecodePlugin, FusabiScriptPlugin};
+use keybindings::{BoundCommand, UserKeyBindings};
+use native::NativePlugin;
+use output_filter::OutputFilterSet;
+use status_segments::StatusSegments;
+pub use status_segments::STATUS_SEGMENT_INTERVAL;
+#[cfg(feature = "wasm")]
+use wasm::WasmPlugin;
+pub use watcher::PluginDirWatcher;
+use workspace::{check_setting_change, is_workspace_command};
+
+/// Version plugins' `min_scarab_version` is checked against
+const SCARAB_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Rounds of message delivery per flush, so plugins answering each other's
+/// messages cannot loop forever
+const MAX_MESSAGE_ROUNDS: usize = 8;
+
+/// How often unanswered permission prompts are checked for expiry
+pub const PERMISSION_EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Plugin wrapper with failure tracking and personality
 pub struct ManagedPlugin {
     /// The actual plugin instance
     pub plugin: Box<dyn Plugin>,
-    /// Plugin configuration (retained for future use in hot-reload)
+    /// Plugin configuration (its path is used to reload the plugin)
     pub config: PluginConfig,
+    /// Context passed to this plugin's hooks, named after the plugin
+    pub context: Arc<PluginContext>,
+    /// Lines passed to `on_output`, or `None` for every line
+    output_filter: Option<OutputFilterSet>,
     /// Number of consecutive failures
     pub failure_count: u32,
     /// Whether plugin is currently enabled
@@ -36,10 +74,17 @@ pub struct ManagedPlugin {
 }
 
 impl ManagedPlugin {
-    fn new(plugin: Box<dyn Plugin>, config: PluginConfig) -> Self {
+    fn new(
+        plugin: Box<dyn Plugin>,
+        config: PluginConfig,
+        context: Arc<PluginContext>,
+        output_filter: Option<OutputFilterSet>,
+    ) -> Self {
         Self {
             plugin,
             config,
+            context,
+            output_filter,
             failure_count: 0,
             enabled: true,
             max_failures: 3,
@@ -125,6 +170,35 @@ pub struct PluginManager {
     client_registry: ClientRegistry,
     /// Total number of plugins ever loaded (for achievements)
     total_loaded: usize,
+    /// Bus messages waiting for delivery to subscribers
+    messages: Mutex<Vec<PluginMessage>>,
+    /// Open prompts mapped to the name of the plugin that asked
+    prompt_owners: Mutex<HashMap<u64, String>>,
+    /// Load plugins even when their version requirements are not met
+    force_load: bool,
+    /// User overrides and client-bound keys for plugin key bindings
+    user_keybindings: UserKeyBindings,
+    /// Keys bound to plugin commands, rebuilt with the command list
+    key_bindings: Mutex<HashMap<KeyCombo, BoundCommand>>,
+    /// The user's `[plugins.settings.<name>]` settings tables
+    user_settings: scarab_config::PluginConfig,
+    /// Status segments shown by plugins, rate limited per segment
+    status_segments: Mutex<StatusSegments>,
+    /// Watch native plugin libraries and keep state across failed rebuilds
+    dev_mode: bool,
+    /// Plugins whose library vanished or failed to load in dev mode, keyed
+    /// by library path, waiting for a build that loads
+    dev_stash: HashMap<PathBuf, StashedPlugin>,
+    /// Where native libraries are copied before they are opened
+    shadow_dir: PathBuf,
+    /// Workspace changes requested through plugins' object handles
+    workspace_tx: Option<mpsc::UnboundedSender<ControlMessage>>,
+}
+
+/// A plugin taken down by a rebuild, kept until its library loads again
+struct StashedPlugin {
+    config: PluginConfig,
+    snapshot: Option<Vec<u8>>,
 }
 
 impl PluginManager {
@@ -142,6 +216,17 @@ impl PluginManager {
             context,
             client_registry,
             total_loaded: 0,
+            messages: Mutex::new(Vec::new()),
+            prompt_owners: Mutex::new(HashMap::new()),
+            force_load: false,
+            user_keybindings: UserKeyBindings::default(),
+            key_bindings: Mutex::new(HashMap::new()),
+            user_settings: scarab_config::PluginConfig::default(),
+            status_segments: Mutex::new(StatusSegments::default()),
+            dev_mode: false,
+            dev_stash: HashMap::new(),
+            shadow_dir: Self::shadow_dir_in(&scarab_platform::Paths::from_env().data_dir),
+            workspace_tx: None,
         }
     }
 
@@ -151,6 +236,80 @@ impl PluginManager {
         self
     }
 
+    /// Look for the user's plugins in `dir` instead of the default directory
+    pub fn with_plugin_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.discovery = PluginDiscovery::with_plugin_dir(dir);
+        self
+    }
+
+    /// Keep shadow copies of native libraries under `dir`, the data
+    /// directory resolved from the config
+    pub fn with_data_dir(mut self, dir: &Path) -> Self {
+        self.shadow_dir = Self::shadow_dir_in(dir);
+        self
+    }
+
+    fn shadow_dir_in(data_dir: &Path) -> PathBuf {
+        data_dir.join("plugin-shadows")
+    }
+
+    /// Load plugins that fail the API and Scarab version checks anyway
+    pub fn with_force_load(mut self, force_load: bool) -> Self {
+        self.force_load = force_load;
+        self
+    }
+
+    /// Apply the user's `[keybindings]` to keys declared by plugins
+    pub fn with_keybindings(mut self, config: &scarab_config::KeyBindings) -> Self {
+        self.user_keybindings = UserKeyBindings::from_config(config);
+        self.refresh_commands();
+        self
+    }
+
+    /// Hand the user's `[plugins.settings.<name>]` tables to plugins as they load
+    pub fn with_plugin_settings(mut self, config: &scarab_config::PluginConfig) -> Self {
+        self.user_settings = config.clone();
+        self
+    }
+
+    /// Enable plugin development mode
+    ///
+    /// The directory watcher then also follows native libraries loaded from
+    /// outside the plugin directories, such as a crate's `target/debug`.
+    /// A plugin whose library disappears or fails to load mid-build is kept
+    /// aside with its state snapshot and comes back once a build loads.
+    pub fn with_dev_mode(mut self, dev_mode: bool) -> Self {
+        self.dev_mode = dev_mode;
+        self
+    }
+
+    /// Send workspace changes made through `ctx.objects()` handles to
+    /// `tx`, whose receiver applies them like client control messages
+    pub fn with_workspace_control(mut self, tx: mpsc::UnboundedSender<ControlMessage>) -> Self {
+        self.workspace_tx = Some(tx);
+        self
+    }
+
+    pub fn dev_mode(&self) -> bool {
+        self.dev_mode
+    }
+
+    /// Native plugin libraries to watch in dev mode, including ones waiting
+    /// for a build that loads
+    pub fn dev_watch_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .plugins
+            .iter()
+            .filter(|p| !p.config.path.as_os_str().is_empty())
+            .map(|p| p.config.expanded_path())
+            .filter(|path| is_native_library(path))
+            .chain(self.dev_stash.keys().cloned())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
     /// Check and celebrate achievements
     fn check_achievements(&self) {
         let enabled_count = self.enabled_count();
@@ -337,6 +496,26 @@ impl PluginManager {
                         })
                         .await;
                 }
+                RemoteCommand::SpawnPanel {
+                    plugin_name,
+                    overlay_id,
+                    panel,
+                } => {
+                    log::debug!(
+                        "Plugin {} spawning panel {} at ({}, {})",
+                        plugin_name,
+                        overlay_id,
+                        panel.x,
+                        panel.y
+                    );
+                    self.client_registry
+                        .broadcast(DaemonMessage::SpawnPanel {
+                            plugin_name: plugin_name.into(),
+                            overlay_id,
+                            panel,
+                        })
+                        .await;
+                }
                 RemoteCommand::RemoveOverlay {
                     plugin_name,
                     overlay_id,
@@ -360,14 +539,31 @@ impl PluginManager {
                         item_id,
                         item.label
                     );
-                    self.client_registry
-                        .broadcast(DaemonMessage::AddStatusItem {
-                            plugin_name: plugin_name.into(),
-                            item_id,
-                            label: item.label.into(),
-                            content: item.content.into(),
-                            priority: item.priority,
-                        })
+                    let message = DaemonMessage::AddStatusItem {
+                        plugin_name: plugin_name.clone(),
+                        item_id,
+                        label: item.label,
+                        content: item.content,
+                        priority: item.priority,
+                    };
+                    self.forward_status_segment(&plugin_name, item_id, message)
+                        .await;
+                }
+                RemoteCommand::SetStatusSegment {
+                    plugin_name,
+                    item_id,
+                    side,
+                    priority,
+                    items,
+                } => {
+                    let message = DaemonMessage::StatusSegment {
+                        plugin_name: plugin_name.clone(),
+                        item_id,
+                        side: side.into(),
+                        priority,
+                        items: items.iter().filter_map(|item| item.to_protocol()).collect(),
+                    };
+                    self.forward_status_segment(&plugin_name, item_id, message)
                         .await;
                 }
                 RemoteCommand::RemoveStatusItem {
@@ -375,6 +571,7 @@ impl PluginManager {
                     item_id,
                 } => {
                     log::debug!("Plugin {} removing status item {}", plugin_name, item_id);
+                    self.status_segments.lock().remove(&plugin_name, item_id);
                     self.client_registry
                         .broadcast(DaemonMessage::RemoveStatusItem {
                             plugin_name: plugin_name.into(),
@@ -443,6 +640,63 @@ impl PluginManager {
                         })
                         .await;
                 }
+                RemoteCommand::Publish(message) => {
+                    // Delivered to subscribers by flush_commands
+                    self.messages.lock().push(message);
+                }
+                RemoteCommand::ShowInputPrompt {
+                    plugin_name,
+                    prompt_id,
+                    title,
+                    placeholder,
+                    masked,
+                } => {
+                    log::debug!("Plugin {} opening input prompt {}", plugin_name, prompt_id);
+                    self.prompt_owners.lock().insert(prompt_id, plugin_name);
+                    self.client_registry
+                        .broadcast_to_writers(DaemonMessage::ShowInputPrompt {
+                            prompt_id,
+                            title: title.into(),
+                            placeholder: placeholder.into(),
+                            masked,
+                        })
+                        .await;
+                }
+                RemoteCommand::ShowForm {
+                    plugin_name,
+                    prompt_id,
+                    title,
+                    fields,
+                } => {
+                    log::debug!("Plugin {} opening form {}", plugin_name, prompt_id);
+                    self.prompt_owners.lock().insert(prompt_id, plugin_name);
+                    self.client_registry
+                        .broadcast_to_writers(DaemonMessage::ShowForm {
+                            prompt_id,
+                            title: title.into(),
+                            fields,
+                        })
+                        .await;
+                }
+                RemoteCommand::TaskProgress {
+                    plugin_name,
+                    task_id,
+                    title,
+                    percent,
+                    message,
+                    done,
+                } => {
+                    self.client_registry
+                        .broadcast(DaemonMessage::TaskProgress {
+                            plugin_name,
+                            task_id,
+                            title,
+                            percent,
+                            message,
+                            done,
+                        })
+                        .await;
+                }
                 RemoteCommand::GetCurrentTheme { plugin_name } => {
                     log::debug!("Plugin {} requesting current theme", plugin_name);
                     // TODO: Retrieve actual current theme name from config
@@ -454,6 +708,164 @@ impl PluginManager {
                         })
                         .await;
                 }
+                RemoteCommand::Control {
+                    plugin_name,
+                    message,
+                } => {
+                    if !is_workspace_command(&message) {
+                        log::warn!(
+                            "Plugin '{}' sent a control message plugins may not send: {:?}",
+                            plugin_name,
+                            message
+                        );
+                        continue;
+                    }
+                    if let Err(reason) = check_setting_change(&plugin_name, &message) {
+                        log::warn!("Refused a setting change: {}", reason);
+                        continue;
+                    }
+                    match &self.workspace_tx {
+                        Some(tx) => {
+                            log::debug!("Plugin '{}' workspace change: {:?}", plugin_name, message);
+                            let _ = tx.send(message);
+                        }
+                        None => log::warn!(
+                            "Plugin '{}' changed the workspace, but no session is attached",
+                            plugin_name
+                        ),
+                    }
+                }
+                RemoteCommand::RequestPermission {
+                    plugin_name,
+                    prompt_id,
+                    capability,
+                    detail,
+                } => {
+                    log::info!(
+                        "Asking whether plugin '{}' may use {}: {}",
+                        plugin_name,
+                        capability_key(&capability),
+                        detail
+                    );
+                    self.client_registry
+                        .broadcast_to_writers(DaemonMessage::ShowPermissionPrompt {
+                            prompt_id,
+                            plugin_name: plugin_name.into(),
+                            capability: capability_key(&capability).into(),
+                            detail: detail.into(),
+                        })
+                        .await;
+                }
+                RemoteCommand::SetClipboard { plugin_name, text } => {
+                    log::debug!("Plugin '{}' copied {} bytes", plugin_name, text.len());
+                    self.client_registry
+                        .broadcast_to_writers(DaemonMessage::SetClipboard { text: text.into() })
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Broadcast a status segment update unless the segment was updated
+    /// too recently, in which case it waits for `flush_status_segments`
+    async fn forward_status_segment(
+        &self,
+        plugin_name: &str,
+        item_id: u64,
+        message: DaemonMessage,
+    ) {
+        let ready =
+            self.status_segments
+                .lock()
+                .offer(plugin_name, item_id, message, Instant::now());
+        if let Some(message) = ready {
+            self.client_registry.broadcast(message).await;
+        }
+    }
+
+    /// Broadcast status segment updates held back by rate limiting
+    ///
+    /// Call this about every [`STATUS_SEGMENT_INTERVAL`] so the latest
+    /// update of each segment reaches clients.
+    pub async fn flush_status_segments(&self) {
+        let due = self.status_segments.lock().due(Instant::now());
+        for message in due {
+            self.client_registry.broadcast(message).await;
+        }
+    }
+
+    /// Remove every status segment and item shown by a plugin
+    async fn clear_status_segments(&self, name: &str) {
+        let ids = self.status_segments.lock().remove_plugin(name);
+        for item_id in ids {
+            self.client_registry
+                .broadcast(DaemonMessage::RemoveStatusItem {
+                    plugin_name: name.to_string(),
+                    item_id,
+                })
+                .await;
+        }
+    }
+
+    /// Process pending commands and deliver bus messages to subscribers
+    ///
+    /// Subscribers may publish in turn, so delivery repeats until no
+    /// messages are left or `MAX_MESSAGE_ROUNDS` is reached.
+    pub async fn flush_commands(&mut self) {
+        for _ in 0..MAX_MESSAGE_ROUNDS {
+            self.process_pending_commands().await;
+
+            let messages = std::mem::take(&mut *self.messages.lock());
+            if messages.is_empty() {
+                return;
+            }
+            for message in &messages {
+                self.deliver_message(message).await;
+            }
+        }
+
+        self.process_pending_commands().await;
+        let dropped = std::mem::take(&mut *self.messages.lock()).len();
+        if dropped > 0 {
+            log::warn!(
+                "Dropped {} plugin messages after {} delivery rounds",
+                dropped,
+                MAX_MESSAGE_ROUNDS
+            );
+        }
+    }
+
+    /// Deliver one bus message to every enabled subscriber except its sender
+    async fn deliver_message(&mut self, message: &PluginMessage) {
+        for managed in &mut self.plugins {
+            let metadata = managed.plugin.metadata();
+            if !managed.enabled
+                || metadata.name == message.sender
+                || !metadata.subscribes_to(&message.topic)
+            {
+                continue;
+            }
+
+            let plugin_name = metadata.display_name();
+            let ctx = managed.context.clone();
+
+            let result = timeout(self.hook_timeout, managed.plugin.on_message(message, &ctx)).await;
+
+            match result {
+                Ok(Ok(_)) => managed.record_success(),
+                Ok(Err(e)) => {
+                    log::error!(
+                        "{} Plugin '{}' message hook failed: {}",
+                        managed.mood().emoji(),
+                        plugin_name,
+                        e
+                    );
+                    managed.record_failure();
+                }
+                Err(_) => {
+                    log::error!("⏱️  Plugin '{}' message hook timed out", plugin_name);
+                    managed.record_failure();
+                }
             }
         }
     }
@@ -467,6 +879,19 @@ impl PluginManager {
             }
         }
         self.context.state.lock().commands = all_commands;
+
+        let declared: Vec<_> = self
+            .plugins
+            .iter()
+            .filter(|managed| managed.enabled)
+            .map(|managed| {
+                (
+                    managed.plugin.metadata().name.clone(),
+                    managed.plugin.get_keybindings(),
+                )
+            })
+            .collect();
+        *self.key_bindings.lock() = self.user_keybindings.resolve(&declared);
     }
 
     /// Load plugins from configuration file
@@ -511,6 +936,11 @@ impl PluginManager {
         Ok(loaded)
     }
 
+    /// Directories scanned for plugins
+    pub fn search_paths(&self) -> Vec<PathBuf> {
+        self.discovery.search_paths().to_vec()
+    }
+
     /// Discover and load all plugins from search paths
     pub async fn discover_and_load(&mut self) -> Result<usize> {
         let plugin_files = self.discovery.discover();
@@ -519,21 +949,9 @@ impl PluginManager {
         log::info!("🔍 Scanning plugin directories...");
 
         for path in plugin_files {
-            // Create minimal config for discovered plugin
-            let config = PluginConfig {
-                name: path
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("unknown")
-                    .to_string(),
-                path: path.clone(),
-                enabled: true,
-                config: Default::default(),
-            };
-
             log::info!("⏳ {}", delight::random_loading_message());
 
-            match self.load_plugin_from_config(config).await {
+            match self.load_plugin_from_path(path.clone()).await {
                 Ok(_) => loaded += 1,
                 Err(e) => log::warn!("Failed to load plugin from {:?}: {}", path, e),
             }
@@ -551,8 +969,50 @@ impl PluginManager {
         Ok(loaded)
     }
 
+    /// Load a single discovered plugin file, returning the registered plugin name
+    ///
+    /// A plugin stashed in dev mode for this path gets its configuration and
+    /// state snapshot back.
+    pub async fn load_plugin_from_path(&mut self, path: PathBuf) -> Result<String> {
+        if let Some(stashed) = self.dev_stash.remove(&path) {
+            if let Err(e) = self.load_plugin_from_config(stashed.config.clone()).await {
+                self.dev_stash.insert(path, stashed);
+                return Err(e);
+            }
+            let hook_timeout = self.hook_timeout;
+            let Some(managed) = self.plugins.last_mut() else {
+                return Ok(String::new());
+            };
+            if let Some(state) = stashed.snapshot {
+                restore_snapshot(managed, &state, hook_timeout).await;
+            }
+            return Ok(managed.plugin.metadata().name.clone());
+        }
+
+        // Create minimal config for discovered plugin
+        let config = PluginConfig {
+            name: path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown")
+                .to_string(),
+            path,
+            enabled: true,
+            config: Default::default(),
+            capabilities: Default::default(),
+            allowed_hosts: Default::default(),
+        };
+
+        self.load_plugin_from_config(config).await?;
+        Ok(self
+            .plugins
+            .last()
+            .map(|p| p.plugin.metadata().name.clone())
+            .unwrap_or_default())
+    }
+
     /// Load a single plugin from configuration
-    pub async fn load_plugin_from_config(&mut self, config: PluginConfig) -> Result<()> {
+    pub async fn load_plugin_from_config(&mut self, mut config: PluginConfig) -> Result<()> {
         let path = config.expanded_path();
 
         log::debug!("📦 Loading plugin: {} from {:?}", config.name, path);
@@ -568,10 +1028,36 @@ impl PluginManager {
                 log::debug!("⚡ Loading compiled bytecode plugin: {:?}", path);
                 Box::new(FusabiBytecodePlugin::load(&path)?)
             }
+            Some("scarabpkg") => {
+                log::debug!("📦 Loading plugin package: {:?}", path);
+                let (plugin, manifest) = FusabiBytecodePlugin::load_package(&path)?;
+                // The hosts a package declares apply once network use is
+                // granted, unless the config names its own
+                if config.allowed_hosts.is_empty() {
+                    config.allowed_hosts = manifest.allowed_hosts;
+                }
+                Box::new(plugin)
+            }
             Some("fsx") => {
                 log::debug!("📜 Loading script plugin: {:?}", path);
                 Box::new(FusabiScriptPlugin::load(&path)?)
             }
+            Some("so") | Some("dylib") | Some("dll") => {
+                log::debug!("🔌 Loading native plugin: {:?}", path);
+                Box::new(NativePlugin::load(&path, &self.shadow_dir)?)
+            }
+            #[cfg(feature = "wasm")]
+            Some("wasm") => {
+                log::debug!("🧩 Loading WASM plugin: {:?}", path);
+                Box::new(WasmPlugin::load(&path)?)
+            }
+            #[cfg(not(feature = "wasm"))]
+            Some("wasm") => {
+                return Err(PluginError::LoadError(format!(
+                    "WASM plugin support is not enabled in this build: {:?}",
+                    path
+                )))
+            }
             _ => {
                 return Err(PluginError::LoadError(format!(
                     "Unsupported plugin format: {:?}",
@@ -580,36 +1066,107 @@ impl PluginManager {
             }
         };
 
+        grant_declared_capabilities(&mut config, plugin.metadata());
+
         // Register the loaded plugin
-        self.register_plugin(plugin).await
+        self.register_plugin_with_config(plugin, config).await
     }
 
     /// Manually register a plugin
-    pub async fn register_plugin(&mut self, mut plugin: Box<dyn Plugin>) -> Result<()> {
+    pub async fn register_plugin(&mut self, plugin: Box<dyn Plugin>) -> Result<()> {
+        let config = PluginConfig {
+            name: plugin.metadata().name.clone(),
+            path: PathBuf::new(),
+            enabled: true,
+            config: Default::default(),
+            capabilities: Default::default(),
+            allowed_hosts: Default::default(),
+        };
+        self.register_plugin_with_config(plugin, config).await
+    }
+
+    /// Register a built-in plugin with capabilities granted up front
+    ///
+    /// Network, exec and clipboard use still asks the user first.
+    pub async fn register_plugin_with_capabilities(
+        &mut self,
+        plugin: Box<dyn Plugin>,
+        capabilities: &[Capability],
+        allowed_hosts: &[&str],
+    ) -> Result<()> {
+        let config = PluginConfig {
+            name: plugin.metadata().name.clone(),
+            path: PathBuf::new(),
+            enabled: true,
+            config: Default::default(),
+            capabilities: capabilities.iter().cloned().collect(),
+            allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+        };
+        self.register_plugin_with_config(plugin, config).await
+    }
+
+    /// Register a plugin, remembering the configuration it was loaded from
+    async fn register_plugin_with_config(
+        &mut self,
+        mut plugin: Box<dyn Plugin>,
+        config: PluginConfig,
+    ) -> Result<()> {
         // Clone metadata values we need before calling on_load
         let plugin_name = plugin.metadata().display_name();
         let plugin_version = plugin.metadata().version.clone();
-        let api_version = plugin.metadata().api_version.clone();
         let catchphrase = plugin.metadata().catchphrase.clone();
 
-        // Check API compatibility
-        if !plugin
+        // Refuse plugins built for another plugin API or a newer Scarab
+        if let Err(e) = plugin
             .metadata()
-            .is_compatible(scarab_plugin_api::API_VERSION)
+            .check_compatibility(scarab_plugin_api::API_VERSION, SCARAB_VERSION)
         {
-            return Err(PluginError::VersionIncompatible {
-                required: scarab_plugin_api::API_VERSION.to_string(),
-                actual: api_version,
-            });
+            if !self.force_load {
+                log::error!(
+                    "🚫 Refusing to load plugin '{}': {} (start the daemon with --force-load to load it anyway)",
+                    plugin_name,
+                    e
+                );
+                return Err(e);
+            }
+            log::warn!("⚠️  Force-loading plugin '{}': {}", plugin_name, e);
         }
 
+        let output_filter = match OutputFilterSet::compile(&plugin.metadata().output_filters) {
+            Ok(filter) => filter,
+            Err(e) => {
+                log::error!("🚫 Refusing to load plugin '{}': {}", plugin_name, e);
+                return Err(e);
+            }
+        };
+
         log::info!("🎯 Registering plugin: {} v{}", plugin_name, plugin_version);
         if let Some(phrase) = &catchphrase {
             log::info!("   💬 \"{}\"", phrase);
         }
 
-        // Call on_load with timeout
+        // Refuse settings the plugin's schema rejects rather than start it
+        // with values it does not expect
+        let settings = match resolve_settings(&self.user_settings, plugin.as_ref(), &config) {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::error!(
+                    "🚫 Refusing to load plugin '{}': invalid settings: {}",
+                    plugin_name,
+                    e
+                );
+                return Err(e);
+            }
+        };
+
+        // Each plugin gets its own context so logs, messages and storage
+        // are attributed to it and only its granted capabilities apply; the
+        // command queue and state stay shared
         let mut ctx = (*self.context).clone();
+        ctx.logger_name = plugin.metadata().name.clone();
+        ctx.capabilities = config.capabilities.clone();
+        ctx.allowed_hosts = config.allowed_hosts.clone();
+        ctx.config = settings;
         let timeout_duration = self.hook_timeout;
 
         // Call on_load directly with timeout
@@ -617,13 +1174,12 @@ impl PluginManager {
 
         match load_result {
             Ok(Ok(_)) => {
-                let config = PluginConfig {
-                    name: plugin.metadata().name.clone(),
-                    path: PathBuf::new(),
-                    enabled: true,
-                    config: Default::default(),
-                };
-                self.plugins.push(ManagedPlugin::new(plugin, config));
+                self.plugins.push(ManagedPlugin::new(
+                    plugin,
+                    config,
+                    Arc::new(ctx),
+                    output_filter,
+                ));
                 self.total_loaded += 1;
 
                 log::info!(
@@ -639,7 +1195,7 @@ impl PluginManager {
                 self.check_achievements();
 
                 // Process commands that might have been queued during on_load
-                self.process_pending_commands().await;
+                self.flush_commands().await;
 
                 Ok(())
             }
@@ -656,6 +1212,9 @@ impl PluginManager {
     }
 
     /// Dispatch output hook to all enabled plugins
+    ///
+    /// Plugins with output filters are skipped for lines none of their
+    /// filters match, as changed by the plugins before them.
     pub async fn dispatch_output(&mut self, line: &str) -> Result<String> {
         let mut data = line.to_string();
 
@@ -663,10 +1222,15 @@ impl PluginManager {
             if !managed.enabled {
                 continue;
             }
+            if let Some(filter) = &managed.output_filter {
+                if !filter.matches(&data) {
+                    continue;
+                }
+            }
 
             let plugin_name = managed.plugin.metadata().display_name();
             let current_data = data.clone();
-            let ctx = self.context.clone();
+            let ctx = managed.context.clone();
 
             // Apply timeout to plugin call
             let result = timeout(
@@ -704,14 +1268,17 @@ impl PluginManager {
         }
 
         // Process pending commands from all plugins
-        self.process_pending_commands().await;
+        self.flush_commands().await;
 
         Ok(data)
     }
 
     /// Dispatch input hook to all enabled plugins
+    ///
+    /// Key presses are decoded and offered to `on_key` first; the resulting
+    /// bytes then go through `on_input` as before.
     pub async fn dispatch_input(&mut self, input: &[u8]) -> Result<Vec<u8>> {
-        let mut data = input.to_vec();
+        let mut data = self.dispatch_keys(input).await;
 
         for managed in &mut self.plugins {
             if !managed.enabled {
@@ -720,7 +1287,7 @@ impl PluginManager {
 
             let plugin_name = managed.plugin.metadata().display_name();
             let current_data = data.clone();
-            let ctx = self.context.clone();
+            let ctx = managed.context.clone();
 
             let result = timeout(
                 self.hook_timeout,
@@ -757,11 +1324,181 @@ impl PluginManager {
         }
 
         // Process pending commands
-        self.process_pending_commands().await;
+        self.flush_commands().await;
 
         Ok(data)
     }
 
+    /// Decode input into keys and run each through the key hook
+    async fn dispatch_keys(&mut self, input: &[u8]) -> Vec<u8> {
+        let keys = key_decoder::decode_keys(input);
+        if keys.iter().all(|key| key.combo.is_none()) {
+            return input.to_vec();
+        }
+
+        let mut data = Vec::with_capacity(input.len());
+        for key in keys {
+            let bytes = &input[key.range];
+            match key.combo {
+                Some(combo) => {
+                    let sent = self.dispatch_key(combo, bytes).await;
+                    // A kitty key no plugin took has nowhere to go
+                    if !(sent == bytes && key_decoder::is_csi_u(bytes)) {
+                        data.extend(sent);
+                    }
+                }
+                None => data.extend_from_slice(bytes),
+            }
+        }
+        data
+    }
+
+    /// Dispatch one decoded key, returning the bytes to send in its place
+    ///
+    /// A key bound to a plugin command runs the command and is swallowed.
+    async fn dispatch_key(&mut self, combo: KeyCombo, bytes: &[u8]) -> Vec<u8> {
+        let bound = self.key_bindings.lock().get(&combo).cloned();
+        if let Some(bound) = bound {
+            if self.run_bound_command(&bound).await {
+                return Vec::new();
+            }
+        }
+
+        for managed in &mut self.plugins {
+            if !managed.enabled {
+                continue;
+            }
+
+            let plugin_name = managed.plugin.metadata().display_name();
+            let ctx = managed.context.clone();
+
+            let result = timeout(
+                self.hook_timeout,
+                managed.plugin.on_key(combo.clone(), &ctx),
+            )
+            .await;
+
+            match result {
+                Ok(Ok(Action::Continue)) => {
+                    managed.record_success();
+                }
+                Ok(Ok(Action::Stop)) => {
+                    managed.record_success();
+                    break;
+                }
+                Ok(Ok(Action::Modify(new_data))) => {
+                    managed.record_success();
+                    return new_data;
+                }
+                Ok(Err(e)) => {
+                    log::error!(
+                        "{} Plugin '{}' key hook failed: {}",
+                        managed.mood().emoji(),
+                        plugin_name,
+                        e
+                    );
+                    managed.record_failure();
+                }
+                Err(_) => {
+                    log::error!("⏱️  Plugin '{}' key hook timed out", plugin_name);
+                    managed.record_failure();
+                }
+            }
+        }
+
+        bytes.to_vec()
+    }
+
+    /// Run a key-bound command in the plugin that declared it
+    ///
+    /// Returns false when that plugin has since been disabled.
+    async fn run_bound_command(&mut self, bound: &BoundCommand) -> bool {
+        let Some(managed) = self
+            .plugins
+            .iter_mut()
+            .find(|managed| managed.enabled && managed.plugin.metadata().name == bound.plugin)
+        else {
+            return false;
+        };
+
+        let plugin_name = managed.plugin.metadata().display_name();
+        let ctx = managed.context.clone();
+
+        let result = timeout(
+            self.hook_timeout,
+            managed.plugin.on_remote_command(&bound.command, &ctx),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(_)) => managed.record_success(),
+            Ok(Err(e)) => {
+                log::error!(
+                    "{} Plugin '{}' command '{}' failed: {}",
+                    managed.mood().emoji(),
+                    plugin_name,
+                    bound.command,
+                    e
+                );
+                managed.record_failure();
+            }
+            Err(_) => {
+                log::error!(
+                    "⏱️  Plugin '{}' command '{}' timed out",
+                    plugin_name,
+                    bound.command
+                );
+                managed.record_failure();
+            }
+        }
+        true
+    }
+
+    /// Dispatch a mouse event to all enabled plugins
+    ///
+    /// `Action::Stop` ends dispatch; `Action::Modify` is treated like
+    /// `Continue` since there are no bytes to replace.
+    pub async fn dispatch_mouse(&mut self, event: &MouseEvent) -> Result<()> {
+        for managed in &mut self.plugins {
+            if !managed.enabled {
+                continue;
+            }
+
+            let plugin_name = managed.plugin.metadata().display_name();
+            let ctx = managed.context.clone();
+
+            let result = timeout(self.hook_timeout, managed.plugin.on_mouse(event, &ctx)).await;
+
+            match result {
+                Ok(Ok(Action::Stop)) => {
+                    managed.record_success();
+                    break;
+                }
+                Ok(Ok(_)) => {
+                    managed.record_success();
+                }
+                Ok(Err(e)) => {
+                    log::error!(
+                        "{} Plugin '{}' mouse hook failed: {}",
+                        managed.mood().emoji(),
+                        plugin_name,
+                        e
+                    );
+                    managed.record_failure();
+                }
+                Err(_) => {
+                    log::error!("⏱️  Plugin '{}' mouse hook timed out", plugin_name);
+                    managed.record_failure();
+                }
+            }
+        }
+
+        // Process pending commands
+        self.flush_commands().await;
+
+        Ok(())
+    }
+
     /// Dispatch resize event to all enabled plugins
     pub async fn dispatch_resize(&mut self, cols: u16, rows: u16) -> Result<()> {
         for managed in &mut self.plugins {
@@ -770,7 +1507,7 @@ impl PluginManager {
             }
 
             let plugin_name = managed.plugin.metadata().display_name();
-            let ctx = self.context.clone();
+            let ctx = managed.context.clone();
 
             let result = timeout(
                 self.hook_timeout,
@@ -797,21 +1534,250 @@ impl PluginManager {
         }
 
         // Process pending commands
-        self.process_pending_commands().await;
+        self.flush_commands().await;
 
         Ok(())
     }
 
+    /// Apply edited `[plugins.settings.<name>]` tables to loaded plugins
+    ///
+    /// Plugins whose settings changed get a fresh context and an
+    /// `on_config_changed` call. Settings their schema rejects are reported
+    /// to the user and the plugin keeps its previous settings.
+    pub async fn apply_plugin_settings(&mut self, config: &scarab_config::PluginConfig) {
+        self.user_settings = config.clone();
+
+        for managed in &mut self.plugins {
+            let plugin_name = managed.plugin.metadata().display_name();
+            let settings = match resolve_settings(
+                &self.user_settings,
+                managed.plugin.as_ref(),
+                &managed.config,
+            ) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    log::warn!(
+                        "Keeping previous settings for plugin '{}': {}",
+                        plugin_name,
+                        e
+                    );
+                    self.context.notify_error(
+                        &format!("Invalid settings for {}", plugin_name),
+                        &e.to_string(),
+                    );
+                    continue;
+                }
+            };
+            if settings == managed.context.config {
+                continue;
+            }
+
+            let mut ctx = (*managed.context).clone();
+            ctx.config = settings;
+            managed.context = Arc::new(ctx);
+            if !managed.enabled {
+                continue;
+            }
+
+            let ctx = managed.context.clone();
+            let result = timeout(self.hook_timeout, managed.plugin.on_config_changed(&ctx)).await;
+
+            match result {
+                Ok(Ok(_)) => managed.record_success(),
+                Ok(Err(e)) => {
+                    log::error!(
+                        "{} Plugin '{}' config change hook failed: {}",
+                        managed.mood().emoji(),
+                        plugin_name,
+                        e
+                    );
+                    managed.record_failure();
+                }
+                Err(_) => {
+                    log::error!("⏱️  Plugin '{}' config change hook timed out", plugin_name);
+                    managed.record_failure();
+                }
+            }
+        }
+
+        self.flush_commands().await;
+    }
+
     /// Get information about all loaded plugins
     pub fn list_plugins(&self) -> Vec<PluginInfo> {
         self.plugins.iter().map(|p| p.info()).collect()
     }
 
+    /// Get plugin information in the format sent to clients
+    pub fn inspector_infos(&self) -> Vec<PluginInspectorInfo> {
+        self.list_plugins()
+            .into_iter()
+            .map(|p| PluginInspectorInfo {
+                name: p.name,
+                version: p.version,
+                description: p.description,
+                author: p.author,
+                homepage: p.homepage,
+                api_version: p.api_version,
+                min_scarab_version: p.min_scarab_version,
+                enabled: p.enabled,
+                failure_count: p.failure_count,
+                emoji: p.emoji,
+                color: p.color,
+                verification: scarab_protocol::PluginVerificationStatus::Unverified {
+                    warning: "Verification not yet implemented".into(),
+                },
+            })
+            .collect()
+    }
+
     /// Get count of enabled plugins
     pub fn enabled_count(&self) -> usize {
         self.plugins.iter().filter(|p| p.enabled).count()
     }
 
+    /// Find the loaded plugin whose source file is `path`
+    pub fn plugin_name_for_path(&self, path: &Path) -> Option<String> {
+        self.plugins
+            .iter()
+            .find(|p| !p.config.path.as_os_str().is_empty() && p.config.expanded_path() == path)
+            .map(|p| p.plugin.metadata().name.clone())
+    }
+
+    /// Call on_unload on a plugin that has already been removed from the list
+    async fn shutdown_plugin(&self, mut managed: ManagedPlugin) {
+        let plugin_name = managed.plugin.metadata().display_name();
+
+        match timeout(self.hook_timeout, managed.plugin.on_unload()).await {
+            Ok(Ok(_)) => log::debug!("✅ Plugin '{}' unloaded cleanly", plugin_name),
+            Ok(Err(e)) => log::error!("❌ Error unloading plugin '{}': {}", plugin_name, e),
+            Err(_) => log::error!("⏱️  Plugin '{}' unload timed out", plugin_name),
+        }
+        self.cancel_tasks(&managed.plugin.metadata().name).await;
+
+        // Forward what on_unload queued before taking its segments down
+        self.process_pending_commands().await;
+        self.clear_status_segments(&managed.plugin.metadata().name)
+            .await;
+
+        // Dropping the plugin here also closes native libraries
+        drop(managed);
+    }
+
+    /// Abort a plugin's background tasks and close their progress indicators
+    async fn cancel_tasks(&self, name: &str) {
+        for (task_id, title) in self.context.tasks.cancel_owner(name) {
+            log::debug!("Cancelled task '{}' of plugin '{}'", title, name);
+            self.client_registry
+                .broadcast(DaemonMessage::TaskProgress {
+                    plugin_name: name.to_string(),
+                    task_id,
+                    title,
+                    percent: 0,
+                    message: "Cancelled".to_string(),
+                    done: true,
+                })
+                .await;
+        }
+    }
+
+    /// Unload a single plugin by name
+    pub async fn unload_plugin(&mut self, name: &str) -> Result<()> {
+        let idx = self
+            .plugins
+            .iter()
+            .position(|p| p.plugin.metadata().name == name)
+            .ok_or_else(|| PluginError::NotFound(name.to_string()))?;
+
+        let managed = self.plugins.remove(idx);
+        self.shutdown_plugin(managed).await;
+        self.refresh_commands();
+        Ok(())
+    }
+
+    /// Reload a plugin from its source file, carrying its state across
+    ///
+    /// The old instance is unloaded and dropped before the file is loaded
+    /// again, then handed the snapshot from [`Plugin::snapshot_state`]. The
+    /// plugin keeps its position in the dispatch order.
+    pub async fn reload_plugin(&mut self, name: &str) -> Result<()> {
+        let idx = self
+            .plugins
+            .iter()
+            .position(|p| p.plugin.metadata().name == name)
+            .ok_or_else(|| PluginError::NotFound(name.to_string()))?;
+
+        if self.plugins[idx].config.path.as_os_str().is_empty() {
+            return Err(PluginError::LoadError(format!(
+                "Plugin '{}' was registered in-process and has no file to reload",
+                name
+            )));
+        }
+
+        let managed = self.plugins.remove(idx);
+        let config = managed.config.clone();
+        let was_enabled = managed.enabled;
+        let snapshot = managed.plugin.snapshot_state();
+        self.shutdown_plugin(managed).await;
+
+        if let Err(e) = self.load_plugin_from_config(config.clone()).await {
+            if self.dev_mode {
+                log::info!(
+                    "🧪 Keeping state of plugin '{}' until its library loads again",
+                    name
+                );
+                self.dev_stash
+                    .insert(config.expanded_path(), StashedPlugin { config, snapshot });
+            }
+            self.refresh_commands();
+            return Err(e);
+        }
+
+        // Move the fresh instance back to its original slot
+        if let Some(mut reloaded) = self.plugins.pop() {
+            reloaded.enabled = was_enabled;
+
+            if let Some(state) = snapshot {
+                restore_snapshot(&mut reloaded, &state, self.hook_timeout).await;
+            }
+
+            let idx = idx.min(self.plugins.len());
+            self.plugins.insert(idx, reloaded);
+        }
+
+        self.refresh_commands();
+        log::info!("🔄 Plugin '{}' reloaded", name);
+        Ok(())
+    }
+
+    /// Unload a plugin whose library was removed, stashing it in dev mode
+    ///
+    /// Build tools often delete a library before writing the new one, so in
+    /// dev mode the plugin comes back with its state once the file returns.
+    pub async fn unload_removed_plugin(&mut self, name: &str) -> Result<()> {
+        if !self.dev_mode {
+            return self.unload_plugin(name).await;
+        }
+
+        let idx = self
+            .plugins
+            .iter()
+            .position(|p| p.plugin.metadata().name == name)
+            .ok_or_else(|| PluginError::NotFound(name.to_string()))?;
+
+        let managed = self.plugins.remove(idx);
+        let config = managed.config.clone();
+        let snapshot = managed.plugin.snapshot_state();
+        self.shutdown_plugin(managed).await;
+        self.refresh_commands();
+
+        if !config.path.as_os_str().is_empty() {
+            self.dev_stash
+                .insert(config.expanded_path(), StashedPlugin { config, snapshot });
+        }
+        Ok(())
+    }
+
     /// Unload all plugins
     pub async fn unload_all(&mut self) -> Result<()> {
         log::info!("👋 Saying goodbye to {} plugins...", self.plugins.len());
@@ -832,6 +1798,15 @@ impl PluginManager {
                     log::error!("⏱️  Plugin '{}' unload timed out", plugin_name);
                 }
             }
+            self.context
+                .tasks
+                .cancel_owner(&managed.plugin.metadata().name);
+        }
+
+        self.process_pending_commands().await;
+        for managed in &self.plugins {
+            self.clear_status_segments(&managed.plugin.metadata().name)
+                .await;
         }
 
         self.plugins.clear();
@@ -848,7 +1823,7 @@ impl PluginManager {
             }
 
             let plugin_name = managed.plugin.metadata().display_name();
-            let ctx = self.context.clone();
+            let ctx = managed.context.clone();
 
             let result = timeout(
                 self.hook_timeout,
@@ -875,8 +1850,219 @@ impl PluginManager {
         }
 
         // Process pending commands (e.g. if command triggers another UI update)
-        self.process_pending_commands().await;
+        self.flush_commands().await;
+
+        Ok(())
+    }
 
+    /// Record the user's answer to a permission prompt
+    ///
+    /// The plugin is not told; its next attempt succeeds or fails with the
+    /// decision. Other clients showing the prompt are told to close it.
+    pub async fn resolve_permission(
+        &self,
+        prompt_id: u64,
+        decision: PermissionDecision,
+    ) -> Result<()> {
+        let Some(permissions) = &self.context.permissions else {
+            return Ok(());
+        };
+        match permissions.resolve(prompt_id, decision)? {
+            Some(request) => {
+                log::info!(
+                    "User answered {:?} for plugin '{}' using {}",
+                    decision,
+                    request.plugin_name,
+                    capability_key(&request.capability)
+                );
+                self.client_registry
+                    .broadcast(DaemonMessage::PermissionPromptClosed { prompt_id })
+                    .await;
+            }
+            None => log::debug!("Ignoring answer to unknown permission prompt {}", prompt_id),
+        }
         Ok(())
     }
+
+    /// Deny permission prompts nobody answered in time and close them
+    ///
+    /// Call this about every [`PERMISSION_EXPIRY_INTERVAL`].
+    pub async fn expire_permission_prompts(&self) {
+        let Some(permissions) = &self.context.permissions else {
+            return;
+        };
+        for request in permissions.expire(Instant::now()) {
+            log::warn!(
+                "Nobody answered whether plugin '{}' may use {}; denying it",
+                request.plugin_name,
+                capability_key(&request.capability)
+            );
+            self.client_registry
+                .broadcast(DaemonMessage::PermissionPromptClosed {
+                    prompt_id: request.prompt_id,
+                })
+                .await;
+        }
+    }
+
+    /// Route a prompt answer to the plugin that opened the prompt
+    ///
+    /// Each prompt is answered once; later responses with the same ID, or
+    /// responses for prompts no plugin opened, are ignored.
+    pub async fn dispatch_prompt_response(
+        &mut self,
+        prompt_id: u64,
+        values: Option<Vec<String>>,
+    ) -> Result<()> {
+        let Some(owner) = self.prompt_owners.lock().remove(&prompt_id) else {
+            log::debug!("Ignoring response to unknown prompt {}", prompt_id);
+            return Ok(());
+        };
+        let response = PromptResponse { prompt_id, values };
+
+        if let Some(managed) = self
+            .plugins
+            .iter_mut()
+            .find(|managed| managed.enabled && managed.plugin.metadata().name == owner)
+        {
+            let plugin_name = managed.plugin.metadata().display_name();
+            let ctx = managed.context.clone();
+
+            let result = timeout(
+                self.hook_timeout,
+                managed.plugin.on_prompt_response(&response, &ctx),
+            )
+            .await;
+
+            match result {
+                Ok(Ok(_)) => managed.record_success(),
+                Ok(Err(e)) => {
+                    log::error!(
+                        "{} Plugin '{}' prompt response hook failed: {}",
+                        managed.mood().emoji(),
+                        plugin_name,
+                        e
+                    );
+                    managed.record_failure();
+                }
+                Err(_) => {
+                    log::error!(
+                        "⏱️  Plugin '{}' prompt response hook timed out",
+                        plugin_name
+                    );
+                    managed.record_failure();
+                }
+            }
+        }
+
+        self.flush_commands().await;
+
+        Ok(())
+    }
+
+    /// Tell a plugin the user picked an item of one of its panels
+    pub async fn dispatch_panel_selection(
+        &mut self,
+        plugin_name: &str,
+        overlay_id: u64,
+        item_id: &str,
+    ) -> Result<()> {
+        let Some(managed) = self
+            .plugins
+            .iter_mut()
+            .find(|managed| managed.enabled && managed.plugin.metadata().name == plugin_name)
+        else {
+            log::debug!(
+                "Ignoring selection in panel {} of unknown plugin '{}'",
+                overlay_id,
+                plugin_name
+            );
+            return Ok(());
+        };
+
+        let display_name = managed.plugin.metadata().display_name();
+        let ctx = managed.context.clone();
+
+        let result = timeout(
+            self.hook_timeout,
+            managed
+                .plugin
+                .on_panel_item_selected(overlay_id, item_id, &ctx),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(_)) => managed.record_success(),
+            Ok(Err(e)) => {
+                log::error!(
+                    "{} Plugin '{}' panel selection hook failed: {}",
+                    managed.mood().emoji(),
+                    display_name,
+                    e
+                );
+                managed.record_failure();
+            }
+            Err(_) => {
+                log::error!(
+                    "⏱️  Plugin '{}' panel selection hook timed out",
+                    display_name
+                );
+                managed.record_failure();
+            }
+        }
+
+        self.flush_commands().await;
+
+        Ok(())
+    }
+}
+
+/// Settings for a plugin: its `plugins.toml` entry overlaid with the user's
+/// `[plugins.settings.<name>]` table, checked against the plugin's schema
+fn resolve_settings(
+    user_settings: &scarab_config::PluginConfig,
+    plugin: &dyn Plugin,
+    config: &PluginConfig,
+) -> Result<PluginConfigData> {
+    let mut values = config.config.data.clone();
+    if let Some(table) = user_settings.settings_for(&plugin.metadata().name) {
+        values.extend(table.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    plugin.config_schema().validate(&values)
+}
+
+/// Keep the capabilities granted in `plugins.toml` that the plugin declares
+///
+/// A grant for a capability the plugin never asked for is dropped.
+fn grant_declared_capabilities(config: &mut PluginConfig, metadata: &PluginMetadata) {
+    config.capabilities.retain(|capability| {
+        let declared = metadata.capabilities.contains(capability);
+        if !declared {
+            log::warn!(
+                "🚫 Not granting '{}' to plugin '{}': it does not declare it",
+                capability_key(capability),
+                config.name
+            );
+        }
+        declared
+    });
+}
+
+/// Hand a state snapshot to a freshly loaded plugin instance
+async fn restore_snapshot(managed: &mut ManagedPlugin, state: &[u8], hook_timeout: Duration) {
+    let name = managed.plugin.metadata().name.clone();
+    let ctx = managed.context.clone();
+    match timeout(hook_timeout, managed.plugin.restore_state(state, &ctx)).await {
+        Ok(Ok(_)) => log::debug!("Restored state for plugin '{}'", name),
+        Ok(Err(e)) => log::warn!("Plugin '{}' failed to restore state: {}", name, e),
+        Err(_) => log::warn!("⏱️  Plugin '{}' state restore timed out", name),
+    }
+}
+
+/// Whether `path` is a native shared library
+fn is_native_library(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("so") | Some("dylib") | Some("dll")
+    )
 }
@@ -0,0 +1,237 @@
+//! Native shared-library plugin loader
+//!
+//! Native plugins are `cdylib` crates that export the entry point declared by
+//! [`scarab_plugin_api::declare_plugin!`]. To make hot reload reliable, the
+//! library is copied to a unique shadow path in Scarab's data directory
+//! before `dlopen`, so rebuilding the original file never aliases an
+//! already-mapped image.
+
+use async_trait::async_trait;
+use libloading::Library;
+use scarab_plugin_api::{
+    key_tables::KeyCombo,
+    menu::MenuItem,
+    types::{ModalItem, MouseEvent, PluginKeyBinding, PluginMessage, PromptResponse},
+    Action, ConfigSchema, NativePluginCreate, Plugin, PluginContext, PluginError, PluginMetadata,
+    Result, NATIVE_PLUGIN_ENTRY,
+};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Plugin loaded from a native shared library
+///
+/// Field order matters: the plugin instance must be dropped before the library
+/// that contains its code and vtable is unmapped.
+pub struct NativePlugin {
+    plugin: Box<dyn Plugin>,
+    _library: Library,
+    shadow_path: PathBuf,
+}
+
+impl NativePlugin {
+    /// Load a native plugin from a shared library, copying it into
+    /// `shadow_dir` first
+    pub fn load(path: &Path, shadow_dir: &Path) -> Result<Self> {
+        let shadow_path = Self::shadow_copy(path, shadow_dir)?;
+
+        // SAFETY: loading a library runs its initializers. Plugins are trusted
+        // code the user placed in their plugin directory.
+        let library = unsafe { Library::new(&shadow_path) }.map_err(|e| {
+            let _ = std::fs::remove_file(&shadow_path);
+            PluginError::LoadError(format!("Failed to open native plugin {:?}: {}", path, e))
+        })?;
+
+        // SAFETY: the symbol type matches the one generated by `declare_plugin!`,
+        // and the returned box is leaked to us exactly once.
+        let plugin = unsafe {
+            let create = library
+                .get::<NativePluginCreate>(NATIVE_PLUGIN_ENTRY)
+                .map_err(|e| {
+                    PluginError::LoadError(format!(
+                        "Native plugin {:?} does not export a Scarab entry point: {}",
+                        path, e
+                    ))
+                })?;
+            let raw = create();
+            if raw.is_null() {
+                return Err(PluginError::LoadError(format!(
+                    "Native plugin {:?} returned a null instance",
+                    path
+                )));
+            }
+            *Box::from_raw(raw)
+        };
+
+        Ok(Self {
+            plugin,
+            _library: library,
+            shadow_path,
+        })
+    }
+
+    /// Copy the library to a unique path in `dir`
+    fn shadow_copy(path: &Path, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("plugin");
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("so");
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+
+        let shadow = dir.join(format!("{}-{}-{}.{}", stem, std::process::id(), nanos, ext));
+        std::fs::copy(path, &shadow)?;
+        Ok(shadow)
+    }
+}
+
+impl Drop for NativePlugin {
+    fn drop(&mut self) {
+        // The library is still mapped here; the file itself can go
+        if let Err(e) = std::fs::remove_file(&self.shadow_path) {
+            log::debug!("Failed to remove shadow copy {:?}: {}", self.shadow_path, e);
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for NativePlugin {
+    fn metadata(&self) -> &PluginMetadata {
+        self.plugin.metadata()
+    }
+
+    fn get_menu(&self) -> Vec<MenuItem> {
+        self.plugin.get_menu()
+    }
+
+    fn get_commands(&self) -> Vec<ModalItem> {
+        self.plugin.get_commands()
+    }
+
+    fn get_keybindings(&self) -> Vec<PluginKeyBinding> {
+        self.plugin.get_keybindings()
+    }
+
+    fn config_schema(&self) -> ConfigSchema {
+        self.plugin.config_schema()
+    }
+
+    async fn on_load(&mut self, ctx: &mut PluginContext) -> Result<()> {
+        self.plugin.on_load(ctx).await
+    }
+
+    async fn on_unload(&mut self) -> Result<()> {
+        self.plugin.on_unload().await
+    }
+
+    async fn on_output(&mut self, line: &str, ctx: &PluginContext) -> Result<Action> {
+        self.plugin.on_output(line, ctx).await
+    }
+
+    async fn on_input(&mut self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
+        self.plugin.on_input(input, ctx).await
+    }
+
+    async fn on_key(&mut self, key: KeyCombo, ctx: &PluginContext) -> Result<Action> {
+        self.plugin.on_key(key, ctx).await
+    }
+
+    async fn on_mouse(&mut self, event: &MouseEvent, ctx: &PluginContext) -> Result<Action> {
+        self.plugin.on_mouse(event, ctx).await
+    }
+
+    async fn on_pre_command(&mut self, command: &str, ctx: &PluginContext) -> Result<Action> {
+        self.plugin.on_pre_command(command, ctx).await
+    }
+
+    async fn on_post_command(
+        &mut self,
+        command: &str,
+        exit_code: i32,
+        ctx: &PluginContext,
+    ) -> Result<()> {
+        self.plugin.on_post_command(command, exit_code, ctx).await
+    }
+
+    async fn on_resize(&mut self, cols: u16, rows: u16, ctx: &PluginContext) -> Result<()> {
+        self.plugin.on_resize(cols, rows, ctx).await
+    }
+
+    async fn on_attach(&mut self, client_id: u64, ctx: &PluginContext) -> Result<()> {
+        self.plugin.on_attach(client_id, ctx).await
+    }
+
+    async fn on_detach(&mut self, client_id: u64, ctx: &PluginContext) -> Result<()> {
+        self.plugin.on_detach(client_id, ctx).await
+    }
+
+    async fn on_message(&mut self, message: &PluginMessage, ctx: &PluginContext) -> Result<()> {
+        self.plugin.on_message(message, ctx).await
+    }
+
+    async fn on_prompt_response(
+        &mut self,
+        response: &PromptResponse,
+        ctx: &PluginContext,
+    ) -> Result<()> {
+        self.plugin.on_prompt_response(response, ctx).await
+    }
+
+    async fn on_panel_item_selected(
+        &mut self,
+        overlay_id: u64,
+        item_id: &str,
+        ctx: &PluginContext,
+    ) -> Result<()> {
+        self.plugin
+            .on_panel_item_selected(overlay_id, item_id, ctx)
+            .await
+    }
+
+    async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
+        self.plugin.on_remote_command(id, ctx).await
+    }
+
+    async fn on_config_changed(&mut self, ctx: &PluginContext) -> Result<()> {
+        self.plugin.on_config_changed(ctx).await
+    }
+
+    fn snapshot_state(&self) -> Option<Vec<u8>> {
+        self.plugin.snapshot_state()
+    }
+
+    async fn restore_state(&mut self, state: &[u8], ctx: &PluginContext) -> Result<()> {
+        self.plugin.restore_state(state, ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = NativePlugin::load(Path::new("/nonexistent/libplugin.so"), dir.path());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_load_invalid_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("libbogus.so");
+        std::fs::write(&path, b"not an elf").unwrap();
+
+        let shadow_dir = dir.path().join("shadow");
+        match NativePlugin::load(&path, &shadow_dir) {
+            Err(PluginError::LoadError(_)) => {}
+            other => panic!("expected LoadError, got {:?}", other.err()),
+        }
+        // The shadow copy goes once the library fails to open
+        assert_eq!(std::fs::read_dir(&shadow_dir).unwrap().count(), 0);
+    }
+}
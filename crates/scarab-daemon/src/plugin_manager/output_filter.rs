@@ -0,0 +1,75 @@
+//! Output filters declared in plugin metadata
+//!
+//! Plugins that only care about some lines (build errors, prompts, URLs)
+//! declare prefixes or regular expressions, and `on_output` is only called
+//! for lines matching one of them. The regular expressions are compiled
+//! once when the plugin is loaded.
+
+use regex::RegexSet;
+use scarab_plugin_api::{OutputFilter, PluginError, Result};
+
+/// Compiled output filters of one plugin
+#[derive(Debug, Clone)]
+pub struct OutputFilterSet {
+    prefixes: Vec<String>,
+    patterns: RegexSet,
+}
+
+impl OutputFilterSet {
+    /// Compile a plugin's filters, or `None` if it wants every line
+    pub fn compile(filters: &[OutputFilter]) -> Result<Option<Self>> {
+        if filters.is_empty() {
+            return Ok(None);
+        }
+
+        let mut prefixes = Vec::new();
+        let mut patterns = Vec::new();
+        for filter in filters {
+            match filter {
+                OutputFilter::Prefix(prefix) => prefixes.push(prefix.clone()),
+                OutputFilter::Regex(pattern) => patterns.push(pattern.as_str()),
+            }
+        }
+
+        let patterns = RegexSet::new(patterns)
+            .map_err(|e| PluginError::InvalidMetadata(format!("invalid output filter: {}", e)))?;
+        Ok(Some(Self { prefixes, patterns }))
+    }
+
+    /// Check whether a line should be passed to the plugin
+    pub fn matches(&self, line: &str) -> bool {
+        self.prefixes
+            .iter()
+            .any(|prefix| line.starts_with(prefix.as_str()))
+            || self.patterns.is_match(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefixes_and_patterns() {
+        let filters = OutputFilterSet::compile(&[
+            OutputFilter::Prefix("error".into()),
+            OutputFilter::Regex(r"https?://\S+".into()),
+        ])
+        .unwrap()
+        .unwrap();
+
+        assert!(filters.matches("error[E0308]: mismatched types"));
+        assert!(filters.matches("see https://example.com for details"));
+        assert!(!filters.matches("   Compiling scarab v0.3.3"));
+        assert!(!filters.matches("warning: error in docs"));
+    }
+
+    #[test]
+    fn test_no_filters_and_invalid_pattern() {
+        assert!(OutputFilterSet::compile(&[]).unwrap().is_none());
+        assert!(matches!(
+            OutputFilterSet::compile(&[OutputFilter::Regex("(".into())]),
+            Err(PluginError::InvalidMetadata(_))
+        ));
+    }
+}
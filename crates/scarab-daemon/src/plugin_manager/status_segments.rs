@@ -0,0 +1,182 @@
+//! Rate limiting and bookkeeping for plugin status bar segments
+//!
+//! A plugin updating its segment on every output line would flood clients
+//! with redraws, so each segment is forwarded at most once per interval.
+//! Updates arriving sooner replace a pending one, which goes out once the
+//! interval has passed, so the client always ends up with the latest
+//! content. The segments shown for each plugin are remembered so they can
+//! be removed when the plugin is unloaded.
+
+use scarab_protocol::DaemonMessage;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Shortest time between two forwarded updates of one segment
+pub const STATUS_SEGMENT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Status segments shown by plugins, keyed by plugin name and item ID
+#[derive(Debug)]
+pub struct StatusSegments {
+    min_interval: Duration,
+    /// When each shown segment was last forwarded
+    last_sent: HashMap<(String, u64), Instant>,
+    /// Latest update held back by the rate limit
+    pending: HashMap<(String, u64), DaemonMessage>,
+}
+
+impl Default for StatusSegments {
+    fn default() -> Self {
+        Self::new(STATUS_SEGMENT_INTERVAL)
+    }
+}
+
+impl StatusSegments {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_sent: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Offer an update of a segment, returning it if it may be sent now
+    ///
+    /// Otherwise it replaces any pending update of the same segment.
+    pub fn offer(
+        &mut self,
+        plugin: &str,
+        item_id: u64,
+        message: DaemonMessage,
+        now: Instant,
+    ) -> Option<DaemonMessage> {
+        let key = (plugin.to_string(), item_id);
+        if let Some(last) = self.last_sent.get(&key) {
+            if now.duration_since(*last) < self.min_interval {
+                self.pending.insert(key, message);
+                return None;
+            }
+        }
+        self.pending.remove(&key);
+        self.last_sent.insert(key, now);
+        Some(message)
+    }
+
+    /// Take the pending updates whose interval has passed
+    pub fn due(&mut self, now: Instant) -> Vec<DaemonMessage> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+
+        let ready: Vec<(String, u64)> = self
+            .pending
+            .keys()
+            .filter(|key| match self.last_sent.get(*key) {
+                Some(last) => now.duration_since(*last) >= self.min_interval,
+                None => true,
+            })
+            .cloned()
+            .collect();
+
+        ready
+            .into_iter()
+            .filter_map(|key| {
+                let message = self.pending.remove(&key)?;
+                self.last_sent.insert(key, now);
+                Some(message)
+            })
+            .collect()
+    }
+
+    /// Forget a segment the plugin removed, dropping any pending update
+    pub fn remove(&mut self, plugin: &str, item_id: u64) {
+        let key = (plugin.to_string(), item_id);
+        self.pending.remove(&key);
+        self.last_sent.remove(&key);
+    }
+
+    /// Forget every segment of a plugin, returning their IDs
+    pub fn remove_plugin(&mut self, plugin: &str) -> Vec<u64> {
+        self.pending.retain(|(owner, _), _| owner != plugin);
+        let mut ids = Vec::new();
+        self.last_sent.retain(|(owner, item_id), _| {
+            if owner == plugin {
+                ids.push(*item_id);
+                false
+            } else {
+                true
+            }
+        });
+        ids.sort_unstable();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(plugin: &str, item_id: u64, text: &str) -> DaemonMessage {
+        DaemonMessage::StatusSegment {
+            plugin_name: plugin.to_string(),
+            item_id,
+            side: scarab_protocol::StatusBarSide::Right,
+            priority: 0,
+            items: vec![scarab_protocol::StatusRenderItem::Text(text.to_string())],
+        }
+    }
+
+    fn text(message: &DaemonMessage) -> &str {
+        match message {
+            DaemonMessage::StatusSegment { items, .. } => match &items[0] {
+                scarab_protocol::StatusRenderItem::Text(text) => text,
+                _ => panic!("Expected text"),
+            },
+            _ => panic!("Expected StatusSegment"),
+        }
+    }
+
+    #[test]
+    fn test_updates_are_coalesced() {
+        let mut segments = StatusSegments::new(Duration::from_millis(100));
+        let start = Instant::now();
+
+        assert!(segments
+            .offer("git", 1, update("git", 1, "a"), start)
+            .is_some());
+        // Too soon: held back, and replaced by the next update
+        assert!(segments
+            .offer("git", 1, update("git", 1, "b"), start)
+            .is_none());
+        assert!(segments
+            .offer("git", 1, update("git", 1, "c"), start)
+            .is_none());
+        // Other segments are limited separately
+        assert!(segments
+            .offer("git", 2, update("git", 2, "x"), start)
+            .is_some());
+
+        assert!(segments.due(start + Duration::from_millis(50)).is_empty());
+        let due = segments.due(start + Duration::from_millis(100));
+        assert_eq!(due.len(), 1);
+        assert_eq!(text(&due[0]), "c");
+        assert!(segments.due(start + Duration::from_millis(300)).is_empty());
+    }
+
+    #[test]
+    fn test_remove_drops_pending_updates() {
+        let mut segments = StatusSegments::new(Duration::from_millis(100));
+        let start = Instant::now();
+
+        segments.offer("git", 1, update("git", 1, "a"), start);
+        segments.offer("git", 1, update("git", 1, "b"), start);
+        segments.remove("git", 1);
+        assert!(segments.due(start + Duration::from_secs(1)).is_empty());
+
+        segments.offer("git", 3, update("git", 3, "a"), start);
+        segments.offer("git", 2, update("git", 2, "a"), start);
+        segments.offer("clock", 1, update("clock", 1, "a"), start);
+        assert_eq!(segments.remove_plugin("git"), vec![2, 3]);
+        assert!(segments.remove_plugin("git").is_empty());
+        assert_eq!(segments.remove_plugin("clock"), vec![1]);
+    }
+}
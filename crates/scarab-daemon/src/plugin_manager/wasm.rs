@@ -0,0 +1,800 @@
+//! WebAssembly plugin loader
+//!
+//! `.wasm` plugins are WASI (preview 1) modules run in wasmtime. Unlike
+//! native plugins they need no matching rustc version, and a misbehaving
+//! plugin cannot corrupt daemon memory: each instance gets its own linear
+//! memory, a memory cap, and a fuel budget per hook call.
+//!
+//! # Guest ABI
+//!
+//! Data crosses the boundary as UTF-8 JSON. A buffer returned to the host is
+//! packed into a `u64` as `(ptr << 32) | len`; `0` means no value.
+//!
+//! The module exports:
+//!
+//! - `memory`
+//! - `scarab_alloc(len: u32) -> u32`, which the host uses to pass buffers in
+//! - `scarab_free(ptr: u32, len: u32)`, optional, called for every buffer the
+//!   host is done with
+//! - `scarab_metadata() -> u64`, returning the plugin's [`WasmManifest`]
+//! - `scarab_hook(ptr: u32, len: u32) -> u64`, called with a hook event such
+//!   as `{"hook":"output","line":"..."}` and returning a result such as
+//!   `{"action":"modify","data":"..."}`, or `0` to continue
+//!
+//! and may import `scarab.host_call(ptr: u32, len: u32) -> u64` to reach the
+//! [`PluginContext`] during a hook, e.g. `{"fn":"get_line","y":0}`.
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use scarab_plugin_api::{
+    context::{LogLevel, NotifyLevel, PluginConfigData},
+    key_tables::{parse_key_combo, KeyCombo},
+    status_bar::{RenderItem, StatusBarSide},
+    types::{
+        ModalItem, MouseButton, MouseEvent, MouseEventKind, PluginKeyBinding, PluginMessage,
+        PromptResponse,
+    },
+    Action, ConfigSchema, Plugin, PluginContext, PluginError, PluginMetadata, Result,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use wasmtime::{
+    Caller, Config, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    TypedFunc,
+};
+use wasmtime_wasi::{preview1::WasiP1Ctx, WasiCtxBuilder};
+
+/// Instructions a guest may execute per call before it is trapped
+const FUEL_PER_CALL: u64 = 500_000_000;
+
+/// Largest linear memory a guest may grow to
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Metadata returned by `scarab_metadata`
+#[derive(Debug, Deserialize)]
+pub struct WasmManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub author: String,
+    pub homepage: Option<String>,
+    pub api_version: Option<String>,
+    pub min_scarab_version: Option<String>,
+    pub emoji: Option<String>,
+    pub color: Option<String>,
+    pub catchphrase: Option<String>,
+    #[serde(default)]
+    pub subscriptions: Vec<String>,
+    /// Only pass output lines starting with one of these to `on_output`
+    #[serde(default)]
+    pub output_prefixes: Vec<String>,
+    /// Only pass output lines matching one of these regexes to `on_output`
+    #[serde(default)]
+    pub output_patterns: Vec<String>,
+    /// Entries for the command palette
+    #[serde(default)]
+    pub commands: Vec<WasmCommand>,
+    /// Settings read from the plugin's `[plugins.settings.<name>]` table
+    #[serde(default)]
+    pub config: ConfigSchema,
+}
+
+/// Command palette entry declared by a WASM plugin
+#[derive(Debug, Deserialize)]
+pub struct WasmCommand {
+    pub id: String,
+    pub label: String,
+    pub description: Option<String>,
+    /// Default key for the command, e.g. `"Ctrl+Alt+g"`
+    pub key: Option<String>,
+}
+
+impl WasmManifest {
+    fn keybindings(&self) -> Vec<PluginKeyBinding> {
+        self.commands
+            .iter()
+            .filter_map(|c| {
+                let key = c.key.as_deref()?;
+                match parse_key_combo(key) {
+                    Some(combo) => Some(PluginKeyBinding::new(combo, c.id.clone())),
+                    None => {
+                        log::warn!("Plugin '{}' has an invalid key '{}'", self.name, key);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn into_metadata(self) -> (PluginMetadata, Vec<ModalItem>) {
+        let mut metadata =
+            PluginMetadata::new(self.name, self.version, self.description, self.author);
+        if let Some(homepage) = self.homepage {
+            metadata = metadata.with_homepage(homepage);
+        }
+        if let Some(version) = self.api_version {
+            metadata = metadata.with_api_version(version);
+        }
+        if let Some(version) = self.min_scarab_version {
+            metadata = metadata.with_min_scarab_version(version);
+        }
+        if let Some(emoji) = self.emoji {
+            metadata = metadata.with_emoji(emoji);
+        }
+        if let Some(color) = self.color {
+            metadata = metadata.with_color(color);
+        }
+        if let Some(catchphrase) = self.catchphrase {
+            metadata = metadata.with_catchphrase(catchphrase);
+        }
+        for topic in self.subscriptions {
+            metadata = metadata.with_subscription(topic);
+        }
+        for prefix in self.output_prefixes {
+            metadata = metadata.with_output_prefix(prefix);
+        }
+        for pattern in self.output_patterns {
+            metadata = metadata.with_output_regex(pattern);
+        }
+
+        let commands = self
+            .commands
+            .into_iter()
+            .map(|c| ModalItem {
+                id: c.id,
+                label: c.label,
+                description: c.description,
+            })
+            .collect();
+        (metadata, commands)
+    }
+}
+
+/// Hook event passed to `scarab_hook`
+#[derive(Serialize)]
+#[serde(tag = "hook", rename_all = "snake_case")]
+enum HookEvent<'a> {
+    Load,
+    Unload,
+    Output {
+        line: &'a str,
+    },
+    Input {
+        data: &'a [u8],
+    },
+    Key {
+        key: &'a KeyCombo,
+    },
+    Mouse {
+        kind: &'static str,
+        button: &'static str,
+        col: u16,
+        row: u16,
+        modifiers: u8,
+        click_count: u8,
+    },
+    PreCommand {
+        command: &'a str,
+    },
+    PostCommand {
+        command: &'a str,
+        exit_code: i32,
+    },
+    Resize {
+        cols: u16,
+        rows: u16,
+    },
+    Attach {
+        client_id: u64,
+    },
+    Detach {
+        client_id: u64,
+    },
+    Message {
+        message: &'a PluginMessage,
+    },
+    PromptResponse {
+        prompt_id: u64,
+        values: &'a Option<Vec<String>>,
+    },
+    RemoteCommand {
+        id: &'a str,
+    },
+    ConfigChanged {
+        config: &'a PluginConfigData,
+    },
+    Snapshot,
+    Restore {
+        state: Value,
+    },
+}
+
+/// Result returned by `scarab_hook`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct HookResult {
+    action: HookAction,
+    /// Replacement for `modify`, as text or bytes
+    data: Option<HookData>,
+    /// Failure reported by the plugin
+    error: Option<String>,
+    /// Payload of a `snapshot` hook
+    state: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum HookAction {
+    #[default]
+    Continue,
+    Stop,
+    Modify,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum HookData {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl HookResult {
+    fn into_action(self) -> Result<Action> {
+        if let Some(error) = self.error {
+            return Err(PluginError::Other(anyhow::anyhow!(error)));
+        }
+        Ok(match self.action {
+            HookAction::Continue => Action::Continue,
+            HookAction::Stop => Action::Stop,
+            HookAction::Modify => Action::Modify(match self.data {
+                Some(HookData::Text(text)) => text.into_bytes(),
+                Some(HookData::Bytes(bytes)) => bytes,
+                None => Vec::new(),
+            }),
+        })
+    }
+
+    fn into_unit(self) -> Result<()> {
+        self.into_action().map(|_| ())
+    }
+}
+
+/// Request made through `scarab.host_call`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "fn", rename_all = "snake_case")]
+enum HostCall {
+    Log {
+        level: String,
+        message: String,
+    },
+    Notify {
+        title: String,
+        body: String,
+        level: Option<String>,
+    },
+    Emit {
+        topic: String,
+        payload: Value,
+    },
+    GetLine {
+        y: u16,
+    },
+    GetSize,
+    GetCursor,
+    GetEnv {
+        key: String,
+    },
+    GetData {
+        key: String,
+    },
+    GetConfig,
+    SetData {
+        key: String,
+        value: String,
+    },
+    GetScrollbackLen,
+    GetScrollbackRange {
+        start: usize,
+        end: usize,
+    },
+    ShowInputPrompt {
+        title: String,
+        #[serde(default)]
+        placeholder: String,
+        #[serde(default)]
+        masked: bool,
+    },
+    SetStatusSegment {
+        id: u64,
+        side: StatusBarSide,
+        #[serde(default)]
+        priority: i32,
+        items: Vec<RenderItem>,
+    },
+    RemoveStatusSegment {
+        id: u64,
+    },
+}
+
+impl HostCall {
+    fn run(self, ctx: &PluginContext) -> Value {
+        match self {
+            HostCall::Log { level, message } => {
+                let level = match level.as_str() {
+                    "error" => LogLevel::Error,
+                    "warn" => LogLevel::Warn,
+                    "debug" => LogLevel::Debug,
+                    _ => LogLevel::Info,
+                };
+                ctx.log(level, &message);
+                Value::Null
+            }
+            HostCall::Notify { title, body, level } => {
+                let level = match level.as_deref() {
+                    Some("error") => NotifyLevel::Error,
+                    Some("warning") => NotifyLevel::Warning,
+                    Some("success") => NotifyLevel::Success,
+                    _ => NotifyLevel::Info,
+                };
+                ctx.notify(&title, &body, level);
+                Value::Null
+            }
+            HostCall::Emit { topic, payload } => {
+                ctx.emit(topic, payload);
+                Value::Null
+            }
+            HostCall::GetLine { y } => json!(ctx.get_line(y)),
+            HostCall::GetSize => {
+                let (cols, rows) = ctx.get_size();
+                json!({ "cols": cols, "rows": rows })
+            }
+            HostCall::GetCursor => {
+                let (x, y) = ctx.get_cursor();
+                json!({ "x": x, "y": y })
+            }
+            HostCall::GetEnv { key } => json!(ctx.get_env(&key)),
+            HostCall::GetData { key } => json!(ctx.get_data(&key)),
+            HostCall::GetConfig => json!(ctx.config()),
+            HostCall::SetData { key, value } => {
+                ctx.set_data(key, value);
+                Value::Null
+            }
+            HostCall::GetScrollbackLen => json!(ctx.get_scrollback_len()),
+            HostCall::GetScrollbackRange { start, end } => {
+                json!(ctx.get_scrollback_range(start, end))
+            }
+            HostCall::ShowInputPrompt {
+                title,
+                placeholder,
+                masked,
+            } => json!(ctx.show_input_prompt(title, placeholder, masked)),
+            HostCall::SetStatusSegment {
+                id,
+                side,
+                priority,
+                items,
+            } => {
+                ctx.set_status_segment(id, side, priority, items);
+                Value::Null
+            }
+            HostCall::RemoveStatusSegment { id } => {
+                ctx.remove_status_segment(id);
+                Value::Null
+            }
+        }
+    }
+}
+
+/// Store data for one instance
+struct HostState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+    /// Context of the hook being run, for host calls
+    ctx: Option<PluginContext>,
+}
+
+/// A running module and the exports the host calls
+struct WasmInstance {
+    store: Store<HostState>,
+    memory: Memory,
+    alloc: TypedFunc<u32, u32>,
+    free: Option<TypedFunc<(u32, u32), ()>>,
+    hook: TypedFunc<(u32, u32), u64>,
+}
+
+impl WasmInstance {
+    /// Run one hook with a JSON-encoded event, giving host calls access to `ctx`
+    fn call(&mut self, ctx: Option<PluginContext>, input: &[u8]) -> Result<HookResult> {
+        if ctx.is_some() {
+            self.store.data_mut().ctx = ctx;
+        }
+        self.store.set_fuel(FUEL_PER_CALL)?;
+
+        let ptr = self.alloc.call(&mut self.store, input.len() as u32)?;
+        self.memory
+            .write(&mut self.store, ptr as usize, input)
+            .map_err(anyhow::Error::from)?;
+        let packed = self.hook.call(&mut self.store, (ptr, input.len() as u32))?;
+        self.release(ptr, input.len() as u32)?;
+
+        let output = self.take_output(packed)?;
+        if output.is_empty() {
+            return Ok(HookResult::default());
+        }
+        serde_json::from_slice(&output)
+            .map_err(|e| PluginError::Other(anyhow::anyhow!("Invalid WASM hook result: {}", e)))
+    }
+
+    /// Copy a packed guest buffer out of linear memory and free it
+    fn take_output(&mut self, packed: u64) -> Result<Vec<u8>> {
+        if packed == 0 {
+            return Ok(Vec::new());
+        }
+        let (ptr, len) = unpack(packed);
+        let mut output = vec![0; len as usize];
+        self.memory
+            .read(&self.store, ptr as usize, &mut output)
+            .map_err(anyhow::Error::from)?;
+        self.release(ptr, len)?;
+        Ok(output)
+    }
+
+    fn release(&mut self, ptr: u32, len: u32) -> Result<()> {
+        if let Some(free) = &self.free {
+            free.call(&mut self.store, (ptr, len))?;
+        }
+        Ok(())
+    }
+}
+
+/// Plugin running in a wasmtime sandbox
+pub struct WasmPlugin {
+    metadata: PluginMetadata,
+    commands: Vec<ModalItem>,
+    keybindings: Vec<PluginKeyBinding>,
+    config_schema: ConfigSchema,
+    instance: Arc<Mutex<WasmInstance>>,
+}
+
+impl WasmPlugin {
+    /// Load a `.wasm` plugin from disk
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes).map_err(|e| {
+            PluginError::LoadError(format!("Failed to load WASM plugin {:?}: {:#}", path, e))
+        })
+    }
+
+    /// Instantiate a plugin from module bytes (binary or text format)
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, bytes)?;
+
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |state: &mut HostState| {
+            &mut state.wasi
+        })?;
+        linker.func_wrap("scarab", "host_call", host_call)?;
+
+        // No preopened directories or sockets; only stderr for debugging
+        let state = HostState {
+            wasi: WasiCtxBuilder::new().inherit_stderr().build_p1(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_BYTES)
+                .build(),
+            ctx: None,
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CALL)?;
+
+        let instance = linker.instantiate(&mut store, &module)?;
+        // Reactor modules run their constructors here
+        if let Ok(initialize) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+            initialize.call(&mut store, ())?;
+        }
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("module does not export `memory`"))?;
+        let alloc = instance.get_typed_func(&mut store, "scarab_alloc")?;
+        let free = instance.get_typed_func(&mut store, "scarab_free").ok();
+        let hook = instance.get_typed_func(&mut store, "scarab_hook")?;
+        let metadata_fn = instance.get_typed_func::<(), u64>(&mut store, "scarab_metadata")?;
+
+        let mut instance = WasmInstance {
+            store,
+            memory,
+            alloc,
+            free,
+            hook,
+        };
+        let packed = metadata_fn.call(&mut instance.store, ())?;
+        let manifest: WasmManifest = serde_json::from_slice(&instance.take_output(packed)?)?;
+        let keybindings = manifest.keybindings();
+        let config_schema = manifest.config.clone();
+        let (metadata, commands) = manifest.into_metadata();
+
+        Ok(Self {
+            metadata,
+            commands,
+            keybindings,
+            config_schema,
+            instance: Arc::new(Mutex::new(instance)),
+        })
+    }
+
+    /// Run a hook on the blocking pool, since a guest may use its whole
+    /// fuel budget before returning
+    async fn call(&self, ctx: Option<&PluginContext>, event: HookEvent<'_>) -> Result<HookResult> {
+        let input = encode_event(&event)?;
+        let instance = Arc::clone(&self.instance);
+        let ctx = ctx.cloned();
+        tokio::task::spawn_blocking(move || instance.lock().call(ctx, &input))
+            .await
+            .map_err(|e| PluginError::Other(anyhow::anyhow!("WASM hook task failed: {}", e)))?
+    }
+}
+
+fn encode_event(event: &HookEvent) -> Result<Vec<u8>> {
+    serde_json::to_vec(event).map_err(|e| PluginError::Other(e.into()))
+}
+
+/// `scarab.host_call`: run a JSON request against the current hook's context
+fn host_call(mut caller: Caller<'_, HostState>, ptr: u32, len: u32) -> anyhow::Result<u64> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| anyhow::anyhow!("module does not export `memory`"))?;
+    let mut request = vec![0; len as usize];
+    memory.read(&caller, ptr as usize, &mut request)?;
+    let call: HostCall = serde_json::from_slice(&request)?;
+
+    let result = {
+        let ctx = caller
+            .data()
+            .ctx
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("host calls are only available inside hooks"))?;
+        call.run(ctx)
+    };
+    if result.is_null() {
+        return Ok(0);
+    }
+
+    let output = serde_json::to_vec(&result)?;
+    let alloc = caller
+        .get_export("scarab_alloc")
+        .and_then(|export| export.into_func())
+        .ok_or_else(|| anyhow::anyhow!("module does not export `scarab_alloc`"))?
+        .typed::<u32, u32>(&caller)?;
+    let out = alloc.call(&mut caller, output.len() as u32)?;
+    memory.write(&mut caller, out as usize, &output)?;
+    Ok(((out as u64) << 32) | output.len() as u64)
+}
+
+fn unpack(packed: u64) -> (u32, u32) {
+    ((packed >> 32) as u32, packed as u32)
+}
+
+#[async_trait]
+impl Plugin for WasmPlugin {
+    fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
+    fn get_commands(&self) -> Vec<ModalItem> {
+        self.commands.clone()
+    }
+
+    fn get_keybindings(&self) -> Vec<PluginKeyBinding> {
+        self.keybindings.clone()
+    }
+
+    fn config_schema(&self) -> ConfigSchema {
+        self.config_schema.clone()
+    }
+
+    async fn on_load(&mut self, ctx: &mut PluginContext) -> Result<()> {
+        self.call(Some(&*ctx), HookEvent::Load).await?.into_unit()
+    }
+
+    async fn on_unload(&mut self) -> Result<()> {
+        self.call(None, HookEvent::Unload).await?.into_unit()
+    }
+
+    async fn on_output(&mut self, line: &str, ctx: &PluginContext) -> Result<Action> {
+        self.call(Some(ctx), HookEvent::Output { line })
+            .await?
+            .into_action()
+    }
+
+    async fn on_input(&mut self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
+        self.call(Some(ctx), HookEvent::Input { data: input })
+            .await?
+            .into_action()
+    }
+
+    async fn on_key(&mut self, key: KeyCombo, ctx: &PluginContext) -> Result<Action> {
+        self.call(Some(ctx), HookEvent::Key { key: &key })
+            .await?
+            .into_action()
+    }
+
+    async fn on_mouse(&mut self, event: &MouseEvent, ctx: &PluginContext) -> Result<Action> {
+        let kind = match event.kind {
+            MouseEventKind::Press => "press",
+            MouseEventKind::Release => "release",
+            MouseEventKind::Drag => "drag",
+            MouseEventKind::Scroll => "scroll",
+        };
+        let button = match event.button {
+            MouseButton::Left => "left",
+            MouseButton::Middle => "middle",
+            MouseButton::Right => "right",
+            MouseButton::WheelUp => "wheel_up",
+            MouseButton::WheelDown => "wheel_down",
+        };
+        let hook = HookEvent::Mouse {
+            kind,
+            button,
+            col: event.col,
+            row: event.row,
+            modifiers: event.modifiers.bits(),
+            click_count: event.click_count,
+        };
+        self.call(Some(ctx), hook).await?.into_action()
+    }
+
+    async fn on_pre_command(&mut self, command: &str, ctx: &PluginContext) -> Result<Action> {
+        self.call(Some(ctx), HookEvent::PreCommand { command })
+            .await?
+            .into_action()
+    }
+
+    async fn on_post_command(
+        &mut self,
+        command: &str,
+        exit_code: i32,
+        ctx: &PluginContext,
+    ) -> Result<()> {
+        self.call(Some(ctx), HookEvent::PostCommand { command, exit_code })
+            .await?
+            .into_unit()
+    }
+
+    async fn on_resize(&mut self, cols: u16, rows: u16, ctx: &PluginContext) -> Result<()> {
+        self.call(Some(ctx), HookEvent::Resize { cols, rows })
+            .await?
+            .into_unit()
+    }
+
+    async fn on_attach(&mut self, client_id: u64, ctx: &PluginContext) -> Result<()> {
+        self.call(Some(ctx), HookEvent::Attach { client_id })
+            .await?
+            .into_unit()
+    }
+
+    async fn on_detach(&mut self, client_id: u64, ctx: &PluginContext) -> Result<()> {
+        self.call(Some(ctx), HookEvent::Detach { client_id })
+            .await?
+            .into_unit()
+    }
+
+    async fn on_message(&mut self, message: &PluginMessage, ctx: &PluginContext) -> Result<()> {
+        self.call(Some(ctx), HookEvent::Message { message })
+            .await?
+            .into_unit()
+    }
+
+    async fn on_prompt_response(
+        &mut self,
+        response: &PromptResponse,
+        ctx: &PluginContext,
+    ) -> Result<()> {
+        let event = HookEvent::PromptResponse {
+            prompt_id: response.prompt_id,
+            values: &response.values,
+        };
+        self.call(Some(ctx), event).await?.into_unit()
+    }
+
+    async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
+        self.call(Some(ctx), HookEvent::RemoteCommand { id })
+            .await?
+            .into_unit()
+    }
+
+    async fn on_config_changed(&mut self, ctx: &PluginContext) -> Result<()> {
+        let config = ctx.config();
+        self.call(Some(ctx), HookEvent::ConfigChanged { config })
+            .await?
+            .into_unit()
+    }
+
+    fn snapshot_state(&self) -> Option<Vec<u8>> {
+        let result = encode_event(&HookEvent::Snapshot)
+            .and_then(|input| self.instance.lock().call(None, &input));
+        match result {
+            Ok(result) => result
+                .state
+                .and_then(|state| serde_json::to_vec(&state).ok()),
+            Err(e) => {
+                log::warn!(
+                    "WASM plugin '{}' snapshot failed: {}",
+                    self.metadata.name,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    async fn restore_state(&mut self, state: &[u8], ctx: &PluginContext) -> Result<()> {
+        let state = serde_json::from_slice(state).map_err(anyhow::Error::from)?;
+        self.call(Some(ctx), HookEvent::Restore { state })
+            .await?
+            .into_unit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_plugin_api::context::PluginSharedState;
+    use scarab_plugin_api::key_tables::{KeyCode, KeyModifiers};
+
+    /// Guest with a bump allocator that records every hook via `set_data`
+    const GUEST: &str = r#"
+        (module
+          (import "scarab" "host_call" (func $host_call (param i32 i32) (result i64)))
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (data (i32.const 0) "{\"name\":\"wat-guest\",\"version\":\"1.2.0\",\"commands\":[{\"id\":\"hello\",\"label\":\"Say hello\",\"key\":\"Ctrl+Alt+h\"}]}")
+          (data (i32.const 256) "{\"fn\":\"set_data\",\"key\":\"seen\",\"value\":\"yes\"}")
+          (data (i32.const 512) "{\"action\":\"modify\",\"data\":\"hidden\"}")
+          (func (export "scarab_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "scarab_metadata") (result i64)
+            (i64.const 105))
+          (func (export "scarab_hook") (param i32 i32) (result i64)
+            (drop (call $host_call (i32.const 256) (i32.const 44)))
+            (i64.or (i64.shl (i64.const 512) (i64.const 32)) (i64.const 35))))
+    "#;
+
+    #[tokio::test]
+    async fn test_wasm_plugin() {
+        let mut plugin = WasmPlugin::from_bytes(GUEST.as_bytes()).unwrap();
+        assert_eq!(plugin.metadata().name, "wat-guest");
+        assert_eq!(plugin.metadata().version, "1.2.0");
+        assert_eq!(plugin.get_commands()[0].id, "hello");
+        assert_eq!(
+            plugin.get_keybindings(),
+            vec![PluginKeyBinding::new(
+                KeyCombo::new(KeyCode::KeyH, KeyModifiers::CTRL | KeyModifiers::ALT),
+                "hello"
+            )]
+        );
+
+        let state = Arc::new(parking_lot::Mutex::new(PluginSharedState::new(80, 24)));
+        let ctx = PluginContext::new(Default::default(), state, "wat-guest");
+        let action = plugin.on_output("secret", &ctx).await.unwrap();
+        assert_eq!(action, Action::Modify(b"hidden".to_vec()));
+        assert_eq!(ctx.get_data("seen").as_deref(), Some("yes"));
+    }
+
+    #[test]
+    fn test_missing_exports() {
+        let result = WasmPlugin::from_bytes(b"(module (memory (export \"memory\") 1))");
+        assert!(result.is_err());
+    }
+}
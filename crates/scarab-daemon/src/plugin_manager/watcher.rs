@@ -0,0 +1,210 @@
+//! Plugin directory watcher for hot (un)loading
+//!
+//! Watches the plugin search paths and loads, reloads, or unloads plugins as
+//! their files appear, change, or disappear. Events are debounced because
+//! compilers and editors usually touch a file several times per write.
+//!
+//! In dev mode the native libraries of loaded plugins are watched too, so a
+//! plugin loaded straight from its crate's `target/` directory reloads on
+//! every build. Only those files are followed there; other libraries in the
+//! same directory are ignored.
+
+use super::PluginManager;
+use crate::ipc::ClientRegistry;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use scarab_plugin_api::PluginDiscovery;
+use scarab_protocol::DaemonMessage;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+/// Quiet period before a burst of file events is acted upon
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Watches plugin directories and forwards file changes
+pub struct PluginDirWatcher {
+    watcher: RecommendedWatcher,
+    events: mpsc::UnboundedReceiver<PathBuf>,
+    /// Watched plugin directories
+    dirs: HashSet<PathBuf>,
+    /// Plugin files followed outside the plugin directories (dev mode)
+    files: Arc<parking_lot::Mutex<HashSet<PathBuf>>>,
+}
+
+impl PluginDirWatcher {
+    /// Start watching the given directories
+    ///
+    /// Directories that do not exist are skipped.
+    pub fn new(dirs: &[PathBuf]) -> notify::Result<Self> {
+        let (tx, events) = mpsc::unbounded_channel();
+        let dirs: HashSet<PathBuf> = dirs.iter().filter(|d| d.is_dir()).cloned().collect();
+        let files = Arc::new(parking_lot::Mutex::new(HashSet::new()));
+
+        let watched_dirs = dirs.clone();
+        let watched_files = files.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+                Ok(event) => {
+                    if matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    ) {
+                        for path in event.paths {
+                            let in_plugin_dir =
+                                path.parent().is_some_and(|dir| watched_dirs.contains(dir));
+                            let followed = watched_files.lock().contains(&path);
+                            if PluginDiscovery::has_plugin_extension(&path)
+                                && (in_plugin_dir || followed)
+                            {
+                                let _ = tx.send(path);
+                            }
+                        }
+                    }
+                }
+                Err(e) => log::error!("Plugin watch error: {:?}", e),
+            })?;
+
+        for dir in &dirs {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            log::info!("👀 Watching plugin directory: {}", dir.display());
+        }
+
+        Ok(Self {
+            watcher,
+            events,
+            dirs,
+            files,
+        })
+    }
+
+    /// Follow a plugin file outside the plugin directories
+    ///
+    /// Its directory is watched rather than the file itself, since builds
+    /// usually replace the file instead of writing to it.
+    pub fn watch_file(&mut self, path: &Path) -> notify::Result<()> {
+        let Some(dir) = path.parent() else {
+            return Ok(());
+        };
+        let dir_watched = {
+            let files = self.files.lock();
+            if files.contains(path) {
+                return Ok(());
+            }
+            self.dirs.contains(dir) || files.iter().any(|file| file.parent() == Some(dir))
+        };
+
+        if !dir_watched {
+            self.watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+        self.files.lock().insert(path.to_path_buf());
+        log::info!("🧪 Watching plugin library: {}", path.display());
+        Ok(())
+    }
+
+    /// Follow the native libraries of loaded plugins when in dev mode
+    fn watch_dev_files(&mut self, pm: &PluginManager) {
+        if !pm.dev_mode() {
+            return;
+        }
+        for path in pm.dev_watch_paths() {
+            if let Err(e) = self.watch_file(&path) {
+                log::warn!("Failed to watch plugin library {:?}: {}", path, e);
+            }
+        }
+    }
+
+    /// Wait for the next debounced batch of changed plugin paths
+    ///
+    /// Returns `None` once the watcher has shut down.
+    pub async fn next_batch(&mut self) -> Option<Vec<PathBuf>> {
+        let first = self.events.recv().await?;
+        let mut batch = vec![first];
+
+        while let Ok(Some(path)) = tokio::time::timeout(DEBOUNCE, self.events.recv()).await {
+            if !batch.contains(&path) {
+                batch.push(path);
+            }
+        }
+
+        Some(batch)
+    }
+
+    /// Apply file changes to the plugin manager until the watcher shuts down
+    pub async fn run(
+        mut self,
+        plugin_manager: Arc<Mutex<PluginManager>>,
+        client_registry: ClientRegistry,
+    ) {
+        self.watch_dev_files(&*plugin_manager.lock().await);
+
+        while let Some(paths) = self.next_batch().await {
+            let mut pm = plugin_manager.lock().await;
+            let mut changed: HashMap<String, bool> = HashMap::new();
+
+            for path in paths {
+                let loaded_name = pm.plugin_name_for_path(&path);
+
+                match (PluginDiscovery::is_plugin_file(&path), loaded_name) {
+                    (true, Some(name)) => {
+                        log::info!("🔄 Plugin file changed, reloading '{}'", name);
+                        match pm.reload_plugin(&name).await {
+                            Ok(()) => {
+                                changed.insert(name, true);
+                            }
+                            Err(e) => {
+                                log::error!("Failed to reload plugin '{}': {}", name, e);
+                                client_registry
+                                    .broadcast(DaemonMessage::PluginError {
+                                        name: name.clone(),
+                                        error: format!("Reload failed: {}", e),
+                                    })
+                                    .await;
+                                changed.insert(name, false);
+                            }
+                        }
+                    }
+                    (true, None) => {
+                        log::info!("✨ New plugin file detected: {:?}", path);
+                        match pm.load_plugin_from_path(path.clone()).await {
+                            Ok(name) => {
+                                changed.insert(name, true);
+                            }
+                            Err(e) => {
+                                log::warn!("Failed to load plugin from {:?}: {}", path, e)
+                            }
+                        }
+                    }
+                    (false, Some(name)) => {
+                        log::info!("👋 Plugin file removed, unloading '{}'", name);
+                        if let Err(e) = pm.unload_removed_plugin(&name).await {
+                            log::error!("Failed to unload plugin '{}': {}", name, e);
+                        }
+                        changed.insert(name, false);
+                    }
+                    (false, None) => {}
+                }
+            }
+
+            self.watch_dev_files(&pm);
+            if changed.is_empty() {
+                continue;
+            }
+
+            client_registry
+                .broadcast(DaemonMessage::PluginList {
+                    plugins: pm.inspector_infos(),
+                })
+                .await;
+
+            for (name, enabled) in changed {
+                client_registry
+                    .broadcast(DaemonMessage::PluginStatusChanged { name, enabled })
+                    .await;
+            }
+        }
+
+        log::debug!("Plugin directory watcher stopped");
+    }
+}
@@ -0,0 +1,144 @@
+//! Workspace layout exposed to plugins
+//!
+//! Bridges [`PluginContext::objects`] to the session manager. Changes made
+//! through the handles come back as `RemoteCommand::Control` and are handled
+//! by the IPC server like control messages from a client.
+//!
+//! [`PluginContext::objects`]: scarab_plugin_api::PluginContext::objects
+
+use crate::session::SessionManager;
+use scarab_plugin_api::{settings::check_plugin_setting, Workspace};
+use scarab_protocol::{ControlMessage, PaneInfo, SessionInfo, TabInfo};
+use std::sync::Arc;
+
+/// Sessions, and the tabs and panes of the default session
+pub struct SessionWorkspace {
+    sessions: Arc<SessionManager>,
+}
+
+impl SessionWorkspace {
+    /// Create a workspace view over the session manager
+    pub fn new(sessions: Arc<SessionManager>) -> Self {
+        Self { sessions }
+    }
+}
+
+impl Workspace for SessionWorkspace {
+    fn sessions(&self) -> Vec<SessionInfo> {
+        self.sessions.session_infos()
+    }
+
+    fn tabs(&self) -> Vec<TabInfo> {
+        let Some(session) = self.sessions.get_default_session() else {
+            return Vec::new();
+        };
+        session
+            .list_tabs()
+            .into_iter()
+            .map(|(id, title, is_active, pane_count)| TabInfo {
+                id,
+                title,
+                session_id: Some(session.id.clone()),
+                is_active,
+                pane_count: pane_count as u32,
+                has_activity: session.tab_has_activity(id),
+            })
+            .collect()
+    }
+
+    fn panes(&self, tab_id: u64) -> Vec<PaneInfo> {
+        self.sessions
+            .get_default_session()
+            .map(|session| session.pane_infos(tab_id))
+            .unwrap_or_default()
+    }
+}
+
+/// Whether plugins may send a control message through workspace handles
+/// or `set_setting` and `override_setting`, or save and restore the
+/// workspace and connect domains from the session plugin's commands
+///
+/// Anything else a client can send, such as loading plugins or attaching
+/// to sessions, stays out of plugins' reach.
+pub fn is_workspace_command(message: &ControlMessage) -> bool {
+    matches!(
+        message,
+        ControlMessage::SessionRename { .. }
+            | ControlMessage::TabCreate { .. }
+            | ControlMessage::TabClose { .. }
+            | ControlMessage::TabSwitch { .. }
+            | ControlMessage::TabRename { .. }
+            | ControlMessage::PaneSplit { .. }
+            | ControlMessage::PaneClose { .. }
+            | ControlMessage::PaneFocus { .. }
+            | ControlMessage::PaneInput { .. }
+            | ControlMessage::ConfigSet { .. }
+            | ControlMessage::ConfigOverride { .. }
+            | ControlMessage::ConfigOverrideClear { .. }
+            | ControlMessage::WorkspaceSave { .. }
+            | ControlMessage::WorkspaceRestore { .. }
+            | ControlMessage::DomainList
+            | ControlMessage::DomainConnect { .. }
+            | ControlMessage::DomainDisconnect { .. }
+    )
+}
+
+/// Check that `plugin_name` may make the setting change in `message`
+///
+/// The plugin API refuses these already; this keeps a plugin that builds
+/// its own control messages to the same keys.
+pub fn check_setting_change(plugin_name: &str, message: &ControlMessage) -> Result<(), String> {
+    match message {
+        ControlMessage::ConfigSet { key, persist, .. } => {
+            check_plugin_setting(plugin_name, key, *persist)
+        }
+        ControlMessage::ConfigOverride { key, .. } => check_plugin_setting(plugin_name, key, false),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_workspace_commands_allowed() {
+        assert!(is_workspace_command(&ControlMessage::TabRename {
+            tab_id: 1,
+            new_title: "build".into(),
+        }));
+        assert!(is_workspace_command(&ControlMessage::PaneInput {
+            tab_id: 1,
+            pane_id: 1,
+            data: b"ls\r".to_vec(),
+        }));
+        assert!(!is_workspace_command(&ControlMessage::LoadPlugin {
+            path: "/tmp/evil.so".into(),
+        }));
+        assert!(!is_workspace_command(&ControlMessage::SessionAttach {
+            id: "main".into(),
+            read_only: false,
+        }));
+    }
+
+    #[test]
+    fn test_setting_changes_limited() {
+        let set = |key: &str, persist| ControlMessage::ConfigSet {
+            key: key.into(),
+            value: "x".into(),
+            persist,
+        };
+        assert!(check_setting_change("themes", &set("colors.theme", false)).is_ok());
+        assert!(check_setting_change("themes", &set("colors.theme", true)).is_err());
+        assert!(check_setting_change("themes", &set("terminal.default_shell", false)).is_err());
+        assert!(check_setting_change(
+            "themes",
+            &ControlMessage::ConfigOverride {
+                scope: scarab_protocol::ConfigScope::Tab(1),
+                key: "terminal.default_shell".into(),
+                value: Some("sh".into()),
+            }
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Scrollback search
+//!
+//! Runs a plain-text or regex query over a pane's scrollback and visible grid
+//! on behalf of the client search overlay. Matches are reported in cell
+//! columns on absolute lines (scrollback first, then grid rows) so the client
+//! can highlight them and scroll them into view.
+//!
+//! A search can be narrowed to the screen or to the output of the last
+//! command, whose lines come from the pane's zone tracker.
+
+use crate::vte::TerminalState;
+use regex::{Regex, RegexBuilder};
+use scarab_protocol::{SearchMatch, SearchScope, SCROLLBACK_FETCH_BYTES};
+use std::ops::Range;
+
+/// Compiled search query
+pub struct SearchQuery {
+    regex: Regex,
+}
+
+impl SearchQuery {
+    /// Compile a query; plain-text queries are escaped before compiling
+    pub fn new(query: &str, case_sensitive: bool, use_regex: bool) -> Result<Self, regex::Error> {
+        let pattern = if use_regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive)
+            .build()?;
+        Ok(Self { regex })
+    }
+
+    /// Find all non-empty matches in a line as `(column, length)` pairs
+    ///
+    /// `text` must hold one char per cell, as produced by
+    /// `TerminalState::line_text`.
+    pub fn find_all(&self, text: &str) -> Vec<(u16, u16)> {
+        self.regex
+            .find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                let col = text[..m.start()].chars().count();
+                let len = m.as_str().chars().count();
+                (col as u16, len as u16)
+            })
+            .collect()
+    }
+}
+
+/// Absolute lines of a terminal that `scope` covers
+///
+/// The last command is the one still running, if any, else the last one
+/// that finished. Its output runs up to the row the next prompt starts on.
+/// Without shell integration there is no last command and the range is
+/// empty.
+pub fn scope_lines(state: &TerminalState, scope: SearchScope) -> Range<usize> {
+    let (_, rows) = state.dimensions();
+    let line_count = state.scrollback_len() + rows as usize;
+    match scope {
+        SearchScope::All => 0..line_count,
+        SearchScope::Screen => state.scrollback_len()..line_count,
+        SearchScope::LastCommand => {
+            let tracker = &state.zone_tracker;
+            let running = tracker
+                .current_block()
+                .and_then(|block| block.output_bounds())
+                .map(|(start, _)| start as usize..line_count);
+            let finished = || {
+                let zone = tracker.last_output_zone()?;
+                let start = zone.start_row as usize;
+                Some(start..(zone.end_row as usize).max(start + 1))
+            };
+            match running.or_else(finished) {
+                Some(lines) => lines.start.min(line_count)..lines.end.min(line_count),
+                None => 0..0,
+            }
+        }
+    }
+}
+
+/// Search the lines of a terminal that `scope` covers, oldest line first
+///
+/// Returns the newest `max_results` matches, since those nearest the prompt
+/// are the ones users usually want, together with the total number found.
+pub fn search_terminal(
+    state: &TerminalState,
+    query: &SearchQuery,
+    scope: SearchScope,
+    max_results: usize,
+) -> (Vec<SearchMatch>, usize) {
+    let mut matches = Vec::new();
+    for line in scope_lines(state, scope) {
+        let Some(text) = state.line_text(line) else {
+            continue;
+        };
+        for (col, len) in query.find_all(&text) {
+            matches.push(SearchMatch {
+                line: line as u32,
+                col,
+                len,
+            });
+        }
+    }
+
+    let total = matches.len();
+    matches.drain(..total.saturating_sub(max_results));
+    (matches, total)
+}
+
+/// Text of scrollback lines starting at `start`
+///
+/// Stops after `count` lines, at the end of scrollback, or once the reply
+/// would exceed `SCROLLBACK_FETCH_BYTES`; the client requests the rest
+/// starting after the last line it received. At least one line is returned
+/// when any remain.
+pub fn fetch_lines(state: &TerminalState, start: usize, count: usize) -> Vec<String> {
+    let end = start.saturating_add(count).min(state.scrollback_len());
+
+    let mut lines = Vec::new();
+    let mut bytes = 0;
+    for line in start..end {
+        let Some(text) = state.line_text(line) else {
+            break;
+        };
+        bytes += text.len() + 8;
+        if bytes > SCROLLBACK_FETCH_BYTES && !lines.is_empty() {
+            break;
+        }
+        lines.push(text);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_query_is_escaped() {
+        let query = SearchQuery::new("a.b", true, false).unwrap();
+        assert_eq!(query.find_all("axb a.b"), vec![(4, 3)]);
+    }
+
+    #[test]
+    fn test_case_and_regex() {
+        let query = SearchQuery::new("err(or)?", false, true).unwrap();
+        assert_eq!(query.find_all("ERROR: err"), vec![(0, 5), (7, 3)]);
+
+        assert!(SearchQuery::new("(", true, true).is_err());
+    }
+
+    #[test]
+    fn test_columns_count_chars() {
+        let query = SearchQuery::new("x", true, false).unwrap();
+        assert_eq!(query.find_all("日本x"), vec![(2, 1)]);
+    }
+
+    #[test]
+    fn test_search_terminal_spans_scrollback() {
+        let mut state = TerminalState::new(20, 3);
+        state.process_output(b"needle one\r\nfiller\r\nfiller\r\nneedle two\r\n");
+
+        let query = SearchQuery::new("needle", true, false).unwrap();
+        let (matches, total) = search_terminal(&state, &query, SearchScope::All, 1);
+
+        assert!(state.scrollback_len() > 0);
+        assert_eq!(total, 2);
+        // Only the newest match is kept
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, 3);
+    }
+
+    #[test]
+    fn test_search_scopes() {
+        let mut state = TerminalState::new(20, 4);
+        state.process_output(b"\x1b]133;A\x07$ \x1b]133;B\x07make\r\n\x1b]133;C\x07");
+        state.process_output(b"error: old\r\n\x1b]133;D;2\x07");
+        state.process_output(b"\x1b]133;A\x07$ \x1b]133;B\x07make\r\n\x1b]133;C\x07");
+        state.process_output(b"error: new\r\nok\r\n\x1b]133;D;2\x07");
+        state.process_output(b"\x1b]133;A\x07$ ");
+
+        let query = SearchQuery::new("error", true, false).unwrap();
+        let lines = |scope| {
+            let (matches, _) = search_terminal(&state, &query, scope, 10);
+            matches.iter().map(|m| m.line).collect::<Vec<_>>()
+        };
+        assert_eq!(lines(SearchScope::All), vec![1, 3]);
+        // Only the output of the second make, not the prompt after it
+        assert_eq!(scope_lines(&state, SearchScope::LastCommand), 3..5);
+        assert_eq!(lines(SearchScope::LastCommand), vec![3]);
+
+        // The first make's output has scrolled off the 4-row screen
+        assert_eq!(state.scrollback_len(), 2);
+        assert_eq!(lines(SearchScope::Screen), vec![3]);
+
+        // No shell integration, no last command
+        let plain = TerminalState::new(20, 4);
+        assert!(scope_lines(&plain, SearchScope::LastCommand).is_empty());
+    }
+
+    #[test]
+    fn test_fetch_lines_respects_budget() {
+        let mut state = TerminalState::new(200, 2);
+        let line = format!("{}\r\n", "x".repeat(200));
+        for _ in 0..100 {
+            state.process_output(line.as_bytes());
+        }
+
+        let lines = fetch_lines(&state, 0, 100);
+        assert!(!lines.is_empty());
+        assert!(lines.len() < 100);
+        assert!(lines.iter().map(|l| l.len()).sum::<usize>() <= SCROLLBACK_FETCH_BYTES);
+    }
+}
@@ -1,11 +1,14 @@
 use super::pane::PaneId;
-use super::tab::SplitDirection as SessionSplitDirection;
-use super::{ClientId, SessionManager};
+use super::shmem::shmem_path_for;
+use super::tab::{SplitDirection as SessionSplitDirection, TabId};
+use super::{ClientId, Session, SessionManager};
+use crate::domains::{domain_infos, remote_domain, DomainPane, LOCAL_DOMAIN};
 use anyhow::Result;
 use scarab_protocol::{
-    ControlMessage, DaemonMessage, PaneInfo, SessionInfo, SessionResponse,
+    ControlMessage, DaemonMessage, NotifyLevel, PaneInfo, SessionResponse,
     SplitDirection as ProtocolSplitDirection, TabInfo,
 };
+use scarab_session::workspace;
 use std::sync::Arc;
 
 /// Handle session-related control messages
@@ -46,30 +49,22 @@ pub async fn handle_session_command(
         ControlMessage::SessionList => {
             log::info!("Client {} listing sessions", client_id);
 
-            let sessions = session_manager.list_sessions();
-            let session_infos: Vec<SessionInfo> = sessions
-                .into_iter()
-                .map(
-                    |(id, name, created_at, last_attached, attached_clients)| SessionInfo {
-                        id,
-                        name,
-                        created_at,
-                        last_attached,
-                        attached_clients: attached_clients as u32,
-                    },
-                )
-                .collect();
+            let session_infos = session_manager.session_infos();
 
             Ok(Some(SessionResponse::List {
                 sessions: session_infos,
             }))
         }
 
-        ControlMessage::SessionAttach { id } => {
+        ControlMessage::SessionAttach { id, read_only } => {
             log::info!("Client {} attaching to session: {}", client_id, id);
 
-            match session_manager.attach_client(&id.to_string(), client_id) {
-                Ok(_) => Ok(Some(SessionResponse::Attached { id: id.clone() })),
+            match session_manager.attach_client_as(&id.to_string(), client_id, read_only) {
+                Ok(read_only) => Ok(Some(SessionResponse::Attached {
+                    id: id.clone(),
+                    shmem_path: shmem_path_for(session_manager, &id.to_string()),
+                    read_only,
+                })),
                 Err(e) => Ok(Some(SessionResponse::Error {
                     message: format!("Failed to attach to session: {}", e),
                 })),
@@ -106,6 +101,73 @@ pub async fn handle_session_command(
             }
         }
 
+        ControlMessage::SessionSetAccess {
+            read_only_by_default,
+        } => {
+            let Some(session) = session_manager
+                .client_session(client_id)
+                .or_else(|| session_manager.get_default_session())
+            else {
+                return Ok(Some(SessionResponse::Error {
+                    message: "No session to change access of".to_string(),
+                }));
+            };
+            log::info!(
+                "Client {} setting session {} read-only by default: {}",
+                client_id,
+                session.id,
+                read_only_by_default
+            );
+
+            match session_manager.set_read_only_by_default(
+                &session.id,
+                client_id,
+                read_only_by_default,
+            ) {
+                Ok(_) => Ok(Some(SessionResponse::AccessChanged {
+                    id: session.id.clone(),
+                    read_only_by_default,
+                })),
+                Err(e) => Ok(Some(SessionResponse::Error {
+                    message: format!("Failed to change session access: {}", e),
+                })),
+            }
+        }
+
+        ControlMessage::WorkspaceSave { name } => {
+            log::info!("Client {} saving workspace: {}", client_id, name);
+
+            let snapshot = session_manager.snapshot_workspace(0);
+            match workspace::save_workspace(session_manager.workspaces_dir(), &name, &snapshot) {
+                Ok(path) => {
+                    log::info!("Saved workspace to {}", path.display());
+                    Ok(Some(SessionResponse::WorkspaceSaved {
+                        name: name.clone(),
+                        sessions: snapshot.sessions.len() as u32,
+                    }))
+                }
+                Err(e) => Ok(Some(SessionResponse::Error {
+                    message: format!("Failed to save workspace: {:#}", e),
+                })),
+            }
+        }
+
+        ControlMessage::WorkspaceRestore { name } => {
+            log::info!("Client {} restoring workspace: {}", client_id, name);
+
+            let restored = workspace::load_workspace(session_manager.workspaces_dir(), &name)
+                .and_then(|snapshot| session_manager.restore_workspace(&snapshot, 80, 24));
+            match restored {
+                Ok(session_ids) => Ok(Some(SessionResponse::WorkspaceRestored {
+                    name: name.clone(),
+                    session_ids,
+                })),
+                Err(e) => Ok(Some(SessionResponse::Error {
+                    message: format!("Failed to restore workspace: {:#}", e),
+                })),
+            }
+        }
+
         _ => {
             // Not a session command
             Ok(None)
@@ -140,10 +202,19 @@ pub async fn handle_tab_command(
     };
 
     match msg {
-        ControlMessage::TabCreate { title } => {
-            log::info!("Client {} creating tab: {:?}", client_id, title);
+        ControlMessage::TabCreate { title, domain } => {
+            log::info!(
+                "Client {} creating tab: {:?} (domain: {:?})",
+                client_id,
+                title,
+                domain
+            );
 
-            match session.create_tab(title.map(|s| s.to_string())) {
+            let created = match domain {
+                Some(domain) => create_domain_tab(&session, session_manager, title, &domain).await,
+                None => session.create_tab(title.map(|s| s.to_string())),
+            };
+            match created {
                 Ok(tab_id) => {
                     let tabs = session.list_tabs();
                     let tab_info = tabs.iter().find(|(id, _, _, _)| *id == tab_id);
@@ -157,6 +228,7 @@ pub async fn handle_tab_command(
                                     session_id: Some(session.id.clone()),
                                     is_active: *is_active,
                                     pane_count: *pane_count as u32,
+                                    has_activity: session.tab_has_activity(*id),
                                 },
                             }),
                             destroyed_pane_ids: Vec::new(),
@@ -228,6 +300,7 @@ pub async fn handle_tab_command(
                             session_id: Some(session.id.clone()),
                             is_active,
                             pane_count: pane_count as u32,
+                            has_activity: session.tab_has_activity(id),
                         })
                         .collect();
                     Ok(Some(TabCommandResult {
@@ -256,6 +329,7 @@ pub async fn handle_tab_command(
                     session_id: Some(session.id.clone()),
                     is_active,
                     pane_count: pane_count as u32,
+                    has_activity: session.tab_has_activity(id),
                 })
                 .collect();
 
@@ -269,6 +343,67 @@ pub async fn handle_tab_command(
     }
 }
 
+/// Open a tab whose first pane runs in the domain `domain_id`
+async fn create_domain_tab(
+    session: &Session,
+    session_manager: &SessionManager,
+    title: Option<String>,
+    domain_id: &str,
+) -> Result<TabId> {
+    let Some(domain) = remote_domain(session_manager.domains(), domain_id)? else {
+        return session.create_tab(title);
+    };
+
+    let (cols, rows) = session.default_size();
+    let (pane, relay) = DomainPane::spawn(domain.clone(), cols, rows).await?;
+    let pane_id = pane.id;
+    let tab_id = session.add_tab(
+        title.unwrap_or_else(|| domain.name().to_string()),
+        vec![pane],
+    );
+    session.set_tab_domain(tab_id, Some(domain_id.to_string()))?;
+    if let Some(pane) = session.get_pane(tab_id, pane_id) {
+        relay.relay(&pane);
+    }
+    Ok(tab_id)
+}
+
+/// Split the active pane with a pane in the domain `domain_id`
+async fn split_into_domain(
+    session: &Session,
+    session_manager: &SessionManager,
+    direction: SessionSplitDirection,
+    domain_id: &str,
+) -> Result<PaneId> {
+    let Some(domain) = remote_domain(session_manager.domains(), domain_id)? else {
+        return session.split_pane(direction);
+    };
+
+    let tab_id = session.active_tab_id();
+    let (cols, rows) = session.split_size(direction)?;
+    let (pane, relay) = DomainPane::spawn(domain, cols, rows).await?;
+    let pane_id = session.split_with(pane)?;
+    if let Some(pane) = session.get_pane(tab_id, pane_id) {
+        relay.relay(&pane);
+    }
+    Ok(pane_id)
+}
+
+/// Whether handling `msg` may connect to a domain, which can take a while
+/// and ask the user for a password
+pub fn may_connect(msg: &ControlMessage, session_manager: &SessionManager) -> bool {
+    let domain = match msg {
+        ControlMessage::DomainConnect { .. } => return true,
+        ControlMessage::TabCreate { domain, .. } => domain.clone(),
+        ControlMessage::PaneSplit { domain, .. } => domain.clone().or_else(|| {
+            let session = session_manager.get_default_session()?;
+            session.tab_domain(session.active_tab_id())
+        }),
+        _ => None,
+    };
+    domain.is_some_and(|id| id != LOCAL_DOMAIN)
+}
+
 /// Handle pane-related control messages
 /// Returns a DaemonMessage response for the client
 pub async fn handle_pane_command(
@@ -288,10 +423,20 @@ pub async fn handle_pane_command(
 
     match msg {
         ControlMessage::PaneSplit {
-            pane_id: _,
+            pane_id,
             direction,
+            domain,
         } => {
-            log::info!("Client {} splitting pane: {:?}", client_id, direction);
+            log::info!(
+                "Client {} splitting pane {}: {:?} (domain: {:?})",
+                client_id,
+                pane_id,
+                direction,
+                domain
+            );
+
+            // Split the named pane; an unknown ID splits the focused one
+            let _ = session.focus_pane(pane_id);
 
             // Convert protocol direction to session direction
             let session_direction = match direction {
@@ -299,7 +444,15 @@ pub async fn handle_pane_command(
                 ProtocolSplitDirection::Vertical => SessionSplitDirection::Vertical,
             };
 
-            match session.split_pane(session_direction) {
+            // New panes open in the tab's domain unless told otherwise
+            let domain = domain.or_else(|| session.tab_domain(session.active_tab_id()));
+            let split = match domain {
+                Some(domain) => {
+                    split_into_domain(&session, session_manager, session_direction, &domain).await
+                }
+                None => session.split_pane(session_direction),
+            };
+            match split {
                 Ok(new_pane_id) => {
                     // Get the new pane info
                     if let Some(pane) = session.get_active_pane() {
@@ -380,9 +533,61 @@ pub async fn handle_pane_command(
     }
 }
 
+/// Handle domain-related control messages
+///
+/// Connecting and disconnecting are answered with a notification saying
+/// how it went.
+pub async fn handle_domain_command(
+    msg: ControlMessage,
+    session_manager: &Arc<SessionManager>,
+    client_id: ClientId,
+) -> Result<Option<DaemonMessage>> {
+    let domains = session_manager.domains();
+    let (id, connect) = match msg {
+        ControlMessage::DomainList => {
+            return Ok(Some(DaemonMessage::DomainListResponse {
+                domains: domain_infos(domains),
+            }));
+        }
+        ControlMessage::DomainConnect { id } => (id, true),
+        ControlMessage::DomainDisconnect { id } => (id, false),
+        _ => return Ok(None),
+    };
+
+    let name = domains
+        .get(&id)
+        .map(|domain| domain.name().to_string())
+        .unwrap_or_else(|| id.clone());
+    let (body, level) = if connect {
+        log::info!("Client {} connecting domain {}", client_id, id);
+        match domains.connect(&id).await {
+            Ok(()) => (format!("Connected to {}", name), NotifyLevel::Success),
+            Err(e) => (
+                format!("Failed to connect to {}: {:#}", name, e),
+                NotifyLevel::Error,
+            ),
+        }
+    } else {
+        log::info!("Client {} disconnecting domain {}", client_id, id);
+        match domains.disconnect(&id).await {
+            Ok(()) => (format!("Disconnected from {}", name), NotifyLevel::Info),
+            Err(e) => (
+                format!("Failed to disconnect from {}: {:#}", name, e),
+                NotifyLevel::Error,
+            ),
+        }
+    };
+    Ok(Some(DaemonMessage::PluginNotification {
+        title: "Domains".into(),
+        body,
+        level,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use scarab_session::{DomainRegistry, LocalDomain};
     use tempfile::TempDir;
 
     #[tokio::test]
@@ -416,4 +621,71 @@ mod tests {
             panic!("Expected List response");
         }
     }
+
+    #[tokio::test]
+    async fn test_domain_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let domains = Arc::new(DomainRegistry::new());
+        domains.register(Arc::new(LocalDomain::new()));
+        let manager = Arc::new(
+            SessionManager::new(temp_dir.path().join("test.db"))
+                .unwrap()
+                .with_domains(domains),
+        );
+        manager
+            .create_session("default".to_string(), 80, 24)
+            .unwrap();
+
+        let response = handle_domain_command(ControlMessage::DomainList, &manager, 1)
+            .await
+            .unwrap();
+        let Some(DaemonMessage::DomainListResponse { domains }) = response else {
+            panic!("Expected DomainListResponse");
+        };
+        assert_eq!(domains.len(), 1);
+        assert!(domains[0].connected && domains[0].is_default);
+
+        // This machine can't be disconnected from
+        let response = handle_domain_command(
+            ControlMessage::DomainDisconnect {
+                id: "local".to_string(),
+            },
+            &manager,
+            1,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            response,
+            Some(DaemonMessage::PluginNotification {
+                level: NotifyLevel::Error,
+                ..
+            })
+        ));
+
+        // Tabs can't open in domains that aren't configured
+        let msg = ControlMessage::TabCreate {
+            title: None,
+            domain: Some("prod".to_string()),
+        };
+        assert!(may_connect(&msg, &manager));
+        let result = handle_tab_command(msg, &manager, 1).await.unwrap().unwrap();
+        assert!(matches!(
+            result.message,
+            Some(DaemonMessage::Session(SessionResponse::Error { .. }))
+        ));
+
+        // Naming the local domain opens a tab as usual
+        let msg = ControlMessage::TabCreate {
+            title: None,
+            domain: Some(LOCAL_DOMAIN.to_string()),
+        };
+        assert!(!may_connect(&msg, &manager));
+        let result = handle_tab_command(msg, &manager, 1).await.unwrap().unwrap();
+        let Some(DaemonMessage::TabCreated { tab }) = result.message else {
+            panic!("Expected TabCreated");
+        };
+        let session = manager.get_default_session().unwrap();
+        assert_eq!(session.tab_domain(tab.id), None);
+    }
 }
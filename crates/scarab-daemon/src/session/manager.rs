@@ -3,9 +3,17 @@ use super::tab::{SplitDirection, Tab, TabId};
 use super::{ClientId, SessionId, SessionStore, TerminalState};
 use anyhow::{bail, Result};
 use parking_lot::RwLock;
+use scarab_platform::Paths;
+use scarab_protocol::{PaneInfo, ProgressState, SessionInfo};
+use scarab_session::workspace::{
+    self, resumable_command, PaneSnapshot, SessionSnapshot, TabSnapshot, WorkspaceSnapshot,
+    SNAPSHOT_VERSION,
+};
+use scarab_session::DomainRegistry;
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 /// A terminal session containing one or more tabs, each with panes
@@ -27,8 +35,17 @@ pub struct Session {
     pub created_at: SystemTime,
     /// Last time a client attached
     pub last_attached: Arc<RwLock<SystemTime>>,
+    /// Last time a client typed into the session
+    last_input: RwLock<Instant>,
     /// Currently attached clients
     pub attached_clients: Arc<RwLock<HashSet<ClientId>>>,
+    /// Clients that attached read-only, kept until they disconnect so they
+    /// can't re-attach able to type
+    read_only_clients: RwLock<HashSet<ClientId>>,
+    /// Clients that attached able to type
+    writers: RwLock<HashSet<ClientId>>,
+    /// Clients not in `writers` may only attach read-only
+    read_only_by_default: RwLock<bool>,
     /// Default shell for new panes
     default_shell: String,
     /// Default terminal dimensions
@@ -63,7 +80,11 @@ impl Session {
             next_tab_id: RwLock::new(2),
             created_at: now,
             last_attached: Arc::new(RwLock::new(now)),
+            last_input: RwLock::new(Instant::now()),
             attached_clients: Arc::new(RwLock::new(HashSet::new())),
+            read_only_clients: RwLock::new(HashSet::new()),
+            writers: RwLock::new(HashSet::new()),
+            read_only_by_default: RwLock::new(false),
             default_shell: shell.to_string(),
             default_cols: cols,
             default_rows: rows,
@@ -85,13 +106,121 @@ impl Session {
             next_tab_id: RwLock::new(1),
             created_at,
             last_attached: Arc::new(RwLock::new(last_attached)),
+            last_input: RwLock::new(Instant::now()),
             attached_clients: Arc::new(RwLock::new(HashSet::new())),
+            read_only_clients: RwLock::new(HashSet::new()),
+            writers: RwLock::new(HashSet::new()),
+            read_only_by_default: RwLock::new(false),
             default_shell: "bash".to_string(),
             default_cols: 80,
             default_rows: 24,
         }
     }
 
+    /// Recreate a saved session with fresh shells
+    ///
+    /// Each pane's shell starts in its saved directory, if it still exists,
+    /// below the output saved with the pane, and runs the saved command if
+    /// there was one.
+    pub fn from_snapshot(snapshot: &SessionSnapshot, cols: u16, rows: u16) -> Result<Self> {
+        let mut tabs = HashMap::new();
+        let mut active_tab_id = 0;
+        let mut default_shell = None;
+
+        for (index, saved) in snapshot.tabs.iter().enumerate() {
+            if saved.panes.is_empty() {
+                continue;
+            }
+            let tab_id = index as TabId + 1;
+            let mut tab = Tab::empty(tab_id, saved.title.clone());
+            for (pane_index, saved_pane) in saved.panes.iter().enumerate() {
+                let cwd = saved_pane
+                    .cwd
+                    .clone()
+                    .filter(|dir| std::path::Path::new(dir).is_dir());
+                let pane = Pane::new(pane_index as PaneId + 1, &saved_pane.shell, cols, rows, cwd)?;
+                if !saved_pane.scrollback.is_empty() {
+                    let mut output = saved_pane.scrollback.join("\r\n");
+                    output.push_str("\r\n");
+                    pane.process_output(output.as_bytes());
+                }
+                if let Some(command) = &saved_pane.command {
+                    pane.run_command(command)?;
+                }
+                tab.add_pane(pane);
+                default_shell.get_or_insert_with(|| saved_pane.shell.clone());
+            }
+            // Out of range when the file was edited; the first pane stays focused
+            let _ = tab.set_active_pane(saved.active_pane as PaneId + 1);
+
+            if index == snapshot.active_tab || active_tab_id == 0 {
+                active_tab_id = tab_id;
+            }
+            tabs.insert(tab_id, tab);
+        }
+        let Some(default_shell) = default_shell else {
+            bail!("Session '{}' has no panes to restore", snapshot.name);
+        };
+
+        let now = SystemTime::now();
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name: snapshot.name.clone(),
+            active_tab_id: RwLock::new(active_tab_id),
+            next_tab_id: RwLock::new(snapshot.tabs.len() as TabId + 1),
+            tabs: RwLock::new(tabs),
+            created_at: now,
+            last_attached: Arc::new(RwLock::new(now)),
+            last_input: RwLock::new(Instant::now()),
+            attached_clients: Arc::new(RwLock::new(HashSet::new())),
+            read_only_clients: RwLock::new(HashSet::new()),
+            writers: RwLock::new(HashSet::new()),
+            read_only_by_default: RwLock::new(false),
+            default_shell,
+            default_cols: cols,
+            default_rows: rows,
+        })
+    }
+
+    /// Tabs and panes of this session, for saving as part of a workspace
+    ///
+    /// Up to `scrollback_lines` of each pane's output are kept with it.
+    pub fn snapshot(&self, scrollback_lines: usize) -> SessionSnapshot {
+        let tabs = self.tabs.read();
+        let active_tab_id = *self.active_tab_id.read();
+        let mut tab_ids: Vec<TabId> = tabs.keys().copied().collect();
+        tab_ids.sort();
+
+        let tab_snapshots = tab_ids
+            .iter()
+            .map(|id| {
+                let tab = &tabs[id];
+                let mut panes: Vec<&Arc<Pane>> = tab.panes().collect();
+                panes.sort_by_key(|pane| pane.id);
+                TabSnapshot {
+                    title: tab.title.clone(),
+                    active_pane: panes
+                        .iter()
+                        .position(|pane| pane.id == tab.active_pane_id())
+                        .unwrap_or(0),
+                    panes: panes
+                        .iter()
+                        .map(|pane| pane_snapshot(pane, scrollback_lines))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        SessionSnapshot {
+            name: self.name.clone(),
+            active_tab: tab_ids
+                .iter()
+                .position(|&id| id == active_tab_id)
+                .unwrap_or(0),
+            tabs: tab_snapshots,
+        }
+    }
+
     /// Ensure the session has at least one tab with a PTY
     ///
     /// Called after restoration to spawn a new shell for restored sessions.
@@ -131,6 +260,11 @@ impl Session {
 
     // ==================== Tab Management ====================
 
+    /// Size new tabs' panes are opened at
+    pub fn default_size(&self) -> (u16, u16) {
+        (self.default_cols, self.default_rows)
+    }
+
     /// Create a new tab
     pub fn create_tab(&self, title: Option<String>) -> Result<TabId> {
         let tab_id = {
@@ -166,6 +300,25 @@ impl Session {
         Ok(tab_id)
     }
 
+    /// Add a tab of panes made elsewhere, without switching to it
+    pub fn add_tab(&self, title: String, panes: Vec<Pane>) -> TabId {
+        let tab_id = {
+            let mut next_id = self.next_tab_id.write();
+            let id = *next_id;
+            *next_id += 1;
+            id
+        };
+
+        let mut tab = Tab::empty(tab_id, title);
+        for pane in panes {
+            tab.add_pane(pane);
+        }
+        self.tabs.write().insert(tab_id, tab);
+
+        log::info!("Added tab {} to session {}", tab_id, self.id);
+        tab_id
+    }
+
     /// Close a tab
     /// Returns the list of pane IDs that were destroyed
     pub fn close_tab(&self, tab_id: TabId) -> Result<Vec<PaneId>> {
@@ -206,11 +359,41 @@ impl Session {
             bail!("Tab {} not found in session {}", tab_id, self.id);
         }
 
-        *self.active_tab_id.write() = tab_id;
+        // Output seen while a tab is on screen is not "activity"
+        let mut active_tab_id = self.active_tab_id.write();
+        for id in [*active_tab_id, tab_id] {
+            if let Some(tab) = tabs.get(&id) {
+                tab.clear_activity();
+            }
+        }
+
+        *active_tab_id = tab_id;
         log::info!("Switched to tab {} in session {}", tab_id, self.id);
         Ok(())
     }
 
+    /// Check whether a background tab produced output since it was last viewed
+    ///
+    /// The active tab never reports activity.
+    pub fn tab_has_activity(&self, tab_id: TabId) -> bool {
+        if tab_id == *self.active_tab_id.read() {
+            return false;
+        }
+        self.tabs
+            .read()
+            .get(&tab_id)
+            .is_some_and(|tab| tab.has_activity())
+    }
+
+    /// Overall progress of each tab whose panes reported a change
+    pub fn take_progress_updates(&self) -> Vec<(TabId, ProgressState)> {
+        self.tabs
+            .read()
+            .iter()
+            .filter_map(|(id, tab)| tab.take_progress_change().map(|p| (*id, p)))
+            .collect()
+    }
+
     /// Rename a tab
     pub fn rename_tab(&self, tab_id: TabId, new_title: String) -> Result<()> {
         let mut tabs = self.tabs.write();
@@ -222,6 +405,37 @@ impl Session {
         }
     }
 
+    /// Domain a tab's new panes are split into (`None` = local)
+    pub fn tab_domain(&self, tab_id: TabId) -> Option<String> {
+        self.tabs
+            .read()
+            .get(&tab_id)
+            .and_then(|tab| tab.domain.clone())
+    }
+
+    /// Split a tab's new panes into `domain` (`None` = local)
+    pub fn set_tab_domain(&self, tab_id: TabId, domain: Option<String>) -> Result<()> {
+        match self.tabs.write().get_mut(&tab_id) {
+            Some(tab) => {
+                tab.domain = domain;
+                Ok(())
+            }
+            None => bail!("Tab {} not found", tab_id),
+        }
+    }
+
+    /// Title the initial tab and open one more per remaining title
+    pub fn open_startup_tabs(&self, titles: &[String]) -> Result<()> {
+        let Some((first, rest)) = titles.split_first() else {
+            return Ok(());
+        };
+        self.rename_tab(self.active_tab_id(), first.clone())?;
+        for title in rest {
+            self.create_tab(Some(title.clone()))?;
+        }
+        Ok(())
+    }
+
     /// Get the active tab ID
     pub fn active_tab_id(&self) -> TabId {
         *self.active_tab_id.read()
@@ -319,6 +533,30 @@ impl Session {
         }
     }
 
+    /// Size of the pane splitting the active pane would make
+    pub fn split_size(&self, direction: SplitDirection) -> Result<(u16, u16)> {
+        let tabs = self.tabs.read();
+        let active_tab_id = *self.active_tab_id.read();
+
+        if let Some(tab) = tabs.get(&active_tab_id) {
+            tab.split_size(direction)
+        } else {
+            bail!("No active tab")
+        }
+    }
+
+    /// Split the active pane in the active tab with a pane made elsewhere
+    pub fn split_with(&self, pane: Pane) -> Result<PaneId> {
+        let mut tabs = self.tabs.write();
+        let active_tab_id = *self.active_tab_id.read();
+
+        if let Some(tab) = tabs.get_mut(&active_tab_id) {
+            tab.split_with(pane)
+        } else {
+            bail!("No active tab")
+        }
+    }
+
     /// Close a pane in the active tab
     pub fn close_pane(&self, pane_id: PaneId) -> Result<()> {
         let mut tabs = self.tabs.write();
@@ -331,6 +569,30 @@ impl Session {
         }
     }
 
+    /// Add a pane made elsewhere to a tab
+    pub fn add_pane(&self, tab_id: TabId, pane: Pane) -> Result<PaneId> {
+        match self.tabs.write().get_mut(&tab_id) {
+            Some(tab) => Ok(tab.add_pane(pane)),
+            None => bail!("Tab {} not found", tab_id),
+        }
+    }
+
+    /// Close a pane of any tab
+    pub fn remove_pane(&self, tab_id: TabId, pane_id: PaneId) -> Result<()> {
+        match self.tabs.write().get_mut(&tab_id) {
+            Some(tab) => tab.close_pane(pane_id),
+            None => bail!("Tab {} not found", tab_id),
+        }
+    }
+
+    /// Focus a pane of any tab
+    pub fn focus_tab_pane(&self, tab_id: TabId, pane_id: PaneId) -> Result<()> {
+        match self.tabs.write().get_mut(&tab_id) {
+            Some(tab) => tab.set_active_pane(pane_id),
+            None => bail!("Tab {} not found", tab_id),
+        }
+    }
+
     /// Focus a specific pane in the active tab
     pub fn focus_pane(&self, pane_id: PaneId) -> Result<()> {
         let mut tabs = self.tabs.write();
@@ -404,21 +666,134 @@ impl Session {
         tabs.values().flat_map(|tab| tab.panes().cloned()).collect()
     }
 
+    /// Get every tab's panes, with the tab's ID
+    pub fn panes_by_tab(&self) -> Vec<(TabId, Vec<Arc<Pane>>)> {
+        let tabs = self.tabs.read();
+        tabs.iter()
+            .map(|(tab_id, tab)| (*tab_id, tab.panes().cloned().collect()))
+            .collect()
+    }
+
+    /// Get a pane of any tab
+    pub fn get_pane(&self, tab_id: TabId, pane_id: PaneId) -> Option<Arc<Pane>> {
+        self.tabs.read().get(&tab_id)?.get_pane(pane_id)
+    }
+
+    /// Layout of a tab's panes, empty if the tab does not exist
+    pub fn pane_infos(&self, tab_id: TabId) -> Vec<PaneInfo> {
+        let tabs = self.tabs.read();
+        let Some(tab) = tabs.get(&tab_id) else {
+            return Vec::new();
+        };
+        let active_pane_id = tab.active_pane_id();
+        tab.panes()
+            .map(|pane| PaneInfo {
+                id: pane.id,
+                x: pane.viewport.x,
+                y: pane.viewport.y,
+                width: pane.viewport.width,
+                height: pane.viewport.height,
+                is_focused: pane.id == active_pane_id,
+            })
+            .collect()
+    }
+
     // ==================== Client Management ====================
 
-    /// Attach a client to this session
+    /// Attach a client that may type into this session
+    ///
+    /// Callers check [`allows_writer`](Self::allows_writer) first.
     pub fn attach_client(&self, client_id: ClientId) {
+        self.attach(client_id);
+        self.read_only_clients.write().remove(&client_id);
+        self.writers.write().insert(client_id);
+    }
+
+    fn attach(&self, client_id: ClientId) {
         let mut clients = self.attached_clients.write();
         clients.insert(client_id);
         *self.last_attached.write() = SystemTime::now();
     }
 
+    /// Note that a client typed into this session
+    pub fn touch_input(&self) {
+        *self.last_input.write() = Instant::now();
+    }
+
+    /// Time since a client last typed into this session
+    pub fn idle_time(&self) -> Duration {
+        self.last_input.read().elapsed()
+    }
+
+    /// Attach a client that may watch but not type
+    pub fn attach_read_only_client(&self, client_id: ClientId) {
+        self.attach(client_id);
+        self.read_only_clients.write().insert(client_id);
+        self.writers.write().remove(&client_id);
+    }
+
     /// Detach a client from this session
+    ///
+    /// A client that attached read-only stays read-only here until it
+    /// disconnects.
     pub fn detach_client(&self, client_id: ClientId) {
         let mut clients = self.attached_clients.write();
         clients.remove(&client_id);
     }
 
+    /// Drop everything this session knows about a disconnected client
+    pub fn forget_client(&self, client_id: ClientId) {
+        self.detach_client(client_id);
+        self.read_only_clients.write().remove(&client_id);
+        self.writers.write().remove(&client_id);
+    }
+
+    /// Check if a client is read-only in this session
+    pub fn is_read_only_client(&self, client_id: ClientId) -> bool {
+        self.read_only_clients.read().contains(&client_id)
+    }
+
+    /// Whether the session's access policy lets a client type
+    ///
+    /// Clients that attached read-only never may; with
+    /// [`read_only_by_default`](Self::read_only_by_default) set, only
+    /// clients that already could may.
+    pub fn allows_writer(&self, client_id: ClientId) -> bool {
+        if self.is_read_only_client(client_id) {
+            return false;
+        }
+        !*self.read_only_by_default.read() || self.writers.read().contains(&client_id)
+    }
+
+    /// Whether clients that can't type yet may only attach read-only
+    pub fn read_only_by_default(&self) -> bool {
+        *self.read_only_by_default.read()
+    }
+
+    /// Let only `writer` and the clients that already can type into this
+    /// session keep doing so, or let every client attach able to type
+    pub fn set_read_only_by_default(&self, read_only_by_default: bool, writer: ClientId) {
+        if read_only_by_default {
+            self.writers.write().insert(writer);
+        }
+        *self.read_only_by_default.write() = read_only_by_default;
+    }
+
+    /// Get the number of attached read-only clients
+    pub fn read_only_client_count(&self) -> usize {
+        let attached = self.attached_clients.read();
+        self.read_only_clients
+            .read()
+            .iter()
+            .filter(|id| attached.contains(id))
+            .count()
+    }
+
+    /// Check if a specific client is attached to this session
+    pub fn has_client(&self, client_id: ClientId) -> bool {
+        self.attached_clients.read().contains(&client_id)
+    }
+
     /// Check if session has any attached clients
     pub fn has_attached_clients(&self) -> bool {
         !self.attached_clients.read().is_empty()
@@ -430,11 +805,32 @@ impl Session {
     }
 }
 
+/// Shell, directory, resumable command and last lines of output of a pane
+fn pane_snapshot(pane: &Pane, scrollback_lines: usize) -> PaneSnapshot {
+    let command = pane
+        .foreground_argv()
+        .and_then(|argv| resumable_command(&argv, &pane.shell));
+    PaneSnapshot {
+        shell: pane.shell.clone(),
+        cwd: pane.current_dir(),
+        command,
+        scrollback: pane.output_tail(scrollback_lines),
+    }
+}
+
 /// Manages multiple sessions
 pub struct SessionManager {
     sessions: Arc<RwLock<HashMap<SessionId, Arc<Session>>>>,
     store: SessionStore,
     default_session_id: Arc<RwLock<Option<SessionId>>>,
+    /// Shared memory region of the default session, which other sessions' are named from
+    shmem_path: String,
+    /// Sessions of a daemon that crashed, until the user decides about them
+    pending_recovery: RwLock<Option<WorkspaceSnapshot>>,
+    /// Domains tabs and panes can be opened in
+    domains: Arc<DomainRegistry>,
+    /// Where workspaces are saved and restored from
+    workspaces_dir: PathBuf,
 }
 
 impl SessionManager {
@@ -446,9 +842,46 @@ impl SessionManager {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             store,
             default_session_id: Arc::new(RwLock::new(None)),
+            shmem_path: super::base_shmem_path(),
+            pending_recovery: RwLock::new(None),
+            domains: Arc::new(DomainRegistry::new()),
+            workspaces_dir: workspace::workspaces_dir(&Paths::from_env().data_dir),
         })
     }
 
+    /// Draw the default session to the region at `path`, and name other sessions' from it
+    pub fn with_shmem_path(mut self, path: impl Into<String>) -> Self {
+        self.shmem_path = path.into();
+        self
+    }
+
+    /// Shared memory region of the default session
+    pub fn shmem_path(&self) -> &str {
+        &self.shmem_path
+    }
+
+    /// Open tabs and panes in the domains of `registry`
+    pub fn with_domains(mut self, registry: Arc<DomainRegistry>) -> Self {
+        self.domains = registry;
+        self
+    }
+
+    /// Domains tabs and panes can be opened in
+    pub fn domains(&self) -> &Arc<DomainRegistry> {
+        &self.domains
+    }
+
+    /// Save and restore workspaces under `data_dir`
+    pub fn with_data_dir(mut self, data_dir: &Path) -> Self {
+        self.workspaces_dir = workspace::workspaces_dir(data_dir);
+        self
+    }
+
+    /// Where workspaces are saved and restored from
+    pub fn workspaces_dir(&self) -> &Path {
+        &self.workspaces_dir
+    }
+
     /// Initialize from persisted sessions
     ///
     /// This restores session metadata from the database and spawns new PTYs
@@ -541,6 +974,84 @@ impl SessionManager {
         }
     }
 
+    /// Check whether `id` is the session drawn to the main shared memory region
+    pub fn is_default_session(&self, id: &SessionId) -> bool {
+        self.get_default_session()
+            .map_or(false, |session| &session.id == id)
+    }
+
+    /// Session holding `pane` in one of its tabs
+    pub fn session_with_pane(&self, pane: &Arc<Pane>) -> Option<Arc<Session>> {
+        self.sessions
+            .read()
+            .values()
+            .find(|session| {
+                session
+                    .all_panes()
+                    .iter()
+                    .any(|other| Arc::ptr_eq(other, pane))
+            })
+            .cloned()
+    }
+
+    /// Non-default session a client is attached to, if any
+    ///
+    /// Clients driving an additional window attach to their own session;
+    /// their input and resizes go there instead of the default session.
+    pub fn client_session(&self, client_id: ClientId) -> Option<Arc<Session>> {
+        self.attached_secondary_sessions()
+            .into_iter()
+            .find(|session| session.has_client(client_id))
+    }
+
+    /// Non-default sessions with at least one attached client
+    pub fn attached_secondary_sessions(&self) -> Vec<Arc<Session>> {
+        let default_id = self.get_default_session().map(|s| s.id.clone());
+        self.sessions
+            .read()
+            .values()
+            .filter(|s| Some(&s.id) != default_id.as_ref() && s.has_attached_clients())
+            .cloned()
+            .collect()
+    }
+
+    /// Whether a client may do more than watch its session
+    ///
+    /// False for clients attached read-only, and for clients that never
+    /// attached when the default session is read-only by default.
+    pub fn can_write(&self, client_id: ClientId) -> bool {
+        if let Some(session) = self.client_session(client_id) {
+            return !session.is_read_only_client(client_id);
+        }
+        self.get_default_session().map_or(true, |session| {
+            if session.has_client(client_id) {
+                !session.is_read_only_client(client_id)
+            } else {
+                session.allows_writer(client_id)
+            }
+        })
+    }
+
+    /// Note that a client typed into the session its input goes to
+    pub fn record_input(&self, client_id: ClientId) {
+        if let Some(session) = self
+            .client_session(client_id)
+            .or_else(|| self.get_default_session())
+        {
+            session.touch_input();
+        }
+    }
+
+    /// Detach a client from every session (on disconnect)
+    pub fn detach_client_everywhere(&self, client_id: ClientId) {
+        for session in self.sessions.read().values() {
+            if session.has_client(client_id) {
+                log::info!("Client {} detached from session {}", client_id, session.id);
+            }
+            session.forget_client(client_id);
+        }
+    }
+
     /// List all sessions
     pub fn list_sessions(&self) -> Vec<(SessionId, String, u64, u64, usize)> {
         let sessions = self.sessions.read();
@@ -571,19 +1082,96 @@ impl SessionManager {
             .collect()
     }
 
+    /// Session metadata for listing to clients and plugins
+    pub fn session_infos(&self) -> Vec<SessionInfo> {
+        self.list_sessions()
+            .into_iter()
+            .map(|(id, name, created_at, last_attached, attached_clients)| {
+                let session = self.get_session(&id);
+                SessionInfo {
+                    read_only_clients: session
+                        .as_ref()
+                        .map_or(0, |s| s.read_only_client_count() as u32),
+                    read_only_by_default: session
+                        .as_ref()
+                        .is_some_and(|s| s.read_only_by_default()),
+                    idle_secs: session.map_or(0, |s| s.idle_time().as_secs()),
+                    id,
+                    name,
+                    created_at,
+                    last_attached,
+                    attached_clients: attached_clients as u32,
+                }
+            })
+            .collect()
+    }
+
     /// Attach a client to a session
     pub fn attach_client(&self, session_id: &SessionId, client_id: ClientId) -> Result<()> {
-        if let Some(session) = self.get_session(session_id) {
+        self.attach_client_as(session_id, client_id, false)
+            .map(|_| ())
+    }
+
+    /// Attach a client to a session, read-only if asked or if the
+    /// session's access policy doesn't let it type
+    ///
+    /// Returns whether the client was attached read-only.
+    pub fn attach_client_as(
+        &self,
+        session_id: &SessionId,
+        client_id: ClientId,
+        read_only: bool,
+    ) -> Result<bool> {
+        let Some(session) = self.get_session(session_id) else {
+            bail!("Session not found: {}", session_id)
+        };
+        let read_only = read_only || !session.allows_writer(client_id);
+        if read_only {
+            session.attach_read_only_client(client_id);
+        } else {
             session.attach_client(client_id);
+        }
 
-            // Update persistence
-            self.store.update_last_attached(session_id)?;
+        // Update persistence
+        self.store.update_last_attached(session_id)?;
 
-            log::info!("Client {} attached to session {}", client_id, session_id);
-            Ok(())
-        } else {
+        log::info!(
+            "Client {} attached to session {}{}",
+            client_id,
+            session_id,
+            if read_only { " (read-only)" } else { "" }
+        );
+        Ok(read_only)
+    }
+
+    /// Make clients that can't type into a session yet attach read-only,
+    /// or let them type again
+    ///
+    /// Only a client that can type into the session may change this.
+    pub fn set_read_only_by_default(
+        &self,
+        session_id: &SessionId,
+        client_id: ClientId,
+        read_only_by_default: bool,
+    ) -> Result<()> {
+        let Some(session) = self.get_session(session_id) else {
             bail!("Session not found: {}", session_id)
+        };
+        if !session.allows_writer(client_id) {
+            bail!("Only clients that can type into a session may change its access");
         }
+        session.set_read_only_by_default(read_only_by_default, client_id);
+        log::info!(
+            "Client {} made session {} {}",
+            client_id,
+            session_id,
+            if read_only_by_default {
+                "read-only for new clients"
+            } else {
+                "writable for every client"
+            }
+        );
+        Ok(())
     }
 
     /// Detach a client from a session
@@ -611,6 +1199,138 @@ impl SessionManager {
         }
     }
 
+    /// Every session with its tabs and panes, oldest session first
+    ///
+    /// Up to `scrollback_lines` of each pane's output are kept with it.
+    pub fn snapshot_workspace(&self, scrollback_lines: usize) -> WorkspaceSnapshot {
+        let mut sessions: Vec<Arc<Session>> = self.sessions.read().values().cloned().collect();
+        sessions.sort_by_key(|session| session.created_at);
+        WorkspaceSnapshot {
+            version: SNAPSHOT_VERSION,
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            sessions: sessions
+                .iter()
+                .map(|session| session.snapshot(scrollback_lines))
+                .collect(),
+        }
+    }
+
+    /// Recreate the sessions of a saved workspace
+    ///
+    /// Sessions already running under a saved session's name are left
+    /// alone. Returns the IDs of the sessions created.
+    pub fn restore_workspace(
+        &self,
+        snapshot: &WorkspaceSnapshot,
+        cols: u16,
+        rows: u16,
+    ) -> Result<Vec<SessionId>> {
+        let mut restored = Vec::new();
+        for saved in &snapshot.sessions {
+            let running = self
+                .sessions
+                .read()
+                .values()
+                .any(|session| session.name == saved.name);
+            if running {
+                log::info!(
+                    "Session '{}' is already running, not restoring it",
+                    saved.name
+                );
+                continue;
+            }
+
+            let session = Session::from_snapshot(saved, cols, rows)?;
+            let id = session.id.clone();
+            self.store.save_session(&session)?;
+
+            let mut sessions = self.sessions.write();
+            sessions.insert(id.clone(), Arc::new(session));
+            if sessions.len() == 1 {
+                *self.default_session_id.write() = Some(id.clone());
+            }
+            log::info!("Restored session: {} ({})", saved.name, id);
+            restored.push(id);
+        }
+        Ok(restored)
+    }
+
+    /// Offer the sessions of a crashed daemon to the user
+    pub fn set_pending_recovery(&self, snapshot: WorkspaceSnapshot) {
+        *self.pending_recovery.write() = Some(snapshot).filter(|s| !s.sessions.is_empty());
+    }
+
+    /// Sessions of a crashed daemon the user hasn't decided about yet
+    pub fn pending_recovery(&self) -> Option<WorkspaceSnapshot> {
+        self.pending_recovery.read().clone()
+    }
+
+    /// Take the pending session named `name`, or every one with `None`,
+    /// out of the recovery
+    pub fn take_pending_recovery(&self, name: Option<&str>) -> Vec<SessionSnapshot> {
+        let mut pending = self.pending_recovery.write();
+        let Some(snapshot) = pending.as_mut() else {
+            return Vec::new();
+        };
+        let (taken, kept) = std::mem::take(&mut snapshot.sessions)
+            .into_iter()
+            .partition(|saved| name.map_or(true, |name| saved.name == name));
+        snapshot.sessions = kept;
+        if snapshot.sessions.is_empty() {
+            *pending = None;
+        }
+        taken
+    }
+
+    /// Put a session of a crashed daemon back as it was checkpointed
+    ///
+    /// After a crash the daemon starts its sessions again from the database
+    /// with a single shell each, so a running session of the same name is
+    /// replaced, keeping its ID, clients and size. Returns the session's ID
+    /// and the panes that were replaced.
+    pub fn recover_session(
+        &self,
+        saved: &SessionSnapshot,
+        cols: u16,
+        rows: u16,
+    ) -> Result<(SessionId, Vec<PaneId>)> {
+        let replaced = self
+            .sessions
+            .read()
+            .values()
+            .find(|running| running.name == saved.name)
+            .cloned();
+        let (cols, rows) = replaced
+            .as_ref()
+            .and_then(|old| old.get_active_pane())
+            .map_or((cols, rows), |pane| pane.dimensions());
+
+        let mut session = Session::from_snapshot(saved, cols, rows)?;
+        let mut replaced_panes = Vec::new();
+        if let Some(old) = replaced {
+            session.id = old.id.clone();
+            session.created_at = old.created_at;
+            *session.attached_clients.write() = old.attached_clients.read().clone();
+            *session.read_only_clients.write() = old.read_only_clients.read().clone();
+            *session.writers.write() = old.writers.read().clone();
+            *session.read_only_by_default.write() = old.read_only_by_default();
+            replaced_panes = old.all_panes().iter().map(|pane| pane.id).collect();
+        }
+
+        let id = session.id.clone();
+        self.store.save_session(&session)?;
+        let mut sessions = self.sessions.write();
+        sessions.insert(id.clone(), Arc::new(session));
+        if sessions.len() == 1 {
+            *self.default_session_id.write() = Some(id.clone());
+        }
+        log::info!("Recovered session: {} ({})", saved.name, id);
+        Ok((id, replaced_panes))
+    }
+
     /// Get session count
     pub fn session_count(&self) -> usize {
         self.sessions.read().len()
@@ -695,6 +1415,195 @@ mod tests {
         assert!(!session.has_attached_clients());
     }
 
+    #[test]
+    fn test_client_session_routing() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("sessions.db");
+
+        let manager = SessionManager::new(db_path).unwrap();
+        let main = manager.create_session("main".to_string(), 80, 24).unwrap();
+        let second = manager
+            .create_session("second".to_string(), 80, 24)
+            .unwrap();
+        assert!(manager.is_default_session(&main));
+        assert!(!manager.is_default_session(&second));
+
+        // Clients on the default session are routed there implicitly
+        manager.attach_client(&main, 1).unwrap();
+        assert!(manager.client_session(1).is_none());
+        assert!(manager.attached_secondary_sessions().is_empty());
+
+        manager.attach_client(&second, 2).unwrap();
+        assert_eq!(manager.client_session(2).unwrap().id, second);
+        assert_eq!(manager.attached_secondary_sessions().len(), 1);
+
+        manager.detach_client_everywhere(2);
+        assert!(manager.client_session(2).is_none());
+        assert!(manager.attached_secondary_sessions().is_empty());
+    }
+
+    #[test]
+    fn test_read_only_attach() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("sessions.db");
+
+        let manager = SessionManager::new(db_path).unwrap();
+        let main = manager.create_session("main".to_string(), 80, 24).unwrap();
+
+        assert!(manager.attach_client_as(&main, 1, true).unwrap());
+        manager.attach_client(&main, 2).unwrap();
+        assert!(!manager.can_write(1));
+        assert!(manager.can_write(2));
+
+        let info = &manager.session_infos()[0];
+        assert_eq!(info.attached_clients, 2);
+        assert_eq!(info.read_only_clients, 1);
+
+        // Detaching doesn't drop the restriction; disconnecting does
+        manager.detach_client(&main, 1).unwrap();
+        assert!(!manager.can_write(1));
+        manager.detach_client_everywhere(1);
+        assert!(manager.can_write(1));
+    }
+
+    #[test]
+    fn test_viewer_cannot_reattach_as_writer() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("sessions.db");
+
+        let manager = SessionManager::new(db_path).unwrap();
+        let main = manager.create_session("main".to_string(), 80, 24).unwrap();
+
+        assert!(manager.attach_client_as(&main, 1, true).unwrap());
+        assert!(manager.attach_client_as(&main, 1, false).unwrap());
+        assert!(!manager.can_write(1));
+        manager.detach_client(&main, 1).unwrap();
+        assert!(manager.attach_client_as(&main, 1, false).unwrap());
+        assert!(!manager.can_write(1));
+
+        // A viewer can't open the session up to itself either
+        assert!(manager.set_read_only_by_default(&main, 1, false).is_err());
+    }
+
+    #[test]
+    fn test_read_only_by_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("sessions.db");
+
+        let manager = SessionManager::new(db_path).unwrap();
+        let main = manager.create_session("main".to_string(), 80, 24).unwrap();
+        manager.attach_client(&main, 1).unwrap();
+        manager.set_read_only_by_default(&main, 1, true).unwrap();
+        assert!(manager.session_infos()[0].read_only_by_default);
+
+        // Clients that could type keep typing; new ones only watch
+        assert!(!manager.attach_client_as(&main, 1, false).unwrap());
+        assert!(manager.attach_client_as(&main, 2, false).unwrap());
+        assert!(!manager.can_write(2));
+        // Even without attaching
+        assert!(!manager.can_write(3));
+
+        manager.set_read_only_by_default(&main, 1, false).unwrap();
+        assert!(manager.can_write(3));
+        assert!(!manager.attach_client_as(&main, 3, false).unwrap());
+    }
+
+    #[test]
+    fn test_idle_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("sessions.db");
+
+        let manager = SessionManager::new(db_path).unwrap();
+        let main = manager.create_session("main".to_string(), 80, 24).unwrap();
+        let work = manager.create_session("work".to_string(), 80, 24).unwrap();
+        manager.attach_client(&work, 1).unwrap();
+        for id in [&main, &work] {
+            *manager.get_session(id).unwrap().last_input.write() =
+                Instant::now() - Duration::from_secs(120);
+        }
+
+        // Typing only wakes the session the client is attached to
+        manager.record_input(1);
+        let idle = |name: &str| {
+            manager
+                .session_infos()
+                .into_iter()
+                .find(|info| info.name == name)
+                .unwrap()
+                .idle_secs
+        };
+        assert!(idle("work") < 120);
+        assert_eq!(idle("main"), 120);
+    }
+
+    #[test]
+    fn test_workspace_snapshot_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::new(temp_dir.path().join("sessions.db")).unwrap();
+        let id = manager.create_session("dev".to_string(), 80, 24).unwrap();
+        let session = manager.get_session(&id).unwrap();
+        let logs = session.create_tab(Some("logs".to_string())).unwrap();
+        session.switch_tab(logs).unwrap();
+        session.split_pane(SplitDirection::Vertical).unwrap();
+
+        let snapshot = manager.snapshot_workspace(0);
+        let saved = &snapshot.sessions[0];
+        assert_eq!(saved.name, "dev");
+        assert_eq!(saved.active_tab, 1);
+        assert_eq!(saved.tabs[1].title, "logs");
+        assert_eq!(saved.tabs[1].panes.len(), 2);
+        assert_eq!(saved.tabs[1].panes[0].shell, "bash");
+
+        // Running sessions aren't restored twice
+        assert!(manager
+            .restore_workspace(&snapshot, 80, 24)
+            .unwrap()
+            .is_empty());
+
+        let other = SessionManager::new(temp_dir.path().join("other.db")).unwrap();
+        let restored = other.restore_workspace(&snapshot, 80, 24).unwrap();
+        let session = other.get_session(&restored[0]).unwrap();
+        assert_eq!(session.name, "dev");
+        assert_eq!(session.tab_count(), 2);
+        assert_eq!(session.active_tab_id(), logs);
+        assert_eq!(session.snapshot(0).tabs[1].panes.len(), 2);
+        assert!(other.is_default_session(&session.id));
+    }
+
+    #[test]
+    fn test_crash_recovery() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::new(temp_dir.path().join("sessions.db")).unwrap();
+        let id = manager.create_session("dev".to_string(), 80, 24).unwrap();
+        manager.attach_client(&id, 7).unwrap();
+
+        let mut saved = manager.snapshot_workspace(0);
+        saved.sessions[0].tabs[0].panes[0].scrollback =
+            vec!["$ make".to_string(), "build ok".to_string()];
+        let mut logs = saved.sessions[0].clone();
+        logs.name = "logs".to_string();
+        saved.sessions.push(logs);
+        manager.set_pending_recovery(saved);
+
+        // The running session of the same name is replaced in place
+        let dev = manager.take_pending_recovery(Some("dev"));
+        assert_eq!(dev.len(), 1);
+        let (recovered, replaced) = manager.recover_session(&dev[0], 80, 24).unwrap();
+        assert_eq!(recovered, id);
+        assert_eq!(replaced, vec![1]);
+        let session = manager.get_session(&id).unwrap();
+        assert!(session.has_client(7));
+        assert_eq!(
+            session.get_active_pane().unwrap().output_tail(10),
+            vec!["$ make".to_string(), "build ok".to_string()]
+        );
+
+        // The rest stays pending until taken
+        assert_eq!(manager.pending_recovery().unwrap().sessions.len(), 1);
+        assert_eq!(manager.take_pending_recovery(None).len(), 1);
+        assert!(manager.pending_recovery().is_none());
+    }
+
     #[test]
     fn test_session_has_initial_tab_and_pane() {
         let session = Session::new("test".to_string(), 80, 24).unwrap();
@@ -732,6 +1641,22 @@ mod tests {
         assert_eq!(session.tab_count(), 1);
     }
 
+    #[test]
+    fn test_session_startup_tabs() {
+        let session = Session::new("test".to_string(), 80, 24).unwrap();
+        let first = session.active_tab_id();
+
+        let titles = vec!["editor".to_string(), "server".to_string()];
+        session.open_startup_tabs(&titles).unwrap();
+
+        let tabs = session.list_tabs();
+        let mut names: Vec<_> = tabs.iter().map(|(_, title, _, _)| title.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["editor", "server"]);
+        // The first tab stays active
+        assert_eq!(session.active_tab_id(), first);
+    }
+
     #[test]
     fn test_session_cannot_close_last_tab() {
         let session = Session::new("test".to_string(), 80, 24).unwrap();
@@ -1,14 +1,17 @@
 mod commands;
 mod manager;
 pub mod pane;
+mod shmem;
 mod store;
 pub mod tab;
 
 pub use commands::{
-    handle_pane_command, handle_session_command, handle_tab_command, TabCommandResult,
+    handle_domain_command, handle_pane_command, handle_session_command, handle_tab_command,
+    may_connect, TabCommandResult,
 };
 pub use manager::{Session, SessionManager};
 pub use pane::{Pane, PaneId, Rect};
+pub use shmem::{base_shmem_path, shmem_path_for, SessionRegions};
 pub use store::SessionStore;
 pub use tab::{SplitDirection, Tab, TabId};
 
@@ -1,7 +1,9 @@
-use crate::vte::TerminalState;
+use crate::vte::{ReportedColors, TerminalState};
 use anyhow::Result;
 use parking_lot::RwLock;
 use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 
@@ -64,6 +66,8 @@ pub struct Pane {
     pub cwd: Option<String>,
     /// Timestamp when pane was created
     pub created_at: SystemTime,
+    /// Set when output arrives, cleared when the pane's tab is viewed
+    activity: AtomicBool,
 }
 
 // Pane is Sync because all interior mutability is behind locks
@@ -103,6 +107,10 @@ impl Pane {
         // Set TERM so the shell knows what terminal capabilities we support
         cmd.env("TERM", "xterm-256color");
 
+        // Lets shell integration scripts tell they run inside Scarab
+        cmd.env("TERM_PROGRAM", "scarab");
+        cmd.env("TERM_PROGRAM_VERSION", env!("CARGO_PKG_VERSION"));
+
         // Spawn shell in PTY
         let _child = pair.slave.spawn_command(cmd)?;
 
@@ -124,6 +132,7 @@ impl Pane {
             shell: shell.to_string(),
             cwd,
             created_at: SystemTime::now(),
+            activity: AtomicBool::new(false),
         })
     }
 
@@ -138,6 +147,33 @@ impl Pane {
             shell,
             cwd,
             created_at: SystemTime::now(),
+            activity: AtomicBool::new(false),
+        }
+    }
+
+    /// Create a pane for a program running elsewhere, such as a pane of a
+    /// tmux in control mode, whose input goes to `writer`
+    ///
+    /// There is no PTY to read; output is fed in with `process_output`.
+    pub fn remote(
+        id: PaneId,
+        label: String,
+        viewport: Rect,
+        writer: Box<dyn std::io::Write + Send>,
+    ) -> Self {
+        Self {
+            id,
+            pty_master: Arc::new(Mutex::new(None)),
+            pty_writer: Arc::new(Mutex::new(Some(writer))),
+            terminal_state: Arc::new(RwLock::new(TerminalState::new(
+                viewport.width,
+                viewport.height,
+            ))),
+            viewport,
+            shell: label,
+            cwd: None,
+            created_at: SystemTime::now(),
+            activity: AtomicBool::new(false),
         }
     }
 
@@ -206,12 +242,175 @@ impl Pane {
         state.process_output(data);
     }
 
+    /// Process output that didn't come from the pane's PTY, answering any
+    /// queries in it through the pane's writer
+    pub fn feed_output(&self, data: &[u8]) {
+        let responses: Vec<Vec<u8>> = {
+            let mut state = self.terminal_state.write();
+            state.process_output(data);
+            state.pending_responses.drain(..).collect()
+        };
+        self.mark_activity();
+        self.send_responses(responses);
+    }
+
+    /// Change the colors reported to the pane's programs, sending any
+    /// light/dark report they asked for
+    pub fn set_reported_colors(&self, colors: ReportedColors) {
+        let responses: Vec<Vec<u8>> = {
+            let mut state = self.terminal_state.write();
+            state.set_reported_colors(colors);
+            state.pending_responses.drain(..).collect()
+        };
+        self.send_responses(responses);
+    }
+
+    /// Up to `max_lines` of the pane's text, ending at its last non-blank line
+    pub fn output_tail(&self, max_lines: usize) -> Vec<String> {
+        let state = self.terminal_state.read();
+        let total = state.scrollback_len() + state.grid.rows as usize;
+        let mut lines: Vec<String> = (0..total)
+            .filter_map(|line| state.line_text(line))
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines.split_off(lines.len().saturating_sub(max_lines))
+    }
+
+    /// Give the pane a theme of its own, or take it away with `None`
+    pub fn set_theme(&self, colors: Option<ReportedColors>) {
+        let responses: Vec<Vec<u8>> = {
+            let mut state = self.terminal_state.write();
+            state.set_pane_theme(colors);
+            state.pending_responses.drain(..).collect()
+        };
+        self.send_responses(responses);
+    }
+
+    fn send_responses(&self, responses: Vec<Vec<u8>>) {
+        if responses.is_empty() {
+            return;
+        }
+
+        let mut writer = match self.pty_writer.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(ref mut writer) = *writer {
+            for response in responses {
+                if let Err(e) = writer.write_all(&response) {
+                    log::warn!("Failed to answer terminal query: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Process ID of the program in the foreground
+    #[cfg(unix)]
+    fn foreground_pid(&self) -> Option<i32> {
+        let master = match self.pty_master.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        master.as_ref()?.process_group_leader()
+    }
+
+    /// User and command line of the program in the foreground, such as
+    /// the shell or an ssh it started
+    #[cfg(unix)]
+    pub fn foreground_process(&self) -> Option<(String, String)> {
+        let pid = self.foreground_pid()?;
+        let output = std::process::Command::new("ps")
+            .args(["-o", "user=,args=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        parse_ps_line(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    #[cfg(not(unix))]
+    pub fn foreground_process(&self) -> Option<(String, String)> {
+        None
+    }
+
+    /// Arguments of the program in the foreground, as it was started
+    ///
+    /// Unlike the command line of [`Self::foreground_process`], which `ps`
+    /// joins with spaces, these keep arguments with spaces in them apart.
+    #[cfg(target_os = "linux")]
+    pub fn foreground_argv(&self) -> Option<Vec<String>> {
+        let pid = self.foreground_pid()?;
+        let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+        let argv: Vec<String> = cmdline
+            .split(|&b| b == 0)
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect();
+        // The list ends with a NUL, leaving an empty last entry
+        match argv.split_last() {
+            Some((last, args)) if last.is_empty() && !args.is_empty() => Some(args.to_vec()),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn foreground_argv(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Working directory of the program in the foreground, or the one the
+    /// pane was opened in where that can't be read
+    pub fn current_dir(&self) -> Option<String> {
+        #[cfg(target_os = "linux")]
+        if let Some(pid) = self.foreground_pid() {
+            if let Ok(dir) = std::fs::read_link(format!("/proc/{}/cwd", pid)) {
+                return Some(dir.to_string_lossy().into_owned());
+            }
+        }
+        self.cwd.clone()
+    }
+
+    /// Type `command` into the pane's shell and run it
+    pub fn run_command(&self, command: &str) -> Result<()> {
+        let mut writer = match self.pty_writer.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(writer) = writer.as_mut() {
+            writer.write_all(command.as_bytes())?;
+            writer.write_all(b"\r")?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Record that the pane produced output
+    pub fn mark_activity(&self) {
+        self.activity.store(true, Ordering::Relaxed);
+    }
+
+    /// Check whether the pane produced output since it was last viewed
+    pub fn has_activity(&self) -> bool {
+        self.activity.load(Ordering::Relaxed)
+    }
+
+    /// Reset the activity flag (the pane is being viewed)
+    pub fn clear_activity(&self) {
+        self.activity.store(false, Ordering::Relaxed);
+    }
+
     /// Get a reference to the terminal state for blitting
     pub fn terminal_state(&self) -> &Arc<RwLock<TerminalState>> {
         &self.terminal_state
     }
 }
 
+/// `ps -o user=,args=` output: the user, then the command line
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_ps_line(output: &str) -> Option<(String, String)> {
+    let (user, command) = output.trim().split_once(char::is_whitespace)?;
+    Some((user.to_string(), command.trim_start().to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,6 +436,15 @@ mod tests {
         assert_eq!(pane.viewport.height, 12);
     }
 
+    #[test]
+    fn test_parse_ps_line() {
+        assert_eq!(
+            parse_ps_line("root      ssh db.prod.example.com\n"),
+            Some(("root".to_string(), "ssh db.prod.example.com".to_string()))
+        );
+        assert_eq!(parse_ps_line(""), None);
+    }
+
     #[test]
     fn test_rect_full() {
         let rect = Rect::full(120, 40);
@@ -0,0 +1,141 @@
+//! Per-session shared memory regions
+//!
+//! The default session is drawn to the main region, `SHMEM_PATH` unless
+//! `[paths]` or the environment moves it. Every
+//! other session with an attached client (an additional client window) gets
+//! its own region, named from the base path and the session id, so each
+//! window can map the grid of the session it shows.
+
+use super::pane::PaneId;
+use super::{SessionId, SessionManager};
+use scarab_protocol::{session_shmem_path, SharedState, SHMEM_PATH, SHMEM_PATH_ENV};
+use shared_memory::{Shmem, ShmemConf, ShmemError};
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+
+/// Slime theme colors used to clear a fresh region
+const DEFAULT_BG: u32 = 0xFF0D1208;
+const DEFAULT_FG: u32 = 0xFFA8DF5A;
+
+/// Base shared memory path, honoring the environment override
+///
+/// The daemon passes the path from its config to
+/// [`SessionManager::with_shmem_path`]; this is the default without one.
+pub fn base_shmem_path() -> String {
+    std::env::var(SHMEM_PATH_ENV).unwrap_or_else(|_| SHMEM_PATH.to_string())
+}
+
+/// Shared memory path a session is drawn to
+pub fn shmem_path_for(session_manager: &SessionManager, id: &SessionId) -> String {
+    let base = session_manager.shmem_path();
+    if session_manager.is_default_session(id) {
+        base.to_string()
+    } else {
+        session_shmem_path(base, id)
+    }
+}
+
+/// A mapped region and the pane last drawn into it
+struct SessionRegion {
+    shmem: Shmem,
+    sequence: Arc<AtomicU64>,
+    pane_id: Option<PaneId>,
+}
+
+/// Shared memory regions for attached non-default sessions
+#[derive(Default)]
+pub struct SessionRegions {
+    regions: HashMap<SessionId, SessionRegion>,
+    failed: Vec<SessionId>,
+}
+
+impl SessionRegions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Blit each attached secondary session's active pane to its region
+    ///
+    /// Regions are created when a session first gains a client and dropped
+    /// once it has none left.
+    pub fn sync(&mut self, session_manager: &SessionManager) {
+        let sessions = session_manager.attached_secondary_sessions();
+        self.regions
+            .retain(|id, _| sessions.iter().any(|session| &session.id == id));
+
+        for session in sessions {
+            if !self.regions.contains_key(&session.id) {
+                let path = session_shmem_path(session_manager.shmem_path(), &session.id);
+                match open_region(&path) {
+                    Ok(shmem) => {
+                        self.failed.retain(|id| id != &session.id);
+                        self.regions.insert(
+                            session.id.clone(),
+                            SessionRegion {
+                                shmem,
+                                sequence: Arc::new(AtomicU64::new(0)),
+                                pane_id: None,
+                            },
+                        );
+                    }
+                    Err(e) => {
+                        if !self.failed.contains(&session.id) {
+                            log::warn!(
+                                "Failed to map shared memory for session {}: {}",
+                                session.id,
+                                e
+                            );
+                            self.failed.push(session.id.clone());
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            let Some(region) = self.regions.get_mut(&session.id) else {
+                continue;
+            };
+            let Some(pane) = session.get_active_pane() else {
+                continue;
+            };
+
+            let terminal_state_arc = pane.terminal_state();
+            let mut terminal_state = terminal_state_arc.write();
+            // A new region or a pane switch needs a full blit even if the
+            // pane itself has not changed since it was last drawn
+            if region.pane_id != Some(pane.id) {
+                region.pane_id = Some(pane.id);
+                terminal_state.mark_changed();
+            }
+            let ptr = region.shmem.as_ptr() as *mut SharedState;
+            // SAFETY: ptr points to a mapped region sized for SharedState
+            unsafe { terminal_state.blit_to_shm(ptr, &region.sequence) };
+        }
+    }
+}
+
+/// Create (or reopen) a region and clear it to the theme background
+fn open_region(path: &str) -> Result<Shmem, ShmemError> {
+    let shmem = match ShmemConf::new()
+        .size(std::mem::size_of::<SharedState>())
+        .os_id(path)
+        .create()
+    {
+        Ok(shmem) => shmem,
+        Err(ShmemError::MappingIdExists) => ShmemConf::new().os_id(path).open()?,
+        Err(e) => return Err(e),
+    };
+
+    let ptr = shmem.as_ptr() as *mut SharedState;
+    // SAFETY: the region was just mapped with room for one SharedState
+    unsafe {
+        std::ptr::write_bytes(ptr, 0, 1);
+        for cell in (*ptr).cells.iter_mut() {
+            cell.bg = DEFAULT_BG;
+            cell.fg = DEFAULT_FG;
+            cell.char_codepoint = b' ' as u32;
+        }
+    }
+    Ok(shmem)
+}
@@ -1,5 +1,7 @@
 use super::pane::{Pane, PaneId, Rect};
 use anyhow::{bail, Result};
+use scarab_protocol::ProgressState;
+use scarab_session::DomainId;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::SystemTime;
@@ -28,6 +30,8 @@ pub struct Tab {
     active_pane_id: PaneId,
     /// Next pane ID to assign
     next_pane_id: PaneId,
+    /// Domain new panes are split into (`None` = local)
+    pub domain: Option<DomainId>,
     /// Tab creation timestamp
     pub created_at: SystemTime,
 }
@@ -47,6 +51,7 @@ impl Tab {
             panes,
             active_pane_id: pane_id,
             next_pane_id: 2,
+            domain: None,
             created_at: SystemTime::now(),
         })
     }
@@ -59,6 +64,7 @@ impl Tab {
             panes: HashMap::new(),
             active_pane_id: 0,
             next_pane_id: 1,
+            domain: None,
             created_at: SystemTime::now(),
         }
     }
@@ -67,6 +73,7 @@ impl Tab {
     pub fn add_pane(&mut self, pane: Pane) -> PaneId {
         let pane_id = pane.id;
         self.panes.insert(pane_id, Arc::new(pane));
+        self.next_pane_id = self.next_pane_id.max(pane_id + 1);
 
         // If this is the first pane, make it active
         if self.active_pane_id == 0 {
@@ -76,8 +83,8 @@ impl Tab {
         pane_id
     }
 
-    /// Split the active pane, creating a new pane
-    pub fn split_pane(&mut self, direction: SplitDirection, shell: &str) -> Result<PaneId> {
+    /// Size of the pane a split of the active pane makes
+    pub fn split_size(&self, direction: SplitDirection) -> Result<(u16, u16)> {
         let active_pane = self
             .get_active_pane()
             .ok_or_else(|| anyhow::anyhow!("No active pane to split"))?;
@@ -85,17 +92,30 @@ impl Tab {
         let (cols, rows) = active_pane.dimensions();
 
         // Calculate new dimensions based on split direction
-        let (new_cols, new_rows) = match direction {
+        Ok(match direction {
             SplitDirection::Horizontal => (cols, rows / 2),
             SplitDirection::Vertical => (cols / 2, rows),
-        };
+        })
+    }
+
+    /// Split the active pane, creating a new pane
+    pub fn split_pane(&mut self, direction: SplitDirection, shell: &str) -> Result<PaneId> {
+        let (new_cols, new_rows) = self.split_size(direction)?;
 
         // Create new pane
+        let new_pane_id = self.next_pane_id;
+        let new_pane = Pane::new(new_pane_id, shell, new_cols, new_rows, None)?;
+        self.split_with(new_pane)
+    }
+
+    /// Split the active pane with a pane made elsewhere, which is given
+    /// the next pane ID
+    pub fn split_with(&mut self, mut pane: Pane) -> Result<PaneId> {
         let new_pane_id = self.next_pane_id;
         self.next_pane_id += 1;
 
-        let new_pane = Pane::new(new_pane_id, shell, new_cols, new_rows, None)?;
-        self.panes.insert(new_pane_id, Arc::new(new_pane));
+        pane.id = new_pane_id;
+        self.panes.insert(new_pane_id, Arc::new(pane));
 
         // Update viewports (simplified - just splits in half)
         self.recalculate_layout()?;
@@ -154,6 +174,35 @@ impl Tab {
         self.panes.len()
     }
 
+    /// Check whether any pane produced output since the tab was last viewed
+    pub fn has_activity(&self) -> bool {
+        self.panes.values().any(|pane| pane.has_activity())
+    }
+
+    /// Reset activity for every pane in this tab
+    pub fn clear_activity(&self) {
+        for pane in self.panes.values() {
+            pane.clear_activity();
+        }
+    }
+
+    /// Take progress changes from the tab's panes
+    ///
+    /// Returns the tab's overall progress (the most severe pane state) if any
+    /// pane's progress changed since the last call.
+    pub fn take_progress_change(&self) -> Option<ProgressState> {
+        let mut changed = false;
+        let mut progress = ProgressState::Hidden;
+        for pane in self.panes.values() {
+            let mut state = pane.terminal_state().write();
+            changed |= state.take_progress_change().is_some();
+            if state.progress.severity() > progress.severity() {
+                progress = state.progress;
+            }
+        }
+        changed.then_some(progress)
+    }
+
     /// Get all pane IDs in this tab
     pub fn pane_ids(&self) -> Vec<PaneId> {
         self.panes.keys().copied().collect()
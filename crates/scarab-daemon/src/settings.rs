@@ -0,0 +1,254 @@
+//! The daemon's running configuration
+//!
+//! Starts as the config loaded at startup and follows `ConfigGet` and
+//! `ConfigSet` messages from clients and plugins, as well as edits to the
+//! config file. Plugins read it through [`PluginContext::get_setting`].
+//!
+//! Tabs and panes can override settings on top of it, such as a different
+//! theme for a pane connected to production. Overrides live only as long as
+//! the daemon and are resolved for the focused pane: its tab's first, then
+//! the pane's own.
+//!
+//! [`PluginContext::get_setting`]: scarab_plugin_api::PluginContext::get_setting
+
+use parking_lot::RwLock;
+use scarab_config::{settings, ScarabConfig};
+use scarab_plugin_api::Settings;
+use scarab_protocol::{ConfigEntry, ConfigScope, PanePalette};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// Live settings, and the file changes are persisted to
+pub struct RuntimeConfig {
+    config: RwLock<ScarabConfig>,
+    path: PathBuf,
+    /// Settings overridden per tab and pane, as TOML by dotted key
+    overrides: RwLock<HashMap<ConfigScope, BTreeMap<String, String>>>,
+    /// Theme picked for the desktop's light/dark mode, as JSON
+    system_theme: RwLock<Option<String>>,
+    /// Themes given to single panes, by pane
+    pane_palettes: RwLock<HashMap<u64, PanePalette>>,
+}
+
+impl RuntimeConfig {
+    /// Start from `config`; persisted changes are written to `path`
+    pub fn new(config: ScarabConfig, path: PathBuf) -> Self {
+        Self {
+            config: RwLock::new(config),
+            path,
+            overrides: RwLock::new(HashMap::new()),
+            system_theme: RwLock::new(None),
+            pane_palettes: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// A copy of the current configuration
+    pub fn snapshot(&self) -> ScarabConfig {
+        self.config.read().clone()
+    }
+
+    /// Replace the configuration, such as after the config file changed
+    pub fn replace(&self, config: ScarabConfig) {
+        *self.config.write() = config;
+    }
+
+    /// The theme following the desktop's appearance, sent to clients as
+    /// they connect
+    pub fn system_theme(&self) -> Option<String> {
+        self.system_theme.read().clone()
+    }
+
+    /// Set or clear the theme following the desktop's appearance
+    pub fn set_system_theme(&self, theme_json: Option<String>) {
+        *self.system_theme.write() = theme_json;
+    }
+
+    /// Panes with a theme of their own, sent to clients as they connect
+    pub fn pane_palettes(&self) -> Vec<(u64, PanePalette)> {
+        let palettes = self.pane_palettes.read();
+        palettes
+            .iter()
+            .map(|(pane_id, palette)| (*pane_id, palette.clone()))
+            .collect()
+    }
+
+    /// Give a pane a theme of its own, or take it away with `None`
+    pub fn set_pane_palette(&self, pane_id: u64, palette: Option<PanePalette>) {
+        let mut palettes = self.pane_palettes.write();
+        match palette {
+            Some(palette) => palettes.insert(pane_id, palette),
+            None => palettes.remove(&pane_id),
+        };
+    }
+
+    /// The setting at `key` as TOML
+    pub fn get(&self, key: &str) -> scarab_config::Result<String> {
+        let value = settings::get_setting(&self.config.read(), key)?;
+        Ok(value.to_string())
+    }
+
+    /// Change the setting at `key`, returning its new value as TOML
+    ///
+    /// The running config is only changed once the new value is valid and,
+    /// with `persist`, written to the config file.
+    pub fn set(&self, key: &str, value: &str, persist: bool) -> scarab_config::Result<String> {
+        let mut config = self.config.write();
+        let updated = settings::set_setting(&config, key, settings::parse_setting_value(value))?;
+        let value = settings::get_setting(&updated, key)?;
+        if persist {
+            settings::persist_setting(&self.path, key, &value)?;
+        }
+        *config = updated;
+        Ok(value.to_string())
+    }
+
+    /// Override `key` for one tab or pane, or drop the override with `None`
+    ///
+    /// The value is checked like one passed to [`set`](Self::set). Returns
+    /// the override's value as TOML.
+    pub fn set_override(
+        &self,
+        scope: ConfigScope,
+        key: &str,
+        value: Option<&str>,
+    ) -> scarab_config::Result<Option<String>> {
+        let mut overrides = self.overrides.write();
+        let Some(value) = value else {
+            if let Some(entries) = overrides.get_mut(&scope) {
+                entries.remove(key);
+                if entries.is_empty() {
+                    overrides.remove(&scope);
+                }
+            }
+            return Ok(None);
+        };
+
+        let config = self.config.read();
+        let updated = settings::set_setting(&config, key, settings::parse_setting_value(value))?;
+        let value = settings::get_setting(&updated, key)?.to_string();
+        overrides
+            .entry(scope)
+            .or_default()
+            .insert(key.to_string(), value.clone());
+        Ok(Some(value))
+    }
+
+    /// Drop every override for `scope`
+    pub fn clear_overrides(&self, scope: ConfigScope) {
+        self.overrides.write().remove(&scope);
+    }
+
+    /// Drop the overrides of tabs and panes `keep` says are gone
+    pub fn retain_overrides(&self, keep: impl Fn(ConfigScope) -> bool) {
+        self.overrides.write().retain(|scope, _| keep(*scope));
+    }
+
+    /// Overrides in effect for `pane_id` in `tab_id`
+    ///
+    /// The tab's come first, then the pane's; a key set for both only
+    /// appears with the pane's value.
+    pub fn overrides_for(&self, tab_id: u64, pane_id: u64) -> Vec<ConfigEntry> {
+        let overrides = self.overrides.read();
+        let tab = overrides.get(&ConfigScope::Tab(tab_id));
+        let pane = overrides.get(&ConfigScope::Pane(pane_id));
+
+        let mut entries: Vec<ConfigEntry> = Vec::new();
+        for (key, value) in tab.into_iter().flatten() {
+            if !pane.is_some_and(|pane| pane.contains_key(key)) {
+                entries.push(ConfigEntry {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        for (key, value) in pane.into_iter().flatten() {
+            entries.push(ConfigEntry {
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+        entries
+    }
+}
+
+impl Settings for RuntimeConfig {
+    fn get(&self, key: &str) -> Result<String, String> {
+        RuntimeConfig::get(self, key).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let runtime = RuntimeConfig::new(ScarabConfig::default(), path.clone());
+
+        assert_eq!(
+            runtime
+                .set("terminal.scrollback_lines", "20000", false)
+                .unwrap(),
+            "20000"
+        );
+        assert_eq!(runtime.snapshot().terminal.scrollback_lines, 20000);
+        assert!(!path.exists());
+
+        runtime.set("colors.theme", "nord", true).unwrap();
+        assert_eq!(runtime.get("colors.theme").unwrap(), "\"nord\"");
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("theme = \"nord\""));
+
+        // A rejected change leaves the running config alone
+        assert!(runtime.set("font.size", "0", false).is_err());
+        assert_eq!(
+            runtime.snapshot().font.size,
+            ScarabConfig::default().font.size
+        );
+    }
+
+    #[test]
+    fn test_overrides_layer_pane_over_tab() {
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = RuntimeConfig::new(ScarabConfig::default(), dir.path().join("config.toml"));
+
+        runtime
+            .set_override(ConfigScope::Tab(1), "font.size", Some("20"))
+            .unwrap();
+        runtime
+            .set_override(ConfigScope::Tab(1), "colors.theme", Some("nord"))
+            .unwrap();
+        runtime
+            .set_override(ConfigScope::Pane(7), "colors.theme", Some("dracula"))
+            .unwrap();
+        assert!(runtime
+            .set_override(ConfigScope::Pane(7), "font.size", Some("500"))
+            .is_err());
+
+        let entries: Vec<(String, String)> = runtime
+            .overrides_for(1, 7)
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("font.size".to_string(), "20.0".to_string()),
+                ("colors.theme".to_string(), "\"dracula\"".to_string()),
+            ]
+        );
+        // The base config is untouched
+        assert_eq!(
+            runtime.snapshot().font.size,
+            ScarabConfig::default().font.size
+        );
+
+        runtime
+            .set_override(ConfigScope::Pane(7), "colors.theme", None)
+            .unwrap();
+        runtime.retain_overrides(|scope| scope != ConfigScope::Tab(1));
+        assert!(runtime.overrides_for(1, 7).is_empty());
+    }
+}
@@ -539,6 +539,26 @@ mod tests {
         assert_eq!(state.cursor_y, 0);
     }
 
+    #[test]
+    fn test_blit_records_row_damage() {
+        let (mut state, mut terminal, seq) = create_test_terminal();
+
+        // First blit over zeroed memory touches every row
+        terminal.process_output(b"Test");
+        unsafe { terminal.blit_to_shm(&mut *state as *mut SharedState, &seq) };
+        assert_eq!(state.damage_sequence, state.sequence_number);
+        assert!(state.is_row_damaged(0));
+        assert!(state.is_row_damaged(GRID_HEIGHT - 1));
+
+        // Typing on the next line only damages that line
+        terminal.process_output(b"\r\nls");
+        unsafe { terminal.blit_to_shm(&mut *state as *mut SharedState, &seq) };
+        assert_eq!(state.damage_sequence, state.sequence_number);
+        assert!(!state.is_row_damaged(0));
+        assert!(state.is_row_damaged(1));
+        assert!(!state.is_row_damaged(2));
+    }
+
     #[test]
     fn test_cursor_bounds_checking() {
         let (_state, mut terminal, _seq) = create_test_terminal();
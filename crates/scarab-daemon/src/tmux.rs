@@ -0,0 +1,1059 @@
+//! tmux control mode
+//!
+//! Running `tmux -CC` in a pane, locally or over ssh, puts tmux in control
+//! mode: instead of drawing its own screen it speaks a line protocol on the
+//! pane's output. That pane becomes the gateway, and every tmux window
+//! opens as a Scarab tab with a native pane for each tmux pane, the way
+//! iTerm2 does it. Output of tmux panes is fed to their Scarab panes, keys
+//! typed in them go back with `send-keys`, and windows and panes opened,
+//! closed, renamed or resized in tmux turn into tab and pane updates for
+//! clients. When tmux exits or detaches, its tabs close and the gateway is
+//! a normal terminal again.
+//!
+//! Output that only looks like tmux, such as a file with the DCS in it
+//! being printed, ends control mode at its first line that isn't part of
+//! the protocol, and so does a tmux that died without saying goodbye once
+//! the shell behind it prints a prompt.
+
+use crate::session::{Pane, PaneId, Rect, Session, SessionManager, TabId};
+use parking_lot::Mutex;
+use scarab_protocol::{DaemonMessage, PaneInfo, TabInfo};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::sync::Arc;
+
+/// DCS tmux writes when it enters control mode
+const START: &[u8] = b"\x1bP1000p";
+/// String terminator tmux writes when it leaves control mode
+const END: &[u8] = b"\x1b\\";
+
+/// Most bytes typed with a single `send-keys`
+const SEND_KEYS_CHUNK: usize = 256;
+
+/// Longest control mode line waited for; output without a newline for
+/// longer than that isn't tmux
+const MAX_LINE: usize = 1 << 20;
+
+/// Windows with the layout and name of each, one per line
+const LIST_WINDOWS: &str = "list-windows -F '#{window_id} #{window_layout} #{window_name}'";
+
+/// A piece of a gateway pane's output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParserEvent {
+    /// Output for the pane's own terminal
+    Shown(Vec<u8>),
+    Started,
+    /// A line of the control protocol, without its line ending
+    Line(Vec<u8>),
+    Ended,
+}
+
+/// Splits a pane's output into terminal output and control mode lines
+#[derive(Debug, Default)]
+pub struct ControlModeParser {
+    buffer: Vec<u8>,
+    active: bool,
+    /// `%exit` was seen and the terminator comes next
+    exiting: bool,
+    /// ID of the `%begin` block whose lines are being read
+    block: Option<Vec<u8>>,
+}
+
+impl ControlModeParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether tmux is in control mode
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn feed(&mut self, data: &[u8]) -> Vec<ParserEvent> {
+        let mut events = Vec::new();
+        if !self.active && self.buffer.is_empty() && !data.contains(&0x1b) {
+            events.push(ParserEvent::Shown(data.to_vec()));
+            return events;
+        }
+
+        self.buffer.extend_from_slice(data);
+        loop {
+            if !self.active {
+                if let Some(pos) = find(&self.buffer, START) {
+                    let shown: Vec<u8> = self.buffer.drain(..pos + START.len()).take(pos).collect();
+                    if !shown.is_empty() {
+                        events.push(ParserEvent::Shown(shown));
+                    }
+                    self.active = true;
+                    events.push(ParserEvent::Started);
+                    continue;
+                }
+                // Hold back what may be the start of the DCS cut off by the read
+                let keep = partial_suffix(&self.buffer, START);
+                let shown: Vec<u8> = self.buffer.drain(..self.buffer.len() - keep).collect();
+                if !shown.is_empty() {
+                    events.push(ParserEvent::Shown(shown));
+                }
+                break;
+            }
+
+            let end = if self.exiting {
+                find(&self.buffer, END)
+            } else {
+                self.buffer.starts_with(END).then_some(0)
+            };
+            if let Some(pos) = end {
+                self.buffer.drain(..pos + END.len());
+                self.leave(&mut events);
+                continue;
+            }
+            // Outside of a command's output every line is a notification;
+            // anything else is shown as it is, out of control mode
+            if self.block.is_none() && !self.exiting {
+                match self.buffer.first() {
+                    None => break,
+                    Some(b'%') => {}
+                    // What may be the terminator cut off by the read
+                    Some(_) if END.starts_with(&self.buffer) => break,
+                    Some(_) => {
+                        self.leave(&mut events);
+                        continue;
+                    }
+                }
+            }
+
+            let newline = self.buffer.iter().position(|&b| b == b'\n');
+            let Some(newline) = newline.filter(|_| !self.exiting) else {
+                if self.buffer.len() > MAX_LINE {
+                    self.leave(&mut events);
+                    continue;
+                }
+                break;
+            };
+
+            let mut line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            match (self.block.take(), block_marker(&line)) {
+                (None, Some((b"%begin", id))) => self.block = Some(id),
+                (Some(open), Some((keyword, id))) if keyword != b"%begin" && id == open => {}
+                (open, _) => self.block = open,
+            }
+            if self.block.is_none() && (line == b"%exit" || line.starts_with(b"%exit ")) {
+                self.exiting = true;
+            }
+            events.push(ParserEvent::Line(line));
+        }
+        events
+    }
+
+    /// Stop reading control mode lines, showing what is left as output
+    fn leave(&mut self, events: &mut Vec<ParserEvent>) {
+        self.active = false;
+        self.exiting = false;
+        self.block = None;
+        events.push(ParserEvent::Ended);
+    }
+}
+
+/// Keyword and `time number` ID of a `%begin`, `%end` or `%error` line
+fn block_marker(line: &[u8]) -> Option<(&[u8], Vec<u8>)> {
+    let mut words = line.split(|&b| b == b' ');
+    let keyword = words.next()?;
+    if !matches!(keyword, b"%begin" | b"%end" | b"%error") {
+        return None;
+    }
+    let (time, number) = (words.next()?, words.next()?);
+    Some((keyword, [time, b" ", number].concat()))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Length of the longest end of `data` that `pattern` starts with
+fn partial_suffix(data: &[u8], pattern: &[u8]) -> usize {
+    (1..pattern.len())
+        .rev()
+        .find(|&len| data.ends_with(&pattern[..len]))
+        .unwrap_or(0)
+}
+
+/// A control mode line other than a command's output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notification {
+    /// Output of a command starts; `ours` when the gateway sent it
+    Begin {
+        id: String,
+        ours: bool,
+    },
+    /// Output of a command ends, with `%end` or `%error`
+    End {
+        id: String,
+    },
+    Output {
+        pane: u32,
+        data: Vec<u8>,
+    },
+    WindowAdd {
+        window: u32,
+    },
+    WindowClose {
+        window: u32,
+    },
+    WindowRenamed {
+        window: u32,
+        name: String,
+    },
+    LayoutChange {
+        window: u32,
+        layout: String,
+    },
+    WindowPaneChanged {
+        window: u32,
+        pane: u32,
+    },
+    SessionWindowChanged {
+        window: u32,
+    },
+    Exit,
+}
+
+impl Notification {
+    /// The notification on `line`, if it is one this adapter follows
+    pub fn parse(line: &[u8]) -> Option<Self> {
+        let (keyword, rest) = match line.iter().position(|&b| b == b' ') {
+            Some(space) => (&line[..space], &line[space + 1..]),
+            None => (line, &[][..]),
+        };
+        if keyword == b"%output" {
+            let (pane, data) = match rest.iter().position(|&b| b == b' ') {
+                Some(space) => (&rest[..space], &rest[space + 1..]),
+                None => (rest, &[][..]),
+            };
+            return Some(Self::Output {
+                pane: tmux_id(&String::from_utf8_lossy(pane), '%')?,
+                data: unescape_output(data),
+            });
+        }
+
+        let rest = String::from_utf8_lossy(rest);
+        let mut args = rest.splitn(3, ' ');
+        let mut arg = || args.next().unwrap_or_default();
+        Some(match keyword {
+            b"%begin" => {
+                let (time, number) = (arg(), arg());
+                Self::Begin {
+                    id: format!("{} {}", time, number),
+                    ours: arg() == "1",
+                }
+            }
+            b"%end" | b"%error" => {
+                let (time, number) = (arg(), arg());
+                Self::End {
+                    id: format!("{} {}", time, number),
+                }
+            }
+            b"%window-add" => Self::WindowAdd {
+                window: tmux_id(arg(), '@')?,
+            },
+            b"%window-close" | b"%unlinked-window-close" => Self::WindowClose {
+                window: tmux_id(arg(), '@')?,
+            },
+            b"%window-renamed" => {
+                let (window, name) = rest.split_once(' ')?;
+                Self::WindowRenamed {
+                    window: tmux_id(window, '@')?,
+                    name: name.to_string(),
+                }
+            }
+            b"%layout-change" => Self::LayoutChange {
+                window: tmux_id(arg(), '@')?,
+                layout: arg().to_string(),
+            },
+            b"%window-pane-changed" => Self::WindowPaneChanged {
+                window: tmux_id(arg(), '@')?,
+                pane: tmux_id(arg(), '%')?,
+            },
+            b"%session-window-changed" => {
+                arg();
+                Self::SessionWindowChanged {
+                    window: tmux_id(arg(), '@')?,
+                }
+            }
+            b"%exit" => Self::Exit,
+            _ => return None,
+        })
+    }
+}
+
+/// Number of a tmux ID such as `@3` (window) or `%7` (pane)
+fn tmux_id(token: &str, sigil: char) -> Option<u32> {
+    token.strip_prefix(sigil)?.parse().ok()
+}
+
+/// Bytes of `%output`, where tmux writes control characters and
+/// backslashes as three octal digits
+pub fn unescape_output(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let octal = data
+            .get(i + 1..i + 4)
+            .filter(|digits| digits.iter().all(|d| (b'0'..=b'7').contains(d)));
+        match octal {
+            Some(digits) if data[i] == b'\\' => {
+                out.push(digits.iter().fold(0u8, |n, d| (n << 3) | (d - b'0')));
+                i += 4;
+            }
+            _ => {
+                out.push(data[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// A pane's place in a tmux window layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutPane {
+    pub pane: u32,
+    pub x: u16,
+    pub y: u16,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// Panes of a tmux layout such as `b25d,80x24,0,0{40x24,0,0,1,39x24,41,0,2}`
+pub fn parse_layout(layout: &str) -> Option<Vec<LayoutPane>> {
+    let (_checksum, cells) = layout.split_once(',')?;
+    let mut panes = Vec::new();
+    let mut pos = 0;
+    parse_cell(cells.as_bytes(), &mut pos, &mut panes)?;
+    Some(panes).filter(|_| pos == cells.len())
+}
+
+/// A cell `WxH,X,Y` followed by `,ID` for a pane, or by `{...}` or `[...]`
+/// for cells side by side or stacked
+fn parse_cell(s: &[u8], pos: &mut usize, panes: &mut Vec<LayoutPane>) -> Option<()> {
+    let cols = layout_number(s, pos)?;
+    expect(s, pos, b'x')?;
+    let rows = layout_number(s, pos)?;
+    expect(s, pos, b',')?;
+    let x = layout_number(s, pos)?;
+    expect(s, pos, b',')?;
+    let y = layout_number(s, pos)?;
+
+    let close = match *s.get(*pos)? {
+        b',' => {
+            *pos += 1;
+            panes.push(LayoutPane {
+                pane: layout_number(s, pos)?,
+                x: x as u16,
+                y: y as u16,
+                cols: cols as u16,
+                rows: rows as u16,
+            });
+            return Some(());
+        }
+        b'{' => b'}',
+        b'[' => b']',
+        _ => return None,
+    };
+    *pos += 1;
+    loop {
+        parse_cell(s, pos, panes)?;
+        match *s.get(*pos)? {
+            b',' => *pos += 1,
+            c if c == close => {
+                *pos += 1;
+                return Some(());
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn layout_number(s: &[u8], pos: &mut usize) -> Option<u32> {
+    let start = *pos;
+    while s.get(*pos).is_some_and(u8::is_ascii_digit) {
+        *pos += 1;
+    }
+    std::str::from_utf8(&s[start..*pos]).ok()?.parse().ok()
+}
+
+fn expect(s: &[u8], pos: &mut usize, c: u8) -> Option<()> {
+    (s.get(*pos) == Some(&c)).then(|| *pos += 1)
+}
+
+/// What the output of a command sent to tmux is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reply {
+    Ignore,
+    Windows,
+    Screen(u32),
+    Cursor(u32),
+}
+
+/// Sends commands through the gateway, remembering what each one's output
+/// is for; tmux answers them in order
+#[derive(Clone)]
+struct Commands {
+    writer: Arc<std::sync::Mutex<Option<Box<dyn Write + Send>>>>,
+    replies: Arc<Mutex<VecDeque<Reply>>>,
+}
+
+impl Commands {
+    fn send(&self, command: &str, reply: Reply) -> std::io::Result<()> {
+        let mut replies = self.replies.lock();
+        let mut writer = match self.writer.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let Some(writer) = writer.as_mut() else {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        };
+        writer.write_all(command.as_bytes())?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        replies.push_back(reply);
+        Ok(())
+    }
+}
+
+/// Input of a tmux pane, typed into tmux with `send-keys`
+struct PaneInput {
+    pane: u32,
+    commands: Commands,
+}
+
+impl Write for PaneInput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let chunk = &buf[..buf.len().min(SEND_KEYS_CHUNK)];
+        let keys: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let command = format!("send-keys -t %{} -H {}", self.pane, keys.join(" "));
+        self.commands.send(&command, Reply::Ignore)?;
+        Ok(chunk.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Output of a command, collected until its `%end`
+struct Block {
+    id: String,
+    ours: bool,
+    lines: Vec<Vec<u8>>,
+}
+
+/// Mirrors the windows and panes of a tmux in control mode into a session
+pub struct TmuxClient {
+    session: Arc<Session>,
+    commands: Commands,
+    /// Tab of each tmux window
+    windows: HashMap<u32, TabId>,
+    /// Window and Scarab pane of each tmux pane
+    panes: HashMap<u32, (u32, Arc<Pane>)>,
+    block: Option<Block>,
+}
+
+impl TmuxClient {
+    /// Start mirroring the tmux talking through `gateway`, a pane of `session`
+    pub fn new(session: Arc<Session>, gateway: &Pane) -> Self {
+        let (cols, rows) = gateway.dimensions();
+        let client = Self {
+            session,
+            commands: Commands {
+                writer: gateway.pty_writer(),
+                replies: Arc::new(Mutex::new(VecDeque::new())),
+            },
+            windows: HashMap::new(),
+            panes: HashMap::new(),
+            block: None,
+        };
+        client.send(
+            &format!("refresh-client -C {}x{}", cols, rows),
+            Reply::Ignore,
+        );
+        client.send(LIST_WINDOWS, Reply::Windows);
+        client
+    }
+
+    fn send(&self, command: &str, reply: Reply) {
+        if let Err(e) = self.commands.send(command, reply) {
+            log::warn!("tmux: failed to send '{}': {}", command, e);
+        }
+    }
+
+    /// Handle a line from tmux, returning updates for clients
+    pub fn handle_line(&mut self, line: &[u8]) -> Vec<DaemonMessage> {
+        let notification = Notification::parse(line);
+        if let Some(block) = &mut self.block {
+            match notification {
+                Some(Notification::End { id }) if id == block.id => {
+                    let block = self.block.take().expect("block is open");
+                    return self.finish_block(block);
+                }
+                _ => {
+                    block.lines.push(line.to_vec());
+                    return Vec::new();
+                }
+            }
+        }
+
+        match notification {
+            Some(Notification::Begin { id, ours }) => {
+                self.block = Some(Block {
+                    id,
+                    ours,
+                    lines: Vec::new(),
+                });
+                Vec::new()
+            }
+            Some(Notification::Output { pane, data }) => {
+                if let Some((_, pane)) = self.panes.get(&pane) {
+                    pane.feed_output(&data);
+                }
+                Vec::new()
+            }
+            Some(Notification::WindowAdd { .. }) => {
+                self.send(LIST_WINDOWS, Reply::Windows);
+                Vec::new()
+            }
+            Some(Notification::WindowClose { window }) => self.close_window(window),
+            Some(Notification::WindowRenamed { window, name }) => {
+                let Some(&tab_id) = self.windows.get(&window) else {
+                    return Vec::new();
+                };
+                match self.session.rename_tab(tab_id, name) {
+                    Ok(()) => vec![tab_list(&self.session)],
+                    Err(_) => Vec::new(),
+                }
+            }
+            Some(Notification::LayoutChange { window, layout }) => match parse_layout(&layout) {
+                Some(panes) => self.sync_window(window, None, &panes),
+                None => {
+                    log::warn!("tmux: can't read layout '{}'", layout);
+                    Vec::new()
+                }
+            },
+            Some(Notification::WindowPaneChanged { window, pane }) => {
+                let (Some(&tab_id), Some((_, pane))) =
+                    (self.windows.get(&window), self.panes.get(&pane))
+                else {
+                    return Vec::new();
+                };
+                let focused = self.session.focus_tab_pane(tab_id, pane.id).is_ok();
+                if focused && self.session.active_tab_id() == tab_id {
+                    vec![DaemonMessage::PaneFocused { pane_id: pane.id }]
+                } else {
+                    Vec::new()
+                }
+            }
+            Some(Notification::SessionWindowChanged { window }) => {
+                let Some(&tab_id) = self.windows.get(&window) else {
+                    return Vec::new();
+                };
+                match self.session.switch_tab(tab_id) {
+                    Ok(()) => vec![DaemonMessage::TabSwitched { tab_id }],
+                    Err(_) => Vec::new(),
+                }
+            }
+            Some(Notification::End { .. }) | Some(Notification::Exit) | None => Vec::new(),
+        }
+    }
+
+    fn finish_block(&mut self, block: Block) -> Vec<DaemonMessage> {
+        // The command tmux was started with has output too
+        if !block.ours {
+            return Vec::new();
+        }
+        let Some(reply) = self.commands.replies.lock().pop_front() else {
+            return Vec::new();
+        };
+
+        match reply {
+            Reply::Ignore => Vec::new(),
+            Reply::Windows => {
+                let mut updates = Vec::new();
+                for line in &block.lines {
+                    let line = String::from_utf8_lossy(line);
+                    let mut fields = line.splitn(3, ' ');
+                    let window = fields.next().and_then(|id| tmux_id(id, '@'));
+                    let layout = fields.next().and_then(parse_layout);
+                    let name = fields.next().unwrap_or_default().to_string();
+                    if let (Some(window), Some(layout)) = (window, layout) {
+                        updates.extend(self.sync_window(window, Some(name), &layout));
+                    }
+                }
+                updates
+            }
+            Reply::Screen(pane) => {
+                if let Some((_, pane)) = self.panes.get(&pane) {
+                    let mut screen = b"\x1b[H\x1b[2J".to_vec();
+                    screen.extend(block.lines.join(&b"\r\n"[..]));
+                    pane.process_output(&screen);
+                }
+                Vec::new()
+            }
+            Reply::Cursor(pane) => {
+                let position = block.lines.first().and_then(|line| {
+                    let line = String::from_utf8_lossy(line);
+                    let (x, y) = line.trim().split_once(' ')?;
+                    Some((x.parse::<u16>().ok()?, y.parse::<u16>().ok()?))
+                });
+                if let (Some((_, pane)), Some((x, y))) = (self.panes.get(&pane), position) {
+                    pane.process_output(format!("\x1b[{};{}H", y + 1, x + 1).as_bytes());
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// Bring a window's tab in line with its layout, opening the tab first
+    /// if the window is new
+    fn sync_window(
+        &mut self,
+        window: u32,
+        name: Option<String>,
+        layout: &[LayoutPane],
+    ) -> Vec<DaemonMessage> {
+        let mut updates = Vec::new();
+        let tab_id = match self.windows.get(&window) {
+            Some(&tab_id) => tab_id,
+            None => {
+                let title = name.unwrap_or_else(|| format!("tmux @{}", window));
+                let tab_id = self.session.add_tab(title, Vec::new());
+                self.windows.insert(window, tab_id);
+                if let Some(tab) = tab_info(&self.session, tab_id) {
+                    updates.push(DaemonMessage::TabCreated { tab });
+                }
+                tab_id
+            }
+        };
+
+        for cell in layout {
+            if let Some((_, pane)) = self.panes.get(&cell.pane) {
+                if pane.dimensions() != (cell.cols, cell.rows) {
+                    let _ = pane.resize(cell.cols, cell.rows);
+                }
+                continue;
+            }
+
+            let input = PaneInput {
+                pane: cell.pane,
+                commands: self.commands.clone(),
+            };
+            let pane = Pane::remote(
+                cell.pane as PaneId + 1,
+                format!("tmux %{}", cell.pane),
+                Rect::new(cell.x, cell.y, cell.cols, cell.rows),
+                Box::new(input),
+            );
+            let Some(pane) = self
+                .session
+                .add_pane(tab_id, pane)
+                .ok()
+                .and_then(|pane_id| self.session.get_pane(tab_id, pane_id))
+            else {
+                continue;
+            };
+            updates.push(DaemonMessage::PaneCreated {
+                pane: pane_info(cell, &pane, false),
+            });
+            self.panes.insert(cell.pane, (window, pane));
+            self.send(
+                &format!("capture-pane -p -e -t %{}", cell.pane),
+                Reply::Screen(cell.pane),
+            );
+            self.send(
+                &format!(
+                    "display-message -p -t %{} '#{{cursor_x}} #{{cursor_y}}'",
+                    cell.pane
+                ),
+                Reply::Cursor(cell.pane),
+            );
+        }
+
+        // Panes tmux closed
+        let closed: Vec<u32> = self
+            .panes
+            .iter()
+            .filter(|(id, (in_window, _))| {
+                *in_window == window && !layout.iter().any(|cell| cell.pane == **id)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in closed {
+            if let Some((_, pane)) = self.panes.remove(&id) {
+                if self.session.remove_pane(tab_id, pane.id).is_ok() {
+                    updates.push(DaemonMessage::PaneClosed { pane_id: pane.id });
+                }
+            }
+        }
+
+        if self.session.active_tab_id() == tab_id {
+            let focused = self
+                .session
+                .pane_infos(tab_id)
+                .into_iter()
+                .find(|info| info.is_focused)
+                .map(|info| info.id);
+            let panes = layout
+                .iter()
+                .filter_map(|cell| {
+                    let (_, pane) = self.panes.get(&cell.pane)?;
+                    Some(pane_info(cell, pane, Some(pane.id) == focused))
+                })
+                .collect();
+            updates.push(DaemonMessage::PaneLayoutUpdate { panes });
+        }
+        updates
+    }
+
+    fn close_window(&mut self, window: u32) -> Vec<DaemonMessage> {
+        let Some(tab_id) = self.windows.remove(&window) else {
+            return Vec::new();
+        };
+        self.panes.retain(|_, (in_window, _)| *in_window != window);
+        match self.session.close_tab(tab_id) {
+            Ok(_) => vec![DaemonMessage::TabClosed { tab_id }],
+            Err(e) => {
+                log::warn!("tmux: failed to close tab {}: {}", tab_id, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Close every tab opened for tmux, once it has left control mode
+    pub fn detach(mut self) -> Vec<DaemonMessage> {
+        let windows: Vec<u32> = self.windows.keys().copied().collect();
+        windows
+            .into_iter()
+            .flat_map(|window| self.close_window(window))
+            .collect()
+    }
+}
+
+fn pane_info(cell: &LayoutPane, pane: &Pane, is_focused: bool) -> PaneInfo {
+    PaneInfo {
+        id: pane.id,
+        x: cell.x,
+        y: cell.y,
+        width: cell.cols,
+        height: cell.rows,
+        is_focused,
+    }
+}
+
+fn tab_infos(session: &Session) -> Vec<TabInfo> {
+    session
+        .list_tabs()
+        .into_iter()
+        .map(|(id, title, is_active, pane_count)| TabInfo {
+            id,
+            title,
+            session_id: Some(session.id.clone()),
+            is_active,
+            pane_count: pane_count as u32,
+            has_activity: session.tab_has_activity(id),
+        })
+        .collect()
+}
+
+fn tab_info(session: &Session, tab_id: TabId) -> Option<TabInfo> {
+    tab_infos(session).into_iter().find(|tab| tab.id == tab_id)
+}
+
+fn tab_list(session: &Session) -> DaemonMessage {
+    DaemonMessage::TabListResponse {
+        tabs: tab_infos(session),
+    }
+}
+
+/// Watches a pane's output for tmux switching to control mode
+///
+/// Commands are only sent to tmux once it has sent a line of the protocol,
+/// so nothing is typed into a shell whose output merely contained the DCS.
+#[derive(Default)]
+pub struct ControlModeGateway {
+    parser: ControlModeParser,
+    client: Option<TmuxClient>,
+}
+
+impl ControlModeGateway {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take output read from `pane`, returning what the pane itself shows
+    /// and updates for clients
+    pub fn feed(
+        &mut self,
+        pane: &Arc<Pane>,
+        session_manager: &SessionManager,
+        data: &[u8],
+    ) -> (Vec<u8>, Vec<DaemonMessage>) {
+        let mut shown = Vec::new();
+        let mut updates = Vec::new();
+        for event in self.parser.feed(data) {
+            match event {
+                ParserEvent::Shown(bytes) => shown.extend(bytes),
+                ParserEvent::Started => {
+                    log::debug!("tmux control mode may have started in pane {}", pane.id);
+                }
+                ParserEvent::Line(line) => {
+                    if self.client.is_none() {
+                        let Some(session) = session_manager.session_with_pane(pane) else {
+                            log::warn!("tmux: pane {} is in no session", pane.id);
+                            continue;
+                        };
+                        log::info!("tmux control mode started in pane {}", pane.id);
+                        shown.extend_from_slice(
+                            b"\r\n[tmux control mode: windows open as tabs]\r\n",
+                        );
+                        self.client = Some(TmuxClient::new(session, pane));
+                    }
+                    if let Some(client) = &mut self.client {
+                        updates.extend(client.handle_line(&line));
+                    }
+                }
+                ParserEvent::Ended => {
+                    if let Some(client) = self.client.take() {
+                        log::info!("tmux control mode ended in pane {}", pane.id);
+                        updates.extend(client.detach());
+                        shown.extend_from_slice(b"[tmux control mode ended]\r\n");
+                    }
+                }
+            }
+        }
+        (shown, updates)
+    }
+
+    /// The pane's program exited, and tmux with it: close its tabs
+    pub fn end(&mut self) -> Vec<DaemonMessage> {
+        self.parser = ControlModeParser::new();
+        self.client
+            .take()
+            .map(TmuxClient::detach)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer whose output the test can read
+    #[derive(Clone, Default)]
+    struct Sent(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Sent {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Sent {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8_lossy(&self.0.lock())
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_parser_splits_control_mode() {
+        let mut parser = ControlModeParser::new();
+        assert_eq!(
+            parser.feed(b"$ tmux -CC\r\n\x1bP10"),
+            vec![ParserEvent::Shown(b"$ tmux -CC\r\n".to_vec())]
+        );
+        assert_eq!(
+            parser.feed(b"00p%begin 1 1 0\n%end 1 1 0\n%out"),
+            vec![
+                ParserEvent::Started,
+                ParserEvent::Line(b"%begin 1 1 0".to_vec()),
+                ParserEvent::Line(b"%end 1 1 0".to_vec()),
+            ]
+        );
+        assert!(parser.is_active());
+        assert_eq!(
+            parser.feed(b"put %1 hi\r\n%exit\n\x1b\\$ "),
+            vec![
+                ParserEvent::Line(b"%output %1 hi".to_vec()),
+                ParserEvent::Line(b"%exit".to_vec()),
+                ParserEvent::Ended,
+                ParserEvent::Shown(b"$ ".to_vec()),
+            ]
+        );
+        assert!(!parser.is_active());
+    }
+
+    #[test]
+    fn test_parser_leaves_on_other_output() {
+        // A tmux that died without %exit, and the shell prompt after it;
+        // command output may be anything
+        let mut parser = ControlModeParser::new();
+        parser.feed(b"\x1bP1000p%begin 1 1 0\nfoo\n%end 1 1 0\n");
+        assert!(parser.is_active());
+        assert_eq!(
+            parser.feed(b"%output %1 x\nuser@host:~$ "),
+            vec![
+                ParserEvent::Line(b"%output %1 x".to_vec()),
+                ParserEvent::Ended,
+                ParserEvent::Shown(b"user@host:~$ ".to_vec()),
+            ]
+        );
+
+        // A file with the DCS in it, without a newline for too long
+        let mut parser = ControlModeParser::new();
+        parser.feed(b"\x1bP1000p");
+        let events = parser.feed(&vec![b'%'; MAX_LINE + 1]);
+        assert_eq!(events[0], ParserEvent::Ended);
+        assert!(!parser.is_active());
+    }
+
+    #[test]
+    fn test_notifications() {
+        assert_eq!(
+            Notification::parse(b"%output %3 ls\\015\\012a\\134b"),
+            Some(Notification::Output {
+                pane: 3,
+                data: b"ls\r\na\\b".to_vec()
+            })
+        );
+        assert_eq!(
+            Notification::parse(b"%window-renamed @2 my window"),
+            Some(Notification::WindowRenamed {
+                window: 2,
+                name: "my window".to_string()
+            })
+        );
+        assert_eq!(
+            Notification::parse(b"%begin 1700000000 15 1"),
+            Some(Notification::Begin {
+                id: "1700000000 15".to_string(),
+                ours: true
+            })
+        );
+        assert_eq!(
+            Notification::parse(b"%error 1700000000 15 1"),
+            Some(Notification::End {
+                id: "1700000000 15".to_string()
+            })
+        );
+        assert_eq!(Notification::parse(b"%sessions-changed"), None);
+        assert_eq!(Notification::parse(b"@1 b25d,80x24,0,0,1 zsh"), None);
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        assert_eq!(
+            parse_layout("b25d,80x24,0,0{40x24,0,0,1,39x24,41,0[39x12,41,0,2,39x11,41,13,3]}"),
+            Some(vec![
+                LayoutPane {
+                    pane: 1,
+                    x: 0,
+                    y: 0,
+                    cols: 40,
+                    rows: 24
+                },
+                LayoutPane {
+                    pane: 2,
+                    x: 41,
+                    y: 0,
+                    cols: 39,
+                    rows: 12
+                },
+                LayoutPane {
+                    pane: 3,
+                    x: 41,
+                    y: 13,
+                    cols: 39,
+                    rows: 11
+                },
+            ])
+        );
+        assert_eq!(parse_layout("c0de,80x24,0,0,7").unwrap()[0].pane, 7);
+        assert_eq!(parse_layout("c0de,80x24,0,0{40x24,0,0,1"), None);
+    }
+
+    #[test]
+    fn test_tmux_windows_become_tabs() {
+        let session = Arc::new(Session::new("tmux".to_string(), 80, 24).unwrap());
+        let sent = Sent::default();
+        let gateway = Pane::remote(
+            9,
+            "ssh".to_string(),
+            Rect::full(80, 24),
+            Box::new(sent.clone()),
+        );
+        let mut client = TmuxClient::new(session.clone(), &gateway);
+        assert_eq!(sent.lines()[0], "refresh-client -C 80x24");
+
+        let mut updates = Vec::new();
+        for line in [
+            "%begin 1 1 0",
+            "%end 1 1 0",
+            "%begin 1 2 1",
+            "%end 1 2 1",
+            "%begin 1 3 1",
+            "@4 b25d,80x24,0,0{40x24,0,0,1,39x24,41,0,2} editor",
+            "%end 1 3 1",
+        ] {
+            updates.extend(client.handle_line(line.as_bytes()));
+        }
+        assert!(matches!(
+            &updates[0],
+            DaemonMessage::TabCreated { tab } if tab.title == "editor" && tab.pane_count == 0
+        ));
+        assert_eq!(
+            updates
+                .iter()
+                .filter(|update| matches!(update, DaemonMessage::PaneCreated { .. }))
+                .count(),
+            2
+        );
+        assert_eq!(session.tab_count(), 2);
+
+        // Output goes to the pane and typing goes back to tmux
+        client.handle_line(b"%output %2 hello\\015\\012");
+        let tab_id = client.windows[&4];
+        let pane = session.get_pane(tab_id, 3).unwrap();
+        assert_eq!(pane.output_tail(5), vec!["hello".to_string()]);
+        pane.pty_writer()
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .write_all(b"ls\r")
+            .unwrap();
+        assert_eq!(sent.lines().last().unwrap(), "send-keys -t %2 -H 6c 73 0d");
+
+        // Panes closed in tmux close here, and so does the window
+        let updates = client.handle_line(b"%layout-change @4 c0de,80x24,0,0,1 c0de,80x24,0,0,1 *");
+        assert!(updates
+            .iter()
+            .any(|update| matches!(update, DaemonMessage::PaneClosed { pane_id: 3 })));
+        let updates = client.handle_line(b"%window-close @4");
+        assert!(matches!(
+            updates[..],
+            [DaemonMessage::TabClosed { tab_id: closed }] if closed == tab_id
+        ));
+        assert_eq!(session.tab_count(), 1);
+    }
+}
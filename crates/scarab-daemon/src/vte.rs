@@ -1,8 +1,11 @@
 use crate::images::{parse_iterm2_image, parse_sixel_dcs, ImagePlacementState, ImageSize};
-use scarab_protocol::{Cell, SharedState, ZoneTracker, GRID_HEIGHT, GRID_WIDTH};
-use std::collections::VecDeque;
+use scarab_protocol::{
+    Cell, HyperlinkInfo, ProgressState, PromptMarkerInfo, SharedState, ZoneTracker,
+    CURSOR_STYLE_DEFAULT, CURSOR_STYLE_STEADY_BAR, DAMAGE_WORDS, GRID_HEIGHT, GRID_WIDTH,
+};
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 /// VTE (Virtual Terminal Emulator) Parser Integration
@@ -19,6 +22,9 @@ use std::time::{Instant, SystemTime, UNIX_EPOCH};
 /// - Image protocol support (iTerm2)
 /// - Instance-based grid storage (for multiplexing)
 /// - OSC 133 shell integration markers
+/// - OSC 8 hyperlinks
+/// - OSC 4/10/11/12 color queries and changes, OSC 104/110/111/112 resets
+/// - Light/dark reports (`CSI ? 996 n`, mode 2031)
 use vte::{Parser, Perform};
 
 /// Maximum scrollback buffer size (10,000 lines)
@@ -27,17 +33,178 @@ const SCROLLBACK_SIZE: usize = 10_000;
 /// Maximum images per pane (matches SharedImageBuffer MAX_IMAGES)
 const MAX_IMAGES_PER_PANE: usize = 64;
 
+/// Maximum hyperlinked runs tracked on the visible screen
+const MAX_HYPERLINKS: usize = 512;
+
 /// Default colors - Slime theme
 /// These match the default slime theme: foreground #a8df5a, background #0d1208
 const DEFAULT_FG: u32 = 0xFFA8DF5A; // Slime green (#a8df5a)
 const DEFAULT_BG: u32 = 0xFF0D1208; // Slime dark (#0d1208)
 
+/// The theme's colors, reported to applications that ask
+///
+/// Answers OSC 4, 10, 11 and 12 queries for colors no program has changed,
+/// and tells `CSI ? 996 n` whether the terminal is dark, going by the
+/// background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportedColors {
+    pub foreground: [u8; 3],
+    pub background: [u8; 3],
+    pub cursor: [u8; 3],
+    /// ANSI colors 0-15
+    pub palette: [[u8; 3]; 16],
+}
+
+impl ReportedColors {
+    /// Slime, matching `DEFAULT_FG`, `DEFAULT_BG` and the SGR colors
+    pub const DEFAULT: Self = Self {
+        foreground: [0xa8, 0xdf, 0x5a],
+        background: [0x0d, 0x12, 0x08],
+        cursor: [0xa8, 0xdf, 0x5a],
+        palette: [
+            [0x0d, 0x12, 0x08],
+            [0xff, 0x55, 0x55],
+            [0xa8, 0xdf, 0x5a],
+            [0xf1, 0xfa, 0x8c],
+            [0x62, 0x72, 0xa4],
+            [0xff, 0x79, 0xc6],
+            [0x8b, 0xe9, 0xfd],
+            [0xf8, 0xf8, 0xf2],
+            [0x44, 0x47, 0x5a],
+            [0xff, 0x6e, 0x6e],
+            [0xc4, 0xf0, 0x7a],
+            [0xff, 0xff, 0xa5],
+            [0x7c, 0x8d, 0xbd],
+            [0xff, 0x92, 0xdf],
+            [0xa4, 0xff, 0xff],
+            [0xff, 0xff, 0xff],
+        ],
+    };
+
+    /// From the configured colors; any that aren't `#rrggbb` stay Slime's
+    pub fn from_config(colors: &scarab_config::ColorConfig) -> Self {
+        let parse = |color: Option<&String>| {
+            let hex = color?.strip_prefix('#')?;
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            Some([channel(0)?, channel(2)?, channel(4)?])
+        };
+        let p = &colors.palette;
+        let configured = [
+            &p.black,
+            &p.red,
+            &p.green,
+            &p.yellow,
+            &p.blue,
+            &p.magenta,
+            &p.cyan,
+            &p.white,
+            &p.bright_black,
+            &p.bright_red,
+            &p.bright_green,
+            &p.bright_yellow,
+            &p.bright_blue,
+            &p.bright_magenta,
+            &p.bright_cyan,
+            &p.bright_white,
+        ];
+        let mut palette = Self::DEFAULT.palette;
+        for (slot, color) in palette.iter_mut().zip(configured) {
+            if let Some(rgb) = parse(Some(color)) {
+                *slot = rgb;
+            }
+        }
+
+        let foreground = parse(colors.foreground.as_ref()).unwrap_or(Self::DEFAULT.foreground);
+        Self {
+            foreground,
+            background: parse(colors.background.as_ref()).unwrap_or(Self::DEFAULT.background),
+            cursor: parse(colors.cursor.as_ref()).unwrap_or(foreground),
+            palette,
+        }
+    }
+
+    /// Whether the background is dark
+    pub fn is_dark(&self) -> bool {
+        is_dark(self.background)
+    }
+}
+
+/// A color programs can query and change: OSC 4 for the palette, and
+/// OSC 10, 11 and 12 for the rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum DynamicColor {
+    Palette(u8),
+    Foreground,
+    Background,
+    Cursor,
+}
+
+/// `[colors]` keys of the ANSI colors, by index
+const PALETTE_KEYS: [&str; 16] = [
+    "palette.black",
+    "palette.red",
+    "palette.green",
+    "palette.yellow",
+    "palette.blue",
+    "palette.magenta",
+    "palette.cyan",
+    "palette.white",
+    "palette.bright_black",
+    "palette.bright_red",
+    "palette.bright_green",
+    "palette.bright_yellow",
+    "palette.bright_blue",
+    "palette.bright_magenta",
+    "palette.bright_cyan",
+    "palette.bright_white",
+];
+
+impl DynamicColor {
+    /// OSC parameters naming the color in a reply
+    fn osc_prefix(self) -> String {
+        match self {
+            DynamicColor::Palette(index) => format!("4;{}", index),
+            DynamicColor::Foreground => "10".to_string(),
+            DynamicColor::Background => "11".to_string(),
+            DynamicColor::Cursor => "12".to_string(),
+        }
+    }
+
+    /// Key of the color in `[colors]`; palette colors past 15 have none
+    fn config_key(self) -> Option<&'static str> {
+        match self {
+            DynamicColor::Palette(index) => PALETTE_KEYS.get(index as usize).copied(),
+            DynamicColor::Foreground => Some("foreground"),
+            DynamicColor::Background => Some("background"),
+            DynamicColor::Cursor => Some("cursor"),
+        }
+    }
+}
+
+/// RGBA of an RGB color
+pub(crate) fn rgb_to_rgba([r, g, b]: [u8; 3]) -> u32 {
+    0xFF000000 | (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+/// Whether a background of this color makes the terminal dark
+fn is_dark(background: [u8; 3]) -> bool {
+    let [r, g, b] = background.map(|c| c as f32 / 255.0);
+    0.2126 * r + 0.7152 * g + 0.0722 * b < 0.5
+}
+
+/// Colors new panes start out reporting, following the config
+static DEFAULT_REPORTED_COLORS: RwLock<ReportedColors> = RwLock::new(ReportedColors::DEFAULT);
+
+/// Set the colors new panes report; existing panes are changed with
+/// [`TerminalState::set_reported_colors`]
+pub fn set_default_reported_colors(colors: ReportedColors) {
+    if let Ok(mut default) = DEFAULT_REPORTED_COLORS.write() {
+        *default = colors;
+    }
+}
+
 /// Text attribute flags
-pub const FLAG_BOLD: u8 = 1 << 0;
-pub const FLAG_ITALIC: u8 = 1 << 1;
-pub const FLAG_UNDERLINE: u8 = 1 << 2;
-pub const FLAG_INVERSE: u8 = 1 << 3;
-pub const FLAG_DIM: u8 = 1 << 4;
+pub use scarab_protocol::{FLAG_BOLD, FLAG_DIM, FLAG_INVERSE, FLAG_ITALIC, FLAG_UNDERLINE};
 
 /// Shell prompt marker types (OSC 133)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -60,6 +227,29 @@ pub struct PromptMarker {
     pub line: usize,
     /// Timestamp when marker was recorded
     pub timestamp: Instant,
+    /// Wall-clock time of the marker in microseconds since the UNIX epoch
+    pub timestamp_micros: u64,
+}
+
+impl PromptMarker {
+    /// This marker as sent to clients
+    pub fn to_info(&self) -> PromptMarkerInfo {
+        let line = self.line as u32;
+        match self.marker_type {
+            PromptMarkerType::PromptStart => {
+                PromptMarkerInfo::prompt_start(line, self.timestamp_micros)
+            }
+            PromptMarkerType::CommandStart => {
+                PromptMarkerInfo::command_start(line, self.timestamp_micros)
+            }
+            PromptMarkerType::CommandExecuted => {
+                PromptMarkerInfo::command_executed(line, self.timestamp_micros)
+            }
+            PromptMarkerType::CommandFinished { exit_code } => {
+                PromptMarkerInfo::command_finished(line, exit_code, self.timestamp_micros)
+            }
+        }
+    }
 }
 
 /// Current text attributes for rendering
@@ -201,6 +391,8 @@ pub struct TerminalState {
     attrs: TextAttributes,
     /// Scrollback buffer (stores lines that scrolled off the top)
     scrollback: VecDeque<Vec<Cell>>,
+    /// Cursor style requested via DECSCUSR (see `CURSOR_STYLE_*`)
+    pub cursor_style: u8,
     /// Saved cursor position (for DECSC/DECRC)
     saved_cursor: (u16, u16),
     saved_attrs: TextAttributes,
@@ -210,6 +402,8 @@ pub struct TerminalState {
     pub prompt_markers: Vec<PromptMarker>,
     /// Maximum markers to retain
     pub max_markers: usize,
+    /// Prompt markers changed since they were last taken
+    prompt_markers_changed: bool,
     /// Maximum images per pane (for eviction)
     pub max_images: usize,
     /// DCS sequence buffer for Sixel graphics
@@ -220,6 +414,27 @@ pub struct TerminalState {
     pub pending_responses: Vec<Vec<u8>>,
     /// Semantic zone tracker for deep shell integration
     pub zone_tracker: ZoneTracker,
+    /// Absolute line and column where the command line began (OSC 133;B)
+    command_input: Option<(usize, u16)>,
+    /// Progress reported via OSC 9;4
+    pub progress: ProgressState,
+    /// Progress changed since it was last taken
+    progress_changed: bool,
+    /// The theme's colors, reported for OSC 4/10/11/12 and `CSI ? 996 n`
+    reported_colors: ReportedColors,
+    /// Colors of a theme given to this pane alone, used in place of
+    /// `reported_colors` and for the ANSI colors
+    pane_theme: Option<ReportedColors>,
+    /// Colors changed by programs with OSC 4/10/11/12
+    color_overrides: HashMap<DynamicColor, [u8; 3]>,
+    /// Changed colors not yet sent to clients, by `[colors]` key
+    color_changes: Vec<(String, String)>,
+    /// Report light/dark changes without being asked (DEC mode 2031)
+    color_scheme_updates: bool,
+    /// Target of the OSC 8 hyperlink currently being printed
+    active_hyperlink: Option<String>,
+    /// Hyperlinked runs on the visible screen (OSC 8)
+    hyperlinks: Vec<HyperlinkInfo>,
     /// Content changed since last blit - enables reactive updates
     content_changed: bool,
 }
@@ -239,16 +454,31 @@ impl TerminalState {
             rows,
             attrs: TextAttributes::default(),
             scrollback: VecDeque::with_capacity(SCROLLBACK_SIZE),
+            cursor_style: CURSOR_STYLE_DEFAULT,
             saved_cursor: (0, 0),
             saved_attrs: TextAttributes::default(),
             image_state: ImagePlacementState::new(),
             prompt_markers: Vec::new(),
             max_markers: 1000, // Keep last 1000 markers
+            prompt_markers_changed: false,
             max_images: MAX_IMAGES_PER_PANE,
             dcs_buffer: Vec::new(),
             in_dcs: false,
             pending_responses: Vec::new(),
             zone_tracker: ZoneTracker::new(500), // Keep last 500 command blocks
+            command_input: None,
+            progress: ProgressState::Hidden,
+            progress_changed: false,
+            reported_colors: DEFAULT_REPORTED_COLORS
+                .read()
+                .map(|colors| *colors)
+                .unwrap_or(ReportedColors::DEFAULT),
+            pane_theme: None,
+            color_scheme_updates: false,
+            color_overrides: HashMap::new(),
+            color_changes: Vec::new(),
+            active_hyperlink: None,
+            hyperlinks: Vec::new(),
             content_changed: true, // Start dirty to ensure initial render
         }
     }
@@ -281,10 +511,230 @@ impl TerminalState {
         state
     }
 
+    /// Take the progress state if it changed since the last call
+    pub fn take_progress_change(&mut self) -> Option<ProgressState> {
+        std::mem::take(&mut self.progress_changed).then_some(self.progress)
+    }
+
+    fn set_progress(&mut self, progress: ProgressState) {
+        if self.progress != progress {
+            self.progress = progress;
+            self.progress_changed = true;
+        }
+    }
+
+    /// Change the colors reported to applications
+    ///
+    /// Applications that enabled mode 2031 are sent a report when this
+    /// turns the terminal from dark to light or back.
+    pub fn set_reported_colors(&mut self, colors: ReportedColors) {
+        let was_dark = self.is_dark();
+        self.reported_colors = colors;
+        self.scheme_may_have_changed(was_dark);
+    }
+
+    /// Give the pane a theme of its own, or go back to the window's with
+    /// `None`
+    ///
+    /// Text written from now on uses the theme's ANSI colors, and text in
+    /// the default color takes its foreground.
+    pub fn set_pane_theme(&mut self, colors: Option<ReportedColors>) {
+        if self.pane_theme == colors {
+            return;
+        }
+        let was_dark = self.is_dark();
+        self.pane_theme = colors;
+        self.content_changed = true;
+        self.scheme_may_have_changed(was_dark);
+    }
+
+    /// Colors programs changed since the last call, as `[colors]` keys
+    /// and `#rrggbb` values
+    pub fn take_color_changes(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.color_changes)
+    }
+
+    /// Whether the background, as programs last set it, is dark
+    fn is_dark(&self) -> bool {
+        is_dark(self.dynamic_color(DynamicColor::Background))
+    }
+
+    /// Tell programs that enabled mode 2031 if the terminal went from dark
+    /// to light or back
+    fn scheme_may_have_changed(&mut self, was_dark: bool) {
+        if was_dark != self.is_dark() && self.color_scheme_updates {
+            self.report_color_scheme();
+        }
+    }
+
+    /// Color scheme report: `CSI ? 997 ; 1 n` when dark, `; 2 n` when light
+    fn report_color_scheme(&mut self) {
+        let scheme = if self.is_dark() { 1 } else { 2 };
+        let response = format!("\x1b[?997;{}n", scheme);
+        self.pending_responses.push(response.into_bytes());
+    }
+
+    /// A color as a program last set it, or else the theme's
+    fn dynamic_color(&self, color: DynamicColor) -> [u8; 3] {
+        if let Some(rgb) = self.color_overrides.get(&color) {
+            return *rgb;
+        }
+        let theme = self.pane_theme.as_ref().unwrap_or(&self.reported_colors);
+        match color {
+            DynamicColor::Palette(index) => match theme.palette.get(index as usize) {
+                Some(rgb) => *rgb,
+                None => {
+                    let rgba = color_256_to_rgba(index);
+                    [(rgba >> 16) as u8, (rgba >> 8) as u8, rgba as u8]
+                }
+            },
+            DynamicColor::Foreground => theme.foreground,
+            DynamicColor::Background => theme.background,
+            DynamicColor::Cursor => theme.cursor,
+        }
+    }
+
+    /// Answer a `?` query for a color, or change it to the given spec
+    fn query_or_set_color(&mut self, color: DynamicColor, spec: &[u8], bell_terminated: bool) {
+        if spec == b"?" {
+            let [r, g, b] = self.dynamic_color(color);
+            // Answer with the terminator the query used
+            let terminator = if bell_terminated { "\x07" } else { "\x1b\\" };
+            let response = format!(
+                "\x1b]{};rgb:{:02x}{:02x}/{:02x}{:02x}/{:02x}{:02x}{}",
+                color.osc_prefix(),
+                r,
+                r,
+                g,
+                g,
+                b,
+                b,
+                terminator
+            );
+            self.pending_responses.push(response.into_bytes());
+        } else if let Some(rgb) = parse_color_spec(spec) {
+            self.set_dynamic_color(color, Some(rgb));
+        } else {
+            log::debug!("Unknown color spec: {:?}", String::from_utf8_lossy(spec));
+        }
+    }
+
+    /// Change a color, or with `None` go back to the theme's
+    ///
+    /// Clients are told about colors they have a setting for.
+    fn set_dynamic_color(&mut self, color: DynamicColor, rgb: Option<[u8; 3]>) {
+        let was_dark = self.is_dark();
+        let changed = match rgb {
+            Some(rgb) => self.color_overrides.insert(color, rgb) != Some(rgb),
+            None => self.color_overrides.remove(&color).is_some(),
+        };
+        if !changed {
+            return;
+        }
+
+        if let Some(key) = color.config_key() {
+            let [r, g, b] = self.dynamic_color(color);
+            let value = format!("#{:02x}{:02x}{:02x}", r, g, b);
+            self.color_changes.retain(|(changed, _)| changed != key);
+            self.color_changes.push((key.to_string(), value));
+        }
+        self.scheme_may_have_changed(was_dark);
+    }
+
+    /// RGBA of palette color `index`, as a program last set it
+    ///
+    /// Colors no program changed come from the pane's theme, if it has one,
+    /// or else keep the built-in palette.
+    fn indexed_color(&self, index: u8) -> u32 {
+        let pane_theme = self
+            .pane_theme
+            .as_ref()
+            .and_then(|theme| theme.palette.get(index as usize));
+        match self
+            .color_overrides
+            .get(&DynamicColor::Palette(index))
+            .or(pane_theme)
+        {
+            Some(&rgb) => rgb_to_rgba(rgb),
+            None => color_256_to_rgba(index),
+        }
+    }
+
+    /// Hyperlinked runs (OSC 8) on the visible screen
+    pub fn hyperlinks(&self) -> &[HyperlinkInfo] {
+        &self.hyperlinks
+    }
+
+    /// Record the hyperlink (or lack of one) for a cell that was just written
+    fn link_cell(&mut self, x: u16, y: u16) {
+        let Some(uri) = self.active_hyperlink.as_deref() else {
+            if !self.hyperlinks.is_empty() {
+                self.unlink_line(y, x, x);
+            }
+            return;
+        };
+
+        if let Some(last) = self.hyperlinks.last_mut() {
+            if last.row == y && last.uri == uri && (last.start_col..=last.end_col + 1).contains(&x)
+            {
+                last.end_col = last.end_col.max(x);
+                return;
+            }
+        }
+
+        self.unlink_line(y, x, x);
+        if self.hyperlinks.len() >= MAX_HYPERLINKS {
+            self.hyperlinks.remove(0);
+        }
+        self.hyperlinks.push(HyperlinkInfo {
+            row: y,
+            start_col: x,
+            end_col: x,
+            uri: self.active_hyperlink.clone().unwrap_or_default(),
+        });
+    }
+
+    /// Drop links from columns `from..=to` of row `y`, splitting runs that
+    /// extend past both ends
+    fn unlink_line(&mut self, y: u16, from: u16, to: u16) {
+        let mut split = Vec::new();
+        self.hyperlinks.retain_mut(|link| {
+            if link.row != y || link.end_col < from || link.start_col > to {
+                return true;
+            }
+            if link.start_col < from && link.end_col > to {
+                split.push(HyperlinkInfo {
+                    start_col: to + 1,
+                    ..link.clone()
+                });
+            }
+            if link.start_col < from {
+                link.end_col = from - 1;
+                true
+            } else if link.end_col > to {
+                link.start_col = to + 1;
+                true
+            } else {
+                false
+            }
+        });
+        self.hyperlinks.extend(split);
+    }
+
+    /// Drop links on the given rows
+    fn unlink_rows(&mut self, rows: std::ops::Range<u16>) {
+        self.hyperlinks.retain(|link| !rows.contains(&link.row));
+    }
+
     /// Update terminal dimensions
     pub fn resize(&mut self, cols: u16, rows: u16) {
         self.cols = cols.min(GRID_WIDTH as u16);
         self.rows = rows.min(GRID_HEIGHT as u16);
+        let (cols, rows) = (self.cols, self.rows);
+        self.hyperlinks.retain_mut(|link| {
+            link.end_col = link.end_col.min(cols.saturating_sub(1));
+            link.row < rows && link.start_col < cols
+        });
         self.cursor_x = self.cursor_x.min(self.cols.saturating_sub(1));
         self.cursor_y = self.cursor_y.min(self.rows.saturating_sub(1));
         self.grid.resize(self.cols, self.rows);
@@ -317,8 +767,8 @@ impl TerminalState {
         }
         let state = &mut *shm;
 
-        // Fill the ENTIRE shared memory grid with theme background color
-        // This ensures areas outside the active terminal area have uniform color
+        // Areas outside the active terminal area get theme background cells,
+        // so the whole shared grid has a uniform color
         let empty_cell = Cell {
             char_codepoint: b' ' as u32,
             fg: DEFAULT_FG,
@@ -327,31 +777,57 @@ impl TerminalState {
             _padding: [0; 3],
         };
 
-        // First, fill the entire buffer with empty cells (theme colors)
-        for cell in state.cells.iter_mut() {
-            *cell = empty_cell;
-        }
-
-        // Then copy cells from local grid to shared memory
-        // We need to map from local grid layout to SharedState's fixed GRID_WIDTH layout
-        for y in 0..self.rows.min(GRID_HEIGHT as u16) {
-            for x in 0..self.cols.min(GRID_WIDTH as u16) {
-                let local_idx = y as usize * self.cols as usize + x as usize;
-                let shm_idx = y as usize * GRID_WIDTH + x as usize;
+        // Invalidate the damage bitmask while cells are rewritten
+        state.damage_sequence = 0;
+        std::sync::atomic::fence(Ordering::Release);
+
+        // Text in the default color takes the foreground of the pane's theme
+        let theme_fg = self.pane_theme.map(|theme| rgb_to_rgba(theme.foreground));
+
+        // Copy cells from the local grid, mapping its layout onto SharedState's
+        // fixed GRID_WIDTH layout, and record which rows actually changed
+        let mut damage = [0u64; DAMAGE_WORDS];
+        for y in 0..GRID_HEIGHT {
+            let mut row_changed = false;
+            for x in 0..GRID_WIDTH {
+                let local_idx = y * self.cols as usize + x;
+                let mut cell = if y < self.rows as usize && x < self.cols as usize {
+                    self.grid
+                        .cells
+                        .get(local_idx)
+                        .copied()
+                        .unwrap_or(empty_cell)
+                } else {
+                    empty_cell
+                };
+                if let Some(fg) = theme_fg.filter(|_| cell.fg == DEFAULT_FG) {
+                    cell.fg = fg;
+                }
 
-                if local_idx < self.grid.cells.len() && shm_idx < state.cells.len() {
-                    state.cells[shm_idx] = self.grid.cells[local_idx];
+                let shm_cell = &mut state.cells[y * GRID_WIDTH + x];
+                if !same_cell(shm_cell, &cell) {
+                    *shm_cell = cell;
+                    row_changed = true;
                 }
             }
+            if row_changed {
+                damage[y / 64] |= 1 << (y % 64);
+            }
         }
 
         // Update cursor position
         state.cursor_x = self.cursor_x;
         state.cursor_y = self.cursor_y;
+        state.cursor_style = self.cursor_style;
 
-        // Mark dirty and increment sequence number (signals new data available)
+        // Mark dirty and increment sequence number (signals new data available).
+        // Damage is published before the sequence so a client that sees the
+        // new sequence also sees the rows it covers.
         state.dirty_flag = 1;
         let new_seq = sequence_counter.fetch_add(1, Ordering::SeqCst) + 1;
+        state.damage_rows = damage;
+        std::sync::atomic::fence(Ordering::Release);
+        state.damage_sequence = new_seq;
         state.sequence_number = new_seq;
 
         // Clear the changed flag now that we've blitted
@@ -386,8 +862,10 @@ impl TerminalState {
             marker_type,
             line: self.absolute_line(),
             timestamp: Instant::now(),
+            timestamp_micros: Self::current_timestamp_micros(),
         };
         self.prompt_markers.push(marker);
+        self.prompt_markers_changed = true;
 
         // Trim old markers if needed
         if self.prompt_markers.len() > self.max_markers {
@@ -423,6 +901,99 @@ impl TerminalState {
         &self.prompt_markers
     }
 
+    /// All prompt markers as sent to clients
+    pub fn prompt_marker_infos(&self) -> Vec<PromptMarkerInfo> {
+        self.prompt_markers
+            .iter()
+            .map(PromptMarker::to_info)
+            .collect()
+    }
+
+    /// Take the prompt markers if any were added since the last call
+    pub fn take_prompt_markers_change(&mut self) -> Option<Vec<PromptMarkerInfo>> {
+        std::mem::take(&mut self.prompt_markers_changed).then(|| self.prompt_marker_infos())
+    }
+
+    /// Number of lines currently held in scrollback
+    pub fn scrollback_len(&self) -> usize {
+        self.scrollback.len()
+    }
+
+    /// Text of an absolute line: scrollback first, then the visible grid
+    ///
+    /// Produces one char per cell (blank cells become spaces) so char
+    /// indices map directly to columns. Trailing whitespace is trimmed.
+    pub fn line_text(&self, line: usize) -> Option<String> {
+        let text: String = self.line_cells(line)?.iter().map(cell_char).collect();
+        Some(text.trim_end().to_string())
+    }
+
+    /// Text of an absolute line with its colors and attributes as SGR
+    /// escapes
+    ///
+    /// Colors are 24-bit; the default foreground and background are left
+    /// to whatever shows the text. Trailing blanks are trimmed as in
+    /// [`line_text`](Self::line_text), and the line ends with a reset if
+    /// anything was set.
+    pub fn line_ansi(&self, line: usize) -> Option<String> {
+        let cells = self.line_cells(line)?;
+        let end = cells
+            .iter()
+            .rposition(|cell| cell_char(cell) != ' ' || cell.bg != DEFAULT_BG)
+            .map_or(0, |last| last + 1);
+
+        let plain = TextAttributes::default();
+        let mut current = (plain.fg, plain.bg, plain.flags);
+        let mut text = String::new();
+        for cell in &cells[..end] {
+            let style = (cell.fg, cell.bg, cell.flags);
+            if style != current {
+                text.push_str(&sgr(style.0, style.1, style.2));
+                current = style;
+            }
+            text.push(cell_char(cell));
+        }
+        if current != (plain.fg, plain.bg, plain.flags) {
+            text.push_str("\x1b[0m");
+        }
+        Some(text)
+    }
+
+    /// Cells of an absolute line: scrollback first, then the visible grid
+    fn line_cells(&self, line: usize) -> Option<&[Cell]> {
+        match line.checked_sub(self.scrollback.len()) {
+            None => Some(&self.scrollback[line]),
+            Some(row) if row < self.grid.rows as usize => {
+                let cols = self.grid.cols as usize;
+                Some(&self.grid.cells[row * cols..(row + 1) * cols])
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// Text typed from absolute `line`, column `col` up to the cursor
+    ///
+    /// Rows the command filled to the edge are joined as one wrapped line,
+    /// and the first shorter row ends it. Later lines of a multi-line
+    /// command start with the shell's PS2 (`> `), which can't be told from
+    /// what was typed, so only the first line is kept.
+    fn command_text_from(&self, line: usize, col: u16) -> String {
+        let mut text = String::new();
+        for current in line..=self.absolute_line() {
+            let Some(row) = self.line_text(current) else {
+                break;
+            };
+            let skip = if current == line { col as usize } else { 0 };
+            let row: String = row.chars().skip(skip).collect();
+            let wrapped = skip + row.chars().count() >= self.cols as usize;
+            text.push_str(&row);
+            if !wrapped {
+                break;
+            }
+        }
+        text.trim().to_string()
+    }
+
     /// Add an image placement from iTerm2 parser
     ///
     /// Automatically evicts oldest image if at max_images limit.
@@ -503,6 +1074,7 @@ impl TerminalState {
                 _padding: [0; 3],
             };
         }
+        self.link_cell(self.cursor_x, self.cursor_y);
 
         self.cursor_x += 1;
     }
@@ -513,6 +1085,7 @@ impl TerminalState {
         let rows = self.rows as usize;
 
         // Save scrolled lines to scrollback buffer
+        let mut dropped = 0;
         for i in 0..lines {
             if i >= rows {
                 break;
@@ -529,6 +1102,7 @@ impl TerminalState {
             // Limit scrollback buffer size
             if self.scrollback.len() > SCROLLBACK_SIZE {
                 self.scrollback.pop_front();
+                dropped += 1;
             }
         }
 
@@ -569,14 +1143,26 @@ impl TerminalState {
         // Update image positions when scrolling
         self.image_state.scroll(lines as i32);
 
-        // Update zone line numbers when scrolling
-        // Lines move into scrollback, so absolute line numbers increase
-        self.zone_tracker.adjust_for_scroll(lines as i32);
+        // Links scrolled off the top are no longer on screen
+        self.hyperlinks.retain_mut(|link| {
+            if (link.row as usize) < lines {
+                return false;
+            }
+            link.row -= lines as u16;
+            true
+        });
+
+        // Zones are on absolute lines, which keep their numbers as they move
+        // into scrollback and only shift when the oldest lines are dropped
+        if dropped > 0 {
+            self.zone_tracker.adjust_for_scroll(-dropped);
+        }
     }
 
     /// Clear the screen
     fn clear_screen(&mut self) {
         self.grid.clear();
+        self.hyperlinks.clear();
         self.cursor_x = 0;
         self.cursor_y = 0;
 
@@ -586,6 +1172,7 @@ impl TerminalState {
 
     /// Clear from cursor to end of line
     fn clear_to_eol(&mut self) {
+        self.unlink_line(self.cursor_y, self.cursor_x, self.cols.saturating_sub(1));
         let cols = self.cols as usize;
         for x in self.cursor_x as usize..cols {
             let idx = self.cursor_y as usize * cols + x;
@@ -624,12 +1211,12 @@ impl TerminalState {
                 27 => self.attrs.flags &= !FLAG_INVERSE,
 
                 // Foreground colors (30-37, 90-97)
-                30..=37 => self.attrs.fg = ansi_color_to_rgba(params[i] as u8 - 30),
-                90..=97 => self.attrs.fg = ansi_bright_color_to_rgba(params[i] as u8 - 90),
+                30..=37 => self.attrs.fg = self.indexed_color(params[i] as u8 - 30),
+                90..=97 => self.attrs.fg = self.indexed_color(params[i] as u8 - 90 + 8),
 
                 // Background colors (40-47, 100-107)
-                40..=47 => self.attrs.bg = ansi_color_to_rgba(params[i] as u8 - 40),
-                100..=107 => self.attrs.bg = ansi_bright_color_to_rgba(params[i] as u8 - 100),
+                40..=47 => self.attrs.bg = self.indexed_color(params[i] as u8 - 40),
+                100..=107 => self.attrs.bg = self.indexed_color(params[i] as u8 - 100 + 8),
 
                 // Extended color modes (38;5;n for 256-color, 38;2;r;g;b for true color)
                 38 | 48 => {
@@ -638,7 +1225,7 @@ impl TerminalState {
                             // 256-color mode: 38;5;n or 48;5;n
                             5 => {
                                 if i + 2 < params.len() {
-                                    let color = color_256_to_rgba(params[i + 2] as u8);
+                                    let color = self.indexed_color(params[i + 2] as u8);
                                     if params[i] == 38 {
                                         self.attrs.fg = color;
                                     } else {
@@ -820,7 +1407,7 @@ impl Perform for TerminalState {
         }
     }
 
-    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
         // Handle OSC sequences
         if params.is_empty() {
             return;
@@ -828,6 +1415,61 @@ impl Perform for TerminalState {
 
         let first = params[0];
 
+        // Handle OSC 4/10/11/12 - query or change palette and default colors
+        // Programs such as editors ask to pick a light or dark scheme
+        let code = std::str::from_utf8(first)
+            .ok()
+            .and_then(|c| c.parse::<u16>().ok());
+        let special = [
+            DynamicColor::Foreground,
+            DynamicColor::Background,
+            DynamicColor::Cursor,
+        ];
+        match code {
+            Some(4) => {
+                // OSC 4 ; index ; spec [; index ; spec ...]
+                for pair in params[1..].chunks_exact(2) {
+                    let index = std::str::from_utf8(pair[0])
+                        .ok()
+                        .and_then(|i| i.parse().ok());
+                    if let Some(index) = index {
+                        let color = DynamicColor::Palette(index);
+                        self.query_or_set_color(color, pair[1], bell_terminated);
+                    }
+                }
+                return;
+            }
+            Some(code @ 10..=12) => {
+                // Each further spec goes to the next color: OSC 10 ; fg ; bg
+                let colors = &special[(code - 10) as usize..];
+                for (color, spec) in colors.iter().zip(&params[1..]) {
+                    self.query_or_set_color(*color, spec, bell_terminated);
+                }
+                return;
+            }
+            Some(104) => {
+                // Reset the given palette colors, or all of them
+                let indices: Vec<u8> = if params.len() > 1 {
+                    params[1..]
+                        .iter()
+                        .filter_map(|i| std::str::from_utf8(i).ok()?.parse().ok())
+                        .collect()
+                } else {
+                    (0..=255).collect()
+                };
+                for index in indices {
+                    self.set_dynamic_color(DynamicColor::Palette(index), None);
+                }
+                return;
+            }
+            Some(code @ 110..=112) => {
+                // Reset the foreground, background or cursor color
+                self.set_dynamic_color(special[(code - 110) as usize], None);
+                return;
+            }
+            _ => {}
+        }
+
         // Handle OSC 133 - Shell Integration (FinalTerm/VS Code)
         if first == b"133" {
             if let Some(code) = params.get(1) {
@@ -836,7 +1478,8 @@ impl Perform for TerminalState {
 
                 match *code {
                     b"A" => {
-                        // Prompt start
+                        // Prompt start; a command still showing progress is done
+                        self.set_progress(ProgressState::Hidden);
                         self.add_prompt_marker(PromptMarkerType::PromptStart);
                         self.zone_tracker.mark_prompt_start(line, timestamp);
                     }
@@ -844,9 +1487,17 @@ impl Perform for TerminalState {
                         // Command start / input begins
                         self.add_prompt_marker(PromptMarkerType::CommandStart);
                         self.zone_tracker.mark_command_start(line, timestamp);
+                        self.command_input = Some((line as usize, self.cursor_x));
                     }
                     b"C" => {
-                        // Command executed / output begins
+                        // Command executed / output begins; what was typed
+                        // since 133;B is the command
+                        if let Some((start, col)) = self.command_input.take() {
+                            let command = self.command_text_from(start, col);
+                            if !command.is_empty() {
+                                self.zone_tracker.set_command_text(command);
+                            }
+                        }
                         self.add_prompt_marker(PromptMarkerType::CommandExecuted);
                         self.zone_tracker.mark_command_executed(line, timestamp);
                     }
@@ -873,6 +1524,28 @@ impl Perform for TerminalState {
             return;
         }
 
+        // Handle OSC 9;4 - ConEmu / Windows Terminal progress
+        if first == b"9" && params.get(1) == Some(&&b"4"[..]) {
+            match parse_progress(params.get(2), params.get(3), self.progress) {
+                Some(progress) => self.set_progress(progress),
+                None => log::debug!("Unknown OSC 9;4 state: {:?}", params.get(2)),
+            }
+            return;
+        }
+
+        // Handle OSC 8 - Hyperlinks: OSC 8 ; params ; URI ST
+        // Cells printed until the next OSC 8 (with an empty URI) carry the link
+        if first == b"8" {
+            // The URI may itself contain ';', which VTE splits on
+            let uri = params
+                .get(2..)
+                .map(|parts| parts.join(&b';'))
+                .and_then(|uri| String::from_utf8(uri).ok())
+                .unwrap_or_default();
+            self.active_hyperlink = (!uri.is_empty()).then_some(uri);
+            return;
+        }
+
         // Handle OSC 1337 - iTerm2 image protocol
         if first == b"1337" {
             if params.len() < 2 {
@@ -913,7 +1586,7 @@ impl Perform for TerminalState {
     fn csi_dispatch(
         &mut self,
         params: &vte::Params,
-        _intermediates: &[u8],
+        intermediates: &[u8],
         _ignore: bool,
         action: char,
     ) {
@@ -960,6 +1633,7 @@ impl Perform for TerminalState {
                     0 => {
                         // Clear from cursor to end of screen
                         self.clear_to_eol();
+                        self.unlink_rows(self.cursor_y + 1..self.rows);
                         for y in (self.cursor_y as usize + 1)..self.rows as usize {
                             for x in 0..cols {
                                 let idx = y * cols + x;
@@ -971,6 +1645,7 @@ impl Perform for TerminalState {
                     }
                     1 => {
                         // Clear from cursor to beginning of screen
+                        self.unlink_rows(0..self.cursor_y);
                         for y in 0..self.cursor_y as usize {
                             for x in 0..cols {
                                 let idx = y * cols + x;
@@ -995,6 +1670,7 @@ impl Perform for TerminalState {
                     0 => self.clear_to_eol(),
                     1 => {
                         // Clear from beginning of line to cursor
+                        self.unlink_line(self.cursor_y, 0, self.cursor_x);
                         for x in 0..=self.cursor_x as usize {
                             let idx = self.cursor_y as usize * cols + x;
                             if idx < self.grid.cells.len() {
@@ -1004,6 +1680,7 @@ impl Perform for TerminalState {
                     }
                     2 => {
                         // Clear entire line
+                        self.unlink_rows(self.cursor_y..self.cursor_y + 1);
                         for x in 0..cols {
                             let idx = self.cursor_y as usize * cols + x;
                             if idx < self.grid.cells.len() {
@@ -1029,6 +1706,26 @@ impl Perform for TerminalState {
                 self.cursor_y = self.saved_cursor.1;
                 self.attrs = self.saved_attrs;
             }
+            'q' if intermediates == b" " => {
+                // Set Cursor Style (DECSCUSR)
+                let style = params.get(0).copied().unwrap_or(0);
+                if (0..=CURSOR_STYLE_STEADY_BAR as i64).contains(&style) {
+                    self.cursor_style = style as u8;
+                    self.content_changed = true;
+                }
+            }
+            'n' if intermediates == b"?" => {
+                // Color scheme query (CSI ? 996 n)
+                if params.first() == Some(&996) {
+                    self.report_color_scheme();
+                }
+            }
+            'h' | 'l' if intermediates == b"?" => {
+                // Color scheme change reports (DEC mode 2031)
+                if params.contains(&2031) {
+                    self.color_scheme_updates = action == 'h';
+                }
+            }
             'n' => {
                 // Device Status Report (DSR)
                 let n = params.get(0).copied().unwrap_or(0);
@@ -1052,6 +1749,11 @@ impl Perform for TerminalState {
     fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {}
 }
 
+/// Whether two cells render identically (padding is ignored)
+fn same_cell(a: &Cell, b: &Cell) -> bool {
+    a.char_codepoint == b.char_codepoint && a.fg == b.fg && a.bg == b.bg && a.flags == b.flags
+}
+
 /// Convert ANSI color index (0-7) to RGBA
 /// Colors match the Slime theme palette
 fn ansi_color_to_rgba(index: u8) -> u32 {
@@ -1108,6 +1810,115 @@ fn color_256_to_rgba(index: u8) -> u32 {
     }
 }
 
+/// RGB for a color spec of OSC 4/10/11/12
+///
+/// Takes the X11 forms `rgb:r/g/b`, with 1-4 hex digits per channel, and
+/// `#rgb` through `#rrrrggggbbbb`. Color names are not supported.
+fn parse_color_spec(spec: &[u8]) -> Option<[u8; 3]> {
+    let spec = std::str::from_utf8(spec).ok()?;
+    if !spec.is_ascii() {
+        return None;
+    }
+
+    if let Some(channels) = spec.strip_prefix("rgb:") {
+        // Scaled, so `f`, `ff` and `ffff` are all full intensity
+        let scale = |hex: &str| {
+            if hex.is_empty() || hex.len() > 4 {
+                return None;
+            }
+            let value = u32::from_str_radix(hex, 16).ok()?;
+            let max = (1u32 << (4 * hex.len())) - 1;
+            Some((value * 255 / max) as u8)
+        };
+        let mut parts = channels.split('/');
+        let rgb = [
+            scale(parts.next()?)?,
+            scale(parts.next()?)?,
+            scale(parts.next()?)?,
+        ];
+        return parts.next().is_none().then_some(rgb);
+    }
+
+    // The digits are the high bits of each channel, so `#f00` is `#f00000`
+    let hex = spec.strip_prefix('#')?;
+    let width = hex.len() / 3;
+    if hex.len() % 3 != 0 || !(1..=4).contains(&width) {
+        return None;
+    }
+    let high_bits = |i: usize| {
+        let digits = &hex[i * width..i * width + width.min(2)];
+        u8::from_str_radix(digits, 16)
+            .ok()
+            .map(|v| v << (8 - 4 * digits.len()))
+    };
+    Some([high_bits(0)?, high_bits(1)?, high_bits(2)?])
+}
+
+/// Parse the `state;progress` parameters of OSC 9;4
+///
+/// States: 0 hides, 1 sets normal progress, 2 is an error, 3 is
+/// indeterminate, and 4 is paused. Error and paused keep the previous percent
+/// when none is given. Returns `None` for an unknown state.
+fn parse_progress(
+    state: Option<&&[u8]>,
+    percent: Option<&&[u8]>,
+    previous: ProgressState,
+) -> Option<ProgressState> {
+    let percent = percent
+        .and_then(|p| std::str::from_utf8(p).ok())
+        .and_then(|p| p.parse::<u32>().ok())
+        .map(|p| p.min(100) as u8);
+    let kept = percent.or(previous.percent()).unwrap_or(0);
+
+    match state.map_or(&b"0"[..], |s| *s) {
+        b"0" => Some(ProgressState::Hidden),
+        b"1" => Some(ProgressState::Normal(percent.unwrap_or(0))),
+        b"2" => Some(ProgressState::Error(kept)),
+        b"3" => Some(ProgressState::Indeterminate),
+        b"4" => Some(ProgressState::Paused(kept)),
+        _ => None,
+    }
+}
+
+/// Character shown for a cell; empty cells are blank
+fn cell_char(cell: &Cell) -> char {
+    match cell.char_codepoint {
+        0 => ' ',
+        cp => char::from_u32(cp).unwrap_or(' '),
+    }
+}
+
+/// SGR escape setting exactly these colors and flags
+fn sgr(fg: u32, bg: u32, flags: u8) -> String {
+    let mut params = vec!["0".to_string()];
+    for (flag, code) in [
+        (FLAG_BOLD, "1"),
+        (FLAG_DIM, "2"),
+        (FLAG_ITALIC, "3"),
+        (FLAG_UNDERLINE, "4"),
+        (FLAG_INVERSE, "7"),
+    ] {
+        if flags & flag != 0 {
+            params.push(code.to_string());
+        }
+    }
+    let rgb = |color: u32| {
+        format!(
+            "{};{};{}",
+            (color >> 16) & 0xFF,
+            (color >> 8) & 0xFF,
+            color & 0xFF
+        )
+    };
+    if fg != DEFAULT_FG {
+        params.push(format!("38;2;{}", rgb(fg)));
+    }
+    if bg != DEFAULT_BG {
+        params.push(format!("48;2;{}", rgb(bg)));
+    }
+    format!("\x1b[{}m", params.join(";"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1179,6 +1990,240 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_osc_133_command_text() {
+        let mut state = TerminalState::new(10, 5);
+        state.process_output(b"\x1b]133;A\x07$ \x1b]133;B\x07cargo build --release\r\n");
+        state.process_output(b"\x1b]133;C\x07done\r\n\x1b]133;D;0\x07");
+        state.process_output(b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+        state.process_output(b"\x1b]133;D;2\x07");
+        state.process_output(
+            b"\x1b]133;A\x07$ \x1b]133;B\x07for f in *\r\n> do rm $f\r\n> done\r\n",
+        );
+        state.process_output(b"\x1b]133;C\x07\x1b]133;D;0\x07");
+
+        // Rows the command wrapped across are joined back together
+        let blocks = state.zone_tracker.command_blocks();
+        assert_eq!(blocks[0].command_text(), Some("cargo build --release"));
+        assert_eq!(blocks[1].command_text(), Some("ls"));
+        // Continuation rows are left out rather than kept with their PS2
+        assert_eq!(blocks[2].command_text(), Some("for f in *"));
+    }
+
+    #[test]
+    fn test_prompt_markers_change() {
+        let mut state = TerminalState::new(20, 5);
+        assert!(state.take_prompt_markers_change().is_none());
+
+        state.process_output(b"\x1b]133;A\x07$ \x1b]133;B\x07false\r\n\x1b]133;C\x07");
+        state.process_output(b"\x1b]133;D;1\x07");
+        let markers = state.take_prompt_markers_change().unwrap();
+        let kinds: Vec<(u8, u32, Option<i32>)> = markers
+            .iter()
+            .map(|m| (m.marker_type, m.line, m.exit_code))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![(0, 0, None), (1, 0, None), (2, 1, None), (3, 1, Some(1))]
+        );
+        assert!(markers[3].timestamp_micros >= markers[0].timestamp_micros);
+
+        // Taken until the next marker
+        assert!(state.take_prompt_markers_change().is_none());
+    }
+
+    #[test]
+    fn test_line_ansi() {
+        let mut state = TerminalState::new(20, 3);
+        state.process_output(b"ok \x1b[31mfail\x1b[0m \x1b[1mdone\x1b[0m\r\nplain");
+
+        assert_eq!(
+            state.line_ansi(0).unwrap(),
+            "ok \x1b[0;38;2;255;85;85mfail\x1b[0m \x1b[0;1mdone\x1b[0m"
+        );
+        assert_eq!(state.line_ansi(1).unwrap(), "plain");
+        assert_eq!(state.line_text(0).unwrap(), "ok fail done");
+        assert_eq!(state.line_ansi(3), None);
+    }
+
+    #[test]
+    fn test_osc_9_4_progress() {
+        let mut state = TerminalState::new(80, 24);
+        assert_eq!(state.take_progress_change(), None);
+
+        state.process_output(b"\x1b]9;4;1;42\x07");
+        assert_eq!(
+            state.take_progress_change(),
+            Some(ProgressState::Normal(42))
+        );
+        // Taken once per change
+        assert_eq!(state.take_progress_change(), None);
+
+        // Repeating the same state is not a change
+        state.process_output(b"\x1b]9;4;1;42\x1b\\");
+        assert_eq!(state.take_progress_change(), None);
+
+        // Error keeps the percent reached; values are capped at 100
+        state.process_output(b"\x1b]9;4;2\x07");
+        assert_eq!(state.progress, ProgressState::Error(42));
+        state.process_output(b"\x1b]9;4;1;250\x07");
+        assert_eq!(state.progress, ProgressState::Normal(100));
+
+        state.process_output(b"\x1b]9;4;3\x07");
+        assert_eq!(state.progress, ProgressState::Indeterminate);
+
+        // A new prompt clears progress left behind by the last command
+        state.osc_dispatch(&[b"133", b"A"], true);
+        assert_eq!(state.take_progress_change(), Some(ProgressState::Hidden));
+
+        // Plain OSC 9 notifications are not progress
+        state.process_output(b"\x1b]9;build done\x07");
+        assert_eq!(state.take_progress_change(), None);
+    }
+
+    #[test]
+    fn test_osc_8_hyperlinks() {
+        let mut state = TerminalState::new(80, 24);
+        state.process_output(b"see \x1b]8;;https://example.com/a;b\x1b\\docs\x1b]8;;\x1b\\ here");
+
+        // The URI keeps its ';' and only the linked text is covered
+        assert_eq!(
+            state.hyperlinks(),
+            &[HyperlinkInfo {
+                row: 0,
+                start_col: 4,
+                end_col: 7,
+                uri: "https://example.com/a;b".to_string(),
+            }]
+        );
+
+        // Overwriting part of a link trims it
+        state.process_output(b"\x1b[1;6Hx");
+        assert_eq!(state.hyperlinks()[0].end_col, 4);
+        assert_eq!(state.hyperlinks()[1].start_col, 6);
+
+        // Links move with the screen and drop off the top
+        state.process_output(b"\x1b[24;1H\n");
+        assert!(state.hyperlinks().is_empty());
+
+        state.process_output(b"\x1b]8;id=1;file:///tmp\x07tmp\x1b]8;;\x07");
+        assert_eq!(state.hyperlinks()[0].row, 23);
+        state.process_output(b"\x1b[2J");
+        assert!(state.hyperlinks().is_empty());
+    }
+
+    #[test]
+    fn test_color_queries() {
+        let mut state = TerminalState::new(80, 24);
+        state.set_reported_colors(ReportedColors::DEFAULT);
+
+        state.process_output(b"\x1b]11;?\x07\x1b]10;?\x1b\\\x1b[?996n");
+        let responses: Vec<Vec<u8>> = state.pending_responses.drain(..).collect();
+        assert_eq!(responses[0], b"\x1b]11;rgb:0d0d/1212/0808\x07");
+        assert_eq!(responses[1], b"\x1b]10;rgb:a8a8/dfdf/5a5a\x1b\\");
+        assert_eq!(responses[2], b"\x1b[?997;1n");
+
+        // Switching to light reports only once mode 2031 is on
+        let light = ReportedColors {
+            foreground: [0x33, 0x33, 0x33],
+            background: [0xfa, 0xfa, 0xfa],
+            ..ReportedColors::DEFAULT
+        };
+        state.set_reported_colors(light);
+        assert!(state.pending_responses.is_empty());
+        state.set_reported_colors(ReportedColors::DEFAULT);
+        state.process_output(b"\x1b[?2031h");
+        state.set_reported_colors(light);
+        assert_eq!(state.pending_responses, vec![b"\x1b[?997;2n".to_vec()]);
+    }
+
+    #[test]
+    fn test_osc_palette_colors() {
+        let mut state = TerminalState::new(80, 24);
+        state.set_reported_colors(ReportedColors::DEFAULT);
+
+        // Changed colors are used by SGR and reported back
+        state.process_output(b"\x1b]4;1;rgb:00/80/ff;200;#fff\x07\x1b[31mx\x1b[38;5;200my");
+        assert_eq!(state.grid.get(0, 0).unwrap().fg, 0xFF0080FF);
+        assert_eq!(state.grid.get(1, 0).unwrap().fg, 0xFFF0F0F0);
+        state.process_output(b"\x1b]4;1;?;2;?\x07");
+        let responses: Vec<Vec<u8>> = state.pending_responses.drain(..).collect();
+        assert_eq!(responses[0], b"\x1b]4;1;rgb:0000/8080/ffff\x07");
+        assert_eq!(responses[1], b"\x1b]4;2;rgb:a8a8/dfdf/5a5a\x07");
+
+        // Clients hear about colors they have settings for
+        assert_eq!(
+            state.take_color_changes(),
+            vec![("palette.red".to_string(), "#0080ff".to_string())]
+        );
+
+        // OSC 10 ; fg ; bg sets both, and resets go back to the theme
+        state.process_output(b"\x1b]10;#111;#eeeeee\x1b\\\x1b]104\x07\x1b]110\x07");
+        assert_eq!(
+            state.dynamic_color(DynamicColor::Background),
+            [0xee, 0xee, 0xee]
+        );
+        assert_eq!(state.indexed_color(1), 0xFFFF5555);
+        assert_eq!(
+            state.take_color_changes(),
+            vec![
+                ("background".to_string(), "#eeeeee".to_string()),
+                ("palette.red".to_string(), "#ff5555".to_string()),
+                ("foreground".to_string(), "#a8df5a".to_string()),
+            ]
+        );
+
+        assert_eq!(parse_color_spec(b"rgb:f/8/0"), Some([0xff, 0x88, 0x00]));
+        assert_eq!(parse_color_spec(b"#123456789abc"), Some([0x12, 0x56, 0x9a]));
+        assert_eq!(parse_color_spec(b"red"), None);
+    }
+
+    #[test]
+    fn test_pane_theme_colors() {
+        let mut state = TerminalState::new(80, 24);
+        state.set_reported_colors(ReportedColors::DEFAULT);
+        let mut theme = ReportedColors {
+            background: [0x3a, 0x00, 0x00],
+            ..ReportedColors::DEFAULT
+        };
+        theme.palette[1] = [0xff, 0x00, 0x00];
+        state.set_pane_theme(Some(theme));
+
+        // The pane's palette and colors take the place of the window's
+        state.process_output(b"\x1b[31mx\x1b]11;?\x07");
+        assert_eq!(state.grid.get(0, 0).unwrap().fg, 0xFFFF0000);
+        assert_eq!(
+            state.pending_responses[0],
+            b"\x1b]11;rgb:3a3a/0000/0000\x07"
+        );
+
+        // Colors set by programs still win
+        state.process_output(b"\x1b]4;1;#00ff00\x07\x1b[31my");
+        assert_eq!(state.grid.get(1, 0).unwrap().fg, 0xFF00FF00);
+
+        state.set_pane_theme(None);
+        state.process_output(b"\x1b]104\x07\x1b[31mz");
+        assert_eq!(state.grid.get(2, 0).unwrap().fg, 0xFFFF5555);
+    }
+
+    #[test]
+    fn test_decscusr_cursor_style() {
+        let mut state = TerminalState::new(80, 24);
+        assert_eq!(state.cursor_style, CURSOR_STYLE_DEFAULT);
+
+        // CSI 6 SP q - steady bar
+        state.process_output(b"\x1b[6 q");
+        assert_eq!(state.cursor_style, CURSOR_STYLE_STEADY_BAR);
+
+        // Out-of-range values are ignored
+        state.process_output(b"\x1b[9 q");
+        assert_eq!(state.cursor_style, CURSOR_STYLE_STEADY_BAR);
+
+        // CSI SP q - back to the configured default
+        state.process_output(b"\x1b[ q");
+        assert_eq!(state.cursor_style, CURSOR_STYLE_DEFAULT);
+    }
+
     #[test]
     fn test_prompt_navigation() {
         let mut state = TerminalState::new(80, 24);
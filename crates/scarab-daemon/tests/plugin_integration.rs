@@ -364,7 +364,7 @@ mod script_tests {
 mod plugin_manager_tests {
     use super::*;
     use async_trait::async_trait;
-    use scarab_plugin_api::PluginError;
+    use scarab_plugin_api::{ConfigField, ConfigSchema, ConfigValueType, PluginError};
 
     /// Mock plugin for testing
     struct MockPlugin {
@@ -500,6 +500,27 @@ mod plugin_manager_tests {
         assert_eq!(manager.enabled_count(), 0);
     }
 
+    #[tokio::test]
+    async fn test_register_incompatible_plugin() {
+        let mut manager = create_test_manager();
+        let mut plugin = MockPlugin::new("future_plugin");
+        plugin.metadata = plugin.metadata.with_min_scarab_version("99.0.0");
+
+        let result = manager.register_plugin(Box::new(plugin)).await;
+        assert!(matches!(
+            result,
+            Err(PluginError::VersionIncompatible { .. })
+        ));
+        assert_eq!(manager.enabled_count(), 0);
+
+        // --force-load registers it anyway
+        let mut manager = create_test_manager().with_force_load(true);
+        let mut plugin = MockPlugin::new("future_plugin");
+        plugin.metadata = plugin.metadata.with_api_version("9.0.0");
+        manager.register_plugin(Box::new(plugin)).await.unwrap();
+        assert_eq!(manager.enabled_count(), 1);
+    }
+
     #[tokio::test]
     async fn test_plugin_failure_tracking() {
         let mut manager = create_test_manager();
@@ -635,6 +656,35 @@ mod plugin_manager_tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_output_filters_skip_unmatched_lines() {
+        let mut manager = create_test_manager();
+
+        let mut errors = MockPlugin::new("errors").with_modification();
+        errors.metadata = errors
+            .metadata
+            .with_output_prefix("error")
+            .with_output_regex(r"^\s+--> ");
+        manager.register_plugin(Box::new(errors)).await.unwrap();
+
+        let result = manager
+            .dispatch_output("error: linker failed")
+            .await
+            .unwrap();
+        assert_eq!(result, "[errors] error: linker failed");
+        let result = manager
+            .dispatch_output("  --> src/main.rs:3")
+            .await
+            .unwrap();
+        assert_eq!(result, "[errors]   --> src/main.rs:3");
+        let result = manager.dispatch_output("Compiling scarab").await.unwrap();
+        assert_eq!(result, "Compiling scarab");
+
+        let mut invalid = MockPlugin::new("invalid");
+        invalid.metadata = invalid.metadata.with_output_regex("(unclosed");
+        assert!(manager.register_plugin(Box::new(invalid)).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_remote_command_queueing() {
         let mut manager = create_test_manager();
@@ -670,6 +720,387 @@ mod plugin_manager_tests {
         assert_eq!(result, b"test input");
     }
 
+    #[tokio::test]
+    async fn test_dispatch_key() {
+        use scarab_plugin_api::key_tables::{KeyCode, KeyCombo};
+
+        let mut manager = create_test_manager();
+
+        // Swallows Ctrl+C and records every key it sees
+        struct KeyPlugin {
+            metadata: PluginMetadata,
+            seen: Arc<parking_lot::Mutex<Vec<KeyCombo>>>,
+        }
+
+        #[async_trait]
+        impl Plugin for KeyPlugin {
+            fn metadata(&self) -> &PluginMetadata {
+                &self.metadata
+            }
+
+            async fn on_key(
+                &mut self,
+                key: KeyCombo,
+                _ctx: &PluginContext,
+            ) -> scarab_plugin_api::Result<Action> {
+                self.seen.lock().push(key.clone());
+                if key == KeyCombo::ctrl(KeyCode::KeyC) {
+                    return Ok(Action::Modify(Vec::new()));
+                }
+                Ok(Action::Continue)
+            }
+        }
+
+        let seen = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let plugin = Box::new(KeyPlugin {
+            metadata: PluginMetadata::new("keys", "1.0.0", "Watches keys", "Test"),
+            seen: seen.clone(),
+        });
+        manager.register_plugin(plugin).await.unwrap();
+
+        // Legacy Ctrl+C, then CSI u Ctrl+C, then an arrow key, then a CSI u
+        // Ctrl+Shift+T nothing takes, which is dropped
+        let result = manager
+            .dispatch_input(b"a\x03b\x1b[99;5uc\x1b[A\x1b[116;6u")
+            .await
+            .unwrap();
+        assert_eq!(result, b"abc\x1b[A");
+
+        let seen = seen.lock();
+        assert_eq!(seen.len(), 7);
+        assert_eq!(seen[1], KeyCombo::ctrl(KeyCode::KeyC));
+        assert_eq!(seen[3], KeyCombo::ctrl(KeyCode::KeyC));
+        assert_eq!(seen[5], KeyCombo::key(KeyCode::Up));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_key_binding() {
+        use scarab_plugin_api::key_tables::{KeyCode, KeyCombo, KeyModifiers};
+        use scarab_plugin_api::PluginKeyBinding;
+
+        // Binds Ctrl+Alt+G to its command and records the commands it runs
+        struct BindingPlugin {
+            metadata: PluginMetadata,
+            command: &'static str,
+            ran: Arc<parking_lot::Mutex<Vec<String>>>,
+        }
+
+        #[async_trait]
+        impl Plugin for BindingPlugin {
+            fn metadata(&self) -> &PluginMetadata {
+                &self.metadata
+            }
+
+            fn get_keybindings(&self) -> Vec<PluginKeyBinding> {
+                let key = KeyCombo::new(KeyCode::KeyG, KeyModifiers::CTRL | KeyModifiers::ALT);
+                vec![PluginKeyBinding::new(key, self.command)]
+            }
+
+            async fn on_remote_command(
+                &mut self,
+                id: &str,
+                _ctx: &PluginContext,
+            ) -> scarab_plugin_api::Result<()> {
+                self.ran
+                    .lock()
+                    .push(format!("{}:{}", self.metadata.name, id));
+                Ok(())
+            }
+        }
+
+        async fn register(manager: &mut PluginManager, ran: &Arc<parking_lot::Mutex<Vec<String>>>) {
+            for (name, command) in [("git", "git.status"), ("grep", "grep.open")] {
+                let plugin = Box::new(BindingPlugin {
+                    metadata: PluginMetadata::new(name, "1.0.0", "Binds a key", "Test"),
+                    command,
+                    ran: ran.clone(),
+                });
+                manager.register_plugin(plugin).await.unwrap();
+            }
+        }
+
+        let ran = Arc::new(parking_lot::Mutex::new(Vec::new()));
+
+        // The first plugin keeps the contested key, which never reaches the PTY
+        let mut manager = create_test_manager();
+        register(&mut manager, &ran).await;
+        let result = manager.dispatch_input(b"a\x1b\x07b").await.unwrap();
+        assert_eq!(result, b"ab");
+        assert_eq!(*ran.lock(), vec!["git:git.status".to_string()]);
+
+        // Unbinding the first plugin's command hands the key to the second
+        let mut config = scarab_config::KeyBindings::default();
+        config
+            .plugins
+            .insert("git.status".to_string(), String::new());
+        let mut manager = create_test_manager().with_keybindings(&config);
+        register(&mut manager, &ran).await;
+        ran.lock().clear();
+        manager.dispatch_input(b"\x1b\x07").await.unwrap();
+        assert_eq!(*ran.lock(), vec!["grep:grep.open".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_mouse() {
+        use scarab_plugin_api::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+        let mut manager = create_test_manager();
+
+        // Claims double clicks and records every event it sees
+        struct MousePlugin {
+            metadata: PluginMetadata,
+            seen: Arc<parking_lot::Mutex<Vec<MouseEvent>>>,
+        }
+
+        #[async_trait]
+        impl Plugin for MousePlugin {
+            fn metadata(&self) -> &PluginMetadata {
+                &self.metadata
+            }
+
+            async fn on_mouse(
+                &mut self,
+                event: &MouseEvent,
+                _ctx: &PluginContext,
+            ) -> scarab_plugin_api::Result<Action> {
+                self.seen.lock().push(*event);
+                if event.is_press(MouseButton::Left) && event.click_count == 2 {
+                    return Ok(Action::Stop);
+                }
+                Ok(Action::Continue)
+            }
+        }
+
+        let first = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let second = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        for (name, seen) in [("first", &first), ("second", &second)] {
+            let plugin = Box::new(MousePlugin {
+                metadata: PluginMetadata::new(name, "1.0.0", "Watches the mouse", "Test"),
+                seen: seen.clone(),
+            });
+            manager.register_plugin(plugin).await.unwrap();
+        }
+
+        let click = |click_count| scarab_protocol::MouseEvent {
+            kind: MouseEventKind::Press,
+            button: MouseButton::Left,
+            col: 12,
+            row: 3,
+            modifiers: KeyModifiers::CTRL.bits(),
+            click_count,
+        };
+        manager.dispatch_mouse(&click(1).into()).await.unwrap();
+        manager.dispatch_mouse(&click(2).into()).await.unwrap();
+
+        let first = first.lock();
+        assert_eq!(first.len(), 2);
+        assert_eq!((first[0].col, first[0].row), (12, 3));
+        assert!(first[0].modifiers.ctrl());
+        assert_eq!(first[1].click_count, 2);
+
+        // The double click stopped at the first plugin
+        assert_eq!(second.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_message_bus() {
+        use scarab_plugin_api::PluginMessage;
+
+        let mut manager = create_test_manager();
+
+        // Publishes when a line reports a failed command
+        struct Publisher {
+            metadata: PluginMetadata,
+        }
+
+        #[async_trait]
+        impl Plugin for Publisher {
+            fn metadata(&self) -> &PluginMetadata {
+                &self.metadata
+            }
+
+            async fn on_output(
+                &mut self,
+                line: &str,
+                ctx: &PluginContext,
+            ) -> scarab_plugin_api::Result<Action> {
+                if line.contains("exit 1") {
+                    ctx.emit(
+                        "zones.command_failed",
+                        serde_json::json!({ "exit_code": 1 }),
+                    );
+                }
+                Ok(Action::Continue)
+            }
+        }
+
+        struct Subscriber {
+            metadata: PluginMetadata,
+            received: Arc<parking_lot::Mutex<Vec<PluginMessage>>>,
+        }
+
+        #[async_trait]
+        impl Plugin for Subscriber {
+            fn metadata(&self) -> &PluginMetadata {
+                &self.metadata
+            }
+
+            async fn on_message(
+                &mut self,
+                message: &PluginMessage,
+                _ctx: &PluginContext,
+            ) -> scarab_plugin_api::Result<()> {
+                self.received.lock().push(message.clone());
+                Ok(())
+            }
+        }
+
+        let received = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        manager
+            .register_plugin(Box::new(Publisher {
+                metadata: PluginMetadata::new("zones", "1.0.0", "Publishes", "Test"),
+            }))
+            .await
+            .unwrap();
+        manager
+            .register_plugin(Box::new(Subscriber {
+                metadata: PluginMetadata::new("notify", "1.0.0", "Subscribes", "Test")
+                    .with_subscription("zones.*"),
+                received: received.clone(),
+            }))
+            .await
+            .unwrap();
+
+        manager.dispatch_output("all good").await.unwrap();
+        assert!(received.lock().is_empty());
+
+        manager.dispatch_output("make: exit 1").await.unwrap();
+        let received = received.lock();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].topic, "zones.command_failed");
+        assert_eq!(received[0].payload["exit_code"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_response() {
+        use scarab_plugin_api::PromptResponse;
+
+        let mut manager = create_test_manager();
+
+        // Asks for a tab name when the command named after it is selected
+        struct Asker {
+            metadata: PluginMetadata,
+            prompt_ids: Arc<parking_lot::Mutex<Vec<u64>>>,
+            responses: Arc<parking_lot::Mutex<Vec<PromptResponse>>>,
+        }
+
+        #[async_trait]
+        impl Plugin for Asker {
+            fn metadata(&self) -> &PluginMetadata {
+                &self.metadata
+            }
+
+            async fn on_remote_command(
+                &mut self,
+                id: &str,
+                ctx: &PluginContext,
+            ) -> scarab_plugin_api::Result<()> {
+                if id == self.metadata.name {
+                    let prompt_id = ctx.show_input_prompt("Rename tab", "Tab name", false);
+                    self.prompt_ids.lock().push(prompt_id);
+                }
+                Ok(())
+            }
+
+            async fn on_prompt_response(
+                &mut self,
+                response: &PromptResponse,
+                _ctx: &PluginContext,
+            ) -> scarab_plugin_api::Result<()> {
+                self.responses.lock().push(response.clone());
+                Ok(())
+            }
+        }
+
+        let prompt_ids = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let asker_responses = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let other_responses = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        for (name, responses) in [("asker", &asker_responses), ("other", &other_responses)] {
+            manager
+                .register_plugin(Box::new(Asker {
+                    metadata: PluginMetadata::new(name, "1.0.0", "Asks", "Test"),
+                    prompt_ids: prompt_ids.clone(),
+                    responses: responses.clone(),
+                }))
+                .await
+                .unwrap();
+        }
+
+        manager.dispatch_remote_command("asker").await.unwrap();
+        let id = prompt_ids.lock()[0];
+
+        manager
+            .dispatch_prompt_response(id, Some(vec!["build".to_string()]))
+            .await
+            .unwrap();
+        // Each prompt is answered once
+        manager.dispatch_prompt_response(id, None).await.unwrap();
+
+        let responses = asker_responses.lock();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].prompt_id, id);
+        assert_eq!(responses[0].value(), Some("build"));
+        assert!(other_responses.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_panel_item_selected() {
+        let mut manager = create_test_manager();
+
+        struct Picker {
+            metadata: PluginMetadata,
+            picked: Arc<parking_lot::Mutex<Vec<(u64, String)>>>,
+        }
+
+        #[async_trait]
+        impl Plugin for Picker {
+            fn metadata(&self) -> &PluginMetadata {
+                &self.metadata
+            }
+
+            async fn on_panel_item_selected(
+                &mut self,
+                overlay_id: u64,
+                item_id: &str,
+                _ctx: &PluginContext,
+            ) -> scarab_plugin_api::Result<()> {
+                self.picked.lock().push((overlay_id, item_id.to_string()));
+                Ok(())
+            }
+        }
+
+        let picked = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        manager
+            .register_plugin(Box::new(Picker {
+                metadata: PluginMetadata::new("picker", "1.0.0", "Picks", "Test"),
+                picked: picked.clone(),
+            }))
+            .await
+            .unwrap();
+
+        manager
+            .dispatch_panel_selection("picker", 3, "main")
+            .await
+            .unwrap();
+        // Selections for plugins that are not loaded are ignored
+        manager
+            .dispatch_panel_selection("missing", 3, "main")
+            .await
+            .unwrap();
+
+        assert_eq!(*picked.lock(), vec![(3, "main".to_string())]);
+    }
+
     #[tokio::test]
     async fn test_dispatch_resize() {
         let mut manager = create_test_manager();
@@ -771,6 +1202,83 @@ mod plugin_manager_tests {
         assert_eq!(context.get_data("key2"), Some("value2".to_string()));
         assert_eq!(context.get_data("nonexistent"), None);
     }
+
+    /// Plugin that records the settings it is handed
+    struct SettingsPlugin {
+        metadata: PluginMetadata,
+        seen: Arc<parking_lot::Mutex<Vec<bool>>>,
+    }
+
+    #[async_trait]
+    impl Plugin for SettingsPlugin {
+        fn metadata(&self) -> &PluginMetadata {
+            &self.metadata
+        }
+
+        fn config_schema(&self) -> ConfigSchema {
+            ConfigSchema::new().with_field(
+                ConfigField::new("verbose", ConfigValueType::Bool, "Log more").with_default(false),
+            )
+        }
+
+        async fn on_load(&mut self, ctx: &mut PluginContext) -> scarab_plugin_api::Result<()> {
+            self.seen.lock().push(ctx.config().get("verbose")?);
+            Ok(())
+        }
+
+        async fn on_config_changed(
+            &mut self,
+            ctx: &PluginContext,
+        ) -> scarab_plugin_api::Result<()> {
+            self.seen.lock().push(ctx.config().get("verbose")?);
+            Ok(())
+        }
+    }
+
+    fn plugin_settings(toml: &str) -> scarab_config::PluginConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_plugin_settings_delivered_and_reloaded() {
+        let seen = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut manager = create_test_manager()
+            .with_plugin_settings(&plugin_settings("[settings-plugin]\nverbose = true"));
+        let plugin = Box::new(SettingsPlugin {
+            metadata: PluginMetadata::new("settings-plugin", "1.0.0", "Settings", "Test"),
+            seen: seen.clone(),
+        });
+        manager.register_plugin(plugin).await.unwrap();
+        assert_eq!(*seen.lock(), vec![true]);
+
+        // Invalid edits keep the previous settings without calling the hook
+        manager
+            .apply_plugin_settings(&plugin_settings("[settings-plugin]\nverbose = \"no\""))
+            .await;
+        assert_eq!(*seen.lock(), vec![true]);
+
+        // Removing the table falls back to the declared default
+        manager.apply_plugin_settings(&plugin_settings("")).await;
+        assert_eq!(*seen.lock(), vec![true, false]);
+
+        // Unchanged settings do not notify the plugin again
+        manager.apply_plugin_settings(&plugin_settings("")).await;
+        assert_eq!(seen.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_plugin_settings_refuse_load() {
+        let mut manager = create_test_manager()
+            .with_plugin_settings(&plugin_settings("[settings-plugin]\ncolour = \"red\""));
+        let plugin = Box::new(SettingsPlugin {
+            metadata: PluginMetadata::new("settings-plugin", "1.0.0", "Settings", "Test"),
+            seen: Arc::new(parking_lot::Mutex::new(Vec::new())),
+        });
+
+        let result = manager.register_plugin(plugin).await;
+        assert!(matches!(result, Err(PluginError::ConfigError(_))));
+        assert_eq!(manager.enabled_count(), 0);
+    }
 }
 
 mod error_propagation_tests {
@@ -1124,6 +1632,8 @@ mod discovery_tests {
             path: fzb_path,
             enabled: true,
             config: Default::default(),
+            capabilities: Default::default(),
+            allowed_hosts: Default::default(),
         };
 
         // This tests the internal load_plugin_from_config method indirectly
@@ -1135,6 +1645,81 @@ mod discovery_tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_dev_mode_keeps_plugin_until_file_returns() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("dev.fsx");
+        std::fs::write(&path, b"let x = 1").unwrap();
+
+        let mut manager = create_test_manager().with_dev_mode(true);
+        let name = manager.load_plugin_from_path(path.clone()).await.unwrap();
+
+        // A reload that fails mid-build keeps the plugin aside
+        std::fs::remove_file(&path).unwrap();
+        assert!(manager.reload_plugin(&name).await.is_err());
+        assert!(manager.plugin_name_for_path(&path).is_none());
+        assert_eq!(manager.dev_watch_paths(), vec![path.clone()]);
+
+        std::fs::write(&path, b"let x = 2").unwrap();
+        assert_eq!(
+            manager.load_plugin_from_path(path.clone()).await.unwrap(),
+            name
+        );
+        assert!(manager.dev_watch_paths().is_empty());
+
+        // So does removing its file
+        std::fs::remove_file(&path).unwrap();
+        manager.unload_removed_plugin(&name).await.unwrap();
+        assert_eq!(manager.list_plugins().len(), 0);
+        assert_eq!(manager.dev_watch_paths(), vec![path]);
+    }
+
+    #[tokio::test]
+    async fn test_undeclared_capabilities_are_not_granted() {
+        use scarab_plugin_api::{Capability, Package};
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("weather.scarabpkg");
+        let manifest = r#"
+name = "weather"
+version = "0.1.0"
+description = "Forecasts"
+author = "Someone"
+api-version = "0.1.0"
+min-scarab-version = "0.1.0"
+capabilities = ["network"]
+"#;
+        let chunk = fusabi_vm::ChunkBuilder::new().build();
+        let mut package = Package::new();
+        package
+            .add("plugin.toml", manifest.as_bytes().to_vec())
+            .unwrap();
+        package
+            .add("weather.fzb", fusabi_vm::serialize_chunk(&chunk).unwrap())
+            .unwrap();
+        package.write(&path).unwrap();
+
+        // plugins.toml grants exec too, which the manifest never asks for
+        let mut manager = create_test_manager();
+        let config = scarab_plugin_api::PluginConfig {
+            name: "weather".to_string(),
+            path,
+            enabled: true,
+            config: Default::default(),
+            capabilities: [Capability::Network, Capability::Exec].into(),
+            allowed_hosts: Default::default(),
+        };
+        manager.load_plugin_from_config(config).await.unwrap();
+
+        let granted = &manager.plugins[0].context.capabilities;
+        assert!(granted.contains(&Capability::Network));
+        assert!(!granted.contains(&Capability::Exec));
+        assert!(!manager.plugins[0]
+            .config
+            .capabilities
+            .contains(&Capability::Exec));
+    }
+
     // Note: PluginConfigData is not public, so config parsing tests would need to be
     // in the plugin-api crate's tests
 }
@@ -22,7 +22,10 @@ mod smoke_tests {
             error_mode: 0,
             cursor_x: 0,
             cursor_y: 0,
-            _padding2: [0; 2],
+            cursor_style: 0,
+            _padding2: [0; 1],
+            damage_sequence: 0,
+            damage_rows: [0; scarab_protocol::DAMAGE_WORDS],
             cells: [scarab_protocol::Cell::default(); scarab_protocol::BUFFER_SIZE],
         };
 
@@ -498,7 +501,10 @@ mod smoke_tests {
             error_mode: 0,
             cursor_x: 0,
             cursor_y: 0,
-            _padding2: [0; 2],
+            cursor_style: 0,
+            _padding2: [0; 1],
+            damage_sequence: 0,
+            damage_rows: [0; scarab_protocol::DAMAGE_WORDS],
             cells: [scarab_protocol::Cell::default(); scarab_protocol::BUFFER_SIZE],
         };
 
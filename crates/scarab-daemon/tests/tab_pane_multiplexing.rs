@@ -779,6 +779,36 @@ fn test_rename_tab() {
     assert_eq!(tab_title, "Renamed Tab");
 }
 
+#[test]
+fn test_background_tab_activity() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("sessions.db");
+
+    let manager = SessionManager::new(db_path).unwrap();
+    let session_id = manager.create_session("test".to_string(), 80, 24).unwrap();
+    let session = manager.get_session(&session_id).unwrap();
+
+    let tab1 = session.active_tab_id();
+    let tab2 = session.create_tab(Some("Tab 2".to_string())).unwrap();
+
+    let tab1_pane = session.get_active_pane().unwrap();
+
+    // Output while the tab is on screen is not activity
+    tab1_pane.mark_activity();
+    assert!(!session.tab_has_activity(tab1));
+    session.switch_tab(tab2).unwrap();
+    assert!(!session.tab_has_activity(tab1));
+
+    // Output in the background marks the tab
+    tab1_pane.mark_activity();
+    assert!(session.tab_has_activity(tab1));
+
+    // Viewing the tab clears it
+    session.switch_tab(tab1).unwrap();
+    session.switch_tab(tab2).unwrap();
+    assert!(!session.tab_has_activity(tab1));
+}
+
 #[test]
 fn test_concurrent_pane_operations() {
     use std::sync::Arc;
@@ -0,0 +1,205 @@
+//! Diagnostics: what the Fusabi frontend says about a document
+//!
+//! The document goes through the same steps as a config or script load:
+//! `#load`s are inlined, then it is lexed, parsed and compiled. A document
+//! that compiles then goes through type inference, as
+//! `scarab-plugin-compiler` does. The first error becomes the diagnostic.
+//! Frontend errors print their position as `line: N, column: M`; errors
+//! without one are shown on the first line.
+//!
+//! Inference starts from an empty environment: it knows nothing of
+//! `Scarab.*` and the stdlib, so a name it can't find is left to the
+//! compiler rather than reported.
+
+use fusabi_frontend::{Compiler, Lexer, Parser, TypeEnv, TypeInference};
+use scarab_plugin_api::fusabi_modules::AstCache;
+
+use crate::document::{Document, Position};
+
+/// An error to show in the editor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub at: Position,
+    pub message: String,
+}
+
+/// The first error in `doc`, if any
+///
+/// `cache` keeps files loaded with `#load` parsed between checks.
+pub fn check(doc: &Document, cache: &mut AstCache) -> Option<Problem> {
+    let first_load = doc
+        .text
+        .lines()
+        .position(|line| line.trim_start().starts_with("#load"));
+
+    // Lines of loaded files ahead of the document's own
+    let (source, prelude_lines) = match (&doc.path, first_load) {
+        (Some(path), Some(_)) => match cache.resolve_text(path, &doc.text) {
+            Ok(resolved) => {
+                // The document's text ends the source with as many newlines
+                let prelude =
+                    resolved.source.matches('\n').count() - doc.text.matches('\n').count();
+                (resolved.source, prelude)
+            }
+            Err(e) => {
+                let message = e.to_string();
+                let line = error_line(&message, path)
+                    .or(first_load)
+                    .unwrap_or_default();
+                return Some(Problem {
+                    at: Position {
+                        line: line as u32,
+                        character: 0,
+                    },
+                    message,
+                });
+            }
+        },
+        _ => (doc.text.clone(), 0),
+    };
+
+    let error = compile(&source)?;
+    let at = match error_position(&error.1) {
+        Some((line, column)) if line > prelude_lines => Position {
+            line: (line - prelude_lines - 1) as u32,
+            character: column.saturating_sub(1) as u32,
+        },
+        // In a loaded file: point at the `#load`s
+        Some(_) => Position {
+            line: first_load.unwrap_or_default() as u32,
+            character: 0,
+        },
+        None => Position {
+            line: 0,
+            character: 0,
+        },
+    };
+    Some(Problem {
+        at,
+        message: error.0,
+    })
+}
+
+/// Display and debug text of the first compile error in `source`
+fn compile(source: &str) -> Option<(String, String)> {
+    let tokens = match Lexer::new(source).tokenize() {
+        Ok(tokens) => tokens,
+        Err(e) => return Some((format!("Lexer error: {}", e), format!("{:?}", e))),
+    };
+    let program = match Parser::new(tokens).parse_program() {
+        Ok(program) => program,
+        Err(e) => return Some((format!("Parse error: {}", e), format!("{:?}", e))),
+    };
+    if let Err(e) = Compiler::compile_program(&program) {
+        return Some((format!("Compile error: {}", e), format!("{:?}", e)));
+    }
+    match infer(source) {
+        Some(Err(error)) if !is_unknown_name(&error.1) => Some(error),
+        _ => None,
+    }
+}
+
+/// Inferred type of top-level `name` in `source`
+pub fn type_of(source: &str, name: &str) -> Option<String> {
+    infer(&format!("{}\n{}\n", source.trim_end(), name))?.ok()
+}
+
+/// The type of `source`, or the display and debug text of the type error
+///
+/// `None` for sources the parser can't read as one expression.
+fn infer(source: &str) -> Option<Result<String, (String, String)>> {
+    let tokens = Lexer::new(source).tokenize().ok()?;
+    let expr = Parser::new(tokens).parse().ok()?;
+    let inferred = TypeInference::new()
+        .infer_and_solve(&expr, &TypeEnv::new())
+        .map(|ty| ty.to_string())
+        .map_err(|e| (format!("Type error: {}", e), format!("{:?}", e)));
+    Some(inferred)
+}
+
+/// Whether a type error is only about a name inference wasn't given
+fn is_unknown_name(debug: &str) -> bool {
+    debug.contains("Unbound") || debug.contains("Undefined")
+}
+
+/// 1-based `(line, column)` printed in a frontend error
+fn error_position(debug: &str) -> Option<(usize, usize)> {
+    let line = number_after(debug, "line: ")?;
+    let column = number_after(debug, "column: ").unwrap_or(1);
+    Some((line, column))
+}
+
+/// 0-based line of `path` named in a `file.fsx:N:` load error
+fn error_line(message: &str, path: &std::path::Path) -> Option<usize> {
+    let name = path.file_name()?.to_string_lossy();
+    let line = number_after(message, &format!("{}:", name))?;
+    Some(line.saturating_sub(1))
+}
+
+fn number_after(text: &str, label: &str) -> Option<usize> {
+    let start = text.find(label)? + label.len();
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::path_to_uri;
+
+    #[test]
+    fn test_positions_are_read_from_errors() {
+        assert_eq!(
+            error_position(
+                "UnexpectedToken { pos: Position { line: 11, column: 3, offset: 233 } }"
+            ),
+            Some((11, 3))
+        );
+        assert_eq!(error_position("UndefinedVariable(\"x\")"), None);
+    }
+
+    #[test]
+    fn test_check() {
+        let mut cache = AstCache::new();
+        assert_eq!(
+            check(&Document::new("untitled:1", "let x = 1\nx\n"), &mut cache),
+            None
+        );
+
+        let problem = check(&Document::new("untitled:2", "let x = (1 +\n"), &mut cache);
+        assert!(problem.is_some());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.fsx");
+        let doc = Document::new(path_to_uri(&path), "let x = 1\n#load \"gone.fsx\"\nx\n");
+        let problem = check(&doc, &mut cache).unwrap();
+        assert_eq!(problem.at.line, 1);
+        assert!(problem.message.contains("cannot load \"gone.fsx\""));
+    }
+
+    #[test]
+    fn test_types() {
+        let mut cache = AstCache::new();
+        let problem = check(
+            &Document::new("untitled:3", "let x = 1\nlet y = x + \"a\"\ny\n"),
+            &mut cache,
+        );
+        assert!(problem.is_some());
+
+        // Host functions are unknown to inference, not errors
+        assert_eq!(
+            check(
+                &Document::new("untitled:4", "let c = \"#fff\"\nScarab.setColor \"fg\" c\n"),
+                &mut cache
+            ),
+            None
+        );
+
+        let ty = type_of("let n = 1\nlet s = \"a\"\n", "n").unwrap();
+        assert!(ty.contains("int"), "{}", ty);
+        assert_eq!(type_of("let s = \"a\"\n", "missing"), None);
+    }
+}
@@ -0,0 +1,255 @@
+//! Functions scripts can call without defining them
+//!
+//! fusabi-vm's stdlib and Scarab's stdlib extensions are read from a VM set
+//! up the way the client sets up script VMs, so the names always match what
+//! scripts get. The `Scarab.*` host functions are registered by the client
+//! itself and are listed here with their signatures.
+
+use std::collections::BTreeMap;
+
+use fusabi_vm::{Value, Vm};
+use scarab_plugin_api::fusabi_stdlib::register_stdlib_ext;
+
+/// What a built-in name is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinKind {
+    Function,
+    Module,
+    Value,
+}
+
+/// A name scripts get for free
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Builtin {
+    pub name: String,
+    pub kind: BuiltinKind,
+    /// One-line signature shown in hover and completion
+    pub detail: String,
+    pub doc: Option<&'static str>,
+}
+
+/// `Scarab.*` host functions: name, parameters, description
+const HOST_FUNCTIONS: &[(&str, &str, &str)] = &[
+    (
+        "Scarab.status_add",
+        "side content priority",
+        "Add an item to the status bar. `side` is \"left\" or \"right\".",
+    ),
+    (
+        "Scarab.status_remove",
+        "item_id",
+        "Remove a status bar item.",
+    ),
+    (
+        "Scarab.notify",
+        "title message level",
+        "Show a notification. `level` is \"info\", \"warning\", \"error\" or \"success\".",
+    ),
+    (
+        "Scarab.spawn_overlay",
+        "x y width height content z_index",
+        "Draw a text overlay over the terminal grid.",
+    ),
+    ("Scarab.despawn_overlay", "overlay_id", "Remove an overlay."),
+    (
+        "Scarab.send_input",
+        "data",
+        "Send text to the shell as if typed.",
+    ),
+    (
+        "Scarab.get_terminal_rows",
+        "start_row end_row",
+        "Request the text of terminal rows `start_row` to `end_row`.",
+    ),
+    (
+        "Scarab.log",
+        "level message",
+        "Write to the client log. `level` is \"debug\", \"info\", \"warn\" or \"error\".",
+    ),
+    ("Scarab.version", "()", "The running Scarab version."),
+    (
+        "Scarab.setColor",
+        "name color",
+        "Set a theme colour, such as `\"background\"`, to a hex colour.",
+    ),
+    ("Scarab.setWindowTitle", "title", "Set the window title."),
+    ("Scarab.setFont", "family size", "Set the terminal font."),
+];
+
+/// Globals that are the same functions as `Scarab.*` ones
+const HOST_ALIASES: &[(&str, &str)] = &[
+    ("status_add", "Scarab.status_add"),
+    ("status_remove", "Scarab.status_remove"),
+    ("notify", "Scarab.notify"),
+    ("spawn_overlay", "Scarab.spawn_overlay"),
+    ("despawn_overlay", "Scarab.despawn_overlay"),
+    ("send_input", "Scarab.send_input"),
+    ("get_terminal_rows", "Scarab.get_terminal_rows"),
+    ("log", "Scarab.log"),
+    ("scarab_version", "Scarab.version"),
+    ("set_color", "Scarab.setColor"),
+    ("set_window_title", "Scarab.setWindowTitle"),
+    ("set_font", "Scarab.setFont"),
+];
+
+/// Descriptions of the stdlib extensions, which the VM only knows by arity
+const STDLIB_EXT_DOCS: &[(&str, &str, &str)] = &[
+    (
+        "json_parse",
+        "text",
+        "The parsed value, or `()` if `text` is not JSON.",
+    ),
+    ("json_stringify", "value", "Compact JSON text."),
+    (
+        "regex_match",
+        "pattern text",
+        "Whether `pattern` matches anywhere in `text`.",
+    ),
+    (
+        "regex_replace",
+        "pattern replacement text",
+        "`text` with every match replaced (`$1` names a group).",
+    ),
+    ("time_now", "()", "Seconds since the Unix epoch."),
+    (
+        "time_format",
+        "seconds format",
+        "Local time formatted with strftime `format`.",
+    ),
+    (
+        "time_format_utc",
+        "seconds format",
+        "UTC time formatted with strftime `format`.",
+    ),
+];
+
+/// Keywords offered in completion
+pub const KEYWORDS: &[&str] = &[
+    "let", "rec", "mutable", "in", "fun", "if", "then", "else", "match", "with", "open", "true",
+    "false",
+];
+
+/// Every built-in name, by name
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    builtins: BTreeMap<String, Builtin>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        let mut vm = Vm::new();
+        fusabi_vm::stdlib::register_stdlib(&mut vm);
+        register_stdlib_ext(&mut vm, true);
+
+        let mut catalog = Self::default();
+        for (name, value) in vm.globals.iter() {
+            catalog.add_value(name, value);
+        }
+        for (name, params, doc) in STDLIB_EXT_DOCS {
+            if let Some(builtin) = catalog.builtins.get_mut(*name) {
+                builtin.detail = format!("{} {}", name, params);
+                builtin.doc = Some(*doc);
+            }
+        }
+
+        catalog.add(Builtin {
+            name: "Scarab".to_string(),
+            kind: BuiltinKind::Module,
+            detail: "module Scarab".to_string(),
+            doc: Some("Functions acting on the running terminal."),
+        });
+        for (name, params, doc) in HOST_FUNCTIONS {
+            catalog.add(Builtin {
+                name: name.to_string(),
+                kind: BuiltinKind::Function,
+                detail: format!("{} {}", name, params),
+                doc: Some(*doc),
+            });
+        }
+        for (alias, name) in HOST_ALIASES {
+            if let Some(target) = catalog.builtins.get(*name).cloned() {
+                catalog.add(Builtin {
+                    name: alias.to_string(),
+                    detail: target.detail.replacen(*name, alias, 1),
+                    ..target
+                });
+            }
+        }
+        catalog
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Builtin> {
+        self.builtins.get(name)
+    }
+
+    /// Names usable without a module prefix
+    pub fn globals(&self) -> impl Iterator<Item = &Builtin> {
+        self.builtins.values().filter(|b| !b.name.contains('.'))
+    }
+
+    /// Members of `module`, such as `Scarab`
+    pub fn members<'a>(&'a self, module: &'a str) -> impl Iterator<Item = &'a Builtin> {
+        self.builtins.values().filter(move |b| {
+            b.name
+                .strip_prefix(module)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .is_some_and(|member| !member.contains('.'))
+        })
+    }
+
+    fn add(&mut self, builtin: Builtin) {
+        self.builtins.insert(builtin.name.clone(), builtin);
+    }
+
+    fn add_value(&mut self, name: &str, value: &Value) {
+        let (kind, detail) = match value {
+            Value::NativeFn { arity, .. } => (
+                BuiltinKind::Function,
+                format!(
+                    "{}: native function of {} argument{}",
+                    name,
+                    arity,
+                    if *arity == 1 { "" } else { "s" }
+                ),
+            ),
+            Value::Record(fields) | Value::Map(fields) => {
+                for (field, value) in fields.lock().unwrap().iter() {
+                    self.add_value(&format!("{}.{}", name, field), value);
+                }
+                (BuiltinKind::Module, format!("module {}", name))
+            }
+            _ => (BuiltinKind::Value, name.to_string()),
+        };
+        self.add(Builtin {
+            name: name.to_string(),
+            kind,
+            detail,
+            doc: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog() {
+        let catalog = Catalog::new();
+        let set_color = catalog.get("Scarab.setColor").unwrap();
+        assert_eq!(set_color.detail, "Scarab.setColor name color");
+        assert_eq!(
+            catalog.get("set_color").unwrap().detail,
+            "set_color name color"
+        );
+        assert_eq!(
+            catalog.get("json_parse").unwrap().kind,
+            BuiltinKind::Function
+        );
+
+        let members: Vec<&str> = catalog.members("Scarab").map(|b| b.name.as_str()).collect();
+        assert_eq!(members.len(), HOST_FUNCTIONS.len());
+        assert!(catalog.globals().any(|b| b.name == "Scarab"));
+        assert!(catalog.globals().all(|b| !b.name.contains('.')));
+    }
+}
@@ -0,0 +1,202 @@
+//! Open documents and positions in them
+//!
+//! LSP positions count UTF-16 code units from the start of a line; the
+//! helpers here convert to and from byte offsets into the line.
+
+use std::path::{Path, PathBuf};
+
+use scarab_plugin_api::DefinitionMap;
+
+/// Zero-based line and UTF-16 column, as LSP positions are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+/// A top-level `let` and where its name is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub name: String,
+    pub at: Position,
+    /// The `let` line, for hover
+    pub signature: String,
+    /// `//` comment lines right above the `let`
+    pub doc: Option<String>,
+}
+
+/// A text document the client has open
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub uri: String,
+    /// File behind `uri`, for resolving `#load`s
+    pub path: Option<PathBuf>,
+    pub text: String,
+}
+
+impl Document {
+    pub fn new(uri: impl Into<String>, text: impl Into<String>) -> Self {
+        let uri = uri.into();
+        Self {
+            path: uri_to_path(&uri),
+            uri,
+            text: text.into(),
+        }
+    }
+
+    pub fn line(&self, line: u32) -> Option<&str> {
+        self.text.lines().nth(line as usize)
+    }
+
+    /// The dotted name under `at`, such as `Scarab.setColor`
+    pub fn word_at(&self, at: Position) -> Option<String> {
+        let line = self.line(at.line)?;
+        let cursor = utf16_to_byte(line, at.character);
+        let start = line[..cursor]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_name_char(*c))
+            .last()
+            .map_or(cursor, |(index, _)| index);
+        let end = line[cursor..]
+            .char_indices()
+            .find(|(_, c)| !is_name_char(*c))
+            .map_or(line.len(), |(index, _)| cursor + index);
+        let word = line[start..end].trim_matches('.');
+        (!word.is_empty()).then(|| word.to_string())
+    }
+
+    /// What has been typed of the name ending at `at`, for completion
+    pub fn prefix_at(&self, at: Position) -> String {
+        let Some(line) = self.line(at.line) else {
+            return String::new();
+        };
+        let cursor = utf16_to_byte(line, at.character);
+        let typed: String = line[..cursor]
+            .chars()
+            .rev()
+            .take_while(|c| is_name_char(*c))
+            .collect();
+        typed.chars().rev().collect()
+    }
+
+    pub fn definitions(&self) -> Vec<Definition> {
+        definitions(&self.text)
+    }
+}
+
+/// The top-level `let`s of `text`, in name order
+pub fn definitions(text: &str) -> Vec<Definition> {
+    let lines: Vec<&str> = text.lines().collect();
+    DefinitionMap::from_source("", text)
+        .definitions
+        .into_iter()
+        .filter_map(|(name, location)| {
+            let index = location.line as usize - 1;
+            let line = lines.get(index)?;
+            let comments: Vec<&str> = lines[..index]
+                .iter()
+                .rev()
+                .map_while(|line| line.trim().strip_prefix("//").map(str::trim))
+                .collect();
+            let doc = (!comments.is_empty())
+                .then(|| comments.into_iter().rev().collect::<Vec<_>>().join("\n"));
+            Some(Definition {
+                at: Position {
+                    line: index as u32,
+                    character: byte_to_utf16(line, location.column as usize - 1),
+                },
+                signature: line.trim_end().trim_end_matches('=').trim_end().to_string(),
+                doc,
+                name,
+            })
+        })
+        .collect()
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\'' || c == '.'
+}
+
+/// Byte offset of UTF-16 column `character`, clamped to the line
+pub fn utf16_to_byte(line: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= character as usize {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
+
+pub fn byte_to_utf16(line: &str, byte: usize) -> u32 {
+    line[..byte.min(line.len())]
+        .chars()
+        .map(|c| c.len_utf16() as u32)
+        .sum()
+}
+
+/// Local path of a `file://` URI
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(rest.len());
+    let mut chars = rest.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'%' {
+            let hex = [chars.next()?, chars.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for b in path.to_string_lossy().bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_and_definitions() {
+        let doc = Document::new(
+            "file:///home/me/My%20Config/config.fsx",
+            "// Accent colour\n// for tabs\nlet accent = \"#ff79c6\"\nlet é = Scarab.setColor \"a\" accent\n",
+        );
+        assert_eq!(
+            doc.path,
+            Some(PathBuf::from("/home/me/My Config/config.fsx"))
+        );
+        assert_eq!(
+            path_to_uri(doc.path.as_deref().unwrap()),
+            "file:///home/me/My%20Config/config.fsx"
+        );
+
+        let at = |line, character| Position { line, character };
+        assert_eq!(doc.word_at(at(3, 12)).as_deref(), Some("Scarab.setColor"));
+        assert_eq!(doc.word_at(at(2, 4)).as_deref(), Some("accent"));
+        assert_eq!(doc.word_at(at(2, 11)), None);
+        assert_eq!(doc.prefix_at(at(3, 15)), "Scarab.");
+
+        let defs = doc.definitions();
+        assert_eq!(defs[0].name, "accent");
+        assert_eq!(defs[0].at, at(2, 4));
+        assert_eq!(defs[0].signature, "let accent = \"#ff79c6\"");
+        assert_eq!(defs[0].doc.as_deref(), Some("Accent colour\nfor tabs"));
+        assert_eq!(defs[1].name, "é");
+        assert_eq!(defs[1].doc, None);
+    }
+}
@@ -0,0 +1,39 @@
+//! `fusabi-lsp`: a language server for Fusabi `.fsx` files
+//!
+//! Editors start it and talk to it over stdin and stdout. For `config.fsx`,
+//! client scripts and plugins it offers:
+//!
+//! - diagnostics for lexer, parser and compiler errors, `#load`s included
+//! - hover with the signature and doc comment of builtins and `let`s
+//! - go-to-definition for `let`s in the file and in files it `#load`s
+//! - completion for keywords, fusabi-vm's stdlib, Scarab's stdlib
+//!   extensions and `Scarab.*` host functions, and the file's own `let`s
+
+use std::io;
+
+mod analysis;
+mod catalog;
+mod document;
+mod server;
+mod transport;
+
+use server::Server;
+use transport::{read_message, write_message};
+
+fn main() -> anyhow::Result<()> {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+
+    let mut server = Server::new();
+    while let Some(message) = read_message(&mut reader)? {
+        for reply in server.handle(&message) {
+            write_message(&mut writer, &reply)?;
+        }
+        if let Some(code) = server.exit_code() {
+            std::process::exit(code);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,491 @@
+//! Request handling
+//!
+//! Documents are synced whole (`TextDocumentSyncKind::Full`) and checked on
+//! every change. Saving any file checks every open document again, since
+//! it may be one they `#load`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use scarab_plugin_api::fusabi_modules::AstCache;
+use serde_json::{json, Value};
+
+use crate::analysis::{check, type_of};
+use crate::catalog::{Builtin, BuiltinKind, Catalog, KEYWORDS};
+use crate::document::{definitions, path_to_uri, uri_to_path, Definition, Document, Position};
+
+/// JSON-RPC error code for unknown methods
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// LSP `CompletionItemKind`s
+const KIND_FUNCTION: u32 = 3;
+const KIND_VARIABLE: u32 = 6;
+const KIND_MODULE: u32 = 9;
+const KIND_KEYWORD: u32 = 14;
+
+/// A definition and the file it is in
+struct Found {
+    uri: String,
+    definition: Definition,
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    catalog: Catalog,
+    cache: AstCache,
+    shutting_down: bool,
+    exit_code: Option<i32>,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self {
+            catalog: Catalog::new(),
+            ..Self::default()
+        }
+    }
+
+    /// Process code once `exit` has been received
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Handle one message, returning the messages to send back
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                },
+                "serverInfo": {
+                    "name": "fusabi-lsp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => {
+                self.shutting_down = true;
+                Some(Value::Null)
+            }
+            "exit" => {
+                self.exit_code = Some(if self.shutting_down { 0 } else { 1 });
+                None
+            }
+            "textDocument/didOpen" => {
+                let uri = text(&params["textDocument"]["uri"]);
+                let doc = Document::new(uri.clone(), text(&params["textDocument"]["text"]));
+                self.documents.insert(uri.clone(), doc);
+                return self.diagnostics(&uri).into_iter().collect();
+            }
+            "textDocument/didChange" => {
+                let uri = text(&params["textDocument"]["uri"]);
+                let changes = params["contentChanges"].as_array();
+                if let Some(change) = changes.and_then(|changes| changes.last()) {
+                    let doc = Document::new(uri.clone(), text(&change["text"]));
+                    self.documents.insert(uri.clone(), doc);
+                }
+                return self.diagnostics(&uri).into_iter().collect();
+            }
+            "textDocument/didSave" => {
+                let mut uris: Vec<String> = self.documents.keys().cloned().collect();
+                uris.sort();
+                return uris
+                    .iter()
+                    .filter_map(|uri| self.diagnostics(uri))
+                    .collect();
+            }
+            "textDocument/didClose" => {
+                let uri = text(&params["textDocument"]["uri"]);
+                self.documents.remove(&uri);
+                return vec![publish(&uri, Vec::new())];
+            }
+            "textDocument/hover" => Some(self.hover(params)),
+            "textDocument/definition" => Some(self.definition(params)),
+            "textDocument/completion" => Some(self.completion(params)),
+            _ if id.is_some() => {
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": METHOD_NOT_FOUND,
+                        "message": format!("Unknown method: {}", method),
+                    },
+                })];
+            }
+            // Other notifications need nothing from us
+            _ => None,
+        };
+
+        match (id, result) {
+            (Some(id), Some(result)) => {
+                vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// `publishDiagnostics` for an open document
+    fn diagnostics(&mut self, uri: &str) -> Option<Value> {
+        let doc = self.documents.get(uri)?;
+        let problems = check(doc, &mut self.cache)
+            .map(|problem| {
+                let line = doc.line(problem.at.line).unwrap_or_default();
+                let end = Position {
+                    line: problem.at.line,
+                    character: crate::document::byte_to_utf16(line, line.len())
+                        .max(problem.at.character),
+                };
+                json!({
+                    "range": range(problem.at, end),
+                    "severity": 1,
+                    "source": "fusabi",
+                    "message": problem.message,
+                })
+            })
+            .into_iter()
+            .collect();
+        Some(publish(uri, problems))
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((doc, at)) = self.locate(params) else {
+            return Value::Null;
+        };
+        let Some(word) = doc.word_at(at) else {
+            return Value::Null;
+        };
+
+        let contents = if let Some(builtin) = self.catalog.get(&word) {
+            markdown(&builtin.detail, builtin.doc)
+        } else if let Some(found) = self.find_definition(doc, &word) {
+            let signature = match self.type_of(doc, &found, &word) {
+                Some(ty) => format!("val {} : {}", found.definition.name, ty),
+                None => found.definition.signature.clone(),
+            };
+            markdown(&signature, found.definition.doc.as_deref())
+        } else {
+            return Value::Null;
+        };
+        json!({ "contents": { "kind": "markdown", "value": contents } })
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let found = self
+            .locate(params)
+            .and_then(|(doc, at)| Some((doc, doc.word_at(at)?)))
+            .and_then(|(doc, word)| self.find_definition(doc, &word));
+        let Some(found) = found else {
+            return Value::Null;
+        };
+        let at = found.definition.at;
+        let end = Position {
+            line: at.line,
+            character: at.character + found.definition.name.encode_utf16().count() as u32,
+        };
+        json!({ "uri": found.uri, "range": range(at, end) })
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let Some((doc, at)) = self.locate(params) else {
+            return json!([]);
+        };
+        let prefix = doc.prefix_at(at);
+
+        let mut items = Vec::new();
+        if let Some((module, _)) = prefix.rsplit_once('.') {
+            for builtin in self.catalog.members(module) {
+                let member = &builtin.name[module.len() + 1..];
+                items.push(builtin_item(member, builtin));
+            }
+            for (uri, definition) in self.loaded_definitions(doc) {
+                if module_name_of(&uri) == module {
+                    items.push(definition_item(&definition));
+                }
+            }
+        } else {
+            items.extend(
+                KEYWORDS
+                    .iter()
+                    .map(|keyword| json!({ "label": keyword, "kind": KIND_KEYWORD })),
+            );
+            items.extend(
+                self.catalog
+                    .globals()
+                    .map(|builtin| builtin_item(&builtin.name, builtin)),
+            );
+            for definition in doc.definitions() {
+                items.push(definition_item(&definition));
+            }
+            for (uri, definition) in self.loaded_definitions(doc) {
+                items.push(definition_item(&definition));
+                let module = module_name_of(&uri);
+                if !module.is_empty() && !items.iter().any(|item| item["label"] == module) {
+                    items.push(json!({ "label": module, "kind": KIND_MODULE }));
+                }
+            }
+        }
+        Value::Array(items)
+    }
+
+    /// The open document and position named by request `params`
+    fn locate(&self, params: &Value) -> Option<(&Document, Position)> {
+        let doc = self
+            .documents
+            .get(params["textDocument"]["uri"].as_str()?)?;
+        let at = Position {
+            line: params["position"]["line"].as_u64()? as u32,
+            character: params["position"]["character"].as_u64()? as u32,
+        };
+        Some((doc, at))
+    }
+
+    /// Where `word` is defined: in `doc`, or in a file it loads
+    ///
+    /// `Module.name` is looked up as `name` in the loaded file for `Module`.
+    fn find_definition(&self, doc: &Document, word: &str) -> Option<Found> {
+        if let Some(definition) = doc.definitions().into_iter().find(|d| d.name == word) {
+            return Some(Found {
+                uri: doc.uri.clone(),
+                definition,
+            });
+        }
+
+        let (module, name) = match word.rsplit_once('.') {
+            Some((module, name)) => (Some(module), name),
+            None => (None, word),
+        };
+        self.loaded_definitions(doc)
+            .into_iter()
+            .find(|(uri, definition)| {
+                definition.name == name && module.map_or(true, |m| module_name_of(uri) == m)
+            })
+            .map(|(uri, definition)| Found { uri, definition })
+    }
+
+    /// Inferred type of the definition `word` names
+    ///
+    /// Tried against the whole document first, then the definition's own
+    /// `let` alone, for documents that call functions inference doesn't
+    /// know.
+    fn type_of(&self, doc: &Document, found: &Found, word: &str) -> Option<String> {
+        let source = match &doc.path {
+            Some(path) if doc.text.contains("#load") => AstCache::new()
+                .resolve_text(path, &doc.text)
+                .map(|resolved| resolved.source)
+                .unwrap_or_else(|_| doc.text.clone()),
+            _ => doc.text.clone(),
+        };
+        if let Some(ty) = type_of(&source, word) {
+            return Some(ty);
+        }
+
+        let text = match self.documents.get(&found.uri) {
+            Some(open) => open.text.clone(),
+            None => std::fs::read_to_string(uri_to_path(&found.uri)?).ok()?,
+        };
+        let block = let_block(&text, found.definition.at.line as usize);
+        type_of(&block, &found.definition.name)
+    }
+
+    /// Top-level definitions of the files `doc` loads, with their URIs
+    fn loaded_definitions(&self, doc: &Document) -> Vec<(String, Definition)> {
+        let Some(path) = &doc.path else {
+            return Vec::new();
+        };
+        if !doc.text.contains("#load") {
+            return Vec::new();
+        }
+        let Ok(resolved) = AstCache::new().resolve_text(path, &doc.text) else {
+            return Vec::new();
+        };
+
+        let mut found = Vec::new();
+        for dep in resolved.dependencies {
+            let uri = path_to_uri(&dep);
+            // An open buffer is newer than the file on disk
+            let text = match self.documents.get(&uri) {
+                Some(open) => open.text.clone(),
+                None => std::fs::read_to_string(&dep).unwrap_or_default(),
+            };
+            found.extend(
+                definitions(&text)
+                    .into_iter()
+                    .map(|definition| (uri.clone(), definition)),
+            );
+        }
+        found
+    }
+}
+
+/// The `let` starting at `line` and the indented lines under it
+fn let_block(text: &str, line: usize) -> String {
+    let mut lines = text.lines().skip(line);
+    let mut block: Vec<&str> = lines.next().into_iter().collect();
+    block.extend(lines.take_while(|l| l.trim().is_empty() || l.starts_with([' ', '\t'])));
+    block.join("\n")
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn range(start: Position, end: Position) -> Value {
+    json!({
+        "start": { "line": start.line, "character": start.character },
+        "end": { "line": end.line, "character": end.character },
+    })
+}
+
+fn publish(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn markdown(signature: &str, doc: Option<&str>) -> String {
+    let mut value = format!("```fsharp\n{}\n```", signature);
+    if let Some(doc) = doc {
+        value.push_str("\n\n");
+        value.push_str(doc);
+    }
+    value
+}
+
+fn builtin_item(label: &str, builtin: &Builtin) -> Value {
+    let kind = match builtin.kind {
+        BuiltinKind::Function => KIND_FUNCTION,
+        BuiltinKind::Module => KIND_MODULE,
+        BuiltinKind::Value => KIND_VARIABLE,
+    };
+    json!({
+        "label": label,
+        "kind": kind,
+        "detail": builtin.detail,
+        "documentation": builtin.doc,
+    })
+}
+
+fn definition_item(definition: &Definition) -> Value {
+    json!({
+        "label": definition.name,
+        "kind": KIND_VARIABLE,
+        "detail": definition.signature,
+        "documentation": definition.doc,
+    })
+}
+
+/// Record name `#load` gives a file: `lib/my-theme.fsx` is `MyTheme`
+fn module_name_of(uri: &str) -> String {
+    let path = crate::document::uri_to_path(uri).unwrap_or_else(|| PathBuf::from(uri));
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    stem.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn position(uri: &str, line: u32, character: u32) -> Value {
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+        })
+    }
+
+    #[test]
+    fn test_session() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("lib")).unwrap();
+        fs::write(
+            dir.path().join("lib/my-theme.fsx"),
+            "// Tab accent\nlet accent = \"#ff79c6\"\n",
+        )
+        .unwrap();
+        let config = dir.path().join("config.fsx");
+        let uri = path_to_uri(&config);
+        let text = "#load \"lib/my-theme.fsx\"\nlet bg = MyTheme.accent\nScarab.setColor \"background\" bg\n";
+
+        let mut server = Server::new();
+        let reply = server.handle(&request(1, "initialize", json!({})));
+        assert_eq!(reply[0]["result"]["capabilities"]["hoverProvider"], true);
+
+        let sent = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": uri, "text": text } },
+        }));
+        assert_eq!(sent[0]["method"], "textDocument/publishDiagnostics");
+
+        // A builtin
+        let hover = server.handle(&request(2, "textDocument/hover", position(&uri, 2, 10)));
+        let contents = hover[0]["result"]["contents"]["value"].as_str().unwrap();
+        assert!(
+            contents.contains("Scarab.setColor name color"),
+            "{}",
+            contents
+        );
+
+        // A definition in a loaded file, by module
+        let hover = server.handle(&request(3, "textDocument/hover", position(&uri, 1, 20)));
+        let contents = hover[0]["result"]["contents"]["value"].as_str().unwrap();
+        assert!(contents.contains("val accent :"), "{}", contents);
+        assert!(contents.contains("Tab accent"), "{}", contents);
+
+        let found = server.handle(&request(
+            4,
+            "textDocument/definition",
+            position(&uri, 2, 31),
+        ));
+        assert_eq!(found[0]["result"]["uri"], uri.as_str());
+        assert_eq!(found[0]["result"]["range"]["start"]["line"], 1);
+        let found = server.handle(&request(
+            5,
+            "textDocument/definition",
+            position(&uri, 1, 20),
+        ));
+        assert!(found[0]["result"]["uri"]
+            .as_str()
+            .unwrap()
+            .ends_with("lib/my-theme.fsx"));
+
+        let items = server.handle(&request(6, "textDocument/completion", position(&uri, 2, 7)));
+        let labels: Vec<&str> = items[0]["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|item| item["label"].as_str())
+            .collect();
+        assert!(labels.contains(&"setColor"));
+        assert!(!labels.contains(&"bg"));
+
+        let reply = server.handle(&request(7, "fusabi/unknown", json!({})));
+        assert_eq!(reply[0]["error"]["code"], METHOD_NOT_FOUND);
+
+        server.handle(&request(8, "shutdown", Value::Null));
+        server.handle(&json!({ "jsonrpc": "2.0", "method": "exit" }));
+        assert_eq!(server.exit_code(), Some(0));
+    }
+}
@@ -293,7 +293,10 @@ fn execute_menu_action(
         "new_tab" => {
             // Create new terminal tab via IPC
             if let Some(ipc) = ipc {
-                ipc.0.send(ControlMessage::TabCreate { title: None });
+                ipc.0.send(ControlMessage::TabCreate {
+                    title: None,
+                    domain: None,
+                });
                 log::info!("Sent TabCreate command to daemon");
             } else {
                 log::error!("Cannot create new tab: IPC not available");
@@ -306,6 +309,7 @@ fn execute_menu_action(
                 ipc.0.send(ControlMessage::PaneSplit {
                     pane_id: 0,
                     direction: scarab_protocol::SplitDirection::Horizontal,
+                    domain: None,
                 });
                 log::info!("Sent PaneSplit horizontal command to daemon");
             } else {
@@ -319,6 +323,7 @@ fn execute_menu_action(
                 ipc.0.send(ControlMessage::PaneSplit {
                     pane_id: 0,
                     direction: scarab_protocol::SplitDirection::Vertical,
+                    domain: None,
                 });
                 log::info!("Sent PaneSplit vertical command to daemon");
             } else {
@@ -1,6 +1,8 @@
 use async_trait::async_trait;
 use scarab_plugin_api::{
-    types::RemoteCommand, Action, Plugin, PluginContext, PluginMetadata, Result,
+    key_tables::{KeyCode, KeyCombo, KeyModifiers},
+    types::RemoteCommand,
+    Plugin, PluginContext, PluginKeyBinding, PluginMetadata, Result,
 };
 use std::sync::Mutex;
 
@@ -36,10 +38,19 @@ impl Plugin for PalettePlugin {
         &self.metadata
     }
 
-    async fn on_input(&mut self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
-        // Trigger: Ctrl+P (0x10)
-        if input == [0x10] {
-            // Ctrl+P
+    /// Ctrl+Alt+P opens the palette
+    ///
+    /// Ctrl+P is readline's previous-history, and Ctrl+Shift+P is the
+    /// client's own palette.
+    fn get_keybindings(&self) -> Vec<PluginKeyBinding> {
+        vec![PluginKeyBinding::new(
+            KeyCombo::new(KeyCode::KeyP, KeyModifiers::CTRL | KeyModifiers::ALT),
+            "palette.open",
+        )]
+    }
+
+    async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
+        if id == "palette.open" {
             log::info!("Opening Command Palette");
 
             // Get aggregated commands from shared state
@@ -50,10 +61,8 @@ impl Plugin for PalettePlugin {
                 title: "Command Palette".to_string(),
                 items,
             });
-
-            return Ok(Action::Modify(Vec::new())); // Consume key
         }
 
-        Ok(Action::Continue)
+        Ok(())
     }
 }
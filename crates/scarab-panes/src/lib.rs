@@ -2,10 +2,17 @@
 //!
 //! Provides split pane management with separate PTY sessions per pane.
 //! Works in conjunction with scarab-tabs for full workspace management.
+//!
+//! Pane actions are commands; their default keys are declared with
+//! `get_keybindings` and can be moved under `[keybindings.plugins]`.
 
 use async_trait::async_trait;
 use parking_lot::Mutex;
-use scarab_plugin_api::{types::ModalItem, Action, Plugin, PluginContext, PluginMetadata, Result};
+use scarab_plugin_api::{
+    key_tables::{KeyCode, KeyCombo, KeyModifiers},
+    types::ModalItem,
+    Plugin, PluginContext, PluginKeyBinding, PluginMetadata, Result,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -622,38 +629,6 @@ impl PanesPlugin {
             state: Arc::new(Mutex::new(PluginState::new(cols, rows))),
         }
     }
-
-    fn handle_keybinding(&self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
-        let mut state = self.state.lock();
-
-        // Ctrl+Shift+| (vertical split) - would be custom sequence
-        // For now, we'll use simpler bindings for testing
-
-        // Ctrl+Shift+- (horizontal split) - ASCII 0x1F
-        if input == [0x1f] {
-            let active_id = state.active_pane_id;
-            if let Some(new_id) = state.split_pane(active_id, SplitDirection::Horizontal) {
-                log::info!("Split pane horizontally, created pane {}", new_id);
-                ctx.notify_success(
-                    "Split Pane",
-                    &format!("Created pane {} (horizontal)", new_id),
-                );
-                return Ok(Action::Modify(Vec::new()));
-            }
-        }
-
-        // Ctrl+Shift+\ (vertical split) - ASCII 0x1C
-        if input == [0x1c] {
-            let active_id = state.active_pane_id;
-            if let Some(new_id) = state.split_pane(active_id, SplitDirection::Vertical) {
-                log::info!("Split pane vertically, created pane {}", new_id);
-                ctx.notify_success("Split Pane", &format!("Created pane {} (vertical)", new_id));
-                return Ok(Action::Modify(Vec::new()));
-            }
-        }
-
-        Ok(Action::Continue)
-    }
 }
 
 impl Default for PanesPlugin {
@@ -673,17 +648,17 @@ impl Plugin for PanesPlugin {
             ModalItem {
                 id: "panes.split_horizontal".to_string(),
                 label: "Split Pane Horizontally".to_string(),
-                description: Some("Split current pane horizontally (Ctrl+Shift+-)".to_string()),
+                description: Some("Split current pane horizontally (Ctrl+Shift+E)".to_string()),
             },
             ModalItem {
                 id: "panes.split_vertical".to_string(),
                 label: "Split Pane Vertically".to_string(),
-                description: Some("Split current pane vertically (Ctrl+Shift+|)".to_string()),
+                description: Some("Split current pane vertically (Ctrl+Shift+O)".to_string()),
             },
             ModalItem {
                 id: "panes.close".to_string(),
                 label: "Close Pane".to_string(),
-                description: Some("Close current pane (Ctrl+Shift+W)".to_string()),
+                description: Some("Close current pane".to_string()),
             },
             ModalItem {
                 id: "panes.navigate_up".to_string(),
@@ -733,8 +708,37 @@ impl Plugin for PanesPlugin {
         ]
     }
 
-    async fn on_input(&mut self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
-        self.handle_keybinding(input, ctx)
+    /// Ctrl+Shift+E splits horizontally, Ctrl+Shift+O vertically, and
+    /// Ctrl+Shift+arrows move focus
+    ///
+    /// None of these is a key a shell uses: the client sends Ctrl+Shift
+    /// letters as `CSI u` and arrows as `CSI 1;6 X`. `panes.close` has no
+    /// default key, since Ctrl+Shift+W is the client's.
+    fn get_keybindings(&self) -> Vec<PluginKeyBinding> {
+        let ctrl_shift = KeyModifiers::CTRL | KeyModifiers::SHIFT;
+        vec![
+            PluginKeyBinding::new(
+                KeyCombo::new(KeyCode::KeyE, ctrl_shift),
+                "panes.split_horizontal",
+            ),
+            PluginKeyBinding::new(
+                KeyCombo::new(KeyCode::KeyO, ctrl_shift),
+                "panes.split_vertical",
+            ),
+            PluginKeyBinding::new(KeyCombo::new(KeyCode::Up, ctrl_shift), "panes.navigate_up"),
+            PluginKeyBinding::new(
+                KeyCombo::new(KeyCode::Down, ctrl_shift),
+                "panes.navigate_down",
+            ),
+            PluginKeyBinding::new(
+                KeyCombo::new(KeyCode::Left, ctrl_shift),
+                "panes.navigate_left",
+            ),
+            PluginKeyBinding::new(
+                KeyCombo::new(KeyCode::Right, ctrl_shift),
+                "panes.navigate_right",
+            ),
+        ]
     }
 
     async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
@@ -253,68 +253,43 @@ async fn test_resize_after_terminal_resize() {
     assert!(result.is_ok());
 }
 
-#[tokio::test]
-async fn test_on_input_horizontal_split() {
-    use scarab_plugin_api::Action;
-
-    let mut plugin = PanesPlugin::with_size(80, 40);
-    let ctx = create_test_context();
-
-    // Ctrl+Shift+- (ASCII 0x1F) for horizontal split
-    let input = &[0x1f];
-    let result = plugin.on_input(input, &ctx).await;
-
-    assert!(result.is_ok());
-    match result.unwrap() {
-        Action::Modify(_) => {
-            // Success - split was created
-        }
-        Action::Continue => {
-            panic!("Expected Modify action for split");
-        }
-        _ => panic!("Unexpected action"),
-    }
-}
-
-#[tokio::test]
-async fn test_on_input_vertical_split() {
-    use scarab_plugin_api::Action;
-
-    let mut plugin = PanesPlugin::with_size(80, 40);
-    let ctx = create_test_context();
+#[test]
+fn test_split_keys_are_bound_to_commands() {
+    use scarab_plugin_api::key_tables::{KeyCode, KeyCombo, KeyModifiers};
+
+    let plugin = PanesPlugin::with_size(80, 40);
+    let bindings = plugin.get_keybindings();
+
+    let split = bindings
+        .iter()
+        .find(|binding| {
+            binding.key == KeyCombo::new(KeyCode::KeyE, KeyModifiers::CTRL | KeyModifiers::SHIFT)
+        })
+        .unwrap();
+    assert_eq!(split.command, "panes.split_horizontal");
 
-    // Ctrl+Shift+\ (ASCII 0x1C) for vertical split
-    let input = &[0x1c];
-    let result = plugin.on_input(input, &ctx).await;
+    // Readline's undo (Ctrl+/, 0x1F) is left to the shell
+    assert!(bindings
+        .iter()
+        .all(|binding| binding.key != KeyCombo::ctrl(KeyCode::Slash)));
 
-    assert!(result.is_ok());
-    match result.unwrap() {
-        Action::Modify(_) => {
-            // Success - split was created
-        }
-        Action::Continue => {
-            panic!("Expected Modify action for split");
-        }
-        _ => panic!("Unexpected action"),
+    // Every bound key runs a palette command
+    let commands = plugin.get_commands();
+    for binding in &bindings {
+        assert!(commands.iter().any(|command| command.id == binding.command));
     }
 }
 
 #[tokio::test]
-async fn test_on_input_unrecognized() {
+async fn test_on_input_passes_keys_through() {
     use scarab_plugin_api::Action;
 
     let mut plugin = PanesPlugin::with_size(80, 40);
     let ctx = create_test_context();
 
-    // Some random input
-    let input = b"hello";
-    let result = plugin.on_input(input, &ctx).await;
-
-    assert!(result.is_ok());
-    match result.unwrap() {
-        Action::Continue => {
-            // Expected - unrecognized input should continue
-        }
-        _ => panic!("Expected Continue action for unrecognized input"),
+    // Splits come from bound keys, not raw bytes
+    for input in [&[0x1f][..], b"hello"] {
+        let result = plugin.on_input(input, &ctx).await;
+        assert!(matches!(result, Ok(Action::Continue)));
     }
 }
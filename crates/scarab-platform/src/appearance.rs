@@ -0,0 +1,170 @@
+//! Whether the desktop is in light or dark mode
+//!
+//! Each platform is asked through the tool it ships with, so no extra
+//! libraries are needed:
+//!
+//! - Linux: the XDG desktop portal's `color-scheme` setting over `gdbus`,
+//!   then GNOME's `color-scheme` through `gsettings`
+//! - macOS: `AppleInterfaceStyle` through `defaults`
+//! - Windows: `AppsUseLightTheme` through `reg`
+//!
+//! There is no change notification; callers poll [`system_appearance`].
+
+use std::process::Command;
+
+/// Light or dark mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+impl Appearance {
+    pub fn is_dark(self) -> bool {
+        self == Appearance::Dark
+    }
+}
+
+/// The desktop's current appearance, or `None` if it has no preference or
+/// can't be asked
+pub fn system_appearance() -> Option<Appearance> {
+    query()
+}
+
+#[cfg(target_os = "linux")]
+fn query() -> Option<Appearance> {
+    let portal = output(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.portal.Desktop",
+            "--object-path",
+            "/org/freedesktop/portal/desktop",
+            "--method",
+            "org.freedesktop.portal.Settings.Read",
+            "org.freedesktop.appearance",
+            "color-scheme",
+        ],
+    );
+    portal.as_deref().and_then(parse_portal).or_else(|| {
+        output(
+            "gsettings",
+            &["get", "org.gnome.desktop.interface", "color-scheme"],
+        )
+        .as_deref()
+        .and_then(parse_gsettings)
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn query() -> Option<Appearance> {
+    // The key only exists in dark mode; `defaults` fails without it
+    let result = Command::new("defaults")
+        .args(["read", "-g", "AppleInterfaceStyle"])
+        .output()
+        .ok()?;
+    let style = String::from_utf8_lossy(&result.stdout);
+    Some(if style.trim().eq_ignore_ascii_case("dark") {
+        Appearance::Dark
+    } else {
+        Appearance::Light
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn query() -> Option<Appearance> {
+    output(
+        "reg",
+        &[
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+            "/v",
+            "AppsUseLightTheme",
+        ],
+    )
+    .as_deref()
+    .and_then(parse_reg_query)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn query() -> Option<Appearance> {
+    None
+}
+
+/// Standard output of a command that succeeded
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let result = Command::new(program).args(args).output().ok()?;
+    if !result.status.success() {
+        return None;
+    }
+    String::from_utf8(result.stdout).ok()
+}
+
+/// Reply to the portal's `Settings.Read`, such as `(<<uint32 1>>,)`
+///
+/// 1 prefers dark and 2 light; 0 is no preference.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_portal(reply: &str) -> Option<Appearance> {
+    let value = reply.split("uint32").nth(1)?;
+    let digits: String = value
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    match digits.parse::<u32>().ok()? {
+        1 => Some(Appearance::Dark),
+        2 => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+/// GNOME's `color-scheme`: `'prefer-dark'`, `'prefer-light'` or `'default'`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_gsettings(value: &str) -> Option<Appearance> {
+    match value.trim().trim_matches('\'') {
+        "prefer-dark" => Some(Appearance::Dark),
+        "prefer-light" | "default" => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+/// `reg query` output ending in a line like
+/// `AppsUseLightTheme    REG_DWORD    0x0`
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_reg_query(output: &str) -> Option<Appearance> {
+    let line = output
+        .lines()
+        .find(|line| line.trim_start().starts_with("AppsUseLightTheme"))?;
+    match line.split_whitespace().last()? {
+        "0x0" => Some(Appearance::Dark),
+        "0x1" => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_linux_replies() {
+        assert_eq!(parse_portal("(<<uint32 1>>,)\n"), Some(Appearance::Dark));
+        assert_eq!(parse_portal("(<uint32 2>,)"), Some(Appearance::Light));
+        assert_eq!(parse_portal("(<<uint32 0>>,)"), None);
+        assert_eq!(parse_gsettings("'prefer-dark'\n"), Some(Appearance::Dark));
+        assert_eq!(parse_gsettings("'default'"), Some(Appearance::Light));
+    }
+
+    #[test]
+    fn test_parse_reg_query() {
+        let output = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize\r\n    AppsUseLightTheme    REG_DWORD    0x0\r\n\r\n";
+        assert_eq!(parse_reg_query(output), Some(Appearance::Dark));
+        assert_eq!(
+            parse_reg_query("AppsUseLightTheme REG_DWORD 0x1"),
+            Some(Appearance::Light)
+        );
+    }
+}
@@ -4,7 +4,7 @@
 //! - File paths (config, data, cache)
 //! - IPC mechanisms (Unix sockets vs Named Pipes)
 //! - Graphics backend selection
-//! - System integration
+//! - System integration, such as light/dark mode
 
 use anyhow::Result;
 use std::path::PathBuf;
@@ -16,7 +16,12 @@ mod macos;
 #[cfg(target_os = "windows")]
 mod windows;
 
+pub mod appearance;
 pub mod ipc;
+pub mod paths;
+
+pub use appearance::Appearance;
+pub use paths::{PathSettings, Paths};
 
 /// Platform-specific behavior trait
 pub trait Platform {
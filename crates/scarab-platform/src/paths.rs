@@ -0,0 +1,146 @@
+//! Where Scarab keeps its files, socket and shared memory
+//!
+//! Every location can be moved, so that packaged and sandboxed installs
+//! (NixOS, Flatpak, containers) can keep Scarab inside the directories they
+//! allow. For each path the first of these wins:
+//!
+//! 1. Its environment variable, such as `SCARAB_SOCKET_PATH`
+//! 2. The `[paths]` section of `config.toml`, passed in as [`PathSettings`]
+//! 3. The default, derived from the config and data directories
+//!
+//! The config directory holds `config.toml` itself, so it can only be moved
+//! with `SCARAB_CONFIG_DIR`. Programs that don't read the config, such as
+//! `scarab-tui`, use [`Paths::from_env`].
+
+use std::path::PathBuf;
+
+/// Directory holding `config.toml`, `config.fsx` and `plugins.toml`
+pub const CONFIG_DIR_ENV: &str = "SCARAB_CONFIG_DIR";
+/// Directory for state such as `sessions.db`
+pub const DATA_DIR_ENV: &str = "SCARAB_DATA_DIR";
+/// Socket the daemon listens on
+pub const SOCKET_PATH_ENV: &str = "SCARAB_SOCKET_PATH";
+/// Session database
+pub const SESSIONS_DB_ENV: &str = "SCARAB_SESSIONS_DB";
+/// Directory plugins are installed to and loaded from
+pub const PLUGIN_DIR_ENV: &str = "SCARAB_PLUGIN_DIR";
+/// Shared image buffer
+pub use scarab_protocol::IMAGE_SHMEM_PATH_ENV;
+/// Main shared memory region
+pub use scarab_protocol::SHMEM_PATH_ENV;
+
+/// Locations set in the config, each `None` to use the default
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathSettings {
+    pub data_dir: Option<PathBuf>,
+    pub socket: Option<PathBuf>,
+    pub shmem: Option<String>,
+    pub image_shmem: Option<String>,
+    pub sessions_db: Option<PathBuf>,
+    pub plugin_dir: Option<PathBuf>,
+}
+
+/// Every location Scarab uses, resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+    pub socket: PathBuf,
+    /// Name of the main shared memory region
+    pub shmem: String,
+    /// Name of the shared image buffer
+    pub image_shmem: String,
+    pub sessions_db: PathBuf,
+    pub plugin_dir: PathBuf,
+}
+
+impl Paths {
+    /// Locations from the environment and defaults, without a config
+    pub fn from_env() -> Self {
+        Self::resolve(&PathSettings::default())
+    }
+
+    /// Locations from the environment, then `settings`, then defaults
+    pub fn resolve(settings: &PathSettings) -> Self {
+        Self::resolve_with(settings, &|name| std::env::var(name).ok())
+    }
+
+    /// [`resolve`](Self::resolve), reading variables from `lookup`
+    pub fn resolve_with(settings: &PathSettings, lookup: &dyn Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| lookup(name).filter(|value| !value.is_empty());
+        let path = |name: &str, setting: &Option<PathBuf>| {
+            var(name).map(PathBuf::from).or_else(|| setting.clone())
+        };
+
+        let home = var("HOME").map_or_else(|| PathBuf::from("."), PathBuf::from);
+        let config_dir =
+            var(CONFIG_DIR_ENV).map_or_else(|| home.join(".config/scarab"), PathBuf::from);
+        let data_dir = path(DATA_DIR_ENV, &settings.data_dir)
+            .unwrap_or_else(|| home.join(".local/share/scarab"));
+
+        Self {
+            socket: path(SOCKET_PATH_ENV, &settings.socket)
+                .unwrap_or_else(|| PathBuf::from(scarab_protocol::SOCKET_PATH)),
+            shmem: var(SHMEM_PATH_ENV)
+                .or_else(|| settings.shmem.clone())
+                .unwrap_or_else(|| scarab_protocol::SHMEM_PATH.to_string()),
+            image_shmem: var(IMAGE_SHMEM_PATH_ENV)
+                .or_else(|| settings.image_shmem.clone())
+                .unwrap_or_else(|| scarab_protocol::IMAGE_SHMEM_PATH.to_string()),
+            sessions_db: path(SESSIONS_DB_ENV, &settings.sessions_db)
+                .unwrap_or_else(|| data_dir.join("sessions.db")),
+            plugin_dir: path(PLUGIN_DIR_ENV, &settings.plugin_dir)
+                .unwrap_or_else(|| config_dir.join("plugins")),
+            config_dir,
+            data_dir,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_follow_home() {
+        let paths = Paths::resolve_with(&PathSettings::default(), &|name| {
+            (name == "HOME").then(|| "/home/me".to_string())
+        });
+        assert_eq!(paths.config_dir, PathBuf::from("/home/me/.config/scarab"));
+        assert_eq!(
+            paths.sessions_db,
+            PathBuf::from("/home/me/.local/share/scarab/sessions.db")
+        );
+        assert_eq!(
+            paths.plugin_dir,
+            PathBuf::from("/home/me/.config/scarab/plugins")
+        );
+        assert_eq!(paths.socket, PathBuf::from(scarab_protocol::SOCKET_PATH));
+        assert_eq!(paths.shmem, scarab_protocol::SHMEM_PATH);
+    }
+
+    #[test]
+    fn test_environment_wins_over_settings() {
+        let settings = PathSettings {
+            data_dir: Some(PathBuf::from("/var/lib/scarab")),
+            socket: Some(PathBuf::from("/run/scarab/config.sock")),
+            shmem: Some("/scarab_sandbox".to_string()),
+            ..Default::default()
+        };
+        let paths = Paths::resolve_with(&settings, &|name| match name {
+            "HOME" => Some("/home/me".to_string()),
+            SOCKET_PATH_ENV => Some("/run/scarab/env.sock".to_string()),
+            SHMEM_PATH_ENV => Some(String::new()),
+            CONFIG_DIR_ENV => Some("/etc/scarab".to_string()),
+            _ => None,
+        });
+        assert_eq!(paths.socket, PathBuf::from("/run/scarab/env.sock"));
+        // An empty variable counts as unset
+        assert_eq!(paths.shmem, "/scarab_sandbox");
+        assert_eq!(
+            paths.sessions_db,
+            PathBuf::from("/var/lib/scarab/sessions.db")
+        );
+        assert_eq!(paths.plugin_dir, PathBuf::from("/etc/scarab/plugins"));
+    }
+}
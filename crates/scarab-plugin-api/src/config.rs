@@ -1,8 +1,14 @@
 //! Plugin configuration loading and discovery
 
-use crate::{context::PluginConfigData, error::Result};
+use crate::{
+    context::PluginConfigData,
+    error::{PluginError, Result},
+    manifest::Capability,
+};
+use scarab_platform::Paths;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashSet,
     fs,
     path::{Path, PathBuf},
 };
@@ -12,7 +18,7 @@ use std::{
 pub struct PluginConfig {
     /// Plugin name
     pub name: String,
-    /// Path to plugin file (.fzb or .fsx)
+    /// Path to plugin file (.fzb, .fsx, .scarabpkg, or a native shared library)
     pub path: PathBuf,
     /// Whether plugin is enabled
     #[serde(default = "default_true")]
@@ -20,6 +26,12 @@ pub struct PluginConfig {
     /// Plugin-specific configuration
     #[serde(default)]
     pub config: PluginConfigData,
+    /// Capabilities granted to the plugin, using manifest names like `"exec"`
+    #[serde(default)]
+    pub capabilities: HashSet<Capability>,
+    /// Hosts the plugin may reach with the `network` capability
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
 }
 
 fn default_true() -> bool {
@@ -31,7 +43,17 @@ impl PluginConfig {
     pub fn from_file(path: impl AsRef<Path>) -> Result<Vec<Self>> {
         let content = fs::read_to_string(path)?;
         let config: PluginsToml = toml::from_str(&content)?;
-        Ok(config.plugin)
+
+        let mut plugins = config.plugin;
+        for plugin in &mut plugins {
+            if let Some(path) = plugin.path.to_str() {
+                let path = expand(path, &|name| std::env::var(name).ok()).map_err(|e| {
+                    PluginError::ConfigError(format!("plugin '{}': {}", plugin.name, e))
+                })?;
+                plugin.path = PathBuf::from(path);
+            }
+        }
+        Ok(plugins)
     }
 
     /// Expand path with home directory
@@ -55,8 +77,14 @@ pub struct PluginDiscovery {
 impl PluginDiscovery {
     /// Create new discovery with default paths
     pub fn new() -> Self {
+        Self::with_plugin_dir(Self::default_plugin_dir())
+    }
+
+    /// Create discovery searching `plugin_dir` in place of the default
+    /// user plugin directory, such as the one set in `[paths]`
+    pub fn with_plugin_dir(plugin_dir: impl Into<PathBuf>) -> Self {
         let mut search_paths = vec![
-            Self::default_plugin_dir(),
+            plugin_dir.into(),
             PathBuf::from("/usr/local/share/scarab/plugins"),
             PathBuf::from("/usr/share/scarab/plugins"),
         ];
@@ -70,21 +98,15 @@ impl PluginDiscovery {
     }
 
     /// Get default plugin directory (~/.config/scarab/plugins)
+    ///
+    /// `SCARAB_PLUGIN_DIR` or `SCARAB_CONFIG_DIR` move it.
     pub fn default_plugin_dir() -> PathBuf {
-        if let Some(home) = std::env::var_os("HOME") {
-            PathBuf::from(home).join(".config/scarab/plugins")
-        } else {
-            PathBuf::from(".config/scarab/plugins")
-        }
+        Paths::from_env().plugin_dir
     }
 
     /// Get default config file path (~/.config/scarab/plugins.toml)
     pub fn default_config_path() -> PathBuf {
-        if let Some(home) = std::env::var_os("HOME") {
-            PathBuf::from(home).join(".config/scarab/plugins.toml")
-        } else {
-            PathBuf::from(".config/scarab/plugins.toml")
-        }
+        Paths::from_env().config_dir.join("plugins.toml")
     }
 
     /// Add search path
@@ -92,6 +114,11 @@ impl PluginDiscovery {
         self.search_paths.push(path.into());
     }
 
+    /// Directories searched for plugins, in priority order
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
     /// Discover all plugin files in search paths
     pub fn discover(&self) -> Vec<PathBuf> {
         let mut plugins = Vec::new();
@@ -111,14 +138,27 @@ impl PluginDiscovery {
     }
 
     /// Check if file is a valid plugin file
-    fn is_plugin_file(path: &Path) -> bool {
+    pub fn is_plugin_file(path: &Path) -> bool {
         if !path.is_file() {
             return false;
         }
 
+        Self::has_plugin_extension(path)
+    }
+
+    /// Check if the path has a plugin extension, without touching the filesystem
+    ///
+    /// Useful for paths that were just removed and can no longer be stat'ed.
+    pub fn has_plugin_extension(path: &Path) -> bool {
         matches!(
             path.extension().and_then(|e| e.to_str()),
-            Some("fzb") | Some("fsx")
+            Some("fzb")
+                | Some("fsx")
+                | Some("scarabpkg")
+                | Some("so")
+                | Some("dylib")
+                | Some("dll")
+                | Some("wasm")
         )
     }
 
@@ -251,10 +291,74 @@ fn expand_path(path: &Path) -> PathBuf {
     path.to_path_buf()
 }
 
+/// `value` with a leading `~` and each `${NAME}` replaced
+///
+/// A `$` not followed by `{` is kept as it is, and a variable that is not
+/// set is an error. Plugin paths in `plugins.toml` and the path-valued
+/// fields of the main config are both expanded this way.
+pub fn expand(
+    value: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+
+    if rest == "~" || rest.starts_with("~/") {
+        let home = lookup("HOME")
+            .or_else(|| dirs::home_dir().map(|home| home.to_string_lossy().into_owned()))
+            .ok_or_else(|| format!("no home directory to expand `~` in \"{}\"", value))?;
+        out.push_str(&home);
+        rest = &rest[1..];
+    }
+
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("unterminated `${{` in \"{}\"", value))?;
+        let name = &after[..end];
+        if name.is_empty() {
+            return Err(format!("empty `${{}}` in \"{}\"", value));
+        }
+        let var = lookup(name)
+            .ok_or_else(|| format!("variable `{}` is not set (in \"{}\")", name, value))?;
+        out.push_str(&var);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/me".to_string()),
+            "SCARAB_THEMES" => Some("/opt/themes".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expand("~", &lookup).unwrap(), "/home/me");
+        assert_eq!(expand("~/bin/fish", &lookup).unwrap(), "/home/me/bin/fish");
+        assert_eq!(
+            expand("${SCARAB_THEMES}/${HOME}.png", &lookup).unwrap(),
+            "/opt/themes//home/me.png"
+        );
+        // Only a leading `~`, and only `${...}` is a variable
+        assert_eq!(expand("~user/a~b", &lookup).unwrap(), "~user/a~b");
+        assert_eq!(expand("/bin/$SHELL", &lookup).unwrap(), "/bin/$SHELL");
+
+        assert!(expand("${NOPE}/x", &lookup).unwrap_err().contains("`NOPE`"));
+        assert!(expand("${HOME", &lookup).is_err());
+        assert!(expand("${}", &lookup).is_err());
+    }
+
     #[test]
     fn test_expand_path() {
         let path = PathBuf::from("~/test/path");
@@ -268,15 +372,16 @@ mod tests {
         // For unit testing, we just test the extension logic
         use std::path::Path;
 
-        let has_valid_ext = |path: &Path| -> bool {
-            matches!(
-                path.extension().and_then(|e| e.to_str()),
-                Some("fzb") | Some("fsx")
-            )
-        };
-
-        assert!(has_valid_ext(Path::new("test.fzb")));
-        assert!(has_valid_ext(Path::new("test.fsx")));
-        assert!(!has_valid_ext(Path::new("test.txt")));
+        assert!(PluginDiscovery::has_plugin_extension(Path::new("test.fzb")));
+        assert!(PluginDiscovery::has_plugin_extension(Path::new("test.fsx")));
+        assert!(PluginDiscovery::has_plugin_extension(Path::new(
+            "libtest.so"
+        )));
+        assert!(!PluginDiscovery::has_plugin_extension(Path::new(
+            "test.txt"
+        )));
+        assert!(!PluginDiscovery::is_plugin_file(Path::new(
+            "/nonexistent/test.fzb"
+        )));
     }
 }
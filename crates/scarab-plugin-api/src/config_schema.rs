@@ -0,0 +1,229 @@
+//! Schemas for plugin settings
+//!
+//! Users configure a plugin in a `[plugins.settings.<name>]` table of their
+//! Scarab config. A plugin describes the settings it understands with a
+//! [`ConfigSchema`], and the daemon checks the table against it before the
+//! values reach [`PluginContext::config`](crate::PluginContext::config), so
+//! every declared key a plugin reads has the declared type.
+
+use crate::context::PluginConfigData;
+use crate::error::{PluginError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Type of a setting's value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigValueType {
+    Bool,
+    Integer,
+    /// A number; integers are accepted and converted
+    Float,
+    String,
+    /// An array of strings
+    StringList,
+    /// Any table, for nested settings the plugin checks itself
+    Table,
+}
+
+impl ConfigValueType {
+    /// Name used in validation errors
+    fn name(self) -> &'static str {
+        match self {
+            ConfigValueType::Bool => "a boolean",
+            ConfigValueType::Integer => "an integer",
+            ConfigValueType::Float => "a number",
+            ConfigValueType::String => "a string",
+            ConfigValueType::StringList => "a list of strings",
+            ConfigValueType::Table => "a table",
+        }
+    }
+
+    /// Check a value, returning it as the plugin will see it
+    fn check(self, value: &toml::Value) -> Option<toml::Value> {
+        use toml::Value;
+
+        match (self, value) {
+            (ConfigValueType::Bool, Value::Boolean(_))
+            | (ConfigValueType::Integer, Value::Integer(_))
+            | (ConfigValueType::Float, Value::Float(_))
+            | (ConfigValueType::String, Value::String(_))
+            | (ConfigValueType::Table, Value::Table(_)) => Some(value.clone()),
+            (ConfigValueType::Float, Value::Integer(i)) => Some(Value::Float(*i as f64)),
+            (ConfigValueType::StringList, Value::Array(items))
+                if items.iter().all(Value::is_str) =>
+            {
+                Some(value.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A setting a plugin accepts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigField {
+    /// Key in the plugin's config table
+    pub key: String,
+    #[serde(rename = "type")]
+    pub value_type: ConfigValueType,
+    /// Value used when the user does not set one
+    #[serde(default)]
+    pub default: Option<toml::Value>,
+    /// What the setting does
+    #[serde(default)]
+    pub description: String,
+}
+
+impl ConfigField {
+    /// Create a setting with no default
+    pub fn new(
+        key: impl Into<String>,
+        value_type: ConfigValueType,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            value_type,
+            default: None,
+            description: description.into(),
+        }
+    }
+
+    /// Set the value used when the user does not set one
+    pub fn with_default(mut self, default: impl Into<toml::Value>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+}
+
+/// Settings a plugin accepts, returned from
+/// [`Plugin::config_schema`](crate::Plugin::config_schema)
+///
+/// An empty schema accepts any table without checking it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConfigSchema {
+    pub fields: Vec<ConfigField>,
+}
+
+impl ConfigSchema {
+    /// Create an empty schema
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a setting
+    pub fn with_field(mut self, field: ConfigField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Check whether the schema declares no settings
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Check user settings against the schema, filling in defaults
+    ///
+    /// Unknown keys and values of the wrong type are all reported in one
+    /// `ConfigError`.
+    pub fn validate(&self, values: &HashMap<String, toml::Value>) -> Result<PluginConfigData> {
+        if self.is_empty() {
+            return Ok(PluginConfigData {
+                data: values.clone(),
+            });
+        }
+
+        let mut problems: Vec<String> = values
+            .keys()
+            .filter(|key| !self.fields.iter().any(|field| &field.key == *key))
+            .map(|key| format!("unknown setting '{}'", key))
+            .collect();
+        problems.sort();
+
+        let mut data = HashMap::new();
+        for field in &self.fields {
+            match values.get(&field.key) {
+                Some(value) => match field.value_type.check(value) {
+                    Some(value) => {
+                        data.insert(field.key.clone(), value);
+                    }
+                    None => problems.push(format!(
+                        "'{}' should be {}",
+                        field.key,
+                        field.value_type.name()
+                    )),
+                },
+                None => {
+                    if let Some(default) = &field.default {
+                        data.insert(field.key.clone(), default.clone());
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(PluginConfigData { data })
+        } else {
+            Err(PluginError::ConfigError(problems.join("; ")))
+        }
+    }
+
+    /// Settings with only the declared defaults
+    pub fn defaults(&self) -> PluginConfigData {
+        self.validate(&HashMap::new()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> ConfigSchema {
+        ConfigSchema::new()
+            .with_field(
+                ConfigField::new("show_branch", ConfigValueType::Bool, "Show the branch")
+                    .with_default(true),
+            )
+            .with_field(ConfigField::new(
+                "refresh_secs",
+                ConfigValueType::Float,
+                "Seconds between refreshes",
+            ))
+            .with_field(ConfigField::new(
+                "ignore",
+                ConfigValueType::StringList,
+                "Paths to skip",
+            ))
+    }
+
+    #[test]
+    fn test_validate_fills_defaults_and_converts() {
+        let values: HashMap<String, toml::Value> =
+            toml::from_str("refresh_secs = 5\nignore = [\"target\"]").unwrap();
+        let config = schema().validate(&values).unwrap();
+
+        assert!(config.get::<bool>("show_branch").unwrap());
+        assert_eq!(config.get::<f64>("refresh_secs").unwrap(), 5.0);
+        assert_eq!(
+            config.get::<Vec<String>>("ignore").unwrap(),
+            vec!["target".to_string()]
+        );
+        assert_eq!(schema().defaults().data.len(), 1);
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let values: HashMap<String, toml::Value> =
+            toml::from_str("show_branch = \"yes\"\nignore = [1]\ncolour = \"red\"").unwrap();
+        let err = schema().validate(&values).unwrap_err().to_string();
+
+        assert!(err.contains("unknown setting 'colour'"));
+        assert!(err.contains("'show_branch' should be a boolean"));
+        assert!(err.contains("'ignore' should be a list of strings"));
+
+        // Without a schema, settings pass through unchecked
+        assert_eq!(ConfigSchema::new().validate(&values).unwrap().data, values);
+    }
+}
@@ -1,12 +1,33 @@
 //! Plugin context providing access to terminal state
 
 use crate::{
-    error::Result,
-    types::{Cell, ModalItem, RemoteCommand},
+    error::{PluginError, Result},
+    exec::{CommandOutput, ExecLimits},
+    history::{CommandBlock, TerminalHistory},
+    http::HttpLimits,
+    manifest::Capability,
+    object_model::{Objects, Workspace},
+    permissions::{capability_key, Permission, PermissionStore},
+    settings::{ConfigScope, Settings},
+    status_bar::{RenderItem, StatusBarSide},
+    storage::{PluginStorage, DEFAULT_STORAGE_QUOTA},
+    tasks::{TaskId, TaskRegistry},
+    types::{Cell, ModalItem, PluginMessage, PromptField, RemoteCommand},
 };
 use parking_lot::Mutex;
 use serde::Deserialize;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Prompt IDs are global so the daemon can route answers to their plugin
+static NEXT_PROMPT_ID: AtomicU64 = AtomicU64::new(1);
 
 /// Shared state accessible to plugins
 ///
@@ -98,6 +119,29 @@ pub struct PluginContext {
     pub logger_name: String,
     /// Queue of commands to be sent to the client/daemon
     pub commands: Arc<Mutex<Vec<RemoteCommand>>>,
+    /// Directory for plugin storage, `None` if there is no data directory
+    pub storage_root: Option<PathBuf>,
+    /// Size limit for this plugin's storage in bytes
+    pub storage_quota: usize,
+    /// Capabilities granted to this plugin; empty denies everything gated
+    pub capabilities: HashSet<Capability>,
+    /// Limits for commands run with `spawn_command`
+    pub exec_limits: ExecLimits,
+    /// Hosts reachable with `http_get` and `http_post`
+    pub allowed_hosts: Vec<String>,
+    /// Limits for HTTP requests
+    pub http_limits: HttpLimits,
+    /// Scrollback and command blocks of the active pane, when available
+    pub history: Option<Arc<dyn TerminalHistory>>,
+    /// Sessions, tabs and panes of the daemon, when available
+    pub workspace: Option<Arc<dyn Workspace>>,
+    /// The user's settings, when available
+    pub settings: Option<Arc<dyn Settings>>,
+    /// Background tasks, shared with every context cloned from this one
+    pub tasks: Arc<TaskRegistry>,
+    /// User decisions about network, exec and clipboard use; without a
+    /// store, granted capabilities are used without asking
+    pub permissions: Option<Arc<PermissionStore>>,
 }
 
 impl PluginContext {
@@ -112,14 +156,228 @@ impl PluginContext {
             state,
             logger_name: logger_name.into(),
             commands: Arc::new(Mutex::new(Vec::new())),
+            storage_root: PluginStorage::default_root(),
+            storage_quota: DEFAULT_STORAGE_QUOTA,
+            capabilities: HashSet::new(),
+            exec_limits: ExecLimits::default(),
+            allowed_hosts: Vec::new(),
+            http_limits: HttpLimits::default(),
+            history: None,
+            workspace: None,
+            settings: None,
+            tasks: Arc::new(TaskRegistry::default()),
+            permissions: None,
         }
     }
 
+    /// Give plugins access to terminal history
+    pub fn with_history(mut self, history: Arc<dyn TerminalHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Give plugins access to sessions, tabs and panes
+    pub fn with_workspace(mut self, workspace: Arc<dyn Workspace>) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Give plugins access to the user's settings
+    pub fn with_settings(mut self, settings: Arc<dyn Settings>) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// Keep plugin storage under `root` instead of the default directory
+    pub fn with_storage_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.storage_root = Some(root.into());
+        self
+    }
+
+    /// Ask the user before plugins first use network, exec or clipboard
+    pub fn with_permissions(mut self, permissions: Arc<PermissionStore>) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Settings for this plugin from its `[plugins.settings.<name>]` config table
+    ///
+    /// Values have been checked against
+    /// [`Plugin::config_schema`](crate::Plugin::config_schema), with defaults
+    /// filled in.
+    pub fn config(&self) -> &PluginConfigData {
+        &self.config
+    }
+
     /// Queue a command to be sent to the client or daemon
     pub fn queue_command(&self, cmd: RemoteCommand) {
         self.commands.lock().push(cmd);
     }
 
+    /// Publish a message to every plugin subscribed to `topic`
+    ///
+    /// Delivery happens through the plugin manager once the current hook
+    /// returns, so publishers need no dependency on their subscribers.
+    pub fn emit(&self, topic: impl Into<String>, payload: serde_json::Value) {
+        self.queue_command(RemoteCommand::Publish(PluginMessage {
+            topic: topic.into(),
+            sender: self.logger_name.clone(),
+            payload,
+        }));
+    }
+
+    /// Ask the user for one line of text
+    ///
+    /// Returns the prompt ID; the answer arrives later in
+    /// [`Plugin::on_prompt_response`](crate::Plugin::on_prompt_response).
+    /// Set `masked` for secrets such as passwords.
+    pub fn show_input_prompt(
+        &self,
+        title: impl Into<String>,
+        placeholder: impl Into<String>,
+        masked: bool,
+    ) -> u64 {
+        let prompt_id = NEXT_PROMPT_ID.fetch_add(1, Ordering::Relaxed);
+        self.queue_command(RemoteCommand::ShowInputPrompt {
+            plugin_name: self.logger_name.clone(),
+            prompt_id,
+            title: title.into(),
+            placeholder: placeholder.into(),
+            masked,
+        });
+        prompt_id
+    }
+
+    /// Ask the user to fill in a form
+    ///
+    /// Values come back in the order of `fields`. Returns the prompt ID.
+    pub fn show_form(&self, title: impl Into<String>, fields: Vec<PromptField>) -> u64 {
+        let prompt_id = NEXT_PROMPT_ID.fetch_add(1, Ordering::Relaxed);
+        self.queue_command(RemoteCommand::ShowForm {
+            plugin_name: self.logger_name.clone(),
+            prompt_id,
+            title: title.into(),
+            fields,
+        });
+        prompt_id
+    }
+
+    /// Show a styled segment in the status bar, replacing any segment or
+    /// status item of this plugin with the same ID
+    ///
+    /// IDs are chosen by the plugin and scoped to it. Higher priorities sit
+    /// closer to the bar's edge and are hidden last when space runs out.
+    /// The daemon forwards at most a few updates per second for each
+    /// segment, always ending with the latest one, and removes a plugin's
+    /// segments when it is unloaded.
+    pub fn set_status_segment(
+        &self,
+        item_id: u64,
+        side: StatusBarSide,
+        priority: i32,
+        items: Vec<RenderItem>,
+    ) {
+        self.queue_command(RemoteCommand::SetStatusSegment {
+            plugin_name: self.logger_name.clone(),
+            item_id,
+            side,
+            priority,
+            items,
+        });
+    }
+
+    /// Remove a segment shown with [`set_status_segment`](Self::set_status_segment)
+    pub fn remove_status_segment(&self, item_id: u64) {
+        self.queue_command(RemoteCommand::RemoveStatusItem {
+            plugin_name: self.logger_name.clone(),
+            item_id,
+        });
+    }
+
+    /// Run a future in the background without blocking hook dispatch
+    ///
+    /// `task` receives the task's ID for use with
+    /// [`report_progress`](Self::report_progress) and returns the future to
+    /// run. The task is cancelled when the plugin is unloaded. Errors are
+    /// logged, and the progress indicator is closed when the task ends.
+    pub fn spawn_task<F, Fut>(&self, name: impl Into<String>, task: F) -> TaskId
+    where
+        F: FnOnce(TaskId) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let task_id = self.tasks.next_id();
+        let future = task(task_id);
+        let name = name.into();
+        let plugin_name = self.logger_name.clone();
+        let title = name.clone();
+        let commands = self.commands.clone();
+        let tasks = self.tasks.clone();
+
+        let task = async move {
+            let message = match future.await {
+                Ok(()) => "Done".to_string(),
+                Err(e) => {
+                    log::error!("[{}] Task '{}' failed: {}", plugin_name, title, e);
+                    format!("Failed: {}", e)
+                }
+            };
+            commands.lock().push(RemoteCommand::TaskProgress {
+                plugin_name,
+                task_id,
+                title,
+                percent: 100,
+                message,
+                done: true,
+            });
+            tasks.finish(task_id);
+        };
+        self.tasks.spawn(task_id, &self.logger_name, &name, task);
+        task_id
+    }
+
+    /// Show progress of a running task to the user
+    ///
+    /// `percent` is clamped to 100. Reports for tasks that already ended
+    /// are dropped.
+    pub fn report_progress(&self, task_id: TaskId, percent: u8, message: impl Into<String>) {
+        let Some(title) = self.tasks.name(task_id) else {
+            return;
+        };
+        self.queue_command(RemoteCommand::TaskProgress {
+            plugin_name: self.logger_name.clone(),
+            task_id,
+            title,
+            percent: percent.min(100),
+            message: message.into(),
+            done: false,
+        });
+        self.tasks.notify();
+    }
+
+    /// Cancel a running task of this plugin, returning whether it was running
+    pub fn cancel_task(&self, task_id: TaskId) -> bool {
+        let Some((_, title)) = self
+            .tasks
+            .running(&self.logger_name)
+            .into_iter()
+            .find(|(id, _)| *id == task_id)
+        else {
+            return false;
+        };
+        if !self.tasks.cancel(task_id) {
+            return false;
+        }
+        self.queue_command(RemoteCommand::TaskProgress {
+            plugin_name: self.logger_name.clone(),
+            task_id,
+            title,
+            percent: 0,
+            message: "Cancelled".to_string(),
+            done: true,
+        });
+        true
+    }
+
     /// Get cell at position
     pub fn get_cell(&self, x: u16, y: u16) -> Option<Cell> {
         self.state.lock().get_cell(x, y)
@@ -135,6 +393,41 @@ impl PluginContext {
         self.state.lock().get_line(y)
     }
 
+    /// Get text of absolute lines `start..end`, scrollback first
+    ///
+    /// Lines past the end are left out; returns nothing when the host
+    /// provides no history.
+    pub fn get_scrollback_range(&self, start: usize, end: usize) -> Vec<String> {
+        match &self.history {
+            Some(history) if start < end => history.lines(start, end),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the number of lines in scrollback
+    pub fn get_scrollback_len(&self) -> usize {
+        self.history.as_ref().map_or(0, |h| h.scrollback_len())
+    }
+
+    /// Get finished shell-integration command blocks, oldest first
+    ///
+    /// Zone rows are the absolute lines used by
+    /// [`get_scrollback_range`](Self::get_scrollback_range); use
+    /// [`get_block_output`](Self::get_block_output) for a block's output.
+    pub fn get_command_blocks(&self) -> Vec<CommandBlock> {
+        self.history
+            .as_ref()
+            .map_or_else(Vec::new, |h| h.command_blocks())
+    }
+
+    /// Get the output lines of a command block
+    pub fn get_block_output(&self, block: &CommandBlock) -> Vec<String> {
+        match block.output_bounds() {
+            Some((start, end)) => self.get_scrollback_range(start as usize, end as usize + 1),
+            None => Vec::new(),
+        }
+    }
+
     /// Get terminal size
     pub fn get_size(&self) -> (u16, u16) {
         let state = self.state.lock();
@@ -161,6 +454,244 @@ impl PluginContext {
         self.state.lock().data.get(key).cloned()
     }
 
+    /// Open this plugin's persistent key-value store
+    ///
+    /// The store is namespaced by the context's logger name, which the
+    /// daemon sets to the plugin name.
+    pub fn storage(&self) -> Result<PluginStorage> {
+        let root = self.storage_root.as_deref().ok_or_else(|| {
+            PluginError::ConfigError("No data directory for plugin storage".into())
+        })?;
+        PluginStorage::open(root, &self.logger_name, self.storage_quota)
+    }
+
+    /// Get handles to the sessions, tabs and panes of the workspace
+    ///
+    /// Requires [`Capability::TerminalControl`] and the user's permission.
+    /// Changes made through the handles are applied after the hook returns.
+    pub fn objects(&self) -> Result<Objects> {
+        if !self.has_capability(&Capability::TerminalControl) {
+            return Err(PluginError::CapabilityDenied(format!(
+                "terminal control ({} may not change tabs and panes)",
+                self.logger_name
+            )));
+        }
+        self.check_permission(
+            &Capability::TerminalControl,
+            "type into panes and change tabs and panes",
+        )?;
+        Ok(Objects::new(
+            self.workspace.clone(),
+            self.logger_name.clone(),
+            self.commands.clone(),
+        ))
+    }
+
+    /// Read a setting by dotted key, such as `font.size`, as TOML
+    pub fn get_setting(&self, key: &str) -> Result<String> {
+        let settings = self
+            .settings
+            .as_ref()
+            .ok_or_else(|| PluginError::ConfigError("No settings available".into()))?;
+        settings.get(key).map_err(PluginError::ConfigError)
+    }
+
+    /// Change a setting, such as `colors.theme` to `"dracula"`
+    ///
+    /// Requires [`Capability::TerminalControl`] and the user's permission,
+    /// and only reaches the keys [`check_plugin_setting`] allows. `value` is
+    /// TOML, or a bare string. The daemon checks the change after the hook
+    /// returns and logs it if the key or value is invalid. `persist` must be
+    /// `false`: plugins can't write to `config.toml`.
+    ///
+    /// [`check_plugin_setting`]: crate::settings::check_plugin_setting
+    pub fn set_setting(&self, key: &str, value: &str, persist: bool) -> Result<()> {
+        self.check_setting_key(key, persist)?;
+        self.queue_setting_change(scarab_protocol::ControlMessage::ConfigSet {
+            key: key.to_string(),
+            value: value.to_string(),
+            persist,
+        })
+    }
+
+    /// Change a setting for one tab or pane only, such as a larger
+    /// `font.size` for a presentation tab; `None` removes the override
+    ///
+    /// Requires [`Capability::TerminalControl`] and reaches the same keys as
+    /// [`set_setting`](Self::set_setting). A pane's overrides win over its
+    /// tab's. Overrides are never written to `config.toml` and go away with
+    /// the tab or pane.
+    pub fn override_setting(
+        &self,
+        scope: ConfigScope,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<()> {
+        self.check_setting_key(key, false)?;
+        self.queue_setting_change(scarab_protocol::ControlMessage::ConfigOverride {
+            scope,
+            key: key.to_string(),
+            value: value.map(str::to_string),
+        })
+    }
+
+    /// Remove every override for a tab or pane
+    ///
+    /// Requires [`Capability::TerminalControl`].
+    pub fn clear_overrides(&self, scope: ConfigScope) -> Result<()> {
+        self.queue_setting_change(scarab_protocol::ControlMessage::ConfigOverrideClear { scope })
+    }
+
+    fn check_setting_key(&self, key: &str, persist: bool) -> Result<()> {
+        crate::settings::check_plugin_setting(&self.logger_name, key, persist)
+            .map_err(PluginError::CapabilityDenied)
+    }
+
+    fn queue_setting_change(&self, message: scarab_protocol::ControlMessage) -> Result<()> {
+        if !self.has_capability(&Capability::TerminalControl) {
+            return Err(PluginError::CapabilityDenied(format!(
+                "terminal control ({} may not change settings)",
+                self.logger_name
+            )));
+        }
+        self.check_permission(&Capability::TerminalControl, "change settings")?;
+        self.queue_command(RemoteCommand::Control {
+            plugin_name: self.logger_name.clone(),
+            message,
+        });
+        Ok(())
+    }
+
+    /// Check whether this plugin was granted a capability
+    pub fn has_capability(&self, capability: &Capability) -> bool {
+        self.capabilities.contains(capability)
+    }
+
+    /// Check that the user lets this plugin use a granted capability
+    ///
+    /// The first use of a capability in [`PROMPTED_CAPABILITIES`](crate::permissions::PROMPTED_CAPABILITIES) asks the
+    /// user and fails until they allow it; `detail` tells them what the
+    /// plugin was trying to do.
+    pub fn check_permission(&self, capability: &Capability, detail: &str) -> Result<()> {
+        let Some(permissions) = &self.permissions else {
+            return Ok(());
+        };
+        let name = capability_key(capability);
+        match permissions.check(&self.logger_name, capability) {
+            Permission::Granted => Ok(()),
+            Permission::Denied => Err(PluginError::CapabilityDenied(format!(
+                "{} (denied to {} by the user)",
+                name, self.logger_name
+            ))),
+            Permission::Undecided => {
+                if let Some(request) = permissions.request(&self.logger_name, capability) {
+                    self.queue_command(RemoteCommand::RequestPermission {
+                        plugin_name: request.plugin_name,
+                        prompt_id: request.prompt_id,
+                        capability: request.capability,
+                        detail: detail.to_string(),
+                    });
+                }
+                Err(PluginError::CapabilityDenied(format!(
+                    "{} (waiting for the user to allow {})",
+                    name, self.logger_name
+                )))
+            }
+        }
+    }
+
+    /// Run a program and capture its output
+    ///
+    /// Requires [`Capability::Exec`] and the user's permission. The program
+    /// runs directly, without a shell, and is killed if it outlives
+    /// [`ExecLimits::timeout`] or prints more than
+    /// [`ExecLimits::max_output_bytes`] on either stream.
+    pub async fn spawn_command(&self, program: &str, args: &[&str]) -> Result<CommandOutput> {
+        if !self.has_capability(&Capability::Exec) {
+            return Err(PluginError::CapabilityDenied(format!(
+                "exec ({} may not run '{}')",
+                self.logger_name, program
+            )));
+        }
+        self.check_permission(&Capability::Exec, &format!("run {}", program))?;
+        log::debug!("[{}] Running {} {:?}", self.logger_name, program, args);
+        crate::exec::run_command(program, args, self.exec_limits).await
+    }
+
+    /// Fetch a URL with a GET request
+    ///
+    /// Requires [`Capability::Network`], the user's permission and a host on
+    /// [`allowed_hosts`](Self::allowed_hosts); see [`HttpLimits`] for the
+    /// timeout and body size cap.
+    #[cfg(feature = "http")]
+    pub async fn http_get(&self, url: &str) -> Result<crate::http::HttpResponse> {
+        let url = self.check_network(url)?;
+        crate::http::send(
+            &self.allowed_hosts,
+            reqwest::Method::GET,
+            url,
+            None,
+            self.http_limits,
+        )
+        .await
+    }
+
+    /// Send a POST request with a body of the given content type
+    ///
+    /// Subject to the same checks as [`http_get`](Self::http_get).
+    #[cfg(feature = "http")]
+    pub async fn http_post(
+        &self,
+        url: &str,
+        content_type: &str,
+        body: impl Into<Vec<u8>>,
+    ) -> Result<crate::http::HttpResponse> {
+        let url = self.check_network(url)?;
+        crate::http::send(
+            &self.allowed_hosts,
+            reqwest::Method::POST,
+            url,
+            Some((content_type.to_string(), body.into())),
+            self.http_limits,
+        )
+        .await
+    }
+
+    #[cfg(feature = "http")]
+    fn check_network(&self, url: &str) -> Result<reqwest::Url> {
+        if !self.has_capability(&Capability::Network) {
+            return Err(PluginError::CapabilityDenied(format!(
+                "network ({} may not fetch '{}')",
+                self.logger_name, url
+            )));
+        }
+        let url = crate::http::check_url(&self.allowed_hosts, url)?;
+        self.check_permission(
+            &Capability::Network,
+            &format!("connect to {}", url.host_str().unwrap_or_default()),
+        )?;
+        log::debug!("[{}] HTTP request to {}", self.logger_name, url);
+        Ok(url)
+    }
+
+    /// Put text on the system clipboard of connected clients
+    ///
+    /// Requires [`Capability::Clipboard`] and the user's permission.
+    pub fn copy_to_clipboard(&self, text: &str) -> Result<()> {
+        if !self.has_capability(&Capability::Clipboard) {
+            return Err(PluginError::CapabilityDenied(format!(
+                "clipboard ({} may not copy text)",
+                self.logger_name
+            )));
+        }
+        self.check_permission(&Capability::Clipboard, "copy text to the clipboard")?;
+        self.queue_command(RemoteCommand::SetClipboard {
+            plugin_name: self.logger_name.clone(),
+            text: text.to_string(),
+        });
+        Ok(())
+    }
+
     /// Log a message with the integrated logging system
     ///
     /// Messages are sent to both the Rust logging infrastructure (using the `log` crate)
@@ -235,7 +766,7 @@ pub enum NotifyLevel {
 }
 
 /// Plugin-specific configuration data
-#[derive(Debug, Clone, Default, Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, serde::Serialize)]
 pub struct PluginConfigData {
     #[serde(flatten)]
     pub data: HashMap<String, toml::Value>,
@@ -0,0 +1,177 @@
+//! Where the hooks of Fusabi plugins are defined, for error messages
+//!
+//! `scarab-plugin-compiler` writes a definition map next to each `.fzb` it
+//! builds (`plugin.fzb.defs`) listing where every top-level `let` of the
+//! `.fsx` starts. When a hook fails, the daemon names the hook and where it
+//! is defined:
+//!
+//! ```text
+//! Hook execution failed: Type mismatch
+//!   in on_output, defined at git-status.fsx:12:5
+//! ```
+//!
+//! This is not a source map: the bytecode carries no spans, and the VM
+//! reports errors per call rather than per instruction, so the location is
+//! that of the hook called, not of the failing expression. Nested and
+//! indented `let`s are not mapped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Extension appended to a bytecode file's name for its definition map
+pub const DEFINITION_MAP_EXTENSION: &str = "defs";
+
+/// 1-based position in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Where the top-level definitions of a Fusabi source start
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefinitionMap {
+    /// Name of the source file, as shown in errors
+    pub source: String,
+    /// Definition name -> location of the name in its `let`
+    pub definitions: BTreeMap<String, SourceLocation>,
+}
+
+impl DefinitionMap {
+    /// Map the unindented `let`s of `text`, read from the file `source`
+    pub fn from_source(source: &str, text: &str) -> Self {
+        let mut definitions = BTreeMap::new();
+        for (index, line) in text.lines().enumerate() {
+            if let Some((name, column)) = definition(line) {
+                definitions.entry(name).or_insert(SourceLocation {
+                    line: index as u32 + 1,
+                    column: column as u32 + 1,
+                });
+            }
+        }
+        Self {
+            source: source.to_string(),
+            definitions,
+        }
+    }
+
+    /// Where the definition map of a bytecode file lives
+    pub fn path_for(bytecode: &Path) -> PathBuf {
+        let mut name = bytecode.as_os_str().to_os_string();
+        name.push(".");
+        name.push(DEFINITION_MAP_EXTENSION);
+        PathBuf::from(name)
+    }
+
+    /// Read the definition map written next to `bytecode`, if there is one
+    ///
+    /// A missing or unreadable map only costs the locations in errors, so
+    /// it is not an error itself.
+    pub fn load_for(bytecode: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(Self::path_for(bytecode)).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(map) => Some(map),
+            Err(e) => {
+                log::warn!(
+                    "Ignoring unreadable definition map for {}: {}",
+                    bytecode.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Write the map next to `bytecode`
+    pub fn write_for(&self, bytecode: &Path) -> std::io::Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(Self::path_for(bytecode), content)
+    }
+
+    /// Where `name` is defined
+    pub fn locate(&self, name: &str) -> Option<SourceLocation> {
+        self.definitions.get(name).copied()
+    }
+
+    /// `error`, followed by where the failing function is defined if known
+    pub fn annotate(&self, function: &str, error: &str) -> String {
+        match self.locate(function) {
+            Some(at) => format!(
+                "{}\n  in {}, defined at {}:{}:{}",
+                error, function, self.source, at.line, at.column
+            ),
+            None => error.to_string(),
+        }
+    }
+}
+
+/// Name bound by an unindented `let` and the byte offset of the name
+fn definition(line: &str) -> Option<(String, usize)> {
+    let mut rest = line.strip_prefix("let ")?.trim_start();
+    for keyword in ["rec ", "mutable "] {
+        if let Some(after) = rest.strip_prefix(keyword) {
+            rest = after.trim_start();
+        }
+    }
+    let offset = line.len() - rest.len();
+    let name: String = rest
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '\'')
+        .collect();
+    let starts_well = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_');
+    starts_well.then_some((name, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_definitions_and_annotate() {
+        let map = DefinitionMap::from_source(
+            "git-status.fsx",
+            "// @name: git-status\nlet branch = \"main\"\n\nlet rec  on_output line =\n    let inner = 1\n    true\nlet (a, b) = (1, 2)\n",
+        );
+        assert_eq!(
+            map.locate("branch"),
+            Some(SourceLocation { line: 2, column: 5 })
+        );
+        assert_eq!(
+            map.locate("on_output"),
+            Some(SourceLocation {
+                line: 4,
+                column: 10
+            })
+        );
+        // Indented `let`s and patterns are not mapped
+        assert_eq!(map.definitions.len(), 2);
+
+        assert_eq!(
+            map.annotate("on_output", "Type mismatch"),
+            "Type mismatch\n  in on_output, defined at git-status.fsx:4:10"
+        );
+        assert_eq!(map.annotate("on_input", "Boom"), "Boom");
+    }
+
+    #[test]
+    fn test_sidecar_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("scarab-definition-map-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let bytecode = dir.join("plugin.fzb");
+        assert_eq!(
+            DefinitionMap::path_for(&bytecode),
+            dir.join("plugin.fzb.defs")
+        );
+        assert!(DefinitionMap::load_for(&bytecode).is_none());
+
+        let map = DefinitionMap::from_source("plugin.fsx", "let on_load = fun () -> ()\n");
+        map.write_for(&bytecode).unwrap();
+        assert_eq!(DefinitionMap::load_for(&bytecode), Some(map));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,167 @@
+//! Capability-checked subprocess execution for plugins
+//!
+//! Plugins run programs through
+//! [`PluginContext::spawn_command`](crate::PluginContext::spawn_command),
+//! which is denied unless the plugin was granted
+//! [`Capability::Exec`](crate::Capability::Exec). Commands run without a
+//! shell or stdin, are killed when they exceed their timeout, and have their
+//! captured output capped.
+
+use crate::error::{PluginError, Result};
+use std::{process::Stdio, time::Duration};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Limits applied to every command a plugin runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecLimits {
+    /// Time before the command is killed
+    pub timeout: Duration,
+    /// Bytes kept from each of stdout and stderr
+    pub max_output_bytes: usize,
+}
+
+impl Default for ExecLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            max_output_bytes: 256 * 1024,
+        }
+    }
+}
+
+/// Result of a finished command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Exit code, `None` if the process was killed by a signal
+    pub status: Option<i32>,
+    /// Captured stdout, lossily decoded as UTF-8
+    pub stdout: String,
+    /// Captured stderr, lossily decoded as UTF-8
+    pub stderr: String,
+    /// Whether output went over the limit; the process is killed when it does
+    pub truncated: bool,
+}
+
+impl CommandOutput {
+    /// Check whether the command exited with status 0
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// Run a program to completion within `limits`
+pub async fn run_command(
+    program: &str,
+    args: &[&str],
+    limits: ExecLimits,
+) -> Result<CommandOutput> {
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let max = limits.max_output_bytes;
+
+    let run = async {
+        let (stdout, stderr) =
+            tokio::try_join!(read_limited(stdout, max), read_limited(stderr, max))?;
+        let truncated = stdout.1 || stderr.1;
+        if truncated {
+            // The process may already have exited on its own
+            let _ = child.start_kill();
+        }
+        let status = child.wait().await?;
+        Ok::<_, std::io::Error>(CommandOutput {
+            status: status.code(),
+            stdout: String::from_utf8_lossy(&stdout.0).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr.0).into_owned(),
+            truncated,
+        })
+    };
+
+    let result = tokio::time::timeout(limits.timeout, run).await;
+    match result {
+        Ok(output) => Ok(output?),
+        Err(_) => {
+            let _ = child.start_kill();
+            Err(PluginError::Other(anyhow::anyhow!(
+                "Command '{}' timed out after {}ms",
+                program,
+                limits.timeout.as_millis()
+            )))
+        }
+    }
+}
+
+/// Read a stream up to `limit` bytes, reporting whether there was more
+async fn read_limited<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    limit: usize,
+) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut buf = Vec::new();
+    if let Some(reader) = reader {
+        reader.take(limit as u64 + 1).read_to_end(&mut buf).await?;
+    }
+    let truncated = buf.len() > limit;
+    buf.truncate(limit);
+    Ok((buf, truncated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::PluginSharedState, Capability, PluginContext};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    fn make_ctx() -> PluginContext {
+        let state = Arc::new(Mutex::new(PluginSharedState::new(80, 24)));
+        PluginContext::new(Default::default(), state, "git-status")
+    }
+
+    #[tokio::test]
+    async fn test_denied_by_default() {
+        let ctx = make_ctx();
+        let result = ctx.spawn_command("echo", &["hi"]).await;
+        assert!(matches!(result, Err(PluginError::CapabilityDenied(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_spawn_command() {
+        let mut ctx = make_ctx();
+        ctx.capabilities.insert(Capability::Exec);
+
+        let output = ctx
+            .spawn_command("echo", &["hello", "world"])
+            .await
+            .unwrap();
+        assert!(output.success());
+        assert_eq!(output.stdout, "hello world\n");
+        assert!(!output.truncated);
+
+        let output = ctx.spawn_command("false", &[]).await.unwrap();
+        assert_eq!(output.status, Some(1));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_limits() {
+        let limits = ExecLimits {
+            timeout: Duration::from_millis(100),
+            max_output_bytes: 4,
+        };
+
+        let output = run_command("echo", &["truncated"], limits).await.unwrap();
+        assert_eq!(output.stdout, "trun");
+        assert!(output.truncated);
+
+        let result = run_command("sleep", &["5"], limits).await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,422 @@
+//! `#load` and `open` for Fusabi sources split across files
+//!
+//! The Fusabi frontend compiles a single source, so a script that loads
+//! others is flattened first: `#load "theme.fsx"` pulls theme.fsx (relative
+//! to the loading file) in ahead of the script, once, however many files
+//! load it. Its top-level `let`s stay in scope as they are and are also
+//! gathered into a record named after the file, so `Theme.accent` works as
+//! well as `accent`. `open Theme` is accepted and dropped.
+//!
+//! Loaded files should only hold declarations; a trailing expression would
+//! land in the middle of the flattened script.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// Why a script and the files it loads could not be put together
+#[derive(Debug, Error)]
+pub enum ModuleError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A `#load` that is missing, unreadable or loops back on itself
+    #[error("{0}")]
+    Load(String),
+}
+
+type Result<T> = std::result::Result<T, ModuleError>;
+
+/// A script with everything it loads inlined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSource {
+    /// Flattened source, ready for the compiler
+    pub source: String,
+    /// Files loaded directly or indirectly, in the order they were inlined
+    pub dependencies: Vec<PathBuf>,
+}
+
+impl ResolvedSource {
+    /// Whether a change to `path` affects this script
+    pub fn depends_on(&self, path: &Path) -> bool {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.dependencies.contains(&path)
+    }
+}
+
+/// A file split into its `#load` directives and the rest
+#[derive(Debug, Clone)]
+struct ParsedModule {
+    modified: SystemTime,
+    /// Canonical paths of the files it loads
+    loads: Vec<PathBuf>,
+    /// Source without the `#load` lines
+    body: String,
+    /// Names bound by top-level `let`s
+    bindings: Vec<String>,
+}
+
+/// Parsed files by path, reused until their modification time changes
+///
+/// Keep one cache around between reloads so a module shared by many
+/// scripts is only read again when it changes.
+#[derive(Debug, Default)]
+pub struct AstCache {
+    modules: HashMap<PathBuf, ParsedModule>,
+}
+
+impl AstCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of files cached
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Read `path` and inline everything it loads
+    ///
+    /// Fails if a loaded file is missing or files load each other in a
+    /// cycle, naming the files involved.
+    pub fn resolve(&mut self, path: &Path) -> Result<ResolvedSource> {
+        let root = path.canonicalize()?;
+        let mut order = Vec::new();
+        self.visit(&root, &mut Vec::new(), &mut order)?;
+        // The script itself comes last
+        order.pop();
+
+        let (mut source, names) = self.inline(&order);
+        push_body(&mut source, &self.modules[&root].body, &names);
+
+        Ok(ResolvedSource {
+            source,
+            dependencies: order,
+        })
+    }
+
+    /// Like [`resolve`](Self::resolve), with the script's text given rather
+    /// than read from `path`
+    ///
+    /// For editors checking unsaved buffers. The script's `#load` lines and
+    /// `open` lines for loaded modules become blank, so its text ends the
+    /// source with its line numbers intact, after the loaded files.
+    pub fn resolve_text(&mut self, path: &Path, text: &str) -> Result<ResolvedSource> {
+        let root = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let script = parse_module(&root, text, SystemTime::UNIX_EPOCH)?;
+        let mut order = Vec::new();
+        let mut stack = vec![root];
+        for dep in &script.loads {
+            self.visit(dep, &mut stack, &mut order)?;
+        }
+
+        let (mut source, names) = self.inline(&order);
+        for line in text.split_inclusive('\n') {
+            let directive = line.trim_start().starts_with("#load")
+                || line
+                    .trim()
+                    .strip_prefix("open ")
+                    .is_some_and(|name| names.contains(name.trim()));
+            if directive {
+                source.push_str(if line.ends_with('\n') { "\n" } else { "" });
+            } else {
+                source.push_str(line);
+            }
+        }
+
+        Ok(ResolvedSource {
+            source,
+            dependencies: order,
+        })
+    }
+
+    /// Bodies of `order` with a record for each, and the records' names
+    fn inline(&self, order: &[PathBuf]) -> (String, HashSet<String>) {
+        let names: HashSet<String> = order.iter().map(|dep| module_name(dep)).collect();
+        let mut source = String::new();
+        for dep in order {
+            let module = &self.modules[dep];
+            push_body(&mut source, &module.body, &names);
+            if !source.is_empty() && !source.ends_with('\n') {
+                source.push('\n');
+            }
+            if !module.bindings.is_empty() {
+                let fields: Vec<String> = module
+                    .bindings
+                    .iter()
+                    .map(|name| format!("{} = {}", name, name))
+                    .collect();
+                source.push_str(&format!(
+                    "let {} = {{ {} }}\n",
+                    module_name(dep),
+                    fields.join("; ")
+                ));
+            }
+        }
+        (source, names)
+    }
+
+    /// Depth-first walk adding each file after the files it loads
+    fn visit(
+        &mut self,
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        order: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        if let Some(start) = stack.iter().position(|p| p == path) {
+            let cycle: Vec<String> = stack[start..]
+                .iter()
+                .map(|p| p.as_path())
+                .chain(std::iter::once(path))
+                .map(file_name)
+                .collect();
+            return Err(ModuleError::Load(format!(
+                "#load cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+        if order.iter().any(|p| p == path) {
+            return Ok(());
+        }
+
+        let loads = self.parse(path)?.loads.clone();
+        stack.push(path.to_path_buf());
+        for dep in &loads {
+            self.visit(dep, stack, order)?;
+        }
+        stack.pop();
+        order.push(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Cached parse of `path`, read again if the file changed
+    fn parse(&mut self, path: &Path) -> Result<&ParsedModule> {
+        let modified = std::fs::metadata(path)?.modified()?;
+        let fresh = matches!(self.modules.get(path), Some(module) if module.modified == modified);
+        if !fresh {
+            let source = std::fs::read_to_string(path)?;
+            let module = parse_module(path, &source, modified)?;
+            self.modules.insert(path.to_path_buf(), module);
+        }
+        Ok(&self.modules[path])
+    }
+}
+
+/// Read `path` and inline everything it loads, without a cache
+pub fn resolve_source(path: &Path) -> Result<ResolvedSource> {
+    AstCache::new().resolve(path)
+}
+
+fn parse_module(path: &Path, source: &str, modified: SystemTime) -> Result<ParsedModule> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut loads = Vec::new();
+    let mut body = String::new();
+    let mut bindings = Vec::new();
+
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        if let Some(rest) = line.trim_start().strip_prefix("#load") {
+            // `#load "a.fsx" "b.fsx"`: the quoted parts are every other piece
+            let files: Vec<&str> = rest.split('"').skip(1).step_by(2).collect();
+            if files.is_empty() {
+                return Err(ModuleError::Load(format!(
+                    "{}:{}: #load needs a quoted file name",
+                    file_name(path),
+                    index + 1
+                )));
+            }
+            for file in files {
+                let target = dir.join(file).canonicalize().map_err(|e| {
+                    ModuleError::Load(format!(
+                        "{}:{}: cannot load \"{}\": {}",
+                        file_name(path),
+                        index + 1,
+                        file,
+                        e
+                    ))
+                })?;
+                loads.push(target);
+            }
+            continue;
+        }
+
+        if let Some(name) = top_level_binding(line) {
+            if !bindings.contains(&name) {
+                bindings.push(name);
+            }
+        }
+        body.push_str(line);
+    }
+
+    Ok(ParsedModule {
+        modified,
+        loads,
+        body,
+        bindings,
+    })
+}
+
+/// Name bound by an unindented `let`, skipping patterns like `let (a, b)`
+pub fn top_level_binding(line: &str) -> Option<String> {
+    let mut words = line.strip_prefix("let ")?.split_whitespace();
+    let mut word = words.next()?;
+    if word == "rec" || word == "mutable" {
+        word = words.next()?;
+    }
+    let name: String = word
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '\'')
+        .collect();
+    let starts_well = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_');
+    starts_well.then_some(name)
+}
+
+/// Append a body, dropping `open` lines for inlined modules
+fn push_body(out: &mut String, body: &str, modules: &HashSet<String>) {
+    for line in body.split_inclusive('\n') {
+        let opened = line
+            .trim()
+            .strip_prefix("open ")
+            .is_some_and(|name| modules.contains(name.trim()));
+        if !opened {
+            out.push_str(line);
+        }
+    }
+}
+
+/// `my-theme.fsx` -> `MyTheme`
+fn module_name(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    stem.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_inlines_each_module_once() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("lib")).unwrap();
+        fs::write(
+            dir.path().join("lib/colors.fsx"),
+            "let accent = \"#ff79c6\"\nlet rec fib n = n\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("lib/my-theme.fsx"),
+            "#load \"colors.fsx\"\nlet theme = accent\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("config.fsx"),
+            "#load \"lib/colors.fsx\" \"lib/my-theme.fsx\"\nopen MyTheme\nopen Other\nlet x = MyTheme.theme\n",
+        )
+        .unwrap();
+
+        let resolved = resolve_source(&dir.path().join("config.fsx")).unwrap();
+        assert_eq!(
+            resolved.source,
+            "let accent = \"#ff79c6\"\nlet rec fib n = n\n\
+             let Colors = { accent = accent; fib = fib }\n\
+             let theme = accent\n\
+             let MyTheme = { theme = theme }\n\
+             open Other\nlet x = MyTheme.theme\n"
+        );
+        assert_eq!(resolved.dependencies.len(), 2);
+        assert!(resolved.depends_on(&dir.path().join("lib/../lib/colors.fsx")));
+        assert!(!resolved.depends_on(&dir.path().join("config.fsx")));
+
+        // Without `#load`s the source is untouched
+        fs::write(dir.path().join("plain.fsx"), "let x = 1\r\nx").unwrap();
+        let plain = resolve_source(&dir.path().join("plain.fsx")).unwrap();
+        assert_eq!(plain.source, "let x = 1\r\nx");
+        assert!(plain.dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_cycles_and_missing_files_fail() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.fsx"), "#load \"b.fsx\"\n").unwrap();
+        fs::write(dir.path().join("b.fsx"), "#load \"a.fsx\"\n").unwrap();
+        fs::write(dir.path().join("c.fsx"), "let x = 1\n#load \"gone.fsx\"\n").unwrap();
+
+        let err = resolve_source(&dir.path().join("a.fsx")).unwrap_err();
+        assert!(
+            err.to_string().contains("a.fsx -> b.fsx -> a.fsx"),
+            "{}",
+            err
+        );
+        let err = resolve_source(&dir.path().join("c.fsx")).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("c.fsx:2: cannot load \"gone.fsx\""),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_resolve_text_keeps_script_lines() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("lib.fsx"), "let size = 12\n").unwrap();
+        let main = dir.path().join("main.fsx");
+        fs::write(&main, "#load \"gone.fsx\"\n").unwrap();
+
+        // The unsaved text wins over the file on disk
+        let text = "#load \"lib.fsx\"\nopen Lib\nlet font = size";
+        let resolved = AstCache::new().resolve_text(&main, text).unwrap();
+        assert_eq!(
+            resolved.source,
+            "let size = 12\nlet Lib = { size = size }\n\n\nlet font = size"
+        );
+        assert_eq!(resolved.dependencies.len(), 1);
+
+        let err = AstCache::new()
+            .resolve_text(&main, "let x = 1\n#load \"lib.fsx\" \"main.fsx\"\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("main.fsx -> main.fsx"), "{}", err);
+    }
+
+    #[test]
+    fn test_cache_rereads_changed_files() {
+        let dir = TempDir::new().unwrap();
+        let lib = dir.path().join("lib.fsx");
+        let main = dir.path().join("main.fsx");
+        fs::write(&lib, "let size = 12\n").unwrap();
+        fs::write(&main, "#load \"lib.fsx\"\nlet font = size\n").unwrap();
+
+        let mut cache = AstCache::new();
+        assert!(cache.resolve(&main).unwrap().source.contains("size = 12"));
+        assert_eq!(cache.len(), 2);
+
+        fs::write(&lib, "let size = 14\n").unwrap();
+        let file = fs::File::options().write(true).open(&lib).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+        assert!(cache.resolve(&main).unwrap().source.contains("size = 14"));
+    }
+}
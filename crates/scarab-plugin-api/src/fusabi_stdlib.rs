@@ -0,0 +1,247 @@
+//! JSON, regex and time functions for Fusabi plugins and scripts
+//!
+//! Registered next to fusabi-vm's own stdlib, as globals:
+//!
+//! | Function | Returns |
+//! |----------|---------|
+//! | `json_parse text` | the parsed value, or `()` if `text` is not JSON |
+//! | `json_stringify value` | compact JSON text |
+//! | `regex_match pattern text` | whether `pattern` matches anywhere in `text` |
+//! | `regex_replace pattern replacement text` | `text` with every match replaced (`$1` names a group) |
+//! | `time_now ()` | seconds since the Unix epoch |
+//! | `time_format seconds format` | local time formatted with strftime `format` |
+//! | `time_format_utc seconds format` | the same in UTC |
+//!
+//! JSON objects become maps, arrays become tuples and `null` becomes `()`.
+//! An invalid pattern never matches and leaves text unchanged; an invalid
+//! format gives `""`. Daemon plugins only get the regex functions with the
+//! output- or input-filtering capability, since that is the text they are
+//! meant to pick apart.
+
+use crate::manifest::Capability;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use fusabi_vm::{Value, Vm};
+use regex::{Regex, RegexBuilder};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Compiled patterns kept per VM before the cache is cleared
+const REGEX_CACHE_SIZE: usize = 64;
+
+/// Upper bound on a compiled pattern's size, in bytes
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Whether a plugin with `capabilities` gets the regex functions
+pub fn allows_regex(capabilities: &HashSet<Capability>) -> bool {
+    capabilities.contains(&Capability::OutputFiltering)
+        || capabilities.contains(&Capability::InputFiltering)
+}
+
+/// Add the functions to `vm`, leaving out the regex ones unless allowed
+pub fn register_stdlib_ext(vm: &mut Vm, allow_regex: bool) {
+    let mut functions: Vec<(&str, u8)> = vec![
+        ("json_parse", 1),
+        ("json_stringify", 1),
+        ("time_now", 0),
+        ("time_format", 2),
+        ("time_format_utc", 2),
+    ];
+
+    {
+        let mut registry = vm.host_registry.lock().unwrap();
+
+        registry.register("json_parse", |_vm, args| {
+            let text = args.first().and_then(|v| v.as_str()).unwrap_or("");
+            Ok(serde_json::from_str(text)
+                .map(|json| json_to_value(&json))
+                .unwrap_or(Value::Unit))
+        });
+
+        registry.register("json_stringify", |_vm, args| {
+            let json = args.first().map(value_to_json).unwrap_or_default();
+            Ok(Value::Str(json.to_string()))
+        });
+
+        registry.register("time_now", |_vm, _args| {
+            Ok(Value::Int(Utc::now().timestamp()))
+        });
+
+        registry.register("time_format", |_vm, args| {
+            let seconds = args.first().and_then(|v| v.as_int()).unwrap_or(0);
+            let format = args.get(1).and_then(|v| v.as_str()).unwrap_or("");
+            Ok(Value::Str(format_time(&Local, seconds, format)))
+        });
+
+        registry.register("time_format_utc", |_vm, args| {
+            let seconds = args.first().and_then(|v| v.as_int()).unwrap_or(0);
+            let format = args.get(1).and_then(|v| v.as_str()).unwrap_or("");
+            Ok(Value::Str(format_time(&Utc, seconds, format)))
+        });
+
+        if allow_regex {
+            let patterns = RegexCache::default();
+
+            let cache = patterns.clone();
+            registry.register("regex_match", move |_vm, args| {
+                let pattern = args.first().and_then(|v| v.as_str()).unwrap_or("");
+                let text = args.get(1).and_then(|v| v.as_str()).unwrap_or("");
+                let matched = cache.get(pattern).is_some_and(|re| re.is_match(text));
+                Ok(Value::Bool(matched))
+            });
+
+            let cache = patterns;
+            registry.register("regex_replace", move |_vm, args| {
+                let pattern = args.first().and_then(|v| v.as_str()).unwrap_or("");
+                let replacement = args.get(1).and_then(|v| v.as_str()).unwrap_or("");
+                let text = args.get(2).and_then(|v| v.as_str()).unwrap_or("");
+                let replaced = match cache.get(pattern) {
+                    Some(re) => re.replace_all(text, replacement).into_owned(),
+                    None => text.to_string(),
+                };
+                Ok(Value::Str(replaced))
+            });
+
+            functions.extend([("regex_match", 2), ("regex_replace", 3)]);
+        }
+    }
+
+    for (name, arity) in functions {
+        vm.globals.insert(
+            name.to_string(),
+            Value::NativeFn {
+                name: name.to_string(),
+                arity,
+                args: vec![],
+            },
+        );
+    }
+}
+
+/// Patterns compiled so far; `None` marks one that failed to compile
+#[derive(Clone, Default)]
+struct RegexCache(Arc<Mutex<HashMap<String, Option<Regex>>>>);
+
+impl RegexCache {
+    fn get(&self, pattern: &str) -> Option<Regex> {
+        let mut cache = self.0.lock().unwrap();
+        if let Some(compiled) = cache.get(pattern) {
+            return compiled.clone();
+        }
+        if cache.len() >= REGEX_CACHE_SIZE {
+            cache.clear();
+        }
+        let compiled = RegexBuilder::new(pattern)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()
+            .map_err(|e| log::warn!("Invalid regex {:?} in Fusabi plugin: {}", pattern, e))
+            .ok();
+        cache.insert(pattern.to_string(), compiled.clone());
+        compiled
+    }
+}
+
+/// Convert parsed JSON to a Fusabi value
+pub fn json_to_value(json: &serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Unit,
+        serde_json::Value::Bool(b) => Value::Bool(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::Float(n.as_f64().unwrap_or(0.0)),
+        },
+        serde_json::Value::String(s) => Value::Str(s.clone()),
+        serde_json::Value::Array(items) => Value::Tuple(items.iter().map(json_to_value).collect()),
+        serde_json::Value::Object(fields) => Value::Map(Arc::new(Mutex::new(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), json_to_value(value)))
+                .collect(),
+        ))),
+    }
+}
+
+/// Convert a Fusabi value to JSON; functions and other values without a
+/// JSON form become `null`
+pub fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(b) => (*b).into(),
+        Value::Int(i) => (*i).into(),
+        Value::Float(f) => serde_json::Number::from_f64(*f)
+            .map(serde_json::Value::Number)
+            .unwrap_or_default(),
+        Value::Str(s) => s.clone().into(),
+        Value::Tuple(items) => items.iter().map(value_to_json).collect(),
+        Value::Record(fields) | Value::Map(fields) => serde_json::Value::Object(
+            fields
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(key, value)| (key.clone(), value_to_json(value)))
+                .collect(),
+        ),
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// Format Unix `seconds` in `zone` with a strftime `format`
+///
+/// Returns `""` for an invalid format or a time out of range rather than
+/// failing the hook.
+pub fn format_time<Tz: TimeZone>(zone: &Tz, seconds: i64, format: &str) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return String::new();
+    }
+    let Some(time): Option<DateTime<Tz>> = zone.timestamp_opt(seconds, 0).single() else {
+        return String::new();
+    };
+    time.format_with_items(items.into_iter()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let json: serde_json::Value = serde_json::from_str(
+            r#"{"branch":"main","ahead":2,"ratio":0.5,"tags":["a",null,true]}"#,
+        )
+        .unwrap();
+        let value = json_to_value(&json);
+        let Value::Map(fields) = &value else {
+            panic!("Expected a map, got {:?}", value);
+        };
+        assert!(matches!(
+            fields.lock().unwrap().get("ahead"),
+            Some(Value::Int(2))
+        ));
+        assert_eq!(value_to_json(&value), json);
+        assert_eq!(value_to_json(&Value::Unit), serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_regex_cache_and_capability() {
+        let cache = RegexCache::default();
+        assert!(cache
+            .get(r"^error\[E\d+\]")
+            .unwrap()
+            .is_match("error[E0308]: mismatched"));
+        assert!(cache.get("(unclosed").is_none());
+        assert_eq!(cache.0.lock().unwrap().len(), 2);
+
+        assert!(!allows_regex(&HashSet::new()));
+        assert!(allows_regex(&HashSet::from([Capability::OutputFiltering])));
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(&Utc, 0, "%Y-%m-%d %H:%M"), "1970-01-01 00:00");
+        assert_eq!(format_time(&Utc, 86_400, "%a"), "Fri");
+        assert_eq!(format_time(&Utc, 0, "%Q"), "");
+    }
+}
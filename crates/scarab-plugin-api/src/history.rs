@@ -0,0 +1,23 @@
+//! Read access to terminal history for plugins
+//!
+//! The daemon implements [`TerminalHistory`] over the active pane so that
+//! plugins can look back at earlier output through
+//! [`PluginContext::get_scrollback_range`](crate::PluginContext::get_scrollback_range)
+//! and [`PluginContext::get_command_blocks`](crate::PluginContext::get_command_blocks).
+
+pub use scarab_protocol::{CommandBlock, SemanticZone, ZoneType};
+
+/// Source of scrollback text and shell-integration command blocks
+///
+/// Lines are absolute: scrollback first, oldest line at 0, followed by the
+/// visible grid. Zone rows in [`CommandBlock`] use the same numbering.
+pub trait TerminalHistory: Send + Sync {
+    /// Number of lines held in scrollback
+    fn scrollback_len(&self) -> usize;
+
+    /// Text of lines `start..end`, stopping early at the last line
+    fn lines(&self, start: usize, end: usize) -> Vec<String>;
+
+    /// Finished command blocks, oldest first
+    fn command_blocks(&self) -> Vec<CommandBlock>;
+}
@@ -52,10 +52,11 @@
 
 use crate::context::PluginContext;
 use crate::error::{PluginError, Result};
+use crate::manifest::Capability;
 use crate::navigation::{
     validate_focusable, PluginFocusable, PluginFocusableAction, PluginNavCapabilities,
 };
-use crate::types::{JumpDirection, OverlayConfig, StatusBarItem};
+use crate::types::{JumpDirection, OverlayConfig, OverlayPanel, StatusBarItem};
 use parking_lot::Mutex;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::time::Instant;
@@ -349,6 +350,47 @@ impl HostBindings {
         self.rate_limiter.check()
     }
 
+    /// Check that the plugin may use a capability from its config
+    ///
+    /// Network, exec and clipboard also need the user's permission, which
+    /// is asked for on first use; see [`PluginContext::check_permission`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// - Plugin was not granted the capability
+    /// - The user denied it, or has not answered yet
+    /// - Rate limit exceeded
+    pub fn check_permission(
+        &self,
+        ctx: &PluginContext,
+        capability: &Capability,
+        detail: &str,
+    ) -> Result<()> {
+        if !ctx.has_capability(capability) {
+            return Err(PluginError::CapabilityDenied(
+                crate::permissions::capability_key(capability).into(),
+            ));
+        }
+
+        self.check_rate_limit()?;
+
+        ctx.check_permission(capability, detail)
+    }
+
+    /// Copy text to the system clipboard
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// - Plugin was not granted `clipboard`, or the user denied it
+    /// - Rate limit exceeded
+    pub fn copy_to_clipboard(&self, ctx: &PluginContext, text: &str) -> Result<()> {
+        self.check_rate_limit()?;
+
+        ctx.copy_to_clipboard(text)
+    }
+
     /// Enter hint mode
     ///
     /// Triggers the navigation hint mode UI, displaying labels for all
@@ -553,6 +595,57 @@ impl HostBindings {
     /// - Rate limit exceeded
     /// - Overlay position is out of bounds
     pub fn spawn_overlay(&self, ctx: &PluginContext, config: OverlayConfig) -> Result<u64> {
+        let overlay_id = self.reserve_overlay(config.x, config.y)?;
+
+        ctx.queue_command(crate::types::RemoteCommand::SpawnOverlay {
+            plugin_name: ctx.logger_name.clone(),
+            overlay_id,
+            config,
+        });
+
+        Ok(overlay_id)
+    }
+
+    /// Spawn a floating panel with several lines of text
+    ///
+    /// Panels can have a title, a border, aligned text wrapped at a maximum
+    /// width, and selectable items. Picking an item calls
+    /// [`Plugin::on_panel_item_selected`](crate::Plugin::on_panel_item_selected)
+    /// with the returned overlay ID and the item's ID. Panels count against
+    /// `max_overlays` and are removed with [`remove_overlay`](Self::remove_overlay).
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// - Plugin has reached `max_overlays` quota
+    /// - Rate limit exceeded
+    /// - Panel position is out of bounds
+    /// - An item has an empty ID, or `max_width` is zero
+    pub fn spawn_panel(&self, ctx: &PluginContext, panel: OverlayPanel) -> Result<u64> {
+        if panel.items.iter().any(|item| item.id.is_empty()) {
+            return Err(PluginError::ValidationError(
+                "Panel items need a non-empty ID".into(),
+            ));
+        }
+        if panel.max_width == Some(0) {
+            return Err(PluginError::ValidationError(
+                "Panel max_width must be at least one column".into(),
+            ));
+        }
+
+        let overlay_id = self.reserve_overlay(panel.x, panel.y)?;
+
+        ctx.queue_command(crate::types::RemoteCommand::SpawnPanel {
+            plugin_name: ctx.logger_name.clone(),
+            overlay_id,
+            panel,
+        });
+
+        Ok(overlay_id)
+    }
+
+    /// Check quota, bounds and rate limit, then allocate an overlay ID
+    fn reserve_overlay(&self, x: u16, y: u16) -> Result<u64> {
         let current = self.resources.overlays();
         if current >= self.limits.max_overlays as u64 {
             return Err(PluginError::QuotaExceeded {
@@ -563,11 +656,11 @@ impl HostBindings {
         }
 
         if self.limits.bounds_check
-            && (config.x >= self.limits.max_coordinate || config.y >= self.limits.max_coordinate)
+            && (x >= self.limits.max_coordinate || y >= self.limits.max_coordinate)
         {
             return Err(PluginError::ValidationError(format!(
                 "Overlay position ({}, {}) exceeds max coordinate {}",
-                config.x, config.y, self.limits.max_coordinate
+                x, y, self.limits.max_coordinate
             )));
         }
 
@@ -575,17 +668,10 @@ impl HostBindings {
 
         let overlay_id = self.next_overlay_id.fetch_add(1, Ordering::SeqCst);
         self.resources.add_overlay();
-
-        ctx.queue_command(crate::types::RemoteCommand::SpawnOverlay {
-            plugin_name: ctx.logger_name.clone(),
-            overlay_id,
-            config,
-        });
-
         Ok(overlay_id)
     }
 
-    /// Remove a previously spawned overlay
+    /// Remove a previously spawned overlay or panel
     ///
     /// Removes an overlay by its ID. If the overlay doesn't exist, this is a no-op.
     ///
@@ -947,6 +1033,37 @@ mod tests {
         assert!(matches!(result, Err(PluginError::QuotaExceeded { .. })));
     }
 
+    #[test]
+    fn test_spawn_panel() {
+        let ctx = make_test_ctx();
+        let limits = HostBindingLimits {
+            max_overlays: 1,
+            ..Default::default()
+        };
+        let bindings = HostBindings::new(limits, PluginNavCapabilities::default());
+
+        let bad = OverlayPanel::new(0, 0).with_item("", "Nameless");
+        assert!(matches!(
+            bindings.spawn_panel(&ctx, bad),
+            Err(PluginError::ValidationError(_))
+        ));
+
+        let panel = OverlayPanel::new(2, 3)
+            .with_title("Branches")
+            .with_item("main", "main")
+            .with_max_width(30);
+        let id = bindings.spawn_panel(&ctx, panel.clone()).unwrap();
+        assert!(matches!(
+            &ctx.commands.lock()[0],
+            crate::types::RemoteCommand::SpawnPanel { overlay_id, panel: queued, .. }
+                if *overlay_id == id && *queued == panel
+        ));
+
+        // Panels share the overlay quota
+        let result = bindings.spawn_overlay(&ctx, OverlayConfig::new(0, 0, "tip"));
+        assert!(matches!(result, Err(PluginError::QuotaExceeded { .. })));
+    }
+
     #[test]
     fn test_resource_usage() {
         let bindings = HostBindings::with_defaults();
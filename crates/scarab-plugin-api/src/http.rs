@@ -0,0 +1,205 @@
+//! Capability-checked HTTP requests for plugins
+//!
+//! [`PluginContext::http_get`](crate::PluginContext::http_get) and
+//! [`PluginContext::http_post`](crate::PluginContext::http_post) need
+//! [`Capability::Network`](crate::Capability::Network) and only reach hosts
+//! on the plugin's allowlist. Redirects to other hosts are not followed and
+//! response bodies are capped.
+
+use std::time::Duration;
+
+#[cfg(feature = "http")]
+use crate::error::{PluginError, Result};
+
+/// Limits applied to every request a plugin makes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpLimits {
+    /// Time allowed for the whole request, including the body
+    pub timeout: Duration,
+    /// Largest response body accepted
+    pub max_body_bytes: usize,
+}
+
+impl Default for HttpLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_body_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
+/// Response to a plugin's HTTP request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// Value of the `Content-Type` header, if any
+    pub content_type: Option<String>,
+    /// Response body
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Check for a 2xx status
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Body decoded as UTF-8, replacing invalid sequences
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Body parsed as JSON
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+/// Check a host against an allowlist
+///
+/// Entries match a host exactly, case-insensitively; `*.example.com`
+/// matches any subdomain of `example.com` but not `example.com` itself.
+pub fn host_allowed(allowed_hosts: &[String], host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    allowed_hosts.iter().any(|entry| {
+        let entry = entry.to_ascii_lowercase();
+        match entry.strip_prefix("*.") {
+            Some(domain) => host
+                .strip_suffix(domain)
+                .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+            None => host == entry,
+        }
+    })
+}
+
+/// Check that a URL is http(s) and its host is on the allowlist
+#[cfg(feature = "http")]
+pub(crate) fn check_url(allowed_hosts: &[String], url: &str) -> Result<reqwest::Url> {
+    let url = reqwest::Url::parse(url)
+        .map_err(|e| PluginError::ValidationError(format!("Invalid URL '{}': {}", url, e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(PluginError::ValidationError(format!(
+            "Unsupported URL scheme '{}'",
+            url.scheme()
+        )));
+    }
+    match url.host_str() {
+        Some(host) if host_allowed(allowed_hosts, host) => Ok(url),
+        host => Err(PluginError::CapabilityDenied(format!(
+            "network (host '{}' is not allowed)",
+            host.unwrap_or_default()
+        ))),
+    }
+}
+
+/// Send a request and read the response within `limits`
+#[cfg(feature = "http")]
+pub(crate) async fn send(
+    allowed_hosts: &[String],
+    method: reqwest::Method,
+    url: reqwest::Url,
+    body: Option<(String, Vec<u8>)>,
+    limits: HttpLimits,
+) -> Result<HttpResponse> {
+    // Redirects are re-checked so an allowed host cannot forward elsewhere
+    let allowed = allowed_hosts.to_vec();
+    let client = reqwest::Client::builder()
+        .timeout(limits.timeout)
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            let host_ok = attempt
+                .url()
+                .host_str()
+                .is_some_and(|host| host_allowed(&allowed, host));
+            if attempt.previous().len() >= 5 {
+                attempt.error("too many redirects")
+            } else if host_ok {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        }))
+        .build()
+        .map_err(request_error)?;
+
+    let mut builder = client.request(method, url);
+    if let Some((content_type, body)) = body {
+        builder = builder
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body);
+    }
+    let mut response = builder.send().await.map_err(request_error)?;
+
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+        if body.len() + chunk.len() > limits.max_body_bytes {
+            return Err(PluginError::QuotaExceeded {
+                resource: "http body bytes".to_string(),
+                current: body.len() + chunk.len(),
+                limit: limits.max_body_bytes,
+            });
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(HttpResponse {
+        status,
+        content_type,
+        body,
+    })
+}
+
+#[cfg(feature = "http")]
+fn request_error(e: reqwest::Error) -> PluginError {
+    PluginError::Other(anyhow::anyhow!("HTTP request failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_allowed() {
+        let allowed = vec!["api.github.com".to_string(), "*.example.com".to_string()];
+
+        assert!(host_allowed(&allowed, "api.github.com"));
+        assert!(host_allowed(&allowed, "API.GitHub.com"));
+        assert!(!host_allowed(&allowed, "github.com"));
+        assert!(host_allowed(&allowed, "ci.example.com"));
+        assert!(host_allowed(&allowed, "a.b.example.com"));
+        assert!(!host_allowed(&allowed, "example.com"));
+        assert!(!host_allowed(&allowed, "evilexample.com"));
+        assert!(!host_allowed(&[], "api.github.com"));
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_requests_are_gated() {
+        use crate::{context::PluginSharedState, Capability, PluginContext};
+        use std::sync::Arc;
+
+        let state = Arc::new(parking_lot::Mutex::new(PluginSharedState::new(80, 24)));
+        let mut ctx = PluginContext::new(Default::default(), state, "weather");
+
+        // No network capability
+        let result = ctx.http_get("https://api.github.com/").await;
+        assert!(matches!(result, Err(PluginError::CapabilityDenied(_))));
+
+        // Capability, but the host is not on the allowlist
+        ctx.capabilities.insert(Capability::Network);
+        ctx.allowed_hosts = vec!["wttr.in".to_string()];
+        let result = ctx.http_get("https://api.github.com/").await;
+        assert!(matches!(result, Err(PluginError::CapabilityDenied(_))));
+
+        let result = ctx.http_get("file:///etc/passwd").await;
+        assert!(matches!(result, Err(PluginError::ValidationError(_))));
+    }
+}
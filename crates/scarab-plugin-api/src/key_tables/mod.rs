@@ -9,12 +9,14 @@ use std::time::Duration;
 
 pub mod defaults;
 pub mod leader;
+pub mod parse;
 pub mod stack;
 
 pub use defaults::{
     default_copy_mode_table, default_resize_mode_table, default_search_mode_table, KeyTableRegistry,
 };
 pub use leader::{LeaderKeyConfig, LeaderKeyState};
+pub use parse::{combo_label, parse_key_combo, parse_key_sequence, DIGIT_KEYS, LETTER_KEYS};
 pub use stack::{KeyTableActivation, KeyTableStack};
 
 /// A named key table containing key bindings
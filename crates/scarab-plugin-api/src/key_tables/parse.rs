@@ -0,0 +1,168 @@
+//! Parsing of key names used in configuration
+//!
+//! Key combinations are written as `+`-separated modifiers and a key, such
+//! as `Ctrl+Shift+C`, `Alt+F12` or `Leader+v`, and chords as combinations
+//! separated by spaces. The client reads `[keybindings]` with these, and
+//! the daemon reads plugin key bindings and their overrides.
+
+use super::{KeyCode, KeyCombo, KeyModifiers};
+
+/// Letter keys in alphabetical order
+pub const LETTER_KEYS: [KeyCode; 26] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+];
+/// Digit keys 0-9
+pub const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+/// F1-F12
+const FUNCTION_KEYS: [KeyCode; 12] = [
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+/// Parse a key combination such as `Ctrl+Shift+C`, `Leader+v` or `G`
+///
+/// A single uppercase letter implies Shift, as in the vim-style tables.
+pub fn parse_key_combo(s: &str) -> Option<KeyCombo> {
+    let mut mods = KeyModifiers::NONE;
+    let mut key = None;
+
+    for part in s.split('+') {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => mods |= KeyModifiers::CTRL,
+            "alt" | "option" | "opt" => mods |= KeyModifiers::ALT,
+            "shift" => mods |= KeyModifiers::SHIFT,
+            "super" | "cmd" | "win" => mods |= KeyModifiers::SUPER,
+            "leader" => mods |= KeyModifiers::LEADER,
+            _ if key.is_none() => {
+                let mut chars = part.chars();
+                if let (Some(c), None) = (chars.next(), chars.next()) {
+                    if c.is_ascii_uppercase() {
+                        mods |= KeyModifiers::SHIFT;
+                    }
+                }
+                key = Some(parse_key_name(part)?);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(KeyCombo::new(key?, mods))
+}
+
+/// Parse space-separated key combinations into a chord
+pub fn parse_key_sequence(s: &str) -> Option<Vec<KeyCombo>> {
+    let sequence = s
+        .split_whitespace()
+        .map(parse_key_combo)
+        .collect::<Option<Vec<_>>>()?;
+    (!sequence.is_empty()).then_some(sequence)
+}
+
+fn parse_key_name(name: &str) -> Option<KeyCode> {
+    // Accept both "a" and Bevy-style "KeyA" / "Digit1" names
+    let bare = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .filter(|rest| rest.len() == 1)
+        .unwrap_or(name);
+    let mut chars = bare.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return match c.to_ascii_lowercase() {
+            c @ 'a'..='z' => Some(LETTER_KEYS[(c as u8 - b'a') as usize]),
+            c @ '0'..='9' => Some(DIGIT_KEYS[(c as u8 - b'0') as usize]),
+            '/' => Some(KeyCode::Slash),
+            _ => None,
+        };
+    }
+
+    let lower = name.to_ascii_lowercase();
+    if let Some(n) = lower
+        .strip_prefix('f')
+        .and_then(|n| n.parse::<usize>().ok())
+    {
+        return FUNCTION_KEYS.get(n.checked_sub(1)?).copied();
+    }
+    match lower.as_str() {
+        "space" => Some(KeyCode::Space),
+        "enter" | "return" => Some(KeyCode::Enter),
+        "tab" => Some(KeyCode::Tab),
+        "escape" | "esc" => Some(KeyCode::Escape),
+        "backspace" => Some(KeyCode::Backspace),
+        "slash" => Some(KeyCode::Slash),
+        "left" | "arrowleft" => Some(KeyCode::Left),
+        "right" | "arrowright" => Some(KeyCode::Right),
+        "up" | "arrowup" => Some(KeyCode::Up),
+        "down" | "arrowdown" => Some(KeyCode::Down),
+        "home" => Some(KeyCode::Home),
+        "end" => Some(KeyCode::End),
+        "pageup" => Some(KeyCode::PageUp),
+        "pagedown" => Some(KeyCode::PageDown),
+        "insert" => Some(KeyCode::Insert),
+        "delete" => Some(KeyCode::Delete),
+        _ => None,
+    }
+}
+
+/// Human-readable form of a key combination, e.g. `Ctrl+Shift+KeyC`
+pub fn combo_label(combo: &KeyCombo) -> String {
+    let mut parts = Vec::new();
+    for (flag, name) in [
+        (KeyModifiers::LEADER, "Leader"),
+        (KeyModifiers::CTRL, "Ctrl"),
+        (KeyModifiers::ALT, "Alt"),
+        (KeyModifiers::SHIFT, "Shift"),
+        (KeyModifiers::SUPER, "Super"),
+    ] {
+        if combo.mods.contains(flag) {
+            parts.push(name.to_string());
+        }
+    }
+    parts.push(format!("{:?}", combo.key));
+    parts.join("+")
+}
@@ -11,22 +11,35 @@
 //! See [`host_bindings::HostBindings`] for the main entry point.
 
 pub mod config;
+pub mod config_schema;
 pub mod context;
 pub mod copy_mode;
+pub mod definition_map;
 pub mod delight;
 pub mod error;
 pub mod events;
+pub mod exec;
+pub mod fusabi_modules;
+pub mod fusabi_stdlib;
+pub mod history;
 pub mod host_bindings;
+pub mod http;
 pub mod key_tables;
 pub mod manifest;
 pub mod menu;
 pub mod navigation;
 pub mod object_model;
+pub mod package;
+pub mod permissions;
 pub mod plugin;
+pub mod settings;
 pub mod status_bar;
+pub mod storage;
+pub mod tasks;
 pub mod types;
 
 pub use config::{PluginConfig, PluginDiscovery};
+pub use config_schema::{ConfigField, ConfigSchema, ConfigValueType};
 pub use context::PluginContext;
 pub use copy_mode::{
     get_selection_bounds, normalize_selection, CopyModeCursor, CopyModeState, SearchDirection,
@@ -34,16 +47,20 @@ pub use copy_mode::{
 };
 pub use delight::{Achievement, PluginMood};
 pub use error::{PluginError, Result};
+pub use exec::{CommandOutput, ExecLimits};
 
 // Note: EventRegistry is deprecated for client-side use. See events module docs for migration guide.
+pub use definition_map::{DefinitionMap, SourceLocation};
 #[allow(deprecated)]
 pub use events::{
     EventArgs, EventData, EventHandler, EventRegistry, EventResult, EventType, HandlerEntry,
 };
+pub use history::TerminalHistory;
 pub use host_bindings::{
     HostBindingLimits, HostBindings, NavKeymap, NavStyle, ResourceUsage, DEFAULT_MAX_FOCUSABLES,
     DEFAULT_MAX_OVERLAYS, DEFAULT_MAX_STATUS_ITEMS, DEFAULT_RATE_LIMIT,
 };
+pub use http::{HttpLimits, HttpResponse};
 pub use key_tables::{
     ActivateKeyTableMode, ClipboardKind, CopyModeAction, Direction, KeyAction, KeyCode, KeyCombo,
     KeyModifiers, KeyTable, KeyTableActivation, KeyTableStack, LeaderKeyConfig, LeaderKeyState,
@@ -55,12 +72,27 @@ pub use navigation::{
     validate_focusable, NavigationExt, PluginFocusable, PluginFocusableAction,
     PluginNavCapabilities, ValidationError,
 };
-pub use object_model::{ObjectError, ObjectHandle, ObjectRegistry, ObjectType, RegistryEntry};
-pub use plugin::{Plugin, PluginMetadata};
+pub use object_model::{
+    ObjectError, ObjectHandle, ObjectRegistry, ObjectType, Objects, PaneHandle, RegistryEntry,
+    SessionHandle, TabHandle, Workspace,
+};
+pub use package::{Package, PACKAGE_EXTENSION};
+pub use permissions::{Permission, PermissionDecision, PermissionStore};
+pub use plugin::{
+    load_state, save_state, NativePluginCreate, OutputFilter, Plugin, PluginMetadata,
+    NATIVE_PLUGIN_ENTRY,
+};
+pub use settings::{ConfigScope, Settings};
 pub use status_bar::{
     AnsiColor, Color, RenderItem, StatusBarSide, StatusBarUpdate, UnderlineStyle,
 };
-pub use types::{Action, HookType, PluginInfo};
+pub use storage::{PluginStorage, DEFAULT_STORAGE_QUOTA};
+pub use tasks::{TaskId, TaskRegistry};
+pub use types::{
+    Action, HookType, MouseButton, MouseEvent, MouseEventKind, OverlayPanel, PanelAlign,
+    PanelBorder, PanelItem, PluginInfo, PluginKeyBinding, PluginMessage, PromptField,
+    PromptResponse,
+};
 
 /// Current plugin API version
 pub const API_VERSION: &str = "0.1.0";
@@ -61,6 +61,15 @@ pub struct PluginManifest {
     #[serde(default, rename = "required-modules")]
     pub required_modules: HashSet<FusabiModule>,
 
+    /// Hosts reachable over HTTP (`*.example.com` covers subdomains);
+    /// requires the `network` capability
+    #[serde(
+        default,
+        rename = "allowed-hosts",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub allowed_hosts: Vec<String>,
+
     /// Optional visual metadata
     #[serde(skip_serializing_if = "Option::is_none")]
     pub emoji: Option<String>,
@@ -108,6 +117,9 @@ pub enum Capability {
 
     /// Can register commands in command palette
     CommandRegistration,
+
+    /// Can run programs through `PluginContext::spawn_command`
+    Exec,
 }
 
 /// Fusabi stdlib modules that plugins can depend on
@@ -167,6 +179,22 @@ impl PluginManifest {
             )));
         }
 
+        if !self.allowed_hosts.is_empty() && !self.has_capability(&Capability::Network) {
+            return Err(ManifestError::ValidationFailed(
+                "allowed-hosts requires the network capability".to_string(),
+            ));
+        }
+        if let Some(host) = self
+            .allowed_hosts
+            .iter()
+            .find(|host| host.trim_start_matches("*.").is_empty() || host.contains('/'))
+        {
+            return Err(ManifestError::ValidationFailed(format!(
+                "Invalid allowed host: {:?}",
+                host
+            )));
+        }
+
         Ok(())
     }
 
@@ -207,6 +235,7 @@ impl Default for PluginManifest {
             min_scarab_version: "0.1.0".to_string(),
             capabilities: HashSet::new(),
             required_modules: HashSet::new(),
+            allowed_hosts: Vec::new(),
             emoji: None,
             color: None,
             catchphrase: None,
@@ -230,6 +259,7 @@ mod tests {
             min_scarab_version: "0.1.0".to_string(),
             capabilities: HashSet::new(),
             required_modules: HashSet::new(),
+            allowed_hosts: Vec::new(),
             emoji: None,
             color: None,
             catchphrase: None,
@@ -260,6 +290,57 @@ mod tests {
         assert!(!manifest.has_capability(&Capability::Network));
     }
 
+    #[test]
+    fn test_exec_capability_name() {
+        let manifest: PluginManifest = toml::from_str(
+            r#"
+            name = "git-status"
+            version = "0.1.0"
+            description = "Git branch segment"
+            author = "Test"
+            api-version = "0.1.0"
+            min-scarab-version = "0.1.0"
+            capabilities = ["exec"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(manifest.has_capability(&Capability::Exec));
+    }
+
+    #[test]
+    fn test_allowed_hosts() {
+        let manifest: PluginManifest = toml::from_str(
+            r#"
+            name = "ci-status"
+            version = "0.1.0"
+            description = "CI status segment"
+            author = "Test"
+            api-version = "0.1.0"
+            min-scarab-version = "0.1.0"
+            capabilities = ["network"]
+            allowed-hosts = ["api.github.com", "*.example.com"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.allowed_hosts, ["api.github.com", "*.example.com"]);
+        assert!(manifest.validate("0.1.0").is_ok());
+
+        // Hosts without the network capability are a mistake
+        let manifest = PluginManifest {
+            allowed_hosts: vec!["api.github.com".to_string()],
+            ..Default::default()
+        };
+        assert!(manifest.validate("0.1.0").is_err());
+
+        let mut manifest = PluginManifest {
+            allowed_hosts: vec!["https://api.github.com/".to_string()],
+            ..Default::default()
+        };
+        manifest.capabilities.insert(Capability::Network);
+        assert!(manifest.validate("0.1.0").is_err());
+    }
+
     #[test]
     fn test_module_requirements() {
         let mut manifest = PluginManifest::default();
@@ -283,6 +364,7 @@ mod tests {
             min_scarab_version: "0.1.0".to_string(),
             capabilities: HashSet::new(),
             required_modules: HashSet::new(),
+            allowed_hosts: Vec::new(),
             emoji: Some("🔌".to_string()),
             color: Some("#FF5733".to_string()),
             catchphrase: Some("Power to the plugins!".to_string()),
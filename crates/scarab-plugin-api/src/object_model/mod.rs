@@ -59,6 +59,7 @@ mod pane;
 mod registry;
 mod tab;
 mod window;
+mod workspace;
 
 pub use error::{ObjectError, Result};
 pub use handle::{ObjectHandle, ObjectType};
@@ -66,3 +67,4 @@ pub use pane::PaneProxy;
 pub use registry::{ObjectRegistry, RegistryEntry};
 pub use tab::TabProxy;
 pub use window::WindowProxy;
+pub use workspace::{Objects, PaneHandle, SessionHandle, TabHandle, Workspace};
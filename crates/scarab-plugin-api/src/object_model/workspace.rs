@@ -0,0 +1,438 @@
+//! Live handles to the daemon's sessions, tabs and panes
+//!
+//! [`PluginContext::objects`](crate::PluginContext::objects) snapshots the
+//! workspace through a [`Workspace`] implemented by the daemon. Methods that
+//! change the workspace queue a [`ControlMessage`] that the daemon handles
+//! like one sent by a client, once the hook returns.
+
+use crate::key_tables::SplitDirection;
+use crate::types::RemoteCommand;
+use parking_lot::Mutex;
+use scarab_protocol::{ControlMessage, PaneInfo, SessionInfo, TabInfo};
+use std::sync::Arc;
+
+/// Source of the current workspace layout
+///
+/// Tabs and panes are those of the default session, the one drawn in the
+/// main client window.
+pub trait Workspace: Send + Sync {
+    /// All sessions
+    fn sessions(&self) -> Vec<SessionInfo>;
+
+    /// Tabs of the default session
+    fn tabs(&self) -> Vec<TabInfo>;
+
+    /// Panes of a tab of the default session
+    fn panes(&self, tab_id: u64) -> Vec<PaneInfo>;
+}
+
+/// Queues workspace commands on behalf of one plugin
+#[derive(Clone)]
+struct CommandSink {
+    plugin_name: String,
+    commands: Arc<Mutex<Vec<RemoteCommand>>>,
+}
+
+impl CommandSink {
+    fn send(&self, message: ControlMessage) {
+        self.commands.lock().push(RemoteCommand::Control {
+            plugin_name: self.plugin_name.clone(),
+            message,
+        });
+    }
+}
+
+/// Snapshot of the workspace returned by `ctx.objects()`
+///
+/// Handles describe the workspace when they were taken; changes made
+/// through them show up in the next snapshot.
+#[derive(Clone)]
+pub struct Objects {
+    workspace: Option<Arc<dyn Workspace>>,
+    sink: CommandSink,
+}
+
+impl Objects {
+    pub(crate) fn new(
+        workspace: Option<Arc<dyn Workspace>>,
+        plugin_name: String,
+        commands: Arc<Mutex<Vec<RemoteCommand>>>,
+    ) -> Self {
+        Self {
+            workspace,
+            sink: CommandSink {
+                plugin_name,
+                commands,
+            },
+        }
+    }
+
+    /// All sessions
+    pub fn sessions(&self) -> Vec<SessionHandle> {
+        let Some(workspace) = &self.workspace else {
+            return Vec::new();
+        };
+        workspace
+            .sessions()
+            .into_iter()
+            .map(|info| SessionHandle {
+                info,
+                sink: self.sink.clone(),
+            })
+            .collect()
+    }
+
+    /// Tabs of the default session, ordered by ID
+    pub fn tabs(&self) -> Vec<TabHandle> {
+        let Some(workspace) = &self.workspace else {
+            return Vec::new();
+        };
+        let mut tabs: Vec<TabHandle> = workspace
+            .tabs()
+            .into_iter()
+            .map(|info| TabHandle {
+                info,
+                workspace: workspace.clone(),
+                sink: self.sink.clone(),
+            })
+            .collect();
+        tabs.sort_by_key(TabHandle::id);
+        tabs
+    }
+
+    /// Tab with the given ID
+    pub fn tab(&self, id: u64) -> Option<TabHandle> {
+        self.tabs().into_iter().find(|tab| tab.id() == id)
+    }
+
+    /// Tab shown in the main window
+    pub fn active_tab(&self) -> Option<TabHandle> {
+        self.tabs().into_iter().find(TabHandle::is_active)
+    }
+
+    /// Focused pane of the active tab
+    pub fn active_pane(&self) -> Option<PaneHandle> {
+        self.active_tab()?
+            .panes()
+            .into_iter()
+            .find(PaneHandle::is_focused)
+    }
+
+    /// Open a tab, which becomes the active one
+    pub fn new_tab(&self, title: Option<&str>) {
+        self.sink.send(ControlMessage::TabCreate {
+            title: title.map(Into::into),
+            domain: None,
+        });
+    }
+}
+
+/// A session
+#[derive(Clone)]
+pub struct SessionHandle {
+    info: SessionInfo,
+    sink: CommandSink,
+}
+
+impl SessionHandle {
+    pub fn id(&self) -> &str {
+        &self.info.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    /// Number of clients showing this session
+    pub fn attached_clients(&self) -> u32 {
+        self.info.attached_clients
+    }
+
+    pub fn rename(&self, name: &str) {
+        self.sink.send(ControlMessage::SessionRename {
+            id: self.info.id.clone(),
+            new_name: name.into(),
+        });
+    }
+}
+
+/// A tab of the default session
+#[derive(Clone)]
+pub struct TabHandle {
+    info: TabInfo,
+    workspace: Arc<dyn Workspace>,
+    sink: CommandSink,
+}
+
+impl TabHandle {
+    pub fn id(&self) -> u64 {
+        self.info.id
+    }
+
+    pub fn title(&self) -> &str {
+        &self.info.title
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.info.is_active
+    }
+
+    /// Panes of this tab, ordered by ID
+    pub fn panes(&self) -> Vec<PaneHandle> {
+        let mut panes: Vec<PaneHandle> = self
+            .workspace
+            .panes(self.info.id)
+            .into_iter()
+            .map(|info| PaneHandle {
+                info,
+                tab_id: self.info.id,
+                tab_active: self.info.is_active,
+                sink: self.sink.clone(),
+            })
+            .collect();
+        panes.sort_by_key(PaneHandle::id);
+        panes
+    }
+
+    pub fn set_title(&self, title: &str) {
+        self.sink.send(ControlMessage::TabRename {
+            tab_id: self.info.id,
+            new_title: title.into(),
+        });
+    }
+
+    /// Show this tab in the main window
+    pub fn activate(&self) {
+        self.sink.send(ControlMessage::TabSwitch {
+            tab_id: self.info.id,
+        });
+    }
+
+    /// Close the tab and all of its panes
+    pub fn close(&self) {
+        self.sink.send(ControlMessage::TabClose {
+            tab_id: self.info.id,
+        });
+    }
+}
+
+/// A pane of a tab in the default session
+///
+/// Pane IDs are only unique within their tab. Splitting, focusing or
+/// closing a pane of a background tab switches to that tab first.
+#[derive(Clone)]
+pub struct PaneHandle {
+    info: PaneInfo,
+    tab_id: u64,
+    tab_active: bool,
+    sink: CommandSink,
+}
+
+impl PaneHandle {
+    pub fn id(&self) -> u64 {
+        self.info.id
+    }
+
+    /// ID of the tab holding this pane
+    pub fn tab_id(&self) -> u64 {
+        self.tab_id
+    }
+
+    /// Size in cells
+    pub fn size(&self) -> (u16, u16) {
+        (self.info.width, self.info.height)
+    }
+
+    /// Position in cells within the tab
+    pub fn position(&self) -> (u16, u16) {
+        (self.info.x, self.info.y)
+    }
+
+    /// Whether this is the focused pane of its tab
+    pub fn is_focused(&self) -> bool {
+        self.info.is_focused
+    }
+
+    /// Type text into the pane's shell, as if pasted by the user
+    ///
+    /// Plugin input hooks are not run for this text.
+    pub fn send_text(&self, text: &str) {
+        self.sink.send(ControlMessage::PaneInput {
+            tab_id: self.tab_id,
+            pane_id: self.info.id,
+            data: text.as_bytes().to_vec(),
+        });
+    }
+
+    /// Split the pane, focusing the new one
+    pub fn split(&self, direction: SplitDirection) {
+        self.bring_to_front();
+        let direction = match direction {
+            SplitDirection::Horizontal => scarab_protocol::SplitDirection::Horizontal,
+            SplitDirection::Vertical => scarab_protocol::SplitDirection::Vertical,
+        };
+        self.sink.send(ControlMessage::PaneSplit {
+            pane_id: self.info.id,
+            direction,
+            domain: None,
+        });
+    }
+
+    pub fn focus(&self) {
+        self.bring_to_front();
+        self.sink.send(ControlMessage::PaneFocus {
+            pane_id: self.info.id,
+        });
+    }
+
+    /// Close the pane; the last pane of a tab cannot be closed
+    pub fn close(&self) {
+        self.bring_to_front();
+        self.sink.send(ControlMessage::PaneClose {
+            pane_id: self.info.id,
+        });
+    }
+
+    /// Pane commands act on the active tab
+    fn bring_to_front(&self) {
+        if !self.tab_active {
+            self.sink.send(ControlMessage::TabSwitch {
+                tab_id: self.tab_id,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestWorkspace;
+
+    impl Workspace for TestWorkspace {
+        fn sessions(&self) -> Vec<SessionInfo> {
+            vec![SessionInfo {
+                id: "s1".into(),
+                name: "default".into(),
+                created_at: 0,
+                last_attached: 0,
+                attached_clients: 1,
+                read_only_clients: 0,
+                read_only_by_default: false,
+                idle_secs: 0,
+            }]
+        }
+
+        fn tabs(&self) -> Vec<TabInfo> {
+            [(2, "logs", false), (1, "main", true)]
+                .into_iter()
+                .map(|(id, title, is_active)| TabInfo {
+                    id,
+                    title: title.into(),
+                    session_id: Some("s1".into()),
+                    is_active,
+                    pane_count: 2,
+                    has_activity: false,
+                })
+                .collect()
+        }
+
+        fn panes(&self, _tab_id: u64) -> Vec<PaneInfo> {
+            (1..=2)
+                .map(|id| PaneInfo {
+                    id,
+                    x: 0,
+                    y: 0,
+                    width: 80,
+                    height: 12,
+                    is_focused: id == 2,
+                })
+                .collect()
+        }
+    }
+
+    fn objects() -> (Objects, Arc<Mutex<Vec<RemoteCommand>>>) {
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let objects = Objects::new(
+            Some(Arc::new(TestWorkspace)),
+            "layout".into(),
+            commands.clone(),
+        );
+        (objects, commands)
+    }
+
+    fn messages(commands: &Mutex<Vec<RemoteCommand>>) -> Vec<ControlMessage> {
+        commands
+            .lock()
+            .drain(..)
+            .map(|cmd| match cmd {
+                RemoteCommand::Control {
+                    plugin_name,
+                    message,
+                } => {
+                    assert_eq!(plugin_name, "layout");
+                    message
+                }
+                other => panic!("Expected Control, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_navigation() {
+        let (objects, _) = objects();
+        let ids: Vec<u64> = objects.tabs().iter().map(TabHandle::id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(objects.active_tab().unwrap().title(), "main");
+        assert_eq!(objects.tab(2).unwrap().title(), "logs");
+
+        let pane = objects.active_pane().unwrap();
+        assert_eq!((pane.tab_id(), pane.id()), (1, 2));
+        assert_eq!(pane.size(), (80, 12));
+        assert_eq!(objects.sessions()[0].name(), "default");
+
+        let empty = Objects::new(None, "layout".into(), Default::default());
+        assert!(empty.tabs().is_empty());
+        assert!(empty.active_pane().is_none());
+    }
+
+    #[test]
+    fn test_methods_queue_control_messages() {
+        let (objects, commands) = objects();
+
+        objects.active_tab().unwrap().set_title("build");
+        let pane = objects.active_pane().unwrap();
+        pane.send_text("cargo test\r");
+        pane.split(SplitDirection::Vertical);
+
+        let sent = messages(&commands);
+        assert!(matches!(
+            &sent[0],
+            ControlMessage::TabRename { tab_id: 1, new_title } if new_title == "build"
+        ));
+        assert!(matches!(
+            &sent[1],
+            ControlMessage::PaneInput { tab_id: 1, pane_id: 2, data } if data == b"cargo test\r"
+        ));
+        assert!(matches!(
+            sent[2],
+            ControlMessage::PaneSplit {
+                pane_id: 2,
+                direction: scarab_protocol::SplitDirection::Vertical,
+                domain: None,
+            }
+        ));
+        assert_eq!(sent.len(), 3);
+    }
+
+    #[test]
+    fn test_background_pane_switches_tab_first() {
+        let (objects, commands) = objects();
+
+        objects.tab(2).unwrap().panes()[0].focus();
+
+        let sent = messages(&commands);
+        assert!(matches!(sent[0], ControlMessage::TabSwitch { tab_id: 2 }));
+        assert!(matches!(sent[1], ControlMessage::PaneFocus { pane_id: 1 }));
+    }
+}
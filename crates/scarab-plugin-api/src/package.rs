@@ -0,0 +1,184 @@
+//! `.scarabpkg` archives built from plugin projects
+//!
+//! A package is one bincode-encoded [`Package`]: a magic and version
+//! followed by named files. `scarab-plugin-compiler` packages a project as
+//!
+//! - `plugin.toml`, the build manifest as written
+//! - `<name>.fzb` and `<name>.fzb.defs`, the compiled plugin and where its
+//!   hooks are defined
+//! - the entry script and every file it `#load`s, under their project paths
+//! - the assets listed in the manifest
+//!
+//! The sources are there for reading and debugging; the `.fzb` already
+//! holds everything they compile to, and is what the daemon loads.
+
+use crate::error::{PluginError, Result};
+use crate::manifest::PluginManifest;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Leading bytes of every package
+pub const PACKAGE_MAGIC: &[u8; 4] = b"SPKG";
+
+/// Current package format version
+pub const PACKAGE_VERSION: u8 = 1;
+
+/// File extension of packages
+pub const PACKAGE_EXTENSION: &str = "scarabpkg";
+
+/// The plugin manifest, at the root of every package
+pub const MANIFEST_FILE: &str = "plugin.toml";
+
+/// A file stored in a package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageFile {
+    /// Path inside the package, with `/` separators
+    pub path: String,
+    pub contents: Vec<u8>,
+}
+
+/// A plugin and everything shipped with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Package {
+    magic: Vec<u8>,
+    version: u8,
+    pub files: Vec<PackageFile>,
+}
+
+impl Default for Package {
+    fn default() -> Self {
+        Self {
+            magic: PACKAGE_MAGIC.to_vec(),
+            version: PACKAGE_VERSION,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Package {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, failing if the path is already taken
+    pub fn add(&mut self, path: impl Into<String>, contents: Vec<u8>) -> Result<()> {
+        let path = path.into();
+        if self.get(&path).is_some() {
+            return Err(PluginError::ValidationError(format!(
+                "{} is packaged twice",
+                path
+            )));
+        }
+        self.files.push(PackageFile { path, contents });
+        Ok(())
+    }
+
+    /// Contents of the file at `path`
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.files
+            .iter()
+            .find(|file| file.path == path)
+            .map(|file| file.contents.as_slice())
+    }
+
+    /// Total size of the packaged files
+    pub fn content_len(&self) -> usize {
+        self.files.iter().map(|file| file.contents.len()).sum()
+    }
+
+    /// The packaged `plugin.toml`; its `[build]` table is ignored
+    pub fn manifest(&self) -> Result<PluginManifest> {
+        let source = self
+            .get(MANIFEST_FILE)
+            .ok_or_else(|| PluginError::LoadError(format!("Package has no {}", MANIFEST_FILE)))?;
+        let source = std::str::from_utf8(source).map_err(|e| {
+            PluginError::LoadError(format!("{} is not UTF-8: {}", MANIFEST_FILE, e))
+        })?;
+        Ok(toml::from_str(source)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self)
+            .map_err(|e| PluginError::Other(anyhow::anyhow!("Failed to serialize package: {}", e)))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let package: Package = bincode::deserialize(bytes)
+            .map_err(|e| PluginError::LoadError(format!("Invalid package: {}", e)))?;
+        if package.magic != PACKAGE_MAGIC {
+            return Err(PluginError::LoadError(
+                "Invalid package: bad magic bytes".to_string(),
+            ));
+        }
+        if package.version != PACKAGE_VERSION {
+            return Err(PluginError::LoadError(format!(
+                "Unsupported package version {} (expected {})",
+                package.version, PACKAGE_VERSION
+            )));
+        }
+        Ok(package)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_roundtrip() {
+        let mut package = Package::new();
+        package
+            .add("plugin.toml", b"name = \"x\"".to_vec())
+            .unwrap();
+        package.add("x.fzb", vec![1, 2, 3]).unwrap();
+        assert!(package.add("x.fzb", vec![]).is_err());
+
+        let decoded = Package::from_bytes(&package.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, package);
+        assert_eq!(decoded.get("x.fzb"), Some(&[1u8, 2, 3][..]));
+        assert_eq!(decoded.content_len(), 13);
+
+        let mut bytes = package.to_bytes().unwrap();
+        // The magic follows bincode's u64 length prefix
+        bytes[8] = b'X';
+        assert!(Package::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_manifest_ignores_build_table() {
+        let mut package = Package::new();
+        assert!(package.manifest().is_err());
+
+        let manifest = r#"
+name = "weather"
+version = "0.1.0"
+description = "Forecasts"
+author = "Someone"
+api-version = "0.1.0"
+min-scarab-version = "0.1.0"
+allowed-hosts = ["api.weather.example"]
+
+[build]
+entry = "src/main.fsx"
+"#;
+        package
+            .add(MANIFEST_FILE, manifest.as_bytes().to_vec())
+            .unwrap();
+        let manifest = package.manifest().unwrap();
+        assert_eq!(manifest.name, "weather");
+        assert_eq!(manifest.allowed_hosts, vec!["api.weather.example"]);
+    }
+}
@@ -0,0 +1,376 @@
+//! Decisions the user made about plugins' sensitive capabilities
+//!
+//! Granting [`Capability::Network`], [`Capability::Exec`],
+//! [`Capability::Clipboard`] or [`Capability::TerminalControl`] in a
+//! plugin's config only makes it eligible: the first time the plugin uses
+//! one, the client asks the user to allow it once, allow it always, or deny
+//! it. "Always" and "deny" are saved per plugin in
+//! `plugin-permissions.json` in Scarab's data directory; "once" covers
+//! the plugin's next use only. A question nobody answers within
+//! [`PROMPT_TIMEOUT`] is denied until the daemon restarts.
+
+use crate::error::{PluginError, Result};
+use crate::manifest::Capability;
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+pub use scarab_protocol::PermissionDecision;
+
+/// Capabilities the user is asked about on first use
+pub const PROMPTED_CAPABILITIES: [Capability; 4] = [
+    Capability::Network,
+    Capability::Exec,
+    Capability::Clipboard,
+    Capability::TerminalControl,
+];
+
+/// How long a question waits for an answer before it counts as denied
+pub const PROMPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What the user has said about a plugin using a capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Granted,
+    Denied,
+    /// Not asked yet, or the question is still open
+    Undecided,
+}
+
+/// A question waiting for the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionRequest {
+    pub prompt_id: u64,
+    pub plugin_name: String,
+    pub capability: Capability,
+}
+
+/// Saved and session permission decisions, shared by every plugin context
+#[derive(Debug, Default)]
+pub struct PermissionStore {
+    /// Backing file; `None` keeps decisions in memory only
+    path: Option<PathBuf>,
+    /// plugin -> capability -> allowed, as saved on disk
+    saved: Mutex<BTreeMap<String, BTreeMap<String, bool>>>,
+    /// Allowed for the next use only
+    once: Mutex<HashSet<(String, Capability)>>,
+    /// Questions nobody answered, denied until the daemon restarts
+    unanswered: Mutex<HashSet<(String, Capability)>>,
+    /// Open questions and when they were asked
+    pending: Mutex<HashMap<u64, (String, Capability, Instant)>>,
+    next_prompt_id: AtomicU64,
+}
+
+impl PermissionStore {
+    /// File holding saved decisions, from the environment
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Self::path_in(&scarab_platform::Paths::from_env().data_dir))
+    }
+
+    /// File holding saved decisions under `data_dir`
+    pub fn path_in(data_dir: &Path) -> PathBuf {
+        data_dir.join("plugin-permissions.json")
+    }
+
+    /// Load saved decisions from `path`
+    ///
+    /// A missing file has no decisions; it is created on the first
+    /// "always" or "deny".
+    pub fn open(path: &Path) -> Result<Self> {
+        let saved = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                PluginError::Other(anyhow::anyhow!(
+                    "Corrupt plugin permissions {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            saved: Mutex::new(saved),
+            ..Default::default()
+        })
+    }
+
+    /// Store that forgets every decision when dropped
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Whether `plugin` may use `capability` right now
+    ///
+    /// Capabilities outside [`PROMPTED_CAPABILITIES`] are always granted;
+    /// the plugin's config still decides whether it has them at all. A
+    /// one-time grant is used up by the check that returns it.
+    pub fn check(&self, plugin: &str, capability: &Capability) -> Permission {
+        if !PROMPTED_CAPABILITIES.contains(capability) {
+            return Permission::Granted;
+        }
+        let key = (plugin.to_string(), capability.clone());
+        if self.once.lock().remove(&key) {
+            return Permission::Granted;
+        }
+        if self.unanswered.lock().contains(&key) {
+            return Permission::Denied;
+        }
+        match self
+            .saved
+            .lock()
+            .get(plugin)
+            .and_then(|caps| caps.get(capability_key(capability)))
+        {
+            Some(true) => Permission::Granted,
+            Some(false) => Permission::Denied,
+            None => Permission::Undecided,
+        }
+    }
+
+    /// Open a question for the user
+    ///
+    /// Returns `None` if the same question is already waiting, so a plugin
+    /// retrying in a loop does not stack up prompts.
+    pub fn request(&self, plugin: &str, capability: &Capability) -> Option<PermissionRequest> {
+        let mut pending = self.pending.lock();
+        if pending
+            .values()
+            .any(|(name, cap, _)| name == plugin && cap == capability)
+        {
+            return None;
+        }
+        let prompt_id = self.next_prompt_id.fetch_add(1, Ordering::Relaxed) + 1;
+        pending.insert(
+            prompt_id,
+            (plugin.to_string(), capability.clone(), Instant::now()),
+        );
+        Some(PermissionRequest {
+            prompt_id,
+            plugin_name: plugin.to_string(),
+            capability: capability.clone(),
+        })
+    }
+
+    /// Record the user's answer to a question
+    ///
+    /// Returns the question answered, or `None` for an unknown prompt.
+    /// "Always" and "deny" are written to disk before returning.
+    pub fn resolve(
+        &self,
+        prompt_id: u64,
+        decision: PermissionDecision,
+    ) -> Result<Option<PermissionRequest>> {
+        let Some((plugin, capability, _)) = self.pending.lock().remove(&prompt_id) else {
+            return Ok(None);
+        };
+
+        let allowed = match decision {
+            PermissionDecision::AllowOnce => {
+                self.once
+                    .lock()
+                    .insert((plugin.clone(), capability.clone()));
+                None
+            }
+            PermissionDecision::AllowAlways => Some(true),
+            PermissionDecision::Deny => Some(false),
+        };
+        if let Some(allowed) = allowed {
+            let mut saved = self.saved.lock();
+            saved
+                .entry(plugin.clone())
+                .or_default()
+                .insert(capability_key(&capability).to_string(), allowed);
+            self.save(&saved)?;
+        }
+
+        Ok(Some(PermissionRequest {
+            prompt_id,
+            plugin_name: plugin,
+            capability,
+        }))
+    }
+
+    /// Deny questions asked more than [`PROMPT_TIMEOUT`] before `now`
+    ///
+    /// Returns the questions closed, so the prompts can be taken down.
+    pub fn expire(&self, now: Instant) -> Vec<PermissionRequest> {
+        let mut expired = Vec::new();
+        self.pending
+            .lock()
+            .retain(|&prompt_id, (plugin, capability, asked_at)| {
+                if now.saturating_duration_since(*asked_at) < PROMPT_TIMEOUT {
+                    return true;
+                }
+                expired.push(PermissionRequest {
+                    prompt_id,
+                    plugin_name: plugin.clone(),
+                    capability: capability.clone(),
+                });
+                false
+            });
+        let mut unanswered = self.unanswered.lock();
+        for request in &expired {
+            unanswered.insert((request.plugin_name.clone(), request.capability.clone()));
+        }
+        expired
+    }
+
+    /// Forget every decision about a plugin, so the user is asked again
+    pub fn reset(&self, plugin: &str) -> Result<()> {
+        self.once.lock().retain(|(name, _)| name != plugin);
+        self.unanswered.lock().retain(|(name, _)| name != plugin);
+        let mut saved = self.saved.lock();
+        if saved.remove(plugin).is_some() {
+            self.save(&saved)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, saved: &BTreeMap<String, BTreeMap<String, bool>>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(saved).map_err(|e| {
+            PluginError::Other(anyhow::anyhow!("Unserializable permissions: {}", e))
+        })?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+/// Name of a capability as shown to the user and saved on disk
+pub fn capability_key(capability: &Capability) -> &'static str {
+    match capability {
+        Capability::OutputFiltering => "output-filtering",
+        Capability::InputFiltering => "input-filtering",
+        Capability::ShellExecution => "shell-execution",
+        Capability::FileSystem => "file-system",
+        Capability::Network => "network",
+        Capability::Clipboard => "clipboard",
+        Capability::ProcessSpawn => "process-spawn",
+        Capability::TerminalControl => "terminal-control",
+        Capability::UiOverlay => "ui-overlay",
+        Capability::MenuRegistration => "menu-registration",
+        Capability::CommandRegistration => "command-registration",
+        Capability::Exec => "exec",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ask_once_per_question() {
+        let store = PermissionStore::in_memory();
+        assert_eq!(
+            store.check("git-status", &Capability::UiOverlay),
+            Permission::Granted
+        );
+        assert_eq!(
+            store.check("git-status", &Capability::Exec),
+            Permission::Undecided
+        );
+
+        let request = store.request("git-status", &Capability::Exec).unwrap();
+        assert!(store.request("git-status", &Capability::Exec).is_none());
+        assert!(store.request("git-status", &Capability::Network).is_some());
+
+        store
+            .resolve(request.prompt_id, PermissionDecision::AllowOnce)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            store.check("git-status", &Capability::Exec),
+            Permission::Granted
+        );
+        // "Once" is used up by that check
+        assert_eq!(
+            store.check("git-status", &Capability::Exec),
+            Permission::Undecided
+        );
+        assert!(store
+            .resolve(request.prompt_id, PermissionDecision::Deny)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_unanswered_prompt_is_denied() {
+        let store = PermissionStore::in_memory();
+        let request = store
+            .request("panes", &Capability::TerminalControl)
+            .unwrap();
+
+        assert!(store.expire(Instant::now()).is_empty());
+        let expired = store.expire(Instant::now() + PROMPT_TIMEOUT);
+        assert_eq!(expired, vec![request.clone()]);
+        assert_eq!(
+            store.check("panes", &Capability::TerminalControl),
+            Permission::Denied
+        );
+        // A late answer finds nothing to resolve
+        assert!(store
+            .resolve(request.prompt_id, PermissionDecision::AllowAlways)
+            .unwrap()
+            .is_none());
+
+        store.reset("panes").unwrap();
+        assert_eq!(
+            store.check("panes", &Capability::TerminalControl),
+            Permission::Undecided
+        );
+    }
+
+    #[test]
+    fn test_always_and_deny_persist() {
+        let dir = std::env::temp_dir().join(format!("scarab-permissions-{}", std::process::id()));
+        let path = dir.join("plugin-permissions.json");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let store = PermissionStore::open(&path).unwrap();
+        let exec = store.request("weather", &Capability::Exec).unwrap();
+        let network = store.request("weather", &Capability::Network).unwrap();
+        let clipboard = store.request("weather", &Capability::Clipboard).unwrap();
+        store
+            .resolve(exec.prompt_id, PermissionDecision::Deny)
+            .unwrap();
+        store
+            .resolve(network.prompt_id, PermissionDecision::AllowAlways)
+            .unwrap();
+        store
+            .resolve(clipboard.prompt_id, PermissionDecision::AllowOnce)
+            .unwrap();
+
+        let reopened = PermissionStore::open(&path).unwrap();
+        assert_eq!(
+            reopened.check("weather", &Capability::Exec),
+            Permission::Denied
+        );
+        assert_eq!(
+            reopened.check("weather", &Capability::Network),
+            Permission::Granted
+        );
+        assert_eq!(
+            reopened.check("weather", &Capability::Clipboard),
+            Permission::Undecided
+        );
+
+        reopened.reset("weather").unwrap();
+        assert_eq!(
+            PermissionStore::open(&path)
+                .unwrap()
+                .check("weather", &Capability::Network),
+            Permission::Undecided
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,12 +1,17 @@
 //! Core plugin trait and metadata definitions
 
 use crate::{
+    config_schema::ConfigSchema,
     context::PluginContext,
-    error::Result,
+    error::{PluginError, Result},
+    key_tables::KeyCombo,
+    manifest::Capability,
     menu::MenuItem,
-    types::{Action, ModalItem},
+    types::{Action, ModalItem, MouseEvent, PluginKeyBinding, PluginMessage, PromptResponse},
 };
 use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashSet;
 
 /// Main plugin trait that all plugins must implement
 ///
@@ -45,6 +50,28 @@ pub trait Plugin: Send + Sync {
         Vec::new()
     }
 
+    /// Get keys that run this plugin's commands
+    ///
+    /// The daemon runs a bound command through
+    /// [`on_remote_command`](Plugin::on_remote_command) instead of sending
+    /// the key to the shell. When two plugins bind the same key, the one
+    /// loaded first keeps it. Users can move or remove a binding under
+    /// `[keybindings.plugins]`, and keys bound in their own `[keybindings]`
+    /// always win.
+    fn get_keybindings(&self) -> Vec<PluginKeyBinding> {
+        Vec::new()
+    }
+
+    /// Describe the settings this plugin reads from `[plugins.settings.<name>]`
+    ///
+    /// The daemon checks the user's table against the schema and hands the
+    /// result, with defaults filled in, to the plugin as
+    /// [`PluginContext::config`]. The default empty schema passes the table
+    /// through unchecked.
+    fn config_schema(&self) -> ConfigSchema {
+        ConfigSchema::default()
+    }
+
     /// Called when the plugin is loaded
     ///
     /// This is where plugins should initialize their state and resources.
@@ -73,6 +100,29 @@ pub trait Plugin: Send + Sync {
         Ok(Action::Continue)
     }
 
+    /// Hook called for each key press in the user's input
+    ///
+    /// The daemon decodes input once, including CSI u sequences, and calls
+    /// this before [`Plugin::on_input`]. `Action::Modify` replaces the key's
+    /// bytes (empty to swallow it) and ends the hook for that key;
+    /// `Action::Stop` passes the key on without calling remaining plugins.
+    /// Bytes that are not a key press, such as pasted text or punctuation
+    /// without a [`KeyCode`](crate::key_tables::KeyCode), only reach
+    /// `on_input`.
+    async fn on_key(&mut self, _key: KeyCombo, _ctx: &PluginContext) -> Result<Action> {
+        Ok(Action::Continue)
+    }
+
+    /// Hook called for mouse presses, releases, drags and scrolls
+    ///
+    /// Positions are in grid cells and presses carry a click count, so
+    /// plugins need not parse mouse escape sequences in `on_input`.
+    /// `Action::Stop` keeps the event from remaining plugins; the client's
+    /// own handling, such as selection, still applies.
+    async fn on_mouse(&mut self, _event: &MouseEvent, _ctx: &PluginContext) -> Result<Action> {
+        Ok(Action::Continue)
+    }
+
     /// Hook called before a command is executed
     async fn on_pre_command(&mut self, _command: &str, _ctx: &PluginContext) -> Result<Action> {
         Ok(Action::Continue)
@@ -103,12 +153,127 @@ pub trait Plugin: Send + Sync {
         Ok(())
     }
 
+    /// Hook called with messages on topics this plugin subscribes to
+    ///
+    /// See [`PluginMetadata::with_subscription`] and [`PluginContext::emit`].
+    async fn on_message(&mut self, _message: &PluginMessage, _ctx: &PluginContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Hook called when the user answers or cancels a prompt this plugin opened
+    ///
+    /// See [`PluginContext::show_input_prompt`] and [`PluginContext::show_form`].
+    async fn on_prompt_response(
+        &mut self,
+        _response: &PromptResponse,
+        _ctx: &PluginContext,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Hook called when the user picks an item of one of this plugin's panels
+    ///
+    /// See [`HostBindings::spawn_panel`](crate::host_bindings::HostBindings::spawn_panel).
+    async fn on_panel_item_selected(
+        &mut self,
+        _overlay_id: u64,
+        _item_id: &str,
+        _ctx: &PluginContext,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// Hook called when a remote command is selected/triggered by the client
     ///
     /// This is called when a user selects a menu item with `MenuAction::Remote(id)`.
     async fn on_remote_command(&mut self, _id: &str, _ctx: &PluginContext) -> Result<()> {
         Ok(())
     }
+
+    /// Hook called when the user's settings for this plugin change
+    ///
+    /// `ctx.config()` holds the new settings. Edits that fail validation
+    /// are reported to the user and the previous settings stay in effect,
+    /// without calling this hook.
+    async fn on_config_changed(&mut self, _ctx: &PluginContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Capture plugin state before a hot reload
+    ///
+    /// The returned bytes are handed to [`Plugin::restore_state`] on the freshly
+    /// loaded instance. Return `None` if the plugin has nothing worth keeping.
+    /// [`save_state`] and [`load_state`] encode any serde type, which keeps
+    /// the snapshot readable by a rebuilt library whose layout changed.
+    fn snapshot_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restore state captured by [`Plugin::snapshot_state`] after a hot reload
+    async fn restore_state(&mut self, _state: &[u8], _ctx: &PluginContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Encode plugin state for [`Plugin::snapshot_state`]
+///
+/// # Example
+///
+/// ```rust,ignore
+/// fn snapshot_state(&self) -> Option<Vec<u8>> {
+///     save_state(&self.counts)
+/// }
+///
+/// async fn restore_state(&mut self, state: &[u8], _ctx: &PluginContext) -> Result<()> {
+///     self.counts = load_state(state)?;
+///     Ok(())
+/// }
+/// ```
+pub fn save_state<T: Serialize>(state: &T) -> Option<Vec<u8>> {
+    match serde_json::to_vec(state) {
+        Ok(bytes) => Some(bytes),
+        Err(e) => {
+            log::warn!("Failed to encode plugin state: {}", e);
+            None
+        }
+    }
+}
+
+/// Decode state encoded by [`save_state`] in [`Plugin::restore_state`]
+///
+/// Fails if the snapshot no longer matches `T`, e.g. after a field was
+/// renamed; the plugin then starts with its fresh state.
+pub fn load_state<T: DeserializeOwned>(state: &[u8]) -> Result<T> {
+    serde_json::from_slice(state)
+        .map_err(|e| PluginError::Other(anyhow::anyhow!("Invalid state snapshot: {}", e)))
+}
+
+/// Symbol exported by native (`.so`/`.dylib`/`.dll`) plugins
+///
+/// Use [`declare_plugin!`](crate::declare_plugin) to export it.
+pub const NATIVE_PLUGIN_ENTRY: &[u8] = b"_scarab_plugin_create\0";
+
+/// Signature of the native plugin entry point
+///
+/// The returned pointer is a leaked `Box<Box<dyn Plugin>>` owned by the host.
+pub type NativePluginCreate = unsafe extern "C" fn() -> *mut Box<dyn Plugin>;
+
+/// Export a plugin type from a native `cdylib` crate
+///
+/// # Example
+///
+/// ```rust,ignore
+/// scarab_plugin_api::declare_plugin!(MyPlugin::new);
+/// ```
+#[macro_export]
+macro_rules! declare_plugin {
+    ($constructor:path) => {
+        #[no_mangle]
+        pub extern "C" fn _scarab_plugin_create() -> *mut Box<dyn $crate::Plugin> {
+            let plugin: Box<dyn $crate::Plugin> = Box::new($constructor());
+            Box::into_raw(Box::new(plugin))
+        }
+    };
 }
 
 /// Plugin metadata with personality
@@ -134,6 +299,24 @@ pub struct PluginMetadata {
     pub color: Option<String>,
     /// Plugin catchphrase or motto
     pub catchphrase: Option<String>,
+    /// Message bus topics this plugin receives in `on_message`
+    pub subscriptions: Vec<String>,
+    /// Output lines this plugin receives in `on_output`; empty means all
+    pub output_filters: Vec<OutputFilter>,
+    /// Capabilities the plugin asks for; `plugins.toml` can only grant these
+    pub capabilities: HashSet<Capability>,
+}
+
+/// Which output lines a plugin's `on_output` hook is called for
+///
+/// A plugin with filters only sees lines matching at least one of them, so
+/// the daemon can skip it for the rest of the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputFilter {
+    /// Lines starting with this text
+    Prefix(String),
+    /// Lines containing a match of this regular expression
+    Regex(String),
 }
 
 impl PluginMetadata {
@@ -155,6 +338,9 @@ impl PluginMetadata {
             emoji: None,
             color: None,
             catchphrase: None,
+            subscriptions: Vec::new(),
+            output_filters: Vec::new(),
+            capabilities: HashSet::new(),
         }
     }
 
@@ -194,6 +380,55 @@ impl PluginMetadata {
         self
     }
 
+    /// Subscribe to a message bus topic
+    ///
+    /// A trailing `*` matches every topic with that prefix, so `zones.*`
+    /// receives all messages published by the zones plugin.
+    pub fn with_subscription(mut self, topic: impl Into<String>) -> Self {
+        self.subscriptions.push(topic.into());
+        self
+    }
+
+    /// Only call `on_output` for lines starting with `prefix`
+    ///
+    /// Can be combined with other filters; a line matching any of them is
+    /// delivered.
+    pub fn with_output_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.output_filters
+            .push(OutputFilter::Prefix(prefix.into()));
+        self
+    }
+
+    /// Only call `on_output` for lines matching the regular expression
+    /// `pattern`
+    ///
+    /// The pattern is compiled when the plugin is loaded; an invalid one
+    /// stops the plugin from loading.
+    pub fn with_output_regex(mut self, pattern: impl Into<String>) -> Self {
+        self.output_filters
+            .push(OutputFilter::Regex(pattern.into()));
+        self
+    }
+
+    /// Declare a capability the plugin needs
+    ///
+    /// The user still has to grant it in `plugins.toml`; a grant for a
+    /// capability the plugin never declared is ignored.
+    pub fn with_capability(mut self, capability: Capability) -> Self {
+        self.capabilities.insert(capability);
+        self
+    }
+
+    /// Check whether this plugin subscribes to a topic
+    pub fn subscribes_to(&self, topic: &str) -> bool {
+        self.subscriptions
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => topic.starts_with(prefix),
+                None => pattern == topic,
+            })
+    }
+
     /// Get display name with emoji if available
     pub fn display_name(&self) -> String {
         if let Some(emoji) = &self.emoji {
@@ -219,6 +454,49 @@ impl PluginMetadata {
         plugin_version.major == current_version.major
             && plugin_version.minor <= current_version.minor
     }
+
+    /// Check that this plugin can run on the given host
+    ///
+    /// Refuses plugins built against an incompatible plugin API (see
+    /// [`is_compatible`](Self::is_compatible)) and plugins whose
+    /// `min_scarab_version` is newer than `scarab_version`.
+    pub fn check_compatibility(&self, api_version: &str, scarab_version: &str) -> Result<()> {
+        use semver::Version;
+
+        if Version::parse(&self.api_version).is_err() {
+            return Err(PluginError::InvalidMetadata(format!(
+                "api_version '{}' is not a semantic version",
+                self.api_version
+            )));
+        }
+        if !self.is_compatible(api_version) {
+            return Err(PluginError::VersionIncompatible {
+                required: format!("plugin API {}", api_version),
+                actual: format!("plugin API {}", self.api_version),
+            });
+        }
+
+        let min_scarab = Version::parse(&self.min_scarab_version).map_err(|_| {
+            PluginError::InvalidMetadata(format!(
+                "min_scarab_version '{}' is not a semantic version",
+                self.min_scarab_version
+            ))
+        })?;
+        let current = Version::parse(scarab_version).map_err(|_| {
+            PluginError::InvalidMetadata(format!(
+                "Scarab version '{}' is not a semantic version",
+                scarab_version
+            ))
+        })?;
+        if min_scarab > current {
+            return Err(PluginError::VersionIncompatible {
+                required: format!("Scarab >= {}", min_scarab),
+                actual: format!("Scarab {}", current),
+            });
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -236,6 +514,29 @@ mod tests {
         assert!(!meta.is_compatible("0.0.1"));
     }
 
+    #[test]
+    fn test_check_compatibility() {
+        let meta = PluginMetadata::new("test", "1.0.0", "Test plugin", "Test Author")
+            .with_api_version("0.1.0")
+            .with_min_scarab_version("0.3.0");
+
+        assert!(meta.check_compatibility("0.1.0", "0.3.3").is_ok());
+        assert!(matches!(
+            meta.check_compatibility("0.1.0", "0.2.9"),
+            Err(PluginError::VersionIncompatible { .. })
+        ));
+        assert!(matches!(
+            meta.check_compatibility("1.0.0", "0.3.3"),
+            Err(PluginError::VersionIncompatible { .. })
+        ));
+
+        let meta = meta.with_min_scarab_version("latest");
+        assert!(matches!(
+            meta.check_compatibility("0.1.0", "0.3.3"),
+            Err(PluginError::InvalidMetadata(_))
+        ));
+    }
+
     #[test]
     fn test_display_name_with_emoji() {
         let meta =
@@ -244,10 +545,32 @@ mod tests {
         assert_eq!(meta.display_name(), "🚀 awesome-plugin");
     }
 
+    #[test]
+    fn test_subscriptions() {
+        let meta = PluginMetadata::new("notify", "1.0.0", "Notifier", "Dev")
+            .with_subscription("zones.command_failed")
+            .with_subscription("git.*");
+
+        assert!(meta.subscribes_to("zones.command_failed"));
+        assert!(!meta.subscribes_to("zones.command_finished"));
+        assert!(meta.subscribes_to("git.branch_changed"));
+        assert!(!meta.subscribes_to("gitlab.push"));
+    }
+
     #[test]
     fn test_display_name_without_emoji() {
         let meta = PluginMetadata::new("plain-plugin", "1.0.0", "Plain plugin", "Dev");
 
         assert_eq!(meta.display_name(), "plain-plugin");
     }
+
+    #[test]
+    fn test_state_round_trip() {
+        let counts: std::collections::HashMap<String, u32> = [("ls".to_string(), 3)].into();
+        let bytes = save_state(&counts).unwrap();
+        let restored: std::collections::HashMap<String, u32> = load_state(&bytes).unwrap();
+        assert_eq!(restored, counts);
+
+        assert!(load_state::<Vec<u32>>(&bytes).is_err());
+    }
 }
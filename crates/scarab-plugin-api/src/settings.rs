@@ -0,0 +1,166 @@
+//! Read access to the user's settings for plugins
+//!
+//! The daemon implements [`Settings`] over its running configuration so
+//! that plugins can read a setting with
+//! [`PluginContext::get_setting`](crate::PluginContext::get_setting).
+//! Changes go through
+//! [`PluginContext::set_setting`](crate::PluginContext::set_setting), which
+//! the daemon checks like a change sent by a client.
+//! [`PluginContext::override_setting`](crate::PluginContext::override_setting)
+//! changes a setting for one tab or pane only.
+//!
+//! Plugins may only change how the terminal looks, in the sections listed
+//! in [`PLUGIN_SETTINGS`], and their own `plugins.config.<name>` table, and
+//! may not write their changes to `config.toml`. Settings such as
+//! `terminal.default_shell` stay the user's to change.
+
+pub use scarab_protocol::ConfigScope;
+
+/// Config sections plugins may change
+pub const PLUGIN_SETTINGS: &[&str] = &["colors", "font", "ui"];
+
+/// Check that plugin `plugin_name` may change the setting at `key`
+///
+/// Fails with the reason if `key` is outside [`PLUGIN_SETTINGS`] and the
+/// plugin's own `plugins.config.<name>` table, or if the change would be
+/// persisted.
+pub fn check_plugin_setting(
+    plugin_name: &str,
+    key: &str,
+    persist: bool,
+) -> std::result::Result<(), String> {
+    if persist {
+        return Err(format!(
+            "{} may not save settings to config.toml",
+            plugin_name
+        ));
+    }
+    let own = format!("plugins.config.{}", plugin_name);
+    let allowed = PLUGIN_SETTINGS
+        .iter()
+        .copied()
+        .chain([own.as_str()])
+        .any(|section| {
+            key.strip_prefix(section)
+                .is_some_and(|rest| rest.starts_with('.'))
+        });
+    if allowed {
+        Ok(())
+    } else {
+        Err(format!("{} may not change `{}`", plugin_name, key))
+    }
+}
+
+/// Source of the current configuration
+///
+/// Keys are dotted paths into `config.toml`, such as `font.size` or
+/// `colors.theme`.
+pub trait Settings: Send + Sync {
+    /// The setting's value as TOML, or why there is none
+    fn get(&self, key: &str) -> std::result::Result<String, String>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::PluginSharedState, manifest::Capability, types::RemoteCommand, PluginContext,
+    };
+    use parking_lot::Mutex;
+    use scarab_protocol::ControlMessage;
+    use std::sync::Arc;
+
+    struct Fixed;
+
+    impl Settings for Fixed {
+        fn get(&self, key: &str) -> std::result::Result<String, String> {
+            match key {
+                "font.size" => Ok("14.0".to_string()),
+                _ => Err(format!("Not found: setting `{}`", key)),
+            }
+        }
+    }
+
+    fn make_ctx() -> PluginContext {
+        let state = Arc::new(Mutex::new(PluginSharedState::new(80, 24)));
+        PluginContext::new(Default::default(), state, "theme-cycler").with_settings(Arc::new(Fixed))
+    }
+
+    #[test]
+    fn test_get_setting() {
+        let ctx = make_ctx();
+        assert_eq!(ctx.get_setting("font.size").unwrap(), "14.0");
+        assert!(ctx.get_setting("font.sise").is_err());
+    }
+
+    #[test]
+    fn test_override_setting() {
+        let mut ctx = make_ctx();
+        assert!(ctx
+            .override_setting(ConfigScope::Tab(2), "font.size", Some("20"))
+            .is_err());
+
+        ctx.capabilities.insert(Capability::TerminalControl);
+        assert!(ctx
+            .override_setting(ConfigScope::Tab(2), "terminal.default_shell", Some("sh"))
+            .is_err());
+        ctx.override_setting(ConfigScope::Tab(2), "font.size", Some("20"))
+            .unwrap();
+        ctx.clear_overrides(ConfigScope::Pane(5)).unwrap();
+        assert!(matches!(
+            ctx.commands.lock().as_slice(),
+            [
+                RemoteCommand::Control {
+                    message: ControlMessage::ConfigOverride {
+                        scope: ConfigScope::Tab(2),
+                        value: Some(_),
+                        ..
+                    },
+                    ..
+                },
+                RemoteCommand::Control {
+                    message: ControlMessage::ConfigOverrideClear {
+                        scope: ConfigScope::Pane(5)
+                    },
+                    ..
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_check_plugin_setting() {
+        assert!(check_plugin_setting("theme-cycler", "colors.theme", false).is_ok());
+        assert!(
+            check_plugin_setting("theme-cycler", "plugins.config.theme-cycler.order", false)
+                .is_ok()
+        );
+        assert!(check_plugin_setting("theme-cycler", "colors.theme", true).is_err());
+        assert!(check_plugin_setting("theme-cycler", "terminal.default_shell", false).is_err());
+        assert!(check_plugin_setting("theme-cycler", "plugins.config.git.token", false).is_err());
+        assert!(check_plugin_setting("theme-cycler", "colors", false).is_err());
+        assert!(check_plugin_setting("theme-cycler", "fonts.size", false).is_err());
+    }
+
+    #[test]
+    fn test_set_setting_needs_terminal_control() {
+        let mut ctx = make_ctx();
+        assert!(ctx.set_setting("colors.theme", "dracula", false).is_err());
+        assert!(ctx.commands.lock().is_empty());
+
+        ctx.capabilities.insert(Capability::TerminalControl);
+        assert!(ctx.set_setting("colors.theme", "dracula", true).is_err());
+        assert!(ctx
+            .set_setting("terminal.default_shell", "/tmp/evil", false)
+            .is_err());
+        assert!(ctx.commands.lock().is_empty());
+        ctx.set_setting("colors.theme", "dracula", false).unwrap();
+        assert!(matches!(
+            ctx.commands.lock().as_slice(),
+            [RemoteCommand::Control {
+                message: ControlMessage::ConfigSet { key, persist: false, .. },
+                ..
+            }] if key == "colors.theme"
+        ));
+    }
+}
@@ -426,7 +426,7 @@ pub enum UnderlineStyle {
 /// Status bar side/position
 ///
 /// Identifies which side of the status bar to update.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StatusBarSide {
     /// Left side of the status bar
     Left,
@@ -462,6 +462,91 @@ pub struct StatusBarUpdate {
     pub items: Vec<RenderItem>,
 }
 
+impl Color {
+    /// RGB components, or `None` for an unknown name or malformed hex string
+    pub fn to_rgb(&self) -> Option<(u8, u8, u8)> {
+        match self {
+            Color::Rgb(r, g, b) => Some((*r, *g, *b)),
+            Color::Hex(hex) => {
+                let hex = hex.trim_start_matches('#');
+                if hex.len() != 6 {
+                    return None;
+                }
+                let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+                Some((channel(0)?, channel(2)?, channel(4)?))
+            }
+            Color::Named(name) => match name.to_lowercase().as_str() {
+                "black" => Some((0, 0, 0)),
+                "white" => Some((255, 255, 255)),
+                "red" => Some((255, 0, 0)),
+                "green" => Some((0, 255, 0)),
+                "blue" => Some((0, 0, 255)),
+                "yellow" => Some((255, 255, 0)),
+                "cyan" => Some((0, 255, 255)),
+                "magenta" => Some((255, 0, 255)),
+                "orange" => Some((255, 165, 0)),
+                "purple" => Some((128, 0, 128)),
+                "pink" => Some((255, 192, 203)),
+                "gray" | "grey" => Some((128, 128, 128)),
+                "darkgray" | "darkgrey" => Some((64, 64, 64)),
+                "lightgray" | "lightgrey" => Some((192, 192, 192)),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl RenderItem {
+    /// Convert to the simplified form sent to clients
+    ///
+    /// Returns `None` for items the IPC format cannot express: underline,
+    /// strikethrough, the foreground/background resets, and colors that
+    /// don't resolve to RGB.
+    pub fn to_protocol(&self) -> Option<scarab_protocol::StatusRenderItem> {
+        use scarab_protocol::StatusRenderItem;
+
+        Some(match self {
+            RenderItem::Text(text) => StatusRenderItem::Text(text.clone()),
+            RenderItem::Icon(icon) => StatusRenderItem::Icon(icon.clone()),
+            RenderItem::Foreground(color) => {
+                let (r, g, b) = color.to_rgb()?;
+                StatusRenderItem::Foreground { r, g, b }
+            }
+            RenderItem::ForegroundAnsi(ansi) => {
+                let (r, g, b) = ansi.to_rgb();
+                StatusRenderItem::Foreground { r, g, b }
+            }
+            RenderItem::Background(color) => {
+                let (r, g, b) = color.to_rgb()?;
+                StatusRenderItem::Background { r, g, b }
+            }
+            RenderItem::BackgroundAnsi(ansi) => {
+                let (r, g, b) = ansi.to_rgb();
+                StatusRenderItem::Background { r, g, b }
+            }
+            RenderItem::Bold => StatusRenderItem::Bold,
+            RenderItem::Italic => StatusRenderItem::Italic,
+            RenderItem::ResetAttributes => StatusRenderItem::ResetAttributes,
+            RenderItem::Spacer => StatusRenderItem::Spacer,
+            RenderItem::Padding(n) => StatusRenderItem::Padding(*n),
+            RenderItem::Separator(sep) => StatusRenderItem::Separator(sep.clone()),
+            RenderItem::Underline(_)
+            | RenderItem::Strikethrough
+            | RenderItem::ResetForeground
+            | RenderItem::ResetBackground => return None,
+        })
+    }
+}
+
+impl From<StatusBarSide> for scarab_protocol::StatusBarSide {
+    fn from(side: StatusBarSide) -> Self {
+        match side {
+            StatusBarSide::Left => scarab_protocol::StatusBarSide::Left,
+            StatusBarSide::Right => scarab_protocol::StatusBarSide::Right,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -514,6 +599,28 @@ mod tests {
         assert_eq!(update.items.len(), 2);
     }
 
+    #[test]
+    fn test_render_item_to_protocol() {
+        use scarab_protocol::StatusRenderItem;
+
+        assert!(matches!(
+            RenderItem::Foreground(Color::Hex("#7aa2f7".to_string())).to_protocol(),
+            Some(StatusRenderItem::Foreground {
+                r: 0x7a,
+                g: 0xa2,
+                b: 0xf7
+            })
+        ));
+        assert!(matches!(
+            RenderItem::BackgroundAnsi(AnsiColor::BrightRed).to_protocol(),
+            Some(StatusRenderItem::Background { r: 255, g: 0, b: 0 })
+        ));
+        assert!(RenderItem::Foreground(Color::Named("nope".to_string()))
+            .to_protocol()
+            .is_none());
+        assert!(RenderItem::Strikethrough.to_protocol().is_none());
+    }
+
     #[test]
     fn test_complex_status_bar_styling() {
         let items = vec![
@@ -0,0 +1,249 @@
+//! Persistent per-plugin key-value storage
+//!
+//! Each plugin gets its own JSON file under Scarab's data directory
+//! (`~/.local/share/scarab/plugin-data/<name>/storage.json` unless `[paths]`
+//! or `SCARAB_DATA_DIR` move it), so
+//! state such as clipboard history or achievements survives daemon
+//! restarts. Writes go straight to disk and are limited by a size quota.
+
+use crate::error::{PluginError, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Default storage quota per plugin (1 MiB of serialized JSON)
+pub const DEFAULT_STORAGE_QUOTA: usize = 1024 * 1024;
+
+/// Key-value store namespaced to one plugin
+///
+/// Obtained from [`PluginContext::storage`](crate::PluginContext::storage).
+/// Every handle reads the file when opened, so plugins should open the
+/// store once, typically in `on_load`, and keep the handle.
+#[derive(Debug)]
+pub struct PluginStorage {
+    path: PathBuf,
+    quota: usize,
+    entries: BTreeMap<String, serde_json::Value>,
+}
+
+impl PluginStorage {
+    /// Directory holding every plugin's storage, from the environment
+    ///
+    /// The daemon uses [`root_in`](Self::root_in) with the data directory
+    /// it resolved from the config instead.
+    pub fn default_root() -> Option<PathBuf> {
+        Some(Self::root_in(&scarab_platform::Paths::from_env().data_dir))
+    }
+
+    /// Directory holding every plugin's storage under `data_dir`
+    pub fn root_in(data_dir: &Path) -> PathBuf {
+        data_dir.join("plugin-data")
+    }
+
+    /// Open the store for `namespace` under `root`
+    ///
+    /// A missing file is an empty store; the file is created on first write.
+    pub fn open(root: &Path, namespace: &str, quota: usize) -> Result<Self> {
+        let path = root.join(sanitize(namespace)).join("storage.json");
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                PluginError::Other(anyhow::anyhow!(
+                    "Corrupt plugin storage {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            quota,
+            entries,
+        })
+    }
+
+    /// Path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get a value, or `None` if it is missing or has a different shape
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.entries
+            .get(key)
+            .and_then(|value| T::deserialize(value).ok())
+    }
+
+    /// Store a value and persist the store
+    ///
+    /// Fails with [`PluginError::QuotaExceeded`] if the store would grow
+    /// past its quota, leaving the previous contents untouched.
+    pub fn set<T: Serialize>(&mut self, key: impl Into<String>, value: &T) -> Result<()> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| PluginError::Other(anyhow::anyhow!("Unserializable value: {}", e)))?;
+        let key = key.into();
+        let previous = self.entries.insert(key.clone(), value);
+
+        if let Err(e) = self.save() {
+            match previous {
+                Some(previous) => self.entries.insert(key, previous),
+                None => self.entries.remove(&key),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Remove a key, returning whether it existed
+    pub fn remove(&mut self, key: &str) -> Result<bool> {
+        if self.entries.remove(key).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Check whether a key exists
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Stored keys in sorted order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Remove every key
+    pub fn clear(&mut self) -> Result<()> {
+        self.entries.clear();
+        self.save()
+    }
+
+    /// Serialized size of the store in bytes, as counted against the quota
+    pub fn used_bytes(&self) -> usize {
+        self.serialize().map_or(0, |bytes| bytes.len())
+    }
+
+    /// Maximum serialized size in bytes
+    pub fn quota(&self) -> usize {
+        self.quota
+    }
+
+    /// The bytes written to disk; both the quota and `used_bytes` measure these
+    fn serialize(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec_pretty(&self.entries).map_err(|e| PluginError::Other(anyhow::anyhow!(e)))
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = self.serialize()?;
+        if json.len() > self.quota {
+            return Err(PluginError::QuotaExceeded {
+                resource: "storage bytes".to_string(),
+                current: json.len(),
+                limit: self.quota,
+            });
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename so a crash never leaves a truncated file
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Turn a plugin name into a safe directory name
+fn sanitize(namespace: &str) -> String {
+    let name: String = namespace
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.trim_matches('.') {
+        "" => "_".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("scarab-storage-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let root = temp_root("round-trip");
+        let mut storage = PluginStorage::open(&root, "clipboard", DEFAULT_STORAGE_QUOTA).unwrap();
+        assert!(storage.get::<Vec<String>>("history").is_none());
+
+        storage
+            .set("history", &vec!["one".to_string(), "two".to_string()])
+            .unwrap();
+        storage.set("count", &2u32).unwrap();
+
+        let reopened = PluginStorage::open(&root, "clipboard", DEFAULT_STORAGE_QUOTA).unwrap();
+        assert_eq!(
+            reopened.get::<Vec<String>>("history"),
+            Some(vec!["one".to_string(), "two".to_string()])
+        );
+        assert_eq!(reopened.get::<u32>("count"), Some(2));
+        assert_eq!(
+            reopened.keys().collect::<Vec<_>>(),
+            vec!["count", "history"]
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_namespaces_are_separate() {
+        let root = temp_root("namespaces");
+        let mut a = PluginStorage::open(&root, "a", DEFAULT_STORAGE_QUOTA).unwrap();
+        a.set("key", &"a").unwrap();
+
+        let b = PluginStorage::open(&root, "b", DEFAULT_STORAGE_QUOTA).unwrap();
+        assert!(!b.contains("key"));
+
+        // Names cannot escape the storage root
+        let escaped = PluginStorage::open(&root, "../../etc", DEFAULT_STORAGE_QUOTA).unwrap();
+        assert!(escaped.path().starts_with(&root));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_quota() {
+        let root = temp_root("quota");
+        let mut storage = PluginStorage::open(&root, "small", 64).unwrap();
+        storage.set("a", &1).unwrap();
+
+        let result = storage.set("big", &"x".repeat(100));
+        assert!(matches!(result, Err(PluginError::QuotaExceeded { .. })));
+        // The failed write is rolled back
+        assert!(!storage.contains("big"));
+        assert_eq!(storage.get::<i32>("a"), Some(1));
+
+        // Usage matches what the quota counts: the file on disk
+        let on_disk = std::fs::metadata(storage.path()).unwrap().len();
+        assert_eq!(storage.used_bytes() as u64, on_disk);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,213 @@
+//! Long-running background tasks owned by plugins
+//!
+//! Work such as indexing scrollback or fetching marketplace data should not
+//! run inside a hook, where it would hold up dispatch for every plugin.
+//! [`PluginContext::spawn_task`](crate::PluginContext::spawn_task) runs it on
+//! the tokio runtime instead; the plugin manager cancels a plugin's tasks
+//! when it is unloaded.
+
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::{sync::Notify, task::AbortHandle};
+
+/// Identifier of a background task
+pub type TaskId = u64;
+
+struct TaskEntry {
+    owner: String,
+    name: String,
+    abort: AbortHandle,
+}
+
+/// Running background tasks of every plugin
+///
+/// Shared by all contexts cloned from the same root, so the plugin manager
+/// can cancel any plugin's tasks.
+#[derive(Default)]
+pub struct TaskRegistry {
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<TaskId, TaskEntry>>,
+    /// Signalled when a task queued commands from outside a hook
+    wake: Notify,
+}
+
+impl TaskRegistry {
+    /// Reserve an ID for a task about to be spawned
+    pub fn next_id(&self) -> TaskId {
+        self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Spawn `future` on the tokio runtime under `id`
+    ///
+    /// The future should call [`TaskRegistry::finish`] as its last step.
+    pub fn spawn<F>(&self, id: TaskId, owner: &str, name: &str, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        // Held across the spawn so a task that finishes at once cannot
+        // remove its entry before it is inserted
+        let mut tasks = self.tasks.lock();
+        let handle = tokio::spawn(future);
+        tasks.insert(
+            id,
+            TaskEntry {
+                owner: owner.to_string(),
+                name: name.to_string(),
+                abort: handle.abort_handle(),
+            },
+        );
+    }
+
+    /// Forget a task that ran to completion
+    pub fn finish(&self, id: TaskId) {
+        self.tasks.lock().remove(&id);
+        self.notify();
+    }
+
+    /// Abort a single task, returning whether it was running
+    pub fn cancel(&self, id: TaskId) -> bool {
+        match self.tasks.lock().remove(&id) {
+            Some(entry) => {
+                entry.abort.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Abort every task of `owner`, returning their IDs and names
+    pub fn cancel_owner(&self, owner: &str) -> Vec<(TaskId, String)> {
+        let mut tasks = self.tasks.lock();
+        let ids: Vec<TaskId> = tasks
+            .iter()
+            .filter(|(_, entry)| entry.owner == owner)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let mut cancelled = Vec::new();
+        for id in ids {
+            if let Some(entry) = tasks.remove(&id) {
+                entry.abort.abort();
+                cancelled.push((id, entry.name));
+            }
+        }
+        cancelled.sort();
+        cancelled
+    }
+
+    /// Check whether a task is still running
+    pub fn is_running(&self, id: TaskId) -> bool {
+        self.tasks.lock().contains_key(&id)
+    }
+
+    /// Name of a running task
+    pub fn name(&self, id: TaskId) -> Option<String> {
+        self.tasks.lock().get(&id).map(|entry| entry.name.clone())
+    }
+
+    /// Running tasks of `owner` as `(id, name)` pairs
+    pub fn running(&self, owner: &str) -> Vec<(TaskId, String)> {
+        let mut running: Vec<_> = self
+            .tasks
+            .lock()
+            .iter()
+            .filter(|(_, entry)| entry.owner == owner)
+            .map(|(id, entry)| (*id, entry.name.clone()))
+            .collect();
+        running.sort();
+        running
+    }
+
+    /// Signal that tasks queued commands that need flushing
+    pub fn notify(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Wait until a task queues commands or finishes
+    pub async fn changed(&self) {
+        self.wake.notified().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{context::PluginSharedState, types::RemoteCommand, PluginContext};
+    use parking_lot::Mutex;
+    use std::{sync::Arc, time::Duration};
+
+    fn make_ctx() -> PluginContext {
+        let state = Arc::new(Mutex::new(PluginSharedState::new(80, 24)));
+        PluginContext::new(Default::default(), state, "indexer")
+    }
+
+    fn progress(ctx: &PluginContext) -> Vec<(u8, String, bool)> {
+        ctx.commands
+            .lock()
+            .iter()
+            .filter_map(|cmd| match cmd {
+                RemoteCommand::TaskProgress {
+                    percent,
+                    message,
+                    done,
+                    ..
+                } => Some((*percent, message.clone(), *done)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_task_reports_progress() {
+        let ctx = make_ctx();
+        let task_ctx = ctx.clone();
+        let id = ctx.spawn_task("Index scrollback", move |id| async move {
+            task_ctx.report_progress(id, 50, "Halfway");
+            task_ctx.report_progress(id, 150, "Nearly");
+            Ok(())
+        });
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while ctx.tasks.is_running(id) {
+                ctx.tasks.changed().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            progress(&ctx),
+            vec![
+                (50, "Halfway".to_string(), false),
+                (100, "Nearly".to_string(), false),
+                (100, "Done".to_string(), true),
+            ]
+        );
+
+        // Finished tasks no longer accept progress
+        ctx.report_progress(id, 10, "Late");
+        assert_eq!(progress(&ctx).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_owner() {
+        let ctx = make_ctx();
+        let id = ctx.spawn_task("Fetch", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        });
+        assert_eq!(
+            ctx.tasks.running("indexer"),
+            vec![(id, "Fetch".to_string())]
+        );
+        assert!(ctx.tasks.running("other").is_empty());
+
+        let cancelled = ctx.tasks.cancel_owner("indexer");
+        assert_eq!(cancelled, vec![(id, "Fetch".to_string())]);
+        assert!(!ctx.tasks.is_running(id));
+        assert!(!ctx.cancel_task(id));
+    }
+}
@@ -1,6 +1,10 @@
 //! Common types used throughout the plugin API
 
-pub use scarab_protocol::{ModalItem, OverlayStyle};
+use crate::key_tables::{KeyCombo, KeyModifiers};
+pub use scarab_protocol::{
+    ModalItem, MouseButton, MouseEventKind, OverlayPanel, OverlayStyle, PanelAlign, PanelBorder,
+    PanelItem, PromptField,
+};
 use serde::{Deserialize, Serialize};
 
 /// Configuration for spawning an overlay
@@ -131,6 +135,12 @@ pub enum RemoteCommand {
         overlay_id: u64,
         config: OverlayConfig,
     },
+    /// Spawn a multi-line panel with optional border and selectable items
+    SpawnPanel {
+        plugin_name: String,
+        overlay_id: u64,
+        panel: OverlayPanel,
+    },
     /// Remove a previously spawned overlay
     RemoveOverlay {
         plugin_name: String,
@@ -142,6 +152,14 @@ pub enum RemoteCommand {
         item_id: u64,
         item: StatusBarItem,
     },
+    /// Add or replace a styled status bar segment
+    SetStatusSegment {
+        plugin_name: String,
+        item_id: u64,
+        side: crate::status_bar::StatusBarSide,
+        priority: i32,
+        items: Vec<crate::status_bar::RenderItem>,
+    },
     /// Remove a status bar item
     RemoveStatusItem {
         plugin_name: String,
@@ -167,6 +185,142 @@ pub enum RemoteCommand {
     GetCurrentTheme {
         plugin_name: String,
     },
+    /// Publish a message to plugins subscribed to its topic
+    Publish(PluginMessage),
+    /// Ask the user for one line of text
+    ShowInputPrompt {
+        plugin_name: String,
+        prompt_id: u64,
+        title: String,
+        placeholder: String,
+        masked: bool,
+    },
+    /// Ask the user to fill in several text fields
+    ShowForm {
+        plugin_name: String,
+        prompt_id: u64,
+        title: String,
+        fields: Vec<PromptField>,
+    },
+    /// Progress of a background task; `done` closes its indicator
+    TaskProgress {
+        plugin_name: String,
+        task_id: u64,
+        title: String,
+        percent: u8,
+        message: String,
+        done: bool,
+    },
+    /// Change the workspace as a client would, such as renaming a tab
+    Control {
+        plugin_name: String,
+        message: scarab_protocol::ControlMessage,
+    },
+    /// Ask the user whether a plugin may use a capability
+    RequestPermission {
+        plugin_name: String,
+        prompt_id: u64,
+        capability: crate::manifest::Capability,
+        detail: String,
+    },
+    /// Put text on the system clipboard
+    SetClipboard {
+        plugin_name: String,
+        text: String,
+    },
+}
+
+/// Message published on the inter-plugin bus
+///
+/// Plugins publish with [`PluginContext::emit`](crate::PluginContext::emit)
+/// and receive messages for the topics listed in their
+/// [`PluginMetadata`](crate::PluginMetadata) subscriptions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginMessage {
+    /// Topic, conventionally `<plugin>.<event>` such as `zones.command_failed`
+    pub topic: String,
+    /// Name of the context that published the message
+    pub sender: String,
+    /// Message body
+    pub payload: serde_json::Value,
+}
+
+/// User's answer to an input prompt or form
+///
+/// Delivered to [`Plugin::on_prompt_response`](crate::Plugin::on_prompt_response)
+/// of the plugin that opened the prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptResponse {
+    /// ID returned when the prompt was shown
+    pub prompt_id: u64,
+    /// Entered values in field order, or `None` if the user cancelled
+    pub values: Option<Vec<String>>,
+}
+
+impl PromptResponse {
+    /// Check whether the user dismissed the prompt
+    pub fn is_cancelled(&self) -> bool {
+        self.values.is_none()
+    }
+
+    /// Value of the first field, the only one for single-line prompts
+    pub fn value(&self) -> Option<&str> {
+        self.values.as_ref()?.first().map(String::as_str)
+    }
+}
+
+/// Key a plugin binds to one of its commands
+///
+/// Returned from [`Plugin::get_keybindings`](crate::Plugin::get_keybindings).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginKeyBinding {
+    pub key: KeyCombo,
+    /// ID of a command from `get_commands`, passed to `on_remote_command`
+    pub command: String,
+}
+
+impl PluginKeyBinding {
+    /// Bind `key` to the command `command`
+    pub fn new(key: KeyCombo, command: impl Into<String>) -> Self {
+        Self {
+            key,
+            command: command.into(),
+        }
+    }
+}
+
+/// Mouse event passed to [`Plugin::on_mouse`](crate::Plugin::on_mouse)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub button: MouseButton,
+    /// Grid column under the pointer
+    pub col: u16,
+    /// Grid row under the pointer
+    pub row: u16,
+    pub modifiers: KeyModifiers,
+    /// 1 for a single click, 2 for double, 3 for triple; 0 unless `Press`
+    pub click_count: u8,
+}
+
+impl MouseEvent {
+    /// Check for a press of `button`
+    pub fn is_press(&self, button: MouseButton) -> bool {
+        self.kind == MouseEventKind::Press && self.button == button
+    }
+}
+
+impl From<scarab_protocol::MouseEvent> for MouseEvent {
+    fn from(event: scarab_protocol::MouseEvent) -> Self {
+        Self {
+            kind: event.kind,
+            button: event.button,
+            col: event.col,
+            row: event.row,
+            modifiers: KeyModifiers::from_bits_truncate(event.modifiers),
+            click_count: event.click_count,
+        }
+    }
 }
 
 /// Action that a plugin hook can return
@@ -8,8 +8,8 @@ use scarab_plugin_api::context::{PluginConfigData, PluginSharedState};
 use scarab_plugin_api::error::PluginError;
 use scarab_plugin_api::host_bindings::{HostBindingLimits, HostBindings, DEFAULT_RATE_LIMIT};
 use scarab_plugin_api::navigation::PluginNavCapabilities;
-use scarab_plugin_api::types::{JumpDirection, OverlayConfig, StatusBarItem};
-use scarab_plugin_api::PluginContext;
+use scarab_plugin_api::types::{JumpDirection, OverlayConfig, RemoteCommand, StatusBarItem};
+use scarab_plugin_api::{Capability, PermissionDecision, PermissionStore, PluginContext};
 use std::sync::Arc;
 
 fn make_test_ctx() -> PluginContext {
@@ -224,6 +224,96 @@ fn test_prompt_jump_rate_limiting() {
     assert!(matches!(result, Err(PluginError::RateLimitExceeded { .. })));
 }
 
+// ============================================================================
+// Permission Prompt Tests
+// ============================================================================
+
+/// Take the one permission prompt the plugin queued
+fn take_permission_request(ctx: &PluginContext) -> (u64, Capability) {
+    let mut commands = ctx.commands.lock();
+    assert_eq!(
+        commands.len(),
+        1,
+        "Expected one command, got {:?}",
+        commands
+    );
+    match commands.remove(0) {
+        RemoteCommand::RequestPermission {
+            prompt_id,
+            plugin_name,
+            capability,
+            ..
+        } => {
+            assert_eq!(plugin_name, "test_fusabi_plugin");
+            (prompt_id, capability)
+        }
+        other => panic!("Expected RequestPermission, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_clipboard_asks_user_on_first_use() {
+    let store = Arc::new(PermissionStore::in_memory());
+    let mut ctx = make_test_ctx().with_permissions(store.clone());
+    let bindings = HostBindings::with_defaults();
+
+    // Not granted in the config: denied without asking
+    assert!(matches!(
+        bindings.copy_to_clipboard(&ctx, "secret"),
+        Err(PluginError::CapabilityDenied(_))
+    ));
+    assert!(ctx.commands.lock().is_empty());
+
+    // Granted: the first use asks, and retries don't ask again
+    ctx.capabilities.insert(Capability::Clipboard);
+    assert!(bindings.copy_to_clipboard(&ctx, "hello").is_err());
+    assert!(bindings.copy_to_clipboard(&ctx, "hello").is_err());
+    let (prompt_id, capability) = take_permission_request(&ctx);
+    assert_eq!(capability, Capability::Clipboard);
+
+    store
+        .resolve(prompt_id, PermissionDecision::AllowOnce)
+        .unwrap();
+    bindings.copy_to_clipboard(&ctx, "hello").unwrap();
+    assert!(matches!(
+        &ctx.commands.lock()[0],
+        RemoteCommand::SetClipboard { text, .. } if text == "hello"
+    ));
+    ctx.commands.lock().clear();
+
+    // "Once" covers a single copy; the next one asks again
+    bindings.reset_rate_limit();
+    assert!(bindings.copy_to_clipboard(&ctx, "again").is_err());
+    let (_, capability) = take_permission_request(&ctx);
+    assert_eq!(capability, Capability::Clipboard);
+}
+
+#[test]
+fn test_denied_permission_sticks() {
+    let store = Arc::new(PermissionStore::in_memory());
+    let mut ctx = make_test_ctx().with_permissions(store.clone());
+    ctx.capabilities.insert(Capability::Network);
+    let bindings = HostBindings::with_defaults();
+
+    let detail = "connect to example.com";
+    assert!(bindings
+        .check_permission(&ctx, &Capability::Network, detail)
+        .is_err());
+    let (prompt_id, _) = take_permission_request(&ctx);
+    store.resolve(prompt_id, PermissionDecision::Deny).unwrap();
+
+    bindings.reset_rate_limit();
+    let result = bindings.check_permission(&ctx, &Capability::Network, detail);
+    assert!(
+        matches!(result, Err(PluginError::CapabilityDenied(reason)) if reason.contains("denied"))
+    );
+    // Capabilities the user is not asked about only need the config grant
+    ctx.capabilities.insert(Capability::UiOverlay);
+    assert!(bindings
+        .check_permission(&ctx, &Capability::UiOverlay, "draw")
+        .is_ok());
+}
+
 // ============================================================================
 // Type Construction Tests
 // ============================================================================
@@ -6,10 +6,16 @@ use std::path::{Path, PathBuf};
 
 use fusabi_frontend::{Compiler, Lexer, Parser as FusabiParser, TypeEnv, TypeInference};
 use fusabi_vm::{Chunk, FZB_MAGIC, FZB_VERSION};
+use scarab_plugin_api::{DefinitionMap, Package, PluginManifest, PACKAGE_EXTENSION};
+
+mod project;
+
+use project::{PluginProject, BUILD_MANIFEST};
 
 /// Scarab Fusabi Plugin Compiler
 ///
-/// Compiles .fsx Fusabi source files to .fzb bytecode files
+/// Compiles .fsx Fusabi source files to .fzb bytecode files, or a plugin
+/// project directory with a plugin.toml to a .scarabpkg package
 #[derive(Parser, Debug)]
 #[command(
     name = "scarab-plugin-compiler",
@@ -18,11 +24,13 @@ use fusabi_vm::{Chunk, FZB_MAGIC, FZB_VERSION};
     long_about = None
 )]
 struct Args {
-    /// Input .fsx source file
+    /// Input .fsx source file, or a project directory containing plugin.toml
     #[arg(value_name = "INPUT")]
     input: PathBuf,
 
-    /// Output .fzb bytecode file (default: same name as input with .fzb extension)
+    /// Output .fzb bytecode file (default: same name as input with .fzb extension),
+    /// or .scarabpkg package for a project (default: <name>-<version>.scarabpkg
+    /// in the project directory)
     #[arg(short, long, value_name = "OUTPUT")]
     output: Option<PathBuf>,
 
@@ -45,6 +53,11 @@ struct Args {
     /// Print bytecode disassembly
     #[arg(long)]
     disassemble: bool,
+
+    /// Don't write the .fzb.defs map of hook locations used in runtime
+    /// error messages
+    #[arg(long)]
+    no_definition_map: bool,
 }
 
 /// Plugin metadata extracted from source comments
@@ -113,6 +126,18 @@ impl PluginMetadata {
         Ok(())
     }
 
+    /// Metadata of a project, taken from its manifest
+    fn from_manifest(manifest: &PluginManifest) -> Self {
+        PluginMetadata {
+            name: Some(manifest.name.clone()),
+            version: Some(manifest.version.clone()),
+            description: Some(manifest.description.clone()),
+            author: Some(manifest.author.clone()),
+            api_version: Some(manifest.api_version.clone()),
+            min_scarab_version: Some(manifest.min_scarab_version.clone()),
+        }
+    }
+
     /// Print metadata summary
     fn print_summary(&self) {
         println!("{}", "Plugin Metadata:".bold());
@@ -193,12 +218,148 @@ fn compile_plugin(source_path: &Path, output_path: &Path, args: &Args) -> Result
         println!("{} Metadata validation passed", "✓".green().bold());
     }
 
+    let chunk = compile_source(&source, args)?;
+
+    // Write bytecode to file
+    write_bytecode_file(output_path, &chunk, &metadata, args.verbose)?;
+
+    if !args.no_definition_map {
+        let file_name = source_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let definitions = DefinitionMap::from_source(&file_name, &source);
+        definitions.write_for(output_path).with_context(|| {
+            format!(
+                "Failed to write definition map: {}",
+                DefinitionMap::path_for(output_path).display()
+            )
+        })?;
+
+        if args.verbose {
+            println!(
+                "  {} definitions mapped to {}",
+                definitions.definitions.len(),
+                DefinitionMap::path_for(output_path).display()
+            );
+        }
+    }
+
+    println!(
+        "{} Compiled successfully: {}",
+        "✓".green().bold(),
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+/// Compile a plugin project and package it with its sources and assets
+fn compile_project(project: &PluginProject, output_path: &Path, args: &Args) -> Result<()> {
+    let manifest = &project.manifest.plugin;
+    let entry = project.entry_path();
+
+    if args.verbose {
+        println!(
+            "{} Resolving {} and the files it loads...",
+            "[1/6]".dimmed(),
+            entry.display()
+        );
+    }
+
+    let resolved = project.resolve()?;
+    let entry_name = project.relative_name(&entry)?;
+    let mut sources = Vec::with_capacity(resolved.dependencies.len() + 1);
+    for path in resolved.dependencies.iter().chain(std::iter::once(&entry)) {
+        let contents = fs::read(path)
+            .with_context(|| format!("Failed to read source file: {}", path.display()))?;
+        sources.push((project.relative_name(path)?, contents));
+    }
+
+    if args.verbose {
+        println!("  {} source files", sources.len());
+    }
+
+    let metadata = PluginMetadata::from_manifest(manifest);
+
+    if args.verbose {
+        println!("{} Reading {}...", "[2/6]".dimmed(), BUILD_MANIFEST);
+        metadata.print_summary();
+        let capabilities: Vec<String> = manifest
+            .capabilities_list()
+            .iter()
+            .map(|capability| format!("{:?}", capability))
+            .collect();
+        if !capabilities.is_empty() {
+            println!("  {}: {}", "Capabilities".cyan(), capabilities.join(", "));
+        }
+    }
+
+    let chunk = compile_source(&resolved.source, args)?;
+
+    let mut package = Package::new();
+    package.add(BUILD_MANIFEST, project.manifest_source.clone().into_bytes())?;
+
+    let bytecode_name = format!("{}.fzb", manifest.name);
+    package.add(bytecode_name.as_str(), encode_bytecode(&chunk, &metadata)?)?;
+
+    if !args.no_definition_map {
+        // Hooks are defined in the entry script, so its lines are the ones
+        // worth pointing at
+        let entry_source = fs::read_to_string(&entry)
+            .with_context(|| format!("Failed to read source file: {}", entry.display()))?;
+        let definitions = DefinitionMap::from_source(&entry_name, &entry_source);
+        let map_name = DefinitionMap::path_for(Path::new(&bytecode_name));
+        let map_json = serde_json::to_vec_pretty(&definitions)
+            .context("Failed to serialize definition map")?;
+        package.add(map_name.to_string_lossy(), map_json)?;
+    }
+
+    for (name, contents) in sources {
+        package.add(name, contents)?;
+    }
+
+    let assets = project.asset_files()?;
+    for (name, path) in &assets {
+        let contents =
+            fs::read(path).with_context(|| format!("Failed to read asset: {}", path.display()))?;
+        package.add(name.as_str(), contents)?;
+    }
+
+    if args.verbose {
+        println!("  {} assets", assets.len());
+        println!("  Writing package to: {}", output_path.display());
+    }
+
+    package
+        .write(output_path)
+        .with_context(|| format!("Failed to write package: {}", output_path.display()))?;
+
+    if args.verbose {
+        println!(
+            "  {} files, {} bytes packaged",
+            package.files.len(),
+            package.content_len()
+        );
+    }
+
+    println!(
+        "{} Packaged successfully: {}",
+        "✓".green().bold(),
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+/// Lex, parse, type check and compile a flattened source
+fn compile_source(source: &str, args: &Args) -> Result<Chunk> {
     // Tokenize
     if args.verbose {
         println!("{} Tokenizing source...", "[3/6]".dimmed());
     }
 
-    let mut lexer = Lexer::new(&source);
+    let mut lexer = Lexer::new(source);
     let tokens = lexer
         .tokenize()
         .map_err(|e| anyhow::anyhow!("Lexer error: {:?}", e))?;
@@ -259,16 +420,7 @@ fn compile_plugin(source_path: &Path, output_path: &Path, args: &Args) -> Result
         chunk.disassemble();
     }
 
-    // Write bytecode to file
-    write_bytecode_file(output_path, &chunk, &metadata, args.verbose)?;
-
-    println!(
-        "{} Compiled successfully: {}",
-        "✓".green().bold(),
-        output_path.display()
-    );
-
-    Ok(())
+    Ok(chunk)
 }
 
 /// Write bytecode chunk to .fzb file with header
@@ -288,6 +440,21 @@ fn write_bytecode_file(
             .with_context(|| format!("Failed to create output directory: {}", parent.display()))?;
     }
 
+    let file_data = encode_bytecode(chunk, metadata)?;
+
+    // Write to file
+    fs::write(path, &file_data)
+        .with_context(|| format!("Failed to write bytecode file: {}", path.display()))?;
+
+    if verbose {
+        println!("  {} bytes written", file_data.len());
+    }
+
+    Ok(())
+}
+
+/// Contents of a .fzb file: header, then chunk
+fn encode_bytecode(chunk: &Chunk, metadata: &PluginMetadata) -> Result<Vec<u8>> {
     // Prepare file header
     let header = BytecodeFileHeader {
         magic: FZB_MAGIC.to_vec(),
@@ -308,15 +475,7 @@ fn write_bytecode_file(
     file_data.extend_from_slice(&header_bytes);
     file_data.extend_from_slice(&chunk_bytes);
 
-    // Write to file
-    fs::write(path, &file_data)
-        .with_context(|| format!("Failed to write bytecode file: {}", path.display()))?;
-
-    if verbose {
-        println!("  {} bytes written", file_data.len());
-    }
-
-    Ok(())
+    Ok(file_data)
 }
 
 fn main() -> Result<()> {
@@ -333,6 +492,25 @@ fn main() -> Result<()> {
         bail!("Input file does not exist: {}", args.input.display());
     }
 
+    if args.input.is_dir() {
+        if !PluginProject::is_project(&args.input) {
+            bail!(
+                "Input directory has no {}: {}",
+                BUILD_MANIFEST,
+                args.input.display()
+            );
+        }
+        let project = PluginProject::load(&args.input)?;
+        let output_path = args.output.clone().unwrap_or_else(|| {
+            let manifest = &project.manifest.plugin;
+            args.input.join(format!(
+                "{}-{}.{}",
+                manifest.name, manifest.version, PACKAGE_EXTENSION
+            ))
+        });
+        return compile_project(&project, &output_path, &args);
+    }
+
     if args.input.extension().and_then(|s| s.to_str()) != Some("fsx") {
         eprintln!(
             "{} Input file should have .fsx extension: {}",
@@ -0,0 +1,285 @@
+//! Plugin projects: a directory with a `plugin.toml` build manifest
+//!
+//! The manifest is the usual plugin manifest plus a `[build]` table naming
+//! the entry script and any assets to ship with it:
+//!
+//! ```toml
+//! name = "git-status"
+//! version = "0.2.0"
+//! description = "Shows the branch in the status bar"
+//! author = "Jane Doe"
+//! api-version = "0.1.0"
+//! min-scarab-version = "0.1.0"
+//! capabilities = ["ui-overlay"]
+//!
+//! [build]
+//! entry = "src/main.fsx"
+//! assets = ["icons", "README.md"]
+//! ```
+//!
+//! Files the entry `#load`s are inlined into it before compiling, so they
+//! need no listing of their own.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use scarab_plugin_api::fusabi_modules::{resolve_source, ResolvedSource};
+use scarab_plugin_api::package::MANIFEST_FILE;
+use scarab_plugin_api::PluginManifest;
+
+/// Name of the build manifest at the root of a project
+pub const BUILD_MANIFEST: &str = MANIFEST_FILE;
+
+/// `plugin.toml`: the plugin manifest and how to build the plugin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildManifest {
+    #[serde(flatten)]
+    pub plugin: PluginManifest,
+
+    #[serde(default)]
+    pub build: BuildSection,
+}
+
+/// The `[build]` table of `plugin.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildSection {
+    /// Script compiled into the plugin, relative to the project root
+    #[serde(default = "default_entry")]
+    pub entry: PathBuf,
+
+    /// Files and directories packaged as they are
+    #[serde(default)]
+    pub assets: Vec<PathBuf>,
+}
+
+impl Default for BuildSection {
+    fn default() -> Self {
+        Self {
+            entry: default_entry(),
+            assets: Vec::new(),
+        }
+    }
+}
+
+fn default_entry() -> PathBuf {
+    PathBuf::from("main.fsx")
+}
+
+/// A plugin project on disk
+#[derive(Debug, Clone)]
+pub struct PluginProject {
+    pub root: PathBuf,
+    pub manifest: BuildManifest,
+    /// Text of `plugin.toml`, packaged unchanged
+    pub manifest_source: String,
+}
+
+impl PluginProject {
+    /// Whether `dir` holds a build manifest
+    pub fn is_project(dir: &Path) -> bool {
+        dir.join(BUILD_MANIFEST).is_file()
+    }
+
+    /// Read and check the build manifest of the project in `root`
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(BUILD_MANIFEST);
+        let manifest_source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read build manifest: {}", path.display()))?;
+        let manifest: BuildManifest = toml::from_str(&manifest_source)
+            .with_context(|| format!("Invalid build manifest: {}", path.display()))?;
+
+        if manifest.plugin.name.trim().is_empty() {
+            bail!("{}: `name` must not be empty", path.display());
+        }
+        manifest
+            .plugin
+            .validate(scarab_plugin_api::API_VERSION)
+            .with_context(|| format!("Invalid build manifest: {}", path.display()))?;
+        relative_path(&manifest.build.entry)?;
+        for asset in &manifest.build.assets {
+            relative_path(asset)?;
+        }
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            manifest,
+            manifest_source,
+        })
+    }
+
+    pub fn entry_path(&self) -> PathBuf {
+        self.root.join(&self.manifest.build.entry)
+    }
+
+    /// The entry script with everything it loads inlined
+    pub fn resolve(&self) -> Result<ResolvedSource> {
+        let entry = self.entry_path();
+        resolve_source(&entry)
+            .with_context(|| format!("Failed to resolve entry script: {}", entry.display()))
+    }
+
+    /// Path of `file` relative to the project root, with `/` separators
+    ///
+    /// Fails for files outside the project, such as a `#load "../shared.fsx"`.
+    pub fn relative_name(&self, file: &Path) -> Result<String> {
+        let root = self
+            .root
+            .canonicalize()
+            .with_context(|| format!("Failed to read project: {}", self.root.display()))?;
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        let relative = file.strip_prefix(&root).map_err(|_| {
+            anyhow::anyhow!(
+                "{} is outside the project directory {}",
+                file.display(),
+                root.display()
+            )
+        })?;
+        Ok(slash_path(relative))
+    }
+
+    /// Every asset file, by name relative to the project root
+    ///
+    /// Directories are included with everything under them.
+    pub fn asset_files(&self) -> Result<Vec<(String, PathBuf)>> {
+        let mut files = Vec::new();
+        for asset in &self.manifest.build.assets {
+            let path = self.root.join(asset);
+            if !path.exists() {
+                bail!("Asset not found: {}", path.display());
+            }
+            collect_files(&path, &mut files)?;
+        }
+
+        let mut named = Vec::with_capacity(files.len());
+        for file in files {
+            let name = self.relative_name(&file)?;
+            if !named.iter().any(|(existing, _)| existing == &name) {
+                named.push((name, file));
+            }
+        }
+        Ok(named)
+    }
+}
+
+/// Reject absolute paths and ones that climb out of the project
+fn relative_path(path: &Path) -> Result<()> {
+    let escapes = path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        bail!(
+            "{} in {} must be a path inside the project",
+            path.display(),
+            BUILD_MANIFEST
+        );
+    }
+    Ok(())
+}
+
+/// `path` and, for a directory, every file under it in name order
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)
+        .with_context(|| format!("Failed to read asset directory: {}", path.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        collect_files(&entry, files)?;
+    }
+    Ok(())
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+name = "git-status"
+version = "0.2.0"
+description = "Shows the branch"
+author = "Test"
+api-version = "0.1.0"
+min-scarab-version = "0.1.0"
+capabilities = ["ui-overlay"]
+
+[build]
+entry = "src/main.fsx"
+assets = ["icons"]
+"#;
+
+    fn write(root: &Path, name: &str, contents: &str) {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_load_project() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), BUILD_MANIFEST, MANIFEST);
+        write(
+            dir.path(),
+            "src/main.fsx",
+            "#load \"lib/util.fsx\"\nlet main = double 21\n",
+        );
+        write(dir.path(), "src/lib/util.fsx", "let double x = x * 2\n");
+        write(dir.path(), "icons/branch.svg", "<svg/>");
+        write(dir.path(), "icons/dark/branch.svg", "<svg/>");
+
+        assert!(PluginProject::is_project(dir.path()));
+        let project = PluginProject::load(dir.path()).unwrap();
+        assert_eq!(project.manifest.plugin.name, "git-status");
+        assert_eq!(project.manifest.build.entry, PathBuf::from("src/main.fsx"));
+
+        let resolved = project.resolve().unwrap();
+        assert!(resolved.source.contains("let double x"));
+        let deps: Vec<String> = resolved
+            .dependencies
+            .iter()
+            .map(|dep| project.relative_name(dep).unwrap())
+            .collect();
+        assert_eq!(deps, vec!["src/lib/util.fsx"]);
+
+        let assets: Vec<String> = project
+            .asset_files()
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(assets, vec!["icons/branch.svg", "icons/dark/branch.svg"]);
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_project() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            BUILD_MANIFEST,
+            &MANIFEST.replace("[\"icons\"]", "[\"../secrets\"]"),
+        );
+        let err = PluginProject::load(dir.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("must be a path inside the project"));
+
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            BUILD_MANIFEST,
+            "name = \"x\"\nversion = \"0.1.0\"\ndescription = \"\"\nauthor = \"\"\n",
+        );
+        assert!(PluginProject::load(dir.path()).is_err());
+    }
+}
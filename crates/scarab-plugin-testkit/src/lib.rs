@@ -0,0 +1,317 @@
+//! Test harness for Scarab daemon plugins
+//!
+//! [`PluginHarness`] runs a plugin's hooks against a scripted
+//! [`PluginContext`] instead of a running daemon. Tests feed it output,
+//! input, keys and resizes, and then inspect the [`RemoteCommand`]s the
+//! plugin queued, such as notifications, logs and overlays.
+//!
+//! ```rust,ignore
+//! use scarab_plugin_testkit::PluginHarness;
+//!
+//! #[tokio::test]
+//! async fn warns_about_failed_builds() {
+//!     let mut harness = PluginHarness::new(BuildWatcher::default());
+//!     harness.load().await.unwrap();
+//!
+//!     harness.output("error[E0308]: mismatched types").await.unwrap();
+//!
+//!     harness.assert_notified("Build failed");
+//! }
+//! ```
+
+use parking_lot::Mutex;
+use scarab_plugin_api::{
+    context::{LogLevel, NotifyLevel, PluginSharedState},
+    history::{CommandBlock, TerminalHistory},
+    key_tables::parse_key_combo,
+    types::{Cell, PluginMessage, RemoteCommand},
+    Action, Capability, Plugin, PluginContext, Result,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Default grid width of the scripted terminal
+pub const DEFAULT_COLS: u16 = 80;
+
+/// Default grid height of the scripted terminal
+pub const DEFAULT_ROWS: u16 = 24;
+
+/// A notification queued with `ctx.notify`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub level: NotifyLevel,
+}
+
+/// Scrollback and command blocks handed to the plugin's context
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedHistory {
+    pub lines: Vec<String>,
+    pub blocks: Vec<CommandBlock>,
+}
+
+impl ScriptedHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a scrollback line
+    pub fn with_line(mut self, line: impl Into<String>) -> Self {
+        self.lines.push(line.into());
+        self
+    }
+
+    /// Append a finished command block
+    pub fn with_block(mut self, block: CommandBlock) -> Self {
+        self.blocks.push(block);
+        self
+    }
+}
+
+impl TerminalHistory for ScriptedHistory {
+    fn scrollback_len(&self) -> usize {
+        self.lines.len()
+    }
+
+    fn lines(&self, start: usize, end: usize) -> Vec<String> {
+        let end = end.min(self.lines.len());
+        self.lines
+            .get(start..end)
+            .map(<[String]>::to_vec)
+            .unwrap_or_default()
+    }
+
+    fn command_blocks(&self) -> Vec<CommandBlock> {
+        self.blocks.clone()
+    }
+}
+
+/// Runs one plugin's hooks against a scripted context
+pub struct PluginHarness<P: Plugin> {
+    plugin: P,
+    ctx: PluginContext,
+}
+
+impl<P: Plugin> PluginHarness<P> {
+    /// Wrap `plugin` in an 80x24 terminal with no capabilities, settings,
+    /// history or storage
+    pub fn new(plugin: P) -> Self {
+        let state = Arc::new(Mutex::new(PluginSharedState::new(
+            DEFAULT_COLS,
+            DEFAULT_ROWS,
+        )));
+        let mut ctx = PluginContext::new(Default::default(), state, &plugin.metadata().name);
+        // Never touch the user's real plugin data from tests
+        ctx.storage_root = None;
+        Self { plugin, ctx }
+    }
+
+    /// Start with a grid of a different size
+    pub fn with_size(self, cols: u16, rows: u16) -> Self {
+        self.resize_grid(cols, rows);
+        self
+    }
+
+    /// Grant a capability, as an entry in `plugins.toml` would
+    pub fn with_capability(mut self, capability: Capability) -> Self {
+        self.ctx.capabilities.insert(capability);
+        self
+    }
+
+    /// Set a value returned by `ctx.config()`
+    ///
+    /// Values are not checked against the plugin's schema.
+    pub fn with_setting(mut self, key: impl Into<String>, value: impl Into<toml::Value>) -> Self {
+        self.ctx.config.data.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set an environment variable seen by `ctx.get_env`
+    pub fn with_env(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.ctx.state.lock().env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Give the plugin scrollback and command blocks
+    pub fn with_history(mut self, history: ScriptedHistory) -> Self {
+        self.ctx.history = Some(Arc::new(history));
+        self
+    }
+
+    /// Let the plugin use `ctx.storage()` under `root`, such as a temp dir
+    pub fn with_storage_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.ctx.storage_root = Some(root.into());
+        self
+    }
+
+    /// The context passed to every hook
+    pub fn context(&self) -> &PluginContext {
+        &self.ctx
+    }
+
+    pub fn plugin(&self) -> &P {
+        &self.plugin
+    }
+
+    pub fn plugin_mut(&mut self) -> &mut P {
+        &mut self.plugin
+    }
+
+    /// Write `text` into row `y` of the grid, clearing the rest of the row
+    pub fn set_line(&self, y: u16, text: &str) {
+        let mut state = self.ctx.state.lock();
+        let mut chars = text.chars();
+        for x in 0..state.cols {
+            let cell = Cell {
+                c: chars.next().unwrap_or(' '),
+                ..Cell::default()
+            };
+            state.set_cell(x, y, cell);
+        }
+    }
+
+    /// Move the cursor reported by `ctx.get_cursor`
+    pub fn set_cursor(&self, x: u16, y: u16) {
+        self.ctx.state.lock().cursor = (x, y);
+    }
+
+    /// Run `on_load`
+    pub async fn load(&mut self) -> Result<()> {
+        self.plugin.on_load(&mut self.ctx).await
+    }
+
+    /// Run `on_unload`
+    pub async fn unload(&mut self) -> Result<()> {
+        self.plugin.on_unload().await
+    }
+
+    /// Feed one line of terminal output to `on_output`
+    pub async fn output(&mut self, line: &str) -> Result<Action> {
+        self.plugin.on_output(line, &self.ctx).await
+    }
+
+    /// Feed each line of `text` to `on_output`, returning every action
+    pub async fn output_lines(&mut self, text: &str) -> Result<Vec<Action>> {
+        let mut actions = Vec::new();
+        for line in text.lines() {
+            actions.push(self.plugin.on_output(line, &self.ctx).await?);
+        }
+        Ok(actions)
+    }
+
+    /// Feed user input to `on_input`
+    pub async fn input(&mut self, input: impl AsRef<[u8]>) -> Result<Action> {
+        self.plugin.on_input(input.as_ref(), &self.ctx).await
+    }
+
+    /// Press a key such as `"Ctrl+Shift+P"`, calling `on_key`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `combo` is not a valid key combination.
+    pub async fn key(&mut self, combo: &str) -> Result<Action> {
+        let key =
+            parse_key_combo(combo).unwrap_or_else(|| panic!("Invalid key combination: {}", combo));
+        self.plugin.on_key(key, &self.ctx).await
+    }
+
+    /// Resize the grid and call `on_resize`
+    ///
+    /// The grid is cleared, as the daemon reflows it before the hook runs.
+    pub async fn resize(&mut self, cols: u16, rows: u16) -> Result<()> {
+        self.resize_grid(cols, rows);
+        self.plugin.on_resize(cols, rows, &self.ctx).await
+    }
+
+    /// Select a command from the palette, calling `on_remote_command`
+    pub async fn remote_command(&mut self, id: &str) -> Result<()> {
+        self.plugin.on_remote_command(id, &self.ctx).await
+    }
+
+    /// Deliver a bus message to `on_message`
+    pub async fn message(
+        &mut self,
+        topic: impl Into<String>,
+        sender: impl Into<String>,
+        payload: serde_json::Value,
+    ) -> Result<()> {
+        let message = PluginMessage {
+            topic: topic.into(),
+            sender: sender.into(),
+            payload,
+        };
+        self.plugin.on_message(&message, &self.ctx).await
+    }
+
+    fn resize_grid(&self, cols: u16, rows: u16) {
+        let mut state = self.ctx.state.lock();
+        state.cols = cols;
+        state.rows = rows;
+        state.cells = vec![Cell::default(); cols as usize * rows as usize];
+        state.cursor = (0, 0);
+    }
+
+    /// Commands queued by the plugin so far
+    pub fn commands(&self) -> Vec<RemoteCommand> {
+        self.ctx.commands.lock().clone()
+    }
+
+    /// Remove and return the commands queued so far
+    pub fn take_commands(&self) -> Vec<RemoteCommand> {
+        std::mem::take(&mut *self.ctx.commands.lock())
+    }
+
+    /// Notifications queued so far
+    pub fn notifications(&self) -> Vec<Notification> {
+        self.ctx
+            .commands
+            .lock()
+            .iter()
+            .filter_map(|cmd| match cmd {
+                RemoteCommand::PluginNotify { title, body, level } => Some(Notification {
+                    title: title.clone(),
+                    body: body.clone(),
+                    level: *level,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Messages logged with `ctx.log` so far
+    pub fn logs(&self) -> Vec<(LogLevel, String)> {
+        self.ctx
+            .commands
+            .lock()
+            .iter()
+            .filter_map(|cmd| match cmd {
+                RemoteCommand::PluginLog { level, message, .. } => Some((*level, message.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Assert that a notification titled `title` was queued
+    #[track_caller]
+    pub fn assert_notified(&self, title: &str) {
+        let notifications = self.notifications();
+        assert!(
+            notifications.iter().any(|n| n.title == title),
+            "Expected a notification titled {:?}, got {:?}",
+            title,
+            notifications
+        );
+    }
+
+    /// Assert that no notification was queued
+    #[track_caller]
+    pub fn assert_not_notified(&self) {
+        let notifications = self.notifications();
+        assert!(
+            notifications.is_empty(),
+            "Expected no notifications, got {:?}",
+            notifications
+        );
+    }
+}
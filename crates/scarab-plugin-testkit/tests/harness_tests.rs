@@ -0,0 +1,166 @@
+//! Tests for PluginHarness, driving a small build-watcher plugin
+
+use async_trait::async_trait;
+use scarab_plugin_api::{
+    context::{LogLevel, NotifyLevel},
+    types::RemoteCommand,
+    Action, Plugin, PluginContext, PluginMetadata, Result,
+};
+use scarab_plugin_testkit::{PluginHarness, ScriptedHistory};
+
+struct BuildWatcher {
+    metadata: PluginMetadata,
+    failures: usize,
+    resizes: Vec<(u16, u16)>,
+}
+
+impl BuildWatcher {
+    fn new() -> Self {
+        Self {
+            metadata: PluginMetadata::new("build-watcher", "0.1.0", "Watches builds", "Test"),
+            failures: 0,
+            resizes: Vec::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for BuildWatcher {
+    fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
+    async fn on_load(&mut self, ctx: &mut PluginContext) -> Result<()> {
+        ctx.log(LogLevel::Info, "watching builds");
+        Ok(())
+    }
+
+    async fn on_output(&mut self, line: &str, ctx: &PluginContext) -> Result<Action> {
+        if line.starts_with("error") {
+            self.failures += 1;
+            ctx.notify_error("Build failed", line);
+        }
+        Ok(Action::Continue)
+    }
+
+    async fn on_input(&mut self, input: &[u8], _ctx: &PluginContext) -> Result<Action> {
+        if input == b"\x03" {
+            return Ok(Action::Stop);
+        }
+        Ok(Action::Continue)
+    }
+
+    async fn on_resize(&mut self, cols: u16, rows: u16, _ctx: &PluginContext) -> Result<()> {
+        self.resizes.push((cols, rows));
+        Ok(())
+    }
+
+    async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
+        if id == "summary" {
+            let env = ctx.get_env("CARGO_TARGET").unwrap_or_default();
+            let last = ctx
+                .history
+                .as_ref()
+                .and_then(|h| h.lines(0, h.scrollback_len()).pop())
+                .unwrap_or_default();
+            ctx.notify_info(
+                "Build summary",
+                &format!("{} failures on {}, last: {}", self.failures, env, last),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_records_logs_and_notifications() {
+    let mut harness = PluginHarness::new(BuildWatcher::new());
+    harness.load().await.unwrap();
+    assert_eq!(
+        harness.logs(),
+        vec![(LogLevel::Info, "watching builds".to_string())]
+    );
+    harness.assert_not_notified();
+
+    let actions = harness
+        .output_lines("Compiling scarab\nerror[E0308]: mismatched types")
+        .await
+        .unwrap();
+    assert_eq!(actions.len(), 2);
+
+    harness.assert_notified("Build failed");
+    let notifications = harness.notifications();
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].body, "error[E0308]: mismatched types");
+    assert_eq!(notifications[0].level, NotifyLevel::Error);
+    assert_eq!(harness.plugin().failures, 1);
+}
+
+#[tokio::test]
+async fn test_take_commands_drains_queue() {
+    let mut harness = PluginHarness::new(BuildWatcher::new());
+    harness.output("error: linker failed").await.unwrap();
+
+    let commands = harness.take_commands();
+    assert!(matches!(
+        commands.as_slice(),
+        [RemoteCommand::PluginNotify { .. }]
+    ));
+    assert!(harness.commands().is_empty());
+    harness.assert_not_notified();
+}
+
+#[tokio::test]
+async fn test_input_and_resize() {
+    let mut harness = PluginHarness::new(BuildWatcher::new()).with_size(100, 30);
+    assert_eq!(harness.context().get_size(), (100, 30));
+
+    assert!(matches!(
+        harness.input(b"\x03").await.unwrap(),
+        Action::Stop
+    ));
+    assert!(matches!(
+        harness.input("ls").await.unwrap(),
+        Action::Continue
+    ));
+
+    harness.set_line(0, "hello");
+    assert_eq!(harness.context().get_line(0).unwrap().trim_end(), "hello");
+
+    harness.resize(120, 40).await.unwrap();
+    assert_eq!(harness.plugin().resizes, vec![(120, 40)]);
+    assert_eq!(harness.context().get_size(), (120, 40));
+}
+
+#[tokio::test]
+async fn test_env_and_history_reach_plugin() {
+    let mut harness = PluginHarness::new(BuildWatcher::new())
+        .with_env("CARGO_TARGET", "x86_64")
+        .with_history(ScriptedHistory::new().with_line("$ cargo build"));
+    harness.output("error: oops").await.unwrap();
+    harness.take_commands();
+
+    harness.remote_command("summary").await.unwrap();
+
+    let notifications = harness.notifications();
+    assert_eq!(notifications[0].title, "Build summary");
+    assert_eq!(
+        notifications[0].body,
+        "1 failures on x86_64, last: $ cargo build"
+    );
+}
+
+#[tokio::test]
+#[should_panic(expected = "Expected a notification titled \"Build failed\"")]
+async fn test_assert_notified_fails_without_notification() {
+    let mut harness = PluginHarness::new(BuildWatcher::new());
+    harness.output("Finished dev profile").await.unwrap();
+    harness.assert_notified("Build failed");
+}
+
+#[tokio::test]
+#[should_panic(expected = "Invalid key combination")]
+async fn test_key_rejects_invalid_combo() {
+    let mut harness = PluginHarness::new(BuildWatcher::new());
+    let _ = harness.key("Ctrl+Nope").await;
+}
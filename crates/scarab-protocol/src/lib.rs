@@ -3,6 +3,7 @@
 // It must be #[repr(C)] to ensure memory layout consistency across processes.
 
 use bytemuck::{Pod, Zeroable};
+use core::sync::atomic::{fence, Ordering};
 
 // Safe abstraction layer for SharedState access
 pub mod terminal_state;
@@ -20,12 +21,32 @@ pub const SHMEM_PATH: &str = "/scarab_shm_v1";
 /// Useful for sandboxed environments where /dev/shm is not writable.
 pub const SHMEM_PATH_ENV: &str = "SCARAB_SHMEM_PATH";
 
+/// Shared memory path for a session attached by a secondary client window
+///
+/// Named after `base` (`SHMEM_PATH` or its override) and the session id.
+/// Characters that are not valid in a POSIX shm name become `_`.
+pub fn session_shmem_path(base: &str, session_id: &str) -> alloc::string::String {
+    let mut path = alloc::string::String::from(base);
+    path.push('_');
+    path.extend(session_id.chars().map(|c| {
+        if c.is_ascii_alphanumeric() || c == '-' {
+            c
+        } else {
+            '_'
+        }
+    }));
+    path
+}
+
 /// Environment variable to override the image shared memory path.
 pub const IMAGE_SHMEM_PATH_ENV: &str = "SCARAB_IMAGE_SHMEM_PATH";
 pub const GRID_WIDTH: usize = 200;
 pub const GRID_HEIGHT: usize = 100;
 pub const BUFFER_SIZE: usize = GRID_WIDTH * GRID_HEIGHT;
 
+/// Number of `u64` words in the per-row damage bitmask of `SharedState`
+pub const DAMAGE_WORDS: usize = GRID_HEIGHT.div_ceil(64);
+
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
 pub struct Cell {
@@ -36,6 +57,13 @@ pub struct Cell {
     pub _padding: [u8; 3], // Align to 16 bytes
 }
 
+/// Attribute bits in `Cell::flags`, as written by the daemon's SGR handling
+pub const FLAG_BOLD: u8 = 1 << 0;
+pub const FLAG_ITALIC: u8 = 1 << 1;
+pub const FLAG_UNDERLINE: u8 = 1 << 2;
+pub const FLAG_INVERSE: u8 = 1 << 3;
+pub const FLAG_DIM: u8 = 1 << 4;
+
 impl Default for Cell {
     fn default() -> Self {
         Self {
@@ -57,12 +85,87 @@ pub struct SharedState {
     pub error_mode: u8, // 0 = normal mode, 1 = error mode (PTY/SHM unavailable)
     pub cursor_x: u16,
     pub cursor_y: u16,
-    pub _padding2: [u8; 2], // Align to u64 boundary for cells array
+    pub cursor_style: u8, // Last DECSCUSR parameter (0 = client default, see CURSOR_STYLE_*)
+    pub _padding2: [u8; 1], // Align to u64 boundary for damage and cells
+    // Sequence the damage bitmask belongs to (0 while it is being rewritten)
+    pub damage_sequence: u64,
+    // Rows changed by the update that produced `damage_sequence`, one bit per row
+    pub damage_rows: [u64; DAMAGE_WORDS],
     // Fixed size buffer for the "visible" screen.
     // In production, use offset pointers to a larger ring buffer.
     pub cells: [Cell; BUFFER_SIZE],
 }
 
+/// DECSCUSR cursor styles (`CSI Ps SP q`) as stored in `SharedState::cursor_style`
+///
+/// `CURSOR_STYLE_DEFAULT` means the application has not requested a shape and
+/// the client's configured style applies.
+pub const CURSOR_STYLE_DEFAULT: u8 = 0;
+pub const CURSOR_STYLE_BLINKING_BLOCK: u8 = 1;
+pub const CURSOR_STYLE_STEADY_BLOCK: u8 = 2;
+pub const CURSOR_STYLE_BLINKING_UNDERLINE: u8 = 3;
+pub const CURSOR_STYLE_STEADY_UNDERLINE: u8 = 4;
+pub const CURSOR_STYLE_BLINKING_BAR: u8 = 5;
+pub const CURSOR_STYLE_STEADY_BAR: u8 = 6;
+
+impl SharedState {
+    /// Whether a row is set in the damage bitmask
+    pub fn is_row_damaged(&self, row: usize) -> bool {
+        row_damaged(&self.damage_rows, row)
+    }
+
+    /// Set a row in the damage bitmask
+    pub fn mark_row_damaged(&mut self, row: usize) {
+        if row < GRID_HEIGHT {
+            self.damage_rows[row / 64] |= 1 << (row % 64);
+        }
+    }
+
+    /// Clear the damage bitmask
+    pub fn clear_damage(&mut self) {
+        self.damage_rows = [0; DAMAGE_WORDS];
+    }
+
+    /// Sequence number, read volatile so a concurrent writer is observed
+    #[inline]
+    pub fn sequence(&self) -> u64 {
+        // SAFETY: `sequence_number` is a plain aligned u64 owned by self
+        unsafe { core::ptr::read_volatile(&self.sequence_number) }
+    }
+
+    /// Seqlock read: run `read` and check the sequence didn't move meanwhile
+    ///
+    /// Retries up to `max_retries` times (0 = unlimited). Returns the
+    /// sequence the data belongs to, or `None` if every attempt was torn.
+    pub fn read_consistent<T>(
+        &self,
+        max_retries: u32,
+        mut read: impl FnMut(&Self) -> T,
+    ) -> Option<(u64, T)> {
+        let mut attempts = 0u32;
+        loop {
+            let before = self.sequence();
+            fence(Ordering::Acquire);
+            let data = read(self);
+            fence(Ordering::Acquire);
+            if self.sequence() == before {
+                return Some((before, data));
+            }
+
+            core::hint::spin_loop();
+            attempts += 1;
+            if max_retries > 0 && attempts >= max_retries {
+                return None;
+            }
+        }
+    }
+}
+
+/// Whether a row is set in a damage bitmask
+pub fn row_damaged(damage: &[u64; DAMAGE_WORDS], row: usize) -> bool {
+    row < GRID_HEIGHT && damage[row / 64] & (1 << (row % 64)) != 0
+}
+
 // Manual implementations needed for large arrays
 unsafe impl Pod for SharedState {}
 unsafe impl Zeroable for SharedState {}
@@ -177,6 +280,36 @@ pub enum SplitDirection {
     Vertical,
 }
 
+/// The tab or pane a config override applies to
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[archive(check_bytes)]
+pub enum ConfigScope {
+    Tab(u64),
+    Pane(u64),
+}
+
+/// A setting by dotted key, with its value as TOML
+#[derive(Debug, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct ConfigEntry {
+    pub key: alloc::string::String,
+    pub value: alloc::string::String,
+}
+
+/// The user's answer when a plugin first uses a sensitive capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum PermissionDecision {
+    /// Allow this one use
+    AllowOnce,
+    /// Allow from now on
+    AllowAlways,
+    /// Refuse from now on
+    Deny,
+}
+
 // Menu action types from plugin API
 #[derive(Debug, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[archive(check_bytes)]
@@ -229,6 +362,8 @@ pub enum ControlMessage {
     SessionList,
     SessionAttach {
         id: alloc::string::String,
+        /// Watch the session without sending it input
+        read_only: bool,
     },
     SessionDetach {
         id: alloc::string::String,
@@ -237,10 +372,36 @@ pub enum ControlMessage {
         id: alloc::string::String,
         new_name: alloc::string::String,
     },
+    /// Make clients that can't type into this client's session yet attach
+    /// read-only, or let every client type again
+    SessionSetAccess {
+        read_only_by_default: bool,
+    },
+    /// Save every session's tabs and panes under `name`
+    WorkspaceSave {
+        name: alloc::string::String,
+    },
+    /// Recreate the sessions saved under `name`
+    WorkspaceRestore {
+        name: alloc::string::String,
+    },
+
+    /// List the configured domains (local, SSH, serial) with their status
+    DomainList,
+    /// Connect a domain, or reconnect it if it is connected
+    DomainConnect {
+        id: alloc::string::String,
+    },
+    /// Disconnect a domain; its panes stop receiving output
+    DomainDisconnect {
+        id: alloc::string::String,
+    },
 
     // Tab management commands
+    /// Open a tab, with its first pane in `domain` (by default, locally)
     TabCreate {
         title: Option<alloc::string::String>,
+        domain: Option<alloc::string::String>,
     },
     TabClose {
         tab_id: u64,
@@ -255,9 +416,12 @@ pub enum ControlMessage {
     TabList,
 
     // Pane management commands
+    /// Split a pane; the new pane runs in `domain`, or in the domain of
+    /// the tab when it is `None`
     PaneSplit {
         pane_id: u64,
         direction: SplitDirection,
+        domain: Option<alloc::string::String>,
     },
     PaneClose {
         pane_id: u64,
@@ -270,6 +434,13 @@ pub enum ControlMessage {
         width: u16,
         height: u16,
     },
+    /// Type into a pane of the default session without running plugin
+    /// input hooks; pane IDs are only unique within their tab
+    PaneInput {
+        tab_id: u64,
+        pane_id: u64,
+        data: alloc::vec::Vec<u8>,
+    },
     /// Focus the next pane in the current tab (for navigation)
     PaneFocusNext,
     /// Focus the previous pane in the current tab (for navigation)
@@ -288,11 +459,31 @@ pub enum ControlMessage {
         row: u16,
         button: u8,
     },
+    /// Mouse event for daemon plugins, decoded by the client
+    MouseEvent {
+        event: MouseEvent,
+    },
 
     // Remote UI Responses
     CommandSelected {
         id: alloc::string::String,
     },
+    /// Answer to a `ShowInputPrompt` or `ShowForm`; `None` when cancelled
+    PromptResponse {
+        prompt_id: u64,
+        values: Option<alloc::vec::Vec<alloc::string::String>>,
+    },
+    /// Answer to a `ShowPermissionPrompt`
+    PermissionResponse {
+        prompt_id: u64,
+        decision: PermissionDecision,
+    },
+    /// The user picked an item of a plugin's overlay panel
+    PanelItemSelected {
+        plugin_name: alloc::string::String,
+        overlay_id: u64,
+        item_id: alloc::string::String,
+    },
 
     // Plugin inspection commands
     PluginListRequest,
@@ -364,6 +555,48 @@ pub enum ControlMessage {
     ExtractZoneText {
         zone_id: u64,
     },
+
+    // Scrollback search over the active pane's history and visible grid
+    ScrollbackSearch {
+        query: alloc::string::String,
+        case_sensitive: bool,
+        use_regex: bool,
+        max_results: u32,
+        /// Lines to look through
+        scope: SearchScope,
+    },
+    /// Request the text of scrollback lines `start..start + count`
+    ScrollbackFetch {
+        start: u32,
+        count: u32,
+    },
+
+    // Runtime configuration
+    /// Read a setting by dotted key, such as `font.size`
+    ConfigGet {
+        key: alloc::string::String,
+    },
+    /// Change a setting; `value` is TOML, or a bare string
+    ///
+    /// With `persist`, the setting is also written to `config.toml`.
+    ConfigSet {
+        key: alloc::string::String,
+        value: alloc::string::String,
+        persist: bool,
+    },
+    /// Override a setting for one tab or pane; a `value` of `None` removes
+    /// the override
+    ///
+    /// Overrides are never persisted and go away with their tab or pane.
+    ConfigOverride {
+        scope: ConfigScope,
+        key: alloc::string::String,
+        value: Option<alloc::string::String>,
+    },
+    /// Remove every override for a tab or pane
+    ConfigOverrideClear {
+        scope: ConfigScope,
+    },
 }
 
 // Session response messages
@@ -382,6 +615,10 @@ pub enum SessionResponse {
     },
     Attached {
         id: alloc::string::String,
+        /// Shared memory region the session's active pane is drawn to
+        shmem_path: alloc::string::String,
+        /// Input from this client is ignored
+        read_only: bool,
     },
     Detached {
         id: alloc::string::String,
@@ -390,6 +627,20 @@ pub enum SessionResponse {
         id: alloc::string::String,
         new_name: alloc::string::String,
     },
+    AccessChanged {
+        id: alloc::string::String,
+        read_only_by_default: bool,
+    },
+    WorkspaceSaved {
+        name: alloc::string::String,
+        sessions: u32,
+    },
+    WorkspaceRestored {
+        name: alloc::string::String,
+        /// Sessions created; those already running under the same name
+        /// are left alone
+        session_ids: alloc::vec::Vec<alloc::string::String>,
+    },
     Error {
         message: alloc::string::String,
     },
@@ -403,6 +654,12 @@ pub struct SessionInfo {
     pub created_at: u64,
     pub last_attached: u64,
     pub attached_clients: u32,
+    /// Attached clients whose input is ignored
+    pub read_only_clients: u32,
+    /// Clients that can't type into the session yet may only watch it
+    pub read_only_by_default: bool,
+    /// Seconds since a client last typed into the session
+    pub idle_secs: u64,
 }
 
 // Tab information
@@ -414,6 +671,21 @@ pub struct TabInfo {
     pub session_id: Option<alloc::string::String>,
     pub is_active: bool,
     pub pane_count: u32,
+    /// Background tab produced output since it was last viewed
+    pub has_activity: bool,
+}
+
+/// A domain panes can run in, as listed by `DomainList`
+#[derive(Debug, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct DomainInfo {
+    pub id: alloc::string::String,
+    pub name: alloc::string::String,
+    /// "local", "ssh" or "serial"
+    pub kind: alloc::string::String,
+    pub connected: bool,
+    /// Where tabs and panes open when no domain is given
+    pub is_default: bool,
 }
 
 // Pane layout information
@@ -510,6 +782,11 @@ pub enum DaemonMessage {
         tabs: alloc::vec::Vec<TabInfo>,
     },
 
+    // Domain state updates
+    DomainListResponse {
+        domains: alloc::vec::Vec<DomainInfo>,
+    },
+
     // Pane state updates
     PaneCreated {
         pane: PaneInfo,
@@ -547,6 +824,36 @@ pub enum DaemonMessage {
         items: alloc::vec::Vec<ModalItem>,
     },
     HideModal,
+    /// Ask the user for a single line of text
+    ShowInputPrompt {
+        prompt_id: u64,
+        title: alloc::string::String,
+        placeholder: alloc::string::String,
+        masked: bool,
+    },
+    /// Ask the user to fill in several text fields
+    ShowForm {
+        prompt_id: u64,
+        title: alloc::string::String,
+        fields: alloc::vec::Vec<PromptField>,
+    },
+    /// Ask the user whether a plugin may use a capability
+    ShowPermissionPrompt {
+        prompt_id: u64,
+        plugin_name: alloc::string::String,
+        /// Capability name, e.g. `exec`
+        capability: alloc::string::String,
+        /// What the plugin was doing, e.g. the program it tried to run
+        detail: alloc::string::String,
+    },
+    /// Take down a permission prompt that was answered elsewhere or timed out
+    PermissionPromptClosed {
+        prompt_id: u64,
+    },
+    /// Put text on the system clipboard for a plugin
+    SetClipboard {
+        text: alloc::string::String,
+    },
 
     // Plugin inspection responses
     PluginList {
@@ -572,6 +879,15 @@ pub enum DaemonMessage {
         body: alloc::string::String,
         level: NotifyLevel,
     },
+    /// Progress of a plugin's background task; `done` closes the indicator
+    TaskProgress {
+        plugin_name: alloc::string::String,
+        task_id: u64,
+        title: alloc::string::String,
+        percent: u8,
+        message: alloc::string::String,
+        done: bool,
+    },
 
     // Plugin menu response
     PluginMenuResponse {
@@ -594,6 +910,18 @@ pub enum DaemonMessage {
         markers: alloc::vec::Vec<PromptMarkerInfo>,
     },
 
+    /// Progress of a tab's panes changed (OSC 9;4)
+    ProgressUpdate {
+        tab_id: u64,
+        /// Most severe progress among the tab's panes
+        state: ProgressState,
+    },
+
+    /// Hyperlinks (OSC 8) on the visible screen of the active pane changed
+    HyperlinksUpdate {
+        links: alloc::vec::Vec<HyperlinkInfo>,
+    },
+
     // Semantic zones update (deep shell integration)
     SemanticZonesUpdate {
         /// List of current semantic zones (prompt, input, output regions)
@@ -604,6 +932,12 @@ pub enum DaemonMessage {
     CommandBlocksUpdate {
         /// List of completed command blocks
         blocks: alloc::vec::Vec<CommandBlock>,
+        /// Lines of scrollback when sent; block lines from here on are
+        /// rows of the screen
+        scrollback_lines: u32,
+        /// A command has started (OSC 133;C) and not finished yet, so
+        /// input goes to it rather than the shell
+        command_running: bool,
     },
 
     /// Response to ExtractZoneText with the zone's text content
@@ -654,6 +988,12 @@ pub enum DaemonMessage {
         content: alloc::string::String,
         style: OverlayStyle,
     },
+    /// Spawn a multi-line overlay panel; removed with `RemoveOverlay`
+    SpawnPanel {
+        plugin_name: alloc::string::String,
+        overlay_id: u64,
+        panel: OverlayPanel,
+    },
     /// Remove a previously spawned overlay
     RemoveOverlay {
         plugin_name: alloc::string::String,
@@ -667,6 +1007,16 @@ pub enum DaemonMessage {
         content: alloc::string::String,
         priority: i32,
     },
+    /// Add or replace a styled status bar segment
+    ///
+    /// Shares IDs with `AddStatusItem` and is removed by `RemoveStatusItem`.
+    StatusSegment {
+        plugin_name: alloc::string::String,
+        item_id: u64,
+        side: StatusBarSide,
+        priority: i32,
+        items: alloc::vec::Vec<StatusRenderItem>,
+    },
     /// Remove a status bar item
     RemoveStatusItem {
         plugin_name: alloc::string::String,
@@ -681,7 +1031,8 @@ pub enum DaemonMessage {
     ThemeApply {
         theme_name: alloc::string::String,
     },
-    /// Set a specific palette color
+    /// Set a single color, named by its `[colors]` key such as `background`
+    /// or `palette.red`
     PaletteColorSet {
         color_name: alloc::string::String,
         value: alloc::string::String,
@@ -691,6 +1042,176 @@ pub enum DaemonMessage {
         plugin_name: alloc::string::String,
         theme_name: alloc::string::String,
     },
+    /// Matches for a `ScrollbackSearch`, oldest first
+    ScrollbackSearchResults {
+        query: alloc::string::String,
+        /// Lines in scrollback when searched; grid row N is line `scrollback_lines + N`
+        scrollback_lines: u32,
+        /// Number of matches found, which may exceed `matches.len()`
+        total: u32,
+        matches: alloc::vec::Vec<SearchMatch>,
+        /// Set when the query was not a valid regex
+        error: Option<alloc::string::String>,
+    },
+    /// Text of scrollback lines for a `ScrollbackFetch`
+    ScrollbackLines {
+        start: u32,
+        lines: alloc::vec::Vec<alloc::string::String>,
+    },
+    /// Answer to a `ConfigGet` or `ConfigSet`
+    ConfigValue {
+        key: alloc::string::String,
+        /// The setting's value as TOML, after any change
+        value: Option<alloc::string::String>,
+        /// Why the setting could not be read or changed
+        error: Option<alloc::string::String>,
+    },
+    /// A setting was changed at runtime; `value` is TOML
+    ConfigChanged {
+        key: alloc::string::String,
+        value: alloc::string::String,
+    },
+    /// Overrides in effect for the focused pane: its tab's, then the pane's
+    /// own, with the pane's winning for a key set in both
+    ConfigOverrides {
+        tab_id: u64,
+        pane_id: u64,
+        settings: alloc::vec::Vec<ConfigEntry>,
+    },
+    /// A pane was given a theme of its own, or went back to the window's
+    /// with `None`
+    PanePaletteUpdate {
+        pane_id: u64,
+        palette: Option<PanePalette>,
+    },
+}
+
+/// Colors of a theme given to a single pane, as `0xAARRGGBB` like cells
+#[derive(Debug, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct PanePalette {
+    /// Theme ID, such as "dracula"
+    pub theme: alloc::string::String,
+    pub foreground: u32,
+    pub background: u32,
+    pub cursor: u32,
+    /// ANSI colors 0-15
+    pub ansi: [u32; 16],
+}
+
+/// A search match on one line of scrollback or the visible grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct SearchMatch {
+    /// Absolute line (scrollback lines first, then grid rows)
+    pub line: u32,
+    /// First matching column
+    pub col: u16,
+    /// Match length in cells
+    pub len: u16,
+}
+
+/// Lines a `ScrollbackSearch` looks through
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[archive(check_bytes)]
+pub enum SearchScope {
+    /// The pane's screen, without its scrollback
+    Screen,
+    /// Output of the last command, bounded by its shell integration zone
+    LastCommand,
+    /// The whole scrollback and the screen
+    #[default]
+    All,
+}
+
+impl SearchScope {
+    /// The scope after this one, for cycling through them
+    pub fn next(self) -> Self {
+        match self {
+            SearchScope::All => SearchScope::LastCommand,
+            SearchScope::LastCommand => SearchScope::Screen,
+            SearchScope::Screen => SearchScope::All,
+        }
+    }
+
+    /// Short name shown in the search bar
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchScope::Screen => "screen",
+            SearchScope::LastCommand => "last command",
+            SearchScope::All => "all",
+        }
+    }
+}
+
+/// Progress reported by a program through OSC 9;4 (ConEmu / Windows Terminal)
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[archive(check_bytes)]
+pub enum ProgressState {
+    /// No progress shown
+    #[default]
+    Hidden,
+    /// Progress in percent (0-100)
+    Normal(u8),
+    /// Failed, with the percent reached
+    Error(u8),
+    /// Busy without a known percentage
+    Indeterminate,
+    /// Paused or warning, with the percent reached
+    Paused(u8),
+}
+
+impl ProgressState {
+    /// Percent complete, if the state carries one
+    pub fn percent(&self) -> Option<u8> {
+        match self {
+            ProgressState::Normal(p) | ProgressState::Error(p) | ProgressState::Paused(p) => {
+                Some(*p)
+            }
+            ProgressState::Hidden | ProgressState::Indeterminate => None,
+        }
+    }
+
+    /// Whether there is anything to show
+    pub fn is_visible(&self) -> bool {
+        !matches!(self, ProgressState::Hidden)
+    }
+
+    /// Rank used to pick one state for several panes (errors win)
+    pub fn severity(&self) -> u8 {
+        match self {
+            ProgressState::Hidden => 0,
+            ProgressState::Indeterminate => 1,
+            ProgressState::Normal(_) => 2,
+            ProgressState::Paused(_) => 3,
+            ProgressState::Error(_) => 4,
+        }
+    }
+}
+
+/// A run of cells on one visible row carrying an OSC 8 hyperlink
+#[derive(Debug, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct HyperlinkInfo {
+    /// Screen row (0 = top of the visible grid)
+    pub row: u16,
+    /// First linked column
+    pub start_col: u16,
+    /// Last linked column (inclusive)
+    pub end_col: u16,
+    /// Link target
+    pub uri: alloc::string::String,
+}
+
+impl HyperlinkInfo {
+    /// Whether the cell at (col, row) is part of this link
+    pub fn contains(&self, col: u16, row: u16) -> bool {
+        self.row == row && (self.start_col..=self.end_col).contains(&col)
+    }
 }
 
 /// Direction for prompt jump navigation
@@ -747,13 +1268,202 @@ pub struct ModalItem {
     pub description: Option<alloc::string::String>,
 }
 
+/// One text field of a plugin form
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct PromptField {
+    pub id: alloc::string::String,
+    pub label: alloc::string::String,
+    pub placeholder: alloc::string::String,
+    /// Hide typed characters, e.g. for passwords
+    pub masked: bool,
+}
+
+/// Border drawn around an overlay panel
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[archive(check_bytes)]
+pub enum PanelBorder {
+    None,
+    #[default]
+    Single,
+    Rounded,
+    Double,
+}
+
+/// Horizontal alignment of an overlay panel's text
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[archive(check_bytes)]
+pub enum PanelAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Selectable row of an overlay panel
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct PanelItem {
+    /// ID handed back to the plugin when the item is picked
+    pub id: alloc::string::String,
+    pub label: alloc::string::String,
+}
+
+/// Floating panel drawn by a plugin
+///
+/// Text lines are shown first, then the selectable items. Long lines wrap
+/// at `max_width` columns.
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct OverlayPanel {
+    /// Column of the panel's top-left corner
+    pub x: u16,
+    /// Row of the panel's top-left corner
+    pub y: u16,
+    pub title: Option<alloc::string::String>,
+    pub lines: alloc::vec::Vec<alloc::string::String>,
+    pub items: alloc::vec::Vec<PanelItem>,
+    pub border: PanelBorder,
+    pub align: PanelAlign,
+    /// Widest the panel may grow, in columns
+    pub max_width: Option<u16>,
+    pub style: OverlayStyle,
+}
+
+impl OverlayPanel {
+    /// Create an empty panel at a grid position
+    pub fn new(x: u16, y: u16) -> Self {
+        Self {
+            x,
+            y,
+            title: None,
+            lines: alloc::vec::Vec::new(),
+            items: alloc::vec::Vec::new(),
+            border: PanelBorder::default(),
+            align: PanelAlign::default(),
+            max_width: None,
+            style: OverlayStyle::default(),
+        }
+    }
+
+    pub fn with_title(mut self, title: impl Into<alloc::string::String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Add a line of text
+    pub fn with_line(mut self, line: impl Into<alloc::string::String>) -> Self {
+        self.lines.push(line.into());
+        self
+    }
+
+    /// Add an item the user can pick
+    pub fn with_item(
+        mut self,
+        id: impl Into<alloc::string::String>,
+        label: impl Into<alloc::string::String>,
+    ) -> Self {
+        self.items.push(PanelItem {
+            id: id.into(),
+            label: label.into(),
+        });
+        self
+    }
+
+    pub fn with_border(mut self, border: PanelBorder) -> Self {
+        self.border = border;
+        self
+    }
+
+    pub fn with_align(mut self, align: PanelAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_max_width(mut self, columns: u16) -> Self {
+        self.max_width = Some(columns);
+        self
+    }
+
+    pub fn with_style(mut self, style: OverlayStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+/// Kind of mouse event offered to plugins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum MouseEventKind {
+    Press,
+    Release,
+    /// Pointer moved to another cell with a button held
+    Drag,
+    Scroll,
+}
+
+/// Mouse button, with wheel directions as buttons like in xterm reporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    WheelUp,
+    WheelDown,
+}
+
+/// Mouse event in terminal grid coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub button: MouseButton,
+    pub col: u16,
+    pub row: u16,
+    /// Modifier bits in the layout of the plugin API's `KeyModifiers`
+    pub modifiers: u8,
+    /// 1 for a single click, 2 for double, 3 for triple; 0 unless `Press`
+    pub click_count: u8,
+}
+
 // IPC configuration constants
 pub const SOCKET_PATH: &str = "/tmp/scarab-daemon.sock";
 pub const MAX_MESSAGE_SIZE: usize = 8192;
+/// Bytes in the big-endian `u32` length that precedes every message body
+pub const FRAME_HEADER_LEN: usize = 4;
 pub const MAX_CLIENTS: usize = 16;
 pub const RECONNECT_DELAY_MS: u64 = 100;
 pub const MAX_RECONNECT_ATTEMPTS: u32 = 10;
 
+/// Wire bytes for an rkyv-serialized message: length header, then body
+///
+/// Returns `None` if the body is larger than `MAX_MESSAGE_SIZE`.
+pub fn encode_frame(body: &[u8]) -> Option<alloc::vec::Vec<u8>> {
+    if body.len() > MAX_MESSAGE_SIZE {
+        return None;
+    }
+    let mut frame = alloc::vec::Vec::with_capacity(FRAME_HEADER_LEN + body.len());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(body);
+    Some(frame)
+}
+
+/// Body length announced by a frame header, or `None` if it is out of range
+pub fn frame_body_len(header: [u8; FRAME_HEADER_LEN]) -> Option<usize> {
+    let len = u32::from_be_bytes(header) as usize;
+    (len > 0 && len <= MAX_MESSAGE_SIZE).then_some(len)
+}
+
+/// Most matches a `ScrollbackSearchResults` carries so it fits in one message
+pub const MAX_SEARCH_RESULTS: u32 = 512;
+/// Text budget for one `ScrollbackLines` reply; clients fetch in chunks
+pub const SCROLLBACK_FETCH_BYTES: usize = 6144;
+
 /// Terminal display metrics shared between rendering and input systems
 ///
 /// This provides the coordinate conversion information needed by:
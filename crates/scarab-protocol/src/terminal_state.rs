@@ -68,6 +68,24 @@ pub trait TerminalStateReader {
     /// Tuple of (x, y) cursor coordinates in grid space
     fn cursor_pos(&self) -> (u16, u16);
 
+    /// Get the cursor style requested by the application (DECSCUSR)
+    ///
+    /// # Returns
+    /// One of the `CURSOR_STYLE_*` constants; `CURSOR_STYLE_DEFAULT` when
+    /// the application has not requested a style
+    fn cursor_style(&self) -> u8 {
+        crate::CURSOR_STYLE_DEFAULT
+    }
+
+    /// Get the rows changed by the update that produced `sequence()`
+    ///
+    /// # Returns
+    /// * `Some(bitmask)` with one bit per row (see `crate::row_damaged`)
+    /// * `None` if damage is unknown; callers should treat every row as changed
+    fn row_damage(&self) -> Option<[u64; crate::DAMAGE_WORDS]> {
+        None
+    }
+
     /// Get current sequence number
     ///
     /// The sequence number increments with each state update.
@@ -3,6 +3,7 @@
 //! Domains represent different execution environments where terminal panes can run:
 //! - LocalDomain: PTY processes on the local machine
 //! - SshDomain: Remote shells over SSH connections
+//! - SerialDomain: Devices on a serial port
 //!
 //! This abstraction enables:
 //! - Cross-domain pane splits (local + remote panes in same session)
@@ -77,6 +78,12 @@ pub trait Domain: Send + Sync {
     /// For SshDomain, this re-establishes SSH connection.
     async fn reconnect(&self) -> Result<()>;
 
+    /// Drop the connection and close the domain's panes
+    ///
+    /// A later `reconnect` connects again, but panes closed here stay
+    /// closed.
+    async fn disconnect(&self) -> Result<()>;
+
     /// Spawn a new pane in this domain
     ///
     /// Returns a handle that can be used to interact with the pane.
@@ -114,6 +121,8 @@ pub enum DomainType {
     Local,
     /// Remote SSH session
     Ssh,
+    /// Serial port device
+    Serial,
     /// Future: Docker container
     Docker,
     /// Future: Kubernetes pod
@@ -125,6 +134,7 @@ impl std::fmt::Display for DomainType {
         match self {
             DomainType::Local => write!(f, "local"),
             DomainType::Ssh => write!(f, "ssh"),
+            DomainType::Serial => write!(f, "serial"),
             DomainType::Docker => write!(f, "docker"),
             DomainType::Kubernetes => write!(f, "kubernetes"),
         }
@@ -184,9 +194,25 @@ impl DomainRegistry {
             .and_then(|id| self.domains.read().get(id).cloned())
     }
 
-    /// List all registered domains
+    /// ID of the default domain
+    pub fn default_id(&self) -> Option<DomainId> {
+        self.default_domain_id.read().clone()
+    }
+
+    /// Make `id` the default domain
+    pub fn set_default(&self, id: &DomainId) -> Result<()> {
+        if !self.domains.read().contains_key(id) {
+            anyhow::bail!("No domain named '{}'", id);
+        }
+        *self.default_domain_id.write() = Some(id.clone());
+        Ok(())
+    }
+
+    /// List all registered domains, the default first and the rest by ID
     pub fn list(&self) -> Vec<(DomainId, String, DomainType, bool)> {
-        self.domains
+        let default_id = self.default_id();
+        let mut domains: Vec<_> = self
+            .domains
             .read()
             .values()
             .map(|d| {
@@ -197,7 +223,29 @@ impl DomainRegistry {
                     d.is_connected(),
                 )
             })
-            .collect()
+            .collect();
+        domains.sort_by(|a, b| {
+            (Some(&b.0) == default_id.as_ref())
+                .cmp(&(Some(&a.0) == default_id.as_ref()))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        domains
+    }
+
+    /// Connect the domain `id`, or reconnect it if it is connected
+    pub async fn connect(&self, id: &DomainId) -> Result<()> {
+        let domain = self
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("No domain named '{}'", id))?;
+        domain.reconnect().await
+    }
+
+    /// Disconnect the domain `id`
+    pub async fn disconnect(&self, id: &DomainId) -> Result<()> {
+        let domain = self
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("No domain named '{}'", id))?;
+        domain.disconnect().await
     }
 
     /// Remove a domain
@@ -250,4 +298,32 @@ mod tests {
         assert_eq!(registry.count(), 0);
         assert!(registry.get_default().is_none());
     }
+
+    #[tokio::test]
+    async fn test_domain_registry_listing() {
+        use crate::local_domain::LocalDomain;
+
+        let registry = DomainRegistry::new();
+        registry.register(Arc::new(LocalDomain::new()));
+        registry.register(Arc::new(LocalDomain::with_id(
+            "build".to_string(),
+            "Build Box".to_string(),
+        )));
+        registry.register(Arc::new(LocalDomain::with_id(
+            "admin".to_string(),
+            "Admin".to_string(),
+        )));
+
+        let ids: Vec<DomainId> = registry.list().into_iter().map(|d| d.0).collect();
+        assert_eq!(ids, vec!["local", "admin", "build"]);
+
+        registry.set_default(&"build".to_string()).unwrap();
+        assert_eq!(registry.default_id().as_deref(), Some("build"));
+        assert_eq!(registry.list()[0].0, "build");
+        assert!(registry.set_default(&"nowhere".to_string()).is_err());
+
+        assert!(registry.connect(&"local".to_string()).await.is_ok());
+        assert!(registry.disconnect(&"local".to_string()).await.is_err());
+        assert!(registry.connect(&"nowhere".to_string()).await.is_err());
+    }
 }
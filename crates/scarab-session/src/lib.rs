@@ -1,20 +1,46 @@
 use async_trait::async_trait;
-use scarab_plugin_api::{Plugin, PluginContext, PluginMetadata, Result};
-use scarab_protocol::ModalItem;
+use scarab_platform::Paths;
+use scarab_plugin_api::types::RemoteCommand;
+use scarab_plugin_api::{Plugin, PluginContext, PluginMetadata, PromptResponse, Result};
+use scarab_protocol::{ControlMessage, ModalItem, SplitDirection};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 // Domain abstraction for terminal multiplexing
 pub mod domain;
 pub mod local_domain;
+pub mod serial_domain;
+pub mod ssh_auth;
 pub mod ssh_domain;
+pub mod ssh_hosts;
+pub mod workspace;
 
 pub use domain::{
     Domain, DomainId, DomainPaneHandle, DomainRegistry, DomainStats, DomainType, PaneConfig,
 };
 pub use local_domain::LocalDomain;
-pub use ssh_domain::{SshAuth, SshDomain, SshDomainConfig};
+pub use serial_domain::{SerialDomain, SerialDomainConfig};
+pub use ssh_auth::{AuthPrompt, AuthPrompter, AuthQuestion, ModalPrompter};
+pub use ssh_domain::{
+    AuthenticationFailed, HostKeyRejected, HostKeyStatus, SshAuth, SshConnectionPool, SshDomain,
+    SshDomainConfig,
+};
+pub use ssh_hosts::SshHost;
+pub use workspace::{PaneSnapshot, SessionSnapshot, TabSnapshot, WorkspaceSnapshot};
 
 pub struct SessionPlugin {
     metadata: PluginMetadata,
+    /// Shows SSH domains' credential prompts in the client
+    auth_prompter: Arc<ModalPrompter>,
+    /// Prompt asking for the name to save the workspace as, while it is open
+    save_prompt: Option<u64>,
+    /// Domains offered in the "Domains" palette
+    domains: Arc<DomainRegistry>,
+    /// Configured SSH domains, whose settings override the SSH config's
+    /// for hosts picked from it
+    ssh_overrides: Vec<SshDomainConfig>,
+    /// Where saved workspaces are listed from
+    workspaces_dir: PathBuf,
 }
 
 impl SessionPlugin {
@@ -26,7 +52,67 @@ impl SessionPlugin {
                 "Session management commands",
                 "Scarab Team",
             ),
+            auth_prompter: Arc::new(ModalPrompter::new()),
+            save_prompt: None,
+            domains: Arc::new(DomainRegistry::new()),
+            ssh_overrides: Vec::new(),
+            workspaces_dir: workspace::workspaces_dir(&Paths::from_env().data_dir),
+        }
+    }
+
+    /// Offer the domains of `registry` in the "Domains" palette
+    pub fn with_domains(mut self, registry: Arc<DomainRegistry>) -> Self {
+        self.domains = registry;
+        self
+    }
+
+    /// Use the settings of `domains` for the hosts they are configured for
+    /// when picked from the SSH config
+    pub fn with_ssh_overrides(mut self, domains: Vec<SshDomainConfig>) -> Self {
+        self.ssh_overrides = domains;
+        self
+    }
+
+    /// List saved workspaces from under `data_dir`, the data directory
+    /// resolved from the config
+    pub fn with_data_dir(mut self, data_dir: &Path) -> Self {
+        self.workspaces_dir = workspace::workspaces_dir(data_dir);
+        self
+    }
+
+    /// Open a tab on the host `alias` from the SSH config or known hosts,
+    /// adding a domain for it the first time
+    fn open_ssh_host(&self, alias: &str, ctx: &PluginContext) {
+        let home = ssh_home();
+        let Some(host) = ssh_hosts::discover_hosts(&home)
+            .into_iter()
+            .find(|host| host.alias == alias)
+        else {
+            ctx.notify_error(
+                "SSH",
+                &format!("'{}' is no longer in the SSH config", alias),
+            );
+            return;
+        };
+        let id = host.domain_id();
+        if self.domains.get(&id).is_none() {
+            let config = host.domain_config(&self.ssh_overrides, &home);
+            self.domains.register(Arc::new(
+                SshDomain::new(config).with_prompter(self.auth_prompter.clone()),
+            ));
         }
+        send_control(
+            ctx,
+            ControlMessage::TabCreate {
+                title: None,
+                domain: Some(id),
+            },
+        );
+    }
+
+    /// Prompter for SSH domains to ask the user for credentials through
+    pub fn auth_prompter(&self) -> Arc<ModalPrompter> {
+        self.auth_prompter.clone()
     }
 }
 
@@ -36,6 +122,35 @@ impl Plugin for SessionPlugin {
         &self.metadata
     }
 
+    async fn on_load(&mut self, ctx: &mut PluginContext) -> Result<()> {
+        self.auth_prompter.attach(ctx.clone());
+        Ok(())
+    }
+
+    async fn on_prompt_response(
+        &mut self,
+        response: &PromptResponse,
+        ctx: &PluginContext,
+    ) -> Result<()> {
+        if self.save_prompt == Some(response.prompt_id) {
+            self.save_prompt = None;
+            match response.value() {
+                Some(name) if !name.trim().is_empty() => {
+                    send_control(
+                        ctx,
+                        ControlMessage::WorkspaceSave {
+                            name: name.trim().to_string(),
+                        },
+                    );
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+        self.auth_prompter.answer(response);
+        Ok(())
+    }
+
     fn get_commands(&self) -> Vec<ModalItem> {
         vec![
             ModalItem {
@@ -53,6 +168,33 @@ impl Plugin for SessionPlugin {
                 label: "Detach Session".to_string(),
                 description: Some("Detach client from session".to_string()),
             },
+            ModalItem {
+                id: "workspace.save".to_string(),
+                label: "Workspace: Save".to_string(),
+                description: Some(
+                    "Save every session's tabs, panes and directories under a name".to_string(),
+                ),
+            },
+            ModalItem {
+                id: "workspace.restore".to_string(),
+                label: "Workspace: Restore".to_string(),
+                description: Some("Reopen the sessions of a saved workspace".to_string()),
+            },
+            ModalItem {
+                id: "ssh.hosts".to_string(),
+                label: "SSH: Connect to Host".to_string(),
+                description: Some(
+                    "Open a tab on a host from ~/.ssh/config or known_hosts".to_string(),
+                ),
+            },
+            ModalItem {
+                id: "domain.list".to_string(),
+                label: "Domains".to_string(),
+                description: Some(
+                    "Connect to SSH hosts and serial ports, or open tabs and panes on them"
+                        .to_string(),
+                ),
+            },
         ]
     }
 
@@ -84,8 +226,144 @@ impl Plugin for SessionPlugin {
                     "Session plugin: detach command should trigger SessionDetach control message"
                 );
             }
+            "workspace.save" => {
+                self.save_prompt = Some(ctx.show_input_prompt("Save Workspace", "Name", false));
+            }
+            "workspace.restore" => {
+                let names = workspace::list_workspaces(&self.workspaces_dir);
+                if names.is_empty() {
+                    ctx.notify_info("Workspace", "No saved workspaces; use Workspace: Save");
+                    return Ok(());
+                }
+                let items = names
+                    .into_iter()
+                    .map(|name| ModalItem {
+                        id: format!("workspace.restore:{}", name),
+                        label: name,
+                        description: None,
+                    })
+                    .collect();
+                ctx.queue_command(RemoteCommand::ShowModal {
+                    title: "Restore Workspace".to_string(),
+                    items,
+                });
+            }
+            "domain.list" => {
+                if self.domains.count() == 0 {
+                    ctx.notify_info("Domains", "No domains are configured");
+                    return Ok(());
+                }
+                ctx.queue_command(RemoteCommand::ShowModal {
+                    title: "Domains".to_string(),
+                    items: domain_items(&self.domains),
+                });
+            }
+            "ssh.hosts" => {
+                let hosts = ssh_hosts::discover_hosts(&ssh_home());
+                if hosts.is_empty() {
+                    ctx.notify_info("SSH", "No hosts in ~/.ssh/config or known_hosts");
+                    return Ok(());
+                }
+                let items = hosts
+                    .iter()
+                    .map(|host| ModalItem {
+                        id: format!("ssh.host:{}", host.alias),
+                        label: host.alias.clone(),
+                        description: Some(host.describe()),
+                    })
+                    .collect();
+                ctx.queue_command(RemoteCommand::ShowModal {
+                    title: "SSH Hosts".to_string(),
+                    items,
+                });
+            }
+            id if id.starts_with("ssh.host:") => {
+                let alias = id.strip_prefix("ssh.host:").unwrap();
+                self.open_ssh_host(alias, ctx);
+            }
+            id if id.starts_with("domain.") => {
+                let Some((action, domain)) = id
+                    .strip_prefix("domain.")
+                    .and_then(|rest| rest.split_once(':'))
+                else {
+                    return Ok(());
+                };
+                let domain = domain.to_string();
+                let message = match action {
+                    "tab" => ControlMessage::TabCreate {
+                        title: None,
+                        domain: Some(domain),
+                    },
+                    // Pane 0 is none, so the focused pane is split
+                    "split_right" => ControlMessage::PaneSplit {
+                        pane_id: 0,
+                        direction: SplitDirection::Vertical,
+                        domain: Some(domain),
+                    },
+                    "split_down" => ControlMessage::PaneSplit {
+                        pane_id: 0,
+                        direction: SplitDirection::Horizontal,
+                        domain: Some(domain),
+                    },
+                    "connect" => ControlMessage::DomainConnect { id: domain },
+                    "disconnect" => ControlMessage::DomainDisconnect { id: domain },
+                    _ => return Ok(()),
+                };
+                send_control(ctx, message);
+            }
+            id if id.starts_with("workspace.restore:") => {
+                let name = id.strip_prefix("workspace.restore:").unwrap();
+                send_control(
+                    ctx,
+                    ControlMessage::WorkspaceRestore {
+                        name: name.to_string(),
+                    },
+                );
+            }
             _ => {}
         }
         Ok(())
     }
 }
+
+/// What can be done with each domain: open a tab or split the focused pane
+/// there, and connect or disconnect it
+fn domain_items(registry: &DomainRegistry) -> Vec<ModalItem> {
+    let mut items = Vec::new();
+    for (id, name, kind, connected) in registry.list() {
+        let status = match (kind, connected) {
+            (DomainType::Local, _) => "local".to_string(),
+            (kind, true) => format!("{}, connected", kind),
+            (kind, false) => format!("{}, disconnected", kind),
+        };
+        let item = |action: &str, label: &str| ModalItem {
+            id: format!("domain.{}:{}", action, id),
+            label: format!("{}: {}", name, label),
+            description: Some(status.clone()),
+        };
+        items.push(item("tab", "New Tab"));
+        items.push(item("split_right", "Split Right"));
+        items.push(item("split_down", "Split Down"));
+        if kind != DomainType::Local {
+            let connect = if connected { "Reconnect" } else { "Connect" };
+            items.push(item("connect", connect));
+            if connected {
+                items.push(item("disconnect", "Disconnect"));
+            }
+        }
+    }
+    items
+}
+
+/// Home directory whose `.ssh` the host picker reads
+fn ssh_home() -> PathBuf {
+    std::env::var_os("HOME").map_or_else(|| PathBuf::from("/"), PathBuf::from)
+}
+
+/// Have the daemon handle `message` as if a client sent it
+fn send_control(ctx: &PluginContext, message: ControlMessage) {
+    ctx.queue_command(RemoteCommand::Control {
+        plugin_name: "scarab-session".to_string(),
+        message,
+    });
+}
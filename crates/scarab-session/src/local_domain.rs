@@ -111,6 +111,10 @@ impl Domain for LocalDomain {
         Ok(())
     }
 
+    async fn disconnect(&self) -> Result<()> {
+        bail!("The local domain can't be disconnected")
+    }
+
     async fn spawn_pane(&self, config: PaneConfig) -> Result<DomainPaneHandle> {
         // Allocate pane ID
         let pane_id = self.next_pane_id.fetch_add(1, Ordering::SeqCst);
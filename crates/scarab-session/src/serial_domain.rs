@@ -0,0 +1,452 @@
+//! Serial port domain for embedded development
+//!
+//! SerialDomain opens a serial device, such as `/dev/ttyUSB0` or `COM3`, as
+//! a pane, much like picocom or minicom: what is typed goes out on the line
+//! and what the device sends is shown.
+//!
+//! Features:
+//! - Baud rate, data bits, parity, stop bits and flow control
+//! - DTR and RTS control, e.g. to reset or bootload a board
+//! - Sending a break
+//! - Logging everything received to a file
+//! - Reopening the port after the device goes away, as USB adapters do when
+//!   a board resets
+
+use super::domain::{Domain, DomainId, DomainPaneHandle, DomainStats, DomainType, PaneConfig};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
+use serialport::SerialPort;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use serialport::{DataBits, FlowControl, Parity, StopBits};
+
+/// How long a read waits for the device before reporting no data
+const READ_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Port used when none is configured
+#[cfg(windows)]
+const DEFAULT_PORT: &str = "COM1";
+#[cfg(not(windows))]
+const DEFAULT_PORT: &str = "/dev/ttyUSB0";
+
+/// How long a break lasts
+const BREAK_DURATION: Duration = Duration::from_millis(250);
+
+/// Serial domain configuration
+#[derive(Debug, Clone)]
+pub struct SerialDomainConfig {
+    /// Unique identifier for this serial domain
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Device path, e.g. "/dev/ttyUSB0" or "COM3"
+    pub port: String,
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+    /// DTR level set on opening; `None` leaves it as the driver does
+    pub dtr: Option<bool>,
+    /// RTS level set on opening; `None` leaves it as the driver does
+    pub rts: Option<bool>,
+    /// File everything received is appended to
+    pub log_file: Option<PathBuf>,
+}
+
+impl Default for SerialDomainConfig {
+    fn default() -> Self {
+        Self {
+            id: "serial".to_string(),
+            name: "Serial Port".to_string(),
+            port: DEFAULT_PORT.to_string(),
+            baud_rate: 115_200,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            dtr: None,
+            rts: None,
+            log_file: None,
+        }
+    }
+}
+
+impl SerialDomainConfig {
+    /// Line settings the usual way, e.g. "115200 8N1"
+    pub fn line_settings(&self) -> String {
+        let data_bits = match self.data_bits {
+            DataBits::Five => 5,
+            DataBits::Six => 6,
+            DataBits::Seven => 7,
+            DataBits::Eight => 8,
+        };
+        let parity = match self.parity {
+            Parity::None => 'N',
+            Parity::Odd => 'O',
+            Parity::Even => 'E',
+        };
+        let stop_bits = match self.stop_bits {
+            StopBits::One => 1,
+            StopBits::Two => 2,
+        };
+        format!("{} {}{}{}", self.baud_rate, data_bits, parity, stop_bits)
+    }
+
+    /// Open the port with these settings
+    fn open(&self) -> Result<Box<dyn SerialPort>> {
+        let mut port = serialport::new(&self.port, self.baud_rate)
+            .data_bits(self.data_bits)
+            .parity(self.parity)
+            .stop_bits(self.stop_bits)
+            .flow_control(self.flow_control)
+            .timeout(READ_TIMEOUT)
+            .open()
+            .with_context(|| format!("Failed to open serial port {}", self.port))?;
+        if let Some(dtr) = self.dtr {
+            port.write_data_terminal_ready(dtr)?;
+        }
+        if let Some(rts) = self.rts {
+            port.write_request_to_send(rts)?;
+        }
+        Ok(port)
+    }
+}
+
+/// Serial devices present on this machine
+pub fn available_ports() -> Vec<String> {
+    match serialport::available_ports() {
+        Ok(ports) => ports.into_iter().map(|port| port.port_name).collect(),
+        Err(e) => {
+            log::warn!("Failed to list serial ports: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// The open port of a pane
+struct SerialPane {
+    /// Used for writing and the control lines
+    port: Mutex<Box<dyn SerialPort>>,
+    /// A clone of the port for reading, so reads don't hold up writes
+    reader: Mutex<Box<dyn SerialPort>>,
+    log: Option<Mutex<File>>,
+    dtr: AtomicBool,
+    rts: AtomicBool,
+}
+
+/// Serial port domain
+///
+/// A port can only be opened once, so the domain has at most one pane.
+pub struct SerialDomain {
+    config: SerialDomainConfig,
+    /// Active panes: pane_id -> port
+    panes: Arc<RwLock<HashMap<u64, Arc<SerialPane>>>>,
+    /// Next pane ID to assign
+    next_pane_id: AtomicU64,
+    /// Statistics
+    stats: Arc<RwLock<DomainStats>>,
+}
+
+impl SerialDomain {
+    /// Create a new serial domain (port not opened)
+    pub fn new(config: SerialDomainConfig) -> Self {
+        Self {
+            config,
+            panes: Arc::new(RwLock::new(HashMap::new())),
+            next_pane_id: AtomicU64::new(1),
+            stats: Arc::new(RwLock::new(DomainStats::default())),
+        }
+    }
+
+    pub fn config(&self) -> &SerialDomainConfig {
+        &self.config
+    }
+
+    fn pane(&self, handle: &DomainPaneHandle) -> Result<Arc<SerialPane>> {
+        if handle.domain_id != self.config.id {
+            bail!("Pane handle domain mismatch");
+        }
+        match self.panes.read().get(&handle.pane_id) {
+            Some(pane) => Ok(Arc::clone(pane)),
+            None => bail!("Serial pane {} not found", handle.pane_id),
+        }
+    }
+
+    /// Open the port and everything a pane needs with it
+    fn open_pane(&self) -> Result<SerialPane> {
+        let port = self.config.open()?;
+        let reader = port.try_clone().context("Failed to clone serial port")?;
+        let log = match &self.config.log_file {
+            Some(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Failed to open serial log {}", path.display()))?;
+                Some(Mutex::new(file))
+            }
+            None => None,
+        };
+        Ok(SerialPane {
+            port: Mutex::new(port),
+            reader: Mutex::new(reader),
+            log,
+            dtr: AtomicBool::new(self.config.dtr.unwrap_or(true)),
+            rts: AtomicBool::new(self.config.rts.unwrap_or(true)),
+        })
+    }
+
+    /// Set the DTR line
+    pub fn set_dtr(&self, handle: &DomainPaneHandle, level: bool) -> Result<()> {
+        let pane = self.pane(handle)?;
+        pane.port.lock().write_data_terminal_ready(level)?;
+        pane.dtr.store(level, Ordering::SeqCst);
+        log::debug!("Serial: DTR {} on {}", level, self.config.port);
+        Ok(())
+    }
+
+    /// Set the RTS line
+    pub fn set_rts(&self, handle: &DomainPaneHandle, level: bool) -> Result<()> {
+        let pane = self.pane(handle)?;
+        pane.port.lock().write_request_to_send(level)?;
+        pane.rts.store(level, Ordering::SeqCst);
+        log::debug!("Serial: RTS {} on {}", level, self.config.port);
+        Ok(())
+    }
+
+    /// Flip the DTR line, returning its new level
+    pub fn toggle_dtr(&self, handle: &DomainPaneHandle) -> Result<bool> {
+        let level = !self.pane(handle)?.dtr.load(Ordering::SeqCst);
+        self.set_dtr(handle, level)?;
+        Ok(level)
+    }
+
+    /// Flip the RTS line, returning its new level
+    pub fn toggle_rts(&self, handle: &DomainPaneHandle) -> Result<bool> {
+        let level = !self.pane(handle)?.rts.load(Ordering::SeqCst);
+        self.set_rts(handle, level)?;
+        Ok(level)
+    }
+
+    /// Hold the line in the break condition for a moment
+    pub async fn send_break(&self, handle: &DomainPaneHandle) -> Result<()> {
+        let pane = self.pane(handle)?;
+        pane.port.lock().set_break()?;
+        tokio::time::sleep(BREAK_DURATION).await;
+        pane.port.lock().clear_break()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Domain for SerialDomain {
+    fn id(&self) -> &DomainId {
+        &self.config.id
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn domain_type(&self) -> DomainType {
+        DomainType::Serial
+    }
+
+    fn is_connected(&self) -> bool {
+        // Connected while the port is open
+        !self.panes.read().is_empty()
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        log::info!("Serial: Reopening {}", self.config.port);
+
+        // Update stats
+        {
+            let mut stats = self.stats.write();
+            stats.reconnect_attempts += 1;
+        }
+
+        let pane_ids: Vec<u64> = self.panes.read().keys().copied().collect();
+        for pane_id in pane_ids {
+            // Release the old port first; it can only be open once
+            self.panes.write().remove(&pane_id);
+            let pane = self.open_pane()?;
+            self.panes.write().insert(pane_id, Arc::new(pane));
+        }
+
+        self.stats.write().last_connected_at = Some(std::time::SystemTime::now());
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        // Dropping the panes closes the port
+        self.panes.write().clear();
+        self.stats.write().active_panes = 0;
+
+        log::info!("Serial: closed {}", self.config.port);
+        Ok(())
+    }
+
+    async fn spawn_pane(&self, _config: PaneConfig) -> Result<DomainPaneHandle> {
+        if !self.panes.read().is_empty() {
+            bail!("Serial port {} is already open", self.config.port);
+        }
+
+        let pane = self.open_pane()?;
+
+        // Allocate pane ID
+        let pane_id = self.next_pane_id.fetch_add(1, Ordering::SeqCst);
+        self.panes.write().insert(pane_id, Arc::new(pane));
+
+        // Update stats
+        {
+            let mut stats = self.stats.write();
+            stats.active_panes = self.panes.read().len();
+            stats.last_connected_at = Some(std::time::SystemTime::now());
+        }
+
+        log::info!(
+            "Serial: opened {} ({}) as pane {}",
+            self.config.port,
+            self.config.line_settings(),
+            pane_id
+        );
+
+        Ok(DomainPaneHandle {
+            domain_id: self.config.id.clone(),
+            pane_id,
+        })
+    }
+
+    async fn attach_pane(&self, pane_id: u64) -> Result<DomainPaneHandle> {
+        // Check if pane exists
+        if self.panes.read().contains_key(&pane_id) {
+            Ok(DomainPaneHandle {
+                domain_id: self.config.id.clone(),
+                pane_id,
+            })
+        } else {
+            bail!(
+                "Serial pane {} not found in domain {}",
+                pane_id,
+                self.config.id
+            )
+        }
+    }
+
+    async fn close_pane(&self, handle: &DomainPaneHandle) -> Result<()> {
+        if handle.domain_id != self.config.id {
+            bail!("Pane handle domain mismatch");
+        }
+
+        // Dropping the pane closes the port
+        if self.panes.write().remove(&handle.pane_id).is_some() {
+            // Update stats
+            let mut stats = self.stats.write();
+            stats.active_panes = self.panes.read().len();
+
+            log::info!("Serial: closed {}", self.config.port);
+            Ok(())
+        } else {
+            bail!("Serial pane {} not found", handle.pane_id)
+        }
+    }
+
+    async fn write_to_pane(&self, handle: &DomainPaneHandle, data: &[u8]) -> Result<()> {
+        let pane = self.pane(handle)?;
+        {
+            let mut port = pane.port.lock();
+            port.write_all(data)?;
+            port.flush()?;
+        }
+
+        // Update stats
+        let mut stats = self.stats.write();
+        stats.bytes_sent += data.len() as u64;
+
+        Ok(())
+    }
+
+    async fn read_from_pane(&self, handle: &DomainPaneHandle, buf: &mut [u8]) -> Result<usize> {
+        let pane = self.pane(handle)?;
+        let n = match pane.reader.lock().read(buf) {
+            Ok(n) => n,
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => 0,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Serial port {} failed", self.config.port))
+            }
+        };
+
+        if n > 0 {
+            if let Some(log) = &pane.log {
+                if let Err(e) = log.lock().write_all(&buf[..n]) {
+                    log::warn!("Failed to write serial log: {}", e);
+                }
+            }
+
+            // Update stats
+            let mut stats = self.stats.write();
+            stats.bytes_received += n as u64;
+        }
+
+        Ok(n)
+    }
+
+    async fn resize_pane(&self, handle: &DomainPaneHandle, _cols: u16, _rows: u16) -> Result<()> {
+        // A serial line has no window size to tell the device about
+        self.pane(handle).map(|_| ())
+    }
+
+    fn stats(&self) -> DomainStats {
+        self.stats.read().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_settings() {
+        let config = SerialDomainConfig::default();
+        assert_eq!(config.line_settings(), "115200 8N1");
+
+        let config = SerialDomainConfig {
+            baud_rate: 9600,
+            data_bits: DataBits::Seven,
+            parity: Parity::Even,
+            stop_bits: StopBits::Two,
+            ..Default::default()
+        };
+        assert_eq!(config.line_settings(), "9600 7E2");
+    }
+
+    #[tokio::test]
+    async fn test_missing_port() {
+        let domain = SerialDomain::new(SerialDomainConfig {
+            id: "board".to_string(),
+            port: "/dev/scarab-no-such-port".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(domain.domain_type(), DomainType::Serial);
+        assert!(!domain.is_connected());
+
+        let err = domain.spawn_pane(PaneConfig::default()).await.unwrap_err();
+        assert!(err.to_string().contains("/dev/scarab-no-such-port"));
+        assert_eq!(domain.stats().active_panes, 0);
+
+        let handle = DomainPaneHandle {
+            domain_id: "board".to_string(),
+            pane_id: 1,
+        };
+        assert!(domain.toggle_dtr(&handle).is_err());
+    }
+}
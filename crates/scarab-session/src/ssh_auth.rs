@@ -0,0 +1,218 @@
+//! Interactive SSH authentication prompts
+//!
+//! When an SSH domain needs something only the user knows, a key
+//! passphrase, a password or the answers to a keyboard-interactive
+//! challenge, it asks through an [`AuthPrompter`]. [`ModalPrompter`] shows
+//! each round as a form in the client, one field per question, with
+//! answers that shouldn't echo masked.
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use scarab_plugin_api::{PluginContext, PromptField, PromptResponse};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// How long an unanswered prompt is waited for before giving up
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// One question of an authentication round
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthQuestion {
+    /// Text shown to the user, such as "Password: "
+    pub prompt: String,
+    /// Whether the answer may be shown as it is typed
+    pub echo: bool,
+}
+
+/// Questions the server or a key needs answered at once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthPrompt {
+    pub title: String,
+    /// Extra text from the server, often empty
+    pub instructions: String,
+    pub questions: Vec<AuthQuestion>,
+}
+
+impl AuthPrompt {
+    /// A prompt with a single hidden answer, such as a password
+    pub fn secret(title: impl Into<String>, prompt: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            instructions: String::new(),
+            questions: vec![AuthQuestion {
+                prompt: prompt.into(),
+                echo: false,
+            }],
+        }
+    }
+}
+
+/// Asks the user for credentials
+#[async_trait]
+pub trait AuthPrompter: Send + Sync {
+    /// Answers in question order, or `None` if the user cancelled
+    async fn ask(&self, prompt: AuthPrompt) -> Option<Vec<String>>;
+}
+
+/// Prompter showing each round as a form in the client
+///
+/// Answers come back through the plugin that owns the context, which must
+/// pass them on with [`answer`](Self::answer); the session plugin does.
+#[derive(Default)]
+pub struct ModalPrompter {
+    ctx: Mutex<Option<PluginContext>>,
+    pending: Mutex<HashMap<u64, oneshot::Sender<Option<Vec<String>>>>>,
+}
+
+impl ModalPrompter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show prompts through `ctx` from now on
+    pub fn attach(&self, ctx: PluginContext) {
+        *self.ctx.lock() = Some(ctx);
+    }
+
+    /// Hand an answer to the prompt waiting for it
+    ///
+    /// Returns false if the response is for a prompt not shown here.
+    pub fn answer(&self, response: &PromptResponse) -> bool {
+        match self.pending.lock().remove(&response.prompt_id) {
+            Some(waiting) => {
+                let _ = waiting.send(response.values.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Form fields for the questions of `prompt`
+    fn fields(prompt: &AuthPrompt) -> Vec<PromptField> {
+        prompt
+            .questions
+            .iter()
+            .enumerate()
+            .map(|(i, question)| PromptField {
+                id: format!("answer-{}", i),
+                label: question.prompt.trim().to_string(),
+                placeholder: String::new(),
+                masked: !question.echo,
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AuthPrompter for ModalPrompter {
+    async fn ask(&self, prompt: AuthPrompt) -> Option<Vec<String>> {
+        let Some(ctx) = self.ctx.lock().clone() else {
+            log::warn!("SSH: no client to ask for '{}'", prompt.title);
+            return None;
+        };
+
+        let title = if prompt.instructions.trim().is_empty() {
+            prompt.title.clone()
+        } else {
+            format!("{}: {}", prompt.title, prompt.instructions.trim())
+        };
+        let (sender, answer) = oneshot::channel();
+        let prompt_id = {
+            // Registered before the form can be answered
+            let mut pending = self.pending.lock();
+            let prompt_id = ctx.show_form(title, Self::fields(&prompt));
+            pending.insert(prompt_id, sender);
+            prompt_id
+        };
+
+        match tokio::time::timeout(PROMPT_TIMEOUT, answer).await {
+            Ok(Ok(values)) => values,
+            _ => {
+                self.pending.lock().remove(&prompt_id);
+                log::warn!("SSH: '{}' went unanswered", prompt.title);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scarab_plugin_api::context::{PluginConfigData, PluginSharedState};
+    use scarab_plugin_api::types::RemoteCommand;
+    use std::sync::Arc;
+
+    fn context() -> PluginContext {
+        let state = Arc::new(Mutex::new(PluginSharedState::new(80, 24)));
+        PluginContext::new(PluginConfigData::default(), state, "scarab-session")
+    }
+
+    #[tokio::test]
+    async fn test_modal_prompter_round_trip() {
+        let prompter = Arc::new(ModalPrompter::new());
+        let ctx = context();
+        prompter.attach(ctx.clone());
+
+        let asking = tokio::spawn({
+            let prompter = prompter.clone();
+            async move {
+                prompter
+                    .ask(AuthPrompt {
+                        title: "SSH dev@example.com".to_string(),
+                        instructions: String::new(),
+                        questions: vec![
+                            AuthQuestion {
+                                prompt: "Verification code: ".to_string(),
+                                echo: true,
+                            },
+                            AuthQuestion {
+                                prompt: "Password: ".to_string(),
+                                echo: false,
+                            },
+                        ],
+                    })
+                    .await
+            }
+        });
+
+        let prompt_id = loop {
+            let shown = ctx.commands.lock().iter().find_map(|cmd| match cmd {
+                RemoteCommand::ShowForm {
+                    prompt_id, fields, ..
+                } => Some((*prompt_id, fields.clone())),
+                _ => None,
+            });
+            if let Some((prompt_id, fields)) = shown {
+                assert_eq!(fields[0].label, "Verification code:");
+                assert!(!fields[0].masked);
+                assert!(fields[1].masked);
+                break prompt_id;
+            }
+            tokio::task::yield_now().await;
+        };
+
+        let values = vec!["123456".to_string(), "hunter2".to_string()];
+        assert!(prompter.answer(&PromptResponse {
+            prompt_id,
+            values: Some(values.clone()),
+        }));
+        assert_eq!(asking.await.unwrap(), Some(values));
+
+        // Answered prompts aren't answered twice
+        assert!(!prompter.answer(&PromptResponse {
+            prompt_id,
+            values: None,
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_modal_prompter_without_client() {
+        let prompter = ModalPrompter::new();
+        let answer = prompter
+            .ask(AuthPrompt::secret("SSH dev@example.com", "Password: "))
+            .await;
+        assert_eq!(answer, None);
+    }
+}
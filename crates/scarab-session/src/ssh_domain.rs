@@ -4,24 +4,43 @@
 //! multiplexed channels, and remote PTY allocation.
 //!
 //! Features:
-//! - Connection multiplexing (single SSH connection, multiple channels)
-//! - Automatic reconnection on network failure
+//! - Connection multiplexing (single SSH connection, multiple channels),
+//!   shared by every domain for the same user and server
+//! - Automatic reconnection with backoff on network failure
 //! - Persistent remote panes across client disconnects
 //! - SSH agent forwarding support
-//! - Public key and password authentication
+//! - Agent, public key and password authentication, falling back to asking
+//!   the user through an [`AuthPrompter`] as `ssh` does
+//! - Host keys checked against `~/.ssh/known_hosts`: an unknown key is
+//!   trusted only if the user says so, and a changed one is refused
 
 use super::domain::{Domain, DomainId, DomainPaneHandle, DomainStats, DomainType, PaneConfig};
+use super::ssh_auth::{AuthPrompt, AuthPrompter, AuthQuestion};
 use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
-use parking_lot::RwLock;
-use russh::client::{Handle, Handler};
+use parking_lot::{Mutex, RwLock};
+use russh::client::{Handle, Handler, KeyboardInteractiveAuthResponse, Msg, Session};
 use russh::{Channel, ChannelMsg};
 use russh_keys::key::PublicKey;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::Duration;
 use tokio::sync::Mutex as TokioMutex;
 
+/// Keys tried, in order, from `~/.ssh` when the agent has none that work
+const DEFAULT_KEYS: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// Attempts made to bring a dropped connection back
+const RECONNECT_ATTEMPTS: u32 = 6;
+
+/// Wait before the first reconnection attempt, doubled after each failure
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between reconnection attempts
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
 /// SSH domain configuration
 #[derive(Debug, Clone)]
 pub struct SshDomainConfig {
@@ -43,6 +62,8 @@ pub struct SshDomainConfig {
     pub forward_agent: bool,
     /// Remote working directory
     pub remote_cwd: Option<String>,
+    /// Hosts to connect through first, as `[user@]host[:port]`
+    pub jump_hosts: Vec<String>,
 }
 
 /// SSH authentication methods
@@ -57,6 +78,8 @@ pub enum SshAuth {
     },
     /// Password authentication
     Password(String),
+    /// Ask the user: keyboard-interactive challenges, or a password
+    Interactive,
 }
 
 impl Default for SshDomainConfig {
@@ -71,15 +94,186 @@ impl Default for SshDomainConfig {
             connect_timeout: 10,
             forward_agent: false,
             remote_cwd: None,
+            jump_hosts: Vec::new(),
+        }
+    }
+}
+
+/// The server, user and jump hosts a connection is for
+type ConnectionKey = (String, u16, String, Vec<String>);
+
+/// A pooled connection, locked while it's being set up
+type ConnectionSlot = Arc<TokioMutex<Weak<Handle<ClientHandler>>>>;
+
+/// SSH connections shared between domains, ControlMaster style
+///
+/// Domains for the same user on the same server and port, reached through
+/// the same jump hosts, reuse one connection, each pane a channel on it. The pool only holds weak
+/// references, so a connection closes once no domain uses it.
+#[derive(Default)]
+pub struct SshConnectionPool {
+    slots: Mutex<HashMap<ConnectionKey, ConnectionSlot>>,
+}
+
+impl SshConnectionPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The pool domains use unless given another
+    pub fn global() -> Arc<Self> {
+        static POOL: OnceLock<Arc<SshConnectionPool>> = OnceLock::new();
+        POOL.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    /// Number of connections open and not being set up
+    pub fn connection_count(&self) -> usize {
+        self.slots
+            .lock()
+            .values()
+            .filter(|slot| {
+                slot.try_lock()
+                    .is_ok_and(|handle| handle.strong_count() > 0)
+            })
+            .count()
+    }
+
+    /// Slot holding the connection for `key`
+    ///
+    /// Locking the slot while connecting keeps two domains from opening
+    /// connections to the same place at once, without holding up others.
+    fn slot(&self, key: ConnectionKey) -> ConnectionSlot {
+        self.slots.lock().entry(key).or_default().clone()
+    }
+}
+
+/// User, host and port of a jump host given as `[user@]host[:port]`
+///
+/// The user defaults to `default_user` and the port to 22; IPv6
+/// addresses with a port go in brackets, as in `[::1]:2222`.
+pub fn parse_jump_host(spec: &str, default_user: &str) -> Result<(String, String, u16)> {
+    let spec = spec.trim();
+    let (user, address) = match spec.rsplit_once('@') {
+        Some((user, address)) => (user.to_string(), address),
+        None => (default_user.to_string(), spec),
+    };
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| anyhow!("Unclosed '[' in jump host '{}'", spec))?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match address.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (address, None),
         }
+    };
+    if host.is_empty() {
+        bail!("Jump host '{}' has no host", spec);
+    }
+    let port = match port {
+        Some(port) => port
+            .parse()
+            .with_context(|| format!("Bad port in jump host '{}'", spec))?,
+        None => 22,
+    };
+    Ok((user, host.to_string(), port))
+}
+
+/// How long to wait before reconnection attempt `attempt`, counting from 0
+pub fn reconnect_delay(attempt: u32) -> Duration {
+    RECONNECT_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(RECONNECT_MAX_DELAY)
+}
+
+/// The server turned down every credential tried, or the user cancelled
+///
+/// Unlike a network failure this isn't retried.
+#[derive(Debug)]
+pub struct AuthenticationFailed {
+    pub user: String,
+    pub host: String,
+}
+
+impl std::fmt::Display for AuthenticationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SSH authentication failed for {}@{}",
+            self.user, self.host
+        )
+    }
+}
+
+impl std::error::Error for AuthenticationFailed {}
+
+/// The server's host key was refused: it changed, or the user didn't trust
+/// a key seen for the first time
+///
+/// Like [`AuthenticationFailed`] this isn't retried.
+#[derive(Debug)]
+pub struct HostKeyRejected {
+    pub host: String,
+    pub port: u16,
+}
+
+impl std::fmt::Display for HostKeyRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SSH host key for {}:{} was not accepted",
+            self.host, self.port
+        )
+    }
+}
+
+impl std::error::Error for HostKeyRejected {}
+
+/// How a server's host key compares with `known_hosts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostKeyStatus {
+    /// Listed for this host
+    Known,
+    /// The host isn't listed with a key of this type
+    Unknown,
+    /// The host is listed with a different key, on this line
+    Changed(usize),
+}
+
+/// Check `key` for `host` and `port` against the `known_hosts` file at
+/// `path`
+///
+/// A file that doesn't exist lists no hosts.
+pub fn host_key_status(
+    host: &str,
+    port: u16,
+    key: &PublicKey,
+    path: &Path,
+) -> Result<HostKeyStatus> {
+    match russh_keys::check_known_hosts_path(host, port, key, path) {
+        Ok(true) => Ok(HostKeyStatus::Known),
+        Ok(false) => Ok(HostKeyStatus::Unknown),
+        Err(russh_keys::Error::KeyChanged { line }) => Ok(HostKeyStatus::Changed(line)),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
     }
 }
 
+/// The user's `known_hosts` file
+fn known_hosts_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".ssh").join("known_hosts"))
+}
+
 /// SSH domain with multiplexed connections
 pub struct SshDomain {
     config: SshDomainConfig,
     /// SSH client session handle (Arc allows sharing between methods)
     session: Arc<TokioMutex<Option<Arc<Handle<ClientHandler>>>>>,
+    /// Connections shared with other domains
+    pool: Arc<SshConnectionPool>,
+    /// Asks the user for passwords, passphrases and challenge answers
+    prompter: Option<Arc<dyn AuthPrompter>>,
     /// Active channels: pane_id -> Channel
     channels: Arc<RwLock<HashMap<u64, Arc<TokioMutex<Channel<russh::client::Msg>>>>>>,
     /// Connection state
@@ -90,8 +284,18 @@ pub struct SshDomain {
     stats: Arc<RwLock<DomainStats>>,
 }
 
-/// russh client handler
-struct ClientHandler;
+/// russh client handler for a connection to `host` and `port`
+///
+/// A host key not in `known_hosts` is refused and kept in `unknown_key`,
+/// so the user can be asked about it outside the connection's timeout,
+/// unless it is the one the user already said to trust.
+struct ClientHandler {
+    host: String,
+    port: u16,
+    /// Fingerprint of a key the user trusted for this connection
+    trusted: Option<String>,
+    unknown_key: Arc<Mutex<Option<PublicKey>>>,
+}
 
 #[async_trait]
 impl Handler for ClientHandler {
@@ -99,21 +303,83 @@ impl Handler for ClientHandler {
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &PublicKey,
+        server_public_key: &PublicKey,
     ) -> Result<bool, Self::Error> {
-        // TODO: Implement proper host key verification
-        // For now, accept all keys (similar to ssh -o StrictHostKeyChecking=no)
-        log::warn!("SSH: Host key verification disabled (accepting all keys)");
-        Ok(true)
+        if self.trusted.as_deref() == Some(server_public_key.fingerprint().as_str()) {
+            return Ok(true);
+        }
+        let Some(known_hosts) = known_hosts_path() else {
+            log::warn!("SSH: no home directory, so no known_hosts to check the host key against");
+            return Ok(false);
+        };
+        match host_key_status(&self.host, self.port, server_public_key, &known_hosts) {
+            Ok(HostKeyStatus::Known) => Ok(true),
+            Ok(HostKeyStatus::Unknown) => {
+                *self.unknown_key.lock() = Some(server_public_key.clone());
+                Ok(false)
+            }
+            Ok(HostKeyStatus::Changed(line)) => {
+                log::error!(
+                    "SSH: host key for {}:{} has changed (offending key in {}:{}); refusing to connect",
+                    self.host,
+                    self.port,
+                    known_hosts.display(),
+                    line
+                );
+                Ok(false)
+            }
+            Err(e) => {
+                log::warn!("SSH: {:#}", e);
+                Ok(false)
+            }
+        }
+    }
+
+    async fn server_channel_open_agent_forward(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        // Only opened for channels that asked for agent forwarding
+        tokio::spawn(forward_agent(channel));
+        Ok(())
     }
 }
 
+/// Relay a forwarded agent channel to the local agent
+#[cfg(unix)]
+async fn forward_agent(channel: Channel<Msg>) {
+    let Some(socket) = std::env::var_os("SSH_AUTH_SOCK") else {
+        log::warn!("SSH: agent forwarding requested but SSH_AUTH_SOCK is not set");
+        return;
+    };
+    let mut agent = match tokio::net::UnixStream::connect(&socket).await {
+        Ok(agent) => agent,
+        Err(e) => {
+            log::warn!("SSH: failed to reach the local agent: {}", e);
+            return;
+        }
+    };
+    let mut stream = channel.into_stream();
+    if let Err(e) = tokio::io::copy_bidirectional(&mut stream, &mut agent).await {
+        log::debug!("SSH: forwarded agent channel closed: {}", e);
+    }
+}
+
+/// Relay a forwarded agent channel to the local agent
+#[cfg(not(unix))]
+async fn forward_agent(_channel: Channel<Msg>) {
+    log::warn!("SSH: agent forwarding is only supported on Unix");
+}
+
 impl SshDomain {
     /// Create a new SSH domain (not connected)
     pub fn new(config: SshDomainConfig) -> Self {
         Self {
             config,
             session: Arc::new(TokioMutex::new(None)),
+            pool: SshConnectionPool::global(),
+            prompter: None,
             channels: Arc::new(RwLock::new(HashMap::new())),
             connected: Arc::new(AtomicBool::new(false)),
             next_pane_id: Arc::new(AtomicU64::new(1)),
@@ -121,8 +387,100 @@ impl SshDomain {
         }
     }
 
-    /// Connect to the SSH server
-    async fn connect_internal(&self) -> Result<()> {
+    /// Ask the user through `prompter` when credentials are needed
+    pub fn with_prompter(mut self, prompter: Arc<dyn AuthPrompter>) -> Self {
+        self.prompter = Some(prompter);
+        self
+    }
+
+    /// Share connections through `pool` instead of the global pool
+    pub fn with_pool(mut self, pool: Arc<SshConnectionPool>) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    fn connection_key(&self) -> ConnectionKey {
+        (
+            self.config.host.clone(),
+            self.config.port,
+            self.config.user.clone(),
+            self.config.jump_hosts.clone(),
+        )
+    }
+
+    /// Ask the user whether to trust `key`, the first seen for the server,
+    /// and add it to `known_hosts` if they do
+    async fn trust_new_key(&self, key: &PublicKey) -> bool {
+        let prompt = AuthPrompt {
+            title: format!("SSH {}:{}", self.config.host, self.config.port),
+            instructions: format!(
+                "The authenticity of this host can't be established. {} key fingerprint is SHA256:{}.",
+                key.name(),
+                key.fingerprint()
+            ),
+            questions: vec![AuthQuestion {
+                prompt: "Trust this host and continue connecting? (yes/no)".to_string(),
+                echo: true,
+            }],
+        };
+        let trusted = self
+            .ask(prompt)
+            .await
+            .is_some_and(|answer| answer.trim().eq_ignore_ascii_case("yes"));
+        if !trusted {
+            log::warn!(
+                "SSH: host key for {}:{} not trusted",
+                self.config.host,
+                self.config.port
+            );
+            return false;
+        }
+        if let Some(known_hosts) = known_hosts_path() {
+            if let Err(e) = russh_keys::learn_known_hosts_path(
+                &self.config.host,
+                self.config.port,
+                key,
+                &known_hosts,
+            ) {
+                log::warn!(
+                    "SSH: failed to add the host key to {}: {}",
+                    known_hosts.display(),
+                    e
+                );
+            }
+        }
+        true
+    }
+
+    /// Connect to the SSH server through the jump hosts, if any, and
+    /// authenticate
+    async fn connect_internal(&self) -> Result<Handle<ClientHandler>> {
+        let mut via = None;
+        for (index, spec) in self.config.jump_hosts.iter().enumerate() {
+            let (user, host, port) = parse_jump_host(spec, &self.config.user)?;
+            // Jump hosts are logged into the same way as the server
+            let mut hop = SshDomain::new(SshDomainConfig {
+                id: format!("{}-jump-{}", self.config.id, index + 1),
+                name: host.clone(),
+                host,
+                port,
+                user,
+                auth: self.config.auth.clone(),
+                connect_timeout: self.config.connect_timeout,
+                ..Default::default()
+            });
+            hop.prompter = self.prompter.clone();
+            via = Some(hop.connect_via(via).await?);
+        }
+        self.connect_via(via).await
+    }
+
+    /// Connect to the SSH server, tunnelled through the connection `via`
+    /// if given, and authenticate
+    async fn connect_via(
+        &self,
+        via: Option<Handle<ClientHandler>>,
+    ) -> Result<Handle<ClientHandler>> {
         log::info!(
             "SSH: Connecting to {}@{}:{}",
             self.config.user,
@@ -130,49 +488,81 @@ impl SshDomain {
             self.config.port
         );
 
-        // Create SSH client config
-        let ssh_config = Arc::new(russh::client::Config::default());
-
-        let sh = ClientHandler;
-
-        let mut session = russh::client::connect(
-            ssh_config,
-            (self.config.host.as_str(), self.config.port),
-            sh,
-        )
-        .await
-        .context("Failed to connect to SSH server")?;
+        // A key seen for the first time is asked about between attempts,
+        // so the user's answer doesn't count against the timeout
+        let mut trusted = None;
+        let mut session = loop {
+            let unknown_key = Arc::new(Mutex::new(None));
+            let handler = ClientHandler {
+                host: self.config.host.clone(),
+                port: self.config.port,
+                trusted: trusted.clone(),
+                unknown_key: Arc::clone(&unknown_key),
+            };
+            let result = tokio::time::timeout(
+                Duration::from_secs(self.config.connect_timeout),
+                self.open_transport(via.as_ref(), handler),
+            )
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to SSH server"))?;
+            match result {
+                Ok(session) => break session,
+                Err(russh::Error::UnknownKey) => {
+                    let unknown = unknown_key.lock().take();
+                    match unknown {
+                        Some(key) if trusted.is_none() && self.trust_new_key(&key).await => {
+                            trusted = Some(key.fingerprint());
+                        }
+                        _ => {
+                            return Err(HostKeyRejected {
+                                host: self.config.host.clone(),
+                                port: self.config.port,
+                            }
+                            .into())
+                        }
+                    }
+                }
+                Err(e) => return Err(e).context("Failed to connect to SSH server"),
+            }
+        };
 
-        // Authenticate
-        let auth_result = match &self.config.auth {
-            SshAuth::Agent => {
-                // TODO: Implement SSH agent authentication
-                // This requires more complex integration with russh agent API
-                // For now, fall back to default SSH key
-                log::warn!("SSH Agent auth not yet implemented, trying default key ~/.ssh/id_rsa");
+        if !self.authenticate(&mut session).await? {
+            return Err(AuthenticationFailed {
+                user: self.config.user.clone(),
+                host: self.config.host.clone(),
+            }
+            .into());
+        }
 
-                let default_key_path = std::env::var("HOME")
-                    .map(|home| format!("{}/.ssh/id_rsa", home))
-                    .unwrap_or_else(|_| "~/.ssh/id_rsa".to_string());
+        log::info!("SSH: Authentication successful");
+        Ok(session)
+    }
 
-                let key = russh_keys::load_secret_key(&default_key_path, None)
-                    .context("Failed to load default SSH key (~/.ssh/id_rsa)")?;
+    /// Open the connection to the server, directly or through `via`
+    async fn open_transport(
+        &self,
+        via: Option<&Handle<ClientHandler>>,
+        handler: ClientHandler,
+    ) -> Result<Handle<ClientHandler>, russh::Error> {
+        let ssh_config = Arc::new(russh::client::Config::default());
+        let (host, port) = (self.config.host.as_str(), self.config.port);
+        let Some(jump) = via else {
+            return russh::client::connect(ssh_config, (host, port), handler).await;
+        };
+        // The jump host's connection stays up as long as the channel
+        // through it is open
+        let channel = jump
+            .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+            .await?;
+        russh::client::connect_stream(ssh_config, channel.into_stream(), handler).await
+    }
 
-                session
-                    .authenticate_publickey(&self.config.user, Arc::new(key))
-                    .await?
-            }
+    /// Try the configured method, then ask the user if it didn't work
+    async fn authenticate(&self, session: &mut Handle<ClientHandler>) -> Result<bool> {
+        let accepted = match &self.config.auth {
+            SshAuth::Agent => self.authenticate_agent(session).await?,
             SshAuth::PublicKey { path, passphrase } => {
-                // Load private key from file
-                let key = if let Some(pass) = passphrase {
-                    russh_keys::load_secret_key(path, Some(pass.as_str()))
-                        .context("Failed to load private key")?
-                } else {
-                    russh_keys::load_secret_key(path, None).context("Failed to load private key")?
-                };
-
-                session
-                    .authenticate_publickey(&self.config.user, Arc::new(key))
+                self.authenticate_key(session, path, passphrase.as_deref())
                     .await?
             }
             SshAuth::Password(password) => {
@@ -180,16 +570,206 @@ impl SshDomain {
                     .authenticate_password(&self.config.user, password)
                     .await?
             }
+            SshAuth::Interactive => false,
         };
+        if accepted {
+            return Ok(true);
+        }
+        self.authenticate_interactive(session).await
+    }
 
-        if !auth_result {
-            bail!("SSH authentication failed");
+    /// Offer the agent's keys, then the default key files
+    async fn authenticate_agent(&self, session: &mut Handle<ClientHandler>) -> Result<bool> {
+        #[cfg(unix)]
+        match russh_keys::agent::client::AgentClient::connect_env().await {
+            Ok(mut agent) => {
+                let keys = agent.request_identities().await.unwrap_or_else(|e| {
+                    log::warn!("SSH: failed to list agent keys: {}", e);
+                    Vec::new()
+                });
+                for key in keys {
+                    let (returned, result) = session
+                        .authenticate_future(self.config.user.as_str(), key, agent)
+                        .await;
+                    agent = returned;
+                    match result {
+                        Ok(true) => return Ok(true),
+                        Ok(false) => {}
+                        Err(e) => log::debug!("SSH: agent failed to sign: {}", e),
+                    }
+                }
+            }
+            Err(e) => log::debug!("SSH: no agent available: {}", e),
         }
 
-        log::info!("SSH: Authentication successful");
+        let Some(home) = std::env::var_os("HOME") else {
+            return Ok(false);
+        };
+        for name in DEFAULT_KEYS {
+            let path = PathBuf::from(&home).join(".ssh").join(name);
+            if !path.exists() {
+                continue;
+            }
+            let path = path.to_string_lossy();
+            match self.authenticate_key(session, &path, None).await {
+                Ok(true) => return Ok(true),
+                Ok(false) => {}
+                Err(e) => log::debug!("SSH: key {} not used: {:#}", path, e),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Offer the key in `path`, asking for its passphrase if it needs one
+    /// that wasn't given
+    async fn authenticate_key(
+        &self,
+        session: &mut Handle<ClientHandler>,
+        path: &str,
+        passphrase: Option<&str>,
+    ) -> Result<bool> {
+        let key = match russh_keys::load_secret_key(path, passphrase) {
+            Ok(key) => key,
+            Err(russh_keys::Error::KeyIsEncrypted) if passphrase.is_none() => {
+                let prompt =
+                    AuthPrompt::secret(self.prompt_title(), format!("Passphrase for {}", path));
+                let Some(passphrase) = self.ask(prompt).await else {
+                    return Ok(false);
+                };
+                russh_keys::load_secret_key(path, Some(&passphrase))
+                    .context("Failed to load private key")?
+            }
+            Err(e) => return Err(e).context("Failed to load private key"),
+        };
+
+        Ok(session
+            .authenticate_publickey(&self.config.user, Arc::new(key))
+            .await?)
+    }
+
+    /// Answer keyboard-interactive challenges, or give a password for
+    /// servers that don't offer them
+    async fn authenticate_interactive(&self, session: &mut Handle<ClientHandler>) -> Result<bool> {
+        let Some(prompter) = &self.prompter else {
+            return Ok(false);
+        };
+
+        let mut response = session
+            .authenticate_keyboard_interactive_start(self.config.user.as_str(), None::<String>)
+            .await?;
+        loop {
+            match response {
+                KeyboardInteractiveAuthResponse::Success => return Ok(true),
+                KeyboardInteractiveAuthResponse::Failure => break,
+                KeyboardInteractiveAuthResponse::InfoRequest {
+                    name,
+                    instructions,
+                    prompts,
+                } => {
+                    // Servers may send rounds without questions
+                    let answers = if prompts.is_empty() {
+                        Vec::new()
+                    } else {
+                        let prompt = AuthPrompt {
+                            title: if name.is_empty() {
+                                self.prompt_title()
+                            } else {
+                                name
+                            },
+                            instructions,
+                            questions: prompts
+                                .into_iter()
+                                .map(|p| AuthQuestion {
+                                    prompt: p.prompt,
+                                    echo: p.echo,
+                                })
+                                .collect(),
+                        };
+                        match prompter.ask(prompt).await {
+                            Some(answers) => answers,
+                            None => return Ok(false),
+                        }
+                    };
+                    response = session
+                        .authenticate_keyboard_interactive_respond(answers)
+                        .await?;
+                }
+            }
+        }
+
+        let prompt = AuthPrompt::secret(self.prompt_title(), "Password");
+        let Some(password) = self.ask(prompt).await else {
+            return Ok(false);
+        };
+        Ok(session
+            .authenticate_password(&self.config.user, password)
+            .await?)
+    }
+
+    /// The single answer to `prompt`, if there is a prompter and the user
+    /// gave one
+    async fn ask(&self, prompt: AuthPrompt) -> Option<String> {
+        let prompter = self.prompter.as_ref()?;
+        prompter.ask(prompt).await?.into_iter().next()
+    }
+
+    fn prompt_title(&self) -> String {
+        format!("SSH {}@{}", self.config.user, self.config.host)
+    }
+
+    /// Connect, retrying with growing waits while the server can't be
+    /// reached
+    async fn connect_with_backoff(&self) -> Result<Handle<ClientHandler>> {
+        let mut attempt = 0;
+        loop {
+            match self.connect_internal().await {
+                Ok(session) => return Ok(session),
+                Err(e) if e.downcast_ref::<AuthenticationFailed>().is_some() => return Err(e),
+                Err(e) if e.downcast_ref::<HostKeyRejected>().is_some() => return Err(e),
+                Err(e) if attempt + 1 >= RECONNECT_ATTEMPTS => return Err(e),
+                Err(e) => {
+                    let delay = reconnect_delay(attempt);
+                    log::warn!(
+                        "SSH: connection to {} failed ({:#}), retrying in {:?}",
+                        self.config.host,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    self.stats.write().reconnect_attempts += 1;
+                }
+            }
+        }
+    }
+
+    /// Use the pooled connection for this server, connecting if there is
+    /// none or it has dropped
+    ///
+    /// `retry` reconnects with backoff; a first connection is tried once.
+    async fn open_connection(&self, retry: bool) -> Result<Arc<Handle<ClientHandler>>> {
+        let slot = self.pool.slot(self.connection_key());
+        let mut shared = slot.lock().await;
+
+        let handle = match shared.upgrade().filter(|handle| !handle.is_closed()) {
+            Some(handle) => {
+                log::debug!("SSH: reusing connection to {}", self.config.host);
+                handle
+            }
+            None => {
+                let session = if retry {
+                    self.connect_with_backoff().await?
+                } else {
+                    self.connect_internal().await?
+                };
+                let handle = Arc::new(session);
+                *shared = Arc::downgrade(&handle);
+                handle
+            }
+        };
 
         // Store session handle wrapped in Arc
-        *self.session.lock().await = Some(Arc::new(session));
+        *self.session.lock().await = Some(Arc::clone(&handle));
         self.connected.store(true, Ordering::SeqCst);
 
         // Update stats
@@ -198,7 +778,7 @@ impl SshDomain {
             stats.last_connected_at = Some(std::time::SystemTime::now());
         }
 
-        Ok(())
+        Ok(handle)
     }
 
     /// Get or create a connection
@@ -210,23 +790,14 @@ impl SshDomain {
             if handle.is_closed() {
                 log::warn!("SSH: Session closed, reconnecting...");
                 drop(session_guard); // Release lock before reconnecting
-                self.reconnect().await?;
-                let session_guard = self.session.lock().await;
-                return session_guard
-                    .as_ref()
-                    .ok_or_else(|| anyhow!("SSH session not available after reconnect"))
-                    .map(|h| Arc::clone(h));
+                self.connected.store(false, Ordering::SeqCst);
+                return self.open_connection(true).await;
             }
             Ok(Arc::clone(handle))
         } else {
             // Not connected, establish connection
             drop(session_guard); // Release lock before connecting
-            self.connect_internal().await?;
-            let session_guard = self.session.lock().await;
-            session_guard
-                .as_ref()
-                .ok_or_else(|| anyhow!("SSH session not available after connect"))
-                .map(|h| Arc::clone(h))
+            self.open_connection(false).await
         }
     }
 }
@@ -262,25 +833,53 @@ impl Domain for SshDomain {
             stats.reconnect_attempts += 1;
         }
 
-        // Close existing session
+        // Close existing session, unless other domains still use it
         {
             let mut session = self.session.lock().await;
             if let Some(handle) = session.take() {
-                let _ = handle
-                    .disconnect(russh::Disconnect::ByApplication, "", "en")
-                    .await;
+                if Arc::strong_count(&handle) == 1 {
+                    let _ = handle
+                        .disconnect(russh::Disconnect::ByApplication, "", "en")
+                        .await;
+                }
             }
         }
 
         self.connected.store(false, Ordering::SeqCst);
 
         // Establish new connection
-        self.connect_internal().await?;
+        self.open_connection(true).await?;
 
         log::info!("SSH: Reconnection successful");
         Ok(())
     }
 
+    async fn disconnect(&self) -> Result<()> {
+        log::info!(
+            "SSH: Disconnecting from {}@{}",
+            self.config.user,
+            self.config.host
+        );
+
+        let channels: Vec<_> = self.channels.write().drain().map(|(_, ch)| ch).collect();
+        for ch_arc in channels {
+            let _ = ch_arc.lock().await.close().await;
+        }
+        self.stats.write().active_panes = 0;
+
+        // Close the session, unless other domains still use it
+        if let Some(handle) = self.session.lock().await.take() {
+            if Arc::strong_count(&handle) == 1 {
+                let _ = handle
+                    .disconnect(russh::Disconnect::ByApplication, "", "en")
+                    .await;
+            }
+        }
+
+        self.connected.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
     async fn spawn_pane(&self, config: PaneConfig) -> Result<DomainPaneHandle> {
         let session_arc = self.ensure_connected().await?;
 
@@ -290,6 +889,10 @@ impl Domain for SshDomain {
         // Open a new channel
         let channel = session_arc.channel_open_session().await?;
 
+        if self.config.forward_agent {
+            channel.agent_forward(false).await?;
+        }
+
         // Request PTY
         let term = std::env::var("TERM").unwrap_or_else(|_| "xterm-256color".to_string());
         channel
@@ -521,6 +1124,100 @@ mod tests {
         assert!(!domain.is_connected());
     }
 
+    #[test]
+    fn test_parse_jump_host() {
+        assert_eq!(
+            parse_jump_host("bastion.example.com", "alice").unwrap(),
+            ("alice".to_string(), "bastion.example.com".to_string(), 22)
+        );
+        assert_eq!(
+            parse_jump_host("ops@10.0.0.1:2222", "alice").unwrap(),
+            ("ops".to_string(), "10.0.0.1".to_string(), 2222)
+        );
+        assert_eq!(
+            parse_jump_host("[fd00::1]:2200", "alice").unwrap(),
+            ("alice".to_string(), "fd00::1".to_string(), 2200)
+        );
+        assert_eq!(parse_jump_host("fd00::1", "alice").unwrap().1, "fd00::1");
+        assert!(parse_jump_host("ops@", "alice").is_err());
+        assert!(parse_jump_host("bastion:ssh", "alice").is_err());
+    }
+
+    #[test]
+    fn test_reconnect_delay_backs_off() {
+        assert_eq!(reconnect_delay(0), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(1), Duration::from_secs(2));
+        assert_eq!(reconnect_delay(3), Duration::from_secs(8));
+        assert_eq!(reconnect_delay(10), RECONNECT_MAX_DELAY);
+        assert_eq!(reconnect_delay(u32::MAX), RECONNECT_MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_failed_connection_is_not_pooled() {
+        let pool = Arc::new(SshConnectionPool::new());
+        let domain = SshDomain::new(SshDomainConfig {
+            host: "127.0.0.1".to_string(),
+            // Nothing listens on port 1
+            port: 1,
+            connect_timeout: 2,
+            ..Default::default()
+        })
+        .with_pool(pool.clone());
+
+        assert!(domain.spawn_pane(PaneConfig::default()).await.is_err());
+        assert!(!domain.is_connected());
+        assert_eq!(pool.connection_count(), 0);
+    }
+
+    #[test]
+    fn test_host_key_status() {
+        let key = russh_keys::parse_public_key_base64(
+            "AAAAC3NzaC1lZDI1NTE5AAAAIITwgjSaFvGug3vGA/yR4YcM1aN50/itZ6HFW9xaiUME",
+        )
+        .unwrap();
+        let other = russh_keys::parse_public_key_base64(
+            "AAAAC3NzaC1lZDI1NTE5AAAAIMepIngsKInWldLLlnA5X5Mi0cpPLtnqUvoYNiY/f3HT",
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known_hosts");
+
+        // No file lists no hosts
+        assert_eq!(
+            host_key_status("example.com", 22, &key, &path).unwrap(),
+            HostKeyStatus::Unknown
+        );
+
+        std::fs::write(
+            &path,
+            "example.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIITwgjSaFvGug3vGA/yR4YcM1aN50/itZ6HFW9xaiUME\n",
+        )
+        .unwrap();
+        assert_eq!(
+            host_key_status("example.com", 22, &key, &path).unwrap(),
+            HostKeyStatus::Known
+        );
+        assert_eq!(
+            host_key_status("example.com", 22, &other, &path).unwrap(),
+            HostKeyStatus::Changed(1)
+        );
+        // The port is part of the host's entry
+        assert_eq!(
+            host_key_status("example.com", 2222, &key, &path).unwrap(),
+            HostKeyStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn test_jump_hosts_get_their_own_connection() {
+        let direct = SshDomain::new(SshDomainConfig::default());
+        let jumped = SshDomain::new(SshDomainConfig {
+            jump_hosts: vec!["bastion".to_string()],
+            ..Default::default()
+        });
+        assert_ne!(direct.connection_key(), jumped.connection_key());
+    }
+
     // Note: Integration tests that actually connect to SSH servers
     // should be in tests/ directory with #[ignore] attribute
 }
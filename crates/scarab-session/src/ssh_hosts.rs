@@ -0,0 +1,362 @@
+//! Hosts the user already connects to with `ssh`
+//!
+//! "SSH: Connect to Host" offers the hosts named in `~/.ssh/config` and
+//! those in `~/.ssh/known_hosts` that aren't hashed. Picking one opens a
+//! tab in an SSH domain for it, set up from its `HostName`, `User`,
+//! `Port`, `IdentityFile` and `ProxyJump`. A `[[ssh_domains]]` entry whose
+//! `host` is the alias or the host name overrides the user, login method
+//! and jump hosts, so those can differ from plain `ssh`.
+
+use crate::ssh_domain::{SshAuth, SshDomainConfig};
+use std::path::Path;
+
+/// Prefix of the IDs of domains opened from the host picker
+pub const HOST_DOMAIN_PREFIX: &str = "ssh:";
+
+/// A host from the SSH config or known hosts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshHost {
+    /// Name picked, the `Host` alias or the known host's name
+    pub alias: String,
+    /// Name or address connected to
+    pub hostname: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    /// Hosts to connect through, from `ProxyJump`
+    pub proxy_jump: Vec<String>,
+}
+
+impl SshHost {
+    fn named(alias: &str) -> Self {
+        Self {
+            alias: alias.to_string(),
+            hostname: alias.to_string(),
+            user: None,
+            port: None,
+            identity_file: None,
+            proxy_jump: Vec::new(),
+        }
+    }
+
+    /// ID of the domain for this host
+    pub fn domain_id(&self) -> String {
+        format!("{}{}", HOST_DOMAIN_PREFIX, self.alias)
+    }
+
+    /// One-line summary, e.g. `alice@build.example.com:2222 via bastion`
+    pub fn describe(&self) -> String {
+        let mut text = self.hostname.clone();
+        if let Some(user) = &self.user {
+            text = format!("{}@{}", user, text);
+        }
+        if let Some(port) = self.port.filter(|&port| port != 22) {
+            text = format!("{}:{}", text, port);
+        }
+        if !self.proxy_jump.is_empty() {
+            text = format!("{} via {}", text, self.proxy_jump.join(", "));
+        }
+        text
+    }
+
+    /// Domain settings for this host, with those of the first of
+    /// `overrides` configured for it
+    ///
+    /// `home` expands a leading `~` in `IdentityFile`.
+    pub fn domain_config(&self, overrides: &[SshDomainConfig], home: &Path) -> SshDomainConfig {
+        let mut config = SshDomainConfig {
+            id: self.domain_id(),
+            name: self.alias.clone(),
+            host: self.hostname.clone(),
+            port: self.port.unwrap_or(22),
+            auth: match &self.identity_file {
+                Some(path) => SshAuth::PublicKey {
+                    path: expand_home(path, home),
+                    passphrase: None,
+                },
+                None => SshAuth::Agent,
+            },
+            jump_hosts: self.proxy_jump.clone(),
+            ..Default::default()
+        };
+        if let Some(user) = &self.user {
+            config.user = user.clone();
+        }
+
+        let configured = overrides
+            .iter()
+            .find(|o| o.host == self.alias || o.host == self.hostname);
+        if let Some(configured) = configured {
+            config.user = configured.user.clone();
+            config.auth = configured.auth.clone();
+            config.connect_timeout = configured.connect_timeout;
+            config.forward_agent = configured.forward_agent;
+            config.remote_cwd = configured.remote_cwd.clone();
+            if !configured.jump_hosts.is_empty() {
+                config.jump_hosts = configured.jump_hosts.clone();
+            }
+        }
+        config
+    }
+}
+
+fn expand_home(path: &str, home: &Path) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest).to_string_lossy().into_owned(),
+        None => path.to_string(),
+    }
+}
+
+/// Whether `name` is a pattern rather than a single host
+fn is_pattern(name: &str) -> bool {
+    name.contains(['*', '?', '!'])
+}
+
+/// Hosts named in an SSH config file, in order
+///
+/// Only `Host` entries naming single hosts are offered; patterns such as
+/// `Host *` and `Match` blocks are skipped. As with `ssh`, the first value
+/// given for an option wins.
+pub fn parse_ssh_config(text: &str) -> Vec<SshHost> {
+    let mut hosts: Vec<SshHost> = Vec::new();
+    // Indices into `hosts` of the block being read
+    let mut current: Vec<usize> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, value) = match line.split_once(|c: char| c == '=' || c.is_whitespace()) {
+            Some((keyword, value)) => (keyword, value.trim().trim_start_matches('=').trim()),
+            None => (line, ""),
+        };
+        let value = value.trim_matches('"');
+
+        match keyword.to_ascii_lowercase().as_str() {
+            "host" => {
+                current.clear();
+                for alias in value.split_whitespace().filter(|a| !is_pattern(a)) {
+                    let index = match hosts.iter().position(|h| h.alias == alias) {
+                        Some(index) => index,
+                        None => {
+                            hosts.push(SshHost::named(alias));
+                            hosts.len() - 1
+                        }
+                    };
+                    current.push(index);
+                }
+            }
+            "match" => current.clear(),
+            option => {
+                for &index in &current {
+                    let host = &mut hosts[index];
+                    match option {
+                        "hostname" if host.hostname == host.alias => {
+                            host.hostname = value.to_string()
+                        }
+                        "user" if host.user.is_none() => host.user = Some(value.to_string()),
+                        "port" if host.port.is_none() => host.port = value.parse().ok(),
+                        "identityfile" if host.identity_file.is_none() => {
+                            host.identity_file = Some(value.to_string())
+                        }
+                        "proxyjump" if host.proxy_jump.is_empty() && value != "none" => {
+                            host.proxy_jump = value
+                                .split(',')
+                                .map(|jump| jump.trim().to_string())
+                                .filter(|jump| !jump.is_empty())
+                                .collect();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    hosts
+}
+
+/// Hosts in a `known_hosts` file with the port each was seen on, if not 22
+///
+/// Hashed entries can't be read back and are skipped, as are
+/// certificate authorities, revoked keys and patterns.
+pub fn parse_known_hosts(text: &str) -> Vec<(String, Option<u16>)> {
+    let mut hosts = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+        let Some(names) = line.split_whitespace().next() else {
+            continue;
+        };
+        for name in names.split(',') {
+            if name.starts_with('|') || is_pattern(name) {
+                continue;
+            }
+            let entry = match name
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once("]:"))
+            {
+                Some((host, port)) => (host.to_string(), port.parse().ok()),
+                None => (name.to_string(), None),
+            };
+            if !entry.0.is_empty() && !hosts.contains(&entry) {
+                hosts.push(entry);
+            }
+        }
+    }
+    hosts
+}
+
+/// Hosts from `~/.ssh/config`, then the known hosts they don't already
+/// cover, under the home directory `home`
+pub fn discover_hosts(home: &Path) -> Vec<SshHost> {
+    let ssh_dir = home.join(".ssh");
+    let read = |name: &str| std::fs::read_to_string(ssh_dir.join(name)).unwrap_or_default();
+
+    let mut hosts = parse_ssh_config(&read("config"));
+    for (name, port) in parse_known_hosts(&read("known_hosts")) {
+        let covered = hosts.iter().any(|host| {
+            (host.alias == name || host.hostname == name) && (port.is_none() || host.port == port)
+        });
+        if covered {
+            continue;
+        }
+        let mut host = SshHost::named(&name);
+        if let Some(port) = port {
+            host.alias = format!("{}:{}", name, port);
+            host.port = Some(port);
+        }
+        hosts.push(host);
+    }
+    hosts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SSH_CONFIG: &str = "
+# Work machines
+Host build build.example.com
+    HostName build.example.com
+    User alice
+    Port 2222
+    IdentityFile ~/.ssh/build_ed25519
+    ProxyJump ops@bastion.example.com:22,jump2
+
+Host *.internal !skip
+    User root
+
+Host db
+    HostName=10.0.0.5
+    User bob
+    User carol
+
+Match host db
+    Port 5022
+
+Host *
+    ServerAliveInterval 30
+";
+
+    const KNOWN_HOSTS: &str = "
+build.example.com,192.168.1.10 ssh-ed25519 AAAAC3Nz
+[git.example.com]:7999 ssh-rsa AAAAB3Nz
+|1|JfKTdBh7rNbXkVAQCRp4OQoPfmI=|USECr3SWf1JUPsms5AqfD5QfxkM= ssh-rsa AAAA
+@cert-authority *.example.com ssh-rsa AAAAB3Nz
+192.168.1.10 ecdsa-sha2-nistp256 AAAAE2Vj
+";
+
+    #[test]
+    fn test_parse_ssh_config() {
+        let hosts = parse_ssh_config(SSH_CONFIG);
+        let aliases: Vec<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["build", "build.example.com", "db"]);
+
+        assert_eq!(hosts[0].hostname, "build.example.com");
+        assert_eq!(hosts[0].user.as_deref(), Some("alice"));
+        assert_eq!(hosts[0].port, Some(2222));
+        assert_eq!(
+            hosts[0].proxy_jump,
+            vec!["ops@bastion.example.com:22", "jump2"]
+        );
+        assert_eq!(
+            hosts[0].describe(),
+            "alice@build.example.com:2222 via ops@bastion.example.com:22, jump2"
+        );
+
+        // The first value wins, and Match blocks are left out
+        assert_eq!(hosts[2].hostname, "10.0.0.5");
+        assert_eq!(hosts[2].user.as_deref(), Some("bob"));
+        assert_eq!(hosts[2].port, None);
+    }
+
+    #[test]
+    fn test_parse_known_hosts() {
+        assert_eq!(
+            parse_known_hosts(KNOWN_HOSTS),
+            vec![
+                ("build.example.com".to_string(), None),
+                ("192.168.1.10".to_string(), None),
+                ("git.example.com".to_string(), Some(7999)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_hosts() {
+        let home = std::env::temp_dir().join(format!("scarab-ssh-hosts-{}", std::process::id()));
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        std::fs::write(home.join(".ssh/config"), SSH_CONFIG).unwrap();
+        std::fs::write(home.join(".ssh/known_hosts"), KNOWN_HOSTS).unwrap();
+        let hosts = discover_hosts(&home);
+        std::fs::remove_dir_all(&home).ok();
+
+        // Known hosts the config already names aren't repeated
+        let aliases: Vec<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+        assert_eq!(
+            aliases,
+            vec![
+                "build",
+                "build.example.com",
+                "db",
+                "192.168.1.10",
+                "git.example.com:7999"
+            ]
+        );
+        assert_eq!(hosts[4].hostname, "git.example.com");
+        assert_eq!(hosts[4].port, Some(7999));
+    }
+
+    #[test]
+    fn test_domain_config_overrides() {
+        let home = Path::new("/home/alice");
+        let build = &parse_ssh_config(SSH_CONFIG)[0];
+
+        let config = build.domain_config(&[], home);
+        assert_eq!(config.id, "ssh:build");
+        assert_eq!(config.host, "build.example.com");
+        assert_eq!(config.port, 2222);
+        assert_eq!(config.user, "alice");
+        assert!(matches!(
+            config.auth,
+            SshAuth::PublicKey { ref path, .. } if path == "/home/alice/.ssh/build_ed25519"
+        ));
+        assert_eq!(config.jump_hosts.len(), 2);
+
+        // A configured domain for the host changes the login
+        let configured = SshDomainConfig {
+            host: "build".to_string(),
+            user: "deploy".to_string(),
+            jump_hosts: vec!["gateway.example.com".to_string()],
+            ..Default::default()
+        };
+        let config = build.domain_config(&[configured], home);
+        assert_eq!(config.host, "build.example.com");
+        assert_eq!(config.user, "deploy");
+        assert!(matches!(config.auth, SshAuth::Agent));
+        assert_eq!(config.jump_hosts, vec!["gateway.example.com"]);
+    }
+}
@@ -0,0 +1,255 @@
+//! Saved workspaces
+//!
+//! "Workspace: Save" snapshots every session with its tabs and panes: each
+//! pane's shell, working directory and, when it is a program that can
+//! simply be started again such as an editor or a pager, the command in
+//! the foreground. "Workspace: Restore" recreates the sessions from the
+//! snapshot, like tmux-resurrect. Snapshots are JSON files in
+//! `workspaces/` under the data directory, one per name.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Version of the snapshot format written
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Programs started again when a workspace is restored
+///
+/// Anything else in the foreground, a build or a REPL with state, is left
+/// out; the pane gets a fresh shell in the same directory.
+pub const RESUMABLE_PROGRAMS: [&str; 14] = [
+    "vi", "vim", "nvim", "emacs", "nano", "hx", "man", "less", "more", "tail", "top", "htop",
+    "btop", "watch",
+];
+
+/// Every session of the daemon at the time it was saved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceSnapshot {
+    pub version: u32,
+    /// Unix time of the save
+    pub saved_at: u64,
+    pub sessions: Vec<SessionSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub name: String,
+    /// Index of the active tab in `tabs`
+    pub active_tab: usize,
+    pub tabs: Vec<TabSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TabSnapshot {
+    pub title: String,
+    /// Index of the focused pane in `panes`
+    pub active_pane: usize,
+    /// Panes in the order they were opened
+    pub panes: Vec<PaneSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaneSnapshot {
+    pub shell: String,
+    pub cwd: Option<String>,
+    /// Command line to run again in the new shell
+    pub command: Option<String>,
+    /// Last lines of output, shown again above the new shell's prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scrollback: Vec<String>,
+}
+
+/// Directory saved workspaces are kept in under `data_dir`
+pub fn workspaces_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("workspaces")
+}
+
+/// File the workspace `name` is saved to under `dir`
+///
+/// Names are used as file names, so path separators and leading dots are
+/// refused.
+pub fn workspace_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!("'{}' can't be used as a workspace name", name);
+    }
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+/// Write `snapshot` as the workspace `name`, replacing any saved before
+pub fn save_workspace(dir: &Path, name: &str, snapshot: &WorkspaceSnapshot) -> Result<PathBuf> {
+    let path = workspace_path(dir, name)?;
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let json = serde_json::to_string_pretty(snapshot)?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Read the workspace `name`
+pub fn load_workspace(dir: &Path, name: &str) -> Result<WorkspaceSnapshot> {
+    let path = workspace_path(dir, name)?;
+    let json = std::fs::read_to_string(&path)
+        .with_context(|| format!("No workspace named '{}'", name.trim()))?;
+    let snapshot: WorkspaceSnapshot = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if snapshot.version > SNAPSHOT_VERSION {
+        bail!(
+            "Workspace '{}' was saved by a newer Scarab (format {})",
+            name.trim(),
+            snapshot.version
+        );
+    }
+    Ok(snapshot)
+}
+
+/// Names of the workspaces saved under `dir`, sorted
+pub fn list_workspaces(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// The command line to type into a fresh shell to run the program in a
+/// pane's foreground again, if it should be on restore
+///
+/// `argv` are the program's arguments as it was started, each quoted for
+/// the shell so spaces and shell syntax in them stay part of the argument.
+/// `shell` is the pane's shell, which is never a command of its own.
+pub fn resumable_command(argv: &[String], shell: &str) -> Option<String> {
+    let program = argv.first()?;
+    let program = program.rsplit('/').next()?.trim_start_matches('-');
+    let shell = shell.rsplit('/').next().unwrap_or(shell);
+    if program == shell || !RESUMABLE_PROGRAMS.contains(&program) {
+        return None;
+    }
+    let quoted: Vec<String> = argv.iter().map(|arg| shell_quote(arg)).collect();
+    Some(quoted.join(" "))
+}
+
+/// `arg` as a single word for a POSIX shell
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-+=@%:,./".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> WorkspaceSnapshot {
+        WorkspaceSnapshot {
+            version: SNAPSHOT_VERSION,
+            saved_at: 1_700_000_000,
+            sessions: vec![SessionSnapshot {
+                name: "dev".to_string(),
+                active_tab: 1,
+                tabs: vec![
+                    TabSnapshot {
+                        title: "editor".to_string(),
+                        active_pane: 0,
+                        panes: vec![PaneSnapshot {
+                            shell: "/bin/zsh".to_string(),
+                            cwd: Some("/home/dev/scarab".to_string()),
+                            command: Some("nvim src/main.rs".to_string()),
+                            scrollback: Vec::new(),
+                        }],
+                    },
+                    TabSnapshot {
+                        title: "server".to_string(),
+                        active_pane: 1,
+                        panes: vec![
+                            PaneSnapshot {
+                                shell: "/bin/zsh".to_string(),
+                                cwd: None,
+                                command: None,
+                                scrollback: vec![
+                                    "$ cargo run".to_string(),
+                                    "Listening".to_string(),
+                                ],
+                            },
+                            PaneSnapshot {
+                                shell: "/bin/zsh".to_string(),
+                                cwd: Some("/var/log".to_string()),
+                                command: Some("tail -f syslog".to_string()),
+                                scrollback: Vec::new(),
+                            },
+                        ],
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_workspace_round_trip() {
+        let dir = std::env::temp_dir().join(format!("scarab-workspaces-{}", std::process::id()));
+        let saved = snapshot();
+        let path = save_workspace(&dir, "daily", &saved).unwrap();
+        assert!(path.ends_with("daily.json"));
+
+        assert_eq!(list_workspaces(&dir), vec!["daily".to_string()]);
+        let loaded = load_workspace(&dir, "daily");
+        let missing = load_workspace(&dir, "weekly");
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(loaded.unwrap(), saved);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_workspace_names() {
+        let dir = Path::new("/data/workspaces");
+        assert!(workspace_path(dir, " daily ")
+            .unwrap()
+            .ends_with("daily.json"));
+        assert!(workspace_path(dir, "").is_err());
+        assert!(workspace_path(dir, "../config").is_err());
+        assert!(workspace_path(dir, ".hidden").is_err());
+    }
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resumable_command() {
+        assert_eq!(
+            resumable_command(&argv(&["/usr/bin/nvim", "src/main.rs"]), "/bin/zsh"),
+            Some("/usr/bin/nvim src/main.rs".to_string())
+        );
+        assert_eq!(
+            resumable_command(&argv(&["tail", "-f", "/var/log/syslog"]), "bash"),
+            Some("tail -f /var/log/syslog".to_string())
+        );
+        // The shell itself, and programs that can't just be started again
+        assert_eq!(resumable_command(&argv(&["-zsh"]), "/bin/zsh"), None);
+        assert_eq!(
+            resumable_command(&argv(&["cargo", "build"]), "/bin/zsh"),
+            None
+        );
+        assert_eq!(resumable_command(&[], "/bin/zsh"), None);
+    }
+
+    #[test]
+    fn test_resumable_command_quotes_arguments() {
+        assert_eq!(
+            resumable_command(&argv(&["watch", "ls | wc"]), "bash"),
+            Some("watch 'ls | wc'".to_string())
+        );
+        assert_eq!(
+            resumable_command(&argv(&["less", "it's; $(rm -rf ~)", ""]), "bash"),
+            Some("less 'it'\\''s; $(rm -rf ~)' ''".to_string())
+        );
+    }
+}
@@ -180,6 +180,7 @@ async fn test_ssh_domain_config_creation() {
         connect_timeout: 15,
         forward_agent: true,
         remote_cwd: Some("/home/developer/projects".to_string()),
+        jump_hosts: Vec::new(),
     };
 
     let domain = SshDomain::new(config.clone());
@@ -210,6 +211,7 @@ async fn test_ssh_domain_connect_local() {
         connect_timeout: 5,
         forward_agent: false,
         remote_cwd: None,
+        jump_hosts: Vec::new(),
     };
 
     let domain = SshDomain::new(config);
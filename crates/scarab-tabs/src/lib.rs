@@ -2,12 +2,16 @@
 //!
 //! Provides tab creation, switching, reordering, and persistence.
 //! Works in conjunction with scarab-panes for full workspace management.
+//!
+//! Tab actions are commands; their default keys are declared with
+//! `get_keybindings` and can be moved under `[keybindings.plugins]`.
 
 use async_trait::async_trait;
 use parking_lot::Mutex;
 use scarab_plugin_api::{
-    types::{ModalItem, RemoteCommand},
-    Action, Plugin, PluginContext, PluginMetadata, Result,
+    key_tables::{KeyCode, KeyCombo, KeyModifiers},
+    types::ModalItem,
+    Plugin, PluginContext, PluginKeyBinding, PluginMetadata, Result,
 };
 use serde::{Deserialize, Serialize};
 
@@ -166,76 +170,27 @@ impl TabsPlugin {
             state: Mutex::new(PluginState::new()),
         }
     }
+}
 
-    fn handle_keybinding(&self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
-        let mut state = self.state.lock();
-
-        // Ctrl+Shift+T (new tab) - ASCII 0x14
-        if input == [0x14] {
-            let tab = state.create_tab(None);
-            log::info!("Created new tab: {} (ID: {})", tab.title, tab.id);
-
-            ctx.notify_success("New Tab", &format!("Created tab: {}", tab.title));
-
-            // Queue command to create session in daemon
-            ctx.queue_command(RemoteCommand::PluginNotify {
-                title: "Tab Created".to_string(),
-                body: format!("Tab #{}: {}", tab.id, tab.title),
-                level: scarab_plugin_api::context::NotifyLevel::Success,
-            });
-
-            return Ok(Action::Modify(Vec::new()));
-        }
-
-        // Ctrl+Tab (next tab) - ASCII 0x09 with special handling
-        if input == [0x09] && state.tabs.len() > 1 {
-            state.next_tab();
-            let tab = state.active_tab();
-            log::info!("Switched to next tab: {}", tab.title);
-
-            ctx.notify_info("Tab Switch", &format!("Active: {}", tab.title));
-            return Ok(Action::Modify(Vec::new()));
-        }
-
-        // Ctrl+Shift+W (close tab) - ASCII 0x17
-        if input == [0x17] {
-            if state.tabs.len() > 1 {
-                let index = state.active_tab_index;
-                if let Some(closed_tab) = state.close_tab(index) {
-                    log::info!("Closed tab: {} (ID: {})", closed_tab.title, closed_tab.id);
-
-                    ctx.notify_info("Tab Closed", &format!("Closed: {}", closed_tab.title));
-
-                    return Ok(Action::Modify(Vec::new()));
-                }
-            } else {
-                ctx.notify_warning("Cannot Close", "Cannot close the last tab");
-            }
-            return Ok(Action::Modify(Vec::new()));
-        }
-
-        // Ctrl+1-9 (switch to tab by number) - ASCII 0x31-0x39 with Ctrl modifier
-        if input.len() == 1 {
-            let byte = input[0];
-            // Check for Ctrl+1 through Ctrl+9
-            if (1..=9).contains(&byte) {
-                let tab_index = (byte - 1) as usize;
-                if state.switch_to_tab(tab_index) {
-                    let tab = state.active_tab();
-                    log::info!("Switched to tab {}: {}", tab_index + 1, tab.title);
-
-                    ctx.notify_info(
-                        "Tab Switch",
-                        &format!("Tab {}: {}", tab_index + 1, tab.title),
-                    );
-
-                    return Ok(Action::Modify(Vec::new()));
-                }
-            }
-        }
-
-        Ok(Action::Continue)
-    }
+/// Keys for `tabs.goto.1` to `tabs.goto.9`, with Ctrl
+const GOTO_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// Zero-based tab index of a `tabs.goto.<n>` command
+fn goto_index(id: &str) -> Option<usize> {
+    id.strip_prefix("tabs.goto.")?
+        .parse::<usize>()
+        .ok()?
+        .checked_sub(1)
 }
 
 impl Default for TabsPlugin {
@@ -255,12 +210,12 @@ impl Plugin for TabsPlugin {
             ModalItem {
                 id: "tabs.new".to_string(),
                 label: "New Tab".to_string(),
-                description: Some("Create a new tab (Ctrl+Shift+T)".to_string()),
+                description: Some("Create a new tab (Ctrl+Alt+N)".to_string()),
             },
             ModalItem {
                 id: "tabs.close".to_string(),
                 label: "Close Tab".to_string(),
-                description: Some("Close current tab (Ctrl+Shift+W)".to_string()),
+                description: Some("Close current tab".to_string()),
             },
             ModalItem {
                 id: "tabs.next".to_string(),
@@ -285,8 +240,19 @@ impl Plugin for TabsPlugin {
         ]
     }
 
-    async fn on_input(&mut self, input: &[u8], ctx: &PluginContext) -> Result<Action> {
-        self.handle_keybinding(input, ctx)
+    /// Ctrl+Alt+N opens a tab and Ctrl+1 to Ctrl+9 switch to one
+    ///
+    /// `tabs.next`, `tabs.prev` and `tabs.close` have no default key, since
+    /// Ctrl+Tab, Ctrl+Shift+Tab and Ctrl+Shift+W are the client's own, as
+    /// is Ctrl+Shift+T (the telemetry HUD). Ctrl+digit has no control byte,
+    /// so the client sends these keys as `CSI u`.
+    fn get_keybindings(&self) -> Vec<PluginKeyBinding> {
+        let new_tab = KeyCombo::new(KeyCode::KeyN, KeyModifiers::CTRL | KeyModifiers::ALT);
+        let mut bindings = vec![PluginKeyBinding::new(new_tab, "tabs.new")];
+        bindings.extend(GOTO_KEYS.iter().enumerate().map(|(index, key)| {
+            PluginKeyBinding::new(KeyCombo::ctrl(*key), format!("tabs.goto.{}", index + 1))
+        }));
+        bindings
     }
 
     async fn on_remote_command(&mut self, id: &str, ctx: &PluginContext) -> Result<()> {
@@ -343,7 +309,15 @@ impl Plugin for TabsPlugin {
                 log::info!("Command: Rename tab (not yet implemented)");
                 ctx.notify_info("Rename Tab", "Feature coming soon");
             }
-            _ => {}
+            id => {
+                if let Some(index) = goto_index(id) {
+                    if state.switch_to_tab(index) {
+                        let tab = state.active_tab();
+                        log::info!("Command: Switched to tab {}: {}", index + 1, tab.title);
+                        ctx.notify_info("Tab Switch", &format!("Tab {}: {}", index + 1, tab.title));
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -446,4 +420,17 @@ mod tests {
         assert_eq!(state.tabs[2].title, "Terminal 1");
         assert_eq!(state.active_tab_index, 2);
     }
+
+    #[test]
+    fn test_goto_commands() {
+        assert_eq!(goto_index("tabs.goto.1"), Some(0));
+        assert_eq!(goto_index("tabs.goto.9"), Some(8));
+        assert_eq!(goto_index("tabs.goto.0"), None);
+        assert_eq!(goto_index("tabs.new"), None);
+
+        let bindings = TabsPlugin::new().get_keybindings();
+        assert_eq!(bindings.len(), 10);
+        assert_eq!(bindings[1].key, KeyCombo::ctrl(KeyCode::Digit1));
+        assert_eq!(bindings[0].command, "tabs.new");
+    }
 }
@@ -0,0 +1,177 @@
+//! Catalog of base16 and base24 schemes
+//!
+//! "Theme: Sync Base16/Base24 Schemes" downloads every scheme in the
+//! tinted-theming schemes repository, converts each to a Scarab theme and
+//! caches it as TOML under the data directory, in `themes/base16` and
+//! `themes/base24`. The theme manager loads the cache with the built-in and
+//! user themes, so the schemes can be found in the theme selector. Their
+//! IDs carry the scheme system, e.g. `base16-nord`, so they never shadow a
+//! built-in theme of the same name.
+
+use crate::{
+    error::{ThemeError, ThemeResult},
+    format::{self, Base16Format, FormatHandler, ThemeFormat},
+    theme::Theme,
+};
+use scarab_platform::Paths;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Scheme systems synced, each a directory of the repository
+pub const SCHEME_SYSTEMS: [&str; 2] = ["base16", "base24"];
+
+/// Hosts the sync talks to: the GitHub API for the listings and the raw
+/// file host for the schemes themselves
+pub const CATALOG_HOSTS: [&str; 2] = ["api.github.com", "raw.githubusercontent.com"];
+
+/// GitHub repository the schemes come from
+const REPOSITORY: &str = "tinted-theming/schemes";
+
+/// Directory the catalog is cached in
+pub fn catalog_dir() -> PathBuf {
+    Paths::from_env().data_dir.join("themes")
+}
+
+/// URL listing the scheme files of `system`
+pub fn listing_url(system: &str) -> String {
+    format!(
+        "https://api.github.com/repos/{}/contents/{}",
+        REPOSITORY, system
+    )
+}
+
+/// A scheme file in the repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeFile {
+    pub name: String,
+    pub download_url: String,
+}
+
+/// An entry of the GitHub contents API's directory listing
+#[derive(Debug, Deserialize)]
+struct ListingEntry {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    download_url: Option<String>,
+}
+
+/// The YAML scheme files in a directory listing
+pub fn parse_listing(json: &str) -> ThemeResult<Vec<SchemeFile>> {
+    let entries: Vec<ListingEntry> = serde_json::from_str(json)?;
+    Ok(entries
+        .into_iter()
+        .filter(|entry| entry.kind == "file")
+        .filter(|entry| entry.name.ends_with(".yaml") || entry.name.ends_with(".yml"))
+        .filter_map(|entry| {
+            Some(SchemeFile {
+                download_url: entry.download_url?,
+                name: entry.name,
+            })
+        })
+        .collect())
+}
+
+/// Convert the scheme in `file_name` to a theme with a catalog ID
+pub fn convert_scheme(system: &str, file_name: &str, content: &str) -> ThemeResult<Theme> {
+    let mut theme = Base16Format::parse(content)?;
+    let stem = Path::new(file_name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| ThemeError::InvalidFormat(format!("Bad scheme file name: {}", file_name)))?;
+    theme.metadata.id = format!("{}-{}", system, stem);
+    if !theme.metadata.tags.iter().any(|tag| tag == system) {
+        theme.metadata.tags.push(system.to_string());
+    }
+    Ok(theme)
+}
+
+/// Cache `theme` under `dir`, returning the file written
+pub fn save_theme(dir: &Path, system: &str, theme: &Theme) -> ThemeResult<PathBuf> {
+    let dir = dir.join(system);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.toml", theme.id()));
+    std::fs::write(&path, format::serialize_theme(theme, ThemeFormat::Toml)?)?;
+    Ok(path)
+}
+
+/// Every theme cached under `dir`
+///
+/// Files that fail to load are skipped with a warning.
+pub fn load_catalog(dir: &Path) -> Vec<Theme> {
+    let mut themes = Vec::new();
+    for system in SCHEME_SYSTEMS {
+        let Ok(entries) = std::fs::read_dir(dir.join(system)) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("toml") {
+                continue;
+            }
+            let theme = std::fs::read_to_string(&path)
+                .map_err(ThemeError::from)
+                .and_then(|content| format::parse_theme(&content, ThemeFormat::Toml));
+            match theme {
+                Ok(theme) => themes.push(theme),
+                Err(e) => log::warn!("Failed to load cached scheme {:?}: {}", path, e),
+            }
+        }
+    }
+    themes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NORD: &str = r##"system: "base16"
+name: "Nord"
+author: "arcticicestudio"
+variant: "dark"
+palette:
+  base00: "#2e3440"
+  base01: "#3b4252"
+  base02: "#434c5e"
+  base03: "#4c566a"
+  base04: "#d8dee9"
+  base05: "#e5e9f0"
+  base06: "#eceff4"
+  base07: "#8fbcbb"
+  base08: "#bf616a"
+  base09: "#d08770"
+  base0A: "#ebcb8b"
+  base0B: "#a3be8c"
+  base0C: "#88c0d0"
+  base0D: "#81a1c1"
+  base0E: "#b48ead"
+  base0F: "#5e81ac"
+"##;
+
+    #[test]
+    fn test_parse_listing() {
+        let json = r#"[
+            {"name": "nord.yaml", "type": "file", "download_url": "https://raw.githubusercontent.com/tinted-theming/schemes/main/base16/nord.yaml"},
+            {"name": "README.md", "type": "file", "download_url": "https://raw.githubusercontent.com/tinted-theming/schemes/main/base16/README.md"},
+            {"name": "extra", "type": "dir", "download_url": null}
+        ]"#;
+        let files = parse_listing(json).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "nord.yaml");
+        assert!(parse_listing("{\"message\": \"API rate limit exceeded\"}").is_err());
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let theme = convert_scheme("base16", "nord.yaml", NORD).unwrap();
+        assert_eq!(theme.id(), "base16-nord");
+        assert_eq!(theme.name(), "Nord");
+
+        let dir = std::env::temp_dir().join(format!("scarab-catalog-{}", std::process::id()));
+        let path = save_theme(&dir, "base16", &theme).unwrap();
+        assert!(path.ends_with("base16/base16-nord.toml"));
+
+        let themes = load_catalog(&dir);
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(themes, vec![theme]);
+    }
+}
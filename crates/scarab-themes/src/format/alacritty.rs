@@ -0,0 +1,169 @@
+//! Alacritty color scheme handler (import only)
+//!
+//! Reads the `colors` section of an Alacritty config, in either the
+//! current TOML form or the older YAML one. Colors may be written as
+//! `#rrggbb` or `0xrrggbb`. Cursor and selection colors of
+//! `CellForeground` or `CellBackground` follow the cell, which a theme
+//! can't express, so they fall back to the defaults.
+
+use crate::{
+    error::{ThemeError, ThemeResult},
+    format::{
+        import::{normalize_color, ImportedColors},
+        FormatHandler,
+    },
+    theme::Theme,
+};
+use serde::Deserialize;
+
+pub struct AlacrittyFormat;
+
+#[derive(Debug, Deserialize)]
+struct AlacrittyConfig {
+    colors: AlacrittyColors,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AlacrittyColors {
+    primary: Primary,
+    cursor: CellColors,
+    selection: CellColors,
+    normal: AnsiColors,
+    bright: AnsiColors,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Primary {
+    foreground: Option<String>,
+    background: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CellColors {
+    text: Option<String>,
+    cursor: Option<String>,
+    background: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnsiColors {
+    black: Option<String>,
+    red: Option<String>,
+    green: Option<String>,
+    yellow: Option<String>,
+    blue: Option<String>,
+    magenta: Option<String>,
+    cyan: Option<String>,
+    white: Option<String>,
+}
+
+impl AnsiColors {
+    fn into_array(self) -> [Option<String>; 8] {
+        [
+            self.black,
+            self.red,
+            self.green,
+            self.yellow,
+            self.blue,
+            self.magenta,
+            self.cyan,
+            self.white,
+        ]
+    }
+}
+
+impl FormatHandler for AlacrittyFormat {
+    fn parse(content: &str) -> ThemeResult<Theme> {
+        Self::parse_named(content, "Imported Alacritty Theme")
+    }
+
+    fn serialize(_theme: &Theme) -> ThemeResult<String> {
+        Err(ThemeError::InvalidFormat(
+            "Exporting to Alacritty is not supported".to_string(),
+        ))
+    }
+}
+
+impl AlacrittyFormat {
+    /// Parse a config's colors as a theme named `fallback_name`
+    pub(crate) fn parse_named(content: &str, fallback_name: &str) -> ThemeResult<Theme> {
+        let config: AlacrittyConfig = match toml::from_str(content) {
+            Ok(config) => config,
+            Err(toml_err) => serde_yaml::from_str(content).map_err(|yaml_err| {
+                ThemeError::InvalidFormat(format!(
+                    "Not an Alacritty TOML ({}) or YAML ({}) config",
+                    toml_err, yaml_err
+                ))
+            })?,
+        };
+        let AlacrittyColors {
+            primary,
+            cursor,
+            selection,
+            normal,
+            bright,
+        } = config.colors;
+
+        let required = |color: Option<String>| color.as_deref().map(normalize_color).transpose();
+        // `CellForeground` and friends are not colors of their own
+        let optional = |color: Option<String>| color.and_then(|c| normalize_color(&c).ok());
+
+        let mut colors = ImportedColors {
+            foreground: required(primary.foreground)?,
+            background: required(primary.background)?,
+            cursor: optional(cursor.cursor),
+            cursor_text: optional(cursor.text),
+            selection_background: optional(selection.background),
+            selection_foreground: optional(selection.text),
+            ..Default::default()
+        };
+        let ansi = normal.into_array().into_iter().chain(bright.into_array());
+        for (index, color) in ansi.enumerate() {
+            if let Some(color) = color {
+                colors.set_ansi(index, &color)?;
+            }
+        }
+        colors.into_theme("Alacritty", fallback_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NORMAL: &str = "black = '#000000'\nred = '#ff0000'\ngreen = '#00ff00'\n\
+                          yellow = '#ffff00'\nblue = '#0000ff'\nmagenta = '#ff00ff'\n\
+                          cyan = '#00ffff'\nwhite = '#ffffff'\n";
+
+    #[test]
+    fn test_parse_alacritty_toml() {
+        let content = format!(
+            "[colors.primary]\nbackground = '0x1d1f21'\nforeground = '#c5c8c6'\n\n\
+             [colors.cursor]\ntext = 'CellBackground'\ncursor = 'CellForeground'\n\n\
+             [colors.normal]\n{}",
+            NORMAL
+        );
+        let theme = AlacrittyFormat::parse(&content).unwrap();
+        assert_eq!(theme.colors.background, "#1d1f21");
+        assert_eq!(theme.colors.cursor, "#c5c8c6");
+        assert_eq!(theme.colors.cursor_text, None);
+        assert_eq!(theme.colors.palette.bright_blue, "#0000ff");
+    }
+
+    #[test]
+    fn test_parse_alacritty_yaml() {
+        let content = "colors:\n  primary:\n    background: '#fdf6e3'\n    foreground: '#657b83'\n  \
+                       normal:\n    black: '#073642'\n    red: '#dc322f'\n    green: '#859900'\n    \
+                       yellow: '#b58900'\n    blue: '#268bd2'\n    magenta: '#d33682'\n    \
+                       cyan: '#2aa198'\n    white: '#eee8d5'\n  bright:\n    red: '#cb4b16'\n";
+        let theme = AlacrittyFormat::parse(content).unwrap();
+        assert!(theme.is_light());
+        assert_eq!(theme.colors.palette.red, "#dc322f");
+        assert_eq!(theme.colors.palette.bright_red, "#cb4b16");
+        assert_eq!(theme.colors.palette.bright_green, "#859900");
+    }
+}
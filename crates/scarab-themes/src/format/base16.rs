@@ -2,6 +2,10 @@
 //!
 //! Supports importing themes in Base16 YAML format.
 //! See: https://github.com/chriskempson/base16
+//!
+//! Schemes in the newer tinted-theming format, with the colors under
+//! `palette`, are read too, including base24 schemes and their own bright
+//! colors. See: https://github.com/tinted-theming/schemes
 
 use crate::{
     error::{ThemeError, ThemeResult},
@@ -9,6 +13,7 @@ use crate::{
     theme::{Theme, ThemeColors, ThemeMetadata, ThemePalette, ThemeVariant},
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 pub struct Base16Format;
 
@@ -42,17 +47,39 @@ struct Base16Theme {
     base0F: String, // brown
 }
 
+/// A scheme in the tinted-theming format, for base16 or base24
+#[derive(Debug, Deserialize)]
+struct TintedScheme {
+    #[serde(default = "default_system")]
+    system: String,
+    name: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    variant: Option<String>,
+    palette: HashMap<String, String>,
+}
+
+fn default_system() -> String {
+    "base16".to_string()
+}
+
 impl FormatHandler for Base16Format {
     fn parse(content: &str) -> ThemeResult<Theme> {
-        let base16: Base16Theme = serde_yaml::from_str(content)
+        let value: serde_yaml::Value = serde_yaml::from_str(content)
+            .map_err(|e| ThemeError::InvalidFormat(format!("YAML parse error: {}", e)))?;
+        if value.get("palette").is_some() {
+            let scheme: TintedScheme = serde_yaml::from_value(value)
+                .map_err(|e| ThemeError::InvalidFormat(format!("YAML parse error: {}", e)))?;
+            return parse_tinted(scheme);
+        }
+        let base16: Base16Theme = serde_yaml::from_value(value)
             .map_err(|e| ThemeError::InvalidFormat(format!("YAML parse error: {}", e)))?;
 
         // Convert Base16 to Theme
-        let id = base16
-            .scheme
-            .to_lowercase()
-            .replace(' ', "-")
-            .replace('_', "-");
+        let id = scheme_id(&base16.scheme);
 
         let variant = match base16.variant.as_deref() {
             Some("light") => ThemeVariant::Light,
@@ -138,9 +165,149 @@ impl FormatHandler for Base16Format {
     }
 }
 
+/// Convert a tinted-theming scheme to a Theme
+///
+/// Base16 schemes reuse their normal colors as the bright ones; base24
+/// schemes have `base12` to `base17` for them.
+fn parse_tinted(scheme: TintedScheme) -> ThemeResult<Theme> {
+    let color = |key: &str| -> ThemeResult<String> {
+        let value = scheme.palette.get(key).ok_or_else(|| {
+            ThemeError::InvalidFormat(format!("Scheme '{}' has no {}", scheme.name, key))
+        })?;
+        Ok(format!("#{}", strip_hash(value)))
+    };
+    let base24 = scheme.system == "base24";
+    let pick =
+        |base24_key: &str, base16_key: &str| color(if base24 { base24_key } else { base16_key });
+
+    let variant = match scheme.variant.as_deref() {
+        Some("light") => ThemeVariant::Light,
+        _ => ThemeVariant::Dark,
+    };
+    let description = scheme.description.clone().unwrap_or_else(|| {
+        if base24 {
+            "Base24 theme".to_string()
+        } else {
+            "Base16 theme".to_string()
+        }
+    });
+
+    Ok(Theme {
+        metadata: ThemeMetadata {
+            id: scheme_id(&scheme.name),
+            name: scheme.name.clone(),
+            author: scheme.author.clone(),
+            description,
+            variant,
+            tags: vec![scheme.system.clone()],
+            url: None,
+        },
+        colors: ThemeColors {
+            foreground: color("base05")?,
+            background: color("base00")?,
+            cursor: color("base05")?,
+            cursor_text: Some(color("base00")?),
+            selection_background: color("base02")?,
+            selection_foreground: None,
+            palette: ThemePalette {
+                black: color("base00")?,
+                red: color("base08")?,
+                green: color("base0B")?,
+                yellow: color("base0A")?,
+                blue: color("base0D")?,
+                magenta: color("base0E")?,
+                cyan: color("base0C")?,
+                white: pick("base06", "base05")?,
+                bright_black: pick("base02", "base03")?,
+                bright_red: pick("base12", "base08")?,
+                bright_green: pick("base14", "base0B")?,
+                bright_yellow: pick("base13", "base0A")?,
+                bright_blue: pick("base16", "base0D")?,
+                bright_magenta: pick("base17", "base0E")?,
+                bright_cyan: pick("base15", "base0C")?,
+                bright_white: color("base07")?,
+            },
+            ui: None,
+        },
+    })
+}
+
+/// Theme ID for a scheme name, e.g. "Gruvbox dark_hard" to "gruvbox-dark-hard"
+fn scheme_id(name: &str) -> String {
+    name.to_lowercase().replace([' ', '_'], "-")
+}
+
 /// Strip leading '#' from hex color
 fn strip_hash(color: &str) -> String {
     color.trim_start_matches('#').to_string()
 }
 
-// Add serde_yaml dependency to Cargo.toml
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALETTE: &str = r##"
+  base00: "#2e3440"
+  base01: "#3b4252"
+  base02: "#434c5e"
+  base03: "#4c566a"
+  base04: "#d8dee9"
+  base05: "#e5e9f0"
+  base06: "#eceff4"
+  base07: "#8fbcbb"
+  base08: "#bf616a"
+  base09: "#d08770"
+  base0A: "#ebcb8b"
+  base0B: "#a3be8c"
+  base0C: "#88c0d0"
+  base0D: "#81a1c1"
+  base0E: "#b48ead"
+  base0F: "#5e81ac""##;
+
+    const BASE24_BRIGHTS: &str = r##"
+  base10: "#242933"
+  base11: "#1a1e24"
+  base12: "#d06f79"
+  base13: "#f0d399"
+  base14: "#b1d196"
+  base15: "#8cd0d3"
+  base16: "#88c0d0"
+  base17: "#c895bf""##;
+
+    fn scheme(header: &str, extra: &str) -> String {
+        format!("{}\npalette:{}{}\n", header, PALETTE, extra)
+    }
+
+    #[test]
+    fn test_parse_tinted_base16() {
+        let content = scheme(
+            "system: \"base16\"\nname: \"Nord\"\nauthor: \"arcticicestudio\"",
+            "",
+        );
+        let theme = Base16Format::parse(&content).unwrap();
+        assert_eq!(theme.id(), "nord");
+        assert_eq!(theme.metadata.tags, ["base16"]);
+        assert_eq!(theme.colors.background, "#2e3440");
+        assert_eq!(theme.colors.palette.white, "#e5e9f0");
+        // Bright colors repeat the normal ones
+        assert_eq!(theme.colors.palette.bright_red, "#bf616a");
+    }
+
+    #[test]
+    fn test_parse_tinted_base24() {
+        let content = scheme(
+            "system: \"base24\"\nname: \"Nord Bright\"\nvariant: \"light\"",
+            BASE24_BRIGHTS,
+        );
+        let theme = Base16Format::parse(&content).unwrap();
+        assert_eq!(theme.id(), "nord-bright");
+        assert!(theme.is_light());
+        assert_eq!(theme.colors.palette.white, "#eceff4");
+        assert_eq!(theme.colors.palette.bright_red, "#d06f79");
+        assert_eq!(theme.colors.palette.bright_blue, "#88c0d0");
+
+        // A base24 scheme without its bright colors is rejected
+        let content = scheme("system: \"base24\"\nname: \"Broken\"", "");
+        assert!(Base16Format::parse(&content).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Shared handling for color schemes from other terminals
+//!
+//! iTerm2, Alacritty, kitty and WezTerm each name the same handful of
+//! colors differently. Their parsers collect them into [`ImportedColors`],
+//! which fills the gaps those formats allow and builds the [`Theme`].
+
+use crate::{
+    error::{ThemeError, ThemeResult},
+    theme::{Theme, ThemeColors, ThemeMetadata, ThemePalette, ThemeVariant},
+};
+
+/// Colors read from another terminal's scheme
+#[derive(Debug, Default)]
+pub(crate) struct ImportedColors {
+    /// Scheme name, if the format carries one
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+    pub cursor: Option<String>,
+    pub cursor_text: Option<String>,
+    pub selection_background: Option<String>,
+    pub selection_foreground: Option<String>,
+    /// ANSI colors 0-15
+    pub ansi: [Option<String>; 16],
+}
+
+impl ImportedColors {
+    /// Set ANSI color `index` (0-15) from a color in the source format
+    pub fn set_ansi(&mut self, index: usize, color: &str) -> ThemeResult<()> {
+        if let Some(slot) = self.ansi.get_mut(index) {
+            *slot = Some(normalize_color(color)?);
+        }
+        Ok(())
+    }
+
+    /// Build the theme, tagged with the format it came from
+    ///
+    /// Foreground, background and the eight normal colors are required.
+    /// Missing bright colors repeat the normal ones, the cursor follows the
+    /// foreground, and the selection uses bright black. Without a name of
+    /// its own the theme is named and identified by `fallback_name`.
+    pub fn into_theme(self, format: &str, fallback_name: &str) -> ThemeResult<Theme> {
+        let missing =
+            |what: &str| ThemeError::InvalidFormat(format!("{} theme has no {}", format, what));
+        let foreground = self.foreground.ok_or_else(|| missing("foreground"))?;
+        let background = self.background.ok_or_else(|| missing("background"))?;
+
+        let mut ansi: Vec<String> = Vec::with_capacity(16);
+        for (index, color) in self.ansi.into_iter().enumerate() {
+            match color {
+                Some(color) => ansi.push(color),
+                None if index >= 8 => ansi.push(ansi[index - 8].clone()),
+                None => return Err(missing(&format!("color {}", index))),
+            }
+        }
+        let mut ansi = ansi.into_iter();
+        let mut next = || ansi.next().expect("16 colors");
+        let palette = ThemePalette {
+            black: next(),
+            red: next(),
+            green: next(),
+            yellow: next(),
+            blue: next(),
+            magenta: next(),
+            cyan: next(),
+            white: next(),
+            bright_black: next(),
+            bright_red: next(),
+            bright_green: next(),
+            bright_yellow: next(),
+            bright_blue: next(),
+            bright_magenta: next(),
+            bright_cyan: next(),
+            bright_white: next(),
+        };
+
+        let name = self
+            .name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| fallback_name.to_string());
+        let variant = if luminance(&background) < 0.5 {
+            ThemeVariant::Dark
+        } else {
+            ThemeVariant::Light
+        };
+
+        Ok(Theme {
+            metadata: ThemeMetadata {
+                id: theme_id(&name),
+                description: format!("Imported from {}", format),
+                author: self.author.unwrap_or_else(|| "Unknown".to_string()),
+                name,
+                variant,
+                tags: vec!["imported".to_string(), theme_id(format)],
+                url: None,
+            },
+            colors: ThemeColors {
+                cursor: self.cursor.unwrap_or_else(|| foreground.clone()),
+                cursor_text: self.cursor_text,
+                selection_background: self
+                    .selection_background
+                    .unwrap_or_else(|| palette.bright_black.clone()),
+                selection_foreground: self.selection_foreground,
+                foreground,
+                background,
+                palette,
+                ui: None,
+            },
+        })
+    }
+}
+
+/// `#rrggbb` for a hex color written as `#rgb`, `#rrggbb`, `0xrrggbb` or
+/// `rrggbb`
+pub(crate) fn normalize_color(color: &str) -> ThemeResult<String> {
+    let trimmed = color.trim().trim_matches(|c| c == '"' || c == '\'');
+    let hex = trimmed
+        .strip_prefix('#')
+        .or_else(|| trimmed.strip_prefix("0x"))
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ThemeError::InvalidColor(color.to_string()));
+    }
+    match hex.len() {
+        6 => Ok(format!("#{}", hex.to_lowercase())),
+        3 => Ok(hex
+            .chars()
+            .fold(String::from("#"), |mut out, c| {
+                out.push(c);
+                out.push(c);
+                out
+            })
+            .to_lowercase()),
+        _ => Err(ThemeError::InvalidColor(color.to_string())),
+    }
+}
+
+/// Lowercase, dash-separated ID for a theme name
+pub(crate) fn theme_id(name: &str) -> String {
+    let mut id = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.ends_with('-') && !id.is_empty() {
+            id.push('-');
+        }
+    }
+    id.trim_end_matches('-').to_string()
+}
+
+/// Relative luminance (0-1) of a `#rrggbb` color
+fn luminance(color: &str) -> f32 {
+    let channel = |range: std::ops::Range<usize>| {
+        let value = color
+            .get(range)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .unwrap_or(0);
+        value as f32 / 255.0
+    };
+    0.2126 * channel(1..3) + 0.7152 * channel(3..5) + 0.0722 * channel(5..7)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_color() {
+        assert_eq!(normalize_color("#1E1E2E").unwrap(), "#1e1e2e");
+        assert_eq!(normalize_color("0x282a36").unwrap(), "#282a36");
+        assert_eq!(normalize_color("'#fff'").unwrap(), "#ffffff");
+        assert!(normalize_color("red").is_err());
+    }
+
+    #[test]
+    fn test_missing_brights_repeat_normals() {
+        let mut colors = ImportedColors {
+            foreground: Some("#eeeeee".to_string()),
+            background: Some("#fafafa".to_string()),
+            ..Default::default()
+        };
+        for index in 0..8 {
+            colors.set_ansi(index, &format!("#00000{}", index)).unwrap();
+        }
+        let theme = colors.into_theme("kitty", "My Scheme").unwrap();
+        assert_eq!(theme.id(), "my-scheme");
+        assert!(theme.is_light());
+        assert_eq!(theme.colors.palette.bright_red, "#000001");
+        assert_eq!(theme.colors.cursor, "#eeeeee");
+
+        let err = ImportedColors::default().into_theme("kitty", "x");
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! iTerm2 `.itermcolors` format handler (import only)
+//!
+//! An `.itermcolors` file is an XML property list mapping names such as
+//! `Ansi 1 Color` and `Background Color` to dictionaries of `Red
+//! Component`, `Green Component` and `Blue Component` reals between 0 and
+//! 1. Only that shape is read; binary property lists are not supported.
+
+use crate::{
+    error::{ThemeError, ThemeResult},
+    format::{
+        import::{normalize_color, ImportedColors},
+        FormatHandler,
+    },
+    theme::Theme,
+};
+use std::collections::HashMap;
+
+pub struct ITermFormat;
+
+impl FormatHandler for ITermFormat {
+    fn parse(content: &str) -> ThemeResult<Theme> {
+        Self::parse_named(content, "Imported iTerm2 Theme")
+    }
+
+    fn serialize(_theme: &Theme) -> ThemeResult<String> {
+        Err(ThemeError::InvalidFormat(
+            "Exporting to iTerm2 is not supported".to_string(),
+        ))
+    }
+}
+
+impl ITermFormat {
+    /// Parse a preset named `fallback_name`, as `.itermcolors` files carry
+    /// no name
+    pub(crate) fn parse_named(content: &str, fallback_name: &str) -> ThemeResult<Theme> {
+        let entries = parse_color_dict(content)?;
+        let color = |key: &str| entries.get(key).cloned();
+
+        let mut colors = ImportedColors {
+            foreground: color("Foreground Color"),
+            background: color("Background Color"),
+            cursor: color("Cursor Color"),
+            cursor_text: color("Cursor Text Color"),
+            selection_background: color("Selection Color"),
+            selection_foreground: color("Selected Text Color"),
+            ..Default::default()
+        };
+        for index in 0..16 {
+            if let Some(value) = color(&format!("Ansi {} Color", index)) {
+                colors.set_ansi(index, &value)?;
+            }
+        }
+        colors.into_theme("iTerm2", fallback_name)
+    }
+}
+
+/// Colors of the top-level dictionary, as `#rrggbb` by key
+fn parse_color_dict(content: &str) -> ThemeResult<HashMap<String, String>> {
+    let invalid = |message: &str| ThemeError::InvalidFormat(format!("itermcolors: {}", message));
+    let mut tags = Tags { rest: content };
+
+    // Skip the XML prolog and doctype to the outer <dict>
+    loop {
+        match tags.next_tag() {
+            Some(("dict", _)) => break,
+            Some(_) => continue,
+            None => return Err(invalid("no <dict> found")),
+        }
+    }
+
+    let mut colors = HashMap::new();
+    loop {
+        let key = match tags.next_tag() {
+            Some(("key", text)) => text.to_string(),
+            Some(("/dict", _)) | None => break,
+            Some((tag, _)) => return Err(invalid(&format!("unexpected <{}>", tag))),
+        };
+        match tags.next_tag() {
+            Some(("dict", _)) => {
+                let mut components = [0.0f64; 3];
+                loop {
+                    let component = match tags.next_tag() {
+                        Some(("key", text)) => text.to_string(),
+                        Some(("/dict", _)) => break,
+                        _ => return Err(invalid(&format!("malformed `{}`", key))),
+                    };
+                    let (_, value) = tags
+                        .next_tag()
+                        .ok_or_else(|| invalid(&format!("`{}` has no value", component)))?;
+                    let slot = match component.as_str() {
+                        "Red Component" => 0,
+                        "Green Component" => 1,
+                        "Blue Component" => 2,
+                        _ => continue,
+                    };
+                    components[slot] = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| invalid(&format!("`{}` is not a number", component)))?;
+                }
+                let [r, g, b] = components.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+                colors.insert(
+                    key,
+                    normalize_color(&format!("{:02x}{:02x}{:02x}", r, g, b))?,
+                );
+            }
+            Some(_) => {}
+            None => return Err(invalid(&format!("`{}` has no value", key))),
+        }
+    }
+    Ok(colors)
+}
+
+/// Walks the elements of an XML document, yielding each opening tag with
+/// the text after it up to the next tag
+///
+/// Closing tags are skipped except `</dict>`, which ends a dictionary.
+/// Self-closing tags such as `<true/>` yield no text.
+struct Tags<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Tags<'a> {
+    fn next_tag(&mut self) -> Option<(&'a str, &'a str)> {
+        loop {
+            let start = self.rest.find('<')?;
+            let end = start + self.rest[start..].find('>')?;
+            let tag = &self.rest[start + 1..end];
+            self.rest = &self.rest[end + 1..];
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_suffix('/') {
+                return Some((name.trim(), ""));
+            }
+            let name = tag.split_whitespace().next().unwrap_or("");
+            if name == "/dict" {
+                return Some((name, ""));
+            }
+            if name.starts_with('/') {
+                continue;
+            }
+            let text = &self.rest[..self.rest.find('<').unwrap_or(self.rest.len())];
+            return Some((name, text));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, r: f64, g: f64, b: f64) -> String {
+        format!(
+            "<key>{}</key><dict><key>Alpha Component</key><real>1</real>\
+             <key>Blue Component</key><real>{}</real>\
+             <key>Color Space</key><string>sRGB</string>\
+             <key>Green Component</key><real>{}</real>\
+             <key>Red Component</key><real>{}</real></dict>\n",
+            name, b, g, r
+        )
+    }
+
+    #[test]
+    fn test_parse_itermcolors() {
+        let mut body = String::new();
+        for index in 0..16 {
+            body.push_str(&entry(&format!("Ansi {} Color", index), 0.0, 0.0, 0.0));
+        }
+        body.push_str(&entry("Ansi 1 Color", 1.0, 0.0, 0.0));
+        body.push_str(&entry("Background Color", 0.1, 0.1, 0.1));
+        body.push_str(&entry("Foreground Color", 0.9, 0.9, 0.9));
+        let content = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+             \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n<dict>\n{}</dict>\n</plist>\n",
+            body
+        );
+
+        let theme = ITermFormat::parse(&content).unwrap();
+        assert_eq!(theme.colors.palette.red, "#ff0000");
+        assert_eq!(theme.colors.background, "#1a1a1a");
+        assert_eq!(theme.colors.foreground, "#e6e6e6");
+        assert!(theme.is_dark());
+        assert!(theme.metadata.tags.contains(&"iterm2".to_string()));
+    }
+}
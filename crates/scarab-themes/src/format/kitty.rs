@@ -0,0 +1,113 @@
+//! kitty color scheme handler (import only)
+//!
+//! A kitty theme is a `.conf` file of `key value` lines, such as
+//! `foreground #dddddd` and `color1 #cc0000`, with `#` comments. Themes
+//! from kitty-themes name themselves in `## name:` and `## author:`
+//! comments, which become the theme's metadata. Other settings are
+//! ignored.
+
+use crate::{
+    error::{ThemeError, ThemeResult},
+    format::{
+        import::{normalize_color, ImportedColors},
+        FormatHandler,
+    },
+    theme::Theme,
+};
+
+pub struct KittyFormat;
+
+impl FormatHandler for KittyFormat {
+    fn parse(content: &str) -> ThemeResult<Theme> {
+        Self::parse_named(content, "Imported kitty Theme")
+    }
+
+    fn serialize(_theme: &Theme) -> ThemeResult<String> {
+        Err(ThemeError::InvalidFormat(
+            "Exporting to kitty is not supported".to_string(),
+        ))
+    }
+}
+
+impl KittyFormat {
+    /// Parse a theme, named `fallback_name` unless it has a `## name:`
+    pub(crate) fn parse_named(content: &str, fallback_name: &str) -> ThemeResult<Theme> {
+        let mut colors = ImportedColors::default();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix("##") {
+                if let Some((key, value)) = comment.split_once(':') {
+                    let value = Some(value.trim().to_string());
+                    match key.trim() {
+                        "name" => colors.name = value,
+                        "author" => colors.author = value,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.split_once(char::is_whitespace) {
+                Some((key, value)) => (key, value.trim()),
+                None => continue,
+            };
+            // Colors that follow the cell have no color of their own
+            if value == "none" {
+                continue;
+            }
+            let color = || normalize_color(value).map(Some);
+            match key {
+                "foreground" => colors.foreground = color()?,
+                "background" => colors.background = color()?,
+                "cursor" => colors.cursor = color()?,
+                "cursor_text_color" => {
+                    if value != "background" {
+                        colors.cursor_text = color()?;
+                    }
+                }
+                "selection_background" => colors.selection_background = color()?,
+                "selection_foreground" => colors.selection_foreground = color()?,
+                _ => {
+                    if let Some(index) = key.strip_prefix("color") {
+                        if let Ok(index) = index.parse::<usize>() {
+                            colors.set_ansi(index, value)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        colors.into_theme("kitty", fallback_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kitty_conf() {
+        let mut content = String::from(
+            "# vim:ft=kitty\n\n## name: Tokyo Night\n## author: enkia\n\n\
+             foreground #c0caf5\nbackground   #1a1b26\n\
+             selection_background #33467c\nselection_foreground none\n\
+             cursor_text_color background\nurl_color #73daca\n",
+        );
+        for index in 0..16 {
+            content.push_str(&format!("color{} #0000{:02x}\n", index, index));
+        }
+
+        let theme = KittyFormat::parse(&content).unwrap();
+        assert_eq!(theme.metadata.name, "Tokyo Night");
+        assert_eq!(theme.metadata.id, "tokyo-night");
+        assert_eq!(theme.metadata.author, "enkia");
+        assert_eq!(theme.colors.background, "#1a1b26");
+        assert_eq!(theme.colors.selection_foreground, None);
+        assert_eq!(theme.colors.cursor_text, None);
+        assert_eq!(theme.colors.palette.bright_white, "#00000f");
+    }
+}
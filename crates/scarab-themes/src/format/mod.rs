@@ -4,16 +4,35 @@
 //! - TOML: Simple, human-readable format
 //! - JSON: Standard interchange format
 //! - Base16: Compatible with Base16 theme system
+//!
+//! Schemes from other terminals can be imported, but not exported:
+//! - iTerm2: `.itermcolors` property lists
+//! - Alacritty: the `colors` section of a TOML or YAML config
+//! - kitty: `.conf` theme files
+//! - WezTerm: TOML schemes and Lua color tables
 
+mod alacritty;
 mod base16;
+mod import;
+mod iterm;
 mod json;
+mod kitty;
 mod toml;
+mod wezterm;
 
 pub use self::json::JsonFormat;
 pub use self::toml::TomlFormat;
+pub use alacritty::AlacrittyFormat;
 pub use base16::Base16Format;
+pub use iterm::ITermFormat;
+pub use kitty::KittyFormat;
+pub use wezterm::WezTermFormat;
 
-use crate::{error::ThemeResult, theme::Theme};
+use crate::{
+    error::{ThemeError, ThemeResult},
+    theme::Theme,
+};
+use std::path::Path;
 
 /// Theme file format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -24,6 +43,14 @@ pub enum ThemeFormat {
     Json,
     /// Base16 YAML format
     Base16,
+    /// iTerm2 color preset (import only)
+    ITerm2,
+    /// Alacritty config colors (import only)
+    Alacritty,
+    /// kitty theme (import only)
+    Kitty,
+    /// WezTerm color scheme (import only)
+    WezTerm,
 }
 
 impl ThemeFormat {
@@ -33,18 +60,60 @@ impl ThemeFormat {
             ThemeFormat::Toml => "toml",
             ThemeFormat::Json => "json",
             ThemeFormat::Base16 => "yaml",
+            ThemeFormat::ITerm2 => "itermcolors",
+            ThemeFormat::Alacritty => "toml",
+            ThemeFormat::Kitty => "conf",
+            ThemeFormat::WezTerm => "lua",
         }
     }
 
     /// Detect format from file extension
+    ///
+    /// `.toml` and `.yaml` files are taken as Scarab and Base16 themes; use
+    /// [`ThemeFormat::detect`] to tell them from other terminals' schemes.
     pub fn from_extension(ext: &str) -> Option<Self> {
         match ext.to_lowercase().as_str() {
             "toml" => Some(ThemeFormat::Toml),
             "json" => Some(ThemeFormat::Json),
             "yaml" | "yml" => Some(ThemeFormat::Base16),
+            "itermcolors" => Some(ThemeFormat::ITerm2),
+            "conf" => Some(ThemeFormat::Kitty),
+            "lua" => Some(ThemeFormat::WezTerm),
             _ => None,
         }
     }
+
+    /// Detect format from file extension and content
+    ///
+    /// A TOML file with `[colors.primary]` is an Alacritty config and one
+    /// with a `colors.ansi` list a WezTerm scheme; a YAML file with a
+    /// `colors` key is an Alacritty config.
+    pub fn detect(path: &Path, content: &str) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        match Self::from_extension(ext)? {
+            ThemeFormat::Toml => {
+                // Invalid files are left for the parser to report
+                let value: Option<::toml::Value> = ::toml::from_str(content).ok();
+                let colors = value.as_ref().and_then(|v| v.get("colors"));
+                if colors.and_then(|c| c.get("primary")).is_some() {
+                    Some(ThemeFormat::Alacritty)
+                } else if colors.and_then(|c| c.get("ansi")).is_some() {
+                    Some(ThemeFormat::WezTerm)
+                } else {
+                    Some(ThemeFormat::Toml)
+                }
+            }
+            ThemeFormat::Base16 => {
+                let value: Option<serde_yaml::Value> = serde_yaml::from_str(content).ok();
+                if value.as_ref().and_then(|v| v.get("colors")).is_some() {
+                    Some(ThemeFormat::Alacritty)
+                } else {
+                    Some(ThemeFormat::Base16)
+                }
+            }
+            format => Some(format),
+        }
+    }
 }
 
 /// Parse theme from string with given format
@@ -53,6 +122,31 @@ pub fn parse_theme(content: &str, format: ThemeFormat) -> ThemeResult<Theme> {
         ThemeFormat::Toml => TomlFormat::parse(content),
         ThemeFormat::Json => JsonFormat::parse(content),
         ThemeFormat::Base16 => Base16Format::parse(content),
+        ThemeFormat::ITerm2 => ITermFormat::parse(content),
+        ThemeFormat::Alacritty => AlacrittyFormat::parse(content),
+        ThemeFormat::Kitty => KittyFormat::parse(content),
+        ThemeFormat::WezTerm => WezTermFormat::parse(content),
+    }
+}
+
+/// Read a theme file in any supported format
+///
+/// Imported schemes that don't carry a name are named after the file.
+pub fn import_theme_file(path: &Path) -> ThemeResult<Theme> {
+    let content = std::fs::read_to_string(path)?;
+    let format = ThemeFormat::detect(path, &content).ok_or_else(|| {
+        ThemeError::InvalidFormat(format!("Unknown theme format: {}", path.display()))
+    })?;
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Imported Theme");
+    match format {
+        ThemeFormat::ITerm2 => ITermFormat::parse_named(&content, name),
+        ThemeFormat::Alacritty => AlacrittyFormat::parse_named(&content, name),
+        ThemeFormat::Kitty => KittyFormat::parse_named(&content, name),
+        ThemeFormat::WezTerm => WezTermFormat::parse_named(&content, name),
+        _ => parse_theme(&content, format),
     }
 }
 
@@ -62,6 +156,10 @@ pub fn serialize_theme(theme: &Theme, format: ThemeFormat) -> ThemeResult<String
         ThemeFormat::Toml => TomlFormat::serialize(theme),
         ThemeFormat::Json => JsonFormat::serialize(theme),
         ThemeFormat::Base16 => Base16Format::serialize(theme),
+        ThemeFormat::ITerm2 => ITermFormat::serialize(theme),
+        ThemeFormat::Alacritty => AlacrittyFormat::serialize(theme),
+        ThemeFormat::Kitty => KittyFormat::serialize(theme),
+        ThemeFormat::WezTerm => WezTermFormat::serialize(theme),
     }
 }
 
@@ -95,4 +193,28 @@ mod tests {
         );
         assert_eq!(ThemeFormat::from_extension("txt"), None);
     }
+
+    #[test]
+    fn test_detect_by_content() {
+        let detect = |name: &str, content: &str| ThemeFormat::detect(Path::new(name), content);
+        assert_eq!(
+            detect("a.toml", "[colors.primary]\nbackground = '#000000'\n"),
+            Some(ThemeFormat::Alacritty)
+        );
+        assert_eq!(
+            detect("a.toml", "[colors]\nansi = ['#000000']\n"),
+            Some(ThemeFormat::WezTerm)
+        );
+        assert_eq!(
+            detect("a.toml", "[metadata]\nid = 'x'\n"),
+            Some(ThemeFormat::Toml)
+        );
+        assert_eq!(
+            detect("a.yml", "colors:\n  primary: {}\n"),
+            Some(ThemeFormat::Alacritty)
+        );
+        assert_eq!(detect("a.yaml", "scheme: x\n"), Some(ThemeFormat::Base16));
+        assert_eq!(detect("a.itermcolors", ""), Some(ThemeFormat::ITerm2));
+        assert_eq!(detect("a", ""), None);
+    }
 }
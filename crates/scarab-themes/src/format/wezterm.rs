@@ -0,0 +1,393 @@
+//! WezTerm color scheme handler (import only)
+//!
+//! Reads both forms WezTerm schemes come in: the TOML files of its
+//! `color_schemes` directory, with `[colors]` and `[metadata]` tables, and
+//! Lua tables with the same keys, whether a module that `return`s the
+//! table or a `wezterm.lua` that assigns `config.colors = { ... }`. The Lua
+//! is read as data, not run, so only literal strings and tables count.
+
+use crate::{
+    error::{ThemeError, ThemeResult},
+    format::{
+        import::{normalize_color, ImportedColors},
+        FormatHandler,
+    },
+    theme::Theme,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct WezTermFormat;
+
+/// The `colors` table, common to both forms
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WezTermColors {
+    foreground: Option<String>,
+    background: Option<String>,
+    cursor_bg: Option<String>,
+    cursor_fg: Option<String>,
+    selection_bg: Option<String>,
+    selection_fg: Option<String>,
+    ansi: Vec<String>,
+    brights: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WezTermMetadata {
+    name: Option<String>,
+    author: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WezTermScheme {
+    colors: WezTermColors,
+    #[serde(default)]
+    metadata: WezTermMetadata,
+}
+
+impl FormatHandler for WezTermFormat {
+    fn parse(content: &str) -> ThemeResult<Theme> {
+        Self::parse_named(content, "Imported WezTerm Theme")
+    }
+
+    fn serialize(_theme: &Theme) -> ThemeResult<String> {
+        Err(ThemeError::InvalidFormat(
+            "Exporting to WezTerm is not supported".to_string(),
+        ))
+    }
+}
+
+impl WezTermFormat {
+    /// Parse a scheme, named `fallback_name` unless its `[metadata]` has a
+    /// name
+    pub(crate) fn parse_named(content: &str, fallback_name: &str) -> ThemeResult<Theme> {
+        let scheme = match toml::from_str::<WezTermScheme>(content) {
+            Ok(scheme) => scheme,
+            Err(_) => parse_lua(content)?,
+        };
+        let WezTermScheme { colors, metadata } = scheme;
+
+        let color = |color: Option<String>| color.as_deref().map(normalize_color).transpose();
+        let mut imported = ImportedColors {
+            name: metadata.name,
+            author: metadata.author,
+            foreground: color(colors.foreground)?,
+            background: color(colors.background)?,
+            cursor: color(colors.cursor_bg)?,
+            cursor_text: color(colors.cursor_fg)?,
+            selection_background: color(colors.selection_bg)?,
+            selection_foreground: color(colors.selection_fg)?,
+            ..Default::default()
+        };
+        for (index, value) in colors.ansi.iter().take(8).enumerate() {
+            imported.set_ansi(index, value)?;
+        }
+        for (index, value) in colors.brights.iter().take(8).enumerate() {
+            imported.set_ansi(index + 8, value)?;
+        }
+        imported.into_theme("WezTerm", fallback_name)
+    }
+}
+
+/// A value in a Lua table literal
+#[derive(Debug)]
+enum LuaValue {
+    String(String),
+    Table {
+        fields: HashMap<String, LuaValue>,
+        items: Vec<LuaValue>,
+    },
+    /// Numbers, booleans, names and calls, which schemes don't need
+    Other,
+}
+
+impl LuaValue {
+    fn field(&self, key: &str) -> Option<&LuaValue> {
+        match self {
+            LuaValue::Table { fields, .. } => fields.get(key),
+            _ => None,
+        }
+    }
+
+    fn string(&self, key: &str) -> Option<String> {
+        match self.field(key)? {
+            LuaValue::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn strings(&self, key: &str) -> Vec<String> {
+        match self.field(key) {
+            Some(LuaValue::Table { items, .. }) => items
+                .iter()
+                .filter_map(|item| match item {
+                    LuaValue::String(value) => Some(value.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Read the colors table out of Lua source
+///
+/// The table assigned to `colors` wins, as in a `wezterm.lua`; otherwise
+/// the table after `return`.
+fn parse_lua(content: &str) -> ThemeResult<WezTermScheme> {
+    let source = strip_lua_comments(content);
+    let start = find_table_after(&source, "colors", Some('='))
+        .or_else(|| find_table_after(&source, "return", None))
+        .ok_or_else(|| {
+            ThemeError::InvalidFormat("No WezTerm colors table found in Lua".to_string())
+        })?;
+
+    let mut parser = LuaParser {
+        chars: source[start..].chars().peekable(),
+    };
+    let table = parser.value()?;
+    let colors = WezTermColors {
+        foreground: table.string("foreground"),
+        background: table.string("background"),
+        cursor_bg: table.string("cursor_bg"),
+        cursor_fg: table.string("cursor_fg"),
+        selection_bg: table.string("selection_bg"),
+        selection_fg: table.string("selection_fg"),
+        ansi: table.strings("ansi"),
+        brights: table.strings("brights"),
+    };
+    Ok(WezTermScheme {
+        colors,
+        metadata: WezTermMetadata::default(),
+    })
+}
+
+/// Byte offset of the `{` that follows `keyword`, and `separator` if given
+fn find_table_after(source: &str, keyword: &str, separator: Option<char>) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(found) = source[offset..].find(keyword) {
+        let at = offset + found;
+        offset = at + keyword.len();
+        let before = source[..at].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let mut rest = source[offset..].trim_start();
+        if let Some(separator) = separator {
+            match rest.strip_prefix(separator) {
+                Some(after) => rest = after.trim_start(),
+                None => continue,
+            }
+        }
+        if rest.starts_with('{') {
+            return Some(source.len() - rest.len());
+        }
+    }
+    None
+}
+
+/// `content` with `--` line and `--[[ ]]` block comments blanked out,
+/// leaving strings alone
+fn strip_lua_comments(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(|q: char| q == c || q == '\n')
+                .map_or(rest.len(), |end| end + 2);
+            let end = end.min(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if let Some(comment) = rest.strip_prefix("--") {
+            let end = match comment.strip_prefix("[[") {
+                Some(block) => block.find("]]").map_or(rest.len(), |end| end + 6),
+                None => comment.find('\n').map_or(rest.len(), |end| end + 2),
+            };
+            out.push(' ');
+            rest = &rest[end..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+struct LuaParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl LuaParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn value(&mut self) -> ThemeResult<LuaValue> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('{') => self.table(),
+            Some('"') | Some('\'') => self.string().map(LuaValue::String),
+            Some(_) => {
+                // Anything else runs to the end of the field
+                let mut depth = 0usize;
+                while let Some(&c) = self.chars.peek() {
+                    match c {
+                        '(' | '[' | '{' => depth += 1,
+                        ')' | ']' | '}' if depth > 0 => depth -= 1,
+                        ',' | ';' | '}' if depth == 0 => break,
+                        '"' | '\'' => {
+                            self.string()?;
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    self.chars.next();
+                }
+                Ok(LuaValue::Other)
+            }
+            None => Err(invalid("unexpected end of table")),
+        }
+    }
+
+    fn string(&mut self) -> ThemeResult<String> {
+        let quote = self.chars.next().unwrap_or('"');
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c) => value.push(c),
+                    None => break,
+                },
+                Some('\n') | None => break,
+                Some(c) => value.push(c),
+            }
+        }
+        Err(invalid("unterminated string"))
+    }
+
+    fn table(&mut self) -> ThemeResult<LuaValue> {
+        self.chars.next();
+        let mut fields = HashMap::new();
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('}') => {
+                    self.chars.next();
+                    return Ok(LuaValue::Table { fields, items });
+                }
+                Some(',') | Some(';') => {
+                    self.chars.next();
+                    continue;
+                }
+                None => return Err(invalid("unterminated table")),
+                _ => {}
+            }
+
+            match self.key()? {
+                Some(key) => {
+                    let value = self.value()?;
+                    fields.insert(key, value);
+                }
+                None => items.push(self.value()?),
+            }
+        }
+    }
+
+    /// The key of a `name = value` or `["name"] = value` field, consuming
+    /// it and the `=`, or `None` for a positional item
+    fn key(&mut self) -> ThemeResult<Option<String>> {
+        let mut lookahead = self.chars.clone();
+        let key = match lookahead.peek() {
+            Some('[') => {
+                lookahead.next();
+                while lookahead.next_if(|c| c.is_whitespace()).is_some() {}
+                let mut inner = LuaParser { chars: lookahead };
+                if !matches!(inner.chars.peek(), Some('"') | Some('\'')) {
+                    return Ok(None);
+                }
+                let key = inner.string()?;
+                inner.skip_whitespace();
+                if inner.chars.next() != Some(']') {
+                    return Ok(None);
+                }
+                lookahead = inner.chars;
+                key
+            }
+            Some(c) if c.is_alphabetic() || *c == '_' => {
+                let mut key = String::new();
+                while let Some(c) = lookahead.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    key.push(c);
+                }
+                key
+            }
+            _ => return Ok(None),
+        };
+        while lookahead.next_if(|c| c.is_whitespace()).is_some() {}
+        if lookahead.next() != Some('=') || lookahead.peek() == Some(&'=') {
+            return Ok(None);
+        }
+        self.chars = lookahead;
+        Ok(Some(key))
+    }
+}
+
+fn invalid(message: &str) -> ThemeError {
+    ThemeError::InvalidFormat(format!("WezTerm Lua: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSI: &str = "\"#000000\", \"#cc0000\", \"#00cc00\", \"#cccc00\", \
+                        \"#0000cc\", \"#cc00cc\", \"#00cccc\", \"#cccccc\"";
+
+    #[test]
+    fn test_parse_wezterm_toml() {
+        let content = format!(
+            "[colors]\nforeground = \"#dcd7ba\"\nbackground = \"#1f1f28\"\n\
+             cursor_bg = \"#c8c093\"\nselection_bg = \"#2d4f67\"\n\
+             ansi = [{}]\nbrights = [{}]\n\n\
+             [metadata]\nname = \"Kanagawa\"\nauthor = \"rebelot\"\n",
+            ANSI, ANSI
+        );
+        let theme = WezTermFormat::parse(&content).unwrap();
+        assert_eq!(theme.metadata.id, "kanagawa");
+        assert_eq!(theme.metadata.author, "rebelot");
+        assert_eq!(theme.colors.cursor, "#c8c093");
+        assert_eq!(theme.colors.palette.bright_red, "#cc0000");
+    }
+
+    #[test]
+    fn test_parse_wezterm_lua() {
+        let content = format!(
+            "local wezterm = require 'wezterm'\nlocal config = {{}}\n\
+             -- colors = {{ foreground = 'nope' }}\n\
+             config.font_size = 12.0\n\
+             config.colors = {{\n  foreground = '#c0caf5', -- text\n  \
+             background = \"#1a1b26\",\n  tab_bar = {{ active_tab = {{ bg_color = '#7aa2f7' }} }},\n  \
+             --[[ brights = {{ }} ]]\n  ansi = {{ {} }};\n  [\"selection_bg\"] = '#33467c',\n}}\n\
+             return config\n",
+            ANSI
+        );
+        let theme = WezTermFormat::parse(&content).unwrap();
+        assert_eq!(theme.colors.foreground, "#c0caf5");
+        assert_eq!(theme.colors.background, "#1a1b26");
+        assert_eq!(theme.colors.selection_background, "#33467c");
+        assert_eq!(theme.colors.palette.blue, "#0000cc");
+        assert_eq!(theme.colors.palette.bright_blue, "#0000cc");
+
+        let module = format!(
+            "return {{ foreground = '#ffffff', background = '#000000', ansi = {{ {} }} }}",
+            ANSI
+        );
+        assert!(WezTermFormat::parse(&module).is_ok());
+    }
+}
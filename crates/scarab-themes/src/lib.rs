@@ -4,6 +4,8 @@
 //! - 10+ built-in professional themes
 //! - Theme manager with preview/apply functionality
 //! - Import/export themes in multiple formats (TOML, JSON, Base16)
+//! - Import schemes from iTerm2, Alacritty, kitty and WezTerm
+//! - Sync the base16/base24 scheme catalog (needs network access)
 //! - Command palette integration
 //! - Hot-reload support (no restart required)
 //! - Custom theme creation
@@ -14,6 +16,7 @@
 //! - `ThemeManager`: Core theme management logic
 //! - `themes/`: Built-in theme definitions
 //! - `format/`: Import/export format handlers
+//! - `catalog`: Downloaded base16/base24 schemes
 //!
 //! ## Usage
 //!
@@ -24,6 +27,7 @@
 //! - "Theme: Export" - Export current theme
 //! - "Theme: Create Custom" - Create theme from current colors
 
+pub mod catalog;
 pub mod error;
 pub mod format;
 pub mod manager;
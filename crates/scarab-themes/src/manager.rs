@@ -1,6 +1,7 @@
 //! Theme manager for loading, applying, and managing themes
 
 use crate::{
+    catalog,
     error::{ThemeError, ThemeResult},
     format::{self, ThemeFormat},
     theme::Theme,
@@ -17,6 +18,9 @@ pub struct ThemeManager {
     /// User-installed themes
     user_themes: HashMap<String, Theme>,
 
+    /// Base16 and base24 schemes synced into the catalog
+    catalog_themes: HashMap<String, Theme>,
+
     /// Currently active theme ID
     active_theme_id: Option<String>,
 
@@ -25,6 +29,9 @@ pub struct ThemeManager {
 
     /// User themes directory
     themes_dir: PathBuf,
+
+    /// Directory the scheme catalog is cached in
+    catalog_dir: PathBuf,
 }
 
 impl ThemeManager {
@@ -41,9 +48,11 @@ impl ThemeManager {
         Self {
             builtin_themes,
             user_themes: HashMap::new(),
+            catalog_themes: HashMap::new(),
             active_theme_id: None,
             preview_theme_id: None,
             themes_dir,
+            catalog_dir: catalog::catalog_dir(),
         }
     }
 
@@ -57,10 +66,32 @@ impl ThemeManager {
 
         // Load user themes
         self.load_user_themes()?;
+        self.load_catalog();
 
         Ok(())
     }
 
+    /// Load the synced scheme catalog, replacing any loaded before
+    pub fn load_catalog(&mut self) {
+        self.catalog_themes = catalog::load_catalog(&self.catalog_dir)
+            .into_iter()
+            .map(|theme| (theme.id().to_string(), theme))
+            .collect();
+        if !self.catalog_themes.is_empty() {
+            log::info!("Loaded {} synced schemes", self.catalog_themes.len());
+        }
+    }
+
+    /// Directory the scheme catalog is cached in
+    pub fn catalog_dir(&self) -> &Path {
+        &self.catalog_dir
+    }
+
+    /// Whether `id` is a synced scheme rather than a built-in or user theme
+    pub fn is_catalog_theme(&self, id: &str) -> bool {
+        self.catalog_themes.contains_key(id)
+    }
+
     /// Load all user themes from the themes directory
     fn load_user_themes(&mut self) -> ThemeResult<()> {
         if !self.themes_dir.exists() {
@@ -92,11 +123,12 @@ impl ThemeManager {
         Ok(())
     }
 
-    /// Get all available themes (built-in + user)
+    /// Get all available themes (built-in + user + synced schemes)
     pub fn all_themes(&self) -> Vec<&Theme> {
         self.builtin_themes
             .values()
             .chain(self.user_themes.values())
+            .chain(self.catalog_themes.values())
             .collect()
     }
 
@@ -105,6 +137,7 @@ impl ThemeManager {
         self.builtin_themes
             .get(id)
             .or_else(|| self.user_themes.get(id))
+            .or_else(|| self.catalog_themes.get(id))
     }
 
     /// Get all theme IDs
@@ -113,6 +146,7 @@ impl ThemeManager {
             .builtin_themes
             .keys()
             .chain(self.user_themes.keys())
+            .chain(self.catalog_themes.keys())
             .cloned()
             .collect();
         ids.sort();
@@ -168,32 +202,28 @@ impl ThemeManager {
     }
 
     /// Import theme from file
+    ///
+    /// Accepts Scarab's own formats as well as iTerm2, Alacritty, kitty and
+    /// WezTerm schemes. The theme is saved as TOML in the themes directory,
+    /// so it is still there after a restart.
     pub fn import_theme<P: AsRef<Path>>(&mut self, path: P) -> ThemeResult<Theme> {
         let theme = self.load_theme_from_file(path.as_ref())?;
 
+        std::fs::create_dir_all(&self.themes_dir)?;
+        let saved = self.themes_dir.join(format!("{}.toml", theme.id()));
+        std::fs::write(&saved, format::serialize_theme(&theme, ThemeFormat::Toml)?)?;
+
         // Add to user themes
         self.user_themes
             .insert(theme.id().to_string(), theme.clone());
 
-        log::info!("Imported theme: {}", theme.id());
+        log::info!("Imported theme {} to {}", theme.id(), saved.display());
         Ok(theme)
     }
 
     /// Load theme from file (auto-detect format)
     fn load_theme_from_file(&self, path: &Path) -> ThemeResult<Theme> {
-        let contents = std::fs::read_to_string(path)?;
-
-        let format = match path.extension().and_then(|s| s.to_str()) {
-            Some("toml") => ThemeFormat::Toml,
-            Some("json") => ThemeFormat::Json,
-            _ => {
-                return Err(ThemeError::InvalidFormat(
-                    "Unknown file extension".to_string(),
-                ))
-            }
-        };
-
-        format::parse_theme(&contents, format)
+        format::import_theme_file(path)
     }
 
     /// Export theme to file
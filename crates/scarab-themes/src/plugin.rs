@@ -2,22 +2,27 @@
 
 use async_trait::async_trait;
 use scarab_plugin_api::{
-    types::{ModalItem, RemoteCommand},
-    Plugin, PluginContext, PluginMetadata, Result,
+    types::{ModalItem, PromptResponse, RemoteCommand},
+    Capability, Plugin, PluginContext, PluginError, PluginMetadata, Result,
 };
-use std::sync::Mutex;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
+use crate::catalog;
 use crate::manager::ThemeManager;
 
 /// Theme plugin state
 struct PluginState {
     manager: ThemeManager,
+    /// Prompt asking for the file to import, while it is open
+    import_prompt: Option<u64>,
 }
 
 /// Theme system plugin
 pub struct ThemePlugin {
     metadata: PluginMetadata,
-    state: Mutex<PluginState>,
+    /// Shared with the scheme sync task, which reloads the catalog
+    state: Arc<Mutex<PluginState>>,
 }
 
 impl ThemePlugin {
@@ -33,9 +38,10 @@ impl ThemePlugin {
             .with_homepage("https://github.com/raibid-labs/scarab")
             .with_catchphrase("Paint your terminal in style")
             .with_color("#bd93f9"), // Dracula purple
-            state: Mutex::new(PluginState {
+            state: Arc::new(Mutex::new(PluginState {
                 manager: ThemeManager::new(),
-            }),
+                import_prompt: None,
+            })),
         }
     }
 
@@ -62,7 +68,10 @@ impl ThemePlugin {
             ModalItem {
                 id: "theme:import".to_string(),
                 label: "Theme: Import from File".to_string(),
-                description: Some("Import TOML, JSON, or Base16 theme".to_string()),
+                description: Some(
+                    "Import a Scarab, Base16, iTerm2, Alacritty, kitty or WezTerm theme"
+                        .to_string(),
+                ),
             },
             ModalItem {
                 id: "theme:export".to_string(),
@@ -84,10 +93,22 @@ impl ThemePlugin {
                 label: "Theme: Show Light Themes".to_string(),
                 description: Some("List all light themes".to_string()),
             },
+            ModalItem {
+                id: "theme:sync-schemes".to_string(),
+                label: "Theme: Sync Base16/Base24 Schemes".to_string(),
+                description: Some(
+                    "Download the base16 and base24 scheme catalog (needs network access)"
+                        .to_string(),
+                ),
+            },
         ];
 
-        // Add quick-select commands for each theme
+        // Add quick-select commands for each theme; synced schemes are too
+        // many and are found with Theme: Select Theme instead
         for theme in state.manager.all_themes() {
+            if state.manager.is_catalog_theme(theme.id()) {
+                continue;
+            }
             commands.push(ModalItem {
                 id: format!("theme:apply:{}", theme.id()),
                 label: format!("Theme: {}", theme.name()),
@@ -144,6 +165,11 @@ impl ThemePlugin {
                 });
             }
 
+            "theme:sync-schemes" => {
+                let dir = state.manager.catalog_dir().to_path_buf();
+                self.sync_schemes(dir, ctx);
+            }
+
             "theme:clear-preview" => {
                 state.manager.clear_preview();
                 ctx.queue_command(RemoteCommand::PluginNotify {
@@ -153,6 +179,14 @@ impl ThemePlugin {
                 });
             }
 
+            "theme:import" => {
+                state.import_prompt = Some(ctx.show_input_prompt(
+                    "Import Theme",
+                    "Path to a .toml, .json, .yaml, .itermcolors, .conf or .lua file",
+                    false,
+                ));
+            }
+
             "theme:list-dark" => {
                 let themes: Vec<ModalItem> = state
                     .manager
@@ -242,6 +276,119 @@ impl ThemePlugin {
 
         Ok(())
     }
+
+    /// Download the base16 and base24 schemes into `dir` in the background
+    ///
+    /// Needs the network capability and the user's permission. The first
+    /// run only asks for permission; run it again once it is given.
+    fn sync_schemes(&self, dir: PathBuf, ctx: &PluginContext) {
+        if !ctx.has_capability(&Capability::Network) {
+            ctx.notify_error(
+                "Theme Error",
+                "Syncing schemes needs the network capability for scarab-themes",
+            );
+            return;
+        }
+        if let Err(e) = ctx.check_permission(
+            &Capability::Network,
+            "download base16 and base24 schemes from GitHub",
+        ) {
+            ctx.notify_warning("Theme Sync", &format!("Not synced: {}", e));
+            return;
+        }
+
+        let state = Arc::clone(&self.state);
+        let task_ctx = ctx.clone();
+        ctx.spawn_task("Sync base16/base24 schemes", move |task_id| async move {
+            let mut synced = 0;
+            for (index, system) in catalog::SCHEME_SYSTEMS.into_iter().enumerate() {
+                let listing = task_ctx.http_get(&catalog::listing_url(system)).await?;
+                if !listing.is_success() {
+                    return Err(sync_error(format!(
+                        "listing {} schemes failed with status {}",
+                        system, listing.status
+                    )));
+                }
+                let files = catalog::parse_listing(&listing.text()).map_err(sync_error)?;
+
+                for (done, file) in files.iter().enumerate() {
+                    let percent =
+                        (index * 100 + done * 100 / files.len()) / catalog::SCHEME_SYSTEMS.len();
+                    task_ctx.report_progress(
+                        task_id,
+                        percent as u8,
+                        format!("{}/{}", system, file.name),
+                    );
+
+                    let response = match task_ctx.http_get(&file.download_url).await {
+                        Ok(response) if response.is_success() => response,
+                        Ok(response) => {
+                            log::warn!(
+                                "Skipping {} scheme {}: status {}",
+                                system,
+                                file.name,
+                                response.status
+                            );
+                            continue;
+                        }
+                        Err(e) => {
+                            log::warn!("Skipping {} scheme {}: {}", system, file.name, e);
+                            continue;
+                        }
+                    };
+                    let saved = catalog::convert_scheme(system, &file.name, &response.text())
+                        .and_then(|theme| catalog::save_theme(&dir, system, &theme));
+                    match saved {
+                        Ok(_) => synced += 1,
+                        Err(e) => log::warn!("Skipping {} scheme {}: {}", system, file.name, e),
+                    }
+                }
+            }
+
+            state.lock().unwrap().manager.load_catalog();
+            log::info!("Synced {} schemes into {}", synced, dir.display());
+            task_ctx.notify_success(
+                "Schemes Synced",
+                &format!(
+                    "{} base16 and base24 schemes; find them with Theme: Select Theme",
+                    synced
+                ),
+            );
+            Ok(())
+        });
+    }
+
+    /// Import the theme file named in the import prompt
+    fn handle_import(&self, path: &str, ctx: &PluginContext) {
+        let mut state = self.state.lock().unwrap();
+
+        let result = scarab_config::expand::expand(path.trim(), &|name| std::env::var(name).ok())
+            .map_err(|e| e.to_string())
+            .and_then(|path| state.manager.import_theme(path).map_err(|e| e.to_string()));
+        match result {
+            Ok(theme) => {
+                log::info!("Imported theme {} from {}", theme.id(), path);
+                ctx.queue_command(RemoteCommand::PluginNotify {
+                    title: "Theme Imported".to_string(),
+                    body: format!("Added {}; choose it with Theme: Select Theme", theme.name()),
+                    level: scarab_plugin_api::context::NotifyLevel::Success,
+                });
+            }
+            Err(e) => {
+                log::error!("Failed to import theme from {}: {}", path, e);
+                ctx.queue_command(RemoteCommand::PluginNotify {
+                    title: "Theme Error".to_string(),
+                    body: format!("Failed to import theme: {}", e),
+                    level: scarab_plugin_api::context::NotifyLevel::Error,
+                });
+            }
+        }
+    }
+}
+
+/// Error ending a scheme sync
+fn sync_error(e: impl std::fmt::Display) -> PluginError {
+    PluginError::Other(anyhow::anyhow!("{}", e))
 }
 
 impl Default for ThemePlugin {
@@ -282,6 +429,27 @@ impl Plugin for ThemePlugin {
         }
         Ok(())
     }
+
+    async fn on_prompt_response(
+        &mut self,
+        response: &PromptResponse,
+        ctx: &PluginContext,
+    ) -> Result<()> {
+        let is_import = {
+            let mut state = self.state.lock().unwrap();
+            if state.import_prompt == Some(response.prompt_id) {
+                state.import_prompt = None;
+                true
+            } else {
+                false
+            }
+        };
+        match response.value() {
+            Some(path) if is_import && !path.trim().is_empty() => self.handle_import(path, ctx),
+            _ => {}
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
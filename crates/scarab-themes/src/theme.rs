@@ -156,6 +156,10 @@ impl Theme {
     pub fn to_color_config(&self) -> scarab_config::ColorConfig {
         scarab_config::ColorConfig {
             theme: Some(self.metadata.id.clone()),
+            light_theme: None,
+            dark_theme: None,
+            pane_themes: Vec::new(),
+            custom_themes: Default::default(),
             foreground: Some(self.colors.foreground.clone()),
             background: Some(self.colors.background.clone()),
             cursor: Some(self.colors.cursor.clone()),
@@ -164,9 +168,28 @@ impl Theme {
             palette: self.to_color_palette(),
             opacity: 1.0,
             dim_opacity: 0.7,
+            minimum_contrast: 1.0,
         }
     }
 
+    /// Switch `colors` to this theme
+    ///
+    /// Opacity, minimum contrast, the light, dark and per-pane theme choices
+    /// and the custom theme definitions are settings of their own rather
+    /// than part of a theme, so they are kept.
+    pub fn apply_to(&self, colors: &mut scarab_config::ColorConfig) {
+        *colors = scarab_config::ColorConfig {
+            light_theme: colors.light_theme.take(),
+            dark_theme: colors.dark_theme.take(),
+            pane_themes: std::mem::take(&mut colors.pane_themes),
+            custom_themes: std::mem::take(&mut colors.custom_themes),
+            opacity: colors.opacity,
+            dim_opacity: colors.dim_opacity,
+            minimum_contrast: colors.minimum_contrast,
+            ..self.to_color_config()
+        };
+    }
+
     /// Convert palette to scarab_config::ColorPalette
     fn to_color_palette(&self) -> scarab_config::ColorPalette {
         scarab_config::ColorPalette {
@@ -256,4 +279,19 @@ mod tests {
         let parsed: Theme = serde_json::from_str(&json).unwrap();
         assert_eq!(theme, parsed);
     }
+
+    #[test]
+    fn test_apply_keeps_opacity_and_theme_choices() {
+        let theme = create_test_theme();
+        let mut colors = scarab_config::ColorConfig {
+            dark_theme: Some("test-theme".to_string()),
+            opacity: 0.9,
+            ..Default::default()
+        };
+        theme.apply_to(&mut colors);
+        assert_eq!(colors.theme.as_deref(), Some("test-theme"));
+        assert_eq!(colors.background, Some(theme.colors.background.clone()));
+        assert_eq!(colors.dark_theme.as_deref(), Some("test-theme"));
+        assert_eq!(colors.opacity, 0.9);
+    }
 }
@@ -0,0 +1,112 @@
+//! Translate crossterm key events into the bytes a PTY expects
+//!
+//! Special keys use the same sequences as the GUI client. Ctrl+letter maps
+//! to the matching C0 control byte, and Alt prefixes the key with ESC.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+/// Bytes to send for a key press, or `None` for keys with no encoding
+pub fn key_to_bytes(event: &KeyEvent) -> Option<Vec<u8>> {
+    if event.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = event.modifiers.contains(KeyModifiers::ALT);
+
+    let mut bytes = match event.code {
+        KeyCode::Char(c) if ctrl => vec![control_byte(c)?],
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Backspace => vec![0x7F],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Esc => vec![0x1B],
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        KeyCode::Home => b"\x1b[H".to_vec(),
+        KeyCode::End => b"\x1b[F".to_vec(),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::Insert => b"\x1b[2~".to_vec(),
+        KeyCode::F(n) => function_key(n)?,
+        _ => return None,
+    };
+
+    if alt {
+        bytes.insert(0, 0x1B);
+    }
+    Some(bytes)
+}
+
+/// C0 control byte for Ctrl+`c`
+fn control_byte(c: char) -> Option<u8> {
+    match c.to_ascii_lowercase() {
+        c @ 'a'..='z' => Some(c as u8 - b'a' + 1),
+        ' ' | '@' | '2' => Some(0x00),
+        '[' | '3' => Some(0x1B),
+        '\\' | '4' => Some(0x1C),
+        ']' | '5' => Some(0x1D),
+        '^' | '6' => Some(0x1E),
+        '_' | '/' | '7' => Some(0x1F),
+        '?' | '8' => Some(0x7F),
+        _ => None,
+    }
+}
+
+fn function_key(n: u8) -> Option<Vec<u8>> {
+    let bytes: &[u8] = match n {
+        1 => b"\x1bOP",
+        2 => b"\x1bOQ",
+        3 => b"\x1bOR",
+        4 => b"\x1bOS",
+        5 => b"\x1b[15~",
+        6 => b"\x1b[17~",
+        7 => b"\x1b[18~",
+        8 => b"\x1b[19~",
+        9 => b"\x1b[20~",
+        10 => b"\x1b[21~",
+        11 => b"\x1b[23~",
+        12 => b"\x1b[24~",
+        _ => return None,
+    };
+    Some(bytes.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Option<Vec<u8>> {
+        key_to_bytes(&KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn test_key_to_bytes() {
+        assert_eq!(
+            key(KeyCode::Char('é'), KeyModifiers::NONE),
+            Some("é".as_bytes().to_vec())
+        );
+        assert_eq!(
+            key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Some(vec![3])
+        );
+        assert_eq!(
+            key(KeyCode::Char('b'), KeyModifiers::ALT),
+            Some(b"\x1bb".to_vec())
+        );
+        assert_eq!(
+            key(KeyCode::Up, KeyModifiers::NONE),
+            Some(b"\x1b[A".to_vec())
+        );
+        assert_eq!(
+            key(KeyCode::F(5), KeyModifiers::NONE),
+            Some(b"\x1b[15~".to_vec())
+        );
+        assert_eq!(key(KeyCode::F(20), KeyModifiers::NONE), None);
+        assert_eq!(key(KeyCode::Char('é'), KeyModifiers::CONTROL), None);
+    }
+}
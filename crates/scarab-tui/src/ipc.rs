@@ -0,0 +1,152 @@
+//! Blocking IPC connection to the daemon
+//!
+//! Frames come from `scarab_protocol::encode_frame`: a big-endian `u32`
+//! length followed by an rkyv-serialized message of at most
+//! `MAX_MESSAGE_SIZE` bytes. Daemon messages are read on a background thread and handed over
+//! through a channel.
+
+use anyhow::{bail, Context, Result};
+use scarab_protocol::{
+    frame_body_len, ControlMessage, DaemonMessage, FRAME_HEADER_LEN, MAX_MESSAGE_SIZE,
+    MAX_RECONNECT_ATTEMPTS, RECONNECT_DELAY_MS,
+};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+
+/// A connection to the daemon's control socket
+pub struct DaemonConnection {
+    stream: UnixStream,
+    rx: Receiver<DaemonMessage>,
+    closed: bool,
+}
+
+impl DaemonConnection {
+    /// Connect to the daemon, retrying with backoff while it starts up
+    pub fn connect(path: &Path) -> Result<Self> {
+        let mut delay_ms = RECONNECT_DELAY_MS;
+        let mut attempts = 0;
+        let stream = loop {
+            match UnixStream::connect(path) {
+                Ok(stream) => break stream,
+                Err(e) => {
+                    attempts += 1;
+                    if attempts >= MAX_RECONNECT_ATTEMPTS {
+                        return Err(e).with_context(|| {
+                            format!("Failed to connect to daemon at {}", path.display())
+                        });
+                    }
+                    log::debug!("Connection attempt {} failed: {}", attempts, e);
+                    std::thread::sleep(Duration::from_millis(delay_ms));
+                    delay_ms = (delay_ms * 2).min(5000);
+                }
+            }
+        };
+
+        let reader = stream
+            .try_clone()
+            .context("Failed to clone daemon socket")?;
+        let (tx, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("scarab-tui-ipc".into())
+            .spawn(move || read_loop(reader, tx))
+            .context("Failed to spawn IPC reader")?;
+
+        Ok(Self {
+            stream,
+            rx,
+            closed: false,
+        })
+    }
+
+    /// Send a message to the daemon
+    pub fn send(&mut self, msg: &ControlMessage) -> Result<()> {
+        let bytes = encode_frame(msg)?;
+        self.stream
+            .write_all(&bytes)
+            .context("Failed to write to daemon")?;
+        self.stream.flush().context("Failed to flush daemon socket")
+    }
+
+    /// Messages received since the last call
+    pub fn drain(&mut self) -> Vec<DaemonMessage> {
+        let mut messages = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(msg) => messages.push(msg),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+        messages
+    }
+
+    /// Whether the daemon closed the connection
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+/// Length-prefixed wire bytes for a message
+pub fn encode_frame(msg: &ControlMessage) -> Result<Vec<u8>> {
+    let body = rkyv::to_bytes::<_, MAX_MESSAGE_SIZE>(msg).context("Failed to serialize message")?;
+    match scarab_protocol::encode_frame(&body) {
+        Some(frame) => Ok(frame),
+        None => bail!("Message too large: {} bytes", body.len()),
+    }
+}
+
+fn read_loop(mut stream: UnixStream, tx: mpsc::Sender<DaemonMessage>) {
+    let mut buffer = vec![0u8; MAX_MESSAGE_SIZE];
+    loop {
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        if stream.read_exact(&mut header).is_err() {
+            break; // Connection closed
+        }
+        let Some(len) = frame_body_len(header) else {
+            log::error!(
+                "Invalid message length from daemon: {}",
+                u32::from_be_bytes(header)
+            );
+            break;
+        };
+
+        if let Err(e) = stream.read_exact(&mut buffer[..len]) {
+            log::error!("Failed to read message body: {}", e);
+            break;
+        }
+
+        match rkyv::from_bytes::<DaemonMessage>(&buffer[..len]) {
+            Ok(msg) => {
+                if tx.send(msg).is_err() {
+                    break;
+                }
+            }
+            Err(e) => log::error!("Failed to deserialize daemon message: {:?}", e),
+        }
+    }
+    log::debug!("Read loop terminated");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_frame() {
+        let frame = encode_frame(&ControlMessage::Resize { cols: 80, rows: 24 }).unwrap();
+        let len = frame_body_len(frame[..FRAME_HEADER_LEN].try_into().unwrap()).unwrap();
+        assert_eq!(len, frame.len() - FRAME_HEADER_LEN);
+
+        let decoded = rkyv::from_bytes::<ControlMessage>(&frame[FRAME_HEADER_LEN..]).unwrap();
+        assert!(matches!(
+            decoded,
+            ControlMessage::Resize { cols: 80, rows: 24 }
+        ));
+    }
+}
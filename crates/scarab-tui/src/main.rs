@@ -0,0 +1,149 @@
+//! Scarab TUI client
+//!
+//! A fallback client that draws the daemon's grid inside an existing
+//! terminal instead of a GPU window. It maps the same shared memory region
+//! and talks over the same IPC socket as the GUI client, so it can be used
+//! over SSH on a machine running the daemon, or to debug the daemon without
+//! a graphics stack.
+//!
+//! Keys are forwarded to the shell; Ctrl+] detaches. The daemon's grid is
+//! resized to fit this terminal, which also affects a GUI client attached
+//! to the same session.
+
+mod input;
+mod ipc;
+mod render;
+mod shm;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use crossterm::event::{
+    self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyModifiers,
+};
+use ratatui::layout::Position;
+use ratatui::DefaultTerminal;
+use scarab_config::ConfigLoader;
+use scarab_protocol::terminal_state::TerminalStateReader;
+use scarab_protocol::{ControlMessage, GRID_HEIGHT, GRID_WIDTH};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use ipc::DaemonConnection;
+use render::GridWidget;
+use shm::SharedGrid;
+
+/// How long to wait for input before checking the grid for changes
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Scarab Terminal TUI Client")]
+struct Args {
+    /// Shared memory region to read (defaults to $SCARAB_SHMEM_PATH, then `paths.shmem` in the config)
+    #[arg(long, value_name = "PATH")]
+    shmem_path: Option<String>,
+
+    /// Daemon socket (defaults to $SCARAB_SOCKET_PATH, then `paths.socket` in the config)
+    #[arg(long, value_name = "PATH")]
+    socket: Option<PathBuf>,
+
+    /// Command to execute on startup (sends input to the running shell)
+    #[arg(long)]
+    command: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // Socket and shared memory locations: flags, then environment, then
+    // `[paths]` in the config
+    let paths = ConfigLoader::new().paths();
+    let shmem_path = args.shmem_path.unwrap_or(paths.shmem);
+    let grid = SharedGrid::open(&shmem_path).context("Is the daemon running?")?;
+    let mut conn = DaemonConnection::connect(&args.socket.unwrap_or(paths.socket))?;
+
+    if let Some(command) = args.command {
+        let data = format!("{}\r", command).into_bytes();
+        conn.send(&ControlMessage::Input { data })?;
+    }
+
+    let mut terminal = ratatui::init();
+    crossterm::execute!(std::io::stdout(), EnableBracketedPaste)?;
+    let result = run(&mut terminal, &grid, &mut conn);
+    crossterm::execute!(std::io::stdout(), DisableBracketedPaste)?;
+    ratatui::restore();
+    result
+}
+
+/// Forward input and redraw on grid changes until detached or disconnected
+fn run(
+    terminal: &mut DefaultTerminal,
+    grid: &SharedGrid,
+    conn: &mut DaemonConnection,
+) -> Result<()> {
+    let size = terminal.size()?;
+    send_resize(conn, size.width, size.height)?;
+
+    let mut drawn_sequence = None;
+    loop {
+        // Nothing the daemon sends changes what is drawn; the grid is
+        // read from shared memory
+        conn.drain();
+        if conn.is_closed() {
+            return Ok(());
+        }
+
+        let sequence = grid.sequence();
+        if drawn_sequence != Some(sequence) {
+            draw(terminal, grid)?;
+            drawn_sequence = Some(sequence);
+        }
+
+        if !event::poll(FRAME_INTERVAL)? {
+            continue;
+        }
+        match event::read()? {
+            Event::Key(key) if is_detach_key(&key) => return Ok(()),
+            Event::Key(key) => {
+                if let Some(data) = input::key_to_bytes(&key) {
+                    conn.send(&ControlMessage::Input { data })?;
+                }
+            }
+            Event::Paste(text) => {
+                conn.send(&ControlMessage::Input {
+                    data: text.into_bytes(),
+                })?;
+            }
+            Event::Resize(cols, rows) => {
+                send_resize(conn, cols, rows)?;
+                drawn_sequence = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw(terminal: &mut DefaultTerminal, grid: &SharedGrid) -> Result<()> {
+    let snapshot = grid.snapshot();
+    terminal.draw(|frame| {
+        let area = frame.area();
+        frame.render_widget(GridWidget::new(&snapshot), area);
+
+        let (x, y) = snapshot.cursor_pos();
+        if x < area.width && y < area.height {
+            frame.set_cursor_position(Position::new(x, y));
+        }
+    })?;
+    Ok(())
+}
+
+/// Ctrl+] leaves the TUI, as in telnet
+fn is_detach_key(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char(']') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn send_resize(conn: &mut DaemonConnection, cols: u16, rows: u16) -> Result<()> {
+    // Clamp to protocol limits
+    let cols = cols.min(GRID_WIDTH as u16);
+    let rows = rows.min(GRID_HEIGHT as u16);
+    conn.send(&ControlMessage::Resize { cols, rows })
+}
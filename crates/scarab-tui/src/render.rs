@@ -0,0 +1,98 @@
+//! Draw the shared grid into a ratatui buffer
+//!
+//! Cells carry packed ARGB colors and the attribute bits written by the
+//! daemon's VTE parser; both map directly onto ratatui styles. Terminals
+//! without truecolor support approximate the RGB values themselves.
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier};
+use ratatui::widgets::Widget;
+use scarab_protocol::terminal_state::TerminalStateReader;
+use scarab_protocol::{Cell, FLAG_BOLD, FLAG_DIM, FLAG_INVERSE, FLAG_ITALIC, FLAG_UNDERLINE};
+
+/// Widget drawing the top-left corner of the grid that fits its area
+pub struct GridWidget<'a, R: TerminalStateReader> {
+    state: &'a R,
+}
+
+impl<'a, R: TerminalStateReader> GridWidget<'a, R> {
+    pub fn new(state: &'a R) -> Self {
+        Self { state }
+    }
+}
+
+impl<R: TerminalStateReader> Widget for GridWidget<'_, R> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in 0..area.height {
+            for x in 0..area.width {
+                let Some(cell) = self.state.cell(y as usize, x as usize) else {
+                    continue;
+                };
+                let Some(target) = buf.cell_mut((area.x + x, area.y + y)) else {
+                    continue;
+                };
+
+                let symbol = match char::from_u32(cell.char_codepoint) {
+                    Some(c) if c != '\0' && !c.is_control() => c,
+                    _ => ' ',
+                };
+                target.set_char(symbol);
+                target.set_fg(argb_to_color(cell.fg));
+                target.set_bg(argb_to_color(cell.bg));
+                target.modifier = cell_modifier(cell);
+            }
+        }
+    }
+}
+
+/// Convert a packed ARGB cell color to a ratatui color
+pub fn argb_to_color(argb: u32) -> Color {
+    let [_, r, g, b] = argb.to_be_bytes();
+    Color::Rgb(r, g, b)
+}
+
+/// Text modifiers for a cell's attribute bits
+pub fn cell_modifier(cell: &Cell) -> Modifier {
+    [
+        (FLAG_BOLD, Modifier::BOLD),
+        (FLAG_ITALIC, Modifier::ITALIC),
+        (FLAG_UNDERLINE, Modifier::UNDERLINED),
+        (FLAG_DIM, Modifier::DIM),
+        (FLAG_INVERSE, Modifier::REVERSED),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| cell.flags & flag != 0)
+    .fold(Modifier::empty(), |acc, (_, modifier)| acc | modifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shm::GridSnapshot;
+    use scarab_protocol::{BUFFER_SIZE, GRID_WIDTH};
+
+    #[test]
+    fn test_grid_widget() {
+        let mut cells = vec![Cell::default(); BUFFER_SIZE];
+        cells[0].char_codepoint = 'h' as u32;
+        cells[0].flags = FLAG_BOLD | FLAG_INVERSE;
+        cells[GRID_WIDTH + 1].char_codepoint = 'λ' as u32;
+        cells[GRID_WIDTH + 1].fg = 0xFF112233;
+        let snapshot = GridSnapshot::from_cells(cells, (0, 0));
+
+        let area = Rect::new(0, 0, 4, 2);
+        let mut buf = Buffer::empty(area);
+        GridWidget::new(&snapshot).render(area, &mut buf);
+
+        let first = &buf[(0, 0)];
+        assert_eq!(first.symbol(), "h");
+        assert_eq!(first.modifier, Modifier::BOLD | Modifier::REVERSED);
+        assert_eq!(first.bg, Color::Rgb(0x0D, 0x12, 0x08));
+
+        let second = &buf[(1, 1)];
+        assert_eq!(second.symbol(), "λ");
+        assert_eq!(second.fg, Color::Rgb(0x11, 0x22, 0x33));
+        assert_eq!(buf[(3, 1)].symbol(), " ");
+    }
+}
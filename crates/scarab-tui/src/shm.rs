@@ -0,0 +1,117 @@
+//! Read-only view of the daemon's shared memory grid
+//!
+//! The daemon bumps `sequence_number` after each update. Snapshots copy the
+//! cells and cursor, and are retried if the sequence moves mid-copy, the same
+//! seqlock read (`SharedState::read_consistent`) the GUI client uses.
+
+use anyhow::{Context, Result};
+use scarab_protocol::terminal_state::TerminalStateReader;
+use scarab_protocol::{Cell, SharedState, GRID_HEIGHT, GRID_WIDTH};
+use shared_memory::{Shmem, ShmemConf};
+
+/// Attempts at a consistent copy before settling for a torn one
+const MAX_SNAPSHOT_RETRIES: u32 = 8;
+
+/// The daemon's mapped grid region
+pub struct SharedGrid {
+    shmem: Shmem,
+}
+
+impl SharedGrid {
+    /// Map an existing region created by the daemon
+    pub fn open(path: &str) -> Result<Self> {
+        let shmem = ShmemConf::new()
+            .size(std::mem::size_of::<SharedState>())
+            .os_id(path)
+            .open()
+            .with_context(|| format!("Failed to open shared memory at {}", path))?;
+        Ok(Self { shmem })
+    }
+
+    fn state(&self) -> &SharedState {
+        // SAFETY: the region is mapped for the lifetime of self and sized
+        // for one SharedState
+        unsafe { &*(self.shmem.as_ptr() as *const SharedState) }
+    }
+
+    /// Current sequence number, without copying the grid
+    pub fn sequence(&self) -> u64 {
+        self.state().sequence()
+    }
+
+    /// Copy the grid and cursor
+    pub fn snapshot(&self) -> GridSnapshot {
+        let copy = |state: &SharedState| GridSnapshot {
+            cells: state.cells.to_vec(),
+            cursor: (state.cursor_x, state.cursor_y),
+            cursor_style: state.cursor_style,
+            sequence: state.sequence(),
+            error_mode: state.error_mode != 0,
+        };
+        let state = self.state();
+        match state.read_consistent(MAX_SNAPSHOT_RETRIES, copy) {
+            Some((_, snapshot)) => snapshot,
+            None => copy(state),
+        }
+    }
+}
+
+/// An owned copy of the shared grid
+pub struct GridSnapshot {
+    cells: Vec<Cell>,
+    cursor: (u16, u16),
+    cursor_style: u8,
+    sequence: u64,
+    error_mode: bool,
+}
+
+impl GridSnapshot {
+    /// Snapshot built from cells laid out `GRID_WIDTH` to a row
+    pub fn from_cells(cells: Vec<Cell>, cursor: (u16, u16)) -> Self {
+        Self {
+            cells,
+            cursor,
+            cursor_style: 0,
+            sequence: 0,
+            error_mode: false,
+        }
+    }
+}
+
+impl TerminalStateReader for GridSnapshot {
+    fn cell(&self, row: usize, col: usize) -> Option<&Cell> {
+        self.cell_index(row, col).and_then(|i| self.cells.get(i))
+    }
+
+    fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    fn cursor_pos(&self) -> (u16, u16) {
+        self.cursor
+    }
+
+    fn cursor_style(&self) -> u8 {
+        self.cursor_style
+    }
+
+    fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    fn is_valid(&self) -> bool {
+        self.cells.len() == GRID_WIDTH * GRID_HEIGHT
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (GRID_WIDTH, GRID_HEIGHT)
+    }
+
+    fn is_dirty(&self) -> bool {
+        false
+    }
+
+    fn is_error_mode(&self) -> bool {
+        self.error_mode
+    }
+}